@@ -0,0 +1,42 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+use wasmer::*;
+
+static WAT: &str = r#"(module
+    (func (export "add") (param i32 i32) (result i32)
+       (i32.add (local.get 0) (local.get 1)))
+)"#;
+
+fn export_lookup_benchmark(c: &mut Criterion) {
+    let mut store = Store::default();
+    let module = Module::new(&store, WAT).unwrap();
+    let instance = Instance::new(&mut store, &module, &imports! {}).unwrap();
+
+    let mut group = c.benchmark_group("export lookup + call");
+
+    group.bench_function("Exports::get_typed_function", |b| {
+        b.iter(|| {
+            let add: TypedFunction<(i32, i32), i32> = instance
+                .exports
+                .get_typed_function(&store, black_box("add"))
+                .unwrap();
+            assert_eq!(add.call(&mut store, 4, 6).unwrap(), 10);
+        })
+    });
+
+    let exports = instance.cached_exports();
+    let handle = exports
+        .typed_function_handle::<(i32, i32), i32>(&store, "add")
+        .unwrap();
+    group.bench_function("CachedExports::typed_function", |b| {
+        b.iter(|| {
+            let add = exports.typed_function(&store, black_box(handle));
+            assert_eq!(add.call(&mut store, 4, 6).unwrap(), 10);
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, export_lookup_benchmark);
+criterion_main!(benches);
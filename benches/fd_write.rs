@@ -0,0 +1,102 @@
+use std::path::PathBuf;
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+
+use wasmer::*;
+use wasmer_wasix::{
+    virtual_fs::{mem_fs, FileSystem},
+    WasiEnv,
+};
+
+const WRITE_SIZE: u64 = 4 * 1024;
+const WRITES: u64 = 256;
+
+/// `open(path, len)` opens a file in the preopened `/` (which comes right
+/// after the virtual root, as fd 4) and `write(fd)` seeks `fd` back to the
+/// start and writes the first 4KB of memory to it 256 times.
+const PROGRAM: &str = r#"
+(module
+    (import "wasi_snapshot_preview1" "path_open" (func $path_open (param i32 i32 i32 i32 i32 i64 i64 i32 i32) (result i32)))
+    (import "wasi_snapshot_preview1" "fd_write" (func $fd_write (param i32 i32 i32 i32) (result i32)))
+    (import "wasi_snapshot_preview1" "fd_seek" (func $fd_seek (param i32 i64 i32 i32) (result i32)))
+    (memory (export "memory") 1)
+    (data (i32.const 8192) "file")
+    (data (i32.const 8200) "device")
+
+    (func (export "open") (param $path i32) (param $len i32) (result i32)
+        (if (call $path_open (i32.const 4) (i32.const 0) (local.get $path) (local.get $len)
+                (i32.const 1) (i64.const -1) (i64.const -1) (i32.const 0) (i32.const 8240))
+            (then unreachable))
+        (i32.load (i32.const 8240))
+    )
+
+    (func (export "write") (param $fd i32)
+        (local $i i32)
+        (if (call $fd_seek (local.get $fd) (i64.const 0) (i32.const 0) (i32.const 8248))
+            (then unreachable))
+        (i32.store (i32.const 8224) (i32.const 0))
+        (i32.store (i32.const 8228) (i32.const 4096))
+        (loop $again
+            (if (call $fd_write (local.get $fd) (i32.const 8224) (i32.const 1) (i32.const 8232))
+                (then unreachable))
+            (local.set $i (i32.add (local.get $i) (i32.const 1)))
+            (br_if $again (i32.lt_u (local.get $i) (i32.const 256))))
+    )
+)
+"#;
+
+fn fd_write_benchmark(c: &mut Criterion) {
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+    let _guard = runtime.enter();
+
+    // Device files are written through the copying path, which is what every
+    // memfs file used before `fd_write` learned to borrow guest memory
+    let fs = mem_fs::FileSystem::default();
+    let device = mem_fs::FileSystem::default()
+        .new_open_options()
+        .read(true)
+        .write(true)
+        .create(true)
+        .open("/device")
+        .unwrap();
+    fs.insert_device_file(PathBuf::from("/device"), device)
+        .unwrap();
+
+    let mut store = Store::default();
+    let module = Module::new(&store, PROGRAM).unwrap();
+    let (instance, _env) = WasiEnv::builder("fd-write")
+        .engine(store.engine().clone())
+        .fs(Box::new(fs))
+        .preopen_dir("/")
+        .unwrap()
+        .instantiate(module, &mut store)
+        .unwrap();
+
+    let open: TypedFunction<(i32, i32), i32> =
+        instance.exports.get_typed_function(&store, "open").unwrap();
+    let write: TypedFunction<i32, ()> = instance
+        .exports
+        .get_typed_function(&store, "write")
+        .unwrap();
+    let file = open.call(&mut store, 8192, 4).unwrap();
+    let device = open.call(&mut store, 8200, 6).unwrap();
+
+    let mut group = c.benchmark_group("fd_write (4KB writes to memfs)");
+    group.throughput(Throughput::Bytes(WRITE_SIZE * WRITES));
+
+    group.bench_function("regular file", |b| {
+        b.iter(|| write.call(&mut store, file).unwrap())
+    });
+
+    group.bench_function("device file", |b| {
+        b.iter(|| write.call(&mut store, device).unwrap())
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, fd_write_benchmark);
+criterion_main!(benches);
@@ -0,0 +1,35 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+
+use wasmer::*;
+
+const SIZE: u64 = 64 * 1024 * 1024;
+
+fn memory_copy_benchmark(c: &mut Criterion) {
+    let mut store = Store::default();
+    let pages = (SIZE / WASM_PAGE_SIZE as u64) as u32;
+    let src = Memory::new(&mut store, MemoryType::new(pages, None, false)).unwrap();
+    let dst = Memory::new(&mut store, MemoryType::new(pages, None, false)).unwrap();
+
+    let mut group = c.benchmark_group("memory copy (64MB)");
+    group.throughput(Throughput::Bytes(SIZE));
+    group.sample_size(20);
+
+    group.bench_function("Memory::copy_to", |b| {
+        b.iter(|| src.copy_to(&store, 0, &dst, 0, black_box(SIZE)).unwrap())
+    });
+
+    group.bench_function("Vec round-trip", |b| {
+        b.iter(|| {
+            let data = src
+                .view(&store)
+                .copy_range_to_vec(0..black_box(SIZE))
+                .unwrap();
+            dst.view(&store).write(0, &data).unwrap();
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, memory_copy_benchmark);
+criterion_main!(benches);
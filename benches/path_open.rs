@@ -0,0 +1,105 @@
+use std::path::{Path, PathBuf};
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+
+use wasmer::*;
+use wasmer_wasix::{
+    virtual_fs::{mem_fs, FileSystem},
+    WasiEnv,
+};
+
+const FILES: usize = 10_000;
+
+/// `open(path, len)` opens a file relative to the preopened `/` (fd 4) and
+/// closes it again.
+const PROGRAM: &str = r#"
+(module
+    (import "wasi_snapshot_preview1" "path_open" (func $path_open (param i32 i32 i32 i32 i32 i64 i64 i32 i32) (result i32)))
+    (import "wasi_snapshot_preview1" "fd_close" (func $fd_close (param i32) (result i32)))
+    (memory (export "memory") 8)
+
+    (func (export "open") (param $path i32) (param $len i32)
+        (if (call $path_open (i32.const 4) (i32.const 1) (local.get $path) (local.get $len)
+                (i32.const 0) (i64.const 2) (i64.const 0) (i32.const 0) (i32.const 0))
+            (then unreachable))
+        (drop (call $fd_close (i32.load (i32.const 0))))
+    )
+)
+"#;
+
+/// The `node_modules`-like paths of the files to open, spread over 100
+/// directory trees that are 6 levels deep.
+fn paths() -> Vec<String> {
+    (0..FILES)
+        .map(|i| format!("pkg{}/lib/src/a/b/c/file{i}", i % 100))
+        .collect()
+}
+
+fn path_open_benchmark(c: &mut Criterion) {
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+    let _guard = runtime.enter();
+
+    let paths = paths();
+    let fs = mem_fs::FileSystem::default();
+    for path in &paths {
+        let path = Path::new("/").join(path);
+        let mut dir = PathBuf::from("/");
+        for component in path.parent().unwrap().components().skip(1) {
+            dir.push(component);
+            let _ = fs.create_dir(&dir);
+        }
+        fs.new_open_options()
+            .create(true)
+            .write(true)
+            .open(&path)
+            .unwrap();
+    }
+
+    let mut store = Store::default();
+    let module = Module::new(&store, PROGRAM).unwrap();
+    let (instance, env) = WasiEnv::builder("path-open")
+        .engine(store.engine().clone())
+        .fs(Box::new(fs))
+        .preopen_dir("/")
+        .unwrap()
+        .instantiate(module, &mut store)
+        .unwrap();
+
+    let memory = instance.exports.get_memory("memory").unwrap();
+    let mut offset = 1024;
+    let mut args = Vec::new();
+    for path in &paths {
+        memory.view(&store).write(offset, path.as_bytes()).unwrap();
+        args.push((offset as i32, path.len() as i32));
+        offset += path.len() as u64;
+    }
+    let open: TypedFunction<(i32, i32), ()> =
+        instance.exports.get_typed_function(&store, "open").unwrap();
+
+    // Load every inode up front so both variants resolve the same tree
+    for (ptr, len) in &args {
+        open.call(&mut store, *ptr, *len).unwrap();
+    }
+
+    let mut group = c.benchmark_group("path_open (10k deep paths in memfs)");
+    group.throughput(Throughput::Elements(FILES as u64));
+
+    for (name, enabled) in [("lookup cache", true), ("no lookup cache", false)] {
+        env.data(&store).set_path_lookup_cache(enabled);
+        group.bench_function(name, |b| {
+            b.iter(|| {
+                for (ptr, len) in &args {
+                    open.call(&mut store, *ptr, *len).unwrap();
+                }
+            })
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, path_open_benchmark);
+criterion_main!(benches);
@@ -64,7 +64,11 @@ fn main() -> anyhow::Result<()> {
     //
     // For each `Operator`, the metering middleware will call the cost
     // function and subtract the cost from the remaining points.
-    let metering = Arc::new(Metering::new(10, cost_function));
+    //
+    // Functions can also be charged for each local they declare, every
+    // time they are called. `add_one` only has a parameter, so this
+    // doesn't change anything below.
+    let metering = Arc::new(Metering::new(10, cost_function).with_local_cost(1));
     let mut compiler_config = Cranelift::default();
     compiler_config.push_middleware(metering);
 
@@ -81,6 +81,13 @@ impl<T: Tunables> Tunables for LimitingTunables<T> {
         self.base.table_style(table)
     }
 
+    /// Check that the tunables make sense
+    ///
+    /// Delegated to base.
+    fn validate(&self) -> Result<(), String> {
+        self.base.validate()
+    }
+
     /// Create a memory owned by the host given a [`MemoryType`] and a [`MemoryStyle`].
     ///
     /// The requested memory type is validated, adjusted to the limited and then passed to base.
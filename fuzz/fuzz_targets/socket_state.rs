@@ -0,0 +1,11 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|script: &[u8]| {
+    let results = wasmer_wasix::fuzzing::drive_sockets_arbitrary(script);
+    assert_eq!(
+        results,
+        wasmer_wasix::fuzzing::drive_sockets_arbitrary(script)
+    );
+});
@@ -0,0 +1,7 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|script: &[u8]| {
+    wasmer_wasix::fuzzing::resolve_path_arbitrary(script);
+});
@@ -452,6 +452,11 @@ impl Module {
         )
     }
 
+    pub fn custom_section_names(&self) -> Box<dyn Iterator<Item = &str> + '_> {
+        // The JS API can only look custom sections up by name
+        Box::new(std::iter::empty())
+    }
+
     pub(crate) fn info(&self) -> &ModuleInfo {
         unimplemented!()
     }
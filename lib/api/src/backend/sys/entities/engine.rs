@@ -3,7 +3,9 @@
 use std::{path::Path, sync::Arc};
 
 use shared_buffer::OwnedBuffer;
-pub use wasmer_compiler::{Artifact, BaseTunables, Engine, EngineBuilder, Tunables};
+pub use wasmer_compiler::{
+    Artifact, BaseTunables, Engine, EngineBuilder, SourceMapResolver, Tunables,
+};
 use wasmer_types::{target::Target, DeserializeError, Features, HashAlgorithm};
 
 use crate::{BackendEngine, BackendModule};
@@ -62,6 +64,11 @@ pub trait NativeEngineExt {
     /// Sets the hash algorithm
     fn set_hash_algorithm(&mut self, hash_algorithm: Option<HashAlgorithm>);
 
+    /// Sets the function that loads the source maps modules refer to in
+    /// their `sourceMappingURL` section, used to show the original source
+    /// location of trap frames.
+    fn set_source_map_resolver(&self, resolver: Option<SourceMapResolver>);
+
     /// Create a headless `Engine`
     ///
     /// A headless engine is an engine without any compiler attached.
@@ -184,6 +191,13 @@ impl NativeEngineExt for crate::engine::Engine {
             _ => panic!("Not a `sys` engine!"),
         }
     }
+
+    fn set_source_map_resolver(&self, resolver: Option<SourceMapResolver>) {
+        match self.be {
+            BackendEngine::Sys(ref s) => s.set_source_map_resolver(resolver),
+            _ => panic!("Not a `sys` engine!"),
+        }
+    }
 }
 
 impl crate::Engine {
@@ -2,8 +2,9 @@
 
 use crate::{
     error::InstantiationError, exports::Exports, imports::Imports, module::Module,
-    store::AsStoreMut, Extern,
+    store::AsStoreMut, Extern, Function,
 };
+use wasmer_types::{ExportIndex, ImportLimits};
 use wasmer_vm::{StoreHandle, VMInstance};
 
 use super::store::Store;
@@ -50,7 +51,9 @@ impl Instance {
         let externs = imports
             .imports_for_module(module)
             .map_err(InstantiationError::Link)?;
-        let mut handle = module.as_sys().instantiate(store, &externs)?;
+        let mut handle = module
+            .as_sys()
+            .instantiate(store, &externs, imports.limits())?;
         let exports = Self::get_exports(store, module, handle.as_sys_mut());
 
         let instance = Self {
@@ -60,6 +63,108 @@ impl Instance {
         Ok((instance, exports))
     }
 
+    #[cfg(feature = "tokio")]
+    pub(crate) async fn new_async(
+        store: &mut impl AsStoreMut,
+        module: &Module,
+        imports: &Imports,
+        chunk_size: usize,
+    ) -> Result<(Self, Exports), InstantiationError> {
+        let externs = imports
+            .imports_for_module(module)
+            .map_err(InstantiationError::Link)?;
+        let handle = module
+            .as_sys()
+            .instantiate_async(store, &externs, imports.limits(), chunk_size)
+            .await?;
+
+        let exports = Self::lookup_exports(store, module, &handle);
+
+        Ok((Self { _handle: handle }, exports))
+    }
+
+    /// Like [`Self::new()`], but returns the start function instead of
+    /// running it.
+    #[allow(clippy::result_large_err)]
+    pub(crate) fn new_without_start(
+        store: &mut impl AsStoreMut,
+        module: &Module,
+        imports: &Imports,
+    ) -> Result<(Self, Exports, Option<Function>), InstantiationError> {
+        let externs = imports
+            .imports_for_module(module)
+            .map_err(InstantiationError::Link)?;
+        let handle =
+            module
+                .as_sys()
+                .instantiate_without_start(store, &externs, imports.limits())?;
+
+        Ok(Self::split_start(store, module, handle))
+    }
+
+    /// Like [`Self::new_async()`], but returns the start function instead
+    /// of running it.
+    #[cfg(feature = "tokio")]
+    pub(crate) async fn new_async_without_start(
+        store: &mut impl AsStoreMut,
+        module: &Module,
+        imports: &Imports,
+        chunk_size: usize,
+    ) -> Result<(Self, Exports, Option<Function>), InstantiationError> {
+        let externs = imports
+            .imports_for_module(module)
+            .map_err(InstantiationError::Link)?;
+        let handle = module
+            .as_sys()
+            .instantiate_async_without_start(store, &externs, imports.limits(), chunk_size)
+            .await?;
+
+        Ok(Self::split_start(store, module, handle))
+    }
+
+    /// Looks up the exports and the start function of an instance that
+    /// hasn't been started yet.
+    fn split_start(
+        store: &mut impl AsStoreMut,
+        module: &Module,
+        handle: StoreHandle<VMInstance>,
+    ) -> (Self, Exports, Option<Function>) {
+        let exports = Self::lookup_exports(store, module, &handle);
+        let start = module.as_sys().info().start_function.map(|index| {
+            let export = handle
+                .get_mut(store.objects_mut().as_sys_mut())
+                .lookup_by_declaration(ExportIndex::Function(index));
+            match Extern::from_vm_extern(store, crate::vm::VMExtern::Sys(export)) {
+                Extern::Function(start) => start,
+                _ => unreachable!("the start function is a function"),
+            }
+        });
+
+        (Self { _handle: handle }, exports, start)
+    }
+
+    /// Looks up the exports of an instance that already lives in the store,
+    /// which have to be looked up before they can be turned into `Extern`s.
+    fn lookup_exports(
+        store: &mut impl AsStoreMut,
+        module: &Module,
+        handle: &StoreHandle<VMInstance>,
+    ) -> Exports {
+        let instance = handle.get_mut(store.objects_mut().as_sys_mut());
+        let exports: Vec<_> = module
+            .as_sys()
+            .info()
+            .exports
+            .values()
+            .map(|index| instance.lookup_by_declaration(*index))
+            .collect();
+        let values = exports
+            .into_iter()
+            .map(|export| Extern::from_vm_extern(store, crate::vm::VMExtern::Sys(export)))
+            .collect();
+        Exports::from_shared_names(module.as_sys().export_names().clone(), values)
+    }
+
     #[allow(clippy::result_large_err)]
     pub(crate) fn new_by_index(
         store: &mut impl AsStoreMut,
@@ -67,7 +172,9 @@ impl Instance {
         externs: &[Extern],
     ) -> Result<(Self, Exports), InstantiationError> {
         let externs = externs.to_vec();
-        let mut handle = module.as_sys().instantiate(store, &externs)?;
+        let mut handle = module
+            .as_sys()
+            .instantiate(store, &externs, ImportLimits::Strict)?;
         let exports = Self::get_exports(store, module, handle.as_sys_mut());
         let instance = Self {
             _handle: StoreHandle::new(
@@ -84,15 +191,17 @@ impl Instance {
         module: &Module,
         handle: &mut VMInstance,
     ) -> Exports {
-        module
-            .exports()
-            .map(|export| {
-                let name = export.name().to_string();
-                let export = handle.lookup(&name).expect("export");
-                let extern_ = Extern::from_vm_extern(store, crate::vm::VMExtern::Sys(export));
-                (name, extern_)
+        let module = module.as_sys();
+        let values = module
+            .info()
+            .exports
+            .values()
+            .map(|index| {
+                let export = handle.lookup_by_declaration(*index);
+                Extern::from_vm_extern(store, crate::vm::VMExtern::Sys(export))
             })
-            .collect::<Exports>()
+            .collect();
+        Exports::from_shared_names(module.export_names().clone(), values)
     }
 }
 
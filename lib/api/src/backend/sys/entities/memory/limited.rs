@@ -0,0 +1,252 @@
+//! Memories accounted for with a store's [`MemoryLimiter`].
+use std::{
+    ptr::NonNull,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use wasmer_compiler::Tunables;
+use wasmer_types::{
+    FunctionType, GlobalType, MemoryStyle, MemoryType, Pages, TableStyle, TableType, TagKind,
+    WASM_PAGE_SIZE,
+};
+use wasmer_vm::{
+    LinearMemory, MemoryError, NotifyLocation, ThreadConditions, Trap, VMConfig, VMGlobal,
+    VMMemory, VMMemoryDefinition, VMTable, VMTableDefinition, VMTag, WaiterError,
+};
+
+use crate::entities::store::MemoryLimiter;
+
+/// What a memory, and every clone of it, was charged.
+#[derive(Debug)]
+struct Charge {
+    limiter: Arc<dyn MemoryLimiter>,
+    pages: Mutex<Pages>,
+}
+
+impl Charge {
+    /// Charges whatever `target` needs on top of what is charged already.
+    fn reserve(&self, charged: &mut Pages, target: Pages) -> Result<(), MemoryError> {
+        if target > *charged {
+            self.limiter.on_grow(target - *charged)?;
+            *charged = target;
+        }
+        Ok(())
+    }
+
+    /// Releases what was reserved but isn't used after all.
+    fn settle(&self, charged: &mut Pages, size: Pages) {
+        if size < *charged {
+            self.limiter.on_shrink(*charged - size);
+            *charged = size;
+        }
+    }
+}
+
+impl Drop for Charge {
+    fn drop(&mut self) {
+        let pages = *self.pages.get_mut().unwrap();
+        if pages.0 > 0 {
+            self.limiter.on_shrink(pages);
+        }
+    }
+}
+
+/// A memory that is charged to a [`MemoryLimiter`] as it grows.
+#[derive(Debug)]
+pub(crate) struct LimitedMemory {
+    inner: VMMemory,
+    charge: Arc<Charge>,
+}
+
+impl LimitedMemory {
+    /// Charges the initial size of a memory of type `ty` to `limiter`, then
+    /// creates it with `create`.
+    fn create(
+        ty: &MemoryType,
+        limiter: &Arc<dyn MemoryLimiter>,
+        create: impl FnOnce() -> Result<VMMemory, MemoryError>,
+    ) -> Result<VMMemory, MemoryError> {
+        let charge = Arc::new(Charge {
+            limiter: limiter.clone(),
+            pages: Mutex::new(Pages(0)),
+        });
+        charge.reserve(&mut charge.pages.lock().unwrap(), ty.minimum)?;
+        // Dropping the charge releases the reservation if this fails
+        let inner = create()?;
+        charge.settle(&mut charge.pages.lock().unwrap(), inner.size());
+
+        Ok(VMMemory(Box::new(Self { inner, charge })))
+    }
+
+    /// Wraps a memory that was created without being charged.
+    pub(crate) fn wrap(
+        inner: VMMemory,
+        limiter: &Arc<dyn MemoryLimiter>,
+    ) -> Result<VMMemory, MemoryError> {
+        let ty = MemoryType {
+            minimum: inner.size(),
+            ..inner.ty()
+        };
+        Self::create(&ty, limiter, || Ok(inner))
+    }
+
+    /// Resizes the memory with `resize`, which leaves it at most `target`
+    /// pages large.
+    fn resize<T>(
+        &mut self,
+        target: Pages,
+        resize: impl FnOnce(&mut VMMemory) -> Result<T, MemoryError>,
+    ) -> Result<T, MemoryError> {
+        let mut charged = self.charge.pages.lock().unwrap();
+        self.charge.reserve(&mut charged, target)?;
+        let result = resize(&mut self.inner);
+        self.charge.settle(&mut charged, self.inner.size());
+        result
+    }
+}
+
+impl LinearMemory for LimitedMemory {
+    fn ty(&self) -> MemoryType {
+        self.inner.ty()
+    }
+
+    fn size(&self) -> Pages {
+        self.inner.size()
+    }
+
+    fn style(&self) -> MemoryStyle {
+        self.inner.style()
+    }
+
+    fn grow(&mut self, delta: Pages) -> Result<Pages, MemoryError> {
+        let current = self.inner.size();
+        let target =
+            current
+                .0
+                .checked_add(delta.0)
+                .map(Pages)
+                .ok_or(MemoryError::CouldNotGrow {
+                    current,
+                    attempted_delta: delta,
+                })?;
+        self.resize(target, |inner| inner.grow(delta))
+    }
+
+    fn grow_at_least(&mut self, min_size: u64) -> Result<(), MemoryError> {
+        let current = self.inner.size();
+        let needed = min_size.div_ceil(WASM_PAGE_SIZE as u64);
+        let target = Pages(u32::try_from(needed).unwrap_or(u32::MAX)).max(current);
+        self.resize(target, |inner| inner.grow_at_least(min_size))
+    }
+
+    fn reset(&mut self) -> Result<(), MemoryError> {
+        self.resize(Pages(0), |inner| inner.reset())
+    }
+
+    fn vmmemory(&self) -> NonNull<VMMemoryDefinition> {
+        self.inner.vmmemory()
+    }
+
+    fn try_clone(&self) -> Result<Box<dyn LinearMemory + 'static>, MemoryError> {
+        Ok(Box::new(Self {
+            inner: self.inner.try_clone()?.into(),
+            charge: self.charge.clone(),
+        }))
+    }
+
+    unsafe fn initialize_with_data(&self, start: usize, data: &[u8]) -> Result<(), Trap> {
+        self.inner.initialize_with_data(start, data)
+    }
+
+    /// The copy isn't charged to anything, [`wrap()`](LimitedMemory::wrap)
+    /// it to charge it to the store it is put in.
+    fn copy(&mut self) -> Result<Box<dyn LinearMemory + 'static>, MemoryError> {
+        self.inner.copy()
+    }
+
+    fn do_wait(
+        &mut self,
+        dst: NotifyLocation,
+        timeout: Option<Duration>,
+    ) -> Result<u32, WaiterError> {
+        self.inner.do_wait(dst, timeout)
+    }
+
+    fn do_notify(&mut self, dst: NotifyLocation, count: u32) -> u32 {
+        self.inner.do_notify(dst, count)
+    }
+
+    fn thread_conditions(&self) -> Option<&ThreadConditions> {
+        self.inner.thread_conditions()
+    }
+}
+
+/// Tunables that charge the memories `base` creates to `limiter`.
+pub(crate) struct LimitedTunables<'a> {
+    pub(crate) base: &'a dyn Tunables,
+    pub(crate) limiter: &'a Arc<dyn MemoryLimiter>,
+}
+
+impl Tunables for LimitedTunables<'_> {
+    fn memory_style(&self, memory: &MemoryType) -> MemoryStyle {
+        self.base.memory_style(memory)
+    }
+
+    fn table_style(&self, table: &TableType) -> TableStyle {
+        self.base.table_style(table)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        self.base.validate()
+    }
+
+    fn memory_grow_limit(&self, ty: &MemoryType) -> Option<Pages> {
+        self.base.memory_grow_limit(ty)
+    }
+
+    fn create_host_memory(
+        &self,
+        ty: &MemoryType,
+        style: &MemoryStyle,
+    ) -> Result<VMMemory, MemoryError> {
+        LimitedMemory::create(ty, self.limiter, || self.base.create_host_memory(ty, style))
+    }
+
+    unsafe fn create_vm_memory(
+        &self,
+        ty: &MemoryType,
+        style: &MemoryStyle,
+        vm_definition_location: NonNull<VMMemoryDefinition>,
+    ) -> Result<VMMemory, MemoryError> {
+        LimitedMemory::create(ty, self.limiter, || {
+            self.base
+                .create_vm_memory(ty, style, vm_definition_location)
+        })
+    }
+
+    fn create_host_table(&self, ty: &TableType, style: &TableStyle) -> Result<VMTable, String> {
+        self.base.create_host_table(ty, style)
+    }
+
+    unsafe fn create_vm_table(
+        &self,
+        ty: &TableType,
+        style: &TableStyle,
+        vm_definition_location: NonNull<VMTableDefinition>,
+    ) -> Result<VMTable, String> {
+        self.base.create_vm_table(ty, style, vm_definition_location)
+    }
+
+    fn create_global(&self, ty: GlobalType) -> Result<VMGlobal, String> {
+        self.base.create_global(ty)
+    }
+
+    fn create_tag(&self, kind: TagKind, ty: FunctionType) -> Result<VMTag, String> {
+        self.base.create_tag(kind, ty)
+    }
+
+    fn vmconfig(&self) -> &VMConfig {
+        self.base.vmconfig()
+    }
+}
@@ -1,23 +1,32 @@
 //! Data types, functions and traits for `sys` runtime's `Memory` implementation.
 use std::{
+    any::Any,
     convert::TryInto,
     marker::PhantomData,
     mem::{self, MaybeUninit},
+    ptr::NonNull,
     slice,
 };
 
 use tracing::warn;
-use wasmer_types::{MemoryType, Pages};
-use wasmer_vm::{LinearMemory, MemoryError, StoreHandle, ThreadConditionsHandle, VMMemory};
+use wasmer_compiler::Tunables;
+use wasmer_types::{MemoryGrowthPolicy, MemoryStyle, MemoryType, Pages};
+use wasmer_vm::{
+    LinearMemory, MemoryError, StoreHandle, ThreadConditionsHandle, VMExternalMemory, VMMemory,
+};
 
 use crate::{
-    backend::sys::entities::{engine::NativeEngineExt, memory::MemoryView},
+    backend::sys::entities::{
+        engine::NativeEngineExt,
+        memory::{limited::LimitedTunables, MemoryView},
+    },
     entities::store::{AsStoreMut, AsStoreRef},
     location::{MemoryLocation, SharedMemoryOps},
     vm::{VMExtern, VMExternMemory},
     BackendMemory, MemoryAccessError,
 };
 
+pub(crate) mod limited;
 pub(crate) mod view;
 pub use view::*;
 
@@ -33,15 +42,36 @@ pub struct Memory {
 impl Memory {
     pub(crate) fn new(store: &mut impl AsStoreMut, ty: MemoryType) -> Result<Self, MemoryError> {
         let mut store = store.as_store_mut();
-        let tunables = store.engine().tunables();
-        let style = tunables.memory_style(&ty);
-        let memory = tunables.create_host_memory(&ty, &style)?;
+        let base = store.engine().tunables();
+        let style = base.memory_style(&ty);
+        let memory = match store.inner.memory_limiter.as_ref() {
+            Some(limiter) => LimitedTunables { base, limiter }.create_host_memory(&ty, &style)?,
+            None => base.create_host_memory(&ty, &style)?,
+        };
 
         Ok(Self {
             handle: StoreHandle::new(store.as_store_mut().objects_mut().as_sys_mut(), memory),
         })
     }
 
+    pub(crate) unsafe fn new_from_host_buffer(
+        store: &mut impl AsStoreMut,
+        ty: MemoryType,
+        base: NonNull<u8>,
+        len: usize,
+        policy: MemoryGrowthPolicy,
+        owner: Option<Box<dyn Any + Send>>,
+    ) -> Result<Self, MemoryError> {
+        let memory = match owner {
+            Some(owner) => VMExternalMemory::with_owner(&ty, base, len, policy, owner)?,
+            None => VMExternalMemory::new(&ty, base, len, policy)?,
+        };
+
+        Ok(Self {
+            handle: StoreHandle::new(store.objects_mut().as_sys_mut(), memory.into()),
+        })
+    }
+
     pub(crate) fn new_from_existing(new_store: &mut impl AsStoreMut, memory: VMMemory) -> Self {
         let handle = StoreHandle::new(new_store.objects_mut().as_sys_mut(), memory);
         Self::from_vm_extern(new_store, VMExternMemory::Sys(handle.internal_handle()))
@@ -53,6 +83,12 @@ impl Memory {
             .ty()
     }
 
+    pub(crate) fn style(&self, store: &impl AsStoreRef) -> MemoryStyle {
+        self.handle
+            .get(store.as_store_ref().objects().as_sys())
+            .style()
+    }
+
     pub(crate) fn size(&self, store: &impl AsStoreRef) -> Pages {
         self.handle
             .get(store.as_store_ref().objects().as_sys())
@@ -3,15 +3,20 @@ use std::path::Path;
 use std::sync::Arc;
 
 use bytes::Bytes;
-use wasmer_compiler::{Artifact, ArtifactCreate, Engine};
+use indexmap::IndexSet;
+use wasmer_compiler::{Artifact, ArtifactCreate, Engine, Tunables};
 use wasmer_types::{
-    CompileError, DeserializeError, ExportType, ExportsIterator, ImportType, ImportsIterator,
-    ModuleInfo, SerializeError,
+    target::Target, ArtifactStats, CompileError, DeserializeError, ExportType, ExportsIterator,
+    Features, ImportLimits, ImportType, ImportsIterator, ModuleInfo, SerializeError,
 };
 
 use crate::{
-    backend::sys::entities::engine::NativeEngineExt, engine::AsEngineRef,
-    error::InstantiationError, vm::VMInstance, AsStoreMut, AsStoreRef, BackendModule, IntoBytes,
+    backend::sys::entities::{engine::NativeEngineExt, memory::limited::LimitedTunables},
+    engine::AsEngineRef,
+    entities::store::limits,
+    error::{InstantiationError, LinkError},
+    vm::VMInstance,
+    AsStoreMut, AsStoreRef, BackendModule, IntoBytes,
 };
 
 #[derive(Clone, PartialEq, Eq)]
@@ -33,6 +38,9 @@ pub struct Module {
     // In the future, this code should be refactored to properly describe the
     // ownership of the code and its metadata.
     artifact: Arc<Artifact>,
+    // The export names, shared by the `Exports` of every instance.
+    #[cfg_attr(feature = "artifact-size", loupe(skip))]
+    export_names: Arc<IndexSet<String>>,
 }
 
 impl Module {
@@ -40,8 +48,9 @@ impl Module {
         engine: &impl AsEngineRef,
         binary: &[u8],
     ) -> Result<Self, CompileError> {
-        Self::validate(engine, binary)?;
-        unsafe { Self::from_binary_unchecked(engine, binary) }
+        // Compiling validates the module, and records the features it uses
+        // while doing so
+        Self::compile(engine, binary)
     }
 
     pub(crate) unsafe fn from_binary_unchecked(
@@ -52,6 +61,33 @@ impl Module {
         Ok(module)
     }
 
+    /// Validate the module while it is being read, then compile it once the
+    /// whole module has arrived.
+    #[cfg(all(feature = "compiler", feature = "tokio"))]
+    pub(crate) async fn from_async_reader(
+        engine: &impl AsEngineRef,
+        mut reader: impl tokio::io::AsyncRead + Unpin,
+    ) -> Result<Self, crate::IoCompileError> {
+        use tokio::io::AsyncReadExt;
+
+        let mut validator = engine
+            .as_engine_ref()
+            .engine()
+            .as_sys()
+            .streaming_validator();
+        let mut chunk = vec![0; 64 * 1024];
+        loop {
+            let len = reader.read(&mut chunk).await?;
+            if len == 0 {
+                break;
+            }
+            validator.write(&chunk[..len])?;
+        }
+        let binary = validator.finish()?;
+
+        Ok(unsafe { Self::from_binary_unchecked(engine, &binary)? })
+    }
+
     #[cfg(feature = "compiler")]
     #[tracing::instrument(level = "debug", skip_all)]
     pub(crate) fn validate(engine: &impl AsEngineRef, binary: &[u8]) -> Result<(), CompileError> {
@@ -65,9 +101,39 @@ impl Module {
         ))
     }
 
+    #[cfg(feature = "compiler")]
+    pub(crate) fn validate_with_features(
+        features: &Features,
+        binary: &[u8],
+    ) -> Result<(), CompileError> {
+        wasmer_compiler::validate_module_with_features(features, binary)
+    }
+
+    #[cfg(not(feature = "compiler"))]
+    pub(crate) fn validate_with_features(
+        _features: &Features,
+        _binary: &[u8],
+    ) -> Result<(), CompileError> {
+        Err(CompileError::UnsupportedTarget(
+            "The compiler feature is not enabled, but is required to validate a Module".to_string(),
+        ))
+    }
+
     #[cfg(feature = "compiler")]
     fn compile(engine: &impl AsEngineRef, binary: &[u8]) -> Result<Self, CompileError> {
-        let artifact = engine.as_engine_ref().engine().as_sys().compile(binary)?;
+        // Modules using features the store forbids shouldn't even make it to
+        // the compiler
+        let disabled = engine
+            .maybe_as_store()
+            .map_or_else(Features::none, |store| {
+                store.inner.disabled_features.clone()
+            });
+
+        let engine = engine.as_engine_ref();
+        let engine = engine.engine().as_sys();
+        let artifact = engine
+            .compile_without_features(binary, &disabled)
+            .map_err(|e| with_alternative_compilers(e, binary, engine.target()))?;
         Ok(Self::from_artifact(artifact))
     }
 
@@ -135,7 +201,11 @@ impl Module {
     }
 
     pub(super) fn from_artifact(artifact: Arc<Artifact>) -> Self {
-        Self { artifact }
+        let export_names = Arc::new(artifact.module_info().exports.keys().cloned().collect());
+        Self {
+            artifact,
+            export_names,
+        }
     }
 
     #[allow(clippy::result_large_err)]
@@ -143,7 +213,127 @@ impl Module {
         &self,
         store: &mut impl AsStoreMut,
         imports: &[crate::Extern],
+        limits: ImportLimits,
     ) -> Result<VMInstance, InstantiationError> {
+        let mut instance_handle = self.create_instance(store, imports, limits)?;
+
+        let signal_handler = store.as_store_ref().signal_handler();
+        let store_mut = store.as_store_mut();
+        let config = store_mut.engine().tunables().vmconfig();
+        unsafe {
+            // After the instance handle is created, we need to initialize
+            // the data, call the start function and so. However, if any
+            // of this steps traps, we still need to keep the instance alive
+            // as some of the Instance elements may have placed in other
+            // instance tables.
+            self.artifact
+                .finish_instantiation(config, signal_handler, &mut instance_handle)?;
+        }
+
+        Ok(VMInstance::Sys(instance_handle))
+    }
+
+    /// Like [`Self::instantiate()`], but leaves running the start function
+    /// to the caller.
+    ///
+    /// The instance is added to the store, so the start function can be
+    /// looked up and called like any other function.
+    #[allow(clippy::result_large_err)]
+    pub(crate) fn instantiate_without_start(
+        &self,
+        store: &mut impl AsStoreMut,
+        imports: &[crate::Extern],
+        limits: ImportLimits,
+    ) -> Result<wasmer_vm::StoreHandle<wasmer_vm::VMInstance>, InstantiationError> {
+        use wasmer_vm::{InitializationProgress, StoreHandle};
+
+        let instance_handle = self.create_instance(store, imports, limits)?;
+        let handle = StoreHandle::new(store.objects_mut().as_sys_mut(), instance_handle);
+
+        // No module has anywhere near `usize::MAX` bytes of segments, so this
+        // applies all of them in one step
+        let data_initializers = self.artifact.vm_data_initializers();
+        let mut progress = InitializationProgress::default();
+        let instance = handle.get_mut(store.objects_mut().as_sys_mut());
+        unsafe { instance.initialize_step(&data_initializers, &mut progress, usize::MAX) }
+            .map_err(|trap| InstantiationError::Start(trap.into()))?;
+
+        Ok(handle)
+    }
+
+    /// Like [`Self::instantiate()`], but applies the table and data
+    /// initializers `chunk_size` bytes at a time, yielding to the executor
+    /// in between.
+    ///
+    /// The instance is added to the store before it is initialized, as a
+    /// `VMInstance` can't be held across `await`s.
+    #[cfg(feature = "tokio")]
+    pub(crate) async fn instantiate_async(
+        &self,
+        store: &mut impl AsStoreMut,
+        imports: &[crate::Extern],
+        limits: ImportLimits,
+        chunk_size: usize,
+    ) -> Result<wasmer_vm::StoreHandle<wasmer_vm::VMInstance>, InstantiationError> {
+        let handle = self
+            .instantiate_async_without_start(store, imports, limits, chunk_size)
+            .await?;
+
+        let signal_handler = store.as_store_ref().signal_handler();
+        let engine = store.as_store_ref().engine().clone();
+        let config = engine.tunables().vmconfig();
+        let instance = handle.get_mut(store.objects_mut().as_sys_mut());
+        unsafe { instance.invoke_start_function(config, signal_handler) }
+            .map_err(|trap| InstantiationError::Start(trap.into()))?;
+
+        Ok(handle)
+    }
+
+    /// Like [`Self::instantiate_async()`], but leaves running the start
+    /// function to the caller.
+    #[cfg(feature = "tokio")]
+    pub(crate) async fn instantiate_async_without_start(
+        &self,
+        store: &mut impl AsStoreMut,
+        imports: &[crate::Extern],
+        limits: ImportLimits,
+        chunk_size: usize,
+    ) -> Result<wasmer_vm::StoreHandle<wasmer_vm::VMInstance>, InstantiationError> {
+        use wasmer_vm::{InitializationProgress, StoreHandle};
+
+        let instance_handle = self.create_instance(store, imports, limits)?;
+        let handle = StoreHandle::new(store.objects_mut().as_sys_mut(), instance_handle);
+
+        let data_initializers = self.artifact.vm_data_initializers();
+        let mut progress = InitializationProgress::default();
+        loop {
+            let step = {
+                let instance = handle.get_mut(store.objects_mut().as_sys_mut());
+                unsafe { instance.initialize_step(&data_initializers, &mut progress, chunk_size) }
+            };
+            if step.map_err(|trap| InstantiationError::Start(trap.into()))? {
+                break;
+            }
+            tokio::task::yield_now().await;
+        }
+
+        Ok(handle)
+    }
+
+    /// Creates the instance of this module, leaving its initialization to
+    /// the caller.
+    #[allow(clippy::result_large_err)]
+    fn create_instance(
+        &self,
+        store: &mut impl AsStoreMut,
+        imports: &[crate::Extern],
+        limits: ImportLimits,
+    ) -> Result<wasmer_vm::VMInstance, InstantiationError> {
+        if let Some(name) = disabled_feature(store, self.required_features()) {
+            return Err(InstantiationError::Link(LinkError::DisabledFeature(
+                name.to_string(),
+            )));
+        }
         if !self.artifact.allocated() {
             // Return an error mentioning that the artifact is compiled for a different
             // platform.
@@ -155,30 +345,39 @@ impl Module {
                 return Err(InstantiationError::DifferentStores);
             }
         }
-        let signal_handler = store.as_store_ref().signal_handler();
+        let amounts = limits::instance_amounts(self.info());
+        limits::reserve(store, &amounts)?;
+
         let mut store_mut = store.as_store_mut();
+        let memory_limiter = store_mut.inner.memory_limiter.clone();
         let (engine, objects) = store_mut.engine_and_objects_mut();
-        let config = engine.tunables().vmconfig();
-        unsafe {
-            let mut instance_handle = self.artifact.instantiate(
-                engine.tunables(),
+        let limited_tunables;
+        let tunables = match memory_limiter.as_ref() {
+            Some(limiter) => {
+                limited_tunables = LimitedTunables {
+                    base: engine.tunables(),
+                    limiter,
+                };
+                &limited_tunables as &dyn Tunables
+            }
+            None => engine.tunables(),
+        };
+        let instance_handle = unsafe {
+            self.artifact.instantiate(
+                tunables,
                 &imports
                     .iter()
                     .map(|e| crate::Extern::to_vm_extern(e).into_sys())
                     .collect::<Vec<_>>(),
+                limits,
                 objects.as_sys_mut(),
-            )?;
-
-            // After the instance handle is created, we need to initialize
-            // the data, call the start function and so. However, if any
-            // of this steps traps, we still need to keep the instance alive
-            // as some of the Instance elements may have placed in other
-            // instance tables.
-            self.artifact
-                .finish_instantiation(config, signal_handler, &mut instance_handle)?;
-
-            Ok(VMInstance::Sys(instance_handle))
-        }
+            )
+        };
+        instance_handle.map_err(|e| {
+            // Nothing was created
+            limits::release(store, &amounts);
+            e.into()
+        })
     }
 
     pub(crate) fn name(&self) -> Option<&str> {
@@ -205,9 +404,84 @@ impl Module {
         self.info().custom_sections(name)
     }
 
+    pub(crate) fn custom_section_names(&self) -> Box<dyn Iterator<Item = &str> + '_> {
+        Box::new(self.info().custom_section_names())
+    }
+
+    pub(crate) fn export_names(&self) -> &Arc<IndexSet<String>> {
+        &self.export_names
+    }
+
     pub(crate) fn info(&self) -> &ModuleInfo {
         self.artifact.module_info()
     }
+
+    pub(crate) fn required_features(&self) -> &Features {
+        self.artifact.required_features()
+    }
+
+    pub(crate) fn stats(&self) -> &ArtifactStats {
+        self.artifact.stats()
+    }
+}
+
+/// The first of the `required` features that has been disabled for the
+/// `store`.
+fn disabled_feature(store: &impl AsStoreRef, required: &Features) -> Option<&'static str> {
+    store
+        .as_store_ref()
+        .inner
+        .disabled_features
+        .shared(required)
+        .next()
+}
+
+/// Lists the other compilers built into this crate that can translate the
+/// instruction a [`CompileError::UnsupportedOperator`] is about.
+#[cfg(feature = "compiler")]
+fn with_alternative_compilers(error: CompileError, binary: &[u8], target: &Target) -> CompileError {
+    use wasmer_compiler::{wasmparser::BinaryReader, CompilerConfig};
+
+    let CompileError::UnsupportedOperator(mut unsupported) = error else {
+        return error;
+    };
+    let Some(data) = binary.get(unsupported.offset..) else {
+        return CompileError::UnsupportedOperator(unsupported);
+    };
+    let Ok(operator) = BinaryReader::new(data, unsupported.offset).read_operator() else {
+        return CompileError::UnsupportedOperator(unsupported);
+    };
+    let required = Features::required_by_operator(&operator);
+
+    let compilers: Vec<(&str, Box<dyn CompilerConfig>)> = vec![
+        #[cfg(feature = "cranelift")]
+        (
+            "cranelift",
+            Box::new(wasmer_compiler_cranelift::Cranelift::default()) as Box<dyn CompilerConfig>,
+        ),
+        #[cfg(feature = "llvm")]
+        (
+            "llvm",
+            Box::new(wasmer_compiler_llvm::LLVM::default()) as Box<dyn CompilerConfig>,
+        ),
+        #[cfg(feature = "singlepass")]
+        (
+            "singlepass",
+            Box::new(wasmer_compiler_singlepass::Singlepass::default()) as Box<dyn CompilerConfig>,
+        ),
+    ];
+    for (name, config) in compilers {
+        if name != unsupported.compiler
+            && config
+                .supported_features_for_target(target)
+                .contains_features(&required)
+            && config.supports_operator(&operator)
+        {
+            unsupported.supported_by.push(name.to_string());
+        }
+    }
+
+    CompileError::UnsupportedOperator(unsupported)
 }
 
 impl crate::Module {
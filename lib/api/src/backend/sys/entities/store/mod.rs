@@ -23,13 +23,14 @@ impl std::fmt::Debug for Store {
 }
 
 impl Store {
-    pub(crate) fn new(engine: Engine) -> Self {
+    pub(crate) fn try_new(engine: Engine) -> Result<Self, String> {
+        engine.as_sys().tunables().validate()?;
         init_traps();
 
-        Self {
+        Ok(Self {
             engine,
             trap_handler: None,
-        }
+        })
     }
 
     pub(crate) fn engine(&self) -> &Engine {
@@ -11,10 +11,11 @@ pub use tunables::*;
 
 #[cfg(feature = "compiler")]
 pub use wasmer_compiler::{
-    wasmparser, CompilerConfig, FunctionMiddleware, MiddlewareReaderState, ModuleMiddleware,
+    wasmparser, CompilerConfig, FunctionBodyData, FunctionMiddleware, MiddlewareReaderState,
+    ModuleMiddleware,
 };
 
-pub use wasmer_compiler::{Artifact, EngineBuilder, Features, Tunables};
+pub use wasmer_compiler::{Artifact, EngineBuilder, Features, SourceMapResolver, Tunables};
 
 pub use wasmer_types::target::{Architecture, CpuFeature, OperatingSystem, Target, Triple};
 pub use wasmer_types::MiddlewareError;
@@ -20,11 +20,10 @@ mod tests {
 
     #[test]
     fn memory_style() {
-        let tunables = BaseTunables {
-            static_memory_bound: Pages(2048),
-            static_memory_offset_guard_size: 128,
-            dynamic_memory_offset_guard_size: 256,
-        };
+        let mut tunables =
+            BaseTunables::for_target(&Default::default()).with_static_memory_bound(Pages(2048));
+        tunables.static_memory_offset_guard_size = 128;
+        tunables.dynamic_memory_offset_guard_size = 256;
 
         // No maximum
         let requested = MemoryType::new(3, None, true);
@@ -55,6 +54,46 @@ mod tests {
             }
             s => panic!("Unexpected memory style: {s:?}"),
         }
+
+        // Small maximum, but every access is checked
+        let tunables = tunables.with_dynamic_bounds_checks(true);
+        let style = tunables.memory_style(&requested);
+        match style {
+            MemoryStyle::Dynamic { offset_guard_size } => assert_eq!(offset_guard_size, 256),
+            s => panic!("Unexpected memory style: {s:?}"),
+        }
+    }
+
+    #[test]
+    fn validate_tunables() {
+        let tunables = BaseTunables::for_target(&Default::default());
+        assert!(tunables.validate().is_ok());
+        assert!(tunables.clone().with_guard_size(0).validate().is_ok());
+        assert!(tunables
+            .clone()
+            .with_static_memory_bound(Pages(16))
+            .with_guard_size(0x1_0000)
+            .validate()
+            .is_ok());
+
+        // Guards have to be made of whole pages
+        assert!(tunables.clone().with_guard_size(128).validate().is_err());
+
+        // Static memories have to fit in the address space, unless they are
+        // never used
+        let tunables = tunables.with_guard_size(u64::MAX & !0xffff);
+        assert!(tunables.validate().is_err());
+        assert!(tunables
+            .clone()
+            .with_dynamic_bounds_checks(true)
+            .validate()
+            .is_ok());
+
+        // Stores refuse engines with invalid tunables
+        let mut engine = crate::Engine::headless();
+        engine.set_tunables(tunables);
+        let err = crate::Store::try_new(engine).unwrap_err();
+        assert!(err.contains("address space"), "{err}");
     }
 
     #[derive(Debug)]
@@ -305,6 +344,52 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn memory_grow_limit() -> Result<(), Box<dyn std::error::Error>> {
+        #[cfg(feature = "wat")]
+        use crate::wat2wasm;
+        use crate::{imports, Engine, Instance, Module, Store};
+
+        // The memory doesn't declare a maximum
+        let wasm_bytes = wat2wasm(br#"(module (memory (export "memory") 1))"#)?;
+
+        cfg_if::cfg_if! {
+            if #[cfg(feature = "singlepass")] {
+                let compiler =  wasmer_compiler_singlepass::Singlepass::default();
+            } else if #[cfg(feature = "llvm")] {
+                let compiler =  wasmer_compiler_llvm::LLVM::default();
+            } else {
+                let compiler =  wasmer_compiler_cranelift::Cranelift::default();
+            }
+        }
+
+        let tunables =
+            BaseTunables::for_target(&Default::default()).with_memory_grow_limit(Pages(4));
+        #[allow(deprecated)]
+        let mut engine = Engine::new(compiler.into(), Default::default(), Default::default());
+        engine.set_tunables(tunables.clone());
+        let mut store = Store::new(engine);
+        let module = Module::new(&store, wasm_bytes)?;
+        let instance = Instance::new(&mut store, &module, &imports! {})?;
+
+        let memory = instance.exports.get_memory("memory")?;
+        assert_eq!(memory.grow(&mut store, 3)?, Pages(1));
+        assert_eq!(
+            memory.grow(&mut store, 1).unwrap_err(),
+            MemoryError::CouldNotGrow {
+                current: Pages(4),
+                attempted_delta: Pages(1),
+            }
+        );
+        assert_eq!(memory.ty(&store).maximum, None);
+
+        // The tunables never raise a declared maximum
+        let memory_ty = MemoryType::new(1, Some(2), false);
+        assert_eq!(tunables.memory_grow_limit(&memory_ty), Some(Pages(2)));
+
+        Ok(())
+    }
+
     #[test]
     #[cfg(all(
         feature = "singlepass",
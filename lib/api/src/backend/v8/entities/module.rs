@@ -368,6 +368,10 @@ impl Module {
         Box::new(vec![].into_iter())
     }
 
+    pub fn custom_section_names(&self) -> Box<dyn Iterator<Item = &str> + '_> {
+        Box::new(std::iter::empty())
+    }
+
     pub(crate) fn info(&self) -> &ModuleInfo {
         panic!("no info for V8 modules")
     }
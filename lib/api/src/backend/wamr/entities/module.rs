@@ -174,6 +174,10 @@ impl Module {
         self.info().custom_sections(name)
     }
 
+    pub fn custom_section_names(&self) -> Box<dyn Iterator<Item = &str> + '_> {
+        Box::new(self.info().custom_section_names())
+    }
+
     pub(crate) fn info(&self) -> &ModuleInfo {
         &self.info
     }
@@ -1,6 +1,6 @@
 use bytes::Bytes;
 use std::{path::Path, sync::Arc};
-use wasmer_types::{target::Target, DeserializeError, Features};
+use wasmer_types::{target::Target, AllocationStats, DeserializeError, Features};
 
 #[cfg(feature = "sys")]
 use wasmer_compiler::Artifact;
@@ -23,6 +23,17 @@ impl BackendEngine {
         })
     }
 
+    /// Statistics about the memory allocated for the code of loaded modules.
+    #[inline]
+    pub fn allocation_stats(&self) -> Option<AllocationStats> {
+        match self {
+            #[cfg(feature = "sys")]
+            Self::Sys(s) => Some(s.allocation_stats()),
+            #[allow(unreachable_patterns)]
+            _ => None,
+        }
+    }
+
     #[cfg(all(feature = "sys", not(target_arch = "wasm32")))]
     /// Deserializes a WebAssembly module which was previously serialized with
     /// `Module::serialize`,
@@ -4,7 +4,7 @@ use bytes::Bytes;
 use std::{path::Path, sync::Arc};
 use wasmer_types::{
     target::{Target, UserCompilerOptimizations},
-    CompileError, DeserializeError, Features,
+    AllocationStats, CompileError, DeserializeError, Features,
 };
 
 #[cfg(feature = "sys")]
@@ -74,6 +74,15 @@ impl Engine {
         EngineId(self.id)
     }
 
+    /// Statistics about the memory this engine allocated for the code of the
+    /// modules it compiled or deserialized.
+    ///
+    /// This is only known for the `sys` backend; other backends always return
+    /// `None`.
+    pub fn allocation_stats(&self) -> Option<AllocationStats> {
+        self.be.allocation_stats()
+    }
+
     /// Returns the default WebAssembly features supported by this backend for a given target.
     ///
     /// These are the features that will be enabled by default without any user configuration.
@@ -1,8 +1,13 @@
 use crate::store::AsStoreRef;
 use crate::{Extern, Function, Global, Memory, Table, TypedFunction, WasmTypeList};
-use indexmap::IndexMap;
+use indexmap::{IndexMap, IndexSet};
 use std::fmt;
 use std::iter::{ExactSizeIterator, FromIterator};
+use std::marker::PhantomData;
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc, OnceLock,
+};
 use thiserror::Error;
 
 /// The `ExportError` can happen when trying to get a specific
@@ -56,11 +61,23 @@ pub enum ExportError {
 /// Exports is a special kind of map that allows easily unwrapping
 /// the types of instances.
 ///
+/// The names are kept in a table that the exports of every instance of a
+/// [`Module`](crate::Module) share, so only the values are stored per
+/// instance. Modifying the names (e.g. by inserting a new export) gives
+/// this `Exports` its own copy of the table.
+///
 /// TODO: add examples of using exports
-#[derive(Clone, Default, PartialEq, Eq)]
+#[derive(Clone, Default)]
 #[cfg_attr(feature = "artifact-size", derive(loupe::MemoryUsage))]
 pub struct Exports {
-    map: IndexMap<String, Extern>,
+    // loupe can't measure an `IndexSet`
+    #[cfg_attr(feature = "artifact-size", loupe(skip))]
+    names: Arc<IndexSet<String>>,
+    values: Vec<Extern>,
+    /// The names and values as a map of their own, only built when iterating
+    /// over `&Exports`, whose iterator borrows from one.
+    #[cfg_attr(feature = "artifact-size", loupe(skip))]
+    map: OnceLock<IndexMap<String, Extern>>,
 }
 
 impl Exports {
@@ -72,13 +89,26 @@ impl Exports {
     /// Creates a new `Exports` with capacity `n`.
     pub fn with_capacity(n: usize) -> Self {
         Self {
-            map: IndexMap::with_capacity(n),
+            names: Arc::new(IndexSet::with_capacity(n)),
+            values: Vec::with_capacity(n),
+            map: OnceLock::new(),
+        }
+    }
+
+    /// Creates a new `Exports` from a shared table of names and the value
+    /// of each of them, in the same order.
+    pub(crate) fn from_shared_names(names: Arc<IndexSet<String>>, values: Vec<Extern>) -> Self {
+        debug_assert_eq!(names.len(), values.len());
+        Self {
+            names,
+            values,
+            map: OnceLock::new(),
         }
     }
 
     /// Return the number of exports in the `Exports` map.
     pub fn len(&self) -> usize {
-        self.map.len()
+        self.values.len()
     }
 
     /// Return whether or not there are no exports
@@ -92,7 +122,32 @@ impl Exports {
         S: Into<String>,
         E: Into<Extern>,
     {
-        self.map.insert(name.into(), value.into());
+        self.map.take();
+        let (index, added) = Arc::make_mut(&mut self.names).insert_full(name.into());
+        if added {
+            self.values.push(value.into());
+        } else {
+            self.values[index] = value.into();
+        }
+    }
+
+    /// An estimate of the heap memory owned by this `Exports`, not counting
+    /// a table of names it shares with other instances.
+    #[cfg(feature = "heap-size")]
+    pub(crate) fn approx_heap_size(&self) -> usize {
+        let mut size = self.values.capacity() * std::mem::size_of::<Extern>();
+        if Arc::strong_count(&self.names) == 1 {
+            // Each entry is stored next to its hash, plus an index into them
+            let entry = std::mem::size_of::<String>() + 2 * std::mem::size_of::<usize>();
+            size += self.names.capacity() * entry;
+            size += self.names.iter().map(String::capacity).sum::<usize>();
+        }
+        if let Some(map) = self.map.get() {
+            let entry = std::mem::size_of::<(String, Extern)>() + 2 * std::mem::size_of::<usize>();
+            size += map.capacity() * entry;
+            size += map.keys().map(String::capacity).sum::<usize>();
+        }
+        size
     }
 
     /// Get an export given a `name`.
@@ -107,7 +162,7 @@ impl Exports {
     /// If you want to get an export dynamically handling manually
     /// type checking manually, please use `get_extern`.
     pub fn get<'a, T: Exportable<'a>>(&'a self, name: &str) -> Result<&'a T, ExportError> {
-        match self.map.get(name) {
+        match self.get_extern(name) {
             None => Err(ExportError::Missing(name.to_string())),
             Some(extern_) => T::get_self_from_extern(extern_),
         }
@@ -155,7 +210,7 @@ impl Exports {
         Rets: WasmTypeList,
         T: ExportableWithGenerics<'a, Args, Rets>,
     {
-        match self.map.get(name) {
+        match self.get_extern(name) {
             None => Err(ExportError::Missing(name.to_string())),
             Some(extern_) => T::get_self_from_extern_with_generics(extern_),
         }
@@ -163,7 +218,26 @@ impl Exports {
 
     /// Get an export as an `Extern`.
     pub fn get_extern(&self, name: &str) -> Option<&Extern> {
-        self.map.get(name)
+        self.names
+            .get_index_of(name)
+            .map(|index| &self.values[index])
+    }
+
+    /// Get the position of the export called `name`, for use with
+    /// [`Exports::get_by_index()`].
+    ///
+    /// Indices are assigned in insertion order and stay the same for as
+    /// long as the `Exports` isn't modified, so embedders can resolve the
+    /// names they care about once and index their own tables from then on.
+    pub fn get_index(&self, name: &str) -> Option<usize> {
+        self.names.get_index_of(name)
+    }
+
+    /// Get the name and value of the export at `index` (as returned by
+    /// [`Exports::get_index()`]).
+    pub fn get_by_index(&self, index: usize) -> Option<(&str, &Extern)> {
+        let name = self.names.get_index(index)?;
+        Some((name.as_str(), &self.values[index]))
     }
 
     /// Returns true if the `Exports` contains the given export name.
@@ -171,17 +245,28 @@ impl Exports {
     where
         S: Into<String>,
     {
-        self.map.contains_key(&name.into())
+        self.names.contains(&name.into())
     }
 
     /// Get an iterator over the exports.
     pub fn iter(&self) -> ExportsIterator<impl Iterator<Item = (&String, &Extern)>> {
         ExportsIterator {
-            iter: self.map.iter(),
+            iter: self.names.iter().zip(self.values.iter()),
         }
     }
 }
 
+impl PartialEq for Exports {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len()
+            && self
+                .iter()
+                .all(|(name, extern_)| other.get_extern(name) == Some(extern_))
+    }
+}
+
+impl Eq for Exports {}
+
 impl fmt::Debug for Exports {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         f.debug_set().entries(self.iter()).finish()
@@ -253,11 +338,146 @@ where
     }
 }
 
+/// An immutable set of [`Exports`] that can hand out cheap handles to its
+/// typed functions.
+///
+/// Looking a function up with [`Exports::get_typed_function()`] hashes the
+/// name and checks the function's signature every time, which adds up when
+/// it is done on a hot path. A [`TypedFuncHandle`] does both once, when it
+/// is created, and afterwards only costs an index into the cache.
+///
+/// ```
+/// # use wasmer::*;
+/// # fn main() -> anyhow::Result<()> {
+/// # let mut store = Store::default();
+/// # let module = Module::new(&store, r#"(module (func (export "add") (param i32 i32) (result i32) (i32.add (local.get 0) (local.get 1))))"#)?;
+/// # let instance = Instance::new(&mut store, &module, &imports! {})?;
+/// let exports = instance.cached_exports();
+/// let add = exports.typed_function_handle::<(i32, i32), i32>(&store, "add")?;
+///
+/// for i in 0..10 {
+///     let result = exports.typed_function(&store, add).call(&mut store, i, 1)?;
+///     assert_eq!(result, i + 1);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct CachedExports {
+    id: u64,
+    exports: Exports,
+}
+
+impl CachedExports {
+    /// Wrap a set of exports.
+    pub fn new(exports: Exports) -> Self {
+        static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+        Self {
+            id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
+            exports,
+        }
+    }
+
+    /// The underlying exports.
+    pub fn exports(&self) -> &Exports {
+        &self.exports
+    }
+
+    /// Look up the function called `name` and check that it has the
+    /// signature `Args -> Rets`, returning a handle that can be used to
+    /// access it without doing either again.
+    pub fn typed_function_handle<Args, Rets>(
+        &self,
+        store: &impl AsStoreRef,
+        name: &str,
+    ) -> Result<TypedFuncHandle<Args, Rets>, ExportError>
+    where
+        Args: WasmTypeList,
+        Rets: WasmTypeList,
+    {
+        let index = self
+            .exports
+            .get_index(name)
+            .ok_or_else(|| ExportError::Missing(name.to_string()))?;
+        // Make sure the signature matches up front
+        self.exports.get_typed_function::<Args, Rets>(store, name)?;
+
+        Ok(TypedFuncHandle {
+            cache: self.id,
+            index,
+            _phantom: PhantomData,
+        })
+    }
+
+    /// Get the function a [`TypedFuncHandle`] refers to.
+    ///
+    /// # Panics
+    ///
+    /// This will panic if the handle was created by a different
+    /// `CachedExports`.
+    pub fn typed_function<Args, Rets>(
+        &self,
+        store: &impl AsStoreRef,
+        handle: TypedFuncHandle<Args, Rets>,
+    ) -> TypedFunction<Args, Rets>
+    where
+        Args: WasmTypeList,
+        Rets: WasmTypeList,
+    {
+        assert_eq!(
+            handle.cache, self.id,
+            "the TypedFuncHandle was created by a different CachedExports"
+        );
+        match self.exports.get_by_index(handle.index) {
+            Some((_, Extern::Function(func))) => TypedFunction::new(store, func.clone()),
+            _ => unreachable!("the export was checked when the handle was created"),
+        }
+    }
+}
+
+impl From<Exports> for CachedExports {
+    fn from(exports: Exports) -> Self {
+        Self::new(exports)
+    }
+}
+
+/// A pre-resolved, type-checked reference to a function in a
+/// [`CachedExports`].
+///
+/// Handles are `Copy` and only valid for the [`CachedExports`] that created
+/// them.
+pub struct TypedFuncHandle<Args, Rets> {
+    cache: u64,
+    index: usize,
+    _phantom: PhantomData<fn(Args) -> Rets>,
+}
+
+impl<Args, Rets> Clone for TypedFuncHandle<Args, Rets> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<Args, Rets> Copy for TypedFuncHandle<Args, Rets> {}
+
+impl<Args, Rets> fmt::Debug for TypedFuncHandle<Args, Rets> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("TypedFuncHandle")
+            .field("cache", &self.cache)
+            .field("index", &self.index)
+            .finish()
+    }
+}
+
 impl FromIterator<(String, Extern)> for Exports {
     fn from_iter<I: IntoIterator<Item = (String, Extern)>>(iter: I) -> Self {
-        Self {
-            map: IndexMap::from_iter(iter),
+        let iter = iter.into_iter();
+        let mut exports = Self::with_capacity(iter.size_hint().0);
+        for (name, extern_) in iter {
+            exports.insert(name, extern_);
         }
+        exports
     }
 }
 
@@ -266,7 +486,17 @@ impl IntoIterator for Exports {
     type Item = (String, Extern);
 
     fn into_iter(self) -> Self::IntoIter {
-        self.map.into_iter()
+        match self.map.into_inner() {
+            Some(map) => map.into_iter(),
+            None => {
+                let names = Arc::unwrap_or_clone(self.names);
+                names
+                    .into_iter()
+                    .zip(self.values)
+                    .collect::<IndexMap<_, _>>()
+                    .into_iter()
+            }
+        }
     }
 }
 
@@ -274,8 +504,18 @@ impl<'a> IntoIterator for &'a Exports {
     type IntoIter = indexmap::map::Iter<'a, String, Extern>;
     type Item = (&'a String, &'a Extern);
 
+    /// Prefer [`Exports::iter()`], which doesn't need to copy the names and
+    /// values into a map the first time.
     fn into_iter(self) -> Self::IntoIter {
-        self.map.iter()
+        self.map
+            .get_or_init(|| {
+                self.names
+                    .iter()
+                    .cloned()
+                    .zip(self.values.iter().cloned())
+                    .collect()
+            })
+            .iter()
     }
 }
 
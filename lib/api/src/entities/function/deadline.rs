@@ -0,0 +1,61 @@
+//! Calling functions with a deadline, see [`Function::call_with_deadline()`].
+
+use std::time::{Duration, Instant};
+
+use thiserror::Error;
+use wasmer_types::TrapCode;
+
+use crate::{AsStoreMut, BackendStore, BackendTrap, RuntimeError, StoreMut};
+
+#[cfg(doc)]
+use crate::{Function, TypedFunction};
+
+/// The error a call made with [`Function::call_with_deadline()`] or
+/// [`TypedFunction::call_with_deadline()`] fails with when it runs past its
+/// deadline.
+///
+/// The [`RuntimeError`] it comes in has the
+/// [`TrapCode::DeadlineExceeded`] trap code, and can be downcast to this.
+///
+/// Calls are only interrupted in WebAssembly code, or once a libcall (such as
+/// `memory.grow`) returns, so the instance is left in the same state as after
+/// any other trap and can be called again.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+#[error("the call didn't finish within {timeout:?}")]
+pub struct DeadlineExceeded {
+    /// The timeout the call was made with.
+    pub timeout: Duration,
+}
+
+/// Runs `call` with a deadline of `timeout` from now.
+pub(crate) fn call_with_deadline<T>(
+    store: &mut impl AsStoreMut,
+    timeout: Duration,
+    call: impl FnOnce(&mut StoreMut<'_>) -> Result<T, RuntimeError>,
+) -> Result<T, RuntimeError> {
+    let mut store = store.as_store_mut();
+    let code = match &store.inner.store {
+        BackendStore::Sys(_) => store.engine().as_sys().inner().executable_ranges(),
+        #[allow(unreachable_patterns)]
+        _ => {
+            return Err(RuntimeError::new(
+                "calls with a deadline are only supported by the sys backend",
+            ))
+        }
+    };
+
+    let deadline = Instant::now() + timeout;
+    let (result, interrupted) = wasmer_vm::with_deadline(deadline, code, || call(&mut store));
+
+    match (result, interrupted) {
+        (Err(error), true) => {
+            let exceeded = DeadlineExceeded { timeout };
+            Err(RuntimeError::new_from_source(
+                BackendTrap::Sys(wasmer_vm::Trap::user(Box::new(exceeded))),
+                error.trace().to_vec(),
+                Some(TrapCode::DeadlineExceeded),
+            ))
+        }
+        (result, _) => result,
+    }
+}
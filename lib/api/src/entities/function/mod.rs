@@ -10,13 +10,19 @@ pub use host::*;
 pub(crate) mod env;
 pub use env::*;
 
+#[cfg(feature = "sys")]
+pub(crate) mod deadline;
+#[cfg(feature = "sys")]
+pub use deadline::DeadlineExceeded;
+
 use wasmer_types::{FunctionType, RawValue};
 
 use crate::{
+    entities::store::limits,
     error::RuntimeError,
     vm::{VMExtern, VMExternFunction, VMFuncRef},
-    AsStoreMut, AsStoreRef, ExportError, Exportable, Extern, StoreMut, StoreRef, TypedFunction,
-    Value, WasmTypeList,
+    AsStoreMut, AsStoreRef, ExportError, Exportable, Extern, StoreMut, StoreRef, StoreResource,
+    TypedFunction, Value, WasmTypeList,
 };
 
 /// A WebAssembly `function` instance.
@@ -50,6 +56,7 @@ impl Function {
         FT: Into<FunctionType>,
         F: Fn(&[Value]) -> Result<Vec<Value>, RuntimeError> + 'static + Send + Sync,
     {
+        limits::record(store, StoreResource::HostFunctions, 1);
         Self(BackendFunction::new(store, ty, func))
     }
 
@@ -103,6 +110,7 @@ impl Function {
             + Send
             + Sync,
     {
+        limits::record(store, StoreResource::HostFunctions, 1);
         Self(BackendFunction::new_with_env(store, env, ty, func))
     }
 
@@ -113,6 +121,7 @@ impl Function {
         Args: WasmTypeList,
         Rets: WasmTypeList,
     {
+        limits::record(store, StoreResource::HostFunctions, 1);
         Self(BackendFunction::new_typed(store, func))
     }
 
@@ -144,6 +153,7 @@ impl Function {
         Args: WasmTypeList,
         Rets: WasmTypeList,
     {
+        limits::record(store, StoreResource::HostFunctions, 1);
         Self(BackendFunction::new_typed_with_env(store, env, func))
     }
 
@@ -250,6 +260,55 @@ impl Function {
         self.0.call(store, params)
     }
 
+    /// Call the function, interrupting it if it hasn't returned within
+    /// `timeout`.
+    ///
+    /// A call that runs past its deadline fails with a [`RuntimeError`] whose
+    /// trap code is [`TrapCode::DeadlineExceeded`](wasmer_types::TrapCode::DeadlineExceeded),
+    /// and which can be downcast to a [`DeadlineExceeded`] saying whether the
+    /// instance can still be used.
+    ///
+    /// WebAssembly code is interrupted as soon as the deadline passes, but
+    /// host functions and libcalls (such as `memory.fill`) are always allowed
+    /// to return first. When this call is
+    /// nested inside another call with a deadline, only the innermost
+    /// deadline applies until it returns.
+    ///
+    /// Calls are only interrupted on unix, and only by the `sys` backend.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use std::time::Duration;
+    /// # use wasmer::{imports, wat2wasm, DeadlineExceeded, Instance, Module, Store};
+    /// # let mut store = Store::default();
+    /// # let wasm_bytes = wat2wasm(r#"
+    /// # (module
+    /// #   (func (export "spin")
+    /// #     (loop $again (br $again))
+    /// #   ))
+    /// # "#.as_bytes()).unwrap();
+    /// # let module = Module::new(&store, wasm_bytes).unwrap();
+    /// # let instance = Instance::new(&mut store, &module, &imports! {}).unwrap();
+    /// #
+    /// let spin = instance.exports.get_function("spin").unwrap();
+    ///
+    /// let error = spin
+    ///     .call_with_deadline(&mut store, &[], Duration::from_millis(10))
+    ///     .unwrap_err();
+    /// let exceeded = error.downcast_ref::<DeadlineExceeded>().unwrap();
+    /// assert_eq!(exceeded.timeout, Duration::from_millis(10));
+    /// ```
+    #[cfg(feature = "sys")]
+    pub fn call_with_deadline(
+        &self,
+        store: &mut impl AsStoreMut,
+        params: &[Value],
+        timeout: std::time::Duration,
+    ) -> Result<Box<[Value]>, RuntimeError> {
+        deadline::call_with_deadline(store, timeout, |store| self.call(store, params))
+    }
+
     #[doc(hidden)]
     #[allow(missing_docs)]
     pub fn call_raw(
@@ -1,6 +1,7 @@
 use crate::{
+    entities::store::limits,
     error::RuntimeError,
-    store::{AsStoreMut, AsStoreRef, StoreMut, StoreRef},
+    store::{AsStoreMut, AsStoreRef, StoreMut, StoreRef, StoreResource},
     value::Value,
     vm::{VMExtern, VMExternGlobal},
     ExportError, Exportable, Extern,
@@ -35,6 +36,7 @@ impl Global {
     /// assert_eq!(g.ty(&mut store).mutability, Mutability::Const);
     /// ```
     pub fn new(store: &mut impl AsStoreMut, val: Value) -> Self {
+        limits::record(store, StoreResource::Globals, 1);
         Self(BackendGlobal::new(store, val))
     }
 
@@ -52,6 +54,7 @@ impl Global {
     /// assert_eq!(g.ty(&mut store).mutability, Mutability::Var);
     /// ```
     pub fn new_mut(store: &mut impl AsStoreMut, val: Value) -> Self {
+        limits::record(store, StoreResource::Globals, 1);
         Self(BackendGlobal::new_mut(store, val))
     }
 
@@ -1,10 +1,14 @@
 //! The import module contains the implementation data structures and helper functions used to
 //! manipulate and access a wasm module's imports including memories, tables, globals, and
 //! functions.
-use crate::{error::LinkError, Exports, Extern, Module};
+use crate::{
+    error::LinkError,
+    interface::{ImportManifest, ManifestImport},
+    AsStoreRef, Exports, Extern, Module,
+};
 use std::collections::HashMap;
 use std::fmt;
-use wasmer_types::ImportError;
+use wasmer_types::{ImportError, ImportLimits};
 
 /// All of the import data used when instantiating.
 ///
@@ -36,6 +40,7 @@ use wasmer_types::ImportError;
 #[derive(Clone, Default)]
 pub struct Imports {
     pub(crate) map: HashMap<(String, String), Extern>,
+    pub(crate) limits: ImportLimits,
 }
 
 impl Imports {
@@ -44,6 +49,29 @@ impl Imports {
         Default::default()
     }
 
+    /// Accept memories and tables with no maximum, or a larger one than
+    /// the importing module declares, see [`ImportLimits::Relaxed`].
+    ///
+    /// By default the limits are matched as the WebAssembly specification
+    /// requires. Only the `sys` backend supports relaxed limits, the others
+    /// leave the check to their runtime.
+    ///
+    /// # Usage
+    /// ```no_run
+    /// # use wasmer::{Imports, ImportLimits};
+    /// let import_object = Imports::new().with_relaxed_limits();
+    /// assert_eq!(import_object.limits(), ImportLimits::Relaxed);
+    /// ```
+    pub fn with_relaxed_limits(mut self) -> Self {
+        self.limits = ImportLimits::Relaxed;
+        self
+    }
+
+    /// How the limits of imported memories and tables are matched.
+    pub fn limits(&self) -> ImportLimits {
+        self.limits
+    }
+
     /// Gets an export given a module and a name
     ///
     /// # Usage
@@ -160,6 +188,18 @@ impl Imports {
         Ok(ret)
     }
 
+    /// Describe the imports in this structure, with their types, so that
+    /// modules can be checked against them without the host at hand.
+    ///
+    /// See [`check_module_against_manifest()`](crate::interface::check_module_against_manifest).
+    pub fn manifest(&self, store: &impl AsStoreRef) -> ImportManifest {
+        ImportManifest::new(self.map.iter().map(|((module, name), ext)| ManifestImport {
+            module: module.clone(),
+            name: name.clone(),
+            ty: ext.ty(store),
+        }))
+    }
+
     /// Iterates through all the imports in this structure
     pub fn iter(&self) -> ImportsIterator<'_> {
         ImportsIterator::new(self)
@@ -233,6 +273,7 @@ impl fmt::Debug for Imports {
 
         f.debug_struct("Imports")
             .field("map", &SecretMap::new(self.map.len()))
+            .field("limits", &self.limits)
             .finish()
     }
 }
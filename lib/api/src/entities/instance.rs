@@ -1,6 +1,17 @@
+use std::time::Duration;
+
+use wasmer_types::{ExternType, ImportError};
+
+#[cfg(feature = "sys")]
+use crate::DeadlineExceeded;
 use crate::{
-    error::InstantiationError, exports::Exports, imports::Imports, macros::backend::gen_rt_ty,
-    module::Module, store::AsStoreMut, Extern,
+    error::{InstantiationError, LinkError, RuntimeError},
+    exports::{CachedExports, Exports},
+    imports::Imports,
+    macros::backend::gen_rt_ty,
+    module::Module,
+    store::AsStoreMut,
+    Extern, Function,
 };
 
 /// A WebAssembly Instance is a stateful, executable
@@ -20,6 +31,11 @@ pub struct Instance {
 }
 
 impl Instance {
+    /// How many bytes of data segments (and table elements)
+    /// [`Instance::new_async()`] initializes before yielding.
+    #[cfg(feature = "tokio")]
+    pub const DEFAULT_INIT_CHUNK_SIZE: usize = 1024 * 1024;
+
     /// Creates a new `Instance` from a WebAssembly [`Module`] and a
     /// set of imports using [`Imports`] or the [`imports!`] macro helper.
     ///
@@ -99,6 +115,188 @@ impl Instance {
         })
     }
 
+    /// Creates a new `Instance` like [`Instance::new()`], but without
+    /// blocking the executor for long while initializing the memories and
+    /// tables of modules with large data or element segments.
+    ///
+    /// With the `sys` backend, the segments are applied
+    /// [`Instance::DEFAULT_INIT_CHUNK_SIZE`] bytes at a time, yielding to the
+    /// executor between chunks. The resulting instance, as well as any error
+    /// (including traps in the start function), is the same as with
+    /// [`Instance::new()`]. Other backends instantiate synchronously.
+    ///
+    /// ```
+    /// # use wasmer::{imports, Store, Module, Instance};
+    /// # async fn example() -> anyhow::Result<()> {
+    /// let mut store = Store::default();
+    /// let module = Module::new(&store, "(module (memory 1) (data (i32.const 0) \"hello\"))")?;
+    /// let instance = Instance::new_async(&mut store, &module, &imports! {}).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "tokio")]
+    #[allow(clippy::result_large_err)]
+    pub async fn new_async(
+        store: &mut impl AsStoreMut,
+        module: &Module,
+        imports: &Imports,
+    ) -> Result<Self, InstantiationError> {
+        Self::new_async_with_chunk_size(store, module, imports, Self::DEFAULT_INIT_CHUNK_SIZE).await
+    }
+
+    /// Like [`Instance::new_async()`], but yielding to the executor after
+    /// every `chunk_size` bytes of segments.
+    #[cfg(feature = "tokio")]
+    #[allow(clippy::result_large_err)]
+    pub async fn new_async_with_chunk_size(
+        store: &mut impl AsStoreMut,
+        module: &Module,
+        imports: &Imports,
+        chunk_size: usize,
+    ) -> Result<Self, InstantiationError> {
+        #[cfg(feature = "sys")]
+        #[allow(irrefutable_let_patterns)]
+        if let crate::BackendStore::Sys(_) = &store.as_store_mut().inner.store {
+            let (instance, exports) = crate::backend::sys::instance::Instance::new_async(
+                store, module, imports, chunk_size,
+            )
+            .await?;
+            return Ok(Self {
+                _inner: crate::BackendInstance::Sys(instance),
+                module: module.clone(),
+                exports,
+            });
+        }
+
+        let _ = chunk_size;
+        Self::new(store, module, imports)
+    }
+
+    /// Creates a new `Instance` like [`Instance::new()`], with the given
+    /// [`InstantiationOptions`].
+    ///
+    /// ```no_run
+    /// # use std::time::Duration;
+    /// # use wasmer::{imports, Store, Module, Instance, InstantiationError, InstantiationOptions};
+    /// # fn main() -> anyhow::Result<()> {
+    /// let mut store = Store::default();
+    /// let module = Module::new(&store, "(module (func $spin (loop (br 0))) (start $spin))")?;
+    /// let options = InstantiationOptions::new().start_timeout(Duration::from_millis(10));
+    /// let error = Instance::new_with_options(&mut store, &module, &imports! {}, &options)
+    ///     .unwrap_err();
+    /// assert!(matches!(error, InstantiationError::StartTimeout { .. }));
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[allow(clippy::result_large_err)]
+    pub fn new_with_options(
+        store: &mut impl AsStoreMut,
+        module: &Module,
+        imports: &Imports,
+        options: &InstantiationOptions,
+    ) -> Result<Self, InstantiationError> {
+        #[cfg(feature = "sys")]
+        if let (Some(timeout), crate::BackendStore::Sys(_)) =
+            (options.start_timeout, &store.as_store_mut().inner.store)
+        {
+            return Self::instantiate_without_start(store, module, imports)?
+                .start_with_deadline(store, timeout);
+        }
+
+        let _ = options;
+        Self::new(store, module, imports)
+    }
+
+    /// Creates a new `Instance` like [`Instance::new_async()`], with the
+    /// given [`InstantiationOptions`].
+    #[cfg(feature = "tokio")]
+    #[allow(clippy::result_large_err)]
+    pub async fn new_async_with_options(
+        store: &mut impl AsStoreMut,
+        module: &Module,
+        imports: &Imports,
+        options: &InstantiationOptions,
+    ) -> Result<Self, InstantiationError> {
+        #[cfg(feature = "sys")]
+        if let (Some(timeout), crate::BackendStore::Sys(_)) =
+            (options.start_timeout, &store.as_store_mut().inner.store)
+        {
+            let (instance, exports, start) =
+                crate::backend::sys::instance::Instance::new_async_without_start(
+                    store,
+                    module,
+                    imports,
+                    Self::DEFAULT_INIT_CHUNK_SIZE,
+                )
+                .await?;
+            let unstarted = UnstartedInstance {
+                instance: Self {
+                    _inner: crate::BackendInstance::Sys(instance),
+                    module: module.clone(),
+                    exports,
+                },
+                start,
+            };
+            return unstarted.start_with_deadline(store, timeout);
+        }
+
+        let _ = options;
+        Self::new_async(store, module, imports).await
+    }
+
+    /// Creates a new `Instance` like [`Instance::new()`], but without
+    /// running the module's start function.
+    ///
+    /// The memories and tables are initialized, but nothing in the instance
+    /// has run yet. The start function can be run later, with or without a
+    /// deadline, through the returned [`UnstartedInstance`].
+    ///
+    /// ```
+    /// # use wasmer::{imports, Store, Module, Instance};
+    /// # fn main() -> anyhow::Result<()> {
+    /// let mut store = Store::default();
+    /// let module = Module::new(&store, "(module (func $init) (start $init))")?;
+    /// let unstarted = Instance::instantiate_without_start(&mut store, &module, &imports! {})?;
+    /// assert!(unstarted.has_start_function());
+    /// let instance = unstarted.start(&mut store)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// ## Errors
+    ///
+    /// Like [`Instance::new()`], except that the start function can't trap.
+    /// Only the `sys` backend can skip the start function, so this always
+    /// fails with the others.
+    #[allow(clippy::result_large_err)]
+    pub fn instantiate_without_start(
+        store: &mut impl AsStoreMut,
+        module: &Module,
+        imports: &Imports,
+    ) -> Result<UnstartedInstance, InstantiationError> {
+        match &store.as_store_mut().inner.store {
+            #[cfg(feature = "sys")]
+            crate::BackendStore::Sys(_) => {
+                let (instance, exports, start) =
+                    crate::backend::sys::instance::Instance::new_without_start(
+                        store, module, imports,
+                    )?;
+                Ok(UnstartedInstance {
+                    instance: Self {
+                        _inner: crate::BackendInstance::Sys(instance),
+                        module: module.clone(),
+                        exports,
+                    },
+                    start,
+                })
+            }
+            #[allow(unreachable_patterns)]
+            _ => Err(InstantiationError::Start(RuntimeError::new(
+                "skipping the start function is only supported by the sys backend",
+            ))),
+        }
+    }
+
     /// Creates a new `Instance` from a WebAssembly [`Module`] and a
     /// vector of imports.
     ///
@@ -164,10 +362,76 @@ impl Instance {
         })
     }
 
+    /// Creates a new `Instance` from a WebAssembly [`Module`], asking
+    /// `resolve` for each import instead of looking it up in an [`Imports`].
+    ///
+    /// `resolve` is called with the module name, field name and type of
+    /// every import, in the order the imports are declared, and stops at the
+    /// first import it returns `None` for. This makes it possible to resolve
+    /// imports lazily, e.g. by pulling a memory out of another instance.
+    ///
+    /// ```
+    /// # use wasmer::{Store, Module, Instance, Extern, Memory, MemoryType};
+    /// # fn main() -> anyhow::Result<()> {
+    /// let mut store = Store::default();
+    /// let module = Module::new(&store, r#"(module (import "env" "memory" (memory 1)))"#)?;
+    /// let memory = Memory::new(&mut store, MemoryType::new(1, None, false))?;
+    /// let instance = Instance::new_with_resolver_fn(&mut store, &module, |module, name, _ty| {
+    ///     match (module, name) {
+    ///         ("env", "memory") => Some(Extern::Memory(memory.clone())),
+    ///         _ => None,
+    ///     }
+    /// })?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// ## Errors
+    ///
+    /// Like [`Instance::new()`], with an [`ImportError::UnknownImport`] link
+    /// error for the first import `resolve` can't provide.
+    #[allow(clippy::result_large_err)]
+    pub fn new_with_resolver_fn(
+        store: &mut impl AsStoreMut,
+        module: &Module,
+        mut resolve: impl FnMut(&str, &str, &ExternType) -> Option<Extern>,
+    ) -> Result<Self, InstantiationError> {
+        let externs = module
+            .imports()
+            .map(|import| {
+                resolve(import.module(), import.name(), import.ty()).ok_or_else(|| {
+                    InstantiationError::Link(LinkError::Import(
+                        import.module().to_string(),
+                        import.name().to_string(),
+                        ImportError::UnknownImport(import.ty().clone()),
+                    ))
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Self::new_by_index(store, module, &externs)
+    }
+
     /// Gets the [`Module`] associated with this instance.
     pub fn module(&self) -> &Module {
         &self.module
     }
+
+    /// Gets a [`CachedExports`] for resolving this instance's functions
+    /// once and calling them repeatedly.
+    pub fn cached_exports(&self) -> CachedExports {
+        CachedExports::new(self.exports.clone())
+    }
+
+    /// An estimate of the host memory this instance keeps alive on top of
+    /// what is shared with the other instances of its [`Module`].
+    ///
+    /// This only covers the bookkeeping done by this crate (e.g. the
+    /// [`Exports`]), not the instance's memories, tables or `VMContext`.
+    #[cfg(feature = "heap-size")]
+    pub fn approx_host_heap_size(&self) -> usize {
+        std::mem::size_of::<Self>() + self.exports.approx_heap_size()
+    }
 }
 
 impl std::fmt::Debug for Instance {
@@ -178,5 +442,91 @@ impl std::fmt::Debug for Instance {
     }
 }
 
+/// Options for creating an [`Instance`] with
+/// [`Instance::new_with_options()`].
+#[derive(Debug, Clone, Default)]
+pub struct InstantiationOptions {
+    start_timeout: Option<Duration>,
+}
+
+impl InstantiationOptions {
+    /// The default options, which are the same as [`Instance::new()`] uses.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Interrupts the module's start function if it runs for longer than
+    /// `timeout`, failing with [`InstantiationError::StartTimeout`].
+    ///
+    /// Like [`Function::call_with_deadline()`](crate::Function::call_with_deadline),
+    /// this is only enforced by the `sys` backend.
+    pub fn start_timeout(mut self, timeout: Duration) -> Self {
+        self.start_timeout = Some(timeout);
+        self
+    }
+}
+
+/// An [`Instance`] whose start function hasn't been run yet, as returned by
+/// [`Instance::instantiate_without_start()`].
+///
+/// Its exports can already be looked up with [`UnstartedInstance::instance()`],
+/// but the module doesn't expect to be called into before it's started.
+#[derive(Debug)]
+pub struct UnstartedInstance {
+    instance: Instance,
+    start: Option<Function>,
+}
+
+impl UnstartedInstance {
+    /// The instance, as it is before the start function runs.
+    pub fn instance(&self) -> &Instance {
+        &self.instance
+    }
+
+    /// Whether the module has a start function to run.
+    pub fn has_start_function(&self) -> bool {
+        self.start.is_some()
+    }
+
+    /// Runs the start function, if there is one, and returns the instance.
+    ///
+    /// A trap in the start function fails with
+    /// [`InstantiationError::Start`], as with [`Instance::new()`].
+    #[allow(clippy::result_large_err)]
+    pub fn start(self, store: &mut impl AsStoreMut) -> Result<Instance, InstantiationError> {
+        if let Some(start) = &self.start {
+            start.call(store, &[]).map_err(InstantiationError::Start)?;
+        }
+        Ok(self.instance)
+    }
+
+    /// Like [`UnstartedInstance::start()`], but interrupts the start
+    /// function if it runs for longer than `timeout`.
+    ///
+    /// Running past the deadline fails with
+    /// [`InstantiationError::StartTimeout`], which carries the backtrace of
+    /// where the start function was interrupted.
+    #[cfg(feature = "sys")]
+    #[allow(clippy::result_large_err)]
+    pub fn start_with_deadline(
+        self,
+        store: &mut impl AsStoreMut,
+        timeout: Duration,
+    ) -> Result<Instance, InstantiationError> {
+        if let Some(start) = &self.start {
+            start
+                .call_with_deadline(store, &[], timeout)
+                .map_err(|error| match error.downcast_ref::<DeadlineExceeded>() {
+                    Some(exceeded) => InstantiationError::StartTimeout {
+                        timeout: exceeded.timeout,
+                        trace: error.trace().to_vec(),
+                    },
+                    None => InstantiationError::Start(error),
+                })?;
+        }
+        Ok(self.instance)
+    }
+}
+
 /// An enumeration of all the possible instances kind supported by the runtimes.
 gen_rt_ty!(Instance @derives Clone, PartialEq, Eq);
@@ -29,6 +29,73 @@ impl MemoryBuffer<'_> {
         self.0.write(offset, data)
     }
 
+    /// Copies `len` bytes from `src_offset` in this buffer to `dst_offset` in
+    /// `dst`, without any intermediate allocation.
+    ///
+    /// Both buffers may belong to the same memory, in which case the two
+    /// ranges are allowed to overlap.
+    pub(crate) fn copy_to(
+        &self,
+        src_offset: u64,
+        dst: &MemoryBuffer<'_>,
+        dst_offset: u64,
+        len: u64,
+    ) -> Result<(), MemoryAccessError> {
+        let src_end = src_offset
+            .checked_add(len)
+            .ok_or(MemoryAccessError::Overflow)?;
+        let dst_end = dst_offset
+            .checked_add(len)
+            .ok_or(MemoryAccessError::Overflow)?;
+        if src_end > self.len() as u64 || dst_end > dst.len() as u64 {
+            return Err(MemoryAccessError::HeapOutOfBounds);
+        }
+
+        if self.is_owned() || dst.is_owned() {
+            return self.copy_to_chunked(src_offset, dst, dst_offset, len);
+        }
+
+        // Both ranges were checked above and `ptr::copy` behaves like
+        // `memmove`, so overlapping ranges are fine.
+        unsafe {
+            std::ptr::copy(
+                self.base().add(src_offset as usize),
+                dst.base().add(dst_offset as usize),
+                len as usize,
+            );
+        }
+        Ok(())
+    }
+
+    /// Fallback for [`MemoryBuffer::copy_to()`] when a buffer can't be
+    /// accessed through a raw pointer.
+    fn copy_to_chunked(
+        &self,
+        src_offset: u64,
+        dst: &MemoryBuffer<'_>,
+        dst_offset: u64,
+        len: u64,
+    ) -> Result<(), MemoryAccessError> {
+        // When copying towards higher addresses, start at the end so that an
+        // overlapping source range isn't overwritten before it is read.
+        let backwards = dst_offset > src_offset;
+        let mut chunk = [0u8; 40960];
+        let mut copied = 0;
+        while copied < len {
+            let sublen = (len - copied).min(chunk.len() as u64);
+            let pos = if backwards {
+                len - copied - sublen
+            } else {
+                copied
+            };
+            let chunk = &mut chunk[..sublen as usize];
+            self.read(src_offset + pos, chunk)?;
+            dst.write(dst_offset + pos, chunk)?;
+            copied += sublen;
+        }
+        Ok(())
+    }
+
     pub(crate) fn len(&self) -> usize {
         self.0.len()
     }
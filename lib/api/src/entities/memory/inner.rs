@@ -1,5 +1,7 @@
+use std::{any::Any, ptr::NonNull};
+
 use super::{shared::SharedMemory, view::*};
-use wasmer_types::{MemoryError, MemoryType, Pages};
+use wasmer_types::{MemoryError, MemoryGrowthPolicy, MemoryStyle, MemoryType, Pages};
 
 use crate::{
     macros::backend::{gen_rt_ty, match_rt},
@@ -56,6 +58,36 @@ impl BackendMemory {
         }
     }
 
+    /// Creates a new host [`BackendMemory`] that aliases a buffer owned by the
+    /// embedder (or by `owner`).
+    ///
+    /// Only the `sys` backend supports this.
+    ///
+    /// # Safety
+    /// See [`crate::Memory::new_from_host_buffer`].
+    #[inline]
+    pub(crate) unsafe fn new_from_host_buffer(
+        store: &mut impl AsStoreMut,
+        ty: MemoryType,
+        base: NonNull<u8>,
+        len: usize,
+        policy: MemoryGrowthPolicy,
+        owner: Option<Box<dyn Any + Send>>,
+    ) -> Result<Self, MemoryError> {
+        match &store.as_store_mut().inner.store {
+            #[cfg(feature = "sys")]
+            crate::BackendStore::Sys(_) => Ok(Self::Sys(
+                crate::backend::sys::entities::memory::Memory::new_from_host_buffer(
+                    store, ty, base, len, policy, owner,
+                )?,
+            )),
+            _ => Err(MemoryError::UnsupportedOperation {
+                message: "the selected runtime does not support memories backed by host buffers"
+                    .to_string(),
+            }),
+        }
+    }
+
     /// Create a memory object from an existing memory and attaches it to the store
     #[inline]
     pub fn new_from_existing(new_store: &mut impl AsStoreMut, memory: VMMemory) -> Self {
@@ -125,6 +157,18 @@ impl BackendMemory {
         })
     }
 
+    /// The way the memory is laid out in the host's address space, if the
+    /// backend exposes it.
+    #[inline]
+    pub fn style(&self, store: &impl AsStoreRef) -> Option<MemoryStyle> {
+        match self {
+            #[cfg(feature = "sys")]
+            Self::Sys(s) => Some(s.style(store)),
+            #[allow(unreachable_patterns)]
+            _ => None,
+        }
+    }
+
     /// Retrieve the size of the memory in pages.
     pub fn size(&self, store: &impl AsStoreRef) -> Pages {
         match_rt!(on self => s {
@@ -219,12 +263,19 @@ impl BackendMemory {
 
         match self {
             #[cfg(feature = "sys")]
-            Self::Sys(s) => s.try_copy(store).map(|new_memory| {
-                Self::new_from_existing(
+            Self::Sys(s) => {
+                let mut new_memory = crate::backend::sys::vm::VMMemory(s.try_copy(store)?);
+                if let Some(limiter) = new_store.as_store_ref().inner.memory_limiter.as_ref() {
+                    new_memory =
+                        crate::backend::sys::entities::memory::limited::LimitedMemory::wrap(
+                            new_memory, limiter,
+                        )?;
+                }
+                Ok(Self::new_from_existing(
                     new_store,
-                    VMMemory::Sys(crate::backend::sys::vm::VMMemory(new_memory)),
-                )
-            }),
+                    VMMemory::Sys(new_memory),
+                ))
+            }
             #[cfg(feature = "wamr")]
             Self::Wamr(s) => s
                 .try_copy(store)
@@ -1,18 +1,24 @@
+use std::{ptr::NonNull, sync::atomic};
+
 pub use shared::SharedMemory;
-use wasmer_types::{MemoryError, MemoryType, Pages};
+use wasmer_types::{MemoryError, MemoryGrowthPolicy, MemoryStyle, MemoryType, Pages};
 
 use crate::{
     vm::{VMExtern, VMExternMemory, VMMemory},
-    AsStoreMut, AsStoreRef, ExportError, Exportable, Extern, StoreMut, StoreRef,
+    AsStoreMut, AsStoreRef, ExportError, Exportable, Extern, MemoryAccessError, StoreMut, StoreRef,
 };
 
 pub(crate) mod buffer;
 pub(crate) mod inner;
 pub(crate) mod location;
 pub(crate) mod shared;
+#[cfg(feature = "memory-tracking")]
+pub(crate) mod tracking;
 pub(crate) mod view;
 
 pub(crate) use inner::*;
+#[cfg(feature = "memory-tracking")]
+pub use tracking::{DirtyPageTracker, MemoryDelta, PageIndex};
 pub use view::*;
 
 /// A WebAssembly `memory` instance.
@@ -51,6 +57,87 @@ impl Memory {
         BackendMemory::new(store, ty).map(Self)
     }
 
+    /// Creates a new host [`Memory`] whose initial pages are the `len` bytes
+    /// starting at `ptr`. Nothing is copied: WebAssembly reads and writes the
+    /// host buffer directly.
+    ///
+    /// `ptr` must be aligned to 16 bytes and `len` must be a multiple of
+    /// [`WASM_PAGE_SIZE`](crate::WASM_PAGE_SIZE). The memory starts with
+    /// `len / WASM_PAGE_SIZE` pages, which must be within the limits of `ty`.
+    /// Shared memories are not supported.
+    ///
+    /// There are no guard pages after the buffer, so the memory uses
+    /// [`MemoryStyle::External`](crate::MemoryStyle::External). It can only
+    /// be imported by modules compiled with tunables that pick that style (or
+    /// a [`MemoryStyle::Dynamic`](crate::MemoryStyle::Dynamic) style without
+    /// guard pages) for it; instantiating any other module fails with a link
+    /// error.
+    ///
+    /// With [`MemoryGrowthPolicy::Disabled`] the memory can never grow. With
+    /// [`MemoryGrowthPolicy::Remap`], growing the memory copies its contents
+    /// into a new allocation owned by the runtime, and the host buffer is no
+    /// longer used afterwards.
+    ///
+    /// Only the `sys` backend supports this. See
+    /// [`Memory::new_from_owned_buffer()`] for a safe alternative.
+    ///
+    /// # Safety
+    ///
+    /// - `ptr` must be valid for reads and writes of `len` bytes until the
+    ///   memory and every instance importing it have been dropped (or until
+    ///   the memory has been remapped).
+    /// - WebAssembly can write to the buffer at any time, so it must not be
+    ///   memory that Rust considers immutable, such as the contents of an
+    ///   `Arc<[u8]>`. The host must not access the buffer through other
+    ///   references while the guest may be running.
+    pub unsafe fn new_from_host_buffer(
+        store: &mut impl AsStoreMut,
+        ty: MemoryType,
+        ptr: *mut u8,
+        len: usize,
+        policy: MemoryGrowthPolicy,
+    ) -> Result<Self, MemoryError> {
+        let base = NonNull::new(ptr).ok_or_else(|| MemoryError::InvalidMemory {
+            reason: "the buffer pointer is null".to_string(),
+        })?;
+        BackendMemory::new_from_host_buffer(store, ty, base, len, policy, None).map(Self)
+    }
+
+    /// Creates a new host [`Memory`] that takes ownership of `buffer` and
+    /// aliases its bytes instead of copying them.
+    ///
+    /// This is the safe counterpart of [`Memory::new_from_host_buffer()`], and
+    /// the same requirements on the buffer's alignment and length apply. A
+    /// `memmap2::MmapMut` created with `MmapOptions::map_copy()` is a good way
+    /// to expose a file to the guest without reading it up front or writing
+    /// the guest's changes back to it.
+    ///
+    /// The buffer is dropped together with the memory, or as soon as the
+    /// memory is remapped.
+    pub fn new_from_owned_buffer<B>(
+        store: &mut impl AsStoreMut,
+        ty: MemoryType,
+        buffer: B,
+        policy: MemoryGrowthPolicy,
+    ) -> Result<Self, MemoryError>
+    where
+        B: AsMut<[u8]> + Send + 'static,
+    {
+        let mut buffer = Box::new(buffer);
+        let bytes = (*buffer).as_mut();
+        let (ptr, len) = (bytes.as_mut_ptr(), bytes.len());
+        let base = NonNull::new(ptr).ok_or_else(|| MemoryError::InvalidMemory {
+            reason: "the buffer pointer is null".to_string(),
+        })?;
+
+        // Safety: the memory owns the buffer, whose bytes don't move when
+        // the box does, and nothing else can access them.
+        unsafe {
+            BackendMemory::new_from_host_buffer(store, ty, base, len, policy, Some(buffer))
+                .map(Self)
+        }
+    }
+
     /// Create a memory object from an existing memory and attaches it to the store
     pub fn new_from_existing<IntoVMMemory>(
         new_store: &mut impl AsStoreMut,
@@ -85,6 +172,140 @@ impl Memory {
         MemoryView::new(self, store)
     }
 
+    /// Copies `len` bytes starting at `src_offset` in this memory to
+    /// `dst_offset` in `dst`.
+    ///
+    /// The copy is a single bounds-checked `memmove` between the two linear
+    /// memories, so no intermediate buffer is allocated. `dst` may be this
+    /// same memory, in which case the source and destination ranges are
+    /// allowed to overlap. Both memories must belong to `store`.
+    ///
+    /// When either memory is shared, the copy is ordered with the atomic
+    /// operations of the current thread, but (just like `memory.copy`)
+    /// other threads accessing the same range at the same time may observe
+    /// a partially copied range.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use wasmer::{Memory, MemoryType, Store};
+    /// # let mut store = Store::default();
+    /// #
+    /// let src = Memory::new(&mut store, MemoryType::new(1, None, false)).unwrap();
+    /// let dst = Memory::new(&mut store, MemoryType::new(1, None, false)).unwrap();
+    /// src.view(&store).write(0, b"hello").unwrap();
+    ///
+    /// src.copy_to(&store, 0, &dst, 100, 5).unwrap();
+    ///
+    /// let mut buf = [0; 5];
+    /// dst.view(&store).read(100, &mut buf).unwrap();
+    /// assert_eq!(&buf, b"hello");
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if either range is out of the bounds of its memory.
+    pub fn copy_to(
+        &self,
+        store: &impl AsStoreRef,
+        src_offset: u64,
+        dst: &Self,
+        dst_offset: u64,
+        len: u64,
+    ) -> Result<(), MemoryAccessError> {
+        let shared = self.ty(store).shared || dst.ty(store).shared;
+        let src_view = self.view(store);
+        let dst_view = dst.view(store);
+
+        if shared {
+            atomic::fence(atomic::Ordering::SeqCst);
+        }
+        let result = src_view
+            .buffer()
+            .copy_to(src_offset, &dst_view.buffer(), dst_offset, len);
+        if shared {
+            atomic::fence(atomic::Ordering::SeqCst);
+        }
+
+        result
+    }
+
+    /// Copies `buf.len()` bytes starting at `offset` out of the memory.
+    ///
+    /// This is a shorthand for `memory.view(store).read(offset, buf)`: the
+    /// range is checked against the current size of the memory and copied
+    /// with volatile reads, so it is safe even while other threads write to
+    /// or grow a shared memory. As with [`Memory::copy_to()`], accesses to a
+    /// shared memory are also ordered with the atomic operations of the
+    /// current thread.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use wasmer::{Memory, MemoryType, Store};
+    /// # let mut store = Store::default();
+    /// #
+    /// let m = Memory::new(&mut store, MemoryType::new(1, None, false)).unwrap();
+    /// m.write(&store, 8, b"hello").unwrap();
+    ///
+    /// let mut buf = [0; 5];
+    /// m.read(&store, 8, &mut buf).unwrap();
+    /// assert_eq!(&buf, b"hello");
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MemoryAccessError::HeapOutOfBounds`] if the range is out of
+    /// the bounds of the memory, and [`MemoryAccessError::Overflow`] if its
+    /// end overflows.
+    pub fn read(
+        &self,
+        store: &impl AsStoreRef,
+        offset: u64,
+        buf: &mut [u8],
+    ) -> Result<(), MemoryAccessError> {
+        let shared = self.ty(store).shared;
+        let result = self.view(store).read(offset, buf);
+        if shared {
+            atomic::fence(atomic::Ordering::SeqCst);
+        }
+        result
+    }
+
+    /// Copies `data` into the memory starting at `offset`.
+    ///
+    /// This is a shorthand for `memory.view(store).write(offset, data)`,
+    /// with the same guarantees as [`Memory::read()`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MemoryAccessError::HeapOutOfBounds`] if the range is out of
+    /// the bounds of the memory, and [`MemoryAccessError::Overflow`] if its
+    /// end overflows. Nothing is written in either case.
+    pub fn write(
+        &self,
+        store: &impl AsStoreRef,
+        offset: u64,
+        data: &[u8],
+    ) -> Result<(), MemoryAccessError> {
+        let shared = self.ty(store).shared;
+        if shared {
+            atomic::fence(atomic::Ordering::SeqCst);
+        }
+        self.view(store).write(offset, data)
+    }
+
+    /// Returns the way the memory is laid out in the host's address space:
+    /// whether address space is reserved for it up front, how large its
+    /// guard is, and hence whether compiled code checks accesses explicitly
+    /// (see [`MemoryStyle::needs_bounds_checks()`]).
+    ///
+    /// The style is picked by the engine's tunables when the memory is
+    /// created. Only the `sys` backend exposes it; others return `None`.
+    pub fn style(&self, store: &impl AsStoreRef) -> Option<MemoryStyle> {
+        self.0.style(store)
+    }
+
     /// Retrieve the size of the memory in pages.
     pub fn size(&self, store: &impl AsStoreRef) -> Pages {
         self.0.size(store)
@@ -0,0 +1,347 @@
+//! Finding the pages of a memory that changed, for incremental snapshots.
+
+use std::collections::BTreeSet;
+use std::io::{self, Read, Write};
+
+use wasmer_types::{Pages, WASM_PAGE_SIZE};
+
+use super::{Memory, MemoryView};
+use crate::AsStoreRef;
+
+/// The index of a WebAssembly page (64 KiB) in a [`Memory`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct PageIndex(pub u32);
+
+impl PageIndex {
+    /// The offset of the first byte of the page.
+    pub fn offset(self) -> u64 {
+        self.0 as u64 * WASM_PAGE_SIZE as u64
+    }
+}
+
+/// Keeps track of which pages of a [`Memory`] changed, created by
+/// [`Memory::start_tracking()`].
+///
+/// With the `sys` backend on Linux 6.7 and up, the kernel write-protects the
+/// memory and takes note of the pages written to, which costs nothing until
+/// a page is first written to, and only the dirty pages are read. Everywhere
+/// else (or when userfaultfd isn't allowed), the tracker keeps a copy of the
+/// memory, and compares every page to it byte by byte.
+///
+/// No page that changed is ever missed, but some pages that didn't may be
+/// reported too:
+///
+/// - a page that was written to but ended up with the same contents may be
+///   dirty,
+/// - pages added by `memory.grow` are always dirty, even if they are still
+///   all zeroes, and
+/// - when the memory was moved (e.g. grown by copying it elsewhere) or reset,
+///   every page is dirty.
+///
+/// When a memory is forked (e.g. by WASIX's `proc_fork`, which uses
+/// [`Memory::copy_to_store()`]), start tracking the child's copy with
+/// [`fork()`](Self::fork), so that it starts out with the same baseline as
+/// the parent's.
+#[derive(Debug)]
+pub struct DirtyPageTracker {
+    /// The size of the memory as of the last time the pages were taken.
+    size: Pages,
+    /// Pages known to be dirty without looking at the memory, e.g. the ones
+    /// inherited from the parent of a fork.
+    pending: BTreeSet<u32>,
+    method: Method,
+}
+
+#[derive(Debug)]
+enum Method {
+    /// The kernel takes note of the pages written to.
+    #[cfg(all(
+        feature = "sys",
+        target_os = "linux",
+        any(
+            target_arch = "x86_64",
+            target_arch = "aarch64",
+            target_arch = "riscv64"
+        )
+    ))]
+    Watch(wasmer_vm::WriteWatch),
+    /// The contents of the memory as of the last time the pages were taken.
+    Compare(Vec<u8>),
+}
+
+impl DirtyPageTracker {
+    /// The pages that changed since tracking started or since the last call,
+    /// whichever was later.
+    pub fn take_dirty_pages(&mut self, memory: &Memory, store: &impl AsStoreRef) -> Vec<PageIndex> {
+        let mut dirty = Vec::new();
+        self.scan(memory, store, |index, _| dirty.push(index));
+        dirty
+    }
+
+    /// Like [`take_dirty_pages()`](Self::take_dirty_pages), but also copies
+    /// the contents of the dirty pages.
+    pub fn take_delta(&mut self, memory: &Memory, store: &impl AsStoreRef) -> MemoryDelta {
+        let mut pages = Vec::new();
+        let size = self.scan(memory, store, |index, page| {
+            pages.push((index, page.to_vec()))
+        });
+        MemoryDelta { size, pages }
+    }
+
+    /// Starts tracking `child`, a copy of the tracked `memory` made when
+    /// forking, with the same baseline: the child's first dirty pages are the
+    /// ones that changed since the pages were last taken in the parent, along
+    /// with those the child changed since.
+    ///
+    /// This must be called before anything writes to `child`. The parent's
+    /// tracking is left as it is.
+    pub fn fork(
+        &self,
+        memory: &Memory,
+        store: &impl AsStoreRef,
+        child: &Memory,
+        child_store: &impl AsStoreRef,
+    ) -> Self {
+        let view = memory.view(store);
+        let size = view.size();
+        let mut pending = self.pending.clone();
+
+        match &self.method {
+            #[cfg(all(
+                feature = "sys",
+                target_os = "linux",
+                any(
+                    target_arch = "x86_64",
+                    target_arch = "aarch64",
+                    target_arch = "riscv64"
+                )
+            ))]
+            Method::Watch(watch) => {
+                let written = if watch.start() == view.data_ptr() && size >= self.size {
+                    watch.written(|range| pending.extend(pages_in(range)))
+                } else {
+                    Err(io::ErrorKind::NotFound.into())
+                };
+                if written.is_err() {
+                    pending.extend(0..size.0);
+                }
+            }
+            Method::Compare(baseline) => pending.extend(changed_pages(&view, baseline)),
+        }
+        pending.extend(self.size.0..size.0);
+
+        let child_view = child.view(child_store);
+        Self {
+            size: child_view.size(),
+            pending,
+            method: Method::start(&child_view, child_store),
+        }
+    }
+
+    /// Finds the pages of `memory` that changed, calling `on_dirty` with each
+    /// of them in order, and returns the size of the memory.
+    fn scan(
+        &mut self,
+        memory: &Memory,
+        store: &impl AsStoreRef,
+        mut on_dirty: impl FnMut(PageIndex, &[u8]),
+    ) -> Pages {
+        let view = memory.view(store);
+        let size = view.size();
+        let mut dirty = std::mem::take(&mut self.pending);
+        dirty.retain(|index| *index < size.0);
+
+        match &mut self.method {
+            #[cfg(all(
+                feature = "sys",
+                target_os = "linux",
+                any(
+                    target_arch = "x86_64",
+                    target_arch = "aarch64",
+                    target_arch = "riscv64"
+                )
+            ))]
+            Method::Watch(watch) => {
+                // The pages are protected again before they are read, so
+                // that writes made in the meantime show up next time
+                let watched = if watch.start() == view.data_ptr() && size >= self.size {
+                    watch
+                        .take_written(|range| dirty.extend(pages_in(range)))
+                        .and_then(|()| watch.extend(size.bytes().0))
+                } else {
+                    Err(io::ErrorKind::NotFound.into())
+                };
+                dirty.extend(self.size.0..size.0);
+
+                if watched.is_err() {
+                    // The memory moved or was reset, so there is no telling
+                    // what changed
+                    dirty.extend(0..size.0);
+                    self.method = Method::start(&view, store);
+                }
+
+                let mut page = vec![0; WASM_PAGE_SIZE];
+                for index in dirty {
+                    let index = PageIndex(index);
+                    view.read(index.offset(), &mut page)
+                        .expect("the page is within the memory");
+                    on_dirty(index, &page);
+                }
+            }
+            Method::Compare(baseline) => {
+                dirty.extend(changed_pages(&view, baseline));
+                baseline.resize(size.bytes().0, 0);
+
+                for index in dirty {
+                    let index = PageIndex(index);
+                    let page = &mut baseline[index.offset() as usize..][..WASM_PAGE_SIZE];
+                    view.read(index.offset(), page)
+                        .expect("the page is within the memory");
+                    on_dirty(index, page);
+                }
+            }
+        }
+
+        self.size = size;
+        size
+    }
+}
+
+impl Method {
+    /// Starts watching the memory if the kernel can, or takes a copy of it.
+    fn start(view: &MemoryView, store: &impl AsStoreRef) -> Self {
+        #[cfg(all(
+            feature = "sys",
+            target_os = "linux",
+            any(
+                target_arch = "x86_64",
+                target_arch = "aarch64",
+                target_arch = "riscv64"
+            )
+        ))]
+        #[allow(irrefutable_let_patterns)]
+        if let crate::BackendStore::Sys(_) = &store.as_store_ref().inner.store {
+            if let Ok(watch) =
+                wasmer_vm::WriteWatch::new(view.data_ptr(), view.data_size() as usize)
+            {
+                return Self::Watch(watch);
+            }
+        }
+        let _ = store;
+
+        Self::Compare(view.copy_to_vec().expect("the memory can be read"))
+    }
+}
+
+/// The pages of the memory whose contents differ from `baseline`, including
+/// the ones past its end.
+fn changed_pages(view: &MemoryView, baseline: &[u8]) -> Vec<u32> {
+    let mut changed = Vec::new();
+    let mut page = vec![0; WASM_PAGE_SIZE];
+    for index in 0..view.size().0 {
+        let index = PageIndex(index);
+        view.read(index.offset(), &mut page)
+            .expect("the page is within the memory");
+        let offset = index.offset() as usize;
+        if baseline.get(offset..offset + WASM_PAGE_SIZE) != Some(page.as_slice()) {
+            changed.push(index.0);
+        }
+    }
+    changed
+}
+
+/// The pages that overlap a range of bytes.
+#[cfg(all(
+    feature = "sys",
+    target_os = "linux",
+    any(
+        target_arch = "x86_64",
+        target_arch = "aarch64",
+        target_arch = "riscv64"
+    )
+))]
+fn pages_in(range: std::ops::Range<usize>) -> std::ops::Range<u32> {
+    (range.start / WASM_PAGE_SIZE) as u32..range.end.div_ceil(WASM_PAGE_SIZE) as u32
+}
+
+impl Memory {
+    /// Starts keeping track of which pages of this memory change, see
+    /// [`DirtyPageTracker`].
+    pub fn start_tracking(&self, store: &impl AsStoreRef) -> DirtyPageTracker {
+        let view = self.view(store);
+        DirtyPageTracker {
+            size: view.size(),
+            pending: BTreeSet::new(),
+            method: Method::start(&view, store),
+        }
+    }
+}
+
+/// The pages of a memory that changed since a previous snapshot, taken with
+/// [`DirtyPageTracker::take_delta()`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemoryDelta {
+    /// The size of the memory when the delta was taken.
+    pub size: Pages,
+    /// The index and contents of every page that changed, in order.
+    pub pages: Vec<(PageIndex, Vec<u8>)>,
+}
+
+impl MemoryDelta {
+    /// Applies the delta to a full image of the memory as of the previous
+    /// snapshot, resizing it if the memory grew (or was reset) since.
+    pub fn apply(&self, image: &mut Vec<u8>) {
+        image.resize(self.size.bytes().0, 0);
+        for (index, contents) in &self.pages {
+            let offset = index.offset() as usize;
+            image[offset..offset + contents.len()].copy_from_slice(contents);
+        }
+    }
+
+    /// Writes the delta out as the memory size in pages and the number of
+    /// pages that follow, then each page's index and contents, with every
+    /// number a little-endian `u32`.
+    pub fn write_to(&self, mut writer: impl Write) -> io::Result<()> {
+        writer.write_all(&self.size.0.to_le_bytes())?;
+        writer.write_all(&(self.pages.len() as u32).to_le_bytes())?;
+        for (index, contents) in &self.pages {
+            writer.write_all(&index.0.to_le_bytes())?;
+            writer.write_all(contents)?;
+        }
+        Ok(())
+    }
+
+    /// Reads a delta written by [`MemoryDelta::write_to()`].
+    pub fn read_from(mut reader: impl Read) -> io::Result<Self> {
+        fn read_u32(reader: &mut impl Read) -> io::Result<u32> {
+            let mut bytes = [0; 4];
+            reader.read_exact(&mut bytes)?;
+            Ok(u32::from_le_bytes(bytes))
+        }
+
+        let size = Pages(read_u32(&mut reader)?);
+        let count = read_u32(&mut reader)?;
+        if count > size.0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{count} dirty pages in a memory of {} pages", size.0),
+            ));
+        }
+
+        let mut pages = Vec::with_capacity(count as usize);
+        for _ in 0..count {
+            let index = PageIndex(read_u32(&mut reader)?);
+            if index.0 >= size.0 {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("page {} is outside a memory of {} pages", index.0, size.0),
+                ));
+            }
+            let mut contents = vec![0; WASM_PAGE_SIZE];
+            reader.read_exact(&mut contents)?;
+            pages.push((index, contents));
+        }
+
+        Ok(Self { size, pages })
+    }
+}
@@ -0,0 +1,156 @@
+//! Caching of compiled modules, see [`ModuleCache`].
+
+use std::{fmt, io, sync::Arc};
+
+use bytes::Bytes;
+use wasmer_types::ModuleHash;
+
+use crate::Engine;
+
+/// Identifies a compiled module in a [`ModuleCache`].
+///
+/// The key is made of a hash of the WebAssembly bytes and a fingerprint of
+/// everything about the engine that changes the compiled code: the compiler
+/// and its configuration, the target triple and CPU features, the enabled
+/// WebAssembly features and the version of Wasmer. Compiling the same bytes
+/// with a differently configured engine gives a different key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ModuleCacheKey {
+    module: ModuleHash,
+    engine: ModuleHash,
+}
+
+impl ModuleCacheKey {
+    /// The key of `wasm` compiled by `engine`.
+    pub fn new(engine: &Engine, wasm: &[u8]) -> Self {
+        Self {
+            module: ModuleHash::sha256(wasm),
+            engine: ModuleHash::sha256(engine_fingerprint(engine)),
+        }
+    }
+
+    /// The hash of the WebAssembly bytes.
+    pub fn module_hash(&self) -> ModuleHash {
+        self.module
+    }
+
+    /// The hash of the engine's configuration.
+    pub fn engine_hash(&self) -> ModuleHash {
+        self.engine
+    }
+}
+
+/// Formats the key as hex digits, which can be used as a file name.
+impl fmt::Display for ModuleCacheKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{}", self.module, self.engine)
+    }
+}
+
+fn engine_fingerprint(engine: &Engine) -> String {
+    #[allow(unused_mut)]
+    let mut fingerprint = format!(
+        "wasmer-{}/{}",
+        env!("CARGO_PKG_VERSION"),
+        engine.deterministic_id()
+    );
+
+    #[cfg(feature = "sys")]
+    if engine.is_sys() {
+        use std::fmt::Write;
+
+        let engine = engine.as_sys();
+        let target = engine.target();
+        let _ = write!(fingerprint, "/{}/", target.triple());
+        for feature in target.cpu_features().iter() {
+            let _ = write!(fingerprint, "{feature},");
+        }
+        #[cfg(feature = "compiler")]
+        let _ = write!(fingerprint, "/{:?}", engine.inner().features());
+    }
+
+    fingerprint
+}
+
+/// A place to keep compiled modules across runs, so the same WebAssembly
+/// bytes don't have to be compiled again.
+///
+/// Modules are stored serialized, as returned by
+/// [`Module::serialize()`](crate::Module::serialize). Set a cache on a store
+/// with [`Store::with_cache()`](crate::Store::with_cache) to have
+/// [`Module::new()`](crate::Module::new) use it.
+///
+/// # Safety
+///
+/// Loading a module from the cache deserializes it, which executes code
+/// from the cache. Only use caches whose contents you trust, as with
+/// [`Module::deserialize()`](crate::Module::deserialize).
+pub trait ModuleCache: fmt::Debug + Send + Sync {
+    /// Returns the serialized module stored under `key`, or `None` if
+    /// there's none.
+    fn load(&self, key: &ModuleCacheKey) -> io::Result<Option<Bytes>>;
+
+    /// Stores the serialized module under `key`, replacing any module
+    /// already stored under it.
+    fn store(&self, key: &ModuleCacheKey, module: &[u8]) -> io::Result<()>;
+}
+
+impl<C: ModuleCache + ?Sized> ModuleCache for Arc<C> {
+    fn load(&self, key: &ModuleCacheKey) -> io::Result<Option<Bytes>> {
+        (**self).load(key)
+    }
+
+    fn store(&self, key: &ModuleCacheKey, module: &[u8]) -> io::Result<()> {
+        (**self).store(key, module)
+    }
+}
+
+/// A [`ModuleCache`] that keeps each module in its own file in a directory.
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug, Clone)]
+pub struct FileSystemCache {
+    dir: std::path::PathBuf,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl FileSystemCache {
+    /// Creates a cache that keeps modules in `dir`, creating the directory
+    /// if it doesn't exist.
+    pub fn new(dir: impl Into<std::path::PathBuf>) -> io::Result<Self> {
+        let dir = dir.into();
+        std::fs::create_dir_all(&dir)?;
+        Ok(Self { dir })
+    }
+
+    /// The directory the modules are kept in.
+    pub fn dir(&self) -> &std::path::Path {
+        &self.dir
+    }
+
+    /// The file the module stored under `key` is kept in.
+    pub fn path(&self, key: &ModuleCacheKey) -> std::path::PathBuf {
+        self.dir.join(format!("{key}.bin"))
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl ModuleCache for FileSystemCache {
+    fn load(&self, key: &ModuleCacheKey) -> io::Result<Option<Bytes>> {
+        match std::fs::read(self.path(key)) {
+            Ok(module) => Ok(Some(module.into())),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn store(&self, key: &ModuleCacheKey, module: &[u8]) -> io::Result<()> {
+        // Write to a temporary file first, so other processes never see a
+        // partially written module
+        let path = self.path(key);
+        let tmp = path.with_extension(format!("{}.tmp", std::process::id()));
+        std::fs::write(&tmp, module)?;
+        std::fs::rename(&tmp, &path).inspect_err(|_| {
+            let _ = std::fs::remove_file(&tmp);
+        })
+    }
+}
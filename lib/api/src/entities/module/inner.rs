@@ -8,10 +8,11 @@ use thiserror::Error;
 #[cfg(feature = "wat")]
 use wasmer_types::WasmError;
 use wasmer_types::{
-    CompileError, DeserializeError, ExportType, ExportsIterator, ImportType, ImportsIterator,
-    ModuleInfo, SerializeError,
+    ArtifactStats, CompileError, DeserializeError, ExportType, ExportsIterator, Features,
+    ImportType, ImportsIterator, ModuleInfo, SerializeError,
 };
 
+use super::{ModuleCache, ModuleCacheKey};
 use crate::{
     macros::backend::{gen_rt_ty, match_rt},
     utils::IntoBytes,
@@ -67,6 +68,66 @@ impl BackendModule {
     /// this crate).
     #[inline]
     pub fn from_binary(engine: &impl AsEngineRef, binary: &[u8]) -> Result<Self, CompileError> {
+        let cache = engine
+            .maybe_as_store()
+            .and_then(|store| store.inner.cache.clone());
+        match cache {
+            Some(cache) => Self::from_binary_cached(engine, binary, cache.as_ref()),
+            None => Self::compile(engine, binary),
+        }
+    }
+
+    /// Loads the module from `cache` if it's there, compiling it and
+    /// storing it in the cache otherwise.
+    ///
+    /// Cached modules that can't be deserialized, e.g. because they were
+    /// compiled by an incompatible version of Wasmer, are compiled again and
+    /// replaced. Failing to read from or write to the cache isn't an error.
+    fn from_binary_cached(
+        engine: &impl AsEngineRef,
+        binary: &[u8],
+        cache: &dyn ModuleCache,
+    ) -> Result<Self, CompileError> {
+        let key = ModuleCacheKey::new(engine.as_engine_ref().engine(), binary);
+        match cache.load(&key) {
+            // Safety: the cache is trusted, see `ModuleCache`
+            Ok(Some(serialized)) => match unsafe { Self::deserialize(engine, serialized) } {
+                Ok(module) => return Ok(module),
+                Err(error) => tracing::debug!(
+                    %key,
+                    error = &error as &dyn std::error::Error,
+                    "Unable to deserialize the cached module, compiling it again",
+                ),
+            },
+            Ok(None) => {}
+            Err(error) => tracing::warn!(
+                %key,
+                error = &error as &dyn std::error::Error,
+                "Unable to read from the module cache",
+            ),
+        }
+
+        let module = Self::compile(engine, binary)?;
+        match module.serialize() {
+            Ok(serialized) => {
+                if let Err(error) = cache.store(&key, &serialized) {
+                    tracing::warn!(
+                        %key,
+                        error = &error as &dyn std::error::Error,
+                        "Unable to write to the module cache",
+                    );
+                }
+            }
+            Err(error) => tracing::debug!(
+                %key,
+                error = &error as &dyn std::error::Error,
+                "Unable to serialize the module for the cache",
+            ),
+        }
+        Ok(module)
+    }
+
+    fn compile(engine: &impl AsEngineRef, binary: &[u8]) -> Result<Self, CompileError> {
         match engine.as_engine_ref().inner.be {
             #[cfg(feature = "sys")]
             crate::BackendEngine::Sys(_) => Ok(Self::Sys(
@@ -100,6 +161,27 @@ impl BackendModule {
         }
     }
 
+    /// Reads a Wasm binary from `reader` and compiles it once it has been
+    /// read completely.
+    #[cfg(feature = "tokio")]
+    pub async fn from_async_reader(
+        engine: &impl AsEngineRef,
+        mut reader: impl tokio::io::AsyncRead + Unpin,
+    ) -> Result<Self, super::IoCompileError> {
+        #[cfg(all(feature = "sys", feature = "compiler"))]
+        if engine.as_engine_ref().engine().is_sys() {
+            return Ok(Self::Sys(
+                crate::backend::sys::entities::module::Module::from_async_reader(engine, reader)
+                    .await?,
+            ));
+        }
+
+        // The other backends need the whole module up front
+        let mut binary = Vec::new();
+        tokio::io::AsyncReadExt::read_to_end(&mut reader, &mut binary).await?;
+        Ok(Self::from_binary(engine, &binary)?)
+    }
+
     /// Creates a new WebAssembly module from a Wasm binary,
     /// skipping any kind of validation on the WebAssembly file.
     ///
@@ -194,6 +276,28 @@ impl BackendModule {
         Ok(())
     }
 
+    /// Validates a WebAssembly module against `features` instead of the
+    /// features of the engine.
+    #[inline]
+    pub fn validate_with_features(
+        engine: &impl AsEngineRef,
+        features: &Features,
+        binary: &[u8],
+    ) -> Result<(), CompileError> {
+        match engine.as_engine_ref().inner.be {
+            #[cfg(feature = "sys")]
+            crate::BackendEngine::Sys(_) => {
+                crate::backend::sys::entities::module::Module::validate_with_features(
+                    features, binary,
+                )
+            }
+            #[allow(unreachable_patterns)]
+            _ => Err(CompileError::UnsupportedTarget(
+                "Validating a Module against custom features requires the sys backend".to_string(),
+            )),
+        }
+    }
+
     /// Serializes a module into a binary representation that the `Engine`
     /// can later process via [`Self::deserialize`].
     ///
@@ -620,6 +724,36 @@ impl BackendModule {
         })
     }
 
+    /// Get the distinct names of the custom sections of the module.
+    #[inline]
+    pub fn custom_section_names(&self) -> impl Iterator<Item = &str> + '_ {
+        match_rt!(on self => s {
+            s.custom_section_names()
+        })
+    }
+
+    /// The WebAssembly features this module uses.
+    #[inline]
+    pub fn required_features(&self) -> Features {
+        match self {
+            #[cfg(feature = "sys")]
+            Self::Sys(s) => s.required_features().clone(),
+            #[allow(unreachable_patterns)]
+            _ => Features::none(),
+        }
+    }
+
+    /// Statistics about the code generated for this module.
+    #[inline]
+    pub fn stats(&self) -> Option<ArtifactStats> {
+        match self {
+            #[cfg(feature = "sys")]
+            Self::Sys(s) => Some(*s.stats()),
+            #[allow(unreachable_patterns)]
+            _ => None,
+        }
+    }
+
     /// The ABI of the [`ModuleInfo`] is very unstable, we refactor it very often.
     /// This function is public because in some cases it can be useful to get some
     /// extra information from the module.
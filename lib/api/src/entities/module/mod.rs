@@ -4,6 +4,11 @@
 pub(crate) mod inner;
 pub(crate) use inner::*;
 
+mod cache;
+#[cfg(not(target_arch = "wasm32"))]
+pub use cache::FileSystemCache;
+pub use cache::{ModuleCache, ModuleCacheKey};
+
 use std::{fs, path::Path};
 
 use bytes::Bytes;
@@ -11,8 +16,8 @@ use thiserror::Error;
 #[cfg(feature = "wat")]
 use wasmer_types::WasmError;
 use wasmer_types::{
-    CompileError, DeserializeError, ExportType, ExportsIterator, ImportType, ImportsIterator,
-    ModuleInfo, SerializeError,
+    ArtifactStats, CompileError, DeserializeError, ExportType, ExportsIterator, Features,
+    ImportType, ImportsIterator, ModuleInfo, SerializeError,
 };
 
 use crate::{macros::backend::match_rt, utils::IntoBytes, AsEngineRef};
@@ -54,6 +59,14 @@ impl Module {
     /// Before the code is compiled, it will be validated using the store
     /// features.
     ///
+    /// ## Caching
+    ///
+    /// If the store has a [`ModuleCache`] (see [`Store::with_cache()`]),
+    /// the module is loaded from it instead of being compiled when
+    /// possible, and stored in it otherwise.
+    ///
+    /// [`Store::with_cache()`]: crate::Store::with_cache
+    ///
     /// ## Errors
     ///
     /// Creating a WebAssembly module from bytecode can result in a
@@ -130,6 +143,40 @@ impl Module {
         BackendModule::from_binary(engine, binary).map(Self)
     }
 
+    /// Reads a Wasm binary from `reader` (e.g. a file or a download) and
+    /// compiles it.
+    ///
+    /// This is a convenience over reading the whole binary into memory and
+    /// calling [`Module::from_binary()`]: it does **not** compile the module
+    /// while it is being read. Compilation only starts once the reader
+    /// reaches EOF.
+    ///
+    /// With the `sys` backend, the bytes are validated as they are read, so
+    /// a corrupted module is rejected without waiting for the rest of the
+    /// input, and validation errors report the offset within the input at
+    /// which the problem was found. Other backends buffer the whole module
+    /// before validating it.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use wasmer::*;
+    /// # async fn example(store: &Store) -> anyhow::Result<()> {
+    /// let file = tokio::fs::File::open("path/to/foo.wasm").await?;
+    /// let module = Module::from_async_reader(store, file).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "tokio")]
+    pub async fn from_async_reader(
+        engine: &impl AsEngineRef,
+        reader: impl tokio::io::AsyncRead + Unpin,
+    ) -> Result<Self, IoCompileError> {
+        BackendModule::from_async_reader(engine, reader)
+            .await
+            .map(Self)
+    }
+
     /// Creates a new WebAssembly module from a Wasm binary,
     /// skipping any kind of validation on the WebAssembly file.
     ///
@@ -138,6 +185,9 @@ impl Module {
     /// This can speed up compilation time a bit, but it should be only used
     /// in environments where the WebAssembly modules are trusted and validated
     /// beforehand.
+    ///
+    /// The `sys` backend still validates the module, since that is where it
+    /// finds out which [features][Module::required_features] the module uses.
     pub unsafe fn from_binary_unchecked(
         engine: &impl AsEngineRef,
         binary: &[u8],
@@ -156,6 +206,25 @@ impl Module {
         Ok(())
     }
 
+    /// Validates a WebAssembly module against `features` instead of the
+    /// features of the engine, e.g. to check whether a module would still
+    /// be valid with threads disabled.
+    ///
+    /// This only runs validation, so no compiler is involved. When the
+    /// module is only invalid because a feature is disabled, the error names
+    /// that feature and the offset validation failed at.
+    ///
+    /// # Note
+    ///
+    /// This is only supported by the `sys` backend.
+    pub fn validate_with_features(
+        engine: &impl AsEngineRef,
+        features: &Features,
+        binary: &[u8],
+    ) -> Result<(), CompileError> {
+        BackendModule::validate_with_features(engine, features, binary)
+    }
+
     /// Serializes a module into a binary representation that the `Engine`
     /// can later process via [`Module::deserialize`].
     ///
@@ -431,6 +500,47 @@ impl Module {
         self.0.custom_sections(name)
     }
 
+    /// Get the distinct names of the custom sections of the module, in the
+    /// order they first appear.
+    ///
+    /// Use [`Module::custom_sections()`] to read the sections themselves.
+    /// This is always empty on the `js` and `v8` backends, which can only
+    /// look custom sections up by name.
+    pub fn custom_section_names(&self) -> impl Iterator<Item = &str> + '_ {
+        self.0.custom_section_names()
+    }
+
+    /// The WebAssembly features this module actually uses.
+    ///
+    /// Unlike the features enabled on the [`Engine`][crate::Engine], this
+    /// only contains the proposals whose instructions, types or sections
+    /// appear in the module, so it can be used to audit a module before
+    /// running it. See [`Store::set_disabled_features()`][crate::Store::set_disabled_features]
+    /// for forbidding modules from using certain features.
+    ///
+    /// # Note
+    ///
+    /// This is only known for modules compiled with the `sys` backend; other
+    /// backends always return [`Features::none()`].
+    pub fn required_features(&self) -> Features {
+        self.0.required_features()
+    }
+
+    /// Statistics about the code generated for this module, such as the size
+    /// of its functions and trampolines and the memory they were loaded into.
+    ///
+    /// The statistics are recorded when the module is compiled or
+    /// deserialized, so a module loaded from a cache reports the same code
+    /// sizes as the one it was serialized from.
+    ///
+    /// # Note
+    ///
+    /// This is only known for modules compiled with the `sys` backend; other
+    /// backends always return `None`.
+    pub fn stats(&self) -> Option<ArtifactStats> {
+        self.0.stats()
+    }
+
     /// The ABI of the [`ModuleInfo`] is very unstable, we refactor it very often.
     /// This function is public because in some cases it can be useful to get some
     /// extra information from the module.
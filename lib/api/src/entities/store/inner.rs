@@ -1,10 +1,12 @@
+use std::sync::Arc;
+
 use crate::{
     entities::{
         engine::{AsEngineRef, Engine},
-        store::{StoreMut, StoreObjects},
+        store::{MemoryLimiter, ResourceUsage, StoreLimits, StoreMut, StoreObjects},
     },
     macros::backend::{gen_rt_ty, match_rt},
-    AsStoreMut,
+    AsStoreMut, ModuleCache,
 };
 
 #[cfg(feature = "sys")]
@@ -17,6 +19,11 @@ pub(crate) struct StoreInner {
     pub(crate) objects: StoreObjects,
     pub(crate) store: BackendStore,
     pub(crate) on_called: Option<OnCalledHandler>,
+    pub(crate) disabled_features: wasmer_types::Features,
+    pub(crate) limits: StoreLimits,
+    pub(crate) usage: ResourceUsage,
+    pub(crate) memory_limiter: Option<Arc<dyn MemoryLimiter>>,
+    pub(crate) cache: Option<Arc<dyn ModuleCache>>,
 }
 
 impl std::fmt::Debug for StoreInner {
@@ -25,6 +32,11 @@ impl std::fmt::Debug for StoreInner {
             .field("objects", &self.objects)
             .field("store", &self.store)
             .field("on_called", &"<...>")
+            .field("disabled_features", &self.disabled_features)
+            .field("limits", &self.limits)
+            .field("usage", &self.usage)
+            .field("memory_limiter", &self.memory_limiter)
+            .field("cache", &self.cache)
             .finish()
     }
 }
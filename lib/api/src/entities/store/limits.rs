@@ -0,0 +1,244 @@
+//! Limits on the objects a [`Store`] can hold.
+
+use std::fmt;
+
+use wasmer_types::{MemoryError, ModuleInfo, Pages};
+
+use crate::{AsStoreMut, RuntimeError};
+
+#[cfg(doc)]
+use crate::{Function, Global, InstantiationError, Memory, Store, Table};
+
+/// A kind of object whose number is limited by [`StoreLimits`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StoreResource {
+    /// Instances of modules.
+    Instances,
+    /// Tables, defined by modules or created by the host.
+    Tables,
+    /// The elements of all tables together.
+    TableElements,
+    /// Globals, defined by modules or created by the host.
+    Globals,
+    /// Functions created by the host.
+    HostFunctions,
+}
+
+impl fmt::Display for StoreResource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Instances => "instances",
+            Self::Tables => "tables",
+            Self::TableElements => "table elements",
+            Self::Globals => "globals",
+            Self::HostFunctions => "host functions",
+        })
+    }
+}
+
+/// The most objects of each kind a [`Store`] may create, see
+/// [`Store::set_limits()`].
+///
+/// A store's objects are only freed when the store is dropped, not when the
+/// handles to them are, so these cap everything the store has ever created
+/// rather than what is still in use. A long-lived store that keeps
+/// instantiating modules eventually runs into them, so the limits are
+/// [unlimited](Self::UNLIMITED) by default, and are best set on stores
+/// that are dropped once a guest is done, to bound what it can create.
+///
+/// The limits are checked when:
+///
+/// - instantiating a module, which fails with
+///   [`InstantiationError::LimitExceeded`];
+/// - creating or growing a [`Table`] from the host, which fails with a
+///   [`RuntimeError`] that can be downcast to [`LimitExceeded`].
+///
+/// Creating a [`Global`] or a [`Function`] from the host can't fail, so
+/// those are only counted, and instantiating a module fails once the store
+/// holds more than it should.
+///
+/// Tables growing from WebAssembly are only bound by their own maximum.
+///
+/// # Note
+///
+/// Modules are only checked with the `sys` backend.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StoreLimits {
+    /// The most instances.
+    pub max_instances: u64,
+    /// The most tables.
+    pub max_tables: u64,
+    /// The most elements in all tables together.
+    pub max_table_elements: u64,
+    /// The most globals.
+    pub max_globals: u64,
+    /// The most host functions.
+    pub max_host_functions: u64,
+}
+
+impl StoreLimits {
+    /// No limits at all, the default.
+    pub const UNLIMITED: Self = Self {
+        max_instances: u64::MAX,
+        max_tables: u64::MAX,
+        max_table_elements: u64::MAX,
+        max_globals: u64::MAX,
+        max_host_functions: u64::MAX,
+    };
+
+    /// Returns the limit for `resource`.
+    pub fn get(&self, resource: StoreResource) -> u64 {
+        match resource {
+            StoreResource::Instances => self.max_instances,
+            StoreResource::Tables => self.max_tables,
+            StoreResource::TableElements => self.max_table_elements,
+            StoreResource::Globals => self.max_globals,
+            StoreResource::HostFunctions => self.max_host_functions,
+        }
+    }
+}
+
+impl Default for StoreLimits {
+    fn default() -> Self {
+        Self::UNLIMITED
+    }
+}
+
+/// The objects a [`Store`] has created, as counted for its [`StoreLimits`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ResourceUsage {
+    /// The number of instances.
+    pub instances: u64,
+    /// The number of tables.
+    pub tables: u64,
+    /// The number of elements in all tables together.
+    pub table_elements: u64,
+    /// The number of globals.
+    pub globals: u64,
+    /// The number of host functions.
+    pub host_functions: u64,
+}
+
+impl ResourceUsage {
+    /// Returns the usage of `resource`.
+    pub fn get(&self, resource: StoreResource) -> u64 {
+        match resource {
+            StoreResource::Instances => self.instances,
+            StoreResource::Tables => self.tables,
+            StoreResource::TableElements => self.table_elements,
+            StoreResource::Globals => self.globals,
+            StoreResource::HostFunctions => self.host_functions,
+        }
+    }
+
+    fn get_mut(&mut self, resource: StoreResource) -> &mut u64 {
+        match resource {
+            StoreResource::Instances => &mut self.instances,
+            StoreResource::Tables => &mut self.tables,
+            StoreResource::TableElements => &mut self.table_elements,
+            StoreResource::Globals => &mut self.globals,
+            StoreResource::HostFunctions => &mut self.host_functions,
+        }
+    }
+}
+
+/// The error creating a [`Table`] from the host fails with when the store
+/// is at one of its [`StoreLimits`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[error("the store can't hold more than {limit} {resource}")]
+pub struct LimitExceeded {
+    /// The resource that ran out.
+    pub resource: StoreResource,
+    /// The limit for it.
+    pub limit: u64,
+}
+
+impl From<LimitExceeded> for RuntimeError {
+    fn from(error: LimitExceeded) -> Self {
+        Self::user(Box::new(error))
+    }
+}
+
+/// Accounts for the linear memories of a [`Store`], see
+/// [`Store::set_memory_limiter()`].
+///
+/// Unlike [`StoreLimits`], a limiter can be shared by several stores, for
+/// example to cap the memory of everything a tenant runs. It is told about
+/// every memory created in the store, either by instantiating a module or
+/// from the host with [`Memory::new()`], and about every time one of them
+/// grows, whether from the host or from WebAssembly. Memories release what
+/// they were charged when they are dropped.
+///
+/// # Note
+///
+/// Memories are only accounted for with the `sys` backend.
+pub trait MemoryLimiter: fmt::Debug + Send + Sync {
+    /// Charges `pages` more pages of memory, or fails the creation or growth
+    /// of the memory that needs them.
+    ///
+    /// Growing from WebAssembly turns the error into `memory.grow` returning
+    /// -1.
+    fn on_grow(&self, pages: Pages) -> Result<(), MemoryError>;
+
+    /// Releases `pages` pages that were charged with [`Self::on_grow()`].
+    fn on_shrink(&self, pages: Pages);
+}
+
+/// Counts `amounts` towards the store's usage, unless that would take it
+/// over a limit.
+///
+/// Resources with an amount of zero are still checked, which is how
+/// resources that can be created without checking are enforced.
+pub(crate) fn reserve(
+    store: &mut impl AsStoreMut,
+    amounts: &[(StoreResource, u64)],
+) -> Result<(), LimitExceeded> {
+    let mut store = store.as_store_mut();
+    let inner = &mut *store.inner;
+
+    for &(resource, amount) in amounts {
+        let limit = inner.limits.get(resource);
+        let used = inner.usage.get(resource);
+        if used.saturating_add(amount) > limit {
+            return Err(LimitExceeded { resource, limit });
+        }
+    }
+    for &(resource, amount) in amounts {
+        *inner.usage.get_mut(resource) += amount;
+    }
+    Ok(())
+}
+
+/// Undoes a [`reserve()`] for objects that weren't created after all.
+pub(crate) fn release(store: &mut impl AsStoreMut, amounts: &[(StoreResource, u64)]) {
+    let mut store = store.as_store_mut();
+    for &(resource, amount) in amounts {
+        let used = store.inner.usage.get_mut(resource);
+        *used = used.saturating_sub(amount);
+    }
+}
+
+/// Counts objects that are created whatever the limits are.
+pub(crate) fn record(store: &mut impl AsStoreMut, resource: StoreResource, amount: u64) {
+    let mut store = store.as_store_mut();
+    let used = store.inner.usage.get_mut(resource);
+    *used = used.saturating_add(amount);
+}
+
+/// What instantiating the module described by `info` adds to a store.
+pub(crate) fn instance_amounts(info: &ModuleInfo) -> [(StoreResource, u64); 5] {
+    let tables = info.tables.values().skip(info.num_imported_tables);
+    [
+        (StoreResource::Instances, 1),
+        (StoreResource::Tables, tables.clone().count() as u64),
+        (
+            StoreResource::TableElements,
+            tables.map(|table| u64::from(table.minimum)).sum(),
+        ),
+        (
+            StoreResource::Globals,
+            (info.globals.len() - info.num_imported_globals) as u64,
+        ),
+        (StoreResource::HostFunctions, 0),
+    ]
+}
@@ -11,9 +11,19 @@ pub use store_ref::*;
 mod obj;
 pub use obj::*;
 
-use crate::{AsEngineRef, BackendEngine, Engine, EngineRef};
+pub(crate) mod limits;
+pub use limits::{LimitExceeded, MemoryLimiter, ResourceUsage, StoreLimits, StoreResource};
+
+#[cfg(feature = "sys")]
+mod scope;
+#[cfg(feature = "sys")]
+pub use scope::*;
+
+use std::sync::Arc;
+
+use crate::{AsEngineRef, BackendEngine, Engine, EngineRef, ModuleCache};
 pub(crate) use inner::*;
-use wasmer_types::StoreId;
+use wasmer_types::{Features, StoreId};
 
 #[cfg(feature = "sys")]
 use wasmer_vm::TrapHandlerFn;
@@ -34,14 +44,30 @@ pub struct Store {
 
 impl Store {
     /// Creates a new `Store` with a specific [`Engine`].
+    ///
+    /// # Panics
+    ///
+    /// With the `sys` backend, this panics if the engine's tunables are
+    /// inconsistent (see `Tunables::validate()`). Use [`Store::try_new()`]
+    /// to handle that case instead.
     pub fn new(engine: impl Into<Engine>) -> Self {
+        match Self::try_new(engine) {
+            Ok(store) => store,
+            Err(e) => panic!("Unable to create a store with invalid tunables: {e}"),
+        }
+    }
+
+    /// Creates a new `Store` with a specific [`Engine`], or returns a
+    /// description of the problem if the engine's tunables are inconsistent
+    /// (see `Tunables::validate()`).
+    pub fn try_new(engine: impl Into<Engine>) -> Result<Self, String> {
         let engine: Engine = engine.into();
 
         let store = match engine.be {
             #[cfg(feature = "sys")]
-            BackendEngine::Sys(_) => {
-                BackendStore::Sys(crate::backend::sys::entities::store::Store::new(engine))
-            }
+            BackendEngine::Sys(_) => BackendStore::Sys(
+                crate::backend::sys::entities::store::Store::try_new(engine)?,
+            ),
             #[cfg(feature = "wamr")]
             BackendEngine::Wamr(_) => {
                 BackendStore::Wamr(crate::backend::wamr::entities::store::Store::new(engine))
@@ -64,13 +90,33 @@ impl Store {
             }
         };
 
-        Self {
+        Ok(Self {
             inner: Box::new(StoreInner {
                 objects: StoreObjects::from_store_ref(&store),
                 on_called: None,
+                disabled_features: Features::none(),
+                limits: StoreLimits::default(),
+                usage: ResourceUsage::default(),
+                memory_limiter: None,
+                cache: None,
                 store,
             }),
-        }
+        })
+    }
+
+    /// Creates a new `Store` with a specific [`Engine`], whose modules are
+    /// loaded from `cache` when possible.
+    ///
+    /// [`Module::new()`] and [`Module::from_binary()`] look modules up in the
+    /// cache before compiling them, and store the modules they compile in
+    /// it. See [`ModuleCache`] for what that entails.
+    ///
+    /// [`Module::new()`]: crate::Module::new
+    /// [`Module::from_binary()`]: crate::Module::from_binary
+    pub fn with_cache(engine: impl Into<Engine>, cache: impl ModuleCache + 'static) -> Self {
+        let mut store = Self::new(engine);
+        store.set_cache(Some(Arc::new(cache)));
+        store
     }
 
     #[cfg(feature = "sys")]
@@ -98,6 +144,74 @@ impl Store {
         self.inner.store.engine_mut()
     }
 
+    /// Returns the features that modules used with this store are not
+    /// allowed to use.
+    pub fn disabled_features(&self) -> &Features {
+        &self.inner.disabled_features
+    }
+
+    /// Forbid modules that use any of the enabled `features` from being
+    /// compiled with or instantiated in this store.
+    ///
+    /// This is enforced on top of the [`Engine`]'s own features, so it can
+    /// be used to lock down individual stores that share an engine.
+    /// Compiling a module directly against the [`Engine`] bypasses the
+    /// check, but instantiating it in this store will still fail with
+    /// [`LinkError::DisabledFeature`][crate::LinkError::DisabledFeature].
+    ///
+    /// # Note
+    ///
+    /// Only the `sys` backend knows which features a module uses, so this
+    /// has no effect with other backends.
+    pub fn set_disabled_features(&mut self, features: Features) {
+        self.inner.disabled_features = features;
+    }
+
+    /// Returns the limits on the objects this store can hold.
+    pub fn limits(&self) -> &StoreLimits {
+        &self.inner.limits
+    }
+
+    /// Sets the limits on the objects this store can hold, see
+    /// [`StoreLimits`].
+    ///
+    /// Objects the store already holds are kept even if they are over the
+    /// new limits.
+    pub fn set_limits(&mut self, limits: StoreLimits) {
+        self.inner.limits = limits;
+    }
+
+    /// Returns how many objects of each kind this store holds, as counted
+    /// for its [`StoreLimits`].
+    pub fn resource_usage(&self) -> ResourceUsage {
+        self.inner.usage
+    }
+
+    /// Returns the limiter the memories of this store are accounted with.
+    pub fn memory_limiter(&self) -> Option<&Arc<dyn MemoryLimiter>> {
+        self.inner.memory_limiter.as_ref()
+    }
+
+    /// Accounts for every memory created in this store from now on with
+    /// `limiter`, see [`MemoryLimiter`].
+    ///
+    /// Memories the store already holds stay with the limiter they were
+    /// created with, if any.
+    pub fn set_memory_limiter(&mut self, limiter: Option<Arc<dyn MemoryLimiter>>) {
+        self.inner.memory_limiter = limiter;
+    }
+
+    /// Returns the cache modules compiled with this store are kept in.
+    pub fn cache(&self) -> Option<&Arc<dyn ModuleCache>> {
+        self.inner.cache.as_ref()
+    }
+
+    /// Keeps the modules compiled with this store in `cache` from now on,
+    /// see [`Store::with_cache()`].
+    pub fn set_cache(&mut self, cache: Option<Arc<dyn ModuleCache>>) {
+        self.inner.cache = cache;
+    }
+
     /// Checks whether two stores are identical. A store is considered
     /// equal to another store if both have the same engine.
     pub fn same(a: &Self, b: &Self) -> bool {
@@ -0,0 +1,266 @@
+//! Instantiating modules whose host functions borrow from the stack, see
+//! [`Store::scope()`].
+
+use std::{
+    marker::PhantomData,
+    sync::{Arc, Mutex, PoisonError, TryLockError},
+};
+
+use crate::{
+    error::InstantiationError,
+    utils::{FromToNativeWasmType, IntoResult, WasmTypeList},
+    AsEngineRef, AsStoreMut, AsStoreRef, EngineRef, Exports, Function, Imports, Instance, Module,
+    Store, StoreMut, StoreObjects, StoreRef,
+};
+
+impl Store {
+    /// Runs `f` with a fresh store (using the same engine), in which host
+    /// functions can borrow anything that outlives the call, without the
+    /// usual `'static` bound.
+    ///
+    /// The closures behind the functions created in the [`Scope`] are dropped
+    /// along with it before `scope()` returns, which is what makes the
+    /// borrows sound. Calling one of those functions afterwards panics. The
+    /// [`ScopedInstance`]s it creates can't be returned from `f`.
+    ///
+    /// ```
+    /// # use wasmer::{imports, Module, Store};
+    /// # fn main() -> anyhow::Result<()> {
+    /// let store = Store::default();
+    /// let module = Module::new(
+    ///     &store,
+    ///     r#"(module
+    ///         (import "env" "add" (func $add (param i32)))
+    ///         (func (export "run") (call $add (i32.const 2)) (call $add (i32.const 3))))"#,
+    /// )?;
+    ///
+    /// let mut total = 0;
+    /// store.scope(|scope| -> anyhow::Result<()> {
+    ///     let add = scope.function(|x: i32| total += x);
+    ///     let instance = scope.instantiate(&module, &imports! { "env" => { "add" => add } })?;
+    ///     let run = instance.exports().get_typed_function::<(), ()>(scope, "run")?;
+    ///     run.call(scope)?;
+    ///     Ok(())
+    /// })?;
+    ///
+    /// assert_eq!(total, 5);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn scope<'env, F, R>(&self, f: F) -> R
+    where
+        F: for<'scope> FnOnce(&'scope mut Scope<'scope, 'env>) -> R,
+    {
+        let mut store = Self::new(self.engine().clone());
+        store.set_disabled_features(self.disabled_features().clone());
+        store.set_limits(*self.limits());
+        store.set_memory_limiter(self.memory_limiter().cloned());
+        store.set_cache(self.cache().cloned());
+
+        let mut scope = Scope {
+            store,
+            releases: Releases::default(),
+            scope: PhantomData,
+            env: PhantomData,
+        };
+        f(&mut scope)
+        // Every host function borrowing from `'env` is dropped here, along
+        // with the scope
+    }
+}
+
+/// A store whose host functions may borrow data that lives for `'env`,
+/// created by [`Store::scope()`].
+///
+/// Both lifetimes are invariant, so that neither the scope nor anything tied
+/// to `'scope` can leave the closure it was given to.
+pub struct Scope<'scope, 'env: 'scope> {
+    store: Store,
+    /// Drop the closures of the host functions created in the scope.
+    ///
+    /// The store only gets a handle to each closure, since its objects can
+    /// be swapped into a store that outlives `'env` (see
+    /// [`AsStoreMut::objects_mut()`]).
+    releases: Releases,
+    scope: PhantomData<&'scope mut &'scope ()>,
+    env: PhantomData<&'env mut &'env ()>,
+}
+
+impl<'scope, 'env> Scope<'scope, 'env> {
+    /// Creates a host function from a closure that may borrow (mutably, too)
+    /// anything that lives for `'env`.
+    ///
+    /// # Panics
+    ///
+    /// Calling the function while it is already running (i.e. from
+    /// WebAssembly that it called into) panics.
+    pub fn function<F, Args, Rets>(&mut self, func: F) -> Function
+    where
+        F: ScopedHostFunction<'env, Args, Rets>,
+    {
+        let (function, release) = func.into_function(&mut self.store);
+        self.releases.0.push(release);
+        function
+    }
+
+    /// Instantiates `module` in this scope.
+    #[allow(clippy::result_large_err)]
+    pub fn instantiate(
+        &mut self,
+        module: &Module,
+        imports: &Imports,
+    ) -> Result<ScopedInstance<'scope>, InstantiationError> {
+        let instance = Instance::new(&mut self.store, module, imports)?;
+        Ok(ScopedInstance {
+            instance,
+            scope: PhantomData,
+        })
+    }
+}
+
+/// Runs the callbacks that drop a [`Scope`]'s closures when dropped.
+///
+/// This is kept apart from the scope, whose lifetimes would otherwise have
+/// to outlive its own `Drop`.
+#[derive(Default)]
+struct Releases(Vec<Box<dyn FnOnce() + Send>>);
+
+impl Drop for Releases {
+    fn drop(&mut self) {
+        for release in self.0.drain(..) {
+            release();
+        }
+    }
+}
+
+impl std::fmt::Debug for Scope<'_, '_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("Scope").finish()
+    }
+}
+
+impl AsEngineRef for Scope<'_, '_> {
+    fn as_engine_ref(&self) -> EngineRef<'_> {
+        self.store.as_engine_ref()
+    }
+
+    fn maybe_as_store(&self) -> Option<StoreRef<'_>> {
+        Some(self.as_store_ref())
+    }
+}
+
+impl AsStoreRef for Scope<'_, '_> {
+    fn as_store_ref(&self) -> StoreRef<'_> {
+        self.store.as_store_ref()
+    }
+}
+
+impl AsStoreMut for Scope<'_, '_> {
+    fn as_store_mut(&mut self) -> StoreMut<'_> {
+        self.store.as_store_mut()
+    }
+
+    fn objects_mut(&mut self) -> &mut StoreObjects {
+        self.store.objects_mut()
+    }
+}
+
+/// An [`Instance`] created by [`Scope::instantiate()`], which can't outlive
+/// its scope.
+#[derive(Debug)]
+pub struct ScopedInstance<'scope> {
+    instance: Instance,
+    scope: PhantomData<&'scope mut &'scope ()>,
+}
+
+impl ScopedInstance<'_> {
+    /// The exports of the instance.
+    pub fn exports(&self) -> &Exports {
+        &self.instance.exports
+    }
+
+    /// The module the instance was created from.
+    pub fn module(&self) -> &Module {
+        self.instance.module()
+    }
+}
+
+/// A closure that can be turned into a host function by
+/// [`Scope::function()`].
+///
+/// This is implemented for every `FnMut` that is `Send`, lives for `'env` and
+/// has the same kind of signature as a [`Function::new_typed()`] function.
+pub trait ScopedHostFunction<'env, Args, Rets> {
+    /// Creates the function, along with a callback that drops the closure.
+    #[doc(hidden)]
+    fn into_function(self, store: &mut Store) -> (Function, Box<dyn FnOnce() + Send>);
+}
+
+macro_rules! impl_scoped_host_function {
+    ( $( $x:ident ),* ) => {
+        #[allow(unused_parens, non_snake_case)]
+        impl<'env, $( $x, )* Rets, RetsAsResult, Func>
+            ScopedHostFunction<'env, ( $( $x ),* ), Rets>
+        for
+            Func
+        where
+            $( $x: FromToNativeWasmType + 'static, )*
+            Rets: WasmTypeList,
+            RetsAsResult: IntoResult<Rets> + 'static,
+            Func: FnMut($( $x ),*) -> RetsAsResult + Send + 'env,
+        {
+            fn into_function(self, store: &mut Store) -> (Function, Box<dyn FnOnce() + Send>) {
+                let func: Box<dyn FnMut($( $x ),*) -> RetsAsResult + Send + 'env> =
+                    Box::new(self);
+                // SAFETY: the closure is dropped by the release callback,
+                // which the scope runs before `Store::scope()` returns and
+                // so before `'env` ends. It waits for calls on other threads
+                // to finish, and later calls find the closure gone.
+                let func: Box<dyn FnMut($( $x ),*) -> RetsAsResult + Send + 'static> =
+                    unsafe { std::mem::transmute(func) };
+                let func = Arc::new(Mutex::new(Some(func)));
+
+                let release = {
+                    let func = func.clone();
+                    Box::new(move || {
+                        func.lock().unwrap_or_else(PoisonError::into_inner).take();
+                    })
+                };
+
+                let function = Function::new_typed::<_, ( $( $x ),* ), Rets>(
+                    store,
+                    move |$( $x: $x ),*| -> RetsAsResult {
+                        let mut func = match func.try_lock() {
+                            Ok(func) => func,
+                            // A previous call panicked, which doesn't leave
+                            // anything behind for us to worry about
+                            Err(TryLockError::Poisoned(poisoned)) => poisoned.into_inner(),
+                            Err(TryLockError::WouldBlock) => {
+                                panic!("a scoped host function was called while already running")
+                            }
+                        };
+                        match func.as_mut() {
+                            Some(func) => func($( $x ),*),
+                            None => panic!("a scoped host function was called after its scope ended"),
+                        }
+                    },
+                );
+                (function, release)
+            }
+        }
+    };
+}
+
+impl_scoped_host_function!();
+impl_scoped_host_function!(A1);
+impl_scoped_host_function!(A1, A2);
+impl_scoped_host_function!(A1, A2, A3);
+impl_scoped_host_function!(A1, A2, A3, A4);
+impl_scoped_host_function!(A1, A2, A3, A4, A5);
+impl_scoped_host_function!(A1, A2, A3, A4, A5, A6);
+impl_scoped_host_function!(A1, A2, A3, A4, A5, A6, A7);
+impl_scoped_host_function!(A1, A2, A3, A4, A5, A6, A7, A8);
+impl_scoped_host_function!(A1, A2, A3, A4, A5, A6, A7, A8, A9);
+impl_scoped_host_function!(A1, A2, A3, A4, A5, A6, A7, A8, A9, A10);
+impl_scoped_host_function!(A1, A2, A3, A4, A5, A6, A7, A8, A9, A10, A11);
+impl_scoped_host_function!(A1, A2, A3, A4, A5, A6, A7, A8, A9, A10, A11, A12);
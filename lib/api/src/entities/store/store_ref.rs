@@ -1,6 +1,9 @@
-use std::ops::{Deref, DerefMut};
+use std::{
+    ops::{Deref, DerefMut},
+    sync::Arc,
+};
 
-use super::{inner::StoreInner, StoreObjects};
+use super::{inner::StoreInner, MemoryLimiter, StoreObjects};
 use crate::entities::engine::{AsEngineRef, Engine, EngineRef};
 use wasmer_types::{ExternType, OnCalledAction};
 //use wasmer_vm::{StoreObjects, TrapHandlerFn};
@@ -56,6 +59,16 @@ impl StoreMut<'_> {
         StoreObjects::same(&a.inner.objects, &b.inner.objects)
     }
 
+    /// See [`Store::memory_limiter()`](crate::Store::memory_limiter).
+    pub fn memory_limiter(&self) -> Option<&Arc<dyn MemoryLimiter>> {
+        self.inner.memory_limiter.as_ref()
+    }
+
+    /// See [`Store::set_memory_limiter()`](crate::Store::set_memory_limiter).
+    pub fn set_memory_limiter(&mut self, limiter: Option<Arc<dyn MemoryLimiter>>) {
+        self.inner.memory_limiter = limiter;
+    }
+
     #[allow(unused)]
     pub(crate) fn as_raw(&self) -> *mut StoreInner {
         self.inner as *const StoreInner as *mut StoreInner
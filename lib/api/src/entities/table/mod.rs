@@ -4,10 +4,12 @@ pub(crate) mod inner;
 pub(crate) use inner::*;
 
 use crate::{
+    entities::store::limits,
     error::RuntimeError,
     store::BackendStore,
     vm::{VMExtern, VMExternTable},
-    AsStoreMut, AsStoreRef, ExportError, Exportable, Extern, StoreMut, StoreRef, Value,
+    AsStoreMut, AsStoreRef, ExportError, Exportable, Extern, StoreMut, StoreRef, StoreResource,
+    Value,
 };
 
 /// A WebAssembly `table` instance.
@@ -29,12 +31,24 @@ impl Table {
     /// All the elements in the table will be set to the `init` value.
     ///
     /// This function will construct the table using the store `BaseTunables`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the store can't hold another table of this size,
+    /// see [`StoreLimits`](crate::StoreLimits).
     pub fn new(
         store: &mut impl AsStoreMut,
         ty: TableType,
         init: Value,
     ) -> Result<Self, RuntimeError> {
-        BackendTable::new(store, ty, init).map(Self)
+        let amounts = [
+            (StoreResource::Tables, 1),
+            (StoreResource::TableElements, u64::from(ty.minimum)),
+        ];
+        limits::reserve(store, &amounts)?;
+        BackendTable::new(store, ty, init)
+            .map(Self)
+            .inspect_err(|_| limits::release(store, &amounts))
     }
 
     /// Returns the [`TableType`] of the table.
@@ -70,14 +84,19 @@ impl Table {
     ///
     /// # Errors
     ///
-    /// Returns an error if the `delta` is out of bounds for the table.
+    /// Returns an error if the `delta` is out of bounds for the table, or if
+    /// the store can't hold that many more elements.
     pub fn grow(
         &self,
         store: &mut impl AsStoreMut,
         delta: u32,
         init: Value,
     ) -> Result<u32, RuntimeError> {
-        self.0.grow(store, delta, init)
+        let amounts = [(StoreResource::TableElements, u64::from(delta))];
+        limits::reserve(store, &amounts)?;
+        self.0
+            .grow(store, delta, init)
+            .inspect_err(|_| limits::release(store, &amounts))
     }
 
     /// Copies the `len` elements of `src_table` starting at `src_index`
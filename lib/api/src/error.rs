@@ -2,7 +2,7 @@ use std::sync::Arc;
 use thiserror::Error;
 use wasmer_types::{FrameInfo, ImportError, TrapCode};
 
-use crate::BackendTrap as Trap;
+use crate::{BackendTrap as Trap, LimitExceeded, StoreResource};
 
 /// The WebAssembly.LinkError object indicates an error during
 /// module instantiation (besides traps from the start function).
@@ -24,6 +24,14 @@ pub enum LinkError {
     /// Insufficient resources available for linking.
     #[cfg_attr(feature = "std", error("Insufficient resources: {0}"))]
     Resource(String),
+
+    /// The module uses a feature that has been disabled for the
+    /// [`Store`][super::Store].
+    #[cfg_attr(
+        feature = "std",
+        error("the module requires the {0} feature, which is disabled for this store")
+    )]
+    DisabledFeature(String),
 }
 
 /// An error while instantiating a module.
@@ -45,6 +53,19 @@ pub enum InstantiationError {
     #[cfg_attr(feature = "std", error(transparent))]
     Start(RuntimeError),
 
+    /// The start function didn't finish within the deadline given with
+    /// [`InstantiationOptions::start_timeout()`][super::InstantiationOptions::start_timeout].
+    #[cfg_attr(
+        feature = "std",
+        error("the start function didn't finish within {timeout:?}")
+    )]
+    StartTimeout {
+        /// The deadline the start function was given.
+        timeout: std::time::Duration,
+        /// Where the start function was executing when it was interrupted.
+        trace: Vec<FrameInfo>,
+    },
+
     /// The module was compiled with a CPU feature that is not available on
     /// the current host.
     #[cfg_attr(feature = "std", error("missing required CPU features: {0:?}"))]
@@ -59,6 +80,25 @@ pub enum InstantiationError {
     /// This error occurs when an import from a different store is used.
     #[cfg_attr(feature = "std", error("incorrect OS or architecture"))]
     DifferentArchOS,
+
+    /// Instantiating the module would take the [`Store`][super::Store] over
+    /// one of its [`StoreLimits`][super::StoreLimits].
+    #[cfg_attr(
+        feature = "std",
+        error("the store can't hold more than {limit} {resource}")
+    )]
+    LimitExceeded {
+        /// The resource that ran out.
+        resource: StoreResource,
+        /// The limit for it.
+        limit: u64,
+    },
+}
+
+impl From<LimitExceeded> for InstantiationError {
+    fn from(LimitExceeded { resource, limit }: LimitExceeded) -> Self {
+        Self::LimitExceeded { resource, limit }
+    }
 }
 
 /// A struct representing an aborted instruction execution, with a message
@@ -230,11 +270,15 @@ impl std::fmt::Display for RuntimeError {
             }
             write!(
                 f,
-                " ({}[{}]:0x{:x})",
+                " ({}[{}]:0x{:x}",
                 name,
                 func_index,
                 frame.module_offset()
             )?;
+            if let Some(location) = frame.source_location() {
+                write!(f, ", {location}")?;
+            }
+            write!(f, ")")?;
         }
         Ok(())
     }
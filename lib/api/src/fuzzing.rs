@@ -0,0 +1,20 @@
+//! Entry points for fuzzing the parts of Wasmer that don't need an
+//! [`Instance`](crate::Instance) to be reached.
+//!
+//! Every function here takes arbitrary bytes and reports what it found as
+//! an error; a panic is always a bug.
+
+use wasmer_compiler::{ModuleEnvironment, StreamingValidator};
+use wasmer_types::{CompileError, Features};
+
+/// Validates `bytes` as a module with every proposal enabled and, if it is
+/// valid, translates it into a [`ModuleInfo`](wasmer_types::ModuleInfo)
+/// the way compilation would, stopping short of code generation.
+pub fn validate_arbitrary(bytes: &[u8]) -> Result<(), CompileError> {
+    let mut validator = StreamingValidator::new(&Features::all());
+    validator.write(bytes)?;
+    let bytes = validator.finish()?;
+
+    ModuleEnvironment::new().translate(&bytes)?;
+    Ok(())
+}
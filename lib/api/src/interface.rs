@@ -0,0 +1,508 @@
+//! Describe and compare the interface of a WebAssembly [`Module`].
+//!
+//! A [`ModuleInterface`] captures every import and export of a module along
+//! with its [`ExternType`]. Two interfaces can be compared with
+//! [`ModuleInterface::diff()`], which is handy for checking whether a new
+//! version of a plugin is still compatible with the host it is loaded into.
+//!
+//! ```rust
+//! # use wasmer::{Module, Store, interface::ModuleInterface};
+//! # fn main() -> anyhow::Result<()> {
+//! let store = Store::default();
+//! let v1 = Module::new(&store, r#"(module (func (export "run") (param i32)))"#)?;
+//! let v2 = Module::new(&store, r#"(module (func (export "run") (param i64)))"#)?;
+//!
+//! let diff = ModuleInterface::from_module(&v1).diff(&ModuleInterface::from_module(&v2));
+//! assert_eq!(diff.changed.len(), 1);
+//! println!("{diff}");
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! The other way around, an [`ImportManifest`] records what a host provides
+//! (see [`Imports::manifest()`]), and [`check_module_against_manifest()`]
+//! lists everything that keeps a module from being instantiated with it.
+//!
+//! [`Imports::manifest()`]: crate::Imports::manifest
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+};
+
+use crate::{ExternType, Module, Type};
+
+/// An import or export of a module.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "enable-serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum InterfaceEntry {
+    /// An entity imported from `module`.
+    Import {
+        /// The module the entity is imported from.
+        module: String,
+        /// The name of the entity within `module`.
+        name: String,
+    },
+    /// An entity exported by the module.
+    Export {
+        /// The name the entity is exported under.
+        name: String,
+    },
+}
+
+impl fmt::Display for InterfaceEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Import { module, name } => write!(f, "import \"{module}\".\"{name}\""),
+            Self::Export { name } => write!(f, "export \"{name}\""),
+        }
+    }
+}
+
+/// All the imports and exports of a [`Module`], along with their types.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "enable-serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ModuleInterface {
+    /// The imports and exports of the module, sorted by entry.
+    pub entries: Vec<(InterfaceEntry, ExternType)>,
+}
+
+impl ModuleInterface {
+    /// Capture the interface of a [`Module`].
+    pub fn from_module(module: &Module) -> Self {
+        let imports = module.imports().map(|import| {
+            let entry = InterfaceEntry::Import {
+                module: import.module().to_string(),
+                name: import.name().to_string(),
+            };
+            (entry, import.ty().clone())
+        });
+        let exports = module.exports().map(|export| {
+            let entry = InterfaceEntry::Export {
+                name: export.name().to_string(),
+            };
+            (entry, export.ty().clone())
+        });
+
+        let mut entries: Vec<_> = imports.chain(exports).collect();
+        entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        Self { entries }
+    }
+
+    /// Returns the type of an entry, if the module has it.
+    pub fn get(&self, entry: &InterfaceEntry) -> Option<&ExternType> {
+        self.entries
+            .iter()
+            .find(|(e, _)| e == entry)
+            .map(|(_, ty)| ty)
+    }
+
+    /// Compare this interface (the old one) with `other` (the new one).
+    pub fn diff(&self, other: &Self) -> InterfaceDiff {
+        let old: BTreeMap<_, _> = self.entries.iter().map(|(e, ty)| (e, ty)).collect();
+        let new: BTreeMap<_, _> = other.entries.iter().map(|(e, ty)| (e, ty)).collect();
+
+        let mut diff = InterfaceDiff::default();
+
+        for (entry, old_ty) in &old {
+            match new.get(entry) {
+                None => diff.removed.push(((*entry).clone(), (*old_ty).clone())),
+                Some(new_ty) if new_ty != old_ty => diff.changed.push(ChangedEntry {
+                    entry: (*entry).clone(),
+                    old: (*old_ty).clone(),
+                    new: (*new_ty).clone(),
+                }),
+                Some(_) => {}
+            }
+        }
+
+        for (entry, new_ty) in &new {
+            if !old.contains_key(entry) {
+                diff.added.push(((*entry).clone(), (*new_ty).clone()));
+            }
+        }
+
+        diff
+    }
+}
+
+impl fmt::Display for ModuleInterface {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (entry, ty) in &self.entries {
+            writeln!(f, "{entry}: {ty}")?;
+        }
+        Ok(())
+    }
+}
+
+/// The differences between two [`ModuleInterface`]s.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "enable-serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct InterfaceDiff {
+    /// Entries that only exist in the new interface.
+    pub added: Vec<(InterfaceEntry, ExternType)>,
+    /// Entries that only exist in the old interface.
+    pub removed: Vec<(InterfaceEntry, ExternType)>,
+    /// Entries that exist in both interfaces, but with different types.
+    pub changed: Vec<ChangedEntry>,
+}
+
+impl InterfaceDiff {
+    /// Returns `true` if both interfaces are identical.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+impl fmt::Display for InterfaceDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return writeln!(f, "The interfaces are identical");
+        }
+
+        for (entry, ty) in &self.removed {
+            writeln!(f, "- {entry}: {ty}")?;
+        }
+        for (entry, ty) in &self.added {
+            writeln!(f, "+ {entry}: {ty}")?;
+        }
+        for changed in &self.changed {
+            writeln!(f, "{changed}")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// An entry whose type differs between two [`ModuleInterface`]s.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "enable-serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ChangedEntry {
+    /// The import or export that changed.
+    pub entry: InterfaceEntry,
+    /// The type in the old interface.
+    pub old: ExternType,
+    /// The type in the new interface.
+    pub new: ExternType,
+}
+
+impl ChangedEntry {
+    /// Describe how the type changed.
+    pub fn change(&self) -> TypeChange {
+        match (&self.old, &self.new) {
+            (ExternType::Function(old), ExternType::Function(new)) => TypeChange::Function {
+                params: positional_changes(old.params(), new.params()),
+                results: positional_changes(old.results(), new.results()),
+            },
+            (old, new) if std::mem::discriminant(old) != std::mem::discriminant(new) => {
+                TypeChange::Kind
+            }
+            _ => TypeChange::Other,
+        }
+    }
+}
+
+impl fmt::Display for ChangedEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "~ {}: {} => {}", self.entry, self.old, self.new)?;
+
+        if let TypeChange::Function { params, results } = self.change() {
+            let details: Vec<String> = params
+                .iter()
+                .map(|c| c.describe("parameter"))
+                .chain(results.iter().map(|c| c.describe("result")))
+                .collect();
+            if !details.is_empty() {
+                write!(f, " ({})", details.join(", "))?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// A summary of how the type of a [`ChangedEntry`] changed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TypeChange {
+    /// The entry changed kind (e.g. a function became a global).
+    Kind,
+    /// A function's signature changed.
+    Function {
+        /// Changes to the function's parameters.
+        params: Vec<PositionalChange>,
+        /// Changes to the function's results.
+        results: Vec<PositionalChange>,
+    },
+    /// Any other change (limits, mutability, sharing, etc.).
+    Other,
+}
+
+/// A change to a single parameter or result of a function.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PositionalChange {
+    /// The index of the parameter or result.
+    pub index: usize,
+    /// The old type, or `None` if it was added.
+    pub old: Option<Type>,
+    /// The new type, or `None` if it was removed.
+    pub new: Option<Type>,
+}
+
+impl PositionalChange {
+    fn describe(&self, what: &str) -> String {
+        let index = self.index;
+        match (self.old, self.new) {
+            (Some(old), Some(new)) => format!("{what} {index}: {old} -> {new}"),
+            (None, Some(new)) => format!("{what} {index} added: {new}"),
+            (Some(old), None) => format!("{what} {index} removed: {old}"),
+            (None, None) => format!("{what} {index}"),
+        }
+    }
+}
+
+fn positional_changes(old: &[Type], new: &[Type]) -> Vec<PositionalChange> {
+    (0..old.len().max(new.len()))
+        .map(|index| PositionalChange {
+            index,
+            old: old.get(index).copied(),
+            new: new.get(index).copied(),
+        })
+        .filter(|change| change.old != change.new)
+        .collect()
+}
+
+/// The imports a host provides, along with their types, e.g. to publish
+/// what plugins may import.
+///
+/// With the `enable-serde` feature, manifests can be serialized, e.g. to
+/// JSON or TOML. Documents carry a [`version`](Self::version), which should
+/// be checked against [`ImportManifest::VERSION`] when reading them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "enable-serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ImportManifest {
+    /// The version of the manifest format.
+    pub version: u32,
+    /// The provided imports, sorted by module and name.
+    pub imports: Vec<ManifestImport>,
+}
+
+impl ImportManifest {
+    /// The version of the manifest format this crate reads and writes.
+    pub const VERSION: u32 = 1;
+
+    /// Create a manifest of the given imports.
+    pub fn new(imports: impl IntoIterator<Item = ManifestImport>) -> Self {
+        let mut imports: Vec<_> = imports.into_iter().collect();
+        imports.sort_by(|a, b| (&a.module, &a.name).cmp(&(&b.module, &b.name)));
+        Self {
+            version: Self::VERSION,
+            imports,
+        }
+    }
+
+    /// Returns the type of an import, if the host provides it.
+    pub fn get(&self, module: &str, name: &str) -> Option<&ExternType> {
+        self.imports
+            .iter()
+            .find(|import| import.module == module && import.name == name)
+            .map(|import| &import.ty)
+    }
+}
+
+/// An import provided by a host, see [`ImportManifest`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "enable-serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ManifestImport {
+    /// The module the import is provided under.
+    pub module: String,
+    /// The name of the import within `module`.
+    pub name: String,
+    /// The type of the import.
+    #[cfg_attr(feature = "enable-serde", serde(rename = "type"))]
+    pub ty: ExternType,
+}
+
+/// Something that keeps a module from being instantiated with the imports of
+/// an [`ImportManifest`], or that the host provides in vain.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IncompatibilityReport {
+    /// The module of the import.
+    pub module: String,
+    /// The name of the import within `module`.
+    pub name: String,
+    /// What is wrong with the import.
+    pub kind: Incompatibility,
+}
+
+/// What is wrong with an import, see [`IncompatibilityReport`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Incompatibility {
+    /// The module imports something the host doesn't provide.
+    Missing {
+        /// A provided import with a similar name, which might have been meant
+        /// instead, as `(module, name)`.
+        suggestion: Option<(String, String)>,
+    },
+    /// The host provides the import, but with another type.
+    TypeMismatch {
+        /// The type the module imports.
+        expected: ExternType,
+        /// The type the host provides.
+        provided: ExternType,
+    },
+    /// The host provides something the module doesn't import.
+    Unused,
+}
+
+impl IncompatibilityReport {
+    /// Whether the module can't be instantiated because of this, which is
+    /// the case for everything but [`Incompatibility::Unused`] imports.
+    pub fn is_error(&self) -> bool {
+        !matches!(self.kind, Incompatibility::Unused)
+    }
+}
+
+impl fmt::Display for IncompatibilityReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let entry = InterfaceEntry::Import {
+            module: self.module.clone(),
+            name: self.name.clone(),
+        };
+        match &self.kind {
+            Incompatibility::Missing { suggestion } => {
+                write!(f, "{entry} is not provided by the host")?;
+                if let Some((module, name)) = suggestion {
+                    write!(f, " (did you mean \"{module}\".\"{name}\"?)")?;
+                }
+                Ok(())
+            }
+            Incompatibility::TypeMismatch { expected, provided } => {
+                let changed = ChangedEntry {
+                    entry,
+                    old: provided.clone(),
+                    new: expected.clone(),
+                };
+                write!(
+                    f,
+                    "{} is imported as {expected}, but the host provides {provided}",
+                    changed.entry
+                )?;
+                if let TypeChange::Function { params, results } = changed.change() {
+                    let details: Vec<String> = params
+                        .iter()
+                        .map(|c| c.describe("parameter"))
+                        .chain(results.iter().map(|c| c.describe("result")))
+                        .collect();
+                    if !details.is_empty() {
+                        write!(f, " ({})", details.join(", "))?;
+                    }
+                }
+                Ok(())
+            }
+            Incompatibility::Unused => {
+                write!(f, "{entry} is provided by the host, but not imported")
+            }
+        }
+    }
+}
+
+/// Check the imports of `module` against what the host provides.
+///
+/// Reports imports the host doesn't provide or provides with another type
+/// first, in the order the module imports them, followed by the provided
+/// imports the module doesn't use. Imports that are missing get a
+/// [`suggestion`](Incompatibility::Missing::suggestion) when an unused
+/// provided import has a similar name.
+pub fn check_module_against_manifest(
+    module: &Module,
+    manifest: &ImportManifest,
+) -> Vec<IncompatibilityReport> {
+    let imported: BTreeSet<(String, String)> = module
+        .imports()
+        .map(|import| (import.module().to_string(), import.name().to_string()))
+        .collect();
+    let unused: Vec<&ManifestImport> = manifest
+        .imports
+        .iter()
+        .filter(|provided| !imported.contains(&(provided.module.clone(), provided.name.clone())))
+        .collect();
+
+    let mut reports = Vec::new();
+    for import in module.imports() {
+        let kind = match manifest.get(import.module(), import.name()) {
+            None => Incompatibility::Missing {
+                suggestion: suggest(import.module(), import.name(), &unused),
+            },
+            Some(provided) if provided != import.ty() => Incompatibility::TypeMismatch {
+                expected: import.ty().clone(),
+                provided: provided.clone(),
+            },
+            Some(_) => continue,
+        };
+        reports.push(IncompatibilityReport {
+            module: import.module().to_string(),
+            name: import.name().to_string(),
+            kind,
+        });
+    }
+    reports.extend(unused.into_iter().map(|provided| IncompatibilityReport {
+        module: provided.module.clone(),
+        name: provided.name.clone(),
+        kind: Incompatibility::Unused,
+    }));
+
+    reports
+}
+
+/// The provided import whose name is closest to `module`.`name`, if it is
+/// close enough to likely be a typo.
+fn suggest(module: &str, name: &str, candidates: &[&ManifestImport]) -> Option<(String, String)> {
+    // Allow about one typo for every three characters
+    let max_distance = ((module.len() + name.len()) / 3).max(1);
+
+    candidates
+        .iter()
+        .map(|candidate| {
+            let distance =
+                levenshtein(module, &candidate.module) + levenshtein(name, &candidate.name);
+            (distance, candidate)
+        })
+        .filter(|(distance, _)| *distance <= max_distance)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| (candidate.module.clone(), candidate.name.clone()))
+}
+
+/// The number of single character insertions, deletions and substitutions
+/// it takes to turn `a` into `b`.
+fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+
+    for (i, ca) in a.chars().enumerate() {
+        current[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::levenshtein;
+
+    #[test]
+    fn levenshtein_distance() {
+        assert_eq!(levenshtein("", ""), 0);
+        assert_eq!(levenshtein("log", "log"), 0);
+        assert_eq!(levenshtein("log", ""), 3);
+        assert_eq!(levenshtein("lgo", "log"), 2);
+        assert_eq!(levenshtein("kitten", "sitting"), 3);
+        assert_eq!(levenshtein("fd_write", "fd_writ"), 1);
+    }
+}
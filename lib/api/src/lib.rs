@@ -434,16 +434,22 @@ pub use entities::*;
 mod error;
 pub use error::*;
 
+pub mod interface;
+
+#[cfg(feature = "fuzzing")]
+pub mod fuzzing;
+
 mod backend;
 pub use backend::*;
 mod vm;
 
 pub use wasmer_types::{
-    is_wasm, Bytes, CompileError, DeserializeError, ExportIndex, ExportType, ExternType, FrameInfo,
-    FunctionType, GlobalInit, GlobalType, ImportType, LocalFunctionIndex, MemoryError, MemoryStyle,
+    is_wasm, AllocationStats, ArtifactStats, Bytes, CompileError, DeserializeError, ExportIndex,
+    ExportType, ExternType, Features, FrameInfo, FunctionType, GlobalInit, GlobalType,
+    ImportLimits, ImportType, LocalFunctionIndex, MemoryError, MemoryGrowthPolicy, MemoryStyle,
     MemoryType, Mutability, OnCalledAction, Pages, ParseCpuFeatureError, SerializeError,
-    TableStyle, TableType, TagKind, TagType, Type, ValueType, WasmError, WasmResult,
-    WASM_MAX_PAGES, WASM_MIN_PAGES, WASM_PAGE_SIZE,
+    SourceLocation, TableStyle, TableType, TagKind, TagType, Type, UnsupportedOperator, ValueType,
+    WasmError, WasmResult, WASM_MAX_PAGES, WASM_MIN_PAGES, WASM_PAGE_SIZE,
 };
 
 #[cfg(feature = "wasmparser")]
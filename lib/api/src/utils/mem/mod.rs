@@ -31,6 +31,10 @@ pub enum MemoryAccessError {
     /// Pointer to memory is unaligned.
     #[error("unaligned pointer read")]
     UnalignedPointerRead,
+    /// String to be written with a null terminator contains a null
+    /// character.
+    #[error("string contains a nul character")]
+    InteriorNul,
 }
 
 impl From<MemoryAccessError> for RuntimeError {
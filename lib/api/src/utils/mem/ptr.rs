@@ -228,6 +228,60 @@ impl<M: MemorySize> WasmPtr<u8, M> {
         let vec = self.read_until(view, |&byte| byte == 0)?;
         Ok(String::from_utf8(vec)?)
     }
+
+    /// Reads a UTF-8 string from the `WasmPtr` with the given length,
+    /// replacing invalid sequences with `U+FFFD REPLACEMENT CHARACTER`.
+    ///
+    /// This method is safe to call even if the memory is being concurrently
+    /// modified.
+    #[inline]
+    pub fn read_utf8_string_lossy(
+        &self,
+        view: &MemoryView,
+        len: M::Offset,
+    ) -> Result<String, MemoryAccessError> {
+        let vec = self.slice(view, len)?.read_to_vec()?;
+        Ok(match String::from_utf8(vec) {
+            Ok(string) => string,
+            Err(err) => String::from_utf8_lossy(err.as_bytes()).into_owned(),
+        })
+    }
+
+    /// Writes `string` to the `WasmPtr`, without a terminator.
+    ///
+    /// Nothing is written if the string doesn't fit in the memory.
+    #[inline]
+    pub fn write_utf8_string(
+        &self,
+        view: &MemoryView,
+        string: &str,
+    ) -> Result<(), MemoryAccessError> {
+        let len =
+            M::Offset::try_from(string.len() as u64).map_err(|_| MemoryAccessError::Overflow)?;
+        self.slice(view, len)?.write_slice(string.as_bytes())
+    }
+
+    /// Writes `string` followed by a null terminator to the `WasmPtr`.
+    ///
+    /// Returns [`MemoryAccessError::InteriorNul`] if the string contains a
+    /// null character, as it couldn't be read back in full. Nothing is
+    /// written if the string doesn't fit in the memory.
+    #[inline]
+    pub fn write_utf8_string_with_nul(
+        &self,
+        view: &MemoryView,
+        string: &str,
+    ) -> Result<(), MemoryAccessError> {
+        if string.contains('\0') {
+            return Err(MemoryAccessError::InteriorNul);
+        }
+        let mut bytes = Vec::with_capacity(string.len() + 1);
+        bytes.extend_from_slice(string.as_bytes());
+        bytes.push(0);
+        let len =
+            M::Offset::try_from(bytes.len() as u64).map_err(|_| MemoryAccessError::Overflow)?;
+        self.slice(view, len)?.write_slice(&bytes)
+    }
 }
 
 unsafe impl<T: ValueType, M: MemorySize> FromToNativeWasmType for WasmPtr<T, M>
@@ -13,6 +13,14 @@ pub use native::*;
 pub(crate) mod mem;
 pub use mem::*;
 
+/// Calling exports that take and return bytes through the memory of a [`crate::Instance`].
+mod typed_caller;
+pub use typed_caller::{TypedCaller, TypedCallerError};
+
+/// Calling the exports of a [`crate::Instance`] from inside its imports.
+mod reentrant_caller;
+pub use reentrant_caller::ReentrantCaller;
+
 /// Useful macros to generate enums to represent `Runtime`-types.
 pub(crate) mod rt_macros;
 
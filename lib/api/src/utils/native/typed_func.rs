@@ -78,6 +78,16 @@ macro_rules! impl_native_traits {
                 }
             }
 
+            /// Call the typed func, interrupting it if it hasn't returned
+            /// within `timeout`. See [`Function::call_with_deadline()`].
+            #[cfg(feature = "sys")]
+            #[allow(clippy::too_many_arguments)]
+            pub fn call_with_deadline(&self, store: &mut impl AsStoreMut, timeout: std::time::Duration, $( $x: $x, )* ) -> Result<Rets, RuntimeError> {
+                crate::entities::function::deadline::call_with_deadline(store, timeout, |store| {
+                    self.call(store, $( $x, )*)
+                })
+            }
+
             #[doc(hidden)]
             #[allow(missing_docs)]
             #[allow(unused_mut)]
@@ -0,0 +1,97 @@
+use std::sync::{Arc, Mutex};
+
+use crate::{AsStoreMut, Exports, Function, Instance, RuntimeError, Value};
+
+/// Calls the exports of an instance from inside the host functions it
+/// imports, e.g. a guest calling a host `ping` that calls the guest's
+/// `pong`.
+///
+/// As the instance only exists once its imports do, the caller starts out
+/// empty and is given the instance after it has been created. Keep a clone
+/// of it in the data of the [`FunctionEnv`](crate::FunctionEnv) the host
+/// functions share, and clone it out of the data before calling, so the
+/// data isn't borrowed while the guest runs and the host functions it
+/// calls in turn can borrow it:
+///
+/// ```no_run
+/// # use wasmer::{imports, Function, FunctionEnv, FunctionEnvMut, Instance, Module, ReentrantCaller, RuntimeError, Store, Value};
+/// # let mut store = Store::default();
+/// # let module = Module::new(&store, "(module)").unwrap();
+/// struct Env {
+///     caller: ReentrantCaller,
+/// }
+///
+/// fn ping(mut env: FunctionEnvMut<Env>, n: i32) -> Result<i32, RuntimeError> {
+///     let caller = env.data().caller.clone();
+///     let results = caller.call(&mut env, "pong", &[Value::I32(n)])?;
+///     Ok(results[0].unwrap_i32())
+/// }
+///
+/// let caller = ReentrantCaller::new();
+/// let env = FunctionEnv::new(&mut store, Env { caller: caller.clone() });
+/// let imports = imports! {
+///     "env" => { "ping" => Function::new_typed_with_env(&mut store, &env, ping) },
+/// };
+/// let instance = Instance::new(&mut store, &module, &imports)?;
+/// caller.set_instance(&instance);
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+///
+/// A trap in a nested call is returned to the host function that made it,
+/// and if that returns it, unwinds the guest that called the host function
+/// in turn, all the way out to the outermost call. Every nested call takes
+/// up a new Wasm stack and some of the thread's stack. With the `sys`
+/// backend, `wasmer::sys::vm::set_max_nested_calls()` makes nesting them
+/// deeper than a limit trap with
+/// [`TrapCode::StackOverflow`](wasmer_types::TrapCode::StackOverflow)
+/// instead of exhausting either.
+#[derive(Debug, Clone, Default)]
+pub struct ReentrantCaller {
+    exports: Arc<Mutex<Option<Exports>>>,
+}
+
+impl ReentrantCaller {
+    /// Creates a caller without an instance yet.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Calls the exports of `instance` from now on, and so do the clones of
+    /// this caller.
+    pub fn set_instance(&self, instance: &Instance) {
+        *self.exports.lock().unwrap() = Some(instance.exports.clone());
+    }
+
+    /// Whether the caller has been given an instance yet.
+    pub fn is_set(&self) -> bool {
+        self.exports.lock().unwrap().is_some()
+    }
+
+    /// Calls the exported function `name` with `params`.
+    ///
+    /// `store` is usually the [`FunctionEnvMut`](crate::FunctionEnvMut) of
+    /// the host function making the call.
+    pub fn call(
+        &self,
+        store: &mut impl AsStoreMut,
+        name: &str,
+        params: &[Value],
+    ) -> Result<Box<[Value]>, RuntimeError> {
+        self.function(name)?.call(store, params)
+    }
+
+    /// The exported function `name`, which can be called directly, e.g. as a
+    /// [`TypedFunction`](crate::TypedFunction).
+    pub fn function(&self, name: &str) -> Result<Function, RuntimeError> {
+        // The lock is released before the call, as the guest may call back
+        // into a host function using this caller
+        let exports = self.exports.lock().unwrap();
+        let exports = exports
+            .as_ref()
+            .ok_or_else(|| RuntimeError::new("the caller hasn't been given an instance yet"))?;
+        exports
+            .get_function(name)
+            .cloned()
+            .map_err(|e| RuntimeError::new(format!("unable to call `{name}`: {e}")))
+    }
+}
@@ -0,0 +1,335 @@
+use std::marker::PhantomData;
+
+use thiserror::Error;
+use wasmer_types::{FunctionType, Memory32, MemorySize, Type};
+
+use crate::{
+    AsStoreMut, AsStoreRef, Exports, Extern, Function, Instance, Memory, MemoryAccessError,
+    RuntimeError, Value,
+};
+
+/// The export names [`TypedCaller::new()`] uses.
+const DEFAULT_MEMORY: &str = "memory";
+const DEFAULT_MALLOC: &str = "malloc";
+const DEFAULT_FREE: &str = "free";
+
+/// An error from a [`TypedCaller`].
+#[derive(Debug, Clone, Error)]
+#[non_exhaustive]
+pub enum TypedCallerError {
+    /// The instance doesn't export something the caller needs.
+    #[error("missing export `{0}`")]
+    MissingExport(String),
+    /// An export isn't of the kind the caller needs, e.g. a global instead
+    /// of a function.
+    #[error("export `{0}` has the wrong kind")]
+    IncompatibleExport(String),
+    /// A function doesn't have any of the signatures the convention allows.
+    #[error("`{name}` has the signature {found}, expected {expected}")]
+    Signature {
+        /// The name of the function.
+        name: String,
+        /// The signatures it could have.
+        expected: String,
+        /// The signature it has.
+        found: FunctionType,
+    },
+    /// The guest's allocator returned a null pointer.
+    #[error("the guest couldn't allocate {0} bytes")]
+    Allocation(u64),
+    /// The guest returned a buffer that isn't inside its memory, or the input
+    /// is too large to be addressed by it.
+    #[error(transparent)]
+    Memory(#[from] MemoryAccessError),
+    /// One of the calls into the guest failed.
+    #[error(transparent)]
+    Runtime(#[from] RuntimeError),
+}
+
+/// How an export returns its output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Output {
+    /// As a `(ptr, len)` pair of results.
+    Returned,
+    /// By writing a `(ptr, len)` pair to the pointer passed as its third
+    /// parameter.
+    OutPointer,
+}
+
+/// Calls exports that take and return bytes as `(ptr, len)` pairs in the
+/// guest's memory, handling the allocation and copying.
+///
+/// The instance has to export its memory, a `malloc(size) -> ptr` function
+/// and a `free(ptr)` or `free(ptr, len)` function (see
+/// [`TypedCaller::with_exports()`] for other names). The exports
+/// [`call_with_bytes()`](Self::call_with_bytes) calls can have either of
+/// these signatures:
+///
+/// - `(ptr, len) -> (ptr, len)`, returning the output.
+/// - `(ptr, len, out_ptr)`, writing the output's `ptr` and `len` to
+///   `out_ptr`, one after the other.
+///
+/// The input is only borrowed by the export, and freed once it returns. The
+/// output belongs to the caller, and is freed once it has been copied out.
+/// Empty inputs are passed as a null pointer, and empty outputs may be too.
+///
+/// `M` is [`Memory32`] for guests using 32-bit pointers, which are `i32`s,
+/// and [`Memory64`](wasmer_types::Memory64) for 64-bit ones, which are
+/// `i64`s.
+///
+/// ```no_run
+/// # use wasmer::{imports, Instance, Module, Store, TypedCaller};
+/// # let mut store = Store::default();
+/// # let module = Module::new(&store, "(module)").unwrap();
+/// let instance = Instance::new(&mut store, &module, &imports! {})?;
+/// let caller: TypedCaller = TypedCaller::new(&store, &instance)?;
+/// let greeting = caller.call_with_bytes(&mut store, "greet", b"World")?;
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct TypedCaller<M: MemorySize = Memory32> {
+    exports: Exports,
+    memory: Memory,
+    malloc: Function,
+    free: Function,
+    /// Whether `free` takes the length along with the pointer.
+    free_takes_len: bool,
+    _memory_size: PhantomData<M>,
+}
+
+impl<M: MemorySize> TypedCaller<M> {
+    /// Creates a caller using the instance's `memory`, `malloc` and `free`
+    /// exports.
+    pub fn new(store: &impl AsStoreRef, instance: &Instance) -> Result<Self, TypedCallerError> {
+        Self::with_exports(
+            store,
+            instance,
+            DEFAULT_MEMORY,
+            DEFAULT_MALLOC,
+            DEFAULT_FREE,
+        )
+    }
+
+    /// Creates a caller using the given exports for the memory, allocator and
+    /// deallocator.
+    pub fn with_exports(
+        store: &impl AsStoreRef,
+        instance: &Instance,
+        memory: &str,
+        malloc: &str,
+        free: &str,
+    ) -> Result<Self, TypedCallerError> {
+        let exports = instance.exports.clone();
+        let memory = match exports.get_extern(memory) {
+            Some(Extern::Memory(m)) => m.clone(),
+            Some(_) => return Err(TypedCallerError::IncompatibleExport(memory.to_string())),
+            None => return Err(TypedCallerError::MissingExport(memory.to_string())),
+        };
+
+        let offset = offset_type::<M>();
+        let malloc_ty = FunctionType::new([offset], [offset]);
+        let malloc = function(store, &exports, malloc, &[&malloc_ty])?.0;
+
+        let free_ty = FunctionType::new([offset], []);
+        let free_with_len_ty = FunctionType::new([offset, offset], []);
+        let (free, index) = function(store, &exports, free, &[&free_ty, &free_with_len_ty])?;
+
+        Ok(Self {
+            exports,
+            memory,
+            malloc,
+            free,
+            free_takes_len: index == 1,
+            _memory_size: PhantomData,
+        })
+    }
+
+    /// Calls the export called `name` with `input`, returning its output.
+    pub fn call_with_bytes(
+        &self,
+        store: &mut impl AsStoreMut,
+        name: &str,
+        input: &[u8],
+    ) -> Result<Vec<u8>, TypedCallerError> {
+        let offset = offset_type::<M>();
+        let returned_ty = FunctionType::new([offset, offset], [offset, offset]);
+        let out_pointer_ty = FunctionType::new([offset, offset, offset], []);
+        let (func, index) = function(
+            &*store,
+            &self.exports,
+            name,
+            &[&returned_ty, &out_pointer_ty],
+        )?;
+        let output = if index == 0 {
+            Output::Returned
+        } else {
+            Output::OutPointer
+        };
+
+        let len = u64::try_from(input.len()).map_err(|_| MemoryAccessError::Overflow)?;
+        if M::Offset::try_from(len).is_err() {
+            return Err(MemoryAccessError::Overflow.into());
+        }
+
+        let ptr = self.alloc(store, len)?;
+        let result = self
+            .memory
+            .view(&*store)
+            .write(ptr, input)
+            .map_err(TypedCallerError::from)
+            .and_then(|()| self.call(store, &func, output, ptr, len));
+        // The input is freed even if the call failed, but its error is the
+        // one worth reporting
+        let freed = self.release(store, ptr, len);
+        let output = result?;
+        freed?;
+        Ok(output)
+    }
+
+    fn call(
+        &self,
+        store: &mut impl AsStoreMut,
+        func: &Function,
+        output: Output,
+        ptr: u64,
+        len: u64,
+    ) -> Result<Vec<u8>, TypedCallerError> {
+        let (out_ptr, out_len) = match output {
+            Output::Returned => {
+                let results = func.call(store, &[to_value::<M>(ptr), to_value::<M>(len)])?;
+                (from_value(&results[0]), from_value(&results[1]))
+            }
+            Output::OutPointer => {
+                let size = offset_size::<M>();
+                let pair = self.alloc(store, 2 * size)?;
+                let pair_bytes = func
+                    .call(
+                        store,
+                        &[to_value::<M>(ptr), to_value::<M>(len), to_value::<M>(pair)],
+                    )
+                    .map_err(TypedCallerError::from)
+                    .and_then(|_| {
+                        let mut bytes = vec![0; 2 * size as usize];
+                        self.memory.view(&*store).read(pair, &mut bytes)?;
+                        Ok(bytes)
+                    });
+                let freed = self.release(store, pair, 2 * size);
+                let bytes = pair_bytes?;
+                freed?;
+                let (ptr, len) = bytes.split_at(size as usize);
+                (from_le_bytes(ptr), from_le_bytes(len))
+            }
+        };
+
+        let bytes = usize::try_from(out_len)
+            .map_err(|_| MemoryAccessError::Overflow)
+            .and_then(|out_len| {
+                let mut bytes = vec![0; out_len];
+                self.memory.view(&*store).read(out_ptr, &mut bytes)?;
+                Ok(bytes)
+            });
+        let freed = self.release(store, out_ptr, out_len);
+        let bytes = bytes?;
+        freed?;
+        Ok(bytes)
+    }
+
+    /// Allocates `len` bytes in the guest. Nothing is allocated for empty
+    /// buffers, which are represented by a null pointer.
+    fn alloc(&self, store: &mut impl AsStoreMut, len: u64) -> Result<u64, TypedCallerError> {
+        if len == 0 {
+            return Ok(0);
+        }
+        let results = self.malloc.call(store, &[to_value::<M>(len)])?;
+        match from_value(&results[0]) {
+            0 => Err(TypedCallerError::Allocation(len)),
+            ptr => Ok(ptr),
+        }
+    }
+
+    fn release(
+        &self,
+        store: &mut impl AsStoreMut,
+        ptr: u64,
+        len: u64,
+    ) -> Result<(), TypedCallerError> {
+        if ptr == 0 {
+            return Ok(());
+        }
+        if self.free_takes_len {
+            self.free
+                .call(store, &[to_value::<M>(ptr), to_value::<M>(len)])?;
+        } else {
+            self.free.call(store, &[to_value::<M>(ptr)])?;
+        }
+        Ok(())
+    }
+}
+
+/// Looks up the function called `name`, returning it along with the index of
+/// the signature it has.
+fn function(
+    store: &impl AsStoreRef,
+    exports: &Exports,
+    name: &str,
+    signatures: &[&FunctionType],
+) -> Result<(Function, usize), TypedCallerError> {
+    let func = match exports.get_extern(name) {
+        Some(Extern::Function(f)) => f.clone(),
+        Some(_) => return Err(TypedCallerError::IncompatibleExport(name.to_string())),
+        None => return Err(TypedCallerError::MissingExport(name.to_string())),
+    };
+
+    let ty = func.ty(store);
+    match signatures.iter().position(|signature| **signature == ty) {
+        Some(index) => Ok((func, index)),
+        None => Err(TypedCallerError::Signature {
+            name: name.to_string(),
+            expected: signatures
+                .iter()
+                .map(|signature| signature.to_string())
+                .collect::<Vec<_>>()
+                .join(" or "),
+            found: ty,
+        }),
+    }
+}
+
+fn offset_type<M: MemorySize>() -> Type {
+    if M::is_64bit() {
+        Type::I64
+    } else {
+        Type::I32
+    }
+}
+
+fn offset_size<M: MemorySize>() -> u64 {
+    if M::is_64bit() {
+        8
+    } else {
+        4
+    }
+}
+
+fn to_value<M: MemorySize>(offset: u64) -> Value {
+    if M::is_64bit() {
+        Value::I64(offset as i64)
+    } else {
+        Value::I32(offset as u32 as i32)
+    }
+}
+
+/// Converts an offset, whose type has already been checked.
+fn from_value(value: &Value) -> u64 {
+    match *value {
+        Value::I32(offset) => offset as u32 as u64,
+        Value::I64(offset) => offset as u64,
+        _ => unreachable!("offsets are i32 or i64"),
+    }
+}
+
+fn from_le_bytes(bytes: &[u8]) -> u64 {
+    let mut offset = [0; 8];
+    offset[..bytes.len()].copy_from_slice(bytes);
+    u64::from_le_bytes(offset)
+}
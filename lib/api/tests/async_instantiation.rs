@@ -0,0 +1,230 @@
+#![cfg(all(feature = "tokio", feature = "cranelift"))]
+
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use wasmer::*;
+use wasmer_types::TrapCode;
+
+fn leb128(mut value: usize, out: &mut Vec<u8>) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            out.push(byte);
+            return;
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+fn section(id: u8, contents: &[u8], out: &mut Vec<u8>) {
+    out.push(id);
+    leb128(contents.len(), out);
+    out.extend_from_slice(contents);
+}
+
+/// A module exporting a memory that is filled by a single data segment of
+/// `len` bytes. Assembled by hand, as the text format of that much data
+/// would take longer to parse than the test takes to run.
+fn module_with_data(len: usize) -> Vec<u8> {
+    let mut wasm = b"\0asm\x01\0\0\0".to_vec();
+
+    let mut memories = vec![1, 0x00];
+    leb128(len.div_ceil(65536), &mut memories);
+    section(5, &memories, &mut wasm);
+
+    let mut exports = vec![1];
+    leb128(6, &mut exports);
+    exports.extend_from_slice(b"memory");
+    exports.extend_from_slice(&[0x02, 0]);
+    section(7, &exports, &mut wasm);
+
+    let mut data = vec![1, 0x00, 0x41, 0x00, 0x0b];
+    leb128(len, &mut data);
+    data.extend((0..len).map(|i| (i % 251) as u8));
+    section(11, &data, &mut wasm);
+
+    wasm
+}
+
+fn memory_contents(store: &Store, memory: &Memory) -> Vec<u8> {
+    memory.view(store).copy_to_vec().unwrap()
+}
+
+/// A module with several segments, which overlap and are applied in
+/// several chunks each.
+const SEGMENTS: &str = r#"
+(module
+    (global $base (import "env" "base") i32)
+    (memory (export "memory") 1)
+    (table (export "table") 8 funcref)
+    (func $a (result i32) (i32.const 1))
+    (func $b (result i32) (i32.const 2))
+    (elem (i32.const 1) $a $b $a $b $a)
+    (elem (global.get $base) $b $b)
+    (data (i32.const 0) "0123456789abcdefghijklmnopqrstuvwxyz")
+    (data (i32.const 10) "ABCDEFGHIJ")
+    (data (global.get $base) "")
+    (data (i32.const 100) "the end")
+)
+"#;
+
+#[tokio::test]
+async fn chunked_instantiation_matches_synchronous_instantiation() {
+    let mut store = Store::default();
+    let module = Module::new(&store, SEGMENTS).unwrap();
+    let imports = imports! {
+        "env" => { "base" => Global::new(&mut store, Value::I32(5)) },
+    };
+
+    let sync = Instance::new(&mut store, &module, &imports).unwrap();
+    for chunk_size in [1, 3, 7, 1024] {
+        let chunked =
+            Instance::new_async_with_chunk_size(&mut store, &module, &imports, chunk_size)
+                .await
+                .unwrap();
+
+        let memory = |instance: &Instance| instance.exports.get_memory("memory").unwrap().clone();
+        assert_eq!(
+            memory_contents(&store, &memory(&chunked)),
+            memory_contents(&store, &memory(&sync)),
+            "chunk size {chunk_size}"
+        );
+
+        let table = |instance: &Instance| instance.exports.get_table("table").unwrap().clone();
+        let (chunked_table, sync_table) = (table(&chunked), table(&sync));
+        for i in 0..8 {
+            let mut element = |table: &Table| match table.get(&mut store, i).unwrap() {
+                Value::FuncRef(Some(f)) => Some(f.call(&mut store, &[]).unwrap()[0].unwrap_i32()),
+                Value::FuncRef(None) => None,
+                other => panic!("unexpected element: {other:?}"),
+            };
+            assert_eq!(
+                element(&chunked_table),
+                element(&sync_table),
+                "chunk size {chunk_size}"
+            );
+        }
+    }
+}
+
+#[tokio::test]
+async fn errors_match_synchronous_instantiation() {
+    let mut store = Store::default();
+
+    // The second segment is out of bounds, the first one is still applied to
+    // the imported memory
+    let module = Module::new(
+        &store,
+        r#"(module
+            (memory (import "env" "memory") 1)
+            (data (i32.const 0) "applied")
+            (data (i32.const 65530) "out of bounds"))"#,
+    )
+    .unwrap();
+    let sync_memory = Memory::new(&mut store, MemoryType::new(1, None, false)).unwrap();
+    let sync_err = Instance::new(
+        &mut store,
+        &module,
+        &imports! { "env" => { "memory" => sync_memory.clone() } },
+    )
+    .unwrap_err();
+    let chunked_memory = Memory::new(&mut store, MemoryType::new(1, None, false)).unwrap();
+    let chunked_err = Instance::new_async_with_chunk_size(
+        &mut store,
+        &module,
+        &imports! { "env" => { "memory" => chunked_memory.clone() } },
+        2,
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(chunked_err.to_string(), sync_err.to_string());
+    assert_eq!(
+        memory_contents(&store, &chunked_memory),
+        memory_contents(&store, &sync_memory)
+    );
+
+    // Traps in the start function
+    let module = Module::new(
+        &store,
+        r#"(module
+            (memory 1)
+            (data (i32.const 0) "some data")
+            (func $start unreachable)
+            (start $start))"#,
+    )
+    .unwrap();
+    let sync_err = Instance::new(&mut store, &module, &imports! {}).unwrap_err();
+    let chunked_err = Instance::new_async_with_chunk_size(&mut store, &module, &imports! {}, 4)
+        .await
+        .unwrap_err();
+    match (&chunked_err, &sync_err) {
+        (InstantiationError::Start(chunked), InstantiationError::Start(sync)) => {
+            assert_eq!(chunked.message(), sync.message());
+            assert_eq!(
+                chunked.clone().to_trap(),
+                Some(TrapCode::UnreachableCodeReached)
+            );
+        }
+        other => panic!("unexpected errors: {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn large_data_segments_dont_starve_the_executor() {
+    const DATA_LEN: usize = 32 * 1024 * 1024;
+    const CHUNK_SIZE: usize = 256 * 1024;
+
+    let mut store = Store::default();
+    let module = Module::new(&store, module_with_data(DATA_LEN)).unwrap();
+
+    // Runs on the same (current thread) executor as the instantiation
+    let done = Arc::new(AtomicBool::new(false));
+    let beats = Arc::new(AtomicUsize::new(0));
+    let longest_gap_us = Arc::new(AtomicU64::new(0));
+    let heartbeat = tokio::spawn({
+        let (done, beats, longest_gap_us) = (done.clone(), beats.clone(), longest_gap_us.clone());
+        async move {
+            let mut last = Instant::now();
+            while !done.load(Ordering::SeqCst) {
+                let now = Instant::now();
+                let gap = (now - last).as_micros() as u64;
+                longest_gap_us.fetch_max(gap, Ordering::SeqCst);
+                last = now;
+                beats.fetch_add(1, Ordering::SeqCst);
+                tokio::task::yield_now().await;
+            }
+        }
+    });
+    // Let the heartbeat start
+    tokio::task::yield_now().await;
+    let beats_before = beats.load(Ordering::SeqCst);
+
+    let instance =
+        Instance::new_async_with_chunk_size(&mut store, &module, &imports! {}, CHUNK_SIZE)
+            .await
+            .unwrap();
+    done.store(true, Ordering::SeqCst);
+    heartbeat.await.unwrap();
+
+    // The heartbeat got a turn between (almost) every chunk
+    let beats = beats.load(Ordering::SeqCst) - beats_before;
+    assert!(beats >= DATA_LEN / CHUNK_SIZE - 1, "only {beats} beats");
+    let longest_gap = Duration::from_micros(longest_gap_us.load(Ordering::SeqCst));
+    assert!(
+        longest_gap < Duration::from_millis(250),
+        "the executor was blocked for {longest_gap:?}"
+    );
+
+    let memory = instance.exports.get_memory("memory").unwrap();
+    let view = memory.view(&store);
+    for offset in [0, 1, CHUNK_SIZE, DATA_LEN / 2 + 3, DATA_LEN - 1] {
+        assert_eq!(view.read_u8(offset as u64).unwrap(), (offset % 251) as u8);
+    }
+}
@@ -0,0 +1,269 @@
+#![cfg(all(feature = "sys", unix))]
+
+use std::time::{Duration, Instant};
+
+use wasmer::*;
+use wasmer_types::TrapCode;
+
+const MODULE: &str = r#"
+(module
+    (import "env" "nested" (func $nested (result i32)))
+    (import "env" "sleep" (func $sleep))
+    (memory (export "memory") 1024)
+    (global $spins (export "spins") (mut i32) (i32.const 0))
+
+    (func (export "add") (param i32 i32) (result i32)
+        (i32.add (local.get 0) (local.get 1)))
+
+    (func $spin (export "spin")
+        (loop $again
+            (global.set $spins (i32.add (global.get $spins) (i32.const 1)))
+            (br $again)))
+
+    ;; Spends nearly all of its time in the `memory.fill` libcall
+    (func (export "fill")
+        (loop $again
+            (memory.fill (i32.const 0) (i32.const 0xff) (i32.const 0x4000000))
+            (br $again)))
+
+    (func (export "nested") (result i32)
+        (call $nested))
+
+    (func (export "sleep_then_spin")
+        (call $sleep)
+        (call $spin))
+)
+"#;
+
+/// The instance's `spin` export, for the `nested` import.
+#[derive(Default)]
+struct Env {
+    spin: Option<Function>,
+}
+
+fn instantiate() -> (Store, Instance) {
+    let mut store = Store::default();
+    let module = Module::new(&store, MODULE).unwrap();
+    let env = FunctionEnv::new(&mut store, Env::default());
+
+    // Calls `spin` with a deadline shorter than the one it is called with
+    let nested = Function::new_typed_with_env(&mut store, &env, |mut env: FunctionEnvMut<Env>| {
+        let (env, mut store) = env.data_and_store_mut();
+        let spin = env.spin.clone().unwrap();
+        let error = spin
+            .call_with_deadline(&mut store, &[], Duration::from_millis(50))
+            .unwrap_err();
+        (error.to_trap() == Some(TrapCode::DeadlineExceeded)) as i32
+    });
+    let sleep = Function::new_typed(&mut store, || {
+        std::thread::sleep(Duration::from_millis(300));
+    });
+    let imports = imports! {
+        "env" => {
+            "nested" => nested,
+            "sleep" => sleep,
+        }
+    };
+
+    let instance = Instance::new(&mut store, &module, &imports).unwrap();
+    env.as_mut(&mut store).spin = Some(instance.exports.get_function("spin").unwrap().clone());
+    (store, instance)
+}
+
+fn exceeded(error: &RuntimeError) -> DeadlineExceeded {
+    assert_eq!(error.clone().to_trap(), Some(TrapCode::DeadlineExceeded));
+    *error.downcast_ref::<DeadlineExceeded>().unwrap()
+}
+
+#[test]
+fn spinning_calls_time_out() {
+    let (mut store, instance) = instantiate();
+    let spin = instance.exports.get_function("spin").unwrap();
+
+    let start = Instant::now();
+    let error = spin
+        .call_with_deadline(&mut store, &[], Duration::from_millis(100))
+        .unwrap_err();
+    let elapsed = start.elapsed();
+
+    assert!(elapsed >= Duration::from_millis(100), "{elapsed:?}");
+    assert!(elapsed < Duration::from_millis(600), "{elapsed:?}");
+    assert_eq!(
+        exceeded(&error),
+        DeadlineExceeded {
+            timeout: Duration::from_millis(100),
+        }
+    );
+}
+
+#[test]
+fn fast_calls_are_unaffected() {
+    let (mut store, instance) = instantiate();
+    let add = instance.exports.get_function("add").unwrap();
+    let typed_add = add.typed::<(i32, i32), i32>(&store).unwrap();
+
+    for i in 0..1000 {
+        let result = add
+            .call_with_deadline(
+                &mut store,
+                &[Value::I32(i), Value::I32(1)],
+                Duration::from_secs(1),
+            )
+            .unwrap();
+        assert_eq!(*result, [Value::I32(i + 1)]);
+        assert_eq!(
+            typed_add
+                .call_with_deadline(&mut store, Duration::from_secs(1), i, 2)
+                .unwrap(),
+            i + 2
+        );
+    }
+
+    // Nothing is left armed for calls without a deadline
+    std::thread::sleep(Duration::from_millis(50));
+    assert_eq!(typed_add.call(&mut store, 1, 2).unwrap(), 3);
+}
+
+#[test]
+fn instances_interrupted_in_wasm_can_be_reused() {
+    let (mut store, instance) = instantiate();
+    let spin = instance
+        .exports
+        .get_typed_function::<(), ()>(&store, "spin")
+        .unwrap();
+    let add = instance
+        .exports
+        .get_typed_function::<(i32, i32), i32>(&store, "add")
+        .unwrap();
+    let spins = instance.exports.get_global("spins").unwrap();
+
+    let error = spin
+        .call_with_deadline(&mut store, Duration::from_millis(50))
+        .unwrap_err();
+    exceeded(&error);
+
+    // The loop's progress is kept, and the instance works as before
+    let Value::I32(before) = spins.get(&mut store) else {
+        panic!()
+    };
+    assert!(before > 0);
+    assert_eq!(add.call(&mut store, 40, 2).unwrap(), 42);
+
+    spin.call_with_deadline(&mut store, Duration::from_millis(50))
+        .unwrap_err();
+    let Value::I32(after) = spins.get(&mut store) else {
+        panic!()
+    };
+    assert!(after > before);
+}
+
+#[test]
+fn libcalls_time_out_once_they_return() {
+    let (mut store, instance) = instantiate();
+    let fill = instance.exports.get_function("fill").unwrap();
+    let add = instance
+        .exports
+        .get_typed_function::<(i32, i32), i32>(&store, "add")
+        .unwrap();
+
+    let start = Instant::now();
+    let error = fill
+        .call_with_deadline(&mut store, &[], Duration::from_millis(50))
+        .unwrap_err();
+
+    assert!(start.elapsed() < Duration::from_secs(1));
+    exceeded(&error);
+    assert_eq!(add.call(&mut store, 40, 2).unwrap(), 42);
+}
+
+#[test]
+fn host_functions_are_not_interrupted() {
+    let (mut store, instance) = instantiate();
+    let sleep_then_spin = instance.exports.get_function("sleep_then_spin").unwrap();
+
+    let start = Instant::now();
+    let error = sleep_then_spin
+        .call_with_deadline(&mut store, &[], Duration::from_millis(50))
+        .unwrap_err();
+
+    assert!(start.elapsed() >= Duration::from_millis(300));
+    exceeded(&error);
+}
+
+#[test]
+fn the_innermost_deadline_applies() {
+    let (mut store, instance) = instantiate();
+    let nested = instance.exports.get_function("nested").unwrap();
+
+    let start = Instant::now();
+    let result = nested
+        .call_with_deadline(&mut store, &[], Duration::from_secs(5))
+        .unwrap();
+
+    // The inner call timed out, which the outer one survived
+    assert_eq!(*result, [Value::I32(1)]);
+    assert!(start.elapsed() < Duration::from_secs(1));
+}
+
+const LOOPING_START: &str = r#"
+(module
+    (global $started (export "started") (mut i32) (i32.const 0))
+
+    (func $spin
+        (loop $again (br $again)))
+
+    (func $init
+        (global.set $started (i32.const 1))
+        (call $spin))
+
+    (start $init)
+)
+"#;
+
+#[test]
+fn looping_start_functions_time_out() {
+    let mut store = Store::default();
+    let module = Module::new(&store, LOOPING_START).unwrap();
+    let options = InstantiationOptions::new().start_timeout(Duration::from_millis(100));
+
+    let error =
+        Instance::new_with_options(&mut store, &module, &imports! {}, &options).unwrap_err();
+
+    let (timeout, trace) = match error {
+        InstantiationError::StartTimeout { timeout, trace } => (timeout, trace),
+        other => panic!("{other:?}"),
+    };
+    assert_eq!(timeout, Duration::from_millis(100));
+    let names: Vec<_> = trace.iter().map(|frame| frame.function_name()).collect();
+    assert!(
+        names.starts_with(&[Some("spin"), Some("init")]),
+        "{trace:?}"
+    );
+}
+
+#[test]
+fn the_start_function_can_be_skipped() {
+    let mut store = Store::default();
+    let module = Module::new(&store, LOOPING_START).unwrap();
+
+    let start = Instant::now();
+    let unstarted = Instance::instantiate_without_start(&mut store, &module, &imports! {}).unwrap();
+    assert!(start.elapsed() < Duration::from_secs(1));
+    assert!(unstarted.has_start_function());
+
+    // Nothing has run yet
+    let started = unstarted
+        .instance()
+        .exports
+        .get_global("started")
+        .unwrap()
+        .clone();
+    assert_eq!(started.get(&mut store), Value::I32(0));
+
+    // Until the start function is run explicitly
+    let error = unstarted
+        .start_with_deadline(&mut store, Duration::from_millis(50))
+        .unwrap_err();
+    assert!(matches!(error, InstantiationError::StartTimeout { .. }));
+    assert_eq!(started.get(&mut store), Value::I32(1));
+}
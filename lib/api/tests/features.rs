@@ -0,0 +1,211 @@
+#![cfg(feature = "cranelift")]
+
+use wasmer::{
+    sys::{Cranelift, EngineBuilder},
+    *,
+};
+
+/// A module that only uses the given proposal, along with the name of that
+/// proposal's feature.
+const FIXTURES: &[(&str, &str)] = &[
+    (
+        "threads",
+        r#"(module
+            (memory 1 1 shared)
+            (func (param i32) (result i32) (i32.atomic.load (local.get 0))))"#,
+    ),
+    (
+        "simd",
+        r#"(module
+            (func (result i32) (i32x4.extract_lane 0 (v128.const i32x4 1 2 3 4))))"#,
+    ),
+    (
+        "bulk-memory",
+        r#"(module
+            (memory 1)
+            (func (memory.fill (i32.const 0) (i32.const 0) (i32.const 0))))"#,
+    ),
+    (
+        "reference-types",
+        r#"(module
+            (func (result externref) (ref.null extern)))"#,
+    ),
+    (
+        "multi-value",
+        r#"(module
+            (func (result i32 i32) (i32.const 1) (i32.const 2)))"#,
+    ),
+    (
+        "tail-call",
+        r#"(module
+            (func $f (return_call $f)))"#,
+    ),
+    (
+        "multi-memory",
+        r#"(module
+            (memory 1)
+            (memory 1))"#,
+    ),
+    (
+        "memory64",
+        r#"(module
+            (memory i64 1))"#,
+    ),
+    (
+        "extended-const",
+        r#"(module
+            (global i32 (i32.add (i32.const 1) (i32.const 2))))"#,
+    ),
+];
+
+const MVP: &str = r#"(module
+    (memory 1)
+    (func (export "add") (param i32 i32) (result i32)
+        (i32.add (local.get 0) (local.get 1))))"#;
+
+fn engine(features: Features) -> Engine {
+    EngineBuilder::new(Cranelift::default())
+        .set_features(Some(features))
+        .engine()
+        .into()
+}
+
+fn all_except(name: &str) -> Features {
+    let mut features = Features::all();
+    match name {
+        "threads" => features.threads = false,
+        "simd" => features.simd = false,
+        "bulk-memory" => features.bulk_memory = false,
+        "reference-types" => features.reference_types = false,
+        "multi-value" => features.multi_value = false,
+        "tail-call" => features.tail_call = false,
+        "multi-memory" => features.multi_memory = false,
+        "memory64" => features.memory64 = false,
+        "extended-const" => features.extended_const = false,
+        other => panic!("unknown feature: {other}"),
+    }
+    features
+}
+
+fn only(name: &str) -> Features {
+    let mut features = Features::none();
+    match name {
+        "threads" => features.threads = true,
+        "simd" => features.simd = true,
+        "bulk-memory" => features.bulk_memory = true,
+        "reference-types" => features.reference_types = true,
+        "multi-value" => features.multi_value = true,
+        "tail-call" => features.tail_call = true,
+        "multi-memory" => features.multi_memory = true,
+        "memory64" => features.memory64 = true,
+        "extended-const" => features.extended_const = true,
+        other => panic!("unknown feature: {other}"),
+    }
+    features
+}
+
+#[test]
+fn mvp_module_requires_no_features() {
+    let module = Module::new(&engine(Features::none()), MVP).unwrap();
+    assert_eq!(module.required_features(), Features::none());
+}
+
+#[test]
+fn required_features_are_detected() {
+    let engine = engine(Features::all());
+
+    // The compiler doesn't support these yet
+    let unsupported = ["tail-call", "memory64"];
+
+    for (name, wat) in FIXTURES
+        .iter()
+        .filter(|(name, _)| !unsupported.contains(name))
+    {
+        let module = Module::new(&engine, wat).unwrap_or_else(|e| panic!("{name}: {e}"));
+        let required: Vec<_> = module.required_features().enabled().collect();
+        assert_eq!(required, [*name], "{wat}");
+    }
+}
+
+#[test]
+fn required_features_survive_serialization() {
+    let engine = engine(Features::all());
+    let (_, wat) = FIXTURES.iter().find(|(name, _)| *name == "simd").unwrap();
+    let module = Module::new(&engine, wat).unwrap();
+
+    let bytes = module.serialize().unwrap();
+    let module = unsafe { Module::deserialize(&engine, bytes).unwrap() };
+    assert_eq!(module.required_features(), only("simd"));
+}
+
+#[test]
+fn engine_rejects_modules_using_disabled_features() {
+    for (name, wat) in FIXTURES {
+        let err = Module::new(&engine(all_except(name)), wat).unwrap_err();
+        assert!(matches!(err, CompileError::Validate(_)), "{name}: {err:?}");
+    }
+}
+
+#[test]
+fn store_rejects_compiling_modules_using_disabled_features() {
+    for (name, wat) in FIXTURES {
+        let mut store = Store::new(engine(Features::all()));
+        store.set_disabled_features(only(name));
+
+        let err = Module::new(&store, wat).unwrap_err();
+        match err {
+            CompileError::DisabledFeature(feature) => assert_eq!(feature, *name),
+            other => panic!("{name}: unexpected error: {other:?}"),
+        }
+
+        // Other modules are unaffected
+        Module::new(&store, MVP).unwrap();
+    }
+}
+
+#[test]
+fn store_rejects_instantiating_modules_using_disabled_features() {
+    let engine = engine(Features::all());
+    let (_, wat) = FIXTURES.iter().find(|(name, _)| *name == "simd").unwrap();
+    // Compiling against the engine doesn't know about the store's
+    // restrictions
+    let module = Module::new(&engine, wat).unwrap();
+
+    let mut store = Store::new(engine.clone());
+    Instance::new(&mut store, &module, &imports! {}).unwrap();
+
+    store.set_disabled_features(only("simd"));
+    assert_eq!(store.disabled_features(), &only("simd"));
+    let err = Instance::new(&mut store, &module, &imports! {}).unwrap_err();
+    match err {
+        InstantiationError::Link(LinkError::DisabledFeature(feature)) => {
+            assert_eq!(feature, "simd")
+        }
+        other => panic!("unexpected error: {other:?}"),
+    }
+
+    let mvp = Module::new(&engine, MVP).unwrap();
+    Instance::new(&mut store, &mvp, &imports! {}).unwrap();
+}
+
+#[test]
+fn validating_against_custom_features() {
+    // The engine's own features don't matter
+    let engine = engine(Features::none());
+    for name in ["threads", "simd", "multi-value", "reference-types"] {
+        let (_, wat) = FIXTURES.iter().find(|(n, _)| *n == name).unwrap();
+        let wasm = wat::parse_str(wat).unwrap();
+
+        Module::validate_with_features(&engine, &only(name), &wasm)
+            .unwrap_or_else(|e| panic!("{name}: {e}"));
+        let err = Module::validate_with_features(&engine, &all_except(name), &wasm).unwrap_err();
+        let message = err.to_string();
+        assert!(
+            message.contains(&format!("requires the {name} feature (at offset 0x")),
+            "{name}: {message}"
+        );
+    }
+
+    let mvp = wat::parse_str(MVP).unwrap();
+    Module::validate_with_features(&engine, &Features::none(), &mvp).unwrap();
+}
@@ -0,0 +1,86 @@
+#![cfg(all(feature = "heap-size", feature = "sys"))]
+
+use std::fmt::Write;
+
+use wasmer::*;
+
+/// A "medium" module exports 500 functions. Every instance of it should
+/// stay below this many bytes of host memory, which only leaves room for a
+/// value per export and not for a copy of the export names.
+const MAX_INSTANCE_OVERHEAD: usize = 16 * 1024;
+
+fn medium_module() -> String {
+    let mut wat = String::from("(module\n");
+    for i in 0..500 {
+        writeln!(
+            wat,
+            "  (func (export \"exported_function_{i:04}\") (result i32) (i32.const {i}))"
+        )
+        .unwrap();
+    }
+    wat.push(')');
+    wat
+}
+
+#[test]
+fn instances_share_export_names() {
+    let mut store = Store::default();
+    let module = Module::new(&store, medium_module()).unwrap();
+
+    let instances: Vec<_> = (0..10)
+        .map(|_| Instance::new(&mut store, &module, &imports! {}).unwrap())
+        .collect();
+
+    for instance in &instances {
+        assert_eq!(instance.exports.len(), 500);
+        let overhead = instance.approx_host_heap_size();
+        assert!(
+            overhead < MAX_INSTANCE_OVERHEAD,
+            "{overhead} bytes per instance"
+        );
+    }
+
+    let answer = instances[9]
+        .exports
+        .get_typed_function::<(), i32>(&store, "exported_function_0042")
+        .unwrap()
+        .call(&mut store)
+        .unwrap();
+    assert_eq!(answer, 42);
+}
+
+#[test]
+fn modified_exports_own_their_names() {
+    let mut store = Store::default();
+    let module = Module::new(&store, medium_module()).unwrap();
+    let instance = Instance::new(&mut store, &module, &imports! {}).unwrap();
+
+    let mut exports = instance.exports.clone();
+    let global = Global::new(&mut store, Value::I32(1));
+    exports.insert("extra", global);
+    assert_eq!(exports.len(), 501);
+    assert_eq!(exports.get_index("extra"), Some(500));
+    assert_eq!(instance.exports.len(), 500);
+    assert!(!instance.exports.contains("extra"));
+}
+
+#[test]
+fn only_borrowing_iterators_copy_the_exports() {
+    let mut store = Store::default();
+    let module = Module::new(&store, medium_module()).unwrap();
+    let instance = Instance::new(&mut store, &module, &imports! {}).unwrap();
+
+    assert_eq!(instance.exports.iter().count(), 500);
+    assert!(instance.approx_host_heap_size() < MAX_INSTANCE_OVERHEAD);
+    // `&Exports` iterates over a map of its own, built the first time
+    assert_eq!((&instance.exports).into_iter().count(), 500);
+    assert!(instance.approx_host_heap_size() >= MAX_INSTANCE_OVERHEAD);
+
+    // Which doesn't go stale
+    let mut exports = instance.exports.clone();
+    let global = Global::new(&mut store, Value::I32(1));
+    exports.insert("extra", global);
+    let (last, _) = (&exports).into_iter().last().unwrap();
+    assert_eq!(last, "extra");
+    assert_eq!(exports.into_iter().count(), 501);
+}
@@ -0,0 +1,152 @@
+#![cfg(feature = "sys")]
+
+use wasmer::*;
+
+/// Imports a memory with the limits `limits` and exports `grow(delta)`,
+/// which returns what `memory.grow` does.
+fn memory_importer(store: &Store, limits: &str) -> Module {
+    let wat = format!(
+        r#"(module
+            (import "env" "memory" (memory {limits}))
+            (func (export "grow") (param $delta i32) (result i32)
+                (memory.grow (local.get $delta))))"#
+    );
+    Module::new(store, wat).unwrap()
+}
+
+/// Imports a table with the type `ty`.
+fn table_importer(store: &Store, ty: &str) -> Module {
+    let wat = format!(r#"(module (import "env" "table" (table {ty})))"#);
+    Module::new(store, wat).unwrap()
+}
+
+/// Instantiates `module` with `ext` as its only import, matching limits
+/// strictly and relaxed.
+fn instantiate(
+    store: &mut Store,
+    module: &Module,
+    name: &str,
+    ext: impl Into<Extern>,
+) -> (Result<(), String>, Result<(), String>) {
+    let ext: Extern = ext.into();
+    let imports = imports! { "env" => { name => ext } };
+    let strict = Instance::new(store, module, &imports)
+        .map(drop)
+        .map_err(|err| err.to_string());
+    let relaxed = Instance::new(store, module, &imports.with_relaxed_limits())
+        .map(drop)
+        .map_err(|err| err.to_string());
+    (strict, relaxed)
+}
+
+#[test]
+fn memory_import_limits() {
+    // Provided limits, declared limits, and whether they match strictly
+    // and relaxed
+    let cases = [
+        ((1, None), "1", true, true),
+        ((2, Some(3)), "1", true, true),
+        ((1, None), "2", false, false),
+        ((1, Some(100)), "1 100", true, true),
+        ((1, Some(50)), "1 100", true, true),
+        ((1, Some(200)), "1 100", false, true),
+        ((1, None), "1 100", false, true),
+        ((1, Some(100)), "2 100", false, false),
+    ];
+
+    for ((min, max), declared, strict, relaxed) in cases {
+        let mut store = Store::default();
+        let module = memory_importer(&store, declared);
+        let memory = Memory::new(&mut store, MemoryType::new(min, max, false)).unwrap();
+        let (strict_result, relaxed_result) = instantiate(&mut store, &module, "memory", memory);
+        assert_eq!(
+            strict_result.is_ok(),
+            strict,
+            "strict: ({min}, {max:?}) for ({declared}): {strict_result:?}"
+        );
+        assert_eq!(
+            relaxed_result.is_ok(),
+            relaxed,
+            "relaxed: ({min}, {max:?}) for ({declared}): {relaxed_result:?}"
+        );
+    }
+}
+
+#[test]
+fn table_import_limits() {
+    let cases = [
+        ((1, None), "1 funcref", true, true),
+        ((1, None), "2 funcref", false, false),
+        ((1, Some(10)), "1 10 funcref", true, true),
+        ((1, Some(5)), "1 10 funcref", true, true),
+        ((1, Some(20)), "1 10 funcref", false, true),
+        ((1, None), "1 10 funcref", false, true),
+        ((1, None), "1 externref", false, false),
+    ];
+
+    for ((min, max), declared, strict, relaxed) in cases {
+        let mut store = Store::default();
+        let module = table_importer(&store, declared);
+        let table = Table::new(
+            &mut store,
+            TableType::new(Type::FuncRef, min, max),
+            Value::FuncRef(None),
+        )
+        .unwrap();
+        let (strict_result, relaxed_result) = instantiate(&mut store, &module, "table", table);
+        assert_eq!(
+            strict_result.is_ok(),
+            strict,
+            "strict: ({min}, {max:?}) for ({declared}): {strict_result:?}"
+        );
+        assert_eq!(
+            relaxed_result.is_ok(),
+            relaxed,
+            "relaxed: ({min}, {max:?}) for ({declared}): {relaxed_result:?}"
+        );
+    }
+}
+
+#[test]
+fn relaxed_memories_grow_past_the_declared_maximum() {
+    let mut store = Store::default();
+    let module = memory_importer(&store, "1 100");
+    let memory = Memory::new(&mut store, MemoryType::new(1, None, false)).unwrap();
+    let imports = imports! { "env" => { "memory" => memory.clone() } }.with_relaxed_limits();
+    let instance = Instance::new(&mut store, &module, &imports).unwrap();
+
+    let grow = instance.exports.get_function("grow").unwrap();
+    let previous = grow.call(&mut store, &[Value::I32(150)]).unwrap()[0].unwrap_i32();
+    assert_eq!(previous, 1);
+    assert_eq!(memory.view(&store).size(), Pages(151));
+}
+
+#[test]
+fn errors_show_both_limits() {
+    let mut store = Store::default();
+    let module = memory_importer(&store, "1 100");
+    let memory = Memory::new(&mut store, MemoryType::new(1, None, false)).unwrap();
+    let (strict, _) = instantiate(&mut store, &module, "memory", memory);
+    let err = strict.unwrap_err();
+    assert!(
+        err.contains("Expected memory not shared (1 pages..100 pages)")
+            && err.contains("received memory not shared (1 pages..)"),
+        "{err}"
+    );
+
+    // Tables are reported with their current size
+    let module = table_importer(&store, "3 funcref");
+    let table = Table::new(
+        &mut store,
+        TableType::new(Type::FuncRef, 1, Some(5)),
+        Value::FuncRef(None),
+    )
+    .unwrap();
+    table.grow(&mut store, 1, Value::FuncRef(None)).unwrap();
+    let (strict, _) = instantiate(&mut store, &module, "table", table);
+    let err = strict.unwrap_err();
+    assert!(
+        err.contains("Expected table FuncRef (3..) but received table FuncRef (2..5)"),
+        "{err}"
+    );
+}
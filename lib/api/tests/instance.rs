@@ -82,3 +82,168 @@ fn unit_native_function_env() -> Result<(), String> {
 
     Ok(())
 }
+
+#[universal_test]
+fn exports_get_by_index() -> Result<(), String> {
+    let mut store = Store::default();
+    let module = Module::new(
+        &store,
+        r#"
+(module
+  (memory (export "memory") 1)
+  (func (export "answer") (result i32) i32.const 42))
+"#,
+    )
+    .map_err(|e| format!("{e:?}"))?;
+    let instance =
+        Instance::new(&mut store, &module, &imports! {}).map_err(|e| format!("{e:?}"))?;
+
+    assert_eq!(instance.exports.get_index("missing"), None);
+    let index = instance.exports.get_index("answer").unwrap();
+    let (name, export) = instance.exports.get_by_index(index).unwrap();
+    assert_eq!(name, "answer");
+    assert!(matches!(export, Extern::Function(_)));
+    assert!(instance
+        .exports
+        .get_by_index(instance.exports.len())
+        .is_none());
+
+    Ok(())
+}
+
+#[universal_test]
+fn cached_typed_function_handles() -> Result<(), String> {
+    let mut store = Store::default();
+    let module = Module::new(
+        &store,
+        r#"
+(module
+  (memory (export "memory") 1)
+  (func (export "add") (param i32 i32) (result i32)
+    local.get 0
+    local.get 1
+    i32.add))
+"#,
+    )
+    .map_err(|e| format!("{e:?}"))?;
+    let instance =
+        Instance::new(&mut store, &module, &imports! {}).map_err(|e| format!("{e:?}"))?;
+    let exports = instance.cached_exports();
+
+    let add = exports
+        .typed_function_handle::<(i32, i32), i32>(&store, "add")
+        .map_err(|e| format!("{e:?}"))?;
+    // Handles are Copy, so they can be used over and over
+    for i in 0..3 {
+        let result = exports
+            .typed_function(&store, add)
+            .call(&mut store, i, 2)
+            .map_err(|e| format!("{e:?}"))?;
+        assert_eq!(result, i + 2);
+    }
+
+    // Mistakes are caught when the handle is created, not when it's used
+    assert!(matches!(
+        exports.typed_function_handle::<i64, i32>(&store, "add"),
+        Err(ExportError::IncompatibleType)
+    ));
+    assert!(matches!(
+        exports.typed_function_handle::<(), ()>(&store, "memory"),
+        Err(ExportError::IncompatibleType)
+    ));
+    assert!(matches!(
+        exports.typed_function_handle::<(), ()>(&store, "missing"),
+        Err(ExportError::Missing(_))
+    ));
+
+    Ok(())
+}
+
+#[universal_test]
+fn instantiate_with_resolver_fn() -> Result<(), String> {
+    let mut store = Store::default();
+    let parent = Module::new(&store, r#"(module (memory (export "memory") 1))"#)
+        .map_err(|e| format!("{e:?}"))?;
+    let parent = Instance::new(&mut store, &parent, &imports! {}).map_err(|e| format!("{e:?}"))?;
+
+    let module = Module::new(
+        &store,
+        r#"(module
+            (import "env" "memory" (memory 1))
+            (import "env" "answer" (global i32))
+            (func (export "load") (result i32) (i32.load (global.get 0))))"#,
+    )
+    .map_err(|e| format!("{e:?}"))?;
+    let answer = Global::new(&mut store, Value::I32(8));
+
+    let mut requested = Vec::new();
+    let instance = Instance::new_with_resolver_fn(&mut store, &module, |module, name, ty| {
+        requested.push((module.to_string(), name.to_string(), ty.clone()));
+        match name {
+            "memory" => parent.exports.get_extern("memory").cloned(),
+            "answer" => Some(Extern::Global(answer.clone())),
+            _ => None,
+        }
+    })
+    .map_err(|e| format!("{e:?}"))?;
+
+    let names: Vec<_> = requested
+        .iter()
+        .map(|(module, name, _)| format!("{module}.{name}"))
+        .collect();
+    assert_eq!(names, ["env.memory", "env.answer"]);
+    assert!(matches!(requested[0].2, ExternType::Memory(_)));
+    assert!(matches!(requested[1].2, ExternType::Global(_)));
+
+    let memory = parent
+        .exports
+        .get_memory("memory")
+        .map_err(|e| format!("{e:?}"))?;
+    memory
+        .view(&store)
+        .write(8, &42_i32.to_le_bytes())
+        .map_err(|e| format!("{e:?}"))?;
+    let load = instance
+        .exports
+        .get_function("load")
+        .map_err(|e| format!("{e:?}"))?;
+    assert_eq!(
+        load.call(&mut store, &[]).map_err(|e| format!("{e:?}"))?[0],
+        Value::I32(42)
+    );
+
+    Ok(())
+}
+
+#[universal_test]
+fn instantiate_with_resolver_fn_reports_missing_imports() -> Result<(), String> {
+    let mut store = Store::default();
+    let module = Module::new(
+        &store,
+        r#"(module
+            (import "env" "first" (global i32))
+            (import "env" "second" (global i32)))"#,
+    )
+    .map_err(|e| format!("{e:?}"))?;
+
+    let mut calls = 0;
+    let err = Instance::new_with_resolver_fn(&mut store, &module, |_, _, _| {
+        calls += 1;
+        None
+    })
+    .unwrap_err();
+
+    assert_eq!(calls, 1);
+    match err {
+        InstantiationError::Link(LinkError::Import(
+            module,
+            name,
+            wasmer_types::ImportError::UnknownImport(_),
+        )) => {
+            assert_eq!((module.as_str(), name.as_str()), ("env", "first"));
+        }
+        other => panic!("unexpected error: {other:?}"),
+    }
+
+    Ok(())
+}
@@ -0,0 +1,301 @@
+use macro_wasmer_universal_test::universal_test;
+#[cfg(feature = "js")]
+use wasm_bindgen_test::*;
+
+use wasmer::{
+    interface::{
+        check_module_against_manifest, ImportManifest, Incompatibility, IncompatibilityReport,
+        InterfaceEntry, ManifestImport, ModuleInterface, PositionalChange, TypeChange,
+    },
+    *,
+};
+
+const PLUGIN_V1: &str = r#"(module
+  (import "host" "log" (func (param i32 i32)))
+  (import "host" "abort" (func))
+  (memory (export "memory") 1)
+  (global (export "version") i32 (i32.const 1))
+  (func (export "add") (param i32 i32) (result i32) (local.get 0))
+  (func (export "init")))"#;
+
+const PLUGIN_V2: &str = r#"(module
+  (import "host" "log" (func (param i32 i32 i32)))
+  (import "host" "now" (func (result i64)))
+  (memory (export "memory") 1)
+  (global (export "version") i32 (i32.const 2))
+  (func (export "add") (param i64 i64) (result i64) (local.get 0))
+  (table (export "init") 1 funcref))"#;
+
+fn interface(store: &Store, wat: &str) -> Result<ModuleInterface, String> {
+    let module = Module::new(store, wat).map_err(|e| format!("{e:?}"))?;
+    Ok(ModuleInterface::from_module(&module))
+}
+
+fn import(module: &str, name: &str) -> InterfaceEntry {
+    InterfaceEntry::Import {
+        module: module.to_string(),
+        name: name.to_string(),
+    }
+}
+
+fn export(name: &str) -> InterfaceEntry {
+    InterfaceEntry::Export {
+        name: name.to_string(),
+    }
+}
+
+#[universal_test]
+fn interface_captures_imports_and_exports() -> Result<(), String> {
+    let store = Store::default();
+    let interface = interface(&store, PLUGIN_V1)?;
+
+    assert_eq!(interface.entries.len(), 6);
+    assert_eq!(
+        interface.get(&import("host", "log")),
+        Some(&ExternType::Function(FunctionType::new(
+            vec![Type::I32, Type::I32],
+            vec![]
+        )))
+    );
+    assert_eq!(
+        interface.get(&export("memory")),
+        Some(&ExternType::Memory(MemoryType::new(Pages(1), None, false)))
+    );
+
+    Ok(())
+}
+
+#[universal_test]
+fn identical_interfaces_have_no_diff() -> Result<(), String> {
+    let store = Store::default();
+    let v1 = interface(&store, PLUGIN_V1)?;
+
+    let diff = v1.diff(&v1.clone());
+
+    assert!(diff.is_empty());
+
+    Ok(())
+}
+
+#[universal_test]
+fn diff_categorizes_changes() -> Result<(), String> {
+    let store = Store::default();
+    let v1 = interface(&store, PLUGIN_V1)?;
+    let v2 = interface(&store, PLUGIN_V2)?;
+
+    let diff = v1.diff(&v2);
+
+    assert_eq!(
+        diff.added,
+        vec![(
+            import("host", "now"),
+            ExternType::Function(FunctionType::new(vec![], vec![Type::I64]))
+        )]
+    );
+    assert_eq!(
+        diff.removed,
+        vec![(
+            import("host", "abort"),
+            ExternType::Function(FunctionType::new(vec![], vec![]))
+        )]
+    );
+
+    let changed: Vec<_> = diff.changed.iter().map(|c| c.entry.clone()).collect();
+    assert_eq!(
+        changed,
+        vec![import("host", "log"), export("add"), export("init")]
+    );
+
+    // An extra parameter was appended
+    assert_eq!(
+        diff.changed[0].change(),
+        TypeChange::Function {
+            params: vec![PositionalChange {
+                index: 2,
+                old: None,
+                new: Some(Type::I32),
+            }],
+            results: vec![],
+        }
+    );
+    // Every parameter and result was widened
+    let TypeChange::Function { params, results } = diff.changed[1].change() else {
+        return Err("expected a function change".to_string());
+    };
+    assert_eq!(params.len(), 2);
+    assert_eq!(
+        results,
+        vec![PositionalChange {
+            index: 0,
+            old: Some(Type::I32),
+            new: Some(Type::I64),
+        }]
+    );
+    // A function became a table
+    assert_eq!(diff.changed[2].change(), TypeChange::Kind);
+
+    let rendered = diff.to_string();
+    assert!(rendered.contains("+ import \"host\".\"now\": function [] -> [I64]"));
+    assert!(rendered.contains("- import \"host\".\"abort\""));
+    assert!(rendered.contains("parameter 0: I32 -> I64"));
+
+    Ok(())
+}
+
+/// What a host gives plugins: `log` and `now` functions, and a memory.
+fn host_manifest(store: &mut Store) -> ImportManifest {
+    let imports = imports! {
+        "host" => {
+            "log" => Function::new_typed(store, |_ptr: i32, _len: i32| {}),
+            "now" => Function::new_typed(store, || 0i64),
+            "memory" => Memory::new(store, MemoryType::new(1, None, false)).unwrap(),
+        },
+    };
+    imports.manifest(store)
+}
+
+fn check(
+    store: &Store,
+    wat: &str,
+    manifest: &ImportManifest,
+) -> Result<Vec<IncompatibilityReport>, String> {
+    let module = Module::new(store, wat).map_err(|e| format!("{e:?}"))?;
+    Ok(check_module_against_manifest(&module, manifest))
+}
+
+#[universal_test]
+fn manifest_lists_the_provided_imports() -> Result<(), String> {
+    let mut store = Store::default();
+    let manifest = host_manifest(&mut store);
+
+    assert_eq!(manifest.version, ImportManifest::VERSION);
+    let names: Vec<_> = manifest.imports.iter().map(|i| i.name.as_str()).collect();
+    assert_eq!(names, ["log", "memory", "now"]);
+    assert_eq!(
+        manifest.get("host", "log"),
+        Some(&ExternType::Function(FunctionType::new(
+            vec![Type::I32, Type::I32],
+            vec![]
+        )))
+    );
+
+    Ok(())
+}
+
+#[universal_test]
+fn compatible_modules_only_report_unused_imports() -> Result<(), String> {
+    let mut store = Store::default();
+    let manifest = host_manifest(&mut store);
+
+    let reports = check(
+        &store,
+        r#"(module (import "host" "log" (func (param i32 i32))))"#,
+        &manifest,
+    )?;
+
+    assert!(reports.iter().all(|r| !r.is_error()), "{reports:?}");
+    let unused: Vec<_> = reports.iter().map(|r| r.name.as_str()).collect();
+    assert_eq!(unused, ["memory", "now"]);
+    assert_eq!(
+        reports[0].to_string(),
+        "import \"host\".\"memory\" is provided by the host, but not imported"
+    );
+
+    Ok(())
+}
+
+#[universal_test]
+fn missing_imports_suggest_near_misses() -> Result<(), String> {
+    let mut store = Store::default();
+    let manifest = host_manifest(&mut store);
+
+    let reports = check(
+        &store,
+        r#"(module
+  (import "host" "lgo" (func (param i32 i32)))
+  (import "hots" "now" (func (result i64)))
+  (import "host" "random" (func (result i32))))"#,
+        &manifest,
+    )?;
+
+    let missing: Vec<_> = reports
+        .iter()
+        .filter_map(|r| match &r.kind {
+            Incompatibility::Missing { suggestion } => Some((r.name.as_str(), suggestion.clone())),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(
+        missing,
+        [
+            ("lgo", Some(("host".to_string(), "log".to_string()))),
+            ("now", Some(("host".to_string(), "now".to_string()))),
+            ("random", None),
+        ]
+    );
+    assert_eq!(
+        reports[0].to_string(),
+        "import \"host\".\"lgo\" is not provided by the host (did you mean \"host\".\"log\"?)"
+    );
+
+    Ok(())
+}
+
+#[universal_test]
+fn signature_mismatches_are_reported() -> Result<(), String> {
+    let mut store = Store::default();
+    let manifest = host_manifest(&mut store);
+
+    let reports = check(
+        &store,
+        r#"(module
+  (import "host" "log" (func (param i32 i32 i32)))
+  (import "host" "now" (global i64))
+  (import "host" "memory" (memory 1)))"#,
+        &manifest,
+    )?;
+
+    let errors: Vec<_> = reports.iter().filter(|r| r.is_error()).collect();
+    assert_eq!(errors.len(), 2, "{reports:?}");
+    assert_eq!(
+        errors[0].kind,
+        Incompatibility::TypeMismatch {
+            expected: ExternType::Function(FunctionType::new(
+                vec![Type::I32, Type::I32, Type::I32],
+                vec![]
+            )),
+            provided: ExternType::Function(FunctionType::new(vec![Type::I32, Type::I32], vec![])),
+        }
+    );
+    assert!(errors[0].to_string().contains("parameter 2 added: I32"));
+    assert_eq!(errors[1].name, "now");
+    assert!(matches!(
+        errors[1].kind,
+        Incompatibility::TypeMismatch {
+            expected: ExternType::Global(_),
+            provided: ExternType::Function(_),
+        }
+    ));
+
+    Ok(())
+}
+
+#[universal_test]
+fn manifests_can_be_built_by_hand() -> Result<(), String> {
+    let store = Store::default();
+    let manifest = ImportManifest::new([ManifestImport {
+        module: "env".to_string(),
+        name: "abort".to_string(),
+        ty: ExternType::Function(FunctionType::new(vec![], vec![])),
+    }]);
+
+    let reports = check(
+        &store,
+        r#"(module (import "env" "abort" (func)))"#,
+        &manifest,
+    )?;
+
+    assert!(reports.is_empty(), "{reports:?}");
+
+    Ok(())
+}
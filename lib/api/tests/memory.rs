@@ -142,3 +142,300 @@ fn test_wasm_memory_size() {
         assert_eq!(memory.size(&store).0, 11);
     }
 }
+
+/// Tunables that make compiled code check every memory access, which is
+/// required to import a memory backed by a host buffer.
+#[cfg(feature = "sys")]
+struct ExternalMemoryTunables(wasmer::sys::BaseTunables);
+
+#[cfg(feature = "sys")]
+impl wasmer::sys::Tunables for ExternalMemoryTunables {
+    fn memory_style(&self, _memory: &MemoryType) -> wasmer::MemoryStyle {
+        wasmer::MemoryStyle::External
+    }
+
+    fn table_style(&self, table: &wasmer::TableType) -> wasmer::TableStyle {
+        self.0.table_style(table)
+    }
+
+    fn create_host_memory(
+        &self,
+        ty: &MemoryType,
+        style: &wasmer::MemoryStyle,
+    ) -> Result<wasmer::sys::vm::VMMemory, wasmer::MemoryError> {
+        self.0.create_host_memory(ty, style)
+    }
+
+    unsafe fn create_vm_memory(
+        &self,
+        ty: &MemoryType,
+        style: &wasmer::MemoryStyle,
+        vm_definition_location: std::ptr::NonNull<wasmer::sys::vm::VMMemoryDefinition>,
+    ) -> Result<wasmer::sys::vm::VMMemory, wasmer::MemoryError> {
+        self.0.create_vm_memory(ty, style, vm_definition_location)
+    }
+
+    fn create_host_table(
+        &self,
+        ty: &wasmer::TableType,
+        style: &wasmer::TableStyle,
+    ) -> Result<wasmer::sys::vm::VMTable, String> {
+        self.0.create_host_table(ty, style)
+    }
+
+    unsafe fn create_vm_table(
+        &self,
+        ty: &wasmer::TableType,
+        style: &wasmer::TableStyle,
+        vm_definition_location: std::ptr::NonNull<wasmer::sys::vm::VMTableDefinition>,
+    ) -> Result<wasmer::sys::vm::VMTable, String> {
+        self.0.create_vm_table(ty, style, vm_definition_location)
+    }
+}
+
+#[cfg(feature = "sys")]
+fn external_memory_store() -> Store {
+    use wasmer::sys::NativeEngineExt;
+
+    let mut engine = wasmer::Engine::default();
+    engine.set_tunables(ExternalMemoryTunables(
+        wasmer::sys::BaseTunables::for_target(engine.target()),
+    ));
+    Store::new(engine)
+}
+
+#[cfg(feature = "sys")]
+const EXTERNAL_MEMORY_WAT: &str = r#"(module
+    (import "host" "memory" (memory 2))
+    (func (export "load") (param i32) (result i32)
+        local.get 0
+        i32.load8_u)
+    (func (export "store") (param i32 i32)
+        local.get 0
+        local.get 1
+        i32.store8))"#;
+
+#[test]
+#[cfg(feature = "sys")]
+fn test_memory_from_mmap_is_not_copied() -> anyhow::Result<()> {
+    use std::io::Write;
+    use wasmer::{MemoryGrowthPolicy, TypedFunction, WASM_PAGE_SIZE};
+
+    let mut file = tempfile::tempfile()?;
+    file.write_all(b"hello from the host")?;
+    file.set_len(2 * WASM_PAGE_SIZE as u64)?;
+    let mmap = unsafe { memmap2::MmapOptions::new().map_copy(&file)? };
+    let host_ptr = mmap.as_ptr();
+
+    let mut store = external_memory_store();
+    let memory = Memory::new_from_owned_buffer(
+        &mut store,
+        MemoryType::new(2, None, false),
+        mmap,
+        MemoryGrowthPolicy::Disabled,
+    )?;
+    assert_eq!(memory.view(&store).data_ptr() as *const u8, host_ptr);
+
+    let module = Module::new(&store, EXTERNAL_MEMORY_WAT)?;
+    let imports = imports! {
+        "host" => {
+            "memory" => memory.clone(),
+        },
+    };
+    let instance = Instance::new(&mut store, &module, &imports)?;
+    let load: TypedFunction<i32, i32> = instance.exports.get_typed_function(&store, "load")?;
+    let store_byte: TypedFunction<(i32, i32), ()> =
+        instance.exports.get_typed_function(&store, "store")?;
+
+    assert_eq!(load.call(&mut store, 0)?, i32::from(b'h'));
+    assert_eq!(load.call(&mut store, 11)?, i32::from(b't'));
+    // There are no guard pages, so accesses past the end must still trap.
+    assert!(load.call(&mut store, 2 * WASM_PAGE_SIZE as i32).is_err());
+
+    // Writes from the guest land in the host buffer.
+    store_byte.call(&mut store, 0, i32::from(b'j'))?;
+    assert_eq!(unsafe { *host_ptr }, b'j');
+    assert_eq!(memory.view(&store).data_ptr() as *const u8, host_ptr);
+
+    assert!(memory.grow(&mut store, 1).is_err());
+
+    Ok(())
+}
+
+#[test]
+#[cfg(feature = "sys")]
+fn test_memory_from_host_buffer_remaps_on_grow() -> anyhow::Result<()> {
+    use wasmer::{MemoryGrowthPolicy, Pages, WASM_PAGE_SIZE};
+
+    let mut buffer = memmap2::MmapMut::map_anon(2 * WASM_PAGE_SIZE)?;
+    buffer[..5].copy_from_slice(b"hello");
+
+    let mut store = external_memory_store();
+    let memory = unsafe {
+        Memory::new_from_host_buffer(
+            &mut store,
+            MemoryType::new(2, Some(4), false),
+            buffer.as_mut_ptr(),
+            buffer.len(),
+            MemoryGrowthPolicy::Remap,
+        )?
+    };
+    assert_eq!(memory.view(&store).data_ptr(), buffer.as_mut_ptr());
+
+    assert_eq!(memory.grow(&mut store, 1)?, Pages(2));
+    let view = memory.view(&store);
+    assert_eq!(view.size(), Pages(3));
+    assert_ne!(view.data_ptr(), buffer.as_mut_ptr());
+    let mut data = [0; 5];
+    view.read(0, &mut data)?;
+    assert_eq!(&data, b"hello");
+
+    // Later grows happen in place instead of copying the memory again
+    let remapped = view.data_ptr();
+    assert_eq!(memory.grow(&mut store, 1)?, Pages(3));
+    assert_eq!(memory.view(&store).data_ptr(), remapped);
+    assert!(memory.grow(&mut store, 1).is_err());
+
+    // Modules compiled for memories with guard pages can't import it.
+    let mut store = Store::default();
+    let memory = Memory::new_from_owned_buffer(
+        &mut store,
+        MemoryType::new(2, None, false),
+        memmap2::MmapMut::map_anon(2 * WASM_PAGE_SIZE)?,
+        MemoryGrowthPolicy::Disabled,
+    )?;
+    let module = Module::new(&store, EXTERNAL_MEMORY_WAT)?;
+    let imports = imports! {
+        "host" => {
+            "memory" => memory,
+        },
+    };
+    let err = Instance::new(&mut store, &module, &imports).unwrap_err();
+    assert!(
+        matches!(err, wasmer::InstantiationError::Link(_)),
+        "{err:?}"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_memory_copy_to_bounds() {
+    use wasmer::WASM_PAGE_SIZE;
+
+    let mut store = Store::default();
+    let src = Memory::new(&mut store, MemoryType::new(1, None, false)).unwrap();
+    let dst = Memory::new(&mut store, MemoryType::new(2, None, false)).unwrap();
+    let page = WASM_PAGE_SIZE as u64;
+    src.view(&store).write(page - 4, b"tail").unwrap();
+
+    // Copying right up to the end of both memories is fine.
+    src.copy_to(&store, page - 4, &dst, 2 * page - 4, 4)
+        .unwrap();
+    let mut buf = [0; 4];
+    dst.view(&store).read(2 * page - 4, &mut buf).unwrap();
+    assert_eq!(&buf, b"tail");
+    src.copy_to(&store, page, &dst, 0, 0).unwrap();
+
+    // ... but not one byte further.
+    assert!(matches!(
+        src.copy_to(&store, page - 4, &dst, 0, 5),
+        Err(MemoryAccessError::HeapOutOfBounds)
+    ));
+    assert!(matches!(
+        src.copy_to(&store, 0, &dst, 2 * page - 4, 5),
+        Err(MemoryAccessError::HeapOutOfBounds)
+    ));
+    assert!(matches!(
+        src.copy_to(&store, u64::MAX, &dst, 0, 1),
+        Err(MemoryAccessError::Overflow)
+    ));
+}
+
+#[test]
+fn test_memory_copy_to_same_memory_overlap() {
+    let mut store = Store::default();
+    let memory = Memory::new(&mut store, MemoryType::new(1, None, false)).unwrap();
+    let reset = |store: &Store| memory.view(store).write(0, b"0123456789").unwrap();
+    let read = |store: &Store| {
+        let mut buf = [0; 10];
+        memory.view(store).read(0, &mut buf).unwrap();
+        buf
+    };
+
+    reset(&store);
+    memory.copy_to(&store, 0, &memory, 3, 6).unwrap();
+    assert_eq!(&read(&store), b"0120123459");
+
+    reset(&store);
+    memory.copy_to(&store, 3, &memory, 0, 6).unwrap();
+    assert_eq!(&read(&store), b"3456786789");
+}
+
+#[test]
+#[cfg_attr(feature = "wasmi", ignore = "wasmi does not support threads")]
+fn test_memory_copy_to_shared_memory() {
+    let mut store = Store::default();
+    let src = Memory::new(&mut store, MemoryType::new(1, Some(1), true)).unwrap();
+    let dst = Memory::new(&mut store, MemoryType::new(1, None, false)).unwrap();
+    src.view(&store).write(8, b"shared").unwrap();
+
+    src.copy_to(&store, 8, &dst, 0, 6).unwrap();
+
+    let mut buf = [0; 6];
+    dst.view(&store).read(0, &mut buf).unwrap();
+    assert_eq!(&buf, b"shared");
+}
+
+#[test]
+fn test_memory_read_write_bounds() {
+    use wasmer::WASM_PAGE_SIZE;
+
+    let mut store = Store::default();
+    let memory = Memory::new(&mut store, MemoryType::new(1, None, false)).unwrap();
+    let page = WASM_PAGE_SIZE as u64;
+
+    // Right up to the end of the memory is fine.
+    memory.write(&store, page - 4, b"tail").unwrap();
+    let mut buf = [0; 4];
+    memory.read(&store, page - 4, &mut buf).unwrap();
+    assert_eq!(&buf, b"tail");
+    memory.read(&store, page, &mut []).unwrap();
+
+    // ... but not one byte further.
+    assert!(matches!(
+        memory.read(&store, page - 4, &mut [0; 5]),
+        Err(MemoryAccessError::HeapOutOfBounds)
+    ));
+    assert!(matches!(
+        memory.write(&store, page - 4, b"tails"),
+        Err(MemoryAccessError::HeapOutOfBounds)
+    ));
+    assert!(matches!(
+        memory.read(&store, u32::MAX as u64, &mut buf),
+        Err(MemoryAccessError::HeapOutOfBounds)
+    ));
+    assert!(matches!(
+        memory.write(&store, u64::MAX - 1, b"tail"),
+        Err(MemoryAccessError::Overflow)
+    ));
+
+    // Growing the memory makes the new pages accessible.
+    memory.grow(&mut store, 1).unwrap();
+    memory.write(&store, 2 * page - 4, b"more").unwrap();
+    memory.read(&store, 2 * page - 4, &mut buf).unwrap();
+    assert_eq!(&buf, b"more");
+}
+
+#[test]
+#[cfg_attr(feature = "wasmi", ignore = "wasmi does not support threads")]
+fn test_memory_read_write_shared_memory() {
+    let mut store = Store::default();
+    let memory = Memory::new(&mut store, MemoryType::new(1, Some(2), true)).unwrap();
+
+    memory.write(&store, 8, b"shared").unwrap();
+
+    let mut buf = [0; 6];
+    memory.read(&store, 8, &mut buf).unwrap();
+    assert_eq!(&buf, b"shared");
+}
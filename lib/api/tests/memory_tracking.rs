@@ -0,0 +1,119 @@
+#![cfg(all(feature = "memory-tracking", feature = "sys"))]
+
+use wasmer::*;
+
+const PAGE: u64 = WASM_PAGE_SIZE as u64;
+
+fn full_dump(memory: &Memory, store: &Store) -> Vec<u8> {
+    memory.view(store).copy_to_vec().unwrap()
+}
+
+#[test]
+fn scattered_writes_are_dirty() {
+    let mut store = Store::default();
+    let memory = Memory::new(&mut store, MemoryType::new(16, None, false)).unwrap();
+    let mut tracker = memory.start_tracking(&store);
+
+    assert!(tracker.take_dirty_pages(&memory, &store).is_empty());
+
+    let view = memory.view(&store);
+    view.write(2 * PAGE + 10, b"two").unwrap();
+    view.write(7 * PAGE, b"seven").unwrap();
+    view.write(13 * PAGE - 1, b"x").unwrap();
+
+    assert_eq!(
+        tracker.take_dirty_pages(&memory, &store),
+        [PageIndex(2), PageIndex(7), PageIndex(12)]
+    );
+    // Taking the pages resets the tracking
+    assert!(tracker.take_dirty_pages(&memory, &store).is_empty());
+}
+
+#[test]
+fn grown_pages_are_dirty() {
+    let mut store = Store::default();
+    let memory = Memory::new(&mut store, MemoryType::new(2, None, false)).unwrap();
+    let mut tracker = memory.start_tracking(&store);
+
+    memory.grow(&mut store, 2).unwrap();
+    memory.view(&store).write(0, b"first").unwrap();
+
+    assert_eq!(
+        tracker.take_dirty_pages(&memory, &store),
+        [PageIndex(0), PageIndex(2), PageIndex(3)]
+    );
+}
+
+#[test]
+fn deltas_rebuild_the_memory() {
+    let mut store = Store::default();
+    let memory = Memory::new(&mut store, MemoryType::new(8, None, false)).unwrap();
+    memory.view(&store).write(PAGE + 1, b"initial").unwrap();
+
+    let mut image = full_dump(&memory, &store);
+    let mut tracker = memory.start_tracking(&store);
+
+    let view = memory.view(&store);
+    view.write(3 * PAGE + 100, b"three").unwrap();
+    view.write(PAGE + 1, b"changed").unwrap();
+    memory.grow(&mut store, 1).unwrap();
+    memory.view(&store).write(8 * PAGE, b"grown").unwrap();
+
+    let delta = tracker.take_delta(&memory, &store);
+    assert_eq!(delta.size, Pages(9));
+    let indices: Vec<_> = delta.pages.iter().map(|(index, _)| *index).collect();
+    assert_eq!(indices, [PageIndex(1), PageIndex(3), PageIndex(8)]);
+
+    let mut serialized = Vec::new();
+    delta.write_to(&mut serialized).unwrap();
+    assert_eq!(serialized.len(), 8 + 3 * (4 + WASM_PAGE_SIZE));
+    let delta = MemoryDelta::read_from(serialized.as_slice()).unwrap();
+
+    delta.apply(&mut image);
+    assert!(image == full_dump(&memory, &store));
+}
+
+#[test]
+fn truncated_deltas_are_rejected() {
+    let mut store = Store::default();
+    let memory = Memory::new(&mut store, MemoryType::new(4, None, false)).unwrap();
+    let mut tracker = memory.start_tracking(&store);
+    memory.view(&store).write(PAGE, b"one").unwrap();
+
+    let mut serialized = Vec::new();
+    tracker
+        .take_delta(&memory, &store)
+        .write_to(&mut serialized)
+        .unwrap();
+    serialized.pop();
+
+    assert!(MemoryDelta::read_from(serialized.as_slice()).is_err());
+}
+
+#[test]
+fn forked_memories_keep_the_baseline() {
+    let mut store = Store::default();
+    // Only shared memories can be copied to another store, like WASIX does
+    let memory = Memory::new(&mut store, MemoryType::new(4, Some(16), true)).unwrap();
+    let mut parent = memory.start_tracking(&store);
+    memory.view(&store).write(0, b"before the fork").unwrap();
+
+    let mut child_store = Store::new(store.engine().clone());
+    let child_memory = memory.copy_to_store(&store, &mut child_store).unwrap();
+    let mut child = parent.fork(&memory, &store, &child_memory, &child_store);
+
+    child_memory
+        .view(&child_store)
+        .write(2 * PAGE, b"child")
+        .unwrap();
+    memory.view(&store).write(3 * PAGE, b"parent").unwrap();
+
+    assert_eq!(
+        child.take_dirty_pages(&child_memory, &child_store),
+        [PageIndex(0), PageIndex(2)]
+    );
+    assert_eq!(
+        parent.take_dirty_pages(&memory, &store),
+        [PageIndex(0), PageIndex(3)]
+    );
+}
@@ -291,3 +291,64 @@ fn module_custom_sections() -> Result<(), String> {
     );
     Ok(())
 }
+
+#[universal_test]
+#[cfg_attr(feature = "wamr", ignore = "wamr does not support custom sections")]
+#[cfg_attr(feature = "wasmi", ignore = "wasmi does not support custom sections")]
+#[cfg_attr(feature = "v8", ignore = "v8 does not support custom sections")]
+fn module_custom_sections_with_the_same_name() -> Result<(), String> {
+    let store = Store::default();
+    let wat = r#"(module
+        (@custom "meta" "first")
+        (@custom "other" "unrelated")
+        (@custom "meta" "second")
+    )"#;
+    let module = Module::new(&store, wat).map_err(|e| format!("{e:?}"))?;
+
+    let sections: Vec<Box<[u8]>> = module.custom_sections("meta").collect();
+    assert_eq!(
+        sections,
+        [
+            b"first".to_vec().into_boxed_slice(),
+            b"second".to_vec().into_boxed_slice()
+        ]
+    );
+    assert_eq!(module.custom_sections("missing").count(), 0);
+    Ok(())
+}
+
+#[test]
+#[cfg(feature = "sys")]
+fn module_custom_sections_survive_serialization() {
+    let store = Store::default();
+    let wat = r#"(module
+        (@custom "meta" "first")
+        (@custom "other" "unrelated")
+        (@custom "meta" "second")
+    )"#;
+    let module = Module::new(&store, wat).unwrap();
+    assert_eq!(
+        module.custom_section_names().collect::<Vec<_>>(),
+        ["meta", "other"]
+    );
+
+    let bytes = module.serialize().unwrap();
+    let module = unsafe { Module::deserialize(&store, bytes).unwrap() };
+
+    assert_eq!(
+        module.custom_section_names().collect::<Vec<_>>(),
+        ["meta", "other"]
+    );
+    let sections: Vec<Box<[u8]>> = module.custom_sections("meta").collect();
+    assert_eq!(
+        sections,
+        [
+            b"first".to_vec().into_boxed_slice(),
+            b"second".to_vec().into_boxed_slice()
+        ]
+    );
+    assert_eq!(
+        module.custom_sections("other").collect::<Vec<_>>(),
+        [b"unrelated".to_vec().into_boxed_slice()]
+    );
+}
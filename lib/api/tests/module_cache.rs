@@ -0,0 +1,144 @@
+#![cfg(feature = "cranelift")]
+
+use std::{
+    io,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+use wasmer::{
+    sys::{CpuFeature, Cranelift, EngineBuilder, Target, Triple},
+    *,
+};
+
+const WAT: &str = r#"(module
+    (func (export "add") (param i32 i32) (result i32)
+        (i32.add (local.get 0) (local.get 1))))"#;
+
+/// A [`FileSystemCache`] that counts how it's used.
+#[derive(Debug)]
+struct CountingCache {
+    inner: FileSystemCache,
+    hits: AtomicUsize,
+    stores: AtomicUsize,
+}
+
+impl CountingCache {
+    fn new(dir: &tempfile::TempDir) -> Arc<Self> {
+        Arc::new(CountingCache {
+            inner: FileSystemCache::new(dir.path()).unwrap(),
+            hits: AtomicUsize::new(0),
+            stores: AtomicUsize::new(0),
+        })
+    }
+
+    fn hits(&self) -> usize {
+        self.hits.load(Ordering::SeqCst)
+    }
+
+    fn stores(&self) -> usize {
+        self.stores.load(Ordering::SeqCst)
+    }
+}
+
+impl ModuleCache for CountingCache {
+    fn load(&self, key: &ModuleCacheKey) -> io::Result<Option<bytes::Bytes>> {
+        let module = self.inner.load(key)?;
+        if module.is_some() {
+            self.hits.fetch_add(1, Ordering::SeqCst);
+        }
+        Ok(module)
+    }
+
+    fn store(&self, key: &ModuleCacheKey, module: &[u8]) -> io::Result<()> {
+        self.stores.fetch_add(1, Ordering::SeqCst);
+        self.inner.store(key, module)
+    }
+}
+
+fn engine(target: Target) -> Engine {
+    EngineBuilder::new(Cranelift::default())
+        .set_target(Some(target))
+        .engine()
+        .into()
+}
+
+fn add(store: &mut Store, module: &Module) -> i32 {
+    let instance = Instance::new(store, module, &imports! {}).unwrap();
+    let add = instance
+        .exports
+        .get_typed_function::<(i32, i32), i32>(store, "add")
+        .unwrap();
+    add.call(store, 1, 2).unwrap()
+}
+
+#[test]
+fn compiling_the_same_module_again_hits_the_cache() {
+    let dir = tempfile::tempdir().unwrap();
+    let cache = CountingCache::new(&dir);
+    let mut store = Store::with_cache(engine(Target::default()), cache.clone());
+
+    let compiled = Module::new(&store, WAT).unwrap();
+    assert_eq!((cache.hits(), cache.stores()), (0, 1));
+
+    let cached = Module::new(&store, WAT).unwrap();
+    assert_eq!((cache.hits(), cache.stores()), (1, 1));
+    assert_eq!(add(&mut store, &compiled), 3);
+    assert_eq!(add(&mut store, &cached), 3);
+
+    // A different module is a miss
+    Module::new(&store, "(module)").unwrap();
+    assert_eq!((cache.hits(), cache.stores()), (1, 2));
+}
+
+#[test]
+fn changing_the_cpu_features_busts_the_cache() {
+    let dir = tempfile::tempdir().unwrap();
+    let cache = CountingCache::new(&dir);
+
+    let all = CpuFeature::for_host();
+    let mut fewer = all;
+    let last = fewer.iter().last().expect("the host has CPU features");
+    fewer.remove(last);
+
+    let with_all = engine(Target::new(Triple::host(), all));
+    let with_fewer = engine(Target::new(Triple::host(), fewer));
+    let wasm = wat2wasm(WAT.as_bytes()).unwrap();
+    assert_ne!(
+        ModuleCacheKey::new(&with_all, &wasm),
+        ModuleCacheKey::new(&with_fewer, &wasm)
+    );
+
+    Module::new(&Store::with_cache(with_all, cache.clone()), WAT).unwrap();
+    let mut store = Store::with_cache(with_fewer, cache.clone());
+    let module = Module::new(&store, WAT).unwrap();
+    assert_eq!((cache.hits(), cache.stores()), (0, 2));
+    assert_eq!(add(&mut store, &module), 3);
+    assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 2);
+}
+
+#[test]
+fn stale_entries_are_compiled_again() {
+    let dir = tempfile::tempdir().unwrap();
+    let cache = CountingCache::new(&dir);
+    let mut store = Store::with_cache(engine(Target::default()), cache.clone());
+
+    // As if written by an incompatible version of Wasmer
+    let wasm = wat2wasm(WAT.as_bytes()).unwrap();
+    let path = cache
+        .inner
+        .path(&ModuleCacheKey::new(store.engine(), &wasm));
+    std::fs::write(&path, b"not a module").unwrap();
+
+    let module = Module::new(&store, WAT).unwrap();
+    assert_eq!((cache.hits(), cache.stores()), (1, 1));
+    assert_eq!(add(&mut store, &module), 3);
+    assert_ne!(std::fs::read(&path).unwrap(), b"not a module");
+
+    // The entry was replaced with one that can be loaded
+    let module = Module::new(&store, WAT).unwrap();
+    assert_eq!((cache.hits(), cache.stores()), (2, 1));
+    assert_eq!(add(&mut store, &module), 3);
+}
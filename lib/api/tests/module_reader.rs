@@ -0,0 +1,118 @@
+#![cfg(all(feature = "tokio", feature = "cranelift"))]
+
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use tokio::io::{AsyncRead, ReadBuf};
+use wasmer::*;
+
+/// A reader that hands out at most `chunk_size` bytes at a time, and is only
+/// ready every other time it is polled.
+struct ThrottledReader {
+    data: Vec<u8>,
+    position: usize,
+    chunk_size: usize,
+    ready: bool,
+}
+
+impl ThrottledReader {
+    fn new(data: Vec<u8>, chunk_size: usize) -> Self {
+        ThrottledReader {
+            data,
+            position: 0,
+            chunk_size,
+            ready: false,
+        }
+    }
+}
+
+impl AsyncRead for ThrottledReader {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        self.ready = !self.ready;
+        if !self.ready {
+            cx.waker().wake_by_ref();
+            return Poll::Pending;
+        }
+
+        let remaining = &self.data[self.position..];
+        let len = remaining.len().min(self.chunk_size).min(buf.remaining());
+        buf.put_slice(&remaining[..len]);
+        self.position += len;
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// A module with a few functions, followed by a large data segment so that
+/// the code section is done long before the end of the input.
+fn module() -> Vec<u8> {
+    let data = "x".repeat(256 * 1024);
+    let wat = format!(
+        r#"(module
+            (memory 8)
+            (func (export "add") (param i32 i32) (result i32)
+                (i32.add (local.get 0) (local.get 1)))
+            (func (export "half") (result f64)
+                (f64.div (f64.const 1.5) (f64.const 3)))
+            (func (export "answer") (result i64)
+                (i64.const 42))
+            (data (i32.const 0) "{data}"))"#
+    );
+    wat::parse_str(wat).unwrap()
+}
+
+#[tokio::test]
+async fn reading_matches_buffered_compilation() {
+    let store = Store::default();
+    let wasm = module();
+
+    let buffered = Module::from_binary(&store, &wasm).unwrap();
+    let read = Module::from_async_reader(&store, ThrottledReader::new(wasm, 1000))
+        .await
+        .unwrap();
+
+    assert_eq!(read.serialize().unwrap(), buffered.serialize().unwrap());
+
+    let mut store = store;
+    let instance = Instance::new(&mut store, &read, &imports! {}).unwrap();
+    let add: TypedFunction<(i32, i32), i32> =
+        instance.exports.get_typed_function(&store, "add").unwrap();
+    assert_eq!(add.call(&mut store, 1, 2).unwrap(), 3);
+}
+
+#[tokio::test]
+async fn corrupted_input_reports_offset() {
+    let store = Store::default();
+    let mut wasm = module();
+
+    // Replace the `f64.const 1.5` opcode in the second function with an
+    // invalid one
+    let mut pattern = vec![0x44];
+    pattern.extend_from_slice(&1.5f64.to_le_bytes());
+    let offset = wasm
+        .windows(pattern.len())
+        .position(|window| window == pattern)
+        .unwrap();
+    wasm[offset] = 0xff;
+    let len = wasm.len();
+
+    let mut reader = ThrottledReader::new(wasm, 1000);
+    let err = Module::from_async_reader(&store, &mut reader)
+        .await
+        .unwrap_err();
+
+    match err {
+        IoCompileError::Compile(CompileError::Wasm(WasmError::InvalidWebAssembly {
+            offset: actual,
+            ..
+        })) => assert_eq!(actual, offset),
+        other => panic!("unexpected error: {other:?}"),
+    }
+    // The error was found without waiting for the rest of the module
+    assert!(reader.position < len);
+}
@@ -0,0 +1,123 @@
+#![cfg(feature = "sys")]
+
+use wasmer::*;
+use wasmer_types::TrapCode;
+
+/// Exports `pong(n)`, which calls the host's `ping(n - 1)` and returns one
+/// more than it does, until `n` is 0, where it runs `base`.
+fn pong_module(store: &Store, base: &str) -> Module {
+    let wat = format!(
+        r#"(module
+            (import "env" "ping" (func $ping (param i32) (result i32)))
+            (func (export "pong") (param $n i32) (result i32)
+                (if (i32.eqz (local.get $n))
+                    (then {base}))
+                (i32.add
+                    (call $ping (i32.sub (local.get $n) (i32.const 1)))
+                    (i32.const 1))))"#
+    );
+    Module::new(store, wat).unwrap()
+}
+
+struct Env {
+    caller: ReentrantCaller,
+    pings: u32,
+}
+
+/// Calls the guest's `pong(n)` and returns what it does.
+fn ping(mut env: FunctionEnvMut<Env>, n: i32) -> Result<i32, RuntimeError> {
+    env.data_mut().pings += 1;
+    let caller = env.data().caller.clone();
+    let results = caller.call(&mut env, "pong", &[Value::I32(n)])?;
+    Ok(results[0].unwrap_i32())
+}
+
+fn instantiate(store: &mut Store, module: &Module) -> (Instance, FunctionEnv<Env>) {
+    let caller = ReentrantCaller::new();
+    let env = FunctionEnv::new(
+        store,
+        Env {
+            caller: caller.clone(),
+            pings: 0,
+        },
+    );
+    let imports = imports! {
+        "env" => { "ping" => Function::new_typed_with_env(store, &env, ping) },
+    };
+    let instance = Instance::new(store, module, &imports).unwrap();
+    caller.set_instance(&instance);
+    (instance, env)
+}
+
+/// Every nested call uses some of the thread's stack, more so in debug
+/// builds, so the tests run on a thread with plenty of it.
+fn with_large_stack(f: impl FnOnce() + Send + 'static) {
+    std::thread::Builder::new()
+        .stack_size(256 * 1024 * 1024)
+        .spawn(f)
+        .unwrap()
+        .join()
+        .unwrap();
+}
+
+#[test]
+fn mutual_recursion() {
+    with_large_stack(|| {
+        let mut store = Store::default();
+        let module = pong_module(&store, "(return (i32.const 0))");
+        let (instance, env) = instantiate(&mut store, &module);
+
+        let pong = instance.exports.get_function("pong").unwrap();
+        let results = pong.call(&mut store, &[Value::I32(1000)]).unwrap();
+        assert_eq!(results[0].unwrap_i32(), 1000);
+        assert_eq!(env.as_ref(&store).pings, 1000);
+    });
+}
+
+#[test]
+fn traps_unwind_to_the_outermost_call() {
+    with_large_stack(|| {
+        let mut store = Store::default();
+        let module = pong_module(&store, "unreachable");
+        let (instance, env) = instantiate(&mut store, &module);
+        let pong = instance.exports.get_function("pong").unwrap();
+
+        for round in 1..=2 {
+            let err = pong.call(&mut store, &[Value::I32(1000)]).unwrap_err();
+            assert_eq!(err.to_trap(), Some(TrapCode::UnreachableCodeReached));
+            assert_eq!(env.as_ref(&store).pings, 1000 * round);
+        }
+    });
+}
+
+#[test]
+fn unbounded_recursion_overflows_the_stack() {
+    with_large_stack(|| {
+        // The limit is global, and high enough for the other tests
+        wasmer::sys::vm::set_max_nested_calls(2000);
+        let mut store = Store::default();
+        let module = pong_module(&store, "(return (i32.const 0))");
+        let (instance, env) = instantiate(&mut store, &module);
+        let pong = instance.exports.get_function("pong").unwrap();
+
+        let err = pong.call(&mut store, &[Value::I32(-1)]).unwrap_err();
+        assert_eq!(err.to_trap(), Some(TrapCode::StackOverflow));
+        assert!(env.as_ref(&store).pings >= 1000);
+
+        // Nothing is left behind by the unwinding
+        let results = pong.call(&mut store, &[Value::I32(10)]).unwrap();
+        assert_eq!(results[0].unwrap_i32(), 10);
+    });
+}
+
+#[test]
+fn calls_before_the_instance_is_set_fail() {
+    let mut store = Store::default();
+    let caller = ReentrantCaller::new();
+    assert!(!caller.is_set());
+    let err = caller.call(&mut store, "pong", &[]).unwrap_err();
+    assert!(
+        err.message().contains("hasn't been given an instance"),
+        "{err}"
+    );
+}
@@ -0,0 +1,113 @@
+#![cfg(feature = "sys")]
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use wasmer::*;
+
+const COUNTER: &str = r#"
+(module
+    (import "env" "add" (func $add (param i32)))
+    (import "env" "total" (func $total (result i32)))
+    (func (export "run") (result i32)
+        (call $add (i32.const 1))
+        (call $add (i32.const 2))
+        (call $add (i32.const 3))
+        (call $total))
+)
+"#;
+
+#[test]
+fn host_functions_borrow_the_stack() {
+    let store = Store::default();
+    let module = Module::new(&store, COUNTER).unwrap();
+
+    let mut counter = 0;
+    let base = 100;
+    let total = store.scope(|scope| {
+        let add = scope.function(|x: i32| counter += x);
+        let total = scope.function(|| base);
+        let imports = imports! {
+            "env" => {
+                "add" => add,
+                "total" => total,
+            }
+        };
+        let instance = scope.instantiate(&module, &imports).unwrap();
+
+        let run = instance
+            .exports()
+            .get_typed_function::<(), i32>(scope, "run")
+            .unwrap();
+        run.call(scope).unwrap()
+    });
+
+    assert_eq!(total, 100);
+    assert_eq!(counter, 6);
+}
+
+#[test]
+fn host_functions_are_dropped_with_the_scope() {
+    struct SetOnDrop<'a>(&'a AtomicBool);
+
+    impl Drop for SetOnDrop<'_> {
+        fn drop(&mut self) {
+            self.0.store(true, Ordering::SeqCst);
+        }
+    }
+
+    let store = Store::default();
+    let dropped = AtomicBool::new(false);
+
+    store.scope(|scope| {
+        let guard = SetOnDrop(&dropped);
+        let _ = scope.function(move || guard.0.load(Ordering::SeqCst) as i32);
+        assert!(!dropped.load(Ordering::SeqCst));
+    });
+
+    assert!(dropped.load(Ordering::SeqCst));
+}
+
+#[test]
+fn scoped_functions_cannot_be_used_outside_the_scope() {
+    let mut store = Store::default();
+    let module = Module::new(&store, COUNTER).unwrap();
+
+    let mut counter = 0;
+    let add = store.scope(|scope| scope.function(|x: i32| counter += x));
+
+    // The handle is useless without the scope's store
+    let imports = imports! {
+        "env" => {
+            "add" => add,
+            "total" => Function::new_typed(&mut store, || 0),
+        }
+    };
+    assert!(matches!(
+        Instance::new(&mut store, &module, &imports),
+        Err(InstantiationError::DifferentStores)
+    ));
+    assert_eq!(counter, 0);
+}
+
+#[test]
+#[should_panic(expected = "a scoped host function was called after its scope ended")]
+fn swapped_out_functions_are_dropped_with_the_scope() {
+    let store = Store::default();
+    let mut outer = Store::new(store.engine().clone());
+
+    let mut counter = 0;
+    let add = store.scope(|scope| {
+        let add = scope.function(|x: i32| counter += x);
+        std::mem::swap(scope.objects_mut(), outer.objects_mut());
+        add
+    });
+
+    // The store that now holds the function outlives `counter`'s borrow
+    let _ = add.call(&mut outer, &[Value::I32(1)]);
+}
+
+#[test]
+fn scoped_instances_cannot_escape() {
+    let t = trybuild::TestCases::new();
+    t.compile_fail("tests/ui/scope_*.rs");
+}
@@ -0,0 +1,64 @@
+#![cfg(feature = "cranelift")]
+
+use wasmer::{
+    sys::{Cranelift, EngineBuilder},
+    *,
+};
+
+const WAT: &str = r#"(module
+    (import "env" "log" (func $log (param i32)))
+    (func (export "add") (param i32 i32) (result i32)
+        (i32.add (local.get 0) (local.get 1)))
+    (func (export "double_and_log") (param i32)
+        (call $log (i32.mul (local.get 0) (i32.const 2)))))"#;
+
+fn engine() -> Engine {
+    EngineBuilder::new(Cranelift::default()).engine().into()
+}
+
+#[test]
+fn compiled_modules_have_stats() {
+    let engine = engine();
+    let module = Module::new(&engine, WAT).unwrap();
+
+    let stats = module.stats().unwrap();
+    assert_eq!(stats.functions, 2);
+    assert!(stats.code_size > 0);
+    assert!(stats.trampolines > 0);
+    assert!(stats.trampolines_size > 0);
+    assert!(stats.relocations > 0);
+    let mapped_size = stats.mapped_size.unwrap();
+    let executable_size = stats.executable_size.unwrap();
+    assert!(executable_size >= stats.code_size + stats.trampolines_size);
+    assert!(mapped_size >= executable_size);
+}
+
+#[test]
+fn stats_survive_serialization() {
+    let engine = engine();
+    let module = Module::new(&engine, WAT).unwrap();
+
+    let bytes = module.serialize().unwrap();
+    let deserialized = unsafe { Module::deserialize(&engine, bytes).unwrap() };
+    assert_eq!(deserialized.stats(), module.stats());
+}
+
+#[test]
+fn engines_track_allocated_code() {
+    let engine = engine();
+    assert_eq!(engine.allocation_stats(), Some(AllocationStats::default()));
+
+    let first = Module::new(&engine, WAT).unwrap().stats().unwrap();
+    let second = Module::new(&engine, WAT).unwrap().stats().unwrap();
+
+    let allocated = engine.allocation_stats().unwrap();
+    assert_eq!(allocated.artifacts, 2);
+    assert_eq!(
+        allocated.mapped_size,
+        first.mapped_size.unwrap() + second.mapped_size.unwrap()
+    );
+    assert_eq!(
+        allocated.executable_size,
+        first.executable_size.unwrap() + second.executable_size.unwrap()
+    );
+}
@@ -0,0 +1,246 @@
+#![cfg(feature = "sys")]
+
+use wasmer::*;
+
+/// Defines a table of 10 elements and 2 globals.
+const MODULE: &str = r#"
+(module
+    (table 10 funcref)
+    (global i32 (i32.const 1))
+    (global (mut i64) (i64.const 2)))
+"#;
+
+fn limits() -> StoreLimits {
+    StoreLimits {
+        max_instances: 3,
+        max_tables: 5,
+        max_table_elements: 35,
+        max_globals: 7,
+        max_host_functions: 2,
+    }
+}
+
+fn limit_exceeded(error: InstantiationError) -> (StoreResource, u64) {
+    match error {
+        InstantiationError::LimitExceeded { resource, limit } => (resource, limit),
+        other => panic!("unexpected error: {other}"),
+    }
+}
+
+#[test]
+fn stores_are_unlimited_by_default() {
+    let mut store = Store::default();
+    let module = Module::new(&store, MODULE).unwrap();
+
+    assert_eq!(*store.limits(), StoreLimits::UNLIMITED);
+    for _ in 0..100 {
+        Instance::new(&mut store, &module, &imports! {}).unwrap();
+    }
+    assert_eq!(store.resource_usage().instances, 100);
+}
+
+#[test]
+fn instances_are_limited() {
+    let mut store = Store::default();
+    store.set_limits(limits());
+    let module = Module::new(&store, "(module)").unwrap();
+
+    for _ in 0..3 {
+        Instance::new(&mut store, &module, &imports! {}).unwrap();
+    }
+    let error = Instance::new(&mut store, &module, &imports! {}).unwrap_err();
+
+    assert_eq!(limit_exceeded(error), (StoreResource::Instances, 3));
+    assert_eq!(store.resource_usage().instances, 3);
+}
+
+#[test]
+fn tables_and_globals_defined_by_modules_are_limited() {
+    let mut store = Store::default();
+    store.set_limits(limits());
+    let module = Module::new(&store, MODULE).unwrap();
+
+    for _ in 0..3 {
+        Instance::new(&mut store, &module, &imports! {}).unwrap();
+    }
+    assert_eq!(
+        store.resource_usage(),
+        ResourceUsage {
+            instances: 3,
+            tables: 3,
+            table_elements: 30,
+            globals: 6,
+            host_functions: 0,
+        }
+    );
+
+    // The next instance would need 40 elements and 8 globals
+    store.set_limits(StoreLimits {
+        max_instances: 10,
+        ..limits()
+    });
+    let error = Instance::new(&mut store, &module, &imports! {}).unwrap_err();
+    assert_eq!(limit_exceeded(error), (StoreResource::TableElements, 35));
+
+    store.set_limits(StoreLimits {
+        max_instances: 10,
+        max_table_elements: 100,
+        ..limits()
+    });
+    let error = Instance::new(&mut store, &module, &imports! {}).unwrap_err();
+    assert_eq!(limit_exceeded(error), (StoreResource::Globals, 7));
+
+    // Failed instantiations don't count
+    assert_eq!(store.resource_usage().instances, 3);
+}
+
+#[test]
+fn host_tables_are_limited() {
+    let mut store = Store::default();
+    store.set_limits(limits());
+    let ty = TableType::new(Type::FuncRef, 10, None);
+
+    let tables: Vec<_> = (0..3)
+        .map(|_| Table::new(&mut store, ty, Value::FuncRef(None)).unwrap())
+        .collect();
+    let error = Table::new(&mut store, ty, Value::FuncRef(None)).unwrap_err();
+    assert_eq!(
+        error.downcast_ref::<LimitExceeded>(),
+        Some(&LimitExceeded {
+            resource: StoreResource::TableElements,
+            limit: 35,
+        })
+    );
+
+    tables[0].grow(&mut store, 5, Value::FuncRef(None)).unwrap();
+    let error = tables[0]
+        .grow(&mut store, 1, Value::FuncRef(None))
+        .unwrap_err();
+    assert!(error.downcast_ref::<LimitExceeded>().is_some());
+    assert_eq!(tables[0].size(&store), 15);
+    assert_eq!(store.resource_usage().table_elements, 35);
+}
+
+#[test]
+fn stores_over_a_limit_refuse_to_instantiate() {
+    let mut store = Store::default();
+    store.set_limits(limits());
+    let module = Module::new(&store, "(module)").unwrap();
+
+    for _ in 0..3 {
+        Function::new_typed(&mut store, || {});
+    }
+    assert_eq!(store.resource_usage().host_functions, 3);
+
+    let error = Instance::new(&mut store, &module, &imports! {}).unwrap_err();
+    assert_eq!(limit_exceeded(error), (StoreResource::HostFunctions, 2));
+}
+
+#[test]
+fn limits_count_every_object_ever_created() {
+    let mut store = Store::default();
+    store.set_limits(limits());
+    let module = Module::new(&store, "(module)").unwrap();
+
+    // The store keeps the instances after their handles are dropped
+    for _ in 0..3 {
+        drop(Instance::new(&mut store, &module, &imports! {}).unwrap());
+    }
+    assert_eq!(store.resource_usage().instances, 3);
+    let error = Instance::new(&mut store, &module, &imports! {}).unwrap_err();
+    assert_eq!(limit_exceeded(error), (StoreResource::Instances, 3));
+
+    // A new store starts from nothing
+    let mut store = Store::new(store.engine().clone());
+    store.set_limits(limits());
+    assert_eq!(store.resource_usage(), ResourceUsage::default());
+    Instance::new(&mut store, &module, &imports! {}).unwrap();
+}
+
+/// Lets the stores sharing it hold `max` pages of memory altogether.
+#[derive(Debug)]
+struct SharedCap {
+    max: u32,
+    used: std::sync::Mutex<u32>,
+}
+
+impl SharedCap {
+    fn new(max: u32) -> std::sync::Arc<Self> {
+        std::sync::Arc::new(Self {
+            max,
+            used: std::sync::Mutex::new(0),
+        })
+    }
+
+    fn used(&self) -> u32 {
+        *self.used.lock().unwrap()
+    }
+}
+
+impl MemoryLimiter for SharedCap {
+    fn on_grow(&self, pages: Pages) -> Result<(), MemoryError> {
+        let mut used = self.used.lock().unwrap();
+        if *used + pages.0 > self.max {
+            return Err(MemoryError::Generic("over the cap".to_string()));
+        }
+        *used += pages.0;
+        Ok(())
+    }
+
+    fn on_shrink(&self, pages: Pages) {
+        *self.used.lock().unwrap() -= pages.0;
+    }
+}
+
+/// Exports a memory of 2 pages and `grow(delta)`.
+const GROWING: &str = r#"
+(module
+    (memory (export "memory") 2)
+    (func (export "grow") (param i32) (result i32)
+        (memory.grow (local.get 0))))
+"#;
+
+#[test]
+fn memory_limiters_are_shared_between_stores() {
+    let cap = SharedCap::new(10);
+    let limiter = || Some(cap.clone() as std::sync::Arc<dyn MemoryLimiter>);
+    // The stores share an engine, which keeps the module's code alive once
+    // `a` is dropped
+    let engine = Engine::default();
+    let mut a = Store::new(engine.clone());
+    a.set_memory_limiter(limiter());
+    let mut b = Store::new(engine);
+    b.set_memory_limiter(limiter());
+    let module = Module::new(&a, GROWING).unwrap();
+
+    let instance_a = Instance::new(&mut a, &module, &imports! {}).unwrap();
+    let grow_a = instance_a
+        .exports
+        .get_typed_function::<i32, i32>(&a, "grow")
+        .unwrap();
+    let instance_b = Instance::new(&mut b, &module, &imports! {}).unwrap();
+    let grow_b = instance_b
+        .exports
+        .get_typed_function::<i32, i32>(&b, "grow")
+        .unwrap();
+    assert_eq!(cap.used(), 4);
+
+    // Growing from WebAssembly fails like any other failed growth
+    assert_eq!(grow_a.call(&mut a, 5).unwrap(), 2);
+    assert_eq!(grow_b.call(&mut b, 2).unwrap(), -1);
+    assert_eq!(grow_b.call(&mut b, 1).unwrap(), 2);
+    assert_eq!(cap.used(), 10);
+
+    // So does creating or growing a memory from the host
+    assert!(Memory::new(&mut b, MemoryType::new(1, None, false)).is_err());
+    let memory_b = instance_b.exports.get_memory("memory").unwrap();
+    assert!(memory_b.grow(&mut b, 1).is_err());
+    assert_eq!(memory_b.view(&b).size(), Pages(3));
+
+    // Dropping a store releases its memories
+    drop(instance_a);
+    drop(a);
+    assert_eq!(cap.used(), 3);
+    assert_eq!(grow_b.call(&mut b, 7).unwrap(), 3);
+    assert_eq!(cap.used(), 10);
+}
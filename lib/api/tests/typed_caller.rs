@@ -0,0 +1,216 @@
+use wasmer::*;
+use wasmer_types::Memory64;
+
+/// Follows the convention with 32-bit pointers. `live` counts the buffers
+/// that were allocated and not freed yet.
+const GUEST: &str = r#"
+(module
+    (memory (export "memory") 1)
+    (global $heap (mut i32) (i32.const 1024))
+    (global $live (export "live") (mut i32) (i32.const 0))
+
+    (func $malloc (export "malloc") (param $size i32) (result i32)
+        (local $ptr i32)
+        (local.set $ptr (global.get $heap))
+        (global.set $heap (i32.and
+            (i32.add (i32.add (local.get $ptr) (local.get $size)) (i32.const 7))
+            (i32.const -8)))
+        (global.set $live (i32.add (global.get $live) (i32.const 1)))
+        (local.get $ptr))
+
+    (func (export "free") (param i32)
+        (global.set $live (i32.sub (global.get $live) (i32.const 1))))
+
+    ;; Returns its input reversed
+    (func (export "reverse") (param $ptr i32) (param $len i32) (result i32 i32)
+        (local $out i32)
+        (local $i i32)
+        (if (i32.eqz (local.get $len))
+            (then (return (i32.const 0) (i32.const 0))))
+        (local.set $out (call $malloc (local.get $len)))
+        (loop $bytes
+            (i32.store8
+                (i32.sub
+                    (i32.add (local.get $out) (local.get $len))
+                    (i32.add (local.get $i) (i32.const 1)))
+                (i32.load8_u (i32.add (local.get $ptr) (local.get $i))))
+            (local.set $i (i32.add (local.get $i) (i32.const 1)))
+            (br_if $bytes (i32.lt_u (local.get $i) (local.get $len))))
+        (local.get $out)
+        (local.get $len))
+
+    ;; Writes a copy of its input followed by its length to `out_ptr`
+    (func (export "with_len") (param $ptr i32) (param $len i32) (param $out_ptr i32)
+        (local $out i32)
+        (local.set $out (call $malloc (i32.add (local.get $len) (i32.const 1))))
+        (memory.copy (local.get $out) (local.get $ptr) (local.get $len))
+        (i32.store8 (i32.add (local.get $out) (local.get $len)) (local.get $len))
+        (i32.store (local.get $out_ptr) (local.get $out))
+        (i32.store offset=4 (local.get $out_ptr) (i32.add (local.get $len) (i32.const 1))))
+
+    (func (export "fail") (param i32 i32) (result i32 i32)
+        unreachable)
+
+    (func (export "wrong") (param i32) (result i32)
+        (local.get 0))
+)
+"#;
+
+/// The same convention with 64-bit pointers and a `free` that takes the
+/// length. The compiler doesn't support 64-bit memories yet, so the pointers
+/// are into a 32-bit one.
+const GUEST64: &str = r#"
+(module
+    (memory (export "memory") 1)
+    (global $heap (mut i64) (i64.const 1024))
+    (global $live (export "live") (mut i32) (i32.const 0))
+
+    (func $malloc (export "malloc") (param $size i64) (result i64)
+        (local $ptr i64)
+        (local.set $ptr (global.get $heap))
+        (global.set $heap (i64.and
+            (i64.add (i64.add (local.get $ptr) (local.get $size)) (i64.const 7))
+            (i64.const -8)))
+        (global.set $live (i32.add (global.get $live) (i32.const 1)))
+        (local.get $ptr))
+
+    (func (export "free") (param i64 i64)
+        (global.set $live (i32.sub (global.get $live) (i32.const 1))))
+
+    ;; Writes a copy of its input followed by its length to `out_ptr`
+    (func (export "with_len") (param $ptr i64) (param $len i64) (param $out_ptr i64)
+        (local $out i64)
+        (local.set $out (call $malloc (i64.add (local.get $len) (i64.const 1))))
+        (memory.copy
+            (i32.wrap_i64 (local.get $out))
+            (i32.wrap_i64 (local.get $ptr))
+            (i32.wrap_i64 (local.get $len)))
+        (i64.store8 (i32.wrap_i64 (i64.add (local.get $out) (local.get $len))) (local.get $len))
+        (i64.store (i32.wrap_i64 (local.get $out_ptr)) (local.get $out))
+        (i64.store offset=8 (i32.wrap_i64 (local.get $out_ptr))
+            (i64.add (local.get $len) (i64.const 1))))
+)
+"#;
+
+fn instantiate(wat: &str) -> (Store, Instance) {
+    let mut store = Store::default();
+    let module = Module::new(&store, wat).unwrap();
+    let instance = Instance::new(&mut store, &module, &imports! {}).unwrap();
+    (store, instance)
+}
+
+fn live(store: &mut Store, instance: &Instance) -> i32 {
+    instance
+        .exports
+        .get_global("live")
+        .unwrap()
+        .get(store)
+        .unwrap_i32()
+}
+
+#[test]
+fn utf8_round_trip() {
+    let (mut store, instance) = instantiate(GUEST);
+    let caller: TypedCaller = TypedCaller::new(&store, &instance).unwrap();
+
+    let text = "Grüße, 世界!";
+    let reversed = caller
+        .call_with_bytes(&mut store, "reverse", text.as_bytes())
+        .unwrap();
+    assert_ne!(reversed, text.as_bytes());
+    let output = caller
+        .call_with_bytes(&mut store, "reverse", &reversed)
+        .unwrap();
+
+    assert_eq!(String::from_utf8(output).unwrap(), text);
+    assert_eq!(live(&mut store, &instance), 0);
+}
+
+#[test]
+fn binary_round_trip() {
+    let (mut store, instance) = instantiate(GUEST);
+    let caller: TypedCaller = TypedCaller::new(&store, &instance).unwrap();
+
+    let input: Vec<u8> = (0..=255).collect();
+    let output = caller
+        .call_with_bytes(&mut store, "reverse", &input)
+        .unwrap();
+    assert_eq!(output, input.iter().rev().copied().collect::<Vec<_>>());
+
+    let output = caller
+        .call_with_bytes(&mut store, "with_len", &[0, 0xff, 0])
+        .unwrap();
+    assert_eq!(output, [0, 0xff, 0, 3]);
+    assert_eq!(live(&mut store, &instance), 0);
+}
+
+#[test]
+fn empty_input() {
+    let (mut store, instance) = instantiate(GUEST);
+    let caller: TypedCaller = TypedCaller::new(&store, &instance).unwrap();
+
+    let output = caller.call_with_bytes(&mut store, "reverse", &[]).unwrap();
+    assert!(output.is_empty());
+    let output = caller.call_with_bytes(&mut store, "with_len", &[]).unwrap();
+    assert_eq!(output, [0]);
+    assert_eq!(live(&mut store, &instance), 0);
+}
+
+#[test]
+fn memory64_offsets() {
+    let (mut store, instance) = instantiate(GUEST64);
+    let caller = TypedCaller::<Memory64>::new(&store, &instance).unwrap();
+
+    let output = caller
+        .call_with_bytes(&mut store, "with_len", "héllo".as_bytes())
+        .unwrap();
+    assert_eq!(output, b"h\xc3\xa9llo\x06");
+    let output = caller.call_with_bytes(&mut store, "with_len", &[]).unwrap();
+    assert_eq!(output, [0]);
+    assert_eq!(live(&mut store, &instance), 0);
+}
+
+#[test]
+fn failed_calls_free_the_input() {
+    let (mut store, instance) = instantiate(GUEST);
+    let caller: TypedCaller = TypedCaller::new(&store, &instance).unwrap();
+
+    let error = caller
+        .call_with_bytes(&mut store, "fail", b"input")
+        .unwrap_err();
+
+    assert!(matches!(error, TypedCallerError::Runtime(_)), "{error:?}");
+    assert_eq!(live(&mut store, &instance), 0);
+}
+
+#[test]
+fn missing_exports_and_wrong_signatures() {
+    let (mut store, instance) = instantiate(GUEST);
+
+    let error = TypedCaller::<Memory32>::with_exports(&store, &instance, "memory", "alloc", "free")
+        .unwrap_err();
+    assert!(matches!(error, TypedCallerError::MissingExport(name) if name == "alloc"));
+
+    let error = TypedCaller::<Memory32>::with_exports(&store, &instance, "live", "malloc", "free")
+        .unwrap_err();
+    assert!(matches!(error, TypedCallerError::IncompatibleExport(name) if name == "live"));
+
+    // The pointers are the wrong size
+    let error = TypedCaller::<Memory64>::new(&store, &instance).unwrap_err();
+    assert!(matches!(error, TypedCallerError::Signature { name, .. } if name == "malloc"));
+
+    let caller: TypedCaller = TypedCaller::new(&store, &instance).unwrap();
+    let error = caller
+        .call_with_bytes(&mut store, "missing", b"input")
+        .unwrap_err();
+    assert!(matches!(error, TypedCallerError::MissingExport(name) if name == "missing"));
+
+    let error = caller
+        .call_with_bytes(&mut store, "wrong", b"input")
+        .unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "`wrong` has the signature [I32] -> [I32], expected [I32, I32] -> [I32, I32] or [I32, I32, I32] -> []"
+    );
+    assert_eq!(live(&mut store, &instance), 0);
+}
@@ -0,0 +1,10 @@
+use wasmer::Store;
+
+fn main() {
+    let store = Store::default();
+    store.scope(|scope| {
+        // Doesn't live as long as the scope's functions may
+        let mut count = 0;
+        let _ = scope.function(|x: i32| count += x);
+    });
+}
@@ -0,0 +1,8 @@
+use wasmer::{imports, Module, Store};
+
+fn main() {
+    let store = Store::default();
+    let module = Module::new(&store, "(module)").unwrap();
+    let instance = store.scope(|scope| scope.instantiate(&module, &imports! {}).unwrap());
+    let _ = instance.exports();
+}
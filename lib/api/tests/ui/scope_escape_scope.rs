@@ -0,0 +1,8 @@
+use wasmer::Store;
+
+fn main() {
+    let store = Store::default();
+    let mut leaked = None;
+    store.scope(|scope| leaked = Some(scope));
+    let _ = leaked;
+}
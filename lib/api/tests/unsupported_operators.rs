@@ -0,0 +1,51 @@
+#![cfg(feature = "singlepass")]
+
+use wasmer::{
+    sys::{wasmparser::Operator, CompilerConfig, EngineBuilder, Singlepass},
+    *,
+};
+
+/// A module whose second function uses SIMD, which Singlepass can't compile.
+const SIMD: &str = r#"(module
+    (func (result i32) (i32.const 0))
+    (func (result i32) (i32x4.extract_lane 0 (v128.const i32x4 1 2 3 4))))"#;
+
+#[test]
+fn singlepass_reports_unsupported_operators() {
+    let engine: Engine = EngineBuilder::new(Singlepass::default()).engine().into();
+    let wasm = wat::parse_str(SIMD).unwrap();
+
+    let err = Module::new(&engine, &wasm).unwrap_err();
+    let CompileError::UnsupportedOperator(unsupported) = &err else {
+        panic!("unexpected error: {err:?}");
+    };
+    assert_eq!(unsupported.compiler, "singlepass");
+    assert_eq!(unsupported.operator, "V128Const");
+    assert_eq!(unsupported.feature.as_deref(), Some("simd"));
+    assert_eq!(unsupported.function_index.as_u32(), 1);
+    // v128.const is encoded as 0xfd 0x0c
+    assert_eq!(&wasm[unsupported.offset..][..2], [0xfd, 0x0c]);
+
+    // Every other compiler handles SIMD
+    let mut expected = Vec::new();
+    if cfg!(feature = "cranelift") {
+        expected.push("cranelift");
+    }
+    if cfg!(feature = "llvm") {
+        expected.push("llvm");
+    }
+    assert_eq!(unsupported.supported_by, expected);
+    if cfg!(feature = "cranelift") {
+        assert!(err.to_string().ends_with("but cranelift does"), "{err}");
+    }
+}
+
+#[test]
+fn singlepass_supports_mvp_operators() {
+    let engine: Engine = EngineBuilder::new(Singlepass::default()).engine().into();
+    let config = Singlepass::default();
+
+    assert!(config.supports_operator(&Operator::I32Add));
+    assert!(!config.supports_operator(&Operator::I32x4Add));
+    Module::new(&engine, "(module (func (result i32) (i32.const 0)))").unwrap();
+}
@@ -0,0 +1,95 @@
+use wasmer::*;
+
+fn memory(store: &mut Store) -> Memory {
+    Memory::new(store, MemoryType::new(1, None, false)).unwrap()
+}
+
+#[test]
+fn utf8_strings_round_trip() {
+    let mut store = Store::default();
+    let memory = memory(&mut store);
+    let view = memory.view(&store);
+    let ptr: WasmPtr<u8> = WasmPtr::new(16);
+
+    ptr.write_utf8_string(&view, "héllo wörld").unwrap();
+    assert_eq!(ptr.read_utf8_string(&view, 13).unwrap(), "héllo wörld");
+
+    // Interior nulls are part of a string with a length...
+    ptr.write_utf8_string(&view, "a\0b").unwrap();
+    assert_eq!(ptr.read_utf8_string(&view, 3).unwrap(), "a\0b");
+    assert_eq!(ptr.read_utf8_string_with_nul(&view).unwrap(), "a");
+
+    // ... but can't be written before a terminator
+    assert!(matches!(
+        ptr.write_utf8_string_with_nul(&view, "a\0b"),
+        Err(MemoryAccessError::InteriorNul)
+    ));
+    ptr.write_utf8_string_with_nul(&view, "ünïcode").unwrap();
+    assert_eq!(ptr.read_utf8_string_with_nul(&view).unwrap(), "ünïcode");
+}
+
+#[test]
+fn utf8_strings_cut_in_the_middle_of_a_character() {
+    let mut store = Store::default();
+    let memory = memory(&mut store);
+    let view = memory.view(&store);
+    let ptr: WasmPtr<u8> = WasmPtr::new(0);
+
+    // "€" is encoded as 3 bytes, the first of which ends the buffer
+    ptr.write_utf8_string(&view, "ab€").unwrap();
+    assert!(matches!(
+        ptr.read_utf8_string(&view, 3),
+        Err(MemoryAccessError::NonUtf8String)
+    ));
+    assert_eq!(ptr.read_utf8_string_lossy(&view, 3).unwrap(), "ab\u{fffd}");
+    assert_eq!(ptr.read_utf8_string_lossy(&view, 5).unwrap(), "ab€");
+
+    // ... or starts it
+    let ptr: WasmPtr<u8> = WasmPtr::new(3);
+    assert_eq!(
+        ptr.read_utf8_string_lossy(&view, 2).unwrap(),
+        "\u{fffd}\u{fffd}"
+    );
+}
+
+#[test]
+fn utf8_strings_at_the_end_of_memory() {
+    let mut store = Store::default();
+    let memory = memory(&mut store);
+    let view = memory.view(&store);
+    let end = WASM_PAGE_SIZE as u32;
+
+    let ptr: WasmPtr<u8> = WasmPtr::new(end - 3);
+    ptr.write_utf8_string(&view, "end").unwrap();
+    assert_eq!(ptr.read_utf8_string(&view, 3).unwrap(), "end");
+
+    // Nothing is written past the end, or before it
+    assert!(matches!(
+        ptr.write_utf8_string(&view, "ends"),
+        Err(MemoryAccessError::HeapOutOfBounds)
+    ));
+    assert!(matches!(
+        ptr.write_utf8_string_with_nul(&view, "END"),
+        Err(MemoryAccessError::HeapOutOfBounds)
+    ));
+    assert_eq!(ptr.read_utf8_string(&view, 3).unwrap(), "end");
+
+    // Reads stop at the end too
+    assert!(matches!(
+        ptr.read_utf8_string(&view, 4),
+        Err(MemoryAccessError::HeapOutOfBounds)
+    ));
+    assert!(matches!(
+        ptr.read_utf8_string_with_nul(&view),
+        Err(MemoryAccessError::HeapOutOfBounds)
+    ));
+    let ptr: WasmPtr<u8> = WasmPtr::new(end);
+    assert_eq!(ptr.read_utf8_string(&view, 0).unwrap(), "");
+
+    // Including right at the end of the 32-bit address space
+    let ptr: WasmPtr<u8> = WasmPtr::new(u32::MAX);
+    assert!(matches!(
+        ptr.read_utf8_string_lossy(&view, 2),
+        Err(MemoryAccessError::HeapOutOfBounds)
+    ));
+}
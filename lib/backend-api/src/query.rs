@@ -927,6 +927,60 @@ pub async fn current_user(client: &WasmerClient) -> Result<Option<types::User>,
         .map(|x| x.viewer)
 }
 
+/// The outcome of [`check_token()`].
+#[derive(Debug)]
+pub enum TokenCheck {
+    /// The token is valid and belongs to this user.
+    Valid(types::User),
+    /// The registry rejected the token, e.g. because it expired or was
+    /// revoked.
+    Invalid {
+        /// Why the token was rejected, as far as the registry says.
+        reason: String,
+    },
+}
+
+/// Check whether the client's token is accepted by the registry, with a
+/// single lightweight query.
+///
+/// Errors are only returned when the registry couldn't be asked, e.g.
+/// because it is unreachable or failed to answer, so they can be told apart
+/// from a token that is no longer valid.
+///
+/// The API doesn't report the scopes or the expiry of a token.
+pub async fn check_token(client: &WasmerClient) -> Result<TokenCheck, anyhow::Error> {
+    let res = match client
+        .run_graphql_raw(types::GetCurrentUser::build(()))
+        .await
+    {
+        Ok(res) => res,
+        Err(err) => {
+            return match err.downcast_ref::<cynic::http::CynicReqwestError>() {
+                Some(cynic::http::CynicReqwestError::ErrorResponse(status, body))
+                    if *status == reqwest::StatusCode::UNAUTHORIZED
+                        || *status == reqwest::StatusCode::FORBIDDEN =>
+                {
+                    Ok(TokenCheck::Invalid {
+                        reason: format!("{status}: {body}"),
+                    })
+                }
+                _ => Err(err),
+            };
+        }
+    };
+
+    match res.data.and_then(|data| data.viewer) {
+        Some(user) => Ok(TokenCheck::Valid(user)),
+        None => {
+            let reason = match res.errors {
+                Some(errors) if !errors.is_empty() => GraphQLApiFailure { errors }.to_string(),
+                _ => "the token is not tied to any user".to_string(),
+            };
+            Ok(TokenCheck::Invalid { reason })
+        }
+    }
+}
+
 /// Get the currently logged in user, together with all accessible namespaces.
 ///
 /// You can optionally filter the namespaces by the user role.
@@ -91,6 +91,18 @@ pub unsafe extern "C" fn wasm_instance_new(
 
             return None;
         }
+
+        Err(e @ InstantiationError::LimitExceeded { .. }) => {
+            crate::error::update_last_error(e);
+
+            return None;
+        }
+
+        Err(e @ InstantiationError::StartTimeout { .. }) => {
+            crate::error::update_last_error(e);
+
+            return None;
+        }
     };
 
     Some(Box::new(wasm_instance_t {
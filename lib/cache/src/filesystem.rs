@@ -1,11 +1,27 @@
 #![cfg_attr(not(feature = "filesystem"), allow(unused))]
 use crate::cache::Cache;
 use crate::hash::Hash;
-use std::fs::{create_dir_all, File};
+use std::fs::{create_dir_all, File, OpenOptions};
 use std::io::{self, Write};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime};
 use wasmer::{AsEngineRef, DeserializeError, Module, SerializeError};
 
+/// Marks the end of every artifact written by a [`FileSystemCache`].
+const FOOTER_MAGIC: &[u8; 8] = b"wasmer\xc4\x01";
+/// The checksum and length of the artifact, then [`FOOTER_MAGIC`].
+const FOOTER_LEN: usize = 8 + 8 + FOOTER_MAGIC.len();
+
+/// How long [`FileSystemCache::load_or_compile`] waits for another process
+/// to finish compiling a module by default.
+const DEFAULT_CLAIM_TIMEOUT: Duration = Duration::from_secs(30);
+/// How often a waiting [`FileSystemCache::load_or_compile`] checks the cache.
+const CLAIM_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Makes the names of temporary files unique within this process.
+static TEMP_FILE_COUNTER: AtomicU64 = AtomicU64::new(0);
+
 /// Representation of a directory that contains compiled wasm artifacts.
 ///
 /// The `FileSystemCache` type implements the [`Cache`] trait, which allows it to be used
@@ -31,10 +47,24 @@ use wasmer::{AsEngineRef, DeserializeError, Module, SerializeError};
 ///     Ok(())
 /// }
 /// ```
+///
+/// # Concurrent access
+///
+/// Several threads or processes can share a cache directory. Artifacts are
+/// stored under their key, and the same key always yields the same
+/// artifact, so writers never need to coordinate: each one writes to a
+/// temporary file of its own and atomically renames it into place, and
+/// whichever rename happens last wins with identical content. Readers
+/// therefore see either a whole artifact or none at all.
+///
+/// Every artifact ends with a footer holding its length and checksum. An
+/// artifact that doesn't match its footer, e.g. one left behind by a crash
+/// before this layout was used, is deleted and reported as a cache miss.
 #[derive(Debug, Clone)]
 pub struct FileSystemCache {
     path: PathBuf,
     ext: Option<String>,
+    claim_timeout: Duration,
 }
 
 #[cfg(feature = "filesystem")]
@@ -46,7 +76,11 @@ impl FileSystemCache {
             let metadata = path.metadata()?;
             if metadata.is_dir() {
                 if !metadata.permissions().readonly() {
-                    Ok(Self { path, ext: None })
+                    Ok(Self {
+                        path,
+                        ext: None,
+                        claim_timeout: DEFAULT_CLAIM_TIMEOUT,
+                    })
                 } else {
                     // This directory is readonly.
                     Err(io::Error::new(
@@ -73,7 +107,11 @@ impl FileSystemCache {
                     format!("failed to create cache directory: {}", path.display()),
                 ))
             } else {
-                Ok(Self { path, ext: None })
+                Ok(Self {
+                    path,
+                    ext: None,
+                    claim_timeout: DEFAULT_CLAIM_TIMEOUT,
+                })
             }
         }
     }
@@ -85,6 +123,190 @@ impl FileSystemCache {
     pub fn set_cache_extension(&mut self, ext: Option<impl ToString>) {
         self.ext = ext.map(|ext| ext.to_string());
     }
+
+    /// Set how long [`FileSystemCache::load_or_compile`] waits for another
+    /// thread or process to compile a module before compiling it itself.
+    pub fn set_claim_timeout(&mut self, timeout: Duration) {
+        self.claim_timeout = timeout;
+    }
+
+    /// Load the module stored under `key`, or compile it with `compile` and
+    /// store it if there is none.
+    ///
+    /// Only one of the threads and processes sharing the cache compiles a
+    /// given module at a time: it claims the key by creating a lock file next
+    /// to the artifact, and the others wait for the artifact to appear. If it
+    /// doesn't within the claim timeout, e.g. because the process that
+    /// claimed the key crashed, they give up waiting and compile the module
+    /// themselves. Failing to store the module isn't an error, as the cache
+    /// is only an optimization.
+    ///
+    /// # Safety
+    ///
+    /// See [`Cache::load`].
+    pub unsafe fn load_or_compile<E>(
+        &mut self,
+        engine: &impl AsEngineRef,
+        key: Hash,
+        compile: impl FnOnce() -> Result<Module, E>,
+    ) -> Result<Module, E> {
+        if let Ok(module) = self.load(engine, key) {
+            return Ok(module);
+        }
+
+        let claim = self.path.join(format!("{}.lock", self.filename(key)));
+        let started = SystemTime::now();
+        let claimed = loop {
+            match Claim::acquire(&claim) {
+                Ok(Some(claim)) => break Some(claim),
+                Ok(None) => {}
+                // Without a claim we can still compile, just not exclusively
+                Err(_) => break None,
+            }
+            if let Ok(module) = self.load(engine, key) {
+                return Ok(module);
+            }
+            if Claim::is_stale(&claim, self.claim_timeout) {
+                let _ = std::fs::remove_file(&claim);
+                continue;
+            }
+            if started.elapsed().unwrap_or_default() >= self.claim_timeout {
+                break None;
+            }
+            std::thread::sleep(CLAIM_POLL_INTERVAL);
+        };
+
+        // Whoever held the claim before us may have stored the module in the
+        // meantime
+        if claimed.is_some() {
+            if let Ok(module) = self.load(engine, key) {
+                return Ok(module);
+            }
+        }
+
+        let module = compile()?;
+        let _ = self.store(key, &module);
+        drop(claimed);
+        Ok(module)
+    }
+
+    fn filename(&self, key: Hash) -> String {
+        if let Some(ref ext) = self.ext {
+            format!("{key}.{ext}")
+        } else {
+            key.to_string()
+        }
+    }
+}
+
+/// The exclusive right to compile a module, released when dropped.
+#[cfg(feature = "filesystem")]
+struct Claim {
+    path: PathBuf,
+}
+
+#[cfg(feature = "filesystem")]
+impl Claim {
+    /// Claims `path`, returning `None` if someone else holds it.
+    fn acquire(path: &Path) -> io::Result<Option<Self>> {
+        match OpenOptions::new().write(true).create_new(true).open(path) {
+            Ok(_) => Ok(Some(Self {
+                path: path.to_path_buf(),
+            })),
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Whether the claim on `path` was made longer than `timeout` ago.
+    fn is_stale(path: &Path, timeout: Duration) -> bool {
+        path.metadata()
+            .and_then(|metadata| metadata.modified())
+            .ok()
+            .and_then(|modified| modified.elapsed().ok())
+            .is_some_and(|age| age > timeout)
+    }
+}
+
+#[cfg(feature = "filesystem")]
+impl Drop for Claim {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// The first 8 bytes of the BLAKE3 hash of `payload`.
+fn checksum(payload: &[u8]) -> [u8; 8] {
+    let mut checksum = [0; 8];
+    checksum.copy_from_slice(&blake3::hash(payload).as_bytes()[..8]);
+    checksum
+}
+
+/// Appends the footer to the serialized artifact `payload`.
+fn append_footer(mut payload: Vec<u8>) -> Vec<u8> {
+    let checksum = checksum(&payload);
+    let len = payload.len() as u64;
+    payload.extend_from_slice(&checksum);
+    payload.extend_from_slice(&len.to_le_bytes());
+    payload.extend_from_slice(FOOTER_MAGIC);
+    payload
+}
+
+/// Checks the footer of `contents`, returning the serialized artifact it
+/// protects.
+fn strip_footer(contents: &[u8]) -> Result<&[u8], DeserializeError> {
+    let corrupted = |reason: &str| DeserializeError::CorruptedBinary(reason.to_string());
+
+    let Some(payload_len) = contents.len().checked_sub(FOOTER_LEN) else {
+        return Err(corrupted("the cached artifact is truncated"));
+    };
+    let (payload, footer) = contents.split_at(payload_len);
+    let (checksum_bytes, rest) = footer.split_at(8);
+    let (len_bytes, magic) = rest.split_at(8);
+
+    if magic != FOOTER_MAGIC {
+        return Err(corrupted("the cached artifact has no footer"));
+    }
+    if u64::from_le_bytes(len_bytes.try_into().unwrap()) != payload.len() as u64 {
+        return Err(corrupted("the cached artifact has the wrong length"));
+    }
+    if checksum_bytes != checksum(payload) {
+        return Err(corrupted("the cached artifact has the wrong checksum"));
+    }
+    Ok(payload)
+}
+
+fn write_and_sync(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let mut file = File::create(path)?;
+    file.write_all(contents)?;
+    file.sync_all()
+}
+
+/// Writes `contents` to `path` without readers ever seeing a partial file.
+fn write_atomically(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let dir = path.parent().unwrap_or_else(|| Path::new("."));
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    // The temporary file lives in the same directory so it can be renamed
+    // over the artifact, and its name is unique so concurrent writers don't
+    // trample on each other
+    let temp = dir.join(format!(
+        ".{name}.{}-{}.tmp",
+        std::process::id(),
+        TEMP_FILE_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+
+    let result = write_and_sync(&temp, contents).and_then(|()| std::fs::rename(&temp, path));
+
+    if result.is_err() {
+        let _ = std::fs::remove_file(&temp);
+        // Renaming onto a file that is open can fail on Windows. As the
+        // artifact for a key is always the same, someone else's copy is as
+        // good as ours.
+        if strip_footer(&std::fs::read(path).unwrap_or_default()).is_ok() {
+            return Ok(());
+        }
+    }
+    result
 }
 
 #[cfg(feature = "filesystem")]
@@ -97,13 +319,9 @@ impl Cache for FileSystemCache {
         engine: &impl AsEngineRef,
         key: Hash,
     ) -> Result<Module, Self::DeserializeError> {
-        let filename = if let Some(ref ext) = self.ext {
-            format!("{key}.{ext}")
-        } else {
-            key.to_string()
-        };
-        let path = self.path.join(filename);
-        let ret = Module::deserialize_from_file(engine, path.clone());
+        let path = self.path.join(self.filename(key));
+        let contents = std::fs::read(&path)?;
+        let ret = strip_footer(&contents).and_then(|payload| Module::deserialize(engine, payload));
         if ret.is_err() {
             // If an error occurs while deserializing then we can not trust it anymore
             // so delete the cache file
@@ -113,16 +331,9 @@ impl Cache for FileSystemCache {
     }
 
     fn store(&mut self, key: Hash, module: &Module) -> Result<(), Self::SerializeError> {
-        let filename = if let Some(ref ext) = self.ext {
-            format!("{key}.{ext}")
-        } else {
-            key.to_string()
-        };
-        let path = self.path.join(filename);
-        let mut file = File::create(path)?;
-
-        let buffer = module.serialize()?;
-        file.write_all(&buffer)?;
+        let path = self.path.join(self.filename(key));
+        let buffer = append_footer(module.serialize()?.to_vec());
+        write_atomically(&path, &buffer)?;
 
         Ok(())
     }
@@ -148,4 +359,116 @@ mod tests {
         cache.store(key, &module).unwrap();
         let _restored = unsafe { cache.load(&engine, key).unwrap() };
     }
+
+    fn cached_module() -> (tempfile::TempDir, FileSystemCache, wasmer::Engine, Hash) {
+        let dir = tempfile::tempdir().unwrap();
+        let mut cache = FileSystemCache::new(dir.path()).unwrap();
+        let engine = wasmer::Engine::default();
+        let bytes = include_bytes!("../../wasix/tests/envvar.wasm");
+        let module = Module::from_binary(&engine, bytes).unwrap();
+        let key = Hash::generate(bytes);
+        cache.store(key, &module).unwrap();
+        (dir, cache, engine, key)
+    }
+
+    #[test]
+    fn torn_writes_are_cache_misses() {
+        let (dir, cache, engine, key) = cached_module();
+        let path = dir.path().join(key.to_string());
+        let len = path.metadata().unwrap().len();
+
+        let file = OpenOptions::new().write(true).open(&path).unwrap();
+        file.set_len(len / 2).unwrap();
+        drop(file);
+
+        let err = unsafe { cache.load(&engine, key).unwrap_err() };
+        assert!(matches!(err, DeserializeError::CorruptedBinary(_)), "{err}");
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn checksum_mismatches_are_cache_misses() {
+        let (dir, cache, engine, key) = cached_module();
+        let path = dir.path().join(key.to_string());
+
+        let mut contents = std::fs::read(&path).unwrap();
+        contents[0] ^= 0xff;
+        std::fs::write(&path, contents).unwrap();
+
+        let err = unsafe { cache.load(&engine, key).unwrap_err() };
+        assert!(matches!(err, DeserializeError::CorruptedBinary(_)), "{err}");
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn concurrent_stores_and_loads() {
+        let (dir, cache, engine, key) = cached_module();
+        let module = unsafe { cache.load(&engine, key).unwrap() };
+
+        std::thread::scope(|s| {
+            for _ in 0..8 {
+                let (mut cache, engine, module) = (cache.clone(), engine.clone(), module.clone());
+                s.spawn(move || {
+                    for _ in 0..20 {
+                        cache.store(key, &module).unwrap();
+                        unsafe { cache.load(&engine, key).unwrap() };
+                    }
+                });
+            }
+        });
+
+        // No temporary files are left behind
+        let files: Vec<_> = std::fs::read_dir(dir.path()).unwrap().collect();
+        assert_eq!(files.len(), 1);
+    }
+
+    #[test]
+    fn only_one_thread_compiles() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = FileSystemCache::new(dir.path()).unwrap();
+        let engine = wasmer::Engine::default();
+        let bytes = include_bytes!("../../wasix/tests/envvar.wasm");
+        let key = Hash::generate(bytes);
+        let compilations = std::sync::atomic::AtomicUsize::new(0);
+
+        std::thread::scope(|s| {
+            for _ in 0..8 {
+                let (mut cache, engine, compilations) =
+                    (cache.clone(), engine.clone(), &compilations);
+                s.spawn(move || unsafe {
+                    cache
+                        .load_or_compile(&engine, key, || {
+                            compilations.fetch_add(1, Ordering::SeqCst);
+                            Module::from_binary(&engine, bytes)
+                        })
+                        .unwrap();
+                });
+            }
+        });
+
+        assert_eq!(compilations.load(Ordering::SeqCst), 1);
+        assert!(!dir.path().join(format!("{key}.lock")).exists());
+    }
+
+    #[test]
+    fn waiters_give_up_after_the_timeout() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut cache = FileSystemCache::new(dir.path()).unwrap();
+        cache.set_claim_timeout(Duration::from_millis(50));
+        let engine = wasmer::Engine::default();
+        let bytes = include_bytes!("../../wasix/tests/envvar.wasm");
+        let key = Hash::generate(bytes);
+
+        // A process that claimed the key and never finished
+        let _claim = Claim::acquire(&dir.path().join(format!("{key}.lock")))
+            .unwrap()
+            .unwrap();
+
+        unsafe {
+            cache
+                .load_or_compile(&engine, key, || Module::from_binary(&engine, bytes))
+                .unwrap();
+        }
+        assert!(unsafe { cache.load(&engine, key).is_ok() });
+    }
 }
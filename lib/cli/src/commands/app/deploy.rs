@@ -143,6 +143,7 @@ impl CmdAppDeploy {
             package_name: None,
             package_version: None,
             no_validate: false,
+            allow_warnings: true,
             package_path: manifest_dir_path.clone(),
             wait: match self.no_wait {
                 true => PublishWait::None,
@@ -154,9 +155,13 @@ impl CmdAppDeploy {
             bump: self.bump,
         };
 
-        publish_cmd
+        let publish = crate::events::sink().phase("publish");
+        let ident = publish_cmd
             .publish(client, &manifest_path, &manifest, true)
-            .await
+            .await?;
+        publish.finish();
+
+        Ok(ident)
     }
 
     async fn get_owner(
@@ -229,7 +234,10 @@ impl CmdAppDeploy {
 impl AsyncCliCommand for CmdAppDeploy {
     type Output = ();
 
-    async fn run_async(self) -> Result<Self::Output, anyhow::Error> {
+    async fn run_async(mut self) -> Result<Self::Output, anyhow::Error> {
+        let events = crate::events::sink();
+        self.quiet |= events.is_enabled();
+
         let client = login_user(&self.env, !self.non_interactive, "deploy an app").await?;
 
         let base_dir_path = self.dir.clone().unwrap_or_else(|| {
@@ -568,7 +576,9 @@ impl AsyncCliCommand for CmdAppDeploy {
             eprintln!("\nDeploying app {pretty_name} to Wasmer Edge...\n");
         }
 
+        let deploy = events.phase("deploy");
         let app_version = deploy_app(&client, opts.clone()).await?;
+        deploy.finish();
 
         let mut new_app_config = app_config_from_api(&app_version)?;
 
@@ -595,7 +605,12 @@ impl AsyncCliCommand for CmdAppDeploy {
             })?;
         }
 
-        wait_app(&client, opts.clone(), app_version.clone(), self.quiet).await?;
+        let wait = events.phase("wait");
+        let (app, _) = wait_app(&client, opts.clone(), app_version.clone(), self.quiet).await?;
+        wait.finish();
+
+        events.record("app", &app.url);
+        events.record("version", &app_version.url);
 
         if self.fmt.format == Some(crate::utils::render::ItemFormat::Json) {
             println!("{}", serde_json::to_string_pretty(&app_version)?);
@@ -693,111 +708,122 @@ pub async fn wait_app(
         eprintln!("→ Dashboard:  {}", app.admin_url);
     }
 
-    match wait {
-        WaitMode::Deployed => {}
-        WaitMode::Reachable => {
+    if wait == WaitMode::Reachable {
+        let check_url = if make_default { &app.url } else { &version.url };
+        wait_reachable(check_url, version.id.inner(), quiet).await?;
+    }
+
+    Ok((app, version))
+}
+
+/// Poll `check_url` until it is served by the app version `version_id`.
+pub async fn wait_reachable(
+    check_url: &str,
+    version_id: &str,
+    quiet: bool,
+) -> Result<(), anyhow::Error> {
+    if !quiet {
+        eprintln!();
+        eprintln!("Waiting for new deployment to become available...");
+        eprintln!("(You can safely stop waiting now with CTRL-C)");
+    }
+
+    let stderr = std::io::stderr();
+
+    tokio::time::sleep(Duration::from_secs(2)).await;
+
+    let start = tokio::time::Instant::now();
+    let client = reqwest::Client::builder()
+        .connect_timeout(Duration::from_secs(10))
+        .timeout(Duration::from_secs(90))
+        // Should not follow redirects.
+        .redirect(reqwest::redirect::Policy::none())
+        .build()
+        .unwrap();
+
+    let mut sleep_millis: u64 = 1_000;
+    loop {
+        let total_elapsed = start.elapsed();
+        if total_elapsed > Duration::from_secs(60 * 5) {
             if !quiet {
                 eprintln!();
-                eprintln!("Waiting for new deployment to become available...");
-                eprintln!("(You can safely stop waiting now with CTRL-C)");
             }
+            anyhow::bail!("\nApp still not reachable after 5 minutes...");
+        }
 
-            let stderr = std::io::stderr();
-
-            tokio::time::sleep(Duration::from_secs(2)).await;
+        {
+            let mut lock = stderr.lock();
 
-            let start = tokio::time::Instant::now();
-            let client = reqwest::Client::builder()
-                .connect_timeout(Duration::from_secs(10))
-                .timeout(Duration::from_secs(90))
-                // Should not follow redirects.
-                .redirect(reqwest::redirect::Policy::none())
-                .build()
-                .unwrap();
+            if !quiet {
+                write!(&mut lock, ".").unwrap();
+            }
+            lock.flush().unwrap();
+        }
 
-            let check_url = if make_default { &app.url } else { &version.url };
+        let request_start = tokio::time::Instant::now();
 
-            let mut sleep_millis: u64 = 1_000;
-            loop {
-                let total_elapsed = start.elapsed();
-                if total_elapsed > Duration::from_secs(60 * 5) {
-                    if !quiet {
-                        eprintln!();
-                    }
-                    anyhow::bail!("\nApp still not reachable after 5 minutes...");
-                }
+        tracing::debug!(%check_url, "checking health of app");
+        match client.get(check_url).send().await {
+            Ok(res) => {
+                let header = res
+                    .headers()
+                    .get(&EDGE_HEADER_APP_VERSION_ID)
+                    .and_then(|x| x.to_str().ok())
+                    .unwrap_or_default();
 
-                {
-                    let mut lock = stderr.lock();
+                tracing::debug!(
+                    %check_url,
+                    status=res.status().as_u16(),
+                    app_version_header=%header,
+                    "app request response received",
+                );
 
+                if header == version_id {
                     if !quiet {
-                        write!(&mut lock, ".").unwrap();
+                        eprintln!();
                     }
-                    lock.flush().unwrap();
-                }
-
-                let request_start = tokio::time::Instant::now();
-
-                tracing::debug!(%check_url, "checking health of app");
-                match client.get(check_url).send().await {
-                    Ok(res) => {
-                        let header = res
-                            .headers()
-                            .get(&EDGE_HEADER_APP_VERSION_ID)
-                            .and_then(|x| x.to_str().ok())
-                            .unwrap_or_default();
-
-                        tracing::debug!(
-                            %check_url,
-                            status=res.status().as_u16(),
-                            app_version_header=%header,
-                            "app request response received",
+                    let events = crate::events::sink();
+                    if !(res.status().is_success() || res.status().is_redirection()) {
+                        let message = format!(
+                            "The app version was deployed correctly, but fails with a non-success status code of {}",
+                            res.status()
                         );
-
-                        if header == version.id.inner() {
-                            if !quiet {
-                                eprintln!();
-                            }
-                            if !(res.status().is_success() || res.status().is_redirection()) {
-                                eprintln!(
-                                    "{}",
-                                    format!(
-                                        "The app version was deployed correctly, but fails with a non-success status code of {}",
-                                        res.status()).yellow()
-                                );
-                            } else {
-                                eprintln!("{} Deployment complete", "𖥔".yellow().bold());
-                            }
-
-                            break;
+                        if events.is_enabled() {
+                            events.warning(message);
+                        } else {
+                            eprintln!("{}", message.yellow());
                         }
-
-                        tracing::debug!(
-                            current=%header,
-                            expected=%version.id.inner(),
-                            "app is not at the right version yet",
-                        );
-                    }
-                    Err(err) => {
-                        tracing::debug!(?err, "health check request failed");
+                    } else if !events.is_enabled() {
+                        eprintln!("{} Deployment complete", "𖥔".yellow().bold());
                     }
-                };
 
-                // Increase the sleep time between requests, up
-                // to a reasonable maximum.
-                let elapsed: u64 = request_start
-                    .elapsed()
-                    .as_millis()
-                    .try_into()
-                    .unwrap_or_default();
-                let to_sleep = Duration::from_millis(sleep_millis.saturating_sub(elapsed));
-                tokio::time::sleep(to_sleep).await;
-                sleep_millis = (sleep_millis * 2).max(10_000);
+                    break;
+                }
+
+                tracing::debug!(
+                    current=%header,
+                    expected=%version_id,
+                    "app is not at the right version yet",
+                );
             }
-        }
+            Err(err) => {
+                tracing::debug!(?err, "health check request failed");
+            }
+        };
+
+        // Increase the sleep time between requests, up
+        // to a reasonable maximum.
+        let elapsed: u64 = request_start
+            .elapsed()
+            .as_millis()
+            .try_into()
+            .unwrap_or_default();
+        let to_sleep = Duration::from_millis(sleep_millis.saturating_sub(elapsed));
+        tokio::time::sleep(to_sleep).await;
+        sleep_millis = (sleep_millis * 2).max(10_000);
     }
 
-    Ok((app, version))
+    Ok(())
 }
 
 pub fn app_config_from_api(version: &DeployAppVersion) -> Result<AppConfigV1, anyhow::Error> {
@@ -11,6 +11,7 @@ pub mod list;
 pub mod logs;
 pub mod purge_cache;
 pub mod regions;
+pub mod rollback;
 pub mod secrets;
 pub mod version;
 pub mod volumes;
@@ -31,7 +32,8 @@ pub enum CmdApp {
     Logs(logs::CmdAppLogs),
     PurgeCache(purge_cache::CmdAppPurgeCache),
     Delete(delete::CmdAppDelete),
-    #[clap(subcommand)]
+    Rollback(rollback::CmdAppRollback),
+    #[clap(subcommand, alias = "versions")]
     Version(version::CmdAppVersion),
     #[clap(subcommand, alias = "secrets")]
     Secret(secrets::CmdAppSecrets),
@@ -67,6 +69,7 @@ impl AsyncCliCommand for CmdApp {
             Self::Logs(cmd) => cmd.run_async().await,
             Self::Delete(cmd) => cmd.run_async().await,
             Self::Version(cmd) => cmd.run_async().await,
+            Self::Rollback(cmd) => cmd.run_async().await,
             Self::Deploy(cmd) => cmd.run_async().await,
             Self::PurgeCache(cmd) => cmd.run_async().await,
             Self::Secret(cmd) => cmd.run_async().await,
@@ -0,0 +1,318 @@
+//! Roll an app back to an earlier version.
+
+use colored::Colorize;
+use comfy_table::Table;
+use time::OffsetDateTime;
+use wasmer_backend_api::types::DeployAppVersion;
+
+use super::{deploy::wait_reachable, version::activate::activate_version};
+use crate::{
+    commands::{app::util::AppIdentOpts, AsyncCliCommand},
+    config::WasmerEnv,
+    opts::ItemFormatOpts,
+    utils::render::{CliRender, ItemFormat},
+};
+
+/// Roll an app back to its previous version, or to a specific one.
+#[derive(clap::Parser, Debug)]
+pub struct CmdAppRollback {
+    #[clap(flatten)]
+    pub env: WasmerEnv,
+
+    #[clap(flatten)]
+    pub fmt: ItemFormatOpts,
+
+    /// The version to activate, instead of the one before the active version.
+    ///
+    /// Either the unique version ID (eg: dav_xYzaB1aaaaax) or the version name.
+    /// See `wasmer app version list` for the versions of an app.
+    #[clap(long)]
+    pub to: Option<String>,
+
+    /// Do not wait for the app to serve the version.
+    #[clap(long)]
+    pub no_wait: bool,
+
+    /// Don't print any message.
+    #[clap(long)]
+    pub quiet: bool,
+
+    #[clap(flatten)]
+    #[allow(missing_docs)]
+    pub ident: AppIdentOpts,
+}
+
+/// The outcome of a rollback.
+#[derive(serde::Serialize, Debug, Clone)]
+pub(crate) struct Rollback {
+    /// The version that was active before.
+    pub from: Option<DeployAppVersion>,
+    /// The version that is active now.
+    pub to: DeployAppVersion,
+}
+
+impl CliRender for Rollback {
+    fn render_item_table(&self) -> String {
+        let mut table = Table::new();
+        table.set_header(vec![
+            "".to_string(),
+            "Version name".to_string(),
+            "Created".to_string(),
+            "Id".to_string(),
+        ]);
+        for (label, version) in [("From", self.from.as_ref()), ("To", Some(&self.to))] {
+            let Some(version) = version else {
+                continue;
+            };
+            table.add_row(vec![
+                label.to_string(),
+                version.version.clone(),
+                version.created_at.0.clone(),
+                version.id.inner().to_string(),
+            ]);
+        }
+        table.to_string()
+    }
+
+    fn render_list_table(items: &[Self]) -> String {
+        items
+            .iter()
+            .map(|item| item.render_item_table())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+#[async_trait::async_trait]
+impl AsyncCliCommand for CmdAppRollback {
+    type Output = ();
+
+    async fn run_async(self) -> Result<(), anyhow::Error> {
+        let client = self.env.client()?;
+        let (_ident, app) = self.ident.load_app(&client).await?;
+        let context = || {
+            format!(
+                "Could not roll back app '{}/{}'",
+                app.owner.global_name, app.name
+            )
+        };
+
+        let mut versions = wasmer_backend_api::query::all_app_versions(
+            &client,
+            app.owner.global_name.clone(),
+            app.name.clone(),
+        )
+        .await?;
+        sort_newest_first(&mut versions);
+
+        let active = app
+            .active_version
+            .as_ref()
+            .map(|version| version.id.inner().to_string());
+        let from = active
+            .as_deref()
+            .and_then(|active| versions.iter().find(|v| v.id.inner() == active))
+            .cloned();
+        let target = rollback_target(&versions, active.as_deref(), self.to.as_deref())
+            .map_err(|err| err.context(context()))?;
+
+        let (_, to) = activate_version(&client, target.id.inner().to_string())
+            .await
+            .map_err(|err| err.context(context()))?;
+        if to.id != target.id {
+            anyhow::bail!(
+                "Failed to activate version: backend activated '{}' instead of '{}'",
+                to.id.inner(),
+                target.id.inner()
+            );
+        }
+
+        if !self.quiet {
+            eprintln!(
+                "Activated version '{}' (id: {}) of app {} ({})",
+                to.version.bold(),
+                to.id.inner(),
+                app.name.bold(),
+                app.owner.global_name.bold(),
+            );
+        }
+
+        if !self.no_wait {
+            wait_reachable(&app.url, to.id.inner(), self.quiet).await?;
+        }
+
+        println!(
+            "{}",
+            self.fmt
+                .get_with_default(ItemFormat::Table)
+                .render(&Rollback { from, to })
+        );
+
+        Ok(())
+    }
+}
+
+/// Sort `versions` by creation time, newest first.
+fn sort_newest_first(versions: &mut [DeployAppVersion]) {
+    versions.sort_by_cached_key(|version| {
+        std::cmp::Reverse(OffsetDateTime::try_from(version.created_at.clone()).ok())
+    });
+}
+
+/// The version to activate: `to` (a version id or name), or the version
+/// before `active` if not given.
+///
+/// `versions` must be sorted newest first.
+fn rollback_target<'a>(
+    versions: &'a [DeployAppVersion],
+    active: Option<&str>,
+    to: Option<&str>,
+) -> anyhow::Result<&'a DeployAppVersion> {
+    let target = match to {
+        Some(to) => find_version(versions, to)?,
+        None => {
+            let Some(from) =
+                active.and_then(|active| versions.iter().position(|v| v.id.inner() == active))
+            else {
+                anyhow::bail!(
+                    "the app has no active version; pick the version to activate with --to \
+                     (see `wasmer app version list`)"
+                );
+            };
+            versions.get(from + 1).ok_or_else(|| {
+                anyhow::anyhow!(
+                    "there is no version before the active version '{}' to roll back to",
+                    versions[from].version
+                )
+            })?
+        }
+    };
+
+    if active == Some(target.id.inner()) {
+        anyhow::bail!(
+            "version '{}' (id: {}) is already the active version",
+            target.version,
+            target.id.inner()
+        );
+    }
+
+    Ok(target)
+}
+
+/// Find a version by its id or, failing that, by its name.
+fn find_version<'a>(
+    versions: &'a [DeployAppVersion],
+    to: &str,
+) -> anyhow::Result<&'a DeployAppVersion> {
+    if let Some(version) = versions.iter().find(|v| v.id.inner() == to) {
+        return Ok(version);
+    }
+
+    let named: Vec<_> = versions.iter().filter(|v| v.version == to).collect();
+    match named.as_slice() {
+        [] => anyhow::bail!(
+            "the app has no version with the id or name '{to}' (see `wasmer app version list`)"
+        ),
+        [version] => Ok(version),
+        _ => {
+            let ids: Vec<_> = named.iter().map(|v| v.id.inner()).collect();
+            anyhow::bail!(
+                "there are {} versions named '{to}'; pass one of their ids to --to instead: {}",
+                ids.len(),
+                ids.join(", ")
+            )
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use wasmer_backend_api::types::{DateTime, Id};
+
+    use super::*;
+
+    /// The versions `v1` to `vN` (ids `dav_1` to `dav_N`), oldest first.
+    fn versions(count: usize) -> Vec<DeployAppVersion> {
+        (1..=count)
+            .map(|i| DeployAppVersion {
+                id: Id::new(format!("dav_{i}")),
+                created_at: DateTime(format!("2024-01-{i:02}T00:00:00Z")),
+                updated_at: DateTime(format!("2024-01-{i:02}T00:00:00Z")),
+                version: format!("v{i}"),
+                description: None,
+                yaml_config: String::new(),
+                user_yaml_config: String::new(),
+                config: String::new(),
+                json_config: String::new(),
+                url: String::new(),
+                disabled_at: None,
+                disabled_reason: None,
+                app: None,
+            })
+            .collect()
+    }
+
+    fn newest_first(count: usize) -> Vec<DeployAppVersion> {
+        let mut versions = versions(count);
+        sort_newest_first(&mut versions);
+        versions
+    }
+
+    #[test]
+    fn versions_are_sorted_newest_first() {
+        let versions = newest_first(12);
+
+        let ids: Vec<_> = versions.iter().map(|v| v.id.inner()).collect();
+        assert_eq!(ids[..3], ["dav_12", "dav_11", "dav_10"]);
+        assert_eq!(ids[11], "dav_1");
+    }
+
+    #[test]
+    fn rollback_to_the_previous_version() {
+        let versions = newest_first(25);
+
+        let target = rollback_target(&versions, Some("dav_25"), None).unwrap();
+        assert_eq!(target.id.inner(), "dav_24");
+
+        // The active version doesn't have to be the newest one
+        let target = rollback_target(&versions, Some("dav_16"), None).unwrap();
+        assert_eq!(target.id.inner(), "dav_15");
+    }
+
+    #[test]
+    fn rollback_to_an_explicit_version() {
+        let versions = newest_first(25);
+
+        let by_id = rollback_target(&versions, Some("dav_25"), Some("dav_3")).unwrap();
+        assert_eq!(by_id.id.inner(), "dav_3");
+
+        let by_name = rollback_target(&versions, Some("dav_3"), Some("v20")).unwrap();
+        assert_eq!(by_name.id.inner(), "dav_20");
+    }
+
+    #[test]
+    fn rollback_without_a_previous_version() {
+        let versions = newest_first(3);
+
+        let err = rollback_target(&versions, Some("dav_1"), None).unwrap_err();
+        assert!(err.to_string().contains("no version before"), "{err}");
+
+        let err = rollback_target(&versions, None, None).unwrap_err();
+        assert!(err.to_string().contains("--to"), "{err}");
+    }
+
+    #[test]
+    fn rollback_to_an_unknown_or_ambiguous_version() {
+        let mut versions = newest_first(3);
+        versions[1].version = "v1".to_string();
+
+        let err = rollback_target(&versions, Some("dav_3"), Some("v9")).unwrap_err();
+        assert!(err.to_string().contains("no version"), "{err}");
+
+        let err = rollback_target(&versions, Some("dav_3"), Some("v1")).unwrap_err();
+        assert!(err.to_string().contains("dav_2, dav_1"), "{err}");
+
+        let err = rollback_target(&versions, Some("dav_3"), Some("dav_3")).unwrap_err();
+        assert!(err.to_string().contains("already the active"), "{err}");
+    }
+}
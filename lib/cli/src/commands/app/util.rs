@@ -328,6 +328,8 @@ pub(super) async fn login_user(
                     cache_dir: env.cache_dir().to_path_buf(),
                     token: None,
                     registry: env.registry.clone(),
+                    check: false,
+                    force: false,
                 }
                 .run_async()
                 .await?;
@@ -1,3 +1,8 @@
+use wasmer_backend_api::{
+    types::{DeployApp, DeployAppVersion},
+    WasmerClient,
+};
+
 use crate::{commands::AsyncCliCommand, config::WasmerEnv, opts::ItemFormatOpts};
 
 /// Switch the active version of an app. (rollback / rollforward)
@@ -23,11 +28,7 @@ impl AsyncCliCommand for CmdAppVersionActivate {
     async fn run_async(self) -> Result<(), anyhow::Error> {
         let client = self.env.client()?;
 
-        let app = wasmer_backend_api::query::app_version_activate(&client, self.version).await?;
-
-        let Some(version) = &app.active_version else {
-            anyhow::bail!("Failed to activate version: backend did not update version!");
-        };
+        let (app, version) = activate_version(&client, self.version).await?;
 
         eprintln!(
             "Changed active version of app '{}/{}' from '{}' to '{}' (id: {})",
@@ -41,3 +42,19 @@ impl AsyncCliCommand for CmdAppVersionActivate {
         Ok(())
     }
 }
+
+/// Make `version` (a unique version ID) the active version of its app.
+///
+/// Returns the app and the version it now serves.
+pub(crate) async fn activate_version(
+    client: &WasmerClient,
+    version: String,
+) -> Result<(DeployApp, DeployAppVersion), anyhow::Error> {
+    let app = wasmer_backend_api::query::app_version_activate(client, version).await?;
+
+    let Some(version) = app.active_version.clone() else {
+        anyhow::bail!("Failed to activate version: backend did not update version!");
+    };
+
+    Ok((app, version))
+}
@@ -10,7 +10,16 @@ use crate::{
 };
 use futures_util::{stream::FuturesUnordered, StreamExt};
 use std::{path::PathBuf, time::Duration};
-use wasmer_backend_api::{types::Nonce, WasmerClient};
+use wasmer_backend_api::{
+    query::TokenCheck,
+    types::{Nonce, User},
+    WasmerClient,
+};
+
+/// The exit code of `wasmer login --check` when the registry rejects the token.
+const EXIT_INVALID_TOKEN: i32 = 3;
+/// The exit code of `wasmer login --check` when the registry can't be reached.
+const EXIT_REGISTRY_UNREACHABLE: i32 = 4;
 
 #[derive(Debug, Clone)]
 enum AuthorizationState {
@@ -44,6 +53,16 @@ pub struct Login {
     /// Change the current registry
     #[clap(long, env = "WASMER_REGISTRY")]
     pub registry: Option<UserRegistry>,
+
+    /// Check that the token is valid instead of logging in, without saving
+    /// anything. Exits with 3 if the registry rejects the token, and with 4
+    /// if the registry can't be reached
+    #[clap(long)]
+    pub check: bool,
+
+    /// Replace the token of a different user that is already logged in
+    #[clap(long)]
+    pub force: bool,
 }
 
 impl Login {
@@ -200,6 +219,65 @@ impl Login {
         }
     }
 
+    /// Asks the registry whether the token, either the one given or the
+    /// saved one, is valid.
+    async fn check_token(&self, env: &WasmerEnv) -> anyhow::Result<TokenCheck> {
+        let client = env.client_unauthennticated()?;
+        if client.auth_token().is_none() {
+            return Ok(TokenCheck::Invalid {
+                reason: "no token provided".to_string(),
+            });
+        }
+
+        wasmer_backend_api::query::check_token(&client).await
+    }
+
+    /// The user whose token is saved for the registry, if it is still valid.
+    async fn saved_user(&self) -> Option<User> {
+        let env = WasmerEnv::new(
+            self.wasmer_dir.clone(),
+            self.cache_dir.clone(),
+            None,
+            self.registry.clone(),
+        );
+        let client = env.client().ok()?;
+        wasmer_backend_api::query::current_user(&client)
+            .await
+            .ok()
+            .flatten()
+    }
+
+    /// Makes sure saving `token` doesn't log out a different user, unless
+    /// `--force` was passed.
+    async fn ensure_same_user(&self, env: &WasmerEnv, token: &str) -> anyhow::Result<()> {
+        if self.force {
+            return Ok(());
+        }
+        let Some(saved) = self.saved_user().await else {
+            return Ok(());
+        };
+
+        let client = env
+            .client_unauthennticated()?
+            .with_auth_token(token.to_string());
+        let user = wasmer_backend_api::query::current_user(&client)
+            .await
+            .ok()
+            .flatten();
+        if user.is_some_and(|user| user.username == saved.username) {
+            return Ok(());
+        }
+
+        anyhow::bail!(
+            "You are already logged in as {} in registry {}. Use --force to replace the token.",
+            saved.username.bold(),
+            env.registry_public_url()?
+                .host_str()
+                .unwrap_or_default()
+                .bold()
+        )
+    }
+
     async fn login_and_save(&self, env: &WasmerEnv, token: String) -> anyhow::Result<String> {
         let registry = env.registry_endpoint()?;
         let mut config = WasmerConfig::from_file(env.dir())
@@ -242,6 +320,38 @@ impl AsyncCliCommand for Login {
     async fn run_async(self) -> Result<Self::Output, anyhow::Error> {
         let env = self.get_wasmer_env();
 
+        if self.check {
+            let host = env
+                .registry_public_url()?
+                .host_str()
+                .unwrap_or_default()
+                .bold();
+            match self.check_token(&env).await {
+                Ok(TokenCheck::Valid(user)) => {
+                    println!(
+                        "{} Token for Wasmer user {} is valid in registry {host}",
+                        "✔".green().bold(),
+                        user.username.bold()
+                    );
+                    return Ok(());
+                }
+                Ok(TokenCheck::Invalid { reason }) => {
+                    eprintln!(
+                        "{} Token rejected by registry {host}: {reason}",
+                        "✖".red().bold()
+                    );
+                    std::process::exit(EXIT_INVALID_TOKEN);
+                }
+                Err(e) => {
+                    eprintln!(
+                        "{} Unable to check the token with registry {host}: {e:?}",
+                        "✖".red().bold()
+                    );
+                    std::process::exit(EXIT_REGISTRY_UNREACHABLE);
+                }
+            }
+        }
+
         let auth_state = match &self.token {
             Some(token) => AuthorizationState::TokenSuccess(token.clone()),
             None => self.do_login(&env).await?,
@@ -249,6 +359,7 @@ impl AsyncCliCommand for Login {
 
         match auth_state {
             AuthorizationState::TokenSuccess(token) => {
+                self.ensure_same_user(&env, &token).await?;
                 match self.login_and_save(&env, token).await {
                     Ok(s) => {
                         print!("Done!");
@@ -293,6 +404,8 @@ mod tests {
             wasmer_dir: temp.path().to_path_buf(),
             token: None,
             cache_dir: temp.path().join("cache").to_path_buf(),
+            check: false,
+            force: false,
         };
         let env = login.get_wasmer_env();
 
@@ -321,6 +434,8 @@ mod tests {
             wasmer_dir: temp.path().to_path_buf(),
             token: Some("abc".to_string()),
             cache_dir: temp.path().join("cache").to_path_buf(),
+            check: false,
+            force: false,
         };
         let env = login.get_wasmer_env();
 
@@ -371,6 +486,159 @@ mod tests {
         assert_eq!(wasmer_env_token_help, login_token_help);
     }
 
+    /// Serves a fake registry on a local port, answering every request with
+    /// the status and body `respond` returns for its token.
+    fn mock_registry(respond: fn(Option<&str>) -> (u16, &'static str)) -> UserRegistry {
+        use std::io::{BufRead, BufReader, Read, Write};
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(stream) = stream else { continue };
+                let mut reader = BufReader::new(&stream);
+                let mut token = None;
+                let mut content_length = 0;
+                loop {
+                    let mut line = String::new();
+                    if reader.read_line(&mut line).unwrap_or(0) == 0 || line.trim().is_empty() {
+                        break;
+                    }
+                    let Some((name, value)) = line.split_once(':') else {
+                        continue;
+                    };
+                    let value = value.trim();
+                    if name.eq_ignore_ascii_case("authorization") {
+                        token = value.strip_prefix("Bearer ").map(str::to_string);
+                    } else if name.eq_ignore_ascii_case("content-length") {
+                        content_length = value.parse().unwrap();
+                    }
+                }
+                let mut body = vec![0; content_length];
+                reader.read_exact(&mut body).unwrap();
+
+                let (status, body) = respond(token.as_deref());
+                write!(
+                    &stream,
+                    "HTTP/1.1 {status} Mock\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{body}",
+                    body.len()
+                )
+                .unwrap();
+            }
+        });
+
+        format!("http://{addr}").into()
+    }
+
+    fn user_for_token(token: Option<&str>) -> (u16, &'static str) {
+        match token {
+            Some("alice-token") => (
+                200,
+                r#"{"data": {"viewer": {"id": "u_1", "username": "alice"}}}"#,
+            ),
+            Some("bob-token") => (
+                200,
+                r#"{"data": {"viewer": {"id": "u_2", "username": "bob"}}}"#,
+            ),
+            _ => (
+                401,
+                r#"{"data": null, "errors": [{"message": "Token has expired"}]}"#,
+            ),
+        }
+    }
+
+    fn login_to(registry: UserRegistry, wasmer_dir: &std::path::Path, token: &str) -> Login {
+        Login {
+            no_browser: true,
+            wasmer_dir: wasmer_dir.to_path_buf(),
+            cache_dir: wasmer_dir.join("cache"),
+            token: Some(token.to_string()),
+            registry: Some(registry),
+            check: false,
+            force: false,
+        }
+    }
+
+    fn check(login: &Login) -> anyhow::Result<TokenCheck> {
+        let env = login.get_wasmer_env();
+        tokio::runtime::Runtime::new()
+            .unwrap()
+            .block_on(login.check_token(&env))
+    }
+
+    #[test]
+    fn check_valid_token() {
+        let temp = TempDir::new().unwrap();
+        let login = login_to(mock_registry(user_for_token), temp.path(), "alice-token");
+
+        match check(&login).unwrap() {
+            TokenCheck::Valid(user) => assert_eq!(user.username, "alice"),
+            other => panic!("unexpected result: {other:?}"),
+        }
+        // Checking doesn't save the token
+        assert!(!temp.path().join("wasmer.toml").exists());
+    }
+
+    #[test]
+    fn check_expired_token() {
+        let temp = TempDir::new().unwrap();
+        let login = login_to(mock_registry(user_for_token), temp.path(), "expired-token");
+
+        match check(&login).unwrap() {
+            TokenCheck::Invalid { reason } => assert!(reason.contains("Token has expired")),
+            other => panic!("unexpected result: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn check_unreachable_registry() {
+        let temp = TempDir::new().unwrap();
+        // Nothing listens on the port once the listener is dropped
+        let addr = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let login = login_to(format!("http://{addr}").into(), temp.path(), "alice-token");
+
+        assert!(check(&login).is_err());
+    }
+
+    #[test]
+    fn login_does_not_replace_another_users_token() {
+        let temp = TempDir::new().unwrap();
+        let registry = mock_registry(user_for_token);
+        login_to(registry.clone(), temp.path(), "alice-token")
+            .run()
+            .unwrap();
+        let saved_token = || {
+            let env = WasmerEnv::new(
+                temp.path().to_path_buf(),
+                temp.path().join("cache"),
+                None,
+                Some(registry.clone()),
+            );
+            env.token()
+        };
+
+        let error = login_to(registry.clone(), temp.path(), "bob-token")
+            .run()
+            .unwrap_err();
+        assert!(
+            error.to_string().contains("already logged in as"),
+            "{error}"
+        );
+        assert_eq!(saved_token().as_deref(), Some("alice-token"));
+
+        // The same user can log in again, and --force replaces the token
+        login_to(registry.clone(), temp.path(), "alice-token")
+            .run()
+            .unwrap();
+        let mut login = login_to(registry.clone(), temp.path(), "bob-token");
+        login.force = true;
+        login.run().unwrap();
+        assert_eq!(saved_token().as_deref(), Some("bob-token"));
+    }
+
     /// Regression test for panics on API errors.
     /// See https://github.com/wasmerio/wasmer/issues/4147.
     #[test]
@@ -381,6 +649,8 @@ mod tests {
             registry: Some("http://localhost:11".to_string().into()),
             token: Some("invalid".to_string()),
             cache_dir: crate::config::DEFAULT_WASMER_CACHE_DIR.clone(),
+            check: false,
+            force: false,
         };
 
         let res = cmd.run();
@@ -0,0 +1,330 @@
+use std::{
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+use anyhow::{bail, Context, Result};
+use clap::{Parser, ValueEnum};
+use comfy_table::Table;
+use wasmer::*;
+use wasmer_types::target::Target;
+use wasmer_wasix::{is_wasi_module, virtual_fs::NullFile, WasiEnv, WasiError};
+
+use super::run::parse_value;
+use crate::{backend::BackendType, backend::RuntimeOptions, opts::ListFormatOpts};
+
+#[derive(Debug, Parser)]
+/// The options for the `wasmer bench` subcommand
+pub struct Bench {
+    /// File to benchmark
+    #[clap(name = "FILE")]
+    path: PathBuf,
+
+    /// The exported function to benchmark (defaults to `_start`)
+    #[clap(long, name = "FUNCTION")]
+    invoke: Option<String>,
+
+    /// The compilers to benchmark with (defaults to the default compiler)
+    #[clap(long, value_enum, value_delimiter = ',')]
+    compiler: Vec<BenchCompiler>,
+
+    /// Number of timed iterations
+    #[clap(long, default_value_t = 10)]
+    iterations: usize,
+
+    /// Number of untimed iterations to run before measuring
+    #[clap(long, default_value_t = 1)]
+    warmup: usize,
+
+    #[clap(flatten)]
+    fmt: ListFormatOpts,
+
+    /// Arguments passed to the invoked function
+    #[clap(allow_negative_numbers = true)]
+    args: Vec<String>,
+}
+
+/// A compiler that can be selected with `--compiler`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum BenchCompiler {
+    /// Every compiler included in this binary
+    All,
+    Cranelift,
+    Llvm,
+    Singlepass,
+}
+
+impl BenchCompiler {
+    fn backend(self) -> Option<BackendType> {
+        match self {
+            BenchCompiler::All => None,
+            BenchCompiler::Cranelift => Some(BackendType::Cranelift),
+            BenchCompiler::Llvm => Some(BackendType::LLVM),
+            BenchCompiler::Singlepass => Some(BackendType::Singlepass),
+        }
+    }
+}
+
+/// The results of benchmarking a module with a single compiler.
+#[derive(Debug, serde::Serialize)]
+pub struct BenchResult {
+    pub compiler: String,
+    pub function: String,
+    pub compile_ns: u64,
+    pub artifact_size: u64,
+    pub instantiate: Timings,
+    pub call: Timings,
+}
+
+/// Summary statistics for a set of timed iterations, in nanoseconds.
+#[derive(Debug, serde::Serialize)]
+pub struct Timings {
+    pub samples: usize,
+    pub min_ns: u64,
+    pub mean_ns: u64,
+    pub p50_ns: u64,
+    pub p95_ns: u64,
+    pub max_ns: u64,
+}
+
+impl Timings {
+    fn from_samples(mut samples: Vec<Duration>) -> Self {
+        samples.sort();
+        let nanos = |d: Duration| d.as_nanos().try_into().unwrap_or(u64::MAX);
+        // Nearest-rank percentile over the sorted samples.
+        let percentile = |p: usize| {
+            let rank = (samples.len() * p).div_ceil(100).max(1);
+            nanos(samples[rank - 1])
+        };
+        let total: Duration = samples.iter().sum();
+
+        Timings {
+            samples: samples.len(),
+            min_ns: nanos(samples[0]),
+            mean_ns: nanos(total / samples.len() as u32),
+            p50_ns: percentile(50),
+            p95_ns: percentile(95),
+            max_ns: nanos(samples[samples.len() - 1]),
+        }
+    }
+}
+
+impl crate::utils::render::CliRender for BenchResult {
+    fn render_item_table(&self) -> String {
+        Self::render_list_table(std::slice::from_ref(self))
+    }
+
+    fn render_list_table(items: &[Self]) -> String {
+        let ns = |ns: u64| format!("{:?}", Duration::from_nanos(ns));
+
+        let mut table = Table::new();
+        table.load_preset(comfy_table::presets::UTF8_FULL_CONDENSED);
+        table.set_header(vec![
+            "Compiler", "Compile", "Artifact", "Phase", "Mean", "p50", "p95",
+        ]);
+        for item in items {
+            let phases = [
+                ("instantiate", &item.instantiate),
+                (&*item.function, &item.call),
+            ];
+            for (i, (phase, timings)) in phases.into_iter().enumerate() {
+                let (compiler, compile, size) = if i == 0 {
+                    (
+                        item.compiler.clone(),
+                        ns(item.compile_ns),
+                        bytesize::ByteSize(item.artifact_size).to_string(),
+                    )
+                } else {
+                    Default::default()
+                };
+                table.add_row(vec![
+                    compiler,
+                    compile,
+                    size,
+                    phase.to_string(),
+                    ns(timings.mean_ns),
+                    ns(timings.p50_ns),
+                    ns(timings.p95_ns),
+                ]);
+            }
+        }
+        table.to_string()
+    }
+}
+
+impl Bench {
+    /// Runs logic for the `bench` subcommand
+    pub fn execute(&self) -> Result<()> {
+        let results = self
+            .inner_execute()
+            .context(format!("failed to benchmark `{}`", self.path.display()))?;
+        println!("{}", self.fmt.format.render(&results));
+        Ok(())
+    }
+
+    fn inner_execute(&self) -> Result<Vec<BenchResult>> {
+        if self.iterations == 0 {
+            bail!("--iterations must be at least 1");
+        }
+
+        let contents = std::fs::read(&self.path)?;
+        // Parse the text format up front so it doesn't count towards the
+        // compile time.
+        let wasm = wat2wasm(&contents)?;
+
+        let rt = RuntimeOptions::default();
+        let target = Target::default();
+        let features = rt.detect_features_from_wasm(&wasm).unwrap_or_default();
+
+        // WASI modules need a tokio runtime to set up their environment.
+        let tokio = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()?;
+        let _guard = tokio.enter();
+
+        self.backends()?
+            .into_iter()
+            .map(|backend| {
+                let engine = backend.get_engine(&target, &features, &rt)?;
+                self.bench_engine(&engine, &wasm)
+                    .map(|mut result| {
+                        result.compiler = backend.to_string();
+                        result
+                    })
+                    .with_context(|| format!("benchmarking with {backend} failed"))
+            })
+            .collect()
+    }
+
+    fn backends(&self) -> Result<Vec<BackendType>> {
+        let compilers: Vec<BackendType> = BackendType::enabled()
+            .into_iter()
+            .filter(|backend| {
+                matches!(
+                    backend,
+                    BackendType::Cranelift | BackendType::LLVM | BackendType::Singlepass
+                )
+            })
+            .collect();
+
+        if self.compiler.is_empty() {
+            return match compilers.first() {
+                Some(backend) => Ok(vec![*backend]),
+                None => bail!("This binary doesn't include any compilers"),
+            };
+        }
+        if self.compiler.contains(&BenchCompiler::All) {
+            return Ok(compilers);
+        }
+
+        let mut backends = Vec::new();
+        for backend in self.compiler.iter().filter_map(|c| c.backend()) {
+            if !compilers.contains(&backend) {
+                bail!("The `{backend}` compiler is not included in this binary.");
+            }
+            if !backends.contains(&backend) {
+                backends.push(backend);
+            }
+        }
+        Ok(backends)
+    }
+
+    fn bench_engine(&self, engine: &Engine, wasm: &[u8]) -> Result<BenchResult> {
+        let start = Instant::now();
+        let module = Module::new(engine, wasm)?;
+        let compile_time = start.elapsed();
+        let artifact_size = module.serialize()?.len() as u64;
+
+        let function = self.invoke.as_deref().unwrap_or("_start");
+        let func_ty = module
+            .exports()
+            .functions()
+            .find(|f| f.name() == function)
+            .map(|f| f.ty().clone())
+            .with_context(|| {
+                format!("The module doesn't export a function named \"{function}\"")
+            })?;
+
+        anyhow::ensure!(
+            func_ty.params().len() == self.args.len(),
+            "Function expected {} arguments, but received {}",
+            func_ty.params().len(),
+            self.args.len(),
+        );
+        let args = self
+            .args
+            .iter()
+            .zip(func_ty.params())
+            .map(|(arg, ty)| parse_value(arg, *ty))
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut instantiate = Vec::with_capacity(self.iterations);
+        let mut call = Vec::with_capacity(self.iterations);
+
+        for i in 0..self.warmup + self.iterations {
+            // Every iteration gets a fresh instance so that calls which
+            // mutate global state (or exit, like WASI's `_start`) stay
+            // comparable.
+            let (instantiate_time, call_time) = self.run_once(engine, &module, function, &args)?;
+            if i >= self.warmup {
+                instantiate.push(instantiate_time);
+                call.push(call_time);
+            }
+        }
+
+        Ok(BenchResult {
+            compiler: String::new(),
+            function: function.to_string(),
+            compile_ns: compile_time.as_nanos().try_into().unwrap_or(u64::MAX),
+            artifact_size,
+            instantiate: Timings::from_samples(instantiate),
+            call: Timings::from_samples(call),
+        })
+    }
+
+    fn run_once(
+        &self,
+        engine: &Engine,
+        module: &Module,
+        function: &str,
+        args: &[Value],
+    ) -> Result<(Duration, Duration)> {
+        let mut store = Store::new(engine.clone());
+
+        let start = Instant::now();
+        let (instance, wasi_env) = if is_wasi_module(module) {
+            let program_name = self.path.display().to_string();
+            let (instance, env) = WasiEnv::builder(program_name)
+                .engine(engine.clone())
+                .stdin(Box::<NullFile>::default())
+                .stdout(Box::<NullFile>::default())
+                .stderr(Box::<NullFile>::default())
+                .instantiate(module.clone(), &mut store)?;
+            (instance, Some(env))
+        } else {
+            let instance = Instance::new(&mut store, module, &Imports::default())
+                .context("Unable to instantiate the WebAssembly module")?;
+            (instance, None)
+        };
+        let instantiate_time = start.elapsed();
+
+        let func = instance.exports.get_function(function)?;
+        let start = Instant::now();
+        let result = func.call(&mut store, args);
+        let call_time = start.elapsed();
+
+        if let Some(env) = wasi_env {
+            env.on_exit(&mut store, None);
+        }
+
+        match result {
+            Ok(_) => {}
+            Err(e) => match e.downcast_ref::<WasiError>() {
+                Some(WasiError::Exit(code)) if code.is_success() => {}
+                _ => return Err(e.into()),
+            },
+        }
+
+        Ok((instantiate_time, call_time))
+    }
+}
@@ -1,4 +1,4 @@
-use std::path::PathBuf;
+use std::{path::PathBuf, sync::Arc};
 
 use anyhow::{bail, Context, Result};
 use clap::Parser;
@@ -6,20 +6,39 @@ use wasmer::{
     sys::{engine::NativeEngineExt, *},
     *,
 };
+use wasmer_package::utils::from_disk;
+use wasmer_wasix::{
+    bin_factory::BinaryPackage,
+    runtime::{module_cache::PrecompiledArtifactsBuilder, task_manager::VirtualTaskManagerExt},
+    Runtime,
+};
 
-use crate::{backend::RuntimeOptions, common::HashAlgorithm, warning};
+use crate::{
+    backend::RuntimeOptions, commands::run::Wasi, common::HashAlgorithm, config::WasmerEnv, warning,
+};
 
 #[derive(Debug, Parser)]
 /// The options for the `wasmer compile` subcommand
 pub struct Compile {
-    /// Input file
+    #[clap(flatten)]
+    env: WasmerEnv,
+
+    /// Input file (a WebAssembly module or a package)
     #[clap(name = "FILE")]
     path: PathBuf,
 
     /// Output file
+    ///
+    /// For packages, this is a directory containing one artifact per module
+    /// and an index, which can be passed to `wasmer run --precompiled`.
     #[clap(name = "OUTPUT PATH", short = 'o')]
     output: PathBuf,
 
+    /// Write the artifacts of a package to a single file instead of a
+    /// directory
+    #[clap(long)]
+    bundle: bool,
+
     /// Compilation Target triple
     #[clap(long = "target")]
     target_triple: Option<Triple>,
@@ -62,8 +81,11 @@ impl Compile {
             .unwrap_or_default();
 
         let module_contents = std::fs::read(&self.path)?;
+        if webc::detect(module_contents.as_slice()).is_ok() {
+            return self.compile_package(&target);
+        }
         if !is_wasm(&module_contents) {
-            bail!("`wasmer compile` only compiles WebAssembly files");
+            bail!("`wasmer compile` only compiles WebAssembly files and packages");
         }
 
         let mut engine = self.rt.get_engine_for_module(&module_contents, &target)?;
@@ -88,15 +110,121 @@ impl Compile {
                 warning!("the output file has no extension. We recommend using `{}.{}` for the chosen target", &output_filename, &recommended_extension)
             }
         }
-        println!("Compiler: {}", engine.deterministic_id());
-        println!("Target: {}", target.triple());
+        let events = crate::events::sink();
+        if events.is_enabled() {
+            events.record("compiler", engine.deterministic_id());
+            events.record("target", target.triple().to_string());
+        } else {
+            println!("Compiler: {}", engine.deterministic_id());
+            println!("Target: {}", target.triple());
+        }
 
+        let compile = events.phase("compile");
         let module = Module::new(&engine, &module_contents)?;
+        compile.finish();
+
+        if let Some(stats) = module.stats() {
+            if events.is_enabled() {
+                events.record("stats", stats);
+            } else {
+                super::inspect::print_stats(&stats);
+            }
+        }
+
+        let write = events.phase("write");
         module.serialize_to_file(&self.output)?;
-        eprintln!(
-            "✔ File compiled successfully to `{}`.",
-            self.output.display(),
-        );
+        write.finish();
+
+        if events.is_enabled() {
+            events.record("output", &self.output);
+        } else {
+            eprintln!(
+                "✔ File compiled successfully to `{}`.",
+                self.output.display(),
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Compiles the modules of every command in a package (including its
+    /// dependencies).
+    fn compile_package(&self, target: &Target) -> Result<()> {
+        let engine = self.rt.get_engine(target)?;
+        let events = crate::events::sink();
+        if events.is_enabled() {
+            events.record("compiler", engine.deterministic_id());
+            events.record("target", target.triple().to_string());
+        } else {
+            println!("Compiler: {}", engine.deterministic_id());
+            println!("Target: {}", target.triple());
+        }
+
+        let tokio_rt = tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .build()?;
+        let handle = tokio_rt.handle().clone();
+        let _guard = handle.enter();
+        let runtime: Arc<dyn Runtime + Send + Sync> = Arc::new(Wasi::prepare_package_runtime(
+            &self.env,
+            engine.clone(),
+            handle,
+        )?);
+
+        let load = events.phase("load-package");
+        let container = from_disk(&self.path)?;
+        let inner_runtime = runtime.clone();
+        let pkg = runtime
+            .task_manager()
+            .spawn_and_block_on(async move {
+                BinaryPackage::from_webc(&container, inner_runtime.as_ref()).await
+            })?
+            .context("Unable to load the package")?;
+        load.finish();
+
+        let compile = events.phase("compile");
+        let mut artifacts = PrecompiledArtifactsBuilder::new(&engine);
+        let total = pkg.commands.len() as u64;
+        for (done, cmd) in pkg.commands.iter().enumerate() {
+            if artifacts.add_command(*cmd.hash(), cmd.name()) {
+                continue;
+            }
+
+            let message = format!("Compiling \"{}\"", cmd.name());
+            if events.is_enabled() {
+                events.progress(Some((done as u64, total)), Some(message));
+            } else {
+                println!("{message}");
+            }
+            let engine =
+                runtime.engine_with_suggested_opts(&cmd.suggested_compiler_optimizations)?;
+            let module = Module::new(&engine, cmd.atom())
+                .with_context(|| format!("Unable to compile the \"{}\" command", cmd.name()))?;
+            artifacts.add(*cmd.hash(), cmd.name(), &module)?;
+        }
+        events.progress(Some((total, total)), None);
+        compile.finish();
+
+        let write = events.phase("write");
+        let index = if self.bundle {
+            artifacts.write_bundle(&self.output)?
+        } else {
+            artifacts.write_dir(&self.output)?
+        };
+        write.finish();
+
+        if events.is_enabled() {
+            events.record("output", &self.output);
+            events.record("modules", index.artifacts.len());
+            events.record("commands", pkg.commands.len());
+        } else {
+            eprintln!(
+                "✔ Compiled {} modules for {} commands to `{}`.",
+                index.artifacts.len(),
+                pkg.commands.len(),
+                self.output.display(),
+            );
+        }
 
         Ok(())
     }
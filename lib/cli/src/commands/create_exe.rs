@@ -18,7 +18,7 @@ use std::{
     process::{Command, Stdio},
 };
 use tar::Archive;
-use target_lexicon::BinaryFormat;
+use target_lexicon::{BinaryFormat, Environment};
 use wasmer::{
     sys::{engine::NativeEngineExt, *},
     *,
@@ -26,6 +26,7 @@ use wasmer::{
 use wasmer_compiler::{
     object::{emit_serialized, get_object_for_target},
     types::symbols::{ModuleMetadataSymbolRegistry, Symbol, SymbolRegistry},
+    StripOutcome,
 };
 use wasmer_package::utils::from_disk;
 use wasmer_types::ModuleInfo;
@@ -106,6 +107,15 @@ pub struct CreateExe {
     /// Hashing algorithm to be used for module hash
     #[clap(long, value_enum)]
     hash_algorithm: Option<HashAlgorithm>,
+
+    /// Only keep the code that the given exported function can reach (can
+    /// be repeated).
+    ///
+    /// Other functions are replaced by a trap, so calling them, even
+    /// indirectly, fails with a "stripped function" error. The executable
+    /// usually needs `_start`.
+    #[clap(long = "only-export", name = "EXPORT")]
+    only_exports: Vec<String>,
 }
 
 /// Url or version to download the release from
@@ -157,6 +167,11 @@ pub(crate) struct CrossCompile {
     /// Specify `zig` binary path (defaults to `zig` in $PATH if not present)
     #[clap(long = "zig-binary-path", env)]
     zig_binary_path: Option<PathBuf>,
+
+    /// The Windows SDK and MSVC CRT to link against for arm64 Windows, as
+    /// created by `xwin --accept-license --arch aarch64 splat --output <DIR>`
+    #[clap(long = "winsdk-path", env)]
+    winsdk_path: Option<PathBuf>,
 }
 
 #[derive(Debug)]
@@ -164,6 +179,17 @@ pub(crate) struct CrossCompileSetup {
     pub(crate) target: Triple,
     pub(crate) zig_binary_path: Option<PathBuf>,
     pub(crate) library: PathBuf,
+    pub(crate) msvc_toolchain: Option<MsvcToolchain>,
+}
+
+/// The tools used to link for `aarch64-pc-windows-msvc`, which zig can't
+/// target because it doesn't ship the MSVC runtime libraries.
+#[derive(Debug, Clone)]
+pub(crate) struct MsvcToolchain {
+    pub(crate) clang: PathBuf,
+    pub(crate) lld_link: PathBuf,
+    /// The Windows SDK and MSVC CRT, in the layout created by `xwin splat`
+    pub(crate) winsdk: PathBuf,
 }
 
 /// Given a pirita file, determines whether the file has one
@@ -224,6 +250,8 @@ impl CliCommand for CreateExe {
             None => None,
         };
 
+        let events = crate::events::sink();
+        let setup = events.phase("setup");
         let cross_compilation = utils::get_cross_compile_setup(
             &self.env,
             &mut cc,
@@ -231,6 +259,7 @@ impl CliCommand for CreateExe {
             &starting_cd,
             url_or_version,
         )?;
+        setup.finish();
 
         if input_path.is_dir() {
             return Err(anyhow::anyhow!("input path cannot be a directory"));
@@ -242,12 +271,18 @@ impl CliCommand for CreateExe {
         let hash_algorithm = self.hash_algorithm.unwrap_or_default().into();
         engine.set_hash_algorithm(Some(hash_algorithm));
 
-        println!("Compiler: {}", engine.deterministic_id());
-        println!("Target: {}", target.triple());
-        println!(
-            "Using path `{}` as libwasmer path.",
-            cross_compilation.library.display()
-        );
+        if events.is_enabled() {
+            events.record("compiler", engine.deterministic_id());
+            events.record("target", target.triple().to_string());
+            events.record("libwasmer", &cross_compilation.library);
+        } else {
+            println!("Compiler: {}", engine.deterministic_id());
+            println!("Target: {}", target.triple());
+            println!(
+                "Using path `{}` as libwasmer path.",
+                cross_compilation.library.display()
+            );
+        }
 
         if !cross_compilation.library.exists() {
             return Err(anyhow::anyhow!("library path does not exist"));
@@ -260,6 +295,7 @@ impl CliCommand for CreateExe {
         };
         std::fs::create_dir_all(&tempdir)?;
 
+        let compile = events.phase("compile");
         let atoms = if let Ok(pirita) = from_disk(&input_path) {
             // pirita file
             compile_pirita_into_directory(
@@ -270,6 +306,7 @@ impl CliCommand for CreateExe {
                 &cross_compilation.target,
                 &self.precompiled_atom,
                 AllowMultiWasm::Allow,
+                &self.only_exports,
                 self.debug_dir.is_some(),
             )
         } else {
@@ -281,10 +318,13 @@ impl CliCommand for CreateExe {
                 &cross_compilation.target,
                 &self.cpu_features,
                 &self.precompiled_atom,
+                &self.only_exports,
                 self.debug_dir.is_some(),
             )
         }?;
+        compile.finish();
 
+        let link = events.phase("link");
         get_module_infos(&engine, &tempdir, &atoms)?;
         let mut entrypoint = get_entrypoint(&tempdir)?;
         create_header_files_in_dir(
@@ -304,8 +344,11 @@ impl CliCommand for CreateExe {
             &atoms,
             &self.precompiled_atom,
         )?;
+        link.finish();
 
-        if self.target_triple.is_some() {
+        if events.is_enabled() {
+            events.record("output", &self.output);
+        } else if self.target_triple.is_some() {
             eprintln!(
                 "✔ Cross-compiled executable for `{}` target compiled successfully to `{}`.",
                 target.triple(),
@@ -378,6 +421,7 @@ pub(super) fn compile_pirita_into_directory(
     triple: &Triple,
     prefixes: &[String],
     allow_multi_wasm: AllowMultiWasm,
+    only_exports: &[String],
     debug: bool,
 ) -> anyhow::Result<Vec<(String, Vec<u8>)>> {
     let all_atoms = match &allow_multi_wasm {
@@ -459,6 +503,7 @@ pub(super) fn compile_pirita_into_directory(
         compiler,
         target,
         &prefix_map,
+        only_exports,
         debug,
     )?;
 
@@ -812,12 +857,112 @@ fn test_split_prefix() {
     );
 }
 
+#[cfg(feature = "cranelift")]
+#[test]
+fn only_exports_strip_the_code_of_other_entry_points() {
+    use object::{Object, ObjectSymbol};
+
+    /// Two entry points, `a` and `b`, each with a helper that does some work.
+    const MODULE: &str = r#"
+(module
+    (func $a (export "a") (param i32) (result i32)
+        (call $helper_a (local.get 0)))
+    (func $helper_a (param $n i32) (result i32)
+        (local $acc i32)
+        (loop $next
+            (local.set $acc (i32.add (i32.mul (local.get $acc) (i32.const 31)) (local.get $n)))
+            (local.set $n (i32.sub (local.get $n) (i32.const 1)))
+            (br_if $next (local.get $n)))
+        (local.get $acc))
+    (func $b (export "b") (param i32) (result i32)
+        (call $helper_b (local.get 0)))
+    (func $helper_b (param $n i32) (result i32)
+        (local $acc i32)
+        (loop $next
+            (local.set $acc (i32.xor (i32.rotl (local.get $acc) (i32.const 7)) (local.get $n)))
+            (local.set $n (i32.sub (local.get $n) (i32.const 1)))
+            (br_if $next (local.get $n)))
+        (local.get $acc)))
+"#;
+
+    /// The size of every function in the object compiled with `only_exports`.
+    fn function_sizes(wasm: &[u8], only_exports: &[&str]) -> Vec<u64> {
+        let dir = tempfile::tempdir().unwrap();
+        let atoms = vec![("main".to_string(), wasm.to_vec())];
+        let prefixes =
+            PrefixMapCompilation::from_input(&atoms, &["test".to_string()], false).unwrap();
+        let only_exports: Vec<String> = only_exports.iter().map(|s| s.to_string()).collect();
+        compile_atoms(
+            &atoms,
+            dir.path(),
+            &RuntimeOptions::default(),
+            &Target::default(),
+            &prefixes,
+            &only_exports,
+            false,
+        )
+        .unwrap();
+
+        let bytes = std::fs::read(dir.path().join("main.o")).unwrap();
+        let obj = object::File::parse(&*bytes).unwrap();
+        (0..4)
+            .map(|i| {
+                let name = format!("wasmer_function_test_{i}");
+                obj.symbols()
+                    .find(|symbol| {
+                        symbol
+                            .name()
+                            .is_ok_and(|n| n.trim_start_matches('_') == name)
+                    })
+                    .unwrap_or_else(|| panic!("no symbol {name}"))
+                    .size()
+            })
+            .collect()
+    }
+
+    let wasm = wat::parse_str(MODULE).unwrap();
+    let full = function_sizes(&wasm, &[]);
+    let only_a = function_sizes(&wasm, &["a"]);
+    let only_b = function_sizes(&wasm, &["b"]);
+
+    // `a` and its helper are compiled as usual, `b`'s helper is a stub
+    assert_eq!(only_a[..2], full[..2]);
+    assert!(only_a[3] < full[3], "{only_a:?} vs {full:?}");
+    // And the other way around
+    assert_eq!(only_b[2..], full[2..]);
+    assert!(only_b[1] < full[1], "{only_b:?} vs {full:?}");
+
+    // Every function is in the table, and called through it
+    let indirect = wat::parse_str(
+        r#"
+(module
+    (type $t (func (param i32) (result i32)))
+    (table funcref (elem $one $two $three))
+    (func $dispatch (export "dispatch") (param i32) (result i32)
+        (call_indirect (type $t) (local.get 0) (local.get 0)))
+    (func $one (param i32) (result i32) (i32.add (local.get 0) (i32.const 1)))
+    (func $two (param i32) (result i32) (i32.mul (local.get 0) (i32.const 2)))
+    (func $three (param i32) (result i32) (i32.shl (local.get 0) (i32.const 3))))
+"#,
+    )
+    .unwrap();
+    assert_eq!(
+        function_sizes(&indirect, &["dispatch"]),
+        function_sizes(&indirect, &[])
+    );
+}
+
+/// Compiles every atom into an object file in `output_dir`.
+///
+/// If `only_exports` isn't empty, only the code those exports can reach is
+/// kept, see [`StripOutcome`].
 fn compile_atoms(
     atoms: &[(String, Vec<u8>)],
     output_dir: &Path,
     compiler: &RuntimeOptions,
     target: &Target,
     prefixes: &PrefixMapCompilation,
+    only_exports: &[String],
     debug: bool,
 ) -> Result<BTreeMap<String, ModuleInfo>, anyhow::Error> {
     use std::{
@@ -845,14 +990,24 @@ fn compile_atoms(
         let compiler = engine_inner.compiler()?;
         let features = engine_inner.features();
         let tunables = engine.tunables();
-        let (module_info, obj, _, _) = Artifact::generate_object(
+        let entries = (!only_exports.is_empty()).then_some(only_exports);
+        let (module_info, obj, _, _, strip_outcome) = Artifact::generate_object_for_entries(
             compiler,
             data,
             Some(prefix.as_str()),
             target,
             tunables,
             features,
-        )?;
+            entries,
+        )
+        .with_context(|| format!("could not compile atom {a:?}"))?;
+        match strip_outcome {
+            Some(StripOutcome::Stripped(report)) => println!("Atom {a:?}: {report}."),
+            Some(StripOutcome::Skipped { reason }) => {
+                eprintln!("Warning: atom {a:?} was not stripped, as {reason}.")
+            }
+            None => {}
+        }
         module_infos.insert(atom_name, module_info);
         // Write object file with functions
         let mut writer = BufWriter::new(File::create(&output_object_path)?);
@@ -956,6 +1111,7 @@ fn write_volume_obj(
 
 /// Given a .wasm file, compiles the .wasm file into the target directory and creates the entrypoint.json
 #[allow(clippy::too_many_arguments)]
+#[allow(clippy::too_many_arguments)]
 pub(super) fn prepare_directory_from_single_wasm_file(
     wasm_file: &Path,
     target_dir: &Path,
@@ -963,6 +1119,7 @@ pub(super) fn prepare_directory_from_single_wasm_file(
     triple: &Triple,
     cpu_features: &[CpuFeature],
     prefix: &[String],
+    only_exports: &[String],
     debug: bool,
 ) -> anyhow::Result<Vec<(String, Vec<u8>)>, anyhow::Error> {
     let bytes = std::fs::read(wasm_file)?;
@@ -1004,6 +1161,7 @@ pub(super) fn prepare_directory_from_single_wasm_file(
         compiler,
         target,
         &prefix_map,
+        only_exports,
         debug,
     )?;
 
@@ -1216,6 +1374,20 @@ fn link_exe_from_dir(
     include_dirs.sort();
     include_dirs.dedup();
 
+    if let Some(toolchain) = cross_compilation.msvc_toolchain.as_ref() {
+        return link_objects_lld_link(
+            toolchain,
+            library_path,
+            &directory.join("wasmer_main.c"),
+            &include_dirs,
+            &object_paths,
+            &cross_compilation.target,
+            additional_libraries,
+            &output_path,
+            debug,
+        );
+    }
+
     // On Windows, cross-compilation to Windows itself with zig does not work due
     // to libunwind and libstdc++ not compiling, so we fake this special case of cross-compilation
     // by falling back to the system compilation + system linker
@@ -1322,6 +1494,9 @@ fn link_exe_from_dir(
     cmd.arg("-OReleaseSafe");
     cmd.arg("-fno-compiler-rt");
     cmd.arg("-fno-lto");
+    if cross_compilation.target.environment == Environment::Musl {
+        cmd.arg("-static");
+    }
     #[cfg(target_os = "windows")]
     let out_path = directory.join("wasmer_main.exe");
     #[cfg(not(target_os = "windows"))]
@@ -1464,7 +1639,11 @@ fn link_objects_system_linker(
         additional_libraries.extend(LINK_SYSTEM_LIBRARIES_UNIX.iter().map(|s| s.to_string()));
     }
     let link_against_extra_libs = additional_libraries.iter().map(|lib| format!("-l{lib}"));
-    let command = command.args(link_against_extra_libs);
+    let mut command = command.args(link_against_extra_libs);
+    // Otherwise the executable still needs the host's (glibc) dynamic loader
+    if target.environment == Environment::Musl {
+        command = command.arg("-static");
+    }
     let command = command.arg("-o").arg(output_path);
     if debug {
         println!("{command:#?}");
@@ -1482,6 +1661,140 @@ fn link_objects_system_linker(
     Ok(())
 }
 
+/// Compile `wasmer_main.c` with clang and link it with `lld-link`, against
+/// the Windows SDK and MSVC CRT shipped with the libwasmer tarball
+#[allow(clippy::too_many_arguments)]
+fn link_objects_lld_link(
+    toolchain: &MsvcToolchain,
+    libwasmer_path: &Path,
+    wasmer_main_c: &Path,
+    include_dirs: &[PathBuf],
+    object_paths: &[PathBuf],
+    target: &Triple,
+    additional_libraries: &[String],
+    output_path: &Path,
+    debug: bool,
+) -> Result<(), anyhow::Error> {
+    let libwasmer_path = libwasmer_path
+        .canonicalize()
+        .context("Failed to find libwasmer")?;
+    let winsdk = &toolchain.winsdk;
+    let arch = utils::msvc_arch_dir(target);
+    let msvc_target = format!("{}-pc-windows-msvc", target.architecture);
+
+    let mut include_path = libwasmer_path.clone();
+    include_path.pop();
+    include_path.pop();
+    include_path.push("include");
+    if !include_path.exists() {
+        // Can happen when we got the wrong library_path
+        return Err(anyhow::anyhow!("Wasmer include path {} does not exist, maybe library path {} is wrong (expected /lib/wasmer.lib)?", include_path.display(), libwasmer_path.display()));
+    }
+
+    // We must use a C++ compiler because wasm.h uses `static_assert`, see
+    // run_c_compile()
+    let wasmer_main_obj = wasmer_main_c.with_extension("obj");
+    let mut command = Command::new(&toolchain.clang);
+    let mut command = command
+        .arg(format!("--target={msvc_target}"))
+        .arg("-O2")
+        .arg("-c")
+        .arg("-fms-runtime-lib=dll")
+        .arg(wasmer_main_c)
+        .arg("-I")
+        .arg(&include_path);
+    for include_dir in include_dirs {
+        command = command.arg("-I");
+        command = command.arg(normalize_path(&format!("{}", include_dir.display())));
+    }
+    for dir in [
+        &["crt", "include"][..],
+        &["sdk", "include", "ucrt"],
+        &["sdk", "include", "um"],
+        &["sdk", "include", "shared"],
+    ] {
+        command = command
+            .arg("-isystem")
+            .arg(dir.iter().fold(winsdk.clone(), |path, dir| path.join(dir)));
+    }
+    let command = command.arg("-o").arg(&wasmer_main_obj);
+    if debug {
+        println!("{command:#?}");
+    }
+    let output = command
+        .output()
+        .with_context(|| format!("Could not execute `{}`", toolchain.clang.display()))?;
+    if !output.status.success() {
+        bail!(
+            "C code compile failed with command line:{:#?} stdout: {}\n\nstderr: {}",
+            command,
+            String::from_utf8_lossy(&output.stdout),
+            String::from_utf8_lossy(&output.stderr),
+        );
+    }
+
+    let mut command = Command::new(&toolchain.lld_link);
+    let mut command = command
+        .arg("/nologo")
+        .arg("/subsystem:console")
+        .arg(format!(
+            "/machine:{}",
+            if arch == "aarch64" { "arm64" } else { "x64" }
+        ))
+        .arg(format!(
+            "/libpath:{}",
+            winsdk.join("crt").join("lib").join(arch).display()
+        ))
+        .arg(format!(
+            "/libpath:{}",
+            winsdk
+                .join("sdk")
+                .join("lib")
+                .join("um")
+                .join(arch)
+                .display()
+        ))
+        .arg(format!(
+            "/libpath:{}",
+            winsdk
+                .join("sdk")
+                .join("lib")
+                .join("ucrt")
+                .join(arch)
+                .display()
+        ))
+        .args(object_paths.iter().map(|path| path.canonicalize().unwrap()))
+        .arg(&wasmer_main_obj)
+        .arg(&libwasmer_path);
+
+    // Add libraries required per platform, plus the dynamic CRT libwasmer was
+    // built against.
+    let link_against_libs = additional_libraries
+        .iter()
+        .map(|s| s.as_str())
+        .chain(LINK_SYSTEM_LIBRARIES_WINDOWS.iter().copied())
+        .chain(["ntdll", "msvcrt", "ucrt", "vcruntime"])
+        .map(|lib| format!("{lib}.lib"));
+    command = command.args(link_against_libs);
+    let command = command.arg(format!("/out:{}", output_path.display()));
+    if debug {
+        println!("{command:#?}");
+    }
+    let output = command
+        .output()
+        .with_context(|| format!("Could not execute `{}`", toolchain.lld_link.display()))?;
+
+    if !output.status.success() {
+        bail!(
+            "linking failed with command line:{:#?} stdout: {}\n\nstderr: {}",
+            command,
+            String::from_utf8_lossy(&output.stdout),
+            String::from_utf8_lossy(&output.stderr),
+        );
+    }
+    Ok(())
+}
+
 /// Generate the wasmer_main.c that links all object files together
 /// (depending on the object format / atoms number)
 fn generate_wasmer_main_c(
@@ -1623,7 +1936,7 @@ pub(super) mod utils {
 
     use crate::config::WasmerEnv;
 
-    use super::{CrossCompile, CrossCompileSetup, UrlOrVersion};
+    use super::{CrossCompile, CrossCompileSetup, MsvcToolchain, UrlOrVersion};
 
     pub(in crate::commands) fn target_triple_to_target(
         target_triple: &Triple,
@@ -1728,14 +2041,102 @@ pub(super) mod utils {
 
         let library = library.ok_or_else(|| anyhow!("libwasmer.a / wasmer.lib not found"))?;
 
+        // Check for the linker toolchain now rather than after compiling
+        let msvc_toolchain = if needs_msvc_toolchain(target) {
+            let winsdk = cross_subc.winsdk_path.as_ref().map(|p| starting_cd.join(p));
+            Some(find_msvc_toolchain(target, &library, winsdk)?)
+        } else {
+            None
+        };
+
         let ccs = CrossCompileSetup {
             target: target.clone(),
             zig_binary_path,
             library,
+            msvc_toolchain,
         };
         Ok(ccs)
     }
 
+    /// Whether linking for `target` goes through clang and `lld-link`
+    /// instead of zig or the system linker.
+    pub(super) fn needs_msvc_toolchain(target: &Triple) -> bool {
+        matches!(target.architecture, Architecture::Aarch64(_))
+            && target.operating_system == OperatingSystem::Windows
+    }
+
+    fn find_msvc_toolchain(
+        target: &Triple,
+        library: &Path,
+        winsdk: Option<PathBuf>,
+    ) -> Result<MsvcToolchain, anyhow::Error> {
+        if library.extension() != Some(OsStr::new("lib")) {
+            return Err(anyhow!(
+                "Linking for {target} needs an MSVC build of libwasmer (wasmer.lib), but `{}` was selected. \
+                 Use the \"windows-arm64\" libwasmer flavor, e.g. `--tarball wasmer-windows-arm64.tar.gz`.",
+                library.display()
+            ));
+        }
+
+        let clang = find_in_path("clang++").ok_or_else(|| {
+            anyhow!("Linking for {target} needs `clang++` in PATH to compile the executable's entrypoint. Install LLVM (https://releases.llvm.org) and try again.")
+        })?;
+        let lld_link = find_in_path("lld-link").ok_or_else(|| {
+            anyhow!("Linking for {target} needs `lld-link` in PATH. Install LLVM's lld (e.g. `apt install lld`) and try again.")
+        })?;
+
+        let arch = msvc_arch_dir(target);
+        let winsdk = winsdk.ok_or_else(|| {
+            anyhow!(
+                "Linking for {target} needs the Windows SDK and MSVC CRT for {arch}, which can't be \
+                 shipped with libwasmer. Create them with `xwin --accept-license --arch {arch} splat \
+                 --output <DIR>` and pass `--winsdk-path <DIR>`."
+            )
+        })?;
+        if !winsdk.join("crt").join("lib").join(arch).is_dir()
+            || !winsdk
+                .join("sdk")
+                .join("lib")
+                .join("um")
+                .join(arch)
+                .is_dir()
+        {
+            return Err(anyhow!(
+                "Linking for {target} needs the Windows SDK and MSVC CRT for {arch} in `{}`. Create \
+                 them with `xwin --accept-license --arch {arch} splat --output {}`.",
+                winsdk.display(),
+                winsdk.display(),
+            ));
+        }
+
+        Ok(MsvcToolchain {
+            clang,
+            lld_link,
+            winsdk,
+        })
+    }
+
+    /// The name `xwin` uses for the per-architecture library directories.
+    pub(super) fn msvc_arch_dir(target: &Triple) -> &'static str {
+        match target.architecture {
+            Architecture::Aarch64(_) => "aarch64",
+            Architecture::X86_32(_) => "x86",
+            _ => "x86_64",
+        }
+    }
+
+    fn find_in_path(name: &str) -> Option<PathBuf> {
+        let name = if cfg!(windows) {
+            format!("{name}.exe")
+        } else {
+            name.to_string()
+        };
+        let path_var = std::env::var_os("PATH")?;
+        std::env::split_paths(&path_var)
+            .map(|dir| dir.join(&name))
+            .find(|p| p.is_file())
+    }
+
     pub(super) fn filter_tarball(p: &Path, target: &Triple) -> bool {
         filter_tarball_internal(p, target).unwrap_or(false)
     }
@@ -1746,10 +2147,13 @@ pub(super) mod utils {
         // The filename scheme:
         // FILENAME := "wasmer-" [ FEATURE ] OS  PLATFORM  .
         // FEATURE  := "wamr-" | "v8-" | "wasmi-" .
-        // OS       := "darwin" | "linux" | "linux-musl" | "windows" .
-        // PLATFORM := "aarch64" | "amd64" | "gnu64" .
+        // OS       := "darwin" | "linux" | "linux-musl" | "linux-musl-static" | "windows" .
+        // PLATFORM := "aarch64" | "amd64" | "arm64" | "gnu64" .
         //
         // In this function we want to select only those version where features don't appear.
+        //
+        // Musl targets need the "linux-musl-static" flavor: the older "linux-musl" one still
+        // links against glibc.
 
         let filename = p.file_name()?.to_str()?;
 
@@ -1761,7 +2165,7 @@ pub(super) mod utils {
             return None;
         }
 
-        if target.environment == Environment::Musl && !filename.contains("musl")
+        if target.environment == Environment::Musl && !filename.contains("musl-static")
             || filename.contains("musl") && target.environment != Environment::Musl
         {
             return None;
@@ -2023,7 +2427,10 @@ pub(super) mod utils {
             "/test/wasmer-linux-aarch64.tar.gz",
             "/test/wasmer-linux-amd64.tar.gz",
             "/test/wasmer-linux-musl-amd64.tar.gz",
+            "/test/wasmer-linux-musl-static-amd64.tar.gz",
+            "/test/wasmer-linux-musl-static-aarch64.tar.gz",
             "/test/wasmer-windows-amd64.tar.gz",
+            "/test/wasmer-windows-arm64.tar.gz",
             "/test/wasmer-windows-gnu64.tar.gz",
             "/test/wasmer-windows.exe",
         ];
@@ -2129,6 +2536,39 @@ pub(super) mod utils {
                 .collect::<Vec<_>>(),
             vec![&Path::new("/test/wasmer-darwin-arm64.tar.gz")],
         );
+
+        assert_eq!(
+            paths
+                .iter()
+                .filter(|p| crate::commands::utils::filter_tarball(
+                    p,
+                    &Triple::from_str("x86_64-unknown-linux-musl").unwrap()
+                ))
+                .collect::<Vec<_>>(),
+            vec![&Path::new("/test/wasmer-linux-musl-static-amd64.tar.gz")],
+        );
+
+        assert_eq!(
+            paths
+                .iter()
+                .filter(|p| crate::commands::utils::filter_tarball(
+                    p,
+                    &Triple::from_str("aarch64-unknown-linux-musl").unwrap()
+                ))
+                .collect::<Vec<_>>(),
+            vec![&Path::new("/test/wasmer-linux-musl-static-aarch64.tar.gz")],
+        );
+
+        assert_eq!(
+            paths
+                .iter()
+                .filter(|p| crate::commands::utils::filter_tarball(
+                    p,
+                    &Triple::from_str("aarch64-pc-windows-msvc").unwrap()
+                ))
+                .collect::<Vec<_>>(),
+            vec![&Path::new("/test/wasmer-windows-arm64.tar.gz")],
+        );
     }
 
     #[test]
@@ -2240,10 +2680,16 @@ mod http_fetch {
                 if !entries.is_empty() {
                     cache_path.push(&entries[0]);
                     if cache_path.exists() {
-                        eprintln!(
+                        let message = format!(
                             "Using cached tarball to cache path `{}`.",
                             cache_path.display()
                         );
+                        let events = crate::events::sink();
+                        if events.is_enabled() {
+                            events.progress(None, Some(message));
+                        } else {
+                            eprintln!("{message}");
+                        }
                         return Ok(cache_path);
                     }
                 }
@@ -2298,6 +2744,7 @@ mod http_fetch {
         let download_path = download_tempdir.path().join(&filename);
 
         let mut file = std::fs::File::create(&download_path)?;
+        crate::events::sink().progress(None, Some(format!("Downloading {browser_download_url}")));
         log::debug!(
             "Downloading {} to {}",
             browser_download_url,
@@ -2323,7 +2770,7 @@ mod http_fetch {
             Ok(mut cache_path) => {
                 cache_path.push(&filename);
                 if let Err(err) = std::fs::copy(&download_path, &cache_path) {
-                    eprintln!(
+                    crate::warning!(
                         "Could not store tarball to cache path `{}`: {}",
                         cache_path.display(),
                         err
@@ -2334,12 +2781,19 @@ mod http_fetch {
                         cache_path.display()
                     ))
                 } else {
-                    eprintln!("Cached tarball to cache path `{}`.", cache_path.display());
+                    let message =
+                        format!("Cached tarball to cache path `{}`.", cache_path.display());
+                    let events = crate::events::sink();
+                    if events.is_enabled() {
+                        events.progress(None, Some(message));
+                    } else {
+                        eprintln!("{message}");
+                    }
                     Ok(cache_path)
                 }
             }
             Err(err) => {
-                eprintln!("Could not determine cache path for downloaded binaries.: {err}");
+                crate::warning!("Could not determine cache path for downloaded binaries.: {err}");
                 Err(anyhow!("Could not determine libwasmer cache path"))
             }
         }
@@ -54,6 +54,11 @@ pub struct CreateObj {
     #[clap(long, short = 'm', number_of_values = 1)]
     cpu_features: Vec<CpuFeature>,
 
+    /// Only keep the code that the given exported function can reach (can
+    /// be repeated), see `wasmer create-exe --help`.
+    #[clap(long = "only-export", name = "EXPORT")]
+    only_exports: Vec<String>,
+
     #[clap(flatten)]
     rt: RuntimeOptions,
 }
@@ -103,6 +108,7 @@ impl CreateObj {
                 &target_triple,
                 &prefix,
                 crate::commands::AllowMultiWasm::Reject(self.atom.clone()),
+                &self.only_exports,
                 self.debug_dir.is_some(),
             )
         } else {
@@ -113,6 +119,7 @@ impl CreateObj {
                 &target_triple,
                 &self.cpu_features,
                 &prefix,
+                &self.only_exports,
                 self.debug_dir.is_some(),
             )
         }?;
@@ -1,10 +1,14 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use crate::backend::RuntimeOptions;
 use anyhow::{Context, Result};
 use bytesize::ByteSize;
 use clap::Parser;
-use wasmer::*;
+use serde::Deserialize;
+use wasmer::{
+    interface::{check_module_against_manifest, ImportManifest, ManifestImport, ModuleInterface},
+    *,
+};
 use wasmer_types::target::Target;
 
 #[derive(Debug, Parser)]
@@ -14,6 +18,23 @@ pub struct Inspect {
     #[clap(name = "FILE")]
     path: PathBuf,
 
+    /// Compare the imports and exports of FILE against OTHER
+    #[clap(long, requires = "OTHER")]
+    diff: bool,
+
+    /// The file FILE is compared against when using --diff
+    #[clap(name = "OTHER", requires = "diff")]
+    other: Option<PathBuf>,
+
+    /// Print statistics about the code generated for the module
+    #[clap(long, conflicts_with = "diff")]
+    stats: bool,
+
+    /// Check the imports of FILE against the JSON import manifest of a host
+    /// instead, failing if FILE can't be instantiated with them
+    #[clap(long, value_name = "MANIFEST", conflicts_with_all = ["diff", "stats"])]
+    check_imports: Option<PathBuf>,
+
     #[clap(flatten)]
     rt: RuntimeOptions,
 }
@@ -26,6 +47,13 @@ impl Inspect {
     }
 
     fn inner_execute(&self) -> Result<()> {
+        if let Some(other) = self.other.as_deref().filter(|_| self.diff) {
+            return self.diff(other);
+        }
+        if let Some(manifest) = &self.check_imports {
+            return self.check_imports(manifest);
+        }
+
         let module_contents = std::fs::read(&self.path)?;
         let engine = self
             .rt
@@ -41,6 +69,18 @@ impl Inspect {
 
         println!("Type: {}", if !iswasm { "wat" } else { "wasm" });
         println!("Size: {}", ByteSize(module_len as _));
+        let features: Vec<_> = module.required_features().enabled().collect();
+        if features.is_empty() {
+            println!("Required features: none");
+        } else {
+            println!("Required features: {}", features.join(", "));
+        }
+        if self.stats {
+            match module.stats() {
+                Some(stats) => print_stats(&stats),
+                None => println!("Code stats: not available for this backend"),
+            }
+        }
         println!("Imports:");
         println!("  Functions:");
         for f in module.imports().functions() {
@@ -77,4 +117,113 @@ impl Inspect {
         }
         Ok(())
     }
+
+    fn diff(&self, other: &Path) -> Result<()> {
+        let old = self.load_interface(&self.path)?;
+        let new = self
+            .load_interface(other)
+            .with_context(|| format!("failed to inspect `{}`", other.display()))?;
+
+        print!("{}", old.diff(&new));
+
+        Ok(())
+    }
+
+    fn check_imports(&self, manifest: &Path) -> Result<()> {
+        let manifest = load_manifest(manifest)
+            .with_context(|| format!("failed to read `{}`", manifest.display()))?;
+        let module = self.load_module(&self.path)?;
+
+        let reports = check_module_against_manifest(&module, &manifest);
+        for report in &reports {
+            let severity = if report.is_error() {
+                "error"
+            } else {
+                "warning"
+            };
+            println!("{severity}: {report}");
+        }
+
+        let errors = reports.iter().filter(|r| r.is_error()).count();
+        if errors > 0 {
+            anyhow::bail!("{errors} import(s) not provided by the host");
+        }
+        println!("All imports are provided by the host");
+
+        Ok(())
+    }
+
+    fn load_interface(&self, path: &Path) -> Result<ModuleInterface> {
+        let module = self.load_module(path)?;
+        Ok(ModuleInterface::from_module(&module))
+    }
+
+    fn load_module(&self, path: &Path) -> Result<Module> {
+        let module_contents = std::fs::read(path)?;
+        let engine = self
+            .rt
+            .get_engine_for_module(&module_contents, &Target::default())?;
+        Ok(Module::new(&engine, module_contents)?)
+    }
+}
+
+/// The on-disk format of an [`ImportManifest`], as written by hosts with the
+/// `enable-serde` feature of `wasmer`.
+#[derive(Debug, Deserialize)]
+struct ManifestFile {
+    version: u32,
+    imports: Vec<ManifestFileImport>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ManifestFileImport {
+    module: String,
+    name: String,
+    #[serde(rename = "type")]
+    ty: ExternType,
+}
+
+fn load_manifest(path: &Path) -> Result<ImportManifest> {
+    let contents = std::fs::read_to_string(path)?;
+    let file: ManifestFile = serde_json::from_str(&contents)?;
+    if file.version != ImportManifest::VERSION {
+        anyhow::bail!(
+            "unsupported manifest version {} (expected {})",
+            file.version,
+            ImportManifest::VERSION
+        );
+    }
+
+    Ok(ImportManifest::new(file.imports.into_iter().map(
+        |import| ManifestImport {
+            module: import.module,
+            name: import.name,
+            ty: import.ty,
+        },
+    )))
+}
+
+/// Print the [`ArtifactStats`] of a module, as shown by `wasmer inspect --stats`
+/// and `wasmer compile`.
+pub(crate) fn print_stats(stats: &ArtifactStats) {
+    println!("Code stats:");
+    println!(
+        "  Functions: {} ({})",
+        stats.functions,
+        ByteSize(stats.code_size as _)
+    );
+    println!(
+        "  Trampolines: {} ({})",
+        stats.trampolines,
+        ByteSize(stats.trampolines_size as _)
+    );
+    println!("  Relocations: {}", stats.relocations);
+    match (stats.mapped_size, stats.executable_size) {
+        (Some(mapped), Some(executable)) => println!(
+            "  Mapped memory: {} ({} executable)",
+            ByteSize(mapped as _),
+            ByteSize(executable as _)
+        ),
+        _ => println!("  Mapped memory: not loaded"),
+    }
 }
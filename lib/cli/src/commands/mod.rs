@@ -2,6 +2,8 @@
 mod add;
 mod app;
 mod auth;
+#[cfg(feature = "compiler")]
+mod bench;
 #[cfg(target_os = "linux")]
 mod binfmt;
 mod cache;
@@ -37,12 +39,12 @@ use tokio::task::JoinHandle;
 #[cfg(target_os = "linux")]
 pub use binfmt::*;
 use clap::{CommandFactory, Parser};
-#[cfg(feature = "compiler")]
-pub use compile::*;
 #[cfg(any(feature = "static-artifact-create", feature = "wasmer-artifact-create"))]
 pub use create_exe::*;
 #[cfg(feature = "wast")]
 pub use wast::*;
+#[cfg(feature = "compiler")]
+pub use {bench::*, compile::*};
 #[cfg(feature = "static-artifact-create")]
 pub use {create_obj::*, gen_c_header::*};
 
@@ -52,7 +54,7 @@ pub use self::{
     add::*, auth::*, cache::*, config::*, container::*, init::*, inspect::*, package::*,
     publish::*, run::Run, self_update::*, validate::*,
 };
-use crate::error::PrettyError;
+use crate::{error::PrettyError, events::EventSink};
 
 /// An executable CLI command.
 pub(crate) trait CliCommand {
@@ -163,12 +165,13 @@ impl WasmerCmd {
         } = self;
 
         output.initialize_logging();
+        EventSink::install(output.output_format);
 
         if version {
             return print_version(output.is_verbose());
         }
 
-        match cmd {
+        let result = match cmd {
             Some(Cmd::GenManPage(cmd)) => cmd.execute(),
             Some(Cmd::GenCompletions(cmd)) => cmd.execute(),
             Some(Cmd::Run(options)) => options.execute(output),
@@ -177,6 +180,8 @@ impl WasmerCmd {
             Some(Cmd::Validate(validate)) => validate.execute(),
             #[cfg(feature = "compiler")]
             Some(Cmd::Compile(compile)) => compile.execute(),
+            #[cfg(feature = "compiler")]
+            Some(Cmd::Bench(bench)) => bench.execute(),
             #[cfg(any(feature = "static-artifact-create", feature = "wasmer-artifact-create"))]
             Some(Cmd::CreateExe(create_exe)) => create_exe.run(),
             #[cfg(feature = "static-artifact-create")]
@@ -194,6 +199,7 @@ impl WasmerCmd {
                 Package::Push(cmd) => cmd.run(),
                 Package::Publish(cmd) => cmd.run().map(|_| ()),
                 Package::Unpack(cmd) => cmd.execute(),
+                Package::Validate(cmd) => cmd.execute(),
             },
             Some(Cmd::Container(cmd)) => match cmd {
                 crate::commands::Container::Unpack(cmd) => cmd.execute(),
@@ -220,7 +226,10 @@ impl WasmerCmd {
                 // Note: clap uses an exit code of 2 when CLI parsing fails
                 std::process::exit(2);
             }
-        }
+        };
+
+        crate::events::sink().finish(&result, None);
+        result
     }
 
     /// The main function for the Wasmer CLI tool.
@@ -304,6 +313,10 @@ enum Cmd {
     #[cfg(feature = "compiler")]
     Compile(Compile),
 
+    /// Benchmark an exported function of a WebAssembly module
+    #[cfg(feature = "compiler")]
+    Bench(Bench),
+
     /// Compile a WebAssembly binary into a native executable
     ///
     /// To use, you need to set the `WASMER_DIR` environment variable
@@ -0,0 +1,826 @@
+//! Chunked, resumable uploads of packages.
+//!
+//! The registry hands out signed Google Cloud Storage URLs, so uploads use
+//! GCS's resumable upload protocol: every chunk is sent with a
+//! `Content-Range` header, the server answers `308` with the range of bytes
+//! it has persisted so far, and an interrupted session can be asked how far
+//! it got. The state of each session is kept in the cache directory so that
+//! publishing the same package again picks up where the last attempt left off.
+
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use anyhow::Context;
+use base64::{prelude::BASE64_STANDARD, Engine};
+use bytes::Bytes;
+use indicatif::{ProgressBar, ProgressStyle};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use wasmer_backend_api::WasmerClient;
+use wasmer_config::package::PackageHash;
+use wasmer_package::package::Package;
+
+/// GCS requires every chunk except the last to be a multiple of 256KiB.
+const DEFAULT_CHUNK_GRANULARITY: u64 = 256 * 1024;
+
+/// How a resumable upload is sent.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct UploadConfig {
+    /// How much data is sent per request, rounded up to whatever the server
+    /// requires.
+    pub chunk_size: u64,
+    /// How many times a failed request is retried before giving up.
+    pub max_retries: u32,
+    /// How long to wait before the first retry, doubling every time.
+    pub retry_delay: Duration,
+}
+
+impl Default for UploadConfig {
+    fn default() -> Self {
+        UploadConfig {
+            chunk_size: 8 * 1024 * 1024,
+            max_retries: 3,
+            retry_delay: Duration::from_secs(1),
+        }
+    }
+}
+
+// Upload a package to a signed url.
+pub(crate) async fn upload(
+    client: &WasmerClient,
+    hash: &PackageHash,
+    timeout: humantime::Duration,
+    package: &Package,
+    pb: ProgressBar,
+    proxy: Option<reqwest::Proxy>,
+    cache_dir: &Path,
+) -> anyhow::Result<String> {
+    let http = {
+        let builder = reqwest::Client::builder()
+            .default_headers(reqwest::header::HeaderMap::default())
+            .timeout(timeout.into());
+
+        let builder = if let Some(proxy) = proxy {
+            builder.proxy(proxy)
+        } else {
+            builder
+        };
+
+        builder.build().unwrap()
+    };
+
+    /* XXX: If the package is large this line may result in
+     * a surge in memory use.
+     *
+     * In the future, we might want a way to stream bytes
+     * from the webc instead of a complete in-memory
+     * representation.
+     */
+    let bytes = package.serialize()?;
+    tracing::info!("webc is {} bytes long", bytes.len());
+
+    pb.set_length(bytes.len().try_into().unwrap());
+    pb.set_style(ProgressStyle::with_template("{spinner:.yellow} [{elapsed_precise}] [{bar:.white}] {bytes}/{total_bytes} ({bytes_per_sec}, {eta})")
+                 .unwrap()
+                 .progress_chars("█▉▊▋▌▍▎▏  ")
+                 .tick_strings(&["✶", "✸", "✹", "✺", "✹", "✷", "✶"]));
+
+    let server = GcsUpload {
+        registry: client,
+        http,
+        filename: hash.to_string().trim_start_matches("sha256:").to_string(),
+    };
+    let sessions = UploadSessions::new(cache_dir);
+
+    upload_resumable(
+        &server,
+        &sessions,
+        hash,
+        bytes,
+        &UploadConfig::default(),
+        &pb,
+    )
+    .await
+}
+
+/// Upload `data` in chunks, resuming a previously interrupted upload of the
+/// same package if there is one, and return the session URI.
+pub(crate) async fn upload_resumable(
+    server: &dyn ResumableUpload,
+    sessions: &UploadSessions,
+    hash: &PackageHash,
+    data: Bytes,
+    config: &UploadConfig,
+    pb: &ProgressBar,
+) -> anyhow::Result<String> {
+    let total_bytes = data.len() as u64;
+    let events = crate::events::sink();
+    let report_progress = |position: u64| {
+        pb.set_position(position);
+        events.progress(Some((position, total_bytes)), None);
+    };
+
+    let mut session = match resume(server, sessions, hash, &data).await {
+        Resumed::Complete(session, crc32c) => {
+            return finish(sessions, hash, session, crc32c, &data);
+        }
+        Resumed::Incomplete(session) => {
+            tracing::info!(
+                session_uri = %session.session_uri,
+                offset = session.acknowledged_bytes(),
+                "Resuming an interrupted upload",
+            );
+            session
+        }
+        Resumed::Nothing => {
+            let (session_uri, granularity) = server.start().await?;
+            tracing::info!("session uri is: {session_uri}");
+
+            let granularity = granularity
+                .filter(|&g| g > 0)
+                .unwrap_or(DEFAULT_CHUNK_GRANULARITY);
+            let chunk_size = config.chunk_size.div_ceil(granularity) * granularity;
+
+            let session = UploadSession::new(session_uri, total_bytes, chunk_size);
+            sessions.save(hash, &session)?;
+            session
+        }
+    };
+
+    report_progress(session.acknowledged_bytes());
+    pb.reset_eta();
+
+    let mut retries = 0;
+
+    loop {
+        let offset = session.acknowledged_bytes();
+        let end = (offset + session.chunk_size).min(total_bytes);
+        let chunk = data.slice(offset as usize..end as usize);
+
+        let status = match server
+            .put_chunk(&session.session_uri, offset, chunk, total_bytes)
+            .await
+        {
+            Ok(status) => {
+                retries = 0;
+                status
+            }
+            Err(e) if retries < config.max_retries => {
+                retries += 1;
+                tracing::warn!(
+                    error = &*e,
+                    retries,
+                    "Uploading a chunk failed, checking what the server received",
+                );
+                tokio::time::sleep(config.retry_delay * 2_u32.pow(retries - 1)).await;
+
+                match server.status(&session.session_uri, total_bytes).await {
+                    Ok(status) => status,
+                    Err(_) => continue,
+                }
+            }
+            Err(e) => {
+                return Err(
+                    e.context("The upload was interrupted. Run the command again to resume it.")
+                )
+            }
+        };
+
+        match status {
+            UploadStatus::Incomplete { persisted } => {
+                session.acknowledge(&data, persisted);
+                sessions.save(hash, &session)?;
+                report_progress(session.acknowledged_bytes());
+            }
+            UploadStatus::Complete { crc32c } => {
+                report_progress(total_bytes);
+                return finish(sessions, hash, session, crc32c, &data);
+            }
+            UploadStatus::Expired => {
+                sessions.remove(hash);
+                anyhow::bail!(
+                    "The upload session expired. Run the command again to start a new one."
+                );
+            }
+        }
+    }
+}
+
+enum Resumed {
+    Nothing,
+    Incomplete(UploadSession),
+    Complete(UploadSession, Option<u32>),
+}
+
+/// Look for an earlier upload of the same package and find out how far the
+/// server got with it.
+async fn resume(
+    server: &dyn ResumableUpload,
+    sessions: &UploadSessions,
+    hash: &PackageHash,
+    data: &[u8],
+) -> Resumed {
+    let Some(mut session) = sessions.load(hash) else {
+        return Resumed::Nothing;
+    };
+
+    if !session.matches(data) {
+        tracing::debug!("Discarding an upload session for different contents");
+        sessions.remove(hash);
+        return Resumed::Nothing;
+    }
+
+    match server.status(&session.session_uri, data.len() as u64).await {
+        Ok(UploadStatus::Incomplete { persisted }) => {
+            session.acknowledge(data, persisted);
+            Resumed::Incomplete(session)
+        }
+        Ok(UploadStatus::Complete { crc32c }) => Resumed::Complete(session, crc32c),
+        Ok(UploadStatus::Expired) => {
+            tracing::debug!("The previous upload session expired");
+            sessions.remove(hash);
+            Resumed::Nothing
+        }
+        Err(e) => {
+            tracing::warn!(
+                error = &*e,
+                "Unable to check on the previous upload, starting over"
+            );
+            sessions.remove(hash);
+            Resumed::Nothing
+        }
+    }
+}
+
+/// Make sure the server ended up with the same bytes we sent.
+fn finish(
+    sessions: &UploadSessions,
+    hash: &PackageHash,
+    session: UploadSession,
+    reported: Option<u32>,
+    data: &[u8],
+) -> anyhow::Result<String> {
+    sessions.remove(hash);
+
+    match reported {
+        Some(reported) => {
+            let expected = crc32c(data);
+            if reported != expected {
+                anyhow::bail!(
+                    "The uploaded package is corrupted (CRC32C {reported:08x}, expected {expected:08x})"
+                );
+            }
+        }
+        None => tracing::warn!("The upload server didn't report a hash for the package"),
+    }
+
+    Ok(session.session_uri)
+}
+
+/// What the server knows about an upload.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum UploadStatus {
+    /// The first `persisted` bytes have been received.
+    Incomplete { persisted: u64 },
+    /// Everything has been received, and the server's CRC32C of it (if any).
+    Complete { crc32c: Option<u32> },
+    /// The session can't be used any more.
+    Expired,
+}
+
+/// The server side of a resumable upload.
+#[async_trait::async_trait]
+pub(crate) trait ResumableUpload: Send + Sync {
+    /// Start a new session, returning its URI and the granularity chunk
+    /// sizes must be a multiple of (if the server has one).
+    async fn start(&self) -> anyhow::Result<(String, Option<u64>)>;
+
+    /// Ask the server how much of the upload it has received.
+    async fn status(&self, session_uri: &str, total_bytes: u64) -> anyhow::Result<UploadStatus>;
+
+    /// Send the chunk of the upload starting at `offset`.
+    async fn put_chunk(
+        &self,
+        session_uri: &str,
+        offset: u64,
+        chunk: Bytes,
+        total_bytes: u64,
+    ) -> anyhow::Result<UploadStatus>;
+}
+
+/// A resumable upload to a GCS signed URL.
+struct GcsUpload<'a> {
+    registry: &'a WasmerClient,
+    http: reqwest::Client,
+    filename: String,
+}
+
+impl GcsUpload<'_> {
+    fn parse_status(response: &reqwest::Response) -> anyhow::Result<UploadStatus> {
+        match response.status().as_u16() {
+            200 | 201 => Ok(UploadStatus::Complete {
+                crc32c: response
+                    .headers()
+                    .get_all("x-goog-hash")
+                    .iter()
+                    .filter_map(|value| value.to_str().ok())
+                    .find_map(parse_goog_hash),
+            }),
+            308 => Ok(UploadStatus::Incomplete {
+                persisted: response
+                    .headers()
+                    .get(reqwest::header::RANGE)
+                    .and_then(|value| value.to_str().ok())
+                    .and_then(parse_range)
+                    .unwrap_or(0),
+            }),
+            404 | 410 => Ok(UploadStatus::Expired),
+            status => anyhow::bail!("Uploading package failed: got HTTP {status} when uploading"),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl ResumableUpload for GcsUpload<'_> {
+    async fn start(&self) -> anyhow::Result<(String, Option<u64>)> {
+        let signed_url = {
+            let default_timeout_secs = Some(60 * 30);
+            let q = wasmer_backend_api::query::get_signed_url_for_package_upload(
+                self.registry,
+                default_timeout_secs,
+                Some(&self.filename),
+                None,
+                None,
+            );
+
+            match q.await? {
+                Some(u) => u.url,
+                None => anyhow::bail!(
+                    "The backend did not provide a valid signed URL to upload the package"
+                ),
+            }
+        };
+
+        tracing::info!("signed url is: {signed_url}");
+
+        let result = self
+            .http
+            .post(&signed_url)
+            .header(reqwest::header::CONTENT_LENGTH, "0")
+            .header(reqwest::header::CONTENT_TYPE, "application/octet-stream")
+            .header("x-goog-resumable", "start")
+            .send()
+            .await?;
+
+        if result.status() != reqwest::StatusCode::CREATED {
+            anyhow::bail!(
+                "Uploading package failed: got HTTP {:?} when uploading",
+                result.status()
+            );
+        }
+
+        let headers = result.headers();
+        let session_uri = headers
+            .get(reqwest::header::LOCATION)
+            .and_then(|value| value.to_str().ok())
+            .ok_or_else(|| {
+                anyhow::anyhow!("The upload server did not provide the upload URL correctly")
+            })?
+            .to_string();
+        let granularity = headers
+            .get("x-goog-upload-chunk-granularity")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse().ok());
+
+        Ok((session_uri, granularity))
+    }
+
+    async fn status(&self, session_uri: &str, total_bytes: u64) -> anyhow::Result<UploadStatus> {
+        let response = self
+            .http
+            .put(session_uri)
+            .header(reqwest::header::CONTENT_LENGTH, "0")
+            .header(
+                reqwest::header::CONTENT_RANGE,
+                format!("bytes */{total_bytes}"),
+            )
+            .send()
+            .await
+            .map_err(|e| anyhow::anyhow!("error checking the upload to {session_uri}: {e}"))?;
+
+        Self::parse_status(&response)
+    }
+
+    async fn put_chunk(
+        &self,
+        session_uri: &str,
+        offset: u64,
+        chunk: Bytes,
+        total_bytes: u64,
+    ) -> anyhow::Result<UploadStatus> {
+        let content_range = if chunk.is_empty() {
+            format!("bytes */{total_bytes}")
+        } else {
+            let last = offset + chunk.len() as u64 - 1;
+            format!("bytes {offset}-{last}/{total_bytes}")
+        };
+
+        let response = self
+            .http
+            .put(session_uri)
+            .header(reqwest::header::CONTENT_TYPE, "application/octet-stream")
+            .header(reqwest::header::CONTENT_LENGTH, chunk.len())
+            .header(reqwest::header::CONTENT_RANGE, content_range)
+            .body(chunk)
+            .send()
+            .await
+            .map_err(|e| anyhow::anyhow!("error uploading package to {session_uri}: {e}"))?;
+
+        Self::parse_status(&response)
+    }
+}
+
+/// Parse the `Range: bytes=0-N` header GCS uses to say how many bytes it
+/// has persisted.
+fn parse_range(value: &str) -> Option<u64> {
+    let (first, last) = value.strip_prefix("bytes=")?.split_once('-')?;
+    if first.trim() != "0" {
+        return None;
+    }
+
+    last.trim().parse::<u64>().ok().map(|last| last + 1)
+}
+
+/// Get the CRC32C out of an `x-goog-hash: crc32c=...,md5=...` header.
+fn parse_goog_hash(value: &str) -> Option<u32> {
+    value.split(',').find_map(|hash| {
+        let encoded = hash.trim().strip_prefix("crc32c=")?;
+        let decoded = BASE64_STANDARD.decode(encoded).ok()?;
+        Some(u32::from_be_bytes(decoded.try_into().ok()?))
+    })
+}
+
+/// CRC-32C (Castagnoli), which is the checksum GCS reports for objects.
+fn crc32c(data: &[u8]) -> u32 {
+    const TABLE: [u32; 256] = {
+        let mut table = [0; 256];
+        let mut i = 0;
+        while i < table.len() {
+            let mut crc = i as u32;
+            let mut bit = 0;
+            while bit < 8 {
+                crc = if crc & 1 == 1 {
+                    (crc >> 1) ^ 0x82F6_3B78
+                } else {
+                    crc >> 1
+                };
+                bit += 1;
+            }
+            table[i] = crc;
+            i += 1;
+        }
+        table
+    };
+
+    !data.iter().fold(!0, |crc: u32, &byte| {
+        TABLE[((crc ^ byte as u32) & 0xff) as usize] ^ (crc >> 8)
+    })
+}
+
+/// The state of an upload, as far as the server has acknowledged it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct UploadSession {
+    pub session_uri: String,
+    pub total_bytes: u64,
+    pub chunk_size: u64,
+    /// The SHA-256 of every chunk the server has received, in order.
+    pub acknowledged_chunks: Vec<String>,
+}
+
+impl UploadSession {
+    fn new(session_uri: String, total_bytes: u64, chunk_size: u64) -> Self {
+        UploadSession {
+            session_uri,
+            total_bytes,
+            chunk_size,
+            acknowledged_chunks: Vec::new(),
+        }
+    }
+
+    pub(crate) fn acknowledged_bytes(&self) -> u64 {
+        (self.acknowledged_chunks.len() as u64 * self.chunk_size).min(self.total_bytes)
+    }
+
+    /// Record every chunk that lies entirely within the first `persisted`
+    /// bytes. A partially received chunk gets sent again.
+    fn acknowledge(&mut self, data: &[u8], persisted: u64) {
+        let chunks = data.chunks(self.chunk_size as usize);
+        let acknowledged = if persisted >= data.len() as u64 {
+            chunks.len()
+        } else {
+            (persisted / self.chunk_size) as usize
+        };
+
+        self.acknowledged_chunks = chunks.take(acknowledged).map(chunk_hash).collect();
+    }
+
+    /// Is this session uploading `data`?
+    fn matches(&self, data: &[u8]) -> bool {
+        self.chunk_size > 0
+            && self.total_bytes == data.len() as u64
+            && self.acknowledged_chunks.len() <= data.chunks(self.chunk_size as usize).len()
+            && self
+                .acknowledged_chunks
+                .iter()
+                .zip(data.chunks(self.chunk_size as usize))
+                .all(|(hash, chunk)| *hash == chunk_hash(chunk))
+    }
+}
+
+fn chunk_hash(chunk: &[u8]) -> String {
+    hex::encode(Sha256::digest(chunk))
+}
+
+/// In-progress uploads, kept in the cache directory and keyed by the hash
+/// of the package being uploaded.
+#[derive(Debug, Clone)]
+pub(crate) struct UploadSessions {
+    dir: PathBuf,
+}
+
+impl UploadSessions {
+    pub(crate) fn new(cache_dir: &Path) -> Self {
+        UploadSessions {
+            dir: cache_dir.join("uploads"),
+        }
+    }
+
+    fn path(&self, hash: &PackageHash) -> PathBuf {
+        let hash = hash.to_string();
+        let hash = hash.trim_start_matches("sha256:");
+        self.dir.join(format!("{hash}.json"))
+    }
+
+    pub(crate) fn load(&self, hash: &PackageHash) -> Option<UploadSession> {
+        let path = self.path(hash);
+        let json = std::fs::read(&path).ok()?;
+
+        match serde_json::from_slice(&json) {
+            Ok(session) => Some(session),
+            Err(e) => {
+                tracing::warn!(
+                    path = %path.display(),
+                    error = &e as &dyn std::error::Error,
+                    "Ignoring an unreadable upload session",
+                );
+                None
+            }
+        }
+    }
+
+    fn save(&self, hash: &PackageHash, session: &UploadSession) -> anyhow::Result<()> {
+        std::fs::create_dir_all(&self.dir)
+            .with_context(|| format!("Unable to create \"{}\"", self.dir.display()))?;
+
+        // Write to a temporary file first so an interrupted save can't leave
+        // a truncated session behind
+        let path = self.path(hash);
+        let temp = path.with_extension("json.part");
+        std::fs::write(&temp, serde_json::to_vec(session)?)
+            .and_then(|_| std::fs::rename(&temp, &path))
+            .with_context(|| {
+                format!(
+                    "Unable to save the upload session to \"{}\"",
+                    path.display()
+                )
+            })
+    }
+
+    fn remove(&self, hash: &PackageHash) {
+        let _ = std::fs::remove_file(self.path(hash));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+
+    const CHUNK: u64 = 1024;
+
+    #[derive(Debug, Default)]
+    struct ServerState {
+        received: Vec<u8>,
+        sessions_started: usize,
+        /// The offset of every chunk that was sent.
+        puts: Vec<u64>,
+        /// Drop the connection once this many chunks have been received.
+        fail_after: Option<usize>,
+        expired: bool,
+        corrupt: bool,
+    }
+
+    #[derive(Debug, Default)]
+    struct MockServer {
+        state: Mutex<ServerState>,
+    }
+
+    impl MockServer {
+        fn status(state: &ServerState, total_bytes: u64) -> UploadStatus {
+            if state.received.len() as u64 == total_bytes {
+                let mut received = state.received.clone();
+                if state.corrupt {
+                    received[0] ^= 0xff;
+                }
+                UploadStatus::Complete {
+                    crc32c: Some(crc32c(&received)),
+                }
+            } else {
+                UploadStatus::Incomplete {
+                    persisted: state.received.len() as u64,
+                }
+            }
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl ResumableUpload for MockServer {
+        async fn start(&self) -> anyhow::Result<(String, Option<u64>)> {
+            let mut state = self.state.lock().unwrap();
+            state.sessions_started += 1;
+            state.received.clear();
+            state.expired = false;
+            Ok((
+                format!("mock://session/{}", state.sessions_started),
+                Some(256),
+            ))
+        }
+
+        async fn status(&self, _: &str, total_bytes: u64) -> anyhow::Result<UploadStatus> {
+            let state = self.state.lock().unwrap();
+            if state.fail_after.is_some_and(|n| state.puts.len() >= n) {
+                anyhow::bail!("connection reset");
+            }
+            if state.expired {
+                return Ok(UploadStatus::Expired);
+            }
+
+            Ok(Self::status(&state, total_bytes))
+        }
+
+        async fn put_chunk(
+            &self,
+            _: &str,
+            offset: u64,
+            chunk: Bytes,
+            total_bytes: u64,
+        ) -> anyhow::Result<UploadStatus> {
+            let mut state = self.state.lock().unwrap();
+            if state.fail_after.is_some_and(|n| state.puts.len() >= n) {
+                anyhow::bail!("connection reset");
+            }
+
+            state.puts.push(offset);
+            assert!(offset <= state.received.len() as u64);
+            state.received.truncate(offset as usize);
+            state.received.extend_from_slice(&chunk);
+
+            Ok(Self::status(&state, total_bytes))
+        }
+    }
+
+    fn package() -> (PackageHash, Bytes) {
+        let data: Vec<u8> = (0..10 * CHUNK + 100).map(|i| (i % 251) as u8).collect();
+        let hash = PackageHash::from_sha256_bytes(Sha256::digest(&data).into());
+        (hash, data.into())
+    }
+
+    async fn upload(
+        server: &MockServer,
+        sessions: &UploadSessions,
+        hash: &PackageHash,
+        data: &Bytes,
+    ) -> anyhow::Result<String> {
+        upload_resumable(
+            server,
+            sessions,
+            hash,
+            data.clone(),
+            &UploadConfig {
+                chunk_size: CHUNK,
+                retry_delay: Duration::ZERO,
+                ..Default::default()
+            },
+            &ProgressBar::hidden(),
+        )
+        .await
+    }
+
+    #[test]
+    fn crc32c_check_value() {
+        assert_eq!(crc32c(b"123456789"), 0xE306_9283);
+        assert_eq!(
+            parse_goog_hash("crc32c=4waSgw==,md5=JfnnlDI7RTiF9RgfG2JNCw=="),
+            Some(0xE306_9283)
+        );
+        assert_eq!(parse_range("bytes=0-524287"), Some(524288));
+        assert_eq!(parse_range("bytes=5-10"), None);
+    }
+
+    #[tokio::test]
+    async fn interrupted_upload_resumes_after_the_last_acknowledged_chunk() {
+        let cache = tempfile::tempdir().unwrap();
+        let sessions = UploadSessions::new(cache.path());
+        let (hash, data) = package();
+        let server = MockServer::default();
+        server.state.lock().unwrap().fail_after = Some(4);
+
+        let err = upload(&server, &sessions, &hash, &data).await.unwrap_err();
+        assert!(err.to_string().contains("Run the command again"), "{err}");
+
+        let session = sessions.load(&hash).unwrap();
+        assert_eq!(session.acknowledged_chunks.len(), 4);
+        assert_eq!(session.acknowledged_bytes(), 4 * CHUNK);
+
+        // The connection comes back and the user runs the command again
+        server.state.lock().unwrap().fail_after = None;
+        let session_uri = upload(&server, &sessions, &hash, &data).await.unwrap();
+
+        let state = server.state.lock().unwrap();
+        assert_eq!(session_uri, "mock://session/1");
+        assert_eq!(state.sessions_started, 1);
+        let offsets: Vec<u64> = (0..11).map(|i| i * CHUNK).collect();
+        assert_eq!(state.puts, offsets);
+        assert_eq!(state.received, data);
+        assert!(sessions.load(&hash).is_none());
+    }
+
+    #[tokio::test]
+    async fn completed_upload_is_not_sent_again() {
+        let cache = tempfile::tempdir().unwrap();
+        let sessions = UploadSessions::new(cache.path());
+        let (hash, data) = package();
+        let server = MockServer::default();
+
+        // Everything made it to the server, but we crashed before the
+        // session was cleaned up
+        let mut session =
+            UploadSession::new("mock://session/1".to_string(), 10 * CHUNK + 100, CHUNK);
+        session.acknowledge(&data, 10 * CHUNK);
+        sessions.save(&hash, &session).unwrap();
+        server.state.lock().unwrap().received = data.to_vec();
+
+        let session_uri = upload(&server, &sessions, &hash, &data).await.unwrap();
+
+        assert_eq!(session_uri, "mock://session/1");
+        assert!(server.state.lock().unwrap().puts.is_empty());
+        assert!(sessions.load(&hash).is_none());
+    }
+
+    #[tokio::test]
+    async fn expired_and_mismatched_sessions_start_over() {
+        let cache = tempfile::tempdir().unwrap();
+        let sessions = UploadSessions::new(cache.path());
+        let (hash, data) = package();
+        let server = MockServer::default();
+
+        let mut session = UploadSession::new("mock://stale".to_string(), 10 * CHUNK + 100, CHUNK);
+        session.acknowledged_chunks = vec![chunk_hash(b"something else")];
+        sessions.save(&hash, &session).unwrap();
+
+        upload(&server, &sessions, &hash, &data).await.unwrap();
+        assert_eq!(server.state.lock().unwrap().puts[0], 0);
+
+        let mut session = UploadSession::new("mock://expired".to_string(), 10 * CHUNK + 100, CHUNK);
+        session.acknowledge(&data, 2 * CHUNK);
+        sessions.save(&hash, &session).unwrap();
+        {
+            let mut state = server.state.lock().unwrap();
+            state.expired = true;
+            state.puts.clear();
+        }
+
+        let session_uri = upload(&server, &sessions, &hash, &data).await.unwrap();
+        let state = server.state.lock().unwrap();
+        assert_eq!(session_uri, "mock://session/2");
+        assert_eq!(state.puts[0], 0);
+        assert_eq!(state.received, data);
+    }
+
+    #[tokio::test]
+    async fn corrupted_uploads_are_rejected() {
+        let cache = tempfile::tempdir().unwrap();
+        let sessions = UploadSessions::new(cache.path());
+        let (hash, data) = package();
+        let server = MockServer::default();
+        server.state.lock().unwrap().corrupt = true;
+
+        let err = upload(&server, &sessions, &hash, &data).await.unwrap_err();
+
+        assert!(err.to_string().contains("corrupted"), "{err}");
+        assert!(sessions.load(&hash).is_none());
+    }
+}
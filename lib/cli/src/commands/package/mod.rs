@@ -5,9 +5,11 @@ pub mod publish;
 mod push;
 mod tag;
 mod unpack;
+mod validate;
 
 pub use build::PackageBuild;
 pub use common::wait::PublishWait;
+pub(crate) use validate::validate_before_publish;
 
 /// Package related commands.
 #[derive(clap::Subcommand, Debug)]
@@ -22,4 +24,5 @@ pub enum Package {
     Push(push::PackagePush),
     Publish(publish::PackagePublish),
     Unpack(unpack::PackageUnpack),
+    Validate(validate::PackageValidate),
 }
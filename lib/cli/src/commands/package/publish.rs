@@ -4,6 +4,7 @@ use crate::{
             common::{wait::*, *},
             push::PackagePush,
             tag::PackageTag,
+            validate_before_publish,
         },
         AsyncCliCommand,
     },
@@ -41,10 +42,14 @@ pub struct PackagePublish {
     #[clap(long = "version")]
     pub package_version: Option<semver::Version>,
 
-    /// Skip validation of the uploaded package
+    /// Skip validation of the package before uploading it
     #[clap(long)]
     pub no_validate: bool,
 
+    /// Publish the package even if validation finds warnings
+    #[clap(long)]
+    pub allow_warnings: bool,
+
     /// Directory containing the `wasmer.toml`, or a custom *.toml manifest file.
     ///
     /// Defaults to current working directory.
@@ -86,6 +91,14 @@ impl PackagePublish {
         manifest: &Manifest,
         allow_unnamed: bool,
     ) -> anyhow::Result<PackageIdent> {
+        let events = crate::events::sink();
+        if !self.no_validate {
+            let validate = events.phase("validate");
+            validate_before_publish(manifest_path, self.allow_warnings)?;
+            validate.finish();
+        }
+
+        let push = events.phase("push");
         let (package_namespace, package_hash) = {
             let push_cmd = PackagePush {
                 env: self.env.clone(),
@@ -100,8 +113,10 @@ impl PackagePublish {
 
             push_cmd.push(client, manifest, manifest_path).await?
         };
+        push.finish();
 
-        PackageTag {
+        let tag = events.phase("tag");
+        let ident = PackageTag {
             wait: self.wait,
             env: self.env.clone(),
             dry_run: self.dry_run,
@@ -123,7 +138,10 @@ impl PackagePublish {
             true,
             allow_unnamed,
         )
-        .await
+        .await?;
+        tag.finish();
+
+        Ok(ident)
     }
 }
 
@@ -131,7 +149,10 @@ impl PackagePublish {
 impl AsyncCliCommand for PackagePublish {
     type Output = PackageIdent;
 
-    async fn run_async(self) -> Result<Self::Output, anyhow::Error> {
+    async fn run_async(mut self) -> Result<Self::Output, anyhow::Error> {
+        let events = crate::events::sink();
+        self.quiet |= events.is_enabled();
+
         tracing::info!("Checking if user is logged in");
         let client = login_user(&self.env, !self.non_interactive, "publish a package").await?;
 
@@ -144,6 +165,7 @@ impl AsyncCliCommand for PackagePublish {
             .await?;
 
         match ident {
+            _ if events.is_enabled() => events.record("package", ident.to_string()),
             PackageIdent::Named(ref n) => {
                 let url = make_package_url(&client, n);
                 eprintln!("\n{} Package URL: {url}", "𖥔".yellow().bold());
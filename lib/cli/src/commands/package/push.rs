@@ -126,6 +126,7 @@ impl PackagePush {
         package_hash: &PackageHash,
         private: bool,
     ) -> anyhow::Result<()> {
+        let upload_phase = crate::events::sink().phase("upload");
         let pb = make_spinner!(self.quiet, "Uploading the package..");
 
         let signed_url = upload(
@@ -135,9 +136,11 @@ impl PackagePush {
             package,
             pb.clone(),
             self.env.proxy()?,
+            &self.env.cache_dir,
         )
         .await?;
         spinner_ok!(pb, "Package correctly uploaded");
+        upload_phase.finish();
 
         let pb = make_spinner!(self.quiet, "Waiting for package to become available...");
         match wasmer_backend_api::query::push_package_release(
@@ -172,12 +175,14 @@ impl PackagePush {
         manifest_path: &Path,
     ) -> anyhow::Result<(String, PackageHash)> {
         tracing::info!("Building package");
+        let build = crate::events::sink().phase("build");
         let pb = make_spinner!(self.quiet, "Creating the package locally...");
         let (package, hash) = PackageBuild::check(manifest_path.to_path_buf())
             .execute()
             .context("While trying to build the package locally")?;
 
         spinner_ok!(pb, "Correctly built package locally");
+        build.finish();
         tracing::info!("Package has hash: {hash}");
 
         let namespace = self.get_namespace(client, manifest).await?;
@@ -0,0 +1,940 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+    ops::Range,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context;
+use colored::Colorize;
+use indexmap::IndexMap;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::Deserialize;
+use toml::Spanned;
+use wasmer_config::package::{Abi, Manifest, ModuleReference};
+use wasmparser::{Parser, Payload, WasmFeatures};
+
+use crate::utils::DEFAULT_PACKAGE_MANIFEST_FILE;
+
+/// Check a package for mistakes without publishing it.
+///
+/// This runs the same checks as `wasmer publish` does before uploading
+/// anything, and reports every problem it finds at once.
+#[derive(Debug, clap::Parser)]
+pub struct PackageValidate {
+    /// Don't fail if the package only has warnings
+    #[clap(long)]
+    pub allow_warnings: bool,
+
+    /// Directory containing the `wasmer.toml`, or a custom *.toml manifest file.
+    ///
+    /// Defaults to current working directory.
+    #[clap(name = "path", default_value = ".")]
+    pub package_path: PathBuf,
+}
+
+impl PackageValidate {
+    pub(crate) fn execute(&self) -> Result<(), anyhow::Error> {
+        let report = validate_package(&self.package_path)?;
+        eprint!("{report}");
+        report.check(self.allow_warnings)?;
+
+        eprintln!(
+            "{} Validation passed for `{}`.",
+            "✔".green().bold(),
+            report.manifest_path.display()
+        );
+        Ok(())
+    }
+}
+
+/// Validate the package at `path` and print any problems, failing if there
+/// are errors (or warnings, unless they are allowed).
+pub(crate) fn validate_before_publish(path: &Path, allow_warnings: bool) -> anyhow::Result<()> {
+    let report = validate_package(path)?;
+    let events = crate::events::sink();
+    if events.is_enabled() {
+        let mut errors = Vec::new();
+        for problem in &report.problems {
+            match problem.severity {
+                Severity::Warning => events.warning(&problem.message),
+                Severity::Error => errors.push(&problem.message),
+            }
+        }
+        if !errors.is_empty() {
+            events.record("validation_errors", errors);
+        }
+    } else {
+        eprint!("{report}");
+    }
+    report
+        .check(allow_warnings)
+        .context("Refusing to publish the package")
+}
+
+/// Run every check against the package at `path`, which is either a
+/// directory containing a `wasmer.toml` or the manifest itself.
+pub(crate) fn validate_package(path: &Path) -> anyhow::Result<Report> {
+    let manifest_path = if path.is_file() {
+        path.to_path_buf()
+    } else {
+        path.join(DEFAULT_PACKAGE_MANIFEST_FILE)
+    };
+
+    let contents = std::fs::read_to_string(&manifest_path).with_context(|| {
+        format!(
+            "Could not read package manifest at '{}'",
+            manifest_path.display()
+        )
+    })?;
+    let base_dir = manifest_path.parent().unwrap_or_else(|| Path::new("."));
+
+    let mut problems = Checks::new(&contents, base_dir).run();
+    problems.sort_by_key(|problem| problem.location);
+
+    Ok(Report {
+        manifest_path,
+        problems,
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Severity {
+    Warning,
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Warning => write!(f, "{}", "warning".yellow().bold()),
+            Severity::Error => write!(f, "{}", "error".red().bold()),
+        }
+    }
+}
+
+/// A position in the manifest, both 1-based.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) struct Location {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Location {
+    fn from_offset(contents: &str, offset: usize) -> Self {
+        let before = &contents[..offset.min(contents.len())];
+        let line_start = before.rfind('\n').map(|i| i + 1).unwrap_or(0);
+
+        Location {
+            line: before.matches('\n').count() + 1,
+            column: before[line_start..].chars().count() + 1,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Problem {
+    pub severity: Severity,
+    pub message: String,
+    pub location: Option<Location>,
+}
+
+/// Everything that was found wrong with a package.
+#[derive(Debug)]
+pub(crate) struct Report {
+    pub manifest_path: PathBuf,
+    pub problems: Vec<Problem>,
+}
+
+impl Report {
+    fn count(&self, severity: Severity) -> usize {
+        self.problems
+            .iter()
+            .filter(|problem| problem.severity == severity)
+            .count()
+    }
+
+    /// Fail if there were any errors, or any warnings when they aren't
+    /// allowed.
+    pub(crate) fn check(&self, allow_warnings: bool) -> anyhow::Result<()> {
+        let errors = self.count(Severity::Error);
+        let warnings = self.count(Severity::Warning);
+
+        if errors > 0 {
+            anyhow::bail!("The package has {errors} error(s) and {warnings} warning(s)");
+        }
+        if warnings > 0 && !allow_warnings {
+            anyhow::bail!(
+                "The package has {warnings} warning(s) (use --allow-warnings to ignore them)"
+            );
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for problem in &self.problems {
+            writeln!(f, "{}: {}", problem.severity, problem.message)?;
+            if let Some(Location { line, column }) = problem.location {
+                writeln!(
+                    f,
+                    "  {} {}:{line}:{column}",
+                    "-->".blue().bold(),
+                    self.manifest_path.display()
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// The parts of a `wasmer.toml` that get checked, along with where they
+/// were written.
+///
+/// This is deliberately looser than [`Manifest`] so that problems with the
+/// values can be reported together instead of failing on the first one.
+#[derive(Deserialize)]
+struct RawManifest {
+    package: Option<RawPackage>,
+    #[serde(default)]
+    dependencies: IndexMap<String, toml::Value>,
+    #[serde(default)]
+    fs: IndexMap<String, Spanned<PathBuf>>,
+    #[serde(default, rename = "module")]
+    modules: Vec<RawModule>,
+    #[serde(default, rename = "command")]
+    commands: Vec<RawCommand>,
+}
+
+#[derive(Deserialize)]
+struct RawPackage {
+    name: Option<Spanned<String>>,
+    version: Option<Spanned<String>>,
+    entrypoint: Option<Spanned<String>>,
+}
+
+#[derive(Deserialize)]
+struct RawModule {
+    name: Spanned<String>,
+    source: Spanned<PathBuf>,
+    abi: Option<Spanned<Abi>>,
+}
+
+#[derive(Deserialize)]
+struct RawCommand {
+    name: Spanned<String>,
+    module: Spanned<String>,
+    runner: Option<Spanned<String>>,
+}
+
+/// The imports and exports of a module, if it could be read.
+#[derive(Debug, Default)]
+struct WasmInfo {
+    /// The module names functions are imported from.
+    imports: BTreeSet<String>,
+    exports: BTreeSet<String>,
+}
+
+impl WasmInfo {
+    fn parse(wasm: &[u8]) -> Result<Self, wasmparser::BinaryReaderError> {
+        // Which proposals are enabled is up to whoever runs the package, so
+        // only reject modules that no runtime could load
+        wasmparser::Validator::new_with_features(WasmFeatures::all()).validate_all(wasm)?;
+
+        let mut info = WasmInfo::default();
+        for payload in Parser::new(0).parse_all(wasm) {
+            match payload? {
+                Payload::ImportSection(imports) => {
+                    for import in imports {
+                        info.imports.insert(import?.module.to_string());
+                    }
+                }
+                Payload::ExportSection(exports) => {
+                    for export in exports {
+                        info.exports.insert(export?.name.to_string());
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Ok(info)
+    }
+
+    fn imports_wasi(&self) -> bool {
+        // wasi_unstable, wasi_snapshot_preview1, wasix_32v1, etc.
+        self.imports.iter().any(|module| module.starts_with("wasi"))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Runner {
+    Wasi,
+    Wcgi,
+    Emscripten,
+    Other,
+}
+
+impl Runner {
+    fn from_name(name: &str) -> Self {
+        use webc::metadata::annotations::{
+            EMSCRIPTEN_RUNNER_URI, WASI_RUNNER_URI, WCGI_RUNNER_URI,
+        };
+
+        match name {
+            "wasi" | "wasi@unstable_" | "generic" | WASI_RUNNER_URI => Runner::Wasi,
+            "wcgi" | WCGI_RUNNER_URI => Runner::Wcgi,
+            "emscripten" | EMSCRIPTEN_RUNNER_URI => Runner::Emscripten,
+            _ => Runner::Other,
+        }
+    }
+
+    /// The runner a command without a `runner` field gets, based on its
+    /// module's ABI.
+    fn from_abi(abi: Abi) -> Self {
+        match abi {
+            Abi::Wasi | Abi::None => Runner::Wasi,
+            _ => Runner::Other,
+        }
+    }
+}
+
+struct Checks<'a> {
+    contents: &'a str,
+    base_dir: &'a Path,
+    problems: Vec<Problem>,
+}
+
+impl<'a> Checks<'a> {
+    fn new(contents: &'a str, base_dir: &'a Path) -> Self {
+        Checks {
+            contents,
+            base_dir,
+            problems: Vec::new(),
+        }
+    }
+
+    fn report(&mut self, severity: Severity, span: Option<Range<usize>>, message: String) {
+        let location = span.map(|span| Location::from_offset(self.contents, span.start));
+
+        self.problems.push(Problem {
+            severity,
+            message,
+            location,
+        });
+    }
+
+    fn error(&mut self, span: Range<usize>, message: String) {
+        self.report(Severity::Error, Some(span), message);
+    }
+
+    fn warning(&mut self, span: Range<usize>, message: String) {
+        self.report(Severity::Warning, Some(span), message);
+    }
+
+    fn run(mut self) -> Vec<Problem> {
+        let raw: RawManifest = match toml::from_str(self.contents) {
+            Ok(raw) => raw,
+            Err(e) => {
+                self.report(Severity::Error, e.span(), e.message().to_string());
+                return self.problems;
+            }
+        };
+
+        self.check_package(&raw);
+        let modules = self.check_modules(&raw.modules);
+        self.check_commands(&raw, &modules);
+        self.check_fs(&raw.fs);
+
+        // Anything the checks above don't look at still has to deserialize
+        if let Err(e) = Manifest::parse(self.contents) {
+            let location = e
+                .span()
+                .map(|span| Location::from_offset(self.contents, span.start));
+            let already_reported = self
+                .problems
+                .iter()
+                .any(|problem| location.is_some() && problem.location == location);
+
+            if !already_reported {
+                self.report(Severity::Error, e.span(), e.message().to_string());
+            }
+        }
+
+        self.problems
+    }
+
+    fn check_package(&mut self, raw: &RawManifest) {
+        static NAME: Lazy<Regex> = Lazy::new(|| {
+            Regex::new(r"^([a-zA-Z][a-zA-Z0-9_.-]*/)?[a-zA-Z][a-zA-Z0-9_.-]*$").unwrap()
+        });
+
+        let Some(package) = &raw.package else {
+            return;
+        };
+
+        if let Some(name) = &package.name {
+            if !NAME.is_match(name.get_ref()) {
+                self.error(
+                    name.span(),
+                    format!(
+                        "\"{}\" isn't a valid package name, expected something like \"namespace/name\"",
+                        name.get_ref()
+                    ),
+                );
+            }
+        }
+
+        if let Some(version) = &package.version {
+            if let Err(e) = semver::Version::parse(version.get_ref()) {
+                self.error(
+                    version.span(),
+                    format!("\"{}\" isn't a valid version: {e}", version.get_ref()),
+                );
+            }
+        }
+
+        if let Some(entrypoint) = &package.entrypoint {
+            let exists = raw
+                .commands
+                .iter()
+                .any(|cmd| cmd.name.get_ref() == entrypoint.get_ref());
+
+            if !exists {
+                self.error(
+                    entrypoint.span(),
+                    format!(
+                        "the entrypoint, \"{}\", isn't one of the package's commands",
+                        entrypoint.get_ref()
+                    ),
+                );
+            }
+        }
+    }
+
+    fn check_modules<'m>(
+        &mut self,
+        modules: &'m [RawModule],
+    ) -> BTreeMap<&'m str, (Abi, Option<WasmInfo>)> {
+        let mut checked = BTreeMap::new();
+
+        for module in modules {
+            let name = module.name.get_ref();
+            if checked.contains_key(name.as_str()) {
+                self.error(module.name.span(), format!("duplicate module, \"{name}\""));
+                continue;
+            }
+
+            let abi = module
+                .abi
+                .as_ref()
+                .map(|abi| *abi.get_ref())
+                .unwrap_or_default();
+            let info = self.check_module_source(module);
+
+            if let Some(info) = &info {
+                match (abi, &module.abi) {
+                    (Abi::Wasi, Some(declared)) if !info.imports_wasi() => self.warning(
+                        declared.span(),
+                        format!(
+                            "the \"{name}\" module declares the WASI ABI but doesn't import any WASI functions"
+                        ),
+                    ),
+                    (Abi::None, _) if info.imports_wasi() => self.warning(
+                        module.name.span(),
+                        format!(
+                            "the \"{name}\" module imports WASI functions but doesn't declare `abi = \"wasi\"`"
+                        ),
+                    ),
+                    _ => {}
+                }
+            }
+
+            checked.insert(name.as_str(), (abi, info));
+        }
+
+        checked
+    }
+
+    fn check_module_source(&mut self, module: &RawModule) -> Option<WasmInfo> {
+        let name = module.name.get_ref();
+        let path = self.base_dir.join(module.source.get_ref());
+
+        let wasm = match std::fs::read(&path) {
+            Ok(wasm) => wasm,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                self.error(
+                    module.source.span(),
+                    format!(
+                        "the \"{name}\" module's source, \"{}\", doesn't exist",
+                        path.display()
+                    ),
+                );
+                return None;
+            }
+            Err(e) => {
+                self.error(
+                    module.source.span(),
+                    format!("unable to read \"{}\": {e}", path.display()),
+                );
+                return None;
+            }
+        };
+
+        if !wasmer::is_wasm(&wasm) {
+            self.error(
+                module.source.span(),
+                format!(
+                    "the \"{name}\" module's source, \"{}\", isn't a WebAssembly binary",
+                    path.display()
+                ),
+            );
+            return None;
+        }
+
+        match WasmInfo::parse(&wasm) {
+            Ok(info) => Some(info),
+            Err(e) => {
+                self.error(
+                    module.source.span(),
+                    format!(
+                        "the \"{name}\" module's source, \"{}\", isn't valid WebAssembly: {e}",
+                        path.display()
+                    ),
+                );
+                None
+            }
+        }
+    }
+
+    fn check_commands(
+        &mut self,
+        raw: &RawManifest,
+        modules: &BTreeMap<&str, (Abi, Option<WasmInfo>)>,
+    ) {
+        let mut names = BTreeSet::new();
+
+        for command in &raw.commands {
+            let name = command.name.get_ref();
+            if !names.insert(name) {
+                self.error(
+                    command.name.span(),
+                    format!("duplicate command, \"{name}\""),
+                );
+            }
+
+            let reference = match command.module.get_ref().parse::<ModuleReference>() {
+                Ok(reference) => reference,
+                Err(e) => {
+                    self.error(
+                        command.module.span(),
+                        format!(
+                            "invalid module reference, \"{}\": {e}",
+                            command.module.get_ref()
+                        ),
+                    );
+                    continue;
+                }
+            };
+
+            let module = match &reference {
+                ModuleReference::CurrentPackage { module } => module,
+                ModuleReference::Dependency { dependency, .. } => {
+                    // The dependency's modules aren't available, so there's
+                    // nothing more to check
+                    if !raw.dependencies.contains_key(dependency) {
+                        self.error(
+                            command.module.span(),
+                            format!(
+                                "the \"{name}\" command uses \"{reference}\", but \"{dependency}\" isn't a dependency"
+                            ),
+                        );
+                    }
+                    continue;
+                }
+            };
+
+            let Some((abi, info)) = modules.get(module.as_str()) else {
+                self.error(
+                    command.module.span(),
+                    format!(
+                        "the \"{name}\" command uses the \"{module}\" module, which isn't defined"
+                    ),
+                );
+                continue;
+            };
+            let Some(info) = info else {
+                // Problems with the module have already been reported
+                continue;
+            };
+
+            let runner = match &command.runner {
+                Some(runner) => Runner::from_name(runner.get_ref()),
+                None => Runner::from_abi(*abi),
+            };
+
+            match runner {
+                Runner::Wasi | Runner::Wcgi if !info.exports.contains("_start") => self.error(
+                    command.name.span(),
+                    format!(
+                        "the \"{name}\" command runs \"{module}\" as a WASI program, but it doesn't export a `_start` function"
+                    ),
+                ),
+                Runner::Emscripten if !info.imports.contains("env") => self.warning(
+                    command
+                        .runner
+                        .as_ref()
+                        .map(|runner| runner.span())
+                        .unwrap_or_else(|| command.name.span()),
+                    format!(
+                        "the \"{name}\" command uses the emscripten runner, but \"{module}\" doesn't import anything from Emscripten"
+                    ),
+                ),
+                _ => {}
+            }
+        }
+    }
+
+    fn check_fs(&mut self, fs: &IndexMap<String, Spanned<PathBuf>>) {
+        for (guest, host) in fs {
+            let path = self.base_dir.join(host.get_ref());
+
+            if !path.exists() {
+                self.error(
+                    host.span(),
+                    format!(
+                        "\"{guest}\" is mapped to \"{}\", which doesn't exist",
+                        path.display()
+                    ),
+                );
+            } else if !path.is_dir() {
+                self.error(
+                    host.span(),
+                    format!(
+                        "\"{guest}\" is mapped to \"{}\", which isn't a directory",
+                        path.display()
+                    ),
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HELLO: &str = r#"(module
+        (import "wasi_snapshot_preview1" "proc_exit" (func (param i32)))
+        (memory (export "memory") 1)
+        (func (export "_start"))
+    )"#;
+
+    /// Validate a package made of `manifest` and `files`, returning each
+    /// problem along with the line it was reported on.
+    fn validate(manifest: &str, files: &[(&str, &[u8])]) -> Vec<(Severity, usize, String)> {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("wasmer.toml"), manifest).unwrap();
+        for (path, contents) in files {
+            let path = dir.path().join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, contents).unwrap();
+        }
+
+        validate_package(dir.path())
+            .unwrap()
+            .problems
+            .into_iter()
+            .map(|p| (p.severity, p.location.unwrap().line, p.message))
+            .collect()
+    }
+
+    fn wat(src: &str) -> Vec<u8> {
+        wat::parse_str(src).unwrap()
+    }
+
+    #[test]
+    fn valid_package() {
+        let problems = validate(
+            r#"
+[package]
+name = "wasmer/hello"
+version = "0.1.0"
+entrypoint = "hello"
+
+[[module]]
+name = "hello"
+source = "hello.wasm"
+abi = "wasi"
+
+[[command]]
+name = "hello"
+module = "hello"
+
+[fs]
+"/data" = "data"
+"#,
+            &[("hello.wasm", &wat(HELLO)), ("data/file.txt", b"")],
+        );
+
+        assert!(problems.is_empty(), "{problems:#?}");
+    }
+
+    #[test]
+    fn missing_and_invalid_module_sources() {
+        let problems = validate(
+            r#"
+[[module]]
+name = "missing"
+source = "missing.wasm"
+
+[[module]]
+name = "text"
+source = "README.md"
+
+[[module]]
+name = "truncated"
+source = "truncated.wasm"
+"#,
+            &[
+                ("README.md", b"# Hello"),
+                ("truncated.wasm", b"\0asm\x01\0\0\0\x01"),
+            ],
+        );
+
+        assert_eq!(problems.len(), 3, "{problems:#?}");
+        assert_eq!(problems[0].0, Severity::Error);
+        assert_eq!(problems[0].1, 4);
+        assert!(problems[0].2.contains("doesn't exist"));
+        assert_eq!(problems[1].1, 8);
+        assert!(problems[1].2.contains("isn't a WebAssembly binary"));
+        assert_eq!(problems[2].1, 12);
+        assert!(problems[2].2.contains("isn't valid WebAssembly"));
+    }
+
+    #[test]
+    fn commands_must_reference_defined_modules() {
+        let problems = validate(
+            r#"
+[[module]]
+name = "hello"
+source = "hello.wasm"
+abi = "wasi"
+
+[[command]]
+name = "typo"
+module = "helo"
+
+[[command]]
+name = "dep"
+module = "python:python"
+"#,
+            &[("hello.wasm", &wat(HELLO))],
+        );
+
+        assert_eq!(
+            problems,
+            [
+                (
+                    Severity::Error,
+                    9,
+                    "the \"typo\" command uses the \"helo\" module, which isn't defined"
+                        .to_string()
+                ),
+                (
+                    Severity::Error,
+                    13,
+                    "the \"dep\" command uses \"python:python\", but \"python\" isn't a dependency"
+                        .to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn wasi_commands_need_a_start_function() {
+        let problems = validate(
+            r#"
+[[module]]
+name = "lib"
+source = "lib.wasm"
+abi = "wasi"
+
+[[command]]
+name = "lib"
+module = "lib"
+runner = "wasi"
+"#,
+            &[(
+                "lib.wasm",
+                &wat(r#"(module
+                    (import "wasi_snapshot_preview1" "proc_exit" (func (param i32)))
+                    (func (export "_initialize"))
+                )"#),
+            )],
+        );
+
+        assert_eq!(problems.len(), 1, "{problems:#?}");
+        assert_eq!(problems[0].0, Severity::Error);
+        assert_eq!(problems[0].1, 8);
+        assert!(problems[0].2.contains("doesn't export a `_start` function"));
+    }
+
+    #[test]
+    fn abi_mismatches_are_warnings() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("wasmer.toml"),
+            r#"
+[[module]]
+name = "undeclared"
+source = "hello.wasm"
+
+[[module]]
+name = "pure"
+source = "pure.wasm"
+abi = "wasi"
+
+[[module]]
+name = "emscripten"
+source = "hello.wasm"
+abi = "wasi"
+
+[[command]]
+name = "emscripten"
+module = "emscripten"
+runner = "emscripten"
+"#,
+        )
+        .unwrap();
+        std::fs::write(dir.path().join("hello.wasm"), wat(HELLO)).unwrap();
+        std::fs::write(
+            dir.path().join("pure.wasm"),
+            wat(r#"(module (func (export "_start")))"#),
+        )
+        .unwrap();
+
+        let report = validate_package(&dir.path().join("wasmer.toml")).unwrap();
+
+        let lines: Vec<_> = report
+            .problems
+            .iter()
+            .map(|p| (p.severity, p.location.unwrap().line))
+            .collect();
+        assert_eq!(
+            lines,
+            [
+                (Severity::Warning, 3),
+                (Severity::Warning, 9),
+                (Severity::Warning, 19),
+            ]
+        );
+        assert!(report.check(false).is_err());
+        report.check(true).unwrap();
+    }
+
+    #[test]
+    fn fs_mappings_must_be_directories() {
+        let problems = validate(
+            r#"
+[fs]
+"/missing" = "missing"
+"/file" = "file.txt"
+"#,
+            &[("file.txt", b"")],
+        );
+
+        assert_eq!(problems.len(), 2, "{problems:#?}");
+        assert_eq!(problems[0].1, 3);
+        assert!(problems[0].2.contains("doesn't exist"));
+        assert_eq!(problems[1].1, 4);
+        assert!(problems[1].2.contains("isn't a directory"));
+    }
+
+    #[test]
+    fn malformed_name_and_version() {
+        let problems = validate(
+            r#"
+[package]
+name = "wasmer/hello world"
+version = "1.0"
+entrypoint = "missing"
+"#,
+            &[],
+        );
+
+        let lines: Vec<_> = problems.iter().map(|p| (p.0, p.1)).collect();
+        assert_eq!(
+            lines,
+            [
+                (Severity::Error, 3),
+                (Severity::Error, 4),
+                (Severity::Error, 5)
+            ],
+            "{problems:#?}"
+        );
+        assert!(problems[0].2.contains("isn't a valid package name"));
+        assert!(problems[1].2.contains("isn't a valid version"));
+    }
+
+    #[test]
+    fn every_problem_is_reported_at_once() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("wasmer.toml"),
+            r#"
+[package]
+name = "-bad"
+
+[[module]]
+name = "missing"
+source = "missing.wasm"
+
+[[command]]
+name = "run"
+module = "nope"
+
+[fs]
+"/data" = "data"
+"#,
+        )
+        .unwrap();
+
+        let report = validate_package(dir.path()).unwrap();
+
+        assert_eq!(report.problems.len(), 4, "{:#?}", report.problems);
+        let err = report.check(true).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "The package has 4 error(s) and 0 warning(s)"
+        );
+
+        colored::control::set_override(false);
+        let rendered = report.to_string();
+        let manifest = report.manifest_path.display();
+        assert!(
+            rendered.contains(&format!("  --> {manifest}:3:8\n")),
+            "{rendered}"
+        );
+        assert!(
+            rendered.contains(&format!("  --> {manifest}:14:11\n")),
+            "{rendered}"
+        );
+    }
+
+    #[test]
+    fn syntax_errors_are_reported_with_their_location() {
+        let problems = validate(
+            "[package]\nname = \"wasmer/hello\nversion = \"0.1.0\"\n",
+            &[],
+        );
+
+        assert_eq!(problems.len(), 1, "{problems:#?}");
+        assert_eq!(problems[0].0, Severity::Error);
+        assert_eq!(problems[0].1, 2);
+    }
+}
@@ -1,8 +1,11 @@
 #![allow(missing_docs, unused)]
 
 mod capabilities;
+mod test_report;
 mod wasi;
 
+pub(crate) use self::wasi::Wasi;
+
 use std::{
     collections::{hash_map::DefaultHasher, BTreeMap},
     fmt::{Binary, Display},
@@ -19,6 +22,7 @@ use std::{
 use anyhow::{anyhow, bail, Context, Error};
 use clap::{Parser, ValueEnum};
 use indicatif::{MultiProgress, ProgressBar};
+use is_terminal::IsTerminal;
 use once_cell::sync::Lazy;
 use tempfile::NamedTempFile;
 use url::Url;
@@ -45,13 +49,13 @@ use wasmer_wasix::{
     runners::{
         dcgi::{DcgiInstanceFactory, DcgiRunner},
         dproxy::DProxyRunner,
-        wasi::{RuntimeOrEngine, WasiRunner},
+        wasi::{RuntimeOrEngine, TimeoutError, WasiRunner},
         wcgi::{self, AbortHandle, NoOpWcgiCallbacks, WcgiRunner},
         MappedCommand, MappedDirectory, Runner,
     },
     runtime::{
         module_cache::CacheError, package_loader::PackageLoader, resolver::QueryError,
-        task_manager::VirtualTaskManagerExt,
+        task_manager::VirtualTaskManagerExt, PackageResolutionPolicy,
     },
     Runtime, WasiError,
 };
@@ -59,12 +63,18 @@ use webc::metadata::Manifest;
 use webc::Container;
 
 use crate::{
-    backend::RuntimeOptions, commands::run::wasi::Wasi, common::HashAlgorithm, config::WasmerEnv,
-    error::PrettyError, logging::Output,
+    backend::RuntimeOptions, common::HashAlgorithm, config::WasmerEnv, error::PrettyError,
+    logging::Output,
 };
 
+use self::test_report::TestReport;
+
 const TICK: Duration = Duration::from_millis(250);
 
+/// The exit code used when the program runs into `--timeout` (the same as
+/// coreutils' `timeout`).
+const TIMEOUT_EXIT_CODE: i32 = 124;
+
 /// The unstable `wasmer run` subcommand.
 #[derive(Debug, Parser)]
 pub struct Run {
@@ -80,27 +90,85 @@ pub struct Run {
     #[clap(long = "stack-size")]
     stack_size: Option<usize>,
     /// The entrypoint module for webc packages.
+    ///
+    /// The command can also be given along with the package, as in
+    /// `wasmer run python:pip`.
     #[clap(short, long, aliases = &["command", "command-name"])]
     entrypoint: Option<String>,
     /// The function to invoke.
     #[clap(short, long)]
     invoke: Option<String>,
+    /// Pass each argument to the invoked function as a string.
+    ///
+    /// Every string is copied into memory allocated with the module's
+    /// exported `malloc` function, and passed as a pointer and a length.
+    #[clap(long, requires = "invoke")]
+    invoke_string: bool,
+    /// Print each result of the invoked function on its own line, followed
+    /// by its type (e.g. `42 (i32)`).
+    #[clap(long, requires = "invoke")]
+    invoke_typed: bool,
     /// Generate a coredump at this path if a WebAssembly trap occurs
     #[clap(name = "COREDUMP_PATH", long)]
     coredump_on_trap: Option<PathBuf>,
-    /// The file, URL, or package to run.
-    #[clap(value_parser = PackageSource::infer)]
-    input: PackageSource,
+    /// Replace `(@data.file "path")` directives in the data segments of a
+    /// `.wat` input with the contents of the file, resolving relative paths
+    /// against this directory
+    #[clap(long)]
+    wat_include_dir: Option<PathBuf>,
+    /// The file, URL, or package to run, optionally followed by the command
+    /// to run from it (e.g. `python:pip` or `python@3.12:pip`).
+    #[clap(value_parser = PackageInput::infer)]
+    input: PackageInput,
     /// Command-line arguments passed to the package
+    #[clap(allow_negative_numbers = true)]
     args: Vec<String>,
     /// Hashing algorithm to be used for module hash
     #[clap(long, value_enum)]
     hash_algorithm: Option<HashAlgorithm>,
+    /// Stop the program if it is still running after this long (e.g. "30s"),
+    /// exiting with code 124.
+    ///
+    /// The program is sent SIGTERM first and is terminated if it hasn't exited
+    /// after the `--kill-after` grace period. The timeout is ignored when stdin
+    /// is an interactive terminal, unless `--timeout-interactive` is passed.
+    #[clap(long)]
+    timeout: Option<humantime::Duration>,
+    /// How long a timed out program gets to exit after being sent SIGTERM
+    #[clap(long, requires = "timeout", default_value = "5s")]
+    kill_after: humantime::Duration,
+    /// Apply `--timeout` even when stdin is an interactive terminal
+    #[clap(long, requires = "timeout")]
+    timeout_interactive: bool,
+    /// Close the program's stdin, so reading from it returns EOF right away
+    #[clap(long)]
+    stdin_close: bool,
+    /// Write the test events the program reports on `/dev/wasmer-test-report`
+    /// to this file, one JSON object per line, as they happen.
+    ///
+    /// A summary is printed once the program exits. If any test failed or
+    /// didn't finish, the exit code is 101 (unless the program failed too).
+    #[clap(long)]
+    test_report: Option<PathBuf>,
+    #[clap(skip)]
+    test_results: Option<Arc<TestReport>>,
 }
 
 impl Run {
-    pub fn execute(self, output: Output) -> ! {
+    pub fn execute(mut self, output: Output) -> ! {
+        if let Some(path) = &self.test_report {
+            match TestReport::create(path) {
+                Ok(report) => self.test_results = Some(report),
+                Err(e) => exit_with_wasi_exit_code(Err(e)),
+            }
+        }
+        let report = self.test_results.clone();
+
         let result = self.execute_inner(output);
+        let result = match report {
+            Some(report) => report.finish(result),
+            None => result,
+        };
         exit_with_wasi_exit_code(result);
     }
 
@@ -110,6 +178,8 @@ impl Run {
         pb.set_draw_target(output.draw_target());
         pb.enable_steady_tick(TICK);
 
+        let events = crate::events::sink();
+        let prepare = events.phase("prepare");
         pb.set_message("Initializing the WebAssembly VM");
 
         let runtime = tokio::runtime::Builder::new_multi_thread()
@@ -135,7 +205,7 @@ impl Run {
 
         // Try to detect WebAssembly features before selecting a backend
         tracing::info!("Input source: {:?}", self.input);
-        if let PackageSource::File(path) = &self.input {
+        if let PackageSource::File(path) = &self.input.source {
             tracing::info!("Input file path: {}", path.display());
 
             // Try to read and detect any file that exists, regardless of extension
@@ -180,7 +250,7 @@ impl Run {
             }
             None => {
                 // No WebAssembly file available for analysis, check if we have a webc package
-                if let PackageSource::Package(ref pkg_source) = &self.input {
+                if let PackageSource::Package(ref pkg_source) = &self.input.source {
                     tracing::info!("Checking package for WebAssembly features: {}", pkg_source);
                     self.rt.get_engine(&Target::default())?
                 } else {
@@ -200,6 +270,9 @@ impl Run {
             }
             let hash_algorithm = self.hash_algorithm.unwrap_or_default().into();
             engine.set_hash_algorithm(Some(hash_algorithm));
+            if let PackageSource::File(path) = &self.input.source {
+                engine.set_source_map_resolver(Some(source_map_resolver(path)));
+            }
         }
 
         let engine = engine.clone();
@@ -207,7 +280,7 @@ impl Run {
         let runtime = self.wasi.prepare_runtime(
             engine,
             &self.env,
-            &capabilities::get_capability_cache_path(&self.env, &self.input)?,
+            &capabilities::get_capability_cache_path(&self.env, &self.input.source)?,
             runtime,
             preferred_webc_version,
         )?;
@@ -218,15 +291,26 @@ impl Run {
         let runtime: Arc<dyn Runtime + Send + Sync> = monitoring_runtime.runtime.clone();
         let monitoring_runtime: Arc<dyn Runtime + Send + Sync> = monitoring_runtime;
 
-        let target = self.input.resolve_target(&monitoring_runtime, &pb)?;
+        let resolve = events.phase("resolve");
+        let target = self.input.source.resolve_target(
+            &monitoring_runtime,
+            &pb,
+            self.wat_include_dir.as_deref(),
+        )?;
+        resolve.finish();
+
+        self.wasi
+            .pass_host_envs(matches!(self.input.source, PackageSource::Package(_)));
 
         if let ExecutableTarget::Package(ref pkg) = target {
             self.wasi
                 .mapped_dirs
                 .extend(pkg.additional_host_mapped_directories.clone());
+            self.wasi.check_precompiled(pkg, &runtime.engine())?;
         }
 
         pb.finish_and_clear();
+        prepare.finish();
 
         // push the TTY state so we can restore it after the program finishes
         let tty = runtime.tty().map(|tty| tty.tty_get());
@@ -237,10 +321,18 @@ impl Run {
                     module,
                     module_hash,
                     path,
-                } => self.execute_wasm(&path, module, module_hash, runtime.clone()),
+                } => {
+                    if let Some(command) = &self.input.command {
+                        bail!(
+                            "\"{}\" is a WebAssembly module and has no \"{command}\" command",
+                            path.display()
+                        );
+                    }
+                    self.execute_wasm(&path, module, module_hash, runtime.clone())
+                }
                 ExecutableTarget::Package(pkg) => {
                     // Check if we should update the engine based on the WebC package features
-                    if let Some(cmd) = pkg.get_entrypoint_command() {
+                    if let Ok(cmd) = pkg.resolve_command(self.command_name()?) {
                         if let Some(features) = cmd.wasm_features() {
                             // Get the right engine for these features
                             let backends = self.rt.get_available_backends()?;
@@ -278,7 +370,7 @@ impl Run {
                                         &self.env,
                                         &capabilities::get_capability_cache_path(
                                             &self.env,
-                                            &self.input,
+                                            &self.input.source,
                                         )?,
                                         tokio::runtime::Builder::new_multi_thread()
                                             .enable_all()
@@ -333,13 +425,8 @@ impl Run {
         pkg: &BinaryPackage,
         runtime: Arc<dyn Runtime + Send + Sync>,
     ) -> Result<(), Error> {
-        let id = match self.entrypoint.as_deref() {
-            Some(cmd) => cmd,
-            None => pkg.infer_entrypoint()?,
-        };
-        let cmd = pkg
-            .get_command(id)
-            .with_context(|| format!("Unable to get metadata for the \"{id}\" command"))?;
+        let cmd = pkg.resolve_command(self.command_name()?)?;
+        let id = cmd.name();
 
         let uses = self.load_injected_packages(&runtime)?;
 
@@ -359,6 +446,17 @@ impl Run {
         }
     }
 
+    /// The command to run from a package, given either with `--entrypoint`
+    /// or as `package:command`.
+    fn command_name(&self) -> Result<Option<&str>, Error> {
+        match (self.entrypoint.as_deref(), self.input.command.as_deref()) {
+            (Some(entrypoint), Some(command)) if entrypoint != command => bail!(
+                "Both \"{command}\" and --entrypoint={entrypoint} were given as the command to run"
+            ),
+            (entrypoint, command) => Ok(entrypoint.or(command)),
+        }
+    }
+
     #[tracing::instrument(level = "debug", skip_all)]
     fn load_injected_packages(
         &self,
@@ -501,16 +599,28 @@ impl Run {
             }
         };
 
-        let return_values = invoke_function(&instance, &mut store, entry_function, &self.args)?;
+        let return_values = invoke_function(
+            &instance,
+            &mut store,
+            entry_function,
+            &self.args,
+            self.invoke_string,
+        )?;
 
-        println!(
-            "{}",
-            return_values
-                .iter()
-                .map(|val| val.to_string())
-                .collect::<Vec<String>>()
-                .join(" ")
-        );
+        if self.invoke_typed {
+            for value in return_values.iter() {
+                println!("{value} ({})", value.ty().to_string().to_lowercase());
+            }
+        } else {
+            println!(
+                "{}",
+                return_values
+                    .iter()
+                    .map(|val| val.to_string())
+                    .collect::<Vec<String>>()
+                    .join(" ")
+            );
+        }
 
         Ok(())
     }
@@ -531,6 +641,7 @@ impl Run {
             .with_injected_packages(packages)
             .with_envs(self.wasi.env_vars.clone())
             .with_mapped_host_commands(self.wasi.build_mapped_commands()?)
+            .with_command_aliases(self.wasi.command_aliases.clone())
             .with_mapped_directories(mapped_diretories)
             .with_home_mapped(is_home_mapped)
             .with_tmp_mapped(is_tmp_mapped)
@@ -541,6 +652,24 @@ impl Run {
             runner.with_entry_function(entry_function);
         }
 
+        if let Some(timeout) = self.timeout {
+            if self.timeout_interactive || !std::io::stdin().is_terminal() {
+                runner
+                    .with_timeout(timeout.into())
+                    .with_kill_after(self.kill_after.into());
+            } else {
+                tracing::warn!("Ignoring --timeout because stdin is an interactive terminal");
+            }
+        }
+
+        if self.stdin_close {
+            runner.with_stdin(Box::<virtual_fs::NullFile>::default());
+        }
+
+        if let Some(report) = &self.test_results {
+            runner.with_test_reporter(report.reporter());
+        }
+
         #[cfg(feature = "journal")]
         {
             for trigger in self.wasi.snapshot_on.iter().cloned() {
@@ -596,7 +725,7 @@ impl Run {
     fn maybe_save_coredump(&self, e: &Error) {
         #[cfg(feature = "coredump")]
         if let Some(coredump) = &self.coredump_on_trap {
-            if let Err(e) = generate_coredump(e, self.input.to_string(), coredump) {
+            if let Err(e) = generate_coredump(e, self.input.source.to_string(), coredump) {
                 tracing::warn!(
                     error = &*e as &dyn std::error::Error,
                     coredump_path=%coredump.display(),
@@ -637,10 +766,22 @@ impl Run {
             stack_size: None,
             entrypoint: Some(original_executable.to_string()),
             invoke: None,
+            invoke_string: false,
+            invoke_typed: false,
             coredump_on_trap: None,
-            input: PackageSource::infer(executable)?,
+            wat_include_dir: None,
+            input: PackageInput {
+                source: PackageSource::infer(executable)?,
+                command: None,
+            },
             args: args.to_vec(),
             hash_algorithm: None,
+            timeout: None,
+            kill_after: Duration::from_secs(5).into(),
+            timeout_interactive: false,
+            stdin_close: false,
+            test_report: None,
+            test_results: None,
         })
     }
 }
@@ -650,45 +791,197 @@ fn invoke_function(
     store: &mut Store,
     func: &Function,
     args: &[String],
+    strings: bool,
 ) -> Result<Box<[Value]>, Error> {
     let func_ty = func.ty(store);
-    let required_arguments = func_ty.params().len();
-    let provided_arguments = args.len();
 
-    anyhow::ensure!(
-        required_arguments == provided_arguments,
-        "Function expected {} arguments, but received {}",
-        required_arguments,
-        provided_arguments,
-    );
+    let invoke_args = if strings {
+        string_arguments(instance, store, func_ty.params(), args)?
+    } else {
+        let required_arguments = func_ty.params().len();
+        let provided_arguments = args.len();
+
+        anyhow::ensure!(
+            required_arguments == provided_arguments,
+            "Function expected {} arguments, but received {}",
+            required_arguments,
+            provided_arguments,
+        );
 
-    let invoke_args = args
-        .iter()
-        .zip(func_ty.params().iter())
-        .map(|(arg, param_type)| {
-            parse_value(arg, *param_type)
-                .with_context(|| format!("Unable to convert {arg:?} to {param_type:?}"))
-        })
-        .collect::<Result<Vec<_>, _>>()?;
+        args.iter()
+            .zip(func_ty.params().iter())
+            .enumerate()
+            .map(|(index, (arg, param_type))| {
+                parse_value(arg, *param_type).with_context(|| {
+                    format!(
+                        "Invalid value {arg:?} for parameter {index} ({})",
+                        param_type.to_string().to_lowercase()
+                    )
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?
+    };
 
     let return_values = func.call(store, &invoke_args)?;
 
     Ok(return_values)
 }
 
-fn parse_value(s: &str, ty: wasmer_types::Type) -> Result<Value, Error> {
+/// Copy each argument into the instance's memory and pass it as a
+/// `(ptr, len)` pair.
+fn string_arguments(
+    instance: &Instance,
+    store: &mut Store,
+    params: &[Type],
+    args: &[String],
+) -> Result<Vec<Value>, Error> {
+    anyhow::ensure!(
+        params.len() == args.len() * 2,
+        "Function expected {} arguments, but received {} strings (each string is passed as a pointer and a length)",
+        params.len(),
+        args.len(),
+    );
+
+    let malloc = instance
+        .exports
+        .get_function("malloc")
+        .context("Passing strings requires the module to export a \"malloc\" function")?;
+    let memory = instance
+        .exports
+        .get_memory("memory")
+        .context("Passing strings requires the module to export its memory as \"memory\"")?;
+    let size_ty = match malloc.ty(store).params() {
+        [ty @ (Type::I32 | Type::I64)] => *ty,
+        other => bail!("Expected \"malloc\" to take a single size parameter, found {other:?}"),
+    };
+
+    let mut values = Vec::with_capacity(params.len());
+
+    for (index, (arg, types)) in args.iter().zip(params.chunks(2)).enumerate() {
+        let len = arg.len() as u64;
+        let ptr = match *malloc.call(store, &[int_value(len, size_ty)?])? {
+            [Value::I32(ptr)] => ptr as u32 as u64,
+            [Value::I64(ptr)] => ptr as u64,
+            ref other => bail!("Expected \"malloc\" to return a pointer, found {other:?}"),
+        };
+        memory
+            .view(store)
+            .write(ptr, arg.as_bytes())
+            .with_context(|| format!("Unable to copy string {index} into memory"))?;
+
+        let (ptr_index, len_index) = (index * 2, index * 2 + 1);
+        values.push(
+            int_value(ptr, types[0])
+                .with_context(|| format!("Invalid type for parameter {ptr_index}"))?,
+        );
+        values.push(
+            int_value(len, types[1])
+                .with_context(|| format!("Invalid type for parameter {len_index}"))?,
+        );
+    }
+
+    Ok(values)
+}
+
+fn int_value(value: u64, ty: Type) -> Result<Value, Error> {
+    match ty {
+        Type::I32 => Ok(Value::I32(u32::try_from(value)? as i32)),
+        Type::I64 => Ok(Value::I64(value as i64)),
+        _ => bail!("expected an i32 or i64 to hold a pointer or length, found {ty:?}"),
+    }
+}
+
+pub(crate) fn parse_value(s: &str, ty: wasmer_types::Type) -> Result<Value, Error> {
     let value = match ty {
-        Type::I32 => Value::I32(s.parse()?),
-        Type::I64 => Value::I64(s.parse()?),
+        // Integers may be written as signed or unsigned numbers, so the
+        // accepted range covers both.
+        Type::I32 => Value::I32(parse_int(s, i32::MIN.into(), u32::MAX.into())? as u32 as i32),
+        Type::I64 => Value::I64(parse_int(s, i64::MIN.into(), u64::MAX.into())? as u64 as i64),
         Type::F32 => Value::F32(s.parse()?),
         Type::F64 => Value::F64(s.parse()?),
-        Type::V128 => Value::V128(s.parse()?),
+        Type::V128 => Value::V128(
+            match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+                Some(hex) => u128::from_str_radix(&hex.replace('_', ""), 16)?,
+                None => s.replace('_', "").parse()?,
+            },
+        ),
         _ => bail!("There is no known conversion from {s:?} to {ty:?}"),
     };
     Ok(value)
 }
 
-/// The input that was passed in via the command-line.
+/// Parse a decimal or `0x`-prefixed hexadecimal integer, optionally with a
+/// sign and `_` separators.
+fn parse_int(s: &str, min: i128, max: i128) -> Result<i128, Error> {
+    let digits = s.replace('_', "");
+    let (negative, digits) = match digits.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, digits.strip_prefix('+').unwrap_or(&digits)),
+    };
+    let magnitude = match digits
+        .strip_prefix("0x")
+        .or_else(|| digits.strip_prefix("0X"))
+    {
+        Some(hex) => i128::from_str_radix(hex, 16)?,
+        None => digits.parse::<i128>()?,
+    };
+    let value = if negative { -magnitude } else { magnitude };
+
+    anyhow::ensure!(
+        (min..=max).contains(&value),
+        "{s} is out of range (expected a number between {min} and {max})"
+    );
+
+    Ok(value)
+}
+
+/// The input that was passed in via the command-line: what to run and,
+/// for packages, optionally which of their commands.
+#[derive(Debug, Clone, PartialEq)]
+struct PackageInput {
+    source: PackageSource,
+    command: Option<String>,
+}
+
+impl PackageInput {
+    fn infer(s: &str) -> Result<PackageInput, Error> {
+        // Files and directories whose names look like `package:command` are
+        // still run as a whole
+        if let Ok(source) = PackageSource::infer(s) {
+            if !matches!(source, PackageSource::Package(_)) {
+                return Ok(PackageInput {
+                    source,
+                    command: None,
+                });
+            }
+        }
+
+        let (package, command) = PackageSpecifier::split_command(s);
+        Ok(PackageInput {
+            source: PackageSource::infer(package)?,
+            command: command.map(String::from),
+        })
+    }
+}
+
+/// Loads the source maps that a module refers to by path, relative to the
+/// directory the module is in, so traps point at the original source code.
+#[cfg(feature = "sys")]
+fn source_map_resolver(module_path: &Path) -> wasmer::sys::SourceMapResolver {
+    let dir = module_path
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_default();
+    Arc::new(move |url| {
+        let path = url.strip_prefix("file://").unwrap_or(url);
+        if path.contains("://") {
+            return None;
+        }
+        std::fs::read(dir.join(path)).ok()
+    })
+}
+
+/// The source of what to run.
 #[derive(Debug, Clone, PartialEq)]
 enum PackageSource {
     /// A file on disk (`*.wasm`, `*.webc`, etc.).
@@ -726,9 +1019,10 @@ impl PackageSource {
         &self,
         rt: &Arc<dyn Runtime + Send + Sync>,
         pb: &ProgressBar,
+        wat_include_dir: Option<&Path>,
     ) -> Result<ExecutableTarget, Error> {
         match self {
-            PackageSource::File(path) => ExecutableTarget::from_file(path, rt, pb),
+            PackageSource::File(path) => ExecutableTarget::from_file(path, rt, pb, wat_include_dir),
             PackageSource::Dir(d) => ExecutableTarget::from_dir(d, rt, pb),
             PackageSource::Package(pkg) => {
                 pb.set_message("Loading from the registry");
@@ -737,6 +1031,9 @@ impl PackageSource {
                 let pkg = rt.task_manager().spawn_and_block_on(async move {
                     BinaryPackage::from_registry(&inner_pck, inner_rt.as_ref()).await
                 })??;
+                if rt.package_resolution_policy() == PackageResolutionPolicy::Refresh {
+                    pb.println(format!("Using {}", pkg.id));
+                }
                 Ok(ExecutableTarget::Package(pkg))
             }
         }
@@ -832,22 +1129,36 @@ impl ExecutableTarget {
     }
 
     /// Try to load a file into something that can be used to run it.
+    ///
+    /// `.wat` files are preprocessed with
+    /// [`include_data_files()`](crate::utils::wat_include::include_data_files)
+    /// when `wat_include_dir` is set.
     #[tracing::instrument(level = "debug", skip_all)]
     fn from_file(
         path: &Path,
         runtime: &Arc<dyn Runtime + Send + Sync>,
         pb: &ProgressBar,
+        wat_include_dir: Option<&Path>,
     ) -> Result<Self, Error> {
         pb.set_message(format!("Loading from \"{}\"", path.display()));
 
         match TargetOnDisk::from_file(path)? {
-            TargetOnDisk::WebAssemblyBinary | TargetOnDisk::Wat => {
-                let wasm = std::fs::read(path)?;
+            target @ (TargetOnDisk::WebAssemblyBinary | TargetOnDisk::Wat) => {
+                let mut wasm = std::fs::read(path)?;
+                if let (TargetOnDisk::Wat, Some(include_dir)) = (target, wat_include_dir) {
+                    let wat = String::from_utf8(wasm)
+                        .with_context(|| format!("\"{}\" isn't valid UTF-8", path.display()))?;
+                    wasm = crate::utils::wat_include::include_data_files(&wat, include_dir)
+                        .with_context(|| format!("Unable to preprocess \"{}\"", path.display()))?
+                        .into_bytes();
+                }
 
                 pb.set_message("Compiling to WebAssembly");
+                let compile = crate::events::sink().phase("compile");
                 let module = runtime
                     .load_module_sync(&wasm)
                     .with_context(|| format!("Unable to compile \"{}\"", path.display()))?;
+                compile.finish();
 
                 Ok(ExecutableTarget::WebAssembly {
                     module,
@@ -973,19 +1284,33 @@ impl wasmer_wasix::runners::wcgi::Callbacks for Callbacks {
 /// Exit the current process, using the WASI exit code if the error contains
 /// one.
 fn exit_with_wasi_exit_code(result: Result<(), Error>) -> ! {
-    let exit_code = match result {
-        Ok(_) => 0,
-        Err(error) => {
-            match error.chain().find_map(get_exit_code) {
-                Some(exit_code) => exit_code.raw(),
-                None => {
-                    eprintln!("{:?}", PrettyError::new(error));
-                    // Something else happened
-                    1
-                }
+    let timed_out = matches!(&result, Err(e) if e.chain().any(|e| e.is::<TimeoutError>()));
+    let program_exit_code = match &result {
+        Err(error) if !timed_out => error.chain().find_map(get_exit_code),
+        _ => None,
+    };
+    let exit_code = match (&result, program_exit_code) {
+        (Ok(_), _) => 0,
+        _ if timed_out => TIMEOUT_EXIT_CODE,
+        (Err(_), Some(exit_code)) => exit_code.raw(),
+        // Something else happened
+        (Err(_), None) => 1,
+    };
+
+    let events = crate::events::sink();
+    if events.is_enabled() {
+        // The error is reported as part of the result
+        events.finish(&result, Some(exit_code));
+    } else if let Err(error) = result {
+        if timed_out {
+            eprintln!("{error}");
+        } else if program_exit_code.is_none() {
+            match error.chain().find_map(get_process_failure) {
+                Some(failure) => eprintln!("{failure:#}"),
+                None => eprintln!("{:?}", PrettyError::new(error)),
             }
         }
-    };
+    }
 
     std::io::stdout().flush().ok();
     std::io::stderr().flush().ok();
@@ -1006,6 +1331,14 @@ fn get_exit_code(
     None
 }
 
+fn get_process_failure<'a>(
+    error: &'a (dyn std::error::Error + 'static),
+) -> Option<&'a wasmer_wasix::ProcessFailure> {
+    error
+        .downcast_ref::<wasmer_wasix::WasiRuntimeError>()
+        .and_then(|error| error.process_failure())
+}
+
 #[derive(Debug)]
 struct MonitoringRuntime<R> {
     runtime: Arc<R>,
@@ -1054,6 +1387,14 @@ impl<R: wasmer_wasix::Runtime + Send + Sync> wasmer_wasix::Runtime for Monitorin
         })
     }
 
+    fn package_resolution_policy(&self) -> PackageResolutionPolicy {
+        self.runtime.package_resolution_policy()
+    }
+
+    fn start_function_timeout(&self) -> Option<Duration> {
+        self.runtime.start_function_timeout()
+    }
+
     fn engine(&self) -> wasmer::Engine {
         self.runtime.engine()
     }
@@ -1102,9 +1443,21 @@ impl wasmer_wasix::runtime::resolver::Source for MonitoringSource {
         &self,
         package: &PackageSpecifier,
     ) -> Result<Vec<wasmer_wasix::runtime::resolver::PackageSummary>, QueryError> {
-        self.progress.set_message(format!("Looking up {package}"));
+        let message = format!("Looking up {package}");
+        crate::events::sink().progress(None, Some(message.clone()));
+        self.progress.set_message(message);
         self.inner.query(package).await
     }
+
+    async fn refresh(
+        &self,
+        package: &PackageSpecifier,
+    ) -> Result<Vec<wasmer_wasix::runtime::resolver::PackageSummary>, QueryError> {
+        let message = format!("Checking for new versions of {package}");
+        crate::events::sink().progress(None, Some(message.clone()));
+        self.progress.set_message(message);
+        self.inner.refresh(package).await
+    }
 }
 
 #[derive(Debug)]
@@ -1120,7 +1473,9 @@ impl wasmer_wasix::runtime::package_loader::PackageLoader for MonitoringPackageL
         summary: &wasmer_wasix::runtime::resolver::PackageSummary,
     ) -> Result<Container, Error> {
         let pkg_id = summary.package_id();
-        self.progress.set_message(format!("Downloading {pkg_id}"));
+        let message = format!("Downloading {pkg_id}");
+        crate::events::sink().progress(None, Some(message.clone()));
+        self.progress.set_message(message);
 
         self.inner.load(summary).await
     }
@@ -0,0 +1,151 @@
+//! `wasmer run --test-report`.
+
+use std::{
+    fs::File,
+    io::Write,
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+use anyhow::{Context, Error};
+use wasmer_wasix::{
+    test_report::{TestEvent, TestReporter, TestSummary},
+    types::wasi::ExitCode,
+    WasiError,
+};
+
+/// The exit code used when a test failed or didn't finish (the same as
+/// libtest).
+const TEST_FAILURE_EXIT_CODE: u16 = 101;
+
+/// Streams the test events a program reports to a file, one JSON object per
+/// line, and keeps track of the results.
+#[derive(Debug)]
+pub(crate) struct TestReport {
+    file: Mutex<File>,
+    summary: Mutex<TestSummary>,
+}
+
+impl TestReport {
+    pub(crate) fn create(path: &Path) -> Result<Arc<Self>, Error> {
+        let file = File::create(path)
+            .with_context(|| format!("Unable to create \"{}\"", path.display()))?;
+        Ok(Arc::new(TestReport {
+            file: Mutex::new(file),
+            summary: Mutex::new(TestSummary::default()),
+        }))
+    }
+
+    pub(crate) fn reporter(self: &Arc<Self>) -> TestReporter {
+        let report = Arc::clone(self);
+        TestReporter::new(move |event| report.record(event))
+    }
+
+    fn record(&self, event: &TestEvent) {
+        self.summary.lock().unwrap().record(event);
+
+        // Written straight away, so a hung test shows up in the file while
+        // the program is still running
+        let mut line = serde_json::to_vec(event).expect("test events are valid JSON");
+        line.push(b'\n');
+        let mut file = self.file.lock().unwrap();
+        if let Err(err) = file.write_all(&line).and_then(|_| file.flush()) {
+            tracing::warn!(
+                error = &err as &dyn std::error::Error,
+                "Unable to write to the test report"
+            );
+        }
+    }
+
+    /// Prints the results, and fails with [`TEST_FAILURE_EXIT_CODE`] if the
+    /// program exited successfully even though not all of its tests passed.
+    pub(crate) fn finish(&self, result: Result<(), Error>) -> Result<(), Error> {
+        let summary = self.summary.lock().unwrap().clone();
+
+        eprintln!();
+        for name in &summary.failed {
+            eprintln!("    failed: {name}");
+        }
+        for name in &summary.running {
+            eprintln!("    did not finish: {name}");
+        }
+        eprintln!("{summary}");
+
+        let exited_cleanly = match &result {
+            Ok(()) => true,
+            Err(error) => error
+                .chain()
+                .find_map(super::get_exit_code)
+                .is_some_and(|code| code.raw() == 0),
+        };
+
+        if exited_cleanly && !summary.is_success() {
+            return Err(WasiError::Exit(ExitCode::from(TEST_FAILURE_EXIT_CODE)).into());
+        }
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use wasmer_wasix::test_report::TestStatus;
+
+    use super::*;
+
+    fn test(event: TestStatus, name: &str) -> TestEvent {
+        TestEvent::Test {
+            event,
+            name: name.to_string(),
+            exec_time: None,
+            stdout: None,
+            message: None,
+        }
+    }
+
+    fn exit_code(result: &Result<(), Error>) -> Option<i32> {
+        let error = result.as_ref().err()?;
+        error
+            .chain()
+            .find_map(super::super::get_exit_code)
+            .map(|c| c.raw())
+    }
+
+    #[test]
+    fn events_are_written_as_they_arrive() {
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join("report.jsonl");
+        let report = TestReport::create(&path).unwrap();
+        let reporter = report.reporter();
+
+        reporter.report(&test(TestStatus::Started, "a"));
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "{\"type\":\"test\",\"event\":\"started\",\"name\":\"a\"}\n"
+        );
+
+        reporter.report(&test(TestStatus::Ok, "a"));
+        assert_eq!(std::fs::read_to_string(&path).unwrap().lines().count(), 2);
+        assert!(report.finish(Ok(())).is_ok());
+    }
+
+    #[test]
+    fn failed_and_unfinished_tests_fail_the_run() {
+        let temp = tempfile::tempdir().unwrap();
+
+        let report = TestReport::create(&temp.path().join("failed")).unwrap();
+        report.reporter().report(&test(TestStatus::Failed, "a"));
+        assert_eq!(exit_code(&report.finish(Ok(()))), Some(101));
+
+        let report = TestReport::create(&temp.path().join("hung")).unwrap();
+        report.reporter().report(&test(TestStatus::Started, "a"));
+        let exited = Err(WasiError::Exit(ExitCode::from(0u16)).into());
+        assert_eq!(exit_code(&report.finish(exited)), Some(101));
+
+        // The program's own failure takes precedence
+        let report = TestReport::create(&temp.path().join("crashed")).unwrap();
+        report.reporter().report(&test(TestStatus::Started, "a"));
+        let crashed = Err(WasiError::Exit(ExitCode::from(3u16)).into());
+        assert_eq!(exit_code(&report.finish(crashed)), Some(3));
+    }
+}
@@ -19,7 +19,7 @@ use wasmer_types::ModuleHash;
 #[cfg(feature = "journal")]
 use wasmer_wasix::journal::{LogFileJournal, SnapshotTrigger};
 use wasmer_wasix::{
-    bin_factory::BinaryPackage,
+    bin_factory::{BinaryPackage, CommandAlias},
     capabilities::Capabilities,
     default_fs_backing, get_wasi_versions,
     http::HttpClient,
@@ -29,7 +29,7 @@ use wasmer_wasix::{
     runners::MAPPED_CURRENT_DIR_DEFAULT_PATH,
     runners::{MappedCommand, MappedDirectory},
     runtime::{
-        module_cache::{FileSystemCache, ModuleCache},
+        module_cache::{CacheError, FileSystemCache, ModuleCache, PrecompiledCache},
         package_loader::{BuiltinPackageLoader, PackageLoader},
         resolver::{
             BackendSource, FileSystemSource, InMemorySource, MultiSource, Source, WebSource,
@@ -38,6 +38,7 @@ use wasmer_wasix::{
             tokio::{RuntimeOrHandle, TokioTaskManager},
             VirtualTaskManagerExt,
         },
+        PackageResolutionPolicy,
     },
     types::__WASI_STDIN_FILENO,
     wasmer_wasix_types::wasi::Errno,
@@ -57,6 +58,18 @@ use super::{
 
 const WAPM_SOURCE_CACHE_TIMEOUT: Duration = Duration::from_secs(10 * 60);
 
+/// Host environment variables that change how programs are loaded, which
+/// `--env-pass` patterns and `--env-pass-all` skip unless a pattern names
+/// them exactly.
+const DENIED_HOST_ENVS: &[&str] = &[
+    "LD_PRELOAD",
+    "LD_LIBRARY_PATH",
+    "LD_AUDIT",
+    "DYLD_INSERT_LIBRARIES",
+    "DYLD_LIBRARY_PATH",
+    "DYLD_FRAMEWORK_PATH",
+];
+
 #[derive(Debug, Parser, Clone, Default)]
 /// WASI Options
 pub struct Wasi {
@@ -80,10 +93,19 @@ pub struct Wasi {
     )]
     pub(crate) env_vars: Vec<(String, String)>,
 
-    /// Forward all host env variables to guest
-    #[clap(long, env)]
+    /// Forward all host env variables to guest, except for the ones that
+    /// change how programs load (see `--env-pass`)
+    #[clap(long, env, alias = "env-pass-all")]
     pub(crate) forward_host_env: bool,
 
+    /// Pass the host environment variables matching a glob pattern (e.g.
+    /// `AWS_*`) to the guest. Values set with `--env` take precedence.
+    ///
+    /// Variables that can change how programs load, like `LD_PRELOAD`, are
+    /// only passed when the pattern is their exact name.
+    #[clap(long = "env-pass", name = "PATTERN")]
+    pub(crate) env_pass: Vec<glob::Pattern>,
+
     /// List of other containers this module depends on
     #[clap(long = "use", name = "USE")]
     pub(crate) uses: Vec<String>,
@@ -97,6 +119,13 @@ pub struct Wasi {
     #[clap(long = "map-command", name = "MAPCMD")]
     pub(super) map_commands: Vec<String>,
 
+    /// Make a command from another package available under a different name
+    /// (e.g. `--mapcommand python=python/python:python3`).
+    ///
+    /// The package is fetched the first time the command is executed.
+    #[clap(long = "mapcommand", name = "NAME=PACKAGE[:COMMAND]")]
+    pub(super) command_aliases: Vec<CommandAlias>,
+
     /// Enable networking with the host network.
     ///
     /// Allows WASI modules to open TCP and UDP connections, create sockets, ...
@@ -124,6 +153,12 @@ pub struct Wasi {
     #[clap(long = "no-tty")]
     pub no_tty: bool,
 
+    /// Ask the registry for the latest version of packages instead of reusing
+    /// recent lookups. Packages requested with a specific version are still
+    /// loaded from the cache.
+    #[clap(long = "refresh")]
+    pub refresh: bool,
+
     /// Enables asynchronous threading
     #[clap(long = "enable-async-threads")]
     pub enable_async_threads: bool,
@@ -225,6 +260,19 @@ pub struct Wasi {
     /// generated artifacts are cached.
     #[clap(long = "disable-cache")]
     disable_cache: bool,
+
+    /// Load modules from artifacts that were compiled ahead of time with
+    /// `wasmer compile <PACKAGE> -o <PATH>` (a directory or a bundle).
+    ///
+    /// Modules that are missing or were compiled with a different engine are
+    /// compiled as usual, after printing a warning.
+    #[clap(long, value_name = "PATH")]
+    precompiled: Option<PathBuf>,
+
+    /// Fail instead of compiling modules that can't be loaded from
+    /// `--precompiled`.
+    #[clap(long, requires = "precompiled")]
+    require_precompiled: bool,
 }
 
 pub struct RunProperties {
@@ -247,6 +295,52 @@ impl Wasi {
         self.env_vars.push((key.to_string(), value.to_string()));
     }
 
+    /// Adds the host environment variables selected with `--env-pass` and
+    /// `--env-pass-all` to the ones set with `--env`.
+    ///
+    /// Afterwards, `forward_host_env` is cleared so the runners don't
+    /// forward the variables this filtered out.
+    pub(crate) fn pass_host_envs(&mut self, from_registry: bool) {
+        if !self.forward_host_env && self.env_pass.is_empty() {
+            return;
+        }
+        if self.forward_host_env && from_registry {
+            tracing::warn!(
+                "Passing all host environment variables to a package from the registry, \
+                 consider selecting them with --env-pass instead"
+            );
+        }
+
+        let mut matched = vec![false; self.env_pass.len()];
+        let mut envs = Vec::new();
+        for (key, value) in std::env::vars() {
+            let mut pass = false;
+            for (pattern, matched) in self.env_pass.iter().zip(&mut matched) {
+                if pattern.as_str() == key {
+                    *matched = true;
+                    pass = true;
+                } else if pattern.matches(&key) {
+                    *matched = true;
+                    pass |= !DENIED_HOST_ENVS.contains(&key.as_str());
+                }
+            }
+            pass |= self.forward_host_env && !DENIED_HOST_ENVS.contains(&key.as_str());
+
+            let explicit = self.env_vars.iter().any(|(k, _)| *k == key);
+            if pass && !explicit {
+                envs.push((key, value));
+            }
+        }
+
+        for (pattern, _) in self.env_pass.iter().zip(matched).filter(|(_, m)| !m) {
+            tracing::warn!("--env-pass {pattern} doesn't match any host environment variable");
+        }
+
+        envs.append(&mut self.env_vars);
+        self.env_vars = envs;
+        self.forward_host_env = false;
+    }
+
     /// Gets the WASI version (if any) for the provided module
     pub fn get_versions(module: &Module) -> Option<BTreeSet<WasiVersion>> {
         // Get the wasi version in non-strict mode, so multiple wasi versions
@@ -299,7 +393,8 @@ impl Wasi {
             .args(args)
             .envs(self.env_vars.clone())
             .uses(uses)
-            .map_commands(map_commands);
+            .map_commands(map_commands)
+            .command_aliases(self.command_aliases.clone());
 
         let mut builder = {
             // If we preopen anything from the host then shallow copy it over
@@ -680,8 +775,30 @@ impl Wasi {
 
         let registry = self.prepare_source(env, client, preferred_webc_version)?;
 
-        if !self.disable_cache {
-            let cache_dir = env.cache_dir().join("compiled");
+        let cache_dir = env.cache_dir().join("compiled");
+        if let Some(path) = &self.precompiled {
+            let precompiled = PrecompiledCache::open(path)
+                .with_context(|| {
+                    format!(
+                        "Unable to load the precompiled artifacts from \"{}\"",
+                        path.display()
+                    )
+                })?
+                .with_required(self.require_precompiled);
+
+            if self.require_precompiled {
+                rt.set_module_cache(precompiled);
+            } else if self.disable_cache {
+                rt.set_module_cache(
+                    wasmer_wasix::runtime::module_cache::in_memory().with_fallback(precompiled),
+                );
+            } else {
+                let module_cache = wasmer_wasix::runtime::module_cache::in_memory().with_fallback(
+                    precompiled.with_fallback(FileSystemCache::new(cache_dir, tokio_task_manager)),
+                );
+                rt.set_module_cache(module_cache);
+            }
+        } else if !self.disable_cache {
             let module_cache = wasmer_wasix::runtime::module_cache::in_memory()
                 .with_fallback(FileSystemCache::new(cache_dir, tokio_task_manager));
             rt.set_module_cache(module_cache);
@@ -691,6 +808,10 @@ impl Wasi {
             .set_source(registry)
             .set_engine(engine);
 
+        if self.refresh {
+            rt.set_package_resolution_policy(PackageResolutionPolicy::Refresh);
+        }
+
         Ok(rt)
     }
 
@@ -710,6 +831,55 @@ impl Wasi {
         Ok((wasi_env, instance))
     }
 
+    /// Warns about the commands in a package whose modules can't be loaded
+    /// from `--precompiled` and will be compiled instead.
+    pub(crate) fn check_precompiled(&self, pkg: &BinaryPackage, engine: &Engine) -> Result<()> {
+        let Some(path) = &self.precompiled else {
+            return Ok(());
+        };
+        if self.require_precompiled {
+            // Loading those modules fails with a more detailed error
+            return Ok(());
+        }
+
+        let precompiled = PrecompiledCache::open(path)?;
+        for cmd in &pkg.commands {
+            if let Err(e) = precompiled.check(*cmd.hash(), engine) {
+                let reason = match e {
+                    CacheError::NotFound => "It isn't in the artifacts".to_string(),
+                    other => other.to_string(),
+                };
+                crate::warning!(
+                    "unable to use the precompiled \"{}\" command, compiling it instead: {reason}",
+                    cmd.name()
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Prepares a runtime that is only used to load packages and their
+    /// dependencies, e.g. to compile them ahead of time.
+    pub(crate) fn prepare_package_runtime(
+        env: &WasmerEnv,
+        engine: Engine,
+        handle: Handle,
+    ) -> Result<impl Runtime + Send + Sync> {
+        let wasi = Self {
+            no_tty: true,
+            disable_cache: true,
+            ..Self::default()
+        };
+        wasi.prepare_runtime(
+            engine,
+            env,
+            &env.cache_dir().join("capabilities"),
+            handle,
+            webc::Version::V3,
+        )
+    }
+
     pub fn for_binfmt_interpreter() -> Result<Self> {
         let dir = std::env::var_os("WASMER_BINFMT_MISC_PREOPEN")
             .map(Into::into)
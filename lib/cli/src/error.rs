@@ -23,7 +23,12 @@ impl PrettyError {
 macro_rules! warning {
     ($($arg:tt)*) => ({
         use colored::*;
-        eprintln!("{}: {}", "warning".yellow().bold(), format!($($arg)*));
+        let events = $crate::events::sink();
+        if events.is_enabled() {
+            events.warning(format!($($arg)*));
+        } else {
+            eprintln!("{}: {}", "warning".yellow().bold(), format!($($arg)*));
+        }
     })
 }
 
@@ -37,7 +42,10 @@ impl PrettyError {
             Err(error) => {
                 let runtime: Option<&RuntimeError> = error.downcast_ref();
                 let trapcode = runtime.map(|e| e.clone().to_trap());
-                eprintln!("{:?}", PrettyError { error });
+                // With JSON events, the error was already part of the result
+                if !crate::events::sink().is_enabled() {
+                    eprintln!("{:?}", PrettyError { error });
+                }
                 // we don't use process:abort() here to avoid message from rust
                 // that could interfer with testing tools
                 // but still exit with the expected error code
@@ -61,7 +69,10 @@ impl PrettyError {
         std::process::exit(match result {
             Ok(_t) => 0,
             Err(error) => {
-                eprintln!("{:?}", PrettyError { error });
+                // With JSON events, the error was already part of the result
+                if !crate::events::sink().is_enabled() {
+                    eprintln!("{:?}", PrettyError { error });
+                }
                 // we don't use process:abort() here to avoid message from rust
                 // that could interfer with testing tools
                 // but still exit with the expected error code
@@ -97,10 +108,29 @@ impl Debug for PrettyError {
                 write!(indented, "{error}")?;
             }
         }
+
+        if let Some(compiler) = alternative_compiler(error) {
+            write!(
+                f,
+                "\n{}: try compiling it with `--{compiler}` instead",
+                "hint".bold().blue()
+            )?;
+        }
         Ok(())
     }
 }
 
+/// A compiler that can translate the module when the one that was used gave
+/// up on one of its instructions.
+fn alternative_compiler(error: &Error) -> Option<&str> {
+    error.chain().find_map(|cause| match cause.downcast_ref() {
+        Some(wasmer::CompileError::UnsupportedOperator(unsupported)) => {
+            unsupported.supported_by.first().map(String::as_str)
+        }
+        _ => None,
+    })
+}
+
 struct Indented<'a, D> {
     inner: &'a mut D,
     number: Option<usize>,
@@ -0,0 +1,370 @@
+//! Machine-readable progress events for tools that wrap the CLI.
+//!
+//! With `--message-format json-events`, long-running commands report what they are
+//! doing as newline-delimited JSON on stderr instead of drawing progress bars
+//! and printing human-readable messages. Every line is a single [`Event`]
+//! object tagged by its `"event"` field, and the last line is always an
+//! [`Event::Result`].
+
+use std::{
+    io::Write,
+    sync::{Mutex, OnceLock},
+    time::Instant,
+};
+
+use serde_json::{Map, Value};
+
+static SINK: OnceLock<EventSink> = OnceLock::new();
+
+/// The version of the event schema, bumped whenever an event changes in a way
+/// that would break existing consumers.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// How progress and status messages are reported.
+#[derive(Debug, Default, Copy, Clone, PartialEq, clap::ValueEnum)]
+pub enum OutputFormat {
+    /// Progress bars and human-readable messages.
+    #[default]
+    Text,
+    /// Newline-delimited JSON events on stderr.
+    JsonEvents,
+}
+
+/// A single line of `--message-format json-events` output.
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+#[serde(tag = "event", rename_all = "kebab-case")]
+pub enum Event {
+    /// A phase of the command started.
+    ///
+    /// Phases nest, so every phase started after this one ends before it does.
+    PhaseStart { phase: String },
+    /// A phase of the command ended.
+    PhaseEnd {
+        phase: String,
+        success: bool,
+        elapsed_ms: u64,
+    },
+    /// Progress was made in the innermost phase.
+    Progress {
+        phase: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        percent: Option<f64>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        message: Option<String>,
+    },
+    /// Something the user should know about, which didn't stop the command.
+    Warning { message: String },
+    /// The command finished, successfully or not.
+    Result {
+        schema_version: u32,
+        success: bool,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        error: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        exit_code: Option<i32>,
+        /// Details specific to the command (e.g. the output file).
+        #[serde(skip_serializing_if = "Map::is_empty")]
+        details: Map<String, Value>,
+    },
+}
+
+/// Where events are written to.
+///
+/// A disabled sink ignores everything, so commands can report events
+/// unconditionally and only need [`EventSink::is_enabled()`] to skip their
+/// human-readable output.
+pub struct EventSink {
+    writer: Option<Mutex<Box<dyn Write + Send>>>,
+    state: Mutex<State>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    phases: Vec<String>,
+    details: Map<String, Value>,
+    finished: bool,
+}
+
+impl EventSink {
+    /// A sink that ignores all events.
+    pub fn disabled() -> Self {
+        EventSink {
+            writer: None,
+            state: Mutex::default(),
+        }
+    }
+
+    /// A sink writing one JSON object per line to `writer`.
+    pub fn new(writer: impl Write + Send + 'static) -> Self {
+        EventSink {
+            writer: Some(Mutex::new(Box::new(writer))),
+            state: Mutex::default(),
+        }
+    }
+
+    /// Install the sink used by [`sink()`] for the rest of the process.
+    pub fn install(format: OutputFormat) {
+        let sink = match format {
+            OutputFormat::Text => EventSink::disabled(),
+            OutputFormat::JsonEvents => EventSink::new(std::io::stderr()),
+        };
+        let _ = SINK.set(sink);
+    }
+
+    /// Are events being reported (i.e. should human-readable output be
+    /// skipped)?
+    pub fn is_enabled(&self) -> bool {
+        self.writer.is_some()
+    }
+
+    fn emit(&self, event: Event) {
+        let Some(writer) = &self.writer else {
+            return;
+        };
+        let Ok(line) = serde_json::to_string(&event) else {
+            return;
+        };
+        let mut writer = writer.lock().unwrap();
+        let _ = writeln!(writer, "{line}");
+        let _ = writer.flush();
+    }
+
+    /// Start a new phase, nested inside the current one.
+    ///
+    /// The phase ends successfully when [`Phase::finish()`] is called, and
+    /// unsuccessfully if the [`Phase`] is dropped first (e.g. because `?`
+    /// returned early).
+    pub fn phase(&self, name: impl Into<String>) -> Phase<'_> {
+        let name = name.into();
+        if self.is_enabled() {
+            self.state.lock().unwrap().phases.push(name.clone());
+            self.emit(Event::PhaseStart {
+                phase: name.clone(),
+            });
+        }
+
+        Phase {
+            sink: self,
+            name,
+            started: Instant::now(),
+            finished: false,
+        }
+    }
+
+    /// Report progress in the innermost phase, as `done` out of `total` and/or
+    /// a message describing what is happening.
+    pub fn progress(&self, done_total: Option<(u64, u64)>, message: Option<String>) {
+        if !self.is_enabled() {
+            return;
+        }
+
+        let phase = self.state.lock().unwrap().phases.last().cloned();
+        let percent = done_total.map(|(done, total)| match total {
+            0 => 100.0,
+            total => (done.min(total) as f64 * 1000.0 / total as f64).round() / 10.0,
+        });
+        self.emit(Event::Progress {
+            phase: phase.unwrap_or_default(),
+            percent,
+            message,
+        });
+    }
+
+    /// Report a warning.
+    pub fn warning(&self, message: impl Into<String>) {
+        self.emit(Event::Warning {
+            message: message.into(),
+        });
+    }
+
+    /// Add a command-specific detail to the final [`Event::Result`].
+    pub fn record(&self, key: &str, value: impl serde::Serialize) {
+        if !self.is_enabled() {
+            return;
+        }
+
+        if let Ok(value) = serde_json::to_value(value) {
+            self.state
+                .lock()
+                .unwrap()
+                .details
+                .insert(key.to_string(), value);
+        }
+    }
+
+    /// Report the outcome of the command. Only the first call has any effect.
+    pub fn finish(&self, result: &Result<(), anyhow::Error>, exit_code: Option<i32>) {
+        let details = {
+            let mut state = self.state.lock().unwrap();
+            if state.finished {
+                return;
+            }
+            state.finished = true;
+            std::mem::take(&mut state.details)
+        };
+
+        self.emit(Event::Result {
+            schema_version: SCHEMA_VERSION,
+            success: result.is_ok(),
+            error: result.as_ref().err().map(|e| format!("{e:#}")),
+            exit_code,
+            details,
+        });
+    }
+}
+
+impl std::fmt::Debug for EventSink {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EventSink")
+            .field("enabled", &self.is_enabled())
+            .field("state", &self.state)
+            .finish()
+    }
+}
+
+/// The sink installed with [`EventSink::install()`], or a disabled one.
+pub fn sink() -> &'static EventSink {
+    static DISABLED: OnceLock<EventSink> = OnceLock::new();
+    SINK.get()
+        .unwrap_or_else(|| DISABLED.get_or_init(EventSink::disabled))
+}
+
+/// A phase started with [`EventSink::phase()`].
+#[derive(Debug)]
+#[must_use = "the phase ends as soon as it is dropped"]
+pub struct Phase<'a> {
+    sink: &'a EventSink,
+    name: String,
+    started: Instant,
+    finished: bool,
+}
+
+impl Phase<'_> {
+    /// End the phase successfully.
+    pub fn finish(mut self) {
+        self.end(true);
+    }
+
+    fn end(&mut self, success: bool) {
+        if self.finished || !self.sink.is_enabled() {
+            return;
+        }
+        self.finished = true;
+
+        {
+            let mut state = self.sink.state.lock().unwrap();
+            if let Some(index) = state.phases.iter().rposition(|p| *p == self.name) {
+                state.phases.truncate(index);
+            }
+        }
+
+        self.sink.emit(Event::PhaseEnd {
+            phase: self.name.clone(),
+            success,
+            elapsed_ms: self.started.elapsed().as_millis() as u64,
+        });
+    }
+}
+
+impl Drop for Phase<'_> {
+    fn drop(&mut self) {
+        self.end(false);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+
+    #[derive(Clone, Default)]
+    struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Buffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl Buffer {
+        fn lines(&self) -> Vec<Value> {
+            let buffer = self.0.lock().unwrap();
+            std::str::from_utf8(&buffer)
+                .unwrap()
+                .lines()
+                .map(|line| serde_json::from_str(line).unwrap())
+                .collect()
+        }
+    }
+
+    fn without_timings(mut event: Value) -> Value {
+        event.as_object_mut().unwrap().remove("elapsed_ms");
+        event
+    }
+
+    #[test]
+    fn phases_nest_and_fail_when_dropped() {
+        let buffer = Buffer::default();
+        let sink = EventSink::new(buffer.clone());
+
+        let outer = sink.phase("outer");
+        {
+            let inner = sink.phase("inner");
+            sink.progress(Some((1, 3)), Some("working".to_string()));
+            inner.finish();
+        }
+        {
+            let _failed = sink.phase("failed");
+            sink.warning("careful");
+        }
+        outer.finish();
+        sink.record("output", "out.wasmu");
+        sink.finish(&Ok(()), None);
+        sink.finish(&Err(anyhow::anyhow!("ignored")), None);
+
+        let events: Vec<_> = buffer.lines().into_iter().map(without_timings).collect();
+        assert_eq!(
+            events,
+            vec![
+                serde_json::json!({"event": "phase-start", "phase": "outer"}),
+                serde_json::json!({"event": "phase-start", "phase": "inner"}),
+                serde_json::json!({
+                    "event": "progress",
+                    "phase": "inner",
+                    "percent": 33.3,
+                    "message": "working",
+                }),
+                serde_json::json!({"event": "phase-end", "phase": "inner", "success": true}),
+                serde_json::json!({"event": "phase-start", "phase": "failed"}),
+                serde_json::json!({"event": "warning", "message": "careful"}),
+                serde_json::json!({"event": "phase-end", "phase": "failed", "success": false}),
+                serde_json::json!({"event": "phase-end", "phase": "outer", "success": true}),
+                serde_json::json!({
+                    "event": "result",
+                    "schema_version": SCHEMA_VERSION,
+                    "success": true,
+                    "details": {"output": "out.wasmu"},
+                }),
+            ]
+        );
+    }
+
+    #[test]
+    fn disabled_sinks_ignore_everything() {
+        let sink = EventSink::disabled();
+
+        let phase = sink.phase("phase");
+        sink.progress(Some((1, 2)), None);
+        sink.record("key", 42);
+        phase.finish();
+
+        assert!(!sink.is_enabled());
+        assert!(sink.state.lock().unwrap().phases.is_empty());
+        assert!(sink.state.lock().unwrap().details.is_empty());
+    }
+}
@@ -26,6 +26,7 @@ mod error;
 mod backend;
 #[cfg(feature = "static-artifact-create")]
 mod c_gen;
+mod events;
 mod logging;
 mod opts;
 mod types;
@@ -1,8 +1,14 @@
 //! Logging functions for the debug feature.
 
+use std::str::FromStr;
+
 use is_terminal::IsTerminal;
 use tracing::level_filters::LevelFilter;
-use tracing_subscriber::{fmt, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
+use tracing_subscriber::{
+    filter::Directive, fmt, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter,
+};
+
+use crate::events::OutputFormat;
 
 const WHITELISTED_LOG_TARGETS: &[&str] = &["wasmer", "wasmer_wasix", "virtual_fs"];
 
@@ -21,9 +27,23 @@ pub struct Output {
     /// Which span events to log.
     #[clap(long, global = true, env, default_value = "close")]
     pub log_events: LogEvents,
+    /// Comma-separated `RUST_LOG`-style directives for logging some modules
+    /// in more detail, e.g. `wasmer_wasix::syscalls::wasix::sock_*=trace`.
+    ///
+    /// A `*` at the end of a target matches every module whose name starts
+    /// with the rest of it. These take precedence over `--verbose` and
+    /// `$RUST_LOG`.
+    #[clap(long, global = true)]
+    pub log_filter: Option<LogFilter>,
     /// When to display colored output.
     #[clap(long, default_value_t = clap::ColorChoice::Auto, global = true)]
     pub color: clap::ColorChoice,
+    /// How to report progress and status messages.
+    ///
+    /// With "json-events", long-running commands write newline-delimited JSON
+    /// events to stderr instead of progress bars and human-readable messages.
+    #[clap(long = "message-format", global = true, value_enum, default_value_t = OutputFormat::Text)]
+    pub output_format: OutputFormat,
 }
 
 impl Output {
@@ -96,6 +116,12 @@ impl Output {
             }
         }
 
+        if let Some(LogFilter(directives)) = &self.log_filter {
+            for directive in directives {
+                filter = filter.add_directive(directive.clone());
+            }
+        }
+
         filter
     }
 
@@ -116,9 +142,9 @@ impl Output {
     /// Get the draw target to be used with the `indicatif` crate.
     ///
     /// Progress indicators won't draw anything if the user passed the `--quiet`
-    /// flag.
+    /// flag or asked for JSON events.
     pub fn draw_target(&self) -> indicatif::ProgressDrawTarget {
-        if self.quiet {
+        if self.quiet || self.output_format == OutputFormat::JsonEvents {
             return indicatif::ProgressDrawTarget::hidden();
         }
 
@@ -126,6 +152,37 @@ impl Output {
     }
 }
 
+/// The directives passed to `--log-filter`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogFilter(Vec<Directive>);
+
+impl FromStr for LogFilter {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.split(',')
+            .map(str::trim)
+            .filter(|directive| !directive.is_empty())
+            .map(|directive| {
+                // Targets are matched by prefix, so `sock_*` is just `sock_`
+                let target_end = directive.find(['[', '=']).unwrap_or(directive.len());
+                let (target, rest) = directive.split_at(target_end);
+                let target = target.strip_suffix('*').unwrap_or(target);
+                if target.contains('*') || rest.contains('*') {
+                    return Err(format!(
+                        "invalid directive \"{directive}\": `*` is only allowed at the end of a target"
+                    ));
+                }
+
+                format!("{target}{rest}")
+                    .parse()
+                    .map_err(|e| format!("invalid directive \"{directive}\": {e}"))
+            })
+            .collect::<Result<_, _>>()
+            .map(LogFilter)
+    }
+}
+
 /// The format used when generating logs.
 #[derive(Debug, Default, Copy, Clone, PartialEq, clap::ValueEnum)]
 pub enum LogFormat {
@@ -147,3 +204,48 @@ pub enum LogEvents {
     // Log at the start and end of a span.
     All,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_log_filter() {
+        let LogFilter(directives) = "wasmer_wasix::syscalls::wasix::sock_*=trace, virtual_fs=debug"
+            .parse()
+            .unwrap();
+
+        let directives: Vec<_> = directives.iter().map(|d| d.to_string()).collect();
+        assert_eq!(
+            directives,
+            [
+                "wasmer_wasix::syscalls::wasix::sock_=trace",
+                "virtual_fs=debug"
+            ]
+        );
+    }
+
+    #[test]
+    fn log_filter_only_allows_trailing_wildcards() {
+        let error = "wasmer_*::syscalls=trace".parse::<LogFilter>().unwrap_err();
+
+        assert_eq!(
+            error,
+            "invalid directive \"wasmer_*::syscalls=trace\": `*` is only allowed at the end of a target"
+        );
+    }
+
+    #[test]
+    fn log_filter_overrides_verbosity() {
+        let output = Output {
+            verbose: 1,
+            log_filter: Some("wasmer_wasix::syscalls=trace".parse().unwrap()),
+            ..Default::default()
+        };
+
+        let filter = output.log_filter().to_string();
+
+        assert!(filter.contains("wasmer_wasix=info"), "{filter}");
+        assert!(filter.contains("wasmer_wasix::syscalls=trace"), "{filter}");
+    }
+}
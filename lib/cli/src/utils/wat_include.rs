@@ -0,0 +1,232 @@
+//! Preprocessing `.wat` files whose data segments include other files.
+
+use std::{fmt::Write as _, path::Path};
+
+use anyhow::{bail, Context as _, Result};
+
+/// The directive that is replaced by the contents of a file.
+const DIRECTIVE: &str = "(@data.file";
+
+/// Replaces every `(@data.file "path")` directive in the data segments of
+/// `wat` with a string holding the contents of the file. Relative paths are
+/// resolved against `include_dir`.
+///
+/// Sources without any directives are returned unchanged.
+pub(crate) fn include_data_files(wat: &str, include_dir: &Path) -> Result<String> {
+    let bytes = wat.as_bytes();
+    let mut output = String::with_capacity(wat.len());
+    // How much of `wat` has been copied to `output`
+    let mut copied = 0;
+    // The keyword of every form we are in, e.g. `["module", "data"]`
+    let mut forms: Vec<&str> = Vec::new();
+
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b';' if bytes.get(i + 1) == Some(&b';') => {
+                i = wat[i..].find('\n').map_or(bytes.len(), |end| i + end);
+            }
+            b'(' if bytes.get(i + 1) == Some(&b';') => i = skip_block_comment(wat, i)?,
+            b'"' => i = skip_string(wat, i)?,
+            b'(' if is_directive(wat, i) => {
+                let (path, end) = parse_directive(wat, i)?;
+                if !forms.contains(&"data") {
+                    bail!(
+                        "line {}: `{DIRECTIVE} ...)` is only allowed in data segments",
+                        line_of(wat, i)
+                    );
+                }
+                let data = read_data_file(&include_dir.join(&path), line_of(wat, i))?;
+
+                output.push_str(&wat[copied..i]);
+                output.push('"');
+                for byte in data {
+                    write!(output, "\\{byte:02x}").unwrap();
+                }
+                output.push('"');
+                copied = end;
+                i = end;
+            }
+            b'(' => {
+                let keyword = &wat[i + 1..];
+                let len = keyword
+                    .find(|c: char| c.is_whitespace() || matches!(c, '(' | ')' | '"'))
+                    .unwrap_or(keyword.len());
+                forms.push(&keyword[..len]);
+                i += 1;
+            }
+            b')' => {
+                forms.pop();
+                i += 1;
+            }
+            _ => i += 1,
+        }
+    }
+
+    output.push_str(&wat[copied..]);
+    Ok(output)
+}
+
+fn is_directive(wat: &str, start: usize) -> bool {
+    wat[start..].starts_with(DIRECTIVE)
+        && wat[start + DIRECTIVE.len()..]
+            .starts_with(|c: char| c.is_whitespace() || c == '"' || c == ')')
+}
+
+/// Parses the directive at `start`, returning its path and where it ends.
+fn parse_directive(wat: &str, start: usize) -> Result<(String, usize)> {
+    let line = line_of(wat, start);
+    let rest = wat[start + DIRECTIVE.len()..].trim_start();
+    let Some(quoted) = rest.strip_prefix('"') else {
+        bail!("line {line}: expected a path after `{DIRECTIVE}`");
+    };
+
+    let mut path = String::new();
+    let mut chars = quoted.char_indices();
+    let after_path = loop {
+        match chars.next() {
+            Some((i, '"')) => break &quoted[i + 1..],
+            Some((_, '\\')) => match chars.next() {
+                Some((_, c @ ('"' | '\\'))) => path.push(c),
+                _ => bail!("line {line}: only `\\\"` and `\\\\` escapes are supported in paths"),
+            },
+            Some((_, c)) => path.push(c),
+            None => bail!("line {line}: unterminated path"),
+        }
+    };
+
+    let Some(after_directive) = after_path.trim_start().strip_prefix(')') else {
+        bail!("line {line}: expected `)` after the path");
+    };
+    Ok((path, wat.len() - after_directive.len()))
+}
+
+fn read_data_file(path: &Path, line: usize) -> Result<Vec<u8>> {
+    let len = std::fs::metadata(path)
+        .with_context(|| format!("line {line}: unable to read \"{}\"", path.display()))?
+        .len();
+    // Data segments, like the memories they are copied to, are addressed
+    // with 32-bit offsets
+    if len > u64::from(u32::MAX) {
+        bail!(
+            "line {line}: \"{}\" is {len} bytes, which is more than a data segment can hold",
+            path.display()
+        );
+    }
+
+    std::fs::read(path)
+        .with_context(|| format!("line {line}: unable to read \"{}\"", path.display()))
+}
+
+/// Skips the (possibly nested) block comment at `start`.
+fn skip_block_comment(wat: &str, start: usize) -> Result<usize> {
+    let bytes = wat.as_bytes();
+    let mut depth = 0;
+    let mut i = start;
+    while i < bytes.len() {
+        if bytes[i..].starts_with(b"(;") {
+            depth += 1;
+            i += 2;
+        } else if bytes[i..].starts_with(b";)") {
+            depth -= 1;
+            i += 2;
+            if depth == 0 {
+                return Ok(i);
+            }
+        } else {
+            i += 1;
+        }
+    }
+    bail!("line {}: unterminated block comment", line_of(wat, start))
+}
+
+/// Skips the string at `start`.
+fn skip_string(wat: &str, start: usize) -> Result<usize> {
+    let bytes = wat.as_bytes();
+    let mut i = start + 1;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 2,
+            b'"' => return Ok(i + 1),
+            _ => i += 1,
+        }
+    }
+    bail!("line {}: unterminated string", line_of(wat, start))
+}
+
+fn line_of(wat: &str, offset: usize) -> usize {
+    wat[..offset].matches('\n').count() + 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sources_without_directives_are_unchanged() {
+        let wat = r#"(module
+    ;; (@data.file "commented.bin")
+    (; a (; nested ;) (@data.file "block.bin") ;)
+    (memory 1)
+    (data (i32.const 0) "(@data.file \"string.bin\")"))"#;
+
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(include_data_files(wat, dir.path()).unwrap(), wat);
+    }
+
+    #[test]
+    fn directives_are_replaced_by_the_file() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("blob.bin"), [0x00, 0xff, b'"', b'\\']).unwrap();
+        let wat = r#"(module (memory 1) (data (i32.const 0) "<" (@data.file "blob.bin") ">"))"#;
+
+        let output = include_data_files(wat, dir.path()).unwrap();
+
+        assert_eq!(
+            output,
+            r#"(module (memory 1) (data (i32.const 0) "<" "\00\ff\22\5c" ">"))"#
+        );
+    }
+
+    #[test]
+    fn errors_have_line_numbers() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("blob.bin"), b"data").unwrap();
+
+        let missing = "(module\n  (memory 1)\n  (data (i32.const 0) (@data.file \"missing.bin\")))";
+        let error = include_data_files(missing, dir.path()).unwrap_err();
+        assert!(
+            error.to_string().starts_with("line 3: unable to read"),
+            "{error}"
+        );
+
+        let misplaced = "(module\n  (@data.file \"blob.bin\"))";
+        let error = include_data_files(misplaced, dir.path()).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "line 2: `(@data.file ...)` is only allowed in data segments"
+        );
+
+        let unterminated = "(module\n  (data (i32.const 0) (@data.file \"blob.bin\"";
+        let error = include_data_files(unterminated, dir.path()).unwrap_err();
+        assert_eq!(error.to_string(), "line 2: expected `)` after the path");
+    }
+
+    #[test]
+    fn files_larger_than_a_data_segment_are_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        // Sparse, so nothing is actually written
+        let f = std::fs::File::create(dir.path().join("huge.bin")).unwrap();
+        f.set_len(u64::from(u32::MAX) + 1).unwrap();
+        let wat = "(module\n  (memory 1)\n  (data (i32.const 0) (@data.file \"huge.bin\")))";
+
+        let error = include_data_files(wat, dir.path()).unwrap_err();
+
+        assert!(
+            error
+                .to_string()
+                .ends_with("is 4294967296 bytes, which is more than a data segment can hold"),
+            "{error}"
+        );
+    }
+}
@@ -152,7 +152,7 @@ impl CraneliftCompiler {
                 reader.set_middleware_chain(
                     self.config
                         .middlewares
-                        .generate_function_middleware_chain(i),
+                        .generate_function_middleware_chain(i, module, input)?,
                 );
 
                 func_translator.translate(
@@ -265,7 +265,7 @@ impl CraneliftCompiler {
                 reader.set_middleware_chain(
                     self.config
                         .middlewares
-                        .generate_function_middleware_chain(*i),
+                        .generate_function_middleware_chain(*i, module, input)?,
                 );
 
                 func_translator.translate(
@@ -1156,7 +1156,7 @@ impl BaseFuncEnvironment for FuncEnvironment<'_> {
         // If we have a declared maximum, we can make this a "static" heap, which is
         // allocated up front and never moved.
         let (offset_guard_size, heap_style, readonly_base) = match self.memory_styles[index] {
-            MemoryStyle::Dynamic { offset_guard_size } => {
+            style @ (MemoryStyle::Dynamic { .. } | MemoryStyle::External) => {
                 let heap_bound = func.create_global_value(ir::GlobalValueData::Load {
                     base: ptr,
                     offset: Offset32::new(current_length_offset),
@@ -1164,7 +1164,7 @@ impl BaseFuncEnvironment for FuncEnvironment<'_> {
                     flags: ir::MemFlags::trusted(),
                 });
                 (
-                    Uimm64::new(offset_guard_size),
+                    Uimm64::new(style.offset_guard_size()),
                     HeapStyle::Dynamic {
                         bound_gv: heap_bound,
                     },
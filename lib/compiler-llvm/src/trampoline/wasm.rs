@@ -500,7 +500,8 @@ impl FuncTrampoline {
             ));
 
             let memory_style = &memory_styles[memory_index];
-            let base_ptr = if let MemoryStyle::Dynamic { .. } = memory_style {
+            let base_ptr = if let MemoryStyle::Dynamic { .. } | MemoryStyle::External = memory_style
+            {
                 base_ptr
             } else {
                 let base_ptr =
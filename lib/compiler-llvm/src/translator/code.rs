@@ -200,11 +200,11 @@ impl FuncTranslator {
             function_body.data,
             function_body.module_offset,
         );
-        reader.set_middleware_chain(
-            config
-                .middlewares
-                .generate_function_middleware_chain(*local_func_index),
-        );
+        reader.set_middleware_chain(config.middlewares.generate_function_middleware_chain(
+            *local_func_index,
+            wasm_module,
+            function_body,
+        )?);
 
         let mut params = vec![];
         let first_param =
@@ -1356,7 +1356,9 @@ impl<'ctx, 'a> CtxType<'ctx, 'a> {
                     intrinsics.vmmemory_definition_base_element,
                     "",
                 ));
-                let value = if let MemoryStyle::Dynamic { .. } = memory_style {
+                // Memories whose accesses have to be checked need the current
+                // length, even if their base never moves
+                let value = if memory_style.needs_bounds_checks() {
                     let current_length_ptr = err!(cache_builder.build_struct_gep(
                         intrinsics.vmmemory_definition_ty,
                         memory_definition_ptr,
@@ -1036,10 +1036,7 @@ impl<'a, M: Machine> FuncGen<'a, M> {
         &mut self,
         cb: F,
     ) -> Result<(), CompileError> {
-        let need_check = match self.memory_styles[MemoryIndex::new(0)] {
-            MemoryStyle::Static { .. } => false,
-            MemoryStyle::Dynamic { .. } => true,
-        };
+        let need_check = self.memory_styles[MemoryIndex::new(0)].needs_bounds_checks();
 
         let offset = if self.module.num_imported_memories != 0 {
             self.vmoffsets
@@ -26,6 +26,7 @@ use wasmer_compiler::{
         module::CompileModuleInfo,
         section::SectionIndex,
     },
+    wasmparser::Operator,
     Compiler, CompilerConfig, FunctionBinaryReader, FunctionBodyData, MiddlewareBinaryReader,
     ModuleMiddleware, ModuleMiddlewareChain, ModuleTranslationState,
 };
@@ -33,7 +34,7 @@ use wasmer_types::entity::{EntityRef, PrimaryMap};
 use wasmer_types::target::{Architecture, CallingConvention, CpuFeature, Target};
 use wasmer_types::{
     CompileError, FunctionIndex, FunctionType, LocalFunctionIndex, MemoryIndex, ModuleInfo,
-    TableIndex, TrapCode, TrapInformation, VMOffsets,
+    TableIndex, TrapCode, TrapInformation, UnsupportedOperator, VMOffsets,
 };
 
 /// A compiler that compiles a WebAssembly module with Singlepass.
@@ -53,6 +54,23 @@ impl SinglepassCompiler {
     fn config(&self) -> &Singlepass {
         &self.config
     }
+
+    /// Fail with a [`CompileError::UnsupportedOperator`] for the operators
+    /// Singlepass can't translate, rather than whatever error code
+    /// generation would run into.
+    fn check_operator(
+        &self,
+        op: &Operator<'_>,
+        function_index: FunctionIndex,
+        offset: usize,
+    ) -> Result<(), CompileError> {
+        if self.config.supports_operator(op) {
+            return Ok(());
+        }
+        Err(CompileError::UnsupportedOperator(Box::new(
+            UnsupportedOperator::new(self.name(), op, function_index, offset),
+        )))
+    }
 }
 
 impl Compiler for SinglepassCompiler {
@@ -150,7 +168,7 @@ impl Compiler for SinglepassCompiler {
                 let middleware_chain = self
                     .config
                     .middlewares
-                    .generate_function_middleware_chain(i);
+                    .generate_function_middleware_chain(i, module, input)?;
                 let mut reader =
                     MiddlewareBinaryReader::new_with_offset(input.data, input.module_offset);
                 reader.set_middleware_chain(middleware_chain);
@@ -180,8 +198,10 @@ impl Compiler for SinglepassCompiler {
                             calling_convention,
                         )?;
                         while generator.has_control_frames() {
-                            generator.set_srcloc(reader.original_position() as u32);
+                            let offset = reader.original_position();
+                            generator.set_srcloc(offset as u32);
                             let op = reader.read_operator()?;
+                            self.check_operator(&op, module.func_index(i), offset)?;
                             generator.feed_operator(op)?;
                         }
 
@@ -201,8 +221,10 @@ impl Compiler for SinglepassCompiler {
                             calling_convention,
                         )?;
                         while generator.has_control_frames() {
-                            generator.set_srcloc(reader.original_position() as u32);
+                            let offset = reader.original_position();
+                            generator.set_srcloc(offset as u32);
                             let op = reader.read_operator()?;
+                            self.check_operator(&op, module.func_index(i), offset)?;
                             generator.feed_operator(op)?;
                         }
 
@@ -315,6 +337,7 @@ mod tests {
     ) {
         let compile_info = CompileModuleInfo {
             features: Features::new(),
+            required_features: Features::none(),
             module: Arc::new(ModuleInfo::new()),
             memory_styles: PrimaryMap::<MemoryIndex, MemoryStyle>::new(),
             table_styles: PrimaryMap::<TableIndex, TableStyle>::new(),
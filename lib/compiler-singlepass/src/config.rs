@@ -3,7 +3,9 @@
 
 use crate::compiler::SinglepassCompiler;
 use std::sync::Arc;
-use wasmer_compiler::{Compiler, CompilerConfig, Engine, EngineBuilder, ModuleMiddleware};
+use wasmer_compiler::{
+    wasmparser::Operator, Compiler, CompilerConfig, Engine, EngineBuilder, ModuleMiddleware,
+};
 use wasmer_types::{
     target::{CpuFeature, Target},
     Features,
@@ -50,6 +52,17 @@ impl CompilerConfig for Singlepass {
         features
     }
 
+    /// Instructions from SIMD, tail calls and exception handling aren't
+    /// implemented yet.
+    fn supports_operator(&self, operator: &Operator<'_>) -> bool {
+        let required = Features::required_by_operator(operator);
+        !(required.simd || required.relaxed_simd || required.tail_call || required.exceptions)
+    }
+
+    fn canonicalize_nans(&mut self, enable: bool) {
+        self.enable_nan_canonicalization = enable;
+    }
+
     /// Pushes a middleware onto the back of the middleware chain.
     fn push_middleware(&mut self, middleware: Arc<dyn ModuleMiddleware>) {
         self.middlewares.push(middleware);
@@ -64,6 +64,30 @@ impl ArtifactBuild {
         memory_styles: PrimaryMap<MemoryIndex, MemoryStyle>,
         table_styles: PrimaryMap<TableIndex, TableStyle>,
         hash_algorithm: Option<HashAlgorithm>,
+    ) -> Result<Self, CompileError> {
+        let required_features = inner_engine.validate(data)?;
+        Self::from_validated(
+            inner_engine,
+            data,
+            required_features,
+            target,
+            memory_styles,
+            table_styles,
+            hash_algorithm,
+        )
+    }
+
+    /// Compile a data buffer that has already been validated, and found to
+    /// use `required_features`, into a `ArtifactBuild`.
+    #[cfg(feature = "compiler")]
+    pub(crate) fn from_validated(
+        inner_engine: &mut EngineInner,
+        data: &[u8],
+        required_features: Features,
+        target: &Target,
+        memory_styles: PrimaryMap<MemoryIndex, MemoryStyle>,
+        table_styles: PrimaryMap<TableIndex, TableStyle>,
+        hash_algorithm: Option<HashAlgorithm>,
     ) -> Result<Self, CompileError> {
         let environ = ModuleEnvironment::new();
         let features = inner_engine.features().clone();
@@ -75,6 +99,9 @@ impl ArtifactBuild {
         // We try to apply the middleware first
         let mut module = translation.module;
         let middlewares = compiler.get_middlewares();
+        middlewares
+            .inspect_function_bodies(&translation.function_body_inputs)
+            .map_err(|err| CompileError::MiddlewareError(err.to_string()))?;
         middlewares
             .apply_on_module_info(&mut module)
             .map_err(|err| CompileError::MiddlewareError(err.to_string()))?;
@@ -91,6 +118,7 @@ impl ArtifactBuild {
         let compile_info = CompileModuleInfo {
             module: Arc::new(module),
             features,
+            required_features,
             memory_styles,
             table_styles,
         };
@@ -240,6 +268,10 @@ impl<'a> ArtifactCreate<'a> for ArtifactBuild {
         &self.serializable.compile_info.features
     }
 
+    fn required_features(&self) -> &Features {
+        &self.serializable.compile_info.required_features
+    }
+
     fn cpu_features(&self) -> EnumSet<CpuFeature> {
         EnumSet::from_u64(self.serializable.cpu_features)
     }
@@ -476,6 +508,10 @@ impl<'a> ArtifactCreate<'a> for ArtifactBuildFromArchive {
         &self.compile_info.features
     }
 
+    fn required_features(&self) -> &Features {
+        &self.compile_info.required_features
+    }
+
     fn cpu_features(&self) -> EnumSet<CpuFeature> {
         EnumSet::from_u64(self.cell.borrow_dependent().cpu_features)
     }
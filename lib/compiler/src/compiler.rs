@@ -9,6 +9,8 @@ use crate::{
     FunctionBodyData, ModuleTranslationState,
 };
 use enumset::EnumSet;
+#[cfg(feature = "translator")]
+use wasmer_types::FeatureDetector;
 use wasmer_types::{
     entity::PrimaryMap,
     error::CompileError,
@@ -16,7 +18,9 @@ use wasmer_types::{
     Features, LocalFunctionIndex,
 };
 #[cfg(feature = "translator")]
-use wasmparser::{Validator, WasmFeatures};
+use wasmparser::{
+    Chunk, FuncValidatorAllocations, Operator, Parser, ValidPayload, Validator, WasmFeatures,
+};
 
 /// The compiler configuration options.
 pub trait CompilerConfig {
@@ -67,6 +71,17 @@ pub trait CompilerConfig {
         Features::default()
     }
 
+    /// Whether this compiler can translate the given instruction, as long
+    /// as the features it belongs to are supported.
+    ///
+    /// Compilers that can't translate some of the instructions of a
+    /// feature they otherwise support should report them here, and fail with
+    /// [`CompileError::UnsupportedOperator`] when they come across them.
+    #[cfg(feature = "translator")]
+    fn supports_operator(&self, _operator: &Operator<'_>) -> bool {
+        true
+    }
+
     /// Pushes a middleware onto the back of the middleware chain.
     fn push_middleware(&mut self, middleware: Arc<dyn ModuleMiddleware>);
 }
@@ -108,40 +123,10 @@ pub trait Compiler: Send + std::fmt::Debug {
 
     /// Validates a module.
     ///
-    /// It returns the a succesful Result in case is valid, `CompileError` in case is not.
+    /// It returns the features the module uses in case is valid, `CompileError` in case is not.
     #[cfg(feature = "translator")]
-    fn validate_module(&self, features: &Features, data: &[u8]) -> Result<(), CompileError> {
-        let mut wasm_features = WasmFeatures::default();
-        wasm_features.set(WasmFeatures::BULK_MEMORY, features.bulk_memory);
-        wasm_features.set(WasmFeatures::THREADS, features.threads);
-        wasm_features.set(WasmFeatures::REFERENCE_TYPES, features.reference_types);
-        wasm_features.set(WasmFeatures::MULTI_VALUE, features.multi_value);
-        wasm_features.set(WasmFeatures::SIMD, features.simd);
-        wasm_features.set(WasmFeatures::TAIL_CALL, features.tail_call);
-        wasm_features.set(WasmFeatures::MULTI_MEMORY, features.multi_memory);
-        wasm_features.set(WasmFeatures::MEMORY64, features.memory64);
-        wasm_features.set(WasmFeatures::EXCEPTIONS, features.exceptions);
-        wasm_features.set(WasmFeatures::EXTENDED_CONST, features.extended_const);
-        wasm_features.set(WasmFeatures::RELAXED_SIMD, features.relaxed_simd);
-        wasm_features.set(WasmFeatures::MUTABLE_GLOBAL, true);
-        wasm_features.set(WasmFeatures::SATURATING_FLOAT_TO_INT, true);
-        wasm_features.set(WasmFeatures::FLOATS, true);
-        wasm_features.set(WasmFeatures::SIGN_EXTENSION, true);
-        wasm_features.set(WasmFeatures::GC_TYPES, true);
-
-        // Not supported
-        wasm_features.set(WasmFeatures::COMPONENT_MODEL, false);
-        wasm_features.set(WasmFeatures::FUNCTION_REFERENCES, false);
-        wasm_features.set(WasmFeatures::MEMORY_CONTROL, false);
-        wasm_features.set(WasmFeatures::GC, false);
-        wasm_features.set(WasmFeatures::COMPONENT_MODEL_VALUES, false);
-        wasm_features.set(WasmFeatures::COMPONENT_MODEL_NESTED_NAMES, false);
-
-        let mut validator = Validator::new_with_features(wasm_features);
-        validator
-            .validate_all(data)
-            .map_err(|e| CompileError::Validate(format!("{e}")))?;
-        Ok(())
+    fn validate_module(&self, features: &Features, data: &[u8]) -> Result<Features, CompileError> {
+        validate_with_features(features, data).map_err(|e| CompileError::Validate(format!("{e}")))
     }
 
     /// Compiles a parsed module.
@@ -186,3 +171,203 @@ pub trait Compiler: Send + std::fmt::Debug {
         false
     }
 }
+
+/// Validates `data` like [`Validator::validate_all()`] does, recording the
+/// features the module uses along the way.
+#[cfg(feature = "translator")]
+fn validate_with_features(
+    features: &Features,
+    data: &[u8],
+) -> Result<Features, wasmparser::BinaryReaderError> {
+    let mut validator = Validator::new_with_features(wasm_features(features));
+    let mut detector = FeatureDetector::new();
+    let mut functions = Vec::new();
+
+    let mut parser = Parser::new(0);
+    parser.set_features(*validator.features());
+    for payload in parser.parse_all(data) {
+        let payload = payload?;
+        if let ValidPayload::Func(func, body) = validator.payload(&payload)? {
+            functions.push((func, body));
+        }
+        detector.payload(&payload)?;
+    }
+
+    // Function bodies are validated once the whole module has been read, so
+    // errors are reported in the same order as `validate_all()` does.
+    let mut allocations = FuncValidatorAllocations::default();
+    for (func, body) in functions {
+        let mut validator = func.into_validator(allocations);
+        let mut reader = body.get_binary_reader();
+        for _ in 0..reader.read_var_u32()? {
+            let offset = reader.original_position();
+            let count = reader.read()?;
+            let ty = reader.read()?;
+            validator.define_locals(offset, count, ty)?;
+            detector.local(ty);
+        }
+        reader.set_features(*validator.features());
+        while !reader.eof() {
+            let offset = reader.original_position();
+            let operator = reader.read_operator()?;
+            validator.op(offset, &operator)?;
+            detector.operator(&operator);
+        }
+        validator.finish(reader.original_position())?;
+        allocations = validator.into_allocations();
+    }
+
+    Ok(detector.finish())
+}
+
+/// Validates a module against `features` only, without needing a compiler.
+///
+/// When the module is only invalid because it uses a disabled feature, the
+/// error names that feature along with the offset validation failed at.
+#[cfg(feature = "translator")]
+pub fn validate_module_with_features(features: &Features, data: &[u8]) -> Result<(), CompileError> {
+    validate_with_features(features, data)
+        .map(drop)
+        .map_err(|e| match missing_feature(features, data) {
+            Some(name) => CompileError::Validate(format!(
+                "{}: the module requires the {name} feature (at offset {:#x})",
+                e.message(),
+                e.offset()
+            )),
+            None => CompileError::Validate(format!("{e}")),
+        })
+}
+
+/// The feature `features` lacks that keeps `data` from validating.
+///
+/// The validator doesn't tell us which feature it was missing, so this
+/// checks whether enabling the ones the module uses would have made it
+/// valid.
+#[cfg(feature = "translator")]
+fn missing_feature(features: &Features, data: &[u8]) -> Option<&'static str> {
+    let required = Features::required_by_wasm(data).unwrap_or_else(|_| Features::none());
+    let name = features.missing(&required).next()?;
+    let mut with_required = features.clone();
+    with_required.extend(&required);
+    validate_with_features(&with_required, data)
+        .is_ok()
+        .then_some(name)
+}
+
+/// The [`WasmFeatures`] used to validate modules compiled with `features`.
+#[cfg(feature = "translator")]
+fn wasm_features(features: &Features) -> WasmFeatures {
+    let mut wasm_features = WasmFeatures::default();
+    wasm_features.set(WasmFeatures::BULK_MEMORY, features.bulk_memory);
+    wasm_features.set(WasmFeatures::THREADS, features.threads);
+    wasm_features.set(WasmFeatures::REFERENCE_TYPES, features.reference_types);
+    wasm_features.set(WasmFeatures::MULTI_VALUE, features.multi_value);
+    wasm_features.set(WasmFeatures::SIMD, features.simd);
+    wasm_features.set(WasmFeatures::TAIL_CALL, features.tail_call);
+    wasm_features.set(WasmFeatures::MULTI_MEMORY, features.multi_memory);
+    wasm_features.set(WasmFeatures::MEMORY64, features.memory64);
+    wasm_features.set(WasmFeatures::EXCEPTIONS, features.exceptions);
+    wasm_features.set(WasmFeatures::EXTENDED_CONST, features.extended_const);
+    wasm_features.set(WasmFeatures::RELAXED_SIMD, features.relaxed_simd);
+    wasm_features.set(WasmFeatures::MUTABLE_GLOBAL, true);
+    wasm_features.set(WasmFeatures::SATURATING_FLOAT_TO_INT, true);
+    wasm_features.set(WasmFeatures::FLOATS, true);
+    wasm_features.set(WasmFeatures::SIGN_EXTENSION, true);
+    wasm_features.set(WasmFeatures::GC_TYPES, true);
+
+    // Not supported
+    wasm_features.set(WasmFeatures::COMPONENT_MODEL, false);
+    wasm_features.set(WasmFeatures::FUNCTION_REFERENCES, false);
+    wasm_features.set(WasmFeatures::MEMORY_CONTROL, false);
+    wasm_features.set(WasmFeatures::GC, false);
+    wasm_features.set(WasmFeatures::COMPONENT_MODEL_VALUES, false);
+    wasm_features.set(WasmFeatures::COMPONENT_MODEL_NESTED_NAMES, false);
+
+    wasm_features
+}
+
+/// Validates a WebAssembly module incrementally, as its bytes become
+/// available.
+///
+/// This allows validation to overlap with downloading a module. Function
+/// bodies and sections are validated as soon as all of their bytes have
+/// been [written][StreamingValidator::write], and once the whole module has
+/// arrived, [`StreamingValidator::finish()`] returns it ready to be
+/// compiled.
+///
+/// Errors report the offset of the offending byte within the stream.
+#[cfg(feature = "translator")]
+pub struct StreamingValidator {
+    parser: Parser,
+    validator: Validator,
+    allocations: FuncValidatorAllocations,
+    buffer: Vec<u8>,
+    consumed: usize,
+}
+
+#[cfg(feature = "translator")]
+impl StreamingValidator {
+    /// Creates a validator that accepts modules using `features`, following
+    /// the same rules as [`Compiler::validate_module()`].
+    pub fn new(features: &Features) -> Self {
+        Self {
+            parser: Parser::new(0),
+            validator: Validator::new_with_features(wasm_features(features)),
+            allocations: FuncValidatorAllocations::default(),
+            buffer: Vec::new(),
+            consumed: 0,
+        }
+    }
+
+    /// The number of bytes that have been written so far.
+    pub fn bytes_written(&self) -> usize {
+        self.buffer.len()
+    }
+
+    /// Adds the next chunk of the module, validating everything that is now
+    /// complete.
+    pub fn write(&mut self, bytes: &[u8]) -> Result<(), CompileError> {
+        self.buffer.extend_from_slice(bytes);
+        self.advance(false)
+    }
+
+    /// Signals that the whole module has been written, returning its bytes.
+    pub fn finish(mut self) -> Result<Vec<u8>, CompileError> {
+        self.advance(true)?;
+        Ok(self.buffer)
+    }
+
+    fn advance(&mut self, eof: bool) -> Result<(), CompileError> {
+        let error = |e: wasmparser::BinaryReaderError| {
+            CompileError::Wasm(crate::translator::from_binaryreadererror_wasmerror(e))
+        };
+
+        loop {
+            let data = &self.buffer[self.consumed..];
+            let (consumed, payload) = match self.parser.parse(data, eof).map_err(error)? {
+                Chunk::NeedMoreData(_) => return Ok(()),
+                Chunk::Parsed { consumed, payload } => (consumed, payload),
+            };
+
+            match self.validator.payload(&payload).map_err(error)? {
+                ValidPayload::Ok => {}
+                ValidPayload::Func(func, body) => {
+                    let allocations = std::mem::take(&mut self.allocations);
+                    let mut validator = func.into_validator(allocations);
+                    validator.validate(&body).map_err(error)?;
+                    self.allocations = validator.into_allocations();
+                }
+                ValidPayload::Parser(_) => {
+                    return Err(CompileError::Validate(
+                        "nested modules are not supported".to_string(),
+                    ));
+                }
+                ValidPayload::End(_) => {
+                    self.consumed += consumed;
+                    return Ok(());
+                }
+            }
+            self.consumed += consumed;
+        }
+    }
+}
@@ -13,14 +13,21 @@ use crate::{
     lib::std::vec::IntoIter,
     register_frame_info, resolve_imports,
     serialize::{MetadataHeader, SerializableModule},
-    types::relocation::{RelocationLike, RelocationTarget},
+    types::{
+        function::FunctionBodyLike,
+        relocation::{RelocationLike, RelocationTarget},
+    },
     ArtifactBuild, ArtifactBuildFromArchive, ArtifactCreate, Engine, EngineInner, Features,
-    FrameInfosVariant, FunctionExtent, GlobalFrameInfoRegistration, InstantiationError, Tunables,
+    FrameInfosVariant, FunctionExtent, GlobalFrameInfoRegistration, InstantiationError,
+    SourceMapResolver, Tunables,
 };
 #[cfg(any(feature = "static-artifact-create", feature = "static-artifact-load"))]
 use crate::{serialize::SerializableCompilation, types::symbols::ModuleMetadata};
 #[cfg(feature = "static-artifact-create")]
-use crate::{types::module::CompileModuleInfo, Compiler, FunctionBodyData, ModuleTranslationState};
+use crate::{
+    strip_unreachable, types::module::CompileModuleInfo, Compiler, FunctionBodyData,
+    ModuleTranslationState, StripOutcome,
+};
 
 use enumset::EnumSet;
 use shared_buffer::OwnedBuffer;
@@ -38,10 +45,10 @@ use wasmer_types::HashAlgorithm;
 use wasmer_types::{
     entity::{BoxedSlice, PrimaryMap},
     target::{CpuFeature, Target},
-    ArchivedDataInitializerLocation, ArchivedOwnedDataInitializer, CompileError, DataInitializer,
-    DataInitializerLike, DataInitializerLocation, DataInitializerLocationLike, DeserializeError,
-    FunctionIndex, LocalFunctionIndex, MemoryIndex, ModuleInfo, OwnedDataInitializer,
-    SerializeError, SignatureIndex, TableIndex,
+    ArchivedDataInitializerLocation, ArchivedOwnedDataInitializer, ArtifactStats, CompileError,
+    DataInitializer, DataInitializerLike, DataInitializerLocation, DataInitializerLocationLike,
+    DeserializeError, FunctionIndex, ImportLimits, LocalFunctionIndex, MemoryIndex, ModuleInfo,
+    OwnedDataInitializer, SerializeError, SignatureIndex, TableIndex,
 };
 
 use wasmer_vm::{
@@ -107,6 +114,7 @@ pub struct Artifact {
     // The artifact will only be allocated in memory in case we can execute it
     // (that means, if the target != host then this will be None).
     allocated: Option<AllocatedArtifact>,
+    stats: ArtifactStats,
 }
 
 /// Artifacts may be created as the result of the compilation of a wasm
@@ -127,8 +135,26 @@ impl Artifact {
         data: &[u8],
         tunables: &dyn Tunables,
         hash_algorithm: Option<HashAlgorithm>,
+    ) -> Result<Self, CompileError> {
+        Self::compile(engine, data, tunables, hash_algorithm, &Features::none())
+    }
+
+    /// Like [`Artifact::new()`], failing with
+    /// [`CompileError::DisabledFeature`] if the module uses any of the
+    /// `disabled` features.
+    #[cfg(feature = "compiler")]
+    pub(crate) fn compile(
+        engine: &Engine,
+        data: &[u8],
+        tunables: &dyn Tunables,
+        hash_algorithm: Option<HashAlgorithm>,
+        disabled: &Features,
     ) -> Result<Self, CompileError> {
         let mut inner_engine = engine.inner_mut();
+        let required_features = inner_engine.validate(data)?;
+        if let Some(name) = disabled.shared(&required_features).next() {
+            return Err(CompileError::DisabledFeature(name.to_string()));
+        }
         let environ = ModuleEnvironment::new();
         let translation = environ.translate(data).map_err(CompileError::Wasm)?;
         let module = translation.module;
@@ -143,9 +169,10 @@ impl Artifact {
             .map(|table_type| tunables.table_style(table_type))
             .collect();
 
-        let artifact = ArtifactBuild::new(
+        let artifact = ArtifactBuild::from_validated(
             &mut inner_engine,
             data,
+            required_features,
             engine.target(),
             memory_styles,
             table_styles,
@@ -292,11 +319,13 @@ impl Artifact {
         artifact: ArtifactBuildVariant,
         target: &Target,
     ) -> Result<Self, DeserializeError> {
+        let mut stats = artifact.stats();
         if !target.is_native() {
             return Ok(Self {
                 id: Default::default(),
                 artifact,
                 allocated: None,
+                stats,
             });
         } else {
             // check if cpu features are compatible before anything else
@@ -330,6 +359,10 @@ impl Artifact {
                 a.get_custom_sections_ref().values(),
             )?,
         };
+        if let Some(code_memory) = engine_inner.last_code_memory() {
+            stats.mapped_size = Some(code_memory.mapped_size());
+            stats.executable_size = Some(code_memory.executable_size());
+        }
 
         let get_got_address: Box<dyn Fn(RelocationTarget) -> Option<usize>> = match &artifact {
             ArtifactBuildVariant::Plain(ref p) => {
@@ -488,10 +521,11 @@ impl Artifact {
                 signatures,
                 finished_function_lengths,
             }),
+            stats,
         };
 
         artifact
-            .internal_register_frame_info()
+            .internal_register_frame_info(engine_inner.source_map_resolver())
             .map_err(|e| DeserializeError::CorruptedBinary(format!("{e:?}")))?;
         if let Some(frame_info) = artifact.internal_take_frame_info_registration() {
             engine_inner.register_frame_info(frame_info);
@@ -500,6 +534,11 @@ impl Artifact {
         Ok(artifact)
     }
 
+    /// Statistics about the code of this artifact.
+    pub fn stats(&self) -> &ArtifactStats {
+        &self.stats
+    }
+
     /// Check if the provided bytes look like a serialized `ArtifactBuild`.
     pub fn is_deserializable(bytes: &[u8]) -> bool {
         ArtifactBuild::is_deserializable(bytes)
@@ -543,6 +582,10 @@ impl<'a> ArtifactCreate<'a> for Artifact {
         self.artifact.features()
     }
 
+    fn required_features(&self) -> &Features {
+        self.artifact.required_features()
+    }
+
     fn cpu_features(&self) -> EnumSet<CpuFeature> {
         self.artifact.cpu_features()
     }
@@ -596,6 +639,13 @@ impl<'a> ArtifactCreate<'a> for ArtifactBuildVariant {
         }
     }
 
+    fn required_features(&self) -> &Features {
+        match self {
+            Self::Plain(artifact) => artifact.required_features(),
+            Self::Archived(artifact) => artifact.required_features(),
+        }
+    }
+
     fn cpu_features(&self) -> EnumSet<CpuFeature> {
         match self {
             Self::Plain(artifact) => artifact.cpu_features(),
@@ -640,6 +690,53 @@ impl<'a> ArtifactCreate<'a> for ArtifactBuildVariant {
     }
 }
 
+impl ArtifactBuildVariant {
+    /// Statistics about the code in the artifact, regardless of where (or
+    /// whether) it is loaded.
+    fn stats(&self) -> ArtifactStats {
+        match self {
+            Self::Plain(p) => code_stats(
+                p.get_function_bodies_ref().values(),
+                p.get_function_call_trampolines_ref()
+                    .values()
+                    .chain(p.get_dynamic_function_trampolines_ref().values()),
+                p.get_function_relocations()
+                    .values()
+                    .chain(p.get_custom_section_relocations_ref().values())
+                    .map(|relocations| relocations.len()),
+            ),
+            Self::Archived(a) => code_stats(
+                a.get_function_bodies_ref().values(),
+                a.get_function_call_trampolines_ref()
+                    .values()
+                    .chain(a.get_dynamic_function_trampolines_ref().values()),
+                a.get_function_relocations()
+                    .values()
+                    .chain(a.get_custom_section_relocations_ref().values())
+                    .map(|relocations| relocations.len()),
+            ),
+        }
+    }
+}
+
+fn code_stats<'a, FunctionBody: FunctionBodyLike<'a> + 'a>(
+    functions: impl Iterator<Item = &'a FunctionBody>,
+    trampolines: impl Iterator<Item = &'a FunctionBody>,
+    relocations: impl Iterator<Item = usize>,
+) -> ArtifactStats {
+    let mut stats = ArtifactStats::default();
+    for function in functions {
+        stats.functions += 1;
+        stats.code_size += function.body().len();
+    }
+    for trampoline in trampolines {
+        stats.trampolines += 1;
+        stats.trampolines_size += trampoline.body().len();
+    }
+    stats.relocations = relocations.sum();
+    stats
+}
+
 #[derive(Clone, Copy)]
 pub enum OwnedDataInitializerVariant<'a> {
     Plain(&'a OwnedDataInitializer),
@@ -709,7 +806,10 @@ impl DataInitializerLocationLike for DataInitializerLocationVariant<'_> {
 }
 
 impl Artifact {
-    fn internal_register_frame_info(&mut self) -> Result<(), DeserializeError> {
+    fn internal_register_frame_info(
+        &mut self,
+        source_map_resolver: Option<SourceMapResolver>,
+    ) -> Result<(), DeserializeError> {
         if self
             .allocated
             .as_ref()
@@ -753,6 +853,7 @@ impl Artifact {
                 }
                 ArtifactBuildVariant::Archived(a) => FrameInfosVariant::Archived(a.clone()),
             },
+            source_map_resolver,
         );
 
         self.allocated
@@ -822,6 +923,9 @@ impl Artifact {
 
     /// Crate an `Instance` from this `Artifact`.
     ///
+    /// The limits of imported memories and tables are matched according to
+    /// `limits`.
+    ///
     /// # Safety
     ///
     /// See [`VMInstance::new`].
@@ -830,6 +934,7 @@ impl Artifact {
         &self,
         tunables: &dyn Tunables,
         imports: &[VMExtern],
+        limits: ImportLimits,
         context: &mut StoreObjects,
     ) -> Result<VMInstance, InstantiationError> {
         // Validate the CPU features this module was compiled with against the
@@ -851,6 +956,7 @@ impl Artifact {
         let imports = resolve_imports(
             &module,
             imports,
+            limits,
             context,
             self.finished_dynamic_function_trampolines(),
             self.memory_styles(),
@@ -915,16 +1021,21 @@ impl Artifact {
         trap_handler: Option<*const TrapHandlerFn<'static>>,
         handle: &mut VMInstance,
     ) -> Result<(), InstantiationError> {
-        let data_initializers = self
-            .data_initializers()
+        let data_initializers = self.vm_data_initializers();
+        handle
+            .finish_instantiation(config, trap_handler, &data_initializers)
+            .map_err(InstantiationError::Start)
+    }
+
+    /// The data initializers of the module, as
+    /// [`VMInstance::initialize_step`] takes them.
+    pub fn vm_data_initializers(&self) -> Vec<DataInitializer<'_>> {
+        self.data_initializers()
             .map(|init| DataInitializer {
                 location: init.location().clone_to_plain(),
                 data: init.data(),
             })
-            .collect::<Vec<_>>();
-        handle
-            .finish_instantiation(config, trap_handler, &data_initializers)
-            .map_err(InstantiationError::Start)
+            .collect()
     }
 
     #[allow(clippy::type_complexity)]
@@ -944,6 +1055,7 @@ impl Artifact {
         ),
         CompileError,
     > {
+        let required_features = compiler.validate_module(features, data)?;
         let environ = ModuleEnvironment::new();
         let translation = environ.translate(data).map_err(CompileError::Wasm)?;
 
@@ -951,6 +1063,9 @@ impl Artifact {
         use crate::translator::ModuleMiddlewareChain;
         let mut module = translation.module;
         let middlewares = compiler.get_middlewares();
+        middlewares
+            .inspect_function_bodies(&translation.function_body_inputs)
+            .map_err(|e| CompileError::MiddlewareError(e.to_string()))?;
         middlewares
             .apply_on_module_info(&mut module)
             .map_err(|e| CompileError::MiddlewareError(e.to_string()))?;
@@ -969,6 +1084,7 @@ impl Artifact {
         let compile_info = CompileModuleInfo {
             module: Arc::new(module),
             features: features.clone(),
+            required_features,
             memory_styles,
             table_styles,
         };
@@ -997,11 +1113,59 @@ impl Artifact {
             PrimaryMap<LocalFunctionIndex, FunctionBodyData<'a>>,
         ),
         CompileError,
+    > {
+        Self::metadata_for_entries(
+            compiler,
+            data,
+            metadata_prefix,
+            target,
+            tunables,
+            features,
+            None,
+        )
+        .map(|(metadata, module_translation, function_body_inputs, _)| {
+            (metadata, module_translation, function_body_inputs)
+        })
+    }
+
+    /// Like [`Artifact::metadata()`], but with the code that the exports
+    /// named in `entries` can't reach stripped, see [`strip_unreachable()`].
+    #[cfg(feature = "static-artifact-create")]
+    #[allow(clippy::type_complexity)]
+    fn metadata_for_entries<'a>(
+        compiler: &dyn Compiler,
+        data: &'a [u8],
+        metadata_prefix: Option<&str>,
+        target: &Target,
+        tunables: &dyn Tunables,
+        features: &Features,
+        entries: Option<&[String]>,
+    ) -> Result<
+        (
+            ModuleMetadata,
+            Option<ModuleTranslationState>,
+            PrimaryMap<LocalFunctionIndex, FunctionBodyData<'a>>,
+            Option<StripOutcome>,
+        ),
+        CompileError,
     > {
         #[allow(dead_code)]
-        let (compile_info, function_body_inputs, data_initializers, module_translation) =
+        let (mut compile_info, mut function_body_inputs, data_initializers, module_translation) =
             Self::generate_metadata(data, compiler, tunables, features)?;
 
+        let strip_outcome = match entries {
+            Some(entries) => {
+                let module = Arc::get_mut(&mut compile_info.module)
+                    .expect("the module info was just created");
+                Some(strip_unreachable(
+                    module,
+                    &mut function_body_inputs,
+                    entries,
+                )?)
+            }
+            None => None,
+        };
+
         let data_initializers = data_initializers
             .iter()
             .map(OwnedDataInitializer::new)
@@ -1024,7 +1188,12 @@ impl Artifact {
             cpu_features: target.cpu_features().as_u64(),
         };
 
-        Ok((metadata, module_translation, function_body_inputs))
+        Ok((
+            metadata,
+            module_translation,
+            function_body_inputs,
+            strip_outcome,
+        ))
     }
 
     /// Compile a module into an object file, which can be statically linked against.
@@ -1050,6 +1219,45 @@ impl Artifact {
             Box<dyn crate::types::symbols::SymbolRegistry>,
         ),
         CompileError,
+    > {
+        Self::generate_object_for_entries(
+            compiler,
+            data,
+            metadata_prefix,
+            target,
+            tunables,
+            features,
+            None,
+        )
+        .map(|(module_info, obj, metadata_len, symbol_registry, _)| {
+            (module_info, obj, metadata_len, symbol_registry)
+        })
+    }
+
+    /// Like [`Artifact::generate_object()`], but if `entries` is given, only
+    /// the code that the exports it names can reach is compiled, see
+    /// [`strip_unreachable()`].
+    ///
+    /// Also returns what was stripped, or why nothing was.
+    #[cfg(feature = "static-artifact-create")]
+    #[allow(clippy::type_complexity)]
+    pub fn generate_object_for_entries<'data>(
+        compiler: &dyn Compiler,
+        data: &[u8],
+        metadata_prefix: Option<&str>,
+        target: &'data Target,
+        tunables: &dyn Tunables,
+        features: &Features,
+        entries: Option<&[String]>,
+    ) -> Result<
+        (
+            ModuleInfo,
+            Object<'data>,
+            usize,
+            Box<dyn crate::types::symbols::SymbolRegistry>,
+            Option<StripOutcome>,
+        ),
+        CompileError,
     > {
         use crate::types::symbols::{ModuleMetadataSymbolRegistry, SymbolRegistry};
 
@@ -1058,9 +1266,17 @@ impl Artifact {
         }
 
         let target_triple = target.triple();
-        let (mut metadata, module_translation, function_body_inputs) =
-            Self::metadata(compiler, data, metadata_prefix, target, tunables, features)
-                .map_err(to_compile_error)?;
+        let (mut metadata, module_translation, function_body_inputs, strip_outcome) =
+            Self::metadata_for_entries(
+                compiler,
+                data,
+                metadata_prefix,
+                target,
+                tunables,
+                features,
+                entries,
+            )
+            .map_err(to_compile_error)?;
 
         /*
         In the C file we need:
@@ -1133,6 +1349,7 @@ impl Artifact {
             obj,
             metadata_builder.placeholder_data().len(),
             Box::new(symbol_registry),
+            strip_outcome,
         ))
     }
 
@@ -1260,6 +1477,14 @@ impl Artifact {
             cpu_features: metadata.cpu_features,
         });
 
+        // The sizes of the functions aren't recorded in the object either
+        let stats = ArtifactStats {
+            functions: finished_functions.len(),
+            trampolines: finished_function_call_trampolines.len()
+                + finished_dynamic_function_trampolines.len(),
+            ..Default::default()
+        };
+
         let finished_function_lengths = finished_functions
             .values()
             .map(|_| 0)
@@ -1280,6 +1505,7 @@ impl Artifact {
                 signatures: signatures.into_boxed_slice(),
                 finished_function_lengths,
             }),
+            stats,
         })
     }
 }
@@ -157,6 +157,23 @@ impl CodeMemory {
         ))
     }
 
+    /// The size of the memory mapping, in bytes.
+    pub fn mapped_size(&self) -> usize {
+        self.mmap.len()
+    }
+
+    /// The size of the executable pages at the start of the memory mapping,
+    /// in bytes.
+    pub fn executable_size(&self) -> usize {
+        round_up(self.start_of_nonexecutable_pages, region::page::size())
+    }
+
+    /// The addresses of the executable code in the memory mapping.
+    pub fn executable_range(&self) -> std::ops::Range<usize> {
+        let start = self.mmap.as_ptr() as usize;
+        start..start + self.start_of_nonexecutable_pages
+    }
+
     /// Apply the page permissions.
     pub fn publish(&mut self) {
         if self.mmap.is_empty() || self.start_of_nonexecutable_pages == 0 {
@@ -7,7 +7,8 @@ use crate::{
         function::FunctionBodyLike,
         section::{CustomSectionLike, CustomSectionProtection, SectionIndex},
     },
-    Artifact, BaseTunables, CodeMemory, FunctionExtent, GlobalFrameInfoRegistration, Tunables,
+    Artifact, BaseTunables, CodeMemory, FunctionExtent, GlobalFrameInfoRegistration,
+    SourceMapResolver, Tunables,
 };
 #[cfg(feature = "compiler")]
 use crate::{Compiler, CompilerConfig};
@@ -27,8 +28,8 @@ use std::{
 use wasmer_types::Features;
 #[cfg(not(target_arch = "wasm32"))]
 use wasmer_types::{
-    entity::PrimaryMap, DeserializeError, FunctionIndex, FunctionType, LocalFunctionIndex,
-    SignatureIndex,
+    entity::PrimaryMap, AllocationStats, DeserializeError, FunctionIndex, FunctionType,
+    LocalFunctionIndex, SignatureIndex,
 };
 use wasmer_types::{target::Target, CompileError, HashAlgorithm, ModuleInfo};
 
@@ -71,6 +72,8 @@ impl Engine {
                 code_memory: vec![],
                 #[cfg(not(target_arch = "wasm32"))]
                 signatures: SignatureRegistry::new(),
+                #[cfg(not(target_arch = "wasm32"))]
+                source_map_resolver: None,
             })),
             target: Arc::new(target),
             engine_id: EngineId::default(),
@@ -96,6 +99,18 @@ impl Engine {
         self.hash_algorithm
     }
 
+    /// Sets the function that loads the source maps modules refer to in
+    /// their `sourceMappingURL` section, for the modules compiled or
+    /// deserialized from now on.
+    ///
+    /// Source maps embedded as `data:` URLs are always used, this is only
+    /// needed for those stored elsewhere (such as a `.wasm.map` file next to
+    /// the module).
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn set_source_map_resolver(&self, resolver: Option<SourceMapResolver>) {
+        self.inner_mut().source_map_resolver = resolver;
+    }
+
     /// Returns the deterministic id of this engine
     pub fn deterministic_id(&self) -> String {
         let i = self.inner();
@@ -141,6 +156,8 @@ impl Engine {
                 code_memory: vec![],
                 #[cfg(not(target_arch = "wasm32"))]
                 signatures: SignatureRegistry::new(),
+                #[cfg(not(target_arch = "wasm32"))]
+                source_map_resolver: None,
             })),
             target: Arc::new(target),
             engine_id: EngineId::default(),
@@ -183,18 +200,39 @@ impl Engine {
     /// Validates a WebAssembly module
     #[cfg(feature = "compiler")]
     pub fn validate(&self, binary: &[u8]) -> Result<(), CompileError> {
-        self.inner().validate(binary)
+        self.inner().validate(binary).map(drop)
+    }
+
+    /// Starts validating a WebAssembly module that is still arriving, using
+    /// this engine's features.
+    #[cfg(feature = "compiler")]
+    pub fn streaming_validator(&self) -> crate::StreamingValidator {
+        crate::StreamingValidator::new(self.inner().features())
     }
 
     /// Compile a WebAssembly binary
     #[cfg(feature = "compiler")]
     #[cfg(not(target_arch = "wasm32"))]
     pub fn compile(&self, binary: &[u8]) -> Result<Arc<Artifact>, CompileError> {
-        Ok(Arc::new(Artifact::new(
+        self.compile_without_features(binary, &Features::none())
+    }
+
+    /// Compile a WebAssembly binary, refusing it with
+    /// [`CompileError::DisabledFeature`] before it reaches the compiler if
+    /// it uses any of the `disabled` features.
+    #[cfg(feature = "compiler")]
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn compile_without_features(
+        &self,
+        binary: &[u8],
+        disabled: &Features,
+    ) -> Result<Arc<Artifact>, CompileError> {
+        Ok(Arc::new(Artifact::compile(
             self,
             binary,
             self.tunables.as_ref(),
             self.hash_algorithm,
+            disabled,
         )?))
     }
 
@@ -270,6 +308,20 @@ impl Engine {
         )
     }
 
+    /// The addresses of the executable code of every artifact loaded by this
+    /// engine.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn executable_ranges(&self) -> Vec<std::ops::Range<usize>> {
+        self.inner().executable_ranges()
+    }
+
+    /// Statistics about the memory allocated for the code of every artifact
+    /// loaded by this engine.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn allocation_stats(&self) -> AllocationStats {
+        self.inner().allocation_stats()
+    }
+
     /// A unique identifier for this object.
     ///
     /// This exists to allow us to compare two Engines for equality. Otherwise,
@@ -341,6 +393,10 @@ pub struct EngineInner {
     /// performantly.
     #[cfg(not(target_arch = "wasm32"))]
     signatures: SignatureRegistry,
+    /// Loads the source maps that modules refer to by URL, to map their
+    /// trap frames back to the original source code.
+    #[cfg(not(target_arch = "wasm32"))]
+    source_map_resolver: Option<SourceMapResolver>,
 }
 
 impl std::fmt::Debug for EngineInner {
@@ -373,9 +429,9 @@ impl EngineInner {
         }
     }
 
-    /// Validate the module
+    /// Validate the module, returning the features it uses
     #[cfg(feature = "compiler")]
-    pub fn validate(&self, data: &[u8]) -> Result<(), CompileError> {
+    pub fn validate(&self, data: &[u8]) -> Result<Features, CompileError> {
         let compiler = self.compiler()?;
         compiler.validate_module(&self.features, data)
     }
@@ -527,6 +583,43 @@ impl EngineInner {
         &self.signatures
     }
 
+    #[cfg(not(target_arch = "wasm32"))]
+    /// The code memory allocated for the artifact that was loaded last.
+    pub(crate) fn last_code_memory(&self) -> Option<&CodeMemory> {
+        self.code_memory.last()
+    }
+
+    /// The addresses of the executable code of every artifact loaded by this
+    /// engine.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn executable_ranges(&self) -> Vec<std::ops::Range<usize>> {
+        self.code_memory
+            .iter()
+            .map(|code_memory| code_memory.executable_range())
+            .collect()
+    }
+
+    /// Statistics about the memory allocated for the code of every artifact
+    /// loaded by this engine.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn allocation_stats(&self) -> AllocationStats {
+        self.code_memory
+            .iter()
+            .fold(AllocationStats::default(), |stats, code_memory| {
+                AllocationStats {
+                    artifacts: stats.artifacts + 1,
+                    mapped_size: stats.mapped_size + code_memory.mapped_size(),
+                    executable_size: stats.executable_size + code_memory.executable_size(),
+                }
+            })
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    /// The function used to load the source maps of modules.
+    pub(crate) fn source_map_resolver(&self) -> Option<SourceMapResolver> {
+        self.source_map_resolver.clone()
+    }
+
     #[cfg(not(target_arch = "wasm32"))]
     /// Register the frame info for the code memory
     pub(crate) fn register_frame_info(&mut self, frame_info: GlobalFrameInfoRegistration) {
@@ -1,14 +1,13 @@
 //! Custom resolution for external references.
 
 use crate::LinkError;
-use more_asserts::assert_ge;
 use wasmer_types::{
     entity::{BoxedSlice, EntityRef, PrimaryMap},
     TagIndex, TagKind,
 };
 use wasmer_types::{
-    ExternType, FunctionIndex, ImportError, ImportIndex, MemoryIndex, ModuleInfo, TableIndex,
-    TagType,
+    ExternType, FunctionIndex, ImportError, ImportIndex, ImportLimits, MemoryIndex, ModuleInfo,
+    TableIndex, TableType, TagType,
 };
 
 use wasmer_vm::{
@@ -74,11 +73,14 @@ fn get_runtime_size(context: &StoreObjects, extern_: &VMExtern) -> Option<u32> {
 /// This function allows to match all imports of a `ModuleInfo` with concrete definitions provided by
 /// a `Resolver`, except for tags which are resolved separately through `resolve_tags`.
 ///
+/// The limits of imported memories and tables are matched according to `limits`.
+///
 /// If all imports are satisfied returns an `Imports` instance required for a module instantiation.
 #[allow(clippy::result_large_err)]
 pub fn resolve_imports(
     module: &ModuleInfo,
     imports: &[VMExtern],
+    limits: ImportLimits,
     context: &mut StoreObjects,
     finished_dynamic_function_trampolines: &BoxedSlice<FunctionIndex, FunctionBodyPtr>,
     memory_styles: &PrimaryMap<MemoryIndex, MemoryStyle>,
@@ -94,11 +96,8 @@ pub fn resolve_imports(
         .iter()
         .filter(|(_, import_index)| !matches!(import_index, ImportIndex::Tag(_)))
     {
-        let ResolvedImport {
-            resolved,
-            import_extern,
-            extern_type,
-        } = resolve_import(module, imports, context, import_key, import_index)?;
+        let ResolvedImport { resolved, .. } =
+            resolve_import(module, imports, limits, context, import_key, import_index)?;
         match *resolved {
             VMExtern::Function(handle) => {
                 let f = handle.get_mut(context);
@@ -129,27 +128,10 @@ pub fn resolve_imports(
             }
             VMExtern::Table(handle) => {
                 let t = handle.get(context);
-                match import_index {
-                    ImportIndex::Table(index) => {
-                        let import_table_ty = t.ty();
-                        let expected_table_ty = &module.tables[*index];
-                        if import_table_ty.ty != expected_table_ty.ty {
-                            return Err(LinkError::Import(
-                                import_key.module.to_string(),
-                                import_key.field.to_string(),
-                                ImportError::IncompatibleType(import_extern, extern_type),
-                            ));
-                        }
-
-                        table_imports.push(VMTableImport {
-                            definition: t.vmtable(),
-                            handle,
-                        });
-                    }
-                    _ => {
-                        unreachable!("Table resolution did not match");
-                    }
-                }
+                table_imports.push(VMTableImport {
+                    definition: t.vmtable(),
+                    handle,
+                });
             }
             VMExtern::Memory(handle) => {
                 let m = handle.get(context);
@@ -159,20 +141,57 @@ pub fn resolve_imports(
                         // guard-page protections the importing module expects it to have.
                         let export_memory_style = m.style();
                         let import_memory_style = &memory_styles[*index];
-                        if let (
-                            MemoryStyle::Static { bound, .. },
-                            MemoryStyle::Static {
-                                bound: import_bound,
-                                ..
-                            },
-                        ) = (export_memory_style, &import_memory_style)
+                        // Memories backed by an embedder's buffer have no guard
+                        // pages or reserved address space, so they can only be
+                        // used by code that checks every access.
+                        if export_memory_style == MemoryStyle::External
+                            && !matches!(
+                                import_memory_style,
+                                MemoryStyle::External
+                                    | MemoryStyle::Dynamic {
+                                        offset_guard_size: 0
+                                    }
+                            )
                         {
-                            assert_ge!(bound, *import_bound);
+                            return Err(LinkError::Import(
+                                import_key.module.to_string(),
+                                import_key.field.to_string(),
+                                ImportError::MemoryError(format!(
+                                    "the module was compiled for {import_memory_style:?} memories, \
+                                     which can not use an externally-owned memory"
+                                )),
+                            ));
+                        }
+                        // Code compiled for a static memory doesn't check
+                        // accesses below its bound, so the memory must reserve
+                        // at least as much address space, and as many guard
+                        // pages. Memories whose limits match the import
+                        // differently, as allowed by `ImportLimits::Relaxed`,
+                        // can have been created with another style.
+                        let covers_bound = match (export_memory_style, import_memory_style) {
+                            (
+                                MemoryStyle::Static { bound, .. },
+                                MemoryStyle::Static {
+                                    bound: import_bound,
+                                    ..
+                                },
+                            ) => bound >= *import_bound,
+                            (_, MemoryStyle::Static { .. }) => false,
+                            _ => true,
+                        };
+                        if !covers_bound
+                            || export_memory_style.offset_guard_size()
+                                < import_memory_style.offset_guard_size()
+                        {
+                            return Err(LinkError::Import(
+                                import_key.module.to_string(),
+                                import_key.field.to_string(),
+                                ImportError::MemoryError(format!(
+                                    "the module was compiled for {import_memory_style:?} \
+                                     memories, which can not use a {export_memory_style:?} memory"
+                                )),
+                            ));
                         }
-                        assert_ge!(
-                            export_memory_style.offset_guard_size(),
-                            import_memory_style.offset_guard_size()
-                        );
                     }
                     _ => {
                         // This should never be reached, as we did compatibility
@@ -231,7 +250,14 @@ pub fn resolve_tags(
             resolved,
             import_extern,
             extern_type,
-        } = resolve_import(module, imports, context, import_key, import_index)?;
+        } = resolve_import(
+            module,
+            imports,
+            ImportLimits::Strict,
+            context,
+            import_key,
+            import_index,
+        )?;
         match *resolved {
             VMExtern::Tag(handle) => {
                 let t = handle.get(context);
@@ -299,6 +325,7 @@ struct ResolvedImport<'a> {
 fn resolve_import<'a>(
     module: &ModuleInfo,
     imports: &'a [VMExtern],
+    limits: ImportLimits,
     context: &mut StoreObjects,
     import: &wasmer_types::ImportKey,
     import_index: &ImportIndex,
@@ -315,11 +342,20 @@ fn resolve_import<'a>(
     };
     let extern_type = get_extern_type(context, resolved);
     let runtime_size = get_runtime_size(context, resolved);
-    if !extern_type.is_compatible_with(&import_extern, runtime_size) {
+    if !extern_type.is_compatible_with_limits(&import_extern, runtime_size, limits) {
+        // Report the size the provided table has now, which is what it was
+        // checked against
+        let provided = match (extern_type, runtime_size) {
+            (ExternType::Table(ty), Some(size)) => ExternType::Table(TableType {
+                minimum: size,
+                ..ty
+            }),
+            (provided, _) => provided,
+        };
         return Err(LinkError::Import(
             import.module.to_string(),
             import.field.to_string(),
-            ImportError::IncompatibleType(import_extern, extern_type),
+            ImportError::IncompatibleType(import_extern, provided),
         ));
     }
     Ok(ResolvedImport {
@@ -12,6 +12,7 @@
 //! FRAME_INFO.register(module, compiled_functions);
 //! ```
 
+use super::source_map::{ModuleSourceMap, SourceMapResolver};
 use crate::types::address_map::{
     ArchivedFunctionAddressMap, ArchivedInstructionAddressMap, FunctionAddressMap,
     InstructionAddressMap,
@@ -64,6 +65,7 @@ struct ModuleInfoFrameInfo {
     functions: BTreeMap<usize, FunctionInfo>,
     module: Arc<ModuleInfo>,
     frame_infos: FrameInfosVariant,
+    source_map: ModuleSourceMap,
 }
 
 impl ModuleInfoFrameInfo {
@@ -138,13 +140,25 @@ impl GlobalFrameInfo {
             None => instr_map.start_srcloc(),
         };
         let func_index = module.module.func_index(func.local_index);
-        Some(FrameInfo::new(
-            module.module.name(),
-            func_index.index() as u32,
-            module.module.function_names.get(&func_index).cloned(),
-            instr_map.start_srcloc(),
-            instr,
-        ))
+        let source_location = module.source_map.lookup(&module.module, instr.bits());
+        Some(
+            FrameInfo::new(
+                module.module.name(),
+                func_index.index() as u32,
+                module.module.function_names.get(&func_index).cloned(),
+                instr_map.start_srcloc(),
+                instr,
+            )
+            .with_source_location(source_location)
+            .with_local_names(
+                module
+                    .module
+                    .local_names
+                    .get(&func_index)
+                    .cloned()
+                    .unwrap_or_default(),
+            ),
+        )
     }
 
     /// Fetches trap information about a program counter in a backtrace.
@@ -358,10 +372,15 @@ impl FunctionAddressMapInstructionVariant<'_> {
 /// compiled functions within `module`. If the `module` has no functions
 /// then `None` will be returned. Otherwise the returned object, when
 /// dropped, will be used to unregister all name information from this map.
+///
+/// If the module has a `sourceMappingURL` section, its source map is loaded
+/// (through `source_map_resolver` unless it's a `data:` URL) the first time
+/// one of its frames is looked up.
 pub fn register(
     module: Arc<ModuleInfo>,
     finished_functions: &BoxedSlice<LocalFunctionIndex, FunctionExtent>,
     frame_infos: FrameInfosVariant,
+    source_map_resolver: Option<SourceMapResolver>,
 ) -> Option<GlobalFrameInfoRegistration> {
     let mut min = usize::MAX;
     let mut max = 0;
@@ -407,6 +426,7 @@ pub fn register(
             functions,
             module,
             frame_infos,
+            source_map: ModuleSourceMap::new(source_map_resolver),
         },
     );
     assert!(prev.is_none());
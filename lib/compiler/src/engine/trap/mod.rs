@@ -1,7 +1,9 @@
 mod frame_info;
+mod source_map;
 mod stack;
 pub use frame_info::{
     register as register_frame_info, CompiledFunctionFrameInfoVariant, FrameInfosVariant,
     FunctionExtent, GlobalFrameInfoRegistration, FRAME_INFO,
 };
+pub use source_map::{SourceMap, SourceMapResolver, SOURCE_MAPPING_URL_SECTION};
 pub use stack::get_trace_and_trapcode;
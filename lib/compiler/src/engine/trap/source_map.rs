@@ -0,0 +1,314 @@
+//! Resolution of wasm offsets to locations in the original source code,
+//! using the source map a module points to with its `sourceMappingURL`
+//! custom section (as emitted by AssemblyScript, TinyGo, Emscripten, etc.).
+//!
+//! Source maps for WebAssembly describe a single generated "line", where the
+//! generated column is the offset of an instruction in the module.
+
+use std::sync::{Arc, OnceLock};
+
+use base64::Engine as _;
+use wasmer_types::{ModuleInfo, SourceLocation};
+
+/// The name of the custom section that points to a module's source map.
+pub const SOURCE_MAPPING_URL_SECTION: &str = "sourceMappingURL";
+
+/// Loads the source map at a URL or path that isn't a `data:` URL, as found
+/// in the `sourceMappingURL` section of a module.
+pub type SourceMapResolver = Arc<dyn Fn(&str) -> Option<Vec<u8>> + Send + Sync>;
+
+/// A parsed source map.
+#[derive(Debug, Default)]
+pub struct SourceMap {
+    sources: Vec<String>,
+    /// Sorted by offset
+    mappings: Vec<Mapping>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Mapping {
+    offset: u32,
+    source: u32,
+    line: u32,
+    column: u32,
+}
+
+impl SourceMap {
+    /// Parses a source map in the JSON format (version 3).
+    ///
+    /// Returns `None` if the JSON itself is invalid. Mappings that are
+    /// malformed or cut short are skipped, so a truncated map still resolves
+    /// the offsets before the point where it was cut.
+    pub fn parse(json: &[u8]) -> Option<Self> {
+        let value: serde_json::Value = serde_json::from_slice(json).ok()?;
+        let source_root = value
+            .get("sourceRoot")
+            .and_then(|root| root.as_str())
+            .filter(|root| !root.is_empty());
+        let sources = value
+            .get("sources")?
+            .as_array()?
+            .iter()
+            .map(|source| {
+                let source = source.as_str().unwrap_or_default();
+                match source_root {
+                    Some(root) if root.ends_with('/') => format!("{root}{source}"),
+                    Some(root) => format!("{root}/{source}"),
+                    None => source.to_string(),
+                }
+            })
+            .collect();
+        let mappings = parse_mappings(value.get("mappings")?.as_str()?);
+
+        Some(Self { sources, mappings })
+    }
+
+    /// Finds the source location of the instruction at `offset` in the
+    /// module.
+    pub fn lookup(&self, offset: u32) -> Option<SourceLocation> {
+        let index = self
+            .mappings
+            .partition_point(|mapping| mapping.offset <= offset)
+            .checked_sub(1)?;
+        let mapping = self.mappings[index];
+        let file = self.sources.get(mapping.source as usize)?;
+        Some(SourceLocation::new(
+            file.clone(),
+            mapping.line + 1,
+            mapping.column + 1,
+        ))
+    }
+}
+
+/// Decodes the segments of the first line of the `mappings`, stopping at
+/// the first one that is malformed.
+fn parse_mappings(mappings: &str) -> Vec<Mapping> {
+    let line = mappings.split(';').next().unwrap_or_default();
+
+    let mut result = Vec::new();
+    let mut fields = [0i64; 4];
+    for segment in line.split(',').filter(|segment| !segment.is_empty()) {
+        let Some(values) = decode_vlq_segment(segment) else {
+            break;
+        };
+        // A single field is an offset without a source location
+        if values.len() < 4 {
+            if values.len() == 1 {
+                fields[0] += values[0];
+                continue;
+            }
+            break;
+        }
+        for (field, delta) in fields.iter_mut().zip(&values) {
+            *field += delta;
+        }
+        let [offset, source, line, column] = fields;
+        let (Ok(offset), Ok(source), Ok(line), Ok(column)) = (
+            u32::try_from(offset),
+            u32::try_from(source),
+            u32::try_from(line),
+            u32::try_from(column),
+        ) else {
+            break;
+        };
+        result.push(Mapping {
+            offset,
+            source,
+            line,
+            column,
+        });
+    }
+
+    result.sort_by_key(|mapping| mapping.offset);
+    result
+}
+
+/// Decodes the base64 VLQ values of one segment of the `mappings`.
+fn decode_vlq_segment(segment: &str) -> Option<Vec<i64>> {
+    let mut values = Vec::new();
+    let mut value = 0i64;
+    let mut shift = 0;
+    for byte in segment.bytes() {
+        let digit = match byte {
+            b'A'..=b'Z' => byte - b'A',
+            b'a'..=b'z' => byte - b'a' + 26,
+            b'0'..=b'9' => byte - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            _ => return None,
+        } as i64;
+        if shift > 60 {
+            return None;
+        }
+        value |= (digit & 0b11111) << shift;
+        if digit & 0b100000 != 0 {
+            shift += 5;
+            continue;
+        }
+
+        let magnitude = value >> 1;
+        values.push(if value & 1 == 1 {
+            -magnitude
+        } else {
+            magnitude
+        });
+        value = 0;
+        shift = 0;
+    }
+    // A value that was cut short
+    if shift != 0 {
+        return None;
+    }
+    Some(values)
+}
+
+/// Reads the URL in the `sourceMappingURL` section of a module.
+fn source_mapping_url(module: &ModuleInfo) -> Option<String> {
+    let section = module.custom_sections(SOURCE_MAPPING_URL_SECTION).next()?;
+    // The URL is a wasm string, prefixed with its LEB128-encoded length
+    let mut reader = &section[..];
+    let url = match leb128::read::unsigned(&mut reader) {
+        Ok(len) if len as usize == reader.len() => reader,
+        _ => &section[..],
+    };
+    String::from_utf8(url.to_vec()).ok()
+}
+
+/// Loads the source map at `url`, either from a `data:` URL or through the
+/// `resolver`.
+fn load(url: &str, resolver: Option<&SourceMapResolver>) -> Option<Vec<u8>> {
+    let Some(data) = url.strip_prefix("data:") else {
+        return resolver?(url);
+    };
+    let (media_type, data) = data.split_once(',')?;
+    if media_type.ends_with(";base64") {
+        base64::engine::general_purpose::STANDARD
+            .decode(data.trim())
+            .ok()
+    } else {
+        Some(percent_decode(data))
+    }
+}
+
+fn percent_decode(data: &str) -> Vec<u8> {
+    let bytes = data.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    decoded
+}
+
+/// The source map of a registered module, which is only loaded the first
+/// time one of its frames is symbolicated.
+pub(crate) struct ModuleSourceMap {
+    resolver: Option<SourceMapResolver>,
+    source_map: OnceLock<Option<SourceMap>>,
+}
+
+impl ModuleSourceMap {
+    pub(crate) fn new(resolver: Option<SourceMapResolver>) -> Self {
+        Self {
+            resolver,
+            source_map: OnceLock::new(),
+        }
+    }
+
+    pub(crate) fn lookup(&self, module: &ModuleInfo, offset: u32) -> Option<SourceLocation> {
+        self.source_map
+            .get_or_init(|| {
+                let url = source_mapping_url(module)?;
+                let json = load(&url, self.resolver.as_ref())?;
+                SourceMap::parse(&json)
+            })
+            .as_ref()?
+            .lookup(offset)
+    }
+}
+
+impl std::fmt::Debug for ModuleSourceMap {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ModuleSourceMap")
+            .field("source_map", &self.source_map)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAP: &str = r#"{
+        "version": 3,
+        "sources": ["~lib/rt.ts", "assembly/index.ts"],
+        "names": [],
+        "mappings": "gBAAA,KCEE,YAAI"
+    }"#;
+
+    #[test]
+    fn offsets_resolve_to_the_closest_mapping_before_them() {
+        let map = SourceMap::parse(MAP.as_bytes()).unwrap();
+
+        assert!(map.lookup(0xf).is_none());
+        let location = map.lookup(0x10).unwrap();
+        assert_eq!(location.to_string(), "~lib/rt.ts:1:1");
+        let location = map.lookup(0x14).unwrap();
+        assert_eq!(location.to_string(), "~lib/rt.ts:1:1");
+        let location = map.lookup(0x15).unwrap();
+        assert_eq!(location.to_string(), "assembly/index.ts:3:3");
+        let location = map.lookup(0x1000).unwrap();
+        assert_eq!(location.to_string(), "assembly/index.ts:3:7");
+    }
+
+    #[test]
+    fn truncated_mappings_keep_what_was_complete() {
+        let map = SourceMap::parse(MAP.replace("YAAI", "YA").as_bytes()).unwrap();
+        assert_eq!(
+            map.lookup(0x1000).unwrap().to_string(),
+            "assembly/index.ts:3:3"
+        );
+
+        let map = SourceMap::parse(MAP.replace(",YAAI", ",Y").as_bytes()).unwrap();
+        assert_eq!(
+            map.lookup(0x1000).unwrap().to_string(),
+            "assembly/index.ts:3:3"
+        );
+
+        assert!(SourceMap::parse(&MAP.as_bytes()[..MAP.len() / 2]).is_none());
+    }
+
+    #[test]
+    fn source_maps_are_loaded_from_data_urls() {
+        let encoded = base64::engine::general_purpose::STANDARD.encode(MAP);
+        let json = load(&format!("data:application/json;base64,{encoded}"), None).unwrap();
+        assert_eq!(json, MAP.as_bytes());
+
+        let json = load("data:application/json,%7B%7D", None).unwrap();
+        assert_eq!(json, b"{}");
+    }
+
+    #[test]
+    fn other_urls_go_through_the_resolver() {
+        assert!(load("index.wasm.map", None).is_none());
+
+        let resolver: SourceMapResolver = Arc::new(|url| Some(url.as_bytes().to_vec()));
+        assert_eq!(
+            load("index.wasm.map", Some(&resolver)).unwrap(),
+            b"index.wasm.map"
+        );
+    }
+}
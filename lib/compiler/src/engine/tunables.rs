@@ -20,6 +20,27 @@ pub trait Tunables {
     /// Construct a `TableStyle` for the provided `TableType`
     fn table_style(&self, table: &TableType) -> TableStyle;
 
+    /// Check that the tunables make sense, returning a description of the
+    /// problem if they don't.
+    ///
+    /// This is called whenever a store is created.
+    fn validate(&self) -> Result<(), String> {
+        Ok(())
+    }
+
+    /// The number of pages a memory of the given type may grow to, or
+    /// [`None`] to let it grow as far as the address space allows.
+    ///
+    /// Memories created with [`VMMemory::new_with_grow_limit()`] (like the
+    /// ones of [`BaseTunables`]) fail to grow past this limit with
+    /// [`MemoryError::CouldNotGrow`]. This defaults to the maximum declared
+    /// by the type. A larger limit lets memories grow beyond it, in which
+    /// case [`Tunables::memory_style()`] should pick a dynamic style for
+    /// them, since they can outgrow a static bound.
+    fn memory_grow_limit(&self, ty: &MemoryType) -> Option<Pages> {
+        ty.maximum
+    }
+
     /// Create a memory owned by the host given a [`MemoryType`] and a [`MemoryStyle`].
     fn create_host_memory(
         &self,
@@ -182,6 +203,14 @@ pub struct BaseTunables {
 
     /// The size in bytes of the offset guard for dynamic heaps.
     pub dynamic_memory_offset_guard_size: u64,
+
+    /// Always use dynamic heaps, which check every access against the
+    /// current length of the memory, regardless of the static memory bound.
+    dynamic_bounds_checks: bool,
+
+    /// The number of wasm pages no memory may grow beyond, whatever its
+    /// declared maximum.
+    pub memory_grow_limit: Option<Pages>,
 }
 
 impl BaseTunables {
@@ -216,8 +245,54 @@ impl BaseTunables {
             static_memory_bound,
             static_memory_offset_guard_size,
             dynamic_memory_offset_guard_size,
+            dynamic_bounds_checks: false,
+            memory_grow_limit: None,
         }
     }
+
+    /// Use static heaps for memories whose maximum is at most `bound`,
+    /// reserving `bound` pages of address space for each of them up front.
+    ///
+    /// Accesses to a static heap are only left unchecked when the bound and
+    /// the guard together cover the whole 32-bit address space (see
+    /// [`MemoryStyle::needs_bounds_checks()`]), so a smaller bound trades
+    /// some speed for address space.
+    pub fn with_static_memory_bound(mut self, bound: Pages) -> Self {
+        self.static_memory_bound = bound;
+        self
+    }
+
+    /// Use `bytes` of guard pages after the end of every memory, both
+    /// static and dynamic.
+    ///
+    /// This must be a multiple of the host's page size.
+    pub fn with_guard_size(mut self, bytes: u64) -> Self {
+        self.static_memory_offset_guard_size = bytes;
+        self.dynamic_memory_offset_guard_size = bytes;
+        self
+    }
+
+    /// Always use dynamic heaps, so that every access is explicitly checked
+    /// and no memory reserves more address space than it currently uses
+    /// (plus its guard).
+    pub fn with_dynamic_bounds_checks(mut self, enabled: bool) -> Self {
+        self.dynamic_bounds_checks = enabled;
+        self
+    }
+
+    /// Whether every memory uses a dynamic heap, see
+    /// [`BaseTunables::with_dynamic_bounds_checks()`].
+    pub fn dynamic_bounds_checks(&self) -> bool {
+        self.dynamic_bounds_checks
+    }
+
+    /// Stop every memory from growing beyond `pages`, including the ones
+    /// that don't declare a maximum. Growing past it fails with
+    /// [`MemoryError::CouldNotGrow`].
+    pub fn with_memory_grow_limit(mut self, pages: Pages) -> Self {
+        self.memory_grow_limit = Some(pages);
+        self
+    }
 }
 
 impl Tunables for BaseTunables {
@@ -226,9 +301,11 @@ impl Tunables for BaseTunables {
         // A heap with a maximum that doesn't exceed the static memory bound specified by the
         // tunables make it static.
         //
-        // If the module doesn't declare an explicit maximum treat it as 4GiB.
-        let maximum = memory.maximum.unwrap_or_else(Pages::max_value);
-        if maximum <= self.static_memory_bound {
+        // If the memory can grow without limit treat it as 4GiB.
+        let maximum = self
+            .memory_grow_limit(memory)
+            .unwrap_or_else(Pages::max_value);
+        if !self.dynamic_bounds_checks && maximum <= self.static_memory_bound {
             MemoryStyle::Static {
                 // Bound can be larger than the maximum for performance reasons
                 bound: self.static_memory_bound,
@@ -246,13 +323,50 @@ impl Tunables for BaseTunables {
         TableStyle::CallerChecksSignature
     }
 
+    /// The smaller of the declared maximum and the grow limit.
+    fn memory_grow_limit(&self, ty: &MemoryType) -> Option<Pages> {
+        match (ty.maximum, self.memory_grow_limit) {
+            (Some(maximum), Some(limit)) => Some(maximum.min(limit)),
+            (maximum, limit) => maximum.or(limit),
+        }
+    }
+
+    /// Check that the guard sizes can actually be mapped, and that static
+    /// memories fit in the address space.
+    fn validate(&self) -> Result<(), String> {
+        let page_size = region::page::size() as u64;
+        for (kind, guard) in [
+            ("static", self.static_memory_offset_guard_size),
+            ("dynamic", self.dynamic_memory_offset_guard_size),
+        ] {
+            if guard % page_size != 0 {
+                return Err(format!(
+                    "the {kind} memory guard size ({guard:#x} bytes) is not a multiple of the page size ({page_size:#x} bytes)"
+                ));
+            }
+        }
+
+        if !self.dynamic_bounds_checks {
+            let reserved = (self.static_memory_bound.bytes().0 as u64)
+                .checked_add(self.static_memory_offset_guard_size);
+            if reserved.is_none_or(|reserved| reserved > isize::MAX as u64) {
+                return Err(format!(
+                    "a static memory bound of {} pages with a {:#x} byte guard doesn't fit in the address space",
+                    self.static_memory_bound.0, self.static_memory_offset_guard_size,
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
     /// Create a memory owned by the host given a [`MemoryType`] and a [`MemoryStyle`].
     fn create_host_memory(
         &self,
         ty: &MemoryType,
         style: &MemoryStyle,
     ) -> Result<VMMemory, MemoryError> {
-        VMMemory::new(ty, style)
+        VMMemory::new_with_grow_limit(ty, style, self.memory_grow_limit(ty))
     }
 
     /// Create a memory owned by the VM given a [`MemoryType`] and a [`MemoryStyle`].
@@ -266,7 +380,12 @@ impl Tunables for BaseTunables {
         style: &MemoryStyle,
         vm_definition_location: NonNull<VMMemoryDefinition>,
     ) -> Result<VMMemory, MemoryError> {
-        VMMemory::from_definition(ty, style, vm_definition_location)
+        VMMemory::from_definition_with_grow_limit(
+            ty,
+            style,
+            vm_definition_location,
+            self.memory_grow_limit(ty),
+        )
     }
 
     /// Create a table owned by the host given a [`TableType`] and a [`TableStyle`].
@@ -298,6 +417,14 @@ impl Tunables for Box<dyn Tunables + Send + Sync> {
         self.as_ref().table_style(table)
     }
 
+    fn validate(&self) -> Result<(), String> {
+        self.as_ref().validate()
+    }
+
+    fn memory_grow_limit(&self, ty: &MemoryType) -> Option<Pages> {
+        self.as_ref().memory_grow_limit(ty)
+    }
+
     fn create_host_memory(
         &self,
         ty: &MemoryType,
@@ -340,6 +467,14 @@ impl Tunables for std::sync::Arc<dyn Tunables + Send + Sync> {
         self.as_ref().table_style(table)
     }
 
+    fn validate(&self) -> Result<(), String> {
+        self.as_ref().validate()
+    }
+
+    fn memory_grow_limit(&self, ty: &MemoryType) -> Option<Pages> {
+        self.as_ref().memory_grow_limit(ty)
+    }
+
     fn create_host_memory(
         &self,
         ty: &MemoryType,
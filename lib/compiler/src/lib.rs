@@ -63,17 +63,19 @@ pub use self::artifact_builders::*;
 #[cfg(feature = "compiler")]
 mod compiler;
 #[cfg(feature = "compiler")]
-pub use crate::compiler::{Compiler, CompilerConfig};
+pub use crate::compiler::{
+    validate_module_with_features, Compiler, CompilerConfig, StreamingValidator,
+};
 
 #[cfg(feature = "translator")]
 #[macro_use]
 mod translator;
 #[cfg(feature = "translator")]
 pub use crate::translator::{
-    from_binaryreadererror_wasmerror, translate_module, wpheaptype_to_type, wptype_to_type,
-    FunctionBinaryReader, FunctionBodyData, FunctionMiddleware, MiddlewareBinaryReader,
-    MiddlewareReaderState, ModuleEnvironment, ModuleMiddleware, ModuleMiddlewareChain,
-    ModuleTranslationState,
+    from_binaryreadererror_wasmerror, strip_unreachable, translate_module, wpheaptype_to_type,
+    wptype_to_type, FunctionBinaryReader, FunctionBodyData, FunctionMiddleware,
+    MiddlewareBinaryReader, MiddlewareReaderState, ModuleEnvironment, ModuleMiddleware,
+    ModuleMiddlewareChain, ModuleTranslationState, StripOutcome, StripReport,
 };
 
 pub use wasmer_types::{Addend, CodeOffset, Features};
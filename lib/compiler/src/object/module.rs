@@ -9,7 +9,7 @@ use crate::{
     },
 };
 use object::{
-    elf, macho,
+    elf, macho, pe,
     write::{
         Object, Relocation, StandardSection, StandardSegment, Symbol as ObjSymbol, SymbolId,
         SymbolSection,
@@ -337,7 +337,17 @@ pub fn emit_compilation(
                             value: macho::ARM64_RELOC_BRANCH26,
                             relative: true,
                         },
-                        fmt => panic!("unsupported binary format {fmt:?}"),
+                        // COFF relocations have implicit addends, which would
+                        // overwrite the branch instruction itself
+                        object::BinaryFormat::Coff if r.addend == 0 => {
+                            RelocationKind::Coff(pe::IMAGE_REL_ARM64_BRANCH26)
+                        }
+                        fmt => {
+                            return Err(ObjectError::UnsupportedBinaryFormat(format!(
+                                "{fmt:?} (relocation: {}, addend: {})",
+                                r.kind, r.addend
+                            )))
+                        }
                     },
                     RelocationEncoding::Generic,
                     32,
@@ -35,6 +35,9 @@ pub trait ArtifactCreate<'a>: Send + Sync + Upcastable {
     /// Returns the features for this Artifact
     fn features(&'a self) -> &'a Features;
 
+    /// Returns the features the module in this Artifact actually uses
+    fn required_features(&'a self) -> &'a Features;
+
     /// Returns the CPU features for this Artifact
     fn cpu_features(&'a self) -> EnumSet<CpuFeature>;
 
@@ -5,7 +5,7 @@ use crate::lib::std::string::ToString;
 use crate::lib::std::{boxed::Box, string::String, vec::Vec};
 use crate::translate_module;
 use crate::wasmparser::{Operator, ValType};
-use std::convert::{TryFrom, TryInto};
+use std::convert::TryInto;
 use std::ops::Range;
 use wasmer_types::entity::PrimaryMap;
 use wasmer_types::FunctionType;
@@ -17,6 +17,17 @@ use wasmer_types::{
 };
 use wasmer_types::{TagIndex, WasmResult};
 
+/// The most entries reserved up front for a section.
+///
+/// Section counts come straight from the module and are only checked
+/// against the section's size while the entries are read, so a large
+/// count must not turn into a large allocation before that.
+const MAX_RESERVE: u32 = 1 << 16;
+
+fn reserve_hint(count: u32) -> usize {
+    count.min(MAX_RESERVE) as usize
+}
+
 /// Contains function data: bytecode and its offset in the module.
 #[derive(Hash)]
 pub struct FunctionBodyData<'a> {
@@ -117,9 +128,7 @@ impl<'data> ModuleEnvironment<'data> {
     }
 
     pub(crate) fn reserve_signatures(&mut self, num: u32) -> WasmResult<()> {
-        self.module
-            .signatures
-            .reserve_exact(usize::try_from(num).unwrap());
+        self.module.signatures.reserve_exact(reserve_hint(num));
         Ok(())
     }
 
@@ -255,11 +264,8 @@ impl<'data> ModuleEnvironment<'data> {
     }
 
     pub(crate) fn reserve_func_types(&mut self, num: u32) -> WasmResult<()> {
-        self.module
-            .functions
-            .reserve_exact(usize::try_from(num).unwrap());
-        self.function_body_inputs
-            .reserve_exact(usize::try_from(num).unwrap());
+        self.module.functions.reserve_exact(reserve_hint(num));
+        self.function_body_inputs.reserve_exact(reserve_hint(num));
         Ok(())
     }
 
@@ -269,9 +275,7 @@ impl<'data> ModuleEnvironment<'data> {
     }
 
     pub(crate) fn reserve_tables(&mut self, num: u32) -> WasmResult<()> {
-        self.module
-            .tables
-            .reserve_exact(usize::try_from(num).unwrap());
+        self.module.tables.reserve_exact(reserve_hint(num));
         Ok(())
     }
 
@@ -281,9 +285,7 @@ impl<'data> ModuleEnvironment<'data> {
     }
 
     pub(crate) fn reserve_memories(&mut self, num: u32) -> WasmResult<()> {
-        self.module
-            .memories
-            .reserve_exact(usize::try_from(num).unwrap());
+        self.module.memories.reserve_exact(reserve_hint(num));
         Ok(())
     }
 
@@ -293,9 +295,7 @@ impl<'data> ModuleEnvironment<'data> {
     }
 
     pub(crate) fn reserve_tags(&mut self, num: u32) -> WasmResult<()> {
-        self.module
-            .tags
-            .reserve_exact(usize::try_from(num).unwrap());
+        self.module.tags.reserve_exact(reserve_hint(num));
         Ok(())
     }
 
@@ -305,9 +305,7 @@ impl<'data> ModuleEnvironment<'data> {
     }
 
     pub(crate) fn reserve_globals(&mut self, num: u32) -> WasmResult<()> {
-        self.module
-            .globals
-            .reserve_exact(usize::try_from(num).unwrap());
+        self.module.globals.reserve_exact(reserve_hint(num));
         Ok(())
     }
 
@@ -322,7 +320,7 @@ impl<'data> ModuleEnvironment<'data> {
     }
 
     pub(crate) fn reserve_exports(&mut self, num: u32) -> WasmResult<()> {
-        self.module.exports.reserve(usize::try_from(num).unwrap());
+        self.module.exports.reserve(reserve_hint(num));
         Ok(())
     }
 
@@ -371,7 +369,7 @@ impl<'data> ModuleEnvironment<'data> {
     pub(crate) fn reserve_table_initializers(&mut self, num: u32) -> WasmResult<()> {
         self.module
             .table_initializers
-            .reserve_exact(usize::try_from(num).unwrap());
+            .reserve_exact(reserve_hint(num));
         Ok(())
     }
 
@@ -419,8 +417,7 @@ impl<'data> ModuleEnvironment<'data> {
     }
 
     pub(crate) fn reserve_data_initializers(&mut self, num: u32) -> WasmResult<()> {
-        self.data_initializers
-            .reserve_exact(usize::try_from(num).unwrap());
+        self.data_initializers.reserve_exact(reserve_hint(num));
         Ok(())
     }
 
@@ -443,8 +440,7 @@ impl<'data> ModuleEnvironment<'data> {
     }
 
     pub(crate) fn reserve_passive_data(&mut self, count: u32) -> WasmResult<()> {
-        let count = usize::try_from(count).unwrap();
-        self.module.passive_data.reserve(count);
+        self.module.passive_data.reserve(reserve_hint(count));
         Ok(())
     }
 
@@ -477,6 +473,20 @@ impl<'data> ModuleEnvironment<'data> {
         Ok(())
     }
 
+    pub(crate) fn declare_local_name(
+        &mut self,
+        func_index: FunctionIndex,
+        local_index: u32,
+        name: &'data str,
+    ) -> WasmResult<()> {
+        self.module
+            .local_names
+            .entry(func_index)
+            .or_default()
+            .insert(local_index, name.to_string());
+        Ok(())
+    }
+
     /// Provides the number of imports up front. By default this does nothing, but
     /// implementations can use this to preallocate memory if desired.
     pub(crate) fn reserve_imports(&mut self, _num: u32) -> WasmResult<()> {
@@ -495,7 +505,9 @@ impl<'data> ModuleEnvironment<'data> {
         );
         self.module
             .custom_sections
-            .insert(String::from(name), custom_section);
+            .entry(String::from(name))
+            .or_default()
+            .push(custom_section);
         self.module.custom_sections_data.push(Box::from(data));
         Ok(())
     }
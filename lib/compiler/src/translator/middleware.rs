@@ -5,11 +5,15 @@ use smallvec::SmallVec;
 use std::collections::VecDeque;
 use std::fmt::Debug;
 use std::ops::{Deref, Range};
-use wasmer_types::{LocalFunctionIndex, MiddlewareError, ModuleInfo, WasmResult};
+use wasmer_types::entity::PrimaryMap;
+use wasmer_types::{
+    FunctionType, LocalFunctionIndex, MiddlewareError, ModuleInfo, Type, WasmResult,
+};
 use wasmparser::{BinaryReader, Operator, ValType};
 
 use super::error::from_binaryreadererror_wasmerror;
-use crate::translator::environ::FunctionBinaryReader;
+use super::sections::wptype_to_type;
+use crate::translator::environ::{FunctionBinaryReader, FunctionBodyData};
 
 /// A shared builder for function middlewares.
 pub trait ModuleMiddleware: Debug + Send + Sync {
@@ -23,6 +27,31 @@ pub trait ModuleMiddleware: Debug + Send + Sync {
         local_function_index: LocalFunctionIndex,
     ) -> Box<dyn FunctionMiddleware>;
 
+    /// Generates a `FunctionMiddleware` for a given function, knowing its signature and locals.
+    ///
+    /// `signature` is the function's type, and `locals` are the locals it declares after its
+    /// parameters, as `(count, type)` runs in the order they appear in the binary.
+    ///
+    /// This is what compilers call. It forwards to `generate_function_middleware` by default, so
+    /// only middlewares that need the signature or the locals have to implement it.
+    fn generate_function_middleware_with_locals(
+        &self,
+        local_function_index: LocalFunctionIndex,
+        _signature: &FunctionType,
+        _locals: &[(u32, Type)],
+    ) -> Box<dyn FunctionMiddleware> {
+        self.generate_function_middleware(local_function_index)
+    }
+
+    /// Inspects the bodies of the local functions. This is called before `transform_module_info`,
+    /// for middlewares that need to know about the code to transform the module.
+    fn inspect_function_bodies(
+        &self,
+        _: &PrimaryMap<LocalFunctionIndex, FunctionBodyData<'_>>,
+    ) -> Result<(), MiddlewareError> {
+        Ok(())
+    }
+
     /// Transforms a `ModuleInfo` struct in-place. This is called before application on functions begins.
     fn transform_module_info(&self, _: &mut ModuleInfo) -> Result<(), MiddlewareError> {
         Ok(())
@@ -31,6 +60,15 @@ pub trait ModuleMiddleware: Debug + Send + Sync {
 
 /// A function middleware specialized for a single function.
 pub trait FunctionMiddleware: Debug {
+    /// Called once at the entry of the function, before the first operator is fed.
+    ///
+    /// Operators pushed here run before the function's own code (and go through the rest of
+    /// the chain like any other), so they can be used to inject a prologue. The locals are
+    /// already declared at this point, so the prologue can refer to the parameters.
+    fn prologue(&mut self, _state: &mut MiddlewareReaderState<'_>) -> Result<(), MiddlewareError> {
+        Ok(())
+    }
+
     /// Processes the given operator.
     fn feed<'a>(
         &mut self,
@@ -50,6 +88,9 @@ pub struct MiddlewareBinaryReader<'a> {
 
     /// The backing middleware chain for this reader.
     chain: Vec<Box<dyn FunctionMiddleware>>,
+
+    /// Whether the prologues of the chain were already run.
+    entered: bool,
 }
 
 /// The state of the binary reader. Exposed to middlewares to push their outputs.
@@ -68,7 +109,15 @@ pub trait ModuleMiddlewareChain {
     fn generate_function_middleware_chain(
         &self,
         local_function_index: LocalFunctionIndex,
-    ) -> Vec<Box<dyn FunctionMiddleware>>;
+        module_info: &ModuleInfo,
+        function_body: &FunctionBodyData<'_>,
+    ) -> WasmResult<Vec<Box<dyn FunctionMiddleware>>>;
+
+    /// Lets each middleware of the chain inspect the bodies of the local functions.
+    fn inspect_function_bodies(
+        &self,
+        function_bodies: &PrimaryMap<LocalFunctionIndex, FunctionBodyData<'_>>,
+    ) -> Result<(), MiddlewareError>;
 
     /// Applies the chain on a `ModuleInfo` struct.
     fn apply_on_module_info(&self, module_info: &mut ModuleInfo) -> Result<(), MiddlewareError>;
@@ -79,10 +128,33 @@ impl<T: Deref<Target = dyn ModuleMiddleware>> ModuleMiddlewareChain for [T] {
     fn generate_function_middleware_chain(
         &self,
         local_function_index: LocalFunctionIndex,
-    ) -> Vec<Box<dyn FunctionMiddleware>> {
-        self.iter()
-            .map(|x| x.generate_function_middleware(local_function_index))
-            .collect()
+        module_info: &ModuleInfo,
+        function_body: &FunctionBodyData<'_>,
+    ) -> WasmResult<Vec<Box<dyn FunctionMiddleware>>> {
+        if self.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let func_index = module_info.func_index(local_function_index);
+        let signature = &module_info.signatures[module_info.functions[func_index]];
+        let locals = read_locals(function_body)?;
+        Ok(self
+            .iter()
+            .map(|x| {
+                x.generate_function_middleware_with_locals(local_function_index, signature, &locals)
+            })
+            .collect())
+    }
+
+    /// Lets each middleware of the chain inspect the bodies of the local functions.
+    fn inspect_function_bodies(
+        &self,
+        function_bodies: &PrimaryMap<LocalFunctionIndex, FunctionBodyData<'_>>,
+    ) -> Result<(), MiddlewareError> {
+        for item in self {
+            item.inspect_function_bodies(function_bodies)?;
+        }
+        Ok(())
     }
 
     /// Applies the chain on a `ModuleInfo` struct.
@@ -94,6 +166,25 @@ impl<T: Deref<Target = dyn ModuleMiddleware>> ModuleMiddlewareChain for [T] {
     }
 }
 
+/// Reads the locals declared at the start of a function body, as `(count, type)` runs.
+fn read_locals(function_body: &FunctionBodyData<'_>) -> WasmResult<Vec<(u32, Type)>> {
+    let mut reader = BinaryReader::new(function_body.data, function_body.module_offset);
+    let num_locals = reader
+        .read_var_u32()
+        .map_err(from_binaryreadererror_wasmerror)?;
+    (0..num_locals)
+        .map(|_| {
+            let count = reader
+                .read_var_u32()
+                .map_err(from_binaryreadererror_wasmerror)?;
+            let ty = reader
+                .read::<ValType>()
+                .map_err(from_binaryreadererror_wasmerror)?;
+            Ok((count, wptype_to_type(ty)?))
+        })
+        .collect()
+}
+
 impl<'a> MiddlewareReaderState<'a> {
     /// Push an operator.
     pub fn push_operator(&mut self, operator: Operator<'a>) {
@@ -123,6 +214,7 @@ impl<'a> MiddlewareBinaryReader<'a> {
                 pending_operations: VecDeque::new(),
             },
             chain: vec![],
+            entered: false,
         }
     }
 
@@ -164,6 +256,19 @@ impl<'a> FunctionBinaryReader<'a> for MiddlewareBinaryReader<'a> {
                 .map_err(from_binaryreadererror_wasmerror);
         }
 
+        // Run the prologues first, feeding what each stage adds to the later stages.
+        if !self.entered {
+            self.entered = true;
+            for stage in &mut self.chain {
+                let pending: SmallVec<[Operator<'a>; 2]> =
+                    self.state.pending_operations.drain(0..).collect();
+                for pending_op in pending {
+                    stage.feed(pending_op, &mut self.state)?;
+                }
+                stage.prologue(&mut self.state)?;
+            }
+        }
+
         // Try to fill the `self.pending_operations` buffer, until it is non-empty.
         while self.state.pending_operations.is_empty() {
             let raw_op = self
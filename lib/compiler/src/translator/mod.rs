@@ -8,6 +8,7 @@
 mod environ;
 mod middleware;
 mod module;
+mod reachability;
 mod state;
 #[macro_use]
 mod error;
@@ -19,6 +20,7 @@ pub use self::middleware::{
     ModuleMiddlewareChain,
 };
 pub use self::module::translate_module;
+pub use self::reachability::{strip_unreachable, StripOutcome, StripReport};
 pub use self::sections::{wpheaptype_to_type, wptype_to_type};
 pub use self::state::ModuleTranslationState;
 pub use error::from_binaryreadererror_wasmerror;
@@ -100,7 +100,12 @@ pub fn translate_module<'data>(
                 }
             }
 
-            Payload::UnknownSection { .. } => unreachable!(),
+            Payload::UnknownSection { id, range, .. } => {
+                return Err(WasmError::InvalidWebAssembly {
+                    message: format!("unknown section with id {id}"),
+                    offset: range.start,
+                })
+            }
             k => {
                 return Err(WasmError::Unsupported(format!(
                     "Unsupported paylod kind: {k:?}"
@@ -111,3 +116,33 @@ pub fn translate_module<'data>(
 
     Ok(module_translation_state)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HEADER: &[u8] = b"\0asm\x01\0\0\0";
+
+    fn translate(sections: &[u8]) -> WasmResult<()> {
+        let bytes = [HEADER, sections].concat();
+        ModuleEnvironment::new().translate(&bytes).map(drop)
+    }
+
+    #[test]
+    fn huge_section_counts_are_not_reserved_up_front() {
+        // A type section claiming u32::MAX entries but holding none
+        let result = translate(&[0x01, 0x05, 0xff, 0xff, 0xff, 0xff, 0x0f]);
+        assert!(matches!(result, Err(WasmError::InvalidWebAssembly { .. })));
+    }
+
+    #[test]
+    fn unknown_sections_are_an_error() {
+        let result = translate(&[0x7f, 0x00]);
+        assert!(matches!(result, Err(WasmError::InvalidWebAssembly { .. })));
+    }
+
+    #[test]
+    fn memory64_is_an_error() {
+        assert!(translate(&[0x05, 0x03, 0x01, 0x04, 0x01]).is_err());
+    }
+}
@@ -0,0 +1,410 @@
+//! Removing the code that a given set of exports can't reach.
+//!
+//! The call graph is built from the direct calls (`call`, `return_call`) and
+//! function references (`ref.func`) in the function bodies. Indirect calls
+//! are handled conservatively, by type: a `call_indirect` of some signature
+//! keeps every function of that signature that is in an element segment.
+
+use super::environ::FunctionBodyData;
+use super::error::from_binaryreadererror_compileerror;
+use std::collections::HashSet;
+use std::fmt;
+use wasmer_types::entity::packed_option::ReservedValue;
+use wasmer_types::entity::{EntityRef, PrimaryMap};
+use wasmer_types::{
+    CompileError, DataIndex, ExportIndex, FunctionIndex, FunctionType, GlobalInit,
+    LocalFunctionIndex, ModuleInfo, SignatureIndex,
+};
+use wasmparser::{BinaryReader, FunctionBody, Operator};
+
+/// The body stripped functions are replaced with: no locals, and a trap.
+const STUB_BODY: &[u8] = &[0x00, 0x00, 0x0b];
+
+/// The largest share of a module's functions that indirect calls may keep,
+/// in percent, before stripping isn't worth it.
+///
+/// Indirect calls can only be followed by type, so in modules that make
+/// heavy use of them nearly everything stays anyway, and the few functions
+/// that would be stripped are the likeliest to be reached in ways the
+/// analysis can't see.
+const MAX_INDIRECT_SHARE: usize = 50;
+
+/// What [`strip_unreachable()`] did to a module.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StripOutcome {
+    /// The code that the entry points can't reach was removed.
+    Stripped(StripReport),
+    /// The module was left untouched.
+    Skipped {
+        /// Why the module couldn't be stripped.
+        reason: String,
+    },
+}
+
+/// The functions and data segments [`strip_unreachable()`] removed.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StripReport {
+    /// The number of functions defined by the module.
+    pub functions: usize,
+    /// The functions whose bodies were replaced by a trap.
+    pub stripped_functions: Vec<FunctionIndex>,
+    /// The size of the bodies that were replaced, in bytes of WebAssembly.
+    pub code_bytes_saved: usize,
+    /// The passive data segments that were dropped.
+    pub pruned_data_segments: Vec<DataIndex>,
+    /// The size of the data segments that were dropped.
+    pub data_bytes_saved: usize,
+}
+
+impl fmt::Display for StripReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "stripped {} of {} functions ({} bytes of code)",
+            self.stripped_functions.len(),
+            self.functions,
+            self.code_bytes_saved
+        )?;
+        if !self.pruned_data_segments.is_empty() {
+            write!(
+                f,
+                " and {} data segments ({} bytes)",
+                self.pruned_data_segments.len(),
+                self.data_bytes_saved
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// What a function body refers to.
+#[derive(Default)]
+struct BodyRefs {
+    calls: Vec<FunctionIndex>,
+    indirect_calls: Vec<SignatureIndex>,
+    data: Vec<DataIndex>,
+}
+
+fn body_refs(body: &FunctionBodyData<'_>) -> Result<BodyRefs, CompileError> {
+    let body = FunctionBody::new(BinaryReader::new(body.data, body.module_offset));
+    let mut reader = body
+        .get_operators_reader()
+        .map_err(from_binaryreadererror_compileerror)?;
+    let mut refs = BodyRefs::default();
+    while !reader.eof() {
+        match reader.read().map_err(from_binaryreadererror_compileerror)? {
+            Operator::Call { function_index }
+            | Operator::ReturnCall { function_index }
+            | Operator::RefFunc { function_index } => {
+                refs.calls.push(FunctionIndex::from_u32(function_index))
+            }
+            Operator::CallIndirect { type_index, .. }
+            | Operator::ReturnCallIndirect { type_index, .. } => refs
+                .indirect_calls
+                .push(SignatureIndex::from_u32(type_index)),
+            Operator::MemoryInit { data_index, .. } | Operator::DataDrop { data_index } => {
+                refs.data.push(DataIndex::from_u32(data_index))
+            }
+            _ => {}
+        }
+    }
+    Ok(refs)
+}
+
+/// Replaces the bodies of the functions that none of the exports named in
+/// `entries` can reach with a trap, and drops the passive data segments
+/// they alone use.
+///
+/// Besides the entries, the start function and the functions referenced by
+/// globals are kept. Active data segments are always kept, as is every
+/// function in an element segment when a table is imported or exported.
+///
+/// Stripped functions keep their signature and their export, and are
+/// renamed to `stripped function {name}`, so that the trap they raise if
+/// they are ever called says what happened.
+///
+/// Fails if one of the entries isn't an exported function.
+pub fn strip_unreachable(
+    module: &mut ModuleInfo,
+    function_bodies: &mut PrimaryMap<LocalFunctionIndex, FunctionBodyData<'_>>,
+    entries: &[String],
+) -> Result<StripOutcome, CompileError> {
+    let mut roots = Vec::new();
+    for name in entries {
+        match module.exports.get(name) {
+            Some(ExportIndex::Function(index)) => roots.push(*index),
+            _ => {
+                return Err(CompileError::Validate(format!(
+                    "`{name}` is not an exported function"
+                )))
+            }
+        }
+    }
+    roots.extend(module.start_function);
+    roots.extend(
+        module
+            .global_initializers
+            .values()
+            .filter_map(|init| match init {
+                GlobalInit::RefFunc(index) => Some(*index),
+                _ => None,
+            }),
+    );
+
+    // The functions an indirect call could end up in
+    let in_elements: HashSet<FunctionIndex> = module
+        .table_initializers
+        .iter()
+        .flat_map(|init| init.elements.iter())
+        .chain(
+            module
+                .passive_elements
+                .values()
+                .flat_map(|elems| elems.iter()),
+        )
+        .copied()
+        .filter(|index| !index.is_reserved_value() && index.index() < module.functions.len())
+        .collect();
+    let tables_escape = module.num_imported_tables > 0
+        || module
+            .exports
+            .values()
+            .any(|export| matches!(export, ExportIndex::Table(_)));
+    if tables_escape {
+        roots.extend(in_elements.iter().copied());
+    }
+
+    let refs = function_bodies
+        .values()
+        .map(body_refs)
+        .collect::<Result<PrimaryMap<LocalFunctionIndex, _>, _>>()?;
+
+    let mut reachable = HashSet::new();
+    let mut called_types: HashSet<&FunctionType> = HashSet::new();
+    let mut used_data = HashSet::new();
+    let mut pending = roots;
+    loop {
+        while let Some(index) = pending.pop() {
+            if !reachable.insert(index) {
+                continue;
+            }
+            let Some(refs) = module.local_func_index(index).and_then(|i| refs.get(i)) else {
+                continue;
+            };
+            pending.extend(refs.calls.iter().copied());
+            used_data.extend(refs.data.iter().copied());
+            called_types.extend(
+                refs.indirect_calls
+                    .iter()
+                    .filter_map(|sig| module.signatures.get(*sig)),
+            );
+        }
+
+        // Functions that can be called indirectly, given the types that
+        // reachable code calls indirectly
+        pending.extend(in_elements.iter().copied().filter(|index| {
+            !reachable.contains(index)
+                && called_types.contains(&module.signatures[module.functions[*index]])
+        }));
+        if pending.is_empty() {
+            break;
+        }
+    }
+
+    let functions = function_bodies.len();
+    let indirect = in_elements
+        .iter()
+        .filter(|index| {
+            !module.is_imported_function(**index)
+                && called_types.contains(&module.signatures[module.functions[**index]])
+        })
+        .count();
+    if indirect > 0 && indirect * 100 > functions * MAX_INDIRECT_SHARE {
+        return Ok(StripOutcome::Skipped {
+            reason: format!("{indirect} of its {functions} functions can be called indirectly"),
+        });
+    }
+
+    let mut report = StripReport {
+        functions,
+        ..Default::default()
+    };
+    for (local_index, body) in function_bodies.iter_mut() {
+        let index = module.func_index(local_index);
+        if reachable.contains(&index) {
+            continue;
+        }
+        report.stripped_functions.push(index);
+        report.code_bytes_saved += body.data.len().saturating_sub(STUB_BODY.len());
+        body.data = STUB_BODY;
+
+        let name = module
+            .function_names
+            .get(&index)
+            .cloned()
+            .or_else(|| {
+                module.exports.iter().find_map(|(name, export)| {
+                    (*export == ExportIndex::Function(index)).then(|| name.clone())
+                })
+            })
+            .unwrap_or_else(|| index.as_u32().to_string());
+        module
+            .function_names
+            .insert(index, format!("stripped function {name}"));
+        // The stub only keeps the parameters
+        let params = module.signatures[module.functions[index]].params().len() as u32;
+        if let Some(names) = module.local_names.get_mut(&index) {
+            names.retain(|local, _| *local < params);
+        }
+    }
+
+    let mut unused_data: Vec<DataIndex> = module
+        .passive_data
+        .keys()
+        .filter(|index| !used_data.contains(*index))
+        .copied()
+        .collect();
+    unused_data.sort();
+    for index in unused_data {
+        if let Some(data) = module.passive_data.remove(&index) {
+            report.data_bytes_saved += data.len();
+            report.pruned_data_segments.push(index);
+        }
+    }
+
+    Ok(StripOutcome::Stripped(report))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ModuleEnvironment;
+
+    /// Two disjoint entry points, `a` and `b`, each with a helper, and a
+    /// function nothing calls.
+    const DISJOINT: &str = r#"
+(module
+    (memory 1)
+    (data $for_b "only b uses this")
+    (func $a (export "a") (result i32)
+        (call $helper_a))
+    (func $helper_a (result i32)
+        (i32.const 1))
+    (func $b (export "b") (result i32)
+        (memory.init $for_b (i32.const 0) (i32.const 0) (i32.const 4))
+        (call $helper_b))
+    (func $helper_b (result i32)
+        (i32.const 2))
+    (func $unused (result i32)
+        (i32.const 3)))
+"#;
+
+    /// Every function is in the table, and `main` calls through it with the
+    /// signature they all have.
+    const INDIRECT: &str = r#"
+(module
+    (type $t (func (result i32)))
+    (table funcref (elem $one $two $three))
+    (func $main (export "main") (param i32) (result i32)
+        (call_indirect (type $t) (local.get 0)))
+    (func $one (result i32) (i32.const 1))
+    (func $two (result i32) (i32.const 2))
+    (func $three (result i32) (i32.const 3))
+    (func $other (export "other") (param i32) (result i32) (local.get 0)))
+"#;
+
+    fn strip(wat: &str, entries: &[&str]) -> Result<(StripOutcome, Vec<usize>), CompileError> {
+        let wasm = wat::parse_str(wat).unwrap();
+        let translation = ModuleEnvironment::new().translate(&wasm).unwrap();
+        let mut module = translation.module;
+        let mut bodies = translation.function_body_inputs;
+        let entries: Vec<String> = entries.iter().map(|s| s.to_string()).collect();
+
+        let outcome = strip_unreachable(&mut module, &mut bodies, &entries)?;
+        let sizes = bodies.values().map(|body| body.data.len()).collect();
+        Ok((outcome, sizes))
+    }
+
+    fn stripped(outcome: StripOutcome) -> Vec<u32> {
+        match outcome {
+            StripOutcome::Stripped(report) => report
+                .stripped_functions
+                .iter()
+                .map(|index| index.as_u32())
+                .collect(),
+            StripOutcome::Skipped { reason } => panic!("skipped: {reason}"),
+        }
+    }
+
+    #[test]
+    fn disjoint_entry_points_keep_only_their_own_code() {
+        let (outcome, sizes) = strip(DISJOINT, &["a"]).unwrap();
+        let StripOutcome::Stripped(report) = &outcome else {
+            panic!("{outcome:?}");
+        };
+        assert_eq!(report.pruned_data_segments, [DataIndex::from_u32(0)]);
+        assert_eq!(report.data_bytes_saved, 16);
+        assert_eq!(stripped(outcome), [2, 3, 4]);
+        assert_eq!(sizes[2], STUB_BODY.len());
+
+        let (outcome, _) = strip(DISJOINT, &["b"]).unwrap();
+        let StripOutcome::Stripped(report) = &outcome else {
+            panic!("{outcome:?}");
+        };
+        assert!(report.pruned_data_segments.is_empty());
+        assert_eq!(stripped(outcome), [0, 1, 4]);
+
+        let (outcome, _) = strip(DISJOINT, &["a", "b"]).unwrap();
+        assert_eq!(stripped(outcome), [4]);
+    }
+
+    #[test]
+    fn stripped_functions_are_renamed() {
+        let wasm = wat::parse_str(DISJOINT).unwrap();
+        let translation = ModuleEnvironment::new().translate(&wasm).unwrap();
+        let mut module = translation.module;
+        let mut bodies = translation.function_body_inputs;
+
+        strip_unreachable(&mut module, &mut bodies, &["a".to_string()]).unwrap();
+
+        assert_eq!(
+            module.function_names[&FunctionIndex::from_u32(2)],
+            "stripped function b"
+        );
+        assert!(!module.function_names[&FunctionIndex::from_u32(0)].contains("stripped"));
+    }
+
+    #[test]
+    fn indirect_calls_keep_their_targets_by_type() {
+        let wasm = r#"
+(module
+    (type $t (func (result i32)))
+    (table funcref (elem $one $wrong_type))
+    (func $main (export "main") (result i32)
+        (call_indirect (type $t) (i32.const 0)))
+    (func $one (result i32) (i32.const 1))
+    (func $wrong_type (param i32) (result i32) (local.get 0))
+    (func $a (result i32) (i32.const 1))
+    (func $b (result i32) (i32.const 1)))
+"#;
+        let (outcome, _) = strip(wasm, &["main"]).unwrap();
+        assert_eq!(stripped(outcome), [2, 3, 4]);
+    }
+
+    #[test]
+    fn indirect_call_heavy_modules_are_left_untouched() {
+        let (outcome, sizes) = strip(INDIRECT, &["main"]).unwrap();
+
+        assert!(
+            matches!(outcome, StripOutcome::Skipped { .. }),
+            "{outcome:?}"
+        );
+        assert!(sizes.iter().all(|size| *size > STUB_BODY.len()));
+    }
+
+    #[test]
+    fn entries_must_be_exported_functions() {
+        let error = strip(DISJOINT, &["c"]).unwrap_err();
+        assert!(matches!(error, CompileError::Validate(_)), "{error:?}");
+    }
+}
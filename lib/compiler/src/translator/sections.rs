@@ -87,18 +87,12 @@ pub fn parse_type_section(
         let returns = functype.results();
         let sig_params: Box<[Type]> = params
             .iter()
-            .map(|ty| {
-                wptype_to_type(*ty)
-                    .expect("only numeric types are supported in function signatures")
-            })
-            .collect();
+            .map(|ty| wptype_to_type(*ty))
+            .collect::<WasmResult<_>>()?;
         let sig_returns: Box<[Type]> = returns
             .iter()
-            .map(|ty| {
-                wptype_to_type(*ty)
-                    .expect("only numeric types are supported in function signatures")
-            })
-            .collect();
+            .map(|ty| wptype_to_type(*ty))
+            .collect::<WasmResult<_>>()?;
         let sig = FunctionType::new(sig_params, sig_returns);
         environ.declare_signature(sig)?;
         module_translation_state
@@ -140,7 +134,7 @@ pub fn parse_import_section<'data>(
                 ..
             }) => {
                 if memory64 {
-                    unimplemented!("64bit memory not implemented yet");
+                    return Err(wasm_unsupported!("64bit memory not implemented yet"));
                 }
                 environ.declare_memory_import(
                     MemoryType {
@@ -211,7 +205,7 @@ pub fn parse_table_section(
     for entry in tables {
         let table = entry.map_err(from_binaryreadererror_wasmerror)?;
         environ.declare_table(TableType {
-            ty: wpreftype_to_type(table.ty.element_type).unwrap(),
+            ty: wpreftype_to_type(table.ty.element_type)?,
             minimum: table.ty.initial as u32,
             maximum: table.ty.maximum.map(|v| v as u32),
         })?;
@@ -236,7 +230,7 @@ pub fn parse_memory_section(
             ..
         } = entry.map_err(from_binaryreadererror_wasmerror)?;
         if memory64 {
-            unimplemented!("64bit memory not implemented yet");
+            return Err(wasm_unsupported!("64bit memory not implemented yet"));
         }
         environ.declare_memory(MemoryType {
             minimum: Pages(initial as u32),
@@ -308,7 +302,7 @@ pub fn parse_global_section(
             }
         };
         let global = GlobalType {
-            ty: wptype_to_type(content_type).unwrap(),
+            ty: wptype_to_type(content_type)?,
             mutability: mutable.into(),
         };
         environ.declare_global(global, initializer)?;
@@ -532,7 +526,14 @@ pub fn parse_name_section<'data>(
             } => {
                 environ.declare_module_name(name)?;
             }
-            wasmparser::Name::Local(_) => {}
+            wasmparser::Name::Local(local_subsection) => {
+                for function in local_subsection.into_iter().flatten() {
+                    let func_index = FunctionIndex::from_u32(function.index);
+                    for naming in function.names.into_iter().flatten() {
+                        environ.declare_local_name(func_index, naming.index, naming.name)?;
+                    }
+                }
+            }
             wasmparser::Name::Label(_)
             | wasmparser::Name::Type(_)
             | wasmparser::Name::Table(_)
@@ -25,6 +25,10 @@ use wasmer_types::{
 pub struct CompileModuleInfo {
     /// The features used for compiling the module
     pub features: Features,
+    /// The features the module actually uses.
+    ///
+    /// This is always a subset of `features`.
+    pub required_features: Features,
     /// The module information
     pub module: Arc<ModuleInfo>,
     /// The memory styles used for compiling.
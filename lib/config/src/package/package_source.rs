@@ -46,6 +46,46 @@ impl PackageSource {
             None
         }
     }
+
+    /// Splits the name of a command off a package source written as
+    /// `package:command`, e.g. `python:pip`, `wasmer/python@3.12:pip` or
+    /// `./python:pip`.
+    ///
+    /// The colon has to come after the last path separator, so registries
+    /// (`wasmer.io:wasmer/python`) and paths with colons in their
+    /// directories are left alone, as are package hashes and URLs, which
+    /// can't have a command.
+    pub fn split_command(value: &str) -> (&str, Option<&str>) {
+        if value.contains("://") || PackageHash::from_str(value).is_ok() {
+            return (value, None);
+        }
+
+        let last_segment = value.rfind(['/', '\\']).map_or(0, |pos| pos + 1);
+        match value[last_segment..].rsplit_once(':') {
+            Some((_, command)) if is_command_name(command) => {
+                let package = &value[..value.len() - command.len() - 1];
+                (package, Some(command))
+            }
+            _ => (value, None),
+        }
+    }
+
+    /// Parses a package source, optionally followed by `:command` (see
+    /// [`PackageSource::split_command()`]).
+    ///
+    /// Without a command, the package's entrypoint is meant.
+    pub fn parse_with_command(value: &str) -> Result<(Self, Option<String>), PackageParseError> {
+        let (package, command) = Self::split_command(value);
+        let package = package.parse()?;
+        Ok((package, command.map(String::from)))
+    }
+}
+
+fn is_command_name(value: &str) -> bool {
+    !value.is_empty()
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
 }
 
 impl From<PackageIdent> for PackageSource {
@@ -309,6 +349,61 @@ mod tests {
         }
     }
 
+    #[test]
+    fn split_commands() {
+        let hash = "sha256:5891b5b522d5df086d0ff0b110fbd9d21bb4fc7163af34d08286a2e846f6be03";
+        let inputs = [
+            ("python", ("python", None)),
+            ("python:pip", ("python", Some("pip"))),
+            (
+                "wasmer/python@3.12:pip",
+                ("wasmer/python@3.12", Some("pip")),
+            ),
+            (
+                "wasmer/python@=3.12.0:pip3.12",
+                ("wasmer/python@=3.12.0", Some("pip3.12")),
+            ),
+            ("reg.com:ns/name", ("reg.com:ns/name", None)),
+            ("reg.com:ns/name:cmd", ("reg.com:ns/name", Some("cmd"))),
+            ("./python:pip", ("./python", Some("pip"))),
+            ("./a:b/python", ("./a:b/python", None)),
+            ("python:", ("python:", None)),
+            (hash, (hash, None)),
+            (
+                "https://example.com:8080",
+                ("https://example.com:8080", None),
+            ),
+        ];
+        for (input, expected) in inputs {
+            assert_eq!(PackageSource::split_command(input), expected, "{input}");
+        }
+
+        let with_hash = format!("{hash}:cmd");
+        assert_eq!(
+            PackageSource::split_command(&with_hash),
+            (hash, Some("cmd"))
+        );
+    }
+
+    #[test]
+    fn parse_with_command() {
+        let (source, command) = PackageSource::parse_with_command("ns/name@1.0.0:cmd").unwrap();
+        assert_eq!(
+            source,
+            PackageSource::from(NamedPackageIdent {
+                registry: None,
+                namespace: Some("ns".to_string()),
+                name: "name".to_string(),
+                tag: Some(Tag::VersionReq("1.0.0".parse().unwrap())),
+            })
+        );
+        assert_eq!(command.as_deref(), Some("cmd"));
+
+        let (source, command) = PackageSource::parse_with_command("ns/name").unwrap();
+        assert_eq!(source, PackageSource::from_str("ns/name").unwrap());
+        assert_eq!(command, None);
+    }
+
     #[test]
     fn parse_package_sources() {
         let inputs = [
@@ -1869,6 +1869,7 @@ pub enum JournalSockoptionV1 {
     MulticastTtlV4,
     Type,
     Proto,
+    TlsAlpn,
 }
 
 #[repr(C)]
@@ -431,6 +431,7 @@ impl From<wasi::Sockoption> for JournalSockoptionV1 {
             wasi::Sockoption::MulticastTtlV4 => JournalSockoptionV1::MulticastTtlV4,
             wasi::Sockoption::Type => JournalSockoptionV1::Type,
             wasi::Sockoption::Proto => JournalSockoptionV1::Proto,
+            wasi::Sockoption::TlsAlpn => JournalSockoptionV1::TlsAlpn,
         }
     }
 }
@@ -465,6 +466,7 @@ impl From<JournalSockoptionV1> for wasi::Sockoption {
             JournalSockoptionV1::MulticastTtlV4 => wasi::Sockoption::MulticastTtlV4,
             JournalSockoptionV1::Type => wasi::Sockoption::Type,
             JournalSockoptionV1::Proto => wasi::Sockoption::Proto,
+            JournalSockoptionV1::TlsAlpn => wasi::Sockoption::TlsAlpn,
         }
     }
 }
@@ -499,6 +501,7 @@ impl From<&'_ ArchivedJournalSockoptionV1> for wasi::Sockoption {
             ArchivedJournalSockoptionV1::MulticastTtlV4 => wasi::Sockoption::MulticastTtlV4,
             ArchivedJournalSockoptionV1::Type => wasi::Sockoption::Type,
             ArchivedJournalSockoptionV1::Proto => wasi::Sockoption::Proto,
+            ArchivedJournalSockoptionV1::TlsAlpn => wasi::Sockoption::TlsAlpn,
         }
     }
 }
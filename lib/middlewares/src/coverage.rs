@@ -0,0 +1,832 @@
+//! `coverage` is a middleware for recording which functions are
+//! entered and which way each conditional branch goes, without
+//! recompiling the guest.
+//!
+//! Every function entry and every outcome of an `if`, `br_if` and
+//! `br_table` is a coverage site, with a counter that is bumped each
+//! time it is reached. The counters are read back with [`dump`], which
+//! maps them to the function and the byte offset in the module of the
+//! instruction they belong to.
+
+use std::fmt::{self, Write as _};
+use std::sync::Mutex;
+use wasmer::wasmparser::{BinaryReader, FunctionBody, Operator};
+use wasmer::{
+    sys::{
+        FunctionBodyData, FunctionMiddleware, MiddlewareError, MiddlewareReaderState,
+        ModuleMiddleware,
+    },
+    AsStoreMut, ExportIndex, GlobalInit, GlobalType, Instance, LocalFunctionIndex, Mutability,
+    Type,
+};
+use wasmer_types::entity::{EntityRef, PrimaryMap};
+use wasmer_types::{FunctionIndex, GlobalIndex, ModuleInfo};
+
+/// The custom section describing the coverage sites of a module.
+const SITES_SECTION: &str = "wasmer_coverage_sites";
+
+/// The size of a site in [`SITES_SECTION`].
+const SITE_SIZE: usize = 13;
+
+/// The name of the exported counter of the site at `index`.
+fn counter_export(index: usize) -> String {
+    format!("wasmer_coverage_counter_{index}")
+}
+
+/// What reaching a coverage site means.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SiteKind {
+    /// The function was entered.
+    Entry,
+
+    /// The condition of an `if` or `br_if` was true: the `then` arm
+    /// runs, or the branch is taken.
+    Taken,
+
+    /// The condition of an `if` or `br_if` was false: the `else` arm
+    /// runs, or execution falls through.
+    NotTaken,
+
+    /// A `br_table` jumped to the target at this position of its list.
+    Target(u32),
+
+    /// A `br_table` jumped to its default target.
+    Default,
+}
+
+impl SiteKind {
+    /// The position of this outcome among those of its instruction.
+    fn arm(&self, targets: u32) -> u32 {
+        match self {
+            Self::Entry | Self::Taken => 0,
+            Self::NotTaken => 1,
+            Self::Target(target) => *target,
+            Self::Default => targets,
+        }
+    }
+
+    fn encode(&self) -> (u8, u32) {
+        match self {
+            Self::Entry => (0, 0),
+            Self::Taken => (1, 0),
+            Self::NotTaken => (2, 0),
+            Self::Target(target) => (3, *target),
+            Self::Default => (4, 0),
+        }
+    }
+
+    fn decode(tag: u8, target: u32) -> Option<Self> {
+        match tag {
+            0 => Some(Self::Entry),
+            1 => Some(Self::Taken),
+            2 => Some(Self::NotTaken),
+            3 => Some(Self::Target(target)),
+            4 => Some(Self::Default),
+            _ => None,
+        }
+    }
+}
+
+/// A site found when scanning a function, before its counter is known.
+#[derive(Debug, Clone, Copy)]
+struct ScannedSite {
+    offset: u32,
+    kind: SiteKind,
+}
+
+/// What `Coverage` learns about a module while it is compiled.
+#[derive(Debug, Default)]
+struct CoverageState {
+    /// The sites of each local function, in the order they are met.
+    sites: PrimaryMap<LocalFunctionIndex, Vec<ScannedSite>>,
+
+    /// The global the instrumentation keeps the condition in while it
+    /// counts.
+    scratch: Option<GlobalIndex>,
+
+    /// The counter of the first site of each local function. The counters
+    /// of a function are consecutive globals.
+    first_counters: PrimaryMap<LocalFunctionIndex, GlobalIndex>,
+}
+
+/// The module-level coverage middleware.
+///
+/// The counters are globals added to the module, and bumping them
+/// doesn't branch, so the overhead of an instruction is bounded by its
+/// number of outcomes. As the instrumentation doesn't add branches, it
+/// can be combined with [`Metering`][crate::Metering], which charges
+/// for the instructions it adds like for any other. `Coverage` must
+/// come before any middleware that adds branches in the chain,
+/// otherwise compilation fails.
+///
+/// # Panic
+///
+/// An instance of `Coverage` should _not_ be shared among different
+/// modules, since it tracks the sites of the module it instruments.
+/// Attempts to use a `Coverage` instance from multiple modules will
+/// result in a panic.
+///
+/// # Example
+///
+/// ```rust
+/// use std::sync::Arc;
+/// use wasmer::sys::CompilerConfig;
+/// use wasmer_middlewares::Coverage;
+///
+/// fn create_coverage_middleware(compiler_config: &mut dyn CompilerConfig) {
+///     compiler_config.push_middleware(Arc::new(Coverage::new()));
+/// }
+/// ```
+#[derive(Debug, Default)]
+pub struct Coverage {
+    state: Mutex<Option<CoverageState>>,
+}
+
+impl Coverage {
+    /// Creates a `Coverage` middleware.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl ModuleMiddleware for Coverage {
+    /// Generates a `FunctionMiddleware` for a given function.
+    fn generate_function_middleware(
+        &self,
+        local_function_index: LocalFunctionIndex,
+    ) -> Box<dyn FunctionMiddleware> {
+        let state = self.state.lock().unwrap();
+        let state = state.as_ref().unwrap();
+        let first = state.first_counters[local_function_index].as_u32();
+        let count = state.sites[local_function_index].len() as u32;
+
+        Box::new(FunctionCoverage {
+            scratch: state.scratch.unwrap().as_u32(),
+            next_counter: first,
+            end_counter: first + count,
+        })
+    }
+
+    /// Finds the coverage sites of every function.
+    fn inspect_function_bodies(
+        &self,
+        function_bodies: &PrimaryMap<LocalFunctionIndex, FunctionBodyData<'_>>,
+    ) -> Result<(), MiddlewareError> {
+        let mut state = self.state.lock().unwrap();
+
+        if state.is_some() {
+            panic!("Coverage::inspect_function_bodies: Attempting to use a `Coverage` middleware from multiple modules.");
+        }
+
+        let mut sites = PrimaryMap::with_capacity(function_bodies.len());
+        for (_, body) in function_bodies.iter() {
+            sites.push(scan(body).map_err(|e| MiddlewareError::new("Coverage", e.to_string()))?);
+        }
+
+        *state = Some(CoverageState {
+            sites,
+            ..Default::default()
+        });
+
+        Ok(())
+    }
+
+    /// Adds the counters, and describes the sites they count in a custom
+    /// section.
+    fn transform_module_info(&self, module_info: &mut ModuleInfo) -> Result<(), MiddlewareError> {
+        let mut state = self.state.lock().unwrap();
+        let state = state.as_mut().unwrap();
+
+        if state.scratch.is_some() {
+            panic!("Coverage::transform_module_info: Attempting to use a `Coverage` middleware from multiple modules.");
+        }
+
+        state.scratch = Some(
+            module_info
+                .globals
+                .push(GlobalType::new(Type::I32, Mutability::Var)),
+        );
+        module_info
+            .global_initializers
+            .push(GlobalInit::I32Const(0));
+
+        let mut section = Vec::new();
+        let mut index = 0;
+        for (local_function_index, sites) in state.sites.iter() {
+            let function_index = module_info.func_index(local_function_index);
+            let mut first_counter = None;
+
+            for site in sites {
+                let counter = module_info
+                    .globals
+                    .push(GlobalType::new(Type::I64, Mutability::Var));
+                module_info
+                    .global_initializers
+                    .push(GlobalInit::I64Const(0));
+                module_info
+                    .exports
+                    .insert(counter_export(index), ExportIndex::Global(counter));
+                first_counter.get_or_insert(counter);
+                index += 1;
+
+                let (tag, target) = site.kind.encode();
+                section.extend_from_slice(&function_index.as_u32().to_le_bytes());
+                section.extend_from_slice(&site.offset.to_le_bytes());
+                section.push(tag);
+                section.extend_from_slice(&target.to_le_bytes());
+            }
+
+            // Functions always have their entry site
+            state.first_counters.push(first_counter.unwrap());
+        }
+
+        let section_index = module_info
+            .custom_sections_data
+            .push(section.into_boxed_slice());
+        module_info
+            .custom_sections
+            .insert(SITES_SECTION.to_string(), vec![section_index]);
+
+        Ok(())
+    }
+}
+
+/// Finds the coverage sites of a function body.
+fn scan(
+    body: &FunctionBodyData<'_>,
+) -> Result<Vec<ScannedSite>, wasmer::wasmparser::BinaryReaderError> {
+    let mut sites = vec![ScannedSite {
+        offset: body.module_offset as u32,
+        kind: SiteKind::Entry,
+    }];
+
+    let body = FunctionBody::new(BinaryReader::new(body.data, body.module_offset));
+    let mut reader = body.get_operators_reader()?;
+    while !reader.eof() {
+        let (operator, offset) = reader.read_with_offset()?;
+        let offset = offset as u32;
+        match operator {
+            Operator::If { .. } | Operator::BrIf { .. } => {
+                sites.push(ScannedSite {
+                    offset,
+                    kind: SiteKind::Taken,
+                });
+                sites.push(ScannedSite {
+                    offset,
+                    kind: SiteKind::NotTaken,
+                });
+            }
+            Operator::BrTable { targets } => {
+                sites.extend((0..targets.len()).map(|target| ScannedSite {
+                    offset,
+                    kind: SiteKind::Target(target),
+                }));
+                sites.push(ScannedSite {
+                    offset,
+                    kind: SiteKind::Default,
+                });
+            }
+            _ => {}
+        }
+    }
+
+    Ok(sites)
+}
+
+/// The function-level coverage middleware.
+#[derive(Debug)]
+pub struct FunctionCoverage {
+    /// The global the condition is kept in while counting.
+    scratch: u32,
+
+    /// The counter of the next site.
+    next_counter: u32,
+
+    /// The counter after the last site of the function.
+    end_counter: u32,
+}
+
+impl FunctionCoverage {
+    /// Takes the counters of the next `count` sites.
+    fn take_counters(&mut self, count: u32) -> Result<u32, MiddlewareError> {
+        let first = self.next_counter;
+        if first + count > self.end_counter {
+            return Err(MiddlewareError::new(
+                "Coverage",
+                "found more branches than the function has, is a middleware adding branches before `Coverage`?",
+            ));
+        }
+        self.next_counter += count;
+        Ok(first)
+    }
+
+    /// Adds `(i64.extend_i32_u condition)` to `counter`, where `condition`
+    /// is computed from the scratch global by `condition`.
+    fn bump_if<'a>(&self, counter: u32, condition: &[Operator<'a>], out: &mut Vec<Operator<'a>>) {
+        out.push(Operator::GlobalGet {
+            global_index: counter,
+        });
+        out.push(Operator::GlobalGet {
+            global_index: self.scratch,
+        });
+        out.extend_from_slice(condition);
+        out.extend([
+            Operator::I64ExtendI32U,
+            Operator::I64Add,
+            Operator::GlobalSet {
+                global_index: counter,
+            },
+        ]);
+    }
+}
+
+impl FunctionMiddleware for FunctionCoverage {
+    fn prologue(&mut self, state: &mut MiddlewareReaderState<'_>) -> Result<(), MiddlewareError> {
+        let counter = self.take_counters(1)?;
+        state.extend(&[
+            // globals[counter] += 1;
+            Operator::GlobalGet {
+                global_index: counter,
+            },
+            Operator::I64Const { value: 1 },
+            Operator::I64Add,
+            Operator::GlobalSet {
+                global_index: counter,
+            },
+        ]);
+        Ok(())
+    }
+
+    fn feed<'a>(
+        &mut self,
+        operator: Operator<'a>,
+        state: &mut MiddlewareReaderState<'a>,
+    ) -> Result<(), MiddlewareError> {
+        // The condition, or the index of the target, is saved to the
+        // scratch global, counted and put back for the original operator
+        let mut counting = vec![Operator::GlobalSet {
+            global_index: self.scratch,
+        }];
+        match &operator {
+            Operator::If { .. } | Operator::BrIf { .. } => {
+                let taken = self.take_counters(2)?;
+                self.bump_if(taken, &[Operator::I32Eqz, Operator::I32Eqz], &mut counting);
+                self.bump_if(taken + 1, &[Operator::I32Eqz], &mut counting);
+            }
+            Operator::BrTable { targets } => {
+                let count = targets.len();
+                let first = self.take_counters(count + 1)?;
+                for target in 0..count {
+                    self.bump_if(
+                        first + target,
+                        &[
+                            Operator::I32Const {
+                                value: target as i32,
+                            },
+                            Operator::I32Eq,
+                        ],
+                        &mut counting,
+                    );
+                }
+                self.bump_if(
+                    first + count,
+                    &[
+                        Operator::I32Const {
+                            value: count as i32,
+                        },
+                        Operator::I32GeU,
+                    ],
+                    &mut counting,
+                );
+            }
+            _ => {
+                state.push_operator(operator);
+                return Ok(());
+            }
+        }
+        counting.push(Operator::GlobalGet {
+            global_index: self.scratch,
+        });
+        state.extend(counting);
+        state.push_operator(operator);
+
+        Ok(())
+    }
+}
+
+/// How many times a coverage site was reached.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SiteCoverage {
+    /// The function the site is in.
+    pub function: FunctionIndex,
+
+    /// The name of the function, from the name section of the module.
+    pub function_name: Option<String>,
+
+    /// The byte offset in the module of the instruction, or of the body
+    /// of the function for [`SiteKind::Entry`].
+    pub offset: usize,
+
+    /// What reaching the site means.
+    pub kind: SiteKind,
+
+    /// How many times the site was reached.
+    pub count: u64,
+}
+
+impl SiteCoverage {
+    /// Whether the site was reached at all.
+    pub fn is_covered(&self) -> bool {
+        self.count > 0
+    }
+
+    /// The name of the function, or `func[<index>]` if it has none.
+    pub fn display_function_name(&self) -> String {
+        match &self.function_name {
+            Some(name) => name.clone(),
+            None => format!("func[{}]", self.function.as_u32()),
+        }
+    }
+}
+
+/// The coverage of an instance, returned by [`dump`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CoverageReport {
+    /// The name of the module, if it has one.
+    pub module_name: Option<String>,
+
+    /// Every site of the module, ordered by function and offset.
+    pub sites: Vec<SiteCoverage>,
+}
+
+impl CoverageReport {
+    /// The function entries.
+    pub fn functions(&self) -> impl Iterator<Item = &SiteCoverage> {
+        self.sites
+            .iter()
+            .filter(|site| site.kind == SiteKind::Entry)
+    }
+
+    /// The outcomes of the conditional branches.
+    pub fn branches(&self) -> impl Iterator<Item = &SiteCoverage> {
+        self.sites
+            .iter()
+            .filter(|site| site.kind != SiteKind::Entry)
+    }
+
+    /// Formats the report in the LCOV tracefile format, using byte
+    /// offsets in the module instead of line numbers.
+    ///
+    /// Each function entry is an `FN`/`FNDA` record, and each branch
+    /// instruction a line (`DA`) with one `BRDA` record per outcome.
+    pub fn to_lcov(&self) -> String {
+        let mut out = String::new();
+        let name = self.module_name.as_deref().unwrap_or("module.wasm");
+        writeln!(out, "TN:").unwrap();
+        writeln!(out, "SF:{name}").unwrap();
+
+        for function in self.functions() {
+            let name = function.display_function_name();
+            writeln!(out, "FN:{},{name}", function.offset).unwrap();
+            writeln!(out, "FNDA:{},{name}", function.count).unwrap();
+        }
+        writeln!(out, "FNF:{}", self.functions().count()).unwrap();
+        let hit = self.functions().filter(|f| f.is_covered()).count();
+        writeln!(out, "FNH:{hit}").unwrap();
+
+        let branches: Vec<_> = self.branches().collect();
+        let mut lines = 0;
+        let mut lines_hit = 0;
+        for instruction in branches.chunk_by(|a, b| a.offset == b.offset) {
+            let targets = instruction.len() as u32 - 1;
+            for branch in instruction {
+                writeln!(
+                    out,
+                    "BRDA:{},0,{},{}",
+                    branch.offset,
+                    branch.kind.arm(targets),
+                    branch.count
+                )
+                .unwrap();
+            }
+            let count: u64 = instruction.iter().map(|branch| branch.count).sum();
+            writeln!(out, "DA:{},{count}", instruction[0].offset).unwrap();
+            lines += 1;
+            lines_hit += (count > 0) as usize;
+        }
+        writeln!(out, "BRF:{}", branches.len()).unwrap();
+        let hit = branches.iter().filter(|b| b.is_covered()).count();
+        writeln!(out, "BRH:{hit}").unwrap();
+        writeln!(out, "LF:{lines}").unwrap();
+        writeln!(out, "LH:{lines_hit}").unwrap();
+        writeln!(out, "end_of_record").unwrap();
+
+        out
+    }
+}
+
+impl fmt::Display for CoverageReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for site in &self.sites {
+            writeln!(
+                f,
+                "{:#x} {} {:?}: {}",
+                site.offset,
+                site.display_function_name(),
+                site.kind,
+                site.count
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Get the coverage of an [`Instance`][wasmer::Instance].
+///
+/// Note: This can be used in a headless engine after an ahead-of-time
+/// compilation as all required state lives in the instance and its
+/// module.
+///
+/// # Panic
+///
+/// The [`Instance`][wasmer::Instance] must have been processed with
+/// the [`Coverage`] middleware at compile time, otherwise this will
+/// panic.
+///
+/// # Example
+///
+/// ```rust
+/// use wasmer::{AsStoreMut, Instance};
+/// use wasmer_middlewares::coverage::dump;
+///
+/// /// Lists the branches that were never taken.
+/// fn missed_branches(store: &mut impl AsStoreMut, instance: &Instance) -> Vec<String> {
+///     dump(store, instance)
+///         .branches()
+///         .filter(|site| !site.is_covered())
+///         .map(|site| format!("{:?} at {:#x}", site.kind, site.offset))
+///         .collect()
+/// }
+/// ```
+pub fn dump(ctx: &mut impl AsStoreMut, instance: &Instance) -> CoverageReport {
+    let info = instance.module().info();
+    let section = info
+        .custom_sections
+        .get(SITES_SECTION)
+        .and_then(|indices| indices.first())
+        .map(|index| &info.custom_sections_data[*index])
+        .expect("Can't find the coverage sites of the Instance's module");
+
+    let sites = section
+        .chunks_exact(SITE_SIZE)
+        .enumerate()
+        .map(|(index, site)| {
+            let word = |at: usize| u32::from_le_bytes(site[at..at + 4].try_into().unwrap());
+            let function = FunctionIndex::new(word(0) as usize);
+            let kind = SiteKind::decode(site[8], word(9))
+                .expect("The coverage sites of the Instance's module are corrupted");
+
+            let name = counter_export(index);
+            let count: u64 = instance
+                .exports
+                .get_global(&name)
+                .unwrap_or_else(|_| panic!("Can't get `{name}` from Instance"))
+                .get(ctx)
+                .try_into()
+                .unwrap_or_else(|_| panic!("`{name}` from Instance has wrong type"));
+
+            SiteCoverage {
+                function,
+                function_name: info.function_names.get(&function).cloned(),
+                offset: word(4) as usize,
+                kind,
+                count,
+            }
+        })
+        .collect();
+
+    CoverageReport {
+        module_name: info.name.clone(),
+        sites,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::Arc;
+    use wasmer::sys::EngineBuilder;
+    use wasmer::{
+        imports,
+        sys::{CompilerConfig, Cranelift},
+        wat2wasm, Module, Store, TypedFunction,
+    };
+
+    use crate::metering::{get_remaining_points, MeteringPoints};
+    use crate::Metering;
+
+    fn bytecode() -> Vec<u8> {
+        wat2wasm(
+            br#"(module $fixture
+            (func $pick (export "pick") (param $first i32) (result i32)
+                (if (result i32) (local.get $first)
+                    (then (i32.const 1))
+                    (else (i32.const 2))))
+            (func $dispatch (export "dispatch") (param $target i32) (result i32)
+                (block $b
+                    (block $a
+                        (br_table $a $b (local.get $target)))
+                    (return (i32.const 10)))
+                (i32.const 20))
+            (func $unused (export "unused") (param $value i32)
+                (br_if 0 (local.get $value)))
+        )"#,
+        )
+        .unwrap()
+        .into()
+    }
+
+    fn instantiate(compiler_config: Cranelift) -> (Store, Instance) {
+        let mut store = Store::new(EngineBuilder::new(compiler_config));
+        let module = Module::new(&store, bytecode()).unwrap();
+        let instance = Instance::new(&mut store, &module, &imports! {}).unwrap();
+        (store, instance)
+    }
+
+    fn coverage_of(report: &CoverageReport, function: &str) -> Vec<(SiteKind, u64)> {
+        report
+            .sites
+            .iter()
+            .filter(|site| site.function_name.as_deref() == Some(function))
+            .map(|site| (site.kind, site.count))
+            .collect()
+    }
+
+    #[test]
+    fn only_taken_branches_are_covered() {
+        let mut compiler_config = Cranelift::default();
+        compiler_config.push_middleware(Arc::new(Coverage::new()));
+        let (mut store, instance) = instantiate(compiler_config);
+
+        let report = dump(&mut store, &instance);
+        assert!(report.sites.iter().all(|site| !site.is_covered()));
+
+        let pick: TypedFunction<i32, i32> = instance
+            .exports
+            .get_function("pick")
+            .unwrap()
+            .typed(&store)
+            .unwrap();
+        assert_eq!(pick.call(&mut store, 1).unwrap(), 1);
+
+        let report = dump(&mut store, &instance);
+        let covered: Vec<_> = report.branches().filter(|site| site.is_covered()).collect();
+        assert_eq!(covered.len(), 1);
+        assert_eq!(covered[0].kind, SiteKind::Taken);
+        assert_eq!(covered[0].function_name.as_deref(), Some("pick"));
+        assert_eq!(
+            coverage_of(&report, "pick"),
+            vec![
+                (SiteKind::Entry, 1),
+                (SiteKind::Taken, 1),
+                (SiteKind::NotTaken, 0)
+            ]
+        );
+        assert_eq!(
+            coverage_of(&report, "unused"),
+            vec![
+                (SiteKind::Entry, 0),
+                (SiteKind::Taken, 0),
+                (SiteKind::NotTaken, 0)
+            ]
+        );
+
+        // The offset is the one of the `if` in the module
+        let wasm = bytecode();
+        assert_eq!(wasm[covered[0].offset], 0x04);
+    }
+
+    #[test]
+    fn br_table_targets_are_counted() {
+        let mut compiler_config = Cranelift::default();
+        compiler_config.push_middleware(Arc::new(Coverage::new()));
+        let (mut store, instance) = instantiate(compiler_config);
+
+        let dispatch: TypedFunction<i32, i32> = instance
+            .exports
+            .get_function("dispatch")
+            .unwrap()
+            .typed(&store)
+            .unwrap();
+        assert_eq!(dispatch.call(&mut store, 0).unwrap(), 10);
+        assert_eq!(dispatch.call(&mut store, 7).unwrap(), 20);
+        assert_eq!(dispatch.call(&mut store, 7).unwrap(), 20);
+
+        let report = dump(&mut store, &instance);
+        assert_eq!(
+            coverage_of(&report, "dispatch"),
+            vec![
+                (SiteKind::Entry, 3),
+                (SiteKind::Target(0), 1),
+                (SiteKind::Default, 2)
+            ]
+        );
+    }
+
+    #[test]
+    fn lcov_is_keyed_by_offsets() {
+        let mut compiler_config = Cranelift::default();
+        compiler_config.push_middleware(Arc::new(Coverage::new()));
+        let (mut store, instance) = instantiate(compiler_config);
+
+        let pick: TypedFunction<i32, i32> = instance
+            .exports
+            .get_function("pick")
+            .unwrap()
+            .typed(&store)
+            .unwrap();
+        pick.call(&mut store, 0).unwrap();
+
+        let report = dump(&mut store, &instance);
+        let lcov = report.to_lcov();
+        let entry = report.functions().next().unwrap();
+        let branch = report.branches().next().unwrap();
+
+        assert!(lcov.starts_with("TN:\nSF:fixture\n"), "{lcov}");
+        assert!(lcov.contains(&format!("FN:{},pick\nFNDA:1,pick\n", entry.offset)));
+        assert!(lcov.contains("FNF:3\nFNH:1\n"), "{lcov}");
+        assert!(lcov.contains(&format!(
+            "BRDA:{0},0,0,0\nBRDA:{0},0,1,1\nDA:{0},1\n",
+            branch.offset
+        )));
+        assert!(
+            lcov.contains("BRF:6\nBRH:1\nLF:3\nLH:1\nend_of_record\n"),
+            "{lcov}"
+        );
+    }
+
+    #[test]
+    fn coverage_composes_with_metering() {
+        // Doesn't charge for any of the operators the instrumentation adds
+        let cost_function = |operator: &Operator| -> u64 {
+            matches!(
+                operator,
+                Operator::LocalGet { .. } | Operator::I32Const { .. } | Operator::If { .. }
+            ) as u64
+        };
+
+        let mut compiler_config = Cranelift::default();
+        compiler_config.push_middleware(Arc::new(Coverage::new()));
+        compiler_config.push_middleware(Arc::new(Metering::new(100, cost_function)));
+        let (mut store, instance) = instantiate(compiler_config);
+
+        let pick: TypedFunction<i32, i32> = instance
+            .exports
+            .get_function("pick")
+            .unwrap()
+            .typed(&store)
+            .unwrap();
+        pick.call(&mut store, 0).unwrap();
+
+        let mut uninstrumented = Cranelift::default();
+        uninstrumented.push_middleware(Arc::new(Metering::new(100, cost_function)));
+        let (mut reference_store, reference) = instantiate(uninstrumented);
+        let reference_pick: TypedFunction<i32, i32> = reference
+            .exports
+            .get_function("pick")
+            .unwrap()
+            .typed(&reference_store)
+            .unwrap();
+        reference_pick.call(&mut reference_store, 0).unwrap();
+        assert_eq!(
+            get_remaining_points(&mut store, &instance),
+            get_remaining_points(&mut reference_store, &reference)
+        );
+        assert_ne!(
+            get_remaining_points(&mut store, &instance),
+            MeteringPoints::Remaining(100)
+        );
+
+        let report = dump(&mut store, &instance);
+        assert_eq!(
+            coverage_of(&report, "pick"),
+            vec![
+                (SiteKind::Entry, 1),
+                (SiteKind::Taken, 0),
+                (SiteKind::NotTaken, 1)
+            ]
+        );
+    }
+
+    #[test]
+    fn coverage_must_come_before_metering() {
+        let mut compiler_config = Cranelift::default();
+        compiler_config.push_middleware(Arc::new(Metering::new(100, |_: &Operator| 1)));
+        compiler_config.push_middleware(Arc::new(Coverage::new()));
+        let store = Store::new(EngineBuilder::new(compiler_config));
+
+        let err = Module::new(&store, bytecode()).unwrap_err();
+        assert!(err.to_string().contains("Coverage"), "{err}");
+    }
+}
@@ -1,7 +1,8 @@
 //! `metering` is a middleware for tracking how many operators are
 //! executed in total and putting a limit on the total number of
 //! operators executed. The WebAssembly instance execution is stopped
-//! when the limit is reached.
+//! when the limit is reached, after which the host can top up the
+//! points and call into the instance again (see [`is_exhaustion`]).
 //!
 //! # Example
 //!
@@ -14,10 +15,18 @@ use std::sync::{Arc, Mutex};
 use wasmer::wasmparser::{BlockType as WpTypeOrFuncType, Operator};
 use wasmer::{
     sys::{FunctionMiddleware, MiddlewareError, MiddlewareReaderState, ModuleMiddleware},
-    AsStoreMut, ExportIndex, GlobalInit, GlobalType, Instance, LocalFunctionIndex, Mutability,
-    Type,
+    AsStoreMut, ExportIndex, FunctionType, GlobalInit, GlobalType, Instance, LocalFunctionIndex,
+    Mutability, RuntimeError, Type,
 };
-use wasmer_types::{GlobalIndex, ModuleInfo};
+use wasmer_types::{GlobalIndex, ModuleInfo, TrapCode};
+
+/// The name [`Metering`] exports the global holding the remaining points
+/// under, an `i64` to be read as unsigned.
+pub const REMAINING_POINTS_EXPORT: &str = "wasmer_metering_remaining_points";
+
+/// The name [`Metering`] exports the global saying whether the points were
+/// exhausted under, an `i32` that is 1 once they were and 0 otherwise.
+pub const POINTS_EXHAUSTED_EXPORT: &str = "wasmer_metering_points_exhausted";
 
 #[derive(Clone)]
 struct MeteringGlobalIndexes(GlobalIndex, GlobalIndex);
@@ -88,6 +97,9 @@ pub struct Metering<F: Fn(&Operator) -> u64 + Send + Sync> {
     /// Function that maps each operator to a cost in "points".
     cost_function: Arc<F>,
 
+    /// The cost of each local a function declares, charged when it's entered.
+    local_cost: u64,
+
     /// The global indexes for metering points.
     global_indexes: Mutex<Option<MeteringGlobalIndexes>>,
 }
@@ -100,6 +112,9 @@ pub struct FunctionMetering<F: Fn(&Operator) -> u64 + Send + Sync> {
     /// The global indexes for metering points.
     global_indexes: MeteringGlobalIndexes,
 
+    /// The cost of setting up the function's frame, charged when it's entered.
+    frame_cost: u64,
+
     /// Accumulated cost of the current basic block.
     accumulated_cost: u64,
 }
@@ -133,9 +148,27 @@ impl<F: Fn(&Operator) -> u64 + Send + Sync> Metering<F> {
         Self {
             initial_limit,
             cost_function: Arc::new(cost_function),
+            local_cost: 0,
             global_indexes: Mutex::new(None),
         }
     }
+
+    /// Charges `cost` points for each local a function declares (not
+    /// counting its parameters) every time the function is entered, for
+    /// the cost of setting up its stack frame. Defaults to 0.
+    pub fn with_local_cost(mut self, cost: u64) -> Self {
+        self.local_cost = cost;
+        self
+    }
+
+    fn function_metering(&self, frame_cost: u64) -> FunctionMetering<F> {
+        FunctionMetering {
+            cost_function: self.cost_function.clone(),
+            global_indexes: self.global_indexes.lock().unwrap().clone().unwrap(),
+            frame_cost,
+            accumulated_cost: 0,
+        }
+    }
 }
 
 impl<F: Fn(&Operator) -> u64 + Send + Sync> fmt::Debug for Metering<F> {
@@ -143,6 +176,7 @@ impl<F: Fn(&Operator) -> u64 + Send + Sync> fmt::Debug for Metering<F> {
         f.debug_struct("Metering")
             .field("initial_limit", &self.initial_limit)
             .field("cost_function", &"<function>")
+            .field("local_cost", &self.local_cost)
             .field("global_indexes", &self.global_indexes)
             .finish()
     }
@@ -150,12 +184,21 @@ impl<F: Fn(&Operator) -> u64 + Send + Sync> fmt::Debug for Metering<F> {
 
 impl<F: Fn(&Operator) -> u64 + Send + Sync + 'static> ModuleMiddleware for Metering<F> {
     /// Generates a `FunctionMiddleware` for a given function.
+    ///
+    /// Without the function's locals, nothing is charged when it's entered.
     fn generate_function_middleware(&self, _: LocalFunctionIndex) -> Box<dyn FunctionMiddleware> {
-        Box::new(FunctionMetering {
-            cost_function: self.cost_function.clone(),
-            global_indexes: self.global_indexes.lock().unwrap().clone().unwrap(),
-            accumulated_cost: 0,
-        })
+        Box::new(self.function_metering(0))
+    }
+
+    /// Generates a `FunctionMiddleware` for a given function, which charges for its locals.
+    fn generate_function_middleware_with_locals(
+        &self,
+        _: LocalFunctionIndex,
+        _: &FunctionType,
+        locals: &[(u32, Type)],
+    ) -> Box<dyn FunctionMiddleware> {
+        let num_locals: u64 = locals.iter().map(|(count, _)| u64::from(*count)).sum();
+        Box::new(self.function_metering(num_locals.saturating_mul(self.local_cost)))
     }
 
     /// Transforms a `ModuleInfo` struct in-place. This is called before application on functions begins.
@@ -176,7 +219,7 @@ impl<F: Fn(&Operator) -> u64 + Send + Sync + 'static> ModuleMiddleware for Meter
             .push(GlobalInit::I64Const(self.initial_limit as i64));
 
         module_info.exports.insert(
-            "wasmer_metering_remaining_points".to_string(),
+            REMAINING_POINTS_EXPORT.to_string(),
             ExportIndex::Global(remaining_points_global_index),
         );
 
@@ -190,7 +233,7 @@ impl<F: Fn(&Operator) -> u64 + Send + Sync + 'static> ModuleMiddleware for Meter
             .push(GlobalInit::I32Const(0));
 
         module_info.exports.insert(
-            "wasmer_metering_points_exhausted".to_string(),
+            POINTS_EXHAUSTED_EXPORT.to_string(),
             ExportIndex::Global(points_exhausted_global_index),
         );
 
@@ -244,11 +287,59 @@ impl<F: Fn(&Operator) -> u64 + Send + Sync> fmt::Debug for FunctionMetering<F> {
         f.debug_struct("FunctionMetering")
             .field("cost_function", &"<function>")
             .field("global_indexes", &self.global_indexes)
+            .field("frame_cost", &self.frame_cost)
             .finish()
     }
 }
 
+impl<F: Fn(&Operator) -> u64 + Send + Sync> FunctionMetering<F> {
+    /// Charges the accumulated cost, trapping if there aren't enough points left.
+    fn charge(&mut self, state: &mut MiddlewareReaderState<'_>) {
+        state.extend(&[
+            // if unsigned(globals[remaining_points_index]) < unsigned(self.accumulated_cost) { throw(); }
+            Operator::GlobalGet {
+                global_index: self.global_indexes.remaining_points().as_u32(),
+            },
+            Operator::I64Const {
+                value: self.accumulated_cost as i64,
+            },
+            Operator::I64LtU,
+            Operator::If {
+                blockty: WpTypeOrFuncType::Empty,
+            },
+            Operator::I32Const { value: 1 },
+            Operator::GlobalSet {
+                global_index: self.global_indexes.points_exhausted().as_u32(),
+            },
+            Operator::Unreachable,
+            Operator::End,
+            // globals[remaining_points_index] -= self.accumulated_cost;
+            Operator::GlobalGet {
+                global_index: self.global_indexes.remaining_points().as_u32(),
+            },
+            Operator::I64Const {
+                value: self.accumulated_cost as i64,
+            },
+            Operator::I64Sub,
+            Operator::GlobalSet {
+                global_index: self.global_indexes.remaining_points().as_u32(),
+            },
+        ]);
+
+        self.accumulated_cost = 0;
+    }
+}
+
 impl<F: Fn(&Operator) -> u64 + Send + Sync> FunctionMiddleware for FunctionMetering<F> {
+    fn prologue(&mut self, state: &mut MiddlewareReaderState<'_>) -> Result<(), MiddlewareError> {
+        // Pay for the frame before running any of the function's code.
+        if self.frame_cost > 0 {
+            self.accumulated_cost += self.frame_cost;
+            self.charge(state);
+        }
+        Ok(())
+    }
+
     fn feed<'a>(
         &mut self,
         operator: Operator<'a>,
@@ -261,38 +352,7 @@ impl<F: Fn(&Operator) -> u64 + Send + Sync> FunctionMiddleware for FunctionMeter
 
         // Finalize the cost of the previous basic block and perform necessary checks.
         if is_accounting(&operator) && self.accumulated_cost > 0 {
-            state.extend(&[
-                // if unsigned(globals[remaining_points_index]) < unsigned(self.accumulated_cost) { throw(); }
-                Operator::GlobalGet {
-                    global_index: self.global_indexes.remaining_points().as_u32(),
-                },
-                Operator::I64Const {
-                    value: self.accumulated_cost as i64,
-                },
-                Operator::I64LtU,
-                Operator::If {
-                    blockty: WpTypeOrFuncType::Empty,
-                },
-                Operator::I32Const { value: 1 },
-                Operator::GlobalSet {
-                    global_index: self.global_indexes.points_exhausted().as_u32(),
-                },
-                Operator::Unreachable,
-                Operator::End,
-                // globals[remaining_points_index] -= self.accumulated_cost;
-                Operator::GlobalGet {
-                    global_index: self.global_indexes.remaining_points().as_u32(),
-                },
-                Operator::I64Const {
-                    value: self.accumulated_cost as i64,
-                },
-                Operator::I64Sub,
-                Operator::GlobalSet {
-                    global_index: self.global_indexes.remaining_points().as_u32(),
-                },
-            ]);
-
-            self.accumulated_cost = 0;
+            self.charge(state);
         }
         state.push_operator(operator);
 
@@ -327,7 +387,7 @@ impl<F: Fn(&Operator) -> u64 + Send + Sync> FunctionMiddleware for FunctionMeter
 pub fn get_remaining_points(ctx: &mut impl AsStoreMut, instance: &Instance) -> MeteringPoints {
     let exhausted: i32 = instance
         .exports
-        .get_global("wasmer_metering_points_exhausted")
+        .get_global(POINTS_EXHAUSTED_EXPORT)
         .expect("Can't get `wasmer_metering_points_exhausted` from Instance")
         .get(ctx)
         .try_into()
@@ -339,7 +399,7 @@ pub fn get_remaining_points(ctx: &mut impl AsStoreMut, instance: &Instance) -> M
 
     let points = instance
         .exports
-        .get_global("wasmer_metering_remaining_points")
+        .get_global(REMAINING_POINTS_EXPORT)
         .expect("Can't get `wasmer_metering_remaining_points` from Instance")
         .get(ctx)
         .try_into()
@@ -377,19 +437,85 @@ pub fn get_remaining_points(ctx: &mut impl AsStoreMut, instance: &Instance) -> M
 pub fn set_remaining_points(ctx: &mut impl AsStoreMut, instance: &Instance, points: u64) {
     instance
         .exports
-        .get_global("wasmer_metering_remaining_points")
+        .get_global(REMAINING_POINTS_EXPORT)
         .expect("Can't get `wasmer_metering_remaining_points` from Instance")
         .set(ctx, points.into())
         .expect("Can't set `wasmer_metering_remaining_points` in Instance");
 
     instance
         .exports
-        .get_global("wasmer_metering_points_exhausted")
+        .get_global(POINTS_EXHAUSTED_EXPORT)
         .expect("Can't get `wasmer_metering_points_exhausted` from Instance")
         .set(ctx, 0i32.into())
         .expect("Can't set `wasmer_metering_points_exhausted` in Instance");
 }
 
+/// Returns `true` if `error`, from a call into `instance`, is the trap the
+/// [`Metering`] middleware raises when the points are exhausted, rather than
+/// any other trap.
+///
+/// The instance is left as it was when the points ran out, so the host can
+/// top them up with [`set_remaining_points`] and call into it again.
+///
+/// # Panic
+///
+/// The given [`Instance`][wasmer::Instance] must have been processed
+/// with the [`Metering`] middleware at compile time, otherwise this
+/// will panic.
+///
+/// # Example
+///
+/// ```rust
+/// use wasmer::{AsStoreMut, Instance, TypedFunction};
+/// use wasmer_middlewares::metering::{is_exhaustion, set_remaining_points};
+///
+/// /// Calls `run`, granting another 1000 points every time they run out.
+/// fn run_to_completion(
+///     store: &mut impl AsStoreMut,
+///     instance: &Instance,
+///     run: &TypedFunction<(), ()>,
+/// ) {
+///     while let Err(error) = run.call(store) {
+///         assert!(is_exhaustion(store, instance, &error), "{error}");
+///         set_remaining_points(store, instance, 1000);
+///     }
+/// }
+/// ```
+pub fn is_exhaustion(ctx: &mut impl AsStoreMut, instance: &Instance, error: &RuntimeError) -> bool {
+    // The middleware sets the flag right before trapping with `unreachable`
+    error.clone().to_trap() == Some(TrapCode::UnreachableCodeReached)
+        && get_remaining_points(ctx, instance) == MeteringPoints::Exhausted
+}
+
+/// [`get_remaining_points`] and [`set_remaining_points`] as methods of a
+/// [`Store`][wasmer::Store], or anything else that is [`AsStoreMut`].
+///
+/// # Example
+///
+/// ```rust
+/// use wasmer::{Instance, Store};
+/// use wasmer_middlewares::metering::{MeteringExt, MeteringPoints};
+///
+/// fn top_up(store: &mut Store, instance: &Instance) {
+///     if store.get_remaining_points(instance) == MeteringPoints::Exhausted {
+///         store.set_remaining_points(instance, 1000);
+///     }
+/// }
+/// ```
+pub trait MeteringExt: AsStoreMut + Sized {
+    /// See [`get_remaining_points`].
+    fn get_remaining_points(&mut self, instance: &Instance) -> MeteringPoints {
+        get_remaining_points(self, instance)
+    }
+
+    /// See [`set_remaining_points`].
+    fn set_remaining_points(&mut self, instance: &Instance, points: u64) {
+        set_remaining_points(self, instance, points)
+    }
+}
+
+impl<T: AsStoreMut> MeteringExt for T {}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -618,4 +744,102 @@ mod tests {
             MeteringPoints::Exhausted
         );
     }
+
+    #[test]
+    fn locals_are_charged_on_entry() {
+        // `short_loop` declares two locals, `add_one` none
+        let metering = Arc::new(Metering::new(10_000, |_: &Operator| 0).with_local_cost(100));
+        let mut compiler_config = Cranelift::default();
+        compiler_config.push_middleware(metering);
+        let mut store = Store::new(EngineBuilder::new(compiler_config));
+        let module = Module::new(&store, bytecode()).unwrap();
+        let instance = Instance::new(&mut store, &module, &imports! {}).unwrap();
+
+        let short_loop: TypedFunction<(), ()> = instance
+            .exports
+            .get_function("short_loop")
+            .unwrap()
+            .typed(&store)
+            .unwrap();
+        short_loop.call(&mut store).unwrap();
+        assert_eq!(
+            get_remaining_points(&mut store, &instance),
+            MeteringPoints::Remaining(9_800)
+        );
+
+        let add_one: TypedFunction<i32, i32> = instance
+            .exports
+            .get_function("add_one")
+            .unwrap()
+            .typed(&store)
+            .unwrap();
+        add_one.call(&mut store, 1).unwrap();
+        assert_eq!(
+            get_remaining_points(&mut store, &instance),
+            MeteringPoints::Remaining(9_800)
+        );
+
+        // Not enough points to enter the function, so none of its code runs
+        set_remaining_points(&mut store, &instance, 150);
+        assert!(short_loop.call(&mut store).is_err());
+        assert_eq!(
+            get_remaining_points(&mut store, &instance),
+            MeteringPoints::Exhausted
+        );
+    }
+
+    #[test]
+    fn exhausted_instances_can_be_topped_up_and_resumed() {
+        // Counts up to 100 in a global, so a call that runs out of points
+        // can pick up where the previous one left off
+        let wasm = wat2wasm(
+            br#"(module
+            (global $count (export "count") (mut i32) (i32.const 0))
+            (func (export "count_to_100")
+                (block $done
+                    (loop $again
+                        (br_if $done (i32.ge_u (global.get $count) (i32.const 100)))
+                        (global.set $count (i32.add (global.get $count) (i32.const 1)))
+                        (br $again))))
+            (func (export "trap")
+                unreachable)
+        )"#,
+        )
+        .unwrap();
+        let metering = Arc::new(Metering::new(50, |_: &Operator| 1));
+        let mut compiler_config = Cranelift::default();
+        compiler_config.push_middleware(metering);
+        let mut store = Store::new(EngineBuilder::new(compiler_config));
+        let module = Module::new(&store, wasm).unwrap();
+        let instance = Instance::new(&mut store, &module, &imports! {}).unwrap();
+        let count_to_100: TypedFunction<(), ()> = instance
+            .exports
+            .get_typed_function(&store, "count_to_100")
+            .unwrap();
+        let count = instance.exports.get_global("count").unwrap();
+
+        let mut attempts = 0;
+        while let Err(error) = count_to_100.call(&mut store) {
+            assert!(is_exhaustion(&mut store, &instance, &error), "{error}");
+            assert_eq!(
+                store.get_remaining_points(&instance),
+                MeteringPoints::Exhausted
+            );
+            store.set_remaining_points(&instance, 50);
+            attempts += 1;
+        }
+
+        assert_eq!(count.get(&mut store).unwrap_i32(), 100);
+        assert!(attempts > 1, "{attempts}");
+        assert!(matches!(
+            store.get_remaining_points(&instance),
+            MeteringPoints::Remaining(_)
+        ));
+
+        // Other traps aren't mistaken for running out of points
+        let trap: TypedFunction<(), ()> =
+            instance.exports.get_typed_function(&store, "trap").unwrap();
+        let error = trap.call(&mut store).unwrap_err();
+        assert!(!is_exhaustion(&mut store, &instance, &error));
+    }
 }
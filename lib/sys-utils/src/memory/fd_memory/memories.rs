@@ -262,7 +262,7 @@ impl VMOwnedMemory {
         let offset_guard_bytes = style.offset_guard_size() as usize;
 
         let minimum_pages = match style {
-            MemoryStyle::Dynamic { .. } => memory.minimum,
+            MemoryStyle::Dynamic { .. } | MemoryStyle::External => memory.minimum,
             MemoryStyle::Static { bound, .. } => {
                 assert!(*bound >= memory.minimum);
                 *bound
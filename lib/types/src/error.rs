@@ -1,5 +1,6 @@
 //! The WebAssembly possible errors
-use crate::{ExternType, Pages};
+use crate::{ExternType, FunctionIndex, Pages};
+use core::fmt;
 use std::io;
 use thiserror::Error;
 
@@ -109,7 +110,7 @@ pub enum MemoryError {
 pub enum ImportError {
     /// Incompatible Import Type.
     /// This error occurs when the import types mismatch.
-    #[error("incompatible import type. Expected {0:?} but received {1:?}")]
+    #[error("incompatible import type. Expected {0} but received {1}")]
     IncompatibleType(ExternType, ExternType),
 
     /// Unknown Import.
@@ -132,7 +133,7 @@ pub enum PreInstantiationError {
     CpuFeature(String),
 }
 
-use crate::lib::std::string::String;
+use crate::lib::std::{string::String, vec::Vec};
 
 // Compilation Errors
 //
@@ -164,6 +165,13 @@ pub enum CompileError {
     #[cfg_attr(feature = "std", error("Feature {0} is not yet supported"))]
     UnsupportedFeature(String),
 
+    /// The module uses a Wasm feature that has been disabled.
+    #[cfg_attr(
+        feature = "std",
+        error("The module requires the {0} feature, which is disabled")
+    )]
+    DisabledFeature(String),
+
     /// The compiler cannot compile for the given target.
     /// This can refer to the OS, the chipset or any other aspect of the target system.
     #[cfg_attr(
@@ -179,6 +187,10 @@ pub enum CompileError {
     /// Middleware error occurred.
     #[cfg_attr(feature = "std", error("Middleware error: {0}"))]
     MiddlewareError(String),
+
+    /// The compiler can't translate an instruction used by the module.
+    #[cfg_attr(feature = "std", error("{0}"))]
+    UnsupportedOperator(Box<UnsupportedOperator>),
 }
 
 impl From<WasmError> for CompileError {
@@ -208,6 +220,78 @@ impl MiddlewareError {
     }
 }
 
+/// An instruction that a compiler can't translate, even though the module
+/// is valid and the features it uses are enabled.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnsupportedOperator {
+    /// The name of the compiler, e.g. `singlepass`.
+    pub compiler: String,
+    /// The name of the instruction, e.g. `V128Load`.
+    pub operator: String,
+    /// The feature the instruction belongs to, if it isn't part of the MVP.
+    pub feature: Option<String>,
+    /// The function the instruction is used in.
+    pub function_index: FunctionIndex,
+    /// The offset of the instruction in the module.
+    pub offset: usize,
+    /// The other compilers available to the embedder that can translate the
+    /// instruction.
+    ///
+    /// Compilers leave this empty, it is filled in by whoever knows which
+    /// compilers are around.
+    pub supported_by: Vec<String>,
+}
+
+impl UnsupportedOperator {
+    /// Describe `operator`, found at `offset` in the function with the given
+    /// index, as unsupported by `compiler`.
+    #[cfg(feature = "detect-wasm-features")]
+    pub fn new(
+        compiler: impl Into<String>,
+        operator: &wasmparser::Operator<'_>,
+        function_index: FunctionIndex,
+        offset: usize,
+    ) -> Self {
+        Self {
+            compiler: compiler.into(),
+            operator: crate::features::required::operator_name(operator).to_string(),
+            feature: crate::Features::required_by_operator(operator)
+                .enabled()
+                .next()
+                .map(String::from),
+            function_index,
+            offset,
+            supported_by: Vec::new(),
+        }
+    }
+}
+
+impl fmt::Display for UnsupportedOperator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "The {} compiler doesn't support the {} instruction",
+            self.compiler, self.operator
+        )?;
+        if let Some(feature) = &self.feature {
+            write!(f, " (from the {feature} feature)")?;
+        }
+        write!(
+            f,
+            " used by function {} at offset {:#x}",
+            self.function_index.as_u32(),
+            self.offset
+        )?;
+        if !self.supported_by.is_empty() {
+            write!(f, ", but {} does", self.supported_by.join(" and "))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for UnsupportedOperator {}
+
 /// A WebAssembly translation error.
 ///
 /// When a WebAssembly function can't be translated, one of these error codes will be returned
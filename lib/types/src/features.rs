@@ -4,6 +4,9 @@ use serde::{Deserialize, Serialize};
 #[cfg(feature = "detect-wasm-features")]
 use wasmparser::{Parser, Payload, Validator, WasmFeatures};
 
+#[cfg(feature = "detect-wasm-features")]
+pub use required::FeatureDetector;
+
 /// Controls which experimental features will be enabled.
 /// Features usually have a corresponding [WebAssembly proposal].
 ///
@@ -303,6 +306,86 @@ impl Features {
             && (!required.extended_const || self.extended_const)
     }
 
+    /// The name of every feature in this set, along with whether it is
+    /// enabled.
+    pub fn iter(&self) -> impl Iterator<Item = (&'static str, bool)> {
+        // Written this way to cause compile errors when new features are added.
+        let Self {
+            threads,
+            reference_types,
+            simd,
+            bulk_memory,
+            multi_value,
+            tail_call,
+            module_linking,
+            multi_memory,
+            memory64,
+            exceptions,
+            relaxed_simd,
+            extended_const,
+        } = self.clone();
+
+        [
+            ("threads", threads),
+            ("reference-types", reference_types),
+            ("simd", simd),
+            ("bulk-memory", bulk_memory),
+            ("multi-value", multi_value),
+            ("tail-call", tail_call),
+            ("module-linking", module_linking),
+            ("multi-memory", multi_memory),
+            ("memory64", memory64),
+            ("exceptions", exceptions),
+            ("relaxed-simd", relaxed_simd),
+            ("extended-const", extended_const),
+        ]
+        .into_iter()
+    }
+
+    /// The names of the features enabled in this set.
+    pub fn enabled(&self) -> impl Iterator<Item = &'static str> {
+        self.iter()
+            .filter_map(|(name, enabled)| enabled.then_some(name))
+    }
+
+    /// The names of the features in `required` that are not enabled in this
+    /// set.
+    pub fn missing<'a>(&'a self, required: &'a Self) -> impl Iterator<Item = &'static str> + 'a {
+        required
+            .iter()
+            .zip(self.iter())
+            .filter_map(|((name, required), (_, enabled))| (required && !enabled).then_some(name))
+    }
+
+    /// The names of the features enabled both in this set and in `other`.
+    pub fn shared<'a>(&'a self, other: &'a Self) -> impl Iterator<Item = &'static str> + 'a {
+        self.iter()
+            .zip(other.iter())
+            .filter_map(|((name, this), (_, other))| (this && other).then_some(name))
+    }
+
+    #[cfg(feature = "detect-wasm-features")]
+    /// Determines the WebAssembly features a module binary actually uses.
+    ///
+    /// Unlike [`Features::detect_from_wasm()`], this walks every section and
+    /// instruction in the module and records the proposals they belong to, so
+    /// the result only contains the features the module can't be run
+    /// without.
+    ///
+    /// Note that this doesn't validate the module. Use a [`FeatureDetector`]
+    /// to find out while validating it instead.
+    pub fn required_by_wasm(wasm_bytes: &[u8]) -> Result<Self, wasmparser::BinaryReaderError> {
+        required::required_features(wasm_bytes)
+    }
+
+    #[cfg(feature = "detect-wasm-features")]
+    /// Determines the WebAssembly features a single instruction belongs to.
+    pub fn required_by_operator(operator: &wasmparser::Operator<'_>) -> Self {
+        let mut features = Self::none();
+        required::operator(&mut features, operator);
+        features
+    }
+
     #[cfg(feature = "detect-wasm-features")]
     /// Detects required WebAssembly features from a module binary.
     ///
@@ -462,6 +545,306 @@ impl Features {
     }
 }
 
+#[cfg(feature = "detect-wasm-features")]
+pub(crate) mod required {
+    use wasmparser::{
+        BinaryReaderError, BlockType, CompositeInnerType, ConstExpr, DataKind, ElementItems,
+        ElementKind, FuncType, MemoryType, Operator, Parser, Payload, RefType, TableInit,
+        TableType, TypeRef, ValType,
+    };
+
+    use super::Features;
+
+    pub(super) fn required_features(wasm_bytes: &[u8]) -> Result<Features, BinaryReaderError> {
+        let mut detector = FeatureDetector::new();
+        for payload in Parser::new(0).parse_all(wasm_bytes) {
+            let payload = payload?;
+            detector.payload(&payload)?;
+            if let Payload::CodeSectionEntry(body) = payload {
+                for local in body.get_locals_reader()? {
+                    let (_, ty) = local?;
+                    detector.local(ty);
+                }
+                let mut operators = body.get_operators_reader()?;
+                while !operators.eof() {
+                    detector.operator(&operators.read()?);
+                }
+            }
+        }
+        Ok(detector.finish())
+    }
+
+    /// Records the WebAssembly features a module uses while something else
+    /// (usually a validator) walks through it.
+    ///
+    /// Sections are recorded with [`FeatureDetector::payload()`]. Function
+    /// bodies are left to the caller, who passes each local declaration and
+    /// instruction on to [`FeatureDetector::local()`] and
+    /// [`FeatureDetector::operator()`] as it reads them, so they only have to
+    /// be decoded once.
+    #[derive(Debug, Clone)]
+    pub struct FeatureDetector {
+        features: Features,
+        tables: u32,
+        memories: u32,
+    }
+
+    impl FeatureDetector {
+        /// Creates a detector that hasn't seen any feature yet.
+        pub fn new() -> Self {
+            Self {
+                features: Features::none(),
+                tables: 0,
+                memories: 0,
+            }
+        }
+
+        /// Records the features used by a section of the module.
+        ///
+        /// Code section entries are ignored, see [`FeatureDetector`].
+        pub fn payload(&mut self, payload: &Payload<'_>) -> Result<(), BinaryReaderError> {
+            let features = &mut self.features;
+            match payload {
+                Payload::TypeSection(types) => {
+                    for group in types.clone() {
+                        for ty in group?.into_types() {
+                            if let CompositeInnerType::Func(func) = &ty.composite_type.inner {
+                                func_type(features, func);
+                            }
+                        }
+                    }
+                }
+                Payload::ImportSection(imports) => {
+                    for import in imports.clone() {
+                        match import?.ty {
+                            TypeRef::Func(_) => {}
+                            TypeRef::Table(ty) => {
+                                self.tables += 1;
+                                table_type(features, &ty);
+                            }
+                            TypeRef::Memory(ty) => {
+                                self.memories += 1;
+                                memory_type(features, &ty);
+                            }
+                            TypeRef::Global(ty) => val_type(features, ty.content_type),
+                            TypeRef::Tag(_) => features.exceptions = true,
+                        }
+                    }
+                }
+                Payload::TableSection(section) => {
+                    for table in section.clone() {
+                        let table = table?;
+                        self.tables += 1;
+                        table_type(features, &table.ty);
+                        if let TableInit::Expr(init) = &table.init {
+                            features.reference_types = true;
+                            const_expr(features, init)?;
+                        }
+                    }
+                }
+                Payload::MemorySection(section) => {
+                    for ty in section.clone() {
+                        self.memories += 1;
+                        memory_type(features, &ty?);
+                    }
+                }
+                Payload::TagSection(_) => features.exceptions = true,
+                Payload::GlobalSection(section) => {
+                    for global in section.clone() {
+                        let global = global?;
+                        val_type(features, global.ty.content_type);
+                        const_expr(features, &global.init_expr)?;
+                    }
+                }
+                Payload::ElementSection(section) => {
+                    for element in section.clone() {
+                        let element = element?;
+                        match &element.kind {
+                            ElementKind::Passive => features.bulk_memory = true,
+                            ElementKind::Declared => features.reference_types = true,
+                            ElementKind::Active {
+                                table_index,
+                                offset_expr,
+                            } => {
+                                if table_index.is_some_and(|index| index != 0) {
+                                    features.reference_types = true;
+                                }
+                                const_expr(features, offset_expr)?;
+                            }
+                        }
+                        if let ElementItems::Expressions(ty, exprs) = element.items {
+                            if ty != RefType::FUNCREF {
+                                features.reference_types = true;
+                            }
+                            for expr in exprs {
+                                const_expr(features, &expr?)?;
+                            }
+                        }
+                    }
+                }
+                Payload::DataCountSection { .. } => features.bulk_memory = true,
+                Payload::DataSection(section) => {
+                    for data in section.clone() {
+                        match data?.kind {
+                            DataKind::Passive => features.bulk_memory = true,
+                            DataKind::Active {
+                                memory_index,
+                                offset_expr,
+                            } => {
+                                if memory_index != 0 {
+                                    features.multi_memory = true;
+                                }
+                                const_expr(features, &offset_expr)?;
+                            }
+                        }
+                    }
+                }
+                _ => {}
+            }
+            Ok(())
+        }
+
+        /// Records the type of a local declared by a function body.
+        pub fn local(&mut self, ty: ValType) {
+            val_type(&mut self.features, ty);
+        }
+
+        /// Records an instruction of a function body.
+        pub fn operator(&mut self, op: &Operator<'_>) {
+            operator(&mut self.features, op);
+        }
+
+        /// The features used by everything that has been recorded.
+        pub fn finish(mut self) -> Features {
+            // Referring to any table or memory other than the first one
+            // requires the module to have more than one, so we don't need to
+            // look at instruction immediates for these.
+            if self.tables > 1 {
+                self.features.reference_types = true;
+            }
+            if self.memories > 1 {
+                self.features.multi_memory = true;
+            }
+            self.features
+        }
+    }
+
+    impl Default for FeatureDetector {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+
+    fn val_type(features: &mut Features, ty: ValType) {
+        match ty {
+            ValType::V128 => features.simd = true,
+            ValType::Ref(_) => features.reference_types = true,
+            ValType::I32 | ValType::I64 | ValType::F32 | ValType::F64 => {}
+        }
+    }
+
+    fn func_type(features: &mut Features, ty: &FuncType) {
+        if ty.results().len() > 1 {
+            features.multi_value = true;
+        }
+        for ty in ty.params().iter().chain(ty.results()) {
+            val_type(features, *ty);
+        }
+    }
+
+    fn table_type(features: &mut Features, ty: &TableType) {
+        if ty.element_type != RefType::FUNCREF {
+            features.reference_types = true;
+        }
+        if ty.table64 {
+            features.memory64 = true;
+        }
+    }
+
+    fn memory_type(features: &mut Features, ty: &MemoryType) {
+        if ty.shared {
+            features.threads = true;
+        }
+        if ty.memory64 {
+            features.memory64 = true;
+        }
+    }
+
+    fn const_expr(features: &mut Features, expr: &ConstExpr<'_>) -> Result<(), BinaryReaderError> {
+        let mut operators = expr.get_operators_reader();
+        while !operators.eof() {
+            let op = operators.read()?;
+            if matches!(
+                op,
+                Operator::I32Add
+                    | Operator::I32Sub
+                    | Operator::I32Mul
+                    | Operator::I64Add
+                    | Operator::I64Sub
+                    | Operator::I64Mul
+            ) {
+                features.extended_const = true;
+            }
+            operator(features, &op);
+        }
+        Ok(())
+    }
+
+    /// The name of the instruction, e.g. `V128Load`.
+    pub(crate) fn operator_name(op: &Operator<'_>) -> &'static str {
+        macro_rules! name {
+            ($( @$proposal:ident $op:ident $({ $($arg:ident: $argty:ty),* })? => $visit:ident ($($ann:tt)*))*) => {
+                match op {
+                    $( Operator::$op { .. } => stringify!($op), )*
+                    _ => "unknown",
+                }
+            };
+        }
+
+        wasmparser::for_each_operator!(name)
+    }
+
+    pub(super) fn operator(features: &mut Features, op: &Operator<'_>) {
+        macro_rules! proposal {
+            ($( @$proposal:ident $op:ident $({ $($arg:ident: $argty:ty),* })? => $visit:ident ($($ann:tt)*))*) => {
+                match op {
+                    $( Operator::$op { .. } => stringify!($proposal), )*
+                    _ => "unknown",
+                }
+            };
+        }
+
+        match wasmparser::for_each_operator!(proposal) {
+            "threads" => features.threads = true,
+            "simd" => features.simd = true,
+            "relaxed_simd" => features.relaxed_simd = true,
+            "bulk_memory" => features.bulk_memory = true,
+            "reference_types" => features.reference_types = true,
+            "tail_call" => features.tail_call = true,
+            "exceptions" | "legacy_exceptions" => features.exceptions = true,
+            _ => {}
+        }
+
+        match op {
+            Operator::Block { blockty }
+            | Operator::Loop { blockty }
+            | Operator::If { blockty }
+            | Operator::Try { blockty } => block_type(features, blockty),
+            Operator::TryTable { try_table } => block_type(features, &try_table.ty),
+            _ => {}
+        }
+    }
+
+    fn block_type(features: &mut Features, ty: &BlockType) {
+        match ty {
+            BlockType::Empty => {}
+            BlockType::Type(ty) => val_type(features, *ty),
+            // Blocks with parameters or multiple results
+            BlockType::FuncType(_) => features.multi_value = true,
+        }
+    }
+}
+
 impl Default for Features {
     fn default() -> Self {
         Self::new()
@@ -576,4 +959,44 @@ mod test_features {
         features.memory64(true);
         assert!(features.memory64);
     }
+
+    #[test]
+    fn missing_features() {
+        let mut enabled = Features::none();
+        enabled.simd(true);
+        let mut required = Features::none();
+        required.simd(true).threads(true).tail_call(true);
+
+        let missing: Vec<_> = enabled.missing(&required).collect();
+        assert_eq!(missing, ["threads", "tail-call"]);
+        assert_eq!(enabled.missing(&enabled).count(), 0);
+        assert_eq!(Features::all().missing(&required).count(), 0);
+    }
+
+    #[test]
+    fn shared_features() {
+        let mut disabled = Features::none();
+        disabled.simd(true).threads(true);
+        let mut required = Features::none();
+        required.simd(true).tail_call(true);
+
+        let shared: Vec<_> = disabled.shared(&required).collect();
+        assert_eq!(shared, ["simd"]);
+        assert_eq!(Features::none().shared(&required).count(), 0);
+    }
+
+    #[test]
+    fn enabled_feature_names() {
+        let names: Vec<_> = Features::default().enabled().collect();
+        assert_eq!(
+            names,
+            [
+                "threads",
+                "reference-types",
+                "simd",
+                "bulk-memory",
+                "multi-value"
+            ]
+        );
+    }
 }
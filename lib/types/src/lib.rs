@@ -60,6 +60,7 @@ mod module;
 mod module_hash;
 mod serialize;
 mod stack;
+mod stats;
 mod store_id;
 mod table;
 pub mod target;
@@ -72,11 +73,14 @@ mod vmoffsets;
 
 pub use error::{
     CompileError, DeserializeError, ImportError, MemoryError, MiddlewareError,
-    ParseCpuFeatureError, PreInstantiationError, SerializeError, WasmError, WasmResult,
+    ParseCpuFeatureError, PreInstantiationError, SerializeError, UnsupportedOperator, WasmError,
+    WasmResult,
 };
 
 /// The entity module, with common helpers for Rust structures
 pub mod entity;
+#[cfg(feature = "detect-wasm-features")]
+pub use crate::features::FeatureDetector;
 pub use crate::features::Features;
 pub use crate::indexes::{
     CustomSectionIndex, DataIndex, ElemIndex, ExportIndex, FunctionIndex, GlobalIndex, ImportIndex,
@@ -92,8 +96,8 @@ pub use crate::memory::{Memory32, Memory64, MemorySize};
 pub use crate::module::{ExportsIterator, ImportKey, ImportsIterator, ModuleInfo};
 pub use crate::module_hash::{HashAlgorithm, ModuleHash};
 pub use crate::types::{
-    ExportType, ExternType, FunctionType, GlobalInit, GlobalType, ImportType, MemoryType,
-    Mutability, TableType, TagKind, TagType, Type, V128,
+    ExportType, ExternType, FunctionType, GlobalInit, GlobalType, ImportLimits, ImportType,
+    MemoryType, Mutability, TableType, TagKind, TagType, Type, V128,
 };
 pub use crate::units::{
     Bytes, PageCountOutOfRange, Pages, WASM_MAX_PAGES, WASM_MIN_PAGES, WASM_PAGE_SIZE,
@@ -101,11 +105,12 @@ pub use crate::units::{
 pub use value::{RawValue, ValueType};
 
 pub use crate::libcalls::LibCall;
-pub use crate::memory::MemoryStyle;
+pub use crate::memory::{MemoryGrowthPolicy, MemoryStyle};
 pub use crate::table::TableStyle;
 pub use serialize::MetadataHeader;
+pub use stats::{AllocationStats, ArtifactStats};
 // TODO: OnCalledAction is needed for asyncify. It will be refactored with https://github.com/wasmerio/wasmer/issues/3451
-pub use crate::stack::{FrameInfo, SourceLoc, TrapInformation};
+pub use crate::stack::{FrameInfo, SourceLoc, SourceLocation, TrapInformation};
 pub use crate::store_id::StoreId;
 pub use crate::trapcode::{OnCalledAction, TrapCode};
 pub use crate::utils::is_wasm;
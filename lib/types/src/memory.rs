@@ -32,6 +32,11 @@ pub enum MemoryStyle {
         /// to optimize loads and stores with constant offsets.
         offset_guard_size: u64,
     },
+    /// The memory is backed by a buffer owned by the embedder.
+    ///
+    /// There are no guard pages after the end of the buffer, so every access
+    /// is checked against the current length of the memory.
+    External,
 }
 
 impl MemoryStyle {
@@ -42,8 +47,46 @@ impl MemoryStyle {
             Self::Static {
                 offset_guard_size, ..
             } => *offset_guard_size,
+            Self::External => 0,
         }
     }
+
+    /// Whether compiled code needs to check every access against the
+    /// current length of the memory.
+    ///
+    /// Checks can only be left out when the reserved address space (the
+    /// static bound plus the offset guard) covers every address a 32-bit
+    /// load or store can reach, so that any out-of-bounds access is
+    /// guaranteed to land on a guard page.
+    pub fn needs_bounds_checks(&self) -> bool {
+        /// The largest address a 32-bit access can touch: the biggest
+        /// address a wasm32 index and offset can add up to, plus the size
+        /// of the widest value.
+        const MAX_ACCESS_END: u64 = (1 << 32) + 16;
+
+        match self {
+            Self::Static {
+                bound,
+                offset_guard_size,
+            } => {
+                let reserved = (bound.bytes().0 as u64).saturating_add(*offset_guard_size);
+                reserved < MAX_ACCESS_END
+            }
+            Self::Dynamic { .. } | Self::External => true,
+        }
+    }
+}
+
+/// What happens when a memory backed by an embedder's buffer is asked to
+/// grow (see [`MemoryStyle::External`]).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum MemoryGrowthPolicy {
+    /// The memory can never grow past the size of the host buffer.
+    #[default]
+    Disabled,
+    /// The contents of the memory are copied into a new allocation owned by
+    /// the runtime. From then on, the memory no longer aliases the host buffer.
+    Remap,
 }
 
 /// Trait for the `Memory32` and `Memory64` marker types.
@@ -107,7 +150,7 @@ pub unsafe trait MemorySize: Copy {
 }
 
 /// Marker trait for 32-bit memories.
-#[derive(Clone, Copy)]
+#[derive(Debug, Clone, Copy)]
 pub struct Memory32;
 unsafe impl MemorySize for Memory32 {
     type Offset = u32;
@@ -126,7 +169,7 @@ unsafe impl MemorySize for Memory32 {
 }
 
 /// Marker trait for 64-bit memories.
-#[derive(Clone, Copy)]
+#[derive(Debug, Clone, Copy)]
 pub struct Memory64;
 unsafe impl MemorySize for Memory64 {
     type Offset = u64;
@@ -150,6 +150,10 @@ pub struct ModuleInfo {
     /// WebAssembly function names.
     pub function_names: HashMap<FunctionIndex, String>,
 
+    /// Names of the parameters and locals of WebAssembly functions, by the
+    /// index of the local within its function.
+    pub local_names: HashMap<FunctionIndex, BTreeMap<u32, String>>,
+
     /// WebAssembly function signatures.
     pub signatures: PrimaryMap<SignatureIndex, FunctionType>,
 
@@ -168,8 +172,11 @@ pub struct ModuleInfo {
     /// WebAssembly tag variables (imported and local).
     pub tags: PrimaryMap<TagIndex, SignatureIndex>,
 
-    /// Custom sections in the module.
-    pub custom_sections: IndexMap<String, CustomSectionIndex>,
+    /// Custom sections in the module, by name.
+    ///
+    /// A name can be used by more than one section, so each name maps to
+    /// all of its sections, in the order they appear in the module.
+    pub custom_sections: IndexMap<String, Vec<CustomSectionIndex>>,
 
     /// The data for each CustomSection in the module.
     pub custom_sections_data: PrimaryMap<CustomSectionIndex, Box<[u8]>>,
@@ -204,13 +211,14 @@ pub struct ArchivableModuleInfo {
     passive_data: BTreeMap<DataIndex, Box<[u8]>>,
     global_initializers: PrimaryMap<LocalGlobalIndex, GlobalInit>,
     function_names: BTreeMap<FunctionIndex, String>,
+    local_names: BTreeMap<FunctionIndex, BTreeMap<u32, String>>,
     signatures: PrimaryMap<SignatureIndex, FunctionType>,
     functions: PrimaryMap<FunctionIndex, SignatureIndex>,
     tables: PrimaryMap<TableIndex, TableType>,
     memories: PrimaryMap<MemoryIndex, MemoryType>,
     globals: PrimaryMap<GlobalIndex, GlobalType>,
     tags: PrimaryMap<TagIndex, SignatureIndex>,
-    custom_sections: IndexMap<String, CustomSectionIndex>,
+    custom_sections: IndexMap<String, Vec<CustomSectionIndex>>,
     custom_sections_data: PrimaryMap<CustomSectionIndex, Box<[u8]>>,
     num_imported_functions: usize,
     num_imported_tables: usize,
@@ -232,6 +240,7 @@ impl From<ModuleInfo> for ArchivableModuleInfo {
             passive_data: it.passive_data.into_iter().collect(),
             global_initializers: it.global_initializers,
             function_names: it.function_names.into_iter().collect(),
+            local_names: it.local_names.into_iter().collect(),
             signatures: it.signatures,
             functions: it.functions,
             tables: it.tables,
@@ -263,6 +272,7 @@ impl From<ArchivableModuleInfo> for ModuleInfo {
             passive_data: it.passive_data.into_iter().collect(),
             global_initializers: it.global_initializers,
             function_names: it.function_names.into_iter().collect(),
+            local_names: it.local_names.into_iter().collect(),
             signatures: it.signatures,
             functions: it.functions,
             tables: it.tables,
@@ -327,6 +337,7 @@ impl PartialEq for ModuleInfo {
             && self.passive_data == other.passive_data
             && self.global_initializers == other.global_initializers
             && self.function_names == other.function_names
+            && self.local_names == other.local_names
             && self.signatures == other.signatures
             && self.functions == other.functions
             && self.tables == other.tables
@@ -457,16 +468,19 @@ impl ModuleInfo {
     ) -> Box<impl Iterator<Item = Box<[u8]>> + 'a> {
         Box::new(
             self.custom_sections
-                .iter()
-                .filter_map(move |(section_name, section_index)| {
-                    if name != section_name {
-                        return None;
-                    }
-                    Some(self.custom_sections_data[*section_index].clone())
-                }),
+                .get(name)
+                .into_iter()
+                .flatten()
+                .map(move |section_index| self.custom_sections_data[*section_index].clone()),
         )
     }
 
+    /// Get the distinct names of the custom sections of the module, in the
+    /// order they first appear.
+    pub fn custom_section_names(&self) -> impl Iterator<Item = &str> + '_ {
+        self.custom_sections.keys().map(String::as_str)
+    }
+
     /// Convert a `LocalFunctionIndex` into a `FunctionIndex`.
     pub fn func_index(&self, local_func: LocalFunctionIndex) -> FunctionIndex {
         FunctionIndex::new(self.num_imported_functions + local_func.index())
@@ -1,4 +1,44 @@
+use crate::lib::std::fmt;
 use crate::SourceLoc;
+use std::collections::BTreeMap;
+
+/// A location in the original source code of a module, as described by
+/// its source map.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SourceLocation {
+    file: String,
+    line: u32,
+    column: u32,
+}
+
+impl SourceLocation {
+    /// Creates a new [`SourceLocation`] from a file name and a 1-based line
+    /// and column.
+    pub fn new(file: String, line: u32, column: u32) -> Self {
+        Self { file, line, column }
+    }
+
+    /// The source file, as named by the source map.
+    pub fn file(&self) -> &str {
+        &self.file
+    }
+
+    /// The line in the source file, starting at 1.
+    pub fn line(&self) -> u32 {
+        self.line
+    }
+
+    /// The column in the source file, starting at 1.
+    pub fn column(&self) -> u32 {
+        self.column
+    }
+}
+
+impl fmt::Display for SourceLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}:{}", self.file, self.line, self.column)
+    }
+}
 
 /// Description of a frame in a backtrace.
 ///
@@ -16,6 +56,10 @@ pub struct FrameInfo {
     func_start: SourceLoc,
     /// The source location of the instruction
     instr: SourceLoc,
+    /// Where the instruction comes from in the original source code
+    source_location: Option<SourceLocation>,
+    /// The names of the function's parameters and locals, by index
+    local_names: BTreeMap<u32, String>,
 }
 
 impl FrameInfo {
@@ -33,9 +77,25 @@ impl FrameInfo {
             function_name,
             func_start,
             instr,
+            source_location: None,
+            local_names: BTreeMap::new(),
         }
     }
 
+    /// Attaches the location in the original source code that this frame's
+    /// instruction was compiled from.
+    pub fn with_source_location(mut self, source_location: Option<SourceLocation>) -> Self {
+        self.source_location = source_location;
+        self
+    }
+
+    /// Attaches the names the module's `name` section gives to the
+    /// parameters and locals of this frame's function.
+    pub fn with_local_names(mut self, local_names: BTreeMap<u32, String>) -> Self {
+        self.local_names = local_names;
+        self
+    }
+
     /// Returns the WebAssembly function index for this frame.
     ///
     /// This function index is the index in the function index space of the
@@ -75,6 +135,14 @@ impl FrameInfo {
         self.function_name.as_deref()
     }
 
+    /// Returns the name of a parameter or local of this frame's function,
+    /// if the module's `name` section gives it one.
+    ///
+    /// Locals are indexed as in WebAssembly, with the parameters first.
+    pub fn local_name(&self, index: u32) -> Option<&str> {
+        self.local_names.get(&index).map(String::as_str)
+    }
+
     /// Returns the offset within the original wasm module this frame's program
     /// counter was at.
     ///
@@ -93,4 +161,11 @@ impl FrameInfo {
     pub fn func_offset(&self) -> usize {
         (self.instr.bits() - self.func_start.bits()) as usize
     }
+
+    /// Returns the location in the original source code (e.g. AssemblyScript
+    /// or Go) that this frame's instruction was compiled from, when the
+    /// module has a source map that describes it.
+    pub fn source_location(&self) -> Option<&SourceLocation> {
+        self.source_location.as_ref()
+    }
 }
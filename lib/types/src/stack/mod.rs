@@ -4,6 +4,6 @@ mod frame;
 mod sourceloc;
 mod trap;
 
-pub use frame::FrameInfo;
+pub use frame::{FrameInfo, SourceLocation};
 pub use sourceloc::SourceLoc;
 pub use trap::TrapInformation;
@@ -0,0 +1,44 @@
+//! Statistics about compiled code and the memory it occupies.
+
+#[cfg(feature = "enable-serde")]
+use serde::{Deserialize, Serialize};
+
+/// Statistics about the code generated for a single module.
+///
+/// These are recorded when the artifact is created, so a module deserialized
+/// from a cache reports the same numbers as the one it was compiled from.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "artifact-size", derive(loupe::MemoryUsage))]
+#[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
+pub struct ArtifactStats {
+    /// The number of functions defined (not imported) by the module.
+    pub functions: usize,
+    /// The size of the machine code generated for those functions, in bytes.
+    pub code_size: usize,
+    /// The number of function call and dynamic function trampolines.
+    pub trampolines: usize,
+    /// The size of the machine code generated for the trampolines, in bytes.
+    pub trampolines_size: usize,
+    /// The number of relocations applied to the functions and custom sections
+    /// when the code is loaded.
+    pub relocations: usize,
+    /// The size of the memory mapping holding the code and data of the
+    /// artifact, or `None` if it wasn't loaded (e.g. because it was compiled
+    /// for another target).
+    pub mapped_size: Option<usize>,
+    /// How much of the mapping is executable, in bytes.
+    pub executable_size: Option<usize>,
+}
+
+/// Statistics about the memory an engine allocated for the code of all the
+/// artifacts it loaded.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
+pub struct AllocationStats {
+    /// The number of artifacts whose code was loaded.
+    pub artifacts: usize,
+    /// The total size of the memory mappings holding code and data, in bytes.
+    pub mapped_size: usize,
+    /// How much of those mappings is executable, in bytes.
+    pub executable_size: usize,
+}
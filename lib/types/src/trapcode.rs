@@ -63,6 +63,10 @@ pub enum TrapCode {
 
     /// An exception was thrown but it was left uncaught.
     UncaughtException = 11,
+
+    /// A call made with a deadline was still running when the deadline
+    /// passed.
+    DeadlineExceeded = 12,
 }
 
 impl TrapCode {
@@ -81,6 +85,7 @@ impl TrapCode {
             Self::UnreachableCodeReached => "unreachable",
             Self::UnalignedAtomic => "unaligned atomic access",
             Self::UncaughtException => "uncaught exception",
+            Self::DeadlineExceeded => "deadline exceeded",
         }
     }
 }
@@ -100,6 +105,7 @@ impl Display for TrapCode {
             Self::UnreachableCodeReached => "unreachable",
             Self::UnalignedAtomic => "unalign_atom",
             Self::UncaughtException => "uncaught_exception",
+            Self::DeadlineExceeded => "deadline",
         };
         f.write_str(identifier)
     }
@@ -121,6 +127,7 @@ impl FromStr for TrapCode {
             "bad_toint" => Ok(Self::BadConversionToInteger),
             "unreachable" => Ok(Self::UnreachableCodeReached),
             "unalign_atom" => Ok(Self::UnalignedAtomic),
+            "deadline" => Ok(Self::DeadlineExceeded),
             _ => Err(()),
         }
     }
@@ -144,7 +151,7 @@ mod tests {
     use super::*;
 
     // Everything but user-defined codes.
-    const CODES: [TrapCode; 11] = [
+    const CODES: [TrapCode; 12] = [
         TrapCode::StackOverflow,
         TrapCode::HeapAccessOutOfBounds,
         TrapCode::HeapMisaligned,
@@ -156,6 +163,7 @@ mod tests {
         TrapCode::BadConversionToInteger,
         TrapCode::UnreachableCodeReached,
         TrapCode::UnalignedAtomic,
+        TrapCode::DeadlineExceeded,
     ];
 
     #[test]
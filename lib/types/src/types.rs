@@ -147,57 +147,74 @@ fn is_global_compatible(exported: GlobalType, imported: GlobalType) -> bool {
     exported_ty == imported_ty && imported_mutability == exported_mutability
 }
 
-fn is_table_element_type_compatible(exported_type: Type, imported_type: Type) -> bool {
-    match exported_type {
-        Type::FuncRef => true,
-        _ => imported_type == exported_type,
-    }
+/// How the limits of a memory or table provided for an import are matched
+/// against the limits the importing module declares.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum ImportLimits {
+    /// The rules of the specification: the provided memory or table must
+    /// currently be at least as large as the declared minimum and, if the
+    /// module declares a maximum, have a maximum that is no larger.
+    #[default]
+    Strict,
+    /// Like [`ImportLimits::Strict`], except that a provided memory or
+    /// table may have no maximum, or a larger one than declared. The module
+    /// then sees it grow past its declared maximum, which is safe as its
+    /// code checks accesses against the actual size. The minimum, element
+    /// type and sharing are still checked strictly.
+    Relaxed,
+}
+
+/// Whether the limits `exported_minimum..exported_maximum` of a provided
+/// memory or table, currently `runtime_size` large, fit the declared ones.
+fn are_limits_compatible(
+    exported_minimum: u32,
+    exported_maximum: Option<u32>,
+    imported_minimum: u32,
+    imported_maximum: Option<u32>,
+    runtime_size: Option<u32>,
+    limits: ImportLimits,
+) -> bool {
+    let maximum_fits = match (imported_maximum, exported_maximum, limits) {
+        (None, _, _) | (_, _, ImportLimits::Relaxed) => true,
+        (Some(imported), Some(exported), ImportLimits::Strict) => exported <= imported,
+        (Some(_), None, ImportLimits::Strict) => false,
+    };
+
+    imported_minimum <= runtime_size.unwrap_or(exported_minimum) && maximum_fits
 }
 
 fn is_table_compatible(
     exported: &TableType,
     imported: &TableType,
     imported_runtime_size: Option<u32>,
+    limits: ImportLimits,
 ) -> bool {
-    let TableType {
-        ty: exported_ty,
-        minimum: exported_minimum,
-        maximum: exported_maximum,
-    } = exported;
-    let TableType {
-        ty: imported_ty,
-        minimum: imported_minimum,
-        maximum: imported_maximum,
-    } = imported;
-
-    is_table_element_type_compatible(*exported_ty, *imported_ty)
-        && *imported_minimum <= imported_runtime_size.unwrap_or(*exported_minimum)
-        && (imported_maximum.is_none()
-            || (!exported_maximum.is_none()
-                && imported_maximum.unwrap() >= exported_maximum.unwrap()))
+    exported.ty == imported.ty
+        && are_limits_compatible(
+            exported.minimum,
+            exported.maximum,
+            imported.minimum,
+            imported.maximum,
+            imported_runtime_size,
+            limits,
+        )
 }
 
 fn is_memory_compatible(
     exported: &MemoryType,
     imported: &MemoryType,
     imported_runtime_size: Option<u32>,
+    limits: ImportLimits,
 ) -> bool {
-    let MemoryType {
-        minimum: exported_minimum,
-        maximum: exported_maximum,
-        shared: exported_shared,
-    } = exported;
-    let MemoryType {
-        minimum: imported_minimum,
-        maximum: imported_maximum,
-        shared: imported_shared,
-    } = imported;
-
-    imported_minimum.0 <= imported_runtime_size.unwrap_or(exported_minimum.0)
-        && (imported_maximum.is_none()
-            || (!exported_maximum.is_none()
-                && imported_maximum.unwrap() >= exported_maximum.unwrap()))
-        && exported_shared == imported_shared
+    exported.shared == imported.shared
+        && are_limits_compatible(
+            exported.minimum.0,
+            exported.maximum.map(|pages| pages.0),
+            imported.minimum.0,
+            imported.maximum.map(|pages| pages.0),
+            imported_runtime_size,
+            limits,
+        )
 }
 
 macro_rules! accessors {
@@ -233,11 +250,24 @@ impl ExternType {
     }
     /// Check if two externs are compatible
     pub fn is_compatible_with(&self, other: &Self, runtime_size: Option<u32>) -> bool {
+        self.is_compatible_with_limits(other, runtime_size, ImportLimits::Strict)
+    }
+
+    /// Check if this extern can be provided for an import of type `other`,
+    /// matching the limits of memories and tables according to `limits`.
+    ///
+    /// `runtime_size` is the current size of the provided memory or table.
+    pub fn is_compatible_with_limits(
+        &self,
+        other: &Self,
+        runtime_size: Option<u32>,
+        limits: ImportLimits,
+    ) -> bool {
         match (self, other) {
             (Self::Function(a), Self::Function(b)) => a == b,
             (Self::Global(a), Self::Global(b)) => is_global_compatible(*a, *b),
-            (Self::Table(a), Self::Table(b)) => is_table_compatible(a, b, runtime_size),
-            (Self::Memory(a), Self::Memory(b)) => is_memory_compatible(a, b, runtime_size),
+            (Self::Table(a), Self::Table(b)) => is_table_compatible(a, b, runtime_size, limits),
+            (Self::Memory(a), Self::Memory(b)) => is_memory_compatible(a, b, runtime_size, limits),
             (Self::Tag(a), Self::Tag(b)) => a == b,
             // The rest of possibilities, are not compatible
             _ => false,
@@ -245,6 +275,18 @@ impl ExternType {
     }
 }
 
+impl fmt::Display for ExternType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Function(ty) => write!(f, "function {ty}"),
+            Self::Global(ty) => write!(f, "global {ty}"),
+            Self::Table(ty) => write!(f, "table {ty}"),
+            Self::Memory(ty) => write!(f, "memory {ty}"),
+            Self::Tag(ty) => write!(f, "tag {ty}"),
+        }
+    }
+}
+
 // TODO: `shrink_to_fit` these or change it to `Box<[Type]>` if not using
 // Cow or something else
 /// The signature of a function that is either implemented
@@ -715,4 +757,81 @@ mod tests {
         assert_eq!(ty.params().len(), 9);
         assert_eq!(ty.results().len(), 9);
     }
+
+    /// Limits of a provided memory or table, limits declared by the
+    /// importing module, and whether they match strictly and relaxed.
+    #[allow(clippy::type_complexity)]
+    const LIMITS: [((u32, Option<u32>), (u32, Option<u32>), bool, bool); 12] = [
+        // No declared maximum
+        ((1, None), (1, None), true, true),
+        ((2, Some(5)), (1, None), true, true),
+        ((1, None), (2, None), false, false),
+        // Declared maximum
+        ((1, Some(100)), (1, Some(100)), true, true),
+        ((1, Some(50)), (1, Some(100)), true, true),
+        ((1, Some(200)), (1, Some(100)), false, true),
+        ((1, None), (1, Some(100)), false, true),
+        ((0, Some(100)), (1, Some(100)), false, false),
+        ((0, None), (1, Some(100)), false, false),
+        // Only the maximum is relaxed
+        ((3, Some(3)), (2, Some(4)), true, true),
+        ((3, Some(3)), (4, Some(4)), false, false),
+        ((5, Some(10)), (1, Some(5)), false, true),
+    ];
+
+    #[test]
+    fn memory_import_limits() {
+        for ((min, max), (declared_min, declared_max), strict, relaxed) in LIMITS {
+            let provided = ExternType::Memory(MemoryType::new(min, max, false));
+            let declared = ExternType::Memory(MemoryType::new(declared_min, declared_max, false));
+            assert_eq!(
+                provided.is_compatible_with_limits(&declared, None, ImportLimits::Strict),
+                strict,
+                "strict: {provided} for {declared}"
+            );
+            assert_eq!(
+                provided.is_compatible_with_limits(&declared, None, ImportLimits::Relaxed),
+                relaxed,
+                "relaxed: {provided} for {declared}"
+            );
+        }
+
+        // Sharing is never relaxed
+        let shared = ExternType::Memory(MemoryType::new(1, Some(1), true));
+        let declared = ExternType::Memory(MemoryType::new(1, Some(1), false));
+        assert!(!shared.is_compatible_with_limits(&declared, None, ImportLimits::Relaxed));
+    }
+
+    #[test]
+    fn table_import_limits() {
+        for ((min, max), (declared_min, declared_max), strict, relaxed) in LIMITS {
+            let provided = ExternType::Table(TableType::new(Type::FuncRef, min, max));
+            let declared =
+                ExternType::Table(TableType::new(Type::FuncRef, declared_min, declared_max));
+            assert_eq!(
+                provided.is_compatible_with_limits(&declared, None, ImportLimits::Strict),
+                strict,
+                "strict: {provided} for {declared}"
+            );
+            assert_eq!(
+                provided.is_compatible_with_limits(&declared, None, ImportLimits::Relaxed),
+                relaxed,
+                "relaxed: {provided} for {declared}"
+            );
+        }
+
+        // The element type must match exactly
+        let funcref = ExternType::Table(TableType::new(Type::FuncRef, 1, None));
+        let externref = ExternType::Table(TableType::new(Type::ExternRef, 1, None));
+        assert!(!funcref.is_compatible_with_limits(&externref, None, ImportLimits::Relaxed));
+        assert!(!externref.is_compatible_with_limits(&funcref, None, ImportLimits::Relaxed));
+    }
+
+    #[test]
+    fn import_limits_use_the_current_size() {
+        let provided = ExternType::Memory(MemoryType::new(1, None, false));
+        let declared = ExternType::Memory(MemoryType::new(3, None, false));
+        assert!(!provided.is_compatible_with(&declared, Some(2)));
+        assert!(provided.is_compatible_with(&declared, Some(3)));
+    }
 }
@@ -62,4 +62,12 @@ impl FileSystem for ArcFileSystem {
     ) -> Result<()> {
         self.fs.mount(name, path, fs)
     }
+
+    fn create_tmpfile(&self, path: &Path) -> Result<Box<dyn VirtualFile + Send + Sync + 'static>> {
+        self.fs.create_tmpfile(path)
+    }
+
+    fn link_tmpfile(&self, file: &dyn VirtualFile, path: &Path) -> Result<()> {
+        self.fs.link_tmpfile(file, path)
+    }
 }
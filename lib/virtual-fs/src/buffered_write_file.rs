@@ -0,0 +1,289 @@
+use std::task::ready;
+
+use super::*;
+
+use crate::VirtualFile;
+
+/// When the writes to a [`BufferedWriteFile`] are passed on to the file it
+/// wraps.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BufferMode {
+    /// Every write is passed on straight away.
+    #[default]
+    Unbuffered,
+    /// Complete lines are passed on, a partial line only once `limit` bytes
+    /// are buffered or the file is flushed.
+    Line { limit: usize },
+    /// Writes are passed on once `size` bytes are buffered or the file is
+    /// flushed.
+    Block { size: usize },
+}
+
+impl BufferMode {
+    /// Line buffering with the same limit as the C standard library.
+    pub const LINE: BufferMode = BufferMode::Line { limit: 4096 };
+}
+
+/// Wraps a [`VirtualFile`], buffering what is written to it according to a
+/// [`BufferMode`] and optionally starting every line with a prefix.
+///
+/// What is passed on at once is written to the inner file in one go, so
+/// several line buffered files sharing a pipe don't mix up their lines.
+/// Whatever is still buffered is written when the file is flushed, shut
+/// down or, as far as it can be without blocking, dropped.
+#[derive(derive_more::Debug)]
+pub struct BufferedWriteFile {
+    inner: Box<dyn VirtualFile + Send + Sync + 'static>,
+    mode: BufferMode,
+    prefix: Option<Vec<u8>>,
+    #[debug(ignore)]
+    buffer: Vec<u8>,
+    /// How many of the buffered bytes are due to be written.
+    due: usize,
+    at_line_start: bool,
+}
+
+impl BufferedWriteFile {
+    pub fn new(inner: Box<dyn VirtualFile + Send + Sync + 'static>, mode: BufferMode) -> Self {
+        Self {
+            inner,
+            mode,
+            prefix: None,
+            buffer: Vec::new(),
+            due: 0,
+            at_line_start: true,
+        }
+    }
+
+    /// Starts every line written to the file with `prefix`.
+    pub fn with_prefix(mut self, prefix: impl Into<Vec<u8>>) -> Self {
+        self.prefix = Some(prefix.into());
+        self
+    }
+
+    pub fn mode(&self) -> BufferMode {
+        self.mode
+    }
+
+    /// Buffers `buf`, adding the prefix to the lines it starts.
+    fn push(&mut self, mut buf: &[u8]) {
+        while !buf.is_empty() {
+            if self.at_line_start {
+                if let Some(prefix) = &self.prefix {
+                    self.buffer.extend_from_slice(prefix);
+                }
+            }
+            let end = buf
+                .iter()
+                .position(|b| *b == b'\n')
+                .map_or(buf.len(), |pos| pos + 1);
+            self.buffer.extend_from_slice(&buf[..end]);
+            self.at_line_start = buf[end - 1] == b'\n';
+            buf = &buf[end..];
+        }
+
+        let due = match self.mode {
+            BufferMode::Unbuffered => self.buffer.len(),
+            BufferMode::Line { limit } if self.buffer.len() >= limit => self.buffer.len(),
+            BufferMode::Line { .. } => self
+                .buffer
+                .iter()
+                .rposition(|b| *b == b'\n')
+                .map_or(0, |pos| pos + 1),
+            BufferMode::Block { size } if self.buffer.len() >= size => self.buffer.len(),
+            BufferMode::Block { .. } => 0,
+        };
+        self.due = self.due.max(due);
+    }
+
+    /// Writes the bytes that are due to the inner file.
+    fn poll_write_due(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        while self.due > 0 {
+            let written =
+                ready!(Pin::new(&mut self.inner).poll_write(cx, &self.buffer[..self.due]))?;
+            if written == 0 {
+                return Poll::Ready(Err(io::ErrorKind::WriteZero.into()));
+            }
+            self.buffer.drain(..written);
+            self.due -= written;
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl Drop for BufferedWriteFile {
+    fn drop(&mut self) {
+        self.due = self.buffer.len();
+        let mut cx = Context::from_waker(futures::task::noop_waker_ref());
+        if let Poll::Ready(Err(err)) = self.poll_write_due(&mut cx) {
+            tracing::debug!(
+                error = &err as &dyn std::error::Error,
+                "unable to flush on drop"
+            );
+        }
+    }
+}
+
+impl VirtualFile for BufferedWriteFile {
+    fn last_accessed(&self) -> u64 {
+        self.inner.last_accessed()
+    }
+
+    fn last_modified(&self) -> u64 {
+        self.inner.last_modified()
+    }
+
+    fn created_time(&self) -> u64 {
+        self.inner.created_time()
+    }
+
+    fn set_times(&mut self, atime: Option<u64>, mtime: Option<u64>) -> crate::Result<()> {
+        self.inner.set_times(atime, mtime)
+    }
+
+    fn size(&self) -> u64 {
+        self.inner.size()
+    }
+
+    fn set_len(&mut self, new_size: u64) -> crate::Result<()> {
+        self.inner.set_len(new_size)
+    }
+
+    fn unlink(&mut self) -> Result<()> {
+        self.inner.unlink()
+    }
+
+    fn poll_read_ready(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<usize>> {
+        Pin::new(self.inner.as_mut()).poll_read_ready(cx)
+    }
+
+    fn poll_write_ready(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<usize>> {
+        Pin::new(self.inner.as_mut()).poll_write_ready(cx)
+    }
+}
+
+impl AsyncWrite for BufferedWriteFile {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+        buf: &[u8],
+    ) -> Poll<std::io::Result<usize>> {
+        // What earlier writes left behind goes first, which also holds the
+        // writer back while the inner file isn't ready
+        ready!(self.poll_write_due(cx))?;
+
+        // Whatever this write made due stays buffered until the next write
+        // or flush if the inner file isn't ready for it
+        self.push(buf);
+        if let Poll::Ready(Err(err)) = self.poll_write_due(cx) {
+            return Poll::Ready(Err(err));
+        }
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        self.due = self.buffer.len();
+        ready!(self.poll_write_due(cx))?;
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        ready!(self.as_mut().poll_flush(cx))?;
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
+
+impl AsyncRead for BufferedWriteFile {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+        buf: &mut tokio::io::ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_read(cx, buf)
+    }
+}
+
+impl AsyncSeek for BufferedWriteFile {
+    fn start_seek(mut self: Pin<&mut Self>, position: io::SeekFrom) -> io::Result<()> {
+        Pin::new(&mut self.inner).start_seek(position)
+    }
+
+    fn poll_complete(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<u64>> {
+        Pin::new(&mut self.inner).poll_complete(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::AsyncWriteExt;
+
+    use super::*;
+    use crate::Pipe;
+
+    /// Everything that can be read from `rx` without blocking.
+    fn available(rx: &mut Pipe) -> String {
+        let mut output = Vec::new();
+        let mut buf = [0; 256];
+        while let Some(read @ 1..) = rx.try_read(&mut buf) {
+            output.extend_from_slice(&buf[..read]);
+        }
+        String::from_utf8(output).unwrap()
+    }
+
+    #[tokio::test]
+    async fn line_buffering_passes_on_complete_lines() {
+        let (tx, mut rx) = Pipe::channel();
+        let mut file = BufferedWriteFile::new(Box::new(tx), BufferMode::LINE);
+
+        file.write_all(b"hello").await.unwrap();
+        assert_eq!(available(&mut rx), "");
+        file.write_all(b" world\nand").await.unwrap();
+        assert_eq!(available(&mut rx), "hello world\n");
+
+        file.flush().await.unwrap();
+        assert_eq!(available(&mut rx), "and");
+    }
+
+    #[tokio::test]
+    async fn long_lines_and_full_blocks_are_passed_on() {
+        let (tx, mut rx) = Pipe::channel();
+        let mut file = BufferedWriteFile::new(Box::new(tx), BufferMode::Line { limit: 4 });
+        file.write_all(b"abcdef").await.unwrap();
+        assert_eq!(available(&mut rx), "abcdef");
+
+        let (tx, mut rx) = Pipe::channel();
+        let mut file = BufferedWriteFile::new(Box::new(tx), BufferMode::Block { size: 4 });
+        file.write_all(b"a\nb").await.unwrap();
+        assert_eq!(available(&mut rx), "");
+        file.write_all(b"c").await.unwrap();
+        assert_eq!(available(&mut rx), "a\nbc");
+    }
+
+    #[tokio::test]
+    async fn prefixed_lines_are_not_mixed_up() {
+        let (tx, mut rx) = Pipe::channel();
+        let mut a =
+            BufferedWriteFile::new(Box::new(tx.clone()), BufferMode::LINE).with_prefix("a: ");
+        let mut b = BufferedWriteFile::new(Box::new(tx), BufferMode::LINE).with_prefix("b: ");
+
+        a.write_all(b"one ").await.unwrap();
+        b.write_all(b"three ").await.unwrap();
+        a.write_all(b"two\nfive").await.unwrap();
+        b.write_all(b"four\n").await.unwrap();
+        drop(a);
+
+        assert_eq!(available(&mut rx), "a: one two\nb: three four\na: five");
+    }
+
+    #[tokio::test]
+    async fn unbuffered_writes_are_prefixed() {
+        let (tx, mut rx) = Pipe::channel();
+        let mut file =
+            BufferedWriteFile::new(Box::new(tx), BufferMode::Unbuffered).with_prefix("> ");
+
+        file.write_all(b"a").await.unwrap();
+        assert_eq!(available(&mut rx), "> a");
+        file.write_all(b"b\n\nc").await.unwrap();
+        assert_eq!(available(&mut rx), "b\n> \n> c");
+    }
+}
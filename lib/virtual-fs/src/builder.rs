@@ -1,15 +1,21 @@
+use crate::limiter::FixedMemoryLimiter;
 use crate::random_file::RandomFile;
 use crate::{FileSystem, VirtualFile};
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use tracing::*;
 
 use super::ZeroFile;
 use super::{DeviceFile, NullFile};
 use crate::tmp_fs::TmpFileSystem;
 
+/// The default size limit of the file system mounted at `/tmp` (1 GiB).
+pub const DEFAULT_TMP_SIZE_LIMIT: usize = 1024 * 1024 * 1024;
+
 pub struct RootFileSystemBuilder {
     default_root_dirs: bool,
     create_tmp: bool,
+    tmp_fs: TmpFileSystem,
     default_dev_files: bool,
     add_wasmer_command: bool,
     stdin: Option<Box<dyn VirtualFile + Send + Sync>>,
@@ -20,9 +26,13 @@ pub struct RootFileSystemBuilder {
 
 impl Default for RootFileSystemBuilder {
     fn default() -> Self {
+        let tmp_fs = TmpFileSystem::new();
+        tmp_fs.set_memory_limiter(Arc::new(FixedMemoryLimiter::new(DEFAULT_TMP_SIZE_LIMIT)));
+
         Self {
             default_root_dirs: true,
             create_tmp: true,
+            tmp_fs,
             default_dev_files: true,
             add_wasmer_command: true,
             stdin: None,
@@ -68,25 +78,42 @@ impl RootFileSystemBuilder {
         self
     }
 
-    pub fn build(self) -> TmpFileSystem {
-        let tmp = TmpFileSystem::new();
+    /// Use `fs` as the separate file system that gets mounted at `/tmp`.
+    pub fn with_tmp_fs(mut self, fs: TmpFileSystem) -> Self {
+        self.tmp_fs = fs;
+        self
+    }
 
-        if self.default_root_dirs {
-            let default_dirs = {
-                let mut dirs = vec!["/.app", "/.private", "/bin", "/dev", "/etc"];
+    /// Limit how much memory the files in `/tmp` may use (defaults to
+    /// [`DEFAULT_TMP_SIZE_LIMIT`]).
+    pub fn with_tmp_size_limit(self, limit: usize) -> Self {
+        self.tmp_fs
+            .set_memory_limiter(Arc::new(FixedMemoryLimiter::new(limit)));
+        self
+    }
 
-                if self.create_tmp {
-                    dirs.push("/tmp");
-                }
+    /// The file system that will be mounted at `/tmp`, e.g. so it can be
+    /// cleared once it is no longer in use.
+    pub fn tmp_fs(&self) -> &TmpFileSystem {
+        &self.tmp_fs
+    }
 
-                dirs
-            };
+    pub fn build(self) -> TmpFileSystem {
+        let tmp = TmpFileSystem::new();
 
-            for root_dir in &default_dirs {
+        if self.default_root_dirs {
+            for root_dir in &["/.app", "/.private", "/bin", "/dev", "/etc"] {
                 if let Err(err) = tmp.create_dir(Path::new(root_dir)) {
                     debug!("failed to create dir [{}] - {}", root_dir, err);
                 }
             }
+
+            if self.create_tmp {
+                let tmp_fs: Arc<dyn FileSystem + Send + Sync> = Arc::new(self.tmp_fs);
+                if let Err(err) = tmp.mount(PathBuf::from("/tmp"), &tmp_fs, PathBuf::from("/")) {
+                    debug!("failed to mount [/tmp] - {}", err);
+                }
+            }
         }
         if self.add_wasmer_command {
             let _ = tmp
@@ -129,7 +156,9 @@ impl RootFileSystemBuilder {
 
 #[cfg(test)]
 mod test_builder {
-    use crate::{FileSystem, RootFileSystemBuilder};
+    use std::path::Path;
+
+    use crate::{FileSystem, FsError, RootFileSystemBuilder};
     use tokio::io::{AsyncReadExt, AsyncWriteExt};
 
     #[tokio::test]
@@ -199,4 +228,34 @@ mod test_builder {
             .unwrap();
         assert_eq!(dev_stderr.get_special_fd().unwrap(), 2);
     }
+
+    #[tokio::test]
+    async fn test_tmp_is_separate_file_system() {
+        let builder = RootFileSystemBuilder::new();
+        let tmp_fs = builder.tmp_fs().clone();
+        let root_fs = builder.build();
+
+        let mut file = root_fs.create_tmpfile(Path::new("/tmp")).unwrap();
+        file.write_all(b"hello").await.unwrap();
+        root_fs.link_tmpfile(&*file, Path::new("/tmp/out")).unwrap();
+        drop(file);
+
+        let mut contents = String::new();
+        tmp_fs
+            .new_open_options()
+            .read(true)
+            .open("/out")
+            .unwrap()
+            .read_to_string(&mut contents)
+            .await
+            .unwrap();
+        assert_eq!(contents, "hello");
+
+        tmp_fs.clear().unwrap();
+        assert_eq!(
+            root_fs.metadata(Path::new("/tmp/out")).unwrap_err(),
+            FsError::EntryNotFound
+        );
+        assert!(root_fs.metadata(Path::new("/tmp")).unwrap().is_dir());
+    }
 }
@@ -0,0 +1,880 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    io,
+    path::{Path, PathBuf},
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+    time::Duration,
+};
+
+#[cfg(not(feature = "js"))]
+use std::time::Instant;
+#[cfg(feature = "js")]
+use web_time::Instant;
+
+use futures::future::{BoxFuture, FutureExt};
+use shared_buffer::OwnedBuffer;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeek, AsyncWrite, ReadBuf};
+
+use crate::{
+    DirEntry, FileOpener, FileSystem, FsError, Metadata, OpenOptionsConfig, ReadDir, Result,
+    StaticFile, VirtualFile,
+};
+
+/// How long entries are kept by default when the wrapped file system isn't
+/// immutable.
+pub const DEFAULT_CACHE_TTL: Duration = Duration::from_secs(1);
+
+/// How much a [`CachingFileSystem`] keeps in memory by default.
+pub const DEFAULT_CACHE_BYTE_BUDGET: usize = 8 * 1024 * 1024;
+
+/// A [`FileSystem`] wrapper that remembers metadata, directory listings and
+/// (optionally) the contents of small files, for file systems where every
+/// lookup is slow (e.g. a webc file on a network file system).
+///
+/// When the wrapped file system is immutable, entries are kept until they
+/// are evicted to stay within the byte budget. Otherwise they also expire
+/// after a TTL ([`DEFAULT_CACHE_TTL`] by default), so changes made behind
+/// the wrapper's back show up eventually. Changes made through the wrapper
+/// are always visible straight away, as is anything passed to
+/// [`CachingFileSystem::invalidate()`].
+///
+/// Clones share the same cache.
+#[derive(Debug, Clone)]
+pub struct CachingFileSystem<F> {
+    inner: F,
+    immutable: bool,
+    ttl: Duration,
+    max_file_size: u64,
+    cache: Arc<Mutex<Cache>>,
+}
+
+impl<F> CachingFileSystem<F> {
+    /// Wraps `inner`, where `immutable` promises that its contents never
+    /// change other than through this wrapper.
+    pub fn new(inner: F, immutable: bool) -> Self {
+        CachingFileSystem {
+            inner,
+            immutable,
+            ttl: DEFAULT_CACHE_TTL,
+            max_file_size: 0,
+            cache: Arc::new(Mutex::new(Cache::new(DEFAULT_CACHE_BYTE_BUDGET))),
+        }
+    }
+
+    /// Sets how long entries are kept for, unless the wrapped file system is
+    /// immutable.
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// Sets roughly how many bytes the cache may use, evicting the least
+    /// recently used entries to stay below it.
+    pub fn with_byte_budget(self, budget: usize) -> Self {
+        self.cache.lock().unwrap().set_budget(budget);
+        self
+    }
+
+    /// Keeps the contents of files of up to `max_file_size` bytes that are
+    /// opened read-only, which isn't done by default.
+    pub fn with_cached_contents(mut self, max_file_size: u64) -> Self {
+        self.max_file_size = max_file_size;
+        self
+    }
+
+    pub fn inner(&self) -> &F {
+        &self.inner
+    }
+
+    /// Forgets everything about `path` and anything below it.
+    pub fn invalidate(&self, path: &Path) {
+        self.cache.lock().unwrap().invalidate(path);
+    }
+
+    /// Forgets everything.
+    pub fn clear(&self) {
+        self.cache.lock().unwrap().clear();
+    }
+
+    fn lookup(&self, key: &Key) -> Option<Value> {
+        let ttl = if self.immutable { None } else { Some(self.ttl) };
+        self.cache.lock().unwrap().get(key, ttl)
+    }
+
+    fn store(&self, key: Key, value: Value) {
+        self.cache.lock().unwrap().insert(key, value);
+    }
+}
+
+/// Only results that say something about the file system are worth
+/// remembering, not e.g. I/O errors.
+fn is_cacheable<T>(result: &Result<T>) -> bool {
+    matches!(result, Ok(_) | Err(FsError::EntryNotFound))
+}
+
+impl<F> CachingFileSystem<F>
+where
+    F: FileSystem,
+{
+    /// Opens a read-only copy of the file's contents, if it is small enough.
+    fn open_cached(&self, path: &Path) -> Option<Box<dyn VirtualFile + Send + Sync + 'static>> {
+        let key = Key::Contents(path.to_path_buf());
+        let (contents, metadata) = match self.lookup(&key) {
+            Some(Value::Contents(contents, metadata)) => (contents, metadata),
+            _ => {
+                let metadata = self.metadata(path).ok()?;
+                if !metadata.is_file() || metadata.len > self.max_file_size {
+                    return None;
+                }
+
+                // Only files that can be read without waiting are kept
+                let mut file = self.inner.new_open_options().read(true).open(path).ok()?;
+                let mut buffer = Vec::with_capacity(metadata.len as usize);
+                file.read_to_end(&mut buffer).now_or_never()?.ok()?;
+
+                let contents = OwnedBuffer::from(buffer);
+                self.store(key, Value::Contents(contents.clone(), metadata.clone()));
+                (contents, metadata)
+            }
+        };
+
+        Some(Box::new(CachedFile {
+            file: StaticFile::new(contents),
+            metadata,
+        }))
+    }
+}
+
+impl<F> FileSystem for CachingFileSystem<F>
+where
+    F: FileSystem,
+{
+    fn readlink(&self, path: &Path) -> Result<PathBuf> {
+        let key = Key::Readlink(path.to_path_buf());
+        if let Some(Value::Readlink(target)) = self.lookup(&key) {
+            return target;
+        }
+
+        let target = self.inner.readlink(path);
+        if is_cacheable(&target) {
+            self.store(key, Value::Readlink(target.clone()));
+        }
+        target
+    }
+
+    fn read_dir(&self, path: &Path) -> Result<ReadDir> {
+        let key = Key::ReadDir(path.to_path_buf());
+        if let Some(Value::ReadDir(entries)) = self.lookup(&key) {
+            return entries.map(ReadDir::new);
+        }
+
+        let entries = self.inner.read_dir(path).map(|entries| entries.data);
+        if is_cacheable(&entries) {
+            self.store(key, Value::ReadDir(entries.clone()));
+        }
+        entries.map(ReadDir::new)
+    }
+
+    fn create_dir(&self, path: &Path) -> Result<()> {
+        let result = self.inner.create_dir(path);
+        self.invalidate(path);
+        result
+    }
+
+    fn remove_dir(&self, path: &Path) -> Result<()> {
+        let result = self.inner.remove_dir(path);
+        self.invalidate(path);
+        result
+    }
+
+    fn rename<'a>(&'a self, from: &'a Path, to: &'a Path) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            let result = self.inner.rename(from, to).await;
+            self.invalidate(from);
+            self.invalidate(to);
+            result
+        })
+    }
+
+    fn metadata(&self, path: &Path) -> Result<Metadata> {
+        let key = Key::Metadata(path.to_path_buf());
+        if let Some(Value::Metadata(metadata)) = self.lookup(&key) {
+            return metadata;
+        }
+
+        let metadata = self.inner.metadata(path);
+        if is_cacheable(&metadata) {
+            self.store(key, Value::Metadata(metadata.clone()));
+        }
+        metadata
+    }
+
+    fn symlink_metadata(&self, path: &Path) -> Result<Metadata> {
+        let key = Key::SymlinkMetadata(path.to_path_buf());
+        if let Some(Value::Metadata(metadata)) = self.lookup(&key) {
+            return metadata;
+        }
+
+        let metadata = self.inner.symlink_metadata(path);
+        if is_cacheable(&metadata) {
+            self.store(key, Value::Metadata(metadata.clone()));
+        }
+        metadata
+    }
+
+    fn remove_file(&self, path: &Path) -> Result<()> {
+        let result = self.inner.remove_file(path);
+        self.invalidate(path);
+        result
+    }
+
+    fn new_open_options(&self) -> crate::OpenOptions {
+        crate::OpenOptions::new(self)
+    }
+
+    fn mount(
+        &self,
+        name: String,
+        path: &Path,
+        fs: Box<dyn FileSystem + Send + Sync>,
+    ) -> Result<()> {
+        let result = self.inner.mount(name, path, fs);
+        self.invalidate(path);
+        result
+    }
+
+    fn create_tmpfile(&self, path: &Path) -> Result<Box<dyn VirtualFile + Send + Sync + 'static>> {
+        self.inner.create_tmpfile(path)
+    }
+
+    fn link_tmpfile(&self, file: &dyn VirtualFile, path: &Path) -> Result<()> {
+        let result = self.inner.link_tmpfile(file, path);
+        self.invalidate(path);
+        result
+    }
+}
+
+impl<F> FileOpener for CachingFileSystem<F>
+where
+    F: FileSystem,
+{
+    fn open(
+        &self,
+        path: &Path,
+        conf: &OpenOptionsConfig,
+    ) -> Result<Box<dyn VirtualFile + Send + Sync + 'static>> {
+        let modifies = conf.would_mutate();
+
+        if !modifies && self.max_file_size > 0 {
+            if let Some(file) = self.open_cached(path) {
+                return Ok(file);
+            }
+        }
+
+        let file = self
+            .inner
+            .new_open_options()
+            .options(conf.clone())
+            .open(path);
+        if !modifies {
+            return file;
+        }
+
+        self.invalidate(path);
+        Ok(Box::new(InvalidatingFile {
+            file: file?,
+            path: path.to_path_buf(),
+            cache: Arc::clone(&self.cache),
+        }))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Key {
+    Metadata(PathBuf),
+    SymlinkMetadata(PathBuf),
+    ReadDir(PathBuf),
+    Readlink(PathBuf),
+    Contents(PathBuf),
+}
+
+impl Key {
+    fn path(&self) -> &Path {
+        match self {
+            Key::Metadata(path)
+            | Key::SymlinkMetadata(path)
+            | Key::ReadDir(path)
+            | Key::Readlink(path)
+            | Key::Contents(path) => path,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Value {
+    Metadata(Result<Metadata>),
+    ReadDir(Result<Vec<DirEntry>>),
+    Readlink(Result<PathBuf>),
+    Contents(OwnedBuffer, Metadata),
+}
+
+impl Value {
+    /// Roughly how much memory the value uses.
+    fn size(&self) -> usize {
+        let heap = match self {
+            Value::Metadata(_) => 0,
+            Value::ReadDir(Ok(entries)) => entries
+                .iter()
+                .map(|entry| std::mem::size_of::<DirEntry>() + entry.path.as_os_str().len())
+                .sum(),
+            Value::ReadDir(Err(_)) => 0,
+            Value::Readlink(target) => target.as_ref().map_or(0, |target| target.as_os_str().len()),
+            Value::Contents(contents, _) => contents.len(),
+        };
+        std::mem::size_of::<Value>() + heap
+    }
+}
+
+#[derive(Debug)]
+struct Entry {
+    value: Value,
+    size: usize,
+    inserted: Instant,
+    /// The entry's position in [`Cache::recency`].
+    last_used: u64,
+}
+
+/// A least-recently-used cache with a byte budget.
+#[derive(Debug)]
+struct Cache {
+    entries: HashMap<Key, Entry>,
+    /// Every key, from the least recently used to the most recently used.
+    recency: BTreeMap<u64, Key>,
+    clock: u64,
+    bytes: usize,
+    budget: usize,
+}
+
+impl Cache {
+    fn new(budget: usize) -> Self {
+        Cache {
+            entries: HashMap::new(),
+            recency: BTreeMap::new(),
+            clock: 0,
+            bytes: 0,
+            budget,
+        }
+    }
+
+    fn set_budget(&mut self, budget: usize) {
+        self.budget = budget;
+        self.evict(0);
+    }
+
+    fn get(&mut self, key: &Key, ttl: Option<Duration>) -> Option<Value> {
+        let inserted = self.entries.get(key)?.inserted;
+        if ttl.is_some_and(|ttl| inserted.elapsed() > ttl) {
+            self.remove(key);
+            return None;
+        }
+
+        self.clock += 1;
+        let entry = self.entries.get_mut(key)?;
+        self.recency.remove(&entry.last_used);
+        self.recency.insert(self.clock, key.clone());
+        entry.last_used = self.clock;
+        Some(entry.value.clone())
+    }
+
+    fn insert(&mut self, key: Key, value: Value) {
+        self.remove(&key);
+
+        let size = std::mem::size_of::<Key>() + key.path().as_os_str().len() + value.size();
+        if size > self.budget {
+            return;
+        }
+        self.evict(size);
+
+        self.clock += 1;
+        self.recency.insert(self.clock, key.clone());
+        self.bytes += size;
+        self.entries.insert(
+            key,
+            Entry {
+                value,
+                size,
+                inserted: Instant::now(),
+                last_used: self.clock,
+            },
+        );
+    }
+
+    /// Evicts entries until another `size` bytes fit in the budget.
+    fn evict(&mut self, size: usize) {
+        while self.bytes + size > self.budget {
+            let Some((_, key)) = self.recency.pop_first() else {
+                break;
+            };
+            if let Some(entry) = self.entries.remove(&key) {
+                self.bytes -= entry.size;
+            }
+        }
+    }
+
+    fn remove(&mut self, key: &Key) {
+        if let Some(entry) = self.entries.remove(key) {
+            self.recency.remove(&entry.last_used);
+            self.bytes -= entry.size;
+        }
+    }
+
+    /// Removes everything at or below `path`, along with the listing and
+    /// metadata of its parent (which a change to `path` also changes).
+    fn invalidate(&mut self, path: &Path) {
+        let stale: Vec<Key> = self
+            .entries
+            .keys()
+            .filter(|key| key.path().starts_with(path))
+            .cloned()
+            .collect();
+        for key in stale {
+            self.remove(&key);
+        }
+
+        if let Some(parent) = path.parent() {
+            self.remove(&Key::ReadDir(parent.to_path_buf()));
+            self.remove(&Key::Metadata(parent.to_path_buf()));
+            self.remove(&Key::SymlinkMetadata(parent.to_path_buf()));
+        }
+    }
+
+    fn clear(&mut self) {
+        self.entries.clear();
+        self.recency.clear();
+        self.bytes = 0;
+    }
+}
+
+/// A file opened read-only from the cached contents.
+#[derive(Debug)]
+struct CachedFile {
+    file: StaticFile,
+    metadata: Metadata,
+}
+
+impl VirtualFile for CachedFile {
+    fn last_accessed(&self) -> u64 {
+        self.metadata.accessed
+    }
+
+    fn last_modified(&self) -> u64 {
+        self.metadata.modified
+    }
+
+    fn created_time(&self) -> u64 {
+        self.metadata.created
+    }
+
+    fn size(&self) -> u64 {
+        self.file.size()
+    }
+
+    fn set_len(&mut self, new_size: u64) -> Result<()> {
+        self.file.set_len(new_size)
+    }
+
+    fn unlink(&mut self) -> Result<()> {
+        self.file.unlink()
+    }
+
+    fn poll_read_ready(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.file).poll_read_ready(cx)
+    }
+
+    fn poll_write_ready(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.file).poll_write_ready(cx)
+    }
+}
+
+impl AsyncRead for CachedFile {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.file).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for CachedFile {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.file).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.file).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.file).poll_shutdown(cx)
+    }
+}
+
+impl AsyncSeek for CachedFile {
+    fn start_seek(mut self: Pin<&mut Self>, position: io::SeekFrom) -> io::Result<()> {
+        Pin::new(&mut self.file).start_seek(position)
+    }
+
+    fn poll_complete(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<u64>> {
+        Pin::new(&mut self.file).poll_complete(cx)
+    }
+}
+
+/// A file that can modify the file system, which invalidates what the cache
+/// knows about it whenever it does.
+#[derive(Debug)]
+struct InvalidatingFile {
+    file: Box<dyn VirtualFile + Send + Sync + 'static>,
+    path: PathBuf,
+    cache: Arc<Mutex<Cache>>,
+}
+
+impl InvalidatingFile {
+    fn invalidate(&self) {
+        self.cache.lock().unwrap().invalidate(&self.path);
+    }
+}
+
+impl VirtualFile for InvalidatingFile {
+    fn last_accessed(&self) -> u64 {
+        self.file.last_accessed()
+    }
+
+    fn last_modified(&self) -> u64 {
+        self.file.last_modified()
+    }
+
+    fn created_time(&self) -> u64 {
+        self.file.created_time()
+    }
+
+    fn set_times(&mut self, atime: Option<u64>, mtime: Option<u64>) -> Result<()> {
+        let result = self.file.set_times(atime, mtime);
+        self.invalidate();
+        result
+    }
+
+    fn size(&self) -> u64 {
+        self.file.size()
+    }
+
+    fn set_len(&mut self, new_size: u64) -> Result<()> {
+        let result = self.file.set_len(new_size);
+        self.invalidate();
+        result
+    }
+
+    fn unlink(&mut self) -> Result<()> {
+        let result = self.file.unlink();
+        self.invalidate();
+        result
+    }
+
+    fn is_open(&self) -> bool {
+        self.file.is_open()
+    }
+
+    fn write_from_mmap(&mut self, offset: u64, len: u64) -> io::Result<()> {
+        let result = self.file.write_from_mmap(offset, len);
+        self.invalidate();
+        result
+    }
+
+    fn write_vectored_at(
+        &mut self,
+        offset: u64,
+        bufs: &[io::IoSlice<'_>],
+    ) -> Option<io::Result<usize>> {
+        let result = self.file.write_vectored_at(offset, bufs);
+        self.invalidate();
+        result
+    }
+
+    fn append_vectored(&mut self, bufs: &[io::IoSlice<'_>]) -> Option<io::Result<u64>> {
+        let result = self.file.append_vectored(bufs);
+        self.invalidate();
+        result
+    }
+
+    fn copy_reference(
+        &mut self,
+        src: Box<dyn VirtualFile + Send + Sync + 'static>,
+    ) -> BoxFuture<'_, io::Result<()>> {
+        self.invalidate();
+        self.file.copy_reference(src)
+    }
+
+    fn copy_from_owned_buffer(&mut self, src: &OwnedBuffer) -> BoxFuture<'_, io::Result<()>> {
+        self.invalidate();
+        self.file.copy_from_owned_buffer(src)
+    }
+
+    fn poll_read_ready(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<usize>> {
+        Pin::new(&mut *self.file).poll_read_ready(cx)
+    }
+
+    fn poll_write_ready(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<usize>> {
+        Pin::new(&mut *self.file).poll_write_ready(cx)
+    }
+}
+
+impl AsyncRead for InvalidatingFile {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut *self.file).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for InvalidatingFile {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let result = Pin::new(&mut *self.file).poll_write(cx, buf);
+        if let Poll::Ready(Ok(_)) = result {
+            self.invalidate();
+        }
+        result
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut *self.file).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut *self.file).poll_shutdown(cx)
+    }
+}
+
+impl AsyncSeek for InvalidatingFile {
+    fn start_seek(mut self: Pin<&mut Self>, position: io::SeekFrom) -> io::Result<()> {
+        Pin::new(&mut *self.file).start_seek(position)
+    }
+
+    fn poll_complete(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<u64>> {
+        Pin::new(&mut *self.file).poll_complete(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use tokio::io::AsyncWriteExt;
+
+    use super::*;
+    use crate::mem_fs::FileSystem as MemFS;
+
+    /// A file system that counts how often it is asked about something.
+    #[derive(Debug, Default, Clone)]
+    struct CountingFs {
+        inner: MemFS,
+        metadata: Arc<AtomicUsize>,
+        read_dir: Arc<AtomicUsize>,
+        open: Arc<AtomicUsize>,
+    }
+
+    impl FileSystem for CountingFs {
+        fn readlink(&self, path: &Path) -> Result<PathBuf> {
+            self.inner.readlink(path)
+        }
+
+        fn read_dir(&self, path: &Path) -> Result<ReadDir> {
+            self.read_dir.fetch_add(1, Ordering::SeqCst);
+            self.inner.read_dir(path)
+        }
+
+        fn create_dir(&self, path: &Path) -> Result<()> {
+            self.inner.create_dir(path)
+        }
+
+        fn remove_dir(&self, path: &Path) -> Result<()> {
+            self.inner.remove_dir(path)
+        }
+
+        fn rename<'a>(&'a self, from: &'a Path, to: &'a Path) -> BoxFuture<'a, Result<()>> {
+            self.inner.rename(from, to)
+        }
+
+        fn metadata(&self, path: &Path) -> Result<Metadata> {
+            self.metadata.fetch_add(1, Ordering::SeqCst);
+            self.inner.metadata(path)
+        }
+
+        fn symlink_metadata(&self, path: &Path) -> Result<Metadata> {
+            self.inner.symlink_metadata(path)
+        }
+
+        fn remove_file(&self, path: &Path) -> Result<()> {
+            self.inner.remove_file(path)
+        }
+
+        fn new_open_options(&self) -> crate::OpenOptions {
+            crate::OpenOptions::new(self)
+        }
+
+        fn mount(
+            &self,
+            name: String,
+            path: &Path,
+            fs: Box<dyn FileSystem + Send + Sync>,
+        ) -> Result<()> {
+            self.inner.mount(name, path, fs)
+        }
+    }
+
+    impl FileOpener for CountingFs {
+        fn open(
+            &self,
+            path: &Path,
+            conf: &OpenOptionsConfig,
+        ) -> Result<Box<dyn VirtualFile + Send + Sync + 'static>> {
+            self.open.fetch_add(1, Ordering::SeqCst);
+            self.inner
+                .new_open_options()
+                .options(conf.clone())
+                .open(path)
+        }
+    }
+
+    async fn write_file(fs: &impl FileSystem, path: &str, contents: &[u8]) {
+        let mut f = fs
+            .new_open_options()
+            .create(true)
+            .write(true)
+            .truncate(true)
+            .open(path)
+            .unwrap();
+        f.write_all(contents).await.unwrap();
+        f.flush().await.unwrap();
+    }
+
+    async fn read_file(fs: &impl FileSystem, path: &str) -> String {
+        let mut f = fs.new_open_options().read(true).open(path).unwrap();
+        let mut contents = String::new();
+        f.read_to_string(&mut contents).await.unwrap();
+        contents
+    }
+
+    #[tokio::test]
+    async fn repeated_lookups_hit_the_cache() {
+        let inner = CountingFs::default();
+        write_file(&inner, "/file.txt", b"Hello, World!").await;
+        let fs = CachingFileSystem::new(inner.clone(), true);
+
+        for _ in 0..3 {
+            assert_eq!(fs.metadata(Path::new("/file.txt")).unwrap().len, 13);
+            assert_eq!(
+                fs.metadata(Path::new("/missing")).unwrap_err(),
+                FsError::EntryNotFound
+            );
+            assert_eq!(fs.read_dir(Path::new("/")).unwrap().count(), 1);
+        }
+
+        assert_eq!(inner.metadata.load(Ordering::SeqCst), 2);
+        assert_eq!(inner.read_dir.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn small_files_are_read_once() {
+        let inner = CountingFs::default();
+        write_file(&inner, "/small.txt", b"Hello, World!").await;
+        write_file(&inner, "/large.txt", &[b'x'; 64]).await;
+        let opened = inner.open.load(Ordering::SeqCst);
+        let fs = CachingFileSystem::new(inner.clone(), true).with_cached_contents(16);
+
+        for _ in 0..3 {
+            assert_eq!(read_file(&fs, "/small.txt").await, "Hello, World!");
+            assert_eq!(read_file(&fs, "/large.txt").await.len(), 64);
+        }
+
+        assert_eq!(inner.open.load(Ordering::SeqCst) - opened, 1 + 3);
+    }
+
+    #[tokio::test]
+    async fn changes_through_the_cache_invalidate_it() {
+        let inner = CountingFs::default();
+        write_file(&inner, "/file.txt", b"Hello").await;
+        let fs = CachingFileSystem::new(inner.clone(), false).with_cached_contents(1024);
+
+        assert_eq!(read_file(&fs, "/file.txt").await, "Hello");
+        assert_eq!(fs.read_dir(Path::new("/")).unwrap().count(), 1);
+
+        write_file(&fs, "/file.txt", b"Hello, World!").await;
+        fs.create_dir(Path::new("/dir")).unwrap();
+
+        assert_eq!(read_file(&fs, "/file.txt").await, "Hello, World!");
+        assert_eq!(fs.metadata(Path::new("/file.txt")).unwrap().len, 13);
+        assert_eq!(fs.read_dir(Path::new("/")).unwrap().count(), 2);
+
+        fs.remove_file(Path::new("/file.txt")).unwrap();
+        assert_eq!(
+            fs.metadata(Path::new("/file.txt")).unwrap_err(),
+            FsError::EntryNotFound
+        );
+        assert_eq!(fs.read_dir(Path::new("/")).unwrap().count(), 1);
+    }
+
+    #[tokio::test]
+    async fn entries_expire_unless_immutable() {
+        let inner = CountingFs::default();
+        let fs = CachingFileSystem::new(inner.clone(), false).with_ttl(Duration::from_millis(50));
+        let immutable =
+            CachingFileSystem::new(inner.clone(), true).with_ttl(Duration::from_millis(50));
+
+        fs.read_dir(Path::new("/")).unwrap();
+        immutable.read_dir(Path::new("/")).unwrap();
+        // A change the cache doesn't know about
+        inner.create_dir(Path::new("/dir")).unwrap();
+        assert_eq!(fs.read_dir(Path::new("/")).unwrap().count(), 0);
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        assert_eq!(fs.read_dir(Path::new("/")).unwrap().count(), 1);
+        assert_eq!(immutable.read_dir(Path::new("/")).unwrap().count(), 0);
+
+        immutable.invalidate(Path::new("/dir"));
+        assert_eq!(immutable.read_dir(Path::new("/")).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn the_least_recently_used_entries_are_evicted() {
+        let inner = CountingFs::default();
+        let stat = |fs: &CachingFileSystem<CountingFs>, path: &str| {
+            fs.metadata(Path::new(path)).unwrap_err();
+            inner.metadata.load(Ordering::SeqCst)
+        };
+        // Enough for two entries, but not three
+        let budget = 2 * (std::mem::size_of::<Key>() + "/a".len() + std::mem::size_of::<Value>());
+        let fs = CachingFileSystem::new(inner.clone(), true).with_byte_budget(budget);
+
+        assert_eq!(stat(&fs, "/a"), 1);
+        assert_eq!(stat(&fs, "/b"), 2);
+        assert_eq!(stat(&fs, "/a"), 2);
+        // Evicts "/b", which was used longest ago
+        assert_eq!(stat(&fs, "/c"), 3);
+        assert_eq!(stat(&fs, "/a"), 3);
+        assert_eq!(stat(&fs, "/c"), 3);
+        assert_eq!(stat(&fs, "/b"), 4);
+    }
+}
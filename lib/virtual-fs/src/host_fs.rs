@@ -8,7 +8,7 @@ use futures::future::BoxFuture;
 use serde::{de, Deserialize, Serialize};
 use std::convert::TryInto;
 use std::fs;
-use std::io::{self, Seek};
+use std::io::{self, Seek, Write};
 use std::path::{Component, Path, PathBuf};
 use std::pin::Pin;
 use std::sync::Arc;
@@ -355,6 +355,10 @@ pub struct File {
     inner: tfs::File,
     #[cfg_attr(feature = "enable-serde", serde(skip_serializing))]
     inner_std: fs::File,
+    /// Opened with `O_APPEND` the first time something is appended, so the
+    /// host can append atomically even though `inner` isn't in append mode
+    #[cfg_attr(feature = "enable-serde", serde(skip))]
+    append: Option<fs::File>,
     pub host_path: PathBuf,
     #[cfg(feature = "enable-serde")]
     flags: u16,
@@ -402,6 +406,7 @@ impl<'de> Deserialize<'de> for File {
                     handle: Handle::current(),
                     inner: tokio::fs::File::from_std(inner.try_clone().unwrap()),
                     inner_std: inner,
+                    append: None,
                     host_path,
                     flags,
                 })
@@ -441,6 +446,7 @@ impl<'de> Deserialize<'de> for File {
                     handle: Handle::current(),
                     inner: tokio::fs::File::from_std(inner.try_clone().unwrap()),
                     inner_std: inner,
+                    append: None,
                     host_path,
                     flags,
                 })
@@ -485,6 +491,7 @@ impl File {
             handle,
             inner_std: file,
             inner: async_file,
+            append: None,
             host_path,
             #[cfg(feature = "enable-serde")]
             flags: _flags,
@@ -495,6 +502,18 @@ impl File {
         // FIXME: no unwrap!
         self.inner_std.metadata().unwrap()
     }
+
+    /// Flushes the writes handed to `inner` if that can be done without
+    /// blocking. Returns [`None`] while some are still in flight, since any
+    /// data written through `inner_std` has to go after them.
+    fn flush_without_blocking(&mut self) -> Option<io::Result<()>> {
+        let _guard = Handle::try_current().map_err(|_| self.handle.enter());
+        let mut cx = Context::from_waker(futures::task::noop_waker_ref());
+        match Pin::new(&mut self.inner).poll_flush(&mut cx) {
+            Poll::Ready(ret) => Some(ret),
+            Poll::Pending => None,
+        }
+    }
 }
 
 //#[cfg_attr(feature = "enable-serde", typetag::serde)]
@@ -569,6 +588,60 @@ impl VirtualFile for File {
     fn poll_write_ready(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<usize>> {
         Poll::Ready(Ok(8192))
     }
+
+    fn write_vectored_at(
+        &mut self,
+        offset: u64,
+        bufs: &[io::IoSlice<'_>],
+    ) -> Option<io::Result<usize>> {
+        if let Err(err) = self.flush_without_blocking()? {
+            return Some(Err(err));
+        }
+
+        // Files opened in append mode ignore the position when writing
+        Some(
+            self.inner_std
+                .seek(io::SeekFrom::Start(offset))
+                .and_then(|_| self.inner_std.write_vectored(bufs)),
+        )
+    }
+
+    fn append_vectored(&mut self, bufs: &[io::IoSlice<'_>]) -> Option<io::Result<u64>> {
+        if let Err(err) = self.flush_without_blocking()? {
+            return Some(Err(err));
+        }
+
+        let append = match &mut self.append {
+            Some(append) => append,
+            None => {
+                let append = fs::OpenOptions::new()
+                    .append(true)
+                    .open(&self.host_path)
+                    .ok()?;
+                self.append.insert(append)
+            }
+        };
+
+        Some(append_all(append, bufs))
+    }
+}
+
+/// Writes all of the buffers to a file opened with `O_APPEND` and returns
+/// the offset they were written at. Each `write` moves to the end and writes
+/// in one step, so the data is only split up if the host writes part of it.
+fn append_all(file: &mut fs::File, bufs: &[io::IoSlice<'_>]) -> io::Result<u64> {
+    let len = bufs.iter().map(|buf| buf.len()).sum::<usize>();
+    let mut written = file.write_vectored(bufs)?;
+    for buf in bufs {
+        if written >= buf.len() {
+            written -= buf.len();
+            continue;
+        }
+        file.write_all(&buf[written..])?;
+        written = 0;
+    }
+    let end = file.stream_position()?;
+    Ok(end - len as u64)
 }
 
 impl AsyncRead for File {
@@ -21,7 +21,9 @@ pub mod arc_box_file;
 pub mod arc_file;
 pub mod arc_fs;
 pub mod buffer_file;
+pub mod buffered_write_file;
 pub mod builder;
+mod caching_fs;
 pub mod combine_file;
 pub mod cow_file;
 pub mod dual_write_file;
@@ -32,6 +34,7 @@ pub mod mem_fs;
 pub mod null_file;
 pub mod passthru_fs;
 pub mod random_file;
+pub mod shm_fs;
 pub mod special_file;
 pub mod tmp_fs;
 pub mod union_fs;
@@ -54,7 +57,9 @@ pub use arc_box_file::*;
 pub use arc_file::*;
 pub use arc_fs::*;
 pub use buffer_file::*;
+pub use buffered_write_file::*;
 pub use builder::*;
+pub use caching_fs::CachingFileSystem;
 pub use combine_file::*;
 pub use cow_file::*;
 pub use dual_write_file::*;
@@ -64,6 +69,7 @@ pub use null_file::*;
 pub use overlay_fs::OverlayFileSystem;
 pub use passthru_fs::*;
 pub use pipe::*;
+pub use shm_fs::{SharedMemoryFileSystem, SharedObject};
 pub use special_file::*;
 pub use static_file::StaticFile;
 pub use tmp_fs::*;
@@ -102,6 +108,24 @@ pub trait FileSystem: fmt::Debug + Send + Sync + 'static + Upcastable {
 
     fn mount(&self, name: String, path: &Path, fs: Box<dyn FileSystem + Send + Sync>)
         -> Result<()>;
+
+    /// Creates an unnamed, readable and writable file in the directory at
+    /// `path` (like `O_TMPFILE`).
+    ///
+    /// The file has no directory entry and is freed once the returned handle
+    /// (and all of its clones) are dropped, unless it is given a name with
+    /// [`FileSystem::link_tmpfile()`] first.
+    fn create_tmpfile(&self, path: &Path) -> Result<Box<dyn VirtualFile + Send + Sync + 'static>> {
+        let _ = path;
+        Err(FsError::Unsupported)
+    }
+
+    /// Gives a file created by [`FileSystem::create_tmpfile()`] a name, so it
+    /// outlives its handles (like `linkat()` with `AT_EMPTY_PATH`).
+    fn link_tmpfile(&self, file: &dyn VirtualFile, path: &Path) -> Result<()> {
+        let _ = (file, path);
+        Err(FsError::Unsupported)
+    }
 }
 
 impl dyn FileSystem + 'static {
@@ -165,6 +189,14 @@ where
     ) -> Result<()> {
         (**self).mount(name, path, fs)
     }
+
+    fn create_tmpfile(&self, path: &Path) -> Result<Box<dyn VirtualFile + Send + Sync + 'static>> {
+        (**self).create_tmpfile(path)
+    }
+
+    fn link_tmpfile(&self, file: &dyn VirtualFile, path: &Path) -> Result<()> {
+        (**self).link_tmpfile(file, path)
+    }
 }
 
 pub trait FileOpener {
@@ -385,6 +417,28 @@ pub trait VirtualFile:
         Err(std::io::ErrorKind::Unsupported.into())
     }
 
+    /// Writes the buffers at `offset` (or at the end for files opened in
+    /// append mode) without blocking, which lets callers write straight out
+    /// of memory they only borrow for the duration of the call. Returns
+    /// [`None`] when the file can't do this right now, in which case the data
+    /// has to go through [`AsyncSeek`] and [`AsyncWrite`] instead.
+    fn write_vectored_at(
+        &mut self,
+        _offset: u64,
+        _bufs: &[io::IoSlice<'_>],
+    ) -> Option<io::Result<usize>> {
+        None
+    }
+
+    /// Writes all of the buffers at the end of the file in a single step, so
+    /// concurrent appends (through this or any other handle to the same file)
+    /// can't overwrite or interleave with each other, whether or not the file
+    /// was opened in append mode. Returns the offset the data was written at,
+    /// or [`None`] when the file can't append atomically.
+    fn append_vectored(&mut self, _bufs: &[io::IoSlice<'_>]) -> Option<io::Result<u64>> {
+        None
+    }
+
     /// This method will copy a file from a source to this destination where
     /// the default is to do a straight byte copy however file system implementors
     /// may optimize this to do a zero copy
@@ -561,8 +615,10 @@ impl From<io::Error> for FsError {
             io::ErrorKind::UnexpectedEof => FsError::UnexpectedEof,
             io::ErrorKind::WouldBlock => FsError::WouldBlock,
             io::ErrorKind::WriteZero => FsError::WriteZero,
-            // NOTE: Add this once the "io_error_more" Rust feature is stabilized
-            // io::ErrorKind::StorageFull => FsError::StorageFull,
+            io::ErrorKind::NotADirectory => FsError::BaseNotDirectory,
+            io::ErrorKind::DirectoryNotEmpty => FsError::DirectoryNotEmpty,
+            io::ErrorKind::StorageFull => FsError::StorageFull,
+            io::ErrorKind::Unsupported => FsError::Unsupported,
             io::ErrorKind::Other => FsError::IOError,
             // if the following triggers, a new error type was added to this non-exhaustive enum
             _ => FsError::UnknownError,
@@ -591,17 +647,15 @@ impl From<FsError> for io::Error {
             FsError::WouldBlock => io::ErrorKind::WouldBlock,
             FsError::WriteZero => io::ErrorKind::WriteZero,
             FsError::IOError => io::ErrorKind::Other,
-            FsError::BaseNotDirectory => io::ErrorKind::Other,
+            FsError::BaseNotDirectory => io::ErrorKind::NotADirectory,
             FsError::NotAFile => io::ErrorKind::Other,
             FsError::InvalidFd => io::ErrorKind::Other,
             FsError::Lock => io::ErrorKind::Other,
             FsError::NoDevice => io::ErrorKind::Other,
-            FsError::DirectoryNotEmpty => io::ErrorKind::Other,
+            FsError::DirectoryNotEmpty => io::ErrorKind::DirectoryNotEmpty,
             FsError::UnknownError => io::ErrorKind::Other,
-            FsError::StorageFull => io::ErrorKind::Other,
+            FsError::StorageFull => io::ErrorKind::StorageFull,
             FsError::Unsupported => io::ErrorKind::Unsupported,
-            // NOTE: Add this once the "io_error_more" Rust feature is stabilized
-            // FsError::StorageFull => io::ErrorKind::StorageFull,
         };
         kind.into()
     }
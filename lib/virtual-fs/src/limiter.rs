@@ -1,4 +1,7 @@
-use std::sync::Arc;
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
 
 use crate::FsError;
 
@@ -12,6 +15,54 @@ pub trait FsMemoryLimiter: Send + Sync + std::fmt::Debug {
 
 pub type DynFsMemoryLimiter = Arc<dyn FsMemoryLimiter + Send + Sync>;
 
+/// A [`FsMemoryLimiter`] that fails allocations once a fixed number of bytes
+/// is in use.
+///
+/// Note that memory is only tracked when the `tracking` feature is enabled.
+#[derive(Debug)]
+pub struct FixedMemoryLimiter {
+    limit: usize,
+    used: AtomicUsize,
+}
+
+impl FixedMemoryLimiter {
+    pub fn new(limit: usize) -> Self {
+        Self {
+            limit,
+            used: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+
+    /// The number of bytes currently allocated by the file system.
+    pub fn used(&self) -> usize {
+        self.used.load(Ordering::Acquire)
+    }
+}
+
+impl FsMemoryLimiter for FixedMemoryLimiter {
+    fn on_grow(&self, grown_bytes: usize) -> std::result::Result<(), FsError> {
+        // The memory has already been allocated by the time we get here, so
+        // it is counted even if this fails and released again on shrink.
+        let used = self.used.fetch_add(grown_bytes, Ordering::AcqRel) + grown_bytes;
+        if used > self.limit {
+            return Err(FsError::StorageFull);
+        }
+        Ok(())
+    }
+
+    fn on_shrink(&self, shrunk_bytes: usize) {
+        let _ = self
+            .used
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |used| {
+                Some(used.saturating_sub(shrunk_bytes))
+            });
+    }
+}
+
 #[cfg(feature = "tracking")]
 mod tracked_vec {
     use crate::FsError;
@@ -34,6 +34,11 @@ pub(super) struct FileHandle {
     append_mode: bool,
     cursor: u64,
     arc_file: Option<Result<Box<dyn VirtualFile + Send + Sync + 'static>>>,
+    /// Set for files created by [`FileSystem::create_tmpfile()`], so they
+    /// are freed with their last handle.
+    ///
+    /// [`FileSystem::create_tmpfile()`]: crate::FileSystem::create_tmpfile
+    tmpfile: Option<Arc<TmpFileGuard>>,
 }
 
 impl Clone for FileHandle {
@@ -46,6 +51,7 @@ impl Clone for FileHandle {
             append_mode: self.append_mode,
             cursor: self.cursor,
             arc_file: None,
+            tmpfile: self.tmpfile.clone(),
         }
     }
 }
@@ -67,9 +73,31 @@ impl FileHandle {
             append_mode,
             cursor,
             arc_file: None,
+            tmpfile: None,
         }
     }
 
+    /// Creates a read/write handle to an unnamed file, which is removed from
+    /// the file system once the last clone of the handle is dropped (unless
+    /// it got linked into a directory in the meantime).
+    pub(super) fn new_tmpfile(inode: Inode, filesystem: FileSystem) -> Self {
+        Self {
+            tmpfile: Some(Arc::new(TmpFileGuard {
+                inode,
+                filesystem: filesystem.clone(),
+            })),
+            ..Self::new(inode, filesystem, true, true, false, 0)
+        }
+    }
+
+    pub(super) fn inode(&self) -> Inode {
+        self.inode
+    }
+
+    pub(super) fn filesystem(&self) -> &FileSystem {
+        &self.filesystem
+    }
+
     fn lazy_load_arc_file_mut(&mut self) -> Result<&mut dyn VirtualFile> {
         if self.arc_file.is_none() {
             let fs = match self.filesystem.inner.read() {
@@ -100,6 +128,90 @@ impl FileHandle {
             .map_err(|err| *err)?
             .as_mut())
     }
+
+    /// Seeks to `position` and writes the buffers while holding the lock on
+    /// the filesystem, so nothing else can write to the file in between.
+    /// Returns where the data was written and how much of it.
+    fn write_vectored_from(
+        &mut self,
+        position: io::SeekFrom,
+        bufs: &[io::IoSlice<'_>],
+    ) -> Option<io::Result<(u64, usize)>> {
+        if !self.writable {
+            return Some(Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                format!(
+                    "the file (inode `{}) doesn't have the `write` permission",
+                    self.inode
+                ),
+            )));
+        }
+
+        let mut cursor = self.cursor;
+        let ret = {
+            let mut fs = match self.filesystem.inner.write() {
+                Ok(fs) => fs,
+                Err(_) => {
+                    return Some(Err(io::Error::new(
+                        io::ErrorKind::Other,
+                        "failed to acquire a write lock",
+                    )))
+                }
+            };
+
+            // Each buffer is copied once, straight into the file
+            let inode = fs.storage.get_mut(self.inode);
+            match inode {
+                Some(Node::File(node)) => {
+                    let mut write = || {
+                        let start = node.file.seek(position, &mut cursor)?;
+                        let mut written = 0;
+                        for buf in bufs {
+                            written += node.file.write(buf, &mut cursor)?;
+                        }
+                        Ok((start, written))
+                    };
+                    let ret = write();
+                    node.metadata.len = node.file.len().try_into().unwrap();
+                    ret
+                }
+                Some(Node::OffloadedFile(node)) => {
+                    let mut write = || {
+                        let start = node.file.seek(position, &mut cursor)?;
+                        let mut written = 0;
+                        for buf in bufs {
+                            written += node.file.write(OffloadWrite::Buffer(buf), &mut cursor)?;
+                        }
+                        Ok((start, written))
+                    };
+                    let ret = write();
+                    node.metadata.len = node.file.len();
+                    ret
+                }
+                _ => return None,
+            }
+        };
+        self.cursor = cursor;
+        Some(ret)
+    }
+}
+
+/// Frees an unnamed file once all of its handles are gone.
+#[derive(Debug)]
+struct TmpFileGuard {
+    inode: Inode,
+    filesystem: FileSystem,
+}
+
+impl Drop for TmpFileGuard {
+    fn drop(&mut self) {
+        if let Ok(mut fs) = self.filesystem.inner.write() {
+            // Files that got linked are no longer tracked as temporary
+            if fs.tmpfiles.remove(&self.inode) {
+                fs.storage.remove(self.inode);
+            }
+        }
+    }
 }
 
 impl VirtualFile for FileHandle {
@@ -507,6 +619,25 @@ impl VirtualFile for FileHandle {
         self.cursor = cursor;
         Ok(())
     }
+
+    fn write_vectored_at(
+        &mut self,
+        offset: u64,
+        bufs: &[io::IoSlice<'_>],
+    ) -> Option<io::Result<usize>> {
+        let position = if self.append_mode {
+            io::SeekFrom::End(0)
+        } else {
+            io::SeekFrom::Start(offset)
+        };
+        self.write_vectored_from(position, bufs)
+            .map(|ret| ret.map(|(_, written)| written))
+    }
+
+    fn append_vectored(&mut self, bufs: &[io::IoSlice<'_>]) -> Option<io::Result<u64>> {
+        self.write_vectored_from(io::SeekFrom::End(0), bufs)
+            .map(|ret| ret.map(|(start, _)| start))
+    }
 }
 
 #[cfg(test)]
@@ -923,14 +1054,22 @@ impl AsyncWrite for FileHandle {
                 io::Error::new(io::ErrorKind::Other, "failed to acquire a write lock")
             })?;
 
+            // Appends move to the end of the file under the same lock as the
+            // write, so they can't overwrite each other
             let inode = fs.storage.get_mut(self.inode);
             match inode {
                 Some(Node::File(node)) => {
+                    if self.append_mode {
+                        node.file.seek(io::SeekFrom::End(0), &mut cursor)?;
+                    }
                     let bytes_written = node.file.write(buf, &mut cursor)?;
                     node.metadata.len = node.file.len().try_into().unwrap();
                     bytes_written
                 }
                 Some(Node::OffloadedFile(node)) => {
+                    if self.append_mode {
+                        node.file.seek(io::SeekFrom::End(0), &mut cursor)?;
+                    }
                     let bytes_written = node.file.write(OffloadWrite::Buffer(buf), &mut cursor)?;
                     node.metadata.len = node.file.len();
                     bytes_written
@@ -3,10 +3,10 @@
 use self::offloaded_file::OffloadBackingStore;
 
 use super::*;
-use crate::{DirEntry, FileType, FsError, Metadata, OpenOptions, ReadDir, Result};
+use crate::{DirEntry, FileType, FsError, Metadata, OpenOptions, ReadDir, Result, VirtualFile};
 use futures::future::{BoxFuture, Either};
 use slab::Slab;
-use std::collections::VecDeque;
+use std::collections::{HashSet, VecDeque};
 use std::convert::identity;
 use std::ffi::OsString;
 use std::fmt;
@@ -51,6 +51,29 @@ impl FileSystem {
         lock.canonicalize_without_inode(path)
     }
 
+    /// Removes all files, directories and mounts, leaving an empty root
+    /// directory behind. The memory limiter and backing offload are kept.
+    ///
+    /// Handles that are still open will fail with
+    /// [`FsError::EntryNotFound`] (or refer to a new file that happened to
+    /// reuse their inode), so this should only be used once nothing is using
+    /// the file system anymore.
+    pub fn clear(&self) -> Result<()> {
+        let mut lock = self.inner.write().map_err(|_| FsError::Lock)?;
+        let cleared = FileSystemInner {
+            backing_offload: lock.backing_offload.clone(),
+            limiter: lock.limiter.clone(),
+            ..Default::default()
+        };
+        let old = std::mem::replace(&mut *lock, cleared);
+        drop(lock);
+
+        // Dropping nodes may drop handles to this file system, so the lock
+        // must be released first
+        drop(old);
+        Ok(())
+    }
+
     /// Merge all items from a given source path (directory) of a different file
     /// system into this file system.
     ///
@@ -709,6 +732,137 @@ impl crate::FileSystem for FileSystem {
         let fs: Arc<dyn crate::FileSystem + Send + Sync> = Arc::new(fs);
         self.mount(path.to_owned(), &fs, PathBuf::from("/"))
     }
+
+    fn create_tmpfile(&self, path: &Path) -> Result<Box<dyn VirtualFile + Send + Sync + 'static>> {
+        {
+            // Read lock.
+            let guard = self.inner.read().map_err(|_| FsError::Lock)?;
+
+            // Canonicalize the path.
+            let path = guard.canonicalize_without_inode(path)?;
+
+            // Check the directory exists.
+            if let InodeResolution::Redirect(fs, path) = guard.inode_of_parent(&path)? {
+                drop(guard);
+                return fs.create_tmpfile(path.as_path());
+            }
+        }
+
+        let inode_of_file = {
+            // Write lock.
+            let mut fs = self.inner.write().map_err(|_| FsError::Lock)?;
+
+            let metadata = {
+                let time = time();
+                Metadata {
+                    ft: FileType {
+                        file: true,
+                        ..Default::default()
+                    },
+                    accessed: time,
+                    created: time,
+                    modified: time,
+                    len: 0,
+                }
+            };
+            let inode_of_file = fs.storage.vacant_entry().key();
+
+            // Creating the file in the storage, without adding it to a
+            // directory.
+            let file = if let Some(offload) = fs.backing_offload.clone() {
+                Node::OffloadedFile(OffloadedFileNode {
+                    inode: inode_of_file,
+                    name: OsString::new(),
+                    file: OffloadedFile::new(fs.limiter.clone(), offload),
+                    metadata,
+                })
+            } else {
+                Node::File(FileNode {
+                    inode: inode_of_file,
+                    name: OsString::new(),
+                    file: File::new(fs.limiter.clone()),
+                    metadata,
+                })
+            };
+            let real_inode_of_file = fs.storage.insert(file);
+
+            assert_eq!(
+                inode_of_file, real_inode_of_file,
+                "new file inode should have been correctly calculated",
+            );
+
+            fs.tmpfiles.insert(inode_of_file);
+
+            inode_of_file
+        };
+
+        Ok(Box::new(FileHandle::new_tmpfile(
+            inode_of_file,
+            self.clone(),
+        )))
+    }
+
+    fn link_tmpfile(&self, file: &dyn VirtualFile, path: &Path) -> Result<()> {
+        let (inode_of_parent, name_of_file) = {
+            // Read lock.
+            let guard = self.inner.read().map_err(|_| FsError::Lock)?;
+
+            // Canonicalize the path without checking the path exists,
+            // because it's about to be created.
+            let path = guard.canonicalize_without_inode(path)?;
+
+            // Check the path has a parent.
+            let parent_of_path = path.parent().ok_or(FsError::BaseNotDirectory)?;
+
+            // Check the file name.
+            let name_of_file = path
+                .file_name()
+                .ok_or(FsError::InvalidInput)?
+                .to_os_string();
+
+            // Find the parent inode.
+            let inode_of_parent = match guard.inode_of_parent(parent_of_path)? {
+                InodeResolution::Found(a) => a,
+                InodeResolution::Redirect(fs, mut parent_path) => {
+                    drop(guard);
+                    parent_path.push(name_of_file);
+                    return fs.link_tmpfile(file, parent_path.as_path());
+                }
+            };
+
+            (inode_of_parent, name_of_file)
+        };
+
+        // Only files created by this file system can be linked into it.
+        let handle = file
+            .upcast_any_ref()
+            .downcast_ref::<FileHandle>()
+            .filter(|handle| Arc::ptr_eq(&handle.filesystem().inner, &self.inner))
+            .ok_or(FsError::InvalidInput)?;
+
+        {
+            // Write lock.
+            let mut fs = self.inner.write().map_err(|_| FsError::Lock)?;
+
+            if fs
+                .as_parent_get_position_and_inode(inode_of_parent, &name_of_file)?
+                .is_some()
+            {
+                return Err(FsError::AlreadyExists);
+            }
+
+            // The file must still be unnamed.
+            if !fs.tmpfiles.remove(&handle.inode()) {
+                return Err(FsError::InvalidInput);
+            }
+
+            // Adding the file to its new parent.
+            fs.update_node_name(handle.inode(), name_of_file)?;
+            fs.add_child_to_node(inode_of_parent, handle.inode())?;
+        }
+
+        Ok(())
+    }
 }
 
 impl fmt::Debug for FileSystem {
@@ -725,6 +879,9 @@ pub(super) struct FileSystemInner {
     pub(super) storage: Slab<Node>,
     pub(super) backing_offload: Option<OffloadBackingStore>,
     pub(super) limiter: Option<crate::limiter::DynFsMemoryLimiter>,
+    /// Files created by [`crate::FileSystem::create_tmpfile()`] that haven't
+    /// been linked into a directory yet.
+    pub(super) tmpfiles: HashSet<Inode>,
 }
 
 #[derive(Debug)]
@@ -1129,6 +1286,7 @@ impl Default for FileSystemInner {
             storage: slab,
             backing_offload: None,
             limiter: None,
+            tmpfiles: HashSet::new(),
         }
     }
 }
@@ -1151,10 +1309,10 @@ impl DirectoryMustBeEmpty {
 
 #[cfg(test)]
 mod test_filesystem {
-    use std::path::Path;
+    use std::{io, path::Path};
 
     use shared_buffer::OwnedBuffer;
-    use tokio::io::AsyncReadExt;
+    use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
 
     use crate::{mem_fs::*, ops, DirEntry, FileSystem as FS, FileType, FsError};
 
@@ -1731,6 +1889,102 @@ mod test_filesystem {
         );
     }
 
+    #[tokio::test]
+    async fn test_tmpfile() {
+        let fs = FileSystem::default();
+        let limiter = Arc::new(crate::limiter::FixedMemoryLimiter::new(1024 * 1024));
+        fs.set_memory_limiter(limiter.clone());
+
+        let mut file = fs.create_tmpfile(path!("/")).unwrap();
+        file.write_all(&[1; 4096]).await.unwrap();
+
+        {
+            let fs_inner = fs.inner.read().unwrap();
+
+            assert_eq!(fs_inner.storage.len(), 2, "storage has the unnamed file");
+            assert!(
+                matches!(
+                    fs_inner.storage.get(ROOT_INODE),
+                    Some(Node::Directory(DirectoryNode { children, .. })) if children.is_empty()
+                ),
+                "`/` doesn't contain the unnamed file",
+            );
+        }
+        assert!(fs.read_dir(path!("/")).unwrap().next().is_none());
+
+        file.seek(io::SeekFrom::Start(0)).await.unwrap();
+        let mut contents = Vec::new();
+        file.read_to_end(&mut contents).await.unwrap();
+        assert_eq!(contents, [1; 4096]);
+        if cfg!(feature = "tracking") {
+            assert!(limiter.used() >= 4096);
+        }
+
+        drop(file);
+
+        assert_eq!(
+            fs.inner.read().unwrap().storage.len(),
+            1,
+            "the unnamed file was freed",
+        );
+        assert_eq!(limiter.used(), 0, "the memory was reclaimed");
+
+        assert_eq!(
+            fs.create_tmpfile(path!("/foo")).map(|_| ()),
+            Err(FsError::EntryNotFound),
+            "creating a file in a directory that doesn't exist",
+        );
+    }
+
+    #[tokio::test]
+    async fn test_link_tmpfile() {
+        let fs = FileSystem::default();
+        fs.create_dir(path!("/tmp")).unwrap();
+
+        let mut file = fs.create_tmpfile(path!("/tmp")).unwrap();
+        file.write_all(b"hello").await.unwrap();
+        let clone = FileHandle::clone(
+            (*file)
+                .upcast_any_ref()
+                .downcast_ref::<FileHandle>()
+                .unwrap(),
+        );
+
+        assert_eq!(fs.link_tmpfile(&*file, path!("/tmp/out")), Ok(()));
+        assert_eq!(
+            fs.link_tmpfile(&*file, path!("/tmp/out2")),
+            Err(FsError::InvalidInput),
+            "a file can only be linked once",
+        );
+
+        // The file outlives its handles now
+        drop(file);
+        drop(clone);
+
+        let mut contents = String::new();
+        fs.new_open_options()
+            .read(true)
+            .open(path!("/tmp/out"))
+            .unwrap()
+            .read_to_string(&mut contents)
+            .await
+            .unwrap();
+        assert_eq!(contents, "hello");
+
+        let file = fs.create_tmpfile(path!("/")).unwrap();
+        assert_eq!(
+            fs.link_tmpfile(&*file, path!("/tmp/out")),
+            Err(FsError::AlreadyExists),
+            "linking over an existing file",
+        );
+        let other = FileSystem::default();
+        assert_eq!(
+            other.link_tmpfile(&*file, path!("/out")),
+            Err(FsError::InvalidInput),
+            "linking a file from another file system",
+        );
+    }
+
     #[tokio::test]
     async fn test_readdir() {
         let fs = FileSystem::default();
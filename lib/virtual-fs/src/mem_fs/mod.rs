@@ -158,7 +158,7 @@ impl Node {
     }
 }
 
-fn time() -> u64 {
+pub(crate) fn time() -> u64 {
     // SAFETY: It's very unlikely that the system returns a time that
     // is before `UNIX_EPOCH` :-).
     SystemTime::now()
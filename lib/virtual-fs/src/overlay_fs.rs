@@ -423,6 +423,19 @@ where
     ) -> Result<(), FsError> {
         Err(FsError::Unsupported)
     }
+
+    fn create_tmpfile(
+        &self,
+        path: &Path,
+    ) -> Result<Box<dyn VirtualFile + Send + Sync + 'static>, FsError> {
+        // Unnamed files are always created in the primary, so they can be
+        // linked into it later on
+        self.primary.create_tmpfile(path)
+    }
+
+    fn link_tmpfile(&self, file: &dyn VirtualFile, path: &Path) -> Result<(), FsError> {
+        self.primary.link_tmpfile(file, path)
+    }
 }
 
 impl<P, S> FileOpener for OverlayFileSystem<P, S>
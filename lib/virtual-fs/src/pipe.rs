@@ -1,9 +1,11 @@
 use bytes::{Buf, Bytes};
 use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 use std::sync::Mutex;
 use std::task::Context;
 use std::task::Poll;
+use std::task::Waker;
 use std::{io::IoSlice, sync::MutexGuard};
 use std::{
     io::{self, Read, Seek, SeekFrom},
@@ -36,6 +38,8 @@ pub struct PipeTx {
     /// Sends bytes down the pipe
     tx: Option<mpsc::UnboundedSender<Vec<u8>>>,
     rx_end: Weak<Mutex<PipeReceiver>>,
+    /// Limits how much data can be waiting in the pipe, if it's bounded
+    capacity: Option<Arc<PipeCapacity>>,
 }
 
 #[derive(Debug, Clone)]
@@ -53,19 +57,20 @@ impl PipeRx {
         // Should return how much actual data was read from the provided slice
         write: impl FnOnce(&[u8]) -> Option<usize>,
     ) -> Option<usize> {
-        rx.buffer.as_mut().and_then(|read_buffer| {
-            let buf_len = read_buffer.len();
-            if buf_len > 0 {
-                let mut read = buf_len.min(max_len);
-                let inner_buf = &read_buffer[..read];
-                // read = ?;
-                read = write(inner_buf)?;
-                read_buffer.advance(read);
-                Some(read)
-            } else {
-                None
-            }
-        })
+        let read_buffer = rx.buffer.as_mut()?;
+        let buf_len = read_buffer.len();
+        if buf_len == 0 {
+            return None;
+        }
+        let inner_buf = &read_buffer[..buf_len.min(max_len)];
+        let read = write(inner_buf)?;
+        read_buffer.advance(read);
+
+        // Make room for the writers that are waiting on a bounded pipe
+        if let Some(capacity) = rx.capacity.as_ref() {
+            capacity.release(read);
+        }
+        Some(read)
     }
 
     pub fn close(&mut self) {
@@ -152,21 +157,140 @@ struct PipeReceiver {
     chan: mpsc::UnboundedReceiver<Vec<u8>>,
     buffer: Option<Bytes>,
     interest_handler: Option<Box<dyn InterestHandler>>,
+    capacity: Option<Arc<PipeCapacity>>,
+}
+
+impl Drop for PipeReceiver {
+    fn drop(&mut self) {
+        // Writers waiting for room need to find out that nobody will read
+        if let Some(capacity) = self.capacity.as_ref() {
+            self.chan.close();
+            capacity.wake_writers();
+        }
+    }
+}
+
+/// Keeps track of how many bytes are waiting in a bounded pipe, so that
+/// writers wait for the reader instead of buffering without limit.
+#[derive(Debug)]
+struct PipeCapacity {
+    limit: usize,
+    buffered: AtomicUsize,
+    writers: Mutex<Vec<Waker>>,
+}
+
+impl PipeCapacity {
+    fn new(limit: usize) -> Self {
+        Self {
+            limit: limit.max(1),
+            buffered: AtomicUsize::new(0),
+            writers: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Reserves room for up to `len` bytes and returns how many bytes were
+    /// reserved, or [`None`] if the pipe is full.
+    fn try_reserve(&self, len: usize) -> Option<usize> {
+        let mut reserved = 0;
+        self.buffered
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |buffered| {
+                reserved = self.limit.saturating_sub(buffered).min(len);
+                Some(buffered + reserved)
+            })
+            .ok();
+        if reserved == 0 && len > 0 {
+            None
+        } else {
+            Some(reserved)
+        }
+    }
+
+    /// Like [`PipeCapacity::try_reserve()`], but registers the task to be
+    /// woken up once the reader makes room.
+    fn poll_reserve(&self, cx: &mut Context<'_>, len: usize) -> Poll<usize> {
+        if let Some(reserved) = self.try_reserve(len) {
+            return Poll::Ready(reserved);
+        }
+        self.register(cx.waker());
+        // The reader might have made room in the meantime
+        match self.try_reserve(len) {
+            Some(reserved) => Poll::Ready(reserved),
+            None => Poll::Pending,
+        }
+    }
+
+    /// How many bytes can be written without waiting, registering the task
+    /// to be woken up once the reader makes room if that is none.
+    fn poll_available(&self, cx: &mut Context<'_>) -> Poll<usize> {
+        let available = || {
+            self.limit
+                .saturating_sub(self.buffered.load(Ordering::Acquire))
+        };
+        if available() > 0 {
+            return Poll::Ready(available());
+        }
+        self.register(cx.waker());
+        match available() {
+            0 => Poll::Pending,
+            available => Poll::Ready(available),
+        }
+    }
+
+    fn register(&self, waker: &Waker) {
+        let mut writers = self.writers.lock().unwrap();
+        if !writers.iter().any(|w| w.will_wake(waker)) {
+            writers.push(waker.clone());
+        }
+    }
+
+    fn release(&self, len: usize) {
+        if len == 0 {
+            return;
+        }
+        self.buffered.fetch_sub(len, Ordering::AcqRel);
+        self.wake_writers();
+    }
+
+    fn wake_writers(&self) {
+        let writers = std::mem::take(&mut *self.writers.lock().unwrap());
+        for waker in writers {
+            waker.wake();
+        }
+    }
 }
 
 impl Pipe {
     pub fn new() -> Self {
+        Self::with_capacity_opt(None)
+    }
+
+    /// Creates a pipe that holds at most `capacity` bytes that haven't been
+    /// read yet.
+    ///
+    /// Once it's full, asynchronous writes wait for the reader to catch up
+    /// (and [`VirtualFile::poll_write_ready()`] reports the pipe as not
+    /// writable), while blocking writes fail with
+    /// [`io::ErrorKind::WouldBlock`]. This keeps a slow reader from making
+    /// the pipe grow without limit.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::with_capacity_opt(Some(capacity))
+    }
+
+    fn with_capacity_opt(capacity: Option<usize>) -> Self {
         let (tx, rx) = mpsc::unbounded_channel();
+        let capacity = capacity.map(|limit| Arc::new(PipeCapacity::new(limit)));
 
         let recv = Arc::new(Mutex::new(PipeReceiver {
             chan: rx,
             buffer: None,
             interest_handler: None,
+            capacity: capacity.clone(),
         }));
         Pipe {
             send: PipeTx {
                 tx: Some(tx),
                 rx_end: Arc::downgrade(&recv),
+                capacity,
             },
             recv: PipeRx { rx: Some(recv) },
         }
@@ -181,6 +305,17 @@ impl Pipe {
         (end1, end2)
     }
 
+    /// Like [`Pipe::channel()`], but each direction holds at most `capacity`
+    /// bytes (see [`Pipe::with_capacity()`]).
+    pub fn channel_with_capacity(capacity: usize) -> (Pipe, Pipe) {
+        let (tx1, rx1) = Pipe::with_capacity(capacity).split();
+        let (tx2, rx2) = Pipe::with_capacity(capacity).split();
+
+        let end1 = Pipe::combine(tx1, rx2);
+        let end2 = Pipe::combine(tx2, rx1);
+        (end1, end2)
+    }
+
     pub fn split(self) -> (PipeTx, PipeRx) {
         (self.send, self.recv)
     }
@@ -218,7 +353,7 @@ impl PipeTx {
         _ = self.tx.take();
     }
 
-    pub fn poll_write_ready(self: Pin<&mut Self>) -> Poll<io::Result<usize>> {
+    pub fn poll_write_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<usize>> {
         let Some(ref tx) = self.tx else {
             return Poll::Ready(Err(std::io::Error::new(
                 std::io::ErrorKind::BrokenPipe,
@@ -227,10 +362,24 @@ impl PipeTx {
         };
 
         if tx.is_closed() {
-            Poll::Ready(Ok(0))
-        } else {
-            Poll::Ready(Ok(8192))
+            return Poll::Ready(Ok(0));
+        }
+        match self.capacity.as_ref() {
+            Some(capacity) => capacity.poll_available(cx).map(Ok),
+            None => Poll::Ready(Ok(8192)),
+        }
+    }
+
+    /// Sends the data that room was reserved for down the pipe.
+    fn send_reserved(&self, tx: &mpsc::UnboundedSender<Vec<u8>>, buf: &[u8]) -> io::Result<()> {
+        if tx.send(buf.to_vec()).is_err() {
+            if let Some(capacity) = self.capacity.as_ref() {
+                capacity.release(buf.len());
+            }
+            return Err(std::io::ErrorKind::BrokenPipe.into());
         }
+        self.mark_other_end_readable();
+        Ok(())
     }
 
     fn mark_other_end_readable(&self) {
@@ -328,10 +477,18 @@ impl std::io::Write for PipeTx {
             ));
         };
 
-        tx.send(buf.to_vec())
-            .map_err(|_| Into::<std::io::Error>::into(std::io::ErrorKind::BrokenPipe))?;
-        self.mark_other_end_readable();
-        Ok(buf.len())
+        if tx.is_closed() {
+            return Err(std::io::ErrorKind::BrokenPipe.into());
+        }
+        let len = match self.capacity.as_ref() {
+            Some(capacity) => capacity
+                .try_reserve(buf.len())
+                .ok_or(std::io::ErrorKind::WouldBlock)?,
+            None => buf.len(),
+        };
+
+        self.send_reserved(tx, &buf[..len])?;
+        Ok(len)
     }
 
     fn flush(&mut self) -> std::io::Result<()> {
@@ -405,7 +562,7 @@ impl AsyncWrite for Pipe {
 impl AsyncWrite for PipeTx {
     fn poll_write(
         self: Pin<&mut Self>,
-        _cx: &mut Context<'_>,
+        cx: &mut Context<'_>,
         buf: &[u8],
     ) -> Poll<io::Result<usize>> {
         let Some(ref tx) = self.tx else {
@@ -415,15 +572,22 @@ impl AsyncWrite for PipeTx {
             )));
         };
 
-        match tx.send(buf.to_vec()) {
-            Ok(()) => {
-                self.mark_other_end_readable();
-                Poll::Ready(Ok(buf.len()))
-            }
-            Err(_) => Poll::Ready(Err(Into::<std::io::Error>::into(
-                std::io::ErrorKind::BrokenPipe,
-            ))),
+        if tx.is_closed() {
+            return Poll::Ready(Err(std::io::ErrorKind::BrokenPipe.into()));
         }
+        let len = match self.capacity.as_ref() {
+            Some(capacity) => match capacity.poll_reserve(cx, buf.len()) {
+                Poll::Ready(len) => len,
+                // Dropping the reader wakes us up, so check again
+                Poll::Pending if tx.is_closed() => {
+                    return Poll::Ready(Err(std::io::ErrorKind::BrokenPipe.into()))
+                }
+                Poll::Pending => return Poll::Pending,
+            },
+            None => buf.len(),
+        };
+
+        Poll::Ready(self.send_reserved(tx, &buf[..len]).map(|()| len))
     }
 
     fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
@@ -530,11 +694,8 @@ impl VirtualFile for Pipe {
     }
 
     /// Polls the file for when it is available for writing
-    fn poll_write_ready(
-        mut self: Pin<&mut Self>,
-        _cx: &mut Context<'_>,
-    ) -> Poll<io::Result<usize>> {
-        Pin::new(&mut self.send).poll_write_ready()
+    fn poll_write_ready(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.send).poll_write_ready(cx)
     }
 }
 
@@ -601,3 +762,76 @@ impl DuplexPipe {
 /// Shared version of BidiPipe for situations where you need
 /// to emulate the old behaviour of `Pipe` (both send and recv on one channel).
 pub type WasiBidirectionalSharedPipePair = ArcFile<DuplexPipe>;
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use futures::task::noop_waker_ref;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    // Pipes are both blocking and asynchronous readers and writers, so the
+    // calls below spell out which one they use
+
+    use super::*;
+
+    #[test]
+    fn bounded_pipes_refuse_blocking_writes_when_full() {
+        let (mut tx, mut rx) = Pipe::with_capacity(4).split();
+
+        assert_eq!(Write::write(&mut tx, b"abcdef").unwrap(), 4);
+        let err = Write::write(&mut tx, b"gh").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::WouldBlock);
+
+        let mut buf = [0; 3];
+        assert_eq!(rx.try_read(&mut buf), Some(3));
+        assert_eq!(Write::write(&mut tx, b"ghij").unwrap(), 3);
+    }
+
+    #[test]
+    fn bounded_pipes_are_not_write_ready_when_full() {
+        let (mut tx, mut rx) = Pipe::with_capacity(4).split();
+        let mut cx = Context::from_waker(noop_waker_ref());
+
+        Write::write_all(&mut tx, b"abcd").unwrap();
+        assert!(Pin::new(&mut tx).poll_write_ready(&mut cx).is_pending());
+
+        let mut buf = [0; 1];
+        assert_eq!(rx.try_read(&mut buf), Some(1));
+        assert!(matches!(
+            Pin::new(&mut tx).poll_write_ready(&mut cx),
+            Poll::Ready(Ok(1))
+        ));
+    }
+
+    #[tokio::test]
+    async fn writers_wait_for_the_reader() {
+        let (mut tx, mut rx) = Pipe::with_capacity(4).split();
+
+        let writer = tokio::spawn(async move {
+            AsyncWriteExt::write_all(&mut tx, b"hello world")
+                .await
+                .unwrap();
+        });
+
+        let mut output = Vec::new();
+        AsyncReadExt::read_to_end(&mut rx, &mut output)
+            .await
+            .unwrap();
+        writer.await.unwrap();
+        assert_eq!(output, b"hello world");
+    }
+
+    #[tokio::test]
+    async fn waiting_writers_see_the_reader_go_away() {
+        let (mut tx, rx) = Pipe::with_capacity(4).split();
+        AsyncWriteExt::write_all(&mut tx, b"abcd").await.unwrap();
+
+        let writer = tokio::spawn(async move { AsyncWriteExt::write_all(&mut tx, b"efgh").await });
+        tokio::task::yield_now().await;
+        drop(rx);
+
+        let err = writer.await.unwrap().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::BrokenPipe);
+    }
+}
@@ -0,0 +1,485 @@
+//! POSIX shared memory objects (`shm_open()`), meant to be mounted at
+//! `/dev/shm`.
+//!
+//! Every open handle of an object refers to the same buffer, no matter which
+//! process opened it, so clones of the file system can be handed to all the
+//! processes that should share their objects. Removing an object only removes
+//! its name: handles that are still open keep the buffer alive.
+
+use std::{
+    collections::HashMap,
+    io::{self, SeekFrom},
+    path::{Component, Path, PathBuf},
+    pin::Pin,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, RwLock, RwLockReadGuard, RwLockWriteGuard,
+    },
+    task::{Context, Poll},
+};
+
+use futures::future::BoxFuture;
+use tokio::io::{AsyncRead, AsyncSeek, AsyncWrite};
+
+use crate::{
+    mem_fs::time, DirEntry, FileOpener, FileSystem, FileType, FsError, Metadata, OpenOptions,
+    OpenOptionsConfig, ReadDir, Result, VirtualFile,
+};
+
+/// A flat directory of [`SharedObject`]s.
+#[derive(Debug, Clone, Default)]
+pub struct SharedMemoryFileSystem {
+    objects: Arc<RwLock<HashMap<String, Arc<SharedObject>>>>,
+}
+
+/// The buffer behind a shared memory object.
+#[derive(Debug)]
+pub struct SharedObject {
+    data: RwLock<Vec<u8>>,
+    created: u64,
+    accessed: AtomicU64,
+    modified: AtomicU64,
+}
+
+impl SharedObject {
+    fn new() -> Self {
+        let now = time();
+        SharedObject {
+            data: RwLock::new(Vec::new()),
+            created: now,
+            accessed: AtomicU64::new(now),
+            modified: AtomicU64::new(now),
+        }
+    }
+
+    /// The current size of the object.
+    pub fn len(&self) -> u64 {
+        self.data.read().unwrap().len() as u64
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Read access to the object's contents.
+    pub fn read(&self) -> RwLockReadGuard<'_, Vec<u8>> {
+        self.data.read().unwrap()
+    }
+
+    /// Write access to the object's contents, which every process that has
+    /// it open will see.
+    pub fn write(&self) -> RwLockWriteGuard<'_, Vec<u8>> {
+        self.modified.store(time(), Ordering::Relaxed);
+        self.data.write().unwrap()
+    }
+
+    fn metadata(&self) -> Metadata {
+        Metadata {
+            ft: FileType::new_file(),
+            accessed: self.accessed.load(Ordering::Relaxed),
+            created: self.created,
+            modified: self.modified.load(Ordering::Relaxed),
+            len: self.len(),
+        }
+    }
+}
+
+impl SharedMemoryFileSystem {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Look up the object called `name` (without a leading `/`).
+    pub fn object(&self, name: &str) -> Option<Arc<SharedObject>> {
+        self.objects.read().unwrap().get(name).cloned()
+    }
+
+    /// The names of all objects, sorted.
+    pub fn names(&self) -> Vec<String> {
+        let mut names: Vec<_> = self.objects.read().unwrap().keys().cloned().collect();
+        names.sort();
+        names
+    }
+
+    /// Remove every object, e.g. once none of the processes sharing them are
+    /// left. Handles that are still open keep working.
+    pub fn clear(&self) {
+        self.objects.write().unwrap().clear();
+    }
+
+    /// The name of the object at `path`, or `None` for the directory itself.
+    fn object_name(path: &Path) -> Result<Option<String>> {
+        let mut name = None;
+        for component in path.components() {
+            match component {
+                Component::RootDir | Component::CurDir => {}
+                Component::Normal(n) if name.is_none() => {
+                    name = Some(n.to_str().ok_or(FsError::InvalidInput)?.to_string());
+                }
+                // Objects live directly in the root directory
+                _ => return Err(FsError::EntryNotFound),
+            }
+        }
+        Ok(name)
+    }
+}
+
+impl FileSystem for SharedMemoryFileSystem {
+    fn readlink(&self, path: &Path) -> Result<PathBuf> {
+        self.metadata(path)?;
+        Err(FsError::InvalidInput)
+    }
+
+    fn read_dir(&self, path: &Path) -> Result<ReadDir> {
+        if Self::object_name(path)?.is_some() {
+            return Err(FsError::BaseNotDirectory);
+        }
+
+        let mut entries: Vec<_> = self
+            .objects
+            .read()
+            .unwrap()
+            .iter()
+            .map(|(name, object)| DirEntry {
+                path: path.join(name),
+                metadata: Ok(object.metadata()),
+            })
+            .collect();
+        entries.sort_by(|a, b| a.path.cmp(&b.path));
+        Ok(ReadDir::new(entries))
+    }
+
+    fn create_dir(&self, path: &Path) -> Result<()> {
+        match Self::object_name(path)? {
+            None => Err(FsError::AlreadyExists),
+            Some(_) => Err(FsError::PermissionDenied),
+        }
+    }
+
+    fn remove_dir(&self, path: &Path) -> Result<()> {
+        match Self::object_name(path)? {
+            None => Err(FsError::PermissionDenied),
+            Some(_) => Err(FsError::BaseNotDirectory),
+        }
+    }
+
+    fn rename<'a>(&'a self, from: &'a Path, to: &'a Path) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            let (Some(from), Some(to)) = (Self::object_name(from)?, Self::object_name(to)?) else {
+                return Err(FsError::PermissionDenied);
+            };
+
+            let mut objects = self.objects.write().unwrap();
+            let object = objects.remove(&from).ok_or(FsError::EntryNotFound)?;
+            objects.insert(to, object);
+            Ok(())
+        })
+    }
+
+    fn metadata(&self, path: &Path) -> Result<Metadata> {
+        match Self::object_name(path)? {
+            Some(name) => self
+                .object(&name)
+                .map(|object| object.metadata())
+                .ok_or(FsError::EntryNotFound),
+            None => Ok(Metadata {
+                ft: FileType::new_dir(),
+                ..Default::default()
+            }),
+        }
+    }
+
+    fn symlink_metadata(&self, path: &Path) -> Result<Metadata> {
+        self.metadata(path)
+    }
+
+    fn remove_file(&self, path: &Path) -> Result<()> {
+        let name = Self::object_name(path)?.ok_or(FsError::NotAFile)?;
+        self.objects
+            .write()
+            .unwrap()
+            .remove(&name)
+            .map(|_| ())
+            .ok_or(FsError::EntryNotFound)
+    }
+
+    fn new_open_options(&self) -> OpenOptions {
+        OpenOptions::new(self)
+    }
+
+    fn mount(
+        &self,
+        _name: String,
+        _path: &Path,
+        _fs: Box<dyn FileSystem + Send + Sync>,
+    ) -> Result<()> {
+        Err(FsError::Unsupported)
+    }
+}
+
+impl FileOpener for SharedMemoryFileSystem {
+    fn open(
+        &self,
+        path: &Path,
+        conf: &OpenOptionsConfig,
+    ) -> Result<Box<dyn VirtualFile + Send + Sync + 'static>> {
+        let name = Self::object_name(path)?.ok_or(FsError::NotAFile)?;
+
+        let object = {
+            let mut objects = self.objects.write().unwrap();
+            match objects.get(&name) {
+                Some(_) if conf.create_new => return Err(FsError::AlreadyExists),
+                Some(object) => object.clone(),
+                None if conf.create || conf.create_new => objects
+                    .entry(name.clone())
+                    .or_insert_with(|| Arc::new(SharedObject::new()))
+                    .clone(),
+                None => return Err(FsError::EntryNotFound),
+            }
+        };
+
+        if conf.truncate && conf.write {
+            object.write().clear();
+        }
+
+        Ok(Box::new(SharedMemoryFile {
+            fs: self.clone(),
+            name,
+            object,
+            cursor: 0,
+            readable: conf.read,
+            writable: conf.write || conf.append,
+            append: conf.append,
+        }))
+    }
+}
+
+/// An open handle of a [`SharedObject`].
+#[derive(Debug)]
+pub struct SharedMemoryFile {
+    fs: SharedMemoryFileSystem,
+    name: String,
+    object: Arc<SharedObject>,
+    cursor: u64,
+    readable: bool,
+    writable: bool,
+    append: bool,
+}
+
+impl SharedMemoryFile {
+    /// The object this handle refers to.
+    pub fn object(&self) -> &Arc<SharedObject> {
+        &self.object
+    }
+}
+
+impl VirtualFile for SharedMemoryFile {
+    fn last_accessed(&self) -> u64 {
+        self.object.accessed.load(Ordering::Relaxed)
+    }
+
+    fn last_modified(&self) -> u64 {
+        self.object.modified.load(Ordering::Relaxed)
+    }
+
+    fn created_time(&self) -> u64 {
+        self.object.created
+    }
+
+    fn size(&self) -> u64 {
+        self.object.len()
+    }
+
+    fn set_len(&mut self, new_size: u64) -> Result<()> {
+        let new_size = usize::try_from(new_size).map_err(|_| FsError::InvalidInput)?;
+        self.object.write().resize(new_size, 0);
+        Ok(())
+    }
+
+    fn unlink(&mut self) -> Result<()> {
+        // Only remove the name if it still refers to this object
+        let mut objects = self.fs.objects.write().unwrap();
+        if objects
+            .get(&self.name)
+            .is_some_and(|object| Arc::ptr_eq(object, &self.object))
+        {
+            objects.remove(&self.name);
+        }
+        Ok(())
+    }
+
+    fn poll_read_ready(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<usize>> {
+        let remaining = self.object.len().saturating_sub(self.cursor);
+        Poll::Ready(Ok(remaining as usize))
+    }
+
+    fn poll_write_ready(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<usize>> {
+        Poll::Ready(Ok(8192))
+    }
+}
+
+impl AsyncRead for SharedMemoryFile {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &mut tokio::io::ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        if !self.readable {
+            return Poll::Ready(Err(io::ErrorKind::PermissionDenied.into()));
+        }
+
+        let read = {
+            let data = self.object.read();
+            let start = (self.cursor as usize).min(data.len());
+            let read = (data.len() - start).min(buf.remaining());
+            buf.put_slice(&data[start..start + read]);
+            read
+        };
+        self.cursor += read as u64;
+        self.object.accessed.store(time(), Ordering::Relaxed);
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl AsyncWrite for SharedMemoryFile {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        if !self.writable {
+            return Poll::Ready(Err(io::ErrorKind::PermissionDenied.into()));
+        }
+
+        let end = {
+            let mut data = self.object.write();
+            let start = if self.append {
+                data.len()
+            } else {
+                self.cursor as usize
+            };
+            let end = start + buf.len();
+            if data.len() < end {
+                data.resize(end, 0);
+            }
+            data[start..end].copy_from_slice(buf);
+            end
+        };
+        self.cursor = end as u64;
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl AsyncSeek for SharedMemoryFile {
+    fn start_seek(mut self: Pin<&mut Self>, position: SeekFrom) -> io::Result<()> {
+        let cursor = match position {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(offset) => self.object.len().checked_add_signed(offset),
+            SeekFrom::Current(offset) => self.cursor.checked_add_signed(offset),
+        };
+        self.cursor = cursor.ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "invalid seek to a negative or overflowing position",
+            )
+        })?;
+        Ok(())
+    }
+
+    fn poll_complete(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<u64>> {
+        Poll::Ready(Ok(self.cursor))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn handles_share_the_object() {
+        let fs = SharedMemoryFileSystem::new();
+        let mut writer = fs
+            .new_open_options()
+            .read(true)
+            .write(true)
+            .create_new(true)
+            .open("/buffer")
+            .unwrap();
+        writer.set_len(8).unwrap();
+        writer.write_all(b"hi").await.unwrap();
+
+        // A clone of the file system (e.g. in another process) sees the same
+        // object
+        let mut reader = fs
+            .clone()
+            .new_open_options()
+            .read(true)
+            .write(true)
+            .open("/buffer")
+            .unwrap();
+        let mut buf = Vec::new();
+        reader.read_to_end(&mut buf).await.unwrap();
+        assert_eq!(buf, b"hi\0\0\0\0\0\0");
+
+        reader.seek(SeekFrom::Start(2)).await.unwrap();
+        reader.write_all(b"!").await.unwrap();
+        assert_eq!(&fs.object("buffer").unwrap().read()[..3], b"hi!");
+        assert_eq!(fs.metadata(Path::new("/buffer")).unwrap().len, 8);
+        assert_eq!(fs.names(), ["buffer"]);
+
+        assert_eq!(
+            fs.new_open_options()
+                .write(true)
+                .create_new(true)
+                .open("/buffer")
+                .unwrap_err(),
+            FsError::AlreadyExists
+        );
+    }
+
+    #[tokio::test]
+    async fn unlinked_objects_outlive_their_name() {
+        let fs = SharedMemoryFileSystem::new();
+        let mut file = fs
+            .new_open_options()
+            .read(true)
+            .write(true)
+            .create(true)
+            .open("/buffer")
+            .unwrap();
+        file.write_all(b"data").await.unwrap();
+
+        file.unlink().unwrap();
+        assert!(fs.object("buffer").is_none());
+        assert_eq!(
+            fs.new_open_options()
+                .read(true)
+                .open("/buffer")
+                .unwrap_err(),
+            FsError::EntryNotFound
+        );
+
+        // The name can be reused for a new object
+        fs.new_open_options()
+            .write(true)
+            .create(true)
+            .open("/buffer")
+            .unwrap();
+        assert!(fs.object("buffer").unwrap().is_empty());
+
+        file.seek(SeekFrom::Start(0)).await.unwrap();
+        let mut buf = String::new();
+        file.read_to_string(&mut buf).await.unwrap();
+        assert_eq!(buf, "data");
+    }
+}
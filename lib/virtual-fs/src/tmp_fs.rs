@@ -9,7 +9,7 @@ use std::{
 
 use crate::{
     limiter::DynFsMemoryLimiter, mem_fs, BoxFuture, FileSystem, Metadata, OpenOptions, ReadDir,
-    Result,
+    Result, VirtualFile,
 };
 
 #[derive(Debug, Default, Clone)]
@@ -58,6 +58,11 @@ impl TmpFileSystem {
     pub fn canonicalize_unchecked(&self, path: &Path) -> Result<PathBuf> {
         self.fs.canonicalize_unchecked(path)
     }
+
+    /// See [`mem_fs::FileSystem::clear`].
+    pub fn clear(&self) -> Result<()> {
+        self.fs.clear()
+    }
 }
 
 impl FileSystem for TmpFileSystem {
@@ -105,4 +110,12 @@ impl FileSystem for TmpFileSystem {
     ) -> Result<()> {
         FileSystem::mount(&self.fs, name, path, fs)
     }
+
+    fn create_tmpfile(&self, path: &Path) -> Result<Box<dyn VirtualFile + Send + Sync + 'static>> {
+        self.fs.create_tmpfile(path)
+    }
+
+    fn link_tmpfile(&self, file: &dyn VirtualFile, path: &Path) -> Result<()> {
+        self.fs.link_tmpfile(file, path)
+    }
 }
@@ -97,6 +97,21 @@ where
     ) -> crate::Result<()> {
         self.0.mount(name, path, fs)
     }
+
+    // Note: the file isn't wrapped in a `TraceFile`, so it can be passed
+    // back to `link_tmpfile()`
+    #[tracing::instrument(level = "trace", skip(self), err)]
+    fn create_tmpfile(
+        &self,
+        path: &std::path::Path,
+    ) -> crate::Result<Box<dyn crate::VirtualFile + Send + Sync + 'static>> {
+        self.0.create_tmpfile(path)
+    }
+
+    #[tracing::instrument(level = "trace", skip(self, file), err)]
+    fn link_tmpfile(&self, file: &dyn VirtualFile, path: &std::path::Path) -> crate::Result<()> {
+        self.0.link_tmpfile(file, path)
+    }
 }
 
 impl<F> FileOpener for TraceFileSystem<F>
@@ -535,6 +535,13 @@ impl VirtualTcpSocket for LocalTcpStream {
     fn is_closed(&self) -> bool {
         false
     }
+
+    fn take_error(&mut self) -> Result<Option<NetworkError>> {
+        self.stream
+            .take_error()
+            .map(|err| err.map(io_err_into_net_error))
+            .map_err(io_err_into_net_error)
+    }
 }
 
 impl VirtualConnectedSocket for LocalTcpStream {
@@ -888,6 +895,24 @@ impl VirtualConnectionlessSocket for LocalUdpSocket {
         }
         .map_err(io_err_into_net_error)
     }
+
+    #[cfg(unix)]
+    fn try_recv_from_vectored(
+        &mut self,
+        bufs: &mut [&mut [MaybeUninit<u8>]],
+        peek: bool,
+    ) -> Result<(usize, SocketAddr)> {
+        let mut bufs: Vec<_> = bufs
+            .iter_mut()
+            .map(|buf| socket2::MaybeUninitSlice::new(buf))
+            .collect();
+        let flags = if peek { libc::MSG_PEEK } else { 0 };
+        let (amt, _, addr) = self
+            .with_sock_ref(|s| s.recv_from_vectored_with_flags(&mut bufs, flags))
+            .map_err(io_err_into_net_error)?;
+        let addr = addr.as_socket().ok_or(NetworkError::AddressNotAvailable)?;
+        Ok((amt, addr))
+    }
 }
 
 impl VirtualSocket for LocalUdpSocket {
@@ -335,6 +335,32 @@ pub trait VirtualConnectedSocket: VirtualSocket + fmt::Debug + Send + Sync + 'st
 
     /// Tries to read a packet from the socket
     fn try_recv(&mut self, buf: &mut [MaybeUninit<u8>], peek: bool) -> Result<usize>;
+
+    /// Tries to read from the socket into several buffers in the manner of
+    /// `readv`, filling each buffer before moving on to the next one
+    ///
+    /// Stops as soon as no more data is available, so a buffer is never
+    /// written to unless the ones before it are full. When peeking, the
+    /// default implementation only reads into the first non-empty buffer.
+    fn try_recv_vectored(
+        &mut self,
+        bufs: &mut [&mut [MaybeUninit<u8>]],
+        peek: bool,
+    ) -> Result<usize> {
+        let mut total = 0;
+        for buf in bufs.iter_mut().filter(|buf| !buf.is_empty()) {
+            let amt = match self.try_recv(buf, peek) {
+                Ok(amt) => amt,
+                Err(_) if total > 0 => break,
+                Err(err) => return Err(err),
+            };
+            total += amt;
+            if peek || amt < buf.len() {
+                break;
+            }
+        }
+        Ok(total)
+    }
 }
 
 #[async_trait::async_trait]
@@ -459,6 +485,19 @@ pub trait VirtualConnectionlessSocket: VirtualSocket + fmt::Debug + Send + Sync
         buf: &mut [MaybeUninit<u8>],
         peek: bool,
     ) -> Result<(usize, SocketAddr)>;
+
+    /// Recv a single packet from the socket, scattered over several buffers
+    /// in the manner of `recvmsg`
+    ///
+    /// The default implementation has to receive the packet into a
+    /// temporary buffer first when more than one buffer is given.
+    fn try_recv_from_vectored(
+        &mut self,
+        bufs: &mut [&mut [MaybeUninit<u8>]],
+        peek: bool,
+    ) -> Result<(usize, SocketAddr)> {
+        recv_datagram_vectored(bufs, |buf| self.try_recv_from(buf, peek))
+    }
 }
 
 #[async_trait::async_trait]
@@ -574,6 +613,19 @@ pub trait VirtualRawSocket: VirtualSocket + fmt::Debug + Send + Sync + 'static {
     /// Recv a packet from the socket
     fn try_recv(&mut self, buf: &mut [MaybeUninit<u8>], peek: bool) -> Result<usize>;
 
+    /// Recv a single packet from the socket, scattered over several buffers
+    ///
+    /// The default implementation has to receive the packet into a
+    /// temporary buffer first when more than one buffer is given.
+    fn try_recv_vectored(
+        &mut self,
+        bufs: &mut [&mut [MaybeUninit<u8>]],
+        peek: bool,
+    ) -> Result<usize> {
+        recv_datagram_vectored(bufs, |buf| self.try_recv(buf, peek).map(|amt| (amt, ())))
+            .map(|(amt, ())| amt)
+    }
+
     /// Tells the raw socket and its backing switch that all packets
     /// should be received by this socket even if they are not
     /// destined for this device
@@ -585,6 +637,34 @@ pub trait VirtualRawSocket: VirtualSocket + fmt::Debug + Send + Sync + 'static {
     fn promiscuous(&self) -> Result<bool>;
 }
 
+/// Receives a single packet with `recv` and scatters it over `bufs`.
+///
+/// A packet can't be split over several reads, so it is only received
+/// straight into the buffer when there is just one to fill.
+fn recv_datagram_vectored<T>(
+    bufs: &mut [&mut [MaybeUninit<u8>]],
+    recv: impl FnOnce(&mut [MaybeUninit<u8>]) -> Result<(usize, T)>,
+) -> Result<(usize, T)> {
+    if bufs.iter().filter(|buf| !buf.is_empty()).count() <= 1 {
+        return match bufs.iter_mut().find(|buf| !buf.is_empty()) {
+            Some(buf) => recv(buf),
+            None => recv(&mut []),
+        };
+    }
+
+    let len = bufs.iter().map(|buf| buf.len()).sum();
+    let mut packet = vec![MaybeUninit::uninit(); len];
+    let (amt, extra) = recv(&mut packet)?;
+
+    let mut rest = &packet[..amt];
+    for buf in bufs.iter_mut() {
+        let n = buf.len().min(rest.len());
+        buf[..n].copy_from_slice(&rest[..n]);
+        rest = &rest[n..];
+    }
+    Ok((amt, extra))
+}
+
 pub trait VirtualTcpSocket: VirtualConnectedSocket + fmt::Debug + Send + Sync + 'static {
     /// Sets the receive buffer size which acts as a trottle for how
     /// much data is buffered on this side of the pipe
@@ -641,6 +721,12 @@ pub trait VirtualTcpSocket: VirtualConnectedSocket + fmt::Debug + Send + Sync +
 
     /// Return true if the socket is closed
     fn is_closed(&self) -> bool;
+
+    /// Takes the pending error of the socket (e.g. why an asynchronous
+    /// connect failed), clearing it in the process.
+    fn take_error(&mut self) -> Result<Option<NetworkError>> {
+        Ok(None)
+    }
 }
 
 #[cfg(feature = "tokio")]
@@ -850,6 +936,9 @@ pub enum NetworkError {
     /// Some other unhandled error. If you see this, it's probably a bug.
     #[error("unknown error found")]
     UnknownError,
+    /// The host or network could not be reached
+    #[error("host or network unreachable")]
+    Unreachable,
 }
 
 pub fn io_err_into_net_error(net_error: std::io::Error) -> NetworkError {
@@ -872,6 +961,7 @@ pub fn io_err_into_net_error(net_error: std::io::Error) -> NetworkError {
         ErrorKind::WouldBlock => NetworkError::WouldBlock,
         ErrorKind::WriteZero => NetworkError::WriteZero,
         ErrorKind::Unsupported => NetworkError::Unsupported,
+        ErrorKind::HostUnreachable | ErrorKind::NetworkUnreachable => NetworkError::Unreachable,
 
         #[cfg(all(target_family = "unix", feature = "libc"))]
         _ => {
@@ -929,6 +1019,7 @@ pub fn net_error_into_io_err(net_error: NetworkError) -> std::io::Error {
         NetworkError::WriteZero => ErrorKind::WriteZero.into(),
         NetworkError::Unsupported => ErrorKind::Unsupported.into(),
         NetworkError::UnknownError => ErrorKind::BrokenPipe.into(),
+        NetworkError::Unreachable => ErrorKind::HostUnreachable.into(),
         NetworkError::InsufficientMemory => ErrorKind::OutOfMemory.into(),
         NetworkError::TooManyOpenFiles => {
             #[cfg(all(target_family = "unix", feature = "libc"))]
@@ -942,3 +1033,62 @@ pub fn net_error_into_io_err(net_error: NetworkError) -> std::io::Error {
         }
     }
 }
+
+#[cfg(test)]
+mod recv_vectored_tests {
+    use super::*;
+
+    const SENTINEL: MaybeUninit<u8> = MaybeUninit::new(0xaa);
+
+    /// Receives `packet` into three 4-byte buffers, returning the amount
+    /// received and what the buffers contain afterwards.
+    fn recv_packet(packet: &[u8]) -> (usize, [[u8; 4]; 3]) {
+        let mut storage = [[SENTINEL; 4]; 3];
+        let [a, b, c] = &mut storage;
+        let mut bufs: [&mut [MaybeUninit<u8>]; 3] = [a, b, c];
+
+        let (amt, ()) = recv_datagram_vectored(&mut bufs, |buf| {
+            let n = buf.len().min(packet.len());
+            for (dst, src) in buf.iter_mut().zip(&packet[..n]) {
+                *dst = MaybeUninit::new(*src);
+            }
+            Ok((n, ()))
+        })
+        .unwrap();
+
+        let contents = storage.map(|buf| buf.map(|b| unsafe { b.assume_init() }));
+        (amt, contents)
+    }
+
+    #[test]
+    fn packets_are_scattered_in_order() {
+        assert_eq!(
+            recv_packet(b"abc"),
+            (3, [*b"abc\xaa", [0xaa; 4], [0xaa; 4]])
+        );
+        assert_eq!(
+            recv_packet(b"defghij"),
+            (7, [*b"defg", *b"hij\xaa", [0xaa; 4]])
+        );
+        // The rest of a packet that doesn't fit is lost, like with `recvmsg`
+        assert_eq!(
+            recv_packet(b"0123456789ABCDEF"),
+            (12, [*b"0123", *b"4567", *b"89AB"])
+        );
+    }
+
+    #[test]
+    fn a_single_buffer_is_received_into_directly() {
+        let mut buf = [SENTINEL; 8];
+        let mut empty: [MaybeUninit<u8>; 0] = [];
+        let mut bufs: [&mut [MaybeUninit<u8>]; 2] = [&mut empty, &mut buf];
+        let ptr = bufs[1].as_ptr();
+
+        let (amt, ()) = recv_datagram_vectored(&mut bufs, |buf| {
+            assert_eq!(buf.as_ptr(), ptr);
+            Ok((0, ()))
+        })
+        .unwrap();
+        assert_eq!(amt, 0);
+    }
+}
@@ -1,5 +1,6 @@
 use std::collections::VecDeque;
 use std::net::SocketAddr;
+use std::ops::RangeInclusive;
 use std::sync::Mutex;
 use std::task::{Context, Poll, Waker};
 use std::{collections::HashMap, sync::Arc};
@@ -13,10 +14,33 @@ use virtual_mio::InterestType;
 
 const DEFAULT_MAX_BUFFER_SIZE: usize = 1_048_576;
 
+/// The ports handed out to sockets bound to port 0 (the IANA dynamic range).
+const EPHEMERAL_PORTS: RangeInclusive<u16> = 49152..=65535;
+
 #[derive(Debug, Default)]
 struct LoopbackNetworkingState {
     tcp_listeners: HashMap<SocketAddr, LoopbackTcpListener>,
     ip_addresses: Vec<IpCidr>,
+    /// Where in [`EPHEMERAL_PORTS`] the search for the next free port starts.
+    next_ephemeral_port: u16,
+}
+
+impl LoopbackNetworkingState {
+    /// Picks the next ephemeral port no listener is using.
+    ///
+    /// Ports are handed out in sequence, so the same sequence of binds and
+    /// connections always gets the same ports.
+    fn allocate_port(&mut self) -> crate::Result<u16> {
+        let count = EPHEMERAL_PORTS.len() as u16;
+        for _ in 0..count {
+            let port = EPHEMERAL_PORTS.start() + self.next_ephemeral_port;
+            self.next_ephemeral_port = (self.next_ephemeral_port + 1) % count;
+            if !self.tcp_listeners.keys().any(|addr| addr.port() == port) {
+                return Ok(port);
+            }
+        }
+        Err(NetworkError::AddressInUse)
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -31,14 +55,31 @@ impl LoopbackNetworking {
         }
     }
 
+    /// Starts the sequence of ephemeral ports handed out to sockets bound
+    /// to port 0 at a position derived from `seed`, rather than at the start
+    /// of the dynamic range.
+    pub fn with_ephemeral_port_seed(self, seed: u64) -> Self {
+        {
+            let mut state = self.state.lock().unwrap();
+            state.next_ephemeral_port = (seed % EPHEMERAL_PORTS.len() as u64) as u16;
+        }
+        self
+    }
+
+    /// Connects `local_addr` to the listener on `peer_addr`, assigning an
+    /// ephemeral port if `local_addr` has none.
+    ///
+    /// The connected socket reports `peer_addr` as its peer, and the socket
+    /// the listener accepts reports it as its local address.
     pub fn loopback_connect_to(
         &self,
         mut local_addr: SocketAddr,
         peer_addr: SocketAddr,
-    ) -> Option<TcpSocketHalf> {
+    ) -> crate::Result<TcpSocketHalf> {
+        let mut state = self.state.lock().unwrap();
         let mut port = local_addr.port();
         if port == 0 {
-            port = peer_addr.port();
+            port = state.allocate_port()?;
         }
 
         local_addr = match local_addr.ip() {
@@ -51,16 +92,15 @@ impl LoopbackNetworking {
             ip => SocketAddr::new(ip, port),
         };
 
-        let state = self.state.lock().unwrap();
-        if let Some(listener) = state.tcp_listeners.get(&peer_addr) {
-            Some(listener.connect_to(local_addr))
-        } else {
-            state
+        let listener = match state.tcp_listeners.get(&peer_addr) {
+            Some(listener) => listener,
+            None => state
                 .tcp_listeners
-                .iter()
+                .values()
                 .next()
-                .map(|listener| listener.1.connect_to(local_addr))
-        }
+                .ok_or(NetworkError::ConnectionRefused)?,
+        };
+        Ok(listener.connect(peer_addr, local_addr))
     }
 }
 
@@ -120,6 +160,10 @@ impl VirtualNetworking for LoopbackNetworking {
         _reuse_port: bool,
         _reuse_addr: bool,
     ) -> crate::Result<Box<dyn VirtualTcpListener + Sync>> {
+        let mut state = self.state.lock().unwrap();
+        if addr.port() == 0 {
+            addr.set_port(state.allocate_port()?);
+        }
         let listener = LoopbackTcpListener::new(addr);
 
         if addr.ip() == IpAddr::V4(Ipv4Addr::UNSPECIFIED) {
@@ -128,7 +172,6 @@ impl VirtualNetworking for LoopbackNetworking {
             addr = SocketAddr::new(Ipv6Addr::LOCALHOST.into(), addr.port());
         }
 
-        let mut state = self.state.lock().unwrap();
         state.tcp_listeners.insert(addr, listener.clone());
 
         Ok(Box::new(listener))
@@ -161,9 +204,15 @@ impl LoopbackTcpListener {
     }
 
     pub fn connect_to(&self, addr_local: SocketAddr) -> TcpSocketHalf {
+        let addr = self.state.lock().unwrap().addr_local;
+        self.connect(addr, addr_local)
+    }
+
+    /// Queues a connection from `addr_peer` to the listener, which the
+    /// accepted socket reports as coming in on `addr`.
+    fn connect(&self, addr: SocketAddr, addr_peer: SocketAddr) -> TcpSocketHalf {
         let mut state = self.state.lock().unwrap();
-        let (half1, half2) =
-            TcpSocketHalf::channel(DEFAULT_MAX_BUFFER_SIZE, state.addr_local, addr_local);
+        let (half1, half2) = TcpSocketHalf::channel(DEFAULT_MAX_BUFFER_SIZE, addr, addr_peer);
 
         state.backlog.push_back(half1);
         if let Some(handler) = state.handler.as_mut() {
@@ -235,3 +284,90 @@ impl VirtualTcpListener for LoopbackTcpListener {
         Ok(64)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::VirtualSocket;
+
+    fn localhost(port: u16) -> SocketAddr {
+        SocketAddr::new(Ipv4Addr::LOCALHOST.into(), port)
+    }
+
+    #[tokio::test]
+    async fn ephemeral_ports_are_assigned_on_bind() {
+        let net = LoopbackNetworking::new();
+        let mut listener = net
+            .listen_tcp(localhost(0), false, false, false)
+            .await
+            .unwrap();
+        let addr = listener.addr_local().unwrap();
+        assert_eq!(addr, localhost(*EPHEMERAL_PORTS.start()));
+
+        let client = net.loopback_connect_to(localhost(0), addr).unwrap();
+        let (server, peer) = listener.try_accept().unwrap();
+
+        // The four views of the connection agree with each other
+        let client_local = client.addr_local().unwrap();
+        assert_ne!(client_local.port(), 0);
+        assert_ne!(client_local.port(), addr.port());
+        assert_eq!(client.addr_peer().unwrap(), addr);
+        assert_eq!(server.addr_local().unwrap(), addr);
+        assert_eq!(server.addr_peer().unwrap(), client_local);
+        assert_eq!(peer, client_local);
+    }
+
+    #[tokio::test]
+    async fn ephemeral_ports_are_deterministic() {
+        async fn ports(net: LoopbackNetworking) -> Vec<u16> {
+            let mut ports = Vec::new();
+            for _ in 0..3 {
+                let listener = net
+                    .listen_tcp(localhost(0), false, false, false)
+                    .await
+                    .unwrap();
+                ports.push(listener.addr_local().unwrap().port());
+            }
+            ports
+        }
+
+        assert_eq!(
+            ports(LoopbackNetworking::new()).await,
+            ports(LoopbackNetworking::new()).await
+        );
+        let seeded = ports(LoopbackNetworking::new().with_ephemeral_port_seed(10)).await;
+        assert_eq!(seeded, [49162, 49163, 49164]);
+        assert_eq!(
+            seeded,
+            ports(LoopbackNetworking::new().with_ephemeral_port_seed(10)).await
+        );
+    }
+
+    #[tokio::test]
+    async fn ports_in_use_are_skipped_until_exhausted() {
+        let net = LoopbackNetworking::new();
+        net.listen_tcp(localhost(49152), false, false, false)
+            .await
+            .unwrap();
+        let listener = net
+            .listen_tcp(localhost(0), false, false, false)
+            .await
+            .unwrap();
+        assert_eq!(listener.addr_local().unwrap().port(), 49153);
+
+        for _ in 49154..=65535 {
+            net.listen_tcp(localhost(0), false, false, false)
+                .await
+                .unwrap();
+        }
+        let err = net
+            .listen_tcp(localhost(0), false, false, false)
+            .await
+            .unwrap_err();
+        assert_eq!(err, NetworkError::AddressInUse);
+        let err = net
+            .loopback_connect_to(localhost(0), localhost(49152))
+            .unwrap_err();
+        assert_eq!(err, NetworkError::AddressInUse);
+    }
+}
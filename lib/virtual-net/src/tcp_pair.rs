@@ -239,6 +239,17 @@ impl SocketBuffer {
         buf: &mut [std::mem::MaybeUninit<u8>],
         peek: bool,
         waker: Option<&Waker>,
+    ) -> crate::Result<usize> {
+        self.try_read_vectored(&mut [buf], peek, waker)
+    }
+
+    /// Reads into each of `bufs` in turn, until they are full or the
+    /// buffered data runs out.
+    pub fn try_read_vectored(
+        &self,
+        bufs: &mut [&mut [std::mem::MaybeUninit<u8>]],
+        peek: bool,
+        waker: Option<&Waker>,
     ) -> crate::Result<usize> {
         let mut state = self.state.lock().unwrap();
         if state.buffer.is_empty() {
@@ -263,13 +274,19 @@ impl SocketBuffer {
             };
         }
 
-        let buf: &mut [u8] = unsafe { std::mem::transmute(buf) };
-        let amt = buf.len().min(state.buffer.len());
-        let amt = if peek {
-            state.buffer.read_allocated(0, &mut buf[..amt])
-        } else {
-            state.buffer.dequeue_slice(&mut buf[..amt])
-        };
+        let mut amt = 0;
+        for buf in bufs.iter_mut() {
+            let buf: &mut [u8] = unsafe { std::mem::transmute(&mut **buf) };
+            let read = if peek {
+                state.buffer.read_allocated(amt, buf)
+            } else {
+                state.buffer.dequeue_slice(buf)
+            };
+            amt += read;
+            if read < buf.len() {
+                break;
+            }
+        }
 
         if let Some(handler) = state.pull_handler.as_mut() {
             handler.push_interest(InterestType::Writable);
@@ -469,6 +486,14 @@ impl VirtualConnectedSocket for TcpSocketHalf {
     ) -> crate::Result<usize> {
         self.rx.try_read(buf, peek, None)
     }
+
+    fn try_recv_vectored(
+        &mut self,
+        bufs: &mut [&mut [std::mem::MaybeUninit<u8>]],
+        peek: bool,
+    ) -> crate::Result<usize> {
+        self.rx.try_read_vectored(bufs, peek, None)
+    }
 }
 
 impl VirtualTcpSocket for TcpSocketHalf {
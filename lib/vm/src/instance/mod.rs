@@ -32,6 +32,7 @@ use std::collections::HashMap;
 use std::convert::TryFrom;
 use std::fmt;
 use std::mem;
+use std::ops::Range;
 use std::ptr::{self, NonNull};
 use std::slice;
 use std::sync::Arc;
@@ -999,6 +1000,16 @@ pub struct VMInstance {
     instance: NonNull<Instance>,
 }
 
+/// How far [`VMInstance::initialize_step`] got in applying the initializers
+/// of an instance.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct InitializationProgress {
+    table_initializer: usize,
+    data_initializer: usize,
+    /// Where to resume within the current initializer.
+    offset: usize,
+}
+
 /// VMInstance are created with an InstanceAllocator
 /// and it will "consume" the memory
 /// So the Drop here actualy free it (else it would be leaked)
@@ -1198,6 +1209,83 @@ impl VMInstance {
         Ok(())
     }
 
+    /// Applies the table and data initializers like
+    /// [`Self::finish_instantiation`] does, but only about `chunk_size`
+    /// bytes of them at a time (a table element counts as a pointer), so
+    /// that the caller can get on with other work between steps.
+    ///
+    /// Returns `Ok(true)` once everything has been applied, after which
+    /// [`Self::invoke_start_function`] completes the instantiation. Traps
+    /// are the same as with [`Self::finish_instantiation`], since the bounds
+    /// of each initializer are still checked before any of it is applied.
+    ///
+    /// # Safety
+    ///
+    /// Only safe to call right after instantiation, with the same
+    /// `data_initializers` and `progress` until everything is applied.
+    pub unsafe fn initialize_step(
+        &mut self,
+        data_initializers: &[DataInitializer<'_>],
+        progress: &mut InitializationProgress,
+        chunk_size: usize,
+    ) -> Result<bool, Trap> {
+        let instance = self.instance_mut();
+        let module = Arc::clone(&instance.module);
+        let mut budget = chunk_size.max(1);
+
+        while let Some(init) = module.table_initializers.get(progress.table_initializer) {
+            let len = (budget / mem::size_of::<usize>())
+                .max(1)
+                .min(init.elements.len() - progress.offset);
+            let end = progress.offset + len;
+            initialize_table_elements(instance, init, progress.offset..end)?;
+            if end == init.elements.len() {
+                progress.table_initializer += 1;
+                progress.offset = 0;
+            } else {
+                progress.offset = end;
+            }
+
+            budget = budget.saturating_sub(len * mem::size_of::<usize>());
+            if budget == 0 {
+                return Ok(false);
+            }
+        }
+
+        while let Some(init) = data_initializers.get(progress.data_initializer) {
+            let len = budget.min(init.data.len() - progress.offset);
+            let end = progress.offset + len;
+            initialize_memory_range(instance, init, progress.offset..end)?;
+            if end == init.data.len() {
+                progress.data_initializer += 1;
+                progress.offset = 0;
+            } else {
+                progress.offset = end;
+            }
+
+            budget -= len;
+            if budget == 0 {
+                return Ok(false);
+            }
+        }
+
+        Ok(true)
+    }
+
+    /// Invokes the start function of the instance, if it has one.
+    ///
+    /// # Safety
+    ///
+    /// Only safe to call once, after [`Self::initialize_step`] has applied
+    /// all the initializers.
+    pub unsafe fn invoke_start_function(
+        &self,
+        config: &VMConfig,
+        trap_handler: Option<*const TrapHandlerFn<'static>>,
+    ) -> Result<(), Trap> {
+        self.instance().invoke_start_function(config, trap_handler)
+    }
+
     /// Return a reference to the vmctx used by compiled wasm code.
     pub fn vmctx(&self) -> &VMContext {
         self.instance().vmctx()
@@ -1430,36 +1518,49 @@ fn get_table_init_start(init: &TableInitializer, instance: &Instance) -> usize {
 fn initialize_tables(instance: &mut Instance) -> Result<(), Trap> {
     let module = Arc::clone(&instance.module);
     for init in &module.table_initializers {
-        let start = get_table_init_start(init, instance);
-        let table = instance.get_table_handle(init.table_index);
-        let table = unsafe { table.get_mut(&mut *instance.context) };
+        initialize_table_elements(instance, init, 0..init.elements.len())?;
+    }
 
-        if start
+    Ok(())
+}
+
+/// Apply the `elements` of a table initializer, checking the bounds of the
+/// whole initializer first when starting at its first element.
+fn initialize_table_elements(
+    instance: &mut Instance,
+    init: &TableInitializer,
+    elements: Range<usize>,
+) -> Result<(), Trap> {
+    let start = get_table_init_start(init, instance);
+    let table = instance.get_table_handle(init.table_index);
+    let table = unsafe { table.get_mut(&mut *instance.context) };
+
+    if elements.start == 0
+        && start
             .checked_add(init.elements.len())
             .map_or(true, |end| end > table.size() as usize)
-        {
-            return Err(Trap::lib(TrapCode::TableAccessOutOfBounds));
-        }
+    {
+        return Err(Trap::lib(TrapCode::TableAccessOutOfBounds));
+    }
 
-        if let wasmer_types::Type::FuncRef = table.ty().ty {
-            for (i, func_idx) in init.elements.iter().enumerate() {
-                let anyfunc = instance.func_ref(*func_idx);
-                table
-                    .set(
-                        u32::try_from(start + i).unwrap(),
-                        TableElement::FuncRef(anyfunc),
-                    )
-                    .unwrap();
-            }
-        } else {
-            for i in 0..init.elements.len() {
-                table
-                    .set(
-                        u32::try_from(start + i).unwrap(),
-                        TableElement::ExternRef(None),
-                    )
-                    .unwrap();
-            }
+    if let wasmer_types::Type::FuncRef = table.ty().ty {
+        for i in elements {
+            let anyfunc = instance.func_ref(init.elements[i]);
+            table
+                .set(
+                    u32::try_from(start + i).unwrap(),
+                    TableElement::FuncRef(anyfunc),
+                )
+                .unwrap();
+        }
+    } else {
+        for i in elements {
+            table
+                .set(
+                    u32::try_from(start + i).unwrap(),
+                    TableElement::ExternRef(None),
+                )
+                .unwrap();
         }
     }
 
@@ -1497,19 +1598,32 @@ fn initialize_memories(
     data_initializers: &[DataInitializer<'_>],
 ) -> Result<(), Trap> {
     for init in data_initializers {
-        let memory = instance.get_vmmemory(init.location.memory_index);
+        initialize_memory_range(instance, init, 0..init.data.len())?;
+    }
 
-        let start = get_memory_init_start(init, instance);
-        unsafe {
-            let current_length = memory.vmmemory().as_ref().current_length;
-            if start
+    Ok(())
+}
+
+/// Copy the `range` of a data initializer into memory, checking the bounds
+/// of the whole initializer first when starting at its first byte.
+fn initialize_memory_range(
+    instance: &mut Instance,
+    init: &DataInitializer<'_>,
+    range: Range<usize>,
+) -> Result<(), Trap> {
+    let memory = instance.get_vmmemory(init.location.memory_index);
+
+    let start = get_memory_init_start(init, instance);
+    unsafe {
+        let current_length = memory.vmmemory().as_ref().current_length;
+        if range.start == 0
+            && start
                 .checked_add(init.data.len())
                 .map_or(true, |end| end > current_length)
-            {
-                return Err(Trap::lib(TrapCode::HeapAccessOutOfBounds));
-            }
-            memory.initialize_with_data(start, init.data)?;
+        {
+            return Err(Trap::lib(TrapCode::HeapAccessOutOfBounds));
         }
+        memory.initialize_with_data(start + range.start, &init.data[range])?;
     }
 
     Ok(())
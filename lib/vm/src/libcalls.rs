@@ -45,7 +45,7 @@ pub use eh::{wasmer_eh_personality, wasmer_eh_personality2};
 
 use crate::probestack::PROBESTACK;
 use crate::table::{RawTableElement, TableElement};
-use crate::trap::{raise_lib_trap, Trap, TrapCode};
+use crate::trap::{raise_lib_trap, take_expired_deadline, Trap, TrapCode};
 use crate::vmcontext::VMContext;
 use crate::{on_host_stack, VMFuncRef};
 pub use wasmer_types::LibCall;
@@ -54,6 +54,16 @@ use wasmer_types::{
     TableIndex, Type,
 };
 
+/// Traps if the call this libcall is part of ran past its deadline (see
+/// [`crate::with_deadline()`]), since libcalls themselves are never
+/// interrupted. Like any other trap, this must only be raised once the
+/// libcall's locals have been dropped.
+unsafe fn check_deadline() {
+    if take_expired_deadline() {
+        raise_lib_trap(Trap::lib(TrapCode::DeadlineExceeded));
+    }
+}
+
 /// Implementation of f32.ceil
 #[no_mangle]
 pub extern "C" fn wasmer_vm_f32_ceil(x: f32) -> f32 {
@@ -155,7 +165,7 @@ pub unsafe extern "C" fn wasmer_vm_memory32_grow(
     delta: u32,
     memory_index: u32,
 ) -> u32 {
-    on_host_stack(|| {
+    let result = on_host_stack(|| {
         let instance = (*vmctx).instance_mut();
         let memory_index = LocalMemoryIndex::from_u32(memory_index);
 
@@ -163,7 +173,9 @@ pub unsafe extern "C" fn wasmer_vm_memory32_grow(
             .memory_grow(memory_index, delta)
             .map(|pages| pages.0)
             .unwrap_or(u32::MAX)
-    })
+    });
+    check_deadline();
+    result
 }
 
 /// Implementation of memory.grow for imported 32-bit memories.
@@ -177,7 +189,7 @@ pub unsafe extern "C" fn wasmer_vm_imported_memory32_grow(
     delta: u32,
     memory_index: u32,
 ) -> u32 {
-    on_host_stack(|| {
+    let result = on_host_stack(|| {
         let instance = (*vmctx).instance_mut();
         let memory_index = MemoryIndex::from_u32(memory_index);
 
@@ -185,7 +197,9 @@ pub unsafe extern "C" fn wasmer_vm_imported_memory32_grow(
             .imported_memory_grow(memory_index, delta)
             .map(|pages| pages.0)
             .unwrap_or(u32::MAX)
-    })
+    });
+    check_deadline();
+    result
 }
 
 /// Implementation of memory.size for locally-defined 32-bit memories.
@@ -246,6 +260,7 @@ pub unsafe extern "C" fn wasmer_vm_table_copy(
     if let Err(trap) = result {
         raise_lib_trap(trap);
     }
+    check_deadline();
 }
 
 /// Implementation of `table.init`.
@@ -271,6 +286,7 @@ pub unsafe extern "C" fn wasmer_vm_table_init(
     if let Err(trap) = result {
         raise_lib_trap(trap);
     }
+    check_deadline();
 }
 
 /// Implementation of `table.fill`.
@@ -300,6 +316,7 @@ pub unsafe extern "C" fn wasmer_vm_table_fill(
     if let Err(trap) = result {
         raise_lib_trap(trap);
     }
+    check_deadline();
 }
 
 /// Implementation of `table.size`.
@@ -448,7 +465,7 @@ pub unsafe extern "C" fn wasmer_vm_table_grow(
     delta: u32,
     table_index: u32,
 ) -> u32 {
-    on_host_stack(|| {
+    let result = on_host_stack(|| {
         let instance = (*vmctx).instance_mut();
         let table_index = LocalTableIndex::from_u32(table_index);
 
@@ -461,7 +478,9 @@ pub unsafe extern "C" fn wasmer_vm_table_grow(
         instance
             .table_grow(table_index, delta, init_value)
             .unwrap_or(u32::MAX)
-    })
+    });
+    check_deadline();
+    result
 }
 
 /// Implementation of `table.grow` for imported tables.
@@ -476,7 +495,7 @@ pub unsafe extern "C" fn wasmer_vm_imported_table_grow(
     delta: u32,
     table_index: u32,
 ) -> u32 {
-    on_host_stack(|| {
+    let result = on_host_stack(|| {
         let instance = (*vmctx).instance_mut();
         let table_index = TableIndex::from_u32(table_index);
         let init_value = match instance.get_table(table_index).ty().ty {
@@ -488,7 +507,9 @@ pub unsafe extern "C" fn wasmer_vm_imported_table_grow(
         instance
             .imported_table_grow(table_index, delta, init_value)
             .unwrap_or(u32::MAX)
-    })
+    });
+    check_deadline();
+    result
 }
 
 /// Implementation of `func.ref`.
@@ -542,6 +563,7 @@ pub unsafe extern "C" fn wasmer_vm_memory32_copy(
     if let Err(trap) = result {
         raise_lib_trap(trap);
     }
+    check_deadline();
 }
 
 /// Implementation of `memory.copy` for imported memories.
@@ -565,6 +587,7 @@ pub unsafe extern "C" fn wasmer_vm_imported_memory32_copy(
     if let Err(trap) = result {
         raise_lib_trap(trap);
     }
+    check_deadline();
 }
 
 /// Implementation of `memory.fill` for locally defined memories.
@@ -588,6 +611,7 @@ pub unsafe extern "C" fn wasmer_vm_memory32_fill(
     if let Err(trap) = result {
         raise_lib_trap(trap);
     }
+    check_deadline();
 }
 
 /// Implementation of `memory.fill` for imported memories.
@@ -611,6 +635,7 @@ pub unsafe extern "C" fn wasmer_vm_imported_memory32_fill(
     if let Err(trap) = result {
         raise_lib_trap(trap);
     }
+    check_deadline();
 }
 
 /// Implementation of `memory.init`.
@@ -636,6 +661,7 @@ pub unsafe extern "C" fn wasmer_vm_memory32_init(
     if let Err(trap) = result {
         raise_lib_trap(trap);
     }
+    check_deadline();
 }
 
 /// Implementation of `data.drop`.
@@ -779,6 +805,7 @@ pub unsafe extern "C" fn wasmer_vm_memory32_atomic_wait32(
     if let Err(trap) = result {
         raise_lib_trap(trap);
     }
+    check_deadline();
     result.unwrap()
 }
 
@@ -804,6 +831,7 @@ pub unsafe extern "C" fn wasmer_vm_imported_memory32_atomic_wait32(
     if let Err(trap) = result {
         raise_lib_trap(trap);
     }
+    check_deadline();
     result.unwrap()
 }
 
@@ -829,6 +857,7 @@ pub unsafe extern "C" fn wasmer_vm_memory32_atomic_wait64(
     if let Err(trap) = result {
         raise_lib_trap(trap);
     }
+    check_deadline();
     result.unwrap()
 }
 
@@ -854,6 +883,7 @@ pub unsafe extern "C" fn wasmer_vm_imported_memory32_atomic_wait64(
     if let Err(trap) = result {
         raise_lib_trap(trap);
     }
+    check_deadline();
     result.unwrap()
 }
 
@@ -18,7 +18,9 @@ use std::rc::Rc;
 use std::slice;
 use std::sync::RwLock;
 use std::time::Duration;
-use wasmer_types::{Bytes, MemoryError, MemoryStyle, MemoryType, Pages, WASM_PAGE_SIZE};
+use wasmer_types::{
+    Bytes, MemoryError, MemoryGrowthPolicy, MemoryStyle, MemoryType, Pages, WASM_PAGE_SIZE,
+};
 
 // The memory mapped area
 #[derive(Debug)]
@@ -308,7 +310,7 @@ impl VMOwnedMemory {
         let offset_guard_bytes = style.offset_guard_size() as usize;
 
         let minimum_pages = match style {
-            MemoryStyle::Dynamic { .. } => memory.minimum,
+            MemoryStyle::Dynamic { .. } | MemoryStyle::External => memory.minimum,
             MemoryStyle::Static { bound, .. } => {
                 assert_ge!(*bound, memory.minimum);
                 *bound
@@ -359,6 +361,16 @@ impl VMOwnedMemory {
         })
     }
 
+    /// Let this memory grow up to `limit` pages rather than the maximum
+    /// declared by its type, or as far as the address space allows if the
+    /// limit is `None`.
+    ///
+    /// The type of the memory is left untouched.
+    pub fn with_grow_limit(mut self, limit: Option<Pages>) -> Self {
+        self.config.maximum = limit;
+        self
+    }
+
     /// Converts this owned memory into shared memory
     pub fn to_shared(self) -> VMSharedMemory {
         VMSharedMemory {
@@ -510,6 +522,13 @@ impl VMSharedMemory {
         .to_shared())
     }
 
+    /// Let this memory grow up to `limit` pages rather than the maximum
+    /// declared by its type (see [`VMOwnedMemory::with_grow_limit()`]).
+    pub fn with_grow_limit(mut self, limit: Option<Pages>) -> Self {
+        self.config.maximum = limit;
+        self
+    }
+
     /// Copies this memory to a new memory
     pub fn copy(&mut self) -> Result<Self, MemoryError> {
         let mut guard = self.mmap.write().unwrap();
@@ -601,6 +620,292 @@ impl LinearMemory for VMSharedMemory {
     }
 }
 
+/// Where the bytes of a [`VMExternalMemory`] currently live.
+#[derive(Debug)]
+enum ExternalAllocation {
+    /// The buffer provided by the embedder.
+    Host { base: NonNull<u8>, len: usize },
+    /// A runtime-owned copy, created the first time the memory grew. Only
+    /// the first `size` pages are accessible; the rest of the mapping is
+    /// reserved so later grows can happen in place.
+    Remapped(Mmap),
+}
+
+/// A linear memory whose pages alias a buffer owned by the embedder.
+///
+/// Nothing is copied when the memory is created: the base pointer seen by
+/// compiled code is the start of the host buffer. The buffer has no guard
+/// pages, so this memory uses [`MemoryStyle::External`] and can only be
+/// imported by modules that were compiled for that style (or for a
+/// [`MemoryStyle::Dynamic`] memory without guard pages).
+pub struct VMExternalMemory {
+    alloc: ExternalAllocation,
+    size: Pages,
+    vm_memory_definition: Box<UnsafeCell<VMMemoryDefinition>>,
+    config: VMMemoryConfig,
+    policy: MemoryGrowthPolicy,
+    // Keeps the host buffer alive for as long as the memory aliases it.
+    owner: Option<Box<dyn std::any::Any + Send>>,
+}
+
+unsafe impl Send for VMExternalMemory {}
+unsafe impl Sync for VMExternalMemory {}
+
+impl std::fmt::Debug for VMExternalMemory {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("VMExternalMemory")
+            .field("alloc", &self.alloc)
+            .field("size", &self.size)
+            .field("config", &self.config)
+            .field("policy", &self.policy)
+            .finish_non_exhaustive()
+    }
+}
+
+impl VMExternalMemory {
+    /// Create a linear memory whose initial pages are the `len` bytes starting
+    /// at `base`.
+    ///
+    /// `base` must be aligned to 16 bytes, `len` must be a multiple of the
+    /// WebAssembly page size, and the number of pages must satisfy the limits
+    /// of `memory`. Shared memories are not supported.
+    ///
+    /// # Safety
+    /// - `base` must be valid for reads and writes of `len` bytes for as long
+    ///   as the memory (or any instance importing it) is alive, unless the
+    ///   memory has been remapped by growing it.
+    /// - The buffer must not be read or written through any other pointer
+    ///   while WebAssembly code may be accessing it.
+    pub unsafe fn new(
+        memory: &MemoryType,
+        base: NonNull<u8>,
+        len: usize,
+        policy: MemoryGrowthPolicy,
+    ) -> Result<Self, MemoryError> {
+        Self::new_internal(memory, base, len, policy, None)
+    }
+
+    /// Create a linear memory that aliases a buffer owned by `owner`.
+    ///
+    /// The owner is dropped together with the memory, or as soon as the memory
+    /// is remapped.
+    ///
+    /// # Safety
+    /// The same requirements as [`VMExternalMemory::new()`] apply, except that
+    /// the buffer only needs to live as long as `owner`.
+    pub unsafe fn with_owner(
+        memory: &MemoryType,
+        base: NonNull<u8>,
+        len: usize,
+        policy: MemoryGrowthPolicy,
+        owner: Box<dyn std::any::Any + Send>,
+    ) -> Result<Self, MemoryError> {
+        Self::new_internal(memory, base, len, policy, Some(owner))
+    }
+
+    unsafe fn new_internal(
+        memory: &MemoryType,
+        base: NonNull<u8>,
+        len: usize,
+        policy: MemoryGrowthPolicy,
+        owner: Option<Box<dyn std::any::Any + Send>>,
+    ) -> Result<Self, MemoryError> {
+        if memory.shared {
+            return Err(MemoryError::InvalidMemory {
+                reason: "externally-owned memories can not be shared".to_string(),
+            });
+        }
+        // Atomic and SIMD accesses rely on the alignment of the base address.
+        if base.as_ptr().align_offset(16) != 0 {
+            return Err(MemoryError::InvalidMemory {
+                reason: "the buffer must be aligned to 16 bytes".to_string(),
+            });
+        }
+        if len % WASM_PAGE_SIZE != 0 {
+            return Err(MemoryError::InvalidMemory {
+                reason: format!(
+                    "the buffer length ({len} bytes) is not a multiple of the page size"
+                ),
+            });
+        }
+
+        let size: Pages = Bytes(len)
+            .try_into()
+            .map_err(|_| MemoryError::InvalidMemory {
+                reason: format!("the buffer length ({len} bytes) is too large"),
+            })?;
+        if size < memory.minimum {
+            return Err(MemoryError::InvalidMemory {
+                reason: format!(
+                    "the buffer ({} pages) is smaller than the minimum ({} pages)",
+                    size.0, memory.minimum.0
+                ),
+            });
+        }
+        let maximum = memory.maximum.unwrap_or_else(Pages::max_value);
+        if size > maximum {
+            return Err(MemoryError::MaximumMemoryTooLarge {
+                max_requested: size,
+                max_allowed: maximum,
+            });
+        }
+
+        Ok(Self {
+            alloc: ExternalAllocation::Host { base, len },
+            size,
+            vm_memory_definition: Box::new(UnsafeCell::new(VMMemoryDefinition {
+                base: base.as_ptr(),
+                current_length: len,
+            })),
+            config: VMMemoryConfig {
+                maximum: memory.maximum,
+                memory: *memory,
+                style: MemoryStyle::External,
+                offset_guard_size: 0,
+            },
+            policy,
+            owner,
+        })
+    }
+
+    /// Returns `true` while the memory still aliases the host buffer.
+    pub fn is_external(&self) -> bool {
+        matches!(self.alloc, ExternalAllocation::Host { .. })
+    }
+
+    fn as_slice(&self) -> &[u8] {
+        match &self.alloc {
+            ExternalAllocation::Host { base, len } => unsafe {
+                slice::from_raw_parts(base.as_ptr(), *len)
+            },
+            ExternalAllocation::Remapped(mmap) => &mmap.as_slice()[..self.size.bytes().0],
+        }
+    }
+}
+
+impl LinearMemory for VMExternalMemory {
+    /// Returns the type for this memory.
+    fn ty(&self) -> MemoryType {
+        self.config.ty(self.size)
+    }
+
+    /// Returns the size of the memory in pages
+    fn size(&self) -> Pages {
+        self.size
+    }
+
+    /// Returns the memory style for this memory.
+    fn style(&self) -> MemoryStyle {
+        self.config.style()
+    }
+
+    /// Grow memory by the specified amount of wasm pages.
+    ///
+    /// This fails unless the memory was created with
+    /// [`MemoryGrowthPolicy::Remap`].
+    fn grow(&mut self, delta: Pages) -> Result<Pages, MemoryError> {
+        if delta.0 == 0 {
+            return Ok(self.size);
+        }
+
+        let could_not_grow = MemoryError::CouldNotGrow {
+            current: self.size,
+            attempted_delta: delta,
+        };
+        if self.policy == MemoryGrowthPolicy::Disabled {
+            return Err(could_not_grow);
+        }
+
+        let new_pages = self.size.checked_add(delta).ok_or(could_not_grow.clone())?;
+        let maximum = self.config.maximum.unwrap_or_else(Pages::max_value);
+        if new_pages > maximum || new_pages > Pages::max_value() {
+            return Err(could_not_grow);
+        }
+
+        let prev_bytes = self.size.bytes().0;
+        let new_bytes = new_pages.bytes().0;
+        match &mut self.alloc {
+            ExternalAllocation::Remapped(mmap) if new_bytes <= mmap.len() => {
+                mmap.make_accessible(prev_bytes, new_bytes - prev_bytes)
+                    .map_err(MemoryError::Region)?;
+            }
+            _ => {
+                // Reserve room for the memory to double in size (up to its
+                // maximum), so that growing it repeatedly doesn't copy it
+                // every time
+                let reserved_pages = self
+                    .size
+                    .0
+                    .saturating_mul(2)
+                    .max(new_pages.0)
+                    .min(maximum.0)
+                    .min(Pages::max_value().0);
+                let reserved_bytes = Pages(reserved_pages).bytes().0;
+                let mut new_mmap =
+                    Mmap::accessible_reserved(new_bytes, reserved_bytes, None, MmapType::Private)
+                        .map_err(MemoryError::Region)?;
+                let old = self.as_slice();
+                new_mmap.as_mut_slice()[..old.len()].copy_from_slice(old);
+                self.alloc = ExternalAllocation::Remapped(new_mmap);
+                self.owner = None;
+            }
+        }
+
+        let prev_pages = self.size;
+        self.size = new_pages;
+
+        let ExternalAllocation::Remapped(mmap) = &mut self.alloc else {
+            unreachable!()
+        };
+        unsafe {
+            let md = &mut *self.vm_memory_definition.get();
+            md.base = mmap.as_mut_ptr();
+            md.current_length = new_bytes;
+        }
+
+        Ok(prev_pages)
+    }
+
+    /// Grows the memory to at least a minimum size. If the memory is already big enough
+    /// for the min size then this function does nothing
+    fn grow_at_least(&mut self, min_size: u64) -> Result<(), MemoryError> {
+        let cur_size = self.size.bytes().0 as u64;
+        if cur_size < min_size {
+            let growth = min_size - cur_size;
+            let growth_pages = ((growth - 1) / WASM_PAGE_SIZE as u64) + 1;
+            self.grow(Pages(growth_pages as u32))?;
+        }
+
+        Ok(())
+    }
+
+    /// Return a `VMMemoryDefinition` for exposing the memory to compiled wasm code.
+    fn vmmemory(&self) -> NonNull<VMMemoryDefinition> {
+        unsafe { NonNull::new_unchecked(self.vm_memory_definition.get()) }
+    }
+
+    /// External memory can not be cloned (this will always return None)
+    fn try_clone(&self) -> Result<Box<dyn LinearMemory + 'static>, MemoryError> {
+        Err(MemoryError::MemoryNotShared)
+    }
+
+    /// Copies the contents of this memory into a new memory owned by the
+    /// runtime.
+    fn copy(&mut self) -> Result<Box<dyn LinearMemory + 'static>, MemoryError> {
+        let forked = VMOwnedMemory::new(&self.ty(), &MemoryStyle::External)?;
+        let data = self.as_slice();
+        unsafe { forked.initialize_with_data(0, data) }
+            .map_err(|trap| MemoryError::Generic(format!("{trap:?}")))?;
+        Ok(Box::new(forked))
+    }
+}
+
+impl From<VMExternalMemory> for VMMemory {
+    fn from(mem: VMExternalMemory) -> Self {
+        Self(Box::new(mem))
+    }
+}
+
 impl From<VMOwnedMemory> for VMMemory {
     fn from(mem: VMOwnedMemory) -> Self {
         Self(Box::new(mem))
@@ -705,10 +1010,24 @@ impl VMMemory {
     /// This creates a `Memory` with owned metadata: this can be used to create a memory
     /// that will be imported into Wasm modules.
     pub fn new(memory: &MemoryType, style: &MemoryStyle) -> Result<Self, MemoryError> {
+        Self::new_with_grow_limit(memory, style, memory.maximum)
+    }
+
+    /// Creates a new linear memory instance like [`VMMemory::new()`], which
+    /// can grow up to `grow_limit` pages rather than its declared maximum.
+    pub fn new_with_grow_limit(
+        memory: &MemoryType,
+        style: &MemoryStyle,
+        grow_limit: Option<Pages>,
+    ) -> Result<Self, MemoryError> {
         Ok(if memory.shared {
-            Self(Box::new(VMSharedMemory::new(memory, style)?))
+            Self(Box::new(
+                VMSharedMemory::new(memory, style)?.with_grow_limit(grow_limit),
+            ))
         } else {
-            Self(Box::new(VMOwnedMemory::new(memory, style)?))
+            Self(Box::new(
+                VMOwnedMemory::new(memory, style)?.with_grow_limit(grow_limit),
+            ))
         })
     }
 
@@ -728,19 +1047,31 @@ impl VMMemory {
         memory: &MemoryType,
         style: &MemoryStyle,
         vm_memory_location: NonNull<VMMemoryDefinition>,
+    ) -> Result<Self, MemoryError> {
+        Self::from_definition_with_grow_limit(memory, style, vm_memory_location, memory.maximum)
+    }
+
+    /// Create a new linear memory instance like [`VMMemory::from_definition()`],
+    /// which can grow up to `grow_limit` pages rather than its declared maximum.
+    ///
+    /// # Safety
+    /// - `vm_memory_location` must point to a valid location in VM memory.
+    pub unsafe fn from_definition_with_grow_limit(
+        memory: &MemoryType,
+        style: &MemoryStyle,
+        vm_memory_location: NonNull<VMMemoryDefinition>,
+        grow_limit: Option<Pages>,
     ) -> Result<Self, MemoryError> {
         Ok(if memory.shared {
-            Self(Box::new(VMSharedMemory::from_definition(
-                memory,
-                style,
-                vm_memory_location,
-            )?))
+            Self(Box::new(
+                VMSharedMemory::from_definition(memory, style, vm_memory_location)?
+                    .with_grow_limit(grow_limit),
+            ))
         } else {
-            Self(Box::new(VMOwnedMemory::from_definition(
-                memory,
-                style,
-                vm_memory_location,
-            )?))
+            Self(Box::new(
+                VMOwnedMemory::from_definition(memory, style, vm_memory_location)?
+                    .with_grow_limit(grow_limit),
+            ))
         })
     }
 
@@ -0,0 +1,253 @@
+//! Interrupting calls into WebAssembly that run past a deadline.
+//!
+//! A watchdog thread sends the interrupt signal (see
+//! [`set_interrupt_signal()`]) to every thread whose deadline has passed, and
+//! keeps doing so until the call returns. The signal handler only unwinds the
+//! call when the thread is executing WebAssembly code, because that is the
+//! only place where unwinding can't cut host code short. Libcalls are left to
+//! finish, and check the deadline before returning to WebAssembly (see
+//! [`take_expired()`]). Host functions, which run on the host stack, are never
+//! interrupted: the call is interrupted once they return.
+//!
+//! Deadlines are only enforced on unix.
+
+use scopeguard::defer;
+use std::ops::Range;
+use std::ptr;
+use std::sync::atomic::{AtomicBool, AtomicPtr, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+
+/// The signal the watchdog uses to interrupt threads, once it has started.
+#[cfg(unix)]
+static INTERRUPT_SIGNAL: std::sync::OnceLock<libc::c_int> = std::sync::OnceLock::new();
+
+#[cfg(unix)]
+const RETRY_INTERVAL: std::time::Duration = std::time::Duration::from_millis(1);
+
+/// A call with a deadline, shared between the thread making it, the watchdog
+/// and the signal handler.
+#[cfg_attr(not(unix), allow(dead_code))]
+struct DeadlineState {
+    expired: AtomicBool,
+    interrupted: AtomicBool,
+    /// The addresses of the WebAssembly code the call may be executing.
+    code: Vec<Range<usize>>,
+}
+
+thread_local! {
+    /// The innermost call with a deadline on this thread. It must be atomic
+    /// since it is used by the signal handler.
+    static CURRENT: AtomicPtr<DeadlineState> = const { AtomicPtr::new(ptr::null_mut()) };
+}
+
+/// Runs `f`, interrupting any WebAssembly code it calls once `deadline` has
+/// passed, which makes the call fail with
+/// [`TrapCode::DeadlineExceeded`](crate::TrapCode::DeadlineExceeded).
+///
+/// `code` holds the addresses of the WebAssembly code `f` may run, which is
+/// where it can be interrupted safely.
+///
+/// Calls can be nested, in which case only the innermost deadline applies
+/// until the inner call returns.
+///
+/// Returns whether the call was interrupted. Calls are only interrupted
+/// where trapping is safe, so the instance can be called again afterwards.
+pub fn with_deadline<R>(
+    deadline: Instant,
+    code: Vec<Range<usize>>,
+    f: impl FnOnce() -> R,
+) -> (R, bool) {
+    let state = Arc::new(DeadlineState {
+        expired: AtomicBool::new(false),
+        interrupted: AtomicBool::new(false),
+        code,
+    });
+
+    let prev = CURRENT
+        .with(|current| current.swap(Arc::as_ptr(&state) as *mut DeadlineState, Ordering::SeqCst));
+    #[cfg(unix)]
+    let id = watchdog::arm(deadline, state.clone());
+    #[cfg(not(unix))]
+    let _ = deadline;
+
+    defer! {
+        #[cfg(unix)]
+        watchdog::disarm(id);
+        CURRENT.with(|current| current.store(prev, Ordering::SeqCst));
+    }
+
+    let result = f();
+    (result, state.interrupted.load(Ordering::SeqCst))
+}
+
+/// Sets the signal used to interrupt calls that run past their deadline,
+/// which is `SIGURG` by default. Signals meant for anything else are passed
+/// on to the handler that was installed before.
+///
+/// This must be called before the first call with a deadline, and can't be
+/// one of the signals used for traps. Returns false if the signal can't be
+/// used.
+#[cfg(unix)]
+pub fn set_interrupt_signal(signal: libc::c_int) -> bool {
+    let used_for_traps = [libc::SIGSEGV, libc::SIGBUS, libc::SIGFPE, libc::SIGILL];
+    !used_for_traps.contains(&signal) && INTERRUPT_SIGNAL.set(signal).is_ok()
+}
+
+/// The signal used to interrupt calls, if the watchdog was started.
+#[cfg(unix)]
+pub(crate) fn interrupt_signal() -> Option<libc::c_int> {
+    INTERRUPT_SIGNAL.get().copied()
+}
+
+/// Whether the innermost call with a deadline on this thread has run past
+/// it, in which case the call is considered interrupted and should trap.
+///
+/// Libcalls check this once they are done, since the signal handler leaves
+/// them to run.
+pub(crate) fn take_expired() -> bool {
+    let state = CURRENT.with(|current| current.load(Ordering::Relaxed));
+    if state.is_null() {
+        return false;
+    }
+    // SAFETY: the state outlives the call, which this thread is making
+    let state = unsafe { &*state };
+
+    let expired = state.expired.load(Ordering::Relaxed);
+    if expired {
+        state.interrupted.store(true, Ordering::Relaxed);
+    }
+    expired
+}
+
+/// Handles an interrupt signal that arrived while executing `pc`, using
+/// `interrupt` to unwind the call. `on_wasm_stack` says whether the thread is
+/// on the stack of a call into WebAssembly, rather than running a host
+/// function. Returns false if the signal wasn't sent by the watchdog.
+///
+/// # Safety
+///
+/// Must only be called from the signal handler.
+#[cfg(unix)]
+pub(crate) unsafe fn handle_signal(
+    pc: usize,
+    on_wasm_stack: bool,
+    interrupt: impl FnOnce() -> bool,
+) -> bool {
+    let state = CURRENT.with(|current| current.load(Ordering::Relaxed));
+    if state.is_null() {
+        return false;
+    }
+    let state = &*state;
+
+    if !state.expired.load(Ordering::Relaxed) {
+        // The innermost deadline is the one that counts
+        return false;
+    }
+    if !on_wasm_stack || !state.code.iter().any(|range| range.contains(&pc)) {
+        // Host functions are left to return on their own, and libcalls check
+        // the deadline before they return
+        return true;
+    }
+
+    // A call that was interrupted can be interrupted again, e.g. when the
+    // host function that made it carries on calling wasm
+    if interrupt() {
+        state.interrupted.store(true, Ordering::Relaxed);
+    }
+    true
+}
+
+#[cfg(unix)]
+mod watchdog {
+    use super::{DeadlineState, INTERRUPT_SIGNAL, RETRY_INTERVAL};
+    use std::sync::atomic::Ordering;
+    use std::sync::{Arc, Condvar, LazyLock, Mutex, Once};
+    use std::time::Instant;
+
+    struct Armed {
+        id: u64,
+        thread: libc::pthread_t,
+        deadline: Instant,
+        state: Arc<DeadlineState>,
+    }
+
+    #[derive(Default)]
+    struct Watchdog {
+        armed: Mutex<(u64, Vec<Armed>)>,
+        changed: Condvar,
+    }
+
+    fn watchdog() -> &'static Watchdog {
+        static WATCHDOG: LazyLock<Watchdog> = LazyLock::new(Watchdog::default);
+        static START: Once = Once::new();
+
+        START.call_once(|| {
+            let signal = *INTERRUPT_SIGNAL.get_or_init(|| libc::SIGURG);
+            unsafe { crate::trap::traphandlers::register_interrupt_handler(signal) };
+            std::thread::Builder::new()
+                .name("wasmer-watchdog".to_string())
+                .spawn(move || WATCHDOG.run(signal))
+                .expect("unable to start the watchdog thread");
+        });
+        &WATCHDOG
+    }
+
+    /// Starts watching the current thread.
+    pub(super) fn arm(deadline: Instant, state: Arc<DeadlineState>) -> u64 {
+        let watchdog = watchdog();
+        let mut armed = watchdog.armed.lock().unwrap();
+        let (next_id, calls) = &mut *armed;
+        let id = *next_id;
+        *next_id += 1;
+        calls.push(Armed {
+            id,
+            thread: unsafe { libc::pthread_self() },
+            deadline,
+            state,
+        });
+        watchdog.changed.notify_one();
+        id
+    }
+
+    /// Stops watching a call, after which the thread is no longer sent any
+    /// signals for it.
+    pub(super) fn disarm(id: u64) {
+        let watchdog = watchdog();
+        let mut armed = watchdog.armed.lock().unwrap();
+        armed.1.retain(|call| call.id != id);
+    }
+
+    impl Watchdog {
+        fn run(&self, signal: libc::c_int) {
+            let mut armed = self.armed.lock().unwrap();
+            loop {
+                let now = Instant::now();
+                let mut wake_up: Option<Instant> = None;
+
+                for call in &armed.1 {
+                    let next = if call.deadline <= now {
+                        call.state.expired.store(true, Ordering::SeqCst);
+                        // The thread can't exit while the call is armed, since
+                        // disarming it needs the lock we're holding
+                        unsafe { libc::pthread_kill(call.thread, signal) };
+                        now + RETRY_INTERVAL
+                    } else {
+                        call.deadline
+                    };
+                    wake_up = Some(wake_up.map_or(next, |wake_up| wake_up.min(next)));
+                }
+
+                armed = match wake_up {
+                    Some(wake_up) => {
+                        self.changed
+                            .wait_timeout(armed, wake_up.saturating_duration_since(now))
+                            .unwrap()
+                            .0
+                    }
+                    None => self.changed.wait(armed).unwrap(),
+                };
+            }
+        }
+    }
+}
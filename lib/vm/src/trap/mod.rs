@@ -4,14 +4,19 @@
 //! This is the module that facilitates the usage of Traps
 //! in Wasmer Runtime
 
+mod deadline;
 #[allow(clippy::module_inception)]
 mod trap;
 mod traphandlers;
 
+#[cfg(unix)]
+pub use deadline::set_interrupt_signal;
+pub(crate) use deadline::take_expired as take_expired_deadline;
+pub use deadline::with_deadline;
 pub use trap::Trap;
 pub use traphandlers::{
-    catch_traps, on_host_stack, raise_lib_trap, raise_user_trap, set_stack_size,
-    wasmer_call_trampoline, TrapHandlerFn, VMConfig,
+    catch_traps, on_host_stack, raise_lib_trap, raise_user_trap, set_max_nested_calls,
+    set_stack_size, wasmer_call_trampoline, TrapHandlerFn, VMConfig,
 };
 pub use traphandlers::{init_traps, resume_panic};
 pub use wasmer_types::TrapCode;
@@ -6,6 +6,8 @@
 //! WebAssembly trap handling, which is built on top of the lower-level
 //! signalhandling mechanisms.
 
+#[cfg(unix)]
+use super::deadline;
 use crate::vmcontext::{VMFunctionContext, VMTrampoline};
 use crate::{Trap, VMContext, VMFunctionBody};
 use backtrace::Backtrace;
@@ -40,6 +42,8 @@ static MAGIC: u8 = 0xc0;
 
 static DEFAULT_STACK_SIZE: AtomicUsize = AtomicUsize::new(1024 * 1024);
 
+static MAX_NESTED_CALLS: AtomicUsize = AtomicUsize::new(usize::MAX);
+
 // Current definition of `ucontext_t` in the `libc` crate is incorrect
 // on aarch64-apple-drawin so it's defined here with a more accurate definition.
 #[repr(C)]
@@ -80,6 +84,18 @@ pub fn set_stack_size(size: usize) {
     DEFAULT_STACK_SIZE.store(size.clamp(8 * 1024, 100 * 1024 * 1024), Ordering::Relaxed);
 }
 
+/// Sets how deeply calls into Wasm may be nested on one thread, e.g. by
+/// host functions calling back into the instance that called them, before
+/// the next one traps with [`TrapCode::StackOverflow`]. There is no limit by
+/// default.
+///
+/// Every nested call runs on a Wasm stack of its own, but the host
+/// functions in between share the stack of the thread, so a thread making
+/// deeply nested calls needs a correspondingly large stack.
+pub fn set_max_nested_calls(max: usize) {
+    MAX_NESTED_CALLS.store(max.max(1), Ordering::Relaxed);
+}
+
 cfg_if::cfg_if! {
     if #[cfg(unix)] {
         /// Function which may handle custom signals while processing traps.
@@ -143,34 +159,41 @@ cfg_if::cfg_if! {
         static mut PREV_SIGBUS: MaybeUninit<libc::sigaction> = MaybeUninit::uninit();
         static mut PREV_SIGILL: MaybeUninit<libc::sigaction> = MaybeUninit::uninit();
         static mut PREV_SIGFPE: MaybeUninit<libc::sigaction> = MaybeUninit::uninit();
+        static mut PREV_INTERRUPT: MaybeUninit<libc::sigaction> = MaybeUninit::uninit();
 
-        unsafe fn platform_init() {
-            let register = |slot: &mut MaybeUninit<libc::sigaction>, signal: i32| {
-                let mut handler: libc::sigaction = mem::zeroed();
-                // The flags here are relatively careful, and they are...
-                //
-                // SA_SIGINFO gives us access to information like the program
-                // counter from where the fault happened.
-                //
-                // SA_ONSTACK allows us to handle signals on an alternate stack,
-                // so that the handler can run in response to running out of
-                // stack space on the main stack. Rust installs an alternate
-                // stack with sigaltstack, so we rely on that.
-                //
-                // SA_NODEFER allows us to reenter the signal handler if we
-                // crash while handling the signal, and fall through to the
-                // Breakpad handler by testing handlingSegFault.
-                handler.sa_flags = libc::SA_SIGINFO | libc::SA_NODEFER | libc::SA_ONSTACK;
-                handler.sa_sigaction = trap_handler as usize;
-                libc::sigemptyset(&mut handler.sa_mask);
-                if libc::sigaction(signal, &handler, slot.as_mut_ptr()) != 0 {
-                    panic!(
-                        "unable to install signal handler: {}",
-                        io::Error::last_os_error(),
-                    );
-                }
-            };
+        unsafe fn register(slot: &mut MaybeUninit<libc::sigaction>, signal: i32) {
+            let mut handler: libc::sigaction = mem::zeroed();
+            // The flags here are relatively careful, and they are...
+            //
+            // SA_SIGINFO gives us access to information like the program
+            // counter from where the fault happened.
+            //
+            // SA_ONSTACK allows us to handle signals on an alternate stack,
+            // so that the handler can run in response to running out of
+            // stack space on the main stack. Rust installs an alternate
+            // stack with sigaltstack, so we rely on that.
+            //
+            // SA_NODEFER allows us to reenter the signal handler if we
+            // crash while handling the signal, and fall through to the
+            // Breakpad handler by testing handlingSegFault.
+            handler.sa_flags = libc::SA_SIGINFO | libc::SA_NODEFER | libc::SA_ONSTACK;
+            handler.sa_sigaction = trap_handler as usize;
+            libc::sigemptyset(&mut handler.sa_mask);
+            if libc::sigaction(signal, &handler, slot.as_mut_ptr()) != 0 {
+                panic!(
+                    "unable to install signal handler: {}",
+                    io::Error::last_os_error(),
+                );
+            }
+        }
+
+        /// Installs the handler for the signal used to interrupt calls that
+        /// run past their deadline.
+        pub(super) unsafe fn register_interrupt_handler(signal: libc::c_int) {
+            register(&mut PREV_INTERRUPT, signal);
+        }
 
+        unsafe fn platform_init() {
             // Allow handling OOB with signals on all architectures
             register(&mut PREV_SIGSEGV, libc::SIGSEGV);
 
@@ -236,6 +259,7 @@ cfg_if::cfg_if! {
                 libc::SIGBUS => &PREV_SIGBUS,
                 libc::SIGFPE => &PREV_SIGFPE,
                 libc::SIGILL => &PREV_SIGILL,
+                _ if Some(signum) == deadline::interrupt_signal() => &PREV_INTERRUPT,
                 _ => panic!("unknown signal: {signum}"),
             };
             // We try to get the fault address associated to this signal
@@ -255,14 +279,27 @@ cfg_if::cfg_if! {
             };
             let ucontext = &mut *(context as *mut ucontext_t);
             let (pc, sp) = get_pc_sp(ucontext);
-            let handled = TrapHandlerContext::handle_trap(
-                pc,
-                sp,
-                maybe_fault_address,
-                trap_code,
-                |regs| update_context(ucontext, regs),
-                |handler| handler(signum, siginfo, context),
-            );
+            let handled = if Some(signum) == deadline::interrupt_signal() {
+                deadline::handle_signal(pc, TrapHandlerContext::is_on_wasm_stack(sp), || {
+                    TrapHandlerContext::handle_trap(
+                        pc,
+                        sp,
+                        None,
+                        Some(TrapCode::DeadlineExceeded),
+                        |regs| update_context(ucontext, regs),
+                        |_| false,
+                    )
+                })
+            } else {
+                TrapHandlerContext::handle_trap(
+                    pc,
+                    sp,
+                    maybe_fault_address,
+                    trap_code,
+                    |regs| update_context(ucontext, regs),
+                    |handler| handler(signum, siginfo, context),
+                )
+            };
 
             if handled {
                 return;
@@ -713,6 +750,16 @@ where
 {
     // Ensure that per-thread initialization is done.
     lazy_per_thread_init()?;
+
+    let depth = NESTED_CALLS.with(|cell| cell.get());
+    if depth >= MAX_NESTED_CALLS.load(Ordering::Relaxed) {
+        return Err(Trap::lib(TrapCode::StackOverflow));
+    }
+    NESTED_CALLS.with(|cell| cell.set(depth + 1));
+    defer! {
+        NESTED_CALLS.with(|cell| cell.set(depth));
+    }
+
     let stack_size = config
         .wasm_stack_size
         .unwrap_or_else(|| DEFAULT_STACK_SIZE.load(Ordering::Relaxed));
@@ -730,6 +777,8 @@ where
 thread_local! {
     static YIELDER: Cell<Option<NonNull<Yielder<(), UnwindReason>>>> = const { Cell::new(None) };
     static TRAP_HANDLER: AtomicPtr<TrapHandlerContext> = const { AtomicPtr::new(ptr::null_mut()) };
+    /// How many calls of `catch_traps` are running on this thread.
+    static NESTED_CALLS: Cell<usize> = const { Cell::new(0) };
 }
 
 /// Read-only information that is used by signal handlers to handle and recover
@@ -745,6 +794,7 @@ struct TrapHandlerContext {
         Option<TrapCode>,
         &mut dyn FnMut(TrapHandlerRegs),
     ) -> bool,
+    stack_ptr_in_bounds: fn(*const u8, usize) -> bool,
     custom_trap: Option<*const TrapHandlerFn<'static>>,
 }
 struct TrapHandlerContextInner<T> {
@@ -780,10 +830,18 @@ impl TrapHandlerContext {
                 )
             }
         }
+        fn in_bounds<T>(ptr: *const u8, sp: usize) -> bool {
+            unsafe {
+                (*(ptr as *const TrapHandlerContextInner<T>))
+                    .coro_trap_handler
+                    .stack_ptr_in_bounds(sp)
+            }
+        }
         let inner = TrapHandlerContextInner { coro_trap_handler };
         let ctx = Self {
             inner: &inner as *const _ as *const u8,
             handle_trap: func::<T>,
+            stack_ptr_in_bounds: in_bounds::<T>,
             custom_trap,
         };
 
@@ -802,6 +860,19 @@ impl TrapHandlerContext {
         f()
     }
 
+    /// Checks whether `sp` is on the stack of the innermost call into wasm,
+    /// i.e. whether a trap there could be handled.
+    #[cfg(unix)]
+    unsafe fn is_on_wasm_stack(sp: usize) -> bool {
+        let ptr = TRAP_HANDLER.with(|ptr| ptr.load(Ordering::Relaxed));
+        if ptr.is_null() {
+            return false;
+        }
+
+        let ctx = &*ptr;
+        (ctx.stack_ptr_in_bounds)(ctx.inner, sp)
+    }
+
     /// Attempts to handle the trap if it's a wasm trap.
     unsafe fn handle_trap(
         pc: usize,
@@ -0,0 +1,215 @@
+//! Finding the pages of a memory that were written to, using the kernel's
+//! asynchronous userfaultfd write-protection (Linux 6.7 and up).
+//!
+//! The watched pages are write-protected, and the kernel takes note of the
+//! first write to each of them and lifts the protection itself, without
+//! involving a signal handler or a thread of ours. This also covers writes
+//! the kernel makes on behalf of the process, e.g. a `read(2)` straight into
+//! the memory. `PAGEMAP_SCAN` then reports the pages that were written to,
+//! and protects them again in the same step.
+
+use std::fs::File;
+use std::io;
+use std::ops::Range;
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+
+const UFFD_API: u64 = 0xAA;
+const UFFD_USER_MODE_ONLY: libc::c_int = 1;
+const UFFD_FEATURE_WP_UNPOPULATED: u64 = 1 << 13;
+const UFFD_FEATURE_WP_ASYNC: u64 = 1 << 15;
+const UFFDIO_REGISTER_MODE_WP: u64 = 1 << 1;
+const UFFDIO_WRITEPROTECT_MODE_WP: u64 = 1 << 0;
+
+const PM_SCAN_WP_MATCHING: u64 = 1 << 0;
+const PM_SCAN_CHECK_WPASYNC: u64 = 1 << 1;
+const PAGE_IS_WRITTEN: u64 = 1 << 1;
+
+/// `_IOWR()`, for the architectures that use the generic ioctl encoding.
+const fn iowr<T>(ty: u8, nr: u8) -> libc::Ioctl {
+    ((3 << 30) | ((std::mem::size_of::<T>() as u32) << 16) | ((ty as u32) << 8) | nr as u32)
+        as libc::Ioctl
+}
+
+const UFFDIO_API: libc::Ioctl = iowr::<UffdioApi>(0xAA, 0x3F);
+const UFFDIO_REGISTER: libc::Ioctl = iowr::<UffdioRegister>(0xAA, 0x00);
+const UFFDIO_WRITEPROTECT: libc::Ioctl = iowr::<UffdioWriteprotect>(0xAA, 0x06);
+const PAGEMAP_SCAN: libc::Ioctl = iowr::<PmScanArg>(b'f', 16);
+
+#[repr(C)]
+struct UffdioApi {
+    api: u64,
+    features: u64,
+    ioctls: u64,
+}
+
+#[repr(C)]
+struct UffdioRange {
+    start: u64,
+    len: u64,
+}
+
+#[repr(C)]
+struct UffdioRegister {
+    range: UffdioRange,
+    mode: u64,
+    ioctls: u64,
+}
+
+#[repr(C)]
+struct UffdioWriteprotect {
+    range: UffdioRange,
+    mode: u64,
+}
+
+#[repr(C)]
+struct PmScanArg {
+    size: u64,
+    flags: u64,
+    start: u64,
+    end: u64,
+    walk_end: u64,
+    vec: u64,
+    vec_len: u64,
+    max_pages: u64,
+    category_inverted: u64,
+    category_mask: u64,
+    category_anyof_mask: u64,
+    return_mask: u64,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Default)]
+struct PageRegion {
+    start: u64,
+    end: u64,
+    categories: u64,
+}
+
+/// Keeps track of the pages of a range of memory that were written to.
+///
+/// The range must be anonymous memory, such as a [`Mmap`](crate::Mmap).
+/// Dropping the watch stops protecting it.
+#[derive(Debug)]
+pub struct WriteWatch {
+    uffd: OwnedFd,
+    pagemap: File,
+    start: usize,
+    len: usize,
+}
+
+impl WriteWatch {
+    /// Starts watching the `len` bytes at `start`, which must both be page
+    /// aligned.
+    ///
+    /// Fails if the kernel can't watch the memory, e.g. because it is too old
+    /// or because userfaultfd isn't allowed.
+    pub fn new(start: *mut u8, len: usize) -> io::Result<Self> {
+        let uffd = unsafe {
+            libc::syscall(
+                libc::SYS_userfaultfd,
+                libc::O_CLOEXEC | libc::O_NONBLOCK | UFFD_USER_MODE_ONLY,
+            )
+        };
+        if uffd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let uffd = unsafe { OwnedFd::from_raw_fd(uffd as libc::c_int) };
+
+        let mut api = UffdioApi {
+            api: UFFD_API,
+            features: UFFD_FEATURE_WP_ASYNC | UFFD_FEATURE_WP_UNPOPULATED,
+            ioctls: 0,
+        };
+        ioctl(&uffd, UFFDIO_API, &mut api)?;
+
+        let mut watch = Self {
+            uffd,
+            pagemap: File::open("/proc/self/pagemap")?,
+            start: start as usize,
+            len: 0,
+        };
+        watch.extend(len)?;
+        Ok(watch)
+    }
+
+    /// The address of the first byte being watched.
+    pub fn start(&self) -> *mut u8 {
+        self.start as *mut u8
+    }
+
+    /// Watches the range up to `len` bytes from the start, after memory was
+    /// added to the end of it. Shrinking the range isn't supported.
+    pub fn extend(&mut self, len: usize) -> io::Result<()> {
+        if len <= self.len {
+            return Ok(());
+        }
+        let range = || UffdioRange {
+            start: (self.start + self.len) as u64,
+            len: (len - self.len) as u64,
+        };
+
+        let mut register = UffdioRegister {
+            range: range(),
+            mode: UFFDIO_REGISTER_MODE_WP,
+            ioctls: 0,
+        };
+        ioctl(&self.uffd, UFFDIO_REGISTER, &mut register)?;
+        let mut protect = UffdioWriteprotect {
+            range: range(),
+            mode: UFFDIO_WRITEPROTECT_MODE_WP,
+        };
+        ioctl(&self.uffd, UFFDIO_WRITEPROTECT, &mut protect)?;
+
+        self.len = len;
+        Ok(())
+    }
+
+    /// Calls `f` with the ranges (relative to the start) that were written to
+    /// since the watch started or since the last call, whichever was later.
+    pub fn take_written(&mut self, f: impl FnMut(Range<usize>)) -> io::Result<()> {
+        self.scan(PM_SCAN_WP_MATCHING | PM_SCAN_CHECK_WPASYNC, f)
+    }
+
+    /// Like [`take_written()`](Self::take_written), but leaves the ranges to
+    /// be taken later.
+    pub fn written(&self, f: impl FnMut(Range<usize>)) -> io::Result<()> {
+        self.scan(PM_SCAN_CHECK_WPASYNC, f)
+    }
+
+    fn scan(&self, flags: u64, mut f: impl FnMut(Range<usize>)) -> io::Result<()> {
+        let mut regions = [PageRegion::default(); 64];
+        let end = (self.start + self.len) as u64;
+        let mut start = self.start as u64;
+
+        while start < end {
+            let mut arg = PmScanArg {
+                size: std::mem::size_of::<PmScanArg>() as u64,
+                flags,
+                start,
+                end,
+                walk_end: 0,
+                vec: regions.as_mut_ptr() as u64,
+                vec_len: regions.len() as u64,
+                max_pages: 0,
+                category_inverted: 0,
+                category_mask: PAGE_IS_WRITTEN,
+                category_anyof_mask: 0,
+                return_mask: PAGE_IS_WRITTEN,
+            };
+            let found = ioctl(&self.pagemap, PAGEMAP_SCAN, &mut arg)?;
+            for region in &regions[..found] {
+                f(region.start as usize - self.start..region.end as usize - self.start);
+            }
+            start = arg.walk_end;
+        }
+        Ok(())
+    }
+}
+
+fn ioctl<T>(fd: &impl AsRawFd, request: libc::Ioctl, arg: &mut T) -> io::Result<usize> {
+    let result = unsafe { libc::ioctl(fd.as_raw_fd(), request, arg as *mut T) };
+    if result < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(result as usize)
+}
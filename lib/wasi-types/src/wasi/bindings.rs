@@ -1044,6 +1044,7 @@ pub enum Sockoption {
     MulticastTtlV4,
     Type,
     Proto,
+    TlsAlpn,
 }
 impl core::fmt::Debug for Sockoption {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
@@ -1075,6 +1076,7 @@ impl core::fmt::Debug for Sockoption {
             Sockoption::MulticastTtlV4 => f.debug_tuple("Sockoption::MulticastTtlV4").finish(),
             Sockoption::Type => f.debug_tuple("Sockoption::Type").finish(),
             Sockoption::Proto => f.debug_tuple("Sockoption::Proto").finish(),
+            Sockoption::TlsAlpn => f.debug_tuple("Sockoption::TlsAlpn").finish(),
         }
     }
 }
@@ -3182,6 +3184,7 @@ unsafe impl wasmer::FromToNativeWasmType for Sockoption {
             24 => Self::MulticastTtlV4,
             25 => Self::Type,
             26 => Self::Proto,
+            27 => Self::TlsAlpn,
 
             q => {
                 tracing::debug!("could not serialize number {q} to enum Sockoption");
@@ -154,6 +154,7 @@ impl std::fmt::Display for Sockoption {
             Self::MulticastTtlV4 => "Sockoption::MulticastTtlV4",
             Self::Type => "Sockoption::Type",
             Self::Proto => "Sockoption::Proto",
+            Self::TlsAlpn => "Sockoption::TlsAlpn",
         };
         write!(f, "{s}")
     }
@@ -313,6 +314,7 @@ impl From<Errno> for std::io::ErrorKind {
     fn from(err: Errno) -> Self {
         use std::io::ErrorKind;
         match err {
+            Errno::Toobig => ErrorKind::ArgumentListTooLong,
             Errno::Access => ErrorKind::PermissionDenied,
             Errno::Addrinuse => ErrorKind::AddrInUse,
             Errno::Addrnotavail => ErrorKind::AddrNotAvailable,
@@ -320,21 +322,38 @@ impl From<Errno> for std::io::ErrorKind {
             Errno::Already => ErrorKind::AlreadyExists,
             Errno::Badf => ErrorKind::InvalidInput,
             Errno::Badmsg => ErrorKind::InvalidData,
+            Errno::Busy => ErrorKind::ResourceBusy,
             Errno::Canceled => ErrorKind::Interrupted,
             Errno::Connaborted => ErrorKind::ConnectionAborted,
             Errno::Connrefused => ErrorKind::ConnectionRefused,
             Errno::Connreset => ErrorKind::ConnectionReset,
+            Errno::Deadlk => ErrorKind::Deadlock,
             Errno::Exist => ErrorKind::AlreadyExists,
+            Errno::Fbig => ErrorKind::FileTooLarge,
+            Errno::Hostunreach => ErrorKind::HostUnreachable,
             Errno::Intr => ErrorKind::Interrupted,
             Errno::Inval => ErrorKind::InvalidInput,
+            Errno::Isdir => ErrorKind::IsADirectory,
+            Errno::Mlink => ErrorKind::TooManyLinks,
+            Errno::Netdown => ErrorKind::NetworkDown,
             Errno::Netreset => ErrorKind::ConnectionReset,
+            Errno::Netunreach => ErrorKind::NetworkUnreachable,
             Errno::Noent => ErrorKind::NotFound,
             Errno::Nomem => ErrorKind::OutOfMemory,
             Errno::Nomsg => ErrorKind::InvalidData,
+            Errno::Nospc => ErrorKind::StorageFull,
             Errno::Notconn => ErrorKind::NotConnected,
+            Errno::Notdir => ErrorKind::NotADirectory,
+            Errno::Notempty => ErrorKind::DirectoryNotEmpty,
+            Errno::Notsup => ErrorKind::Unsupported,
             Errno::Perm => ErrorKind::PermissionDenied,
             Errno::Pipe => ErrorKind::BrokenPipe,
+            Errno::Proto => ErrorKind::UnexpectedEof,
+            Errno::Rofs => ErrorKind::ReadOnlyFilesystem,
+            Errno::Spipe => ErrorKind::NotSeekable,
+            Errno::Stale => ErrorKind::StaleNetworkFileHandle,
             Errno::Timedout => ErrorKind::TimedOut,
+            Errno::Txtbsy => ErrorKind::ExecutableFileBusy,
             _ => ErrorKind::Other,
         }
     }
@@ -347,34 +366,58 @@ impl From<Errno> for std::io::Error {
     }
 }
 
-impl From<std::io::Error> for Errno {
-    fn from(err: std::io::Error) -> Self {
+impl From<std::io::ErrorKind> for Errno {
+    fn from(kind: std::io::ErrorKind) -> Self {
         use std::io::ErrorKind;
-        match err.kind() {
+        match kind {
             ErrorKind::NotFound => Errno::Noent,
             ErrorKind::PermissionDenied => Errno::Perm,
             ErrorKind::ConnectionRefused => Errno::Connrefused,
             ErrorKind::ConnectionReset => Errno::Connreset,
+            ErrorKind::HostUnreachable => Errno::Hostunreach,
+            ErrorKind::NetworkUnreachable => Errno::Netunreach,
             ErrorKind::ConnectionAborted => Errno::Connaborted,
             ErrorKind::NotConnected => Errno::Notconn,
             ErrorKind::AddrInUse => Errno::Addrinuse,
             ErrorKind::AddrNotAvailable => Errno::Addrnotavail,
+            ErrorKind::NetworkDown => Errno::Netdown,
             ErrorKind::BrokenPipe => Errno::Pipe,
             ErrorKind::AlreadyExists => Errno::Exist,
             ErrorKind::WouldBlock => Errno::Again,
-            ErrorKind::InvalidInput => Errno::Io,
+            ErrorKind::NotADirectory => Errno::Notdir,
+            ErrorKind::IsADirectory => Errno::Isdir,
+            ErrorKind::DirectoryNotEmpty => Errno::Notempty,
+            ErrorKind::ReadOnlyFilesystem => Errno::Rofs,
+            ErrorKind::StaleNetworkFileHandle => Errno::Stale,
+            ErrorKind::InvalidInput => Errno::Inval,
             ErrorKind::InvalidData => Errno::Io,
             ErrorKind::TimedOut => Errno::Timedout,
-            ErrorKind::WriteZero => Errno::Io,
+            ErrorKind::WriteZero => Errno::Nospc,
+            ErrorKind::StorageFull => Errno::Nospc,
+            ErrorKind::NotSeekable => Errno::Spipe,
+            ErrorKind::FileTooLarge => Errno::Fbig,
+            ErrorKind::ResourceBusy => Errno::Busy,
+            ErrorKind::ExecutableFileBusy => Errno::Txtbsy,
+            ErrorKind::Deadlock => Errno::Deadlk,
+            ErrorKind::TooManyLinks => Errno::Mlink,
+            ErrorKind::ArgumentListTooLong => Errno::Toobig,
             ErrorKind::Interrupted => Errno::Intr,
-            ErrorKind::Other => Errno::Io,
-            ErrorKind::UnexpectedEof => Errno::Io,
             ErrorKind::Unsupported => Errno::Notsup,
+            ErrorKind::UnexpectedEof => Errno::Proto,
+            ErrorKind::OutOfMemory => Errno::Nomem,
+            ErrorKind::Other => Errno::Io,
+            // `ErrorKind` is non-exhaustive
             _ => Errno::Io,
         }
     }
 }
 
+impl From<std::io::Error> for Errno {
+    fn from(err: std::io::Error) -> Self {
+        err.kind().into()
+    }
+}
+
 // TODO: if necessary, must be implemented in wit-bindgen
 unsafe impl wasmer::FromToNativeWasmType for JoinFlags {
     type Native = i32;
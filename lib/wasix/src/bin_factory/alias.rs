@@ -0,0 +1,203 @@
+use std::{collections::HashMap, fmt::Display, str::FromStr};
+
+use wasmer_config::package::PackageSource;
+
+/// A command name that resolves to a command provided by another package.
+///
+/// When a guest spawns or executes a process called [`CommandAlias::name`],
+/// the [`CommandAlias::package`] is loaded on demand and the
+/// [`CommandAlias::command`] (or the package's entrypoint, when no command
+/// is specified) is executed in its place.
+///
+/// The textual form is `name=pkg[:command]`, for example
+/// `python=python/python:python3`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommandAlias {
+    /// The name the guest will use to invoke the command.
+    pub name: String,
+    /// The package providing the command.
+    pub package: PackageSource,
+    /// The command within [`CommandAlias::package`] to run.
+    pub command: Option<String>,
+}
+
+impl CommandAlias {
+    pub fn new(name: impl Into<String>, package: PackageSource, command: Option<String>) -> Self {
+        CommandAlias {
+            name: name.into(),
+            package,
+            command,
+        }
+    }
+
+    /// If this alias points at a bare name (e.g. `py=python`) rather than a
+    /// fully qualified package, returns the name so it can be looked up in
+    /// the other aliases.
+    fn target_name(&self) -> Option<&str> {
+        match self.package.as_named() {
+            Some(ident)
+                if ident.registry.is_none()
+                    && ident.namespace.is_none()
+                    && ident.tag.is_none()
+                    && self.command.is_none() =>
+            {
+                Some(ident.name.as_str())
+            }
+            _ => None,
+        }
+    }
+}
+
+impl Display for CommandAlias {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}={}", self.name, self.package)?;
+        if let Some(command) = &self.command {
+            write!(f, ":{command}")?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for CommandAlias {
+    type Err = CommandAliasError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = |reason: &str| CommandAliasError::InvalidFormat {
+            input: s.to_string(),
+            reason: reason.to_string(),
+        };
+
+        let (name, target) = s
+            .split_once('=')
+            .ok_or_else(|| invalid("expected <NAME>=<PACKAGE>[:<COMMAND>]"))?;
+        let name = name.trim();
+        let target = target.trim();
+
+        if name.is_empty() {
+            return Err(invalid("the alias name cannot be empty"));
+        }
+        if name.contains('/') {
+            return Err(invalid("the alias name cannot contain a '/'"));
+        }
+
+        // Note: URLs and registry-qualified names also contain a ':', so only
+        // treat the suffix as a command when it looks like a plain name.
+        let (package, command) = match target.rsplit_once(':') {
+            Some((package, command)) if !command.is_empty() && !command.contains(['/', '\\']) => {
+                (package, Some(command.to_string()))
+            }
+            _ => (target, None),
+        };
+
+        let package = package
+            .parse::<PackageSource>()
+            .map_err(|e| invalid(&e.to_string()))?;
+
+        Ok(CommandAlias {
+            name: name.to_string(),
+            package,
+            command,
+        })
+    }
+}
+
+/// Errors that may occur when registering a [`CommandAlias`].
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum CommandAliasError {
+    #[error("invalid command alias \"{input}\": {reason}")]
+    InvalidFormat { input: String, reason: String },
+    #[error("the \"{alias}\" alias would shadow the existing command at \"{path}\"")]
+    ShadowsCommand { alias: String, path: String },
+    #[error("the command aliases form a cycle: {}", .0.join(" -> "))]
+    Cycle(Vec<String>),
+}
+
+/// Follow a chain of aliases starting at `name` until it reaches an alias
+/// that refers to a real package.
+pub(crate) fn resolve_alias_chain<'a>(
+    aliases: &'a HashMap<String, CommandAlias>,
+    name: &str,
+) -> Result<Option<&'a CommandAlias>, CommandAliasError> {
+    let Some(mut current) = aliases.get(name) else {
+        return Ok(None);
+    };
+
+    let mut visited = vec![current.name.clone()];
+
+    while let Some(next) = current.target_name().and_then(|target| aliases.get(target)) {
+        if visited.contains(&next.name) {
+            visited.push(next.name.clone());
+            return Err(CommandAliasError::Cycle(visited));
+        }
+        visited.push(next.name.clone());
+        current = next;
+    }
+
+    Ok(Some(current))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn aliases(items: &[&str]) -> HashMap<String, CommandAlias> {
+        items
+            .iter()
+            .map(|s| s.parse::<CommandAlias>().unwrap())
+            .map(|alias| (alias.name.clone(), alias))
+            .collect()
+    }
+
+    #[test]
+    fn parse_command_aliases() {
+        let alias: CommandAlias = "python=python/python".parse().unwrap();
+        assert_eq!(alias.name, "python");
+        assert_eq!(alias.package, "python/python".parse().unwrap());
+        assert_eq!(alias.command, None);
+
+        let alias: CommandAlias = "node=wasmer/node@lts:node-lts".parse().unwrap();
+        assert_eq!(alias.name, "node");
+        assert_eq!(alias.package, "wasmer/node@lts".parse().unwrap());
+        assert_eq!(alias.command.as_deref(), Some("node-lts"));
+        assert_eq!(alias.to_string(), "node=wasmer/node@lts:node-lts");
+
+        let alias: CommandAlias = "py=https://example.com/python.webc".parse().unwrap();
+        assert_eq!(
+            alias.package,
+            "https://example.com/python.webc".parse().unwrap()
+        );
+        assert_eq!(alias.command, None);
+
+        assert!("python".parse::<CommandAlias>().is_err());
+        assert!("=python/python".parse::<CommandAlias>().is_err());
+        assert!("bin/python=python/python".parse::<CommandAlias>().is_err());
+    }
+
+    #[test]
+    fn follow_alias_chains() {
+        let aliases = aliases(&["py=python3", "python3=python/python:python"]);
+
+        let resolved = resolve_alias_chain(&aliases, "py").unwrap().unwrap();
+
+        assert_eq!(resolved.name, "python3");
+        assert_eq!(resolved.command.as_deref(), Some("python"));
+        assert!(resolve_alias_chain(&aliases, "node").unwrap().is_none());
+    }
+
+    #[test]
+    fn detect_alias_cycles() {
+        let aliases = aliases(&["a=b", "b=c", "c=a"]);
+
+        let err = resolve_alias_chain(&aliases, "a").unwrap_err();
+
+        assert_eq!(
+            err,
+            CommandAliasError::Cycle(vec![
+                "a".to_string(),
+                "b".to_string(),
+                "c".to_string(),
+                "a".to_string()
+            ])
+        );
+    }
+}
@@ -11,10 +11,14 @@ use wasmer_package::package::Package;
 use webc::compat::SharedBytes;
 use webc::Container;
 
+use super::SpawnCancellation;
 use crate::{
     runners::MappedDirectory,
-    runtime::resolver::{PackageInfo, ResolveError},
-    Runtime,
+    runtime::{
+        resolver::{PackageInfo, ResolveError, WebcHash},
+        PackageResolutionPolicy,
+    },
+    Runtime, SpawnError,
 };
 use wasmer_types::ModuleHash;
 
@@ -90,6 +94,8 @@ pub struct BinaryPackage {
     /// entrypoint.
     pub entrypoint_cmd: Option<String>,
     pub hash: OnceCell<ModuleHash>,
+    /// The SHA-256 hash of the root package's `*.webc` file, if known.
+    pub webc_hash: Option<WebcHash>,
     pub webc_fs: Arc<dyn FileSystem + Send + Sync>,
     pub commands: Vec<BinaryPackageCommand>,
     pub uses: Vec<String>,
@@ -179,21 +185,97 @@ impl BinaryPackage {
     }
 
     /// Load a [`BinaryPackage`] and all its dependencies from a registry.
+    ///
+    /// If another process is already loading the same package (see
+    /// [`Runtime::in_flight_packages()`]), this waits for it instead.
     #[tracing::instrument(level = "debug", skip_all)]
     pub async fn from_registry(
         specifier: &PackageSource,
         runtime: &(dyn Runtime + Send + Sync),
+    ) -> Result<Self, anyhow::Error> {
+        match runtime.in_flight_packages() {
+            Some(in_flight) => {
+                in_flight
+                    .get_or_load(specifier, || Self::load_from_registry(specifier, runtime))
+                    .await
+            }
+            None => Self::load_from_registry(specifier, runtime).await,
+        }
+    }
+
+    /// Like [`BinaryPackage::from_registry()`], but gives up as soon as
+    /// `cancel` is cancelled, failing with [`SpawnError::Cancelled`].
+    ///
+    /// The download is dropped rather than left to finish. That is safe for
+    /// the package loader's cache, which only moves fully downloaded
+    /// packages into place, and another process waiting for the same
+    /// package takes over the download.
+    pub async fn from_registry_cancellable(
+        specifier: &PackageSource,
+        runtime: &(dyn Runtime + Send + Sync),
+        cancel: &SpawnCancellation,
+    ) -> Result<Self, anyhow::Error> {
+        if cancel.is_cancelled() {
+            return Err(SpawnError::Cancelled.into());
+        }
+
+        tokio::select! {
+            pkg = Self::from_registry(specifier, runtime) => pkg,
+            _ = cancel.cancelled() => Err(SpawnError::Cancelled.into()),
+        }
+    }
+
+    async fn load_from_registry(
+        specifier: &PackageSource,
+        runtime: &(dyn Runtime + Send + Sync),
     ) -> Result<Self, anyhow::Error> {
         let source = runtime.source();
-        let root_summary =
-            source
-                .latest(specifier)
-                .await
-                .map_err(|error| ResolveError::Registry {
-                    package: specifier.clone(),
-                    error,
-                })?;
-        let root = runtime.package_loader().load(&root_summary).await?;
+        let registry_error = |error| ResolveError::Registry {
+            package: specifier.clone(),
+            error,
+        };
+        let (root_summary, root) = match runtime.package_resolution_policy() {
+            PackageResolutionPolicy::Cached => {
+                let summary = source.latest(specifier).await.map_err(registry_error)?;
+                let root = runtime.package_loader().load(&summary).await?;
+                (summary, root)
+            }
+            PackageResolutionPolicy::Refresh => {
+                // Remember what we would have used so we can keep it if the
+                // new version turns out to be unusable
+                let previous = source.latest(specifier).await.ok();
+                let latest = source
+                    .refresh_latest(specifier)
+                    .await
+                    .map_err(registry_error)?;
+
+                match runtime.package_loader().load(&latest).await {
+                    Ok(root) => {
+                        if let Some(previous) = previous.filter(|p| p.pkg.id != latest.pkg.id) {
+                            tracing::info!(
+                                previous = %previous.pkg.id,
+                                selected = %latest.pkg.id,
+                                "Found a newer version of the package",
+                            );
+                        }
+                        (latest, root)
+                    }
+                    Err(error) => match previous.filter(|p| p.pkg.id != latest.pkg.id) {
+                        Some(previous) => {
+                            tracing::warn!(
+                                error = &*error,
+                                rejected = %latest.pkg.id,
+                                selected = %previous.pkg.id,
+                                "Unable to load the latest version, keeping the previous one",
+                            );
+                            let root = runtime.package_loader().load(&previous).await?;
+                            (previous, root)
+                        }
+                        None => return Err(error),
+                    },
+                }
+            }
+        };
         let id = root_summary.package_id();
 
         let resolution = crate::runtime::resolver::resolve(&id, &root_summary.pkg, &source)
@@ -212,6 +294,31 @@ impl BinaryPackage {
         self.commands.iter().find(|cmd| cmd.name() == name)
     }
 
+    /// Resolve the command `name`, or the entrypoint if there is no name, as
+    /// when running `package:command` or just `package`.
+    ///
+    /// The error lists the available commands if the package doesn't have
+    /// the one asked for.
+    pub fn resolve_command(
+        &self,
+        name: Option<&str>,
+    ) -> Result<&BinaryPackageCommand, anyhow::Error> {
+        let name = match name {
+            Some(name) => name,
+            None => self.infer_entrypoint()?,
+        };
+        if let Some(cmd) = self.get_command(name) {
+            return Ok(cmd);
+        }
+
+        let mut commands: Vec<_> = self.commands.iter().map(|cmd| cmd.name()).collect();
+        commands.sort();
+        anyhow::bail!(
+            "The package doesn't have a \"{name}\" command. Please choose one of {:?}",
+            commands,
+        );
+    }
+
     /// Resolve the entrypoint command name to a [`BinaryPackageCommand`].
     pub fn get_entrypoint_command(&self) -> Option<&BinaryPackageCommand> {
         self.entrypoint_cmd
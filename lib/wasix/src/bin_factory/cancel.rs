@@ -0,0 +1,79 @@
+use std::sync::{Arc, Mutex};
+
+use tokio::sync::watch;
+use wasmer_wasix_types::wasi::Signal;
+
+use crate::os::task::process::WasiProcess;
+
+/// Cancels a program that is being started with
+/// [`spawn_exec_cancellable()`](super::spawn_exec_cancellable) or loaded with
+/// [`BinaryPackage::from_registry_cancellable()`](super::BinaryPackage::from_registry_cancellable).
+///
+/// Cancelling before the process starts running abandons whatever is in
+/// progress (downloading the package, compiling or instantiating the module),
+/// tears down the process with an exit code of [`Errno::Canceled`] and makes
+/// the spawn fail with [`SpawnError::Cancelled`](crate::SpawnError::Cancelled).
+/// Once the process is running, cancelling sends it `SIGTERM` instead, as for
+/// any graceful shutdown.
+///
+/// Clones share the same state, so one can be kept to cancel with while
+/// another is handed to the spawn.
+///
+/// [`Errno::Canceled`]: wasmer_wasix_types::wasi::Errno::Canceled
+#[derive(Debug, Clone)]
+pub struct SpawnCancellation {
+    inner: Arc<Inner>,
+}
+
+#[derive(Debug)]
+struct Inner {
+    cancelled: watch::Sender<bool>,
+    /// The process, once it is running.
+    process: Mutex<Option<WasiProcess>>,
+}
+
+impl SpawnCancellation {
+    pub fn new() -> Self {
+        SpawnCancellation {
+            inner: Arc::new(Inner {
+                cancelled: watch::Sender::new(false),
+                process: Mutex::new(None),
+            }),
+        }
+    }
+
+    /// Cancels the spawn, or asks the process to shut down if it's already
+    /// running.
+    pub fn cancel(&self) {
+        self.inner.cancelled.send_replace(true);
+        if let Some(process) = self.inner.process.lock().unwrap().as_ref() {
+            process.signal(Signal::Sigterm).ok();
+        }
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        *self.inner.cancelled.borrow()
+    }
+
+    /// Waits until [`SpawnCancellation::cancel()`] is called.
+    pub async fn cancelled(&self) {
+        let mut rx = self.inner.cancelled.subscribe();
+        // The sender lives as long as `self`, so this can't fail
+        rx.wait_for(|cancelled| *cancelled).await.ok();
+    }
+
+    /// Hands over to the graceful shutdown path once `process` is running.
+    pub(crate) fn started(&self, process: &WasiProcess) {
+        *self.inner.process.lock().unwrap() = Some(process.clone());
+        // It might have been cancelled just before we stored the process
+        if self.is_cancelled() {
+            process.signal(Signal::Sigterm).ok();
+        }
+    }
+}
+
+impl Default for SpawnCancellation {
+    fn default() -> Self {
+        Self::new()
+    }
+}
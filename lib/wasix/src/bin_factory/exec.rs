@@ -1,44 +1,144 @@
-use std::sync::Arc;
+use std::{future::Future, pin::pin, sync::Arc};
 
 use crate::{
     os::task::{
+        process::WasiProcess,
         thread::{RewindResultType, WasiThreadRunGuard},
         TaskJoinHandle,
     },
     runtime::{
         task_manager::{
-            TaskWasm, TaskWasmRecycle, TaskWasmRecycleProperties, TaskWasmRunProperties,
+            SpawnMemoryTypeOrStore, TaskWasm, TaskWasmRecycle, TaskWasmRecycleProperties,
+            TaskWasmRunProperties,
         },
         TaintReason,
     },
     syscalls::rewind_ext,
     RewindState, SpawnError, WasiError, WasiRuntimeError,
 };
+use futures::future::{select, Either};
 use tracing::*;
 use virtual_mio::InlineWaker;
 use wasmer::{Function, Memory32, Memory64, Module, RuntimeError, Store, Value};
 use wasmer_wasix_types::wasi::Errno;
 
-use super::{BinaryPackage, BinaryPackageCommand};
+use super::{
+    package_metadata::install_package_metadata, BinaryPackage, BinaryPackageCommand,
+    PackageMetadata, SpawnCancellation,
+};
 use crate::{Runtime, WasiEnv, WasiFunctionEnv};
 
-#[tracing::instrument(level = "trace", skip_all, fields(%name, package_id=%binary.id))]
 pub async fn spawn_exec(
     binary: BinaryPackage,
     name: &str,
     env: WasiEnv,
     runtime: &Arc<dyn Runtime + Send + Sync + 'static>,
 ) -> Result<TaskJoinHandle, SpawnError> {
-    spawn_union_fs(&env, &binary).await?;
+    spawn_exec_with(binary, name, env, runtime, None).await
+}
 
-    let cmd = package_command_by_name(&binary, name)?;
-    let module = runtime.load_command_module(cmd).await?;
+/// Like [`spawn_exec()`], but the spawn can be cancelled with `cancel` (see
+/// [`SpawnCancellation`]).
+///
+/// The module is compiled on a thread of its own, so that cancelling doesn't
+/// have to wait for the compiler to finish.
+pub async fn spawn_exec_cancellable(
+    binary: BinaryPackage,
+    name: &str,
+    env: WasiEnv,
+    runtime: &Arc<dyn Runtime + Send + Sync + 'static>,
+    cancel: &SpawnCancellation,
+) -> Result<TaskJoinHandle, SpawnError> {
+    spawn_exec_with(binary, name, env, runtime, Some(cancel)).await
+}
 
-    // Free the space used by the binary, since we don't need it
-    // any longer
-    drop(binary);
+#[tracing::instrument(level = "trace", skip_all, fields(%name, package_id=%binary.id))]
+async fn spawn_exec_with(
+    binary: BinaryPackage,
+    name: &str,
+    env: WasiEnv,
+    runtime: &Arc<dyn Runtime + Send + Sync + 'static>,
+    cancel: Option<&SpawnCancellation>,
+) -> Result<TaskJoinHandle, SpawnError> {
+    let span = crate::telemetry::spawn_exec(env.pid(), name);
+
+    async move {
+        let process = env.process.clone();
+        until_cancelled(cancel, &process, spawn_union_fs(&env, &binary)).await?;
+
+        let cmd = package_command_by_name(&binary, name)?;
+        // Emscripten modules import their runtime from "env" rather than
+        // WASI, so running them as a WASI process can only fail
+        let runner = &cmd.metadata().runner;
+        if runner.starts_with(webc::metadata::annotations::EMSCRIPTEN_RUNNER_URI) {
+            return Err(SpawnError::UnsupportedRunner {
+                command: cmd.name().to_string(),
+                runner: runner.clone(),
+            });
+        }
+        install_package_metadata(&env, Some(PackageMetadata::new(&binary, cmd.name())));
+        let module = match cancel {
+            Some(_) => {
+                until_cancelled(cancel, &process, load_command_module_detached(cmd, runtime))
+                    .await?
+            }
+            None => runtime.load_command_module(cmd).await?,
+        };
+
+        // Free the space used by the binary, since we don't need it
+        // any longer
+        drop(binary);
 
-    spawn_exec_module(module, env, runtime)
+        spawn_exec_module_with(module, env, runtime, cancel).await
+    }
+    .instrument(span)
+    .await
+}
+
+/// Runs `fut`, unless `cancel` is cancelled first, in which case the
+/// process is torn down and this fails with [`SpawnError::Cancelled`].
+async fn until_cancelled<T>(
+    cancel: Option<&SpawnCancellation>,
+    process: &WasiProcess,
+    fut: impl Future<Output = Result<T, SpawnError>>,
+) -> Result<T, SpawnError> {
+    let Some(cancel) = cancel else {
+        return fut.await;
+    };
+
+    let mut fut = pin!(fut);
+    if !cancel.is_cancelled() {
+        if let Either::Left((result, _)) = select(fut.as_mut(), pin!(cancel.cancelled())).await {
+            return result;
+        }
+    }
+
+    // The first exit code wins, so record it before `fut` drops whatever
+    // part of the process it holds on to
+    process.terminate(Errno::Canceled.into());
+    Err(SpawnError::Cancelled)
+}
+
+/// Compiles the command's module on a dedicated thread, so the caller can
+/// give up on it without waiting for the compiler.
+///
+/// Abandoned modules still finish compiling in the background, and end up
+/// in the module cache as usual.
+async fn load_command_module_detached(
+    cmd: &BinaryPackageCommand,
+    runtime: &Arc<dyn Runtime + Send + Sync + 'static>,
+) -> Result<Module, SpawnError> {
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    let cmd = cmd.clone();
+    let compiler = runtime.clone();
+    runtime
+        .task_manager()
+        .task_dedicated(Box::new(move || {
+            tx.send(compiler.load_command_module_sync(&cmd)).ok();
+        }))
+        .map_err(|err| SpawnError::Other(Box::new(err)))?;
+
+    rx.await.map_err(|_| SpawnError::InternalError)?
 }
 
 #[tracing::instrument(level = "trace", skip_all, fields(%name))]
@@ -48,9 +148,16 @@ pub async fn spawn_exec_wasm(
     env: WasiEnv,
     runtime: &Arc<dyn Runtime + Send + Sync + 'static>,
 ) -> Result<TaskJoinHandle, SpawnError> {
-    let module = spawn_load_module(name, wasm, runtime).await?;
+    let span = crate::telemetry::spawn_exec(env.pid(), name);
 
-    spawn_exec_module(module, env, runtime)
+    async move {
+        let module = spawn_load_module(name, wasm, runtime).await?;
+        install_package_metadata(&env, None);
+
+        spawn_exec_module_async(module, env, runtime).await
+    }
+    .instrument(span)
+    .await
 }
 
 pub fn package_command_by_name<'a>(
@@ -133,10 +240,13 @@ pub fn spawn_exec_module(
     {
         // Create a thread that will run this process
         let tasks_outer = tasks.clone();
+        let span = crate::telemetry::process(pid);
+        let log_span = crate::log_sink::process_span(&env.process);
+        let run = move |props| span.in_scope(|| log_span.in_scope(|| run_exec(props)));
 
         tasks_outer
             .task_wasm(
-                TaskWasm::new(Box::new(run_exec), env, module, true, true).with_pre_run(Box::new(
+                TaskWasm::new(Box::new(run), env, module, true, true).with_pre_run(Box::new(
                     |ctx, store| {
                         Box::pin(async move {
                             ctx.data(store).state.fs.close_cloexec_fds().await;
@@ -153,6 +263,82 @@ pub fn spawn_exec_module(
     Ok(join_handle)
 }
 
+/// Like [`spawn_exec_module()`], but creates the process's instance right
+/// away, without blocking the executor while the memory of a module with
+/// large data segments is initialized (see [`wasmer::Instance::new_async()`]).
+///
+/// Only stores of the `sys` runtime can be handed to the thread that runs
+/// the process, so elsewhere this is [`spawn_exec_module()`].
+pub async fn spawn_exec_module_async(
+    module: Module,
+    env: WasiEnv,
+    runtime: &Arc<dyn Runtime + Send + Sync + 'static>,
+) -> Result<TaskJoinHandle, SpawnError> {
+    spawn_exec_module_with(module, env, runtime, None).await
+}
+
+async fn spawn_exec_module_with(
+    module: Module,
+    env: WasiEnv,
+    runtime: &Arc<dyn Runtime + Send + Sync + 'static>,
+    cancel: Option<&SpawnCancellation>,
+) -> Result<TaskJoinHandle, SpawnError> {
+    let process = env.process.clone();
+    if !cfg!(feature = "sys") {
+        let join_handle = until_cancelled(cancel, &process, async {
+            spawn_exec_module(module, env, runtime)
+        })
+        .await?;
+        if let Some(cancel) = cancel {
+            cancel.started(&process);
+        }
+        return Ok(join_handle);
+    }
+
+    let tasks = runtime.task_manager();
+    let pid = env.pid();
+    let join_handle = env.thread.join_handle();
+    let span = crate::telemetry::process(pid);
+    let log_span = crate::log_sink::process_span(&env.process);
+
+    let spawn_type = match module.imports().memories().next() {
+        Some(memory) => SpawnMemoryTypeOrStore::Type(*memory.ty()),
+        None => SpawnMemoryTypeOrStore::New,
+    };
+    let instantiate = async {
+        WasiFunctionEnv::new_with_store_async(module, env, spawn_type, true, true)
+            .await
+            .map_err(|err| {
+                error!("wasi[{}]::failed to launch module - {}", pid, err);
+                SpawnError::Other(Box::new(err))
+            })
+    };
+    let (ctx, store) = until_cancelled(cancel, &process, instantiate).await?;
+    ctx.data(&store).state.fs.close_cloexec_fds().await;
+
+    let run = move || {
+        span.in_scope(|| {
+            log_span.in_scope(|| {
+                run_exec(TaskWasmRunProperties {
+                    ctx,
+                    store,
+                    trigger_result: None,
+                    recycle: None,
+                })
+            })
+        })
+    };
+    tasks.task_dedicated(Box::new(run)).map_err(|err| {
+        error!("wasi[{}]::failed to launch module - {}", pid, err);
+        SpawnError::Other(Box::new(err))
+    })?;
+    if let Some(cancel) = cancel {
+        cancel.started(&process);
+    }
+
+    Ok(join_handle)
+}
+
 /// # SAFETY
 /// This must be executed from the same thread that owns the instance as
 /// otherwise it will cause a panic
@@ -195,7 +381,8 @@ pub fn run_exec(props: TaskWasmRunProperties) {
         {
             let initialize = initialize.clone();
             if let Err(err) = initialize.call(&mut store, &[]) {
-                thread.thread.set_status_finished(Err(err.into()));
+                let err = WasiRuntimeError::from(err).with_process_context(ctx.data(&store));
+                thread.thread.set_status_finished(Err(err));
                 ctx.data(&store)
                     .blocking_on_exit(Some(Errno::Noexec.into()));
                 unsafe { run_recycle(recycle, ctx, store) };
@@ -213,6 +400,7 @@ pub fn run_exec(props: TaskWasmRunProperties) {
         Ok(r) => r,
         Err(err) => {
             tracing::warn!("failed to bootstrap - {}", err);
+            let err = err.with_process_context(ctx.data(&store));
             thread.thread.set_status_finished(Err(err));
             ctx.data(&store)
                 .blocking_on_exit(Some(Errno::Noexec.into()));
@@ -252,6 +440,7 @@ fn call_module(
     let pid = env.pid();
     let tasks = env.tasks().clone();
     handle.thread.set_status_running();
+    handle.thread.resume_cpu_clock();
     let runtime = env.runtime.clone();
 
     // If we need to rewind then do so
@@ -327,16 +516,22 @@ fn call_module(
                 Ok(WasiError::DeepSleep(deep)) => {
                     // Create the callback that will be invoked when the thread respawns after a deep sleep
                     let rewind = deep.rewind;
+                    let span = Span::current();
                     let respawn = {
                         move |ctx, store, rewind_result| {
                             // Call the thread
-                            call_module(
-                                ctx,
-                                store,
-                                handle,
-                                Some((rewind, RewindResultType::RewindWithResult(rewind_result))),
-                                recycle,
-                            );
+                            span.in_scope(|| {
+                                call_module(
+                                    ctx,
+                                    store,
+                                    handle,
+                                    Some((
+                                        rewind,
+                                        RewindResultType::RewindWithResult(rewind_result),
+                                    )),
+                                    recycle,
+                                )
+                            });
                         }
                     };
 
@@ -368,6 +563,9 @@ fn call_module(
         }
     };
 
+    // Attach the process context while it is still available
+    let ret = ret.map_err(|err| err.with_process_context(ctx.data(&store)));
+
     let code = if let Err(err) = &ret {
         match err.as_exit_code() {
             Some(s) => s,
@@ -381,12 +579,26 @@ fn call_module(
         Errno::Success.into()
     };
 
+    crate::telemetry::process_exit(pid, code);
+
+    // Failures must be recorded before the cleanup terminates the process,
+    // otherwise they are replaced by a plain exit code
+    let ret = match ret {
+        Err(err) if err.as_exit_code().is_none() => {
+            handle.thread.set_status_finished(Err(err));
+            None
+        }
+        ret => Some(ret),
+    };
+
     // Cleanup the environment
     ctx.data(&store).blocking_on_exit(Some(code));
     unsafe { run_recycle(recycle, ctx, store) };
 
     debug!("wasi[{pid}]::main() has exited with {code}");
-    handle.thread.set_status_finished(ret.map(|a| a.into()));
+    if let Some(ret) = ret {
+        handle.thread.set_status_finished(ret.map(|a| a.into()));
+    }
 }
 
 #[allow(clippy::type_complexity)]
@@ -483,3 +695,85 @@ fn resume_vfork(
         Ok(None)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use tempfile::TempDir;
+    use wasmer_package::{package::Package, utils::from_disk};
+
+    use crate::{
+        runtime::{package_loader::BuiltinPackageLoader, task_manager::VirtualTaskManager},
+        syscalls::conv_spawn_err_to_errno,
+        PluggableRuntime, WasiEnvBuilder,
+    };
+
+    use super::*;
+
+    fn task_manager() -> Arc<dyn VirtualTaskManager + Send + Sync> {
+        cfg_if::cfg_if! {
+            if #[cfg(feature = "sys-thread")] {
+                Arc::new(crate::runtime::task_manager::tokio::TokioTaskManager::new(tokio::runtime::Handle::current()))
+            } else {
+                unimplemented!("Unable to get the task manager")
+            }
+        }
+    }
+
+    #[tokio::test]
+    #[cfg_attr(
+        not(feature = "sys-thread"),
+        ignore = "The tokio task manager isn't available on this platform"
+    )]
+    async fn emscripten_commands_are_refused() {
+        let temp = TempDir::new().unwrap();
+        let wasmer_toml = r#"
+            [package]
+            name = "some/emscripten"
+            version = "0.0.0"
+
+            [[module]]
+            name = "hello"
+            source = "hello.wasm"
+
+            [[command]]
+            name = "hello"
+            module = "hello"
+            runner = "emscripten"
+        "#;
+        let manifest = temp.path().join("wasmer.toml");
+        std::fs::write(&manifest, wasmer_toml).unwrap();
+        let wasm = wasmer::wat2wasm(br#"(module (func (export "_start")))"#).unwrap();
+        std::fs::write(temp.path().join("hello.wasm"), wasm).unwrap();
+        let webc_path = temp.path().join("package.webc");
+        let data = Package::from_manifest(&manifest)
+            .unwrap()
+            .serialize()
+            .unwrap();
+        std::fs::write(&webc_path, data).unwrap();
+
+        let mut runtime = PluggableRuntime::new(task_manager());
+        runtime.set_package_loader(
+            BuiltinPackageLoader::new()
+                .with_shared_http_client(runtime.http_client().unwrap().clone()),
+        );
+        let runtime: Arc<dyn Runtime + Send + Sync> = Arc::new(runtime);
+        let pkg = BinaryPackage::from_webc(&from_disk(&webc_path).unwrap(), &*runtime)
+            .await
+            .unwrap();
+        let env = WasiEnvBuilder::new("hello")
+            .runtime(runtime.clone())
+            .build()
+            .unwrap();
+
+        let err = spawn_exec(pkg, "hello", env, &runtime).await.unwrap_err();
+
+        match &err {
+            SpawnError::UnsupportedRunner { command, runner } => {
+                assert_eq!(command, "hello");
+                assert_eq!(runner, webc::metadata::annotations::EMSCRIPTEN_RUNNER_URI);
+            }
+            other => panic!("unexpected error: {other:?}"),
+        }
+        assert_eq!(conv_spawn_err_to_errno(&err), Errno::Noexec);
+    }
+}
@@ -0,0 +1,64 @@
+use std::{
+    collections::HashMap,
+    future::Future,
+    sync::{Arc, Mutex},
+};
+
+use tokio::sync::OnceCell;
+use wasmer_config::package::PackageSource;
+
+use super::BinaryPackage;
+
+/// The packages that are currently being loaded from a registry, so that
+/// processes asking for the same package at the same time share a single
+/// download (see [`Runtime::in_flight_packages()`](crate::Runtime::in_flight_packages)).
+///
+/// Packages are forgotten as soon as they finish loading; caching them is up
+/// to the runtime's [`PackageLoader`](crate::runtime::package_loader::PackageLoader).
+#[derive(Debug, Clone, Default)]
+pub struct InFlightPackages {
+    loads: Arc<Mutex<HashMap<PackageSource, Arc<OnceCell<BinaryPackage>>>>>,
+}
+
+impl InFlightPackages {
+    pub fn new() -> Self {
+        InFlightPackages::default()
+    }
+
+    /// Loads a package with `load`, unless it is already being loaded, in
+    /// which case this waits for that load to finish instead.
+    ///
+    /// When a load fails, the callers that were waiting for it retry with
+    /// their own `load`, one after the other.
+    pub async fn get_or_load<F, Fut>(
+        &self,
+        specifier: &PackageSource,
+        load: F,
+    ) -> Result<BinaryPackage, anyhow::Error>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<BinaryPackage, anyhow::Error>>,
+    {
+        let cell = self
+            .loads
+            .lock()
+            .unwrap()
+            .entry(specifier.clone())
+            .or_default()
+            .clone();
+
+        let result = cell.get_or_try_init(load).await.cloned();
+
+        // Whoever finishes first removes the entry, unless a later caller
+        // already replaced it
+        let mut loads = self.loads.lock().unwrap();
+        if loads
+            .get(specifier)
+            .is_some_and(|current| Arc::ptr_eq(current, &cell))
+        {
+            loads.remove(specifier);
+        }
+
+        result
+    }
+}
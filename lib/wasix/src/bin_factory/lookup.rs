@@ -0,0 +1,201 @@
+use std::{collections::HashMap, path::Path};
+
+use virtual_fs::FileSystem;
+
+/// The directories searched for commands when a process doesn't set `PATH`.
+pub const DEFAULT_PATH: &str = "/usr/local/bin:/bin:/usr/bin";
+
+/// How many interpreters a script may go through before giving up, the same
+/// limit Linux uses.
+pub(crate) const MAX_INTERPRETER_DEPTH: usize = 4;
+
+/// The interpreter line (`#!...`) at the start of a script.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Shebang {
+    /// The program the script should be run with.
+    pub interpreter: String,
+    /// The optional argument following the interpreter. Like on Linux,
+    /// everything after the interpreter is passed as a single argument.
+    pub arg: Option<String>,
+}
+
+impl Shebang {
+    /// Parses the interpreter line of `contents`, if it has one.
+    pub fn parse(contents: &[u8]) -> Option<Shebang> {
+        let line = contents.strip_prefix(b"#!")?;
+        let line = match line.iter().position(|b| *b == b'\n') {
+            Some(end) => &line[..end],
+            None => line,
+        };
+        let line = std::str::from_utf8(line).ok()?.trim();
+
+        let (interpreter, arg) = match line.split_once([' ', '\t']) {
+            Some((interpreter, arg)) => (interpreter, Some(arg.trim())),
+            None => (line, None),
+        };
+        if interpreter.is_empty() {
+            return None;
+        }
+
+        Some(Shebang {
+            interpreter: interpreter.to_string(),
+            arg: arg.filter(|arg| !arg.is_empty()).map(String::from),
+        })
+    }
+
+    /// `#!/usr/bin/env NAME` is how scripts ask for `NAME` to be looked up
+    /// in `PATH`. Returns the name in that case.
+    pub fn env_command(&self) -> Option<&str> {
+        let is_env = Path::new(&self.interpreter).file_name()? == "env";
+        match &self.arg {
+            Some(arg) if is_env && !arg.starts_with('-') => Some(arg.as_str()),
+            _ => None,
+        }
+    }
+}
+
+/// Remembers where commands were found in each `PATH`, including the ones
+/// that weren't found at all.
+///
+/// Each process has its own cache, as processes can see different file
+/// systems. Every lookup records the modification times of the directories
+/// it searched, and a cached result is only used while they stay the same
+/// and the commands registered with the [`BinFactory`](super::BinFactory)
+/// are still at the same generation.
+#[derive(Debug, Default)]
+pub(crate) struct CommandLookupCache {
+    generation: u64,
+    entries: HashMap<(String, String), CachedLookup>,
+}
+
+#[derive(Debug, Clone)]
+struct CachedLookup {
+    location: Option<String>,
+    searched: Vec<(String, Option<u64>)>,
+}
+
+impl CommandLookupCache {
+    /// Get the cached location of `name` within `path`, or `None` if it
+    /// hasn't been looked up yet, the directories changed since or the
+    /// registered commands are no longer at `generation`.
+    pub(crate) fn get(
+        &self,
+        path: &str,
+        name: &str,
+        fs: &dyn FileSystem,
+        generation: u64,
+    ) -> Option<Option<String>> {
+        if generation != self.generation {
+            return None;
+        }
+        let entry = self.entries.get(&(path.to_string(), name.to_string()))?;
+        let stale = entry
+            .searched
+            .iter()
+            .any(|(dir, modified)| dir_modified(fs, dir) != *modified);
+        if stale {
+            None
+        } else {
+            Some(entry.location.clone())
+        }
+    }
+
+    pub(crate) fn insert(
+        &mut self,
+        path: &str,
+        name: &str,
+        location: Option<String>,
+        searched: Vec<(String, Option<u64>)>,
+        generation: u64,
+    ) {
+        if generation != self.generation {
+            self.entries.clear();
+            self.generation = generation;
+        }
+        self.entries.insert(
+            (path.to_string(), name.to_string()),
+            CachedLookup { location, searched },
+        );
+    }
+}
+
+/// The modification time of a directory, or `None` if it doesn't exist.
+pub(crate) fn dir_modified(fs: &dyn FileSystem, dir: &str) -> Option<u64> {
+    fs.metadata(Path::new(dir)).ok().map(|meta| meta.modified)
+}
+
+#[cfg(test)]
+mod tests {
+    use virtual_fs::mem_fs;
+
+    use super::*;
+
+    #[test]
+    fn parse_shebangs() {
+        let inputs = [
+            ("#!/bin/sh\necho hi", Some(("/bin/sh", None))),
+            (
+                "#!  /usr/bin/env python3 \n",
+                Some(("/usr/bin/env", Some("python3"))),
+            ),
+            (
+                "#!/bin/awk -f -v x=1",
+                Some(("/bin/awk", Some("-f -v x=1"))),
+            ),
+            ("#!/bin/sh", Some(("/bin/sh", None))),
+            ("#!\n", None),
+            ("\0asm", None),
+            ("echo #!/bin/sh", None),
+        ];
+
+        for (input, expected) in inputs {
+            let expected = expected.map(|(interpreter, arg)| Shebang {
+                interpreter: interpreter.to_string(),
+                arg: arg.map(String::from),
+            });
+            assert_eq!(Shebang::parse(input.as_bytes()), expected, "{input:?}");
+        }
+    }
+
+    #[test]
+    fn env_shebangs_name_a_command() {
+        let env = Shebang::parse(b"#!/usr/bin/env python").unwrap();
+        assert_eq!(env.env_command(), Some("python"));
+        let flags = Shebang::parse(b"#!/usr/bin/env -S python -u").unwrap();
+        assert_eq!(flags.env_command(), None);
+        let sh = Shebang::parse(b"#!/bin/sh -e").unwrap();
+        assert_eq!(sh.env_command(), None);
+    }
+
+    #[test]
+    fn changing_a_searched_directory_invalidates_lookups() {
+        let fs = mem_fs::FileSystem::default();
+        fs.create_dir(Path::new("/bin")).unwrap();
+        let mut cache = CommandLookupCache::default();
+
+        let searched = vec![
+            ("/bin".to_string(), dir_modified(&fs, "/bin")),
+            ("/missing".to_string(), dir_modified(&fs, "/missing")),
+        ];
+        cache.insert("/bin:/missing", "python", None, searched, 0);
+        assert_eq!(cache.get("/bin:/missing", "python", &fs, 0), Some(None));
+        assert_eq!(cache.get("/bin", "python", &fs, 0), None);
+
+        fs.create_dir(Path::new("/missing")).unwrap();
+        assert_eq!(cache.get("/bin:/missing", "python", &fs, 0), None);
+    }
+
+    #[test]
+    fn registering_commands_invalidates_lookups() {
+        let fs = mem_fs::FileSystem::default();
+        let mut cache = CommandLookupCache::default();
+
+        cache.insert("/bin", "python", None, Vec::new(), 0);
+        assert_eq!(cache.get("/bin", "python", &fs, 0), Some(None));
+        assert_eq!(cache.get("/bin", "python", &fs, 1), None);
+
+        cache.insert("/bin", "ls", None, Vec::new(), 1);
+        assert_eq!(cache.get("/bin", "ls", &fs, 1), Some(None));
+        assert_eq!(cache.get("/bin", "python", &fs, 1), None);
+    }
+}
@@ -4,7 +4,10 @@ use std::{
     ops::Deref,
     path::Path,
     pin::Pin,
-    sync::{Arc, RwLock},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, RwLock,
+    },
 };
 
 use anyhow::Context;
@@ -12,15 +15,28 @@ use virtual_fs::{AsyncReadExt, FileSystem};
 use wasmer::FunctionEnvMut;
 use wasmer_package::utils::from_bytes;
 
+mod alias;
 mod binary_package;
+mod cancel;
 mod exec;
+mod in_flight;
+pub(crate) mod lookup;
+mod package_metadata;
+mod spawn_policy;
 
+use self::lookup::{dir_modified, CommandLookupCache, MAX_INTERPRETER_DEPTH};
 pub use self::{
+    alias::{CommandAlias, CommandAliasError},
     binary_package::*,
+    cancel::SpawnCancellation,
     exec::{
-        package_command_by_name, run_exec, spawn_exec, spawn_exec_module, spawn_exec_wasm,
-        spawn_load_module, spawn_union_fs,
+        package_command_by_name, run_exec, spawn_exec, spawn_exec_cancellable, spawn_exec_module,
+        spawn_exec_module_async, spawn_exec_wasm, spawn_load_module, spawn_union_fs,
     },
+    in_flight::InFlightPackages,
+    lookup::{Shebang, DEFAULT_PATH},
+    package_metadata::PackageMetadata,
+    spawn_policy::{DynSpawnPolicy, SpawnDecision, SpawnPolicy, SpawnRequest},
 };
 use crate::{
     os::{command::Commands, task::TaskJoinHandle},
@@ -32,6 +48,10 @@ pub struct BinFactory {
     pub(crate) commands: Commands,
     runtime: Arc<dyn Runtime + Send + Sync + 'static>,
     pub(crate) local: Arc<RwLock<HashMap<String, Option<BinaryPackage>>>>,
+    aliases: Arc<RwLock<HashMap<String, CommandAlias>>>,
+    /// Bumped whenever the registered commands change, which invalidates
+    /// the processes' [`CommandLookupCache`]s.
+    generation: Arc<AtomicU64>,
 }
 
 impl BinFactory {
@@ -40,6 +60,8 @@ impl BinFactory {
             commands: Commands::new_with_builtins(runtime.clone()),
             runtime,
             local: Arc::new(RwLock::new(HashMap::new())),
+            aliases: Arc::new(RwLock::new(HashMap::new())),
+            generation: Arc::new(AtomicU64::new(0)),
         }
     }
 
@@ -50,6 +72,161 @@ impl BinFactory {
     pub fn set_binary(&self, name: &str, binary: BinaryPackage) {
         let mut cache = self.local.write().unwrap();
         cache.insert(name.to_string(), Some(binary));
+        self.generation.fetch_add(1, Ordering::AcqRel);
+    }
+
+    /// Register a [`CommandAlias`] so that spawning [`CommandAlias::name`]
+    /// (either by bare name or via `/bin/NAME` and `/usr/bin/NAME`) will run
+    /// a command from another package instead.
+    ///
+    /// The aliased package is only fetched the first time it is used.
+    pub fn alias_command(&self, alias: CommandAlias) -> Result<(), CommandAliasError> {
+        for path in alias_paths(&alias.name) {
+            let shadowed = self.commands.exists(&path)
+                || matches!(self.local.read().unwrap().get(&path), Some(Some(_)));
+            if shadowed {
+                return Err(CommandAliasError::ShadowsCommand {
+                    alias: alias.name.clone(),
+                    path,
+                });
+            }
+        }
+
+        let mut aliases = self.aliases.write().unwrap();
+        let previous = aliases.insert(alias.name.clone(), alias.clone());
+
+        // Make sure the new alias doesn't introduce a cycle anywhere
+        let names: Vec<String> = aliases.keys().cloned().collect();
+        for name in names {
+            if let Err(e) = alias::resolve_alias_chain(&aliases, &name) {
+                match previous {
+                    Some(previous) => aliases.insert(alias.name.clone(), previous),
+                    None => aliases.remove(&alias.name),
+                };
+                return Err(e);
+            }
+        }
+
+        self.generation.fetch_add(1, Ordering::AcqRel);
+        Ok(())
+    }
+
+    /// Get the [`CommandAlias`] that a command name or path ultimately
+    /// resolves to, following any chains of aliases.
+    pub fn resolve_alias(&self, name: &str) -> Option<CommandAlias> {
+        let name = ["/bin/", "/usr/bin/"]
+            .iter()
+            .find_map(|prefix| name.strip_prefix(prefix))
+            .unwrap_or(name);
+
+        let aliases = self.aliases.read().unwrap();
+        alias::resolve_alias_chain(&aliases, name)
+            .ok()
+            .flatten()
+            .cloned()
+    }
+
+    /// Find the command a process would run when it spawns `name`.
+    ///
+    /// Names containing a `/` are returned as they are. Otherwise each
+    /// directory in `path` (a colon-separated list, like the `PATH`
+    /// environment variable) is searched, both for files in `fs` and for
+    /// commands registered at that location. When that fails, the virtual
+    /// commands in `/bin` and the aliased commands are used.
+    ///
+    /// Processes cache the results (see [`WasiProcess`]'s lookup cache), this
+    /// always searches.
+    ///
+    /// [`WasiProcess`]: crate::os::task::process::WasiProcess
+    pub fn resolve_command(&self, name: &str, path: &str, fs: &dyn FileSystem) -> Option<String> {
+        self.resolve_command_cached(name, path, fs, None)
+    }
+
+    /// [`BinFactory::resolve_command()`], with the results cached in `cache`
+    /// per `path` until one of the directories that were searched is
+    /// modified, or the registered commands change.
+    fn resolve_command_cached(
+        &self,
+        name: &str,
+        path: &str,
+        fs: &dyn FileSystem,
+        cache: Option<&RwLock<CommandLookupCache>>,
+    ) -> Option<String> {
+        if name.contains('/') {
+            return Some(name.to_string());
+        }
+
+        let generation = self.generation.load(Ordering::Acquire);
+        if let Some(cache) = cache {
+            if let Some(location) = cache.read().unwrap().get(path, name, fs, generation) {
+                return location;
+            }
+        }
+
+        let is_command = |location: &str| {
+            self.commands.exists(location)
+                || matches!(self.local.read().unwrap().get(location), Some(Some(_)))
+        };
+
+        let mut searched = Vec::new();
+        let mut location = None;
+        for dir in path.split(':').filter(|dir| !dir.is_empty()) {
+            searched.push((dir.to_string(), dir_modified(fs, dir)));
+
+            let candidate = format!("{}/{}", dir.trim_end_matches('/'), name);
+            let is_file = fs
+                .metadata(Path::new(&candidate))
+                .map(|meta| meta.is_file())
+                .unwrap_or(false);
+            if is_file || is_command(&candidate) {
+                location = Some(candidate);
+                break;
+            }
+        }
+
+        if location.is_none() {
+            let bin = format!("/bin/{name}");
+            if is_command(&bin) {
+                location = Some(bin);
+            } else if is_command(name) || self.resolve_alias(name).is_some() {
+                location = Some(name.to_string());
+            }
+        }
+
+        tracing::trace!(command = name, path, ?location, "resolved command");
+        if let Some(cache) = cache {
+            cache
+                .write()
+                .unwrap()
+                .insert(path, name, location.clone(), searched, generation);
+        }
+        location
+    }
+
+    /// [`BinFactory::resolve_command()`] using the `PATH` and file system of
+    /// `env`, falling back to `name` when the command can't be found.
+    fn resolve_command_for_env(&self, name: &str, env: &WasiEnv) -> String {
+        if name.contains('/') {
+            return name.to_string();
+        }
+
+        let path = env
+            .state
+            .envs
+            .lock()
+            .unwrap()
+            .iter()
+            .find_map(|var| var.strip_prefix(b"PATH="))
+            .map(|path| String::from_utf8_lossy(path).into_owned());
+        let path = path.as_deref().unwrap_or(DEFAULT_PATH);
+
+        self.resolve_command_cached(
+            name,
+            path,
+            env.fs_root(),
+            Some(&env.process.command_lookups),
+        )
+        .unwrap_or_else(|| name.to_string())
     }
 
     #[allow(clippy::await_holding_lock)]
@@ -61,7 +238,7 @@ impl BinFactory {
         self.get_executable(name, fs)
             .await
             .and_then(|executable| match executable {
-                Executable::Wasm(_) => None,
+                Executable::Wasm(_) | Executable::Script { .. } => None,
                 Executable::BinaryPackage(pkg) => Some(pkg),
             })
     }
@@ -70,8 +247,21 @@ impl BinFactory {
         &'a self,
         name: String,
         env: WasiEnv,
+    ) -> Pin<Box<dyn Future<Output = Result<TaskJoinHandle, SpawnError>> + 'a>> {
+        self.spawn_with_depth(name, env, 0)
+    }
+
+    /// Spawns `name`, where `depth` is the number of scripts whose
+    /// interpreters led to it.
+    fn spawn_with_depth<'a>(
+        &'a self,
+        name: String,
+        env: WasiEnv,
+        depth: usize,
     ) -> Pin<Box<dyn Future<Output = Result<TaskJoinHandle, SpawnError>> + 'a>> {
         Box::pin(async move {
+            let name = self.resolve_command_for_env(&name, &env);
+
             // Find the binary (or die trying) and make the spawn type
             let res = self
                 .get_executable(name.as_str(), Some(env.fs_root()))
@@ -87,6 +277,12 @@ impl BinFactory {
                     spawn_exec_wasm(&bytes, name.as_str(), env, &self.runtime).await
                 }
                 Executable::BinaryPackage(pkg) => {
+                    // Aliases always run the command they were mapped to
+                    let name = match (self.resolve_alias(&name), &pkg.entrypoint_cmd) {
+                        (Some(_), Some(entrypoint)) => entrypoint.clone(),
+                        _ => name,
+                    };
+
                     // Get the command that is going to be executed
                     let cmd = package_command_by_name(&pkg, name.as_str())?;
 
@@ -94,6 +290,31 @@ impl BinFactory {
 
                     spawn_exec(pkg, name.as_str(), env, &self.runtime).await
                 }
+                Executable::Script { path, shebang } => {
+                    if depth >= MAX_INTERPRETER_DEPTH {
+                        return Err(SpawnError::InterpreterLoop { script: path });
+                    }
+
+                    // Run the interpreter with the script's path inserted
+                    // in front of its arguments
+                    let env_command = shebang.env_command();
+                    let interpreter = match env_command {
+                        Some(command) => self.resolve_command_for_env(command, &env),
+                        None => shebang.interpreter.clone(),
+                    };
+                    {
+                        let mut args = env.state.args.lock().unwrap();
+                        let mut new_args = vec![interpreter.clone()];
+                        if env_command.is_none() {
+                            new_args.extend(shebang.arg.clone());
+                        }
+                        new_args.push(path);
+                        new_args.extend(args.iter().skip(1).cloned());
+                        *args = new_args;
+                    }
+
+                    self.spawn_with_depth(interpreter, env, depth + 1).await
+                }
             }
         })
     }
@@ -104,6 +325,11 @@ impl BinFactory {
         parent_ctx: Option<&FunctionEnvMut<'_, WasiEnv>>,
         builder: &mut Option<WasiEnv>,
     ) -> Result<TaskJoinHandle, SpawnError> {
+        let name = match builder {
+            Some(env) => self.resolve_command_for_env(&name, env),
+            None => name,
+        };
+
         // We check for built in commands
         if let Some(parent_ctx) = parent_ctx {
             if self.commands.exists(name.as_str()) {
@@ -141,6 +367,26 @@ impl BinFactory {
             return data.clone().map(Executable::BinaryPackage);
         }
 
+        // Check if the command was aliased to another package
+        if let Some(alias) = self.resolve_alias(&name) {
+            match load_aliased_package(&alias, self.runtime()).await {
+                Ok(pkg) => {
+                    cache.insert(name, Some(pkg.clone()));
+                    return Some(Executable::BinaryPackage(pkg));
+                }
+                Err(e) => {
+                    tracing::warn!(
+                        command = name,
+                        alias = %alias,
+                        error = &*e,
+                        "Unable to load the aliased package"
+                    );
+                    cache.insert(name, None);
+                    return None;
+                }
+            }
+        }
+
         // Check the filesystem for the file
         if name.starts_with('/') {
             if let Some(fs) = fs {
@@ -172,6 +418,42 @@ impl BinFactory {
 pub enum Executable {
     Wasm(bytes::Bytes),
     BinaryPackage(BinaryPackage),
+    /// A text file that starts with a `#!` line naming its interpreter
+    Script {
+        path: String,
+        shebang: Shebang,
+    },
+}
+
+/// The locations an aliased command is made available at.
+pub(crate) fn alias_paths(name: &str) -> [String; 2] {
+    [format!("/bin/{name}"), format!("/usr/bin/{name}")]
+}
+
+async fn load_aliased_package(
+    alias: &CommandAlias,
+    rt: &(dyn Runtime + Send + Sync),
+) -> Result<BinaryPackage, anyhow::Error> {
+    let mut pkg = BinaryPackage::from_registry(&alias.package, rt)
+        .await
+        .with_context(|| format!("Unable to load \"{}\"", alias.package))?;
+
+    let command = match &alias.command {
+        Some(command) => pkg
+            .get_command(command)
+            .with_context(|| {
+                format!(
+                    "The \"{}\" package doesn't have a \"{command}\" command",
+                    pkg.id
+                )
+            })?
+            .name()
+            .to_string(),
+        None => package_command_by_name(&pkg, "")?.name().to_string(),
+    };
+    pkg.entrypoint_cmd = Some(command);
+
+    Ok(pkg)
 }
 
 async fn load_executable_from_filesystem(
@@ -188,6 +470,13 @@ async fn load_executable_from_filesystem(
     let mut data = Vec::with_capacity(f.size() as usize);
     f.read_to_end(&mut data).await.context("Read failed")?;
 
+    if let Some(shebang) = Shebang::parse(&data) {
+        return Ok(Executable::Script {
+            path: path.display().to_string(),
+            shebang,
+        });
+    }
+
     let bytes: bytes::Bytes = data.into();
 
     if let Ok(container) = from_bytes(bytes.clone()) {
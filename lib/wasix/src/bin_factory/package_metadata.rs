@@ -0,0 +1,135 @@
+use std::path::Path;
+
+use virtual_fs::StaticFile;
+
+use super::BinaryPackage;
+use crate::WasiEnv;
+
+/// Information about the package a process was started from.
+///
+/// This is made available to the guest as a read-only JSON file at
+/// [`PackageMetadata::PATH`] and through the `proc_package_metadata()`
+/// syscall. It is inherited across `fork()` and replaced when the process
+/// `exec()`s a command from another package.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct PackageMetadata {
+    /// The package's ID (e.g. `wasmer/python@3.12.0` or `sha256:...`).
+    pub id: String,
+    /// The package's name, if it has one.
+    pub name: Option<String>,
+    /// The package's version, if it has one.
+    pub version: Option<String>,
+    /// The command that was run.
+    pub command: String,
+    /// The SHA-256 hash of the package's `*.webc` file, as a lowercase hex
+    /// string.
+    pub webc_sha256: Option<String>,
+}
+
+impl PackageMetadata {
+    /// Where the metadata is made available inside the guest's file system.
+    pub const PATH: &'static str = "/etc/wasmer/package.json";
+
+    pub fn new(pkg: &BinaryPackage, command: &str) -> Self {
+        let named = pkg.id.as_named();
+
+        PackageMetadata {
+            id: pkg.id.to_string(),
+            name: named.map(|id| id.full_name.clone()),
+            version: named.map(|id| id.version.to_string()),
+            command: command.to_string(),
+            webc_sha256: pkg.webc_hash.map(|hash| hash.as_hex()),
+        }
+    }
+
+    /// The metadata as it is presented to the guest.
+    pub fn to_json(&self) -> Vec<u8> {
+        serde_json::to_vec(self).expect("serializing to JSON never fails")
+    }
+}
+
+/// Make `metadata` available to the process, or clear it when `None` (e.g.
+/// after an `exec()` of a raw WebAssembly module).
+///
+/// The file at [`PackageMetadata::PATH`] only exists for this process and
+/// its forks; the root file system, which other processes may share, is
+/// left alone.
+pub(crate) fn install_package_metadata(env: &WasiEnv, metadata: Option<PackageMetadata>) {
+    let path = Path::new(PackageMetadata::PATH);
+    match &metadata {
+        Some(metadata) => {
+            let file = StaticFile::new(metadata.to_json());
+            env.state
+                .fs
+                .set_process_file(&env.state.inodes, path, Box::new(file), false);
+        }
+        None => env.state.fs.remove_process_file(path),
+    }
+    *env.state.package.lock().unwrap() = metadata;
+}
+
+#[cfg(test)]
+mod tests {
+    use virtual_fs::{FileSystem, TmpFileSystem};
+    use wasmer::Engine;
+    use wasmer_wasix_types::wasi::Errno;
+
+    use super::*;
+    use crate::{capabilities::FsAccess, WasiEnvBuilder};
+
+    #[test]
+    fn metadata_file_is_private_to_the_process() {
+        #[cfg(not(target_arch = "wasm32"))]
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        #[cfg(not(target_arch = "wasm32"))]
+        let _guard = runtime.enter();
+
+        let root = TmpFileSystem::new();
+        root.create_dir(Path::new("/etc")).unwrap();
+        let init = WasiEnvBuilder::new("test_prog")
+            .sandbox_fs(root.clone())
+            .preopen_dir("/")
+            .unwrap()
+            .engine(Engine::default())
+            .build_init()
+            .unwrap();
+        let state = init.state;
+        let lookup = |state: &crate::state::WasiState, access| {
+            state
+                .fs
+                .get_inode_at_path(&state.inodes, 3, "etc/wasmer/package.json", true, access)
+        };
+
+        let metadata = PackageMetadata {
+            id: "test/pkg@0.1.0".to_string(),
+            name: Some("test/pkg".to_string()),
+            version: Some("0.1.0".to_string()),
+            command: "pkg".to_string(),
+            webc_sha256: None,
+        };
+        let json = metadata.to_json();
+        state.fs.set_process_file(
+            &state.inodes,
+            Path::new(PackageMetadata::PATH),
+            Box::new(StaticFile::new(json.clone())),
+            false,
+        );
+
+        let inode = lookup(&state, FsAccess::READ).unwrap();
+        assert_eq!(inode.stat.read().unwrap().st_size, json.len() as u64);
+        assert_eq!(lookup(&state, FsAccess::WRITE).unwrap_err(), Errno::Rofs);
+        // Other processes sharing the root file system don't see it
+        assert!(root.metadata(Path::new("/etc/wasmer")).is_err());
+
+        // Forks inherit it, but it can be removed without affecting them
+        let forked = state.fork();
+        state
+            .fs
+            .remove_process_file(Path::new(PackageMetadata::PATH));
+        assert_eq!(lookup(&state, FsAccess::READ).unwrap_err(), Errno::Noent);
+        assert!(lookup(&forked, FsAccess::READ).is_ok());
+    }
+}
@@ -0,0 +1,131 @@
+use wasmer_wasix_types::wasi::Errno;
+
+use super::BinFactory;
+use crate::{os::task::process::WasiProcessId, WasiEnv};
+
+/// Decides which programs a guest may start with `proc_spawn` and
+/// `proc_exec`, see [`Runtime::spawn_policy()`](crate::Runtime::spawn_policy).
+///
+/// The policy is consulted before the program is looked up, so a denied
+/// program is never loaded.
+pub trait SpawnPolicy {
+    fn check(&self, req: &SpawnRequest) -> SpawnDecision;
+}
+
+impl<F> SpawnPolicy for F
+where
+    F: Fn(&SpawnRequest) -> SpawnDecision,
+{
+    fn check(&self, req: &SpawnRequest) -> SpawnDecision {
+        self(req)
+    }
+}
+
+pub type DynSpawnPolicy = dyn SpawnPolicy + Send + Sync;
+
+/// A program a guest wants to start.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpawnRequest {
+    /// The program, as a command name or a path.
+    pub program: String,
+    /// The arguments, starting with the program's own name.
+    pub args: Vec<String>,
+    /// The environment variables.
+    pub env: Vec<(String, String)>,
+    /// The working directory.
+    pub cwd: String,
+    /// The process asking to start the program (which `proc_exec` replaces).
+    pub pid: WasiProcessId,
+}
+
+impl SpawnRequest {
+    fn from_env(program: String, env: &WasiEnv, pid: WasiProcessId) -> Self {
+        let args = env.state.args.lock().unwrap().clone();
+        let vars = env
+            .state
+            .envs
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|var| {
+                let var = String::from_utf8_lossy(var);
+                let (key, value) = var.split_once('=').unwrap_or((&var, ""));
+                (key.to_string(), value.to_string())
+            })
+            .collect();
+        let cwd = env.state.fs.current_dir.lock().unwrap().clone();
+
+        SpawnRequest {
+            program,
+            args,
+            env: vars,
+            cwd,
+            pid,
+        }
+    }
+
+    /// Replaces the arguments, environment variables and working directory
+    /// of `env` with the ones in this request.
+    fn apply_to(&self, env: &WasiEnv) {
+        *env.state.args.lock().unwrap() = self.args.clone();
+        *env.state.envs.lock().unwrap() = self
+            .env
+            .iter()
+            .map(|(key, value)| format!("{key}={value}").into_bytes())
+            .collect();
+        env.state.fs.set_current_dir(&self.cwd);
+    }
+}
+
+/// What a [`SpawnPolicy`] decided to do with a [`SpawnRequest`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SpawnDecision {
+    /// Start the program as requested.
+    Allow,
+    /// Fail the `proc_spawn` or `proc_exec` call with this error.
+    Deny(Errno),
+    /// Start this program instead.
+    ///
+    /// Only the program, arguments, environment variables and working
+    /// directory are used; the requesting process can't be changed.
+    Rewrite(SpawnRequest),
+}
+
+impl BinFactory {
+    /// Asks the runtime's [`SpawnPolicy`] whether the process `pid` may start
+    /// `program` with `env`, applying any rewrite to `env`, and returns the
+    /// program to start.
+    ///
+    /// This has to be called before the program is looked up (e.g. with
+    /// [`BinFactory::try_built_in()`] or [`BinFactory::spawn()`]).
+    pub fn check_spawn(
+        &self,
+        program: String,
+        env: &WasiEnv,
+        pid: WasiProcessId,
+    ) -> Result<String, Errno> {
+        let Some(policy) = self.runtime().spawn_policy() else {
+            return Ok(program);
+        };
+
+        let request = SpawnRequest::from_env(program, env, pid);
+        match policy.check(&request) {
+            SpawnDecision::Allow => Ok(request.program),
+            SpawnDecision::Deny(errno) => {
+                tracing::debug!(program = %request.program, %errno, "spawn denied by the policy");
+                Err(errno)
+            }
+            SpawnDecision::Rewrite(rewritten) => {
+                tracing::debug!(
+                    program = %request.program,
+                    rewritten = %rewritten.program,
+                    "spawn rewritten by the policy",
+                );
+                // The whole request is applied before the child starts, so it
+                // never sees a mix of the two
+                rewritten.apply_to(env);
+                Ok(rewritten.program)
+            }
+        }
+    }
+}
@@ -1,4 +1,9 @@
-use std::time::Duration;
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use wasmer_types::target::CpuFeature;
 
 use crate::http::HttpClientCapabilityV1;
 
@@ -8,6 +13,14 @@ pub struct Capabilities {
     pub insecure_allow_all: bool,
     pub http_client: HttpClientCapabilityV1,
     pub threading: CapabilityThreadingV1,
+    pub signals: CapabilitySignalsV1,
+    pub tls: CapabilityTlsV1,
+    pub fs: CapabilityFsV1,
+    pub cpu: CapabilityCpuV1,
+    pub listen: CapabilityListenV1,
+    pub kv: CapabilityKvV1,
+    pub archive: CapabilityArchiveV1,
+    pub crypto: CapabilityCryptoV1,
 }
 
 impl Capabilities {
@@ -16,6 +29,14 @@ impl Capabilities {
             insecure_allow_all: false,
             http_client: Default::default(),
             threading: Default::default(),
+            signals: Default::default(),
+            tls: Default::default(),
+            fs: Default::default(),
+            cpu: Default::default(),
+            listen: Default::default(),
+            kv: Default::default(),
+            archive: Default::default(),
+            crypto: Default::default(),
         }
     }
 
@@ -26,10 +47,26 @@ impl Capabilities {
             insecure_allow_all,
             http_client,
             threading,
+            signals,
+            tls,
+            fs,
+            cpu,
+            listen,
+            kv,
+            archive,
+            crypto,
         } = other;
         self.insecure_allow_all |= insecure_allow_all;
         self.http_client.update(http_client);
         self.threading.update(threading);
+        self.signals.update(signals);
+        self.tls.update(tls);
+        self.fs.update(fs);
+        self.cpu.update(cpu);
+        self.listen.update(listen);
+        self.kv.update(kv);
+        self.archive.update(archive);
+        self.crypto.update(crypto);
     }
 }
 
@@ -78,3 +115,285 @@ impl CapabilityThreadingV1 {
         self.enable_blocking_sleep |= enable_blocking_sleep;
     }
 }
+
+/// Defines what a process is told about the CPUs it runs on.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub struct CapabilityCpuV1 {
+    /// The number of logical CPUs reported to the guest.
+    ///
+    /// [`None`] reports the parallelism of the task manager.
+    pub count: Option<usize>,
+
+    /// The CPU features reported to the guest.
+    ///
+    /// [`None`] reports the features of the host.
+    pub features: Option<Vec<CpuFeature>>,
+}
+
+impl CapabilityCpuV1 {
+    pub fn update(&mut self, other: CapabilityCpuV1) {
+        let CapabilityCpuV1 { count, features } = other;
+        self.count = count.or(self.count);
+        if let Some(features) = features {
+            self.features = Some(features);
+        }
+    }
+}
+
+/// Defines which of the TCP listeners a process opens are handed to the
+/// host, so it can inject connections into them (see
+/// [`GuestListener`](crate::net::listener::GuestListener)).
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub struct CapabilityListenV1 {
+    /// The ports whose listeners are exposed to the host.
+    pub exposed_ports: Vec<u16>,
+
+    /// Expose the listeners on every port
+    /// (default = false)
+    pub expose_all: bool,
+}
+
+impl CapabilityListenV1 {
+    pub fn update(&mut self, other: CapabilityListenV1) {
+        let CapabilityListenV1 {
+            exposed_ports,
+            expose_all,
+        } = other;
+        for port in exposed_ports {
+            if !self.exposed_ports.contains(&port) {
+                self.exposed_ports.push(port);
+            }
+        }
+        self.expose_all |= expose_all;
+    }
+
+    /// Whether listeners on `port` are exposed to the host.
+    pub fn exposes(&self, port: u16) -> bool {
+        self.expose_all || self.exposed_ports.contains(&port)
+    }
+}
+
+/// Defines whether a process may use the runtime's key-value store (see
+/// [`Runtime::kv_backend()`](crate::Runtime::kv_backend)).
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub struct CapabilityKvV1 {
+    /// Allow the `kv_*` syscalls
+    /// (default = false)
+    pub enabled: bool,
+
+    /// The namespace the process's keys live in.
+    ///
+    /// [`None`] uses the name of the package the process was started from,
+    /// so that packages can't see each other's keys.
+    pub namespace: Option<String>,
+}
+
+impl CapabilityKvV1 {
+    pub fn update(&mut self, other: CapabilityKvV1) {
+        let CapabilityKvV1 { enabled, namespace } = other;
+        self.enabled |= enabled;
+        self.namespace = namespace.or(self.namespace.take());
+    }
+}
+
+/// Defines whether a process may have the host extract archives into its
+/// filesystem (see the `archive_extract` syscall).
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub struct CapabilityArchiveV1 {
+    /// Allow the `archive_extract` syscall
+    /// (default = false)
+    pub enabled: bool,
+}
+
+impl CapabilityArchiveV1 {
+    pub fn update(&mut self, other: CapabilityArchiveV1) {
+        let CapabilityArchiveV1 { enabled } = other;
+        self.enabled |= enabled;
+    }
+}
+
+/// Defines whether a process may use the host's implementation of the
+/// wasi-crypto signatures API (see the `wasi_crypto` syscalls).
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub struct CapabilityCryptoV1 {
+    /// Allow the `wasi_ephemeral_crypto_*` imports
+    /// (default = false)
+    pub enabled: bool,
+
+    /// Allow exporting the secret half of a key pair into the guest's
+    /// memory. Otherwise private keys never leave the host once they were
+    /// generated or imported.
+    /// (default = false)
+    pub allow_secret_export: bool,
+}
+
+impl CapabilityCryptoV1 {
+    pub fn update(&mut self, other: CapabilityCryptoV1) {
+        let CapabilityCryptoV1 {
+            enabled,
+            allow_secret_export,
+        } = other;
+        self.enabled |= enabled;
+        self.allow_secret_export |= allow_secret_export;
+    }
+}
+
+/// Defines which processes a process may send signals to.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub struct CapabilitySignalsV1 {
+    /// Only allow a process to signal itself and its descendants, instead
+    /// of any process on the same control plane
+    /// (default = false)
+    pub isolate_processes: bool,
+}
+
+impl CapabilitySignalsV1 {
+    pub fn update(&mut self, other: CapabilitySignalsV1) {
+        let CapabilitySignalsV1 { isolate_processes } = other;
+        self.isolate_processes |= isolate_processes;
+    }
+}
+
+/// Defines how sockets that are upgraded to TLS verify their peer.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub struct CapabilityTlsV1 {
+    /// Accept any certificate the server presents instead of verifying it
+    /// against the runtime's root store. Only meant for testing.
+    /// (default = false)
+    pub insecure_skip_verify: bool,
+}
+
+impl CapabilityTlsV1 {
+    pub fn update(&mut self, other: CapabilityTlsV1) {
+        let CapabilityTlsV1 {
+            insecure_skip_verify,
+        } = other;
+        self.insecure_skip_verify |= insecure_skip_verify;
+    }
+}
+
+/// Defines which paths in the file system a process may access.
+///
+/// The policy is enforced whenever a path is resolved, before the rights of
+/// the file descriptors involved are checked, so it also applies to device
+/// files and to directories the runtime set up by itself. Operations on file
+/// descriptors that are already open (e.g. stdio) are not affected.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub enum CapabilityFsV1 {
+    /// Any path the file system contains may be accessed (default).
+    #[default]
+    Unrestricted,
+    /// Every path operation fails with `Errno::Notcapable`.
+    DenyAll,
+    /// Only paths inside directories that were explicitly preopened (other
+    /// than the root) and the paths the runner declared it needs may be
+    /// accessed, with the permissions they were granted.
+    PreopensOnly,
+    /// Only the paths matched by these rules may be accessed. When several
+    /// rules match a path, the one with the longest prefix wins.
+    Custom(Vec<PathRule>),
+}
+
+impl CapabilityFsV1 {
+    pub fn update(&mut self, other: CapabilityFsV1) {
+        if other != CapabilityFsV1::Unrestricted {
+            *self = other;
+        }
+    }
+}
+
+/// Grants access to every path that starts with `prefix`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PathRule {
+    /// An absolute path in the guest's file system.
+    pub prefix: PathBuf,
+    pub access: FsAccess,
+}
+
+impl PathRule {
+    pub fn new(prefix: impl Into<PathBuf>, access: FsAccess) -> Self {
+        PathRule {
+            prefix: prefix.into(),
+            access,
+        }
+    }
+
+    /// Allows reading files, listing directories and looking up metadata.
+    pub fn read_only(prefix: impl Into<PathBuf>) -> Self {
+        PathRule::new(prefix, FsAccess::READ)
+    }
+
+    /// Allows creating, writing and removing files without reading them back.
+    pub fn write_only(prefix: impl Into<PathBuf>) -> Self {
+        PathRule::new(prefix, FsAccess::WRITE.union(FsAccess::CREATE))
+    }
+
+    /// Allows any kind of access.
+    pub fn read_write(prefix: impl Into<PathBuf>) -> Self {
+        PathRule::new(prefix, FsAccess::ALL)
+    }
+
+    /// Does this rule apply to `path`?
+    pub fn matches(&self, path: &Path) -> bool {
+        path.starts_with(&self.prefix)
+    }
+}
+
+/// The ways a path may be accessed.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct FsAccess {
+    /// Open files for reading, list directories and read links.
+    pub read: bool,
+    /// Open files for writing, truncate, remove or rename them, and change
+    /// their metadata.
+    pub write: bool,
+    /// Create files, directories and links.
+    pub create: bool,
+}
+
+impl FsAccess {
+    /// No access at all. When required, any access granted to a path is
+    /// enough (e.g. to look up its metadata).
+    pub const NONE: FsAccess = FsAccess {
+        read: false,
+        write: false,
+        create: false,
+    };
+    pub const READ: FsAccess = FsAccess {
+        read: true,
+        ..FsAccess::NONE
+    };
+    pub const WRITE: FsAccess = FsAccess {
+        write: true,
+        ..FsAccess::NONE
+    };
+    pub const CREATE: FsAccess = FsAccess {
+        create: true,
+        ..FsAccess::NONE
+    };
+    pub const ALL: FsAccess = FsAccess {
+        read: true,
+        write: true,
+        create: true,
+    };
+
+    pub const fn union(self, other: FsAccess) -> FsAccess {
+        FsAccess {
+            read: self.read || other.read,
+            write: self.write || other.write,
+            create: self.create || other.create,
+        }
+    }
+
+    pub const fn is_none(&self) -> bool {
+        !self.read && !self.write && !self.create
+    }
+
+    /// Does this grant everything `required` asks for?
+    pub const fn allows(&self, required: FsAccess) -> bool {
+        !self.is_none()
+            && (self.read || !required.read)
+            && (self.write || !required.write)
+            && (self.create || !required.create)
+    }
+}
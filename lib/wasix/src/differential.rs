@@ -0,0 +1,644 @@
+//! Runs a module under two engines, usually built with different compilers,
+//! and compares everything the two runs can observe, to catch
+//! miscompilations.
+//!
+//! Both sides get mirrored environments: the same arguments, environment
+//! variables and files (in an in-memory filesystem), and the same stubs in
+//! place of the WASI functions whose results would otherwise change from one
+//! run to the next. `random_get` returns bytes from a seeded generator, the
+//! clocks start at a fixed time and advance by a fixed step on every read,
+//! and file timestamps always read as zero.
+//!
+//! Every call to a function imported from a WASI namespace is recorded along
+//! with its parameters and results, and the runs are compared on
+//!
+//! - the ordered stream of those calls and what each invocation returned,
+//!   how it trapped or the code it exited with,
+//! - what was written to stdout and stderr,
+//! - a hash of linear memory once the last invocation returned.
+//!
+//! The first divergence is reported with the events leading up to it.
+
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context;
+use rand::{rngs::StdRng, RngCore, SeedableRng};
+use sha2::{Digest, Sha256};
+use virtual_fs::{mem_fs, AsyncWriteExt, FileSystem, Pipe};
+use virtual_mio::InlineWaker;
+use wasmer::{
+    Engine, Extern, Function, FunctionEnv, FunctionEnvMut, Memory, Module, RuntimeError, Store,
+    Value,
+};
+use wasmer_wasix_types::wasi::{Errno, ExitCode};
+
+use crate::{WasiEnv, WasiError};
+
+/// The namespaces whose imports are recorded and stubbed.
+const WASI_NAMESPACES: &[&str] = &[
+    "wasi_unstable",
+    "wasi_snapshot_preview1",
+    "wasix_32v1",
+    "wasix_64v1",
+];
+
+/// What the clocks read the first time, in nanoseconds since the epoch.
+const CLOCK_START: u64 = 1_700_000_000_000_000_000;
+
+/// How far the clocks advance every time they are read, which is also their
+/// reported resolution.
+const CLOCK_STEP: u64 = 1_000_000;
+
+/// Where the access, modification and status change times are in a
+/// `filestat`, and in the `filestat` of `wasi_unstable`.
+const FILESTAT_TIMES: u64 = 40;
+const FILESTAT_TIMES_UNSTABLE: u64 = 32;
+
+/// How many of the events before a divergence are reported with it.
+const CONTEXT_EVENTS: usize = 8;
+
+/// Runs a module under two engines and reports the first difference in what
+/// the runs did.
+///
+/// Without any invocations, the module's `_start` is called.
+#[derive(Debug, Clone)]
+pub struct DifferentialRunner {
+    engines: [Engine; 2],
+    seed: u64,
+    args: Vec<String>,
+    envs: Vec<(String, String)>,
+    files: Vec<(PathBuf, Vec<u8>)>,
+    invocations: Vec<(String, Vec<Value>)>,
+}
+
+impl DifferentialRunner {
+    pub fn new(left: Engine, right: Engine) -> Self {
+        DifferentialRunner {
+            engines: [left, right],
+            seed: 0,
+            args: Vec::new(),
+            envs: Vec::new(),
+            files: Vec::new(),
+            invocations: Vec::new(),
+        }
+    }
+
+    /// Seeds the generator behind `random_get`.
+    pub fn with_seed(&mut self, seed: u64) -> &mut Self {
+        self.seed = seed;
+        self
+    }
+
+    pub fn with_args<A, S>(&mut self, args: A) -> &mut Self
+    where
+        A: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.args.extend(args.into_iter().map(|s| s.into()));
+        self
+    }
+
+    pub fn with_env(&mut self, key: impl Into<String>, value: impl Into<String>) -> &mut Self {
+        self.envs.push((key.into(), value.into()));
+        self
+    }
+
+    /// Adds a file, and the directories leading up to it, to the filesystem
+    /// both sides start with.
+    pub fn with_file(
+        &mut self,
+        path: impl Into<PathBuf>,
+        contents: impl Into<Vec<u8>>,
+    ) -> &mut Self {
+        self.files.push((path.into(), contents.into()));
+        self
+    }
+
+    /// Calls the exported `function` with `params` once the earlier
+    /// invocations returned, unless one of them exited.
+    pub fn with_invocation(
+        &mut self,
+        function: impl Into<String>,
+        params: impl Into<Vec<Value>>,
+    ) -> &mut Self {
+        self.invocations.push((function.into(), params.into()));
+        self
+    }
+
+    /// Runs `wasm` under both engines and compares the runs.
+    ///
+    /// This has to be called from within a Tokio runtime, like
+    /// [`WasiEnvBuilder::instantiate()`](crate::WasiEnvBuilder::instantiate).
+    pub fn run(&self, wasm: &[u8]) -> Result<Report, anyhow::Error> {
+        let [left, right] = &self.engines;
+        let left = self.run_with(left, wasm).context("the left side failed")?;
+        let right = self
+            .run_with(right, wasm)
+            .context("the right side failed")?;
+        let divergence = Divergence::between(&left, &right);
+
+        Ok(Report {
+            left,
+            right,
+            divergence,
+        })
+    }
+
+    fn run_with(&self, engine: &Engine, wasm: &[u8]) -> Result<Run, anyhow::Error> {
+        let mut store = Store::new(engine.clone());
+        let module = Module::new(&store, wasm)?;
+
+        let fs = mem_fs::FileSystem::default();
+        for (path, contents) in &self.files {
+            write_file(&fs, path, contents)
+                .with_context(|| format!("unable to create \"{}\"", path.display()))?;
+        }
+        let (stdout_tx, mut stdout) = Pipe::channel();
+        let (stderr_tx, mut stderr) = Pipe::channel();
+
+        let mut func_env = WasiEnv::builder("differential")
+            .engine(engine.clone())
+            .args(&self.args)
+            .envs(self.envs.iter().cloned())
+            .fs(Box::new(fs))
+            .preopen_dir("/")?
+            .stdout(Box::new(stdout_tx))
+            .stderr(Box::new(stderr_tx))
+            .finalize(&mut store)?;
+
+        let recorder = FunctionEnv::new(&mut store, Recorder::new(self.seed));
+        // Modules that don't import anything from WASI are run all the same
+        let mut imports = func_env
+            .import_object_for_all_wasi_versions(&mut store, &module)
+            .unwrap_or_default();
+        for import in module.imports() {
+            if !WASI_NAMESPACES.contains(&import.module()) {
+                continue;
+            }
+            if let Some(Extern::Function(inner)) =
+                imports.get_export(import.module(), import.name())
+            {
+                let recorded = record(&mut store, &recorder, import.module(), import.name(), inner);
+                imports.define(import.module(), import.name(), recorded);
+            }
+        }
+
+        let instance = wasmer::Instance::new(&mut store, &module, &imports)?;
+        func_env.initialize(&mut store, instance.clone())?;
+        let memory = instance
+            .exports
+            .iter()
+            .memories()
+            .map(|(_, memory)| memory.clone())
+            .next();
+        recorder.as_mut(&mut store).memory = memory.clone();
+
+        let default_invocation = [("_start".to_string(), Vec::new())];
+        let invocations = if self.invocations.is_empty() {
+            &default_invocation[..]
+        } else {
+            &self.invocations[..]
+        };
+        for (name, params) in invocations {
+            let function = instance.exports.get_function(name)?;
+            let outcome = Outcome::of(function.call(&mut store, params));
+            let exited = matches!(outcome, Outcome::Exited(_));
+            recorder.as_mut(&mut store).events.push(Event::Returned {
+                function: name.clone(),
+                outcome,
+            });
+            if exited {
+                break;
+            }
+        }
+
+        let memory_hash = match memory {
+            Some(memory) => Some(Sha256::digest(memory.view(&store).copy_to_vec()?).into()),
+            None => None,
+        };
+
+        Ok(Run {
+            events: std::mem::take(&mut recorder.as_mut(&mut store).events),
+            stdout: drain(&mut stdout),
+            stderr: drain(&mut stderr),
+            memory_hash,
+        })
+    }
+}
+
+/// What one side of a differential run did.
+#[derive(Debug, Clone)]
+pub struct Run {
+    pub events: Vec<Event>,
+    pub stdout: Vec<u8>,
+    pub stderr: Vec<u8>,
+    /// The SHA-256 of the exported memory after the last invocation.
+    pub memory_hash: Option<[u8; 32]>,
+}
+
+/// The result of [`DifferentialRunner::run()`].
+#[derive(Debug, Clone)]
+pub struct Report {
+    pub left: Run,
+    pub right: Run,
+    /// The first difference between the runs, if they did the same.
+    pub divergence: Option<Divergence>,
+}
+
+impl Report {
+    pub fn is_identical(&self) -> bool {
+        self.divergence.is_none()
+    }
+}
+
+/// Something a run did, in the order it happened.
+#[derive(Debug, Clone)]
+pub enum Event {
+    /// A function imported from a WASI namespace was called.
+    Syscall {
+        namespace: String,
+        name: String,
+        params: Vec<Value>,
+        outcome: Outcome,
+    },
+    /// One of the invocations returned.
+    Returned { function: String, outcome: Outcome },
+}
+
+impl Event {
+    fn same_as(&self, other: &Event) -> bool {
+        match (self, other) {
+            (
+                Event::Syscall {
+                    namespace,
+                    name,
+                    params,
+                    outcome,
+                },
+                Event::Syscall {
+                    namespace: other_namespace,
+                    name: other_name,
+                    params: other_params,
+                    outcome: other_outcome,
+                },
+            ) => {
+                namespace == other_namespace
+                    && name == other_name
+                    && same_values(params, other_params)
+                    && outcome.same_as(other_outcome)
+            }
+            (
+                Event::Returned { function, outcome },
+                Event::Returned {
+                    function: other_function,
+                    outcome: other_outcome,
+                },
+            ) => function == other_function && outcome.same_as(other_outcome),
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Event::Syscall {
+                namespace,
+                name,
+                params,
+                outcome,
+            } => write!(f, "{namespace}::{name}{params:?} -> {outcome}"),
+            Event::Returned { function, outcome } => write!(f, "{function}() -> {outcome}"),
+        }
+    }
+}
+
+/// How a call ended.
+#[derive(Debug, Clone)]
+pub enum Outcome {
+    Returned(Vec<Value>),
+    Exited(ExitCode),
+    /// The message of the trap or error the call failed with.
+    Trapped(String),
+}
+
+impl Outcome {
+    fn of(result: Result<Box<[Value]>, RuntimeError>) -> Self {
+        match result {
+            Ok(values) => Outcome::Returned(values.into_vec()),
+            Err(error) => match error.downcast_ref::<WasiError>() {
+                Some(WasiError::Exit(code)) => Outcome::Exited(*code),
+                _ => Outcome::Trapped(error.message()),
+            },
+        }
+    }
+
+    fn same_as(&self, other: &Outcome) -> bool {
+        match (self, other) {
+            (Outcome::Returned(values), Outcome::Returned(other_values)) => {
+                same_values(values, other_values)
+            }
+            (Outcome::Exited(code), Outcome::Exited(other_code)) => code == other_code,
+            (Outcome::Trapped(message), Outcome::Trapped(other_message)) => {
+                message == other_message
+            }
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Outcome::Returned(values) => write!(f, "{values:?}"),
+            Outcome::Exited(code) => write!(f, "exit({})", code.raw()),
+            Outcome::Trapped(message) => write!(f, "trap: {message}"),
+        }
+    }
+}
+
+/// Floats are compared bit for bit, so NaNs with different payloads count
+/// as different.
+fn same_values(values: &[Value], other: &[Value]) -> bool {
+    values.len() == other.len()
+        && values.iter().zip(other).all(|pair| match pair {
+            (Value::I32(a), Value::I32(b)) => a == b,
+            (Value::I64(a), Value::I64(b)) => a == b,
+            (Value::F32(a), Value::F32(b)) => a.to_bits() == b.to_bits(),
+            (Value::F64(a), Value::F64(b)) => a.to_bits() == b.to_bits(),
+            (Value::V128(a), Value::V128(b)) => a == b,
+            (Value::ExternRef(a), Value::ExternRef(b)) => a.is_some() == b.is_some(),
+            (Value::FuncRef(a), Value::FuncRef(b)) => a.is_some() == b.is_some(),
+            (Value::ExceptionRef(a), Value::ExceptionRef(b)) => a.is_some() == b.is_some(),
+            _ => false,
+        })
+}
+
+/// The first difference between two runs.
+#[derive(Debug, Clone)]
+pub enum Divergence {
+    /// The `index`th events differ, or only one of the runs got that far.
+    Event {
+        index: usize,
+        left: Option<Event>,
+        right: Option<Event>,
+        /// The events before, oldest first.
+        context: Vec<Event>,
+    },
+    Stdout {
+        left: Vec<u8>,
+        right: Vec<u8>,
+    },
+    Stderr {
+        left: Vec<u8>,
+        right: Vec<u8>,
+    },
+    Memory {
+        left: Option<[u8; 32]>,
+        right: Option<[u8; 32]>,
+    },
+}
+
+impl Divergence {
+    fn between(left: &Run, right: &Run) -> Option<Self> {
+        let len = left.events.len().max(right.events.len());
+        for index in 0..len {
+            let (l, r) = (left.events.get(index), right.events.get(index));
+            let same = match (l, r) {
+                (Some(l), Some(r)) => l.same_as(r),
+                _ => false,
+            };
+            if !same {
+                return Some(Divergence::Event {
+                    index,
+                    left: l.cloned(),
+                    right: r.cloned(),
+                    context: left.events[index.saturating_sub(CONTEXT_EVENTS)..index].to_vec(),
+                });
+            }
+        }
+
+        if left.stdout != right.stdout {
+            Some(Divergence::Stdout {
+                left: left.stdout.clone(),
+                right: right.stdout.clone(),
+            })
+        } else if left.stderr != right.stderr {
+            Some(Divergence::Stderr {
+                left: left.stderr.clone(),
+                right: right.stderr.clone(),
+            })
+        } else if left.memory_hash != right.memory_hash {
+            Some(Divergence::Memory {
+                left: left.memory_hash,
+                right: right.memory_hash,
+            })
+        } else {
+            None
+        }
+    }
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let event = |event: &Option<Event>| match event {
+            Some(event) => event.to_string(),
+            None => "(nothing)".to_string(),
+        };
+        let hash = |hash: &Option<[u8; 32]>| match hash {
+            Some(hash) => hex::encode(hash),
+            None => "(no memory)".to_string(),
+        };
+
+        match self {
+            Divergence::Event {
+                index,
+                left,
+                right,
+                context,
+            } => {
+                writeln!(f, "the runs diverged at event {index}")?;
+                for event in context {
+                    writeln!(f, "    {event}")?;
+                }
+                writeln!(f, "left:  {}", event(left))?;
+                write!(f, "right: {}", event(right))
+            }
+            Divergence::Stdout { left, right } => write!(
+                f,
+                "stdout differs: {:?} vs {:?}",
+                String::from_utf8_lossy(left),
+                String::from_utf8_lossy(right)
+            ),
+            Divergence::Stderr { left, right } => write!(
+                f,
+                "stderr differs: {:?} vs {:?}",
+                String::from_utf8_lossy(left),
+                String::from_utf8_lossy(right)
+            ),
+            Divergence::Memory { left, right } => write!(
+                f,
+                "memory differs at exit: {} vs {}",
+                hash(left),
+                hash(right)
+            ),
+        }
+    }
+}
+
+/// The state behind the recorded imports of one side.
+struct Recorder {
+    events: Vec<Event>,
+    rng: StdRng,
+    clock: u64,
+    memory: Option<Memory>,
+}
+
+impl Recorder {
+    fn new(seed: u64) -> Self {
+        Recorder {
+            events: Vec::new(),
+            rng: StdRng::seed_from_u64(seed),
+            clock: CLOCK_START,
+            memory: None,
+        }
+    }
+}
+
+/// Wraps `inner`, which was imported as `namespace::name`, so its calls are
+/// recorded, replacing it with a stub if its results would change from one
+/// run to the next.
+fn record(
+    store: &mut Store,
+    recorder: &FunctionEnv<Recorder>,
+    namespace: &str,
+    name: &str,
+    inner: Function,
+) -> Function {
+    let ty = inner.ty(&*store);
+    let namespace = namespace.to_string();
+    let name = name.to_string();
+
+    Function::new_with_env(store, recorder, ty, move |mut env, params| {
+        let result = match name.as_str() {
+            "random_get" => random_get(&mut env, params),
+            "clock_time_get" => clock_time_get(&mut env, params),
+            "clock_res_get" => clock_res_get(&mut env, params),
+            "fd_filestat_get" | "path_filestat_get" => {
+                let result = inner.call(&mut env, params);
+                let succeeded = matches!(&result, Ok(values) if values[..] == [Value::I32(0)]);
+                let times = match namespace.as_str() {
+                    "wasi_unstable" => FILESTAT_TIMES_UNSTABLE,
+                    _ => FILESTAT_TIMES,
+                };
+                // The buffer is always the last parameter
+                match params.last() {
+                    Some(buf) if succeeded => write(&mut env, buf, times, &[0; 24]),
+                    _ => result,
+                }
+            }
+            _ => inner.call(&mut env, params),
+        };
+
+        env.data_mut().events.push(Event::Syscall {
+            namespace: namespace.clone(),
+            name: name.clone(),
+            params: params.to_vec(),
+            outcome: Outcome::of(result.clone()),
+        });
+        result.map(Vec::from)
+    })
+}
+
+/// `random_get(buf, buf_len)`, from the seeded generator.
+fn random_get(
+    env: &mut FunctionEnvMut<'_, Recorder>,
+    params: &[Value],
+) -> Result<Box<[Value]>, RuntimeError> {
+    let mut bytes = vec![0; offset(&params[1]) as usize];
+    env.data_mut().rng.fill_bytes(&mut bytes);
+    write(env, &params[0], 0, &bytes)
+}
+
+/// `clock_time_get(id, precision, time)`, which advances every clock by
+/// [`CLOCK_STEP`].
+fn clock_time_get(
+    env: &mut FunctionEnvMut<'_, Recorder>,
+    params: &[Value],
+) -> Result<Box<[Value]>, RuntimeError> {
+    let recorder = env.data_mut();
+    let now = recorder.clock;
+    recorder.clock += CLOCK_STEP;
+    write(env, &params[2], 0, &now.to_le_bytes())
+}
+
+/// `clock_res_get(id, resolution)`
+fn clock_res_get(
+    env: &mut FunctionEnvMut<'_, Recorder>,
+    params: &[Value],
+) -> Result<Box<[Value]>, RuntimeError> {
+    write(env, &params[1], 0, &CLOCK_STEP.to_le_bytes())
+}
+
+/// Writes `data` to `ptr + offset` in the guest's memory, returning the errno
+/// a syscall would.
+fn write(
+    env: &mut FunctionEnvMut<'_, Recorder>,
+    ptr: &Value,
+    offset: u64,
+    data: &[u8],
+) -> Result<Box<[Value]>, RuntimeError> {
+    let (recorder, store) = env.data_and_store_mut();
+    let memory = recorder
+        .memory
+        .as_ref()
+        .ok_or_else(|| RuntimeError::new("the module doesn't export its memory"))?;
+    let errno = match memory.view(&store).write(self::offset(ptr) + offset, data) {
+        Ok(()) => Errno::Success,
+        Err(_) => Errno::Memviolation,
+    };
+    Ok(vec![Value::I32(errno as i32)].into_boxed_slice())
+}
+
+/// A pointer or length parameter, from either 32 or 64 bit memories.
+fn offset(value: &Value) -> u64 {
+    match value {
+        Value::I32(value) => *value as u32 as u64,
+        Value::I64(value) => *value as u64,
+        _ => 0,
+    }
+}
+
+fn write_file(fs: &mem_fs::FileSystem, path: &Path, contents: &[u8]) -> Result<(), anyhow::Error> {
+    if let Some(parent) = path.parent() {
+        let mut dirs: Vec<_> = parent.ancestors().collect();
+        dirs.reverse();
+        for dir in dirs {
+            if fs.metadata(dir).is_err() {
+                fs.create_dir(dir)?;
+            }
+        }
+    }
+
+    let mut file = fs
+        .new_open_options()
+        .create(true)
+        .write(true)
+        .truncate(true)
+        .open(path)?;
+    InlineWaker::block_on(file.write_all(contents))?;
+    Ok(())
+}
+
+/// Everything that was written to `pipe` so far.
+fn drain(pipe: &mut Pipe) -> Vec<u8> {
+    let mut output = Vec::new();
+    let mut buf = [0; 1024];
+    while let Some(read @ 1..) = pipe.try_read(&mut buf) {
+        output.extend_from_slice(&buf[..read]);
+    }
+    output
+}
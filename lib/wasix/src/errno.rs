@@ -0,0 +1,349 @@
+//! Conversions between [`Errno`] and the error types used by the host side of
+//! the runtime.
+//!
+//! Custom [`VirtualFile`](crate::VirtualFile)s, file systems and
+//! [`VirtualNetworking`](crate::VirtualNetworking) implementations report
+//! failures as [`std::io::Error`], [`FsError`] or [`NetworkError`]. These
+//! functions are what the syscalls use to turn them into the errno a guest
+//! sees, so implementations that need to pick an error for a particular errno
+//! should go through them too.
+//!
+//! [`std::io::ErrorKind`] converts to and from [`Errno`] directly with
+//! [`From`].
+
+use virtual_fs::FsError;
+use virtual_net::NetworkError;
+use wasmer_wasix_types::wasi::Errno;
+
+/// Converts an I/O error into the errno that describes its
+/// [kind](std::io::Error::kind).
+pub fn io_error_into_wasi_err(err: std::io::Error) -> Errno {
+    err.kind().into()
+}
+
+pub fn fs_error_into_wasi_err(fs_error: FsError) -> Errno {
+    match fs_error {
+        FsError::AlreadyExists => Errno::Exist,
+        FsError::AddressInUse => Errno::Addrinuse,
+        FsError::AddressNotAvailable => Errno::Addrnotavail,
+        FsError::BaseNotDirectory => Errno::Notdir,
+        FsError::BrokenPipe => Errno::Pipe,
+        FsError::ConnectionAborted => Errno::Connaborted,
+        FsError::ConnectionRefused => Errno::Connrefused,
+        FsError::ConnectionReset => Errno::Connreset,
+        FsError::Interrupted => Errno::Intr,
+        FsError::InvalidData => Errno::Io,
+        FsError::InvalidFd => Errno::Badf,
+        FsError::InvalidInput => Errno::Inval,
+        FsError::IOError => Errno::Io,
+        FsError::NoDevice => Errno::Nodev,
+        FsError::NotAFile => Errno::Inval,
+        FsError::NotConnected => Errno::Notconn,
+        FsError::EntryNotFound => Errno::Noent,
+        FsError::PermissionDenied => Errno::Perm,
+        FsError::TimedOut => Errno::Timedout,
+        FsError::UnexpectedEof => Errno::Proto,
+        FsError::WouldBlock => Errno::Again,
+        FsError::WriteZero => Errno::Nospc,
+        FsError::DirectoryNotEmpty => Errno::Notempty,
+        FsError::StorageFull => Errno::Nospc,
+        FsError::Lock | FsError::UnknownError => Errno::Io,
+        FsError::Unsupported => Errno::Notsup,
+    }
+}
+
+pub fn fs_error_from_wasi_err(err: Errno) -> FsError {
+    match err {
+        Errno::Badf => FsError::InvalidFd,
+        Errno::Exist => FsError::AlreadyExists,
+        Errno::Io => FsError::IOError,
+        Errno::Addrinuse => FsError::AddressInUse,
+        Errno::Addrnotavail => FsError::AddressNotAvailable,
+        Errno::Pipe => FsError::BrokenPipe,
+        Errno::Connaborted => FsError::ConnectionAborted,
+        Errno::Connrefused => FsError::ConnectionRefused,
+        Errno::Connreset => FsError::ConnectionReset,
+        Errno::Intr => FsError::Interrupted,
+        Errno::Inval => FsError::InvalidInput,
+        Errno::Notconn => FsError::NotConnected,
+        Errno::Nodev => FsError::NoDevice,
+        Errno::Noent => FsError::EntryNotFound,
+        Errno::Notdir => FsError::BaseNotDirectory,
+        Errno::Perm => FsError::PermissionDenied,
+        Errno::Timedout => FsError::TimedOut,
+        Errno::Proto => FsError::UnexpectedEof,
+        Errno::Again => FsError::WouldBlock,
+        Errno::Nospc => FsError::StorageFull,
+        Errno::Notempty => FsError::DirectoryNotEmpty,
+        Errno::Notsup => FsError::Unsupported,
+        _ => FsError::UnknownError,
+    }
+}
+
+pub fn net_error_into_wasi_err(net_error: NetworkError) -> Errno {
+    match net_error {
+        NetworkError::InvalidFd => Errno::Badf,
+        NetworkError::AlreadyExists => Errno::Exist,
+        NetworkError::Lock => Errno::Io,
+        NetworkError::IOError => Errno::Io,
+        NetworkError::AddressInUse => Errno::Addrinuse,
+        NetworkError::AddressNotAvailable => Errno::Addrnotavail,
+        NetworkError::BrokenPipe => Errno::Pipe,
+        NetworkError::ConnectionAborted => Errno::Connaborted,
+        NetworkError::ConnectionRefused => Errno::Connrefused,
+        NetworkError::ConnectionReset => Errno::Connreset,
+        NetworkError::Interrupted => Errno::Intr,
+        NetworkError::InvalidData => Errno::Io,
+        NetworkError::InvalidInput => Errno::Inval,
+        NetworkError::NotConnected => Errno::Notconn,
+        NetworkError::NoDevice => Errno::Nodev,
+        NetworkError::PermissionDenied => Errno::Perm,
+        NetworkError::TimedOut => Errno::Timedout,
+        NetworkError::UnexpectedEof => Errno::Proto,
+        NetworkError::WouldBlock => Errno::Again,
+        NetworkError::WriteZero => Errno::Nospc,
+        NetworkError::TooManyOpenFiles => Errno::Mfile,
+        NetworkError::InsufficientMemory => Errno::Nomem,
+        NetworkError::Unsupported => Errno::Notsup,
+        NetworkError::UnknownError => Errno::Io,
+        NetworkError::Unreachable => Errno::Hostunreach,
+    }
+}
+
+pub fn net_error_from_wasi_err(err: Errno) -> NetworkError {
+    match err {
+        Errno::Badf => NetworkError::InvalidFd,
+        Errno::Exist => NetworkError::AlreadyExists,
+        Errno::Io => NetworkError::IOError,
+        Errno::Addrinuse => NetworkError::AddressInUse,
+        Errno::Addrnotavail => NetworkError::AddressNotAvailable,
+        Errno::Pipe => NetworkError::BrokenPipe,
+        Errno::Connaborted => NetworkError::ConnectionAborted,
+        Errno::Connrefused => NetworkError::ConnectionRefused,
+        Errno::Connreset => NetworkError::ConnectionReset,
+        Errno::Intr => NetworkError::Interrupted,
+        Errno::Inval => NetworkError::InvalidInput,
+        Errno::Notconn => NetworkError::NotConnected,
+        Errno::Nodev => NetworkError::NoDevice,
+        Errno::Perm => NetworkError::PermissionDenied,
+        Errno::Timedout => NetworkError::TimedOut,
+        Errno::Proto => NetworkError::UnexpectedEof,
+        Errno::Again => NetworkError::WouldBlock,
+        Errno::Nospc => NetworkError::WriteZero,
+        Errno::Mfile => NetworkError::TooManyOpenFiles,
+        Errno::Nomem => NetworkError::InsufficientMemory,
+        Errno::Notsup => NetworkError::Unsupported,
+        Errno::Hostunreach | Errno::Netunreach => NetworkError::Unreachable,
+        _ => NetworkError::UnknownError,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::ErrorKind;
+
+    use super::*;
+
+    #[test]
+    fn io_error_kinds() {
+        let table = [
+            (ErrorKind::NotFound, Errno::Noent),
+            (ErrorKind::PermissionDenied, Errno::Perm),
+            (ErrorKind::ConnectionRefused, Errno::Connrefused),
+            (ErrorKind::ConnectionReset, Errno::Connreset),
+            (ErrorKind::HostUnreachable, Errno::Hostunreach),
+            (ErrorKind::NetworkUnreachable, Errno::Netunreach),
+            (ErrorKind::ConnectionAborted, Errno::Connaborted),
+            (ErrorKind::NotConnected, Errno::Notconn),
+            (ErrorKind::AddrInUse, Errno::Addrinuse),
+            (ErrorKind::AddrNotAvailable, Errno::Addrnotavail),
+            (ErrorKind::NetworkDown, Errno::Netdown),
+            (ErrorKind::BrokenPipe, Errno::Pipe),
+            (ErrorKind::AlreadyExists, Errno::Exist),
+            (ErrorKind::WouldBlock, Errno::Again),
+            (ErrorKind::NotADirectory, Errno::Notdir),
+            (ErrorKind::IsADirectory, Errno::Isdir),
+            (ErrorKind::DirectoryNotEmpty, Errno::Notempty),
+            (ErrorKind::ReadOnlyFilesystem, Errno::Rofs),
+            (ErrorKind::StaleNetworkFileHandle, Errno::Stale),
+            (ErrorKind::InvalidInput, Errno::Inval),
+            (ErrorKind::InvalidData, Errno::Io),
+            (ErrorKind::TimedOut, Errno::Timedout),
+            (ErrorKind::WriteZero, Errno::Nospc),
+            (ErrorKind::StorageFull, Errno::Nospc),
+            (ErrorKind::NotSeekable, Errno::Spipe),
+            (ErrorKind::FileTooLarge, Errno::Fbig),
+            (ErrorKind::ResourceBusy, Errno::Busy),
+            (ErrorKind::ExecutableFileBusy, Errno::Txtbsy),
+            (ErrorKind::Deadlock, Errno::Deadlk),
+            (ErrorKind::TooManyLinks, Errno::Mlink),
+            (ErrorKind::ArgumentListTooLong, Errno::Toobig),
+            (ErrorKind::Interrupted, Errno::Intr),
+            (ErrorKind::Unsupported, Errno::Notsup),
+            (ErrorKind::UnexpectedEof, Errno::Proto),
+            (ErrorKind::OutOfMemory, Errno::Nomem),
+            (ErrorKind::Other, Errno::Io),
+        ];
+
+        for (kind, errno) in table {
+            assert_eq!(Errno::from(kind), errno, "{kind:?}");
+            assert_eq!(
+                io_error_into_wasi_err(std::io::Error::from(kind)),
+                errno,
+                "{kind:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn errnos_survive_a_round_trip_through_io_errors() {
+        let errnos = [
+            Errno::Toobig,
+            Errno::Addrinuse,
+            Errno::Addrnotavail,
+            Errno::Again,
+            Errno::Busy,
+            Errno::Connaborted,
+            Errno::Connrefused,
+            Errno::Connreset,
+            Errno::Deadlk,
+            Errno::Exist,
+            Errno::Fbig,
+            Errno::Hostunreach,
+            Errno::Intr,
+            Errno::Inval,
+            Errno::Isdir,
+            Errno::Mlink,
+            Errno::Netdown,
+            Errno::Netunreach,
+            Errno::Noent,
+            Errno::Nomem,
+            Errno::Nospc,
+            Errno::Notconn,
+            Errno::Notdir,
+            Errno::Notempty,
+            Errno::Notsup,
+            Errno::Perm,
+            Errno::Pipe,
+            Errno::Proto,
+            Errno::Rofs,
+            Errno::Spipe,
+            Errno::Stale,
+            Errno::Timedout,
+            Errno::Txtbsy,
+        ];
+
+        for errno in errnos {
+            let err = std::io::Error::from(errno);
+            assert_eq!(io_error_into_wasi_err(err), errno);
+        }
+    }
+
+    #[test]
+    fn fs_errors() {
+        let table = [
+            (FsError::AlreadyExists, Errno::Exist),
+            (FsError::AddressInUse, Errno::Addrinuse),
+            (FsError::AddressNotAvailable, Errno::Addrnotavail),
+            (FsError::BaseNotDirectory, Errno::Notdir),
+            (FsError::BrokenPipe, Errno::Pipe),
+            (FsError::ConnectionAborted, Errno::Connaborted),
+            (FsError::ConnectionRefused, Errno::Connrefused),
+            (FsError::ConnectionReset, Errno::Connreset),
+            (FsError::Interrupted, Errno::Intr),
+            (FsError::InvalidData, Errno::Io),
+            (FsError::InvalidFd, Errno::Badf),
+            (FsError::InvalidInput, Errno::Inval),
+            (FsError::IOError, Errno::Io),
+            (FsError::NoDevice, Errno::Nodev),
+            (FsError::NotAFile, Errno::Inval),
+            (FsError::NotConnected, Errno::Notconn),
+            (FsError::EntryNotFound, Errno::Noent),
+            (FsError::PermissionDenied, Errno::Perm),
+            (FsError::TimedOut, Errno::Timedout),
+            (FsError::UnexpectedEof, Errno::Proto),
+            (FsError::WouldBlock, Errno::Again),
+            (FsError::WriteZero, Errno::Nospc),
+            (FsError::DirectoryNotEmpty, Errno::Notempty),
+            (FsError::StorageFull, Errno::Nospc),
+            (FsError::Lock, Errno::Io),
+            (FsError::UnknownError, Errno::Io),
+            (FsError::Unsupported, Errno::Notsup),
+        ];
+
+        for (fs_error, errno) in table {
+            assert_eq!(fs_error_into_wasi_err(fs_error), errno, "{fs_error:?}");
+        }
+
+        // Every errno that doesn't lose information on the way in comes back
+        // out as the same error
+        for (fs_error, errno) in table {
+            if matches!(
+                fs_error,
+                FsError::InvalidData
+                    | FsError::NotAFile
+                    | FsError::WriteZero
+                    | FsError::Lock
+                    | FsError::UnknownError
+            ) {
+                continue;
+            }
+            assert_eq!(fs_error_from_wasi_err(errno), fs_error, "{errno:?}");
+        }
+        assert_eq!(fs_error_from_wasi_err(Errno::Io), FsError::IOError);
+        assert_eq!(fs_error_from_wasi_err(Errno::Loop), FsError::UnknownError);
+    }
+
+    #[test]
+    fn net_errors() {
+        let table = [
+            (NetworkError::InvalidFd, Errno::Badf),
+            (NetworkError::AlreadyExists, Errno::Exist),
+            (NetworkError::Lock, Errno::Io),
+            (NetworkError::IOError, Errno::Io),
+            (NetworkError::AddressInUse, Errno::Addrinuse),
+            (NetworkError::AddressNotAvailable, Errno::Addrnotavail),
+            (NetworkError::BrokenPipe, Errno::Pipe),
+            (NetworkError::ConnectionAborted, Errno::Connaborted),
+            (NetworkError::ConnectionRefused, Errno::Connrefused),
+            (NetworkError::ConnectionReset, Errno::Connreset),
+            (NetworkError::Interrupted, Errno::Intr),
+            (NetworkError::InvalidData, Errno::Io),
+            (NetworkError::InvalidInput, Errno::Inval),
+            (NetworkError::NotConnected, Errno::Notconn),
+            (NetworkError::NoDevice, Errno::Nodev),
+            (NetworkError::PermissionDenied, Errno::Perm),
+            (NetworkError::TimedOut, Errno::Timedout),
+            (NetworkError::UnexpectedEof, Errno::Proto),
+            (NetworkError::WouldBlock, Errno::Again),
+            (NetworkError::WriteZero, Errno::Nospc),
+            (NetworkError::TooManyOpenFiles, Errno::Mfile),
+            (NetworkError::InsufficientMemory, Errno::Nomem),
+            (NetworkError::Unsupported, Errno::Notsup),
+            (NetworkError::UnknownError, Errno::Io),
+            (NetworkError::Unreachable, Errno::Hostunreach),
+        ];
+
+        for (net_error, errno) in table {
+            assert_eq!(net_error_into_wasi_err(net_error), errno, "{net_error:?}");
+        }
+
+        for (net_error, errno) in table {
+            if matches!(
+                net_error,
+                NetworkError::Lock | NetworkError::InvalidData | NetworkError::UnknownError
+            ) {
+                continue;
+            }
+            assert_eq!(net_error_from_wasi_err(errno), net_error, "{errno:?}");
+        }
+        assert_eq!(
+            net_error_from_wasi_err(Errno::Netunreach),
+            NetworkError::Unreachable
+        );
+        assert_eq!(
+            net_error_from_wasi_err(Errno::Loop),
+            NetworkError::UnknownError
+        );
+    }
+}
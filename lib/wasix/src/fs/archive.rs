@@ -0,0 +1,440 @@
+//! Extraction of tar archives by the host, straight into the WASI filesystem
+//! (see the `archive_extract` syscall).
+
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU32, AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use wasmer_wasix_types::wasi::Errno;
+
+/// An extraction started by `archive_extract`, whose progress is polled with
+/// `archive_extract_poll`.
+#[derive(Debug)]
+pub(crate) struct ArchiveJob {
+    /// The number of entries extracted so far.
+    pub entries: AtomicU64,
+    /// The number of bytes written to files so far.
+    pub bytes: AtomicU64,
+    result: tokio::sync::watch::Sender<Option<Errno>>,
+}
+
+impl ArchiveJob {
+    fn new() -> Self {
+        ArchiveJob {
+            entries: AtomicU64::new(0),
+            bytes: AtomicU64::new(0),
+            result: tokio::sync::watch::Sender::new(None),
+        }
+    }
+
+    pub fn finish(&self, result: Result<(), Errno>) {
+        self.result
+            .send_replace(Some(result.err().unwrap_or(Errno::Success)));
+    }
+
+    /// The outcome of the extraction, or [`None`] while it's still running.
+    pub fn result(&self) -> Option<Errno> {
+        *self.result.borrow()
+    }
+
+    /// Wait for the extraction to finish.
+    pub async fn wait(&self) -> Result<(), Errno> {
+        let mut result = self.result.subscribe();
+        let errno = match result.wait_for(Option::is_some).await {
+            Ok(errno) => errno.unwrap_or(Errno::Success),
+            Err(_) => Errno::Canceled,
+        };
+        match errno {
+            Errno::Success => Ok(()),
+            errno => Err(errno),
+        }
+    }
+}
+
+/// The extractions a process has started and not yet collected the result
+/// of.
+#[derive(Debug, Default)]
+pub(crate) struct ArchiveJobs {
+    next_id: AtomicU32,
+    jobs: Mutex<HashMap<u32, Arc<ArchiveJob>>>,
+}
+
+impl ArchiveJobs {
+    pub fn start(&self) -> (u32, Arc<ArchiveJob>) {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let job = Arc::new(ArchiveJob::new());
+        self.jobs.lock().unwrap().insert(id, job.clone());
+        (id, job)
+    }
+
+    pub fn get(&self, id: u32) -> Option<Arc<ArchiveJob>> {
+        self.jobs.lock().unwrap().get(&id).cloned()
+    }
+
+    pub fn remove(&self, id: u32) {
+        self.jobs.lock().unwrap().remove(&id);
+    }
+}
+
+#[cfg(feature = "host-archive")]
+pub(crate) use self::extract::{extract_archive, ArchiveSource};
+
+#[cfg(feature = "host-archive")]
+mod extract {
+    use std::{
+        future::Future,
+        io::{self, BufRead, BufReader, Read, SeekFrom},
+        ops::{Deref, DerefMut},
+        path::{Component, Path, PathBuf},
+        pin::Pin,
+        sync::{atomic::Ordering, Arc, RwLock},
+        task::Poll,
+    };
+
+    use futures::future::poll_fn;
+    use tokio::io::{AsyncRead, AsyncSeek, AsyncWriteExt, ReadBuf};
+    use virtual_fs::{FileSystem, FsError, VirtualFile};
+    use wasmer_wasix_types::wasi::{Errno, Fd as WasiFd};
+
+    use super::ArchiveJob;
+    use crate::{
+        capabilities::FsAccess,
+        errno::io_error_into_wasi_err,
+        fs::{fs_error_into_wasi_err, Kind, WasiFs, WasiInodes},
+        runtime::task_manager::{VirtualTaskManager, VirtualTaskManagerExt},
+        syscalls::path_link_inner,
+    };
+
+    const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+    const ZSTD_MAGIC: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+    const CHUNK_SIZE: usize = 64 * 1024;
+
+    type SharedFile = Arc<RwLock<Box<dyn VirtualFile + Send + Sync + 'static>>>;
+
+    /// Reads the archive from an open file, starting at `offset`.
+    pub(crate) struct ArchiveSource {
+        file: SharedFile,
+        offset: Option<u64>,
+        tasks: Arc<dyn VirtualTaskManager>,
+    }
+
+    impl ArchiveSource {
+        pub fn new(file: SharedFile, offset: u64, tasks: Arc<dyn VirtualTaskManager>) -> Self {
+            ArchiveSource {
+                file,
+                offset: Some(offset),
+                tasks,
+            }
+        }
+    }
+
+    impl Read for ArchiveSource {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let file = self.file.clone();
+            let offset = self.offset.take();
+            let mut chunk = vec![0; buf.len().min(CHUNK_SIZE)];
+
+            let chunk = block_on(&self.tasks, async move {
+                if let Some(offset) = offset {
+                    let mut started = false;
+                    poll_fn(|cx| {
+                        let mut file = file.write().unwrap();
+                        let mut file = Pin::new(file.deref_mut());
+                        if !started {
+                            file.as_mut().start_seek(SeekFrom::Start(offset))?;
+                            started = true;
+                        }
+                        file.poll_complete(cx)
+                    })
+                    .await?;
+                }
+
+                // The lock is only held while polling, so the future can be
+                // sent to the task manager
+                let read = poll_fn(|cx| {
+                    let mut file = file.write().unwrap();
+                    let mut buf = ReadBuf::new(&mut chunk);
+                    match Pin::new(file.deref_mut()).poll_read(cx, &mut buf) {
+                        Poll::Ready(Ok(())) => Poll::Ready(Ok(buf.filled().len())),
+                        Poll::Ready(Err(e)) => Poll::Ready(Err(e)),
+                        Poll::Pending => Poll::Pending,
+                    }
+                })
+                .await?;
+                chunk.truncate(read);
+                Ok::<_, io::Error>(chunk)
+            })?;
+
+            buf[..chunk.len()].copy_from_slice(&chunk);
+            Ok(chunk.len())
+        }
+    }
+
+    /// Run a file operation on the task manager, as the files of some
+    /// filesystems (e.g. the host's) can only be driven from within its
+    /// runtime.
+    fn block_on<T: Send + 'static>(
+        tasks: &Arc<dyn VirtualTaskManager>,
+        work: impl Future<Output = io::Result<T>> + Send + 'static,
+    ) -> io::Result<T> {
+        tasks.spawn_and_block_on(work).map_err(io::Error::other)?
+    }
+
+    /// Extract the tar archive read from `source`, which may be compressed
+    /// with gzip or zstd, into the directory `dest_fd` refers to, whose path
+    /// in the filesystem is `dest`.
+    ///
+    /// The WASI filesystem has no notion of permissions, so the modes of the
+    /// entries are ignored. Symlinks and hard links only exist in the inode
+    /// tree of `fs`, like the ones `path_symlink` and `path_link` create.
+    pub(crate) fn extract_archive(
+        fs: &WasiFs,
+        inodes: &WasiInodes,
+        tasks: &Arc<dyn VirtualTaskManager>,
+        source: ArchiveSource,
+        dest_fd: WasiFd,
+        dest: &Path,
+        job: &ArchiveJob,
+    ) -> Result<(), Errno> {
+        let mut source = BufReader::with_capacity(CHUNK_SIZE, source);
+        let header = source.fill_buf().map_err(io_error_into_wasi_err)?;
+        let decoder: Box<dyn Read> = if header.starts_with(GZIP_MAGIC) {
+            Box::new(flate2::read::MultiGzDecoder::new(source))
+        } else if header.starts_with(ZSTD_MAGIC) {
+            let decoder =
+                ruzstd::streaming_decoder::StreamingDecoder::new(source).map_err(|e| {
+                    tracing::debug!(error = %e, "Invalid zstd stream");
+                    Errno::Io
+                })?;
+            Box::new(decoder)
+        } else {
+            Box::new(source)
+        };
+
+        let mut archive = tar::Archive::new(decoder);
+        for entry in archive.entries().map_err(io_error_into_wasi_err)? {
+            let mut entry = entry.map_err(io_error_into_wasi_err)?;
+            let path = entry_path(&entry.path().map_err(io_error_into_wasi_err)?)?;
+            if path.as_os_str().is_empty() {
+                continue;
+            }
+            fs.check_path_access(dest_fd, &path, FsAccess::CREATE)?;
+
+            let header = entry.header();
+            let mtime = header.mtime().ok();
+            match header.entry_type() {
+                tar::EntryType::Directory => {
+                    create_dirs(fs, &dest.join(&path))?;
+                }
+                tar::EntryType::Regular | tar::EntryType::Continuous => {
+                    create_parent(fs, dest, &path)?;
+                    write_file(fs, tasks, &dest.join(&path), &mut entry, mtime, job)?;
+                }
+                tar::EntryType::Symlink => {
+                    let target = entry
+                        .link_name()
+                        .map_err(io_error_into_wasi_err)?
+                        .ok_or(Errno::Inval)?
+                        .into_owned();
+                    if !stays_inside(&path, &target) {
+                        tracing::debug!(
+                            path=%path.display(),
+                            target=%target.display(),
+                            "Refusing to extract a symlink that points outside of the destination"
+                        );
+                        return Err(Errno::Notcapable);
+                    }
+                    create_parent(fs, dest, &path)?;
+                    symlink(fs, inodes, dest_fd, &path, &target)?;
+                }
+                tar::EntryType::Link => {
+                    let target = entry
+                        .link_name()
+                        .map_err(io_error_into_wasi_err)?
+                        .ok_or(Errno::Inval)?;
+                    let target = entry_path(&target)?;
+                    create_parent(fs, dest, &path)?;
+                    path_link_inner(
+                        fs,
+                        inodes,
+                        dest_fd,
+                        0,
+                        &target.to_string_lossy(),
+                        dest_fd,
+                        &path.to_string_lossy(),
+                    )?;
+                }
+                // Extended headers are applied to the entry they belong to by
+                // `tar`, and devices and fifos can't be represented
+                other => {
+                    tracing::debug!(path=%path.display(), kind=?other, "Skipping an archive entry");
+                    continue;
+                }
+            }
+            job.entries.fetch_add(1, Ordering::Relaxed);
+        }
+
+        Ok(())
+    }
+
+    /// The path of an entry relative to the destination, rejecting the ones
+    /// that would end up outside of it.
+    fn entry_path(path: &Path) -> Result<PathBuf, Errno> {
+        let mut relative = PathBuf::new();
+        for component in path.components() {
+            match component {
+                Component::Normal(name) => relative.push(name),
+                Component::CurDir => {}
+                Component::ParentDir | Component::RootDir | Component::Prefix(_) => {
+                    tracing::debug!(path=%path.display(), "Refusing to extract an entry outside of the destination");
+                    return Err(Errno::Notcapable);
+                }
+            }
+        }
+        Ok(relative)
+    }
+
+    /// Whether the symlink at `link` resolves to somewhere inside the
+    /// destination when it points at `target`.
+    fn stays_inside(link: &Path, target: &Path) -> bool {
+        // The directory the symlink is in
+        let mut depth = link.components().count() - 1;
+        for component in target.components() {
+            match component {
+                Component::Normal(_) => depth += 1,
+                Component::CurDir => {}
+                Component::ParentDir => match depth.checked_sub(1) {
+                    Some(parent) => depth = parent,
+                    None => return false,
+                },
+                Component::RootDir | Component::Prefix(_) => return false,
+            }
+        }
+        true
+    }
+
+    fn create_parent(fs: &WasiFs, dest: &Path, path: &Path) -> Result<(), Errno> {
+        match path.parent() {
+            Some(parent) => create_dirs(fs, &dest.join(parent)),
+            None => Ok(()),
+        }
+    }
+
+    fn create_dirs(fs: &WasiFs, path: &Path) -> Result<(), Errno> {
+        if fs.root_fs.metadata(path).is_ok_and(|meta| meta.is_dir()) {
+            return Ok(());
+        }
+        if let Some(parent) = path.parent() {
+            create_dirs(fs, parent)?;
+        }
+        match fs.root_fs.create_dir(path) {
+            Ok(()) | Err(FsError::AlreadyExists) => Ok(()),
+            Err(e) => Err(fs_error_into_wasi_err(e)),
+        }
+    }
+
+    fn write_file(
+        fs: &WasiFs,
+        tasks: &Arc<dyn VirtualTaskManager>,
+        path: &Path,
+        contents: &mut impl Read,
+        mtime: Option<u64>,
+        job: &ArchiveJob,
+    ) -> Result<(), Errno> {
+        let mut file = fs
+            .root_fs
+            .new_open_options()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(path)
+            .map_err(fs_error_into_wasi_err)?;
+
+        let mut chunk = vec![0; CHUNK_SIZE];
+        loop {
+            let read = contents.read(&mut chunk).map_err(io_error_into_wasi_err)?;
+            if read == 0 {
+                break;
+            }
+            let data = chunk[..read].to_vec();
+            file = block_on(tasks, async move {
+                file.write_all(&data).await?;
+                Ok::<_, io::Error>(file)
+            })
+            .map_err(io_error_into_wasi_err)?;
+            job.bytes.fetch_add(read as u64, Ordering::Relaxed);
+        }
+        file = block_on(tasks, async move {
+            file.flush().await?;
+            Ok::<_, io::Error>(file)
+        })
+        .map_err(io_error_into_wasi_err)?;
+
+        if let Some(mtime) = mtime {
+            let mtime = mtime.saturating_mul(1_000_000_000);
+            file.set_times(None, Some(mtime))
+                .map_err(fs_error_into_wasi_err)?;
+        }
+        Ok(())
+    }
+
+    fn symlink(
+        fs: &WasiFs,
+        inodes: &WasiInodes,
+        dest_fd: WasiFd,
+        path: &Path,
+        target: &Path,
+    ) -> Result<(), Errno> {
+        let (parent, name) =
+            fs.get_parent_inode_at_path(inodes, dest_fd, path, true, FsAccess::CREATE)?;
+        match parent.read().deref() {
+            Kind::Dir { entries, .. } if entries.contains_key(&name) => return Err(Errno::Exist),
+            Kind::Dir { .. } => {}
+            _ => return Err(Errno::Notdir),
+        }
+
+        let kind = Kind::Symlink {
+            base_po_dir: dest_fd,
+            path_to_symlink: path.to_path_buf(),
+            relative_path: target.to_path_buf(),
+        };
+        let inode = fs.create_inode_with_default_stat(inodes, kind, false, name.clone().into());
+        if let Kind::Dir { entries, .. } = parent.write().deref_mut() {
+            entries.insert(name, inode);
+        }
+        Ok(())
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn entries_stay_inside_the_destination() {
+            assert_eq!(
+                entry_path(Path::new("./pkg/lib/../x")),
+                Err(Errno::Notcapable)
+            );
+            assert_eq!(entry_path(Path::new("/etc/passwd")), Err(Errno::Notcapable));
+            assert_eq!(
+                entry_path(Path::new("./pkg/./lib")).unwrap(),
+                Path::new("pkg/lib")
+            );
+        }
+
+        #[test]
+        fn symlinks_stay_inside_the_destination() {
+            assert!(stays_inside(Path::new("pkg/current"), Path::new("lib")));
+            assert!(stays_inside(Path::new("pkg/current"), Path::new("../pkg")));
+            assert!(stays_inside(Path::new("a/b/c"), Path::new("../../x/./y")));
+            assert!(!stays_inside(Path::new("pkg/current"), Path::new("../..")));
+            assert!(!stays_inside(Path::new("link"), Path::new("..")));
+            assert!(!stays_inside(Path::new("link"), Path::new("/etc")));
+            assert!(!stays_inside(Path::new("a/link"), Path::new("b/../../..")));
+        }
+    }
+}
@@ -0,0 +1,193 @@
+//! Files that hold the result of a future running on the host, which is how
+//! a host future is handed to the guest as an fd.
+
+use std::{
+    io::{self, SeekFrom},
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll, Waker},
+};
+
+use bytes::Bytes;
+use futures::future::AbortHandle;
+use tokio::io::{AsyncRead, AsyncSeek, AsyncWrite, ReadBuf};
+use virtual_fs::{FsError, VirtualFile};
+
+#[derive(Debug, Default)]
+enum Outcome {
+    #[default]
+    Pending,
+    Done(Bytes),
+    Panicked,
+}
+
+#[derive(Debug, Default)]
+struct HostFutureState {
+    outcome: Outcome,
+    wakers: Vec<Waker>,
+}
+
+/// The side of a [`HostFutureFile`] that the task running the future
+/// completes it through.
+#[derive(Debug, Clone)]
+pub(crate) struct HostFutureCompletion {
+    state: Arc<Mutex<HostFutureState>>,
+}
+
+impl HostFutureCompletion {
+    pub(crate) fn complete(&self, result: Bytes) {
+        self.finish(Outcome::Done(result));
+    }
+
+    pub(crate) fn panicked(&self) {
+        self.finish(Outcome::Panicked);
+    }
+
+    fn finish(&self, outcome: Outcome) {
+        let mut state = self.state.lock().unwrap();
+        state.outcome = outcome;
+        for waker in state.wakers.drain(..) {
+            waker.wake();
+        }
+    }
+}
+
+/// A read-only file that is empty and never ready until the host future
+/// behind it completes, after which it holds the bytes the future returned.
+///
+/// Dropping the file (i.e. closing the last fd to it) aborts the future.
+#[derive(Debug)]
+pub(crate) struct HostFutureFile {
+    state: Arc<Mutex<HostFutureState>>,
+    abort: AbortHandle,
+    pos: u64,
+}
+
+impl HostFutureFile {
+    pub(crate) fn new(abort: AbortHandle) -> (Self, HostFutureCompletion) {
+        let state = Arc::new(Mutex::new(HostFutureState::default()));
+        let file = HostFutureFile {
+            state: state.clone(),
+            abort,
+            pos: 0,
+        };
+        (file, HostFutureCompletion { state })
+    }
+
+    /// Polls until the future has completed and returns its result.
+    fn poll_result(&self, cx: &mut Context<'_>) -> Poll<io::Result<Bytes>> {
+        let mut guard = self.state.lock().unwrap();
+        let state = &mut *guard;
+        match &state.outcome {
+            Outcome::Done(result) => Poll::Ready(Ok(result.clone())),
+            Outcome::Panicked => Poll::Ready(Err(io::Error::other("the host future panicked"))),
+            Outcome::Pending => {
+                if !state.wakers.iter().any(|w| w.will_wake(cx.waker())) {
+                    state.wakers.push(cx.waker().clone());
+                }
+                Poll::Pending
+            }
+        }
+    }
+
+    fn remaining(&self, result: &Bytes) -> usize {
+        (result.len() as u64).saturating_sub(self.pos) as usize
+    }
+}
+
+impl Drop for HostFutureFile {
+    fn drop(&mut self) {
+        self.abort.abort();
+    }
+}
+
+impl AsyncRead for HostFutureFile {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let result = match self.poll_result(cx) {
+            Poll::Ready(Ok(result)) => result,
+            Poll::Ready(Err(err)) => return Poll::Ready(Err(err)),
+            Poll::Pending => return Poll::Pending,
+        };
+        let amt = self.remaining(&result).min(buf.remaining());
+        let start = result.len() - self.remaining(&result);
+        buf.put_slice(&result[start..start + amt]);
+        self.pos += amt as u64;
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl AsyncWrite for HostFutureFile {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        _buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Poll::Ready(Err(io::ErrorKind::PermissionDenied.into()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl AsyncSeek for HostFutureFile {
+    fn start_seek(mut self: Pin<&mut Self>, position: SeekFrom) -> io::Result<()> {
+        let pos = match position {
+            SeekFrom::Start(pos) => Some(pos),
+            SeekFrom::End(offset) => self.size().checked_add_signed(offset),
+            SeekFrom::Current(offset) => self.pos.checked_add_signed(offset),
+        };
+        self.pos = pos.ok_or_else(|| io::Error::from(io::ErrorKind::InvalidInput))?;
+        Ok(())
+    }
+
+    fn poll_complete(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<u64>> {
+        Poll::Ready(Ok(self.pos))
+    }
+}
+
+impl VirtualFile for HostFutureFile {
+    fn last_accessed(&self) -> u64 {
+        0
+    }
+
+    fn last_modified(&self) -> u64 {
+        0
+    }
+
+    fn created_time(&self) -> u64 {
+        0
+    }
+
+    fn size(&self) -> u64 {
+        match &self.state.lock().unwrap().outcome {
+            Outcome::Done(result) => result.len() as u64,
+            _ => 0,
+        }
+    }
+
+    fn set_len(&mut self, _new_size: u64) -> virtual_fs::Result<()> {
+        Err(FsError::PermissionDenied)
+    }
+
+    fn unlink(&mut self) -> virtual_fs::Result<()> {
+        Ok(())
+    }
+
+    fn poll_read_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<usize>> {
+        self.poll_result(cx)
+            .map_ok(|result| self.remaining(&result))
+    }
+
+    fn poll_write_ready(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<usize>> {
+        Poll::Ready(Err(io::ErrorKind::PermissionDenied.into()))
+    }
+}
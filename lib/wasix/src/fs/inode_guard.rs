@@ -19,7 +19,7 @@ use super::{notification::NotificationInner, InodeGuard, Kind};
 use crate::{
     net::socket::{InodeSocketInner, InodeSocketKind},
     state::{iterate_poll_events, PollEvent, PollEventSet, WasiState},
-    syscalls::{map_io_err, EventResult, EventResultType},
+    syscalls::{io_error_into_wasi_err, EventResult, EventResultType},
     utils::{OwnedRwLockReadGuard, OwnedRwLockWriteGuard},
 };
 
@@ -247,7 +247,7 @@ impl Future for InodeValFilePollGuardJoin {
                     let bytes_available = match bytes_available {
                         Ok(a) => a,
                         Err(e) => {
-                            error = map_io_err(e);
+                            error = io_error_into_wasi_err(e);
                             0
                         }
                     };
@@ -286,6 +286,7 @@ impl Future for InodeValFilePollGuardJoin {
             };
         }
         if has_write {
+            let mut has_pending_error = false;
             let poll_result = match &mut self.mode {
                 InodeValFilePollGuardMode::File(file) => {
                     let mut guard = file.write().unwrap();
@@ -295,7 +296,9 @@ impl Future for InodeValFilePollGuardJoin {
                 InodeValFilePollGuardMode::EventNotifications(inner) => inner.poll(waker).map(Ok),
                 InodeValFilePollGuardMode::Socket { ref inner } => {
                     let mut guard = inner.protected.write().unwrap();
-                    guard.poll_write_ready(cx)
+                    let ret = guard.poll_write_ready(cx);
+                    has_pending_error = guard.has_pending_error();
+                    ret
                 }
                 InodeValFilePollGuardMode::PipeRx { .. } => Poll::Ready(Err(std::io::Error::new(
                     std::io::ErrorKind::InvalidInput,
@@ -304,7 +307,7 @@ impl Future for InodeValFilePollGuardJoin {
                 InodeValFilePollGuardMode::PipeTx { tx } => {
                     let mut guard = tx.write().unwrap();
                     let tx = Pin::new(guard.as_mut());
-                    tx.poll_write_ready()
+                    tx.poll_write_ready(cx)
                 }
                 InodeValFilePollGuardMode::DuplexPipe { pipe } => {
                     let mut guard = pipe.write().unwrap();
@@ -346,7 +349,7 @@ impl Future for InodeValFilePollGuardJoin {
                     let bytes_available = match bytes_available {
                         Ok(a) => a,
                         Err(e) => {
-                            error = map_io_err(e);
+                            error = io_error_into_wasi_err(e);
                             0
                         }
                     };
@@ -372,7 +375,11 @@ impl Future for InodeValFilePollGuardJoin {
                                 type_: self.subscription.type_,
                                 inner,
                             },
-                            if error == Errno::Success {
+                            if error == Errno::Success && has_pending_error {
+                                // e.g. a failed nonblocking connect, which
+                                // the guest finds out about via `SO_ERROR`
+                                EpollType::EPOLLOUT | EpollType::EPOLLERR
+                            } else if error == Errno::Success {
                                 EpollType::EPOLLOUT
                             } else {
                                 EpollType::EPOLLERR
@@ -404,22 +411,6 @@ impl InodeValFileReadGuard {
     }
 }
 
-impl InodeValFileReadGuard {
-    pub fn into_poll_guard(
-        self,
-        fd: u32,
-        peb: PollEventSet,
-        subscription: Subscription,
-    ) -> InodeValFilePollGuard {
-        InodeValFilePollGuard {
-            fd,
-            peb,
-            subscription,
-            mode: InodeValFilePollGuardMode::File(self.guard.into_inner()),
-        }
-    }
-}
-
 impl Deref for InodeValFileReadGuard {
     type Target = dyn VirtualFile + Send + Sync + 'static;
     fn deref(&self) -> &Self::Target {
@@ -478,21 +469,18 @@ impl WasiStateFileGuard {
 
     pub fn lock_read(&self) -> Option<InodeValFileReadGuard> {
         let guard = self.inode.read();
-        if let Kind::File { handle, .. } = guard.deref() {
-            handle.as_ref().map(InodeValFileReadGuard::new)
-        } else {
-            // Our public API should ensure that this is not possible
-            unreachable!("Non-file found in standard device location")
+        match guard.deref() {
+            Kind::File { handle, .. } => handle.as_ref().map(InodeValFileReadGuard::new),
+            // The process renumbered something else (e.g. a pipe) onto it
+            _ => None,
         }
     }
 
     pub fn lock_write(&self) -> Option<InodeValFileWriteGuard> {
         let guard = self.inode.read();
-        if let Kind::File { handle, .. } = guard.deref() {
-            handle.as_ref().map(InodeValFileWriteGuard::new)
-        } else {
-            // Our public API should ensure that this is not possible
-            unreachable!("Non-file found in standard device location")
+        match guard.deref() {
+            Kind::File { handle, .. } => handle.as_ref().map(InodeValFileWriteGuard::new),
+            _ => None,
         }
     }
 }
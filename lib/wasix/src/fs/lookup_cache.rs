@@ -0,0 +1,101 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Weak},
+};
+
+use super::{InodeGuard, InodeVal, InodeWeakGuard};
+
+/// How many `(directory, name)` lookups are remembered before the least
+/// recently used half is evicted.
+const CAPACITY: usize = 4096;
+
+/// Counters for how a process resolved its paths, see
+/// [`WasiEnv::path_lookup_stats()`](crate::WasiEnv::path_lookup_stats).
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct PathLookupStats {
+    /// Path components that were found in the lookup cache.
+    pub hits: u64,
+    /// Path components that had to be looked up in their directory.
+    pub misses: u64,
+    /// Paths that couldn't be resolved from loaded directory entries alone
+    /// (symlinks, `..`, entries that haven't been loaded yet, ...) and went
+    /// through the slow path instead.
+    pub fallbacks: u64,
+    /// Times the cached entries of a directory were dropped because the
+    /// directory changed.
+    pub invalidations: u64,
+}
+
+/// A small LRU of the files and directories found under a directory by name.
+///
+/// Entries are keyed on the identity of the directory's [`InodeVal`] rather
+/// than its [`Inode`](super::Inode) number, since the latter is derived from
+/// a path and gets reused when something else is created there. Holding a
+/// [`Weak`] to the directory keeps its address from being reused while the
+/// entry exists.
+#[derive(Debug, Default)]
+pub(crate) struct LookupCache {
+    entries: HashMap<(usize, String), CachedEntry>,
+    tick: u64,
+    /// Bumped whenever entries are invalidated.
+    pub generation: u64,
+    pub stats: PathLookupStats,
+    pub disabled: bool,
+}
+
+#[derive(Debug)]
+struct CachedEntry {
+    _dir: Weak<InodeVal>,
+    inode: InodeWeakGuard,
+    last_used: u64,
+}
+
+impl LookupCache {
+    fn key(dir: &InodeGuard, name: &str) -> (usize, String) {
+        (Arc::as_ptr(&dir.inner) as usize, name.to_string())
+    }
+
+    pub fn get(&mut self, dir: &InodeGuard, name: &str) -> Option<InodeGuard> {
+        self.tick += 1;
+        let entry = self.entries.get_mut(&Self::key(dir, name))?;
+        entry.last_used = self.tick;
+        entry.inode.upgrade()
+    }
+
+    /// Remembers that `name` in `dir` is `inode`, which must be a file or a
+    /// directory.
+    pub fn insert(&mut self, dir: &InodeGuard, name: &str, inode: &InodeGuard) {
+        if self.entries.len() >= CAPACITY {
+            self.evict();
+        }
+        self.tick += 1;
+        self.entries.insert(
+            Self::key(dir, name),
+            CachedEntry {
+                _dir: Arc::downgrade(&dir.inner),
+                inode: inode.downgrade(),
+                last_used: self.tick,
+            },
+        );
+    }
+
+    /// Forgets everything that was found in `dir`.
+    pub fn invalidate(&mut self, dir: &InodeGuard) {
+        let dir = Arc::as_ptr(&dir.inner) as usize;
+        let len = self.entries.len();
+        self.entries.retain(|(parent, _), _| *parent != dir);
+        self.generation += 1;
+        if self.entries.len() != len {
+            self.stats.invalidations += 1;
+        }
+    }
+
+    /// Drops the least recently used half of the entries.
+    fn evict(&mut self) {
+        let mut ticks: Vec<u64> = self.entries.values().map(|e| e.last_used).collect();
+        let middle = ticks.len() / 2;
+        let (_, cutoff, _) = ticks.select_nth_unstable(middle);
+        let cutoff = *cutoff;
+        self.entries.retain(|_, e| e.last_used > cutoff);
+    }
+}
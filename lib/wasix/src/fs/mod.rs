@@ -5,9 +5,12 @@
 // through its repective FileOpener and giving it a path as input.
 // TODO: refactor away the InodeVal type
 
+mod archive;
 mod fd;
 mod fd_list;
+mod host_future;
 mod inode_guard;
+mod lookup_cache;
 mod notification;
 
 use std::{
@@ -24,7 +27,9 @@ use std::{
 };
 
 use self::fd_list::FdList;
+use self::lookup_cache::LookupCache;
 use crate::{
+    capabilities::{FsAccess, PathRule},
     net::socket::InodeSocketKind,
     state::{Stderr, Stdin, Stdout},
 };
@@ -33,24 +38,34 @@ use futures::{future::BoxFuture, Future, TryStreamExt};
 use serde_derive::{Deserialize, Serialize};
 use tokio::io::AsyncWriteExt;
 use tracing::{debug, trace};
-use virtual_fs::{copy_reference, FileSystem, FsError, OpenOptions, VirtualFile};
+use virtual_fs::{
+    copy_reference, FileSystem, FsError, NullFile, OpenOptions, SharedMemoryFileSystem, VirtualFile,
+};
 use wasmer_config::package::PackageId;
 use wasmer_wasix_types::{
-    types::{__WASI_STDERR_FILENO, __WASI_STDIN_FILENO, __WASI_STDOUT_FILENO},
+    types::{
+        EVENT_FD_FLAGS_SEMAPHORE, __WASI_STDERR_FILENO, __WASI_STDIN_FILENO, __WASI_STDOUT_FILENO,
+    },
     wasi::{
-        Errno, Fd as WasiFd, Fdflags, Fdflagsext, Fdstat, Filesize, Filestat, Filetype,
-        Preopentype, Prestat, PrestatEnum, Rights, Socktype,
+        Errno, EventFdFlags, Fd as WasiFd, Fdflags, Fdflagsext, Fdstat, Filesize, Filestat,
+        Filetype, Preopentype, Prestat, PrestatEnum, Rights, Socktype,
     },
 };
 
+pub(crate) use self::archive::ArchiveJobs;
+#[cfg(feature = "host-archive")]
+pub(crate) use self::archive::{extract_archive, ArchiveSource};
 pub use self::fd::{EpollFd, EpollInterest, EpollJoinGuard, Fd, FdInner, InodeVal, Kind};
+pub(crate) use self::host_future::HostFutureFile;
 pub(crate) use self::inode_guard::{
     InodeValFilePollGuard, InodeValFilePollGuardJoin, InodeValFilePollGuardMode,
     InodeValFileReadGuard, InodeValFileWriteGuard, WasiStateFileGuard, POLL_GUARD_MAX_RET,
 };
-pub use self::notification::NotificationInner;
-use crate::syscalls::map_io_err;
-use crate::{bin_factory::BinaryPackage, state::PreopenedDir, ALL_RIGHTS};
+pub use self::lookup_cache::PathLookupStats;
+pub use self::notification::{EventFdHandle, NotificationInner};
+use crate::errno::io_error_into_wasi_err;
+pub use crate::errno::{fs_error_from_wasi_err, fs_error_into_wasi_err};
+use crate::{bin_factory::BinaryPackage, os::cpu::CpuInfo, state::PreopenedDir, ALL_RIGHTS};
 
 /// the fd value of the virtual root
 ///
@@ -280,19 +295,11 @@ impl WasiInodes {
         }
     }
 
-    /// Get the `VirtualFile` object at stdout
-    pub(crate) fn stdout(fd_map: &RwLock<FdList>) -> Result<InodeValFileReadGuard, FsError> {
-        Self::std_dev_get(fd_map, __WASI_STDOUT_FILENO)
-    }
     /// Get the `VirtualFile` object at stdout mutably
     pub(crate) fn stdout_mut(fd_map: &RwLock<FdList>) -> Result<InodeValFileWriteGuard, FsError> {
         Self::std_dev_get_mut(fd_map, __WASI_STDOUT_FILENO)
     }
 
-    /// Get the `VirtualFile` object at stderr
-    pub(crate) fn stderr(fd_map: &RwLock<FdList>) -> Result<InodeValFileReadGuard, FsError> {
-        Self::std_dev_get(fd_map, __WASI_STDERR_FILENO)
-    }
     /// Get the `VirtualFile` object at stderr mutably
     pub(crate) fn stderr_mut(fd_map: &RwLock<FdList>) -> Result<InodeValFileWriteGuard, FsError> {
         Self::std_dev_get_mut(fd_map, __WASI_STDERR_FILENO)
@@ -321,7 +328,7 @@ impl WasiInodes {
             {
                 Ok(InodeValFileReadGuard::new(handle))
             } else {
-                // Our public API should ensure that this is not possible
+                // The process renumbered something else (e.g. a pipe) onto it
                 Err(FsError::NotAFile)
             }
         } else {
@@ -344,7 +351,7 @@ impl WasiInodes {
             {
                 Ok(InodeValFileWriteGuard::new(handle))
             } else {
-                // Our public API should ensure that this is not possible
+                // The process renumbered something else (e.g. a pipe) onto it
                 Err(FsError::NotAFile)
             }
         } else {
@@ -458,6 +465,21 @@ impl FileSystem for WasiFsRoot {
             WasiFsRoot::Backing(f) => f.mount(name, path, fs),
         }
     }
+    fn create_tmpfile(
+        &self,
+        path: &Path,
+    ) -> virtual_fs::Result<Box<dyn VirtualFile + Send + Sync + 'static>> {
+        match self {
+            WasiFsRoot::Sandbox(fs) => fs.create_tmpfile(path),
+            WasiFsRoot::Backing(fs) => fs.create_tmpfile(path),
+        }
+    }
+    fn link_tmpfile(&self, file: &dyn VirtualFile, path: &Path) -> virtual_fs::Result<()> {
+        match self {
+            WasiFsRoot::Sandbox(fs) => fs.link_tmpfile(file, path),
+            WasiFsRoot::Backing(fs) => fs.link_tmpfile(file, path),
+        }
+    }
 }
 
 /// Merge the contents of one filesystem into another.
@@ -544,6 +566,27 @@ pub struct WasiFs {
     pub(crate) init_preopens: Vec<PreopenedDir>,
     // The virtual file system preopens when this was initialized
     pub(crate) init_vfs_preopens: Vec<String>,
+
+    // The paths the guest may access, or `None` if it may access any path
+    #[cfg_attr(feature = "enable-serde", serde(skip, default))]
+    path_rules: Option<Arc<Vec<PathRule>>>,
+
+    // Directory entries that path resolution has walked through, shared
+    // with forks since they share the inodes
+    #[cfg_attr(feature = "enable-serde", serde(skip, default))]
+    lookup_cache: Arc<Mutex<LookupCache>>,
+
+    // Files that only this process (and its forks) can see, by absolute
+    // path, which shadow whatever the root file system has there
+    #[cfg_attr(feature = "enable-serde", serde(skip, default))]
+    process_files: RwLock<HashMap<PathBuf, ProcessFile>>,
+}
+
+/// A file set with [`WasiFs::set_process_file()`].
+#[derive(Debug, Clone)]
+struct ProcessFile {
+    inode: InodeGuard,
+    writable: bool,
 }
 
 impl WasiFs {
@@ -557,6 +600,207 @@ impl WasiFs {
         self.is_wasix.store(is_wasix, Ordering::SeqCst);
     }
 
+    /// Mounts the shared memory objects of the control plane at `/dev/shm`,
+    /// unless the root file system has no `/dev` or brings its own `/dev/shm`.
+    pub(crate) fn mount_dev_shm(&self, shm: &SharedMemoryFileSystem) {
+        if !matches!(self.root_fs.metadata(Path::new("/dev")), Ok(m) if m.is_dir())
+            || self.root_fs.metadata(Path::new("/dev/shm")).is_ok()
+        {
+            return;
+        }
+
+        if let Err(err) = self.root_fs.mount(
+            "shm".to_string(),
+            Path::new("/dev/shm"),
+            Box::new(shm.clone()),
+        ) {
+            debug!("failed to mount [/dev/shm] - {}", err);
+        }
+    }
+
+    /// Mounts a `/proc` containing [`CpuInfo::PROC_PATH`], under the same
+    /// conditions as [`WasiFs::mount_dev_shm()`].
+    pub(crate) fn mount_proc(&self, cpu: &CpuInfo) {
+        if !matches!(self.root_fs.metadata(Path::new("/dev")), Ok(m) if m.is_dir())
+            || self.root_fs.metadata(Path::new("/proc")).is_ok()
+        {
+            return;
+        }
+
+        let proc_fs = virtual_fs::mem_fs::FileSystem::default();
+        let result = proc_fs
+            .new_open_options_ext()
+            .insert_ro_file(
+                Path::new("/cpuinfo"),
+                cpu.to_proc_cpuinfo().into_bytes().into(),
+            )
+            .and_then(|_| {
+                self.root_fs
+                    .mount("proc".to_string(), Path::new("/proc"), Box::new(proc_fs))
+            });
+        if let Err(err) = result {
+            debug!("failed to mount [/proc] - {}", err);
+        }
+    }
+
+    /// Only lets the guest access the paths matched by `rules`.
+    pub(crate) fn restrict_paths(&mut self, rules: Vec<PathRule>) {
+        let rules = rules
+            .into_iter()
+            .map(|rule| PathRule {
+                prefix: normalize_path(Path::new("/"), &rule.prefix),
+                ..rule
+            })
+            .collect();
+        self.path_rules = Some(Arc::new(rules));
+    }
+
+    /// Rules granting access to the directories that were explicitly
+    /// preopened, with the permissions they were preopened with. The root
+    /// isn't included, since preopening it is how the whole file system is
+    /// made available.
+    pub(crate) fn preopen_path_rules(&self) -> Vec<PathRule> {
+        let is_root = |path: &Path| normalize_path(Path::new("/"), path) == Path::new("/");
+        let preopens = self
+            .init_preopens
+            .iter()
+            .filter(|preopen| !is_root(&preopen.path))
+            .map(|preopen| {
+                let access = FsAccess {
+                    read: preopen.read,
+                    write: preopen.write,
+                    create: preopen.create,
+                };
+                PathRule::new(preopen.path.clone(), access)
+            });
+        let vfs_preopens = self
+            .init_vfs_preopens
+            .iter()
+            .filter(|preopen| !is_root(Path::new(preopen)))
+            .map(PathRule::read_only);
+        preopens.chain(vfs_preopens).collect()
+    }
+
+    /// Checks that the guest may access `path`, relative to the directory
+    /// `base` refers to, in the given way.
+    pub(crate) fn check_path_access(
+        &self,
+        base: WasiFd,
+        path: &Path,
+        access: FsAccess,
+    ) -> Result<(), Errno> {
+        if self.path_rules.is_none() {
+            return Ok(());
+        }
+
+        let base_inode = self.get_fd_inode(base)?;
+        let base_path = match base_inode.read().deref() {
+            Kind::Dir { path, .. } => path.clone(),
+            _ => PathBuf::from("/"),
+        };
+        self.check_absolute_path_access(&normalize_path(&base_path, path), access)
+    }
+
+    /// Makes `file` appear at the absolute `path` for this process and the
+    /// processes it forks, without touching the root file system (which
+    /// other processes may share).
+    ///
+    /// The file can be opened and stat'ed, but it isn't listed in its
+    /// parent directory. Unless it is `writable`, opening it for writing
+    /// fails with `EROFS`.
+    pub(crate) fn set_process_file(
+        &self,
+        inodes: &WasiInodes,
+        path: &Path,
+        file: Box<dyn VirtualFile + Send + Sync>,
+        writable: bool,
+    ) {
+        let path = normalize_path(Path::new("/"), path);
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let kind = Kind::File {
+            handle: Some(Arc::new(RwLock::new(file))),
+            path: path.clone(),
+            fd: None,
+        };
+        let stat = self
+            .get_stat_for_kind(&kind)
+            .expect("files with a handle always have a stat");
+        let inode = self.create_inode_with_stat(inodes, kind, false, name.into(), stat);
+        self.process_files
+            .write()
+            .unwrap()
+            .insert(path, ProcessFile { inode, writable });
+    }
+
+    /// Removes the file set with [`WasiFs::set_process_file()`] at `path`.
+    pub(crate) fn remove_process_file(&self, path: &Path) {
+        let path = normalize_path(Path::new("/"), path);
+        self.process_files.write().unwrap().remove(&path);
+    }
+
+    /// The file set with [`WasiFs::set_process_file()`] at `path`, relative
+    /// to the directory `base` refers to, if there is one.
+    fn get_process_file(&self, base: WasiFd, path: &Path) -> Result<Option<ProcessFile>, Errno> {
+        let files = self.process_files.read().unwrap();
+        if files.is_empty() {
+            return Ok(None);
+        }
+
+        let base_inode = self.get_fd_inode(base)?;
+        let base_path = match base_inode.read().deref() {
+            Kind::Dir { path, .. } => path.clone(),
+            _ => PathBuf::from("/"),
+        };
+        Ok(files.get(&normalize_path(&base_path, path)).cloned())
+    }
+
+    /// Whether `inode` is one of the files set with
+    /// [`WasiFs::set_process_file()`], which are already open.
+    pub(crate) fn is_process_file(&self, inode: &InodeGuard) -> bool {
+        self.process_files
+            .read()
+            .unwrap()
+            .values()
+            .any(|file| file.inode.ino() == inode.ino())
+    }
+
+    /// Checks that the guest may access the file or directory `inode` refers
+    /// to, which catches symlinks pointing outside of the allowed paths.
+    fn check_inode_access(&self, inode: &InodeGuard, access: FsAccess) -> Result<(), Errno> {
+        if self.path_rules.is_none() {
+            return Ok(());
+        }
+
+        match inode.read().deref() {
+            Kind::File { path, .. } | Kind::Dir { path, .. } => {
+                self.check_absolute_path_access(&normalize_path(Path::new("/"), path), access)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn check_absolute_path_access(&self, path: &Path, access: FsAccess) -> Result<(), Errno> {
+        let Some(rules) = &self.path_rules else {
+            return Ok(());
+        };
+
+        let granted = rules
+            .iter()
+            .filter(|rule| rule.matches(path))
+            .max_by_key(|rule| rule.prefix.components().count())
+            .map(|rule| rule.access)
+            .unwrap_or_default();
+        if granted.allows(access) {
+            Ok(())
+        } else {
+            trace!(path = %path.display(), ?access, "path access denied");
+            Err(Errno::Notcapable)
+        }
+    }
+
     /// Forking the WasiState is used when either fork or vfork is called
     pub fn fork(&self) -> Self {
         Self {
@@ -569,6 +813,9 @@ impl WasiFs {
             has_unioned: Mutex::new(self.has_unioned.lock().unwrap().clone()),
             init_preopens: self.init_preopens.clone(),
             init_vfs_preopens: self.init_vfs_preopens.clone(),
+            path_rules: self.path_rules.clone(),
+            lookup_cache: self.lookup_cache.clone(),
+            process_files: RwLock::new(self.process_files.read().unwrap().clone()),
         }
     }
 
@@ -709,6 +956,9 @@ impl WasiFs {
             has_unioned: Mutex::new(HashSet::new()),
             init_preopens: Default::default(),
             init_vfs_preopens: Default::default(),
+            path_rules: None,
+            lookup_cache: Default::default(),
+            process_files: Default::default(),
         };
         wasi_fs.create_stdin(inodes);
         wasi_fs.create_stdout(inodes);
@@ -1017,8 +1267,8 @@ impl WasiFs {
         mut symlink_count: u32,
         follow_symlinks: bool,
     ) -> Result<InodeGuard, Errno> {
-        if symlink_count > MAX_SYMLINKS {
-            return Err(Errno::Mlink);
+        if symlink_count >= MAX_SYMLINKS {
+            return Err(Errno::Loop);
         }
 
         let path: &Path = Path::new(path_str);
@@ -1037,7 +1287,10 @@ impl WasiFs {
             let last_component = i + 1 == n_components;
             // for each component traverse file structure
             // loading inodes as necessary
-            'symlink_resolution: while symlink_count < MAX_SYMLINKS {
+            'symlink_resolution: loop {
+                if symlink_count >= MAX_SYMLINKS {
+                    return Err(Errno::Loop);
+                }
                 let processing_cur_inode = cur_inode.clone();
                 let mut guard = processing_cur_inode.write();
                 match guard.deref_mut() {
@@ -1258,11 +1511,14 @@ impl WasiFs {
                         };
                         debug!("Following symlink recursively");
                         drop(guard);
+                        // Counted here too, as the target may itself be a
+                        // symlink that leads back here
+                        symlink_count += 1;
                         let symlink_inode = self.get_inode_at_path_inner(
                             inodes,
                             new_base_inode,
                             &new_path,
-                            symlink_count + 1,
+                            symlink_count,
                             follow_symlinks,
                         )?;
                         cur_inode = symlink_inode;
@@ -1285,6 +1541,85 @@ impl WasiFs {
         Ok(cur_inode)
     }
 
+    /// Resolves `path` relative to `base` using only the directory entries
+    /// that have already been loaded. Components found in the lookup cache
+    /// are resolved under its lock alone, the others take a read lock on
+    /// their directory (with the cache unlocked).
+    ///
+    /// Returns `None` when the path needs anything else (symlinks, `..`,
+    /// the root, or entries that still have to be read from the root file
+    /// system, e.g. across a mount), in which case the caller should fall
+    /// back to [`WasiFs::get_inode_at_path_inner()`].
+    fn get_inode_at_path_fast(&self, base: &InodeGuard, path: &str) -> Option<InodeGuard> {
+        let mut cache = self.lookup_cache.lock().unwrap();
+        if cache.disabled {
+            return None;
+        }
+
+        let mut cur_inode = base.clone();
+        for component in Path::new(path).components() {
+            let name = match component {
+                Component::RootDir | Component::CurDir => continue,
+                Component::Normal(name) => name.to_str(),
+                Component::ParentDir | Component::Prefix(_) => None,
+            };
+            let Some(name) = name else {
+                cache.stats.fallbacks += 1;
+                return None;
+            };
+
+            if let Some(inode) = cache.get(&cur_inode, name) {
+                cache.stats.hits += 1;
+                cur_inode = inode;
+                continue;
+            }
+
+            // Paths get resolved while holding inode locks, so waiting for
+            // one with the cache locked could deadlock
+            let generation = cache.generation;
+            drop(cache);
+            let entry = match cur_inode.read().deref() {
+                Kind::Dir { entries, .. } => entries.get(name).cloned(),
+                _ => None,
+            };
+            let entry =
+                entry.filter(|inode| matches!(*inode.read(), Kind::Dir { .. } | Kind::File { .. }));
+            cache = self.lookup_cache.lock().unwrap();
+
+            let Some(inode) = entry else {
+                cache.stats.fallbacks += 1;
+                return None;
+            };
+            cache.stats.misses += 1;
+            // Don't cache what a concurrent change may have made stale
+            if cache.generation == generation {
+                cache.insert(&cur_inode, name, &inode);
+            }
+            cur_inode = inode;
+        }
+
+        Some(cur_inode)
+    }
+
+    /// Drops the cached lookups of the entries in `dir`, which must be
+    /// called after removing or replacing any of them (adding entries under
+    /// new names can't make a cached lookup wrong).
+    pub(crate) fn invalidate_lookups(&self, dir: &InodeGuard) {
+        self.lookup_cache.lock().unwrap().invalidate(dir);
+    }
+
+    /// Counters for how paths have been resolved.
+    pub fn path_lookup_stats(&self) -> PathLookupStats {
+        self.lookup_cache.lock().unwrap().stats
+    }
+
+    /// Enables or disables resolving paths from already loaded directory
+    /// entries, which is only useful to measure how much it helps.
+    #[doc(hidden)]
+    pub fn set_path_lookup_cache(&self, enabled: bool) {
+        self.lookup_cache.lock().unwrap().disabled = !enabled;
+    }
+
     /// Finds the preopened directory that is the "best match" for the given path and
     /// returns a path relative to this preopened directory.
     ///
@@ -1369,11 +1704,11 @@ impl WasiFs {
             let guard = processing_cur_inode.read();
 
             match guard.deref() {
-                Kind::Dir { parent, .. } => {
-                    if let Some(p) = parent.upgrade() {
-                        cur_inode = p;
-                    }
-                }
+                Kind::Dir { parent, .. } => match parent.upgrade() {
+                    Some(p) => cur_inode = p,
+                    // Not under the fd at all
+                    None => return Err(Errno::Inval),
+                },
                 _ => return Err(Errno::Inval),
             }
         }
@@ -1393,20 +1728,37 @@ impl WasiFs {
         base: WasiFd,
         path: &str,
         follow_symlinks: bool,
+        access: FsAccess,
     ) -> Result<InodeGuard, Errno> {
+        self.check_path_access(base, Path::new(path), access)?;
+        if let Some(file) = self.get_process_file(base, Path::new(path))? {
+            if !file.writable && (access.write || access.create) {
+                return Err(Errno::Rofs);
+            }
+            return Ok(file.inode);
+        }
         let base_inode = self.get_fd_inode(base)?;
-        self.get_inode_at_path_inner(inodes, base_inode, path, 0, follow_symlinks)
+        let inode = match self.get_inode_at_path_fast(&base_inode, path) {
+            Some(inode) => inode,
+            None => self.get_inode_at_path_inner(inodes, base_inode, path, 0, follow_symlinks)?,
+        };
+        self.check_inode_access(&inode, access)?;
+        Ok(inode)
     }
 
     /// Returns the parent Dir or Root that the file at a given path is in and the file name
     /// stripped off
+    ///
+    /// `access` is checked against the file itself rather than its parent.
     pub(crate) fn get_parent_inode_at_path(
         &self,
         inodes: &WasiInodes,
         base: WasiFd,
         path: &Path,
         follow_symlinks: bool,
+        access: FsAccess,
     ) -> Result<(InodeGuard, String), Errno> {
+        self.check_path_access(base, path, access)?;
         let mut parent_dir = std::path::PathBuf::new();
         let mut components = path.components().rev();
         let new_entity_name = components
@@ -1418,8 +1770,29 @@ impl WasiFs {
         for comp in components.rev() {
             parent_dir.push(comp);
         }
-        self.get_inode_at_path(inodes, base, &parent_dir.to_string_lossy(), follow_symlinks)
-            .map(|v| (v, new_entity_name))
+        let base_inode = self.get_fd_inode(base)?;
+        let parent_dir = parent_dir.to_string_lossy();
+        let mut parent_inode = match self.get_inode_at_path_fast(&base_inode, &parent_dir) {
+            Some(inode) => inode,
+            None => {
+                self.get_inode_at_path_inner(inodes, base_inode, &parent_dir, 0, follow_symlinks)?
+            }
+        };
+        // A symlink in the last component of the parent is left unresolved
+        // (only the components after it are resolved against its target)
+        if follow_symlinks && matches!(*parent_inode.read(), Kind::Symlink { .. }) {
+            parent_inode = self.get_inode_at_path_inner(inodes, parent_inode, ".", 0, true)?;
+        }
+        if !matches!(*parent_inode.read(), Kind::Dir { .. } | Kind::Root { .. }) {
+            return Err(Errno::Notdir);
+        }
+        if self.path_rules.is_some() {
+            if let Kind::Dir { path, .. } = parent_inode.read().deref() {
+                let path = normalize_path(Path::new("/"), &path.join(&new_entity_name));
+                self.check_absolute_path_access(&path, access)?;
+            }
+        }
+        Ok((parent_inode, new_entity_name))
     }
 
     pub fn get_fd(&self, fd: WasiFd) -> Result<Fd, Errno> {
@@ -1562,15 +1935,13 @@ impl WasiFs {
     pub async fn flush(&self, fd: WasiFd) -> Result<(), Errno> {
         match fd {
             __WASI_STDIN_FILENO => (),
-            __WASI_STDOUT_FILENO => {
-                let mut file =
-                    WasiInodes::stdout_mut(&self.fd_map).map_err(fs_error_into_wasi_err)?;
-                file.flush().await.map_err(map_io_err)?
-            }
-            __WASI_STDERR_FILENO => {
-                let mut file =
-                    WasiInodes::stderr_mut(&self.fd_map).map_err(fs_error_into_wasi_err)?;
-                file.flush().await.map_err(map_io_err)?
+            __WASI_STDOUT_FILENO | __WASI_STDERR_FILENO => {
+                match WasiInodes::std_dev_get_mut(&self.fd_map, fd) {
+                    Ok(mut file) => file.flush().await.map_err(io_error_into_wasi_err)?,
+                    // Renumbered onto a pipe or a socket, which don't buffer
+                    Err(FsError::NotAFile) => {}
+                    Err(err) => return Err(fs_error_into_wasi_err(err)),
+                }
             }
             _ => {
                 let fd = self.get_fd(fd)?;
@@ -1610,6 +1981,33 @@ impl WasiFs {
         Ok(())
     }
 
+    /// Writes all of `buf` to whatever `fd` currently refers to, for writes
+    /// made by the runtime on behalf of the process (e.g. the messages of
+    /// builtin commands), which have to follow the guest's redirections.
+    #[allow(clippy::await_holding_lock)]
+    pub(crate) async fn write_all(&self, fd: WasiFd, buf: &[u8]) -> Result<(), Errno> {
+        let inode = self.get_fd_inode(fd)?;
+        let file = {
+            let mut guard = inode.write();
+            match guard.deref_mut() {
+                Kind::File {
+                    handle: Some(file), ..
+                } => file.clone(),
+                Kind::PipeTx { tx } => {
+                    return std::io::Write::write_all(tx, buf).map_err(io_error_into_wasi_err)
+                }
+                Kind::DuplexPipe { pipe } => {
+                    return std::io::Write::write_all(pipe, buf).map_err(io_error_into_wasi_err)
+                }
+                Kind::Socket { .. } => return Err(Errno::Notsup),
+                Kind::Dir { .. } | Kind::Root { .. } => return Err(Errno::Isdir),
+                _ => return Err(Errno::Badf),
+            }
+        };
+        let mut file = file.write().unwrap();
+        file.write_all(buf).await.map_err(io_error_into_wasi_err)
+    }
+
     /// Creates an inode and inserts it given a Kind and some extra data
     pub(crate) fn create_inode(
         &self,
@@ -1634,6 +2032,81 @@ impl WasiFs {
         self.create_inode_with_stat(inodes, kind, is_preopened, name, stat)
     }
 
+    /// Creates an event counter with the semantics of an `eventfd` (see
+    /// `fd_event`), at `with_fd` if given.
+    pub(crate) fn create_event_fd(
+        &self,
+        inodes: &WasiInodes,
+        initial_val: u64,
+        flags: EventFdFlags,
+        with_fd: Option<WasiFd>,
+    ) -> Result<(WasiFd, Arc<NotificationInner>), Errno> {
+        let is_semaphore = flags & EVENT_FD_FLAGS_SEMAPHORE != 0;
+        let inner = Arc::new(NotificationInner::new(initial_val, is_semaphore));
+        let kind = Kind::EventNotifications {
+            inner: inner.clone(),
+        };
+
+        let inode = self.create_inode_with_default_stat(inodes, kind, false, "event".into());
+        let rights = Rights::FD_READ
+            | Rights::FD_WRITE
+            | Rights::POLL_FD_READWRITE
+            | Rights::FD_FDSTAT_SET_FLAGS;
+        let fd = if let Some(fd) = with_fd {
+            self.with_fd(
+                rights,
+                rights,
+                Fdflags::empty(),
+                Fdflagsext::empty(),
+                0,
+                inode,
+                fd,
+            )
+            .map(|_| fd)?
+        } else {
+            self.create_fd(
+                rights,
+                rights,
+                Fdflags::empty(),
+                Fdflagsext::empty(),
+                0,
+                inode,
+            )?
+        };
+
+        Ok((fd, inner))
+    }
+
+    /// Opens a read-only fd to the result of a host future.
+    pub(crate) fn create_host_future_fd(
+        &self,
+        inodes: &WasiInodes,
+        file: HostFutureFile,
+    ) -> Result<WasiFd, Errno> {
+        let handle: Box<dyn VirtualFile + Send + Sync> = Box::new(file);
+        let kind = Kind::File {
+            handle: Some(Arc::new(RwLock::new(handle))),
+            path: PathBuf::new(),
+            fd: None,
+        };
+
+        let inode = self.create_inode_with_default_stat(inodes, kind, false, "host-future".into());
+        let rights = Rights::FD_READ
+            | Rights::FD_SEEK
+            | Rights::FD_TELL
+            | Rights::FD_FILESTAT_GET
+            | Rights::POLL_FD_READWRITE
+            | Rights::FD_FDSTAT_SET_FLAGS;
+        self.create_fd(
+            rights,
+            rights,
+            Fdflags::empty(),
+            Fdflagsext::empty(),
+            0,
+            inode,
+        )
+    }
+
     /// Creates an inode with the given filestat and inserts it.
     pub(crate) fn create_inode_with_stat(
         &self,
@@ -1840,6 +2313,26 @@ impl WasiFs {
         );
     }
 
+    /// Replaces stdin, stdout and stderr with files that discard everything
+    /// written to them and are always at EOF, like `/dev/null`.
+    pub(crate) fn detach_stdio(&self, inodes: &WasiInodes) {
+        for (raw_fd, rights, fd_flags) in [
+            (__WASI_STDIN_FILENO, STDIN_DEFAULT_RIGHTS, Fdflags::empty()),
+            (__WASI_STDOUT_FILENO, STDOUT_DEFAULT_RIGHTS, Fdflags::APPEND),
+            (__WASI_STDERR_FILENO, STDERR_DEFAULT_RIGHTS, Fdflags::APPEND),
+        ] {
+            self.create_std_dev_inner(
+                inodes,
+                Box::<NullFile>::default(),
+                "null",
+                raw_fd,
+                rights,
+                fd_flags,
+                Inode::from_path("/dev/null"),
+            );
+        }
+    }
+
     pub(crate) fn create_rootfd(&self) -> Result<(), String> {
         // create virtual root
         let all_rights = ALL_RIGHTS;
@@ -2285,59 +2778,19 @@ pub fn virtual_file_type_to_wasi_file_type(file_type: virtual_fs::FileType) -> F
     }
 }
 
-pub fn fs_error_from_wasi_err(err: Errno) -> FsError {
-    match err {
-        Errno::Badf => FsError::InvalidFd,
-        Errno::Exist => FsError::AlreadyExists,
-        Errno::Io => FsError::IOError,
-        Errno::Addrinuse => FsError::AddressInUse,
-        Errno::Addrnotavail => FsError::AddressNotAvailable,
-        Errno::Pipe => FsError::BrokenPipe,
-        Errno::Connaborted => FsError::ConnectionAborted,
-        Errno::Connrefused => FsError::ConnectionRefused,
-        Errno::Connreset => FsError::ConnectionReset,
-        Errno::Intr => FsError::Interrupted,
-        Errno::Inval => FsError::InvalidInput,
-        Errno::Notconn => FsError::NotConnected,
-        Errno::Nodev => FsError::NoDevice,
-        Errno::Noent => FsError::EntryNotFound,
-        Errno::Perm => FsError::PermissionDenied,
-        Errno::Timedout => FsError::TimedOut,
-        Errno::Proto => FsError::UnexpectedEof,
-        Errno::Again => FsError::WouldBlock,
-        Errno::Nospc => FsError::WriteZero,
-        Errno::Notempty => FsError::DirectoryNotEmpty,
-        _ => FsError::UnknownError,
-    }
-}
-
-pub fn fs_error_into_wasi_err(fs_error: FsError) -> Errno {
-    match fs_error {
-        FsError::AlreadyExists => Errno::Exist,
-        FsError::AddressInUse => Errno::Addrinuse,
-        FsError::AddressNotAvailable => Errno::Addrnotavail,
-        FsError::BaseNotDirectory => Errno::Notdir,
-        FsError::BrokenPipe => Errno::Pipe,
-        FsError::ConnectionAborted => Errno::Connaborted,
-        FsError::ConnectionRefused => Errno::Connrefused,
-        FsError::ConnectionReset => Errno::Connreset,
-        FsError::Interrupted => Errno::Intr,
-        FsError::InvalidData => Errno::Io,
-        FsError::InvalidFd => Errno::Badf,
-        FsError::InvalidInput => Errno::Inval,
-        FsError::IOError => Errno::Io,
-        FsError::NoDevice => Errno::Nodev,
-        FsError::NotAFile => Errno::Inval,
-        FsError::NotConnected => Errno::Notconn,
-        FsError::EntryNotFound => Errno::Noent,
-        FsError::PermissionDenied => Errno::Perm,
-        FsError::TimedOut => Errno::Timedout,
-        FsError::UnexpectedEof => Errno::Proto,
-        FsError::WouldBlock => Errno::Again,
-        FsError::WriteZero => Errno::Nospc,
-        FsError::DirectoryNotEmpty => Errno::Notempty,
-        FsError::StorageFull => Errno::Overflow,
-        FsError::Lock | FsError::UnknownError => Errno::Io,
-        FsError::Unsupported => Errno::Notsup,
+/// Lexically resolves `path` against the directory `base`, the way path
+/// resolution treats `.`, `..` and leading slashes, without following
+/// symlinks.
+fn normalize_path(base: &Path, path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::from("/");
+    for component in base.components().chain(path.components()) {
+        match component {
+            Component::Normal(name) => normalized.push(name),
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            Component::Prefix(_) | Component::RootDir | Component::CurDir => {}
+        }
     }
+    normalized
 }
@@ -1,6 +1,6 @@
 use std::{
     collections::VecDeque,
-    sync::Mutex,
+    sync::{Arc, Mutex},
     task::{Poll, Waker},
 };
 
@@ -42,23 +42,25 @@ impl NotificationState {
     }
 
     fn inc(&mut self, val: u64) {
-        self.counter += val;
+        self.counter = self.counter.saturating_add(val);
         self.wake_all();
     }
 
+    /// Takes what a read returns from the counter: one at a time in
+    /// semaphore mode, everything otherwise (like an `eventfd`).
     fn dec(&mut self) -> u64 {
-        let val = self.counter;
         if self.is_semaphore {
+            if self.counter == 0 {
+                return 0;
+            }
+            self.counter -= 1;
             if self.counter > 0 {
-                self.counter -= 1;
-                if self.counter > 0 {
-                    self.wake_all();
-                }
+                self.wake_all();
             }
+            1
         } else {
-            self.counter = 0;
+            std::mem::take(&mut self.counter)
         }
-        val
     }
 }
 
@@ -116,6 +118,10 @@ impl NotificationInner {
         }
     }
 
+    pub fn is_semaphore(&self) -> bool {
+        self.state.lock().unwrap().is_semaphore
+    }
+
     pub fn reset(&self) {
         let mut state = self.state.lock().unwrap();
         state.last_poll = u64::MAX;
@@ -131,3 +137,43 @@ impl NotificationInner {
         state.interest_handler.take()
     }
 }
+
+/// A handle through which the host can use an event counter that the guest
+/// sees as an fd, like the ones `fd_event` creates.
+///
+/// Both sides get the semantics of an `eventfd`: writes add to the counter
+/// and wake up whoever is waiting to read it, and reads take the whole
+/// counter, or one at a time in semaphore mode.
+///
+/// See [`WasiEnv::create_event_fd()`](crate::WasiEnv::create_event_fd) and
+/// [`WasiEnv::event_fd_handle()`](crate::WasiEnv::event_fd_handle).
+#[derive(Debug, Clone)]
+pub struct EventFdHandle {
+    inner: Arc<NotificationInner>,
+}
+
+impl EventFdHandle {
+    pub(crate) fn new(inner: Arc<NotificationInner>) -> Self {
+        EventFdHandle { inner }
+    }
+
+    /// Adds `val` to the counter, waking up anyone waiting on it.
+    pub fn write(&self, val: u64) {
+        self.inner.write(val);
+    }
+
+    /// Reads the counter without waiting, returning `None` if it is zero.
+    pub fn try_read(&self) -> Option<u64> {
+        self.inner.try_read()
+    }
+
+    /// Waits for the counter to be non-zero, then reads it.
+    pub async fn read(&self) -> u64 {
+        std::future::poll_fn(|cx| self.inner.read(cx.waker())).await
+    }
+
+    /// Whether reads take one from the counter at a time.
+    pub fn is_semaphore(&self) -> bool {
+        self.inner.is_semaphore()
+    }
+}
@@ -0,0 +1,43 @@
+//! Entry points for fuzzing the parts of WASIX that are otherwise only
+//! reachable from a running guest.
+//!
+//! Each driver decodes its input into a sequence of operations, runs them
+//! one after the other and returns the errno each of them ended with.
+//! Running the same input twice gives the same results, and a panic is
+//! always a bug.
+
+mod path;
+mod socket;
+
+pub use self::{path::resolve_path_arbitrary, socket::drive_sockets_arbitrary};
+
+/// The most operations run for one input, to keep each run short.
+const MAX_OPS: usize = 256;
+
+/// Reads the operations of a driver out of the fuzzer's input. Every read
+/// returns `None` once the input runs out, which ends the run.
+struct Script<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Script<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Script { bytes }
+    }
+
+    fn byte(&mut self) -> Option<u8> {
+        let (first, rest) = self.bytes.split_first()?;
+        self.bytes = rest;
+        Some(*first)
+    }
+
+    fn bool(&mut self) -> Option<bool> {
+        Some(self.byte()? & 1 == 1)
+    }
+
+    /// Picks one of `items`, which must not be empty.
+    fn pick<T: Copy>(&mut self, items: &[T]) -> Option<T> {
+        let i = self.byte()? as usize % items.len();
+        Some(items[i])
+    }
+}
@@ -0,0 +1,172 @@
+use std::{
+    ops::DerefMut,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use virtual_fs::{mem_fs, FileSystem};
+use wasmer_wasix_types::wasi::{Errno, Fd as WasiFd, Fdflags, Fdflagsext};
+
+use super::{Script, MAX_OPS};
+use crate::{
+    capabilities::FsAccess,
+    errno::fs_error_into_wasi_err,
+    fs::{Kind, WasiFs, WasiFsRoot, WasiInodes},
+    runtime::task_manager::InlineWaker,
+    state::PreopenedDir,
+    syscalls::path_symlink_inner,
+    ALL_RIGHTS,
+};
+
+/// What paths are made of, chosen so that short scripts keep running into
+/// each other's files and symlinks.
+const COMPONENTS: &[&str] = &["a", "b", "file", "link", ".", "..", "", "/"];
+
+/// Runs the file system operations described by `script` against the path
+/// resolution and fd table of a [`WasiFs`] whose only preopen is the root
+/// of an in-memory file system, returning the errno each one ended with.
+///
+/// Besides resolving paths and opening, duplicating and closing fds, the
+/// script creates, removes and renames files and directories behind the
+/// resolver's back (directly in the in-memory file system), creates
+/// symlinks like `path_symlink` and drops loaded directory entries like
+/// `path_unlink_file` does, so resolution also runs into stale state.
+pub fn resolve_path_arbitrary(script: &[u8]) -> Vec<Errno> {
+    let mut script = Script::new(script);
+    let backing = mem_fs::FileSystem::default();
+    let inodes = WasiInodes::new();
+    let preopen = PreopenedDir {
+        path: PathBuf::from("/"),
+        alias: None,
+        read: true,
+        write: true,
+        create: true,
+    };
+    let root = WasiFsRoot::Backing(Arc::new(Box::new(backing.clone())));
+    let Ok(fs) = WasiFs::new_with_preopen(&inodes, &[preopen], &[], root) else {
+        return vec![Errno::Io];
+    };
+    let mut fds = fs.preopen_fds.read().unwrap().clone();
+
+    let mut results = Vec::new();
+    while results.len() < MAX_OPS {
+        let Some(result) = step(&mut script, &fs, &inodes, &backing, &mut fds) else {
+            break;
+        };
+        results.push(result.err().unwrap_or(Errno::Success));
+    }
+    results
+}
+
+fn step(
+    script: &mut Script,
+    fs: &WasiFs,
+    inodes: &WasiInodes,
+    backing: &mem_fs::FileSystem,
+    fds: &mut Vec<WasiFd>,
+) -> Option<Result<(), Errno>> {
+    let result = match script.byte()? % 12 {
+        0 => backing
+            .create_dir(&absolute(&path(script)?))
+            .map_err(fs_error_into_wasi_err),
+        1 => backing
+            .new_open_options()
+            .create(true)
+            .write(true)
+            .open(absolute(&path(script)?))
+            .map(drop)
+            .map_err(fs_error_into_wasi_err),
+        2 => backing
+            .remove_file(&absolute(&path(script)?))
+            .map_err(fs_error_into_wasi_err),
+        3 => backing
+            .remove_dir(&absolute(&path(script)?))
+            .map_err(fs_error_into_wasi_err),
+        4 => {
+            let from = absolute(&path(script)?);
+            let to = absolute(&path(script)?);
+            InlineWaker::block_on(backing.rename(&from, &to)).map_err(fs_error_into_wasi_err)
+        }
+        5 => {
+            let fd = fd(script, fds)?;
+            let target = path(script)?;
+            let link = path(script)?;
+            path_symlink_inner(fs, inodes, &target, fd, &link)
+        }
+        6 => {
+            let (fd, path, follow) = (fd(script, fds)?, path(script)?, script.bool()?);
+            fs.get_inode_at_path(inodes, fd, &path, follow, FsAccess::NONE)
+                .map(drop)
+        }
+        7 => {
+            let (fd, path, follow) = (fd(script, fds)?, path(script)?, script.bool()?);
+            fs.get_parent_inode_at_path(inodes, fd, Path::new(&path), follow, FsAccess::NONE)
+                .map(drop)
+        }
+        8 => {
+            let (fd, path, follow) = (fd(script, fds)?, path(script)?, script.bool()?);
+            fs.get_inode_at_path(inodes, fd, &path, follow, FsAccess::NONE)
+                .and_then(|inode| {
+                    fs.create_fd(
+                        ALL_RIGHTS,
+                        ALL_RIGHTS,
+                        Fdflags::empty(),
+                        Fdflagsext::empty(),
+                        0,
+                        inode,
+                    )
+                })
+                .map(|fd| fds.push(fd))
+        }
+        9 => fs.clone_fd(fd(script, fds)?).map(|fd| fds.push(fd)),
+        10 => {
+            let fd = fd(script, fds)?;
+            fs.close_fd(fd).map(|()| fds.retain(|open| *open != fd))
+        }
+        _ => {
+            let (fd, path) = (fd(script, fds)?, path(script)?);
+            forget(fs, inodes, fd, &path)
+        }
+    };
+    Some(result)
+}
+
+/// Reads a path of up to four components.
+fn path(script: &mut Script) -> Option<String> {
+    let len = script.byte()? % 5;
+    let mut path = String::new();
+    for i in 0..len {
+        if i > 0 {
+            path.push('/');
+        }
+        path.push_str(script.pick(COMPONENTS)?);
+    }
+    Some(path)
+}
+
+fn absolute(path: &str) -> PathBuf {
+    Path::new("/").join(path)
+}
+
+/// Reads an fd, which is usually one that is open but may be any other.
+fn fd(script: &mut Script, fds: &[WasiFd]) -> Option<WasiFd> {
+    let byte = script.byte()?;
+    match fds {
+        [] => Some(byte as WasiFd),
+        _ if byte & 0x80 != 0 => Some((byte & 0x7f) as WasiFd),
+        _ => Some(fds[byte as usize % fds.len()]),
+    }
+}
+
+/// Drops `path` from the loaded entries of its directory, as unlinking or
+/// renaming it does, without touching the in-memory file system.
+fn forget(fs: &WasiFs, inodes: &WasiInodes, fd: WasiFd, path: &str) -> Result<(), Errno> {
+    let (parent, name) =
+        fs.get_parent_inode_at_path(inodes, fd, Path::new(path), false, FsAccess::NONE)?;
+    match parent.write().deref_mut() {
+        Kind::Dir { entries, .. } => entries.remove(&name).ok_or(Errno::Noent)?,
+        _ => return Err(Errno::Notcapable),
+    };
+    fs.invalidate_lookups(&parent);
+    Ok(())
+}
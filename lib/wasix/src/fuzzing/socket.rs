@@ -0,0 +1,268 @@
+use std::{
+    future::Future,
+    mem::MaybeUninit,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, Shutdown, SocketAddr},
+    pin::Pin,
+    sync::{Arc, LazyLock},
+    time::Duration,
+};
+
+use futures::{future::BoxFuture, FutureExt};
+use virtual_net::{
+    LoopbackNetworking, NetworkError, VirtualNetworking, VirtualTcpListener, VirtualTcpSocket,
+};
+use wasmer_wasix_types::wasi::{Addressfamily, Errno, SockProto, Socktype};
+
+use super::{Script, MAX_OPS};
+use crate::{
+    capabilities::CapabilityListenV1,
+    net::{
+        listener::ListenerExposer,
+        socket::{InodeSocket, InodeSocketKind, SocketProperties, WasiSocketOption},
+    },
+    os::task::control_plane::{ControlPlaneConfig, WasiControlPlane},
+    runtime::task_manager::TaskWasm,
+    PluggableRuntime, Runtime, VirtualTaskManager, WasiThreadError,
+};
+
+/// The most sockets open at once.
+const MAX_SOCKETS: usize = 8;
+
+/// The ports sockets bind to and connect to, few enough that they find
+/// each other.
+const PORTS: &[u16] = &[0, 80, 8080];
+
+/// Nothing is ever exposed, so the runtime behind the exposer isn't used.
+static RUNTIME: LazyLock<Arc<dyn Runtime + Send + Sync>> =
+    LazyLock::new(|| Arc::new(PluggableRuntime::new(Arc::new(FrozenTasks))));
+
+/// Runs the socket operations described by `script` against sockets on a
+/// loopback network, returning the errno each one ended with.
+///
+/// Every operation is nonblocking and its future is polled exactly once;
+/// one that would have to wait fails with [`Errno::Again`] instead. Time
+/// never passes, so timeouts never fire and the results only depend on the
+/// script.
+pub fn drive_sockets_arbitrary(script: &[u8]) -> Vec<Errno> {
+    let mut script = Script::new(script);
+    let net = Loopback(LoopbackNetworking::new());
+    let exposer = ListenerExposer {
+        capability: CapabilityListenV1::default(),
+        control_plane: WasiControlPlane::new(ControlPlaneConfig::new()),
+        runtime: RUNTIME.clone(),
+    };
+    let mut sockets = Vec::new();
+
+    let mut results = Vec::new();
+    while results.len() < MAX_OPS {
+        let Some(result) = step(&mut script, &net, &exposer, &mut sockets) else {
+            break;
+        };
+        results.push(result.err().unwrap_or(Errno::Success));
+    }
+    results
+}
+
+fn step(
+    script: &mut Script,
+    net: &Loopback,
+    exposer: &ListenerExposer,
+    sockets: &mut Vec<InodeSocket>,
+) -> Option<Result<(), Errno>> {
+    let tasks = &FrozenTasks;
+    let op = script.byte()? % 11;
+    if op == 0 {
+        let ty = script.pick(&[Socktype::Stream, Socktype::Dgram])?;
+        let family = script.pick(&[Addressfamily::Inet4, Addressfamily::Inet6])?;
+        if sockets.len() >= MAX_SOCKETS {
+            return Some(Err(Errno::Mfile));
+        }
+        sockets.push(open(family, ty));
+        return Some(Ok(()));
+    }
+
+    let i = script.byte()? as usize;
+    if sockets.is_empty() {
+        return Some(Err(Errno::Badf));
+    }
+    let i = i % sockets.len();
+    let socket = &mut sockets[i];
+
+    let result = match op {
+        1 => {
+            let addr = addr(script, socket)?;
+            once(socket.bind(tasks, net, addr)).map(|new| replace(socket, new))
+        }
+        2 => once(socket.listen(tasks, net, 1, exposer)).map(|new| replace(socket, new)),
+        3 => {
+            let peer = addr(script, socket)?;
+            once(socket.connect(tasks, net, peer, None, true)).map(|new| replace(socket, new))
+        }
+        4 => once(socket.accept(tasks, true, None)).map(|(child, _)| {
+            if sockets.len() < MAX_SOCKETS {
+                sockets.push(InodeSocket::new(InodeSocketKind::TcpStream {
+                    socket: child,
+                    write_timeout: None,
+                    read_timeout: None,
+                }));
+            }
+        }),
+        5 => {
+            let data = vec![0xa5; script.byte()? as usize];
+            once(socket.send(tasks, &data, None, true)).map(drop)
+        }
+        6 => {
+            let mut buf = vec![MaybeUninit::uninit(); script.byte()? as usize];
+            let peek = script.bool()?;
+            once(socket.recv(tasks, &mut buf, None, true, peek)).map(drop)
+        }
+        7 => {
+            let how = script.pick(&[Shutdown::Read, Shutdown::Write, Shutdown::Both])?;
+            socket.shutdown(how)
+        }
+        8 => {
+            let result = socket.close();
+            sockets.remove(i);
+            result
+        }
+        9 => {
+            let option = match script.byte()? % 7 {
+                0 => WasiSocketOption::ReuseAddr,
+                1 => WasiSocketOption::ReusePort,
+                2 => WasiSocketOption::NoDelay,
+                3 => WasiSocketOption::OnlyV6,
+                4 => WasiSocketOption::KeepAlive,
+                5 => WasiSocketOption::DontRoute,
+                _ => WasiSocketOption::Broadcast,
+            };
+            socket.set_opt_flag(option, script.bool()?)
+        }
+        _ => socket
+            .status()
+            .and_then(|_| socket.addr_local())
+            .and_then(|_| socket.addr_peer())
+            .map(drop),
+    };
+    Some(result)
+}
+
+fn open(family: Addressfamily, ty: Socktype) -> InodeSocket {
+    let pt = match ty {
+        Socktype::Dgram => SockProto::Udp,
+        _ => SockProto::Tcp,
+    };
+    InodeSocket::new(InodeSocketKind::PreSocket {
+        props: SocketProperties {
+            family,
+            ty,
+            pt,
+            only_v6: false,
+            reuse_port: false,
+            reuse_addr: false,
+            no_delay: None,
+            keep_alive: None,
+            dont_route: None,
+            send_buf_size: None,
+            recv_buf_size: None,
+            write_timeout: None,
+            read_timeout: None,
+            accept_timeout: None,
+            connect_timeout: None,
+            handler: None,
+        },
+        addr: None,
+    })
+}
+
+/// Reads an address, usually of the socket's own family.
+fn addr(script: &mut Script, socket: &InodeSocket) -> Option<SocketAddr> {
+    let port = script.pick(PORTS)?;
+    let flags = script.byte()?;
+    let v6 = match socket.addr_local() {
+        Ok(addr) if flags & 4 == 0 => addr.is_ipv6(),
+        _ => flags & 1 != 0,
+    };
+    let ip: IpAddr = match (v6, flags & 2 != 0) {
+        (false, false) => Ipv4Addr::LOCALHOST.into(),
+        (false, true) => Ipv4Addr::UNSPECIFIED.into(),
+        (true, false) => Ipv6Addr::LOCALHOST.into(),
+        (true, true) => Ipv6Addr::UNSPECIFIED.into(),
+    };
+    Some(SocketAddr::new(ip, port))
+}
+
+/// Polls `future` once, failing with [`Errno::Again`] if it isn't done.
+fn once<T>(future: impl Future<Output = Result<T, Errno>>) -> Result<T, Errno> {
+    future.now_or_never().unwrap_or(Err(Errno::Again))
+}
+
+/// Sockets change kind (e.g. from a pre-socket to a listener) by being
+/// replaced, like the syscalls do with the inodes' sockets.
+fn replace(socket: &mut InodeSocket, new: Option<InodeSocket>) {
+    if let Some(new) = new {
+        *socket = new;
+    }
+}
+
+/// [`LoopbackNetworking`] with connections to its own listeners.
+#[derive(Debug)]
+struct Loopback(LoopbackNetworking);
+
+#[async_trait::async_trait]
+impl VirtualNetworking for Loopback {
+    async fn listen_tcp(
+        &self,
+        addr: SocketAddr,
+        only_v6: bool,
+        reuse_port: bool,
+        reuse_addr: bool,
+    ) -> Result<Box<dyn VirtualTcpListener + Sync>, NetworkError> {
+        self.0
+            .listen_tcp(addr, only_v6, reuse_port, reuse_addr)
+            .await
+    }
+
+    async fn connect_tcp(
+        &self,
+        addr: SocketAddr,
+        peer: SocketAddr,
+    ) -> Result<Box<dyn VirtualTcpSocket + Sync>, NetworkError> {
+        let socket = self.0.loopback_connect_to(addr, peer)?;
+        Ok(Box::new(socket))
+    }
+}
+
+/// A task manager on which time stands still and nothing can be spawned.
+#[derive(Debug)]
+struct FrozenTasks;
+
+impl VirtualTaskManager for FrozenTasks {
+    fn sleep_now(
+        &self,
+        _time: Duration,
+    ) -> Pin<Box<dyn Future<Output = ()> + Send + Sync + 'static>> {
+        Box::pin(std::future::pending::<()>())
+    }
+
+    fn task_shared(
+        &self,
+        _task: Box<dyn FnOnce() -> BoxFuture<'static, ()> + Send + 'static>,
+    ) -> Result<(), WasiThreadError> {
+        Err(WasiThreadError::Unsupported)
+    }
+
+    fn task_wasm(&self, _task: TaskWasm) -> Result<(), WasiThreadError> {
+        Err(WasiThreadError::Unsupported)
+    }
+
+    fn task_dedicated(
+        &self,
+        _task: Box<dyn FnOnce() + Send + 'static>,
+    ) -> Result<(), WasiThreadError> {
+        Err(WasiThreadError::Unsupported)
+    }
+
+    fn thread_parallelism(&self) -> Result<usize, WasiThreadError> {
+        Ok(1)
+    }
+}
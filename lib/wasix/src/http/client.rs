@@ -1,9 +1,11 @@
-use std::{collections::BTreeSet, ops::Deref, sync::Arc};
+use std::{collections::BTreeSet, ops::Deref, sync::Arc, time::Duration};
 
 use futures::future::BoxFuture;
-use http::{HeaderMap, Method, StatusCode};
+use http::{header::HeaderName, HeaderMap, HeaderValue, Method, StatusCode};
 use url::Url;
 
+use crate::capabilities::CapabilityTlsV1;
+
 /// Defines http client permissions.
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct HttpClientCapabilityV1 {
@@ -50,10 +52,143 @@ impl Default for HttpClientCapabilityV1 {
     }
 }
 
-#[derive(Debug, Default)]
+/// How a [`HttpClient`] handles redirects.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RedirectPolicy {
+    /// Return redirect responses as they are
+    None,
+    /// Follow up to this many redirects, failing the request after that
+    Limited(usize),
+    /// Follow every redirect
+    Follow,
+}
+
+impl Default for RedirectPolicy {
+    fn default() -> Self {
+        RedirectPolicy::Limited(10)
+    }
+}
+
+/// A compression scheme used for HTTP bodies (see the `Content-Encoding`
+/// header).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ContentEncoding {
+    Gzip,
+    Brotli,
+}
+
+impl ContentEncoding {
+    /// The `Accept-Encoding` header clients send unless the request already
+    /// has one.
+    pub const ACCEPT_ENCODING: &'static str = "gzip, br";
+
+    /// The name of the encoding as it appears in headers.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ContentEncoding::Gzip => "gzip",
+            ContentEncoding::Brotli => "br",
+        }
+    }
+
+    /// Parses a `Content-Encoding` header.
+    ///
+    /// Returns `None` for `identity` and for anything that isn't a single
+    /// supported encoding, in which case the body can't be decoded.
+    pub fn from_header(value: &HeaderValue) -> Option<Self> {
+        let value = value.to_str().ok()?.trim();
+        if value.eq_ignore_ascii_case("gzip") || value.eq_ignore_ascii_case("x-gzip") {
+            Some(ContentEncoding::Gzip)
+        } else if value.eq_ignore_ascii_case("br") {
+            Some(ContentEncoding::Brotli)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(feature = "host-reqwest")]
+impl ContentEncoding {
+    /// Compresses `data` with this encoding.
+    pub fn encode(&self, data: &[u8]) -> std::io::Result<Vec<u8>> {
+        use std::io::Write;
+
+        match self {
+            ContentEncoding::Gzip => {
+                let mut encoder =
+                    flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+                encoder.write_all(data)?;
+                encoder.finish()
+            }
+            ContentEncoding::Brotli => {
+                let mut encoder = brotli::CompressorWriter::new(Vec::new(), 4096, 5, 22);
+                encoder.write_all(data)?;
+                encoder.flush()?;
+                Ok(encoder.into_inner())
+            }
+        }
+    }
+
+    /// Decompresses `data`, which was compressed with this encoding.
+    pub fn decode(&self, data: &[u8]) -> std::io::Result<Vec<u8>> {
+        use std::io::Read;
+
+        let mut decoded = Vec::new();
+        match self {
+            ContentEncoding::Gzip => {
+                flate2::read::MultiGzDecoder::new(data).read_to_end(&mut decoded)?;
+            }
+            ContentEncoding::Brotli => {
+                brotli::Decompressor::new(data, 4096).read_to_end(&mut decoded)?;
+            }
+        }
+        Ok(decoded)
+    }
+}
+
+impl std::fmt::Display for ContentEncoding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Debug)]
 pub struct HttpRequestOptions {
-    pub gzip: bool,
+    /// Compress the request body with this encoding when it is larger than
+    /// [`HttpRequestOptions::body_compression_threshold`]. Clients that can't
+    /// compress bodies send them as they are.
+    pub body_encoding: Option<ContentEncoding>,
+    /// The size in bytes a request body must exceed to be compressed
+    pub body_compression_threshold: usize,
     pub cors_proxy: Option<String>,
+    pub redirect_policy: RedirectPolicy,
+    /// How long the whole request may take, or [`None`] for the client's
+    /// default
+    pub timeout: Option<Duration>,
+    /// Only set through [`HttpRequestBuilder::accept_invalid_certs()`], which
+    /// checks the TLS capability.
+    accept_invalid_certs: bool,
+}
+
+impl HttpRequestOptions {
+    pub const DEFAULT_BODY_COMPRESSION_THRESHOLD: usize = 1024;
+
+    /// Whether any certificate the server presents is accepted.
+    pub fn accept_invalid_certs(&self) -> bool {
+        self.accept_invalid_certs
+    }
+}
+
+impl Default for HttpRequestOptions {
+    fn default() -> Self {
+        HttpRequestOptions {
+            body_encoding: None,
+            body_compression_threshold: Self::DEFAULT_BODY_COMPRESSION_THRESHOLD,
+            cors_proxy: None,
+            redirect_policy: RedirectPolicy::default(),
+            timeout: None,
+            accept_invalid_certs: false,
+        }
+    }
 }
 
 // TODO: use types from http crate?
@@ -66,6 +201,22 @@ pub struct HttpRequest {
 }
 
 impl HttpRequest {
+    pub fn builder(method: Method, url: Url) -> HttpRequestBuilder {
+        HttpRequestBuilder::new(method, url)
+    }
+
+    pub fn get(url: Url) -> HttpRequestBuilder {
+        HttpRequestBuilder::new(Method::GET, url)
+    }
+
+    pub fn head(url: Url) -> HttpRequestBuilder {
+        HttpRequestBuilder::new(Method::HEAD, url)
+    }
+
+    pub fn post(url: Url) -> HttpRequestBuilder {
+        HttpRequestBuilder::new(Method::POST, url)
+    }
+
     fn from_http_parts(parts: http::request::Parts, body: impl Into<Option<Vec<u8>>>) -> Self {
         let http::request::Parts {
             method,
@@ -138,12 +289,162 @@ impl From<http::Request<()>> for HttpRequest {
     }
 }
 
+/// Errors from building a [`HttpRequest`] with [`HttpRequestBuilder`].
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum HttpRequestError {
+    #[error("invalid header name \"{0}\"")]
+    InvalidHeaderName(String),
+    #[error("invalid value for the \"{0}\" header")]
+    InvalidHeaderValue(String),
+    #[error("accepting invalid certificates requires the insecure TLS capability")]
+    InsecureNotAllowed,
+}
+
+/// Builds a [`HttpRequest`], checking the headers as they are added.
+///
+/// Header names are normalized to lowercase and surrounding whitespace is
+/// trimmed from their values. Names and values containing control characters are
+/// rejected, and so is anything else that isn't valid in a header. Like
+/// [`http::request::Builder`], the first error is kept and returned from
+/// [`HttpRequestBuilder::build()`].
+#[derive(Debug)]
+pub struct HttpRequestBuilder {
+    request: HttpRequest,
+    error: Option<HttpRequestError>,
+}
+
+impl HttpRequestBuilder {
+    pub fn new(method: Method, url: Url) -> Self {
+        HttpRequestBuilder {
+            request: HttpRequest {
+                url,
+                method,
+                headers: HeaderMap::new(),
+                body: None,
+                options: HttpRequestOptions::default(),
+            },
+            error: None,
+        }
+    }
+
+    /// Appends a header, keeping any previous values it had.
+    pub fn header(mut self, name: impl AsRef<str>, value: impl AsRef<str>) -> Self {
+        if self.error.is_none() {
+            match validate_header(name.as_ref(), value.as_ref().as_bytes()) {
+                Ok((name, value)) => {
+                    self.request.headers.append(name, value);
+                }
+                Err(err) => self.error = Some(err),
+            }
+        }
+        self
+    }
+
+    /// Appends all of the headers in `headers`.
+    pub fn headers(mut self, headers: HeaderMap) -> Self {
+        for (name, value) in &headers {
+            if self.error.is_some() {
+                break;
+            }
+            match validate_header(name.as_str(), value.as_bytes()) {
+                Ok((name, value)) => {
+                    self.request.headers.append(name, value);
+                }
+                Err(err) => self.error = Some(err),
+            }
+        }
+        self
+    }
+
+    pub fn body(mut self, body: impl Into<Vec<u8>>) -> Self {
+        self.request.body = Some(body.into());
+        self
+    }
+
+    /// Compress the body with `encoding` if it is larger than the
+    /// compression threshold.
+    pub fn body_encoding(mut self, encoding: ContentEncoding) -> Self {
+        self.request.options.body_encoding = Some(encoding);
+        self
+    }
+
+    pub fn body_compression_threshold(mut self, threshold: usize) -> Self {
+        self.request.options.body_compression_threshold = threshold;
+        self
+    }
+
+    pub fn cors_proxy(mut self, cors_proxy: impl Into<String>) -> Self {
+        self.request.options.cors_proxy = Some(cors_proxy.into());
+        self
+    }
+
+    pub fn redirect_policy(mut self, policy: RedirectPolicy) -> Self {
+        self.request.options.redirect_policy = policy;
+        self
+    }
+
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.request.options.timeout = Some(timeout);
+        self
+    }
+
+    /// Accept any certificate the server presents, which is only allowed
+    /// when the TLS capability skips verification.
+    pub fn accept_invalid_certs(mut self, tls: &CapabilityTlsV1) -> Self {
+        if !tls.insecure_skip_verify {
+            self.error
+                .get_or_insert(HttpRequestError::InsecureNotAllowed);
+        }
+        self.request.options.accept_invalid_certs = true;
+        self
+    }
+
+    pub fn build(self) -> Result<HttpRequest, HttpRequestError> {
+        match self.error {
+            Some(err) => Err(err),
+            None => Ok(self.request),
+        }
+    }
+}
+
+fn validate_header(
+    name: &str,
+    value: &[u8],
+) -> Result<(HeaderName, HeaderValue), HttpRequestError> {
+    if name.chars().any(|c| c.is_ascii_control()) {
+        return Err(HttpRequestError::InvalidHeaderName(
+            name.escape_debug().to_string(),
+        ));
+    }
+    let name = HeaderName::from_bytes(name.as_bytes())
+        .map_err(|_| HttpRequestError::InvalidHeaderName(name.escape_debug().to_string()))?;
+
+    let value = value.trim_ascii();
+    if value.iter().any(|b| b.is_ascii_control()) {
+        return Err(HttpRequestError::InvalidHeaderValue(name.to_string()));
+    }
+    let value = HeaderValue::from_bytes(value)
+        .map_err(|_| HttpRequestError::InvalidHeaderValue(name.to_string()))?;
+
+    Ok((name, value))
+}
+
 // TODO: use types from http crate?
 pub struct HttpResponse {
+    /// The body, decoded if the server compressed it with a supported
+    /// [`ContentEncoding`]
     pub body: Option<Vec<u8>>,
     pub redirected: bool,
     pub status: StatusCode,
+    /// The headers as the server sent them, including `Content-Encoding`
     pub headers: HeaderMap,
+    /// The encoding the body was decoded from, or `None` if it arrived
+    /// uncompressed (or in an encoding the client doesn't support)
+    pub content_encoding: Option<ContentEncoding>,
+    /// The size of the body as it was received
+    pub encoded_body_size: usize,
+    /// The size of the body after decoding it
+    pub decoded_body_size: usize,
 }
 
 impl HttpResponse {
@@ -159,6 +460,9 @@ impl std::fmt::Debug for HttpResponse {
             redirected,
             status,
             headers,
+            content_encoding,
+            encoded_body_size,
+            decoded_body_size,
         } = self;
 
         f.debug_struct("HttpResponse")
@@ -166,6 +470,9 @@ impl std::fmt::Debug for HttpResponse {
             .field("redirected", &redirected)
             .field("status", &status)
             .field("headers", &headers)
+            .field("content_encoding", &content_encoding)
+            .field("encoded_body_size", &encoded_body_size)
+            .field("decoded_body_size", &decoded_body_size)
             .field("body", &body.as_deref().map(String::from_utf8_lossy))
             .finish()
     }
@@ -188,3 +495,142 @@ where
 }
 
 pub type DynHttpClient = Arc<dyn HttpClient + Send + Sync + 'static>;
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+
+    #[derive(Debug, Default)]
+    struct RecordingClient {
+        requests: Mutex<Vec<HttpRequest>>,
+    }
+
+    impl HttpClient for RecordingClient {
+        fn request(
+            &self,
+            request: HttpRequest,
+        ) -> BoxFuture<'_, Result<HttpResponse, anyhow::Error>> {
+            self.requests.lock().unwrap().push(request);
+            Box::pin(async {
+                Ok(HttpResponse {
+                    body: None,
+                    redirected: false,
+                    status: StatusCode::OK,
+                    headers: HeaderMap::new(),
+                    content_encoding: None,
+                    encoded_body_size: 0,
+                    decoded_body_size: 0,
+                })
+            })
+        }
+    }
+
+    fn url() -> Url {
+        "https://example.com/".parse().unwrap()
+    }
+
+    #[test]
+    fn invalid_headers_are_rejected() {
+        let invalid_names = ["X-Foo\r\nX-Injected", "Bad Name", "", "X-Foo:"];
+        for name in invalid_names {
+            let err = HttpRequest::get(url()).header(name, "value").build();
+            assert!(
+                matches!(err, Err(HttpRequestError::InvalidHeaderName(_))),
+                "{name:?}: {err:?}"
+            );
+        }
+
+        let invalid_values = ["a\r\nX-Injected: 1", "a\0b", "a\tb", "a\x7fb"];
+        for value in invalid_values {
+            let err = HttpRequest::get(url()).header("X-Foo", value).build();
+            assert_eq!(
+                err.unwrap_err(),
+                HttpRequestError::InvalidHeaderValue("x-foo".to_string()),
+                "{value:?}"
+            );
+        }
+
+        // The first error is the one that's reported
+        let err = HttpRequest::get(url())
+            .header("X-Ok", "value")
+            .header("Bad Name", "value")
+            .header("X-Foo", "\n")
+            .build()
+            .unwrap_err();
+        assert_eq!(
+            err,
+            HttpRequestError::InvalidHeaderName("Bad Name".to_string())
+        );
+    }
+
+    #[test]
+    fn content_encoding_headers_are_parsed() {
+        let parse = |value| ContentEncoding::from_header(&HeaderValue::from_static(value));
+        assert_eq!(parse("gzip"), Some(ContentEncoding::Gzip));
+        assert_eq!(parse("X-Gzip"), Some(ContentEncoding::Gzip));
+        assert_eq!(parse(" br "), Some(ContentEncoding::Brotli));
+        assert_eq!(parse("identity"), None);
+        assert_eq!(parse("deflate"), None);
+        assert_eq!(parse("gzip, br"), None);
+    }
+
+    #[test]
+    fn accepting_invalid_certs_requires_the_insecure_capability() {
+        let err = HttpRequest::get(url())
+            .accept_invalid_certs(&CapabilityTlsV1::default())
+            .build()
+            .unwrap_err();
+        assert_eq!(err, HttpRequestError::InsecureNotAllowed);
+
+        let tls = CapabilityTlsV1 {
+            insecure_skip_verify: true,
+        };
+        let request = HttpRequest::get(url())
+            .accept_invalid_certs(&tls)
+            .build()
+            .unwrap();
+        assert!(request.options.accept_invalid_certs());
+    }
+
+    #[test]
+    fn clients_see_the_normalized_request() {
+        let client = RecordingClient::default();
+        let request = HttpRequest::post(url())
+            .header("Content-Type", "  application/json ")
+            .header("X-Custom-HEADER", "a")
+            .headers(HeaderMap::from_iter([(
+                HeaderName::from_static("x-custom-header"),
+                HeaderValue::from_static("b"),
+            )]))
+            .body("{}")
+            .redirect_policy(RedirectPolicy::None)
+            .timeout(Duration::from_secs(5))
+            .build()
+            .unwrap();
+        futures::executor::block_on(client.request(request)).unwrap();
+
+        let requests = client.requests.lock().unwrap();
+        let request = &requests[0];
+        assert_eq!(request.url, url());
+        assert_eq!(request.method, Method::POST);
+        let headers = request
+            .headers
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.to_str().unwrap()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            headers,
+            [
+                ("content-type", "application/json"),
+                ("x-custom-header", "a"),
+                ("x-custom-header", "b"),
+            ]
+        );
+        assert_eq!(request.body.as_deref(), Some(&b"{}"[..]));
+        assert_eq!(request.options.redirect_policy, RedirectPolicy::None);
+        assert_eq!(request.options.timeout, Some(Duration::from_secs(5)));
+        assert!(!request.options.accept_invalid_certs());
+    }
+}
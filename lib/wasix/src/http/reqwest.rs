@@ -5,7 +5,7 @@ use futures::{future::BoxFuture, TryStreamExt};
 use std::convert::TryFrom;
 use tokio::runtime::Handle;
 
-use super::{HttpRequest, HttpResponse};
+use super::{ContentEncoding, HttpRequest, HttpResponse};
 
 #[derive(Clone, Debug)]
 pub struct ReqwestHttpClient {
@@ -48,7 +48,18 @@ impl ReqwestHttpClient {
             let mut builder = reqwest::ClientBuilder::new();
             #[cfg(not(feature = "js"))]
             {
-                builder = builder.connect_timeout(self.connect_timeout);
+                use super::RedirectPolicy;
+                use reqwest::redirect::Policy;
+
+                let redirect = match request.options.redirect_policy {
+                    RedirectPolicy::None => Policy::none(),
+                    RedirectPolicy::Limited(max) => Policy::limited(max),
+                    RedirectPolicy::Follow => Policy::custom(|attempt| attempt.follow()),
+                };
+                builder = builder
+                    .connect_timeout(self.connect_timeout)
+                    .redirect(redirect)
+                    .danger_accept_invalid_certs(request.options.accept_invalid_certs());
             }
             builder
         };
@@ -60,15 +71,43 @@ impl ReqwestHttpClient {
             builder = builder.header(header, val);
         }
 
+        // Browsers negotiate and decode compression by themselves
+        #[cfg(not(feature = "js"))]
+        if !request.headers.contains_key(http::header::ACCEPT_ENCODING) {
+            builder = builder.header(
+                http::header::ACCEPT_ENCODING,
+                ContentEncoding::ACCEPT_ENCODING,
+            );
+        }
+
         if let Some(body) = request.body {
+            let body = match request.options.body_encoding {
+                Some(encoding)
+                    if body.len() > request.options.body_compression_threshold
+                        && !request.headers.contains_key(http::header::CONTENT_ENCODING) =>
+                {
+                    builder = builder.header(http::header::CONTENT_ENCODING, encoding.as_str());
+                    encoding.encode(&body).with_context(|| {
+                        format!("failed to encode the request body as {encoding}")
+                    })?
+                }
+                _ => body,
+            };
             builder = builder.body(reqwest::Body::from(body));
         }
 
+        #[cfg(not(feature = "js"))]
+        if let Some(timeout) = request.options.timeout {
+            builder = builder.timeout(timeout);
+        }
+
         let request = builder
             .build()
             .context("Failed to construct http request")?;
 
+        let url = request.url().clone();
         let mut response = client.execute(request).await?;
+        let redirected = response.url() != &url;
         let headers = std::mem::take(response.headers_mut());
 
         let status = response.status();
@@ -139,11 +178,30 @@ impl ReqwestHttpClient {
 
         tracing::debug!(body_size_bytes=%data.len(), "downloaded http response body");
 
+        let encoded_body_size = data.len();
+        #[cfg(not(feature = "js"))]
+        let content_encoding = headers
+            .get(http::header::CONTENT_ENCODING)
+            .and_then(ContentEncoding::from_header)
+            // e.g. the response to a HEAD request
+            .filter(|_| !data.is_empty());
+        #[cfg(feature = "js")]
+        let content_encoding: Option<ContentEncoding> = None;
+        let data = match content_encoding {
+            Some(encoding) => encoding
+                .decode(&data)
+                .with_context(|| format!("failed to decode the {encoding} response body"))?,
+            None => data,
+        };
+
         Ok(HttpResponse {
             status,
-            redirected: false,
+            redirected,
+            decoded_body_size: data.len(),
             body: Some(data),
             headers,
+            content_encoding,
+            encoded_body_size,
         })
     }
 }
@@ -152,8 +210,16 @@ impl super::HttpClient for ReqwestHttpClient {
     #[cfg(not(feature = "js"))]
     fn request(&self, request: HttpRequest) -> BoxFuture<'_, Result<HttpResponse, anyhow::Error>> {
         let client = self.clone();
-        let f = async move { client.request(request).await };
-        Box::pin(f)
+        let span = crate::telemetry::http_request(&request.method, &request.url);
+        let f = async move {
+            let response = client.request(request).await;
+            if let Ok(response) = &response {
+                tracing::Span::current()
+                    .record("http.response.status_code", response.status.as_u16());
+            }
+            response
+        };
+        Box::pin(tracing::Instrument::instrument(f, span))
     }
 
     #[cfg(feature = "js")]
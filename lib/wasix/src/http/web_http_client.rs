@@ -5,10 +5,12 @@ use futures::{channel::oneshot, future::BoxFuture};
 use http::header::{HeaderMap, HeaderValue, IntoHeaderName};
 use wasm_bindgen::{JsCast, JsValue};
 use wasm_bindgen_futures::JsFuture;
-use web_sys::{RequestInit, RequestMode, Window, WorkerGlobalScope};
+use web_sys::{RequestInit, RequestMode, RequestRedirect, Window, WorkerGlobalScope};
 
 use crate::{
-    http::{HttpClient, HttpRequest, HttpRequestOptions, HttpResponse},
+    http::{
+        ContentEncoding, HttpClient, HttpRequest, HttpRequestOptions, HttpResponse, RedirectPolicy,
+    },
     utils::web::js_error,
     VirtualTaskManager, WasiThreadError,
 };
@@ -141,15 +143,28 @@ async fn fetch(request: HttpRequest) -> Result<HttpResponse, Error> {
         method,
         headers,
         body,
-        options: HttpRequestOptions {
-            gzip: _,
-            cors_proxy,
-        },
+        options:
+            HttpRequestOptions {
+                // The browser compresses bodies as it sees fit
+                body_encoding: _,
+                body_compression_threshold: _,
+                cors_proxy,
+                redirect_policy,
+                // The browser decides about the timeout and which
+                // certificates to accept
+                timeout: _,
+                ..
+            },
     } = request;
 
     let mut opts = RequestInit::new();
     opts.method(method.as_str());
     opts.mode(RequestMode::Cors);
+    // Browsers have their own limit on the number of redirects they follow
+    opts.redirect(match redirect_policy {
+        RedirectPolicy::None => RequestRedirect::Manual,
+        RedirectPolicy::Limited(_) | RedirectPolicy::Follow => RequestRedirect::Follow,
+    });
 
     if let Some(data) = body {
         let data_len = data.len();
@@ -218,11 +233,24 @@ async fn read_response(response: &web_sys::Response) -> Result<HttpResponse, any
     let headers = headers(response.headers()).context("Unable to read the headers")?;
     let body = get_response_data(response).await?;
 
+    // The browser already decoded the body, so only the `Content-Length`
+    // header (when it is exposed) tells how big it was on the wire
+    let content_encoding = headers
+        .get(http::header::CONTENT_ENCODING)
+        .and_then(ContentEncoding::from_header);
+    let encoded_body_size = headers
+        .get(http::header::CONTENT_LENGTH)
+        .and_then(|len| len.to_str().ok()?.parse().ok())
+        .unwrap_or(body.len());
+
     Ok(HttpResponse {
+        decoded_body_size: body.len(),
         body: Some(body),
         redirected: response.redirected(),
         status,
         headers,
+        content_encoding,
+        encoded_body_size,
     })
 }
 
@@ -54,14 +54,22 @@ pub mod os;
 pub mod net;
 // TODO: should this be pub?
 pub mod capabilities;
+#[cfg(feature = "differential")]
+pub mod differential;
+pub mod errno;
 pub mod fs;
+#[cfg(feature = "fuzzing")]
+pub mod fuzzing;
 pub mod http;
 pub mod journal;
+pub mod log_sink;
 mod rewind;
 pub mod runners;
 pub mod runtime;
 mod state;
 mod syscalls;
+pub mod telemetry;
+pub mod test_report;
 mod utils;
 
 use std::sync::Arc;
@@ -97,6 +105,7 @@ pub use crate::{
     os::{
         task::{
             control_plane::WasiControlPlane,
+            failure::{ExitIntent, ProcessFailure},
             process::{WasiProcess, WasiProcessId},
             thread::{WasiThread, WasiThreadError, WasiThreadHandle, WasiThreadId},
         },
@@ -105,8 +114,9 @@ pub use crate::{
     rewind::*,
     runtime::{task_manager::VirtualTaskManager, PluggableRuntime, Runtime},
     state::{
-        WasiEnv, WasiEnvBuilder, WasiEnvInit, WasiFunctionEnv, WasiModuleInstanceHandles,
-        WasiModuleTreeHandles, WasiStateCreationError, ALL_RIGHTS,
+        SecretEnv, StdioBuffering, StdioPrefix, WasiEnv, WasiEnvBuilder, WasiEnvInit,
+        WasiFunctionEnv, WasiModuleInstanceHandles, WasiModuleTreeHandles, WasiStateCreationError,
+        ALL_RIGHTS,
     },
     syscalls::{journal::wait_for_snapshot, rewind, rewind_ext, types, unwind},
     utils::is_wasix_module,
@@ -180,12 +190,22 @@ pub enum SpawnError {
     },
     #[error("could not load ")]
     ModuleLoad { message: String },
+    /// The command needs a runner that can't be used to spawn processes
+    #[error("the \"{command}\" command uses the \"{runner}\" runner, which isn't supported")]
+    UnsupportedRunner { command: String, runner: String },
+    /// A script's `#!` interpreters are nested too deeply (or loop)
+    #[error("too many levels of interpreters while running \"{script}\"")]
+    InterpreterLoop { script: String },
     /// Bad request
     #[error("bad request")]
     BadRequest,
     /// Access denied
     #[error("access denied")]
     AccessDenied,
+    /// The spawn was cancelled before the process started running (see
+    /// [`SpawnCancellation`](crate::bin_factory::SpawnCancellation))
+    #[error("the spawn was cancelled")]
+    Cancelled,
     /// Internal error has occurred
     #[error("internal error")]
     InternalError,
@@ -278,6 +298,8 @@ pub enum WasiRuntimeError {
     Thread(#[from] WasiThreadError),
     #[error("{0}")]
     Anyhow(#[from] Arc<anyhow::Error>),
+    #[error("{0}")]
+    Process(Box<ProcessFailure>),
 }
 
 impl WasiRuntimeError {
@@ -285,7 +307,9 @@ impl WasiRuntimeError {
     ///
     /// Returns [`None`] if a general execution error ocurred.
     pub fn as_exit_code(&self) -> Option<ExitCode> {
-        if let WasiRuntimeError::Wasi(WasiError::Exit(code)) = self {
+        if let WasiRuntimeError::Process(failure) = self {
+            failure.error().as_exit_code()
+        } else if let WasiRuntimeError::Wasi(WasiError::Exit(code)) = self {
             Some(*code)
         } else if let WasiRuntimeError::Runtime(err) = self {
             if let Some(WasiError::Exit(code)) = err.downcast_ref() {
@@ -297,6 +321,52 @@ impl WasiRuntimeError {
             None
         }
     }
+
+    /// The process context this error was raised in, if it is known.
+    pub fn process_failure(&self) -> Option<&ProcessFailure> {
+        match self {
+            WasiRuntimeError::Process(failure) => Some(failure),
+            _ => None,
+        }
+    }
+
+    /// The process that failed, if it is known.
+    pub fn pid(&self) -> Option<WasiProcessId> {
+        self.process_failure().map(|f| f.pid())
+    }
+
+    /// The thread the error was raised on, if it is known.
+    pub fn tid(&self) -> Option<WasiThreadId> {
+        self.process_failure().map(|f| f.tid())
+    }
+
+    /// The command that was running, if it is known.
+    pub fn command(&self) -> Option<&str> {
+        self.process_failure().and_then(|f| f.command())
+    }
+
+    /// Why the process stopped, if it is known.
+    pub fn exit_intent(&self) -> Option<&ExitIntent> {
+        self.process_failure().map(|f| f.intent())
+    }
+
+    /// The innermost WebAssembly frames at the point of failure.
+    pub fn frames(&self) -> &[wasmer::FrameInfo] {
+        match self {
+            WasiRuntimeError::Process(failure) => failure.frames(),
+            WasiRuntimeError::Runtime(err) => err.trace(),
+            _ => &[],
+        }
+    }
+
+    /// Attach the context of the process `env` belongs to, unless the error
+    /// already carries one.
+    pub(crate) fn with_process_context(self, env: &WasiEnv) -> Self {
+        match self {
+            WasiRuntimeError::Process(_) => self,
+            other => WasiRuntimeError::Process(Box::new(ProcessFailure::capture(env, other))),
+        }
+    }
 }
 
 #[allow(clippy::result_large_err)]
@@ -382,6 +452,8 @@ pub fn generate_import_object_from_env(
     };
 
     imports.extend(&imports_wasi_generic);
+    #[cfg(feature = "wasi-crypto")]
+    register_wasi_crypto(&mut imports, store, ctx);
 
     imports
 }
@@ -548,6 +620,7 @@ fn wasix_exports_32(mut store: &mut impl AsStoreMut, env: &FunctionEnv<WasiEnv>)
         "fd_sync" => Function::new_typed_with_env(&mut store, env, fd_sync),
         "fd_tell" => Function::new_typed_with_env(&mut store, env, fd_tell::<Memory32>),
         "fd_write" => Function::new_typed_with_env(&mut store, env, fd_write::<Memory32>),
+        "fd_link" => Function::new_typed_with_env(&mut store, env, fd_link::<Memory32>),
         "fd_pipe" => Function::new_typed_with_env(&mut store, env, fd_pipe::<Memory32>),
         "path_create_directory" => Function::new_typed_with_env(&mut store, env, path_create_directory::<Memory32>),
         "path_filestat_get" => Function::new_typed_with_env(&mut store, env, path_filestat_get::<Memory32>),
@@ -555,6 +628,7 @@ fn wasix_exports_32(mut store: &mut impl AsStoreMut, env: &FunctionEnv<WasiEnv>)
         "path_link" => Function::new_typed_with_env(&mut store, env, path_link::<Memory32>),
         "path_open" => Function::new_typed_with_env(&mut store, env, path_open::<Memory32>),
         "path_open2" => Function::new_typed_with_env(&mut store, env, path_open2::<Memory32>),
+        "path_open_tmpfile" => Function::new_typed_with_env(&mut store, env, path_open_tmpfile::<Memory32>),
         "path_readlink" => Function::new_typed_with_env(&mut store, env, path_readlink::<Memory32>),
         "path_remove_directory" => Function::new_typed_with_env(&mut store, env, path_remove_directory::<Memory32>),
         "path_rename" => Function::new_typed_with_env(&mut store, env, path_rename::<Memory32>),
@@ -577,6 +651,14 @@ fn wasix_exports_32(mut store: &mut impl AsStoreMut, env: &FunctionEnv<WasiEnv>)
         "proc_spawn2" => Function::new_typed_with_env(&mut store, env, proc_spawn2::<Memory32>),
         "proc_id" => Function::new_typed_with_env(&mut store, env, proc_id::<Memory32>),
         "proc_parent" => Function::new_typed_with_env(&mut store, env, proc_parent::<Memory32>),
+        "proc_daemonize" => Function::new_typed_with_env(&mut store, env, proc_daemonize::<Memory32>),
+        "proc_package_metadata" => Function::new_typed_with_env(&mut store, env, proc_package_metadata::<Memory32>),
+        "kv_get" => Function::new_typed_with_env(&mut store, env, kv_get::<Memory32>),
+        "kv_put" => Function::new_typed_with_env(&mut store, env, kv_put::<Memory32>),
+        "kv_delete" => Function::new_typed_with_env(&mut store, env, kv_delete::<Memory32>),
+        "kv_scan" => Function::new_typed_with_env(&mut store, env, kv_scan::<Memory32>),
+        "archive_extract" => Function::new_typed_with_env(&mut store, env, archive_extract::<Memory32>),
+        "archive_extract_poll" => Function::new_typed_with_env(&mut store, env, archive_extract_poll::<Memory32>),
         "random_get" => Function::new_typed_with_env(&mut store, env, random_get::<Memory32>),
         "tty_get" => Function::new_typed_with_env(&mut store, env, tty_get::<Memory32>),
         "tty_set" => Function::new_typed_with_env(&mut store, env, tty_set::<Memory32>),
@@ -593,6 +675,7 @@ fn wasix_exports_32(mut store: &mut impl AsStoreMut, env: &FunctionEnv<WasiEnv>)
         "thread_signal" => Function::new_typed_with_env(&mut store, env, thread_signal),
         "thread_join" => Function::new_typed_with_env(&mut store, env, thread_join::<Memory32>),
         "thread_parallelism" => Function::new_typed_with_env(&mut store, env, thread_parallelism::<Memory32>),
+        "cpu_features" => Function::new_typed_with_env(&mut store, env, cpu_features::<Memory32>),
         "thread_exit" => Function::new_typed_with_env(&mut store, env, thread_exit),
         "sched_yield" => Function::new_typed_with_env(&mut store, env, sched_yield::<Memory32>),
         "stack_checkpoint" => Function::new_typed_with_env(&mut store, env, stack_checkpoint::<Memory32>),
@@ -624,6 +707,8 @@ fn wasix_exports_32(mut store: &mut impl AsStoreMut, env: &FunctionEnv<WasiEnv>)
         "sock_get_opt_time" => Function::new_typed_with_env(&mut store, env, sock_get_opt_time::<Memory32>),
         "sock_set_opt_size" => Function::new_typed_with_env(&mut store, env, sock_set_opt_size),
         "sock_get_opt_size" => Function::new_typed_with_env(&mut store, env, sock_get_opt_size::<Memory32>),
+        "sock_set_opt_bytes" => Function::new_typed_with_env(&mut store, env, sock_set_opt_bytes::<Memory32>),
+        "sock_get_opt_bytes" => Function::new_typed_with_env(&mut store, env, sock_get_opt_bytes::<Memory32>),
         "sock_join_multicast_v4" => Function::new_typed_with_env(&mut store, env, sock_join_multicast_v4::<Memory32>),
         "sock_leave_multicast_v4" => Function::new_typed_with_env(&mut store, env, sock_leave_multicast_v4::<Memory32>),
         "sock_join_multicast_v6" => Function::new_typed_with_env(&mut store, env, sock_join_multicast_v6::<Memory32>),
@@ -639,6 +724,7 @@ fn wasix_exports_32(mut store: &mut impl AsStoreMut, env: &FunctionEnv<WasiEnv>)
         "sock_send_to" => Function::new_typed_with_env(&mut store, env, sock_send_to::<Memory32>),
         "sock_send_file" => Function::new_typed_with_env(&mut store, env, sock_send_file::<Memory32>),
         "sock_shutdown" => Function::new_typed_with_env(&mut store, env, sock_shutdown),
+        "sock_tls_upgrade" => Function::new_typed_with_env(&mut store, env, sock_tls_upgrade::<Memory32>),
         "resolve" => Function::new_typed_with_env(&mut store, env, resolve::<Memory32>),
     };
     namespace
@@ -688,6 +774,7 @@ fn wasix_exports_64(mut store: &mut impl AsStoreMut, env: &FunctionEnv<WasiEnv>)
         "fd_sync" => Function::new_typed_with_env(&mut store, env, fd_sync),
         "fd_tell" => Function::new_typed_with_env(&mut store, env, fd_tell::<Memory64>),
         "fd_write" => Function::new_typed_with_env(&mut store, env, fd_write::<Memory64>),
+        "fd_link" => Function::new_typed_with_env(&mut store, env, fd_link::<Memory64>),
         "fd_pipe" => Function::new_typed_with_env(&mut store, env, fd_pipe::<Memory64>),
         "path_create_directory" => Function::new_typed_with_env(&mut store, env, path_create_directory::<Memory64>),
         "path_filestat_get" => Function::new_typed_with_env(&mut store, env, path_filestat_get::<Memory64>),
@@ -695,6 +782,7 @@ fn wasix_exports_64(mut store: &mut impl AsStoreMut, env: &FunctionEnv<WasiEnv>)
         "path_link" => Function::new_typed_with_env(&mut store, env, path_link::<Memory64>),
         "path_open" => Function::new_typed_with_env(&mut store, env, path_open::<Memory64>),
         "path_open2" => Function::new_typed_with_env(&mut store, env, path_open2::<Memory64>),
+        "path_open_tmpfile" => Function::new_typed_with_env(&mut store, env, path_open_tmpfile::<Memory64>),
         "path_readlink" => Function::new_typed_with_env(&mut store, env, path_readlink::<Memory64>),
         "path_remove_directory" => Function::new_typed_with_env(&mut store, env, path_remove_directory::<Memory64>),
         "path_rename" => Function::new_typed_with_env(&mut store, env, path_rename::<Memory64>),
@@ -717,6 +805,14 @@ fn wasix_exports_64(mut store: &mut impl AsStoreMut, env: &FunctionEnv<WasiEnv>)
         "proc_spawn2" => Function::new_typed_with_env(&mut store, env, proc_spawn2::<Memory64>),
         "proc_id" => Function::new_typed_with_env(&mut store, env, proc_id::<Memory64>),
         "proc_parent" => Function::new_typed_with_env(&mut store, env, proc_parent::<Memory64>),
+        "proc_daemonize" => Function::new_typed_with_env(&mut store, env, proc_daemonize::<Memory64>),
+        "proc_package_metadata" => Function::new_typed_with_env(&mut store, env, proc_package_metadata::<Memory64>),
+        "kv_get" => Function::new_typed_with_env(&mut store, env, kv_get::<Memory64>),
+        "kv_put" => Function::new_typed_with_env(&mut store, env, kv_put::<Memory64>),
+        "kv_delete" => Function::new_typed_with_env(&mut store, env, kv_delete::<Memory64>),
+        "kv_scan" => Function::new_typed_with_env(&mut store, env, kv_scan::<Memory64>),
+        "archive_extract" => Function::new_typed_with_env(&mut store, env, archive_extract::<Memory64>),
+        "archive_extract_poll" => Function::new_typed_with_env(&mut store, env, archive_extract_poll::<Memory64>),
         "random_get" => Function::new_typed_with_env(&mut store, env, random_get::<Memory64>),
         "tty_get" => Function::new_typed_with_env(&mut store, env, tty_get::<Memory64>),
         "tty_set" => Function::new_typed_with_env(&mut store, env, tty_set::<Memory64>),
@@ -733,6 +829,7 @@ fn wasix_exports_64(mut store: &mut impl AsStoreMut, env: &FunctionEnv<WasiEnv>)
         "thread_signal" => Function::new_typed_with_env(&mut store, env, thread_signal),
         "thread_join" => Function::new_typed_with_env(&mut store, env, thread_join::<Memory64>),
         "thread_parallelism" => Function::new_typed_with_env(&mut store, env, thread_parallelism::<Memory64>),
+        "cpu_features" => Function::new_typed_with_env(&mut store, env, cpu_features::<Memory64>),
         "thread_exit" => Function::new_typed_with_env(&mut store, env, thread_exit),
         "sched_yield" => Function::new_typed_with_env(&mut store, env, sched_yield::<Memory64>),
         "stack_checkpoint" => Function::new_typed_with_env(&mut store, env, stack_checkpoint::<Memory64>),
@@ -764,6 +861,8 @@ fn wasix_exports_64(mut store: &mut impl AsStoreMut, env: &FunctionEnv<WasiEnv>)
         "sock_get_opt_time" => Function::new_typed_with_env(&mut store, env, sock_get_opt_time::<Memory64>),
         "sock_set_opt_size" => Function::new_typed_with_env(&mut store, env, sock_set_opt_size),
         "sock_get_opt_size" => Function::new_typed_with_env(&mut store, env, sock_get_opt_size::<Memory64>),
+        "sock_set_opt_bytes" => Function::new_typed_with_env(&mut store, env, sock_set_opt_bytes::<Memory64>),
+        "sock_get_opt_bytes" => Function::new_typed_with_env(&mut store, env, sock_get_opt_bytes::<Memory64>),
         "sock_join_multicast_v4" => Function::new_typed_with_env(&mut store, env, sock_join_multicast_v4::<Memory64>),
         "sock_leave_multicast_v4" => Function::new_typed_with_env(&mut store, env, sock_leave_multicast_v4::<Memory64>),
         "sock_join_multicast_v6" => Function::new_typed_with_env(&mut store, env, sock_join_multicast_v6::<Memory64>),
@@ -779,6 +878,7 @@ fn wasix_exports_64(mut store: &mut impl AsStoreMut, env: &FunctionEnv<WasiEnv>)
         "sock_send_to" => Function::new_typed_with_env(&mut store, env, sock_send_to::<Memory64>),
         "sock_send_file" => Function::new_typed_with_env(&mut store, env, sock_send_file::<Memory64>),
         "sock_shutdown" => Function::new_typed_with_env(&mut store, env, sock_shutdown),
+        "sock_tls_upgrade" => Function::new_typed_with_env(&mut store, env, sock_tls_upgrade::<Memory64>),
         "resolve" => Function::new_typed_with_env(&mut store, env, resolve::<Memory64>),
     };
     namespace
@@ -806,10 +906,53 @@ fn import_object_for_all_wasi_versions(
         "wasix_32v1" => exports_wasix_32v1,
         "wasix_64v1" => exports_wasix_64v1,
     };
+    #[cfg(feature = "wasi-crypto")]
+    register_wasi_crypto(&mut imports, store, env);
 
     imports
 }
 
+/// Adds the namespaces of the wasi-crypto signatures API.
+#[cfg(feature = "wasi-crypto")]
+fn register_wasi_crypto(
+    imports: &mut Imports,
+    mut store: &mut impl AsStoreMut,
+    env: &FunctionEnv<WasiEnv>,
+) {
+    use syscalls::{wasi_crypto::*, Function};
+    let common = namespace! {
+        "array_output_len" => Function::new_typed_with_env(&mut store, env, array_output_len::<Memory32>),
+        "array_output_pull" => Function::new_typed_with_env(&mut store, env, array_output_pull::<Memory32>),
+    };
+    let asymmetric_common = namespace! {
+        "keypair_generate" => Function::new_typed_with_env(&mut store, env, keypair_generate::<Memory32>),
+        "keypair_import" => Function::new_typed_with_env(&mut store, env, keypair_import::<Memory32>),
+        "keypair_publickey" => Function::new_typed_with_env(&mut store, env, keypair_publickey::<Memory32>),
+        "keypair_export" => Function::new_typed_with_env(&mut store, env, keypair_export::<Memory32>),
+        "keypair_close" => Function::new_typed_with_env(&mut store, env, keypair_close),
+        "publickey_import" => Function::new_typed_with_env(&mut store, env, publickey_import::<Memory32>),
+        "publickey_export" => Function::new_typed_with_env(&mut store, env, publickey_export::<Memory32>),
+        "publickey_close" => Function::new_typed_with_env(&mut store, env, publickey_close),
+    };
+    let signatures = namespace! {
+        "signature_export" => Function::new_typed_with_env(&mut store, env, signature_export::<Memory32>),
+        "signature_import" => Function::new_typed_with_env(&mut store, env, signature_import::<Memory32>),
+        "signature_close" => Function::new_typed_with_env(&mut store, env, signature_close),
+        "signature_state_open" => Function::new_typed_with_env(&mut store, env, signature_state_open::<Memory32>),
+        "signature_state_update" => Function::new_typed_with_env(&mut store, env, signature_state_update::<Memory32>),
+        "signature_state_sign" => Function::new_typed_with_env(&mut store, env, signature_state_sign::<Memory32>),
+        "signature_state_close" => Function::new_typed_with_env(&mut store, env, signature_state_close),
+        "signature_verification_state_open" => Function::new_typed_with_env(&mut store, env, signature_verification_state_open::<Memory32>),
+        "signature_verification_state_update" => Function::new_typed_with_env(&mut store, env, signature_verification_state_update::<Memory32>),
+        "signature_verification_state_verify" => Function::new_typed_with_env(&mut store, env, signature_verification_state_verify),
+        "signature_verification_state_close" => Function::new_typed_with_env(&mut store, env, signature_verification_state_close),
+    };
+
+    imports.register_namespace("wasi_ephemeral_crypto_common", common);
+    imports.register_namespace("wasi_ephemeral_crypto_asymmetric_common", asymmetric_common);
+    imports.register_namespace("wasi_ephemeral_crypto_signatures", signatures);
+}
+
 /// Combines a state generating function with the import list for legacy WASI
 fn generate_import_object_snapshot0(
     store: &mut impl AsStoreMut,
@@ -857,6 +1000,7 @@ fn mem_error_to_wasi(err: MemoryAccessError) -> Errno {
         MemoryAccessError::HeapOutOfBounds => Errno::Memviolation,
         MemoryAccessError::Overflow => Errno::Overflow,
         MemoryAccessError::NonUtf8String => Errno::Inval,
+        MemoryAccessError::InteriorNul => Errno::Inval,
         _ => Errno::Unknown,
     }
 }
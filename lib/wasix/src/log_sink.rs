@@ -0,0 +1,334 @@
+//! Capturing the runtime's logs for a single process.
+//!
+//! A process built with [`WasiEnvBuilder::with_log_sink()`] runs its threads
+//! inside a span under the [`TARGET`] target, which carries the process's
+//! `pid` and the `log_sink` its events go to. With the `log-sink` feature,
+//! [`LogSinkLayer`] passes every event inside such a span to its
+//! [`LogSink`], and [`LogSinkLayer::exclude_captured()`] keeps the other
+//! layers of the subscriber from seeing them:
+//!
+//! ```rust,ignore
+//! use tracing_subscriber::prelude::*;
+//! use wasmer_wasix::log_sink::LogSinkLayer;
+//!
+//! tracing_subscriber::registry()
+//!     .with(LogSinkLayer::new())
+//!     .with(
+//!         tracing_subscriber::fmt::layer()
+//!             .with_filter(tracing_subscriber::EnvFilter::from_default_env())
+//!             .with_filter(LogSinkLayer::exclude_captured()),
+//!     )
+//!     .init();
+//! ```
+//!
+//! Filters installed for the whole subscriber apply before anything is
+//! captured, so filtering the other layers individually (as above) is what
+//! lets a process be traced in detail while everything else stays quiet.
+//!
+//! [`WasiEnvBuilder::with_log_sink()`]: crate::WasiEnvBuilder::with_log_sink
+
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, LazyLock, Mutex, RwLock,
+    },
+};
+
+use tokio::io::AsyncWriteExt;
+use tracing::{Level, Span};
+use virtual_fs::VirtualFile;
+use virtual_mio::InlineWaker;
+
+use crate::WasiProcess;
+
+/// The target of the spans processes with a [`LogSink`] run in.
+pub const TARGET: &str = "wasmer_wasix::log_sink";
+
+/// An event emitted by the runtime on behalf of a process.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogRecord {
+    /// The process the event belongs to.
+    pub pid: u32,
+    /// The level of the event.
+    pub level: Level,
+    /// The target of the event, usually the module that emitted it.
+    pub target: String,
+    /// The event's message followed by its other fields, as `name=value`.
+    pub message: String,
+}
+
+/// Where the events of a process go, see
+/// [`WasiEnvBuilder::with_log_sink()`](crate::WasiEnvBuilder::with_log_sink).
+#[derive(Clone)]
+pub struct LogSink(Arc<dyn Fn(&LogRecord) + Send + Sync>);
+
+impl LogSink {
+    /// Passes every event to `callback`.
+    pub fn new(callback: impl Fn(&LogRecord) + Send + Sync + 'static) -> Self {
+        LogSink(Arc::new(callback))
+    }
+
+    /// Writes every event to `file`, one line each.
+    pub fn file(file: Box<dyn VirtualFile + Send + Sync + 'static>) -> Self {
+        let file = Mutex::new(file);
+        LogSink::new(move |record| {
+            let line = format!(
+                "{} {} {}: {}\n",
+                record.level, record.pid, record.target, record.message
+            );
+            let mut file = file.lock().unwrap();
+            // Failing to write a log line isn't worth failing the process for
+            let _ = InlineWaker::block_on(file.write_all(line.as_bytes()));
+        })
+    }
+
+    pub fn log(&self, record: &LogRecord) {
+        (self.0)(record)
+    }
+}
+
+impl<F> From<F> for LogSink
+where
+    F: Fn(&LogRecord) + Send + Sync + 'static,
+{
+    fn from(callback: F) -> Self {
+        LogSink::new(callback)
+    }
+}
+
+impl std::fmt::Debug for LogSink {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("LogSink").finish_non_exhaustive()
+    }
+}
+
+/// The sinks of all processes that are still around, by the ID their spans
+/// refer to them with. Process IDs can't be used, as every control plane
+/// hands out its own.
+static SINKS: LazyLock<RwLock<HashMap<u64, LogSink>>> = LazyLock::new(Default::default);
+
+static NEXT_SINK_ID: AtomicU64 = AtomicU64::new(1);
+
+/// Keeps a process's [`LogSink`] registered for as long as the process is
+/// around.
+#[derive(Debug)]
+pub(crate) struct LogSinkHandle {
+    id: u64,
+}
+
+impl LogSinkHandle {
+    pub(crate) fn register(sink: LogSink) -> Self {
+        let id = NEXT_SINK_ID.fetch_add(1, Ordering::Relaxed);
+        SINKS.write().unwrap().insert(id, sink);
+        LogSinkHandle { id }
+    }
+}
+
+impl Drop for LogSinkHandle {
+    fn drop(&mut self) {
+        SINKS.write().unwrap().remove(&self.id);
+    }
+}
+
+/// The span a thread of `process` runs in, if its events are captured.
+///
+/// The span is at the `ERROR` level so that it isn't filtered out along with
+/// the (usually much more detailed) events it is meant to capture.
+pub(crate) fn process_span(process: &WasiProcess) -> Span {
+    match &process.log_sink {
+        Some(handle) => tracing::error_span!(
+            target: TARGET,
+            "log_sink",
+            pid = process.pid().raw(),
+            log_sink = handle.id,
+        ),
+        None => Span::none(),
+    }
+}
+
+#[cfg(feature = "log-sink")]
+pub use self::layer::{ExcludeCaptured, LogSinkLayer};
+
+#[cfg(feature = "log-sink")]
+mod layer {
+    use std::{cell::Cell, fmt::Write as _};
+
+    use tracing::{
+        field::{Field, Visit},
+        span::{Attributes, Id},
+        Event, Metadata, Subscriber,
+    };
+    use tracing_subscriber::{
+        layer::{Context, Filter},
+        registry::LookupSpan,
+        Layer,
+    };
+
+    use super::*;
+
+    thread_local! {
+        /// Set while an event is being passed to a sink, so that events
+        /// emitted by the sink itself (e.g. by the file system it writes to)
+        /// don't end up in it again.
+        static CAPTURING: Cell<bool> = const { Cell::new(false) };
+    }
+
+    /// Where the events inside a span go, stored in the span's extensions.
+    #[derive(Debug, Clone)]
+    struct Captured {
+        pid: u32,
+        sink: LogSink,
+    }
+
+    /// A [`Layer`] passing the events of processes to their [`LogSink`].
+    #[derive(Debug, Default, Clone, Copy)]
+    pub struct LogSinkLayer {
+        _private: (),
+    }
+
+    impl LogSinkLayer {
+        pub fn new() -> Self {
+            LogSinkLayer::default()
+        }
+
+        /// A per-layer [`Filter`] hiding the events this layer captures.
+        pub fn exclude_captured() -> ExcludeCaptured {
+            ExcludeCaptured { _private: () }
+        }
+    }
+
+    impl<S> Layer<S> for LogSinkLayer
+    where
+        S: Subscriber + for<'a> LookupSpan<'a>,
+    {
+        fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+            let Some(span) = ctx.span(id) else {
+                return;
+            };
+
+            let captured = if attrs.metadata().target() == TARGET {
+                let mut fields = SinkFields::default();
+                attrs.record(&mut fields);
+                let sink = fields
+                    .log_sink
+                    .and_then(|id| SINKS.read().unwrap().get(&id).cloned());
+                fields
+                    .pid
+                    .zip(sink)
+                    .map(|(pid, sink)| Captured { pid, sink })
+            } else {
+                // Spans inside a captured span are captured too
+                span.parent()
+                    .and_then(|parent| parent.extensions().get::<Captured>().cloned())
+            };
+
+            if let Some(captured) = captured {
+                span.extensions_mut().insert(captured);
+            }
+        }
+
+        fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+            let Some(captured) = ctx
+                .event_span(event)
+                .and_then(|span| span.extensions().get::<Captured>().cloned())
+            else {
+                return;
+            };
+            if CAPTURING.get() {
+                return;
+            }
+
+            let mut message = Message::default();
+            event.record(&mut message);
+            let record = LogRecord {
+                pid: captured.pid,
+                level: *event.metadata().level(),
+                target: event.metadata().target().to_string(),
+                message: message.into_string(),
+            };
+
+            CAPTURING.set(true);
+            captured.sink.log(&record);
+            CAPTURING.set(false);
+        }
+    }
+
+    /// A per-layer [`Filter`] hiding the events [`LogSinkLayer`] captures,
+    /// see [`LogSinkLayer::exclude_captured()`].
+    #[derive(Debug, Clone, Copy)]
+    pub struct ExcludeCaptured {
+        _private: (),
+    }
+
+    impl<S> Filter<S> for ExcludeCaptured
+    where
+        S: Subscriber + for<'a> LookupSpan<'a>,
+    {
+        fn enabled(&self, metadata: &Metadata<'_>, cx: &Context<'_, S>) -> bool {
+            if !metadata.is_event() {
+                return true;
+            }
+            match cx.lookup_current() {
+                Some(span) => span.extensions().get::<Captured>().is_none(),
+                None => true,
+            }
+        }
+    }
+
+    #[derive(Default)]
+    struct SinkFields {
+        pid: Option<u32>,
+        log_sink: Option<u64>,
+    }
+
+    impl Visit for SinkFields {
+        fn record_u64(&mut self, field: &Field, value: u64) {
+            match field.name() {
+                "pid" => self.pid = u32::try_from(value).ok(),
+                "log_sink" => self.log_sink = Some(value),
+                _ => {}
+            }
+        }
+
+        fn record_debug(&mut self, _field: &Field, _value: &dyn std::fmt::Debug) {}
+    }
+
+    /// An event's message and its other fields.
+    #[derive(Default)]
+    struct Message {
+        message: String,
+        fields: String,
+    }
+
+    impl Message {
+        fn into_string(self) -> String {
+            match (self.message.is_empty(), self.fields.is_empty()) {
+                (_, true) => self.message,
+                (true, false) => self.fields,
+                (false, false) => format!("{} {}", self.message, self.fields),
+            }
+        }
+    }
+
+    impl Visit for Message {
+        fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+            if field.name() == "message" {
+                let _ = write!(self.message, "{value:?}");
+            } else {
+                if !self.fields.is_empty() {
+                    self.fields.push(' ');
+                }
+                let _ = write!(self.fields, "{}={value:?}", field.name());
+            }
+        }
+
+        fn record_str(&mut self, field: &Field, value: &str) {
+            if field.name() == "message" {
+                self.message.push_str(value);
+            } else {
+                self.record_debug(field, &value);
+            }
+        }
+    }
+}
@@ -0,0 +1,166 @@
+use std::{
+    net::SocketAddr,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    task::{Context, Poll},
+};
+
+use virtual_mio::ArcInterestHandler;
+use virtual_net::{
+    loopback::LoopbackTcpListener, tcp_pair::TcpSocketHalf, InterestHandler, NetworkError,
+    VirtualIoSource, VirtualTcpListener, VirtualTcpSocket,
+};
+
+use crate::{
+    capabilities::CapabilityListenV1,
+    os::task::control_plane::{WasiControlPlane, WasiControlPlaneHandle},
+    Runtime,
+};
+
+/// A TCP listener opened by a guest, which the host can hand connections to
+/// without them going through the network.
+///
+/// Handles are given to the host through [`Runtime::on_guest_listener()`]
+/// and can also be looked up with [`WasiControlPlane::guest_listener()`].
+#[derive(Debug, Clone)]
+pub struct GuestListener {
+    addr: SocketAddr,
+    queue: LoopbackTcpListener,
+    closed: Arc<AtomicBool>,
+}
+
+impl GuestListener {
+    /// The address the guest is listening on.
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Whether the guest has closed the listener, after which it won't
+    /// accept any more connections.
+    pub fn is_closed(&self) -> bool {
+        self.closed.load(Ordering::SeqCst)
+    }
+
+    /// Queues a connection from `peer`, which the guest receives the next
+    /// time it accepts one, and returns the host's end of it.
+    ///
+    /// The returned socket can be [split](TcpSocketHalf::split) into halves
+    /// that implement `AsyncRead` and `AsyncWrite`.
+    pub fn connect(&self, peer: SocketAddr) -> Result<TcpSocketHalf, NetworkError> {
+        if self.is_closed() {
+            return Err(NetworkError::ConnectionRefused);
+        }
+        Ok(self.queue.connect_to(peer))
+    }
+
+    pub(crate) fn is_same(&self, other: &GuestListener) -> bool {
+        Arc::ptr_eq(&self.closed, &other.closed)
+    }
+}
+
+/// Wraps the listeners guests open on ports that [`CapabilityListenV1`]
+/// exposes, so the host can inject connections into them.
+#[derive(Debug, Clone)]
+pub(crate) struct ListenerExposer {
+    pub capability: CapabilityListenV1,
+    pub control_plane: WasiControlPlane,
+    pub runtime: Arc<dyn Runtime + Send + Sync>,
+}
+
+impl ListenerExposer {
+    /// Wraps `inner` if its port is exposed, registers it with the control
+    /// plane and tells the runtime about it.
+    pub fn expose(
+        &self,
+        inner: Box<dyn VirtualTcpListener + Sync>,
+    ) -> Box<dyn VirtualTcpListener + Sync> {
+        let addr = match inner.addr_local() {
+            Ok(addr) if self.capability.exposes(addr.port()) => addr,
+            _ => return inner,
+        };
+
+        let listener = GuestListener {
+            addr,
+            queue: LoopbackTcpListener::new(addr),
+            closed: Arc::new(AtomicBool::new(false)),
+        };
+        self.control_plane.register_guest_listener(listener.clone());
+        self.runtime.on_guest_listener(addr, listener.clone());
+
+        Box::new(ExposedTcpListener {
+            inner,
+            listener,
+            control_plane: self.control_plane.handle(),
+        })
+    }
+}
+
+/// A guest's listener that also accepts the connections the host queues
+/// through its [`GuestListener`].
+#[derive(Debug)]
+struct ExposedTcpListener {
+    inner: Box<dyn VirtualTcpListener + Sync>,
+    listener: GuestListener,
+    control_plane: WasiControlPlaneHandle,
+}
+
+impl Drop for ExposedTcpListener {
+    fn drop(&mut self) {
+        self.listener.closed.store(true, Ordering::SeqCst);
+        if let Some(control_plane) = self.control_plane.upgrade() {
+            control_plane.unregister_guest_listener(&self.listener);
+        }
+    }
+}
+
+impl VirtualIoSource for ExposedTcpListener {
+    fn remove_handler(&mut self) {
+        self.listener.queue.remove_handler();
+        self.inner.remove_handler();
+    }
+
+    fn poll_read_ready(&mut self, cx: &mut Context<'_>) -> Poll<virtual_net::Result<usize>> {
+        if let Poll::Ready(ready) = self.listener.queue.poll_read_ready(cx) {
+            return Poll::Ready(ready);
+        }
+        self.inner.poll_read_ready(cx)
+    }
+
+    fn poll_write_ready(&mut self, cx: &mut Context<'_>) -> Poll<virtual_net::Result<usize>> {
+        self.inner.poll_write_ready(cx)
+    }
+}
+
+impl VirtualTcpListener for ExposedTcpListener {
+    fn try_accept(
+        &mut self,
+    ) -> virtual_net::Result<(Box<dyn VirtualTcpSocket + Sync>, SocketAddr)> {
+        match self.listener.queue.try_accept() {
+            Err(NetworkError::WouldBlock) => self.inner.try_accept(),
+            ret => ret,
+        }
+    }
+
+    fn set_handler(
+        &mut self,
+        handler: Box<dyn InterestHandler + Send + Sync>,
+    ) -> virtual_net::Result<()> {
+        let handler = ArcInterestHandler::new(handler);
+        self.listener.queue.set_handler(Box::new(handler.clone()))?;
+        self.inner.set_handler(Box::new(handler))
+    }
+
+    fn addr_local(&self) -> virtual_net::Result<SocketAddr> {
+        self.inner.addr_local()
+    }
+
+    fn set_ttl(&mut self, ttl: u8) -> virtual_net::Result<()> {
+        self.inner.set_ttl(ttl)
+    }
+
+    fn ttl(&self) -> virtual_net::Result<u8> {
+        self.inner.ttl()
+    }
+}
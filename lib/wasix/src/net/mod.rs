@@ -4,7 +4,7 @@ use std::{
     time::Duration,
 };
 
-use virtual_net::{IpCidr, IpRoute, NetworkError};
+use virtual_net::{IpCidr, IpRoute};
 use wasmer::{MemoryView, WasmPtr};
 use wasmer_types::MemorySize;
 use wasmer_wasix_types::{
@@ -16,7 +16,12 @@ use wasmer_wasix_types::{
     wasi::{Addressfamily, Errno},
 };
 
+pub use crate::errno::{net_error_from_wasi_err, net_error_into_wasi_err};
+
+pub mod listener;
 pub mod socket;
+#[cfg(feature = "host-tls")]
+pub mod tls;
 
 #[allow(dead_code)]
 pub(crate) fn read_ip<M: MemorySize>(
@@ -369,32 +374,3 @@ pub(crate) fn write_route<M: MemorySize>(
     route_ptr.write(route).map_err(crate::mem_error_to_wasi)?;
     Ok(())
 }
-
-pub fn net_error_into_wasi_err(net_error: NetworkError) -> Errno {
-    match net_error {
-        NetworkError::InvalidFd => Errno::Badf,
-        NetworkError::AlreadyExists => Errno::Exist,
-        NetworkError::Lock => Errno::Io,
-        NetworkError::IOError => Errno::Io,
-        NetworkError::AddressInUse => Errno::Addrinuse,
-        NetworkError::AddressNotAvailable => Errno::Addrnotavail,
-        NetworkError::BrokenPipe => Errno::Pipe,
-        NetworkError::ConnectionAborted => Errno::Connaborted,
-        NetworkError::ConnectionRefused => Errno::Connrefused,
-        NetworkError::ConnectionReset => Errno::Connreset,
-        NetworkError::Interrupted => Errno::Intr,
-        NetworkError::InvalidData => Errno::Io,
-        NetworkError::InvalidInput => Errno::Inval,
-        NetworkError::NotConnected => Errno::Notconn,
-        NetworkError::NoDevice => Errno::Nodev,
-        NetworkError::PermissionDenied => Errno::Perm,
-        NetworkError::TimedOut => Errno::Timedout,
-        NetworkError::UnexpectedEof => Errno::Proto,
-        NetworkError::WouldBlock => Errno::Again,
-        NetworkError::WriteZero => Errno::Nospc,
-        NetworkError::TooManyOpenFiles => Errno::Mfile,
-        NetworkError::InsufficientMemory => Errno::Nomem,
-        NetworkError::Unsupported => Errno::Notsup,
-        NetworkError::UnknownError => Errno::Io,
-    }
-}
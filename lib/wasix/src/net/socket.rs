@@ -11,7 +11,7 @@ use std::{
 
 #[cfg(feature = "enable-serde")]
 use serde_derive::{Deserialize, Serialize};
-use virtual_mio::InterestHandler;
+use virtual_mio::{InterestHandler, InterestType};
 use virtual_net::{
     net_error_into_io_err, NetworkError, VirtualIcmpSocket, VirtualNetworking, VirtualRawSocket,
     VirtualTcpListener, VirtualTcpSocket, VirtualUdpSocket,
@@ -19,7 +19,7 @@ use virtual_net::{
 use wasmer_types::MemorySize;
 use wasmer_wasix_types::wasi::{Addressfamily, Errno, Rights, SockProto, Sockoption, Socktype};
 
-use crate::{net::net_error_into_wasi_err, VirtualTaskManager};
+use crate::{errno::net_error_into_wasi_err, net::listener::ListenerExposer, VirtualTaskManager};
 
 #[derive(Debug)]
 #[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
@@ -112,6 +112,7 @@ pub enum WasiSocketOption {
     MulticastTtlV4,
     Type,
     Proto,
+    TlsAlpn,
 }
 
 impl From<Sockoption> for WasiSocketOption {
@@ -145,6 +146,7 @@ impl From<Sockoption> for WasiSocketOption {
             Sockoption::MulticastTtlV4 => MulticastTtlV4,
             Sockoption::Type => Type,
             Sockoption::Proto => Proto,
+            Sockoption::TlsAlpn => TlsAlpn,
         }
     }
 }
@@ -199,6 +201,14 @@ impl From<wasmer_journal::SocketOptTimeType> for TimeType {
 //#[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
 pub(crate) struct InodeSocketProtected {
     pub kind: InodeSocketKind,
+    /// Error of an asynchronous operation (e.g. a nonblocking connect)
+    /// that hasn't been read with `SO_ERROR` yet
+    pub last_error: Option<Errno>,
+    /// Protocols offered through ALPN when the socket is upgraded to TLS
+    pub alpn_protocols: Vec<Vec<u8>>,
+    /// Set once the socket was upgraded to TLS, holding the protocol the
+    /// server selected through ALPN (if any)
+    pub tls_alpn_protocol: Option<Option<Vec<u8>>>,
 }
 
 #[derive(Debug)]
@@ -215,7 +225,12 @@ pub struct InodeSocket {
 
 impl InodeSocket {
     pub fn new(kind: InodeSocketKind) -> Self {
-        let protected = InodeSocketProtected { kind };
+        let protected = InodeSocketProtected {
+            kind,
+            last_error: None,
+            alpn_protocols: Vec::new(),
+            tls_alpn_protocol: None,
+        };
         Self {
             inner: Arc::new(InodeSocketInner {
                 protected: RwLock::new(protected),
@@ -389,11 +404,12 @@ impl InodeSocket {
         }
     }
 
-    pub async fn listen(
+    pub(crate) async fn listen(
         &self,
         tasks: &dyn VirtualTaskManager,
         net: &dyn VirtualNetworking,
         _backlog: usize,
+        exposer: &ListenerExposer,
     ) -> Result<Option<InodeSocket>, Errno> {
         let timeout = self
             .opt_time(TimeType::AcceptTimeout)
@@ -477,7 +493,7 @@ impl InodeSocket {
             socket = socket => {
                 let socket = socket.map_err(net_error_into_wasi_err)?;
                 Ok(Some(InodeSocket::new(InodeSocketKind::TcpListener {
-                    socket,
+                    socket: exposer.expose(socket),
                     accept_timeout: Some(timeout),
                 })))
             },
@@ -582,8 +598,10 @@ impl InodeSocket {
         let timeout = timeout.unwrap_or(Duration::from_secs(30));
 
         let handler;
+        let alpn_protocols;
         let connect = {
             let mut inner = self.inner.protected.write().unwrap();
+            alpn_protocols = inner.alpn_protocols.clone();
             match &mut inner.kind {
                 InodeSocketKind::PreSocket { props, addr, .. } => {
                     handler = props.handler.take();
@@ -617,6 +635,9 @@ impl InodeSocket {
                                 }
                                 if !nonblocking {
                                     futures::future::poll_fn(|cx| ret.poll_write_ready(cx)).await?;
+                                    if let Some(err) = ret.take_error()? {
+                                        return Err(err);
+                                    }
                                 }
                                 Ok(ret)
                             })
@@ -639,9 +660,32 @@ impl InodeSocket {
             }
         };
 
-        let mut socket = tokio::select! {
-            res = connect => res.map_err(net_error_into_wasi_err)?,
-            _ = tasks.sleep_now(timeout) => return Err(Errno::Timedout)
+        let res = tokio::select! {
+            res = connect => res,
+            _ = tasks.sleep_now(timeout) => Err(NetworkError::TimedOut)
+        };
+        let mut socket = match res {
+            Ok(socket) => socket,
+            // Nonblocking connects report why they failed through `SO_ERROR`
+            Err(
+                err @ (NetworkError::ConnectionRefused
+                | NetworkError::ConnectionReset
+                | NetworkError::ConnectionAborted
+                | NetworkError::TimedOut
+                | NetworkError::Unreachable),
+            ) if nonblocking => {
+                let mut inner = self.inner.protected.write().unwrap();
+                inner.last_error = Some(net_error_into_wasi_err(err));
+                if let InodeSocketKind::PreSocket { props, .. } = &mut inner.kind {
+                    if let Some(mut handler) = handler {
+                        handler.push_interest(InterestType::Writable);
+                        handler.push_interest(InterestType::Error);
+                        props.handler.replace(handler);
+                    }
+                }
+                return Ok(None);
+            }
+            Err(err) => return Err(net_error_into_wasi_err(err)),
         };
 
         if let Some(handler) = handler {
@@ -655,10 +699,131 @@ impl InodeSocket {
             write_timeout: new_write_timeout,
             read_timeout: new_read_timeout,
         });
+        socket.inner.protected.write().unwrap().alpn_protocols = alpn_protocols;
 
         Ok(Some(socket))
     }
 
+    /// Upgrades a connected TCP socket to TLS, after which everything sent
+    /// and received through it is encrypted. The handshake completes before
+    /// this returns, even for nonblocking sockets.
+    #[cfg(feature = "host-tls")]
+    pub async fn upgrade_tls(
+        &self,
+        tasks: &dyn VirtualTaskManager,
+        roots: Arc<rustls::RootCertStore>,
+        insecure_skip_verify: bool,
+        server_name: &str,
+    ) -> Result<(), Errno> {
+        use crate::net::tls::{client_config, tls_error_into_net_error, TlsTcpSocket};
+
+        let server_name = rustls::pki_types::ServerName::try_from(server_name.to_string())
+            .map_err(|_| Errno::Inval)?;
+
+        let (mut tls, write_timeout, read_timeout) = {
+            let mut inner = self.inner.protected.write().unwrap();
+            if inner.tls_alpn_protocol.is_some() {
+                return Err(Errno::Already);
+            }
+            match &inner.kind {
+                InodeSocketKind::TcpStream { .. } => {}
+                InodeSocketKind::PreSocket { .. } => return Err(Errno::Notconn),
+                _ => return Err(Errno::Notsup),
+            }
+
+            let config = client_config(roots, insecure_skip_verify, inner.alpn_protocols.clone())
+                .map_err(|err| net_error_into_wasi_err(tls_error_into_net_error(err)))?;
+            let conn = rustls::ClientConnection::new(Arc::new(config), server_name)
+                .map_err(|err| net_error_into_wasi_err(tls_error_into_net_error(err)))?;
+
+            // The socket is taken out of the inode while the handshake runs
+            let InodeSocketKind::TcpStream {
+                socket,
+                write_timeout,
+                read_timeout,
+            } = std::mem::replace(&mut inner.kind, Self::handshake_placeholder())
+            else {
+                unreachable!()
+            };
+            inner.tls_alpn_protocol = Some(None);
+            (TlsTcpSocket::new(socket, conn), write_timeout, read_timeout)
+        };
+        let timeout = write_timeout
+            .max(read_timeout)
+            .unwrap_or(Duration::from_secs(30));
+
+        let handshake = futures::future::poll_fn(|cx| tls.poll_handshake(cx));
+        let res = tokio::select! {
+            res = handshake => res,
+            _ = tasks.sleep_now(timeout) => Err(NetworkError::TimedOut)
+        };
+
+        // Only switch to the TLS session once the handshake succeeded, the
+        // socket stays a plain TCP stream otherwise
+        let mut inner = self.inner.protected.write().unwrap();
+        let socket: Box<dyn VirtualTcpSocket + Sync> = match res {
+            Ok(()) => {
+                inner.tls_alpn_protocol = Some(tls.alpn_protocol().map(|p| p.to_vec()));
+                Box::new(tls)
+            }
+            Err(_) => {
+                inner.tls_alpn_protocol = None;
+                tls.into_inner()
+            }
+        };
+        inner.kind = InodeSocketKind::TcpStream {
+            socket,
+            write_timeout,
+            read_timeout,
+        };
+        res.map_err(net_error_into_wasi_err)
+    }
+
+    /// What a socket looks like to other threads while its TLS handshake
+    /// is in progress (i.e. not connected)
+    #[cfg(feature = "host-tls")]
+    fn handshake_placeholder() -> InodeSocketKind {
+        InodeSocketKind::PreSocket {
+            props: SocketProperties {
+                family: Addressfamily::Unspec,
+                ty: Socktype::Stream,
+                pt: SockProto::Tcp,
+                only_v6: false,
+                reuse_port: false,
+                reuse_addr: false,
+                no_delay: None,
+                keep_alive: None,
+                dont_route: None,
+                send_buf_size: None,
+                recv_buf_size: None,
+                write_timeout: None,
+                read_timeout: None,
+                accept_timeout: None,
+                connect_timeout: None,
+                handler: None,
+            },
+            addr: None,
+        }
+    }
+
+    /// Sets the protocols offered through ALPN when the socket is upgraded
+    /// to TLS, in order of preference
+    pub fn set_alpn_protocols(&self, protocols: Vec<Vec<u8>>) -> Result<(), Errno> {
+        let mut inner = self.inner.protected.write().unwrap();
+        if inner.tls_alpn_protocol.is_some() {
+            return Err(Errno::Isconn);
+        }
+        inner.alpn_protocols = protocols;
+        Ok(())
+    }
+
+    /// Returns the protocol the server selected through ALPN, which is
+    /// only known once the socket was upgraded to TLS
+    pub fn alpn_protocol(&self) -> Result<Option<Vec<u8>>, Errno> {
+        let inner = self.inner.protected.read().unwrap();
+        inner.tls_alpn_protocol.clone().ok_or(Errno::Notconn)
+    }
+
     pub fn status(&self) -> Result<WasiSocketStatus, Errno> {
         let inner = self.inner.protected.read().unwrap();
         Ok(match &inner.kind {
@@ -674,6 +839,27 @@ impl InodeSocket {
         })
     }
 
+    /// Takes the error of the last asynchronous operation that failed on
+    /// this socket, which is what `SO_ERROR` reports.
+    pub fn take_last_error(&self) -> Option<Errno> {
+        let mut inner = self.inner.protected.write().unwrap();
+        inner.take_last_error()
+    }
+
+    pub fn proto(&self) -> Result<SockProto, Errno> {
+        let inner = self.inner.protected.read().unwrap();
+        Ok(match &inner.kind {
+            InodeSocketKind::PreSocket { props, .. }
+            | InodeSocketKind::RemoteSocket { props, .. } => props.pt,
+            InodeSocketKind::TcpListener { .. } | InodeSocketKind::TcpStream { .. } => {
+                SockProto::Tcp
+            }
+            InodeSocketKind::UdpSocket { .. } => SockProto::Udp,
+            InodeSocketKind::Icmp(_) => SockProto::Icmp,
+            InodeSocketKind::Raw(_) => return Err(Errno::Notsup),
+        })
+    }
+
     pub fn addr_local(&self) -> Result<SocketAddr, Errno> {
         let inner = self.inner.protected.read().unwrap();
         Ok(match &inner.kind {
@@ -1283,14 +1469,32 @@ impl InodeSocket {
         nonblocking: bool,
         peek: bool,
     ) -> Result<usize, Errno> {
-        struct SocketReceiver<'a, 'b> {
+        self.recv_vectored(tasks, &mut [buf], timeout, nonblocking, peek)
+            .await
+    }
+
+    /// Receives into each of `bufs` in turn, in the manner of `readv`.
+    ///
+    /// Only waits until some data is available: a stream fills the buffers
+    /// with whatever it has buffered and a datagram socket receives a single
+    /// message, so a buffer is only written to once the ones before it are
+    /// full.
+    pub async fn recv_vectored(
+        &self,
+        tasks: &dyn VirtualTaskManager,
+        bufs: &mut [&mut [MaybeUninit<u8>]],
+        timeout: Option<Duration>,
+        nonblocking: bool,
+        peek: bool,
+    ) -> Result<usize, Errno> {
+        struct SocketReceiver<'a, 'b, 'c> {
             inner: &'a InodeSocketInner,
-            data: &'b mut [MaybeUninit<u8>],
+            data: &'b mut [&'c mut [MaybeUninit<u8>]],
             nonblocking: bool,
             peek: bool,
             handler_registered: bool,
         }
-        impl Drop for SocketReceiver<'_, '_> {
+        impl Drop for SocketReceiver<'_, '_, '_> {
             fn drop(&mut self) {
                 if self.handler_registered {
                     let mut inner = self.inner.protected.write().unwrap();
@@ -1298,7 +1502,7 @@ impl InodeSocket {
                 }
             }
         }
-        impl Future for SocketReceiver<'_, '_> {
+        impl Future for SocketReceiver<'_, '_, '_> {
             type Output = Result<usize, Errno>;
             fn poll(
                 mut self: Pin<&mut Self>,
@@ -1308,19 +1512,19 @@ impl InodeSocket {
                     let peek = self.peek;
                     let mut inner = self.inner.protected.write().unwrap();
                     let res = match &mut inner.kind {
-                        InodeSocketKind::Raw(socket) => socket.try_recv(self.data, peek),
+                        InodeSocketKind::Raw(socket) => socket.try_recv_vectored(self.data, peek),
                         InodeSocketKind::TcpStream { socket, .. } => {
-                            socket.try_recv(self.data, peek)
+                            socket.try_recv_vectored(self.data, peek)
                         }
                         InodeSocketKind::UdpSocket { socket, peer } => {
                             if let Some(peer) = peer {
-                                match socket.try_recv_from(self.data, peek) {
+                                match socket.try_recv_from_vectored(self.data, peek) {
                                     Ok((amt, addr)) if addr == *peer => Ok(amt),
                                     Ok(_) => Err(NetworkError::WouldBlock),
                                     Err(err) => Err(err),
                                 }
                             } else {
-                                match socket.try_recv_from(self.data, peek) {
+                                match socket.try_recv_from_vectored(self.data, peek) {
                                     Ok((amt, _)) => Ok(amt),
                                     Err(err) => Err(err),
                                 }
@@ -1360,7 +1564,7 @@ impl InodeSocket {
 
         let poller = SocketReceiver {
             inner: &self.inner,
-            data: buf,
+            data: bufs,
             nonblocking,
             peek,
             handler_registered: false,
@@ -1524,7 +1728,12 @@ impl InodeSocketProtected {
     }
 
     pub fn poll_write_ready(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<usize>> {
-        match &mut self.kind {
+        // Sockets whose connection failed are writable (and hung up), so
+        // that the guest goes on to find out why
+        if self.last_error.is_some() {
+            return Poll::Ready(Ok(0));
+        }
+        let ret = match &mut self.kind {
             InodeSocketKind::TcpListener { socket, .. } => socket.poll_write_ready(cx),
             InodeSocketKind::TcpStream { socket, .. } => socket.poll_write_ready(cx),
             InodeSocketKind::UdpSocket { socket, .. } => socket.poll_write_ready(cx),
@@ -1535,8 +1744,37 @@ impl InodeSocketProtected {
                 true => Poll::Ready(Ok(0)),
                 false => Poll::Pending,
             },
+        };
+        if ret.is_ready() && self.fetch_error() {
+            return Poll::Ready(Ok(0));
         }
-        .map_err(net_error_into_io_err)
+        ret.map_err(net_error_into_io_err)
+    }
+
+    /// Whether an asynchronous operation failed and its error hasn't been
+    /// taken yet.
+    pub fn has_pending_error(&self) -> bool {
+        self.last_error.is_some()
+    }
+
+    pub fn take_last_error(&mut self) -> Option<Errno> {
+        self.fetch_error();
+        self.last_error.take()
+    }
+
+    /// Moves the pending error of the underlying socket (if any) into
+    /// `last_error`, returning whether there is one.
+    fn fetch_error(&mut self) -> bool {
+        if self.last_error.is_none() {
+            if let InodeSocketKind::TcpStream { socket, .. } = &mut self.kind {
+                self.last_error = socket
+                    .take_error()
+                    .ok()
+                    .flatten()
+                    .map(net_error_into_wasi_err);
+            }
+        }
+        self.last_error.is_some()
     }
 
     pub fn set_handler(
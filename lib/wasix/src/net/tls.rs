@@ -0,0 +1,431 @@
+//! Host side TLS for guest sockets.
+//!
+//! Guests that don't bundle a TLS stack can upgrade a connected TCP socket
+//! with `sock_tls_upgrade`, after which the handshake, encryption and
+//! certificate verification all happen on the host using [`rustls`].
+
+use std::{
+    io::{self, Read, Write},
+    mem::MaybeUninit,
+    net::{Shutdown, SocketAddr},
+    sync::{Arc, OnceLock},
+    task::{ready, Context, Poll},
+    time::Duration,
+};
+
+use rustls::{
+    client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier},
+    crypto::{verify_tls12_signature, verify_tls13_signature, CryptoProvider},
+    pki_types::{CertificateDer, ServerName, UnixTime},
+    ClientConfig, ClientConnection, DigitallySignedStruct, RootCertStore, SignatureScheme,
+};
+use virtual_mio::InterestHandler;
+use virtual_net::{
+    io_err_into_net_error, net_error_into_io_err, NetworkError, SocketStatus,
+    VirtualConnectedSocket, VirtualIoSource, VirtualSocket, VirtualTcpSocket,
+};
+
+type Result<T> = std::result::Result<T, NetworkError>;
+
+/// Size of the chunks the encrypted stream is read in
+const TLS_READ_CHUNK: usize = 16 * 1024;
+
+/// The root store used when the runtime doesn't provide one, which holds
+/// the Mozilla root certificates.
+pub fn default_root_store() -> Arc<RootCertStore> {
+    static ROOTS: OnceLock<Arc<RootCertStore>> = OnceLock::new();
+    ROOTS
+        .get_or_init(|| {
+            Arc::new(RootCertStore {
+                roots: webpki_roots::TLS_SERVER_ROOTS.to_vec(),
+            })
+        })
+        .clone()
+}
+
+/// Builds the configuration used for the client side of an upgraded socket.
+///
+/// When `insecure_skip_verify` is set the server certificate is accepted
+/// without being verified against `roots` (the handshake signatures are
+/// still checked).
+pub fn client_config(
+    roots: Arc<RootCertStore>,
+    insecure_skip_verify: bool,
+    alpn_protocols: Vec<Vec<u8>>,
+) -> std::result::Result<ClientConfig, rustls::Error> {
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let builder = ClientConfig::builder_with_provider(provider.clone())
+        .with_safe_default_protocol_versions()?;
+    let mut config = if insecure_skip_verify {
+        builder
+            .dangerous()
+            .with_custom_certificate_verifier(Arc::new(NoCertificateVerification(provider)))
+            .with_no_client_auth()
+    } else {
+        builder.with_root_certificates(roots).with_no_client_auth()
+    };
+    config.alpn_protocols = alpn_protocols;
+    Ok(config)
+}
+
+pub(crate) fn tls_error_into_net_error(err: rustls::Error) -> NetworkError {
+    tracing::debug!("TLS error - {err}");
+    match err {
+        rustls::Error::InvalidCertificate(_) | rustls::Error::NoCertificatesPresented => {
+            NetworkError::PermissionDenied
+        }
+        _ => NetworkError::InvalidData,
+    }
+}
+
+/// Accepts any server certificate, see [`client_config`].
+#[derive(Debug)]
+struct NoCertificateVerification(Arc<CryptoProvider>);
+
+impl ServerCertVerifier for NoCertificateVerification {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> std::result::Result<ServerCertVerified, rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> std::result::Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> std::result::Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.0.signature_verification_algorithms.supported_schemes()
+    }
+}
+
+/// Writes the encrypted stream into the socket underneath
+struct SocketWriter<'a>(&'a mut (dyn VirtualTcpSocket + Sync));
+
+impl Write for SocketWriter<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.try_send(buf).map_err(net_error_into_io_err)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// A TCP socket whose stream is encrypted with TLS, the plaintext is what
+/// is sent and received through it.
+#[derive(Debug)]
+pub struct TlsTcpSocket {
+    inner: Box<dyn VirtualTcpSocket + Sync>,
+    conn: ClientConnection,
+    /// Plaintext that was read from the session but not consumed yet
+    plaintext: Vec<u8>,
+}
+
+impl TlsTcpSocket {
+    /// Runs a client session on top of a connected socket, the handshake
+    /// is then driven by [`TlsTcpSocket::poll_handshake`].
+    pub fn new(inner: Box<dyn VirtualTcpSocket + Sync>, conn: ClientConnection) -> Self {
+        Self {
+            inner,
+            conn,
+            plaintext: Vec::new(),
+        }
+    }
+
+    /// Gives back the socket the session was running on, e.g. after a
+    /// failed handshake.
+    pub fn into_inner(self) -> Box<dyn VirtualTcpSocket + Sync> {
+        self.inner
+    }
+
+    /// Returns the protocol the server selected through ALPN
+    pub fn alpn_protocol(&self) -> Option<&[u8]> {
+        self.conn.alpn_protocol()
+    }
+
+    /// Makes progress on the handshake, which is finished once this
+    /// returns [`Poll::Ready`] with [`Ok`].
+    pub fn poll_handshake(&mut self, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        loop {
+            match self.flush_tls() {
+                Ok(()) => {}
+                Err(NetworkError::WouldBlock) => {
+                    ready!(self.inner.poll_write_ready(cx))?;
+                    continue;
+                }
+                Err(err) => return Poll::Ready(Err(err)),
+            }
+            if !self.conn.is_handshaking() {
+                return Poll::Ready(Ok(()));
+            }
+
+            match self.read_tls() {
+                Ok(0) => return Poll::Ready(Err(NetworkError::ConnectionAborted)),
+                Ok(_) => {}
+                Err(NetworkError::WouldBlock) => {
+                    ready!(self.inner.poll_read_ready(cx))?;
+                }
+                Err(err) => return Poll::Ready(Err(err)),
+            }
+        }
+    }
+
+    /// Sends whatever the session wants to send to the peer
+    fn flush_tls(&mut self) -> Result<()> {
+        while self.conn.wants_write() {
+            let amt = self
+                .conn
+                .write_tls(&mut SocketWriter(self.inner.as_mut()))
+                .map_err(io_err_into_net_error)?;
+            if amt == 0 {
+                return Err(NetworkError::WriteZero);
+            }
+        }
+        Ok(())
+    }
+
+    /// Feeds the session with the encrypted stream, returning how many
+    /// bytes were received (zero once the peer closed the connection)
+    fn read_tls(&mut self) -> Result<usize> {
+        let mut buf = [MaybeUninit::<u8>::uninit(); TLS_READ_CHUNK];
+        let amt = self.inner.try_recv(&mut buf, false)?;
+        // SAFETY: `try_recv` initialized the first `amt` bytes
+        let mut data: &[u8] = unsafe { std::mem::transmute(&buf[..amt]) };
+
+        loop {
+            self.conn
+                .read_tls(&mut data)
+                .map_err(io_err_into_net_error)?;
+            if let Err(err) = self.conn.process_new_packets() {
+                // Let the peer know why we are giving up
+                self.flush_tls().ok();
+                return Err(tls_error_into_net_error(err));
+            }
+            if data.is_empty() {
+                return Ok(amt);
+            }
+        }
+    }
+
+    /// Moves the decrypted data into `self.plaintext`, returning
+    /// `false` once the peer has closed the session
+    fn read_plaintext(&mut self, max: usize) -> Result<bool> {
+        loop {
+            let mut chunk = vec![0u8; max];
+            match self.conn.reader().read(&mut chunk) {
+                Ok(0) => return Ok(false),
+                Ok(amt) => {
+                    self.plaintext.extend_from_slice(&chunk[..amt]);
+                    return Ok(true);
+                }
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => {
+                    if self.read_tls()? == 0 {
+                        // Peers often close the connection without
+                        // a `close_notify` which we treat the same
+                        return Ok(false);
+                    }
+                }
+                Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => return Ok(false),
+                Err(err) => return Err(io_err_into_net_error(err)),
+            }
+        }
+    }
+}
+
+impl VirtualIoSource for TlsTcpSocket {
+    fn remove_handler(&mut self) {
+        self.inner.remove_handler()
+    }
+
+    fn poll_read_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<usize>> {
+        if !self.plaintext.is_empty() {
+            return Poll::Ready(Ok(self.plaintext.len()));
+        }
+        if let Ok(state) = self.conn.process_new_packets() {
+            if state.plaintext_bytes_to_read() > 0 {
+                return Poll::Ready(Ok(state.plaintext_bytes_to_read()));
+            }
+        }
+        self.inner.poll_read_ready(cx)
+    }
+
+    fn poll_write_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<usize>> {
+        match self.flush_tls() {
+            Ok(()) | Err(NetworkError::WouldBlock) => {}
+            Err(err) => return Poll::Ready(Err(err)),
+        }
+        self.inner.poll_write_ready(cx)
+    }
+}
+
+impl VirtualSocket for TlsTcpSocket {
+    fn set_ttl(&mut self, ttl: u32) -> Result<()> {
+        self.inner.set_ttl(ttl)
+    }
+
+    fn ttl(&self) -> Result<u32> {
+        self.inner.ttl()
+    }
+
+    fn addr_local(&self) -> Result<SocketAddr> {
+        self.inner.addr_local()
+    }
+
+    fn status(&self) -> Result<SocketStatus> {
+        self.inner.status()
+    }
+
+    fn set_handler(&mut self, handler: Box<dyn InterestHandler + Send + Sync>) -> Result<()> {
+        self.inner.set_handler(handler)
+    }
+}
+
+impl VirtualConnectedSocket for TlsTcpSocket {
+    fn set_linger(&mut self, linger: Option<Duration>) -> Result<()> {
+        self.inner.set_linger(linger)
+    }
+
+    fn linger(&self) -> Result<Option<Duration>> {
+        self.inner.linger()
+    }
+
+    fn try_send(&mut self, data: &[u8]) -> Result<usize> {
+        let amt = self
+            .conn
+            .writer()
+            .write(data)
+            .map_err(io_err_into_net_error)?;
+
+        // Whatever can't be sent now stays buffered in the session
+        // and goes out with the next operation on this socket
+        match self.flush_tls() {
+            Ok(()) | Err(NetworkError::WouldBlock) => {}
+            Err(err) => return Err(err),
+        }
+        if amt == 0 && !data.is_empty() {
+            return Err(NetworkError::WouldBlock);
+        }
+        Ok(amt)
+    }
+
+    fn try_flush(&mut self) -> Result<()> {
+        self.flush_tls()?;
+        self.inner.try_flush()
+    }
+
+    fn close(&mut self) -> Result<()> {
+        self.conn.send_close_notify();
+        self.flush_tls().ok();
+        self.inner.close()
+    }
+
+    fn try_recv(&mut self, buf: &mut [MaybeUninit<u8>], peek: bool) -> Result<usize> {
+        match self.flush_tls() {
+            Ok(()) | Err(NetworkError::WouldBlock) => {}
+            Err(err) => return Err(err),
+        }
+        if self.plaintext.is_empty() && !self.read_plaintext(buf.len())? {
+            return Ok(0);
+        }
+
+        let amt = buf.len().min(self.plaintext.len());
+        for (dst, src) in buf.iter_mut().zip(&self.plaintext[..amt]) {
+            dst.write(*src);
+        }
+        if !peek {
+            self.plaintext.drain(..amt);
+        }
+        Ok(amt)
+    }
+}
+
+impl VirtualTcpSocket for TlsTcpSocket {
+    fn set_recv_buf_size(&mut self, size: usize) -> Result<()> {
+        self.inner.set_recv_buf_size(size)
+    }
+
+    fn recv_buf_size(&self) -> Result<usize> {
+        self.inner.recv_buf_size()
+    }
+
+    fn set_send_buf_size(&mut self, size: usize) -> Result<()> {
+        self.inner.set_send_buf_size(size)
+    }
+
+    fn send_buf_size(&self) -> Result<usize> {
+        self.inner.send_buf_size()
+    }
+
+    fn set_nodelay(&mut self, nodelay: bool) -> Result<()> {
+        self.inner.set_nodelay(nodelay)
+    }
+
+    fn nodelay(&self) -> Result<bool> {
+        self.inner.nodelay()
+    }
+
+    fn set_keepalive(&mut self, keepalive: bool) -> Result<()> {
+        self.inner.set_keepalive(keepalive)
+    }
+
+    fn keepalive(&self) -> Result<bool> {
+        self.inner.keepalive()
+    }
+
+    fn set_dontroute(&mut self, dontroute: bool) -> Result<()> {
+        self.inner.set_dontroute(dontroute)
+    }
+
+    fn dontroute(&self) -> Result<bool> {
+        self.inner.dontroute()
+    }
+
+    fn addr_peer(&self) -> Result<SocketAddr> {
+        self.inner.addr_peer()
+    }
+
+    fn shutdown(&mut self, how: Shutdown) -> Result<()> {
+        if how != Shutdown::Read {
+            self.conn.send_close_notify();
+            self.flush_tls().ok();
+        }
+        self.inner.shutdown(how)
+    }
+
+    fn is_closed(&self) -> bool {
+        self.inner.is_closed()
+    }
+
+    fn take_error(&mut self) -> Result<Option<NetworkError>> {
+        self.inner.take_error()
+    }
+}
@@ -0,0 +1,216 @@
+use std::any::Any;
+
+use wasmer::FunctionEnvMut;
+use wasmer_wasix_types::wasi::{Errno, ExitCode, Signal};
+
+use crate::{
+    os::{
+        command::VirtualCommand,
+        task::{OwnedTaskStatus, TaskJoinHandle},
+    },
+    runtime::task_manager::InlineWaker,
+    syscalls::stderr_write,
+    SpawnError, WasiEnv, WasiProcessId,
+};
+
+const HELP: &str = r#"USAGE:
+    kill [-s SIGNAL | -SIGNAL] PID...
+
+Sends a signal (TERM by default) to the given processes. SIGNAL is either a
+name (TERM, SIGTERM) or a number (15). Signal 0 only checks that the
+processes exist.
+"#;
+
+const SIGNALS: &[(&str, Signal)] = &[
+    ("HUP", Signal::Sighup),
+    ("INT", Signal::Sigint),
+    ("QUIT", Signal::Sigquit),
+    ("ILL", Signal::Sigill),
+    ("TRAP", Signal::Sigtrap),
+    ("ABRT", Signal::Sigabrt),
+    ("BUS", Signal::Sigbus),
+    ("FPE", Signal::Sigfpe),
+    ("KILL", Signal::Sigkill),
+    ("USR1", Signal::Sigusr1),
+    ("SEGV", Signal::Sigsegv),
+    ("USR2", Signal::Sigusr2),
+    ("PIPE", Signal::Sigpipe),
+    ("ALRM", Signal::Sigalrm),
+    ("TERM", Signal::Sigterm),
+    ("STKFLT", Signal::Sigstkflt),
+    ("CHLD", Signal::Sigchld),
+    ("CONT", Signal::Sigcont),
+    ("STOP", Signal::Sigstop),
+    ("TSTP", Signal::Sigtstp),
+    ("TTIN", Signal::Sigttin),
+    ("TTOU", Signal::Sigttou),
+    ("URG", Signal::Sigurg),
+    ("XCPU", Signal::Sigxcpu),
+    ("XFSZ", Signal::Sigxfsz),
+    ("VTALRM", Signal::Sigvtalrm),
+    ("PROF", Signal::Sigprof),
+    ("WINCH", Signal::Sigwinch),
+    ("POLL", Signal::Sigpoll),
+    ("PWR", Signal::Sigpwr),
+    ("SYS", Signal::Sigsys),
+];
+
+/// Parses a signal given by name (`TERM`, `SIGTERM`) or number (`15`).
+fn parse_signal(value: &str) -> Option<Signal> {
+    if let Ok(num) = value.parse::<u8>() {
+        // Sigwakeup is for the host only
+        return Signal::try_from(num)
+            .ok()
+            .filter(|sig| *sig != Signal::Sigwakeup);
+    }
+
+    let name = value.to_ascii_uppercase();
+    let name = name.strip_prefix("SIG").unwrap_or(&name);
+    SIGNALS
+        .iter()
+        .find(|(n, _)| *n == name)
+        .map(|(_, sig)| *sig)
+}
+
+/// Parses the arguments of `kill` (without the command name itself).
+fn parse_args(args: &[String]) -> Result<(Signal, Vec<WasiProcessId>), String> {
+    let mut signal = Signal::Sigterm;
+    let mut args = args.iter().map(|a| a.as_str()).peekable();
+
+    match args.peek().copied() {
+        Some("-s") => {
+            args.next();
+            let name = args.next().ok_or("option requires an argument -- 's'")?;
+            signal = parse_signal(name).ok_or_else(|| format!("{name}: invalid signal"))?;
+        }
+        Some("--") => {
+            args.next();
+        }
+        Some(flag) if flag.starts_with('-') && flag.len() > 1 => {
+            args.next();
+            signal = parse_signal(&flag[1..]).ok_or_else(|| format!("{flag}: invalid signal"))?;
+        }
+        _ => {}
+    }
+    if args.peek() == Some(&"--") {
+        args.next();
+    }
+
+    let pids = args
+        .map(|pid| {
+            pid.parse::<u32>()
+                .map(WasiProcessId::from)
+                .map_err(|_| format!("{pid}: arguments must be process IDs"))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    if pids.is_empty() {
+        return Err("no process ID given".to_string());
+    }
+
+    Ok((signal, pids))
+}
+
+/// The `kill` builtin, which sends a signal to other processes.
+#[derive(Debug, Clone, Default)]
+pub struct CmdKill;
+
+impl CmdKill {
+    const NAME: &'static str = "kill";
+
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl VirtualCommand for CmdKill {
+    fn name(&self) -> &str {
+        Self::NAME
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn exec(
+        &self,
+        parent_ctx: &FunctionEnvMut<'_, WasiEnv>,
+        _path: &str,
+        env: &mut Option<WasiEnv>,
+    ) -> Result<TaskJoinHandle, SpawnError> {
+        let env_inner = env.as_ref().ok_or(SpawnError::UnknownError)?;
+        let args: Vec<String> = env_inner
+            .state
+            .args
+            .lock()
+            .unwrap()
+            .iter()
+            .skip(1)
+            .cloned()
+            .collect();
+
+        let mut errors = String::new();
+        match parse_args(&args) {
+            Ok((signal, pids)) => {
+                // Signals are sent on behalf of the calling process
+                let sender = parent_ctx.data();
+                for pid in pids {
+                    if let Err(err) = sender.kill(pid, signal) {
+                        errors.push_str(&format!("kill: ({pid}) - {}\n", err.message()));
+                    }
+                }
+            }
+            Err(err) => {
+                errors.push_str(&format!("kill: {err}\n"));
+                errors.push_str(HELP);
+            }
+        }
+
+        let code: ExitCode = if errors.is_empty() {
+            Errno::Success.into()
+        } else {
+            unsafe { InlineWaker::block_on(stderr_write(parent_ctx, errors.as_bytes())) }.ok();
+            ExitCode::from(1u16)
+        };
+        Ok(OwnedTaskStatus::new_finished_with_code(code).handle())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|a| a.to_string()).collect()
+    }
+
+    #[test]
+    fn parse_kill_arguments() {
+        let pids = |raw: &[u32]| raw.iter().map(|p| WasiProcessId::from(*p)).collect();
+
+        assert_eq!(
+            parse_args(&args(&["42"])).unwrap(),
+            (Signal::Sigterm, pids(&[42]))
+        );
+        assert_eq!(
+            parse_args(&args(&["-9", "1", "2"])).unwrap(),
+            (Signal::Sigkill, pids(&[1, 2]))
+        );
+        assert_eq!(
+            parse_args(&args(&["-TERM", "3"])).unwrap(),
+            (Signal::Sigterm, pids(&[3]))
+        );
+        assert_eq!(
+            parse_args(&args(&["-s", "sigusr1", "--", "4"])).unwrap(),
+            (Signal::Sigusr1, pids(&[4]))
+        );
+        assert_eq!(
+            parse_args(&args(&["-0", "5"])).unwrap(),
+            (Signal::Signone, pids(&[5]))
+        );
+
+        assert!(parse_args(&args(&["-NOPE", "1"])).is_err());
+        assert!(parse_args(&args(&["-s"])).is_err());
+        assert!(parse_args(&args(&["-TERM"])).is_err());
+        assert!(parse_args(&args(&["abc"])).is_err());
+    }
+}
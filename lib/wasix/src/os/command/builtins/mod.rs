@@ -1 +1,2 @@
+pub mod cmd_kill;
 pub mod cmd_wasmer;
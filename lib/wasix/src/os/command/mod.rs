@@ -48,6 +48,7 @@ impl Commands {
         let mut cmd = Self::new();
         let cmd_wasmer = builtins::cmd_wasmer::CmdWasmer::new(runtime.clone());
         cmd.register_command(cmd_wasmer);
+        cmd.register_command(builtins::cmd_kill::CmdKill::new());
 
         cmd
     }
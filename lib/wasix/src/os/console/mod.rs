@@ -9,7 +9,11 @@ use std::{
     io::Write,
     ops::{Deref, DerefMut},
     path::{Path, PathBuf},
-    sync::{atomic::AtomicBool, Arc, Mutex},
+    sync::{
+        atomic::{AtomicBool, AtomicU32, Ordering},
+        Arc, Mutex,
+    },
+    time::SystemTime,
 };
 
 use futures::future::Either;
@@ -19,7 +23,7 @@ use tokio::sync::{mpsc, RwLock};
 use tracing::{debug, error, info, trace, warn};
 use virtual_fs::{
     ArcBoxFile, ArcFile, AsyncWriteExt, CombineFile, DeviceFile, DuplexPipe, FileSystem, Pipe,
-    PipeRx, PipeTx, RootFileSystemBuilder, StaticFile, VirtualFile,
+    PipeRx, PipeTx, RootFileSystemBuilder, StaticFile, TmpFileSystem, VirtualFile,
 };
 #[cfg(feature = "sys")]
 use wasmer::Engine;
@@ -29,13 +33,164 @@ use wasmer_wasix_types::{types::__WASI_STDIN_FILENO, wasi::Errno};
 use super::{cconst::ConsoleConst, common::*, task::TaskJoinHandle};
 use crate::{
     bin_factory::{spawn_exec, BinFactory, BinaryPackage},
-    capabilities::Capabilities,
-    os::task::{control_plane::WasiControlPlane, process::WasiProcess},
+    capabilities::{Capabilities, PathRule},
+    os::task::{
+        control_plane::{ControlPlaneConfig, WasiControlPlane},
+        process::{WasiProcess, WasiProcessId},
+    },
     runners::wasi::{PackageOrHash, RuntimeOrEngine},
     runtime::task_manager::InlineWaker,
-    Runtime, SpawnError, WasiEnv, WasiEnvBuilder, WasiRuntimeError,
+    Runtime, SpawnError, StdioBuffering, WasiEnv, WasiEnvBuilder, WasiRuntimeError,
 };
 
+/// The state shared by all the sessions of a [`Console`], much like the
+/// machine behind several SSH logins.
+///
+/// Every session started on the same host sees the same process table
+/// (control plane), root file system and [`BinFactory`]. The shared state is
+/// released once the host and the last of its sessions are dropped.
+#[derive(Debug)]
+pub struct ConsoleHost {
+    runtime: Arc<dyn Runtime + Send + Sync>,
+    control_plane: WasiControlPlane,
+    root_fs: TmpFileSystem,
+    bin_factory: BinFactory,
+    sessions: Mutex<Vec<ConsoleSessionInfo>>,
+    next_session_id: AtomicU32,
+}
+
+impl ConsoleHost {
+    pub fn new(runtime: Arc<dyn Runtime + Send + Sync + 'static>, caps: &Capabilities) -> Self {
+        let root_fs = RootFileSystemBuilder::new();
+        let tmp_fs = root_fs.tmp_fs().clone();
+        Self::with_root_fs(runtime, caps, root_fs.build(), tmp_fs)
+    }
+
+    fn with_root_fs(
+        runtime: Arc<dyn Runtime + Send + Sync + 'static>,
+        caps: &Capabilities,
+        root_fs: TmpFileSystem,
+        tmp_fs: TmpFileSystem,
+    ) -> Self {
+        let plane_config = ControlPlaneConfig {
+            max_task_count: caps.threading.max_threads,
+            enable_asynchronous_threading: caps.threading.enable_asynchronous_threading,
+            enable_exponential_cpu_backoff: caps.threading.enable_exponential_cpu_backoff,
+            ..ControlPlaneConfig::new()
+        };
+
+        // `/tmp` is emptied whenever the host runs out of processes
+        let control_plane = WasiControlPlane::new(plane_config);
+        control_plane.on_idle(move || {
+            if let Err(err) = tmp_fs.clear() {
+                tracing::warn!(
+                    error = &err as &dyn std::error::Error,
+                    "Unable to clear /tmp"
+                );
+            }
+        });
+
+        // The files count towards the memory of the whole host
+        root_fs.set_memory_limiter(control_plane.memfs_limiter());
+
+        Self {
+            control_plane,
+            root_fs,
+            bin_factory: BinFactory::new(runtime.clone()),
+            runtime,
+            sessions: Mutex::new(Vec::new()),
+            next_session_id: AtomicU32::new(1),
+        }
+    }
+
+    pub fn runtime(&self) -> &Arc<dyn Runtime + Send + Sync> {
+        &self.runtime
+    }
+
+    pub fn control_plane(&self) -> &WasiControlPlane {
+        &self.control_plane
+    }
+
+    pub fn root_fs(&self) -> &TmpFileSystem {
+        &self.root_fs
+    }
+
+    pub fn bin_factory(&self) -> &BinFactory {
+        &self.bin_factory
+    }
+
+    /// All the processes that were started on this host, from any session.
+    pub fn processes(&self) -> Vec<WasiProcess> {
+        self.control_plane.processes()
+    }
+
+    /// The sessions that are currently attached to this host (like `who`).
+    pub fn sessions(&self) -> Vec<ConsoleSessionInfo> {
+        self.sessions.lock().unwrap().clone()
+    }
+
+    fn register_session(&self, pid: WasiProcessId, user_agent: Option<String>) -> u32 {
+        let id = self.next_session_id.fetch_add(1, Ordering::SeqCst);
+        self.sessions.lock().unwrap().push(ConsoleSessionInfo {
+            id,
+            pid,
+            user_agent,
+            started: SystemTime::now(),
+        });
+        id
+    }
+
+    fn unregister_session(&self, id: u32) {
+        self.sessions.lock().unwrap().retain(|s| s.id != id);
+    }
+}
+
+/// Describes a session that is attached to a [`ConsoleHost`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConsoleSessionInfo {
+    pub id: u32,
+    /// The session's foreground process.
+    pub pid: WasiProcessId,
+    pub user_agent: Option<String>,
+    pub started: SystemTime,
+}
+
+/// A session started with [`Console::new_session()`].
+///
+/// The session stays attached to its [`ConsoleHost`] until it is dropped.
+#[derive(Debug)]
+pub struct ConsoleSession {
+    id: u32,
+    host: Arc<ConsoleHost>,
+    handle: TaskJoinHandle,
+    process: WasiProcess,
+}
+
+impl ConsoleSession {
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    pub fn host(&self) -> &Arc<ConsoleHost> {
+        &self.host
+    }
+
+    /// The session's foreground process.
+    pub fn process(&self) -> &WasiProcess {
+        &self.process
+    }
+
+    pub fn handle(&mut self) -> &mut TaskJoinHandle {
+        &mut self.handle
+    }
+}
+
+impl Drop for ConsoleSession {
+    fn drop(&mut self) {
+        self.host.unregister_session(self.id);
+    }
+}
+
 #[derive(Debug)]
 pub struct Console {
     user_agent: Option<String>,
@@ -52,9 +207,11 @@ pub struct Console {
     stdin: ArcBoxFile,
     stdout: ArcBoxFile,
     stderr: ArcBoxFile,
+    stdio_buffering: StdioBuffering,
     capabilities: Capabilities,
     ro_files: HashMap<String, Cow<'static, [u8]>>,
     memfs_memory_limiter: Option<virtual_fs::limiter::DynFsMemoryLimiter>,
+    host: Option<Arc<ConsoleHost>>,
 }
 
 impl Console {
@@ -74,9 +231,11 @@ impl Console {
             stdin: ArcBoxFile::new(Box::new(Pipe::channel().0)),
             stdout: ArcBoxFile::new(Box::new(Pipe::channel().0)),
             stderr: ArcBoxFile::new(Box::new(Pipe::channel().0)),
+            stdio_buffering: Default::default(),
             capabilities: Default::default(),
             memfs_memory_limiter: None,
             ro_files: Default::default(),
+            host: None,
         }
     }
 
@@ -87,7 +246,8 @@ impl Console {
 
     pub fn with_boot_cmd(mut self, cmd: String) -> Self {
         let prog = cmd.split_once(' ').map(|a| a.0).unwrap_or(cmd.as_str());
-        self.uses.insert(prog.to_string());
+        let (package, _) = PackageSource::split_command(prog);
+        self.uses.insert(package.to_string());
         self.boot_cmd = cmd;
         self
     }
@@ -139,6 +299,13 @@ impl Console {
         self
     }
 
+    /// Buffer what the sessions' processes write to stdout and stderr, see
+    /// [`StdioBuffering`].
+    pub fn with_stdio_buffering(mut self, buffering: StdioBuffering) -> Self {
+        self.stdio_buffering = buffering;
+        self
+    }
+
     pub fn with_ro_files(mut self, ro_files: HashMap<String, Cow<'static, [u8]>>) -> Self {
         self.ro_files = ro_files;
         self
@@ -152,24 +319,95 @@ impl Console {
         self
     }
 
+    /// Run the sessions of this console on an existing host, so they share
+    /// its processes and file system with the host's other sessions.
+    pub fn with_host(mut self, host: Arc<ConsoleHost>) -> Self {
+        self.host = Some(host);
+        self
+    }
+
+    /// The host this console's sessions run on, creating it if necessary.
+    pub fn host(&mut self) -> Arc<ConsoleHost> {
+        if let Some(host) = &self.host {
+            return host.clone();
+        }
+
+        let root_fs = RootFileSystemBuilder::new();
+        let tmp_fs = root_fs.tmp_fs().clone();
+        let host = Arc::new(ConsoleHost::with_root_fs(
+            self.runtime.clone(),
+            &self.capabilities,
+            root_fs.build(),
+            tmp_fs,
+        ));
+        if let Some(limiter) = &self.memfs_memory_limiter {
+            host.root_fs().set_memory_limiter(limiter.clone());
+        }
+        self.host = Some(host.clone());
+        host
+    }
+
+    /// Start the boot command in a new session on this console's host.
+    ///
+    /// Each session gets its own foreground process, standard streams and
+    /// `/dev/tty` (which its stdin and stdout are wired up to), while sharing
+    /// processes, files and signals with the other sessions.
+    pub fn new_session(
+        &mut self,
+        stdin: Box<dyn VirtualFile + Send + Sync + 'static>,
+        stdout: Box<dyn VirtualFile + Send + Sync + 'static>,
+        stderr: Box<dyn VirtualFile + Send + Sync + 'static>,
+    ) -> Result<ConsoleSession, SpawnError> {
+        let host = self.host();
+        self.start_session(
+            host,
+            ArcBoxFile::new(stdin),
+            ArcBoxFile::new(stdout),
+            ArcBoxFile::new(stderr),
+        )
+    }
+
+    /// Start the boot command in a new session using the console's standard
+    /// streams.
+    ///
+    /// The session stays attached to the host until the boot command exits.
     pub fn run(&mut self) -> Result<(TaskJoinHandle, WasiProcess), SpawnError> {
-        // Extract the program name from the arguments
-        let empty_args: Vec<&str> = Vec::new();
-        let (webc, prog, args) = match self.boot_cmd.split_once(' ') {
-            Some((webc, args)) => (
-                webc,
-                webc.split_once('/').map(|a| a.1).unwrap_or(webc),
-                args.split(' ').collect::<Vec<_>>(),
-            ),
-            None => (
-                self.boot_cmd.as_str(),
-                self.boot_cmd
-                    .split_once('/')
-                    .map(|a| a.1)
-                    .unwrap_or(self.boot_cmd.as_str()),
-                empty_args,
-            ),
+        let host = self.host();
+        let mut session = self.start_session(
+            host,
+            self.stdin.clone(),
+            self.stdout.clone(),
+            self.stderr.clone(),
+        )?;
+
+        let parts = (session.handle.clone(), session.process.clone());
+        self.runtime
+            .task_manager()
+            .task_shared(Box::new(move || {
+                Box::pin(async move {
+                    session.handle.wait_finished().await.ok();
+                    drop(session);
+                })
+            }))
+            .map_err(WasiRuntimeError::from)?;
+        Ok(parts)
+    }
+
+    fn start_session(
+        &self,
+        host: Arc<ConsoleHost>,
+        stdin: ArcBoxFile,
+        stdout: ArcBoxFile,
+        stderr: ArcBoxFile,
+    ) -> Result<ConsoleSession, SpawnError> {
+        // Extract the package, the program name and the arguments from the
+        // boot command, which starts with `package` or `package:command`
+        let (webc, args) = match self.boot_cmd.split_once(' ') {
+            Some((webc, args)) => (webc, args.split(' ').collect::<Vec<_>>()),
+            None => (self.boot_cmd.as_str(), Vec::new()),
         };
+        let (webc, command) = PackageSource::split_command(webc);
+        let prog = command.unwrap_or_else(|| webc.split_once('/').map(|a| a.1).unwrap_or(webc));
 
         let webc_ident: PackageSource = match webc.parse() {
             Ok(ident) => ident,
@@ -184,10 +422,15 @@ impl Console {
             self.runtime.as_ref(),
         ));
 
-        let pkg = match resolved_package {
+        let pkg = match resolved_package.and_then(|pkg| {
+            if let Some(command) = command {
+                pkg.resolve_command(Some(command))?;
+            }
+            Ok(pkg)
+        }) {
             Ok(pkg) => pkg,
             Err(e) => {
-                let mut stderr = self.stderr.clone();
+                let mut stderr = stderr.clone();
                 InlineWaker::block_on(async {
                     let mut buffer = Vec::new();
                     writeln!(buffer, "Error: {e}").ok();
@@ -210,45 +453,51 @@ impl Console {
 
         let wasi_opts = webc::metadata::annotations::Wasi::new(prog);
 
-        let root_fs = RootFileSystemBuilder::new()
-            .with_tty(Box::new(CombineFile::new(
-                Box::new(self.stdout.clone()),
-                Box::new(self.stdin.clone()),
-            )))
-            .build();
-
-        if let Some(limiter) = &self.memfs_memory_limiter {
-            root_fs.set_memory_limiter(limiter.clone());
-        }
-
         let builder = crate::runners::wasi::WasiRunner::new()
             .with_envs(self.env.clone().into_iter())
             .with_args(args)
             .with_capabilities(self.capabilities.clone())
-            .with_stdin(Box::new(self.stdin.clone()))
-            .with_stdout(Box::new(self.stdout.clone()))
-            .with_stderr(Box::new(self.stderr.clone()))
+            // Commands are looked up in `/bin` and the shell talks to the terminal
+            .with_fs_grant(PathRule::read_only("/bin"))
+            .with_fs_grant(PathRule::read_write("/dev/tty"))
+            .with_stdin(Box::new(stdin.clone()))
+            .with_stdout(Box::new(stdout.clone()))
+            .with_stderr(Box::new(stderr.clone()))
             .prepare_webc_env(
                 prog,
                 &wasi_opts,
                 PackageOrHash::Package(&pkg),
                 RuntimeOrEngine::Runtime(self.runtime.clone()),
-                Some(root_fs),
+                Some(host.root_fs.clone()),
             )
             // TODO: better error conversion
             .map_err(|err| SpawnError::Other(err.into()))?;
 
-        let env = builder.build()?;
+        // Processes and commands are shared with the other sessions
+        let mut init = builder.build_init().map_err(WasiRuntimeError::from)?;
+        init.control_plane = host.control_plane.clone();
+        init.bin_factory = host.bin_factory.clone();
+        init.stdio_buffering = self.stdio_buffering.clone();
+        let env = WasiEnv::from_init(init, pkg.hash())?;
+
+        // The session's processes (and the ones they start) share a terminal
+        let tty = CombineFile::new(Box::new(stdout.clone()), Box::new(stdin.clone()));
+        env.state.fs.set_process_file(
+            &env.state.inodes,
+            Path::new("/dev/tty"),
+            Box::new(tty),
+            true,
+        );
 
         // Display the welcome message
         if !self.whitelabel && !self.no_welcome {
-            InlineWaker::block_on(self.draw_welcome());
+            InlineWaker::block_on(self.write_welcome(stderr.clone()));
         }
 
         let wasi_process = env.process.clone();
 
         if let Err(err) = env.uses(self.uses.clone()) {
-            let mut stderr = self.stderr.clone();
+            let mut stderr = stderr.clone();
             InlineWaker::block_on(async {
                 virtual_fs::AsyncWriteExt::write_all(&mut stderr, format!("{err}\r\n").as_bytes())
                     .await
@@ -277,13 +526,22 @@ impl Console {
 
         // Build the config
         // Run the binary
-        let process = InlineWaker::block_on(spawn_exec(pkg, prog, env, &self.runtime))?;
-
-        // Return the process
-        Ok((process, wasi_process))
+        let handle = InlineWaker::block_on(spawn_exec(pkg, prog, env, &self.runtime))?;
+
+        let id = host.register_session(wasi_process.pid(), self.user_agent.clone());
+        Ok(ConsoleSession {
+            id,
+            host,
+            handle,
+            process: wasi_process,
+        })
     }
 
     pub async fn draw_welcome(&self) {
+        self.write_welcome(self.stderr.clone()).await;
+    }
+
+    async fn write_welcome(&self, mut stderr: ArcBoxFile) {
         let welcome = match (self.is_mobile, self.is_ssh) {
             (true, _) => ConsoleConst::WELCOME_MEDIUM,
             (_, true) => ConsoleConst::WELCOME_SMALL,
@@ -295,7 +553,6 @@ impl Console {
             .replace("\\n", "\n");
         data.insert_str(0, ConsoleConst::TERM_NO_WRAPAROUND);
 
-        let mut stderr = self.stderr.clone();
         virtual_fs::AsyncWriteExt::write_all(&mut stderr, data.as_bytes())
             .await
             .ok();
@@ -0,0 +1,133 @@
+use std::fmt::Write;
+
+use wasmer::Engine;
+use wasmer_types::target::CpuFeature;
+
+use crate::{capabilities::CapabilityCpuV1, runtime::task_manager::VirtualTaskManager};
+
+/// The CPU features a guest can be told about, with the names Linux uses for
+/// them in `/proc/cpuinfo`.
+///
+/// A feature's position in this list is its bit in the mask returned by the
+/// `cpu_features()` syscall.
+pub const CPU_FEATURES: [(CpuFeature, &str); 15] = [
+    (CpuFeature::SSE2, "sse2"),
+    (CpuFeature::SSE3, "pni"),
+    (CpuFeature::SSSE3, "ssse3"),
+    (CpuFeature::SSE41, "sse4_1"),
+    (CpuFeature::SSE42, "sse4_2"),
+    (CpuFeature::POPCNT, "popcnt"),
+    (CpuFeature::AVX, "avx"),
+    (CpuFeature::BMI1, "bmi1"),
+    (CpuFeature::BMI2, "bmi2"),
+    (CpuFeature::AVX2, "avx2"),
+    (CpuFeature::AVX512DQ, "avx512dq"),
+    (CpuFeature::AVX512VL, "avx512vl"),
+    (CpuFeature::AVX512F, "avx512f"),
+    (CpuFeature::LZCNT, "abm"),
+    (CpuFeature::NEON, "asimd"),
+];
+
+/// The CPUs a process sees.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CpuInfo {
+    /// The number of logical CPUs.
+    pub count: usize,
+    /// A bitmask of the supported [`CPU_FEATURES`].
+    pub features: u64,
+}
+
+impl CpuInfo {
+    /// Where the CPU information is made available inside the guest's file
+    /// system.
+    pub const PROC_PATH: &'static str = "/proc/cpuinfo";
+
+    /// Work out what to tell a process about its CPUs, preferring the
+    /// overrides in `cpu` over the host's CPU count and the features of the
+    /// CPU `engine` compiles for.
+    pub fn new(cpu: &CapabilityCpuV1, tasks: &dyn VirtualTaskManager, engine: &Engine) -> Self {
+        let count = match cpu.count {
+            Some(count) => count,
+            None => tasks.thread_parallelism().unwrap_or(1),
+        };
+
+        let features = match &cpu.features {
+            Some(features) => features_mask(features.iter().copied()),
+            None => target_features_mask(engine),
+        };
+
+        CpuInfo {
+            count: count.max(1),
+            features,
+        }
+    }
+
+    /// Whether `feature` is part of [`CpuInfo::features`].
+    pub fn has_feature(&self, feature: CpuFeature) -> bool {
+        self.features & feature_bit(feature) != 0
+    }
+
+    /// The contents of `/proc/cpuinfo`, with an entry per logical CPU.
+    pub fn to_proc_cpuinfo(&self) -> String {
+        let flags: Vec<_> = CPU_FEATURES
+            .iter()
+            .filter(|(feature, _)| self.has_feature(*feature))
+            .map(|(_, name)| *name)
+            .collect();
+        let flags = flags.join(" ");
+
+        let mut cpuinfo = String::new();
+        for processor in 0..self.count {
+            let _ = writeln!(cpuinfo, "processor\t: {processor}");
+            let _ = writeln!(cpuinfo, "flags\t\t: {flags}");
+            let _ = writeln!(cpuinfo);
+        }
+        cpuinfo
+    }
+}
+
+/// The features of the CPU the code runs on, which is only the host's when
+/// the engine isn't cross-compiling.
+fn target_features_mask(engine: &Engine) -> u64 {
+    #[cfg(feature = "sys")]
+    if engine.is_sys() {
+        use wasmer::sys::NativeEngineExt;
+        return features_mask(engine.target().cpu_features().iter());
+    }
+    #[cfg(not(feature = "sys"))]
+    let _ = engine;
+    features_mask(CpuFeature::for_host().iter())
+}
+
+fn feature_bit(feature: CpuFeature) -> u64 {
+    CPU_FEATURES
+        .iter()
+        .position(|(f, _)| *f == feature)
+        .map(|index| 1 << index)
+        .unwrap_or(0)
+}
+
+fn features_mask(features: impl Iterator<Item = CpuFeature>) -> u64 {
+    features.fold(0, |mask, feature| mask | feature_bit(feature))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cpuinfo_lists_every_processor() {
+        let cpu = CpuInfo {
+            count: 2,
+            features: features_mask([CpuFeature::SSE2, CpuFeature::AVX2].into_iter()),
+        };
+
+        assert_eq!(cpu.features, 0b10_0000_0001);
+        assert!(cpu.has_feature(CpuFeature::AVX2));
+        assert!(!cpu.has_feature(CpuFeature::NEON));
+        assert_eq!(
+            cpu.to_proc_cpuinfo(),
+            "processor\t: 0\nflags\t\t: sse2 avx2\n\nprocessor\t: 1\nflags\t\t: sse2 avx2\n\n"
+        );
+    }
+}
@@ -1,5 +1,6 @@
 pub mod common;
 pub mod console;
+pub mod cpu;
 pub mod tty;
 
 pub mod command;
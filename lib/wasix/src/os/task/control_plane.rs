@@ -1,14 +1,21 @@
 use std::{
     collections::HashMap,
+    net::SocketAddr,
     sync::{
-        atomic::{AtomicUsize, Ordering},
-        Arc, RwLock,
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        Arc, Mutex, RwLock,
     },
     time::Duration,
 };
 
-use crate::{WasiProcess, WasiProcessId};
+use crate::{net::listener::GuestListener, WasiProcess, WasiProcessId};
+use virtual_fs::{
+    limiter::{DynFsMemoryLimiter, FixedMemoryLimiter},
+    SharedMemoryFileSystem, SharedObject,
+};
+use wasmer::{MemoryError, MemoryLimiter, Pages};
 use wasmer_types::ModuleHash;
+use wasmer_wasix_types::wasi::{Errno, ExitCode, Signal};
 
 #[derive(Debug, Clone)]
 pub struct WasiControlPlane {
@@ -41,6 +48,14 @@ impl WasiControlPlaneHandle {
 pub struct ControlPlaneConfig {
     /// Total number of tasks (processes + threads) that can be spawned.
     pub max_task_count: Option<usize>,
+    /// Total number of processes that can run at the same time.
+    pub max_process_count: Option<usize>,
+    /// Total number of WebAssembly pages (64 KiB each) the linear memories
+    /// of all processes can use.
+    pub max_memory_pages: Option<u64>,
+    /// Total number of bytes the in-memory file systems that use
+    /// [`WasiControlPlane::memfs_limiter()`] can hold.
+    pub max_memfs_bytes: Option<usize>,
     /// Flag that indicates if asynchronous threading is enables (opt-in)
     pub enable_asynchronous_threading: bool,
     /// Enables an exponential backoff of the process CPU usage when there
@@ -54,6 +69,9 @@ impl ControlPlaneConfig {
     pub fn new() -> Self {
         Self {
             max_task_count: None,
+            max_process_count: None,
+            max_memory_pages: None,
+            max_memfs_bytes: None,
             enable_asynchronous_threading: false,
             enable_exponential_cpu_backoff: None,
         }
@@ -66,12 +84,32 @@ impl Default for ControlPlaneConfig {
     }
 }
 
-#[derive(Debug)]
+#[derive(derive_more::Debug)]
 struct State {
     config: ControlPlaneConfig,
 
     /// Total number of active tasks (threads) across all processes.
-    task_count: Arc<AtomicUsize>,
+    task_count: AtomicUsize,
+
+    /// Total number of processes that haven't exited yet.
+    process_count: AtomicUsize,
+
+    /// Total number of pages of linear memory charged to those processes.
+    memory_pages: AtomicU64,
+
+    /// Accounts for the bytes held by in-memory file systems.
+    memfs: Arc<FixedMemoryLimiter>,
+
+    /// Callbacks that run whenever the last task exits.
+    #[debug(ignore)]
+    idle_callbacks: RwLock<Vec<Box<dyn Fn() + Send + Sync>>>,
+
+    /// The POSIX shared memory objects of all processes, mounted at
+    /// `/dev/shm`.
+    shared_memory: SharedMemoryFileSystem,
+
+    /// The listeners guests opened on exposed ports, by their address.
+    guest_listeners: RwLock<HashMap<SocketAddr, GuestListener>>,
 
     /// Mutable state.
     mutable: RwLock<MutableState>,
@@ -90,8 +128,16 @@ impl WasiControlPlane {
     pub fn new(config: ControlPlaneConfig) -> Self {
         Self {
             state: Arc::new(State {
+                memfs: Arc::new(FixedMemoryLimiter::new(
+                    config.max_memfs_bytes.unwrap_or(usize::MAX),
+                )),
                 config,
-                task_count: Arc::new(AtomicUsize::new(0)),
+                task_count: AtomicUsize::new(0),
+                process_count: AtomicUsize::new(0),
+                memory_pages: AtomicU64::new(0),
+                idle_callbacks: RwLock::new(Vec::new()),
+                shared_memory: SharedMemoryFileSystem::new(),
+                guest_listeners: RwLock::new(HashMap::new()),
                 mutable: RwLock::new(MutableState {
                     process_seed: 0,
                     processes: Default::default(),
@@ -125,7 +171,66 @@ impl WasiControlPlane {
                 return Err(ControlPlaneError::TaskLimitReached { max: count });
             }
         }
-        Ok(TaskCountGuard(self.state.task_count.clone()))
+        Ok(TaskCountGuard(self.state.clone()))
+    }
+
+    /// Registers a callback that runs every time the last task (thread) on
+    /// this control plane exits, e.g. to release resources that are shared by
+    /// all of its processes.
+    ///
+    /// The callback runs on the thread that exited last and must not register
+    /// further callbacks.
+    pub fn on_idle(&self, callback: impl Fn() + Send + Sync + 'static) {
+        self.state
+            .idle_callbacks
+            .write()
+            .unwrap()
+            .push(Box::new(callback));
+    }
+
+    /// The file system mounted at `/dev/shm` in every process on this
+    /// control plane, so they can share memory with `shm_open()`.
+    pub fn shared_memory(&self) -> &SharedMemoryFileSystem {
+        &self.state.shared_memory
+    }
+
+    /// Looks up the shared memory object called `name` (e.g. `"buffer"` for
+    /// `/dev/shm/buffer`), so the host can inspect what the processes share.
+    pub fn shared_object(&self, name: &str) -> Option<Arc<SharedObject>> {
+        self.state.shared_memory.object(name)
+    }
+
+    /// Gets the listener a guest opened on `addr`, if its port is exposed
+    /// (see [`CapabilityListenV1`](crate::capabilities::CapabilityListenV1)).
+    pub fn guest_listener(&self, addr: SocketAddr) -> Option<GuestListener> {
+        self.state
+            .guest_listeners
+            .read()
+            .unwrap()
+            .get(&addr)
+            .cloned()
+    }
+
+    /// Gets all the listeners guests currently have open on exposed ports.
+    pub fn guest_listeners(&self) -> Vec<GuestListener> {
+        let listeners = self.state.guest_listeners.read().unwrap();
+        listeners.values().cloned().collect()
+    }
+
+    pub(crate) fn register_guest_listener(&self, listener: GuestListener) {
+        let mut listeners = self.state.guest_listeners.write().unwrap();
+        listeners.insert(listener.addr(), listener);
+    }
+
+    pub(crate) fn unregister_guest_listener(&self, listener: &GuestListener) {
+        let mut listeners = self.state.guest_listeners.write().unwrap();
+        // Another listener may have replaced it on the same address
+        if listeners
+            .get(&listener.addr())
+            .is_some_and(|l| l.is_same(listener))
+        {
+            listeners.remove(&listener.addr());
+        }
     }
 
     /// Creates a new process
@@ -140,8 +245,11 @@ impl WasiControlPlane {
             }
         }
 
+        let account = self.open_account()?;
+
         // Create the process first to do all the allocations before locking.
         let mut proc = WasiProcess::new(WasiProcessId::from(0), module_hash, self.handle());
+        proc.account = account;
 
         let mut mutable = self.state.mutable.write().unwrap();
 
@@ -151,6 +259,64 @@ impl WasiControlPlane {
         Ok(proc)
     }
 
+    /// Counts a new process towards [`ControlPlaneConfig::max_process_count`],
+    /// returning the account its resources are charged to until it exits.
+    pub(crate) fn open_account(&self) -> Result<Arc<ProcessAccount>, ControlPlaneError> {
+        let max = self.state.config.max_process_count;
+        self.state
+            .process_count
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |count| {
+                max.is_none_or(|max| count < max).then_some(count + 1)
+            })
+            .map_err(|_| ControlPlaneError::ProcessLimitReached {
+                max: max.unwrap_or_default(),
+            })?;
+
+        Ok(Arc::new(ProcessAccount {
+            plane: self.handle(),
+            memory_pages: Mutex::new(Some(0)),
+        }))
+    }
+
+    /// A limiter that accounts for the bytes of in-memory file systems on
+    /// this control plane, and enforces
+    /// [`ControlPlaneConfig::max_memfs_bytes`] across all of them.
+    ///
+    /// Set it on the file systems the processes write to, e.g. with
+    /// [`virtual_fs::TmpFileSystem::set_memory_limiter()`]. Writes that go
+    /// over the limit fail with `ENOSPC`.
+    ///
+    /// Note that memory is only tracked when the `tracking` feature of
+    /// `virtual-fs` is enabled.
+    pub fn memfs_limiter(&self) -> DynFsMemoryLimiter {
+        self.state.memfs.clone()
+    }
+
+    /// The resources the processes on this control plane use, in total and
+    /// for each process that hasn't exited yet, e.g. for dashboards.
+    pub fn resource_usage(&self) -> ControlPlaneUsage {
+        let processes = self
+            .processes()
+            .into_iter()
+            .filter_map(|process| {
+                let memory_pages = process.account.memory_pages()?;
+                Some(ProcessUsage {
+                    pid: process.pid(),
+                    threads: process.active_threads(),
+                    memory_pages,
+                })
+            })
+            .collect();
+
+        ControlPlaneUsage {
+            process_count: self.state.process_count.load(Ordering::SeqCst),
+            task_count: self.active_task_count(),
+            memory_pages: self.state.memory_pages.load(Ordering::SeqCst),
+            memfs_bytes: self.state.memfs.used(),
+            processes,
+        }
+    }
+
     /// Generates a new process ID
     pub fn generate_id(&self) -> Result<WasiProcessId, ControlPlaneError> {
         let mut mutable = self.state.mutable.write().unwrap();
@@ -167,6 +333,49 @@ impl WasiControlPlane {
             .get(&pid)
             .cloned()
     }
+
+    /// Sends a signal to the process with the given ID (see
+    /// [`WasiProcess::signal()`]).
+    ///
+    /// This is meant for the host, which is allowed to signal any process.
+    pub fn signal_process(&self, pid: WasiProcessId, signal: Signal) -> Result<(), Errno> {
+        self.get_process(pid).ok_or(Errno::Srch)?.signal(signal)
+    }
+
+    /// Sends a signal to every process on this control plane that is still
+    /// running, giving them a chance to shut down gracefully.
+    pub fn signal_all(&self, signal: Signal) {
+        for process in self.processes() {
+            // The process may exit while we are signalling the others
+            process.signal(signal).ok();
+        }
+    }
+
+    /// Terminates every process on this control plane without waiting for
+    /// them to shut down (see [`WasiProcess::terminate()`]).
+    pub fn terminate_all(&self, exit_code: ExitCode) {
+        for process in self.processes() {
+            if process.try_join().is_none() {
+                process.terminate(exit_code);
+            }
+        }
+    }
+
+    /// Gets all the processes that were started on this control plane,
+    /// ordered by their process ID
+    pub fn processes(&self) -> Vec<WasiProcess> {
+        let mut processes: Vec<_> = self
+            .state
+            .mutable
+            .read()
+            .unwrap()
+            .processes
+            .values()
+            .cloned()
+            .collect();
+        processes.sort_by_key(|p| p.pid());
+        processes
+    }
 }
 
 impl MutableState {
@@ -189,13 +398,134 @@ impl Default for WasiControlPlane {
     }
 }
 
+/// The resources used by the processes on a [`WasiControlPlane`], see
+/// [`WasiControlPlane::resource_usage()`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ControlPlaneUsage {
+    /// The number of processes that haven't exited yet.
+    pub process_count: usize,
+    /// The number of tasks (threads) across all processes.
+    pub task_count: usize,
+    /// The pages of linear memory used by all processes together.
+    pub memory_pages: u64,
+    /// The bytes held by the file systems using
+    /// [`WasiControlPlane::memfs_limiter()`].
+    pub memfs_bytes: usize,
+    /// The processes that haven't exited yet, ordered by their process ID.
+    pub processes: Vec<ProcessUsage>,
+}
+
+/// The resources used by a single process, see [`ControlPlaneUsage`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProcessUsage {
+    /// The ID of the process.
+    pub pid: WasiProcessId,
+    /// The number of threads the process runs.
+    pub threads: u32,
+    /// The pages of linear memory charged to the process.
+    pub memory_pages: u64,
+}
+
+/// What a process is charged on its control plane, which is released when
+/// its main thread finishes, however that happens.
+///
+/// It is the [`MemoryLimiter`] of every store the process runs in, so the
+/// memories created and grown there count towards
+/// [`ControlPlaneConfig::max_memory_pages`].
+#[derive(Debug)]
+pub(crate) struct ProcessAccount {
+    plane: WasiControlPlaneHandle,
+    /// The pages of linear memory charged to the process, or `None` once
+    /// it exited.
+    memory_pages: Mutex<Option<u64>>,
+}
+
+impl ProcessAccount {
+    /// An account for a process that isn't running on a control plane,
+    /// which is never charged anything.
+    pub(crate) fn detached() -> Arc<Self> {
+        Arc::new(Self {
+            plane: WasiControlPlaneHandle {
+                inner: std::sync::Weak::new(),
+            },
+            memory_pages: Mutex::new(None),
+        })
+    }
+
+    /// The pages of linear memory charged to the process, unless it exited.
+    pub(crate) fn memory_pages(&self) -> Option<u64> {
+        *self.memory_pages.lock().unwrap()
+    }
+
+    /// Releases everything the process was charged. Only the first call
+    /// does anything.
+    pub(crate) fn exit(&self) {
+        let Some(pages) = self.memory_pages.lock().unwrap().take() else {
+            return;
+        };
+        if let Some(plane) = self.plane.inner.upgrade() {
+            plane.memory_pages.fetch_sub(pages, Ordering::SeqCst);
+            plane.process_count.fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+}
+
+impl MemoryLimiter for ProcessAccount {
+    fn on_grow(&self, pages: Pages) -> Result<(), MemoryError> {
+        let mut charged = self.memory_pages.lock().unwrap();
+        // The memories of a process can outlive it, but they can't grow
+        let charged = charged.as_mut().ok_or_else(|| {
+            MemoryError::Generic("the process the memory belongs to has exited".to_string())
+        })?;
+
+        let pages = u64::from(pages.0);
+        if let Some(plane) = self.plane.inner.upgrade() {
+            let max = plane.config.max_memory_pages;
+            plane
+                .memory_pages
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |used| {
+                    let used = used.checked_add(pages)?;
+                    max.is_none_or(|max| used <= max).then_some(used)
+                })
+                .map_err(|_| {
+                    MemoryError::Generic(
+                        ControlPlaneError::MemoryLimitReached {
+                            max: max.unwrap_or(u64::MAX),
+                        }
+                        .to_string(),
+                    )
+                })?;
+        }
+        *charged += pages;
+        Ok(())
+    }
+
+    fn on_shrink(&self, pages: Pages) {
+        let mut charged = self.memory_pages.lock().unwrap();
+        // Everything was released when the process exited
+        let Some(charged) = charged.as_mut() else {
+            return;
+        };
+
+        let pages = u64::from(pages.0).min(*charged);
+        *charged -= pages;
+        if let Some(plane) = self.plane.inner.upgrade() {
+            plane.memory_pages.fetch_sub(pages, Ordering::SeqCst);
+        }
+    }
+}
+
 /// Guard that ensures the [`WasiControlPlane`] task counter is decremented when dropped.
 #[derive(Debug)]
-pub struct TaskCountGuard(Arc<AtomicUsize>);
+pub struct TaskCountGuard(Arc<State>);
 
 impl Drop for TaskCountGuard {
     fn drop(&mut self) {
-        self.0.fetch_sub(1, Ordering::SeqCst);
+        if self.0.task_count.fetch_sub(1, Ordering::SeqCst) == 1 {
+            for callback in self.0.idle_callbacks.read().unwrap().iter() {
+                callback();
+            }
+        }
     }
 }
 
@@ -207,6 +537,28 @@ pub enum ControlPlaneError {
         /// The maximum number of tasks.
         max: usize,
     },
+    /// The maximum number of running processes has been reached.
+    #[error("The maximum number of processes has been reached ({max})")]
+    ProcessLimitReached {
+        /// The maximum number of processes.
+        max: usize,
+    },
+    /// The processes use all the memory they are allowed to.
+    #[error("The processes use all the memory they are allowed to ({max} pages)")]
+    MemoryLimitReached {
+        /// The maximum number of pages.
+        max: u64,
+    },
+}
+
+impl From<ControlPlaneError> for Errno {
+    fn from(err: ControlPlaneError) -> Self {
+        match err {
+            ControlPlaneError::TaskLimitReached { .. }
+            | ControlPlaneError::ProcessLimitReached { .. } => Errno::Again,
+            ControlPlaneError::MemoryLimitReached { .. } => Errno::Nomem,
+        }
+    }
 }
 
 #[cfg(test)]
@@ -222,8 +574,7 @@ mod tests {
     fn test_control_plane_task_limits() {
         let p = WasiControlPlane::new(ControlPlaneConfig {
             max_task_count: Some(2),
-            enable_asynchronous_threading: false,
-            enable_exponential_cpu_backoff: None,
+            ..ControlPlaneConfig::new()
         });
 
         let p1 = p.new_process(ModuleHash::random()).unwrap();
@@ -245,8 +596,7 @@ mod tests {
     fn test_control_plane_task_limits_with_dropped_threads() {
         let p = WasiControlPlane::new(ControlPlaneConfig {
             max_task_count: Some(2),
-            enable_asynchronous_threading: false,
-            enable_exponential_cpu_backoff: None,
+            ..ControlPlaneConfig::new()
         });
 
         let p1 = p.new_process(ModuleHash::random()).unwrap();
@@ -269,4 +619,52 @@ mod tests {
             ControlPlaneError::TaskLimitReached { max: 2 }
         );
     }
+
+    /// Processes count towards the limit until their main thread finishes.
+    #[test]
+    fn test_control_plane_process_limits() {
+        let p = WasiControlPlane::new(ControlPlaneConfig {
+            max_process_count: Some(1),
+            ..ControlPlaneConfig::new()
+        });
+
+        let p1 = p.new_process(ModuleHash::random()).unwrap();
+        let t1 = p1
+            .new_thread(WasiMemoryLayout::default(), ThreadStartType::MainThread)
+            .unwrap();
+        let err = p.new_process(ModuleHash::random()).unwrap_err();
+        assert_eq!(err, ControlPlaneError::ProcessLimitReached { max: 1 });
+        assert_eq!(Errno::from(err), Errno::Again);
+        assert_eq!(p.resource_usage().process_count, 1);
+
+        drop(t1);
+        assert_eq!(p.resource_usage().process_count, 0);
+        p.new_process(ModuleHash::random()).unwrap();
+    }
+
+    /// The idle callbacks run once the last thread is gone.
+    #[test]
+    fn test_control_plane_on_idle() {
+        let p = WasiControlPlane::default();
+        let idle = Arc::new(AtomicUsize::new(0));
+        p.on_idle({
+            let idle = idle.clone();
+            move || {
+                idle.fetch_add(1, Ordering::SeqCst);
+            }
+        });
+
+        let p1 = p.new_process(ModuleHash::random()).unwrap();
+        let t1 = p1
+            .new_thread(WasiMemoryLayout::default(), ThreadStartType::MainThread)
+            .unwrap();
+        let t2 = p1
+            .new_thread(WasiMemoryLayout::default(), ThreadStartType::MainThread)
+            .unwrap();
+
+        drop(t1);
+        assert_eq!(idle.load(Ordering::SeqCst), 0);
+        drop(t2);
+        assert_eq!(idle.load(Ordering::SeqCst), 1);
+    }
 }
@@ -0,0 +1,330 @@
+//! Per-process counters of the syscalls a guest makes.
+//!
+//! Counting is compiled in with the `syscall-counters` feature. Without it
+//! the counters stay at zero and cost nothing.
+
+#[cfg(feature = "syscall-counters")]
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc,
+};
+
+macro_rules! syscalls {
+    ($($name:ident => $variant:ident,)*) => {
+        /// A syscall the runtime implements.
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+        #[non_exhaustive]
+        pub enum Syscall {
+            $($variant,)*
+        }
+
+        impl Syscall {
+            /// Every syscall, ordered by ID
+            pub const ALL: &'static [Syscall] = &[$(Syscall::$variant,)*];
+
+            /// The name the guest imports the syscall as
+            pub fn name(self) -> &'static str {
+                match self {
+                    $(Syscall::$variant => stringify!($name),)*
+                }
+            }
+        }
+
+        impl SyscallCounters {
+            $(
+                #[doc = concat!("Number of calls to `", stringify!($name), "`")]
+                pub fn $name(&self) -> u64 {
+                    self.get(Syscall::$variant)
+                }
+            )*
+        }
+    };
+}
+
+syscalls! {
+    archive_extract => ArchiveExtract,
+    archive_extract_poll => ArchiveExtractPoll,
+    args_get => ArgsGet,
+    args_sizes_get => ArgsSizesGet,
+    array_output_len => ArrayOutputLen,
+    array_output_pull => ArrayOutputPull,
+    call_dynamic => CallDynamic,
+    callback_signal => CallbackSignal,
+    chdir => Chdir,
+    clock_res_get => ClockResGet,
+    clock_time_get => ClockTimeGet,
+    clock_time_set => ClockTimeSet,
+    closure_allocate => ClosureAllocate,
+    closure_free => ClosureFree,
+    closure_prepare => ClosurePrepare,
+    cpu_features => CpuFeatures,
+    dl_invalid_handle => DlInvalidHandle,
+    dlopen => Dlopen,
+    dlsym => Dlsym,
+    environ_get => EnvironGet,
+    environ_sizes_get => EnvironSizesGet,
+    epoll_create => EpollCreate,
+    epoll_ctl => EpollCtl,
+    epoll_wait => EpollWait,
+    fd_advise => FdAdvise,
+    fd_allocate => FdAllocate,
+    fd_close => FdClose,
+    fd_datasync => FdDatasync,
+    fd_dup => FdDup,
+    fd_dup2 => FdDup2,
+    fd_event => FdEvent,
+    fd_fdflags_get => FdFdflagsGet,
+    fd_fdflags_set => FdFdflagsSet,
+    fd_fdstat_get => FdFdstatGet,
+    fd_fdstat_set_flags => FdFdstatSetFlags,
+    fd_fdstat_set_rights => FdFdstatSetRights,
+    fd_filestat_get => FdFilestatGet,
+    fd_filestat_set_size => FdFilestatSetSize,
+    fd_filestat_set_times => FdFilestatSetTimes,
+    fd_link => FdLink,
+    fd_pipe => FdPipe,
+    fd_pread => FdPread,
+    fd_prestat_dir_name => FdPrestatDirName,
+    fd_prestat_get => FdPrestatGet,
+    fd_pwrite => FdPwrite,
+    fd_read => FdRead,
+    fd_readdir => FdReaddir,
+    fd_renumber => FdRenumber,
+    fd_seek => FdSeek,
+    fd_sync => FdSync,
+    fd_tell => FdTell,
+    fd_write => FdWrite,
+    futex_wait => FutexWait,
+    futex_wake => FutexWake,
+    futex_wake_all => FutexWakeAll,
+    getcwd => Getcwd,
+    keypair_close => KeypairClose,
+    keypair_export => KeypairExport,
+    keypair_generate => KeypairGenerate,
+    keypair_import => KeypairImport,
+    keypair_publickey => KeypairPublickey,
+    kv_delete => KvDelete,
+    kv_get => KvGet,
+    kv_put => KvPut,
+    kv_scan => KvScan,
+    path_create_directory => PathCreateDirectory,
+    path_filestat_get => PathFilestatGet,
+    path_filestat_set_times => PathFilestatSetTimes,
+    path_link => PathLink,
+    path_open => PathOpen,
+    path_open2 => PathOpen2,
+    path_open_tmpfile => PathOpenTmpfile,
+    path_readlink => PathReadlink,
+    path_remove_directory => PathRemoveDirectory,
+    path_rename => PathRename,
+    path_symlink => PathSymlink,
+    path_unlink_file => PathUnlinkFile,
+    poll_oneoff => PollOneoff,
+    port_addr_add => PortAddrAdd,
+    port_addr_clear => PortAddrClear,
+    port_addr_list => PortAddrList,
+    port_addr_remove => PortAddrRemove,
+    port_bridge => PortBridge,
+    port_dhcp_acquire => PortDhcpAcquire,
+    port_gateway_set => PortGatewaySet,
+    port_mac => PortMac,
+    port_route_add => PortRouteAdd,
+    port_route_clear => PortRouteClear,
+    port_route_list => PortRouteList,
+    port_route_remove => PortRouteRemove,
+    port_unbridge => PortUnbridge,
+    proc_daemonize => ProcDaemonize,
+    proc_exec => ProcExec,
+    proc_exec2 => ProcExec2,
+    proc_exec3 => ProcExec3,
+    proc_exit => ProcExit,
+    proc_fork => ProcFork,
+    proc_id => ProcId,
+    proc_join => ProcJoin,
+    proc_package_metadata => ProcPackageMetadata,
+    proc_parent => ProcParent,
+    proc_raise => ProcRaise,
+    proc_raise_interval => ProcRaiseInterval,
+    proc_signal => ProcSignal,
+    proc_signals_get => ProcSignalsGet,
+    proc_signals_sizes_get => ProcSignalsSizesGet,
+    proc_snapshot => ProcSnapshot,
+    proc_spawn => ProcSpawn,
+    proc_spawn2 => ProcSpawn2,
+    publickey_close => PublickeyClose,
+    publickey_export => PublickeyExport,
+    publickey_import => PublickeyImport,
+    random_get => RandomGet,
+    reflect_signature => ReflectSignature,
+    resolve => Resolve,
+    sched_yield => SchedYield,
+    signature_close => SignatureClose,
+    signature_export => SignatureExport,
+    signature_import => SignatureImport,
+    signature_state_close => SignatureStateClose,
+    signature_state_open => SignatureStateOpen,
+    signature_state_sign => SignatureStateSign,
+    signature_state_update => SignatureStateUpdate,
+    signature_verification_state_close => SignatureVerificationStateClose,
+    signature_verification_state_open => SignatureVerificationStateOpen,
+    signature_verification_state_update => SignatureVerificationStateUpdate,
+    signature_verification_state_verify => SignatureVerificationStateVerify,
+    sock_accept => SockAccept,
+    sock_accept_v2 => SockAcceptV2,
+    sock_addr_local => SockAddrLocal,
+    sock_addr_peer => SockAddrPeer,
+    sock_bind => SockBind,
+    sock_connect => SockConnect,
+    sock_get_opt_bytes => SockGetOptBytes,
+    sock_get_opt_flag => SockGetOptFlag,
+    sock_get_opt_size => SockGetOptSize,
+    sock_get_opt_time => SockGetOptTime,
+    sock_join_multicast_v4 => SockJoinMulticastV4,
+    sock_join_multicast_v6 => SockJoinMulticastV6,
+    sock_leave_multicast_v4 => SockLeaveMulticastV4,
+    sock_leave_multicast_v6 => SockLeaveMulticastV6,
+    sock_listen => SockListen,
+    sock_open => SockOpen,
+    sock_pair => SockPair,
+    sock_recv => SockRecv,
+    sock_recv_from => SockRecvFrom,
+    sock_send => SockSend,
+    sock_send_file => SockSendFile,
+    sock_send_to => SockSendTo,
+    sock_set_opt_bytes => SockSetOptBytes,
+    sock_set_opt_flag => SockSetOptFlag,
+    sock_set_opt_size => SockSetOptSize,
+    sock_set_opt_time => SockSetOptTime,
+    sock_shutdown => SockShutdown,
+    sock_status => SockStatus,
+    sock_tls_upgrade => SockTlsUpgrade,
+    stack_checkpoint => StackCheckpoint,
+    stack_restore => StackRestore,
+    thread_exit => ThreadExit,
+    thread_id => ThreadId,
+    thread_join => ThreadJoin,
+    thread_parallelism => ThreadParallelism,
+    thread_signal => ThreadSignal,
+    thread_sleep => ThreadSleep,
+    thread_spawn => ThreadSpawn,
+    thread_spawn_v2 => ThreadSpawnV2,
+    tty_get => TtyGet,
+    tty_set => TtySet,
+}
+
+const COUNT: usize = Syscall::ALL.len();
+
+/// How many times a process made each syscall, as of when the counters were
+/// read with [`WasiProcess::syscall_counters()`].
+///
+/// A syscall that forwards to another one, like an older version of a
+/// syscall, counts towards both.
+///
+/// [`WasiProcess::syscall_counters()`]: crate::WasiProcess::syscall_counters
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyscallCounters {
+    counts: [u64; COUNT],
+}
+
+impl Default for SyscallCounters {
+    fn default() -> Self {
+        Self { counts: [0; COUNT] }
+    }
+}
+
+impl SyscallCounters {
+    /// Number of calls to `syscall`
+    pub fn get(&self, syscall: Syscall) -> u64 {
+        self.counts[syscall as usize]
+    }
+
+    /// Number of calls to any syscall
+    pub fn total(&self) -> u64 {
+        self.counts.iter().sum()
+    }
+
+    /// The syscalls that were made at least once, with their counts
+    pub fn iter(&self) -> impl Iterator<Item = (Syscall, u64)> + '_ {
+        Syscall::ALL
+            .iter()
+            .map(|syscall| (*syscall, self.get(*syscall)))
+            .filter(|(_, count)| *count > 0)
+    }
+
+    /// The calls made since `earlier` was read, e.g. to compute rates
+    pub fn delta(&self, earlier: &SyscallCounters) -> SyscallCounters {
+        let mut delta = self.clone();
+        for (count, earlier) in delta.counts.iter_mut().zip(earlier.counts) {
+            *count = count.saturating_sub(earlier);
+        }
+        delta
+    }
+}
+
+/// The counters a process increments as it makes syscalls.
+#[derive(Debug, Clone)]
+pub(crate) struct LiveSyscallCounters {
+    #[cfg(feature = "syscall-counters")]
+    counts: Arc<[AtomicU64; COUNT]>,
+}
+
+impl Default for LiveSyscallCounters {
+    fn default() -> Self {
+        Self {
+            #[cfg(feature = "syscall-counters")]
+            counts: Arc::new(std::array::from_fn(|_| AtomicU64::new(0))),
+        }
+    }
+}
+
+impl LiveSyscallCounters {
+    #[inline]
+    pub(crate) fn count(&self, syscall: Syscall) {
+        #[cfg(feature = "syscall-counters")]
+        self.counts[syscall as usize].fetch_add(1, Ordering::Relaxed);
+        #[cfg(not(feature = "syscall-counters"))]
+        let _ = syscall;
+    }
+
+    pub(crate) fn read(&self) -> SyscallCounters {
+        #[allow(unused_mut)]
+        let mut counters = SyscallCounters::default();
+        #[cfg(feature = "syscall-counters")]
+        for (count, live) in counters.counts.iter_mut().zip(self.counts.iter()) {
+            *count = live.load(Ordering::Relaxed);
+        }
+        counters
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_match_the_imports() {
+        assert_eq!(Syscall::FdWrite.name(), "fd_write");
+        assert_eq!(
+            Syscall::ALL[Syscall::SockAcceptV2 as usize],
+            Syscall::SockAcceptV2
+        );
+    }
+
+    #[test]
+    fn delta_only_keeps_the_new_calls() {
+        let mut earlier = SyscallCounters::default();
+        earlier.counts[Syscall::FdRead as usize] = 3;
+        let mut later = earlier.clone();
+        later.counts[Syscall::FdRead as usize] = 5;
+        later.counts[Syscall::FdWrite as usize] = 1;
+
+        let delta = later.delta(&earlier);
+        assert_eq!(delta.fd_read(), 2);
+        assert_eq!(delta.fd_write(), 1);
+        assert_eq!(delta.total(), 3);
+        assert_eq!(
+            delta.iter().collect::<Vec<_>>(),
+            [(Syscall::FdRead, 2), (Syscall::FdWrite, 1)]
+        );
+    }
+}
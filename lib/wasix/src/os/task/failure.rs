@@ -0,0 +1,231 @@
+//! Context about how and where a WASIX process failed.
+
+use std::fmt;
+
+use wasmer::RuntimeError;
+use wasmer_types::{FrameInfo, TrapCode};
+use wasmer_wasix_types::wasi::{ExitCode, Signal};
+
+use super::{process::WasiProcessId, thread::WasiThreadId};
+use crate::{WasiEnv, WasiRuntimeError};
+
+/// The number of frames that are kept from a trap's backtrace.
+const MAX_FRAMES: usize = 8;
+
+/// Why a process stopped running.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExitIntent {
+    /// The process exited with a non-zero exit code.
+    Exit(ExitCode),
+    /// The process hit a WebAssembly trap (e.g. `unreachable` or an
+    /// out-of-bounds memory access).
+    Trap(TrapCode),
+    /// The process was terminated by a signal.
+    Signal(Signal),
+    /// The process failed for some other reason (e.g. a host error).
+    Error,
+}
+
+impl ExitIntent {
+    fn from_error(error: &WasiRuntimeError, signal: Option<Signal>) -> Self {
+        if let Some(signal) = signal {
+            return ExitIntent::Signal(signal);
+        }
+        if let Some(code) = error.as_exit_code() {
+            return ExitIntent::Exit(code);
+        }
+
+        match error {
+            WasiRuntimeError::Runtime(err) => match err.clone().to_trap() {
+                Some(code) => ExitIntent::Trap(code),
+                None => ExitIntent::Error,
+            },
+            _ => ExitIntent::Error,
+        }
+    }
+}
+
+impl fmt::Display for ExitIntent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExitIntent::Exit(code) => write!(f, "exited with code {code}"),
+            ExitIntent::Trap(code) => write!(f, "trapped: {}", code.message()),
+            ExitIntent::Signal(signal) => write!(f, "terminated by {signal:?}"),
+            ExitIntent::Error => write!(f, "failed"),
+        }
+    }
+}
+
+/// A [`WasiRuntimeError`] together with the process it was raised in.
+///
+/// The `Display` implementation prints a single line. Use the alternate
+/// form (`{:#}`) to get a multi-line report that includes the backtrace.
+#[derive(Debug)]
+pub struct ProcessFailure {
+    pid: WasiProcessId,
+    tid: WasiThreadId,
+    package: Option<String>,
+    package_hash: Option<String>,
+    command: Option<String>,
+    intent: ExitIntent,
+    frames: Vec<FrameInfo>,
+    error: WasiRuntimeError,
+}
+
+impl ProcessFailure {
+    /// Capture the context of the process `env` belongs to at the point where
+    /// it failed with `error`.
+    pub(crate) fn capture(env: &WasiEnv, error: WasiRuntimeError) -> Self {
+        let (package, package_hash, command) = match env.state.package.lock().unwrap().as_ref() {
+            Some(metadata) => (
+                metadata.name.clone(),
+                metadata.webc_sha256.clone(),
+                Some(metadata.command.clone()),
+            ),
+            None => (None, None, None),
+        };
+        let frames = match &error {
+            WasiRuntimeError::Runtime(err) => top_frames(err),
+            _ => Vec::new(),
+        };
+
+        ProcessFailure {
+            pid: env.pid(),
+            tid: env.tid(),
+            package,
+            package_hash,
+            command,
+            intent: ExitIntent::from_error(&error, env.thread.terminating_signal()),
+            frames,
+            error,
+        }
+    }
+
+    /// The process that failed.
+    pub fn pid(&self) -> WasiProcessId {
+        self.pid
+    }
+
+    /// The thread the failure was raised on.
+    pub fn tid(&self) -> WasiThreadId {
+        self.tid
+    }
+
+    /// The name of the package the process was started from, if it was
+    /// started from a named package.
+    pub fn package(&self) -> Option<&str> {
+        self.package.as_deref()
+    }
+
+    /// The SHA-256 hash of the webc the process was started from, if known.
+    pub fn package_hash(&self) -> Option<&str> {
+        self.package_hash.as_deref()
+    }
+
+    /// The command that was running, if the process was started from a
+    /// package.
+    pub fn command(&self) -> Option<&str> {
+        self.command.as_deref()
+    }
+
+    /// Why the process stopped.
+    pub fn intent(&self) -> &ExitIntent {
+        &self.intent
+    }
+
+    /// The innermost WebAssembly frames at the point of failure, innermost
+    /// first.
+    pub fn frames(&self) -> &[FrameInfo] {
+        &self.frames
+    }
+
+    /// The underlying error.
+    pub fn error(&self) -> &WasiRuntimeError {
+        &self.error
+    }
+
+    /// Copy this context onto another error.
+    pub(crate) fn with_error(&self, error: WasiRuntimeError) -> Self {
+        ProcessFailure {
+            pid: self.pid,
+            tid: self.tid,
+            package: self.package.clone(),
+            package_hash: self.package_hash.clone(),
+            command: self.command.clone(),
+            intent: self.intent.clone(),
+            frames: self.frames.clone(),
+            error,
+        }
+    }
+}
+
+fn top_frames(err: &RuntimeError) -> Vec<FrameInfo> {
+    err.trace().iter().take(MAX_FRAMES).cloned().collect()
+}
+
+fn write_frame(f: &mut fmt::Formatter<'_>, frame: &FrameInfo) -> fmt::Result {
+    match frame.function_name() {
+        Some(name) => write!(f, "{}!{}", frame.module_name(), name)?,
+        None => write!(
+            f,
+            "{}!<wasm function {}>",
+            frame.module_name(),
+            frame.func_index()
+        )?,
+    }
+    write!(f, " @ {:#x}", frame.module_offset())?;
+    if let Some(location) = frame.source_location() {
+        write!(f, " ({location})")?;
+    }
+    Ok(())
+}
+
+impl fmt::Display for ProcessFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if f.alternate() {
+            writeln!(f, "Process {} {}", self.pid, self.intent)?;
+            if let Some(command) = &self.command {
+                writeln!(f, "  command: {command}")?;
+            }
+            if let Some(package) = &self.package {
+                writeln!(f, "  package: {package}")?;
+            }
+            if let Some(hash) = &self.package_hash {
+                writeln!(f, "  hash:    {hash}")?;
+            }
+            write!(f, "  thread:  {}", self.tid)?;
+            if matches!(self.intent, ExitIntent::Error) {
+                write!(f, "\n  error:   {}", self.error)?;
+            }
+            if !self.frames.is_empty() {
+                write!(f, "\n  backtrace:")?;
+                for (i, frame) in self.frames.iter().enumerate() {
+                    write!(f, "\n    {i:>2}: ")?;
+                    write_frame(f, frame)?;
+                }
+            }
+            Ok(())
+        } else {
+            write!(f, "process {} (thread {}", self.pid, self.tid)?;
+            if let Some(command) = &self.command {
+                write!(f, ", command \"{command}\"")?;
+            }
+            write!(f, ") {}", self.intent)?;
+            if let Some(frame) = self.frames.first() {
+                write!(f, " in ")?;
+                write_frame(f, frame)?;
+            }
+            if matches!(self.intent, ExitIntent::Error) {
+                write!(f, ": {}", self.error)?;
+            }
+            Ok(())
+        }
+    }
+}
+
+impl std::error::Error for ProcessFailure {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        // The underlying error is already part of our own message
+        std::error::Error::source(&self.error)
+    }
+}
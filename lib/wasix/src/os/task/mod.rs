@@ -2,6 +2,8 @@
 
 pub mod backoff;
 pub mod control_plane;
+pub mod counters;
+pub mod failure;
 pub mod process;
 pub mod signal;
 mod task_join_handle;
@@ -1,6 +1,6 @@
 #[cfg(feature = "journal")]
 use crate::{journal::JournalEffector, syscalls::do_checkpoint_from_outside, unwind, WasiResult};
-use crate::{journal::SnapshotTrigger, WasiEnv, WasiRuntimeError};
+use crate::{journal::SnapshotTrigger, log_sink::LogSinkHandle, WasiEnv, WasiRuntimeError};
 use serde::{Deserialize, Serialize};
 #[cfg(feature = "journal")]
 use std::collections::HashSet;
@@ -25,16 +25,17 @@ use wasmer_wasix_types::{
 };
 
 use crate::{
-    os::task::signal::WasiSignalInterval, syscalls::platform_clock_time_get, WasiThread,
-    WasiThreadHandle, WasiThreadId,
+    bin_factory::lookup::CommandLookupCache, os::task::signal::WasiSignalInterval,
+    syscalls::platform_clock_time_get, WasiThread, WasiThreadHandle, WasiThreadId,
 };
 
 use super::{
     backoff::WasiProcessCpuBackoff,
-    control_plane::{ControlPlaneError, WasiControlPlaneHandle},
+    control_plane::{ControlPlaneError, ProcessAccount, WasiControlPlaneHandle},
+    counters::{LiveSyscallCounters, Syscall, SyscallCounters},
     signal::{SignalDeliveryError, SignalHandlerAbi},
     task_join_handle::OwnedTaskStatus,
-    thread::WasiMemoryLayout,
+    thread::{WasiMemoryLayout, WasiThreadHandleProtected},
     TaskStatus,
 };
 
@@ -111,6 +112,15 @@ pub struct WasiProcess {
     /// the exponential backoff of CPU is halted (as in CPU
     /// is allowed to run freely)
     pub(crate) cpu_run_tokens: Arc<AtomicU32>,
+    /// Where the runtime's events for this process go, if not to the
+    /// global subscriber
+    pub(crate) log_sink: Option<Arc<LogSinkHandle>>,
+    /// What the process is charged on the control plane
+    pub(crate) account: Arc<ProcessAccount>,
+    /// How many times this process made each syscall
+    pub(crate) syscall_counters: LiveSyscallCounters,
+    /// Where the commands this process spawned were found in its `PATH`
+    pub(crate) command_lookups: Arc<RwLock<CommandLookupCache>>,
 }
 
 /// Represents a freeze of all threads to perform some action
@@ -161,10 +171,21 @@ pub struct WasiProcessInner {
     pub threads: HashMap<WasiThreadId, WasiThread>,
     /// Number of threads running for this process
     pub thread_count: u32,
+    /// Execution time consumed by the threads of this process that
+    /// have already exited
+    pub(crate) exited_cpu_time: Duration,
     /// Signals that will be triggered at specific intervals
     pub signal_intervals: HashMap<Signal, WasiSignalInterval>,
     /// List of all the children spawned from this thread
     pub children: Vec<WasiProcess>,
+    /// The session this process belongs to, or `None` when it leads its own
+    pub sid: Option<WasiProcessId>,
+    /// The process group this process belongs to, or `None` when it leads
+    /// its own
+    pub pgid: Option<WasiProcessId>,
+    /// The handle that keeps the main thread alive, which is normally owned
+    /// by the parent until the process detaches itself
+    pub(crate) main_thread_handle: Weak<WasiThreadHandleProtected>,
     /// Represents a checkpoint which blocks all the threads
     /// and then executes some maintenance action
     pub checkpoint: WasiProcessCheckpoint,
@@ -428,8 +449,12 @@ impl WasiProcess {
                 pid,
                 threads: Default::default(),
                 thread_count: Default::default(),
+                exited_cpu_time: Duration::ZERO,
                 signal_intervals: Default::default(),
                 children: Default::default(),
+                sid: None,
+                pgid: None,
+                main_thread_handle: Weak::new(),
                 checkpoint: WasiProcessCheckpoint::Execute,
                 wakers: Default::default(),
                 waiting: waiting.clone(),
@@ -469,6 +494,10 @@ impl WasiProcess {
             ),
             waiting,
             cpu_run_tokens: Arc::new(AtomicU32::new(0)),
+            log_sink: None,
+            account: ProcessAccount::detached(),
+            syscall_counters: Default::default(),
+            command_lookups: Default::default(),
         }
     }
 
@@ -491,6 +520,55 @@ impl WasiProcess {
             .unwrap_or(WasiProcessId(0))
     }
 
+    /// Gets the ID of the session this process belongs to
+    pub fn sid(&self) -> WasiProcessId {
+        self.inner.0.lock().unwrap().sid.unwrap_or(self.pid)
+    }
+
+    /// Gets the ID of the process group this process belongs to
+    pub fn pgid(&self) -> WasiProcessId {
+        self.inner.0.lock().unwrap().pgid.unwrap_or(self.pid)
+    }
+
+    /// Puts this process in the same session and process group as `parent`
+    pub(crate) fn inherit_session(&self, parent: &WasiProcess) {
+        let (sid, pgid) = (parent.sid(), parent.pgid());
+        let mut inner = self.inner.0.lock().unwrap();
+        inner.sid = Some(sid);
+        inner.pgid = Some(pgid);
+    }
+
+    /// Detaches this process from the session of the process that started
+    /// it, making it the leader of a new session and process group.
+    ///
+    /// The process is removed from the children of its parent, so the
+    /// parent no longer waits on it or forwards signals to it, and the
+    /// returned handle keeps its main thread alive after the parent exits.
+    ///
+    /// Fails with `EPERM` when the process already leads a session.
+    pub fn detach(&self) -> Result<WasiThreadHandle, Errno> {
+        let handle = {
+            let mut inner = self.inner.0.lock().unwrap();
+            if inner.sid.is_none() {
+                return Err(Errno::Perm);
+            }
+            let handle = WasiThreadHandle::upgrade(&inner.main_thread_handle).ok_or(Errno::Srch)?;
+            inner.sid = None;
+            inner.pgid = None;
+            handle
+        };
+
+        // Nothing points back at the parent, so look for it
+        if let Some(control_plane) = self.compute.upgrade() {
+            for process in control_plane.processes() {
+                if process.pid != self.pid {
+                    process.lock().children.retain(|c| c.pid != self.pid);
+                }
+            }
+        }
+        Ok(handle)
+    }
+
     /// Gains access to the process internals
     // TODO: Make this private, all inner access should be exposed with methods.
     pub fn lock(&self) -> MutexGuard<'_, WasiProcessInner> {
@@ -541,7 +619,7 @@ impl WasiProcess {
         };
 
         // Insert the thread into the pool
-        let ctrl = WasiThread::new(
+        let mut ctrl = WasiThread::new(
             self.pid(),
             tid,
             is_main,
@@ -550,10 +628,17 @@ impl WasiProcess {
             layout,
             start,
         );
+        if is_main {
+            ctrl = ctrl.with_process_account(self.account.clone());
+        }
         inner.threads.insert(tid, ctrl.clone());
         inner.thread_count += 1;
 
-        Ok(WasiThreadHandle::new(ctrl, &self.inner))
+        let handle = WasiThreadHandle::new(ctrl, &self.inner);
+        if is_main {
+            inner.main_thread_handle = handle.downgrade();
+        }
+        Ok(handle)
     }
 
     pub fn all_threads(&self) -> Vec<WasiThreadId> {
@@ -597,6 +682,20 @@ impl WasiProcess {
         signal_process_internal(&self.inner, signal);
     }
 
+    /// Sends a signal to this process, like `kill()`.
+    ///
+    /// [`Signal::Signone`] is not delivered, it only checks that the process
+    /// is still running. Returns [`Errno::Srch`] if the process has exited.
+    pub fn signal(&self, signal: Signal) -> Result<(), Errno> {
+        if self.try_join().is_some() {
+            return Err(Errno::Srch);
+        }
+        if signal != Signal::Signone {
+            self.signal_process(signal);
+        }
+        Ok(())
+    }
+
     /// Takes a snapshot of the process and disables journaling returning
     /// a future that can be waited on for the snapshot to complete
     ///
@@ -762,6 +861,31 @@ impl WasiProcess {
         inner.thread_count
     }
 
+    /// Returns the execution time consumed by all the threads of this
+    /// process, including the ones that already exited
+    pub fn cpu_time(&self) -> Duration {
+        let inner = self.inner.0.lock().unwrap();
+        inner
+            .threads
+            .values()
+            .map(|thread| thread.cpu_time())
+            .sum::<Duration>()
+            + inner.exited_cpu_time
+    }
+
+    /// Returns how many times this process made each syscall so far.
+    ///
+    /// The counters stay at zero unless the `syscall-counters` feature is
+    /// enabled.
+    pub fn syscall_counters(&self) -> SyscallCounters {
+        self.syscall_counters.read()
+    }
+
+    #[inline]
+    pub(crate) fn count_syscall(&self, syscall: Syscall) {
+        self.syscall_counters.count(syscall);
+    }
+
     /// Waits until the process is finished.
     pub async fn join(&self) -> Result<ExitCode, Arc<WasiRuntimeError>> {
         let _guard = WasiProcessWait::new(self);
@@ -5,25 +5,26 @@ use std::{
     ops::{Deref, DerefMut},
     sync::{Arc, Condvar, Mutex, Weak},
     task::Waker,
+    time::Duration,
 };
 
 use bytes::{Bytes, BytesMut};
 use wasmer::{ExportError, InstantiationError, MemoryError};
 use wasmer_wasix_types::{
     types::Signal,
-    wasi::{Errno, ExitCode},
+    wasi::{Errno, ExitCode, Snapshot0Clockid},
     wasix::ThreadStartType,
 };
 
 use crate::{
     os::task::process::{WasiProcessId, WasiProcessInner},
     state::LinkError,
-    syscalls::HandleRewindType,
+    syscalls::{platform_clock_time_get, HandleRewindType},
     WasiRuntimeError,
 };
 
 use super::{
-    control_plane::TaskCountGuard,
+    control_plane::{ProcessAccount, TaskCountGuard},
     task_join_handle::{OwnedTaskStatus, TaskJoinHandle},
 };
 
@@ -244,6 +245,8 @@ struct WasiThreadState {
     signals: Mutex<(Vec<Signal>, Vec<Waker>)>,
     stack: Mutex<ThreadStack>,
     status: Arc<OwnedTaskStatus>,
+    terminated_by: Mutex<Option<Signal>>,
+    cpu_clock: Mutex<CpuClock>,
     #[cfg(feature = "journal")]
     check_pointing: AtomicBool,
     deep_sleeping: AtomicBool,
@@ -251,6 +254,50 @@ struct WasiThreadState {
     // Registers the task termination with the ControlPlane on drop.
     // Never accessed, since it's a drop guard.
     _task_count_guard: TaskCountGuard,
+
+    /// The account of the process, released when this (main) thread
+    /// finishes.
+    process_account: Option<Arc<ProcessAccount>>,
+}
+
+/// Accounts for the execution time of a thread.
+///
+/// A thread counts as running from when it is scheduled until it blocks in
+/// the runtime (waiting on I/O, sleeping, yielding or going into a deep
+/// sleep) or finishes, and the time is accumulated at those switches. The
+/// host doesn't tell us when the OS preempts the thread we run on, so that
+/// time is counted as well, and the resolution is the one of the host's
+/// monotonic clock.
+#[derive(Debug, Default)]
+struct CpuClock {
+    /// Execution time (in nanoseconds) accumulated so far
+    total: u64,
+    /// Monotonic timestamp of when the thread was last scheduled, if it
+    /// is currently running
+    running_since: Option<u64>,
+}
+
+impl CpuClock {
+    fn now() -> u64 {
+        platform_clock_time_get(Snapshot0Clockid::Monotonic, 1).unwrap_or_default() as u64
+    }
+
+    fn elapsed(&self) -> u64 {
+        let running = self
+            .running_since
+            .map(|since| Self::now().saturating_sub(since))
+            .unwrap_or_default();
+        self.total + running
+    }
+
+    fn resume(&mut self) {
+        self.running_since.get_or_insert_with(Self::now);
+    }
+
+    fn pause(&mut self) {
+        self.total = self.elapsed();
+        self.running_since = None;
+    }
 }
 
 static NO_MORE_BYTES: [u8; 0] = [0u8; 0];
@@ -273,10 +320,13 @@ impl WasiThread {
                 status,
                 signals: Mutex::new((Vec::new(), Vec::new())),
                 stack: Mutex::new(ThreadStack::default()),
+                terminated_by: Mutex::new(None),
+                cpu_clock: Mutex::new(CpuClock::default()),
                 #[cfg(feature = "journal")]
                 check_pointing: AtomicBool::new(false),
                 deep_sleeping: AtomicBool::new(false),
                 _task_count_guard: guard,
+                process_account: None,
             }),
             layout,
             start,
@@ -322,6 +372,9 @@ impl WasiThread {
             Signal::Sigpipe => Errno::Pipe.into(),
             _ => Errno::Intr.into(),
         };
+        if self.try_join().is_none() {
+            self.state.terminated_by.lock().unwrap().get_or_insert(sig);
+        }
         // This will only set the status code if its not already set
         self.set_status_finished(Ok(default_exitcode));
         self.try_join()
@@ -329,10 +382,51 @@ impl WasiThread {
             .unwrap_or(default_exitcode)
     }
 
+    /// The signal that terminated this thread, if it was terminated by one
+    pub fn terminating_signal(&self) -> Option<Signal> {
+        *self.state.terminated_by.lock().unwrap()
+    }
+
     /// Marks the thread as finished (which will cause anyone that
     /// joined on it to wake up)
     pub fn set_status_finished(&self, res: Result<ExitCode, WasiRuntimeError>) {
+        self.pause_cpu_clock();
         self.state.status.set_finished(res.map_err(Arc::new));
+        if let Some(account) = &self.state.process_account {
+            account.exit();
+        }
+    }
+
+    /// Makes this (main) thread release `account` when it finishes, so
+    /// the process stops counting towards the control plane's limits.
+    pub(crate) fn with_process_account(mut self, account: Arc<ProcessAccount>) -> Self {
+        Arc::get_mut(&mut self.state)
+            .expect("the thread has already been shared")
+            .process_account = Some(account);
+        self
+    }
+
+    /// Returns the execution time this thread has consumed so far
+    ///
+    /// See [`WasiThread::resume_cpu_clock`] for how this is accounted.
+    pub fn cpu_time(&self) -> Duration {
+        Duration::from_nanos(self.state.cpu_clock.lock().unwrap().elapsed())
+    }
+
+    /// Marks the thread as running again after it was scheduled, which
+    /// makes the time that passes count towards its [`WasiThread::cpu_time`].
+    ///
+    /// The execution time is accumulated at syscall boundaries and
+    /// scheduler switches, so any time the host OS spends running other
+    /// work in between is counted as well.
+    pub(crate) fn resume_cpu_clock(&self) {
+        self.state.cpu_clock.lock().unwrap().resume();
+    }
+
+    /// Marks the thread as no longer running, for instance because it is
+    /// about to block on the async runtime
+    pub(crate) fn pause_cpu_clock(&self) {
+        self.state.cpu_clock.lock().unwrap().pause();
     }
 
     /// Waits until the thread is finished or the timeout is reached
@@ -412,6 +506,21 @@ impl WasiThread {
         }
     }
 
+    /// Returns whether any of the signals waiting to be processed matches
+    /// `filter`, subscribing `waker` to new signals if none does
+    pub fn has_signal_matching_or_subscribe(
+        &self,
+        waker: &Waker,
+        filter: impl Fn(Signal) -> bool,
+    ) -> bool {
+        let mut guard = self.state.signals.lock().unwrap();
+        let has_signal = guard.0.iter().any(|s| filter(*s));
+        if !has_signal && !guard.1.iter().any(|w| w.will_wake(waker)) {
+            guard.1.push(waker.clone());
+        }
+        has_signal
+    }
+
     /// Returns all the signals that are waiting to be processed
     pub fn has_signals_or_subscribe(&self, waker: &Waker) -> bool {
         let mut guard = self.state.signals.lock().unwrap();
@@ -593,6 +702,14 @@ impl WasiThreadHandle {
     pub fn as_thread(&self) -> WasiThread {
         self.protected.thread.clone()
     }
+
+    pub(crate) fn downgrade(&self) -> Weak<WasiThreadHandleProtected> {
+        Arc::downgrade(&self.protected)
+    }
+
+    pub(crate) fn upgrade(weak: &Weak<WasiThreadHandleProtected>) -> Option<Self> {
+        weak.upgrade().map(|protected| Self { protected })
+    }
 }
 
 impl Drop for WasiThreadHandleProtected {
@@ -602,6 +719,7 @@ impl Drop for WasiThreadHandleProtected {
             let mut inner = inner.0.lock().unwrap();
             if let Some(ctrl) = inner.threads.remove(&id) {
                 ctrl.set_status_finished(Ok(Errno::Success.into()));
+                inner.exited_cpu_time += ctrl.cpu_time();
             }
             inner.thread_count -= 1;
         }
@@ -90,8 +90,9 @@ impl SocketManager {
         // (or at least try to)
         self.loopback_networking
             .loopback_connect_to(SocketAddr::new(Ipv4Addr::UNSPECIFIED.into(), 0), dst)
-            .ok_or_else(|| {
+            .map_err(|err| {
                 tracing::debug!(
+                    error = &err as &dyn std::error::Error,
                     "proxy connection attempt failed - could not connect to http server socket as the loopback socket is not open",
                 );
                 anyhow::anyhow!("failed to open HTTP socket as the loopback socket is not open")
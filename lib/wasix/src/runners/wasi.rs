@@ -1,21 +1,25 @@
 //! WebC container support for running WASI modules
 
-use std::{path::PathBuf, sync::Arc};
+use std::{path::PathBuf, sync::Arc, time::Duration};
 
 use anyhow::{Context, Error};
+use futures::future::Either;
 use tracing::Instrument;
 use virtual_fs::{ArcBoxFile, FileSystem, TmpFileSystem, VirtualFile};
 use wasmer::{Engine, Module};
 use wasmer_types::ModuleHash;
+use wasmer_wasix_types::wasi::{Errno, ExitCode, Signal};
 use webc::metadata::{annotations::Wasi, Command};
 
 use crate::{
-    bin_factory::BinaryPackage,
-    capabilities::Capabilities,
+    bin_factory::{BinaryPackage, CommandAlias},
+    capabilities::{Capabilities, PathRule},
     journal::{DynJournal, DynReadableJournal, SnapshotTrigger},
+    os::task::{control_plane::WasiControlPlane, TaskJoinHandle},
     runners::{wasi_common::CommonWasiOptions, MappedDirectory, MountedDirectory},
     runtime::task_manager::VirtualTaskManagerExt,
-    Runtime, WasiEnvBuilder, WasiError, WasiRuntimeError,
+    test_report::TestReporter,
+    Runtime, VirtualTaskManager, WasiEnvBuilder, WasiError, WasiRuntimeError,
 };
 
 use super::wasi_common::{MappedCommand, MAPPED_CURRENT_DIR_DEFAULT_PATH};
@@ -26,6 +30,21 @@ pub struct WasiRunner {
     stdin: Option<ArcBoxFile>,
     stdout: Option<ArcBoxFile>,
     stderr: Option<ArcBoxFile>,
+    timeout: Option<Duration>,
+    kill_after: Option<Duration>,
+}
+
+/// How long a timed out program gets to exit after being sent `SIGTERM`
+/// before it is terminated, unless [`WasiRunner::with_kill_after()`] says
+/// otherwise.
+pub const DEFAULT_KILL_AFTER: Duration = Duration::from_secs(5);
+
+/// The error returned when a program doesn't exit within the time given to
+/// [`WasiRunner::with_timeout()`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[error("The program didn't exit within {timeout:?}")]
+pub struct TimeoutError {
+    pub timeout: Duration,
 }
 
 pub enum PackageOrHash<'a> {
@@ -170,6 +189,22 @@ impl WasiRunner {
         self
     }
 
+    /// Make a command from another package available under a different name.
+    ///
+    /// The package is fetched the first time the guest runs the command.
+    pub fn with_command_alias(&mut self, alias: CommandAlias) -> &mut Self {
+        self.wasi.command_aliases.push(alias);
+        self
+    }
+
+    pub fn with_command_aliases(
+        &mut self,
+        aliases: impl IntoIterator<Item = CommandAlias>,
+    ) -> &mut Self {
+        self.wasi.command_aliases.extend(aliases);
+        self
+    }
+
     pub fn capabilities_mut(&mut self) -> &mut Capabilities {
         &mut self.wasi.capabilities
     }
@@ -179,6 +214,16 @@ impl WasiRunner {
         self
     }
 
+    /// Declare that the program needs access to the paths matched by `rule`
+    /// even when the file system is restricted to the preopened directories
+    /// (see [`CapabilityFsV1::PreopensOnly`]).
+    ///
+    /// [`CapabilityFsV1::PreopensOnly`]: crate::capabilities::CapabilityFsV1::PreopensOnly
+    pub fn with_fs_grant(&mut self, rule: PathRule) -> &mut Self {
+        self.wasi.fs_grants.push(rule);
+        self
+    }
+
     #[cfg(feature = "journal")]
     pub fn with_snapshot_trigger(&mut self, on: SnapshotTrigger) -> &mut Self {
         self.wasi.snapshot_on.push(on);
@@ -237,6 +282,31 @@ impl WasiRunner {
         self
     }
 
+    /// Stops the program if it is still running after `timeout`.
+    ///
+    /// All processes are first sent `SIGTERM`, and those that haven't exited
+    /// after the grace period (see [`WasiRunner::with_kill_after()`]) are
+    /// terminated. Either way, running fails with a [`TimeoutError`].
+    pub fn with_timeout(&mut self, timeout: Duration) -> &mut Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Sets how long a timed out program gets to shut down after being sent
+    /// `SIGTERM` (default: [`DEFAULT_KILL_AFTER`]).
+    pub fn with_kill_after(&mut self, grace_period: Duration) -> &mut Self {
+        self.kill_after = Some(grace_period);
+        self
+    }
+
+    /// Passes the test events the program writes to
+    /// [`TEST_REPORT_PATH`](crate::test_report::TEST_REPORT_PATH) to
+    /// `reporter` (see [`WasiEnvBuilder::with_test_reporter()`]).
+    pub fn with_test_reporter(&mut self, reporter: impl Into<TestReporter>) -> &mut Self {
+        self.wasi.test_reporter = Some(reporter.into());
+        self
+    }
+
     pub fn with_stdout(&mut self, stdout: Box<dyn VirtualFile + Send + Sync>) -> &mut Self {
         self.stdout = Some(ArcBoxFile::new(stdout));
         self
@@ -393,15 +463,23 @@ impl WasiRunner {
         let env = builder.build()?;
         let runtime = env.runtime.clone();
         let tasks = runtime.task_manager().clone();
+        let control_plane = env.control_plane.clone();
 
-        let mut task_handle =
+        let task_handle =
             crate::bin_factory::spawn_exec_module(module, env, &runtime).context("Spawn failed")?;
 
         #[cfg(feature = "ctrlc")]
         task_handle.install_ctrlc_handler();
-        let task_handle = async move { task_handle.wait_finished().await }.in_current_span();
-
-        let result = tasks.spawn_and_block_on(task_handle)?;
+        let task_handle = wait_finished(
+            task_handle,
+            control_plane,
+            tasks.clone(),
+            self.timeout,
+            self.kill_after,
+        )
+        .in_current_span();
+
+        let result = tasks.spawn_and_block_on(task_handle)??;
         let exit_code = result
             .map_err(|err| {
                 // We do our best to recover the error
@@ -494,7 +572,9 @@ impl WasiRunner {
         let runtime = env.runtime.clone();
         let command_name = command_name.to_string();
         let tasks = runtime.task_manager().clone();
+        let control_plane = env.control_plane.clone();
         let pkg = pkg.clone();
+        let (timeout, kill_after) = (self.timeout, self.kill_after);
 
         // Wrapping the call to `spawn_and_block_on` in a call to `spawn_await` could help to prevent deadlocks
         // because then blocking in here won't block the tokio runtime
@@ -502,17 +582,16 @@ impl WasiRunner {
         // See run_wasm above for a possible fix
         let exit_code = tasks.spawn_and_block_on(
             async move {
-                let mut task_handle =
-                    crate::bin_factory::spawn_exec(pkg, &command_name, env, &runtime)
-                        .await
-                        .context("Spawn failed")?;
+                let task_handle = crate::bin_factory::spawn_exec(pkg, &command_name, env, &runtime)
+                    .await
+                    .context("Spawn failed")?;
 
                 #[cfg(feature = "ctrlc")]
                 task_handle.install_ctrlc_handler();
 
-                task_handle
-                    .wait_finished()
-                    .await
+                let tasks = runtime.task_manager().clone();
+                wait_finished(task_handle, control_plane, tasks, timeout, kill_after)
+                    .await?
                     .map_err(|err| {
                         // We do our best to recover the error
                         let msg = err.to_string();
@@ -558,6 +637,45 @@ impl crate::runners::Runner for WasiRunner {
     }
 }
 
+/// Waits for the program to exit, stopping it if it runs into the timeout.
+async fn wait_finished(
+    mut task_handle: TaskJoinHandle,
+    control_plane: WasiControlPlane,
+    tasks: Arc<dyn VirtualTaskManager>,
+    timeout: Option<Duration>,
+    kill_after: Option<Duration>,
+) -> Result<Result<ExitCode, Arc<WasiRuntimeError>>, TimeoutError> {
+    let Some(timeout) = timeout else {
+        return Ok(task_handle.wait_finished().await);
+    };
+    let kill_after = kill_after.unwrap_or(DEFAULT_KILL_AFTER);
+
+    let finished = task_handle.wait_finished();
+    futures::pin_mut!(finished);
+
+    if let Either::Left((result, _)) =
+        futures::future::select(finished.as_mut(), tasks.sleep_now(timeout)).await
+    {
+        return Ok(result);
+    }
+
+    tracing::debug!(?timeout, "Timed out, asking all processes to shut down");
+    control_plane.signal_all(Signal::Sigterm);
+
+    if let Either::Right(_) =
+        futures::future::select(finished.as_mut(), tasks.sleep_now(kill_after)).await
+    {
+        tracing::debug!(
+            ?kill_after,
+            "Terminating the processes that are still running"
+        );
+        control_plane.terminate_all(Errno::Timedout.into());
+        let _ = finished.await;
+    }
+
+    Err(TimeoutError { timeout })
+}
+
 fn wasi_runtime_error_to_owned(err: &WasiRuntimeError) -> WasiRuntimeError {
     match err {
         WasiRuntimeError::Init(a) => WasiRuntimeError::Init(a.clone()),
@@ -580,6 +698,9 @@ fn wasi_runtime_error_to_owned(err: &WasiRuntimeError) -> WasiRuntimeError {
         WasiRuntimeError::Runtime(a) => WasiRuntimeError::Runtime(a.clone()),
         WasiRuntimeError::Thread(a) => WasiRuntimeError::Thread(a.clone()),
         WasiRuntimeError::Anyhow(a) => WasiRuntimeError::Anyhow(a.clone()),
+        WasiRuntimeError::Process(failure) => WasiRuntimeError::Process(Box::new(
+            failure.with_error(wasi_runtime_error_to_owned(failure.error())),
+        )),
     }
 }
 
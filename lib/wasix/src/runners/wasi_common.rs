@@ -11,9 +11,10 @@ use virtual_fs::{FileSystem, FsError, OverlayFileSystem, RootFileSystemBuilder,
 use webc::metadata::annotations::Wasi as WasiAnnotation;
 
 use crate::{
-    bin_factory::BinaryPackage,
-    capabilities::Capabilities,
+    bin_factory::{BinaryPackage, CommandAlias},
+    capabilities::{Capabilities, PathRule},
     journal::{DynJournal, DynReadableJournal, SnapshotTrigger},
+    test_report::{TestReporter, TEST_REPORT_PATH},
     WasiEnvBuilder,
 };
 
@@ -34,11 +35,13 @@ pub(crate) struct CommonWasiOptions {
     pub(crate) env: HashMap<String, String>,
     pub(crate) forward_host_env: bool,
     pub(crate) mapped_host_commands: Vec<MappedCommand>,
+    pub(crate) command_aliases: Vec<CommandAlias>,
     pub(crate) mounts: Vec<MountedDirectory>,
     pub(crate) is_home_mapped: bool,
     pub(crate) is_tmp_mapped: bool,
     pub(crate) injected_packages: Vec<BinaryPackage>,
     pub(crate) capabilities: Capabilities,
+    pub(crate) fs_grants: Vec<PathRule>,
     pub(crate) read_only_journals: Vec<Arc<DynReadableJournal>>,
     pub(crate) writable_journals: Vec<Arc<DynJournal>>,
     pub(crate) snapshot_on: Vec<SnapshotTrigger>,
@@ -46,6 +49,7 @@ pub(crate) struct CommonWasiOptions {
     pub(crate) stop_running_after_snapshot: bool,
     pub(crate) skip_stdio_during_bootstrap: bool,
     pub(crate) current_dir: Option<PathBuf>,
+    pub(crate) test_reporter: Option<TestReporter>,
 }
 
 impl CommonWasiOptions {
@@ -61,10 +65,16 @@ impl CommonWasiOptions {
         }
 
         let root_fs = root_fs.unwrap_or_else(|| {
-            RootFileSystemBuilder::default()
-                .with_tmp(!self.is_tmp_mapped)
-                .build()
+            let root_fs = RootFileSystemBuilder::default().with_tmp(!self.is_tmp_mapped);
+            if !self.is_tmp_mapped {
+                builder.set_tmp_fs(root_fs.tmp_fs().clone());
+            }
+            root_fs.build()
         });
+        if let Some(reporter) = &self.test_reporter {
+            crate::test_report::install(&root_fs, reporter.clone());
+            builder.add_fs_grant(PathRule::read_write(TEST_REPORT_PATH));
+        }
         let fs = prepare_filesystem(root_fs, &self.mounts, container_fs)?;
 
         // TODO: What's a preopen for '.' supposed to mean anyway? Why do we need it?
@@ -87,12 +97,21 @@ impl CommonWasiOptions {
             .iter()
             .map(|c| (c.alias.as_str(), c.target.as_str()));
         builder.add_mapped_commands(mapped_cmds);
+        builder.add_command_aliases(self.command_aliases.iter().cloned());
 
         self.populate_env(wasi, builder);
         self.populate_args(wasi, builder);
 
         *builder.capabilities_mut() = self.capabilities.clone();
 
+        // Whatever the user mounted is meant to be used by the program
+        for mount in &self.mounts {
+            builder.add_fs_grant(PathRule::read_write(&mount.guest));
+        }
+        for rule in &self.fs_grants {
+            builder.add_fs_grant(rule.clone());
+        }
+
         #[cfg(feature = "journal")]
         {
             for journal in &self.read_only_journals {
@@ -380,6 +399,21 @@ impl<F: FileSystem> virtual_fs::FileSystem for RelativeOrAbsolutePathHack<F> {
             f.mount(name_ref.clone(), p, Box::new(f_ref.clone()))
         })
     }
+
+    fn create_tmpfile(
+        &self,
+        path: &Path,
+    ) -> virtual_fs::Result<Box<dyn virtual_fs::VirtualFile + Send + Sync + 'static>> {
+        self.execute(path, |fs, p| fs.create_tmpfile(p))
+    }
+
+    fn link_tmpfile(
+        &self,
+        file: &dyn virtual_fs::VirtualFile,
+        path: &Path,
+    ) -> virtual_fs::Result<()> {
+        self.execute(path, |fs, p| fs.link_tmpfile(file, p))
+    }
 }
 
 impl<F: FileSystem> virtual_fs::FileOpener for RelativeOrAbsolutePathHack<F> {
@@ -35,6 +35,14 @@ pub(crate) async fn default_create_env(conf: CreateEnvConfig) -> anyhow::Result<
             insecure_allow_all: true,
             http_client: HttpClientCapabilityV1::new_allow_all(),
             threading: Default::default(),
+            signals: Default::default(),
+            tls: Default::default(),
+            fs: Default::default(),
+            cpu: Default::default(),
+            listen: Default::default(),
+            kv: Default::default(),
+            archive: Default::default(),
+            crypto: Default::default(),
         });
     let env = builder.build()?;
 
@@ -0,0 +1,223 @@
+use std::{
+    fs::{File, OpenOptions},
+    io::{BufReader, Read, Write},
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use crate::runtime::kv::{InMemoryKvBackend, KvBackend, KvError};
+
+const PUT: u8 = 0;
+const DELETE: u8 = 1;
+
+/// A [`KvBackend`] that persists its entries to a log file on the host.
+///
+/// Every put and delete is appended to the log before it is applied, and
+/// the log is replayed into memory when the store is opened. A record that
+/// was only partially written (e.g. because the host crashed) is dropped
+/// along with anything after it.
+///
+/// The log is never compacted, so it grows with every change.
+#[derive(Debug)]
+pub struct FileKvBackend {
+    path: PathBuf,
+    entries: InMemoryKvBackend,
+    log: Mutex<File>,
+}
+
+impl FileKvBackend {
+    /// Open the log at `path`, creating it if it doesn't exist.
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self, KvError> {
+        let path = path.into();
+        let entries = InMemoryKvBackend::new();
+
+        let mut log = OpenOptions::new()
+            .read(true)
+            .append(true)
+            .create(true)
+            .open(&path)?;
+        let valid_len = replay(&mut log, &entries)?;
+        // Drop whatever was left of a torn write so new records aren't
+        // appended after it
+        if log.metadata()?.len() > valid_len {
+            tracing::warn!(path=%path.display(), "Discarding a truncated key-value log record");
+            log.set_len(valid_len)?;
+        }
+
+        Ok(FileKvBackend {
+            path,
+            entries,
+            log: Mutex::new(log),
+        })
+    }
+
+    /// Limit the size of the keys and values stored in each namespace, in
+    /// bytes (see [`InMemoryKvBackend::with_quota()`]).
+    pub fn with_quota(self, max_bytes: usize) -> Self {
+        FileKvBackend {
+            entries: self.entries.with_quota(max_bytes),
+            ..self
+        }
+    }
+
+    /// The path of the log file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn append(&self, record: &[u8]) -> Result<(), KvError> {
+        let mut log = self.log.lock().unwrap();
+        log.write_all(record)?;
+        log.flush()?;
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl KvBackend for FileKvBackend {
+    async fn get(&self, namespace: &str, key: &[u8]) -> Result<Option<Vec<u8>>, KvError> {
+        self.entries.get(namespace, key).await
+    }
+
+    async fn put(&self, namespace: &str, key: &[u8], value: &[u8]) -> Result<(), KvError> {
+        let record = encode(PUT, &[namespace.as_bytes(), key, value]);
+        self.entries
+            .put_with(namespace, key, value, || self.append(&record))
+    }
+
+    async fn delete(&self, namespace: &str, key: &[u8]) -> Result<bool, KvError> {
+        let record = encode(DELETE, &[namespace.as_bytes(), key]);
+        self.entries
+            .delete_with(namespace, key, || self.append(&record))
+    }
+
+    async fn list(&self, namespace: &str, prefix: &[u8]) -> Result<Vec<Vec<u8>>, KvError> {
+        self.entries.list(namespace, prefix).await
+    }
+}
+
+/// A record is its kind, followed by its fields, each prefixed with its
+/// length as a little-endian `u32`.
+fn encode(kind: u8, fields: &[&[u8]]) -> Vec<u8> {
+    let mut record = vec![kind];
+    for field in fields {
+        record.extend_from_slice(&(field.len() as u32).to_le_bytes());
+        record.extend_from_slice(field);
+    }
+    record
+}
+
+/// Apply the records in the log, returning the length of the complete
+/// records that were read.
+fn replay(log: &mut File, entries: &InMemoryKvBackend) -> Result<u64, KvError> {
+    let mut reader = BufReader::new(log);
+    let mut valid_len = 0;
+
+    loop {
+        let mut kind = [0; 1];
+        if reader.read(&mut kind)? == 0 {
+            break;
+        }
+        let field_count = match kind[0] {
+            PUT => 3,
+            DELETE => 2,
+            _ => break,
+        };
+
+        let mut fields = Vec::with_capacity(field_count);
+        let mut record_len = 1;
+        for _ in 0..field_count {
+            match read_field(&mut reader)? {
+                Some(field) => {
+                    record_len += 4 + field.len() as u64;
+                    fields.push(field);
+                }
+                None => break,
+            }
+        }
+        if fields.len() != field_count {
+            break;
+        }
+        let Ok(namespace) = std::str::from_utf8(&fields[0]) else {
+            break;
+        };
+
+        match kind[0] {
+            PUT => entries.put_with(namespace, &fields[1], &fields[2], || Ok(()))?,
+            _ => {
+                entries.delete_with(namespace, &fields[1], || Ok(()))?;
+            }
+        }
+        valid_len += record_len;
+    }
+
+    Ok(valid_len)
+}
+
+fn read_field(reader: &mut impl Read) -> Result<Option<Vec<u8>>, KvError> {
+    let mut len = [0; 4];
+    if let Err(e) = reader.read_exact(&mut len) {
+        return match e.kind() {
+            std::io::ErrorKind::UnexpectedEof => Ok(None),
+            _ => Err(e.into()),
+        };
+    }
+
+    let len = u32::from_le_bytes(len) as usize;
+    let mut field = Vec::new();
+    reader.take(len as u64).read_to_end(&mut field)?;
+    if field.len() != len {
+        return Ok(None);
+    }
+    Ok(Some(field))
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::TempDir;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn entries_survive_reopening() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("kv.log");
+
+        let kv = FileKvBackend::open(&path).unwrap();
+        kv.put("ns", b"kept", b"1").await.unwrap();
+        kv.put("ns", b"replaced", b"old").await.unwrap();
+        kv.put("ns", b"replaced", b"new").await.unwrap();
+        kv.put("ns", b"deleted", b"1").await.unwrap();
+        kv.delete("ns", b"deleted").await.unwrap();
+        drop(kv);
+
+        let kv = FileKvBackend::open(&path).unwrap();
+        assert_eq!(kv.get("ns", b"kept").await.unwrap().unwrap(), b"1");
+        assert_eq!(kv.get("ns", b"replaced").await.unwrap().unwrap(), b"new");
+        assert!(kv.get("ns", b"deleted").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn a_torn_write_is_discarded() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("kv.log");
+
+        let kv = FileKvBackend::open(&path).unwrap();
+        kv.put("ns", b"complete", b"1").await.unwrap();
+        drop(kv);
+        let complete_len = std::fs::metadata(&path).unwrap().len();
+        let mut log = OpenOptions::new().append(true).open(&path).unwrap();
+        log.write_all(&encode(PUT, &[b"ns", b"torn", b"value"])[..10])
+            .unwrap();
+        drop(log);
+
+        let kv = FileKvBackend::open(&path).unwrap();
+        assert_eq!(std::fs::metadata(&path).unwrap().len(), complete_len);
+        assert!(kv.get("ns", b"torn").await.unwrap().is_none());
+        kv.put("ns", b"after", b"2").await.unwrap();
+        drop(kv);
+
+        let kv = FileKvBackend::open(&path).unwrap();
+        assert_eq!(kv.list("ns", b"").await.unwrap().len(), 2);
+    }
+}
@@ -0,0 +1,169 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Mutex,
+};
+
+use crate::runtime::kv::{KvBackend, KvError};
+
+/// A [`KvBackend`] that keeps its entries in memory, so they are lost when
+/// it is dropped.
+#[derive(Debug, Default)]
+pub struct InMemoryKvBackend {
+    namespaces: Mutex<HashMap<String, Namespace>>,
+    quota: Option<usize>,
+}
+
+#[derive(Debug, Default)]
+struct Namespace {
+    entries: BTreeMap<Vec<u8>, Vec<u8>>,
+    /// The size of the keys and values in `entries`.
+    size: usize,
+}
+
+impl InMemoryKvBackend {
+    pub fn new() -> Self {
+        InMemoryKvBackend::default()
+    }
+
+    /// Limit the size of the keys and values stored in each namespace, in
+    /// bytes. Puts that would exceed it fail with [`KvError::QuotaExceeded`].
+    pub fn with_quota(self, max_bytes: usize) -> Self {
+        InMemoryKvBackend {
+            quota: Some(max_bytes),
+            ..self
+        }
+    }
+
+    /// Set a key after checking the quota, calling `persist` before the
+    /// change is applied so it can be saved elsewhere first.
+    pub(crate) fn put_with(
+        &self,
+        namespace: &str,
+        key: &[u8],
+        value: &[u8],
+        persist: impl FnOnce() -> Result<(), KvError>,
+    ) -> Result<(), KvError> {
+        let mut namespaces = self.namespaces.lock().unwrap();
+        let ns = namespaces.entry(namespace.to_string()).or_default();
+
+        let previous = ns.entries.get(key).map_or(0, |old| key.len() + old.len());
+        let size = ns.size - previous + key.len() + value.len();
+        if self.quota.is_some_and(|quota| size > quota) {
+            return Err(KvError::QuotaExceeded);
+        }
+
+        persist()?;
+        ns.entries.insert(key.to_vec(), value.to_vec());
+        ns.size = size;
+        Ok(())
+    }
+
+    /// Remove a key, calling `persist` before the change is applied if the
+    /// key was set.
+    pub(crate) fn delete_with(
+        &self,
+        namespace: &str,
+        key: &[u8],
+        persist: impl FnOnce() -> Result<(), KvError>,
+    ) -> Result<bool, KvError> {
+        let mut namespaces = self.namespaces.lock().unwrap();
+        let Some(ns) = namespaces.get_mut(namespace) else {
+            return Ok(false);
+        };
+        let Some(value) = ns.entries.get(key) else {
+            return Ok(false);
+        };
+
+        let size = ns.size - key.len() - value.len();
+        persist()?;
+        ns.entries.remove(key);
+        ns.size = size;
+        Ok(true)
+    }
+}
+
+#[async_trait::async_trait]
+impl KvBackend for InMemoryKvBackend {
+    async fn get(&self, namespace: &str, key: &[u8]) -> Result<Option<Vec<u8>>, KvError> {
+        let namespaces = self.namespaces.lock().unwrap();
+        Ok(namespaces
+            .get(namespace)
+            .and_then(|ns| ns.entries.get(key))
+            .cloned())
+    }
+
+    async fn put(&self, namespace: &str, key: &[u8], value: &[u8]) -> Result<(), KvError> {
+        self.put_with(namespace, key, value, || Ok(()))
+    }
+
+    async fn delete(&self, namespace: &str, key: &[u8]) -> Result<bool, KvError> {
+        self.delete_with(namespace, key, || Ok(()))
+    }
+
+    async fn list(&self, namespace: &str, prefix: &[u8]) -> Result<Vec<Vec<u8>>, KvError> {
+        let namespaces = self.namespaces.lock().unwrap();
+        let Some(ns) = namespaces.get(namespace) else {
+            return Ok(Vec::new());
+        };
+        Ok(ns
+            .entries
+            .range(prefix.to_vec()..)
+            .map(|(key, _)| key)
+            .take_while(|key| key.starts_with(prefix))
+            .cloned()
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn namespaces_are_kept_apart() {
+        let kv = InMemoryKvBackend::new();
+
+        kv.put("a", b"key", b"first").await.unwrap();
+        kv.put("b", b"key", b"second").await.unwrap();
+
+        assert_eq!(kv.get("a", b"key").await.unwrap().unwrap(), b"first");
+        assert_eq!(kv.get("b", b"key").await.unwrap().unwrap(), b"second");
+        assert!(kv.delete("a", b"key").await.unwrap());
+        assert!(!kv.delete("a", b"key").await.unwrap());
+        assert!(kv.get("a", b"key").await.unwrap().is_none());
+        assert!(kv.get("b", b"key").await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn keys_are_listed_by_prefix() {
+        let kv = InMemoryKvBackend::new();
+
+        for key in ["user/2", "post/1", "user/1", "user", "users/1"] {
+            kv.put("ns", key.as_bytes(), b"").await.unwrap();
+        }
+
+        assert_eq!(
+            kv.list("ns", b"user/").await.unwrap(),
+            [b"user/1".to_vec(), b"user/2".to_vec()]
+        );
+        assert_eq!(kv.list("ns", b"").await.unwrap().len(), 5);
+        assert!(kv.list("other", b"").await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn the_quota_applies_to_each_namespace() {
+        let kv = InMemoryKvBackend::new().with_quota(8);
+
+        kv.put("a", b"key", b"12345").await.unwrap();
+        assert!(matches!(
+            kv.put("a", b"other", b"x").await,
+            Err(KvError::QuotaExceeded)
+        ));
+        // Replacing a value only counts the difference
+        kv.put("a", b"key", b"54321").await.unwrap();
+        kv.put("b", b"key", b"12345").await.unwrap();
+
+        kv.delete("a", b"key").await.unwrap();
+        kv.put("a", b"other", b"x").await.unwrap();
+    }
+}
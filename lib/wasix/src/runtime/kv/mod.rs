@@ -0,0 +1,66 @@
+//! Key-value stores that guests can use to keep state without a file system.
+//!
+//! Guests reach the store returned by [`Runtime::kv_backend()`] through the
+//! `kv_get()`, `kv_put()`, `kv_delete()` and `kv_scan()` syscalls, once the
+//! [`CapabilityKvV1`] capability has been granted. Each program gets its own
+//! namespace, the name of the package it was started from unless the
+//! capability says otherwise, so processes of the same package share their
+//! keys while other packages can't see them.
+//!
+//! Two backends are provided: the [`InMemoryKvBackend`], and a
+//! [`FileKvBackend`] which persists its entries to a log file (behind the
+//! `host-kv` feature).
+//!
+//! [`Runtime::kv_backend()`]: crate::Runtime::kv_backend
+//! [`CapabilityKvV1`]: crate::capabilities::CapabilityKvV1
+
+#[cfg(feature = "host-kv")]
+mod file;
+mod in_memory;
+mod types;
+
+pub use self::{
+    in_memory::InMemoryKvBackend,
+    types::{DynKvBackend, KvBackend, KvError},
+};
+
+#[cfg(feature = "host-kv")]
+pub use self::file::FileKvBackend;
+
+use wasmer_wasix_types::wasi::Errno;
+
+use crate::WasiEnv;
+
+/// The namespace used by programs that weren't started from a package.
+pub const DEFAULT_NAMESPACE: &str = "default";
+
+/// Looks up the store a process may use and the namespace its keys live in.
+pub(crate) fn for_env(env: &WasiEnv) -> Result<(&DynKvBackend, String), Errno> {
+    if !env.capabilities.kv.enabled {
+        return Err(Errno::Notcapable);
+    }
+    let backend = env.runtime().kv_backend().ok_or(Errno::Notsup)?;
+
+    let namespace = match &env.capabilities.kv.namespace {
+        Some(namespace) => namespace.clone(),
+        None => env
+            .state
+            .package
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|package| package.name.clone().unwrap_or_else(|| package.id.clone()))
+            .unwrap_or_else(|| DEFAULT_NAMESPACE.to_string()),
+    };
+
+    Ok((backend, namespace))
+}
+
+impl From<KvError> for Errno {
+    fn from(error: KvError) -> Self {
+        match error {
+            KvError::QuotaExceeded => Errno::Nospc,
+            KvError::Io(_) | KvError::Other(_) => Errno::Io,
+        }
+    }
+}
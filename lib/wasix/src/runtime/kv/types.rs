@@ -0,0 +1,42 @@
+use std::fmt::Debug;
+
+/// A key-value store shared by the guests of a [`Runtime`](crate::Runtime).
+///
+/// Keys and values are arbitrary bytes, and every key lives in a namespace
+/// (see the [module docs](crate::runtime::kv)), which implementations must
+/// keep apart.
+#[async_trait::async_trait]
+pub trait KvBackend: Debug {
+    /// Look up the value of a key, or [`None`] if it isn't set.
+    async fn get(&self, namespace: &str, key: &[u8]) -> Result<Option<Vec<u8>>, KvError>;
+
+    /// Set the value of a key, replacing any previous value.
+    async fn put(&self, namespace: &str, key: &[u8], value: &[u8]) -> Result<(), KvError>;
+
+    /// Remove a key, returning whether it was set.
+    async fn delete(&self, namespace: &str, key: &[u8]) -> Result<bool, KvError>;
+
+    /// List the keys that start with `prefix`, in lexicographic order.
+    async fn list(&self, namespace: &str, prefix: &[u8]) -> Result<Vec<Vec<u8>>, KvError>;
+}
+
+pub type DynKvBackend = dyn KvBackend + Send + Sync;
+
+/// Possible errors that may occur during [`KvBackend`] operations.
+#[derive(Debug, thiserror::Error)]
+pub enum KvError {
+    /// Storing the value would take the namespace over its quota.
+    #[error("Quota exceeded")]
+    QuotaExceeded,
+    #[error("I/O error")]
+    Io(#[from] std::io::Error),
+    /// A catch-all variant for any other errors that may occur.
+    #[error(transparent)]
+    Other(Box<dyn std::error::Error + Send + Sync>),
+}
+
+impl KvError {
+    pub fn other(error: impl std::error::Error + Send + Sync + 'static) -> Self {
+        KvError::Other(Box::new(error))
+    }
+}
@@ -1,3 +1,4 @@
+pub mod kv;
 pub mod module_cache;
 pub mod package_loader;
 pub mod resolver;
@@ -12,11 +13,14 @@ use wasmer_types::{
 
 use std::{
     fmt,
+    net::SocketAddr,
     ops::Deref,
     sync::{Arc, Mutex},
+    time::Duration,
 };
 
 use futures::future::BoxFuture;
+use tracing::Instrument;
 use virtual_net::{DynVirtualNetworking, VirtualNetworking};
 use wasmer::{CompileError, Module, RuntimeError};
 use wasmer_wasix_types::wasi::ExitCode;
@@ -24,10 +28,12 @@ use wasmer_wasix_types::wasi::ExitCode;
 #[cfg(feature = "journal")]
 use crate::journal::{DynJournal, DynReadableJournal};
 use crate::{
-    bin_factory::BinaryPackageCommand,
+    bin_factory::{BinaryPackageCommand, DynSpawnPolicy, InFlightPackages, SpawnPolicy},
     http::{DynHttpClient, HttpClient},
+    net::listener::GuestListener,
     os::TtyBridge,
     runtime::{
+        kv::{DynKvBackend, KvBackend},
         module_cache::{ModuleCache, ThreadLocalCache},
         package_loader::{PackageLoader, UnsupportedPackageLoader},
         resolver::{BackendSource, MultiSource, Source},
@@ -43,6 +49,24 @@ pub enum TaintReason {
     DlSymbolResolutionFailed(String),
 }
 
+/// How packages requested from a registry are resolved.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PackageResolutionPolicy {
+    /// Reuse recent registry lookups.
+    #[default]
+    Cached,
+    /// Ask the registry for the latest version every time. Requests for a
+    /// specific version are still answered from the cache.
+    Refresh,
+}
+
+/// The default for [`Runtime::package_download_concurrency()`].
+pub const DEFAULT_PACKAGE_DOWNLOAD_CONCURRENCY: usize = 8;
+
+/// The default for [`Runtime::start_function_timeout()`]: start functions may
+/// run for as long as they like unless the embedder sets a timeout.
+pub const DEFAULT_START_FUNCTION_TIMEOUT: Option<Duration> = None;
+
 /// Runtime components used when running WebAssembly programs.
 ///
 /// Think of this as the "System" in "WebAssembly Systems Interface".
@@ -76,6 +100,34 @@ where
     /// The package registry.
     fn source(&self) -> Arc<dyn Source + Send + Sync>;
 
+    /// How packages are resolved against [`Runtime::source()`].
+    fn package_resolution_policy(&self) -> PackageResolutionPolicy {
+        PackageResolutionPolicy::default()
+    }
+
+    /// How many packages [`WasiEnv::uses()`](crate::WasiEnv::uses) may
+    /// download at the same time.
+    fn package_download_concurrency(&self) -> usize {
+        DEFAULT_PACKAGE_DOWNLOAD_CONCURRENCY
+    }
+
+    /// How long a module's start function may run while it's being
+    /// instantiated, or `None` to let it run for as long as it likes.
+    ///
+    /// Start functions run before the process can be signalled, so setting
+    /// this is what stops a package that never finishes starting from hanging
+    /// the spawn. There is no timeout by default.
+    fn start_function_timeout(&self) -> Option<Duration> {
+        DEFAULT_START_FUNCTION_TIMEOUT
+    }
+
+    /// The packages currently being loaded from [`Runtime::source()`], shared
+    /// by every process using this runtime so the same package is never
+    /// downloaded twice at once.
+    fn in_flight_packages(&self) -> Option<&InFlightPackages> {
+        None
+    }
+
     /// Get a [`wasmer::Engine`] for module compilation.
     fn engine(&self) -> wasmer::Engine {
         wasmer::Engine::default()
@@ -113,6 +165,13 @@ where
         None
     }
 
+    /// The certificates that servers are verified against when a guest
+    /// upgrades a socket to TLS.
+    #[cfg(feature = "host-tls")]
+    fn tls_root_store(&self) -> Arc<rustls::RootCertStore> {
+        crate::net::tls::default_root_store()
+    }
+
     /// Load the module for a command.
     ///
     /// NOTE: This always be preferred over [`Self::load_module`] to avoid
@@ -169,6 +228,26 @@ where
     /// for multiple reasons however the most common is a panic within the process
     fn on_taint(&self, _reason: TaintReason) {}
 
+    /// Callback that is invoked whenever a guest starts listening on a port
+    /// that is exposed to the host (see
+    /// [`CapabilityListenV1`](crate::capabilities::CapabilityListenV1)).
+    ///
+    /// The `listener` can be used to hand the guest connections without
+    /// going through the network, e.g. to reverse-proxy requests into it.
+    fn on_guest_listener(&self, _addr: SocketAddr, _listener: GuestListener) {}
+
+    /// Decides which programs guests may start with `proc_spawn` and
+    /// `proc_exec`, where [`None`] allows everything.
+    fn spawn_policy(&self) -> Option<&DynSpawnPolicy> {
+        None
+    }
+
+    /// The key-value store guests can use through the `kv_*` syscalls (see
+    /// the [`kv`] module), if any.
+    fn kv_backend(&self) -> Option<&DynKvBackend> {
+        None
+    }
+
     /// The list of all read-only journals which will be used to restore the state of the
     /// runtime at a particular point in time
     #[cfg(feature = "journal")]
@@ -202,12 +281,31 @@ pub async fn load_module(
     module_cache: &(dyn ModuleCache + Send + Sync),
     wasm: &[u8],
     wasm_hash: ModuleHash,
+) -> Result<Module, crate::SpawnError> {
+    let span = crate::telemetry::compile_module(&wasm_hash, wasm.len());
+    compile_module(engine, module_cache, wasm, wasm_hash, &span)
+        .instrument(span.clone())
+        .await
+}
+
+async fn compile_module(
+    engine: &wasmer::Engine,
+    module_cache: &(dyn ModuleCache + Send + Sync),
+    wasm: &[u8],
+    wasm_hash: ModuleHash,
+    span: &tracing::Span,
 ) -> Result<Module, crate::SpawnError> {
     let result = module_cache.load(wasm_hash, engine).await;
 
     match result {
-        Ok(module) => return Ok(module),
+        Ok(module) => {
+            span.record("module.cached", true);
+            return Ok(module);
+        }
         Err(CacheError::NotFound) => {}
+        Err(e @ CacheError::CompilationNotAllowed { .. }) => {
+            return Err(crate::SpawnError::CacheError(e));
+        }
         Err(other) => {
             tracing::warn!(
                 %wasm_hash,
@@ -216,6 +314,7 @@ pub async fn load_module(
             );
         }
     }
+    span.record("module.cached", false);
 
     let module = Module::new(&engine, wasm).map_err(|err| crate::SpawnError::CompileError {
         module_hash: wasm_hash,
@@ -257,16 +356,30 @@ impl TtyBridge for DefaultTty {
     }
 }
 
-#[derive(Debug, Clone)]
+/// A callback for [`Runtime::on_guest_listener()`].
+pub type GuestListenerCallback = Arc<dyn Fn(SocketAddr, GuestListener) + Send + Sync>;
+
+#[derive(derive_more::Debug, Clone)]
 pub struct PluggableRuntime {
     pub rt: Arc<dyn VirtualTaskManager>,
     pub networking: DynVirtualNetworking,
     pub http_client: Option<DynHttpClient>,
     pub package_loader: Arc<dyn PackageLoader + Send + Sync>,
     pub source: Arc<dyn Source + Send + Sync>,
+    pub package_resolution_policy: PackageResolutionPolicy,
+    pub package_download_concurrency: usize,
+    pub start_function_timeout: Option<Duration>,
+    pub in_flight_packages: InFlightPackages,
     pub engine: wasmer::Engine,
     pub module_cache: Arc<dyn ModuleCache + Send + Sync>,
     pub tty: Option<Arc<dyn TtyBridge + Send + Sync>>,
+    #[debug(ignore)]
+    pub on_guest_listener: Option<GuestListenerCallback>,
+    #[debug(ignore)]
+    pub spawn_policy: Option<Arc<DynSpawnPolicy>>,
+    pub kv_backend: Option<Arc<DynKvBackend>>,
+    #[cfg(feature = "host-tls")]
+    pub tls_root_store: Option<Arc<rustls::RootCertStore>>,
     #[cfg(feature = "journal")]
     pub read_only_journals: Vec<Arc<DynReadableJournal>>,
     #[cfg(feature = "journal")]
@@ -302,9 +415,18 @@ impl PluggableRuntime {
             http_client,
             engine: Default::default(),
             tty: None,
+            on_guest_listener: None,
+            spawn_policy: None,
+            kv_backend: None,
             source: Arc::new(source),
+            package_resolution_policy: PackageResolutionPolicy::default(),
+            package_download_concurrency: DEFAULT_PACKAGE_DOWNLOAD_CONCURRENCY,
+            start_function_timeout: DEFAULT_START_FUNCTION_TIMEOUT,
+            in_flight_packages: InFlightPackages::new(),
             package_loader: Arc::new(loader),
             module_cache: Arc::new(module_cache::in_memory()),
+            #[cfg(feature = "host-tls")]
+            tls_root_store: None,
             #[cfg(feature = "journal")]
             read_only_journals: Vec::new(),
             #[cfg(feature = "journal")]
@@ -343,6 +465,21 @@ impl PluggableRuntime {
         self
     }
 
+    pub fn set_package_resolution_policy(&mut self, policy: PackageResolutionPolicy) -> &mut Self {
+        self.package_resolution_policy = policy;
+        self
+    }
+
+    pub fn set_package_download_concurrency(&mut self, limit: usize) -> &mut Self {
+        self.package_download_concurrency = limit;
+        self
+    }
+
+    pub fn set_start_function_timeout(&mut self, timeout: Option<Duration>) -> &mut Self {
+        self.start_function_timeout = timeout;
+        self
+    }
+
     pub fn set_package_loader(
         &mut self,
         package_loader: impl PackageLoader + 'static,
@@ -359,6 +496,38 @@ impl PluggableRuntime {
         self
     }
 
+    #[cfg(feature = "host-tls")]
+    pub fn set_tls_root_store(&mut self, roots: Arc<rustls::RootCertStore>) -> &mut Self {
+        self.tls_root_store = Some(roots);
+        self
+    }
+
+    /// Calls `callback` whenever a guest starts listening on an exposed port
+    /// (see [`Runtime::on_guest_listener()`]).
+    pub fn set_guest_listener_callback(
+        &mut self,
+        callback: impl Fn(SocketAddr, GuestListener) + Send + Sync + 'static,
+    ) -> &mut Self {
+        self.on_guest_listener = Some(Arc::new(callback));
+        self
+    }
+
+    /// Decides which programs guests may start (see
+    /// [`Runtime::spawn_policy()`]).
+    pub fn set_spawn_policy(
+        &mut self,
+        policy: impl SpawnPolicy + Send + Sync + 'static,
+    ) -> &mut Self {
+        self.spawn_policy = Some(Arc::new(policy));
+        self
+    }
+
+    /// The key-value store guests can use (see [`Runtime::kv_backend()`]).
+    pub fn set_kv_backend(&mut self, backend: impl KvBackend + Send + Sync + 'static) -> &mut Self {
+        self.kv_backend = Some(Arc::new(backend));
+        self
+    }
+
     #[cfg(feature = "journal")]
     pub fn add_read_only_journal(&mut self, journal: Arc<DynReadableJournal>) -> &mut Self {
         self.read_only_journals.push(journal);
@@ -377,6 +546,20 @@ impl Runtime for PluggableRuntime {
         &self.networking
     }
 
+    fn on_guest_listener(&self, addr: SocketAddr, listener: GuestListener) {
+        if let Some(callback) = &self.on_guest_listener {
+            callback(addr, listener);
+        }
+    }
+
+    fn spawn_policy(&self) -> Option<&DynSpawnPolicy> {
+        self.spawn_policy.as_deref()
+    }
+
+    fn kv_backend(&self) -> Option<&DynKvBackend> {
+        self.kv_backend.as_deref()
+    }
+
     fn http_client(&self) -> Option<&DynHttpClient> {
         self.http_client.as_ref()
     }
@@ -389,6 +572,22 @@ impl Runtime for PluggableRuntime {
         Arc::clone(&self.source)
     }
 
+    fn package_resolution_policy(&self) -> PackageResolutionPolicy {
+        self.package_resolution_policy
+    }
+
+    fn package_download_concurrency(&self) -> usize {
+        self.package_download_concurrency
+    }
+
+    fn start_function_timeout(&self) -> Option<Duration> {
+        self.start_function_timeout
+    }
+
+    fn in_flight_packages(&self) -> Option<&InFlightPackages> {
+        Some(&self.in_flight_packages)
+    }
+
     fn engine(&self) -> wasmer::Engine {
         self.engine.clone()
     }
@@ -401,6 +600,13 @@ impl Runtime for PluggableRuntime {
         &self.rt
     }
 
+    #[cfg(feature = "host-tls")]
+    fn tls_root_store(&self) -> Arc<rustls::RootCertStore> {
+        self.tls_root_store
+            .clone()
+            .unwrap_or_else(crate::net::tls::default_root_store)
+    }
+
     fn tty(&self) -> Option<&(dyn TtyBridge + Send + Sync)> {
         self.tty.as_deref()
     }
@@ -435,9 +641,14 @@ pub struct OverriddenRuntime {
     http_client: Option<DynHttpClient>,
     package_loader: Option<Arc<dyn PackageLoader + Send + Sync>>,
     source: Option<Arc<dyn Source + Send + Sync>>,
+    package_resolution_policy: Option<PackageResolutionPolicy>,
+    package_download_concurrency: Option<usize>,
+    start_function_timeout: Option<Option<Duration>>,
     engine: Option<wasmer::Engine>,
     module_cache: Option<Arc<dyn ModuleCache + Send + Sync>>,
     tty: Option<Arc<dyn TtyBridge + Send + Sync>>,
+    #[cfg(feature = "host-tls")]
+    tls_root_store: Option<Arc<rustls::RootCertStore>>,
     #[cfg(feature = "journal")]
     pub read_only_journals: Option<Vec<Arc<DynReadableJournal>>>,
     #[cfg(feature = "journal")]
@@ -453,9 +664,14 @@ impl OverriddenRuntime {
             http_client: None,
             package_loader: None,
             source: None,
+            package_resolution_policy: None,
+            package_download_concurrency: None,
+            start_function_timeout: None,
             engine: None,
             module_cache: None,
             tty: None,
+            #[cfg(feature = "host-tls")]
+            tls_root_store: None,
             #[cfg(feature = "journal")]
             read_only_journals: None,
             #[cfg(feature = "journal")]
@@ -491,6 +707,21 @@ impl OverriddenRuntime {
         self
     }
 
+    pub fn with_package_resolution_policy(mut self, policy: PackageResolutionPolicy) -> Self {
+        self.package_resolution_policy.replace(policy);
+        self
+    }
+
+    pub fn with_package_download_concurrency(mut self, limit: usize) -> Self {
+        self.package_download_concurrency.replace(limit);
+        self
+    }
+
+    pub fn with_start_function_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.start_function_timeout.replace(timeout);
+        self
+    }
+
     pub fn with_engine(mut self, engine: wasmer::Engine) -> Self {
         self.engine.replace(engine);
         self
@@ -506,6 +737,12 @@ impl OverriddenRuntime {
         self
     }
 
+    #[cfg(feature = "host-tls")]
+    pub fn with_tls_root_store(mut self, roots: Arc<rustls::RootCertStore>) -> Self {
+        self.tls_root_store.replace(roots);
+        self
+    }
+
     #[cfg(feature = "journal")]
     pub fn with_read_only_journals(mut self, journals: Vec<Arc<DynReadableJournal>>) -> Self {
         self.read_only_journals.replace(journals);
@@ -536,6 +773,18 @@ impl Runtime for OverriddenRuntime {
         }
     }
 
+    fn on_guest_listener(&self, addr: SocketAddr, listener: GuestListener) {
+        self.inner.on_guest_listener(addr, listener)
+    }
+
+    fn spawn_policy(&self) -> Option<&DynSpawnPolicy> {
+        self.inner.spawn_policy()
+    }
+
+    fn kv_backend(&self) -> Option<&DynKvBackend> {
+        self.inner.kv_backend()
+    }
+
     fn source(&self) -> Arc<dyn Source + Send + Sync> {
         if let Some(source) = self.source.clone() {
             source
@@ -544,6 +793,25 @@ impl Runtime for OverriddenRuntime {
         }
     }
 
+    fn package_resolution_policy(&self) -> PackageResolutionPolicy {
+        self.package_resolution_policy
+            .unwrap_or_else(|| self.inner.package_resolution_policy())
+    }
+
+    fn package_download_concurrency(&self) -> usize {
+        self.package_download_concurrency
+            .unwrap_or_else(|| self.inner.package_download_concurrency())
+    }
+
+    fn start_function_timeout(&self) -> Option<Duration> {
+        self.start_function_timeout
+            .unwrap_or_else(|| self.inner.start_function_timeout())
+    }
+
+    fn in_flight_packages(&self) -> Option<&InFlightPackages> {
+        self.inner.in_flight_packages()
+    }
+
     fn package_loader(&self) -> Arc<dyn PackageLoader + Send + Sync> {
         if let Some(loader) = self.package_loader.clone() {
             loader
@@ -592,6 +860,15 @@ impl Runtime for OverriddenRuntime {
         }
     }
 
+    #[cfg(feature = "host-tls")]
+    fn tls_root_store(&self) -> Arc<rustls::RootCertStore> {
+        if let Some(roots) = self.tls_root_store.as_ref() {
+            roots.clone()
+        } else {
+            self.inner.tls_root_store()
+        }
+    }
+
     #[cfg(feature = "journal")]
     fn read_only_journals<'a>(&'a self) -> Box<dyn Iterator<Item = Arc<DynReadableJournal>> + 'a> {
         if let Some(journals) = self.read_only_journals.as_ref() {
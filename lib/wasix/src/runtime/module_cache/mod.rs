@@ -3,7 +3,8 @@
 //! The core of this module is the [`ModuleCache`] trait, which is designed to
 //! be implemented by different cache storage strategies, such as in-memory
 //! caches ([`SharedCache`] and [`ThreadLocalCache`]), file-based caches
-//! ([`FileSystemCache`]), or distributed caches. Implementing custom caching
+//! ([`FileSystemCache`]), modules compiled ahead of time
+//! ([`PrecompiledCache`]), or distributed caches. Implementing custom caching
 //! strategies allows you to optimize for your specific use case.
 //!
 //! ## Assumptions and Requirements
@@ -34,6 +35,8 @@
 mod fallback;
 #[cfg(feature = "sys-thread")]
 mod filesystem;
+#[cfg(feature = "sys-thread")]
+mod precompiled;
 mod shared;
 mod thread_local;
 mod types;
@@ -48,6 +51,11 @@ use wasmer_types::ModuleHash;
 
 #[cfg(feature = "sys-thread")]
 pub use self::filesystem::FileSystemCache;
+#[cfg(feature = "sys-thread")]
+pub use self::precompiled::{
+    PrecompiledArtifact, PrecompiledArtifactsBuilder, PrecompiledCache, PrecompiledIndex,
+    PRECOMPILED_INDEX_FILE,
+};
 
 /// Get a [`ModuleCache`] which should be good enough for most in-memory use
 /// cases.
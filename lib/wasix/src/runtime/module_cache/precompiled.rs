@@ -0,0 +1,559 @@
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::{Read, Seek, SeekFrom, Write},
+    path::{Component, Path, PathBuf},
+};
+
+use wasmer::{Engine, Module};
+
+use crate::runtime::module_cache::{CacheError, ModuleCache, ModuleHash, SharedCache};
+
+/// The name of the index in a directory of precompiled artifacts.
+pub const PRECOMPILED_INDEX_FILE: &str = "index.json";
+
+/// The magic bytes at the start of a bundle of precompiled artifacts.
+const BUNDLE_MAGIC: &[u8; 8] = b"\0wasmerp";
+
+/// Describes a set of modules that were compiled ahead of time (see
+/// [`PrecompiledArtifactsBuilder`]).
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct PrecompiledIndex {
+    /// The [`Engine::deterministic_id()`] of the engine the modules were
+    /// compiled with.
+    pub engine: String,
+    /// The version of the serialized artifacts (see
+    /// [`wasmer_types::MetadataHeader::CURRENT_VERSION`]).
+    pub artifact_version: u32,
+    /// The artifacts, keyed by the [`ModuleHash`] of the module they were
+    /// compiled from.
+    pub artifacts: BTreeMap<String, PrecompiledArtifact>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct PrecompiledArtifact {
+    /// The name of the file containing the serialized module, relative to the
+    /// index.
+    pub file: String,
+    /// The size of the serialized module in bytes.
+    pub size: u64,
+    /// The commands that run this module.
+    pub commands: Vec<String>,
+}
+
+/// Collects compiled modules and writes them out in a format that can be
+/// loaded by the [`PrecompiledCache`].
+#[derive(Debug)]
+pub struct PrecompiledArtifactsBuilder {
+    engine: String,
+    artifacts: BTreeMap<String, (PrecompiledArtifact, bytes::Bytes)>,
+}
+
+impl PrecompiledArtifactsBuilder {
+    /// Creates an empty set of artifacts for modules compiled with `engine`.
+    pub fn new(engine: &Engine) -> Self {
+        PrecompiledArtifactsBuilder {
+            engine: engine.deterministic_id(),
+            artifacts: BTreeMap::new(),
+        }
+    }
+
+    /// Records that `command` runs a module that was already added, returning
+    /// `false` if there is no such module yet.
+    pub fn add_command(&mut self, key: ModuleHash, command: &str) -> bool {
+        match self.artifacts.get_mut(&key.to_string()) {
+            Some((artifact, _)) => {
+                artifact.commands.push(command.to_string());
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Adds a module that is run by `command`.
+    ///
+    /// Adding the same module for several commands only stores it once.
+    pub fn add(
+        &mut self,
+        key: ModuleHash,
+        command: &str,
+        module: &Module,
+    ) -> Result<(), CacheError> {
+        if self.add_command(key, command) {
+            return Ok(());
+        }
+
+        let key = key.to_string();
+        let serialized = module.serialize()?;
+        let artifact = PrecompiledArtifact {
+            file: format!("{key}.bin"),
+            size: serialized.len() as u64,
+            commands: vec![command.to_string()],
+        };
+        self.artifacts.insert(key, (artifact, serialized));
+
+        Ok(())
+    }
+
+    /// Returns the index describing the artifacts added so far.
+    pub fn index(&self) -> PrecompiledIndex {
+        PrecompiledIndex {
+            engine: self.engine.clone(),
+            artifact_version: wasmer_types::MetadataHeader::CURRENT_VERSION,
+            artifacts: self
+                .artifacts
+                .iter()
+                .map(|(key, (artifact, _))| (key.clone(), artifact.clone()))
+                .collect(),
+        }
+    }
+
+    /// Writes every artifact to its own file in `dir`, next to an
+    /// [`PRECOMPILED_INDEX_FILE`].
+    pub fn write_dir(&self, dir: &Path) -> Result<PrecompiledIndex, CacheError> {
+        std::fs::create_dir_all(dir).map_err(|error| CacheError::FileWrite {
+            path: dir.to_path_buf(),
+            error,
+        })?;
+
+        for (artifact, serialized) in self.artifacts.values() {
+            let path = dir.join(&artifact.file);
+            std::fs::write(&path, serialized)
+                .map_err(|error| CacheError::FileWrite { path, error })?;
+        }
+
+        // The index goes last, so an interrupted write is never mistaken
+        // for a complete one
+        let index = self.index();
+        let path = dir.join(PRECOMPILED_INDEX_FILE);
+        let json = serde_json::to_vec_pretty(&index).map_err(CacheError::other)?;
+        std::fs::write(&path, json).map_err(|error| CacheError::FileWrite { path, error })?;
+
+        Ok(index)
+    }
+
+    /// Writes all artifacts into a single file.
+    ///
+    /// The bundle starts with a magic number and the length of the index as a
+    /// little-endian `u64`, followed by the JSON-encoded index and the
+    /// artifacts in the order they appear in the index.
+    pub fn write_bundle(&self, path: &Path) -> Result<PrecompiledIndex, CacheError> {
+        let index = self.index();
+        let json = serde_json::to_vec(&index).map_err(CacheError::other)?;
+
+        let write = || -> std::io::Result<()> {
+            let mut file = std::io::BufWriter::new(File::create(path)?);
+            file.write_all(BUNDLE_MAGIC)?;
+            file.write_all(&(json.len() as u64).to_le_bytes())?;
+            file.write_all(&json)?;
+            for (_, serialized) in self.artifacts.values() {
+                file.write_all(serialized)?;
+            }
+            file.flush()
+        };
+
+        write().map_err(|error| CacheError::FileWrite {
+            path: path.to_path_buf(),
+            error,
+        })?;
+
+        Ok(index)
+    }
+}
+
+/// Where the artifacts listed in an index are stored.
+#[derive(Debug, Clone)]
+enum Storage {
+    /// One file per artifact, next to the index.
+    Directory(PathBuf),
+    /// A single bundle, with the offset of each artifact.
+    Bundle {
+        path: PathBuf,
+        offsets: BTreeMap<String, u64>,
+    },
+}
+
+/// A read-only [`ModuleCache`] that loads modules which were compiled ahead
+/// of time (e.g. with `wasmer compile` or a [`PrecompiledArtifactsBuilder`]).
+///
+/// A module that isn't in the index, or that was compiled with a different
+/// engine, is reported as missing after logging a warning, so it can be
+/// compiled instead. Use [`PrecompiledCache::with_required()`] to forbid
+/// compiling it altogether.
+#[derive(Debug, Clone)]
+pub struct PrecompiledCache {
+    index: PrecompiledIndex,
+    storage: Storage,
+    required: bool,
+    loaded: SharedCache,
+}
+
+impl PrecompiledCache {
+    /// Opens a directory of artifacts or a bundle.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, CacheError> {
+        let path = path.as_ref();
+
+        if path.is_dir() {
+            let index_path = path.join(PRECOMPILED_INDEX_FILE);
+            let json = std::fs::read(&index_path).map_err(|error| CacheError::FileRead {
+                path: index_path.clone(),
+                error,
+            })?;
+            let index: PrecompiledIndex =
+                serde_json::from_slice(&json).map_err(CacheError::other)?;
+            check_artifact_files(&index).map_err(|error| CacheError::FileRead {
+                path: index_path,
+                error,
+            })?;
+
+            return Ok(PrecompiledCache::new(
+                index,
+                Storage::Directory(path.to_path_buf()),
+            ));
+        }
+
+        let read_index = || -> std::io::Result<(PrecompiledIndex, u64, u64)> {
+            let mut file = File::open(path)?;
+            let mut header = [0; 16];
+            file.read_exact(&mut header)?;
+            if &header[..8] != BUNDLE_MAGIC {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    "not a bundle of precompiled artifacts",
+                ));
+            }
+            let len = u64::from_le_bytes(header[8..].try_into().unwrap());
+            let bundle_len = file.metadata()?.len();
+            if len > bundle_len - header.len() as u64 {
+                return Err(truncated_bundle());
+            }
+
+            let mut json = vec![0; len as usize];
+            file.read_exact(&mut json)?;
+            let index = serde_json::from_slice(&json)?;
+
+            Ok((index, header.len() as u64 + len, bundle_len))
+        };
+        let (index, mut offset, bundle_len) =
+            read_index().map_err(|error| CacheError::FileRead {
+                path: path.to_path_buf(),
+                error,
+            })?;
+
+        let mut offsets = BTreeMap::new();
+        for (key, artifact) in &index.artifacts {
+            offsets.insert(key.clone(), offset);
+            offset = offset
+                .checked_add(artifact.size)
+                .filter(|end| *end <= bundle_len)
+                .ok_or_else(|| CacheError::FileRead {
+                    path: path.to_path_buf(),
+                    error: truncated_bundle(),
+                })?;
+        }
+
+        Ok(PrecompiledCache::new(
+            index,
+            Storage::Bundle {
+                path: path.to_path_buf(),
+                offsets,
+            },
+        ))
+    }
+
+    fn new(index: PrecompiledIndex, storage: Storage) -> Self {
+        PrecompiledCache {
+            index,
+            storage,
+            required: false,
+            loaded: SharedCache::new(),
+        }
+    }
+
+    /// Fail with [`CacheError::CompilationNotAllowed`] instead of reporting
+    /// missing or mismatched modules as [`CacheError::NotFound`], so they are
+    /// never compiled.
+    pub fn with_required(mut self, required: bool) -> Self {
+        self.required = required;
+        self
+    }
+
+    pub fn index(&self) -> &PrecompiledIndex {
+        &self.index
+    }
+
+    /// Checks whether the module with this hash can be loaded for `engine`,
+    /// without reading the artifact.
+    pub fn check(&self, key: ModuleHash, engine: &Engine) -> Result<(), CacheError> {
+        self.check_engine(engine)?;
+        if !self.index.artifacts.contains_key(&key.to_string()) {
+            return Err(CacheError::NotFound);
+        }
+        Ok(())
+    }
+
+    fn check_engine(&self, engine: &Engine) -> Result<(), CacheError> {
+        let engine_id = engine.deterministic_id();
+        if engine_id != self.index.engine {
+            return Err(CacheError::other(PrecompiledMismatch::Engine {
+                expected: self.index.engine.clone(),
+                found: engine_id,
+            }));
+        }
+
+        let artifact_version = wasmer_types::MetadataHeader::CURRENT_VERSION;
+        if artifact_version != self.index.artifact_version {
+            return Err(CacheError::other(PrecompiledMismatch::ArtifactVersion {
+                expected: self.index.artifact_version,
+                found: artifact_version,
+            }));
+        }
+
+        Ok(())
+    }
+
+    fn read_artifact(&self, key: ModuleHash) -> Result<Vec<u8>, CacheError> {
+        let key = key.to_string();
+        let artifact = self.index.artifacts.get(&key).ok_or(CacheError::NotFound)?;
+
+        match &self.storage {
+            Storage::Directory(dir) => {
+                let path = dir.join(&artifact.file);
+                std::fs::read(&path).map_err(|error| CacheError::FileRead { path, error })
+            }
+            Storage::Bundle { path, offsets } => {
+                let read = || -> std::io::Result<Vec<u8>> {
+                    let mut file = File::open(path)?;
+                    file.seek(SeekFrom::Start(offsets[&key]))?;
+                    let mut bytes = vec![0; artifact.size as usize];
+                    file.read_exact(&mut bytes)?;
+                    Ok(bytes)
+                };
+                read().map_err(|error| CacheError::FileRead {
+                    path: path.clone(),
+                    error,
+                })
+            }
+        }
+    }
+
+    async fn load_artifact(&self, key: ModuleHash, engine: &Engine) -> Result<Module, CacheError> {
+        if let Ok(module) = self.loaded.load(key, engine).await {
+            return Ok(module);
+        }
+
+        self.check(key, engine)?;
+        let bytes = self.read_artifact(key)?;
+        let module = unsafe { Module::deserialize(engine, bytes)? };
+        self.loaded.save(key, engine, &module).await?;
+
+        Ok(module)
+    }
+}
+
+#[async_trait::async_trait]
+impl ModuleCache for PrecompiledCache {
+    #[tracing::instrument(level = "debug", skip_all, fields(%key))]
+    async fn load(&self, key: ModuleHash, engine: &Engine) -> Result<Module, CacheError> {
+        match self.load_artifact(key, engine).await {
+            Ok(module) => {
+                tracing::debug!("Loaded the precompiled module");
+                Ok(module)
+            }
+            Err(reason) if self.required => Err(CacheError::CompilationNotAllowed {
+                key,
+                reason: Box::new(reason),
+            }),
+            Err(reason) => {
+                tracing::warn!(
+                    %key,
+                    error = &reason as &dyn std::error::Error,
+                    "Unable to use the precompiled module",
+                );
+                Err(CacheError::NotFound)
+            }
+        }
+    }
+
+    async fn contains(&self, key: ModuleHash, engine: &Engine) -> Result<bool, CacheError> {
+        Ok(self.check(key, engine).is_ok())
+    }
+
+    async fn save(
+        &self,
+        _key: ModuleHash,
+        _engine: &Engine,
+        _module: &Module,
+    ) -> Result<(), CacheError> {
+        // The artifacts are read-only
+        Ok(())
+    }
+}
+
+/// Make sure every artifact of a directory index is a file inside the
+/// directory, so a crafted index can't read files from elsewhere.
+fn check_artifact_files(index: &PrecompiledIndex) -> std::io::Result<()> {
+    for artifact in index.artifacts.values() {
+        let mut components = Path::new(&artifact.file).components();
+        let is_relative = components.all(|component| matches!(component, Component::Normal(_)));
+        if !is_relative || artifact.file.is_empty() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!(
+                    "the artifact file \"{}\" is not a relative path inside the directory",
+                    artifact.file
+                ),
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn truncated_bundle() -> std::io::Error {
+    std::io::Error::new(
+        std::io::ErrorKind::UnexpectedEof,
+        "the bundle is shorter than its index says",
+    )
+}
+
+#[derive(Debug, thiserror::Error)]
+enum PrecompiledMismatch {
+    #[error("The modules were compiled with the \"{expected}\" engine, not \"{found}\"")]
+    Engine { expected: String, found: String },
+    #[error("The artifacts have version {expected}, but version {found} is required")]
+    ArtifactVersion { expected: u32, found: u32 },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ADD_WAT: &[u8] = br#"(
+        module
+            (func
+                (export "add")
+                (param $x i64)
+                (param $y i64)
+                (result i64)
+                (i64.add (local.get $x) (local.get $y)))
+        )"#;
+
+    fn artifacts(engine: &Engine) -> PrecompiledArtifactsBuilder {
+        let module = Module::new(engine, ADD_WAT).unwrap();
+        let mut builder = PrecompiledArtifactsBuilder::new(engine);
+        builder.add(key(), "add", &module).unwrap();
+        assert!(builder.add_command(key(), "plus"));
+        builder
+    }
+
+    fn key() -> ModuleHash {
+        ModuleHash::xxhash_from_bytes([1; 8])
+    }
+
+    #[tokio::test]
+    async fn load_from_directory_and_bundle() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let engine = Engine::default();
+        let builder = artifacts(&engine);
+        let dir = temp.path().join("artifacts");
+        let bundle = temp.path().join("artifacts.bundle");
+        builder.write_dir(&dir).unwrap();
+        builder.write_bundle(&bundle).unwrap();
+
+        for path in [dir, bundle] {
+            let cache = PrecompiledCache::open(&path).unwrap();
+            assert_eq!(cache.index(), &builder.index());
+            assert_eq!(
+                cache.index().artifacts[&key().to_string()].commands,
+                ["add", "plus"]
+            );
+
+            assert!(cache.contains(key(), &engine).await.unwrap());
+            let module = cache.load(key(), &engine).await.unwrap();
+            assert!(module.exports().any(|export| export.name() == "add"));
+        }
+    }
+
+    #[tokio::test]
+    async fn missing_modules_are_not_found() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let engine = Engine::default();
+        artifacts(&engine).write_dir(temp.path()).unwrap();
+        let cache = PrecompiledCache::open(temp.path()).unwrap();
+        let other = ModuleHash::xxhash_from_bytes([2; 8]);
+
+        assert!(!cache.contains(other, &engine).await.unwrap());
+        assert!(matches!(
+            cache.load(other, &engine).await.unwrap_err(),
+            CacheError::NotFound
+        ));
+
+        let cache = cache.with_required(true);
+        assert!(matches!(
+            cache.load(other, &engine).await.unwrap_err(),
+            CacheError::CompilationNotAllowed { key, .. } if key == other
+        ));
+    }
+
+    #[tokio::test]
+    async fn mismatched_engines_are_rejected() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let engine = Engine::default();
+        let mut builder = artifacts(&engine);
+        builder.engine = "some-other-engine".to_string();
+        builder.write_bundle(&temp.path().join("bundle")).unwrap();
+        let cache = PrecompiledCache::open(temp.path().join("bundle"))
+            .unwrap()
+            .with_required(true);
+
+        assert!(!cache.contains(key(), &engine).await.unwrap());
+        let error = cache.load(key(), &engine).await.unwrap_err();
+        let CacheError::CompilationNotAllowed { reason, .. } = error else {
+            panic!("{error:?}");
+        };
+        assert!(reason.to_string().contains("some-other-engine"));
+    }
+
+    #[test]
+    fn artifacts_outside_the_directory_are_rejected() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let engine = Engine::default();
+        let mut index = artifacts(&engine).index();
+
+        for file in [
+            "/etc/passwd",
+            "../outside.bin",
+            "nested/../../outside.bin",
+            "",
+        ] {
+            index.artifacts.get_mut(&key().to_string()).unwrap().file = file.to_string();
+            let json = serde_json::to_vec(&index).unwrap();
+            std::fs::write(temp.path().join(PRECOMPILED_INDEX_FILE), json).unwrap();
+
+            let error = PrecompiledCache::open(temp.path()).unwrap_err();
+            assert!(
+                matches!(&error, CacheError::FileRead { error, .. } if error.kind() == std::io::ErrorKind::InvalidData),
+                "{file}: {error:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn truncated_bundles_are_rejected() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let engine = Engine::default();
+        let path = temp.path().join("bundle");
+        artifacts(&engine).write_bundle(&path).unwrap();
+        let bundle = std::fs::read(&path).unwrap();
+
+        // An index length that is far larger than the file
+        let mut huge_index = bundle.clone();
+        huge_index[8..16].copy_from_slice(&u64::MAX.to_le_bytes());
+        std::fs::write(&path, huge_index).unwrap();
+        assert!(PrecompiledCache::open(&path).is_err());
+
+        // Artifacts that end after the file does
+        std::fs::write(&path, &bundle[..bundle.len() - 1]).unwrap();
+        assert!(PrecompiledCache::open(&path).is_err());
+    }
+}
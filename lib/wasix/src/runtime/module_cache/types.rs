@@ -118,6 +118,16 @@ pub enum CacheError {
     /// The item was not found.
     #[error("Not found")]
     NotFound,
+    /// The item couldn't be loaded, and the cache doesn't allow it to be
+    /// compiled instead (see [`PrecompiledCache::with_required()`]).
+    ///
+    /// [`PrecompiledCache::with_required()`]: crate::runtime::module_cache::PrecompiledCache::with_required
+    #[error("Module {key} isn't precompiled, and compiling it isn't allowed")]
+    CompilationNotAllowed {
+        key: ModuleHash,
+        #[source]
+        reason: Box<CacheError>,
+    },
     /// A catch-all variant for any other errors that may occur.
     #[error(transparent)]
     Other(Box<dyn std::error::Error + Send + Sync>),
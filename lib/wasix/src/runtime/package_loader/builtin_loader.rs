@@ -8,8 +8,9 @@ use std::{
 
 use anyhow::{Context, Error};
 use bytes::Bytes;
-use http::{HeaderMap, Method};
+use http::HeaderMap;
 use tempfile::NamedTempFile;
+use tracing::{Instrument, Span};
 use url::Url;
 use wasmer_package::{
     package::WasmerPackageError,
@@ -252,13 +253,9 @@ impl BuiltinPackageLoader {
             }
         }
 
-        let request = HttpRequest {
-            headers: self.headers(&dist.webc),
-            url: dist.webc.clone(),
-            method: Method::GET,
-            body: None,
-            options: Default::default(),
-        };
+        let request = HttpRequest::get(dist.webc.clone())
+            .headers(self.headers(&dist.webc))
+            .build()?;
 
         tracing::debug!(%request.url, %request.method, "webc_package_download_start");
         tracing::trace!(?request.headers);
@@ -293,52 +290,13 @@ impl BuiltinPackageLoader {
         Ok(body)
     }
 
-    fn headers(&self, url: &Url) -> HeaderMap {
-        let mut headers = HeaderMap::new();
-        headers.insert("Accept", "application/webc".parse().unwrap());
-        headers.insert("User-Agent", USER_AGENT.parse().unwrap());
-
-        if url.has_authority() {
-            if let Some(token) = self.tokens.get(url.authority()) {
-                let header = format!("Bearer {token}");
-                match header.parse() {
-                    Ok(header) => {
-                        headers.insert(http::header::AUTHORIZATION, header);
-                    }
-                    Err(e) => {
-                        tracing::warn!(
-                            error = &e as &dyn std::error::Error,
-                            "An error occurred while parsing the authorization header",
-                        );
-                    }
-                }
-            }
-        }
-
-        headers
-    }
-}
-
-impl Default for BuiltinPackageLoader {
-    fn default() -> Self {
-        BuiltinPackageLoader::new()
-    }
-}
-
-#[async_trait::async_trait]
-impl PackageLoader for BuiltinPackageLoader {
-    #[tracing::instrument(
-        level="debug",
-        skip_all,
-        fields(
-            pkg=%summary.pkg.id,
-        ),
-    )]
-    async fn load(&self, summary: &PackageSummary) -> Result<Container, Error> {
+    async fn fetch(&self, summary: &PackageSummary, span: &Span) -> Result<Container, Error> {
         if let Some(container) = self.get_cached(&summary.dist.webc_sha256).await? {
             tracing::debug!("Cache hit!");
+            span.record("pkg.cached", true);
             return Ok(container);
         }
+        span.record("pkg.cached", false);
 
         // looks like we had a cache miss and need to download it manually
         let bytes = self
@@ -381,6 +339,52 @@ impl PackageLoader for BuiltinPackageLoader {
         Ok(container)
     }
 
+    fn headers(&self, url: &Url) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert("Accept", "application/webc".parse().unwrap());
+        headers.insert("User-Agent", USER_AGENT.parse().unwrap());
+
+        if url.has_authority() {
+            if let Some(token) = self.tokens.get(url.authority()) {
+                let header = format!("Bearer {token}");
+                match header.parse() {
+                    Ok(header) => {
+                        headers.insert(http::header::AUTHORIZATION, header);
+                    }
+                    Err(e) => {
+                        tracing::warn!(
+                            error = &e as &dyn std::error::Error,
+                            "An error occurred while parsing the authorization header",
+                        );
+                    }
+                }
+            }
+        }
+
+        headers
+    }
+}
+
+impl Default for BuiltinPackageLoader {
+    fn default() -> Self {
+        BuiltinPackageLoader::new()
+    }
+}
+
+#[async_trait::async_trait]
+impl PackageLoader for BuiltinPackageLoader {
+    #[tracing::instrument(
+        level="debug",
+        skip_all,
+        fields(
+            pkg=%summary.pkg.id,
+        ),
+    )]
+    async fn load(&self, summary: &PackageSummary) -> Result<Container, Error> {
+        let span = crate::telemetry::fetch_webc(&summary.pkg.id, &summary.dist.webc);
+        self.fetch(summary, &span).instrument(span.clone()).await
+    }
+
     async fn load_package_tree(
         &self,
         root: &Container,
@@ -739,6 +743,9 @@ mod tests {
             redirected: false,
             status: StatusCode::OK,
             headers: HeaderMap::new(),
+            content_encoding: None,
+            encoded_body_size: PYTHON.len(),
+            decoded_body_size: PYTHON.len(),
         }]));
         let loader = BuiltinPackageLoader::new()
             .with_cache_dir(temp.path())
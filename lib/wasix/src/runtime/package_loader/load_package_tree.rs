@@ -9,7 +9,9 @@ use anyhow::{Context, Error};
 use futures::{future::BoxFuture, StreamExt, TryStreamExt};
 use once_cell::sync::OnceCell;
 use petgraph::visit::EdgeRef;
-use virtual_fs::{FileSystem, OverlayFileSystem, UnionFileSystem, WebcVolumeFileSystem};
+use virtual_fs::{
+    CachingFileSystem, FileSystem, OverlayFileSystem, UnionFileSystem, WebcVolumeFileSystem,
+};
 use wasmer_config::package::{PackageId, SuggestedCompilerOptimizations};
 use wasmer_package::utils::wasm_annotations_to_features;
 use webc::metadata::annotations::Atom as AtomAnnotation;
@@ -21,7 +23,7 @@ use crate::{
         package_loader::PackageLoader,
         resolver::{
             DependencyGraph, ItemLocation, PackageSummary, Resolution, ResolvedFileSystemMapping,
-            ResolvedPackage,
+            ResolvedPackage, WebcHash,
         },
     },
 };
@@ -62,6 +64,7 @@ pub async fn load_package_tree(
     let mut containers = fetch_dependencies(loader, &resolution.package, &resolution.graph).await?;
     containers.insert(resolution.package.root_package.clone(), root.clone());
     let package_ids = containers.keys().cloned().collect();
+    let webc_hash = root.webc_hash().map(WebcHash::from_bytes);
     let fs = filesystem(&containers, &resolution.package, root_is_local_dir)?;
 
     let root = &resolution.package.root_package;
@@ -80,6 +83,7 @@ pub async fn load_package_tree(
         .ok()
         .map(|ts| ts as u128),
         hash: OnceCell::new(),
+        webc_hash,
         entrypoint_cmd: resolution.package.entrypoint.clone(),
         webc_fs: Arc::new(fs),
         commands,
@@ -487,7 +491,7 @@ fn filesystem_v3(
             format!("The \"{package}\" package doesn't have a \"{volume_name}\" volume")
         })?;
 
-        let webc_vol = WebcVolumeFileSystem::new(volume.clone());
+        let webc_vol = webc_volume_fs(volume);
         union_fs.mount(volume_name.clone(), mount_path, Box::new(webc_vol))?;
     }
 
@@ -496,6 +500,17 @@ fn filesystem_v3(
     Ok(Box::new(fs))
 }
 
+/// Files up to this size are kept in memory once they have been read from a
+/// package's volume.
+const MAX_CACHED_FILE_SIZE: u64 = 64 * 1024;
+
+/// Exposes a volume from a package. Volumes never change, so everything that
+/// is looked up in them is cached.
+fn webc_volume_fs(volume: &Volume) -> CachingFileSystem<WebcVolumeFileSystem> {
+    CachingFileSystem::new(WebcVolumeFileSystem::new(volume.clone()), true)
+        .with_cached_contents(MAX_CACHED_FILE_SIZE)
+}
+
 /// Build the filesystem for webc v2 packages.
 ///
 // # Note to future readers
@@ -569,7 +584,7 @@ fn filesystem_v2(
             let original = PathBuf::from(original);
 
             MappedPathFileSystem::new(
-                WebcVolumeFileSystem::new(volume.clone()),
+                webc_volume_fs(volume),
                 Box::new(move |path: &Path| {
                     let without_mount_dir = path
                         .strip_prefix(&mount_path)
@@ -579,7 +594,7 @@ fn filesystem_v2(
             )
         } else {
             MappedPathFileSystem::new(
-                WebcVolumeFileSystem::new(volume.clone()),
+                webc_volume_fs(volume),
                 Box::new(move |path: &Path| {
                     let without_mount_dir = path
                         .strip_prefix(&mount_path)
@@ -5,7 +5,7 @@ use std::{
 };
 
 use anyhow::{Context, Error};
-use http::{HeaderMap, Method};
+use http::HeaderMap;
 use semver::{Version, VersionReq};
 use url::Url;
 use wasmer_config::package::{NamedPackageId, PackageHash, PackageId, PackageIdent, PackageSource};
@@ -80,13 +80,10 @@ impl BackendSource {
             query: WASMER_WEBC_QUERY_ALL.replace("$NAME", package_name),
         };
 
-        let request = HttpRequest {
-            url: self.registry_endpoint.clone(),
-            method: Method::POST,
-            body: Some(serde_json::to_string(&body)?.into_bytes()),
-            headers: self.headers(),
-            options: Default::default(),
-        };
+        let request = HttpRequest::post(self.registry_endpoint.clone())
+            .headers(self.headers())
+            .body(serde_json::to_string(&body)?)
+            .build()?;
 
         tracing::debug!(%request.url, %request.method, "Querying the GraphQL API");
         tracing::trace!(?request.headers, request.body=body.query.as_str());
@@ -142,13 +139,10 @@ impl BackendSource {
             query: WASMER_WEBC_QUERY_BY_HASH.replace("$HASH", &hash.to_string()),
         };
 
-        let request = HttpRequest {
-            url: self.registry_endpoint.clone(),
-            method: Method::POST,
-            body: Some(serde_json::to_string(&body)?.into_bytes()),
-            headers: self.headers(),
-            options: Default::default(),
-        };
+        let request = HttpRequest::post(self.registry_endpoint.clone())
+            .headers(self.headers())
+            .body(serde_json::to_string(&body)?)
+            .build()?;
 
         tracing::debug!(%request.url, %request.method, "Querying the GraphQL API");
         tracing::trace!(?request.headers, request.body=body.query.as_str());
@@ -232,8 +226,28 @@ impl BackendSource {
 
 #[async_trait::async_trait]
 impl Source for BackendSource {
-    #[tracing::instrument(level = "debug", skip_all, fields(%package))]
     async fn query(&self, package: &PackageSource) -> Result<Vec<PackageSummary>, QueryError> {
+        self.query_named(package, false).await
+    }
+
+    async fn refresh(&self, package: &PackageSource) -> Result<Vec<PackageSummary>, QueryError> {
+        self.query_named(package, true).await
+    }
+}
+
+impl BackendSource {
+    /// Look a package up, answering from the local cache unless `refresh` is
+    /// set.
+    ///
+    /// Queries for a specific version are answered from the cache whenever it
+    /// has a match, even if the cached entry is stale, because published
+    /// versions never change.
+    #[tracing::instrument(level = "debug", skip_all, fields(%package, refresh))]
+    async fn query_named(
+        &self,
+        package: &PackageSource,
+        refresh: bool,
+    ) -> Result<Vec<PackageSummary>, QueryError> {
         let (package_name, version_constraint) = match package {
             PackageSource::Ident(PackageIdent::Named(n)) => (
                 n.full_name(),
@@ -261,8 +275,10 @@ impl Source for BackendSource {
             }
         };
 
-        if let Some(cache) = &self.cache {
-            match cache.lookup_cached_query(&package_name) {
+        let pinned = is_pinned(&version_constraint);
+
+        if let Some(cache) = self.cache.as_ref().filter(|_| pinned || !refresh) {
+            match cache.lookup_cached_query(&package_name, pinned) {
                 Ok(Some(cached)) => {
                     if let Ok(cached) = matching_package_summaries(
                         package,
@@ -309,6 +325,20 @@ impl Source for BackendSource {
     }
 }
 
+/// Whether a version constraint spells out a full version, like the `1.2.3` in
+/// `wasmer/python@1.2.3`. These are answered from any cached lookup that
+/// contains a matching version.
+fn is_pinned(version_constraint: &VersionReq) -> bool {
+    match version_constraint.comparators.as_slice() {
+        [comparator] => {
+            matches!(comparator.op, semver::Op::Exact | semver::Op::Caret)
+                && comparator.minor.is_some()
+                && comparator.patch.is_some()
+        }
+        _ => false,
+    }
+}
+
 #[allow(clippy::result_large_err)]
 fn matching_package_summaries(
     query: &PackageSource,
@@ -463,7 +493,13 @@ impl FileSystemCache {
         self.cache_dir.join(package_name)
     }
 
-    fn lookup_cached_query(&self, package_name: &str) -> Result<Option<WebQuery>, Error> {
+    /// Read the cached query for `package_name`. Entries older than the
+    /// timeout are ignored unless `allow_stale` is set.
+    fn lookup_cached_query(
+        &self,
+        package_name: &str,
+        allow_stale: bool,
+    ) -> Result<Option<WebQuery>, Error> {
         let filename = self.path(package_name);
 
         let _span =
@@ -494,9 +530,8 @@ impl FileSystemCache {
             }
         };
 
-        if !entry.is_still_valid(self.timeout) {
+        if !allow_stale && !entry.is_still_valid(self.timeout) {
             tracing::debug!(timestamp = entry.unix_timestamp, "Cached entry is stale");
-            let _ = std::fs::remove_file(&filename);
             return Ok(None);
         }
 
@@ -924,6 +959,9 @@ mod tests {
             redirected: false,
             status: StatusCode::OK,
             headers: HeaderMap::new(),
+            content_encoding: None,
+            encoded_body_size: WASMER_PACK_CLI_RESPONSE.len(),
+            decoded_body_size: WASMER_PACK_CLI_RESPONSE.len(),
         };
         let client = Arc::new(DummyClient::new(vec![response]));
         let registry_endpoint = BackendSource::WASMER_PROD_ENDPOINT.parse().unwrap();
@@ -1041,11 +1079,15 @@ mod tests {
             }
 
         };
+        let body = serde_json::to_vec(&body).unwrap();
         let response = HttpResponse {
-            body: Some(serde_json::to_vec(&body).unwrap()),
+            encoded_body_size: body.len(),
+            decoded_body_size: body.len(),
+            body: Some(body),
             redirected: false,
             status: StatusCode::OK,
             headers: HeaderMap::new(),
+            content_encoding: None,
         };
         let client = Arc::new(DummyClient::new(vec![response]));
         let registry_endpoint = BackendSource::WASMER_PROD_ENDPOINT.parse().unwrap();
@@ -1120,11 +1162,15 @@ mod tests {
                 }
             }
         };
+        let body = serde_json::to_vec(&body).unwrap();
         let response = HttpResponse {
-            body: Some(serde_json::to_vec(&body).unwrap()),
+            encoded_body_size: body.len(),
+            decoded_body_size: body.len(),
+            body: Some(body),
             redirected: false,
             status: StatusCode::OK,
             headers: HeaderMap::new(),
+            content_encoding: None,
         };
         let client = Arc::new(DummyClient::new(vec![response]));
         let registry_endpoint = BackendSource::WASMER_PROD_ENDPOINT.parse().unwrap();
@@ -1211,11 +1257,15 @@ mod tests {
                 }
             }
         };
+        let body = serde_json::to_vec(&body).unwrap();
         let response = HttpResponse {
-            body: Some(serde_json::to_vec(&body).unwrap()),
+            encoded_body_size: body.len(),
+            decoded_body_size: body.len(),
+            body: Some(body),
             redirected: false,
             status: StatusCode::OK,
             headers: HeaderMap::new(),
+            content_encoding: None,
         };
         let client = Arc::new(DummyClient::new(vec![response]));
         let registry_endpoint = BackendSource::WASMER_PROD_ENDPOINT.parse().unwrap();
@@ -1238,4 +1288,113 @@ mod tests {
             "4.0.0"
         );
     }
+
+    /// A registry response listing the given versions of `wasmer/python`.
+    fn python_versions(versions: &[&str]) -> HttpResponse {
+        let versions: Vec<_> = versions
+            .iter()
+            .enumerate()
+            .map(|(i, version)| {
+                let manifest = format!(
+                    r#"{{"package": {{"wapm": {{"name": "wasmer/python", "version": "{version}", "description": "Python"}}}}}}"#
+                );
+                let distribution = serde_json::json!({
+                    "webcManifest": manifest,
+                    "piritaDownloadUrl": format!("https://wasmer.io/wasmer/python@{version}"),
+                    "piritaSha256Hash": format!("{i:x}").repeat(64),
+                });
+                serde_json::json!({
+                    "version": version,
+                    "v2": distribution,
+                    "v3": distribution,
+                })
+            })
+            .collect();
+        let body = serde_json::json!({
+            "data": {
+                "getPackage": {
+                    "packageName": "python",
+                    "namespace": "wasmer",
+                    "versions": versions,
+                },
+                "info": {
+                    "defaultFrontend": "https://wasmer.io/",
+                },
+            }
+        });
+        let body = serde_json::to_vec(&body).unwrap();
+        HttpResponse {
+            encoded_body_size: body.len(),
+            decoded_body_size: body.len(),
+            body: Some(body),
+            redirected: false,
+            status: StatusCode::OK,
+            headers: HeaderMap::new(),
+            content_encoding: None,
+        }
+    }
+
+    fn version(summary: &PackageSummary) -> String {
+        summary.pkg.id.as_named().unwrap().version.to_string()
+    }
+
+    #[tokio::test]
+    async fn refreshing_picks_up_new_releases() {
+        let client = Arc::new(DummyClient::new(vec![
+            python_versions(&["3.12.0"]),
+            python_versions(&["4.0.0", "3.12.0"]),
+        ]));
+        let registry_endpoint = BackendSource::WASMER_PROD_ENDPOINT.parse().unwrap();
+        let temp = tempfile::tempdir().unwrap();
+        let source = BackendSource::new(registry_endpoint, client.clone())
+            .with_local_cache(temp.path(), Duration::from_secs(60 * 60));
+        let latest = PackageSource::from_str("wasmer/python").unwrap();
+        let pinned = PackageSource::from_str("wasmer/python@3.12.0").unwrap();
+
+        assert_eq!(version(&source.latest(&latest).await.unwrap()), "3.12.0");
+        assert_eq!(version(&source.latest(&latest).await.unwrap()), "3.12.0");
+        assert_eq!(client.take_requests().len(), 1);
+
+        // A new release was published, but only a refresh notices it
+        let refreshed = source.refresh_latest(&latest).await.unwrap();
+        assert_eq!(version(&refreshed), "4.0.0");
+        assert_eq!(client.take_requests().len(), 1);
+
+        // Pinned requests never go back to the registry
+        let pinned = source.refresh_latest(&pinned).await.unwrap();
+        assert_eq!(version(&pinned), "3.12.0");
+        // and the refreshed lookup replaced the cached one
+        assert_eq!(version(&source.latest(&latest).await.unwrap()), "4.0.0");
+        assert!(client.take_requests().is_empty());
+    }
+
+    #[tokio::test]
+    async fn pinned_versions_use_stale_cache_entries() {
+        let client = Arc::new(DummyClient::new(vec![python_versions(&[
+            "4.0.0", "3.12.0",
+        ])]));
+        let registry_endpoint = BackendSource::WASMER_PROD_ENDPOINT.parse().unwrap();
+        let temp = tempfile::tempdir().unwrap();
+        let source = BackendSource::new(registry_endpoint, client.clone())
+            .with_local_cache(temp.path(), Duration::from_secs(0));
+        let cached =
+            serde_json::from_slice(python_versions(&["3.12.0"]).body.as_deref().unwrap()).unwrap();
+        source
+            .cache
+            .as_ref()
+            .unwrap()
+            .update("wasmer/python", &cached)
+            .unwrap();
+
+        let pinned = PackageSource::from_str("wasmer/python@3.12.0").unwrap();
+        let summaries = source.query(&pinned).await.unwrap();
+        assert_eq!(summaries.len(), 1);
+        assert_eq!(version(&summaries[0]), "3.12.0");
+        assert!(client.take_requests().is_empty());
+
+        // Unpinned requests notice the entry is stale
+        let latest = PackageSource::from_str("wasmer/python").unwrap();
+        assert_eq!(version(&source.latest(&latest).await.unwrap()), "4.0.0");
+        assert_eq!(client.take_requests().len(), 1);
+    }
 }
@@ -53,14 +53,23 @@ impl MultiSource {
     }
 }
 
-#[async_trait::async_trait]
-impl Source for MultiSource {
-    #[tracing::instrument(level = "debug", skip_all, fields(%package))]
-    async fn query(&self, package: &PackageSource) -> Result<Vec<PackageSummary>, QueryError> {
+impl MultiSource {
+    #[tracing::instrument(level = "debug", skip_all, fields(%package, refresh))]
+    async fn query_sources(
+        &self,
+        package: &PackageSource,
+        refresh: bool,
+    ) -> Result<Vec<PackageSummary>, QueryError> {
         let mut output = Vec::<PackageSummary>::new();
 
         for source in &self.sources {
-            match source.query(package).await {
+            let result = if refresh {
+                source.refresh(package).await
+            } else {
+                source.query(package).await
+            };
+
+            match result {
                 Ok(mut summaries) => {
                     if self.strategy.merge_results {
                         // Extend matches, but skip already found versions.
@@ -106,6 +115,17 @@ impl Source for MultiSource {
     }
 }
 
+#[async_trait::async_trait]
+impl Source for MultiSource {
+    async fn query(&self, package: &PackageSource) -> Result<Vec<PackageSummary>, QueryError> {
+        self.query_sources(package, false).await
+    }
+
+    async fn refresh(&self, package: &PackageSource) -> Result<Vec<PackageSummary>, QueryError> {
+        self.query_sources(package, true).await
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 #[non_exhaustive]
 pub struct MultiSourceStrategy {
@@ -22,29 +22,52 @@ pub trait Source: Sync + Debug {
     /// [dep]: crate::runtime::resolver::Dependency
     async fn query(&self, package: &PackageSource) -> Result<Vec<PackageSummary>, QueryError>;
 
+    /// Like [`Source::query()`], but ask the source itself instead of
+    /// answering from a cache, so recently published versions are noticed.
+    ///
+    /// Sources that don't cache their results can rely on the default
+    /// implementation.
+    async fn refresh(&self, package: &PackageSource) -> Result<Vec<PackageSummary>, QueryError> {
+        self.query(package).await
+    }
+
     /// Run [`Source::query()`] and get the [`PackageSummary`] for the latest
     /// version.
     async fn latest(&self, pkg: &PackageSource) -> Result<PackageSummary, QueryError> {
         let candidates = self.query(pkg).await?;
+        select_latest(pkg, candidates)
+    }
 
-        match pkg {
-            PackageSource::Ident(PackageIdent::Named(_)) => candidates
-                .into_iter()
-                .max_by(|left, right| {
-                    let left_version = left.pkg.id.as_named().map(|x| &x.version);
-                    let right_version = right.pkg.id.as_named().map(|x| &x.version);
-
-                    left_version.cmp(&right_version)
-                })
-                .ok_or(QueryError::NoMatches {
-                    query: pkg.clone(),
-                    archived_versions: Vec::new(),
-                }),
-            _ => candidates
-                .into_iter()
-                .next()
-                .ok_or_else(|| QueryError::NotFound { query: pkg.clone() }),
-        }
+    /// Run [`Source::refresh()`] and get the [`PackageSummary`] for the
+    /// latest version.
+    async fn refresh_latest(&self, pkg: &PackageSource) -> Result<PackageSummary, QueryError> {
+        let candidates = self.refresh(pkg).await?;
+        select_latest(pkg, candidates)
+    }
+}
+
+#[allow(clippy::result_large_err)]
+fn select_latest(
+    pkg: &PackageSource,
+    candidates: Vec<PackageSummary>,
+) -> Result<PackageSummary, QueryError> {
+    match pkg {
+        PackageSource::Ident(PackageIdent::Named(_)) => candidates
+            .into_iter()
+            .max_by(|left, right| {
+                let left_version = left.pkg.id.as_named().map(|x| &x.version);
+                let right_version = right.pkg.id.as_named().map(|x| &x.version);
+
+                left_version.cmp(&right_version)
+            })
+            .ok_or(QueryError::NoMatches {
+                query: pkg.clone(),
+                archived_versions: Vec::new(),
+            }),
+        _ => candidates
+            .into_iter()
+            .next()
+            .ok_or_else(|| QueryError::NotFound { query: pkg.clone() }),
     }
 }
 
@@ -57,6 +80,10 @@ where
     async fn query(&self, package: &PackageSource) -> Result<Vec<PackageSummary>, QueryError> {
         (**self).query(package).await
     }
+
+    async fn refresh(&self, package: &PackageSource) -> Result<Vec<PackageSummary>, QueryError> {
+        (**self).refresh(package).await
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -7,7 +7,6 @@ use std::{
 };
 
 use anyhow::{Context, Error};
-use http::Method;
 use sha2::{Digest, Sha256};
 use tempfile::NamedTempFile;
 use url::Url;
@@ -174,13 +173,9 @@ impl WebSource {
     }
 
     async fn get_etag(&self, url: &Url) -> Result<String, Error> {
-        let request = HttpRequest {
-            url: url.clone(),
-            method: Method::HEAD,
-            headers: super::utils::webc_headers(),
-            body: None,
-            options: Default::default(),
-        };
+        let request = HttpRequest::head(url.clone())
+            .headers(super::utils::webc_headers())
+            .build()?;
 
         let response = self.client.request(request).await?;
 
@@ -200,13 +195,9 @@ impl WebSource {
     }
 
     async fn fetch(&self, url: &Url) -> Result<(Vec<u8>, Option<String>), Error> {
-        let request = HttpRequest {
-            url: url.clone(),
-            method: Method::GET,
-            headers: super::utils::webc_headers(),
-            body: None,
-            options: Default::default(),
-        };
+        let request = HttpRequest::get(url.clone())
+            .headers(super::utils::webc_headers())
+            .build()?;
         let response = self.client.request(request).await?;
 
         if !response.is_ok() {
@@ -433,6 +424,9 @@ mod tests {
                 redirected: false,
                 status: StatusCode::OK,
                 headers: HeaderMap::new(),
+                content_encoding: None,
+                encoded_body_size: 0,
+                decoded_body_size: 0,
             })
         }
 
@@ -442,7 +436,10 @@ mod tests {
         }
 
         pub fn with_body(mut self, body: impl Into<Vec<u8>>) -> Self {
-            self.0.body = Some(body.into());
+            let body = body.into();
+            self.0.encoded_body_size = body.len();
+            self.0.decoded_body_size = body.len();
+            self.0.body = Some(body);
             self
         }
 
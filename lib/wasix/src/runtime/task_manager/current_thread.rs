@@ -0,0 +1,392 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex, Weak};
+use std::task::{Context, Poll, RawWaker, RawWakerVTable, Waker};
+use std::time::Instant;
+use std::{pin::Pin, time::Duration};
+
+use futures::{future::BoxFuture, Future};
+
+use crate::os::task::thread::WasiThreadError;
+use crate::runtime::SpawnType;
+
+use super::{instantiate_task, TaskWasm, TaskWasmRunProperties, VirtualTaskManager};
+
+/// A task manager that runs everything on the thread that calls into it and
+/// never spawns OS threads.
+///
+/// This is intended for embedders that can't (or don't want to) spawn
+/// threads, at the cost of some WASIX features:
+///
+/// - `thread_spawn` and `proc_fork` fail with [`WasiThreadError::Unsupported`]
+///   (`Errno::Notsup` inside the guest), because they need a second thread
+///   of execution to run alongside the caller.
+/// - [`VirtualTaskManager::task_wasm()`] (e.g. `proc_exec` and the
+///   runners), [`VirtualTaskManager::task_dedicated()`] and
+///   [`VirtualTaskManager::spawn_with_module()`] run the task to completion
+///   on the calling thread before returning.
+/// - Futures passed to [`VirtualTaskManager::task_shared()`] only make
+///   progress while some thread is inside [`VirtualTaskManager::block_on()`].
+/// - Sleeps awaited from a blocking context (e.g. `thread_sleep`, or a
+///   `poll_oneoff` timeout) block the calling thread, waking up every
+///   millisecond to give the futures they are raced against a chance to run.
+/// - Signals sent to a thread in deep sleep are only processed once it wakes
+///   up.
+///
+/// [`VirtualTaskManager::block_on()`] may be called re-entrantly (from a
+/// task that is itself being driven by `block_on()`) without deadlocking.
+#[derive(Debug, Clone, Default)]
+pub struct CurrentThreadTaskManager {
+    executor: Arc<Executor>,
+}
+
+impl CurrentThreadTaskManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl VirtualTaskManager for CurrentThreadTaskManager {
+    /// See [`VirtualTaskManager::sleep_now`].
+    fn sleep_now(&self, time: Duration) -> Pin<Box<dyn Future<Output = ()> + Send + Sync>> {
+        Box::pin(Sleep {
+            deadline: Instant::now() + time,
+            timer: None,
+        })
+    }
+
+    /// See [`VirtualTaskManager::block_on`].
+    fn block_on(&self, task: BoxFuture<'static, ()>) -> Result<(), WasiThreadError> {
+        self.executor.block_on(task);
+        Ok(())
+    }
+
+    /// See [`VirtualTaskManager::task_shared`].
+    fn task_shared(
+        &self,
+        task: Box<dyn FnOnce() -> BoxFuture<'static, ()> + Send + 'static>,
+    ) -> Result<(), WasiThreadError> {
+        self.executor.spawn(task());
+        Ok(())
+    }
+
+    /// See [`VirtualTaskManager::task_wasm`].
+    fn task_wasm(&self, task: TaskWasm) -> Result<(), WasiThreadError> {
+        let TaskWasm {
+            run,
+            recycle,
+            env,
+            module,
+            globals,
+            spawn_type,
+            trigger,
+            update_layout,
+            call_initialize,
+            pre_run,
+        } = task;
+
+        // New threads and forked processes would need to run alongside the
+        // caller, which isn't possible without spawning an OS thread
+        if trigger.is_none()
+            && matches!(
+                spawn_type,
+                SpawnType::ShareMemory(..) | SpawnType::CopyMemory(..)
+            )
+        {
+            return Err(WasiThreadError::Unsupported);
+        }
+
+        let (mut ctx, mut store) = instantiate_task(
+            self,
+            env,
+            module,
+            globals,
+            spawn_type,
+            update_layout,
+            call_initialize,
+        )?;
+
+        if let Some(trigger) = trigger {
+            tracing::trace!("queueing task_wasm trigger");
+            self.executor.spawn(Box::pin(async move {
+                let result = trigger().await;
+
+                if let Some(pre_run) = pre_run {
+                    pre_run(&mut ctx, &mut store).await;
+                }
+
+                run(TaskWasmRunProperties {
+                    ctx,
+                    store,
+                    trigger_result: Some(result),
+                    recycle,
+                });
+            }));
+        } else {
+            tracing::trace!("running task_wasm on the current thread");
+
+            if let Some(pre_run) = pre_run {
+                self.executor.block_on(pre_run(&mut ctx, &mut store));
+            }
+
+            run(TaskWasmRunProperties {
+                ctx,
+                store,
+                trigger_result: None,
+                recycle,
+            });
+        }
+        Ok(())
+    }
+
+    /// See [`VirtualTaskManager::task_dedicated`].
+    fn task_dedicated(
+        &self,
+        task: Box<dyn FnOnce() + Send + 'static>,
+    ) -> Result<(), WasiThreadError> {
+        task();
+        Ok(())
+    }
+
+    /// See [`VirtualTaskManager::thread_parallelism`].
+    fn thread_parallelism(&self) -> Result<usize, WasiThreadError> {
+        Ok(1)
+    }
+}
+
+/// A single-threaded executor whose [`Executor::block_on()`] can be nested.
+#[derive(Debug, Default)]
+struct Executor {
+    state: Mutex<State>,
+    condvar: Condvar,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    ready: VecDeque<Arc<Task>>,
+    timers: BTreeMap<(Instant, u64), Waker>,
+    next_timer: u64,
+}
+
+#[derive(derive_more::Debug)]
+struct Task {
+    /// Taken out while the task is being polled, so a nested `block_on()`
+    /// can tell that an outer one is already running it.
+    #[debug(ignore)]
+    future: Mutex<Option<BoxFuture<'static, ()>>>,
+    queued: AtomicBool,
+    /// Set when a nested `block_on()` skipped the task because an outer one
+    /// was polling it.
+    rewake: AtomicBool,
+}
+
+impl Executor {
+    fn spawn(self: &Arc<Self>, future: BoxFuture<'static, ()>) {
+        let task = Arc::new(Task {
+            future: Mutex::new(Some(future)),
+            queued: AtomicBool::new(false),
+            rewake: AtomicBool::new(false),
+        });
+        self.schedule(task);
+    }
+
+    fn schedule(&self, task: Arc<Task>) {
+        if !task.queued.swap(true, Ordering::AcqRel) {
+            self.state.lock().unwrap().ready.push_back(task);
+            self.condvar.notify_all();
+        }
+    }
+
+    fn block_on<F: Future>(self: &Arc<Self>, future: F) -> F::Output {
+        let mut future = std::pin::pin!(future);
+        let woken = Arc::new(AtomicBool::new(true));
+        let waker = self.waker(Wakee::BlockOn(woken.clone()));
+
+        loop {
+            if woken.swap(false, Ordering::AcqRel) {
+                if let Poll::Ready(ret) = future.as_mut().poll(&mut Context::from_waker(&waker)) {
+                    return ret;
+                }
+            }
+
+            self.run_ready_tasks();
+            let next_deadline = self.fire_timers();
+
+            let state = self.state.lock().unwrap();
+            if woken.load(Ordering::Acquire) || !state.ready.is_empty() {
+                continue;
+            }
+            match next_deadline {
+                Some(deadline) => {
+                    let timeout = deadline.saturating_duration_since(Instant::now());
+                    drop(self.condvar.wait_timeout(state, timeout).unwrap());
+                }
+                None => drop(self.condvar.wait(state).unwrap()),
+            }
+        }
+    }
+
+    fn run_ready_tasks(self: &Arc<Self>) {
+        let count = self.state.lock().unwrap().ready.len();
+        for _ in 0..count {
+            let Some(task) = self.state.lock().unwrap().ready.pop_front() else {
+                break;
+            };
+            task.queued.store(false, Ordering::Release);
+
+            let Some(mut future) = task.future.lock().unwrap().take() else {
+                // Either finished or being polled further up the stack
+                task.rewake.store(true, Ordering::Release);
+                continue;
+            };
+
+            let waker = self.waker(Wakee::Task(task.clone()));
+            if future
+                .as_mut()
+                .poll(&mut Context::from_waker(&waker))
+                .is_pending()
+            {
+                task.future.lock().unwrap().replace(future);
+                if task.rewake.swap(false, Ordering::AcqRel) {
+                    self.schedule(task);
+                }
+            }
+        }
+    }
+
+    /// Wakes up all expired timers and returns the deadline of the next one.
+    fn fire_timers(&self) -> Option<Instant> {
+        let now = Instant::now();
+        let mut state = self.state.lock().unwrap();
+        let pending = state.timers.split_off(&(now, u64::MAX));
+        let expired = std::mem::replace(&mut state.timers, pending);
+        let next_deadline = state.timers.keys().next().map(|(deadline, _)| *deadline);
+        drop(state);
+
+        for waker in expired.into_values() {
+            waker.wake();
+        }
+        next_deadline
+    }
+
+    fn add_timer(&self, deadline: Instant, waker: Waker) -> (Instant, u64) {
+        let mut state = self.state.lock().unwrap();
+        let key = (deadline, state.next_timer);
+        state.next_timer += 1;
+        state.timers.insert(key, waker);
+        key
+    }
+
+    fn remove_timer(&self, key: &(Instant, u64)) {
+        self.state.lock().unwrap().timers.remove(key);
+    }
+
+    fn waker(self: &Arc<Self>, wakee: Wakee) -> Waker {
+        let data = Arc::into_raw(Arc::new(WakeHandle {
+            executor: Arc::downgrade(self),
+            wakee,
+        }));
+        unsafe { Waker::from_raw(RawWaker::new(data as *const (), &VTABLE)) }
+    }
+}
+
+#[derive(Debug)]
+enum Wakee {
+    BlockOn(Arc<AtomicBool>),
+    Task(Arc<Task>),
+}
+
+#[derive(Debug)]
+struct WakeHandle {
+    executor: Weak<Executor>,
+    wakee: Wakee,
+}
+
+impl WakeHandle {
+    fn wake(&self) {
+        let Some(executor) = self.executor.upgrade() else {
+            return;
+        };
+        match &self.wakee {
+            Wakee::BlockOn(woken) => {
+                woken.store(true, Ordering::Release);
+                let _guard = executor.state.lock().unwrap();
+                executor.condvar.notify_all();
+            }
+            Wakee::Task(task) => executor.schedule(task.clone()),
+        }
+    }
+
+    /// Returns the executor behind `waker`, if it was created by an
+    /// [`Executor`].
+    fn executor(waker: &Waker) -> Option<Arc<Executor>> {
+        if !std::ptr::eq(waker.vtable(), &VTABLE) {
+            return None;
+        }
+        let handle = unsafe { &*(waker.data() as *const WakeHandle) };
+        handle.executor.upgrade()
+    }
+}
+
+// Note: this needs to be a `static` (rather than a `const`) so it has a
+// single address that `WakeHandle::executor()` can compare against.
+static VTABLE: RawWakerVTable = RawWakerVTable::new(
+    |data| unsafe {
+        Arc::increment_strong_count(data as *const WakeHandle);
+        RawWaker::new(data, &VTABLE)
+    },
+    |data| unsafe { Arc::from_raw(data as *const WakeHandle).wake() },
+    |data| unsafe { (*(data as *const WakeHandle)).wake() },
+    |data| unsafe { drop(Arc::from_raw(data as *const WakeHandle)) },
+);
+
+/// How long a [`Sleep`] polled by another executor blocks at a time.
+const SLEEP_SLICE: Duration = Duration::from_millis(1);
+
+/// The future returned by [`CurrentThreadTaskManager::sleep_now()`].
+///
+/// When polled by the task manager's own executor this registers a timer and
+/// yields. Any other executor (for instance the one used by blocking
+/// syscalls) can't be told when to poll again, so the sleep blocks the
+/// current thread in short slices instead, yielding in between so that it
+/// doesn't hold up whatever else the executor is polling.
+struct Sleep {
+    deadline: Instant,
+    timer: Option<(Arc<Executor>, (Instant, u64))>,
+}
+
+impl Future for Sleep {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let now = Instant::now();
+        if now >= self.deadline {
+            return Poll::Ready(());
+        }
+
+        if let Some((executor, key)) = self.timer.take() {
+            executor.remove_timer(&key);
+        }
+
+        match WakeHandle::executor(cx.waker()) {
+            Some(executor) => {
+                let key = executor.add_timer(self.deadline, cx.waker().clone());
+                self.timer = Some((executor, key));
+                Poll::Pending
+            }
+            None => {
+                std::thread::sleep(SLEEP_SLICE.min(self.deadline - now));
+                cx.waker().wake_by_ref();
+                Poll::Pending
+            }
+        }
+    }
+}
+
+impl Drop for Sleep {
+    fn drop(&mut self) {
+        if let Some((executor, key)) = self.timer.take() {
+            executor.remove_timer(&key);
+        }
+    }
+}
@@ -1,3 +1,4 @@
+pub mod current_thread;
 // TODO: should be behind a different , tokio specific feature flag.
 #[cfg(feature = "sys-thread")]
 pub mod tokio;
@@ -165,6 +166,55 @@ impl<'a> TaskWasm<'a> {
     }
 }
 
+/// Creates the store and instance a [`TaskWasm`] will run in, building its
+/// memory according to the [`SpawnType`].
+pub(crate) fn instantiate_task<T>(
+    tasks: &T,
+    env: WasiEnv,
+    module: Module,
+    globals: Option<StoreSnapshot>,
+    spawn_type: SpawnType,
+    update_layout: bool,
+    call_initialize: bool,
+) -> Result<(WasiFunctionEnv, Store), WasiThreadError>
+where
+    T: VirtualTaskManager + ?Sized,
+{
+    let make_memory: SpawnMemoryTypeOrStore = match &spawn_type {
+        SpawnType::CreateMemory | SpawnType::NewLinkerInstanceGroup(..) => {
+            SpawnMemoryTypeOrStore::New
+        }
+        SpawnType::CreateMemoryOfType(t) => SpawnMemoryTypeOrStore::Type(*t),
+        SpawnType::ShareMemory(_, _) | SpawnType::CopyMemory(_, _) => {
+            let mut store = env.new_store();
+            let memory = tasks.build_memory(&mut store.as_store_mut(), &spawn_type)?;
+            SpawnMemoryTypeOrStore::StoreAndMemory(store, memory)
+        }
+    };
+
+    if let SpawnType::NewLinkerInstanceGroup(linker, func_env, mut store) = spawn_type {
+        WasiFunctionEnv::new_with_store(
+            module,
+            env,
+            globals,
+            make_memory,
+            update_layout,
+            call_initialize,
+            Some((linker, &mut func_env.into_mut(&mut store))),
+        )
+    } else {
+        WasiFunctionEnv::new_with_store(
+            module,
+            env,
+            globals,
+            make_memory,
+            update_layout,
+            call_initialize,
+            None,
+        )
+    }
+}
+
 /// A task executor backed by a thread pool.
 ///
 /// ## Thread Safety
@@ -255,6 +305,25 @@ pub trait VirtualTaskManager: std::fmt::Debug + Send + Sync + 'static {
         task: Box<dyn FnOnce() -> BoxFuture<'static, ()> + Send + 'static>,
     ) -> Result<(), WasiThreadError>;
 
+    /// Block the current thread until `task` has run to completion.
+    ///
+    /// Implementations must not deadlock when this is called from inside a
+    /// task that the task manager is already running (e.g. from a syscall
+    /// that was itself invoked from [`VirtualTaskManager::block_on()`]).
+    ///
+    /// The default implementation runs the task on the thread pool via
+    /// [`VirtualTaskManager::task_shared()`] and waits for it to finish.
+    fn block_on(&self, task: BoxFuture<'static, ()>) -> Result<(), WasiThreadError> {
+        let (tx, rx) = std::sync::mpsc::channel();
+        self.task_shared(Box::new(move || {
+            Box::pin(async move {
+                task.await;
+                tx.send(()).ok();
+            })
+        }))?;
+        rx.recv().map_err(|_| WasiThreadError::InvalidWasmContext)
+    }
+
     /// Run a blocking WebAssembly operation on the thread pool.
     ///
     /// This is primarily used inside the context of a syscall and allows
@@ -326,6 +395,10 @@ where
         (**self).task_shared(task)
     }
 
+    fn block_on(&self, task: BoxFuture<'static, ()>) -> Result<(), WasiThreadError> {
+        (**self).block_on(task)
+    }
+
     fn task_wasm(&self, task: TaskWasm) -> Result<(), WasiThreadError> {
         (**self).task_wasm(task)
     }
@@ -475,13 +548,14 @@ where
     where
         A: Send + 'static,
     {
-        let (tx, rx) = ::tokio::sync::oneshot::channel();
+        let (tx, mut rx) = ::tokio::sync::oneshot::channel();
         let work = Box::pin(async move {
             let ret = task.await;
             tx.send(ret).ok();
         });
-        self.task_shared(Box::new(move || work)).unwrap();
-        rx.blocking_recv()
+        self.block_on(work)
+            .map_err(|err| anyhow::anyhow!("task execution failed - {err}"))?;
+        rx.try_recv()
             .map_err(|_| anyhow::anyhow!("task execution failed - result channel dropped"))
     }
 
@@ -2,14 +2,12 @@ use std::sync::Mutex;
 use std::{num::NonZeroUsize, pin::Pin, sync::Arc, time::Duration};
 
 use futures::{future::BoxFuture, Future};
-use tokio::runtime::{Handle, Runtime};
+use tokio::runtime::{Handle, Runtime, RuntimeFlavor};
 use virtual_mio::InlineWaker;
-use wasmer::AsStoreMut;
 
-use crate::runtime::SpawnType;
-use crate::{os::task::thread::WasiThreadError, WasiFunctionEnv};
+use crate::os::task::thread::WasiThreadError;
 
-use super::{SpawnMemoryTypeOrStore, TaskWasm, TaskWasmRunProperties, VirtualTaskManager};
+use super::{instantiate_task, TaskWasm, TaskWasmRunProperties, VirtualTaskManager};
 
 #[derive(Debug, Clone)]
 pub enum RuntimeOrHandle {
@@ -69,6 +67,25 @@ impl std::fmt::Debug for ThreadPool {
     }
 }
 
+impl ThreadPool {
+    fn new(name: String, max_threads: usize) -> Self {
+        Self {
+            inner: rusty_pool::Builder::new()
+                .name(name)
+                .core_size(max_threads)
+                .max_size(max_threads)
+                .build(),
+        }
+    }
+}
+
+fn default_max_threads() -> usize {
+    let concurrency = std::thread::available_parallelism()
+        .unwrap_or(NonZeroUsize::new(1).unwrap())
+        .get();
+    200usize.max(concurrency * 100)
+}
+
 /// A task manager that uses tokio to spawn tasks.
 #[derive(Clone, Debug)]
 pub struct TokioTaskManager {
@@ -81,23 +98,24 @@ impl TokioTaskManager {
     where
         I: Into<RuntimeOrHandle>,
     {
-        let concurrency = std::thread::available_parallelism()
-            .unwrap_or(NonZeroUsize::new(1).unwrap())
-            .get();
-        let max_threads = 200usize.max(concurrency * 100);
-
         Self {
             rt: rt.into(),
-            pool: Arc::new(ThreadPool {
-                inner: rusty_pool::Builder::new()
-                    .name("TokioTaskManager Thread Pool".to_string())
-                    .core_size(max_threads)
-                    .max_size(max_threads)
-                    .build(),
-            }),
+            pool: Arc::new(ThreadPool::new(
+                "TokioTaskManager Thread Pool".to_string(),
+                default_max_threads(),
+            )),
         }
     }
 
+    /// Configure a [`TokioTaskManager`] before creating it.
+    ///
+    /// Unlike [`TokioTaskManager::new()`], the builder can cap the number of
+    /// OS threads the task manager is allowed to spawn, which is useful when
+    /// embedding WASIX in constrained environments.
+    pub fn builder() -> TokioTaskManagerBuilder {
+        TokioTaskManagerBuilder::default()
+    }
+
     pub fn runtime_handle(&self) -> tokio::runtime::Handle {
         self.rt.handle().clone()
     }
@@ -105,6 +123,15 @@ impl TokioTaskManager {
     pub fn pool_handle(&self) -> Arc<ThreadPool> {
         self.pool.clone()
     }
+
+    fn spawn_and_wait(&self, task: BoxFuture<'static, ()>) -> Result<(), WasiThreadError> {
+        let (tx, rx) = std::sync::mpsc::channel();
+        self.rt.handle().spawn(async move {
+            task.await;
+            tx.send(()).ok();
+        });
+        rx.recv().map_err(|_| WasiThreadError::InvalidWasmContext)
+    }
 }
 
 impl Default for TokioTaskManager {
@@ -113,6 +140,91 @@ impl Default for TokioTaskManager {
     }
 }
 
+/// Builder for a [`TokioTaskManager`], created with [`TokioTaskManager::builder()`].
+#[derive(Debug, Default)]
+pub struct TokioTaskManagerBuilder {
+    runtime: Option<RuntimeOrHandle>,
+    worker_threads: Option<usize>,
+    max_blocking_threads: Option<usize>,
+    thread_name_prefix: Option<String>,
+}
+
+impl TokioTaskManagerBuilder {
+    /// Run tasks on an existing runtime instead of creating a new one.
+    ///
+    /// The worker thread count only applies to runtimes created by the
+    /// builder and is ignored when a runtime is provided.
+    pub fn with_runtime(mut self, rt: impl Into<RuntimeOrHandle>) -> Self {
+        self.runtime = Some(rt.into());
+        self
+    }
+
+    /// The number of worker threads driving asynchronous tasks.
+    ///
+    /// Defaults to the number of CPU cores.
+    pub fn with_worker_threads(mut self, worker_threads: usize) -> Self {
+        self.worker_threads = Some(worker_threads);
+        self
+    }
+
+    /// The maximum number of threads used for blocking work.
+    ///
+    /// This caps both tokio's blocking pool and the pool that runs
+    /// WebAssembly code. Every running WASIX thread (including the main
+    /// thread of each process) occupies one of these threads, so it also
+    /// limits how many WASIX threads can run at the same time.
+    pub fn with_max_blocking_threads(mut self, max_blocking_threads: usize) -> Self {
+        self.max_blocking_threads = Some(max_blocking_threads);
+        self
+    }
+
+    /// The prefix used when naming the threads spawned by the task manager.
+    pub fn with_thread_name_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.thread_name_prefix = Some(prefix.into());
+        self
+    }
+
+    pub fn build(self) -> std::io::Result<TokioTaskManager> {
+        if self.worker_threads == Some(0) || self.max_blocking_threads == Some(0) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "the task manager needs at least one thread",
+            ));
+        }
+
+        let max_threads = self
+            .max_blocking_threads
+            .unwrap_or_else(default_max_threads);
+        let (runtime_name, pool_name) = match &self.thread_name_prefix {
+            Some(prefix) => (format!("{prefix}-worker"), format!("{prefix}-pool")),
+            None => (
+                "tokio-runtime-worker".to_string(),
+                "TokioTaskManager Thread Pool".to_string(),
+            ),
+        };
+
+        let rt = match self.runtime {
+            Some(rt) => rt,
+            None => {
+                let mut builder = tokio::runtime::Builder::new_multi_thread();
+                builder
+                    .enable_all()
+                    .thread_name(runtime_name)
+                    .max_blocking_threads(max_threads);
+                if let Some(worker_threads) = self.worker_threads {
+                    builder.worker_threads(worker_threads);
+                }
+                builder.build()?.into()
+            }
+        };
+
+        Ok(TokioTaskManager {
+            rt,
+            pool: Arc::new(ThreadPool::new(pool_name, max_threads)),
+        })
+    }
+}
+
 impl VirtualTaskManager for TokioTaskManager {
     /// See [`VirtualTaskManager::sleep_now`].
     fn sleep_now(&self, time: Duration) -> Pin<Box<dyn Future<Output = ()> + Send + Sync>> {
@@ -126,6 +238,26 @@ impl VirtualTaskManager for TokioTaskManager {
         })
     }
 
+    /// See [`VirtualTaskManager::block_on`].
+    fn block_on(&self, task: BoxFuture<'static, ()>) -> Result<(), WasiThreadError> {
+        match Handle::try_current().map(|h| h.runtime_flavor()) {
+            // Blocking a worker thread of a multi-threaded runtime would panic, so
+            // hand the worker back to the runtime while we wait
+            Ok(RuntimeFlavor::MultiThread) => {
+                tokio::task::block_in_place(|| self.spawn_and_wait(task))
+            }
+            // A current-thread runtime can't make progress on the spawned task
+            // while we block it, so drive the task on this thread instead. This
+            // still hangs if the task waits on the runtime itself (e.g. timers),
+            // embedders that need that should use the `CurrentThreadTaskManager`
+            Ok(_) => {
+                InlineWaker::block_on(task);
+                Ok(())
+            }
+            Err(_) => self.spawn_and_wait(task),
+        }
+    }
+
     /// See [`VirtualTaskManager::task_shared`].
     fn task_shared(
         &self,
@@ -140,48 +272,30 @@ impl VirtualTaskManager for TokioTaskManager {
 
     /// See [`VirtualTaskManager::task_wasm`].
     fn task_wasm(&self, task: TaskWasm) -> Result<(), WasiThreadError> {
-        let run = task.run;
-        let recycle = task.recycle;
-        let env = task.env;
-        let pre_run = task.pre_run;
-
-        let make_memory: SpawnMemoryTypeOrStore = match &task.spawn_type {
-            SpawnType::CreateMemory | SpawnType::NewLinkerInstanceGroup(..) => {
-                SpawnMemoryTypeOrStore::New
-            }
-            SpawnType::CreateMemoryOfType(t) => SpawnMemoryTypeOrStore::Type(*t),
-            SpawnType::ShareMemory(_, _) | SpawnType::CopyMemory(_, _) => {
-                let mut store = env.runtime().new_store();
-                let memory = self.build_memory(&mut store.as_store_mut(), &task.spawn_type)?;
-                SpawnMemoryTypeOrStore::StoreAndMemory(store, memory)
-            }
-        };
-
-        let ret = if let SpawnType::NewLinkerInstanceGroup(linker, func_env, mut store) =
-            task.spawn_type
-        {
-            WasiFunctionEnv::new_with_store(
-                task.module,
-                env,
-                task.globals,
-                make_memory,
-                task.update_layout,
-                task.call_initialize,
-                Some((linker, &mut func_env.into_mut(&mut store))),
-            )
-        } else {
-            WasiFunctionEnv::new_with_store(
-                task.module,
-                env,
-                task.globals,
-                make_memory,
-                task.update_layout,
-                task.call_initialize,
-                None,
-            )
-        };
-
-        if let Some(trigger) = task.trigger {
+        let TaskWasm {
+            run,
+            recycle,
+            env,
+            module,
+            globals,
+            spawn_type,
+            trigger,
+            update_layout,
+            call_initialize,
+            pre_run,
+        } = task;
+
+        let ret = instantiate_task(
+            self,
+            env,
+            module,
+            globals,
+            spawn_type,
+            update_layout,
+            call_initialize,
+        );
+
+        if let Some(trigger) = trigger {
             tracing::trace!("spawning task_wasm trigger in async pool");
             // In principle, we'd need to create this in the `pool.execute` function below, that is
             //
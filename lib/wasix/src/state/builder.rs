@@ -15,15 +15,17 @@ use wasmer_config::package::PackageId;
 #[cfg(feature = "journal")]
 use crate::journal::{DynJournal, DynReadableJournal, SnapshotTrigger};
 use crate::{
-    bin_factory::{BinFactory, BinaryPackage},
-    capabilities::Capabilities,
+    bin_factory::{BinFactory, BinaryPackage, CommandAlias, CommandAliasError},
+    capabilities::{Capabilities, CapabilityFsV1, PathRule},
     fs::{WasiFs, WasiFsRoot, WasiInodes},
+    log_sink::LogSink,
     os::task::control_plane::{ControlPlaneConfig, ControlPlaneError, WasiControlPlane},
-    state::WasiState,
+    state::{secret_env::REDACTED, SecretEnv, StdioBuffering, WasiState},
     syscalls::types::{__WASI_STDERR_FILENO, __WASI_STDIN_FILENO, __WASI_STDOUT_FILENO},
+    test_report::{TestReporter, TEST_REPORT_PATH},
     Runtime, WasiEnv, WasiFunctionEnv, WasiRuntimeError, WasiThreadError,
 };
-use wasmer_types::ModuleHash;
+use wasmer_types::{target::CpuFeature, ModuleHash};
 use wasmer_wasix_types::wasi::SignalDisposition;
 
 use super::env::WasiEnvInit;
@@ -54,6 +56,10 @@ pub struct WasiEnvBuilder {
     pub(super) args: Vec<String>,
     /// Environment variables.
     pub(super) envs: Vec<(String, Vec<u8>)>,
+    /// Environment variables only the process being built can read.
+    pub(super) secret_envs: Vec<(String, Vec<u8>)>,
+    /// Where the secret environment variables go once they are checked.
+    pub(super) secret_env: SecretEnv,
     /// Signals that should get their handler overridden.
     pub(super) signals: Vec<SignalDisposition>,
     /// Pre-opened directories that will be accessible from WASI.
@@ -66,9 +72,16 @@ pub struct WasiEnvBuilder {
     pub(super) stdout: Option<Box<dyn VirtualFile + Send + Sync + 'static>>,
     pub(super) stderr: Option<Box<dyn VirtualFile + Send + Sync + 'static>>,
     pub(super) stdin: Option<Box<dyn VirtualFile + Send + Sync + 'static>>,
+    /// How stdout and stderr are buffered once the process runs.
+    pub(super) stdio_buffering: StdioBuffering,
     pub(super) fs: Option<WasiFsRoot>,
+    /// The file system mounted at `/tmp`, which gets cleared whenever the
+    /// control plane runs out of processes.
+    pub(super) tmp_fs: Option<TmpFileSystem>,
     pub(super) engine: Option<Engine>,
     pub(super) runtime: Option<Arc<dyn crate::Runtime + Send + Sync + 'static>>,
+    /// The control plane the process runs on, instead of one of its own.
+    pub(super) control_plane: Option<WasiControlPlane>,
     pub(super) current_dir: Option<PathBuf>,
 
     /// List of webc dependencies to be injected.
@@ -81,8 +94,15 @@ pub struct WasiEnvBuilder {
     /// List of host commands to map into the WASI instance.
     pub(super) map_commands: HashMap<String, PathBuf>,
 
+    /// Command names that resolve to commands from other packages.
+    pub(super) command_aliases: Vec<CommandAlias>,
+
     pub(super) capabilites: Capabilities,
 
+    /// Paths the runner set up for the program, which remain accessible
+    /// under [`CapabilityFsV1::PreopensOnly`].
+    pub(super) fs_grants: Vec<PathRule>,
+
     #[cfg(feature = "journal")]
     pub(super) snapshot_on: Vec<SnapshotTrigger>,
 
@@ -100,6 +120,12 @@ pub struct WasiEnvBuilder {
 
     pub(super) skip_stdio_during_bootstrap: bool,
 
+    /// Receives the test events the program writes to [`TEST_REPORT_PATH`].
+    pub(super) test_reporter: Option<TestReporter>,
+
+    /// Receives the runtime's events for the process.
+    pub(super) log_sink: Option<LogSink>,
+
     #[cfg(feature = "ctrlc")]
     pub(super) attach_ctrl_c: bool,
 }
@@ -111,15 +137,20 @@ impl std::fmt::Debug for WasiEnvBuilder {
             .field("entry_function", &self.entry_function)
             .field("args", &self.args)
             .field("envs", &self.envs)
+            .field("secret_env", &self.secret_env)
             .field("signals", &self.signals)
             .field("preopens", &self.preopens)
             .field("uses", &self.uses)
             .field("setup_fs_fn exists", &self.setup_fs_fn.is_some())
             .field("stdout_override exists", &self.stdout.is_some())
             .field("stderr_override exists", &self.stderr.is_some())
+            .field("stdio_buffering", &self.stdio_buffering)
             .field("stdin_override exists", &self.stdin.is_some())
             .field("engine_override_exists", &self.engine.is_some())
             .field("runtime_override_exists", &self.runtime.is_some())
+            .field("control_plane", &self.control_plane)
+            .field("test_reporter exists", &self.test_reporter.is_some())
+            .field("log_sink exists", &self.log_sink.is_some())
             .finish()
     }
 }
@@ -149,6 +180,8 @@ pub enum WasiStateCreationError {
     WasiIncludePackageError(String),
     #[error("control plane error")]
     ControlPlane(#[from] ControlPlaneError),
+    #[error(transparent)]
+    CommandAlias(#[from] CommandAliasError),
 }
 
 fn validate_mapped_dir_alias(alias: &str) -> Result<(), WasiStateCreationError> {
@@ -254,6 +287,49 @@ impl WasiEnvBuilder {
         &mut self.envs
     }
 
+    /// Add an environment variable that only the process being built can
+    /// read, and that isn't passed on to the processes it starts. See
+    /// [`SecretEnv`].
+    ///
+    /// Both the key and value of an environment variable must not
+    /// contain a nul byte (`0x0`), and the key must not contain the
+    /// `=` byte (`0x3d`).
+    pub fn secret_env<Key, Value>(mut self, key: Key, value: Value) -> Self
+    where
+        Key: AsRef<[u8]>,
+        Value: AsRef<[u8]>,
+    {
+        self.add_secret_env(key, value);
+        self
+    }
+
+    /// Add an environment variable that only the process being built can
+    /// read, and that isn't passed on to the processes it starts. See
+    /// [`SecretEnv`].
+    ///
+    /// Both the key and value of an environment variable must not
+    /// contain a nul byte (`0x0`), and the key must not contain the
+    /// `=` byte (`0x3d`).
+    pub fn add_secret_env<Key, Value>(&mut self, key: Key, value: Value)
+    where
+        Key: AsRef<[u8]>,
+        Value: AsRef<[u8]>,
+    {
+        self.secret_envs.push((
+            String::from_utf8_lossy(key.as_ref()).to_string(),
+            value.as_ref().to_vec(),
+        ));
+    }
+
+    /// Get the secret environment variables of the processes, to grant
+    /// them to other processes or revoke them while they run.
+    ///
+    /// The secrets added to the builder are only in it once the
+    /// environment is built.
+    pub fn get_secret_env(&self) -> &SecretEnv {
+        &self.secret_env
+    }
+
     /// Add a signal handler override.
     pub fn signal(mut self, sig_action: SignalDisposition) -> Self {
         self.add_signal(sig_action);
@@ -469,6 +545,38 @@ impl WasiEnvBuilder {
         }
     }
 
+    /// Make a command from another package available under a different name.
+    ///
+    /// See [`CommandAlias`] for more details.
+    pub fn command_alias(mut self, alias: CommandAlias) -> Self {
+        self.add_command_alias(alias);
+        self
+    }
+
+    /// Make a command from another package available under a different name.
+    pub fn add_command_alias(&mut self, alias: CommandAlias) {
+        self.command_aliases.push(alias);
+    }
+
+    /// Make a series of commands from other packages available under
+    /// different names.
+    pub fn command_aliases<I>(mut self, aliases: I) -> Self
+    where
+        I: IntoIterator<Item = CommandAlias>,
+    {
+        self.add_command_aliases(aliases);
+        self
+    }
+
+    /// Make a series of commands from other packages available under
+    /// different names.
+    pub fn add_command_aliases<I>(&mut self, aliases: I)
+    where
+        I: IntoIterator<Item = CommandAlias>,
+    {
+        self.command_aliases.extend(aliases);
+    }
+
     /// Preopen a directory
     ///
     /// This opens the given directory at the virtual root, `/`, and allows
@@ -681,6 +789,18 @@ impl WasiEnvBuilder {
         self.stderr = Some(new_file);
     }
 
+    /// Buffer what the process writes to stdout and stderr, and optionally
+    /// start every line with a tag, see [`StdioBuffering`].
+    pub fn stdio_buffering(mut self, buffering: StdioBuffering) -> Self {
+        self.set_stdio_buffering(buffering);
+        self
+    }
+
+    /// See [`WasiEnvBuilder::stdio_buffering()`].
+    pub fn set_stdio_buffering(&mut self, buffering: StdioBuffering) {
+        self.stdio_buffering = buffering;
+    }
+
     /// Overwrite the default WASI `stdin`, if you want to hold on to the
     /// original `stdin` use [`WasiFs::swap_file`] after building.
     pub fn stdin(mut self, new_file: Box<dyn VirtualFile + Send + Sync + 'static>) -> Self {
@@ -715,6 +835,21 @@ impl WasiEnvBuilder {
         self
     }
 
+    /// Sets the file system that is mounted at `/tmp` (see
+    /// [`virtual_fs::RootFileSystemBuilder::tmp_fs()`]).
+    ///
+    /// Its contents are deleted once the last process on the control plane
+    /// exits.
+    pub fn tmp_fs(mut self, fs: TmpFileSystem) -> Self {
+        self.set_tmp_fs(fs);
+        self
+    }
+
+    /// See [`WasiEnvBuilder::tmp_fs()`].
+    pub fn set_tmp_fs(&mut self, fs: TmpFileSystem) {
+        self.tmp_fs = Some(fs);
+    }
+
     /// Configure the WASI filesystem before running.
     // TODO: improve ergonomics on this function
     pub fn setup_fs(mut self, setup_fs_fn: SetupFsFn) -> Self {
@@ -745,6 +880,23 @@ impl WasiEnvBuilder {
         self.runtime = Some(runtime);
     }
 
+    /// Runs the process on an existing control plane, so that it shares its
+    /// limits and accounting (see [`ControlPlaneConfig`]) with the other
+    /// processes on it.
+    ///
+    /// By default every environment gets a control plane of its own,
+    /// configured from its [`Capabilities`]. The threading capabilities
+    /// are ignored when a control plane is given.
+    pub fn control_plane(mut self, control_plane: WasiControlPlane) -> Self {
+        self.set_control_plane(control_plane);
+        self
+    }
+
+    /// See [`WasiEnvBuilder::control_plane()`].
+    pub fn set_control_plane(&mut self, control_plane: WasiControlPlane) {
+        self.control_plane = Some(control_plane);
+    }
+
     pub fn capabilities(mut self, capabilities: Capabilities) -> Self {
         self.set_capabilities(capabilities);
         self
@@ -758,6 +910,40 @@ impl WasiEnvBuilder {
         self.capabilites = capabilities;
     }
 
+    /// Report `count` logical CPUs to the program instead of the task
+    /// manager's parallelism, e.g. for reproducibility or to throttle how
+    /// many threads it starts.
+    pub fn with_cpu_count(mut self, count: usize) -> Self {
+        self.set_cpu_count(count);
+        self
+    }
+
+    /// See [`WasiEnvBuilder::with_cpu_count()`].
+    pub fn set_cpu_count(&mut self, count: usize) {
+        self.capabilites.cpu.count = Some(count);
+    }
+
+    /// Report `features` to the program instead of the CPU features of the
+    /// host.
+    pub fn with_cpu_features(mut self, features: impl IntoIterator<Item = CpuFeature>) -> Self {
+        self.set_cpu_features(features);
+        self
+    }
+
+    /// See [`WasiEnvBuilder::with_cpu_features()`].
+    pub fn set_cpu_features(&mut self, features: impl IntoIterator<Item = CpuFeature>) {
+        self.capabilites.cpu.features = Some(features.into_iter().collect());
+    }
+
+    /// Declares that the program needs access to the paths matched by
+    /// `rule`, e.g. because they were mounted for it.
+    ///
+    /// The grant only has an effect under [`CapabilityFsV1::PreopensOnly`];
+    /// the other policies are applied as they are.
+    pub fn add_fs_grant(&mut self, rule: PathRule) {
+        self.fs_grants.push(rule);
+    }
+
     #[cfg(feature = "journal")]
     pub fn add_snapshot_trigger(&mut self, on: SnapshotTrigger) {
         self.snapshot_on.push(on);
@@ -777,6 +963,40 @@ impl WasiEnvBuilder {
         self.skip_stdio_during_bootstrap = skip;
     }
 
+    /// Passes every test event the program writes to [`TEST_REPORT_PATH`]
+    /// to `reporter`, as soon as it has been written.
+    ///
+    /// See [`crate::test_report`] for the format of the events. The file
+    /// can only be created in a sandbox file system (see
+    /// [`WasiEnvBuilder::sandbox_fs()`]), so the reporter is ignored when
+    /// [`WasiEnvBuilder::fs()`] is used.
+    pub fn with_test_reporter(mut self, reporter: impl Into<TestReporter>) -> Self {
+        self.set_test_reporter(reporter);
+        self
+    }
+
+    /// See [`WasiEnvBuilder::with_test_reporter()`].
+    pub fn set_test_reporter(&mut self, reporter: impl Into<TestReporter>) {
+        self.test_reporter = Some(reporter.into());
+    }
+
+    /// Sends the `tracing` events the runtime emits while running the
+    /// process's threads to `sink`, rather than to the rest of the global
+    /// subscriber.
+    ///
+    /// This only has an effect when the subscriber includes a
+    /// `LogSinkLayer`, see [`crate::log_sink`]. Sub-processes aren't
+    /// captured.
+    pub fn with_log_sink(mut self, sink: impl Into<LogSink>) -> Self {
+        self.set_log_sink(sink);
+        self
+    }
+
+    /// See [`WasiEnvBuilder::with_log_sink()`].
+    pub fn set_log_sink(&mut self, sink: impl Into<LogSink>) {
+        self.log_sink = Some(sink.into());
+    }
+
     /// Consumes the [`WasiEnvBuilder`] and produces a [`WasiEnvInit`], which
     /// can be used to construct a new [`WasiEnv`].
     ///
@@ -799,7 +1019,16 @@ impl WasiEnvBuilder {
             Equal,
         }
 
-        for (env_key, env_value) in self.envs.iter() {
+        let secret_envs = self
+            .secret_envs
+            .iter()
+            .map(|(key, value)| (key, value, true));
+        for (env_key, env_value, secret) in self
+            .envs
+            .iter()
+            .map(|(key, value)| (key, value, false))
+            .chain(secret_envs)
+        {
             match env_key.as_bytes().iter().find_map(|&ch| {
                 if ch == 0 {
                     Some(InvalidCharacter::Nul)
@@ -828,7 +1057,11 @@ impl WasiEnvBuilder {
                 return Err(WasiStateCreationError::EnvironmentVariableFormatError(
                     format!(
                         "found nul byte in env var value \"{}\" (key=value)",
-                        String::from_utf8_lossy(env_value),
+                        if secret {
+                            REDACTED.into()
+                        } else {
+                            String::from_utf8_lossy(env_value)
+                        },
                     ),
                 ));
             }
@@ -845,6 +1078,19 @@ impl WasiEnvBuilder {
             .take()
             .unwrap_or_else(|| WasiFsRoot::Sandbox(Arc::new(TmpFileSystem::new())));
 
+        if let Some(reporter) = self.test_reporter.take() {
+            match &fs_backing {
+                WasiFsRoot::Sandbox(fs) => {
+                    crate::test_report::install(fs, reporter);
+                    self.fs_grants.push(PathRule::read_write(TEST_REPORT_PATH));
+                }
+                // Only sandboxed file systems can hold device files
+                WasiFsRoot::Backing(_) => tracing::warn!(
+                    "Test events can only be reported from a sandbox file system, ignoring the test reporter"
+                ),
+            }
+        }
+
         if let Some(dir) = &self.current_dir {
             match fs_backing.read_dir(dir) {
                 Ok(_) => {
@@ -895,6 +1141,17 @@ impl WasiEnvBuilder {
             if let Some(f) = &self.setup_fs_fn {
                 f(&inodes, &mut wasi_fs).map_err(WasiStateCreationError::WasiFsSetupError)?;
             }
+
+            match &self.capabilites.fs {
+                CapabilityFsV1::Unrestricted => {}
+                CapabilityFsV1::DenyAll => wasi_fs.restrict_paths(Vec::new()),
+                CapabilityFsV1::PreopensOnly => {
+                    let mut rules = wasi_fs.preopen_path_rules();
+                    rules.extend(self.fs_grants.iter().cloned());
+                    wasi_fs.restrict_paths(rules);
+                }
+                CapabilityFsV1::Custom(rules) => wasi_fs.restrict_paths(rules.clone()),
+            }
             wasi_fs
         };
 
@@ -921,7 +1178,17 @@ impl WasiEnvBuilder {
             futexs: Default::default(),
             clock_offset: Default::default(),
             envs: std::sync::Mutex::new(conv_env_vars(self.envs)),
+            secret_env: {
+                for (key, value) in self.secret_envs {
+                    self.secret_env.insert(key, value);
+                }
+                self.secret_env
+            },
             signals: std::sync::Mutex::new(self.signals.iter().map(|s| (s.sig, s.disp)).collect()),
+            package: Default::default(),
+            archive_jobs: Default::default(),
+            #[cfg(feature = "wasi-crypto")]
+            crypto: Default::default(),
         };
 
         let runtime = self.runtime.unwrap_or_else(|| {
@@ -961,23 +1228,52 @@ impl WasiEnvBuilder {
 
         let uses = self.uses;
         let map_commands = self.map_commands;
+        let command_aliases = self.command_aliases;
+
+        // Commands mapped from the host (`--map-command`) live in the root
+        // file system, where the bin factory can't see them
+        if let Some(alias) = command_aliases
+            .iter()
+            .find(|alias| map_commands.contains_key(&alias.name))
+        {
+            return Err(CommandAliasError::ShadowsCommand {
+                alias: alias.name.clone(),
+                path: format!("/bin/{}", alias.name),
+            }
+            .into());
+        }
 
         let bin_factory = BinFactory::new(runtime.clone());
 
         let capabilities = self.capabilites;
 
-        let plane_config = ControlPlaneConfig {
-            max_task_count: capabilities.threading.max_threads,
-            enable_asynchronous_threading: capabilities.threading.enable_asynchronous_threading,
-            enable_exponential_cpu_backoff: capabilities.threading.enable_exponential_cpu_backoff,
-        };
-        let control_plane = WasiControlPlane::new(plane_config);
+        let control_plane = self.control_plane.unwrap_or_else(|| {
+            WasiControlPlane::new(ControlPlaneConfig {
+                max_task_count: capabilities.threading.max_threads,
+                enable_asynchronous_threading: capabilities.threading.enable_asynchronous_threading,
+                enable_exponential_cpu_backoff: capabilities
+                    .threading
+                    .enable_exponential_cpu_backoff,
+                ..ControlPlaneConfig::new()
+            })
+        });
+        if let Some(tmp_fs) = self.tmp_fs {
+            control_plane.on_idle(move || {
+                if let Err(err) = tmp_fs.clear() {
+                    tracing::warn!(
+                        error = &err as &dyn std::error::Error,
+                        "Unable to clear /tmp"
+                    );
+                }
+            });
+        }
 
         let init = WasiEnvInit {
             state,
             runtime,
             webc_dependencies: uses,
             mapped_commands: map_commands,
+            command_aliases,
             control_plane,
             bin_factory,
             capabilities,
@@ -996,6 +1292,8 @@ impl WasiEnvBuilder {
             #[cfg(feature = "journal")]
             stop_running_after_snapshot: self.stop_running_after_snapshot,
             skip_stdio_during_bootstrap: self.skip_stdio_during_bootstrap,
+            log_sink: self.log_sink,
+            stdio_buffering: self.stdio_buffering,
         };
 
         Ok(init)
@@ -1255,4 +1553,26 @@ mod test {
             WasiStateCreationError::ArgumentContainsNulByte(_)
         ));
     }
+
+    #[test]
+    fn aliases_cannot_shadow_mapped_commands() {
+        #[cfg(not(target_arch = "wasm32"))]
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        #[cfg(not(target_arch = "wasm32"))]
+        let _guard = runtime.enter();
+
+        let output = WasiEnvBuilder::new("test_prog")
+            .map_command("python", "/usr/local/bin/python.wasm")
+            .command_alias("python=python/python".parse().unwrap())
+            .engine(Engine::default())
+            .build_init();
+        let err = output.expect_err("should fail");
+        assert!(matches!(
+            err,
+            WasiStateCreationError::CommandAlias(CommandAliasError::ShadowsCommand { .. })
+        ));
+    }
 }
@@ -1,24 +1,28 @@
 use std::{
     collections::HashMap,
+    future::Future,
     ops::Deref,
+    panic::AssertUnwindSafe,
     path::{Path, PathBuf},
     str,
     sync::Arc,
+    task::Waker,
     time::Duration,
 };
 
-use futures::future::BoxFuture;
+use bytes::Bytes;
+use futures::{future::BoxFuture, FutureExt, StreamExt};
 use rand::Rng;
 use virtual_fs::{FileSystem, FsError, VirtualFile};
 use virtual_net::DynVirtualNetworking;
 use wasmer::{
-    AsStoreMut, AsStoreRef, ExportError, FunctionEnvMut, Instance, Memory, MemoryType, MemoryView,
-    Module,
+    AsStoreMut, AsStoreRef, ExportError, FunctionEnvMut, Imports, Instance, InstantiationError,
+    InstantiationOptions, Memory, MemoryType, MemoryView, Module, Store, StoreMut,
 };
 use wasmer_config::package::PackageSource;
 use wasmer_wasix_types::{
     types::Signal,
-    wasi::{Errno, ExitCode, Snapshot0Clockid},
+    wasi::{Errno, EventFdFlags, ExitCode, Fd as WasiFd, Snapshot0Clockid},
     wasix::ThreadStartType,
 };
 use webc::metadata::annotations::Wasi;
@@ -26,24 +30,31 @@ use webc::metadata::annotations::Wasi;
 #[cfg(feature = "journal")]
 use crate::journal::{DynJournal, JournalEffector, SnapshotTrigger};
 use crate::{
-    bin_factory::{BinFactory, BinaryPackage, BinaryPackageCommand},
+    bin_factory::{alias_paths, BinFactory, BinaryPackage, BinaryPackageCommand, CommandAlias},
     capabilities::Capabilities,
-    fs::{WasiFsRoot, WasiInodes},
+    fs::{EventFdHandle, HostFutureFile, Kind, PathLookupStats, WasiFsRoot, WasiInodes},
     import_object_for_all_wasi_versions,
-    os::task::{
-        control_plane::ControlPlaneError,
-        process::{WasiProcess, WasiProcessId},
-        thread::{WasiMemoryLayout, WasiThread, WasiThreadHandle, WasiThreadId},
+    log_sink::{LogSink, LogSinkHandle},
+    os::{
+        cpu::CpuInfo,
+        task::{
+            control_plane::ControlPlaneError,
+            process::{WasiProcess, WasiProcessId},
+            thread::{WasiMemoryLayout, WasiThread, WasiThreadHandle, WasiThreadId},
+        },
     },
     runtime::task_manager::InlineWaker,
-    syscalls::platform_clock_time_get,
+    syscalls::{
+        platform_clock_time_get,
+        types::{__WASI_STDERR_FILENO, __WASI_STDOUT_FILENO},
+    },
     Runtime, VirtualTaskManager, WasiControlPlane, WasiEnvBuilder, WasiError, WasiFunctionEnv,
     WasiResult, WasiRuntimeError, WasiStateCreationError, WasiThreadError, WasiVFork,
 };
 use wasmer_types::ModuleHash;
 
 pub use super::handles::*;
-use super::{conv_env_vars, Linker, WasiState};
+use super::{conv_env_vars, Linker, SecretEnv, StdioBuffering, WasiState};
 
 /// Data required to construct a [`WasiEnv`].
 #[derive(Debug)]
@@ -52,6 +63,7 @@ pub struct WasiEnvInit {
     pub runtime: Arc<dyn Runtime + Send + Sync>,
     pub webc_dependencies: Vec<BinaryPackage>,
     pub mapped_commands: HashMap<String, PathBuf>,
+    pub command_aliases: Vec<CommandAlias>,
     pub bin_factory: BinFactory,
     pub capabilities: Capabilities,
 
@@ -80,6 +92,13 @@ pub struct WasiEnvInit {
 
     /// Skip writes to stdout and stderr when bootstrapping from a journal
     pub skip_stdio_during_bootstrap: bool,
+
+    /// Where the runtime's events for the process go, if not to the global
+    /// subscriber
+    pub log_sink: Option<LogSink>,
+
+    /// How the process's stdout and stderr are buffered
+    pub stdio_buffering: StdioBuffering,
 }
 
 impl WasiEnvInit {
@@ -102,12 +121,18 @@ impl WasiEnvInit {
                 ),
                 args: std::sync::Mutex::new(self.state.args.lock().unwrap().clone()),
                 envs: std::sync::Mutex::new(self.state.envs.lock().unwrap().deref().clone()),
+                secret_env: self.state.secret_env.clone(),
                 signals: std::sync::Mutex::new(self.state.signals.lock().unwrap().deref().clone()),
+                package: std::sync::Mutex::new(self.state.package.lock().unwrap().clone()),
+                archive_jobs: Default::default(),
+                #[cfg(feature = "wasi-crypto")]
+                crypto: Default::default(),
                 preopen: self.state.preopen.clone(),
             },
             runtime: self.runtime.clone(),
             webc_dependencies: self.webc_dependencies.clone(),
             mapped_commands: self.mapped_commands.clone(),
+            command_aliases: self.command_aliases.clone(),
             bin_factory: self.bin_factory.clone(),
             capabilities: self.capabilities.clone(),
             control_plane: self.control_plane.clone(),
@@ -122,6 +147,8 @@ impl WasiEnvInit {
             #[cfg(feature = "journal")]
             stop_running_after_snapshot: self.stop_running_after_snapshot,
             skip_stdio_during_bootstrap: self.skip_stdio_during_bootstrap,
+            log_sink: None,
+            stdio_buffering: self.stdio_buffering.clone(),
         }
     }
 }
@@ -221,6 +248,7 @@ impl WasiEnv {
     /// Forking the WasiState is used when either fork or vfork is called
     pub fn fork(&self) -> Result<(Self, WasiThreadHandle), ControlPlaneError> {
         let process = self.control_plane.new_process(self.process.module_hash)?;
+        process.inherit_session(&self.process);
         let handle = process.new_thread(self.layout.clone(), ThreadStartType::MainThread)?;
 
         let thread = handle.as_thread();
@@ -257,6 +285,24 @@ impl WasiEnv {
         self.process.pid()
     }
 
+    /// Creates a store for this process to run in, whose memories count
+    /// towards its share of the control plane's limits.
+    pub(crate) fn new_store(&self) -> Store {
+        let mut store = self.runtime.new_store();
+        store.set_memory_limiter(Some(self.process.account.clone()));
+        store
+    }
+
+    /// Makes the memories created in a store the host gave us count towards
+    /// this process's share of the control plane's limits, unless the host
+    /// accounts for them itself.
+    fn account_memories(&self, store: &mut impl AsStoreMut) {
+        let mut store = store.as_store_mut();
+        if store.memory_limiter().is_none() {
+            store.set_memory_limiter(Some(self.process.account.clone()));
+        }
+    }
+
     pub fn tid(&self) -> WasiThreadId {
         self.thread.tid()
     }
@@ -299,20 +345,27 @@ impl WasiEnv {
         }
 
         // The process and thread state need to be reset
+        let control_plane = self.process.compute.must_upgrade();
+        self.process.account.exit();
         self.process = WasiProcess::new(
             self.process.pid,
             self.process.module_hash,
             self.process.compute.clone(),
         );
-        self.thread = WasiThread::new(
+        self.process.account = control_plane.open_account()?;
+        let mut thread = WasiThread::new(
             self.thread.pid(),
             self.thread.tid(),
             self.thread.is_main(),
             self.process.finished.clone(),
-            self.process.compute.must_upgrade().register_task()?,
+            control_plane.register_task()?,
             self.thread.memory_layout().clone(),
             self.thread.thread_start_type(),
         );
+        if thread.is_main() {
+            thread = thread.with_process_account(self.process.account.clone());
+        }
+        self.thread = thread;
 
         Ok(())
     }
@@ -348,11 +401,29 @@ impl WasiEnv {
         init: WasiEnvInit,
         module_hash: ModuleHash,
     ) -> Result<Self, WasiRuntimeError> {
-        let process = if let Some(p) = init.process {
+        let is_new_process = init.process.is_none();
+        let mut process = if let Some(p) = init.process {
             p
         } else {
             init.control_plane.new_process(module_hash)?
         };
+        if let Some(sink) = init.log_sink {
+            process.log_sink = Some(Arc::new(LogSinkHandle::register(sink)));
+        }
+        // Only the process the environment was built for sees the secrets
+        init.state.secret_env.grant_all(process.pid());
+
+        // The streams of an existing process are buffered already
+        if is_new_process {
+            let command = init.state.args.lock().unwrap().first().cloned();
+            init.stdio_buffering
+                .install(
+                    &init.state.fs,
+                    process.pid(),
+                    command.as_deref().unwrap_or_default(),
+                )
+                .map_err(WasiStateCreationError::FileSystemError)?;
+        }
 
         #[cfg(feature = "journal")]
         {
@@ -368,6 +439,15 @@ impl WasiEnv {
             process.new_thread(layout.clone(), ThreadStartType::MainThread)?
         };
 
+        init.state
+            .fs
+            .mount_dev_shm(init.control_plane.shared_memory());
+        init.state.fs.mount_proc(&CpuInfo::new(
+            &init.capabilities.cpu,
+            init.runtime.task_manager().as_ref(),
+            &init.runtime.engine(),
+        ));
+
         let mut env = Self {
             control_plane: init.control_plane,
             process,
@@ -404,6 +484,8 @@ impl WasiEnv {
         #[cfg(feature = "sys")]
         env.map_commands(init.mapped_commands.clone())?;
 
+        env.alias_commands(init.command_aliases.iter().cloned())?;
+
         Ok(env)
     }
 
@@ -419,7 +501,9 @@ impl WasiEnv {
         parent_linker_and_ctx: Option<(Linker, &mut FunctionEnvMut<WasiEnv>)>,
     ) -> Result<(Instance, WasiFunctionEnv), WasiThreadError> {
         let pid = self.process.pid();
+        let options = self.instantiation_options();
 
+        self.account_memories(store);
         let mut store = store.as_store_mut();
         let mut func_env = WasiFunctionEnv::new(&mut store, self);
 
@@ -479,9 +563,74 @@ impl WasiEnv {
             }
         }
 
-        // Let's instantiate the module with the imports.
-        let mut import_object =
-            import_object_for_all_wasi_versions(&module, &mut store, &func_env.env);
+        let (import_object, imported_memory) =
+            Self::instance_imports(&module, &mut store, &func_env, memory);
+        let instance = Instance::new_with_options(&mut store, &module, &import_object, &options);
+
+        Self::finish_instantiate(
+            func_env,
+            store,
+            instance,
+            imported_memory,
+            update_layout,
+            call_initialize,
+        )
+    }
+
+    /// Like [`WasiEnv::instantiate()`], but without blocking the executor
+    /// while the memory of a module with large data segments is initialized,
+    /// see [`Instance::new_async()`].
+    ///
+    /// Dynamically linked modules are still instantiated synchronously.
+    #[allow(clippy::result_large_err)]
+    pub(crate) async fn instantiate_async(
+        self,
+        module: Module,
+        store: &mut Store,
+        memory: Option<Memory>,
+        update_layout: bool,
+        call_initialize: bool,
+    ) -> Result<(Instance, WasiFunctionEnv), WasiThreadError> {
+        if super::linker::is_dynamically_linked(&module) {
+            return self.instantiate(module, store, memory, update_layout, call_initialize, None);
+        }
+
+        let options = self.instantiation_options();
+        self.account_memories(store);
+        let func_env = WasiFunctionEnv::new(store, self);
+        let (import_object, imported_memory) =
+            Self::instance_imports(&module, store, &func_env, memory);
+        let instance =
+            Instance::new_async_with_options(store, &module, &import_object, &options).await;
+
+        Self::finish_instantiate(
+            func_env,
+            store.as_store_mut(),
+            instance,
+            imported_memory,
+            update_layout,
+            call_initialize,
+        )
+    }
+
+    /// How modules are instantiated, with the start function deadline from
+    /// [`Runtime::start_function_timeout()`].
+    fn instantiation_options(&self) -> InstantiationOptions {
+        match self.runtime.start_function_timeout() {
+            Some(timeout) => InstantiationOptions::new().start_timeout(timeout),
+            None => InstantiationOptions::new(),
+        }
+    }
+
+    /// The imports a module is instantiated with, along with the memory it
+    /// imports, if any.
+    fn instance_imports(
+        module: &Module,
+        store: &mut impl AsStoreMut,
+        func_env: &WasiFunctionEnv,
+        memory: Option<Memory>,
+    ) -> (Imports, Option<Memory>) {
+        let mut import_object = import_object_for_all_wasi_versions(module, store, &func_env.env);
 
         let imported_memory = if let Some(memory) = memory {
             import_object.define("env", "memory", memory.clone());
@@ -490,8 +639,23 @@ impl WasiEnv {
             None
         };
 
-        // Construct the instance.
-        let instance = match Instance::new(&mut store, &module, &import_object) {
+        (import_object, imported_memory)
+    }
+
+    /// Sets up the environment for a newly created (non dynamically linked)
+    /// instance, or reports why it couldn't be created.
+    #[allow(clippy::result_large_err)]
+    fn finish_instantiate(
+        mut func_env: WasiFunctionEnv,
+        mut store: StoreMut,
+        instance: Result<Instance, InstantiationError>,
+        imported_memory: Option<Memory>,
+        update_layout: bool,
+        call_initialize: bool,
+    ) -> Result<(Instance, WasiFunctionEnv), WasiThreadError> {
+        let pid = func_env.data(&store).pid();
+
+        let instance = match instance {
             Ok(a) => a,
             Err(err) => {
                 tracing::error!(
@@ -587,6 +751,19 @@ impl WasiEnv {
         &self.state.fs.root_fs
     }
 
+    /// Counters for how paths have been resolved in this process's file
+    /// system (which it shares with its forks).
+    pub fn path_lookup_stats(&self) -> PathLookupStats {
+        self.state.fs.path_lookup_stats()
+    }
+
+    /// Enables or disables resolving paths from already loaded directory
+    /// entries, which is only useful to measure how much it helps.
+    #[doc(hidden)]
+    pub fn set_path_lookup_cache(&self, enabled: bool) {
+        self.state.fs.set_path_lookup_cache(enabled)
+    }
+
     /// Overrides the runtime implementation for this environment
     pub fn set_runtime<R>(&mut self, runtime: R)
     where
@@ -595,6 +772,35 @@ impl WasiEnv {
         self.runtime = Arc::new(runtime);
     }
 
+    /// Sends a signal to another process on the same control plane on
+    /// behalf of this process.
+    ///
+    /// Returns [`Errno::Srch`] if there is no such (running) process, and
+    /// [`Errno::Perm`] if this process isn't allowed to signal it.
+    pub fn kill(&self, pid: WasiProcessId, signal: Signal) -> Result<(), Errno> {
+        let target = self.control_plane.get_process(pid).ok_or(Errno::Srch)?;
+        if self.capabilities.signals.isolate_processes && !self.is_ancestor_of(&target) {
+            return Err(Errno::Perm);
+        }
+        target.signal(signal)
+    }
+
+    /// Whether `process` is this process or one of its descendants
+    fn is_ancestor_of(&self, process: &WasiProcess) -> bool {
+        let mut pid = process.pid();
+        // The parent chain is bounded by the number of processes
+        for _ in 0..=self.control_plane.processes().len() {
+            if pid == self.pid() {
+                return true;
+            }
+            match self.control_plane.get_process(pid) {
+                Some(p) if p.ppid() != WasiProcessId::from(0) => pid = p.ppid(),
+                _ => return false,
+            }
+        }
+        false
+    }
+
     /// Returns the number of active threads
     pub fn active_threads(&self) -> u32 {
         self.process.active_threads()
@@ -634,12 +840,7 @@ impl WasiEnv {
             let signals = env.thread.pop_signals();
             if !signals.is_empty() {
                 for sig in signals {
-                    if sig == Signal::Sigint
-                        || sig == Signal::Sigquit
-                        || sig == Signal::Sigkill
-                        || sig == Signal::Sigabrt
-                        || sig == Signal::Sigpipe
-                    {
+                    if terminates_by_default(sig) {
                         let exit_code = env.thread.set_or_get_exit_code_for_signal(sig);
                         return Err(WasiError::Exit(exit_code));
                     } else {
@@ -768,6 +969,26 @@ impl WasiEnv {
         }
     }
 
+    /// Returns whether a signal is waiting that should interrupt a blocking
+    /// syscall, subscribing `waker` to new signals otherwise.
+    ///
+    /// Once a handler has been registered every signal interrupts so that
+    /// the handler can run. Before that, only the signals that terminate the
+    /// process and [`Signal::Sigwakeup`] (which the linker uses to get
+    /// blocked threads to cooperate) do.
+    pub(crate) fn has_interrupting_signal_or_subscribe(&self, waker: &Waker) -> bool {
+        let (signal_set, has_handler) = match self.try_inner() {
+            Some(inner) => {
+                let handles = inner.main_module_instance_handles();
+                (handles.signal_set, handles.signal.is_some())
+            }
+            None => (false, false),
+        };
+        self.thread.has_signal_matching_or_subscribe(waker, |sig| {
+            has_handler || (!signal_set && (terminates_by_default(sig) || sig == Signal::Sigwakeup))
+        })
+    }
+
     /// Returns an exit code if the thread or process has been forced to exit
     pub fn should_exit(&self) -> Option<ExitCode> {
         // Check for forced exit
@@ -899,10 +1120,28 @@ impl WasiEnv {
         )
     }
 
-    /// Copy the lazy reference so that when it's initialized during the
-    /// export phase, all the other references get a copy of it
-    #[allow(dead_code)]
-    pub(crate) fn try_memory_clone(&self) -> Option<Memory> {
+    /// Returns a handle to the process's main memory, or `None` if the
+    /// instance hasn't been initialized yet.
+    ///
+    /// The handle refers to the same memory rather than a copy of it, so it
+    /// can be given to another [`Instance`] in the same [`Store`] as an import
+    /// (as long as the memory types match) to share data without copying.
+    /// Growth on either side is visible to both, but [`MemoryView`]s taken
+    /// before the memory grew are stale and need to be recreated.
+    ///
+    /// Instantiating a module from another [`Store`] with this memory fails
+    /// with [`wasmer::InstantiationError::DifferentStores`].
+    ///
+    /// # Aliasing
+    ///
+    /// Neither the guest nor the other instance knows the memory is shared.
+    /// Whatever the other instance writes can clobber the guest's stack, heap
+    /// or `libc` state, and nothing synchronizes accesses between guest
+    /// threads and the other instance. Agree on a region that only one side
+    /// writes to at a time.
+    ///
+    /// [`Store`]: wasmer::Store
+    pub fn memory_clone(&self) -> Option<Memory> {
         self.try_inner()
             .map(|i| i.main_module_instance_handles().memory_clone())
     }
@@ -912,6 +1151,36 @@ impl WasiEnv {
         &self.state
     }
 
+    /// Get the environment variables of the process as `KEY=value`,
+    /// including the secrets granted to it
+    pub(crate) fn environ(&self) -> Vec<Vec<u8>> {
+        let secrets = self.state.secret_env.granted(self.pid());
+        let is_secret = |var: &[u8]| {
+            secrets.iter().any(|(key, _)| {
+                var.strip_prefix(key.as_bytes())
+                    .is_some_and(|rest| rest.first() == Some(&b'='))
+            })
+        };
+
+        let mut environ: Vec<_> = self
+            .state
+            .envs
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|var| !is_secret(var))
+            .cloned()
+            .collect();
+        environ.extend(conv_env_vars(secrets));
+        environ
+    }
+
+    /// Get the secret environment variables, to grant them to other
+    /// processes or revoke them
+    pub fn secret_env(&self) -> &SecretEnv {
+        &self.state.secret_env
+    }
+
     /// Get the `VirtualFile` object at stdout
     pub fn stdout(&self) -> Result<Option<Box<dyn VirtualFile + Send + Sync + 'static>>, FsError> {
         self.state.stdout()
@@ -1011,6 +1280,62 @@ impl WasiEnv {
         (state, inodes)
     }
 
+    /// Creates an event counter that the guest sees as an fd with the
+    /// semantics of an `eventfd` (like the ones `fd_event` creates), returning
+    /// the fd along with a handle through which the host can use it too.
+    ///
+    /// `flags` are those of `fd_event`, e.g. `EVENT_FD_FLAGS_SEMAPHORE`.
+    pub fn create_event_fd(
+        &self,
+        initial_val: u64,
+        flags: EventFdFlags,
+    ) -> Result<(WasiFd, EventFdHandle), Errno> {
+        let (state, inodes) = self.get_wasi_state_and_inodes();
+        let (fd, inner) = state.fs.create_event_fd(inodes, initial_val, flags, None)?;
+        Ok((fd, EventFdHandle::new(inner)))
+    }
+
+    /// Gets a handle to the event counter behind `fd`, e.g. one the guest
+    /// created with `fd_event`.
+    ///
+    /// Fails with [`Errno::Badf`] if `fd` isn't open and [`Errno::Inval`] if
+    /// it isn't an event counter.
+    pub fn event_fd_handle(&self, fd: WasiFd) -> Result<EventFdHandle, Errno> {
+        let inode = self.state.fs.get_fd_inode(fd)?;
+        let guard = inode.read();
+        match guard.deref() {
+            Kind::EventNotifications { inner } => Ok(EventFdHandle::new(inner.clone())),
+            _ => Err(Errno::Inval),
+        }
+    }
+
+    /// Runs `fut` on the task manager and returns an fd the guest can poll
+    /// until it completes, after which reading the fd returns the bytes the
+    /// future resolved to.
+    ///
+    /// Closing the fd before the future completes drops the future. Reads
+    /// fail with [`Errno::Io`] if the future panics.
+    pub fn spawn_host_future<F>(&self, fut: F) -> Result<WasiFd, Errno>
+    where
+        F: Future<Output = Bytes> + Send + 'static,
+    {
+        let (fut, abort) = futures::future::abortable(AssertUnwindSafe(fut).catch_unwind());
+        let (file, completion) = HostFutureFile::new(abort);
+        self.tasks().task_shared(Box::new(move || {
+            Box::pin(async move {
+                match fut.await {
+                    Ok(Ok(result)) => completion.complete(result),
+                    Ok(Err(_)) => completion.panicked(),
+                    // The fd was closed
+                    Err(_) => {}
+                }
+            })
+        }))?;
+
+        let (state, inodes) = self.get_wasi_state_and_inodes();
+        state.fs.create_host_future_fd(inodes, file)
+    }
+
     pub fn use_package(&self, pkg: &BinaryPackage) -> Result<(), WasiStateCreationError> {
         InlineWaker::block_on(self.use_package_async(pkg))
     }
@@ -1121,26 +1446,52 @@ impl WasiEnv {
 
     /// Given a list of packages, load them from the registry and make them
     /// available.
+    ///
+    /// The packages (and their dependencies) are all downloaded before any
+    /// of them is used, up to [`Runtime::package_download_concurrency()`] at
+    /// a time. A failed download doesn't cancel the others, so whatever did
+    /// download still ends up in the cache.
     pub fn uses<I>(&self, uses: I) -> Result<(), WasiStateCreationError>
     where
         I: IntoIterator<Item = String>,
     {
         let rt = self.runtime();
 
-        for package_name in uses {
-            let specifier = package_name.parse::<PackageSource>().map_err(|e| {
-                WasiStateCreationError::WasiIncludePackageError(format!(
-                    "package_name={package_name}, {e}",
-                ))
-            })?;
-            let pkg = InlineWaker::block_on(BinaryPackage::from_registry(&specifier, rt)).map_err(
-                |e| {
+        let specifiers = uses
+            .into_iter()
+            .map(|package_name| {
+                let specifier = package_name.parse::<PackageSource>().map_err(|e| {
                     WasiStateCreationError::WasiIncludePackageError(format!(
                         "package_name={package_name}, {e}",
                     ))
-                },
-            )?;
-            self.use_package(&pkg)?;
+                })?;
+                Ok((package_name, specifier))
+            })
+            .collect::<Result<Vec<_>, WasiStateCreationError>>()?;
+
+        let results: Vec<_> = InlineWaker::block_on(
+            futures::stream::iter(&specifiers)
+                .map(|(_, specifier)| BinaryPackage::from_registry(specifier, rt))
+                .buffered(rt.package_download_concurrency().max(1))
+                .collect(),
+        );
+
+        let mut packages = Vec::with_capacity(results.len());
+        let mut errors = Vec::new();
+        for ((package_name, _), result) in specifiers.iter().zip(results) {
+            match result {
+                Ok(pkg) => packages.push(pkg),
+                Err(e) => errors.push(format!("package_name={package_name}, {e}")),
+            }
+        }
+        if !errors.is_empty() {
+            return Err(WasiStateCreationError::WasiIncludePackageError(
+                errors.join("; "),
+            ));
+        }
+
+        for pkg in &packages {
+            self.use_package(pkg)?;
         }
 
         Ok(())
@@ -1199,6 +1550,55 @@ impl WasiEnv {
         Ok(())
     }
 
+    /// Make commands from other packages available under different names.
+    ///
+    /// A placeholder is saved to `/bin/NAME` and `/usr/bin/NAME` so the alias
+    /// can be found when searching the `$PATH`, while the package itself is
+    /// only fetched when the command is first executed.
+    pub fn alias_commands<I>(&self, aliases: I) -> Result<(), WasiStateCreationError>
+    where
+        I: IntoIterator<Item = CommandAlias>,
+    {
+        let root_fs = &self.state.fs.root_fs;
+
+        for alias in aliases {
+            let name = alias.name.clone();
+            self.bin_factory.alias_command(alias)?;
+
+            let _ = root_fs.create_dir(Path::new("/bin"));
+            let _ = root_fs.create_dir(Path::new("/usr"));
+            let _ = root_fs.create_dir(Path::new("/usr/bin"));
+
+            for path in alias_paths(&name) {
+                let path = Path::new(path.as_str());
+                let result = match root_fs {
+                    WasiFsRoot::Sandbox(root_fs) => root_fs
+                        .new_open_options_ext()
+                        .insert_ro_file(path, Default::default()),
+                    WasiFsRoot::Backing(fs) => fs
+                        .new_open_options()
+                        .create(true)
+                        .write(true)
+                        .open(path)
+                        .map(|_| ()),
+                };
+
+                if let Err(err) = result {
+                    tracing::debug!(
+                        "failed to add command alias [{}] at [{}] - {}",
+                        name,
+                        path.display(),
+                        err
+                    );
+                }
+            }
+
+            tracing::debug!(command_name = name, "Injected a command alias");
+        }
+
+        Ok(())
+    }
+
     /// Cleans up all the open files (if this is the main thread)
     #[allow(clippy::await_holding_lock)]
     pub fn blocking_on_exit(&self, process_exit_code: Option<ExitCode>) {
@@ -1250,6 +1650,20 @@ impl WasiEnv {
 
                     // Now send a signal that the thread is terminated
                     process.signal_process(Signal::Sigquit);
+                } else {
+                    // The files stay open, but what is buffered for stdout
+                    // and stderr is still written out
+                    tokio::select! {
+                        _ = timeout => {
+                            tracing::debug!(
+                                "WasiEnv::cleanup has timed out after {CLEANUP_TIMEOUT:?}"
+                            );
+                        },
+                        _ = async {
+                            state.fs.flush(__WASI_STDOUT_FILENO).await.ok();
+                            state.fs.flush(__WASI_STDERR_FILENO).await.ok();
+                        } => { }
+                    }
                 }
 
                 // Terminate the process
@@ -1301,3 +1715,12 @@ impl WasiEnv {
         }
     }
 }
+
+/// Whether `sig` terminates the process when the guest hasn't registered a
+/// signal handler.
+fn terminates_by_default(sig: Signal) -> bool {
+    matches!(
+        sig,
+        Signal::Sigint | Signal::Sigquit | Signal::Sigkill | Signal::Sigabrt | Signal::Sigpipe
+    )
+}
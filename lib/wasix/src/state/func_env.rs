@@ -48,6 +48,52 @@ impl WasiFunctionEnv {
         call_initialize: bool,
         parent_linker_and_ctx: Option<(Linker, &mut FunctionEnvMut<WasiEnv>)>,
     ) -> Result<(Self, Store), WasiThreadError> {
+        let (memory, mut store) = Self::store_and_memory(&env, spawn_type)?;
+
+        let (_, ctx) = env.instantiate(
+            module,
+            &mut store,
+            memory,
+            update_layout,
+            call_initialize,
+            parent_linker_and_ctx,
+        )?;
+
+        // FIXME: shouldn't this happen _before_ instantiating, so the startup code in the instance
+        // has access to the globals?
+        // Set all the globals
+        if let Some(snapshot) = store_snapshot {
+            restore_store_snapshot(&mut store, &snapshot);
+        }
+
+        Ok((ctx, store))
+    }
+
+    /// Like [`WasiFunctionEnv::new_with_store()`] for the main thread of a
+    /// new process, but without blocking the executor while the module's
+    /// memory is initialized, see [`WasiEnv::instantiate_async()`].
+    pub(crate) async fn new_with_store_async(
+        module: Module,
+        env: WasiEnv,
+        spawn_type: SpawnMemoryTypeOrStore,
+        update_layout: bool,
+        call_initialize: bool,
+    ) -> Result<(Self, Store), WasiThreadError> {
+        let (memory, mut store) = Self::store_and_memory(&env, spawn_type)?;
+
+        let (_, ctx) = env
+            .instantiate_async(module, &mut store, memory, update_layout, call_initialize)
+            .await?;
+
+        Ok((ctx, store))
+    }
+
+    /// Creates the store an instance goes in, along with the memory it
+    /// imports, if any.
+    fn store_and_memory(
+        env: &WasiEnv,
+        spawn_type: SpawnMemoryTypeOrStore,
+    ) -> Result<(Option<Memory>, Store), WasiThreadError> {
         // Create a new store and put the memory object in it
         // (but only if it has imported memory)
         let (memory, store): (Option<wasmer::Memory>, Option<wasmer::Store>) = match spawn_type {
@@ -55,7 +101,7 @@ impl WasiFunctionEnv {
             SpawnMemoryTypeOrStore::Type(mut ty) => {
                 ty.shared = true;
 
-                let mut store = env.runtime.new_store();
+                let mut store = env.new_store();
 
                 // Note: If memory is shared, maximum needs to be set in the
                 // browser otherwise creation will fail.
@@ -74,25 +120,9 @@ impl WasiFunctionEnv {
             SpawnMemoryTypeOrStore::StoreAndMemory(s, m) => (m, Some(s)),
         };
 
-        let mut store = store.unwrap_or_else(|| env.runtime().new_store());
-
-        let (_, ctx) = env.instantiate(
-            module,
-            &mut store,
-            memory,
-            update_layout,
-            call_initialize,
-            parent_linker_and_ctx,
-        )?;
-
-        // FIXME: shouldn't this happen _before_ instantiating, so the startup code in the instance
-        // has access to the globals?
-        // Set all the globals
-        if let Some(snapshot) = store_snapshot {
-            restore_store_snapshot(&mut store, &snapshot);
-        }
+        let store = store.unwrap_or_else(|| env.new_store());
 
-        Ok((ctx, store))
+        Ok((memory, store))
     }
 
     /// Get an `Imports` for a specific version of WASI detected in the module.
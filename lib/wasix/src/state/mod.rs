@@ -20,6 +20,8 @@ mod env;
 mod func_env;
 mod handles;
 mod linker;
+mod secret_env;
+mod stdio;
 mod types;
 
 use std::{
@@ -41,11 +43,15 @@ pub use self::{
     builder::*,
     env::{WasiEnv, WasiEnvInit, WasiModuleInstanceHandles, WasiModuleTreeHandles},
     func_env::WasiFunctionEnv,
+    secret_env::SecretEnv,
+    stdio::{StdioBuffering, StdioPrefix},
     types::*,
 };
 pub use crate::fs::{InodeGuard, InodeWeakGuard};
 use crate::{
-    fs::{fs_error_into_wasi_err, WasiFs, WasiFsRoot, WasiInodes, WasiStateFileGuard},
+    bin_factory::PackageMetadata,
+    errno::fs_error_into_wasi_err,
+    fs::{ArchiveJobs, WasiFs, WasiFsRoot, WasiInodes, WasiStateFileGuard},
     syscalls::types::*,
     utils::WasiParkingLot,
 };
@@ -137,7 +143,20 @@ pub(crate) struct WasiState {
     pub clock_offset: Mutex<HashMap<Snapshot0Clockid, i64>>,
     pub args: Mutex<Vec<String>>,
     pub envs: Mutex<Vec<Vec<u8>>>,
+    /// Environment variables that aren't in `envs`, as they are only
+    /// visible to the processes they are granted to.
+    #[cfg_attr(feature = "enable-serde", serde(skip))]
+    pub secret_env: SecretEnv,
     pub signals: Mutex<HashMap<Signal, Disposition>>,
+    /// The package this process was started from, if any.
+    pub package: Mutex<Option<PackageMetadata>>,
+    /// The archive extractions started with `archive_extract`.
+    #[cfg_attr(feature = "enable-serde", serde(skip))]
+    pub(crate) archive_jobs: ArchiveJobs,
+    /// The keys and signatures of the wasi-crypto imports.
+    #[cfg(feature = "wasi-crypto")]
+    #[cfg_attr(feature = "enable-serde", serde(skip))]
+    pub(crate) crypto: crate::syscalls::wasi_crypto::CryptoState,
 
     // TODO: should not be here, since this requires active work to resolve.
     // State should only hold active runtime state that can be reproducibly re-created.
@@ -260,7 +279,12 @@ impl WasiState {
             clock_offset: Mutex::new(self.clock_offset.lock().unwrap().clone()),
             args: Mutex::new(self.args.lock().unwrap().clone()),
             envs: Mutex::new(self.envs.lock().unwrap().clone()),
+            secret_env: self.secret_env.clone(),
             signals: Mutex::new(self.signals.lock().unwrap().clone()),
+            package: Mutex::new(self.package.lock().unwrap().clone()),
+            archive_jobs: Default::default(),
+            #[cfg(feature = "wasi-crypto")]
+            crypto: Default::default(),
             preopen: self.preopen.clone(),
         }
     }
@@ -0,0 +1,177 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    sync::{Arc, Mutex},
+};
+
+use crate::WasiProcessId;
+
+/// What traces show instead of the value of a secret.
+pub(crate) const REDACTED: &str = "<redacted>";
+
+struct Secret {
+    value: Vec<u8>,
+    granted: HashSet<WasiProcessId>,
+}
+
+/// Environment variables only the processes they are granted to can read.
+///
+/// The secrets added with [`WasiEnvBuilder::secret_env()`] are granted to
+/// the process the builder starts. Unlike other environment variables,
+/// they aren't inherited: the processes it forks or spawns, and the
+/// programs it `exec`s, only see them once they are granted again with
+/// [`SecretEnv::grant()`]. Their values are redacted from traces, and
+/// left out of snapshots.
+///
+/// Clones share their secrets, so the embedder can keep one to grant and
+/// revoke secrets while the processes run.
+///
+/// [`WasiEnvBuilder::secret_env()`]: crate::WasiEnvBuilder::secret_env
+#[derive(Clone, Default)]
+pub struct SecretEnv {
+    secrets: Arc<Mutex<BTreeMap<String, Secret>>>,
+}
+
+impl SecretEnv {
+    /// Creates an empty set of secrets.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a secret, which isn't granted to any process yet. If the
+    /// secret exists already, its value is replaced for the processes
+    /// it is granted to.
+    pub fn insert(&self, key: impl Into<String>, value: impl AsRef<[u8]>) {
+        self.secrets
+            .lock()
+            .unwrap()
+            .entry(key.into())
+            .or_insert_with(|| Secret {
+                value: Vec::new(),
+                granted: HashSet::new(),
+            })
+            .value = value.as_ref().to_vec();
+    }
+
+    /// Lets the process `pid` read the secret `key` from its environment.
+    ///
+    /// Returns `false` if there is no such secret.
+    pub fn grant(&self, key: &str, pid: WasiProcessId) -> bool {
+        match self.secrets.lock().unwrap().get_mut(key) {
+            Some(secret) => {
+                secret.granted.insert(pid);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Stops the process `pid` from reading the secret `key`.
+    pub fn withdraw(&self, key: &str, pid: WasiProcessId) {
+        if let Some(secret) = self.secrets.lock().unwrap().get_mut(key) {
+            secret.granted.remove(&pid);
+        }
+    }
+
+    /// Removes the secret `key`, which the processes it was granted to no
+    /// longer see when they next read their environment.
+    ///
+    /// Returns `false` if there is no such secret.
+    pub fn revoke(&self, key: &str) -> bool {
+        self.secrets.lock().unwrap().remove(key).is_some()
+    }
+
+    /// Whether `key` is a secret.
+    pub fn contains(&self, key: &str) -> bool {
+        self.secrets.lock().unwrap().contains_key(key)
+    }
+
+    /// The names of the secrets.
+    pub fn keys(&self) -> Vec<String> {
+        self.secrets.lock().unwrap().keys().cloned().collect()
+    }
+
+    /// Grants every secret to the process `pid`.
+    pub(crate) fn grant_all(&self, pid: WasiProcessId) {
+        for secret in self.secrets.lock().unwrap().values_mut() {
+            secret.granted.insert(pid);
+        }
+    }
+
+    /// Withdraws every secret from the process `pid`.
+    pub(crate) fn withdraw_all(&self, pid: WasiProcessId) {
+        for secret in self.secrets.lock().unwrap().values_mut() {
+            secret.granted.remove(&pid);
+        }
+    }
+
+    /// The secrets granted to the process `pid`.
+    pub(crate) fn granted(&self, pid: WasiProcessId) -> Vec<(String, Vec<u8>)> {
+        self.secrets
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(_, secret)| secret.granted.contains(&pid))
+            .map(|(key, secret)| (key.clone(), secret.value.clone()))
+            .collect()
+    }
+
+    /// Formats `KEY=value` environment variables for traces, with the
+    /// values of secrets replaced by [`REDACTED`].
+    pub(crate) fn redact(&self, vars: &[Vec<u8>]) -> Vec<String> {
+        let secrets = self.secrets.lock().unwrap();
+        vars.iter()
+            .map(|var| {
+                let var = String::from_utf8_lossy(var);
+                match var.split_once('=') {
+                    Some((key, _)) if secrets.contains_key(key) => format!("{key}={REDACTED}"),
+                    _ => var.into_owned(),
+                }
+            })
+            .collect()
+    }
+}
+
+impl std::fmt::Debug for SecretEnv {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_set().entries(self.keys()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn secrets_are_only_visible_to_granted_processes() {
+        let secrets = SecretEnv::new();
+        let (parent, child) = (WasiProcessId::from(1), WasiProcessId::from(2));
+        secrets.insert("TOKEN", "hunter2");
+        secrets.grant_all(parent);
+
+        assert_eq!(
+            secrets.granted(parent),
+            vec![("TOKEN".to_string(), b"hunter2".to_vec())]
+        );
+        assert!(secrets.granted(child).is_empty());
+
+        assert!(secrets.grant("TOKEN", child));
+        assert!(!secrets.grant("MISSING", child));
+        secrets.withdraw_all(parent);
+        assert!(secrets.granted(parent).is_empty());
+        assert_eq!(secrets.granted(child).len(), 1);
+
+        assert!(secrets.revoke("TOKEN"));
+        assert!(secrets.granted(child).is_empty());
+        assert!(!secrets.contains("TOKEN"));
+    }
+
+    #[test]
+    fn secrets_are_redacted() {
+        let secrets = SecretEnv::new();
+        secrets.insert("TOKEN", "hunter2");
+
+        let vars = [b"PUBLIC=1".to_vec(), b"TOKEN=hunter2".to_vec()];
+        assert_eq!(secrets.redact(&vars), ["PUBLIC=1", "TOKEN=<redacted>"]);
+        assert_eq!(format!("{secrets:?}"), r#"{"TOKEN"}"#);
+    }
+}
@@ -0,0 +1,83 @@
+use virtual_fs::{BufferMode, BufferedWriteFile, FsError, NullFile};
+use wasmer_wasix_types::wasi::Fd as WasiFd;
+
+use crate::{
+    fs::WasiFs,
+    syscalls::types::{__WASI_STDERR_FILENO, __WASI_STDOUT_FILENO},
+    WasiProcessId,
+};
+
+/// What every line a process writes to stdout and stderr starts with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StdioPrefix {
+    /// `[pid 1] `
+    Pid,
+    /// The name of the program, as in `[python] `
+    Command,
+    /// Some text of the host's choosing
+    Text(String),
+}
+
+/// How what a process writes to stdout and stderr is buffered before it
+/// reaches the files the host installed for them.
+///
+/// By default nothing is buffered, so every write is passed on as it
+/// happens. Whatever is still buffered when the process exits, even if it
+/// traps, is flushed before the exit is reported. The processes it spawns
+/// inherit its streams, and therefore share their buffers and prefix.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StdioBuffering {
+    pub stdout: BufferMode,
+    pub stderr: BufferMode,
+    /// Starts every line with a tag, so the output of several processes
+    /// sharing a terminal or log can be told apart.
+    pub prefix: Option<StdioPrefix>,
+}
+
+impl StdioBuffering {
+    /// Line buffers stdout and stderr.
+    pub fn line_buffered() -> Self {
+        Self {
+            stdout: BufferMode::LINE,
+            stderr: BufferMode::LINE,
+            prefix: None,
+        }
+    }
+
+    pub fn with_prefix(mut self, prefix: StdioPrefix) -> Self {
+        self.prefix = Some(prefix);
+        self
+    }
+
+    /// Wraps the stdout and stderr of `fs` for the process `pid` running
+    /// `command`.
+    pub(crate) fn install(
+        &self,
+        fs: &WasiFs,
+        pid: WasiProcessId,
+        command: &str,
+    ) -> Result<(), FsError> {
+        if *self == Self::default() {
+            return Ok(());
+        }
+
+        let prefix = self.prefix.as_ref().map(|prefix| match prefix {
+            StdioPrefix::Pid => format!("[pid {pid}] "),
+            StdioPrefix::Command => format!("[{command}] "),
+            StdioPrefix::Text(text) => text.clone(),
+        });
+        let wrap = |fd: WasiFd, mode: BufferMode| -> Result<(), FsError> {
+            let Some(inner) = fs.swap_file(fd, Box::new(NullFile::default()))? else {
+                return Ok(());
+            };
+            let mut file = BufferedWriteFile::new(inner, mode);
+            if let Some(prefix) = &prefix {
+                file = file.with_prefix(prefix.as_str());
+            }
+            fs.swap_file(fd, Box::new(file))?;
+            Ok(())
+        };
+        wrap(__WASI_STDOUT_FILENO, self.stdout)?;
+        wrap(__WASI_STDERR_FILENO, self.stderr)
+    }
+}
@@ -8,7 +8,7 @@ use wasmer_wasix_types::wasi::{
 
 use crate::{
     mem_error_to_wasi,
-    os::task::thread::WasiThread,
+    os::task::{counters::Syscall, thread::WasiThread},
     state::{PollEventBuilder, PollEventSet},
     syscalls::types,
     syscalls::{self, handle_rewind},
@@ -22,6 +22,7 @@ pub fn fd_filestat_get(
     fd: Fd,
     buf: WasmPtr<Snapshot0Filestat, Memory32>,
 ) -> Errno {
+    ctx.data().process.count_syscall(Syscall::FdFilestatGet);
     let env = ctx.data();
     let memory = unsafe { env.memory_view(&ctx) };
     let result = syscalls::fd_filestat_get_old::<Memory32>(ctx.as_mut(), fd, buf);
@@ -39,6 +40,7 @@ pub fn path_filestat_get(
     path_len: u32,
     buf: WasmPtr<Snapshot0Filestat, Memory32>,
 ) -> Errno {
+    ctx.data().process.count_syscall(Syscall::PathFilestatGet);
     let env = ctx.data();
     let memory = unsafe { env.memory_view(&ctx) };
 
@@ -77,6 +79,7 @@ pub fn poll_oneoff<M: MemorySize>(
     nsubscriptions: u32,
     nevents: WasmPtr<u32, Memory32>,
 ) -> Result<Errno, WasiError> {
+    ctx.data().process.count_syscall(Syscall::PollOneoff);
     WasiEnv::do_pending_operations(&mut ctx)?;
 
     let env = ctx.data();
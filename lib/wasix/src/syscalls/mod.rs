@@ -18,6 +18,8 @@ pub mod windows;
 
 pub mod journal;
 pub mod wasi;
+#[cfg(feature = "wasi-crypto")]
+pub mod wasi_crypto;
 pub mod wasix;
 
 use bytes::{Buf, BufMut};
@@ -99,11 +101,14 @@ use self::{
     utils::WasiDummyWaker,
 };
 pub(crate) use crate::os::task::{
+    counters::Syscall,
     process::{WasiProcessId, WasiProcessWait},
     thread::{WasiThread, WasiThreadId},
 };
 pub(crate) use crate::{
     bin_factory::spawn_exec_module,
+    capabilities::FsAccess,
+    errno::io_error_into_wasi_err,
     import_object_for_all_wasi_versions, mem_error_to_wasi,
     net::{
         read_ip_port,
@@ -115,15 +120,12 @@ pub(crate) use crate::{
         self, iterate_poll_events, InodeGuard, InodeWeakGuard, PollEvent, PollEventBuilder,
         WasiFutex, WasiState,
     },
-    utils::{self, map_io_err},
-    Runtime, VirtualTaskManager, WasiEnv, WasiError, WasiFunctionEnv, WasiModuleTreeHandles,
+    utils, Runtime, VirtualTaskManager, WasiEnv, WasiError, WasiFunctionEnv, WasiModuleTreeHandles,
     WasiVFork,
 };
 use crate::{
-    fs::{
-        fs_error_into_wasi_err, virtual_file_type_to_wasi_file_type, Fd, FdInner, InodeVal, Kind,
-        MAX_SYMLINKS,
-    },
+    errno::fs_error_into_wasi_err,
+    fs::{virtual_file_type_to_wasi_file_type, Fd, FdInner, InodeVal, Kind, MAX_SYMLINKS},
     journal::{DynJournal, DynReadableJournal, DynWritableJournal, JournalEffector},
     os::task::{
         process::{MaybeCheckpointResult, WasiProcessCheckpoint},
@@ -134,7 +136,7 @@ use crate::{
     DeepSleepWork, RewindPostProcess, RewindState, RewindStateOption, SpawnError, WasiInodes,
     WasiResult, WasiRuntimeError,
 };
-pub(crate) use crate::{net::net_error_into_wasi_err, utils::WasiParkingLot};
+pub(crate) use crate::{errno::net_error_into_wasi_err, utils::WasiParkingLot};
 
 pub(crate) fn to_offset<M: MemorySize>(offset: usize) -> Result<M::Offset, Errno> {
     let ret: M::Offset = offset.try_into().map_err(|_| Errno::Inval)?;
@@ -158,7 +160,9 @@ pub(crate) fn write_bytes_inner<T: Write, M: MemorySize>(
             .slice(memory, iov_inner.buf_len)
             .map_err(mem_error_to_wasi)?;
         let bytes = bytes.read_to_vec().map_err(mem_error_to_wasi)?;
-        write_loc.write_all(&bytes).map_err(map_io_err)?;
+        write_loc
+            .write_all(&bytes)
+            .map_err(io_error_into_wasi_err)?;
 
         bytes_written += from_offset::<M>(iov_inner.buf_len)?;
     }
@@ -223,7 +227,7 @@ pub(crate) fn read_bytes<T: Read, M: MemorySize>(
             .map_err(mem_error_to_wasi)?;
 
         let to_read = buf.len();
-        let has_read = reader.read(buf.as_mut()).map_err(map_io_err)?;
+        let has_read = reader.read(buf.as_mut()).map_err(io_error_into_wasi_err)?;
 
         bytes_read += has_read;
         if has_read != to_read {
@@ -233,19 +237,15 @@ pub(crate) fn read_bytes<T: Read, M: MemorySize>(
     Ok(bytes_read)
 }
 
-// TODO: remove allow once inodes are refactored (see comments on [`WasiState`])
-/// Writes data to the stderr
-#[allow(clippy::await_holding_lock)]
+/// Writes data to the stderr, wherever the process redirected it
 pub unsafe fn stderr_write<'a>(
     ctx: &FunctionEnvMut<'_, WasiEnv>,
     buf: &[u8],
 ) -> LocalBoxFuture<'a, Result<(), Errno>> {
-    let env = ctx.data();
-    let (memory, state, inodes) = env.get_memory_and_wasi_state_and_inodes(ctx, 0);
+    let state = ctx.data().state.clone();
 
     let buf = buf.to_vec();
-    let mut stderr = WasiInodes::stderr_mut(&state.fs.fd_map).map_err(fs_error_into_wasi_err);
-    Box::pin(async move { stderr?.write_all(&buf).await.map_err(map_io_err) })
+    Box::pin(async move { state.fs.write_all(__WASI_STDERR_FILENO, &buf).await })
 }
 
 fn block_on_with_timeout<T, Fut>(
@@ -300,6 +300,9 @@ where
 /// thus allowed for asynchronous operations to execute. It has built in functionality
 /// to (optionally) timeout the IO, force exit the process, callback signals and pump
 /// synchronous IO engine
+///
+/// If a signal interrupts the work (see [`interruptible()`]), its handler runs
+/// after the work has been dropped and [`Errno::Intr`] is returned.
 pub(crate) fn __asyncify<T, Fut>(
     ctx: &mut FunctionEnvMut<'_, WasiEnv>,
     timeout: Option<Duration>,
@@ -309,45 +312,63 @@ where
     T: 'static,
     Fut: std::future::Future<Output = Result<T, Errno>>,
 {
-    let mut env = ctx.data();
+    let env = ctx.data();
 
     // Check if we need to exit the asynchronous loop
     if let Some(exit_code) = env.should_exit() {
         return Err(WasiError::Exit(exit_code));
     }
 
-    // This poller will process any signals when the main working function is idle
-    struct SignalPoller<'a, 'b, Fut, T>
-    where
-        Fut: Future<Output = Result<T, Errno>>,
-    {
-        ctx: &'a mut FunctionEnvMut<'b, WasiEnv>,
-        pinned_work: Pin<Box<Fut>>,
-    }
-    impl<Fut, T> Future for SignalPoller<'_, '_, Fut, T>
-    where
-        Fut: Future<Output = Result<T, Errno>>,
-    {
-        type Output = Result<Fut::Output, WasiError>;
-        fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
-            if let Poll::Ready(res) = Pin::new(&mut self.pinned_work).poll(cx) {
-                return Poll::Ready(Ok(res));
-            }
-            if let Some(signals) = self.ctx.data().thread.pop_signals_or_subscribe(cx.waker()) {
-                if let Err(err) = WasiEnv::process_signals_internal(self.ctx, signals) {
-                    return Poll::Ready(Err(err));
-                }
-                return Poll::Ready(Ok(Err(Errno::Intr)));
-            }
-            Poll::Pending
-        }
-    }
-
     // Block on the work
-    let mut pinned_work = Box::pin(work);
     let tasks = env.tasks().clone();
-    let poller = SignalPoller { ctx, pinned_work };
-    block_on_with_timeout(&tasks, timeout, poller)
+    let thread = env.thread.clone();
+    let work = async { Ok(interruptible(env, work).await) };
+
+    // The thread isn't running while it waits on the runtime
+    thread.pause_cpu_clock();
+    let res = block_on_with_timeout(&tasks, timeout, work);
+    thread.resume_cpu_clock();
+
+    let res = res?;
+    handle_interrupt(ctx, &res)?;
+    Ok(res)
+}
+
+/// Runs `work` until it completes or until a signal that interrupts blocking
+/// syscalls is delivered to the current thread (see
+/// [`WasiEnv::has_interrupting_signal_or_subscribe()`]), in which case the
+/// work is dropped and [`Errno::Intr`] is returned.
+///
+/// The signal itself stays queued so that its handler runs the usual way,
+/// e.g. through [`handle_interrupt()`]. The work must not have committed
+/// anything it can't report when it is dropped at an `.await`.
+pub(crate) async fn interruptible<T, Fut>(env: &WasiEnv, work: Fut) -> Result<T, Errno>
+where
+    Fut: Future<Output = Result<T, Errno>>,
+{
+    let mut work = std::pin::pin!(work);
+    futures::future::poll_fn(|cx| {
+        if let Poll::Ready(res) = work.as_mut().poll(cx) {
+            return Poll::Ready(res);
+        }
+        if env.has_interrupting_signal_or_subscribe(cx.waker()) {
+            return Poll::Ready(Err(Errno::Intr));
+        }
+        Poll::Pending
+    })
+    .await
+}
+
+/// Runs the handlers of the signals that interrupted a blocking syscall, so
+/// they have run by the time the guest sees [`Errno::Intr`].
+pub(crate) fn handle_interrupt<T>(
+    ctx: &mut FunctionEnvMut<'_, WasiEnv>,
+    res: &Result<T, Errno>,
+) -> Result<(), WasiError> {
+    if let Err(Errno::Intr) = res {
+        _ = WasiEnv::process_signals_and_exit(ctx)?;
+    }
+    Ok(())
 }
 
 /// Future that will be polled by asyncify methods
@@ -494,6 +515,7 @@ where
 
     // Define the work
     let tasks = ctx.data().tasks().clone();
+    let thread = ctx.data().thread.clone();
     let work = async move {
         let env = ctx.data();
 
@@ -549,8 +571,14 @@ where
     };
 
     // Block until the work is finished or until we
-    // unload the thread using asyncify
-    InlineWaker::block_on(work)
+    // unload the thread using asyncify (in which case it
+    // resumes its CPU clock when it is rewound)
+    thread.pause_cpu_clock();
+    let res = InlineWaker::block_on(work);
+    if let Ok(AsyncifyAction::Finish(..)) = &res {
+        thread.resume_cpu_clock();
+    }
+    res
 }
 
 /// Asyncify takes the current thread and blocks on the async runtime associated with it
@@ -570,7 +598,10 @@ where
 
     // Block until the work is finished or until we
     // unload the thread using asyncify
-    Ok(InlineWaker::block_on(work))
+    env.thread.pause_cpu_clock();
+    let res = InlineWaker::block_on(work);
+    env.thread.resume_cpu_clock();
+    Ok(res)
 }
 
 // This should be compiled away, it will simply wait forever however its never
@@ -621,9 +652,11 @@ where
         }
     };
 
-    // Block until the work is finished or until we
-    // unload the thread using asyncify
-    InlineWaker::block_on(work)
+    // Block until the work is finished or a signal interrupts it
+    env.thread.pause_cpu_clock();
+    let res = InlineWaker::block_on(interruptible(env, work));
+    env.thread.resume_cpu_clock();
+    res
 }
 
 /// Performs mutable work on a socket under an asynchronous runtime with
@@ -1263,6 +1296,9 @@ pub fn rewind_ext<M: MemorySize>(
     store_data: Bytes,
     rewind_result: RewindResultType,
 ) -> Errno {
+    // The thread has been scheduled again (e.g. after a deep sleep)
+    ctx.data().thread.resume_cpu_clock();
+
     // Store the memory stack so that it can be restored later
     ctx.data_mut().thread.set_rewind(RewindResult {
         memory_stack,
@@ -1473,6 +1509,9 @@ pub(crate) fn _prepare_wasi(
     envs: Option<Vec<(String, String)>>,
     signals: Option<Vec<SignalDisposition>>,
 ) {
+    // The new program only sees the secrets that are granted to it again
+    wasi_env.state.secret_env.withdraw_all(wasi_env.pid());
+
     // Swap out the arguments with the new ones
     if let Some(args) = args {
         *wasi_env.state.args.lock().unwrap() = args;
@@ -1522,7 +1561,9 @@ pub(crate) fn _prepare_wasi(
 pub(crate) fn conv_spawn_err_to_errno(err: &SpawnError) -> Errno {
     match err {
         SpawnError::AccessDenied => Errno::Access,
-        SpawnError::Unsupported => Errno::Noexec,
+        SpawnError::Unsupported | SpawnError::UnsupportedRunner { .. } => Errno::Noexec,
+        SpawnError::InterpreterLoop { .. } => Errno::Loop,
+        SpawnError::Cancelled => Errno::Canceled,
         _ if err.is_not_found() => Errno::Noent,
         _ => Errno::Inval,
     }
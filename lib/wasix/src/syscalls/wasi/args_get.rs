@@ -16,6 +16,7 @@ pub fn args_get<M: MemorySize>(
     argv: WasmPtr<WasmPtr<u8, M>, M>,
     argv_buf: WasmPtr<u8, M>,
 ) -> Errno {
+    ctx.data().process.count_syscall(Syscall::ArgsGet);
     let env = ctx.data();
     let (memory, mut state) = unsafe { env.get_memory_and_wasi_state(&ctx, 0) };
 
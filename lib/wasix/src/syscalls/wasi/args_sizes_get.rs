@@ -14,6 +14,7 @@ pub fn args_sizes_get<M: MemorySize>(
     argc: WasmPtr<M::Offset, M>,
     argv_buf_size: WasmPtr<M::Offset, M>,
 ) -> Errno {
+    ctx.data().process.count_syscall(Syscall::ArgsSizesGet);
     let env = ctx.data();
     let (memory, mut state) = unsafe { env.get_memory_and_wasi_state(&ctx, 0) };
 
@@ -9,16 +9,26 @@ use crate::syscalls::*;
 /// Output:
 /// - `Timestamp *resolution`
 ///     The resolution of the clock in nanoseconds
+///
+/// The CPU time clocks are accounted using the host's monotonic clock,
+/// hence they share its resolution.
 #[instrument(level = "trace", skip_all, ret)]
 pub fn clock_res_get<M: MemorySize>(
     mut ctx: FunctionEnvMut<'_, WasiEnv>,
     clock_id: Snapshot0Clockid,
     resolution: WasmPtr<Timestamp, M>,
 ) -> Errno {
+    ctx.data().process.count_syscall(Syscall::ClockResGet);
     let env = ctx.data();
     let memory = unsafe { env.memory_view(&ctx) };
 
     let out_addr = resolution.deref(&memory);
+    let clock_id = match clock_id {
+        Snapshot0Clockid::ThreadCputimeId | Snapshot0Clockid::ProcessCputimeId => {
+            Snapshot0Clockid::Monotonic
+        }
+        clock_id => clock_id,
+    };
     let t_out = wasi_try!(platform_clock_res_get(clock_id, out_addr));
     wasi_try_mem!(resolution.write(&memory, t_out as Timestamp));
     Errno::Success
@@ -18,6 +18,10 @@ use crate::syscalls::*;
 ///
 /// - `Timestamp *time`
 ///     The value of the clock in nanoseconds
+///
+/// The CPU time clocks report the execution time of the calling thread
+/// (or of all the threads of its process) as accounted by the runtime,
+/// see [`WasiThread::cpu_time`].
 #[cfg_attr(
     feature = "extra-logging",
     tracing::instrument(level = "trace", skip_all, ret)
@@ -28,6 +32,7 @@ pub fn clock_time_get<M: MemorySize>(
     precision: Timestamp,
     time: WasmPtr<Timestamp, M>,
 ) -> Result<Errno, WasiError> {
+    ctx.data().process.count_syscall(Syscall::ClockTimeGet);
     WasiEnv::do_pending_operations(&mut ctx)?;
 
     ctx = wasi_try_ok!(maybe_backoff::<M>(ctx)?);
@@ -35,7 +40,11 @@ pub fn clock_time_get<M: MemorySize>(
     let env = ctx.data();
     let memory = unsafe { env.memory_view(&ctx) };
 
-    let mut t_out = wasi_try_ok!(platform_clock_time_get(clock_id, precision));
+    let mut t_out = match clock_id {
+        Snapshot0Clockid::ThreadCputimeId => env.thread.cpu_time().as_nanos() as i64,
+        Snapshot0Clockid::ProcessCputimeId => env.process.cpu_time().as_nanos() as i64,
+        _ => wasi_try_ok!(platform_clock_time_get(clock_id, precision)),
+    };
     {
         let guard = env.state.clock_offset.lock().unwrap();
         if let Some(offset) = guard.get(&clock_id) {
@@ -14,6 +14,7 @@ pub fn clock_time_set(
     clock_id: Snapshot0Clockid,
     time: Timestamp,
 ) -> Result<Errno, WasiError> {
+    ctx.data().process.count_syscall(Syscall::ClockTimeSet);
     WasiEnv::do_pending_operations(&mut ctx)?;
 
     let ret = clock_time_set_internal(&mut ctx, clock_id, time);
@@ -15,6 +15,7 @@ pub fn environ_get<M: MemorySize>(
     environ: WasmPtr<WasmPtr<u8, M>, M>,
     environ_buf: WasmPtr<u8, M>,
 ) -> Result<Errno, WasiError> {
+    ctx.data().process.count_syscall(Syscall::EnvironGet);
     ctx = wasi_try_ok!(maybe_snapshot_once::<M>(
         ctx,
         SnapshotTrigger::FirstEnviron
@@ -23,6 +24,7 @@ pub fn environ_get<M: MemorySize>(
     let env = ctx.data();
     let (memory, mut state) = unsafe { env.get_memory_and_wasi_state(&ctx, 0) };
 
-    let envs = state.envs.lock().unwrap();
+    let envs = env.environ();
+    trace!(environ = ?state.secret_env.redact(&envs));
     Ok(write_buffer_array(&memory, &envs, environ, environ_buf))
 }
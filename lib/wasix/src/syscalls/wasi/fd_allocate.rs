@@ -17,6 +17,7 @@ pub fn fd_allocate(
     offset: Filesize,
     len: Filesize,
 ) -> Result<Errno, WasiError> {
+    ctx.data().process.count_syscall(Syscall::FdAllocate);
     WasiEnv::do_pending_operations(&mut ctx)?;
 
     wasi_try_ok!(fd_allocate_internal(&mut ctx, fd, offset, len));
@@ -14,6 +14,7 @@ use crate::syscalls::*;
 ///     If `fd` is invalid or not open
 #[instrument(level = "trace", skip_all, fields(pid = ctx.data().process.pid().raw(), %fd), ret)]
 pub fn fd_close(mut ctx: FunctionEnvMut<'_, WasiEnv>, fd: WasiFd) -> Result<Errno, WasiError> {
+    ctx.data().process.count_syscall(Syscall::FdClose);
     WasiEnv::do_pending_operations(&mut ctx)?;
 
     let env = ctx.data();
@@ -8,6 +8,7 @@ use crate::syscalls::*;
 ///     The file descriptor to sync
 #[instrument(level = "trace", skip_all, fields(%fd), ret)]
 pub fn fd_datasync(mut ctx: FunctionEnvMut<'_, WasiEnv>, fd: WasiFd) -> Result<Errno, WasiError> {
+    ctx.data().process.count_syscall(Syscall::FdDatasync);
     WasiEnv::do_pending_operations(&mut ctx)?;
 
     let env = ctx.data();
@@ -1,5 +1,5 @@
 use super::*;
-use crate::{fs::NotificationInner, syscalls::*};
+use crate::syscalls::*;
 
 /// ### `fd_event()`
 /// Creates a file handle for event notifications
@@ -10,6 +10,7 @@ pub fn fd_event<M: MemorySize>(
     flags: EventFdFlags,
     ret_fd: WasmPtr<WasiFd, M>,
 ) -> Result<Errno, WasiError> {
+    ctx.data().process.count_syscall(Syscall::FdEvent);
     WasiEnv::do_pending_operations(&mut ctx)?;
 
     let fd = wasi_try_ok!(fd_event_internal(&mut ctx, initial_val, flags, None)?);
@@ -37,44 +38,11 @@ pub fn fd_event_internal(
     with_fd: Option<WasiFd>,
 ) -> Result<Result<WasiFd, Errno>, WasiError> {
     let env = ctx.data();
-    let (memory, state, mut inodes) = unsafe { env.get_memory_and_wasi_state_and_inodes(&ctx, 0) };
+    let (state, inodes) = env.get_wasi_state_and_inodes();
 
-    let is_semaphore = flags & EVENT_FD_FLAGS_SEMAPHORE != 0;
-    let kind = Kind::EventNotifications {
-        inner: Arc::new(NotificationInner::new(initial_val, is_semaphore)),
-    };
-
-    let inode =
-        state
-            .fs
-            .create_inode_with_default_stat(inodes, kind, false, "event".to_string().into());
-    let rights = Rights::FD_READ
-        | Rights::FD_WRITE
-        | Rights::POLL_FD_READWRITE
-        | Rights::FD_FDSTAT_SET_FLAGS;
-    let fd = wasi_try_ok_ok!(if let Some(fd) = with_fd {
-        state
-            .fs
-            .with_fd(
-                rights,
-                rights,
-                Fdflags::empty(),
-                Fdflagsext::empty(),
-                0,
-                inode,
-                fd,
-            )
-            .map(|_| fd)
-    } else {
-        state.fs.create_fd(
-            rights,
-            rights,
-            Fdflags::empty(),
-            Fdflagsext::empty(),
-            0,
-            inode,
-        )
-    });
+    let (fd, _) = wasi_try_ok_ok!(state
+        .fs
+        .create_event_fd(inodes, initial_val, flags, with_fd));
 
     Ok(Ok(fd))
 }
@@ -15,6 +15,7 @@ pub fn fd_fdstat_get<M: MemorySize>(
     fd: WasiFd,
     buf_ptr: WasmPtr<Fdstat, M>,
 ) -> Result<Errno, WasiError> {
+    ctx.data().process.count_syscall(Syscall::FdFdstatGet);
     WasiEnv::do_pending_operations(&mut ctx)?;
 
     let env = ctx.data();
@@ -14,6 +14,7 @@ pub fn fd_fdstat_set_flags(
     fd: WasiFd,
     flags: Fdflags,
 ) -> Result<Errno, WasiError> {
+    ctx.data().process.count_syscall(Syscall::FdFdstatSetFlags);
     WasiEnv::do_pending_operations(&mut ctx)?;
 
     let ret = fd_fdstat_set_flags_internal(&mut ctx, fd, flags)?;
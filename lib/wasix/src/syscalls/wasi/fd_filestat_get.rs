@@ -18,6 +18,7 @@ pub fn fd_filestat_get<M: MemorySize>(
     fd: WasiFd,
     buf: WasmPtr<Filestat, M>,
 ) -> Result<Errno, WasiError> {
+    ctx.data().process.count_syscall(Syscall::FdFilestatGet);
     WasiEnv::do_pending_operations(&mut ctx)?;
 
     let stat = wasi_try_ok!(fd_filestat_get_internal(&mut ctx, fd));
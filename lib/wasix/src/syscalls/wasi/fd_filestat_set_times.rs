@@ -20,6 +20,9 @@ pub fn fd_filestat_set_times(
     st_mtim: Timestamp,
     fst_flags: Fstflags,
 ) -> Result<Errno, WasiError> {
+    ctx.data()
+        .process
+        .count_syscall(Syscall::FdFilestatSetTimes);
     WasiEnv::do_pending_operations(&mut ctx)?;
 
     wasi_try_ok!(fd_filestat_set_times_internal(
@@ -8,6 +8,7 @@ pub fn fd_prestat_dir_name<M: MemorySize>(
     path: WasmPtr<u8, M>,
     path_len: M::Offset,
 ) -> Errno {
+    ctx.data().process.count_syscall(Syscall::FdPrestatDirName);
     let env = ctx.data();
     let (memory, mut state) = unsafe { env.get_memory_and_wasi_state(&ctx, 0) };
     let path_chars = wasi_try_mem!(path.slice(&memory, path_len));
@@ -15,6 +15,7 @@ pub fn fd_prestat_get<M: MemorySize>(
     fd: WasiFd,
     buf: WasmPtr<Prestat, M>,
 ) -> Errno {
+    ctx.data().process.count_syscall(Syscall::FdPrestatGet);
     let env = ctx.data();
     let (memory, mut state) = unsafe { env.get_memory_and_wasi_state(&ctx, 0) };
 
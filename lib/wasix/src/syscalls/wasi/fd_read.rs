@@ -32,6 +32,7 @@ pub fn fd_read<M: MemorySize>(
     iovs_len: M::Offset,
     nread: WasmPtr<M::Offset, M>,
 ) -> Result<Errno, WasiError> {
+    ctx.data().process.count_syscall(Syscall::FdRead);
     WasiEnv::do_pending_operations(&mut ctx)?;
 
     let pid = ctx.data().pid();
@@ -79,6 +80,7 @@ pub fn fd_pread<M: MemorySize>(
     offset: Filesize,
     nread: WasmPtr<M::Offset, M>,
 ) -> Result<Errno, WasiError> {
+    ctx.data().process.count_syscall(Syscall::FdPread);
     let pid = ctx.data().pid();
     let tid = ctx.data().tid();
 
@@ -170,7 +172,7 @@ pub(crate) fn fd_read_internal<M: MemorySize>(
                                     handle
                                         .seek(std::io::SeekFrom::Start(offset as u64))
                                         .await
-                                        .map_err(map_io_err)?;
+                                        .map_err(io_error_into_wasi_err)?;
                                 }
 
                                 let mut total_read = 0usize;
@@ -25,6 +25,7 @@ pub fn fd_readdir<M: MemorySize>(
     cookie: Dircookie,
     bufused: WasmPtr<M::Offset, M>,
 ) -> Result<Errno, WasiError> {
+    ctx.data().process.count_syscall(Syscall::FdReaddir);
     WasiEnv::do_pending_operations(&mut ctx)?;
 
     let env = ctx.data();
@@ -14,6 +14,7 @@ pub fn fd_renumber(
     from: WasiFd,
     to: WasiFd,
 ) -> Result<Errno, WasiError> {
+    ctx.data().process.count_syscall(Syscall::FdRenumber);
     WasiEnv::do_pending_operations(&mut ctx)?;
 
     let ret = fd_renumber_internal(&mut ctx, from, to)?;
@@ -37,11 +38,14 @@ pub(crate) fn fd_renumber_internal(
     from: WasiFd,
     to: WasiFd,
 ) -> Result<Errno, WasiError> {
+    let env = ctx.data();
+    let (_, mut state) = unsafe { env.get_memory_and_wasi_state(&ctx, 0) };
+
+    // Nothing may happen to `to` unless `from` is valid
+    wasi_try_ok!(state.fs.get_fd(from));
     if from == to {
         return Ok(Errno::Success);
     }
-    let env = ctx.data();
-    let (_, mut state) = unsafe { env.get_memory_and_wasi_state(&ctx, 0) };
 
     if let Ok(fd) = state.fs.get_fd(to) {
         if !fd.is_stdio && fd.inode.is_preopened {
@@ -55,15 +59,15 @@ pub(crate) fn fd_renumber_internal(
                 This will likely break stuff."
             );
         }
-        match __asyncify_light(env, None, state.fs.flush(to))? {
-            Ok(_) | Err(Errno::Isdir) | Err(Errno::Io) | Err(Errno::Access) => {}
-            Err(e) => {
-                return Ok(e);
-            }
+        // Closing a file doesn't fail because it couldn't be flushed, and
+        // neither does renumbering onto it
+        if let Err(err) = __asyncify_light(env, None, state.fs.flush(to))? {
+            trace!(%to, "failed to flush the file descriptor being replaced - {}", err);
         }
-        wasi_try_ok!(state.fs.close_fd(to));
     }
 
+    // Closing `to` and installing the copy of `from` happen under the same
+    // lock, so nothing else can observe (or take) `to` in between
     let mut fd_map = state.fs.fd_map.write().unwrap();
     let fd_entry = wasi_try_ok!(fd_map.get(from).ok_or(Errno::Badf));
 
@@ -83,9 +87,12 @@ pub(crate) fn fd_renumber_internal(
         ..*fd_entry
     };
 
-    // Exclusive insert because we expect `to` to be empty after closing it above
+    if let Some(old) = fd_map.remove(to) {
+        let inode = old.inode.ino().as_u64();
+        trace!(%to, %inode, "closing the file descriptor replaced by fd_renumber");
+    }
     if !fd_map.insert(true, to, new_fd_entry) {
-        panic!("Internal error: expected FD {to} to be free after closing in fd_renumber");
+        panic!("Internal error: expected FD {to} to be free after closing it in fd_renumber");
     }
 
     Ok(Errno::Success)
@@ -21,6 +21,7 @@ pub fn fd_seek<M: MemorySize>(
     whence: Whence,
     newoffset: WasmPtr<Filesize, M>,
 ) -> Result<Errno, WasiError> {
+    ctx.data().process.count_syscall(Syscall::FdSeek);
     WasiEnv::do_pending_operations(&mut ctx)?;
 
     let new_offset = wasi_try_ok!(fd_seek_internal(&mut ctx, fd, offset, whence)?);
@@ -102,7 +103,7 @@ pub(crate) fn fd_seek_internal(
                             let end = handle
                                 .seek(SeekFrom::End(offset))
                                 .await
-                                .map_err(map_io_err)?;
+                                .map_err(io_error_into_wasi_err)?;
 
                             // TODO: handle case if fd_entry.offset uses 64 bits of a u64
                             drop(handle);
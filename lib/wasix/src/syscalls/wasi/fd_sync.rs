@@ -12,6 +12,7 @@ use crate::syscalls::*;
 /// - `Errno::Notcapable`
 #[instrument(level = "trace", skip_all, fields(%fd), ret)]
 pub fn fd_sync(mut ctx: FunctionEnvMut<'_, WasiEnv>, fd: WasiFd) -> Result<Errno, WasiError> {
+    ctx.data().process.count_syscall(Syscall::FdSync);
     WasiEnv::do_pending_operations(&mut ctx)?;
 
     let env = ctx.data();
@@ -38,7 +39,7 @@ pub fn fd_sync(mut ctx: FunctionEnvMut<'_, WasiEnv>, fd: WasiFd) -> Result<Errno
                             // TODO: remove allow once inodes are refactored (see comments on [`WasiState`])
                             #[allow(clippy::await_holding_lock)]
                             let mut handle = handle.write().unwrap();
-                            handle.flush().await.map_err(map_io_err)?;
+                            handle.flush().await.map_err(io_error_into_wasi_err)?;
                             Ok(handle.size())
                         })?)
                     };
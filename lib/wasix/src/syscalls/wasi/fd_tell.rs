@@ -15,6 +15,7 @@ pub fn fd_tell<M: MemorySize>(
     fd: WasiFd,
     offset: WasmPtr<Filesize, M>,
 ) -> Result<Errno, WasiError> {
+    ctx.data().process.count_syscall(Syscall::FdTell);
     WasiEnv::do_pending_operations(&mut ctx)?;
 
     let env = ctx.data();
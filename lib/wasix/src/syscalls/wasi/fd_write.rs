@@ -30,6 +30,7 @@ pub fn fd_write<M: MemorySize>(
     iovs_len: M::Offset,
     nwritten: WasmPtr<M::Offset, M>,
 ) -> Result<Errno, WasiError> {
+    ctx.data().process.count_syscall(Syscall::FdWrite);
     WasiEnv::do_pending_operations(&mut ctx)?;
 
     let env = ctx.data();
@@ -86,6 +87,7 @@ pub fn fd_pwrite<M: MemorySize>(
     offset: Filesize,
     nwritten: WasmPtr<M::Offset, M>,
 ) -> Result<Errno, WasiError> {
+    ctx.data().process.count_syscall(Syscall::FdPwrite);
     WasiEnv::do_pending_operations(&mut ctx)?;
 
     let enable_snapshot_capture = ctx.data().enable_journal;
@@ -119,6 +121,154 @@ pub(crate) enum FdWriteSource<'a, M: MemorySize> {
     Buffer(Cow<'a, [u8]>),
 }
 
+/// Calls `f` with the buffers the iovecs point to, borrowed from guest memory.
+fn with_iovs<M: MemorySize, R>(
+    memory: &MemoryView,
+    iovs: WasmPtr<__wasi_ciovec_t<M>, M>,
+    iovs_len: M::Offset,
+    f: impl FnOnce(&[std::io::IoSlice<'_>]) -> R,
+) -> Result<R, Errno> {
+    let iovs_arr = iovs.slice(memory, iovs_len).map_err(mem_error_to_wasi)?;
+    let iovs_arr = iovs_arr.access().map_err(mem_error_to_wasi)?;
+    let bufs = iovs_arr
+        .iter()
+        .map(|iovs| {
+            WasmPtr::<u8, M>::new(iovs.buf)
+                .slice(memory, iovs.buf_len)
+                .and_then(|buf| buf.access())
+                .map_err(mem_error_to_wasi)
+        })
+        .collect::<Result<Vec<_>, Errno>>()?;
+    let slices = bufs
+        .iter()
+        .map(|buf| std::io::IoSlice::new(buf.as_ref()))
+        .collect::<Vec<_>>();
+    Ok(f(&slices))
+}
+
+/// Writes the iovecs to a file as slices that borrow guest memory, so each
+/// byte is only copied once. Returns [`None`] when the file needs to go
+/// through the asynchronous path instead (see [`VirtualFile::write_vectored_at`]).
+fn write_iovs_at<M: MemorySize>(
+    file: &mut (dyn VirtualFile + Send + Sync),
+    offset: u64,
+    memory: &MemoryView,
+    iovs: WasmPtr<__wasi_ciovec_t<M>, M>,
+    iovs_len: M::Offset,
+) -> Result<Option<usize>, Errno> {
+    with_iovs::<M, _>(memory, iovs, iovs_len, |slices| {
+        file.write_vectored_at(offset, slices)
+    })?
+    .transpose()
+    .map_err(io_error_into_wasi_err)
+}
+
+/// Appends the data to a file in a single step (see
+/// [`VirtualFile::append_vectored`]) and returns the offset it was written
+/// at along with its length, or [`None`] when the file can't append
+/// atomically.
+fn append_at_end<M: MemorySize>(
+    file: &mut (dyn VirtualFile + Send + Sync),
+    memory: &MemoryView,
+    data: &FdWriteSource<'_, M>,
+) -> Result<Option<(u64, usize)>, Errno> {
+    let (appended, len) = match data {
+        FdWriteSource::Iovs { iovs, iovs_len } => {
+            with_iovs::<M, _>(memory, *iovs, *iovs_len, |slices| {
+                let len = slices.iter().map(|buf| buf.len()).sum();
+                (file.append_vectored(slices), len)
+            })?
+        }
+        FdWriteSource::Buffer(data) => (
+            file.append_vectored(&[std::io::IoSlice::new(data)]),
+            data.len(),
+        ),
+    };
+    let start = appended.transpose().map_err(io_error_into_wasi_err)?;
+    Ok(start.map(|start| (start, len)))
+}
+
+/// Writes the data with `poll_write`, which only has to lock the file while
+/// it's being polled, so that waiting for a reader to make room (e.g. in a
+/// bounded pipe) doesn't keep anything else from using it. When the file
+/// isn't ready and `nonblocking` is set, this fails with [`Errno::Again`],
+/// or returns early if something was already written.
+async fn write_polled<M: MemorySize>(
+    memory: &MemoryView<'_>,
+    data: &FdWriteSource<'_, M>,
+    nonblocking: bool,
+    mut poll_write: impl FnMut(&mut Context<'_>, &[u8]) -> Poll<std::io::Result<usize>>,
+) -> Result<usize, Errno> {
+    let mut write = |cx: &mut Context<'_>, buf: &[u8]| match poll_write(cx, buf) {
+        Poll::Pending if nonblocking => Poll::Ready(Err(std::io::ErrorKind::WouldBlock.into())),
+        res => res,
+    };
+
+    let mut written = 0usize;
+    match data {
+        FdWriteSource::Iovs { iovs, iovs_len } => {
+            let iovs_arr = iovs.slice(memory, *iovs_len).map_err(mem_error_to_wasi)?;
+            let iovs_arr = iovs_arr.access().map_err(mem_error_to_wasi)?;
+            for iovs in iovs_arr.iter() {
+                let buf = WasmPtr::<u8, M>::new(iovs.buf)
+                    .slice(memory, iovs.buf_len)
+                    .map_err(mem_error_to_wasi)?
+                    .access()
+                    .map_err(mem_error_to_wasi)?;
+                let local_written =
+                    match futures::future::poll_fn(|cx| write(cx, buf.as_ref())).await {
+                        Ok(w) => w,
+                        Err(err) if err.kind() == std::io::ErrorKind::WouldBlock && written > 0 => {
+                            break
+                        }
+                        Err(err) => return Err(io_error_into_wasi_err(err)),
+                    };
+                written += local_written;
+                if local_written != buf.len() {
+                    break;
+                }
+            }
+        }
+        FdWriteSource::Buffer(data) => {
+            let mut data = data.as_ref();
+            while !data.is_empty() {
+                let local_written = futures::future::poll_fn(|cx| write(cx, data))
+                    .await
+                    .map_err(io_error_into_wasi_err)?;
+                if local_written == 0 {
+                    return Err(Errno::Io);
+                }
+                data = &data[local_written..];
+                written += local_written;
+            }
+        }
+    }
+    Ok(written)
+}
+
+/// Writes to one of the stdio files, which are often pipes that the
+/// embedder reads from at its own pace.
+async fn write_stdio<M: MemorySize>(
+    handle: &Arc<std::sync::RwLock<Box<dyn VirtualFile + Send + Sync + 'static>>>,
+    memory: &MemoryView<'_>,
+    data: &FdWriteSource<'_, M>,
+    nonblocking: bool,
+) -> Result<usize, Errno> {
+    let written = write_polled::<M>(memory, data, nonblocking, |cx, buf| {
+        let mut handle = handle.write().unwrap();
+        tokio::io::AsyncWrite::poll_write(Pin::new(handle.as_mut()), cx, buf)
+    })
+    .await?;
+
+    futures::future::poll_fn(|cx| {
+        let mut handle = handle.write().unwrap();
+        tokio::io::AsyncWrite::poll_flush(Pin::new(handle.as_mut()), cx)
+    })
+    .await
+    .map_err(io_error_into_wasi_err)?;
+    Ok(written)
+}
+
 #[allow(clippy::await_holding_lock)]
 pub(crate) fn fd_write_internal<M: MemorySize>(
     mut ctx: &mut FunctionEnvMut<'_, WasiEnv>,
@@ -160,20 +310,50 @@ pub(crate) fn fd_write_internal<M: MemorySize>(
                                 None
                             },
                             async {
+                                if is_stdio {
+                                    let nonblocking = fd_flags.contains(Fdflags::NONBLOCK);
+                                    return write_stdio::<M>(&handle, &memory, &data, nonblocking)
+                                        .await;
+                                }
+
                                 let mut handle = handle.write().unwrap();
-                                if !is_stdio {
-                                    if fd_entry.inner.flags.contains(Fdflags::APPEND) {
-                                        // `fdflags::append` means we need to seek to the end before writing.
-                                        offset = fd_entry.inode.stat.read().unwrap().st_size;
-                                        fd_entry.inner.offset.store(offset, Ordering::Release);
+                                if fd_entry.inner.flags.contains(Fdflags::APPEND) {
+                                    // `fdflags::append` means we need to write at the end, whatever the
+                                    // offset of the fd is, and in one step so that concurrent appends
+                                    // don't overwrite each other
+                                    let appended =
+                                        append_at_end::<M>(handle.as_mut(), &memory, &data)?;
+                                    offset = match appended {
+                                        Some((start, _)) => start,
+                                        None => handle
+                                            .seek(std::io::SeekFrom::End(0))
+                                            .await
+                                            .map_err(io_error_into_wasi_err)?,
+                                    };
+                                    fd_entry.inner.offset.store(offset, Ordering::Release);
+                                    if let Some((_, written)) = appended {
+                                        return Ok(written);
                                     }
+                                }
 
-                                    handle
-                                        .seek(std::io::SeekFrom::Start(offset))
-                                        .await
-                                        .map_err(map_io_err)?;
+                                // Most files take the data straight out of guest memory
+                                if let FdWriteSource::Iovs { iovs, iovs_len } = &data {
+                                    if let Some(written) = write_iovs_at::<M>(
+                                        handle.as_mut(),
+                                        offset,
+                                        &memory,
+                                        *iovs,
+                                        *iovs_len,
+                                    )? {
+                                        return Ok(written);
+                                    }
                                 }
 
+                                handle
+                                    .seek(std::io::SeekFrom::Start(offset))
+                                    .await
+                                    .map_err(io_error_into_wasi_err)?;
+
                                 let mut written = 0usize;
 
                                 match &data {
@@ -193,7 +373,9 @@ pub(crate) fn fd_write_internal<M: MemorySize>(
                                                 match handle.write(buf.as_ref()).await {
                                                     Ok(s) => s,
                                                     Err(_) if written > 0 => break,
-                                                    Err(err) => return Err(map_io_err(err)),
+                                                    Err(err) => {
+                                                        return Err(io_error_into_wasi_err(err))
+                                                    }
                                                 };
                                             written += local_written;
                                             if local_written != buf.len() {
@@ -207,9 +389,6 @@ pub(crate) fn fd_write_internal<M: MemorySize>(
                                     }
                                 }
 
-                                if is_stdio {
-                                    handle.flush().await.map_err(map_io_err)?;
-                                }
                                 Ok(written)
                             },
                         );
@@ -279,61 +458,25 @@ pub(crate) fn fd_write_internal<M: MemorySize>(
                     return Ok(Err(Errno::Badf));
                 }
                 Kind::PipeTx { tx } => {
-                    let mut written = 0usize;
-
-                    match &data {
-                        FdWriteSource::Iovs { iovs, iovs_len } => {
-                            let mut raise_sigpipe = false;
-                            let iovs_arr = wasi_try_ok_ok!(iovs
-                                .slice(&memory, *iovs_len)
-                                .map_err(mem_error_to_wasi));
-                            let iovs_arr =
-                                wasi_try_ok_ok!(iovs_arr.access().map_err(mem_error_to_wasi));
-                            for iovs in iovs_arr.iter() {
-                                let buf = wasi_try_ok_ok!(WasmPtr::<u8, M>::new(iovs.buf)
-                                    .slice(&memory, iovs.buf_len)
-                                    .map_err(mem_error_to_wasi));
-                                let buf = wasi_try_ok_ok!(buf.access().map_err(mem_error_to_wasi));
-                                let write_result = std::io::Write::write(tx, buf.as_ref());
-                                let local_written = match write_result {
-                                    Ok(w) => w,
-                                    Err(e) if e.kind() == std::io::ErrorKind::BrokenPipe => {
-                                        // Need to do this to avoid double borrow on ctx with iovs_arr
-                                        raise_sigpipe = true;
-                                        break;
-                                    }
-                                    Err(e) => return Ok(Err(map_io_err(e))),
-                                };
-
-                                written += local_written;
-                                if local_written != buf.len() {
-                                    break;
-                                }
-                            }
-
-                            drop(iovs_arr);
+                    // Bounded pipes make the writer wait for the reader, which
+                    // must not happen while holding the lock of the inode
+                    let mut tx = tx.clone();
+                    drop(guard);
 
-                            if raise_sigpipe {
-                                env.process.signal_process(Signal::Sigpipe);
-                                wasi_try_ok_ok!(WasiEnv::process_signals_and_exit(ctx)?);
-                                return Ok(Err(Errno::Pipe));
-                            }
-                        }
-                        FdWriteSource::Buffer(data) => {
-                            match std::io::Write::write_all(tx, data) {
-                                Ok(()) => (),
-                                Err(e) if e.kind() == std::io::ErrorKind::BrokenPipe => {
-                                    env.process.signal_process(Signal::Sigpipe);
-                                    wasi_try_ok_ok!(WasiEnv::process_signals_and_exit(ctx)?);
-                                    return Ok(Err(Errno::Pipe));
-                                }
-                                Err(e) => return Ok(Err(map_io_err(e))),
-                            };
-                            written += data.len();
-                        }
+                    let nonblocking = fd_flags.contains(Fdflags::NONBLOCK);
+                    let res = __asyncify_light(env, None, async {
+                        write_polled::<M>(&memory, &data, nonblocking, |cx, buf| {
+                            tokio::io::AsyncWrite::poll_write(Pin::new(&mut tx), cx, buf)
+                        })
+                        .await
+                    })?;
+                    if let Err(Errno::Pipe) = res {
+                        env.process.signal_process(Signal::Sigpipe);
+                        wasi_try_ok_ok!(WasiEnv::process_signals_and_exit(ctx)?);
+                        return Ok(Err(Errno::Pipe));
                     }
 
-                    (written, false, true)
+                    (wasi_try_ok_ok!(res), false, true)
                 }
                 Kind::DuplexPipe { pipe } => {
                     let mut written = 0usize;
@@ -359,7 +502,7 @@ pub(crate) fn fd_write_internal<M: MemorySize>(
                                         raise_sigpipe = true;
                                         break;
                                     }
-                                    Err(e) => return Ok(Err(map_io_err(e))),
+                                    Err(e) => return Ok(Err(io_error_into_wasi_err(e))),
                                 };
 
                                 written += local_written;
@@ -384,7 +527,7 @@ pub(crate) fn fd_write_internal<M: MemorySize>(
                                     wasi_try_ok_ok!(WasiEnv::process_signals_and_exit(ctx)?);
                                     return Ok(Err(Errno::Pipe));
                                 }
-                                Err(e) => return Ok(Err(map_io_err(e))),
+                                Err(e) => return Ok(Err(io_error_into_wasi_err(e))),
                             };
                             written += data.len();
                         }
@@ -468,7 +611,7 @@ pub(crate) fn fd_write_internal<M: MemorySize>(
                                 let buf = wasi_try_ok_ok!(buf.access().map_err(mem_error_to_wasi));
                                 let local_written =
                                     wasi_try_ok_ok!(std::io::Write::write(buffer, buf.as_ref())
-                                        .map_err(map_io_err));
+                                        .map_err(io_error_into_wasi_err));
                                 written += local_written;
                                 if local_written != buf.len() {
                                     break;
@@ -476,9 +619,8 @@ pub(crate) fn fd_write_internal<M: MemorySize>(
                             }
                         }
                         FdWriteSource::Buffer(data) => {
-                            wasi_try_ok_ok!(
-                                std::io::Write::write_all(buffer, data).map_err(map_io_err)
-                            );
+                            wasi_try_ok_ok!(std::io::Write::write_all(buffer, data)
+                                .map_err(io_error_into_wasi_err));
                             written += data.len();
                         }
                     }
@@ -504,15 +646,25 @@ pub(crate) fn fd_write_internal<M: MemorySize>(
 
         // reborrow and update the size
         if !is_stdio {
+            let is_append = fd_flags.contains(Fdflags::APPEND);
             let curr_offset = if is_file && should_update_cursor {
                 let bytes_written = bytes_written as u64;
                 let mut fd_map = state.fs.fd_map.write().unwrap();
                 let fd_entry = wasi_try_ok_ok!(fd_map.get_mut(fd).ok_or(Errno::Badf));
-                fd_entry
-                    .offset
-                    .fetch_add(bytes_written, Ordering::AcqRel)
-                    // fetch_add returns the previous value, we have to add bytes_written again here
-                    + bytes_written
+                if is_append {
+                    // Other appends may have moved the offset since, but this
+                    // one ended where the data it wrote did
+                    fd_entry
+                        .offset
+                        .store(offset + bytes_written, Ordering::Release);
+                    offset + bytes_written
+                } else {
+                    fd_entry
+                        .offset
+                        .fetch_add(bytes_written, Ordering::AcqRel)
+                        // fetch_add returns the previous value, we have to add bytes_written again here
+                        + bytes_written
+                }
             } else {
                 fd_entry.inner.offset.load(Ordering::Acquire)
             };
@@ -26,6 +26,9 @@ pub fn path_create_directory<M: MemorySize>(
     path: WasmPtr<u8, M>,
     path_len: M::Offset,
 ) -> Result<Errno, WasiError> {
+    ctx.data()
+        .process
+        .count_syscall(Syscall::PathCreateDirectory);
     WasiEnv::do_pending_operations(&mut ctx)?;
 
     let env = ctx.data();
@@ -69,7 +72,7 @@ pub(crate) fn path_create_directory_internal(
     let (parent_inode, dir_name) =
         state
             .fs
-            .get_parent_inode_at_path(inodes, fd, Path::new(path), true)?;
+            .get_parent_inode_at_path(inodes, fd, Path::new(path), true, FsAccess::CREATE)?;
 
     let mut guard = parent_inode.write();
     match guard.deref_mut() {
@@ -25,6 +25,7 @@ pub fn path_filestat_get<M: MemorySize>(
     path_len: M::Offset,
     buf: WasmPtr<Filestat, M>,
 ) -> Errno {
+    ctx.data().process.count_syscall(Syscall::PathFilestatGet);
     let env = ctx.data();
     let (memory, mut state, inodes) = unsafe { env.get_memory_and_wasi_state_and_inodes(&ctx, 0) };
 
@@ -77,6 +78,7 @@ pub(crate) fn path_filestat_get_internal(
         fd,
         path_string,
         flags & __WASI_LOOKUP_SYMLINK_FOLLOW != 0,
+        FsAccess::NONE,
     )?;
 
     let st_ino = file_inode.ino().as_u64();
@@ -29,6 +29,9 @@ pub fn path_filestat_set_times<M: MemorySize>(
     st_mtim: Timestamp,
     fst_flags: Fstflags,
 ) -> Result<Errno, WasiError> {
+    ctx.data()
+        .process
+        .count_syscall(Syscall::PathFilestatSetTimes);
     WasiEnv::do_pending_operations(&mut ctx)?;
 
     let env = ctx.data();
@@ -94,10 +97,13 @@ pub(crate) fn path_filestat_set_times_internal(
         return Err(Errno::Inval);
     }
 
-    let file_inode =
-        state
-            .fs
-            .get_inode_at_path(inodes, fd, path, flags & __WASI_LOOKUP_SYMLINK_FOLLOW != 0)?;
+    let file_inode = state.fs.get_inode_at_path(
+        inodes,
+        fd,
+        path,
+        flags & __WASI_LOOKUP_SYMLINK_FOLLOW != 0,
+        FsAccess::WRITE,
+    )?;
     let stat = {
         let guard = file_inode.read();
         state.fs.get_stat_for_kind(guard.deref())?
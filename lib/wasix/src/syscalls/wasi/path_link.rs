@@ -1,4 +1,5 @@
 use super::*;
+use crate::fs::WasiFs;
 use crate::syscalls::*;
 
 /// ### `path_link()`
@@ -29,6 +30,7 @@ pub fn path_link<M: MemorySize>(
     new_path: WasmPtr<u8, M>,
     new_path_len: M::Offset,
 ) -> Result<Errno, WasiError> {
+    ctx.data().process.count_syscall(Syscall::PathLink);
     WasiEnv::do_pending_operations(&mut ctx)?;
 
     if old_flags & __WASI_LOOKUP_SYMLINK_FOLLOW != 0 {
@@ -80,8 +82,23 @@ pub(crate) fn path_link_internal(
 ) -> Result<(), Errno> {
     let env = ctx.data();
     let (memory, mut state, inodes) = unsafe { env.get_memory_and_wasi_state_and_inodes(&ctx, 0) };
-    let source_fd = state.fs.get_fd(old_fd)?;
-    let target_fd = state.fs.get_fd(new_fd)?;
+    path_link_inner(
+        &state.fs, inodes, old_fd, old_flags, old_path, new_fd, new_path,
+    )
+}
+
+/// Creates the hard link in the inode tree of `fs`, without needing a guest.
+pub(crate) fn path_link_inner(
+    fs: &WasiFs,
+    inodes: &WasiInodes,
+    old_fd: WasiFd,
+    old_flags: LookupFlags,
+    old_path: &str,
+    new_fd: WasiFd,
+    new_path: &str,
+) -> Result<(), Errno> {
+    let source_fd = fs.get_fd(old_fd)?;
+    let target_fd = fs.get_fd(new_fd)?;
 
     if !source_fd.inner.rights.contains(Rights::PATH_LINK_SOURCE)
         || !target_fd.inner.rights.contains(Rights::PATH_LINK_TARGET)
@@ -92,17 +109,16 @@ pub(crate) fn path_link_internal(
     Span::current().record("old_path", old_path);
     Span::current().record("new_path", new_path);
 
-    let source_inode = state.fs.get_inode_at_path(
+    let source_inode = fs.get_inode_at_path(
         inodes,
         old_fd,
         old_path,
         old_flags & __WASI_LOOKUP_SYMLINK_FOLLOW != 0,
+        FsAccess::READ,
     )?;
     let target_path_arg = std::path::PathBuf::from(new_path);
     let (target_parent_inode, new_entry_name) =
-        state
-            .fs
-            .get_parent_inode_at_path(inodes, new_fd, &target_path_arg, false)?;
+        fs.get_parent_inode_at_path(inodes, new_fd, &target_path_arg, false, FsAccess::CREATE)?;
 
     if source_inode.stat.write().unwrap().st_nlink == Linkcount::MAX {
         return Err(Errno::Mlink);
@@ -38,6 +38,7 @@ pub fn path_open<M: MemorySize>(
     fs_flags: Fdflags,
     fd: WasmPtr<WasiFd, M>,
 ) -> Result<Errno, WasiError> {
+    ctx.data().process.count_syscall(Syscall::PathOpen);
     WasiEnv::do_pending_operations(&mut ctx)?;
 
     if dirflags & __WASI_LOOKUP_SYMLINK_FOLLOW != 0 {
@@ -27,6 +27,7 @@ pub fn path_readlink<M: MemorySize>(
     buf_len: M::Offset,
     buf_used: WasmPtr<M::Offset, M>,
 ) -> Result<Errno, WasiError> {
+    ctx.data().process.count_syscall(Syscall::PathReadlink);
     WasiEnv::do_pending_operations(&mut ctx)?;
 
     let env = ctx.data();
@@ -39,7 +40,10 @@ pub fn path_readlink<M: MemorySize>(
     let mut path_str = unsafe { get_input_str_ok!(&memory, path, path_len) };
     Span::current().record("path", path_str.as_str());
 
-    let inode = wasi_try_ok!(state.fs.get_inode_at_path(inodes, dir_fd, &path_str, false));
+    let inode =
+        wasi_try_ok!(state
+            .fs
+            .get_inode_at_path(inodes, dir_fd, &path_str, false, FsAccess::READ));
 
     {
         let guard = inode.read();
@@ -11,6 +11,9 @@ pub fn path_remove_directory<M: MemorySize>(
     path: WasmPtr<u8, M>,
     path_len: M::Offset,
 ) -> Result<Errno, WasiError> {
+    ctx.data()
+        .process
+        .count_syscall(Syscall::PathRemoveDirectory);
     WasiEnv::do_pending_operations(&mut ctx)?;
 
     // TODO check if fd is a dir, ensure it's within sandbox, etc.
@@ -49,10 +52,10 @@ pub(crate) fn path_remove_directory_internal(
     let (parent_inode, dir_name) =
         state
             .fs
-            .get_parent_inode_at_path(inodes, fd, Path::new(path), true)?;
+            .get_parent_inode_at_path(inodes, fd, Path::new(path), true, FsAccess::WRITE)?;
 
     let mut guard = parent_inode.write();
-    match guard.deref_mut() {
+    let res = match guard.deref_mut() {
         Kind::Dir {
             entries: ref mut parent_entries,
             ..
@@ -95,5 +98,11 @@ pub(crate) fn path_remove_directory_internal(
             trace!("path is not a directory");
             Err(Errno::Notdir)
         }
+    };
+    drop(guard);
+
+    if res.is_ok() {
+        state.fs.invalidate_lookups(&parent_inode);
     }
+    res
 }
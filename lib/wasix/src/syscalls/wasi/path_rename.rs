@@ -30,6 +30,7 @@ pub fn path_rename<M: MemorySize>(
     new_path: WasmPtr<u8, M>,
     new_path_len: M::Offset,
 ) -> Result<Errno, WasiError> {
+    ctx.data().process.count_syscall(Syscall::PathRename);
     WasiEnv::do_pending_operations(&mut ctx)?;
 
     let env = ctx.data();
@@ -79,22 +80,24 @@ pub fn path_rename_internal(
     // this is to be sure the source file is fetched from the filesystem if needed
     wasi_try_ok!(state
         .fs
-        .get_inode_at_path(inodes, source_fd, source_path, true));
+        .get_inode_at_path(inodes, source_fd, source_path, true, FsAccess::WRITE));
     // Create the destination inode if the file exists.
     let _ = state
         .fs
-        .get_inode_at_path(inodes, target_fd, target_path, true);
+        .get_inode_at_path(inodes, target_fd, target_path, true, FsAccess::CREATE);
     let (source_parent_inode, source_entry_name) = wasi_try_ok!(state.fs.get_parent_inode_at_path(
         inodes,
         source_fd,
         Path::new(source_path),
-        true
+        true,
+        FsAccess::WRITE
     ));
     let (target_parent_inode, target_entry_name) = wasi_try_ok!(state.fs.get_parent_inode_at_path(
         inodes,
         target_fd,
         Path::new(target_path),
-        true
+        true,
+        FsAccess::CREATE
     ));
     let mut need_create = true;
     let host_adjusted_target_path = {
@@ -141,6 +144,7 @@ pub fn path_rename_internal(
             }
         }
     };
+    state.fs.invalidate_lookups(&source_parent_inode);
 
     {
         let mut guard = source_entry.write();
@@ -217,11 +221,12 @@ pub fn path_rename_internal(
             );
         }
     }
+    state.fs.invalidate_lookups(&target_parent_inode);
 
     // The target entry is created, one way or the other
     let target_inode = state
         .fs
-        .get_inode_at_path(inodes, target_fd, target_path, true)
+        .get_inode_at_path(inodes, target_fd, target_path, true, FsAccess::CREATE)
         .expect("Expected target inode to exist, and it's too late to safely fail");
     *target_inode.name.write().unwrap() = target_entry_name.into();
     target_inode.stat.write().unwrap().st_size = source_size;
@@ -1,4 +1,5 @@
 use super::*;
+use crate::fs::WasiFs;
 use crate::syscalls::*;
 
 /// ### `path_symlink()`
@@ -23,6 +24,7 @@ pub fn path_symlink<M: MemorySize>(
     new_path: WasmPtr<u8, M>,
     new_path_len: M::Offset,
 ) -> Result<Errno, WasiError> {
+    ctx.data().process.count_syscall(Syscall::PathSymlink);
     WasiEnv::do_pending_operations(&mut ctx)?;
 
     let env = ctx.data();
@@ -61,18 +63,27 @@ pub fn path_symlink_internal(
 ) -> Result<(), Errno> {
     let env = ctx.data();
     let (memory, mut state, inodes) = unsafe { env.get_memory_and_wasi_state_and_inodes(&ctx, 0) };
+    path_symlink_inner(&state.fs, inodes, old_path, fd, new_path)
+}
 
-    let base_fd = state.fs.get_fd(fd)?;
+/// Creates the symlink in the inode tree of `fs`, without needing a guest.
+pub(crate) fn path_symlink_inner(
+    fs: &WasiFs,
+    inodes: &WasiInodes,
+    old_path: &str,
+    fd: WasiFd,
+    new_path: &str,
+) -> Result<(), Errno> {
+    let base_fd = fs.get_fd(fd)?;
     if !base_fd.inner.rights.contains(Rights::PATH_SYMLINK) {
         return Err(Errno::Access);
     }
 
     // get the depth of the parent + 1 (UNDER INVESTIGATION HMMMMMMMM THINK FISH ^ THINK FISH)
     let old_path_path = std::path::Path::new(old_path);
-    let (source_inode, _) = state
-        .fs
-        .get_parent_inode_at_path(inodes, fd, old_path_path, true)?;
-    let depth = state.fs.path_depth_from_fd(fd, source_inode);
+    let (source_inode, _) =
+        fs.get_parent_inode_at_path(inodes, fd, old_path_path, true, FsAccess::NONE)?;
+    let depth = fs.path_depth_from_fd(fd, source_inode);
 
     // depth == -1 means folder is not relative. See issue #3233.
     let depth = match depth {
@@ -82,9 +93,7 @@ pub fn path_symlink_internal(
 
     let new_path_path = std::path::Path::new(new_path);
     let (target_parent_inode, entry_name) =
-        state
-            .fs
-            .get_parent_inode_at_path(inodes, fd, new_path_path, true)?;
+        fs.get_parent_inode_at_path(inodes, fd, new_path_path, true, FsAccess::CREATE)?;
 
     // short circuit if anything is wrong, before we create an inode
     {
@@ -121,9 +130,7 @@ pub fn path_symlink_internal(
         relative_path,
     };
     let new_inode =
-        state
-            .fs
-            .create_inode_with_default_stat(inodes, kind, false, entry_name.clone().into());
+        fs.create_inode_with_default_stat(inodes, kind, false, entry_name.clone().into());
 
     {
         let mut guard = target_parent_inode.write();
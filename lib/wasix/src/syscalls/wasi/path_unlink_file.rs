@@ -17,6 +17,7 @@ pub fn path_unlink_file<M: MemorySize>(
     path: WasmPtr<u8, M>,
     path_len: M::Offset,
 ) -> Result<Errno, WasiError> {
+    ctx.data().process.count_syscall(Syscall::PathUnlinkFile);
     WasiEnv::do_pending_operations(&mut ctx)?;
 
     let env = ctx.data();
@@ -55,12 +56,15 @@ pub(crate) fn path_unlink_file_internal(
     let env = ctx.data();
     let (memory, mut state, inodes) = unsafe { env.get_memory_and_wasi_state_and_inodes(&ctx, 0) };
 
-    let inode = wasi_try_ok!(state.fs.get_inode_at_path(inodes, fd, path, false));
+    let inode = wasi_try_ok!(state
+        .fs
+        .get_inode_at_path(inodes, fd, path, false, FsAccess::WRITE));
     let (parent_inode, childs_name) = wasi_try_ok!(state.fs.get_parent_inode_at_path(
         inodes,
         fd,
         std::path::Path::new(path),
-        false
+        false,
+        FsAccess::WRITE
     ));
 
     let removed_inode = {
@@ -81,6 +85,7 @@ pub(crate) fn path_unlink_file_internal(
             ),
         }
     };
+    state.fs.invalidate_lookups(&parent_inode);
 
     let st_nlink = {
         let mut guard = removed_inode.stat.write().unwrap();
@@ -6,7 +6,6 @@ use crate::{
     fs::{InodeValFilePollGuard, InodeValFilePollGuardJoin},
     state::PollEventSet,
     syscalls::*,
-    WasiInodes,
 };
 
 /// An event that occurred.
@@ -64,6 +63,7 @@ pub fn poll_oneoff<M: MemorySize + 'static>(
     nsubscriptions: M::Offset,
     nevents: WasmPtr<M::Offset, M>,
 ) -> Result<Errno, WasiError> {
+    ctx.data().process.count_syscall(Syscall::PollOneoff);
     WasiEnv::do_pending_operations(&mut ctx)?;
 
     ctx = wasi_try_ok!(maybe_backoff::<M>(ctx)?);
@@ -170,32 +170,18 @@ pub(crate) fn poll_fd_guard(
     fd: WasiFd,
     s: Subscription,
 ) -> Result<InodeValFilePollGuard, Errno> {
-    Ok(match fd {
-        __WASI_STDERR_FILENO => WasiInodes::stderr(&state.fs.fd_map)
-            .map(|g| g.into_poll_guard(fd, peb, s))
-            .map_err(fs_error_into_wasi_err)?,
-        __WASI_STDOUT_FILENO => WasiInodes::stdout(&state.fs.fd_map)
-            .map(|g| g.into_poll_guard(fd, peb, s))
-            .map_err(fs_error_into_wasi_err)?,
-        _ => {
-            let fd_entry = state.fs.get_fd(fd)?;
-            if !fd_entry.inner.rights.contains(Rights::POLL_FD_READWRITE) {
-                return Err(Errno::Access);
-            }
-            let inode = fd_entry.inode;
+    let fd_entry = state.fs.get_fd(fd)?;
+    // The standard devices are always pollable, whatever they were renumbered to
+    if fd != __WASI_STDOUT_FILENO
+        && fd != __WASI_STDERR_FILENO
+        && !fd_entry.inner.rights.contains(Rights::POLL_FD_READWRITE)
+    {
+        return Err(Errno::Access);
+    }
+    let inode = fd_entry.inode;
 
-            {
-                let guard = inode.read();
-                if let Some(guard) =
-                    crate::fs::InodeValFilePollGuard::new(fd, peb, s, guard.deref())
-                {
-                    guard
-                } else {
-                    return Err(Errno::Badf);
-                }
-            }
-        }
-    })
+    let guard = inode.read();
+    InodeValFilePollGuard::new(fd, peb, s, guard.deref()).ok_or(Errno::Badf)
 }
 
 /// ### `poll_oneoff()`
@@ -13,6 +13,7 @@ pub fn proc_exit<M: MemorySize>(
     mut ctx: FunctionEnvMut<'_, WasiEnv>,
     code: ExitCode,
 ) -> Result<(), WasiError> {
+    ctx.data().process.count_syscall(Syscall::ProcExit);
     WasiEnv::do_pending_operations(&mut ctx)?;
 
     debug!(%code);
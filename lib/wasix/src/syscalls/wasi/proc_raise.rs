@@ -9,6 +9,7 @@ use crate::syscalls::*;
 ///   Signal to be raised for this process
 #[instrument(level = "trace", skip_all, fields(sig), ret)]
 pub fn proc_raise(mut ctx: FunctionEnvMut<'_, WasiEnv>, sig: Signal) -> Result<Errno, WasiError> {
+    ctx.data().process.count_syscall(Syscall::ProcRaise);
     let env = ctx.data();
     env.process.signal_process(sig);
 
@@ -29,6 +30,7 @@ pub fn proc_raise_interval(
     interval: Timestamp,
     repeat: Bool,
 ) -> Result<Errno, WasiError> {
+    ctx.data().process.count_syscall(Syscall::ProcRaiseInterval);
     let env = ctx.data();
     let interval = match interval {
         0 => None,
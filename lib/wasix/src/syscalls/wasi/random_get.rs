@@ -14,6 +14,7 @@ pub fn random_get<M: MemorySize>(
     buf: WasmPtr<u8, M>,
     buf_len: M::Offset,
 ) -> Errno {
+    ctx.data().process.count_syscall(Syscall::RandomGet);
     let env = ctx.data();
     let memory = unsafe { env.memory_view(&ctx) };
     let buf_len64: u64 = buf_len.into();
@@ -21,6 +21,7 @@ pub fn thread_spawn<M: MemorySize>(
     mut ctx: FunctionEnvMut<'_, WasiEnv>,
     start_ptr: WasmPtr<ThreadStart<M>, M>,
 ) -> i32 {
+    ctx.data().process.count_syscall(Syscall::ThreadSpawn);
     thread_spawn_internal_from_wasi(&mut ctx, start_ptr)
         .map(|tid| tid as i32)
         .map_err(|errno| errno as i32)
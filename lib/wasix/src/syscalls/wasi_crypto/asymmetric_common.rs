@@ -0,0 +1,178 @@
+use std::sync::Arc;
+
+use tracing::instrument;
+use wasmer::{FunctionEnvMut, MemorySize, WasmPtr};
+
+use super::{
+    read_algorithm, read_bytes,
+    state::{Keypair, Object, PublicKey},
+    with_crypto, CryptoErrno, Handle, OPT_OPTIONS_SOME,
+};
+use crate::{os::task::counters::Syscall, WasiEnv, WasiError};
+
+/// ### `keypair_generate()`
+/// Generates a new key pair for an algorithm.
+///
+/// None of the algorithms take options, so passing some returns
+/// `unsupported_option`.
+#[instrument(level = "trace", skip_all, ret)]
+pub fn keypair_generate<M: MemorySize>(
+    mut ctx: FunctionEnvMut<'_, WasiEnv>,
+    algorithm_type: u16,
+    algorithm: WasmPtr<u8, M>,
+    algorithm_len: M::Offset,
+    options: WasmPtr<u8, M>,
+    result: WasmPtr<Handle, M>,
+) -> Result<CryptoErrno, WasiError> {
+    ctx.data().process.count_syscall(Syscall::KeypairGenerate);
+    with_crypto(&mut ctx, |crypto, memory, _| {
+        let algorithm = read_algorithm(memory, algorithm_type, algorithm, algorithm_len)?;
+        if options.read(memory)? == OPT_OPTIONS_SOME {
+            return Err(CryptoErrno::UnsupportedOption);
+        }
+
+        let keypair = Keypair::generate(algorithm)?;
+        let handle = crypto.open(Object::Keypair(Arc::new(keypair)))?;
+        result.write(memory, handle)?;
+        Ok(())
+    })
+}
+
+/// ### `keypair_import()`
+/// Imports a key pair from its `raw` or `pkcs8` encoding.
+///
+/// Ed25519 key pairs are imported from the 32 byte seed when raw, and ECDSA
+/// ones can only be imported from PKCS#8.
+#[instrument(level = "trace", skip_all, fields(%encoding), ret)]
+pub fn keypair_import<M: MemorySize>(
+    mut ctx: FunctionEnvMut<'_, WasiEnv>,
+    algorithm_type: u16,
+    algorithm: WasmPtr<u8, M>,
+    algorithm_len: M::Offset,
+    encoded: WasmPtr<u8, M>,
+    encoded_len: M::Offset,
+    encoding: u16,
+    result: WasmPtr<Handle, M>,
+) -> Result<CryptoErrno, WasiError> {
+    ctx.data().process.count_syscall(Syscall::KeypairImport);
+    with_crypto(&mut ctx, |crypto, memory, _| {
+        let algorithm = read_algorithm(memory, algorithm_type, algorithm, algorithm_len)?;
+        let encoded = read_bytes(memory, encoded, encoded_len)?;
+
+        let keypair = Keypair::import(algorithm, &encoded, encoding)?;
+        let handle = crypto.open(Object::Keypair(Arc::new(keypair)))?;
+        result.write(memory, handle)?;
+        Ok(())
+    })
+}
+
+/// ### `keypair_publickey()`
+/// Gets the public key of a key pair.
+#[instrument(level = "trace", skip_all, fields(%keypair), ret)]
+pub fn keypair_publickey<M: MemorySize>(
+    mut ctx: FunctionEnvMut<'_, WasiEnv>,
+    keypair: Handle,
+    result: WasmPtr<Handle, M>,
+) -> Result<CryptoErrno, WasiError> {
+    ctx.data().process.count_syscall(Syscall::KeypairPublickey);
+    with_crypto(&mut ctx, |crypto, memory, _| {
+        let publickey = crypto.keypair(keypair)?.public_key();
+        let handle = crypto.open(Object::PublicKey(Arc::new(publickey)))?;
+        result.write(memory, handle)?;
+        Ok(())
+    })
+}
+
+/// ### `keypair_export()`
+/// Exports a key pair, secret key included, to an array output.
+///
+/// Returns `prohibited_operation` unless the process is allowed to export
+/// secret keys.
+#[instrument(level = "trace", skip_all, fields(%keypair, %encoding), ret)]
+pub fn keypair_export<M: MemorySize>(
+    mut ctx: FunctionEnvMut<'_, WasiEnv>,
+    keypair: Handle,
+    encoding: u16,
+    result: WasmPtr<Handle, M>,
+) -> Result<CryptoErrno, WasiError> {
+    ctx.data().process.count_syscall(Syscall::KeypairExport);
+    with_crypto(&mut ctx, |crypto, memory, capability| {
+        let keypair = crypto.keypair(keypair)?;
+        if !capability.allow_secret_export {
+            return Err(CryptoErrno::ProhibitedOperation);
+        }
+
+        let handle = crypto.open(Object::ArrayOutput(keypair.export(encoding)?))?;
+        result.write(memory, handle)?;
+        Ok(())
+    })
+}
+
+/// ### `keypair_close()`
+/// Closes a key pair. Operations it was used for can still complete.
+#[instrument(level = "trace", skip_all, fields(%keypair), ret)]
+pub fn keypair_close(
+    mut ctx: FunctionEnvMut<'_, WasiEnv>,
+    keypair: Handle,
+) -> Result<CryptoErrno, WasiError> {
+    ctx.data().process.count_syscall(Syscall::KeypairClose);
+    with_crypto(&mut ctx, |crypto, _, _| {
+        crypto.close(keypair, |object| matches!(object, Object::Keypair(_)))
+    })
+}
+
+/// ### `publickey_import()`
+/// Imports a public key from its `raw` encoding, or `sec` for ECDSA.
+#[instrument(level = "trace", skip_all, fields(%encoding), ret)]
+pub fn publickey_import<M: MemorySize>(
+    mut ctx: FunctionEnvMut<'_, WasiEnv>,
+    algorithm_type: u16,
+    algorithm: WasmPtr<u8, M>,
+    algorithm_len: M::Offset,
+    encoded: WasmPtr<u8, M>,
+    encoded_len: M::Offset,
+    encoding: u16,
+    result: WasmPtr<Handle, M>,
+) -> Result<CryptoErrno, WasiError> {
+    ctx.data().process.count_syscall(Syscall::PublickeyImport);
+    with_crypto(&mut ctx, |crypto, memory, _| {
+        let algorithm = read_algorithm(memory, algorithm_type, algorithm, algorithm_len)?;
+        let encoded = read_bytes(memory, encoded, encoded_len)?;
+
+        let publickey = PublicKey::import(algorithm, &encoded, encoding)?;
+        let handle = crypto.open(Object::PublicKey(Arc::new(publickey)))?;
+        result.write(memory, handle)?;
+        Ok(())
+    })
+}
+
+/// ### `publickey_export()`
+/// Exports a public key to an array output.
+#[instrument(level = "trace", skip_all, fields(%publickey, %encoding), ret)]
+pub fn publickey_export<M: MemorySize>(
+    mut ctx: FunctionEnvMut<'_, WasiEnv>,
+    publickey: Handle,
+    encoding: u16,
+    result: WasmPtr<Handle, M>,
+) -> Result<CryptoErrno, WasiError> {
+    ctx.data().process.count_syscall(Syscall::PublickeyExport);
+    with_crypto(&mut ctx, |crypto, memory, _| {
+        let encoded = crypto.publickey(publickey)?.export(encoding)?;
+        let handle = crypto.open(Object::ArrayOutput(encoded))?;
+        result.write(memory, handle)?;
+        Ok(())
+    })
+}
+
+/// ### `publickey_close()`
+/// Closes a public key.
+#[instrument(level = "trace", skip_all, fields(%publickey), ret)]
+pub fn publickey_close(
+    mut ctx: FunctionEnvMut<'_, WasiEnv>,
+    publickey: Handle,
+) -> Result<CryptoErrno, WasiError> {
+    ctx.data().process.count_syscall(Syscall::PublickeyClose);
+    with_crypto(&mut ctx, |crypto, _, _| {
+        crypto.close(publickey, |object| matches!(object, Object::PublicKey(_)))
+    })
+}
@@ -0,0 +1,44 @@
+use tracing::instrument;
+use wasmer::{FunctionEnvMut, MemorySize, WasmPtr};
+
+use super::{to_size, with_crypto, CryptoErrno, Handle};
+use crate::{os::task::counters::Syscall, WasiEnv, WasiError};
+
+/// ### `array_output_len()`
+/// Writes the length of the data held by an array output to `result`.
+#[instrument(level = "trace", skip_all, fields(%array_output), ret)]
+pub fn array_output_len<M: MemorySize>(
+    mut ctx: FunctionEnvMut<'_, WasiEnv>,
+    array_output: Handle,
+    result: WasmPtr<M::Offset, M>,
+) -> Result<CryptoErrno, WasiError> {
+    ctx.data().process.count_syscall(Syscall::ArrayOutputLen);
+    with_crypto(&mut ctx, |crypto, memory, _| {
+        let len = crypto.array_output_len(array_output)?;
+        result.write(memory, to_size::<M>(len)?)?;
+        Ok(())
+    })
+}
+
+/// ### `array_output_pull()`
+/// Copies the data held by an array output into `buf` and closes it.
+///
+/// Returns `overflow` if the data doesn't fit in the buffer, in which case
+/// the array output is left open.
+#[instrument(level = "trace", skip_all, fields(%array_output), ret)]
+pub fn array_output_pull<M: MemorySize>(
+    mut ctx: FunctionEnvMut<'_, WasiEnv>,
+    array_output: Handle,
+    buf: WasmPtr<u8, M>,
+    buf_len: M::Offset,
+    result: WasmPtr<M::Offset, M>,
+) -> Result<CryptoErrno, WasiError> {
+    ctx.data().process.count_syscall(Syscall::ArrayOutputPull);
+    with_crypto(&mut ctx, |crypto, memory, _| {
+        let data = crypto.pull_array_output(array_output, buf_len.into())?;
+        let len = to_size::<M>(data.len())?;
+        buf.slice(memory, len)?.write_slice(&data)?;
+        result.write(memory, len)?;
+        Ok(())
+    })
+}
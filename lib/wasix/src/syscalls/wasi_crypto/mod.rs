@@ -0,0 +1,206 @@
+//! The signatures module of the [wasi-crypto] proposal, implemented by the
+//! host with `ring`.
+//!
+//! Guests import these from the `wasi_ephemeral_crypto_common`,
+//! `wasi_ephemeral_crypto_asymmetric_common` and
+//! `wasi_ephemeral_crypto_signatures` namespaces. Key pairs, public keys,
+//! signatures and the state of ongoing operations stay on the host and are
+//! referred to by opaque handles, so private keys never enter the guest's
+//! memory unless the process is allowed to export them (see
+//! [`CapabilityCryptoV1`](crate::capabilities::CapabilityCryptoV1)).
+//!
+//! Only the `Ed25519` and `ECDSA_P256_SHA256` algorithms are supported, and
+//! none of the options.
+//!
+//! [wasi-crypto]: https://github.com/WebAssembly/wasi-crypto
+
+mod asymmetric_common;
+mod common;
+mod signatures;
+mod state;
+
+pub(crate) use self::state::CryptoState;
+pub use self::{asymmetric_common::*, common::*, signatures::*};
+
+use wasmer::{FromToNativeWasmType, FunctionEnvMut, MemoryAccessError, MemorySize, MemoryView};
+
+use self::state::Algorithm;
+use crate::{capabilities::CapabilityCryptoV1, WasiEnv, WasiError};
+
+/// A handle to an object kept by the host, such as a key pair.
+pub type Handle = u32;
+
+/// The `signatures` algorithm type, the only one that is supported.
+pub const ALGORITHM_TYPE_SIGNATURES: u16 = 0;
+
+pub const KEYPAIR_ENCODING_RAW: u16 = 0;
+pub const KEYPAIR_ENCODING_PKCS8: u16 = 1;
+pub const KEYPAIR_ENCODING_PEM: u16 = 2;
+pub const KEYPAIR_ENCODING_LOCAL: u16 = 3;
+
+pub const PUBLICKEY_ENCODING_RAW: u16 = 0;
+pub const PUBLICKEY_ENCODING_PKCS8: u16 = 1;
+pub const PUBLICKEY_ENCODING_PEM: u16 = 2;
+pub const PUBLICKEY_ENCODING_SEC: u16 = 3;
+pub const PUBLICKEY_ENCODING_LOCAL: u16 = 4;
+
+pub const SIGNATURE_ENCODING_RAW: u16 = 0;
+pub const SIGNATURE_ENCODING_DER: u16 = 1;
+
+/// The tag of an `opt_options` that holds options.
+const OPT_OPTIONS_SOME: u8 = 0;
+
+/// The error codes of the wasi-crypto proposal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(u16)]
+pub enum CryptoErrno {
+    Success = 0,
+    GuestError = 1,
+    NotImplemented = 2,
+    UnsupportedFeature = 3,
+    ProhibitedOperation = 4,
+    UnsupportedEncoding = 5,
+    UnsupportedAlgorithm = 6,
+    UnsupportedOption = 7,
+    InvalidKey = 8,
+    InvalidLength = 9,
+    VerificationFailed = 10,
+    RngError = 11,
+    AlgorithmFailure = 12,
+    InvalidSignature = 13,
+    Closed = 14,
+    InvalidHandle = 15,
+    Overflow = 16,
+    InternalError = 17,
+    TooManyHandles = 18,
+    KeyNotSupported = 19,
+    KeyRequired = 20,
+    InvalidTag = 21,
+    InvalidOperation = 22,
+    NonceRequired = 23,
+    InvalidNonce = 24,
+    OptionNotSet = 25,
+    NotFound = 26,
+    ParametersMissing = 27,
+    InProgress = 28,
+    IncompatibleKeys = 29,
+    Expired = 30,
+}
+
+impl CryptoErrno {
+    const ALL: [CryptoErrno; 31] = [
+        CryptoErrno::Success,
+        CryptoErrno::GuestError,
+        CryptoErrno::NotImplemented,
+        CryptoErrno::UnsupportedFeature,
+        CryptoErrno::ProhibitedOperation,
+        CryptoErrno::UnsupportedEncoding,
+        CryptoErrno::UnsupportedAlgorithm,
+        CryptoErrno::UnsupportedOption,
+        CryptoErrno::InvalidKey,
+        CryptoErrno::InvalidLength,
+        CryptoErrno::VerificationFailed,
+        CryptoErrno::RngError,
+        CryptoErrno::AlgorithmFailure,
+        CryptoErrno::InvalidSignature,
+        CryptoErrno::Closed,
+        CryptoErrno::InvalidHandle,
+        CryptoErrno::Overflow,
+        CryptoErrno::InternalError,
+        CryptoErrno::TooManyHandles,
+        CryptoErrno::KeyNotSupported,
+        CryptoErrno::KeyRequired,
+        CryptoErrno::InvalidTag,
+        CryptoErrno::InvalidOperation,
+        CryptoErrno::NonceRequired,
+        CryptoErrno::InvalidNonce,
+        CryptoErrno::OptionNotSet,
+        CryptoErrno::NotFound,
+        CryptoErrno::ParametersMissing,
+        CryptoErrno::InProgress,
+        CryptoErrno::IncompatibleKeys,
+        CryptoErrno::Expired,
+    ];
+}
+
+impl TryFrom<u16> for CryptoErrno {
+    type Error = u16;
+
+    fn try_from(value: u16) -> Result<Self, Self::Error> {
+        CryptoErrno::ALL.get(value as usize).copied().ok_or(value)
+    }
+}
+
+impl From<MemoryAccessError> for CryptoErrno {
+    fn from(_: MemoryAccessError) -> Self {
+        CryptoErrno::GuestError
+    }
+}
+
+unsafe impl FromToNativeWasmType for CryptoErrno {
+    type Native = i32;
+
+    fn to_native(self) -> Self::Native {
+        self as i32
+    }
+
+    fn from_native(native: Self::Native) -> Self {
+        u16::try_from(native)
+            .ok()
+            .and_then(|value| CryptoErrno::try_from(value).ok())
+            .unwrap_or(CryptoErrno::InternalError)
+    }
+
+    fn is_from_store(&self, _store: &impl wasmer::AsStoreRef) -> bool {
+        false
+    }
+}
+
+/// Runs `f` with the process's crypto state and the guest's memory, turning
+/// its outcome into the errno returned to the guest.
+fn with_crypto(
+    ctx: &mut FunctionEnvMut<'_, WasiEnv>,
+    f: impl FnOnce(&CryptoState, &MemoryView, &CapabilityCryptoV1) -> Result<(), CryptoErrno>,
+) -> Result<CryptoErrno, WasiError> {
+    WasiEnv::do_pending_operations(ctx)?;
+
+    let env = ctx.data();
+    let capability = &env.capabilities.crypto;
+    if !capability.enabled {
+        return Ok(CryptoErrno::ProhibitedOperation);
+    }
+
+    let memory = unsafe { env.memory_view(&*ctx) };
+    Ok(match f(&env.state.crypto, &memory, capability) {
+        Ok(()) => CryptoErrno::Success,
+        Err(errno) => errno,
+    })
+}
+
+/// Reads the name of an algorithm of the given type.
+fn read_algorithm<M: MemorySize>(
+    memory: &MemoryView,
+    algorithm_type: u16,
+    algorithm: wasmer::WasmPtr<u8, M>,
+    algorithm_len: M::Offset,
+) -> Result<Algorithm, CryptoErrno> {
+    if algorithm_type != ALGORITHM_TYPE_SIGNATURES {
+        return Err(CryptoErrno::NotImplemented);
+    }
+    let name = algorithm.read_utf8_string(memory, algorithm_len)?;
+    Algorithm::from_name(&name)
+}
+
+/// Reads a buffer the guest passed in.
+fn read_bytes<M: MemorySize>(
+    memory: &MemoryView,
+    ptr: wasmer::WasmPtr<u8, M>,
+    len: M::Offset,
+) -> Result<Vec<u8>, CryptoErrno> {
+    Ok(ptr.slice(memory, len)?.read_to_vec()?)
+}
+
+/// Converts a length to the guest's `size`.
+fn to_size<M: MemorySize>(len: usize) -> Result<M::Offset, CryptoErrno> {
+    len.try_into().map_err(|_| CryptoErrno::Overflow)
+}
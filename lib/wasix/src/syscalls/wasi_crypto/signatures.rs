@@ -0,0 +1,245 @@
+use std::sync::Arc;
+
+use tracing::instrument;
+use wasmer::{FunctionEnvMut, MemorySize, WasmPtr};
+
+use super::{
+    read_bytes,
+    state::{Algorithm, Object, Signature},
+    with_crypto, CryptoErrno, Handle,
+};
+use crate::{os::task::counters::Syscall, WasiEnv, WasiError};
+
+/// ### `signature_export()`
+/// Exports a signature to an array output, in the encoding it was created
+/// in.
+#[instrument(level = "trace", skip_all, fields(%signature, %encoding), ret)]
+pub fn signature_export<M: MemorySize>(
+    mut ctx: FunctionEnvMut<'_, WasiEnv>,
+    signature: Handle,
+    encoding: u16,
+    result: WasmPtr<Handle, M>,
+) -> Result<CryptoErrno, WasiError> {
+    ctx.data().process.count_syscall(Syscall::SignatureExport);
+    with_crypto(&mut ctx, |crypto, memory, _| {
+        let encoded = crypto.signature(signature)?.export(encoding)?;
+        let handle = crypto.open(Object::ArrayOutput(encoded))?;
+        result.write(memory, handle)?;
+        Ok(())
+    })
+}
+
+/// ### `signature_import()`
+/// Imports a signature from its `raw` encoding, or `der` for ECDSA.
+#[instrument(level = "trace", skip_all, fields(%encoding), ret)]
+pub fn signature_import<M: MemorySize>(
+    mut ctx: FunctionEnvMut<'_, WasiEnv>,
+    algorithm: WasmPtr<u8, M>,
+    algorithm_len: M::Offset,
+    encoded: WasmPtr<u8, M>,
+    encoded_len: M::Offset,
+    encoding: u16,
+    result: WasmPtr<Handle, M>,
+) -> Result<CryptoErrno, WasiError> {
+    ctx.data().process.count_syscall(Syscall::SignatureImport);
+    with_crypto(&mut ctx, |crypto, memory, _| {
+        let algorithm = algorithm.read_utf8_string(memory, algorithm_len)?;
+        let algorithm = Algorithm::from_name(&algorithm)?;
+        let encoded = read_bytes(memory, encoded, encoded_len)?;
+
+        let signature = Signature::import(algorithm, &encoded, encoding)?;
+        let handle = crypto.open(Object::Signature(Arc::new(signature)))?;
+        result.write(memory, handle)?;
+        Ok(())
+    })
+}
+
+/// ### `signature_close()`
+/// Closes a signature.
+#[instrument(level = "trace", skip_all, fields(%signature), ret)]
+pub fn signature_close(
+    mut ctx: FunctionEnvMut<'_, WasiEnv>,
+    signature: Handle,
+) -> Result<CryptoErrno, WasiError> {
+    ctx.data().process.count_syscall(Syscall::SignatureClose);
+    with_crypto(&mut ctx, |crypto, _, _| {
+        crypto.close(signature, |object| matches!(object, Object::Signature(_)))
+    })
+}
+
+/// ### `signature_state_open()`
+/// Starts signing a message with a key pair.
+#[instrument(level = "trace", skip_all, fields(%keypair), ret)]
+pub fn signature_state_open<M: MemorySize>(
+    mut ctx: FunctionEnvMut<'_, WasiEnv>,
+    keypair: Handle,
+    result: WasmPtr<Handle, M>,
+) -> Result<CryptoErrno, WasiError> {
+    ctx.data()
+        .process
+        .count_syscall(Syscall::SignatureStateOpen);
+    with_crypto(&mut ctx, |crypto, memory, _| {
+        let keypair = crypto.keypair(keypair)?;
+        let handle = crypto.open(Object::SignatureState {
+            keypair,
+            data: Vec::new(),
+        })?;
+        result.write(memory, handle)?;
+        Ok(())
+    })
+}
+
+/// ### `signature_state_update()`
+/// Appends `input` to the message being signed.
+#[instrument(level = "trace", skip_all, fields(%state), ret)]
+pub fn signature_state_update<M: MemorySize>(
+    mut ctx: FunctionEnvMut<'_, WasiEnv>,
+    state: Handle,
+    input: WasmPtr<u8, M>,
+    input_len: M::Offset,
+) -> Result<CryptoErrno, WasiError> {
+    ctx.data()
+        .process
+        .count_syscall(Syscall::SignatureStateUpdate);
+    with_crypto(&mut ctx, |crypto, memory, _| {
+        let input = read_bytes(memory, input, input_len)?;
+        crypto.with(state, |object| match object {
+            Object::SignatureState { data, .. } => {
+                data.extend_from_slice(&input);
+                Some(())
+            }
+            _ => None,
+        })
+    })
+}
+
+/// ### `signature_state_sign()`
+/// Signs the message accumulated so far.
+#[instrument(level = "trace", skip_all, fields(%state), ret)]
+pub fn signature_state_sign<M: MemorySize>(
+    mut ctx: FunctionEnvMut<'_, WasiEnv>,
+    state: Handle,
+    result: WasmPtr<Handle, M>,
+) -> Result<CryptoErrno, WasiError> {
+    ctx.data()
+        .process
+        .count_syscall(Syscall::SignatureStateSign);
+    with_crypto(&mut ctx, |crypto, memory, _| {
+        let (keypair, data) = crypto.with(state, |object| match object {
+            Object::SignatureState { keypair, data } => Some((keypair.clone(), data.clone())),
+            _ => None,
+        })?;
+
+        let signature = keypair.sign(&data)?;
+        let handle = crypto.open(Object::Signature(Arc::new(signature)))?;
+        result.write(memory, handle)?;
+        Ok(())
+    })
+}
+
+/// ### `signature_state_close()`
+/// Closes a signature state.
+#[instrument(level = "trace", skip_all, fields(%state), ret)]
+pub fn signature_state_close(
+    mut ctx: FunctionEnvMut<'_, WasiEnv>,
+    state: Handle,
+) -> Result<CryptoErrno, WasiError> {
+    ctx.data()
+        .process
+        .count_syscall(Syscall::SignatureStateClose);
+    with_crypto(&mut ctx, |crypto, _, _| {
+        crypto.close(state, |object| {
+            matches!(object, Object::SignatureState { .. })
+        })
+    })
+}
+
+/// ### `signature_verification_state_open()`
+/// Starts verifying a message with a public key.
+#[instrument(level = "trace", skip_all, fields(%publickey), ret)]
+pub fn signature_verification_state_open<M: MemorySize>(
+    mut ctx: FunctionEnvMut<'_, WasiEnv>,
+    publickey: Handle,
+    result: WasmPtr<Handle, M>,
+) -> Result<CryptoErrno, WasiError> {
+    ctx.data()
+        .process
+        .count_syscall(Syscall::SignatureVerificationStateOpen);
+    with_crypto(&mut ctx, |crypto, memory, _| {
+        let publickey = crypto.publickey(publickey)?;
+        let handle = crypto.open(Object::VerificationState {
+            publickey,
+            data: Vec::new(),
+        })?;
+        result.write(memory, handle)?;
+        Ok(())
+    })
+}
+
+/// ### `signature_verification_state_update()`
+/// Appends `input` to the message being verified.
+#[instrument(level = "trace", skip_all, fields(%state), ret)]
+pub fn signature_verification_state_update<M: MemorySize>(
+    mut ctx: FunctionEnvMut<'_, WasiEnv>,
+    state: Handle,
+    input: WasmPtr<u8, M>,
+    input_len: M::Offset,
+) -> Result<CryptoErrno, WasiError> {
+    ctx.data()
+        .process
+        .count_syscall(Syscall::SignatureVerificationStateUpdate);
+    with_crypto(&mut ctx, |crypto, memory, _| {
+        let input = read_bytes(memory, input, input_len)?;
+        crypto.with(state, |object| match object {
+            Object::VerificationState { data, .. } => {
+                data.extend_from_slice(&input);
+                Some(())
+            }
+            _ => None,
+        })
+    })
+}
+
+/// ### `signature_verification_state_verify()`
+/// Checks `signature` against the message accumulated so far.
+///
+/// Returns `verification_failed` if it doesn't match, and
+/// `invalid_signature` if it was made with another algorithm.
+#[instrument(level = "trace", skip_all, fields(%state, %signature), ret)]
+pub fn signature_verification_state_verify(
+    mut ctx: FunctionEnvMut<'_, WasiEnv>,
+    state: Handle,
+    signature: Handle,
+) -> Result<CryptoErrno, WasiError> {
+    ctx.data()
+        .process
+        .count_syscall(Syscall::SignatureVerificationStateVerify);
+    with_crypto(&mut ctx, |crypto, _, _| {
+        let (publickey, data) = crypto.with(state, |object| match object {
+            Object::VerificationState { publickey, data } => {
+                Some((publickey.clone(), data.clone()))
+            }
+            _ => None,
+        })?;
+        let signature = crypto.signature(signature)?;
+
+        publickey.verify(&data, &signature)
+    })
+}
+
+/// ### `signature_verification_state_close()`
+/// Closes a verification state.
+#[instrument(level = "trace", skip_all, fields(%state), ret)]
+pub fn signature_verification_state_close(
+    mut ctx: FunctionEnvMut<'_, WasiEnv>,
+    state: Handle,
+) -> Result<CryptoErrno, WasiError> {
+    ctx.data()
+        .process
+        .count_syscall(Syscall::SignatureVerificationStateClose);
+    with_crypto(&mut ctx, |crypto, _, _| {
+        crypto.close(state, |object| {
+            matches!(object, Object::VerificationState { .. })
+        })
+    })
+}
@@ -0,0 +1,365 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::{Arc, Mutex},
+};
+
+use ring::{
+    rand::SystemRandom,
+    signature::{
+        self, EcdsaKeyPair, Ed25519KeyPair, KeyPair as _, UnparsedPublicKey, VerificationAlgorithm,
+    },
+};
+
+use super::{
+    CryptoErrno, Handle, KEYPAIR_ENCODING_PKCS8, KEYPAIR_ENCODING_RAW, PUBLICKEY_ENCODING_RAW,
+    PUBLICKEY_ENCODING_SEC, SIGNATURE_ENCODING_DER, SIGNATURE_ENCODING_RAW,
+};
+
+/// How many objects a process may keep open at the same time.
+const MAX_HANDLES: usize = 4096;
+
+/// The signature algorithms that are supported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Algorithm {
+    Ed25519,
+    EcdsaP256Sha256,
+}
+
+impl Algorithm {
+    pub fn from_name(name: &str) -> Result<Self, CryptoErrno> {
+        match name {
+            "Ed25519" => Ok(Algorithm::Ed25519),
+            "ECDSA_P256_SHA256" => Ok(Algorithm::EcdsaP256Sha256),
+            _ => Err(CryptoErrno::UnsupportedAlgorithm),
+        }
+    }
+}
+
+pub(crate) struct Keypair {
+    algorithm: Algorithm,
+    signer: Signer,
+    /// The key pair as it was generated or imported, which are the only
+    /// encodings it can be exported in.
+    pkcs8: Option<Vec<u8>>,
+    raw: Option<Vec<u8>>,
+}
+
+enum Signer {
+    Ed25519(Ed25519KeyPair),
+    EcdsaP256(EcdsaKeyPair),
+}
+
+impl Keypair {
+    pub fn generate(algorithm: Algorithm) -> Result<Self, CryptoErrno> {
+        let rng = SystemRandom::new();
+        let pkcs8 = match algorithm {
+            Algorithm::Ed25519 => Ed25519KeyPair::generate_pkcs8(&rng),
+            Algorithm::EcdsaP256Sha256 => {
+                EcdsaKeyPair::generate_pkcs8(&signature::ECDSA_P256_SHA256_FIXED_SIGNING, &rng)
+            }
+        }
+        .map_err(|_| CryptoErrno::RngError)?;
+
+        Self::import(algorithm, pkcs8.as_ref(), KEYPAIR_ENCODING_PKCS8)
+    }
+
+    pub fn import(
+        algorithm: Algorithm,
+        encoded: &[u8],
+        encoding: u16,
+    ) -> Result<Self, CryptoErrno> {
+        let signer = match (algorithm, encoding) {
+            (Algorithm::Ed25519, KEYPAIR_ENCODING_PKCS8) => {
+                Ed25519KeyPair::from_pkcs8_maybe_unchecked(encoded).map(Signer::Ed25519)
+            }
+            // Either the seed, or the seed followed by the public key
+            (Algorithm::Ed25519, KEYPAIR_ENCODING_RAW) => match encoded.len() {
+                32 => Ed25519KeyPair::from_seed_unchecked(encoded),
+                64 => Ed25519KeyPair::from_seed_and_public_key(&encoded[..32], &encoded[32..]),
+                _ => return Err(CryptoErrno::InvalidKey),
+            }
+            .map(Signer::Ed25519),
+            (Algorithm::EcdsaP256Sha256, KEYPAIR_ENCODING_PKCS8) => EcdsaKeyPair::from_pkcs8(
+                &signature::ECDSA_P256_SHA256_FIXED_SIGNING,
+                encoded,
+                &SystemRandom::new(),
+            )
+            .map(Signer::EcdsaP256),
+            _ => return Err(CryptoErrno::UnsupportedEncoding),
+        }
+        .map_err(|_| CryptoErrno::InvalidKey)?;
+
+        let encoded = Some(encoded.to_vec());
+        let (pkcs8, raw) = match encoding {
+            KEYPAIR_ENCODING_PKCS8 => (encoded, None),
+            _ => (None, encoded),
+        };
+        Ok(Keypair {
+            algorithm,
+            signer,
+            pkcs8,
+            raw,
+        })
+    }
+
+    pub fn export(&self, encoding: u16) -> Result<Vec<u8>, CryptoErrno> {
+        let encoded = match encoding {
+            KEYPAIR_ENCODING_PKCS8 => &self.pkcs8,
+            KEYPAIR_ENCODING_RAW => &self.raw,
+            _ => &None,
+        };
+        encoded.clone().ok_or(CryptoErrno::UnsupportedEncoding)
+    }
+
+    pub fn public_key(&self) -> PublicKey {
+        let bytes = match &self.signer {
+            Signer::Ed25519(keypair) => keypair.public_key().as_ref().to_vec(),
+            Signer::EcdsaP256(keypair) => keypair.public_key().as_ref().to_vec(),
+        };
+        PublicKey {
+            algorithm: self.algorithm,
+            bytes,
+        }
+    }
+
+    pub fn sign(&self, message: &[u8]) -> Result<Signature, CryptoErrno> {
+        let signature = match &self.signer {
+            Signer::Ed25519(keypair) => keypair.sign(message),
+            Signer::EcdsaP256(keypair) => keypair
+                .sign(&SystemRandom::new(), message)
+                .map_err(|_| CryptoErrno::AlgorithmFailure)?,
+        };
+        Ok(Signature {
+            algorithm: self.algorithm,
+            encoding: SIGNATURE_ENCODING_RAW,
+            bytes: signature.as_ref().to_vec(),
+        })
+    }
+}
+
+pub(crate) struct PublicKey {
+    algorithm: Algorithm,
+    /// The key itself for Ed25519, and the uncompressed point for ECDSA.
+    bytes: Vec<u8>,
+}
+
+impl PublicKey {
+    pub fn import(
+        algorithm: Algorithm,
+        encoded: &[u8],
+        encoding: u16,
+    ) -> Result<Self, CryptoErrno> {
+        let valid = match (algorithm, encoding) {
+            (Algorithm::Ed25519, PUBLICKEY_ENCODING_RAW) => encoded.len() == 32,
+            (Algorithm::EcdsaP256Sha256, PUBLICKEY_ENCODING_RAW | PUBLICKEY_ENCODING_SEC) => {
+                encoded.len() == 65 && encoded[0] == 4
+            }
+            _ => return Err(CryptoErrno::UnsupportedEncoding),
+        };
+        if !valid {
+            return Err(CryptoErrno::InvalidKey);
+        }
+
+        Ok(PublicKey {
+            algorithm,
+            bytes: encoded.to_vec(),
+        })
+    }
+
+    pub fn export(&self, encoding: u16) -> Result<Vec<u8>, CryptoErrno> {
+        match (self.algorithm, encoding) {
+            (Algorithm::Ed25519, PUBLICKEY_ENCODING_RAW)
+            | (Algorithm::EcdsaP256Sha256, PUBLICKEY_ENCODING_RAW | PUBLICKEY_ENCODING_SEC) => {
+                Ok(self.bytes.clone())
+            }
+            _ => Err(CryptoErrno::UnsupportedEncoding),
+        }
+    }
+
+    pub fn verify(&self, message: &[u8], signature: &Signature) -> Result<(), CryptoErrno> {
+        let algorithm: &'static dyn VerificationAlgorithm =
+            match (self.algorithm, signature.algorithm, signature.encoding) {
+                (Algorithm::Ed25519, Algorithm::Ed25519, _) => &signature::ED25519,
+                (
+                    Algorithm::EcdsaP256Sha256,
+                    Algorithm::EcdsaP256Sha256,
+                    SIGNATURE_ENCODING_DER,
+                ) => &signature::ECDSA_P256_SHA256_ASN1,
+                (Algorithm::EcdsaP256Sha256, Algorithm::EcdsaP256Sha256, _) => {
+                    &signature::ECDSA_P256_SHA256_FIXED
+                }
+                _ => return Err(CryptoErrno::InvalidSignature),
+            };
+
+        UnparsedPublicKey::new(algorithm, &self.bytes)
+            .verify(message, &signature.bytes)
+            .map_err(|_| CryptoErrno::VerificationFailed)
+    }
+}
+
+pub(crate) struct Signature {
+    algorithm: Algorithm,
+    encoding: u16,
+    bytes: Vec<u8>,
+}
+
+impl Signature {
+    pub fn import(
+        algorithm: Algorithm,
+        encoded: &[u8],
+        encoding: u16,
+    ) -> Result<Self, CryptoErrno> {
+        let valid = match (algorithm, encoding) {
+            (_, SIGNATURE_ENCODING_RAW) => encoded.len() == 64,
+            (Algorithm::EcdsaP256Sha256, SIGNATURE_ENCODING_DER) => !encoded.is_empty(),
+            _ => return Err(CryptoErrno::UnsupportedEncoding),
+        };
+        if !valid {
+            return Err(CryptoErrno::InvalidSignature);
+        }
+
+        Ok(Signature {
+            algorithm,
+            encoding,
+            bytes: encoded.to_vec(),
+        })
+    }
+
+    /// Signatures can only be exported in the encoding they were created in.
+    pub fn export(&self, encoding: u16) -> Result<Vec<u8>, CryptoErrno> {
+        if encoding != self.encoding {
+            return Err(CryptoErrno::UnsupportedEncoding);
+        }
+        Ok(self.bytes.clone())
+    }
+}
+
+/// An object a handle refers to.
+pub(crate) enum Object {
+    Keypair(Arc<Keypair>),
+    PublicKey(Arc<PublicKey>),
+    Signature(Arc<Signature>),
+    SignatureState {
+        keypair: Arc<Keypair>,
+        data: Vec<u8>,
+    },
+    VerificationState {
+        publickey: Arc<PublicKey>,
+        data: Vec<u8>,
+    },
+    ArrayOutput(Vec<u8>),
+}
+
+/// The objects a process has open, by handle.
+#[derive(Default)]
+pub(crate) struct CryptoState {
+    handles: Mutex<Handles>,
+}
+
+#[derive(Default)]
+struct Handles {
+    next: Handle,
+    objects: HashMap<Handle, Object>,
+}
+
+impl CryptoState {
+    pub fn open(&self, object: Object) -> Result<Handle, CryptoErrno> {
+        let mut handles = self.handles.lock().unwrap();
+        if handles.objects.len() >= MAX_HANDLES {
+            return Err(CryptoErrno::TooManyHandles);
+        }
+
+        let mut handle = handles.next;
+        while handles.objects.contains_key(&handle) {
+            handle = handle.wrapping_add(1);
+        }
+        handles.next = handle.wrapping_add(1);
+        handles.objects.insert(handle, object);
+        Ok(handle)
+    }
+
+    /// Closes `handle`, if it refers to the kind of object `is_kind` accepts.
+    pub fn close(
+        &self,
+        handle: Handle,
+        is_kind: impl Fn(&Object) -> bool,
+    ) -> Result<(), CryptoErrno> {
+        let mut handles = self.handles.lock().unwrap();
+        match handles.objects.get(&handle) {
+            Some(object) if is_kind(object) => {
+                handles.objects.remove(&handle);
+                Ok(())
+            }
+            _ => Err(CryptoErrno::InvalidHandle),
+        }
+    }
+
+    /// Runs `f` on the object `handle` refers to, which fails with
+    /// [`CryptoErrno::InvalidHandle`] if it returns `None`.
+    pub fn with<R>(
+        &self,
+        handle: Handle,
+        f: impl FnOnce(&mut Object) -> Option<R>,
+    ) -> Result<R, CryptoErrno> {
+        let mut handles = self.handles.lock().unwrap();
+        let object = handles
+            .objects
+            .get_mut(&handle)
+            .ok_or(CryptoErrno::InvalidHandle)?;
+        f(object).ok_or(CryptoErrno::InvalidHandle)
+    }
+
+    pub fn keypair(&self, handle: Handle) -> Result<Arc<Keypair>, CryptoErrno> {
+        self.with(handle, |object| match object {
+            Object::Keypair(keypair) => Some(keypair.clone()),
+            _ => None,
+        })
+    }
+
+    pub fn publickey(&self, handle: Handle) -> Result<Arc<PublicKey>, CryptoErrno> {
+        self.with(handle, |object| match object {
+            Object::PublicKey(publickey) => Some(publickey.clone()),
+            _ => None,
+        })
+    }
+
+    pub fn signature(&self, handle: Handle) -> Result<Arc<Signature>, CryptoErrno> {
+        self.with(handle, |object| match object {
+            Object::Signature(signature) => Some(signature.clone()),
+            _ => None,
+        })
+    }
+
+    pub fn array_output_len(&self, handle: Handle) -> Result<usize, CryptoErrno> {
+        self.with(handle, |object| match object {
+            Object::ArrayOutput(data) => Some(data.len()),
+            _ => None,
+        })
+    }
+
+    /// Takes the contents of an array output, closing it, if they fit in
+    /// `max_len` bytes.
+    pub fn pull_array_output(&self, handle: Handle, max_len: u64) -> Result<Vec<u8>, CryptoErrno> {
+        let len = self.array_output_len(handle)?;
+        if len as u64 > max_len {
+            return Err(CryptoErrno::Overflow);
+        }
+
+        let mut handles = self.handles.lock().unwrap();
+        match handles.objects.remove(&handle) {
+            Some(Object::ArrayOutput(data)) => Ok(data),
+            _ => Err(CryptoErrno::InvalidHandle),
+        }
+    }
+}
+
+impl fmt::Debug for CryptoState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Never print the keys
+        let handles = self.handles.lock().unwrap();
+        f.debug_struct("CryptoState")
+            .field("open_handles", &handles.objects.len())
+            .finish()
+    }
+}
@@ -0,0 +1,137 @@
+use super::*;
+#[cfg(feature = "host-archive")]
+use crate::fs::{extract_archive, ArchiveSource};
+use crate::syscalls::*;
+
+/// Return from [`archive_extract()`] as soon as the extraction has started,
+/// instead of waiting for it to finish.
+pub const ARCHIVE_EXTRACT_BACKGROUND: u32 = 1;
+
+/// ### `archive_extract()`
+/// Extracts a tar archive, which may be compressed with gzip or zstd, into a
+/// directory. The host streams the archive from `src_fd` and creates its
+/// entries under `dest_fd`, which is much faster than doing it in the guest.
+///
+/// Entries that would end up outside of the directory, including symlinks
+/// pointing outside of it, fail the extraction with ENOTCAPABLE, and going
+/// over the memory limit of the filesystem fails it with ENOSPC. Entries
+/// extracted before a failure are left in place.
+///
+/// Returns ENOTCAPABLE if the process isn't allowed to extract archives, and
+/// ENOTSUP if the runtime was built without support for it.
+///
+/// ## Parameters
+///
+/// * `src_fd` - File the archive is read from, starting at its current offset
+/// * `dest_fd` - Directory the entries are created in
+/// * `flags` - `ARCHIVE_EXTRACT_BACKGROUND` to return before the extraction
+///   is done, its progress is then polled with `archive_extract_poll`
+/// * `ret_job` - Where the ID of the extraction is written when it runs in
+///   the background
+#[instrument(level = "trace", skip_all, fields(%src_fd, %dest_fd, %flags), ret)]
+pub fn archive_extract<M: MemorySize>(
+    mut ctx: FunctionEnvMut<'_, WasiEnv>,
+    src_fd: WasiFd,
+    dest_fd: WasiFd,
+    flags: u32,
+    ret_job: WasmPtr<u32, M>,
+) -> Result<Errno, WasiError> {
+    ctx.data().process.count_syscall(Syscall::ArchiveExtract);
+    WasiEnv::do_pending_operations(&mut ctx)?;
+
+    let env = ctx.data();
+    if !env.capabilities.archive.enabled {
+        return Ok(Errno::Notcapable);
+    }
+
+    let job = wasi_try_ok!(archive_extract_internal(&mut ctx, src_fd, dest_fd, flags)?);
+    if let Some(job) = job {
+        let env = ctx.data();
+        let memory = unsafe { env.memory_view(&ctx) };
+        wasi_try_mem_ok!(ret_job.write(&memory, job));
+    }
+    Ok(Errno::Success)
+}
+
+/// Starts the extraction, returning its ID if it runs in the background.
+#[cfg(feature = "host-archive")]
+pub(crate) fn archive_extract_internal(
+    ctx: &mut FunctionEnvMut<'_, WasiEnv>,
+    src_fd: WasiFd,
+    dest_fd: WasiFd,
+    flags: u32,
+) -> Result<Result<Option<u32>, Errno>, WasiError> {
+    let env = ctx.data();
+    let state = env.state.clone();
+
+    let source = wasi_try_ok_ok!(state.fs.get_fd(src_fd));
+    if !source.inner.rights.contains(Rights::FD_READ) {
+        return Ok(Err(Errno::Access));
+    }
+    let offset = source.inner.offset.load(Ordering::Acquire);
+    let file = match source.inode.read().deref() {
+        Kind::File {
+            handle: Some(handle),
+            ..
+        } => handle.clone(),
+        Kind::Dir { .. } | Kind::Root { .. } => return Ok(Err(Errno::Isdir)),
+        _ => return Ok(Err(Errno::Badf)),
+    };
+
+    let dest = wasi_try_ok_ok!(state.fs.get_fd(dest_fd));
+    if !dest
+        .inner
+        .rights
+        .contains(Rights::PATH_CREATE_DIRECTORY | Rights::PATH_CREATE_FILE)
+    {
+        return Ok(Err(Errno::Access));
+    }
+    let dest_path = match dest.inode.read().deref() {
+        Kind::Dir { path, .. } => path.clone(),
+        Kind::Root { .. } => return Ok(Err(Errno::Notcapable)),
+        _ => return Ok(Err(Errno::Notdir)),
+    };
+
+    let tasks = env.tasks().clone();
+    let (id, job) = state.archive_jobs.start();
+    let source = ArchiveSource::new(file, offset, tasks.clone());
+    let spawned = tasks.task_dedicated(Box::new({
+        let state = state.clone();
+        let tasks = tasks.clone();
+        let job = job.clone();
+        move || {
+            let result = extract_archive(
+                &state.fs,
+                &state.inodes,
+                &tasks,
+                source,
+                dest_fd,
+                &dest_path,
+                &job,
+            );
+            tracing::debug!(?result, "Archive extraction finished");
+            job.finish(result);
+        }
+    }));
+    if let Err(e) = spawned {
+        state.archive_jobs.remove(id);
+        return Ok(Err(e.into()));
+    }
+
+    if flags & ARCHIVE_EXTRACT_BACKGROUND != 0 {
+        return Ok(Ok(Some(id)));
+    }
+    let result = __asyncify_light(env, None, job.wait())?;
+    state.archive_jobs.remove(id);
+    Ok(result.map(|()| None))
+}
+
+#[cfg(not(feature = "host-archive"))]
+pub(crate) fn archive_extract_internal(
+    _ctx: &mut FunctionEnvMut<'_, WasiEnv>,
+    _src_fd: WasiFd,
+    _dest_fd: WasiFd,
+    _flags: u32,
+) -> Result<Result<Option<u32>, Errno>, WasiError> {
+    Ok(Err(Errno::Notsup))
+}
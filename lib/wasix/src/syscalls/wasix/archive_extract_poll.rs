@@ -0,0 +1,48 @@
+use super::*;
+use crate::syscalls::*;
+
+/// ### `archive_extract_poll()`
+/// Reports the progress of an extraction started in the background with
+/// `archive_extract`.
+///
+/// Returns EAGAIN while the extraction is running. Once it's done, the
+/// outcome of the extraction is returned and the ID is released, after
+/// which it can no longer be polled.
+///
+/// ## Parameters
+///
+/// * `job` - The ID `archive_extract` returned
+/// * `ret_entries` - Where the number of entries extracted so far is written
+/// * `ret_bytes` - Where the number of bytes written to files so far is
+///   written
+#[instrument(level = "trace", skip_all, fields(%job), ret)]
+pub fn archive_extract_poll<M: MemorySize>(
+    mut ctx: FunctionEnvMut<'_, WasiEnv>,
+    job: u32,
+    ret_entries: WasmPtr<u64, M>,
+    ret_bytes: WasmPtr<u64, M>,
+) -> Result<Errno, WasiError> {
+    ctx.data()
+        .process
+        .count_syscall(Syscall::ArchiveExtractPoll);
+    WasiEnv::do_pending_operations(&mut ctx)?;
+
+    let env = ctx.data();
+    let Some(extraction) = env.state.archive_jobs.get(job) else {
+        return Ok(Errno::Inval);
+    };
+    // Read the outcome first, so the counters are final when it's set
+    let result = extraction.result();
+
+    let memory = unsafe { env.memory_view(&ctx) };
+    wasi_try_mem_ok!(ret_entries.write(&memory, extraction.entries.load(Ordering::Relaxed)));
+    wasi_try_mem_ok!(ret_bytes.write(&memory, extraction.bytes.load(Ordering::Relaxed)));
+
+    match result {
+        Some(errno) => {
+            env.state.archive_jobs.remove(job);
+            Ok(errno)
+        }
+        None => Ok(Errno::Again),
+    }
+}
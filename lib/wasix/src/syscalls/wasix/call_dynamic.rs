@@ -132,6 +132,7 @@ pub fn call_dynamic<M: MemorySize>(
     results_len: M::Offset,
     strict: Bool,
 ) -> Result<Errno, RuntimeError> {
+    ctx.data().process.count_syscall(Syscall::CallDynamic);
     let (env, mut store) = ctx.data_and_store_mut();
 
     let strict = matches!(strict, Bool::True);
@@ -13,6 +13,7 @@ pub fn callback_signal<M: MemorySize>(
     name: WasmPtr<u8, M>,
     name_len: M::Offset,
 ) -> Result<(), WasiError> {
+    ctx.data().process.count_syscall(Syscall::CallbackSignal);
     let env = ctx.data();
     let memory = unsafe { env.memory_view(&ctx) };
     let name = match name.read_utf8_string(&memory, name_len) {
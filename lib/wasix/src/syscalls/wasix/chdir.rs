@@ -9,6 +9,7 @@ pub fn chdir<M: MemorySize>(
     path: WasmPtr<u8, M>,
     path_len: M::Offset,
 ) -> Result<Errno, WasiError> {
+    ctx.data().process.count_syscall(Syscall::Chdir);
     WasiEnv::do_pending_operations(&mut ctx)?;
 
     let env = ctx.data();
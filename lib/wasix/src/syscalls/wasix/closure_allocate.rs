@@ -10,6 +10,7 @@ pub fn closure_allocate<M: MemorySize>(
     mut ctx: FunctionEnvMut<'_, WasiEnv>,
     closure: WasmPtr<u32, M>,
 ) -> Result<Errno, WasiError> {
+    ctx.data().process.count_syscall(Syscall::ClosureAllocate);
     WasiEnv::do_pending_operations(&mut ctx)?;
 
     let (env, mut store) = ctx.data_and_store_mut();
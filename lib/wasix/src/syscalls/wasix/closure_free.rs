@@ -8,6 +8,7 @@ pub fn closure_free(
     mut ctx: FunctionEnvMut<'_, WasiEnv>,
     closure: u32,
 ) -> Result<Errno, WasiError> {
+    ctx.data().process.count_syscall(Syscall::ClosureFree);
     WasiEnv::do_pending_operations(&mut ctx)?;
 
     let (env, mut store) = ctx.data_and_store_mut();
@@ -322,6 +322,7 @@ pub fn closure_prepare<M: MemorySize>(
     result_types_length: u32,
     environment: WasmPtr<u8, M>,
 ) -> Result<Errno, WasiError> {
+    ctx.data().process.count_syscall(Syscall::ClosurePrepare);
     WasiEnv::do_pending_operations(&mut ctx)?;
 
     let (env, mut store) = ctx.data_and_store_mut();
@@ -0,0 +1,25 @@
+use super::*;
+use crate::{os::cpu::CpuInfo, syscalls::*};
+
+/// ### `cpu_features()`
+/// Returns the features supported by the CPUs the process runs on as a
+/// bitmask, where bit `N` is set when the `N`th entry of
+/// [`CPU_FEATURES`](crate::os::cpu::CPU_FEATURES) is available.
+#[instrument(level = "trace", skip_all, fields(features = field::Empty), ret)]
+pub fn cpu_features<M: MemorySize>(
+    ctx: FunctionEnvMut<'_, WasiEnv>,
+    ret_features: WasmPtr<u64, M>,
+) -> Errno {
+    ctx.data().process.count_syscall(Syscall::CpuFeatures);
+    let env = ctx.data();
+    let features = CpuInfo::new(
+        &env.capabilities.cpu,
+        env.tasks().as_ref(),
+        &env.runtime().engine(),
+    )
+    .features;
+    Span::current().record("features", features);
+    let memory = unsafe { env.memory_view(&ctx) };
+    wasi_try_mem!(ret_features.write(&memory, features));
+    Errno::Success
+}
@@ -8,6 +8,7 @@ pub fn dl_invalid_handle(
     mut ctx: FunctionEnvMut<'_, WasiEnv>,
     handle: DlHandle,
 ) -> Result<Errno, WasiError> {
+    ctx.data().process.count_syscall(Syscall::DlInvalidHandle);
     WasiEnv::do_pending_operations(&mut ctx)?;
 
     let (env, mut store) = ctx.data_and_store_mut();
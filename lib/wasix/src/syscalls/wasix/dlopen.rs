@@ -14,6 +14,7 @@ pub fn dlopen<M: MemorySize>(
     ld_library_path_len: M::Offset,
     out_handle: WasmPtr<DlHandle, M>,
 ) -> Result<Errno, WasiError> {
+    ctx.data().process.count_syscall(Syscall::Dlopen);
     WasiEnv::do_pending_operations(&mut ctx)?;
 
     let (env, mut store) = ctx.data_and_store_mut();
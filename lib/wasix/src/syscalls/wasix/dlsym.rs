@@ -16,6 +16,7 @@ pub fn dlsym<M: MemorySize>(
     err_buf_len: M::Offset,
     out_symbol: WasmPtr<M::Offset, M>,
 ) -> Result<Errno, WasiError> {
+    ctx.data().process.count_syscall(Syscall::Dlsym);
     WasiEnv::do_pending_operations(&mut ctx)?;
 
     let (env, mut store) = ctx.data_and_store_mut();
@@ -18,6 +18,7 @@ pub fn epoll_create<M: MemorySize + 'static>(
     mut ctx: FunctionEnvMut<'_, WasiEnv>,
     ret_fd: WasmPtr<WasiFd, M>,
 ) -> Result<Errno, WasiError> {
+    ctx.data().process.count_syscall(Syscall::EpollCreate);
     WasiEnv::do_pending_operations(&mut ctx)?;
 
     let fd = wasi_try_ok!(epoll_create_internal(&mut ctx, None)?);
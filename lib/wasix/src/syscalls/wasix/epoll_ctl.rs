@@ -34,6 +34,7 @@ pub fn epoll_ctl<M: MemorySize + 'static>(
     fd: WasiFd,
     event_ref: WasmPtr<EpollEvent<M>, M>,
 ) -> Result<Errno, WasiError> {
+    ctx.data().process.count_syscall(Syscall::EpollCtl);
     WasiEnv::do_pending_operations(&mut ctx)?;
 
     let env = ctx.data();
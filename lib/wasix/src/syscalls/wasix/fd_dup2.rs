@@ -17,6 +17,7 @@ pub fn fd_dup2<M: MemorySize>(
     cloexec: Bool,
     ret_fd: WasmPtr<WasiFd, M>,
 ) -> Result<Errno, WasiError> {
+    ctx.data().process.count_syscall(Syscall::FdDup2);
     WasiEnv::do_pending_operations(&mut ctx)?;
 
     let copied_fd = wasi_try_ok!(fd_dup_internal(
@@ -15,6 +15,7 @@ pub fn fd_fdflags_get<M: MemorySize>(
     wasi_fd: WasiFd,
     buf_ptr: WasmPtr<Fdflagsext, M>,
 ) -> Errno {
+    ctx.data().process.count_syscall(Syscall::FdFdflagsGet);
     let env = ctx.data();
     let (memory, state) = unsafe { env.get_memory_and_wasi_state(&ctx, 0) };
     let fd = wasi_try!(state.fs.get_fd(wasi_fd));
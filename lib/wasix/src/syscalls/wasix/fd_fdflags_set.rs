@@ -14,6 +14,7 @@ pub fn fd_fdflags_set(
     fd: WasiFd,
     flags: Fdflagsext,
 ) -> Result<Errno, WasiError> {
+    ctx.data().process.count_syscall(Syscall::FdFdflagsSet);
     WasiEnv::do_pending_operations(&mut ctx)?;
 
     let ret = fd_fdflags_set_internal(&mut ctx, fd, flags)?;
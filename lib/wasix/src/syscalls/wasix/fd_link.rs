@@ -0,0 +1,104 @@
+use super::*;
+use crate::syscalls::*;
+
+/// ### `fd_link()`
+/// Gives an unnamed file created by `path_open_tmpfile()` a name (like
+/// `linkat()` with `AT_EMPTY_PATH`)
+///
+/// Afterwards the file behaves like any other file and is no longer deleted
+/// when its file descriptors are closed.
+/// Inputs:
+/// - `Fd fd`
+///     The file descriptor of the unnamed file
+/// - `Fd new_fd`
+///     The directory relative to which the `new_path` is
+/// - `const char *new_path`
+///     String containing the new file path
+/// - `u32 new_path_len`
+///     Length of the `new_path` string
+/// Possible Errors:
+/// - `Errno::Access`, `Errno::Badf`, `Errno::Exist`, `Errno::Noent`, `Errno::Notdir`,
+///   `Errno::Inval` (the file already has a name or belongs to another file system)
+#[instrument(level = "trace", skip_all, fields(%fd, %new_fd, new_path = field::Empty), ret)]
+pub fn fd_link<M: MemorySize>(
+    mut ctx: FunctionEnvMut<'_, WasiEnv>,
+    fd: WasiFd,
+    new_fd: WasiFd,
+    new_path: WasmPtr<u8, M>,
+    new_path_len: M::Offset,
+) -> Result<Errno, WasiError> {
+    ctx.data().process.count_syscall(Syscall::FdLink);
+    WasiEnv::do_pending_operations(&mut ctx)?;
+
+    let env = ctx.data();
+    let memory = unsafe { env.memory_view(&ctx) };
+    let new_path_str = unsafe { get_input_str_ok!(&memory, new_path, new_path_len) };
+    Span::current().record("new_path", new_path_str.as_str());
+
+    wasi_try_ok!(fd_link_internal(env, fd, new_fd, &new_path_str));
+
+    Ok(Errno::Success)
+}
+
+pub(crate) fn fd_link_internal(
+    env: &WasiEnv,
+    fd: WasiFd,
+    new_fd: WasiFd,
+    new_path: &str,
+) -> Result<(), Errno> {
+    let state = env.state.deref();
+    let inodes = &state.inodes;
+
+    let source_fd = state.fs.get_fd(fd)?;
+    let target_fd = state.fs.get_fd(new_fd)?;
+    if !target_fd.inner.rights.contains(Rights::PATH_LINK_TARGET) {
+        return Err(Errno::Access);
+    }
+
+    let target_path_arg = std::path::PathBuf::from(new_path);
+    let (target_parent_inode, new_entry_name) = state.fs.get_parent_inode_at_path(
+        inodes,
+        new_fd,
+        &target_path_arg,
+        false,
+        FsAccess::CREATE,
+    )?;
+
+    let mut parent_guard = target_parent_inode.write();
+    let (entries, new_file_path) = match parent_guard.deref_mut() {
+        Kind::Dir { entries, path, .. } => {
+            let new_file_path = path.join(&new_entry_name);
+            (entries, new_file_path)
+        }
+        Kind::Root { .. } => return Err(Errno::Inval),
+        _ => return Err(Errno::Notdir),
+    };
+    if entries.contains_key(&new_entry_name) {
+        return Err(Errno::Exist);
+    }
+
+    {
+        let mut guard = source_fd.inode.write();
+        match guard.deref_mut() {
+            Kind::File {
+                handle: Some(handle),
+                path,
+                ..
+            } => {
+                let handle = handle.read().unwrap();
+                state
+                    .fs
+                    .root_fs
+                    .link_tmpfile(handle.as_ref(), &new_file_path)
+                    .map_err(fs_error_into_wasi_err)?;
+                *path = new_file_path;
+            }
+            _ => return Err(Errno::Inval),
+        }
+    }
+
+    *source_fd.inode.name.write().unwrap() = new_entry_name.clone().into();
+    entries.insert(new_entry_name, source_fd.inode.clone());
+
+    Ok(())
+}
@@ -23,6 +23,7 @@ pub fn fd_pipe<M: MemorySize>(
     ro_read_fd: WasmPtr<WasiFd, M>,
     ro_write_fd: WasmPtr<WasiFd, M>,
 ) -> Result<Errno, WasiError> {
+    ctx.data().process.count_syscall(Syscall::FdPipe);
     WasiEnv::do_pending_operations(&mut ctx)?;
 
     let (read_fd, write_fd) = wasi_try_ok!(fd_pipe_internal(&mut ctx, None, None));
@@ -78,6 +78,7 @@ pub fn futex_wait<M: MemorySize + 'static>(
     timeout: WasmPtr<OptionTimestamp, M>,
     ret_woken: WasmPtr<Bool, M>,
 ) -> Result<Errno, WasiError> {
+    ctx.data().process.count_syscall(Syscall::FutexWait);
     WasiEnv::do_pending_operations(&mut ctx)?;
 
     futex_wait_internal(ctx, futex_ptr, expected, timeout, ret_woken)
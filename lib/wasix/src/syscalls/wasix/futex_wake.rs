@@ -14,6 +14,7 @@ pub fn futex_wake<M: MemorySize>(
     futex_ptr: WasmPtr<u32, M>,
     ret_woken: WasmPtr<Bool, M>,
 ) -> Result<Errno, WasiError> {
+    ctx.data().process.count_syscall(Syscall::FutexWake);
     WasiEnv::do_pending_operations(&mut ctx)?;
 
     let env = ctx.data();
@@ -11,6 +11,7 @@ pub fn getcwd<M: MemorySize>(
     path: WasmPtr<u8, M>,
     path_len: WasmPtr<M::Offset, M>,
 ) -> Errno {
+    ctx.data().process.count_syscall(Syscall::Getcwd);
     let env = ctx.data();
     let (memory, mut state, inodes) = unsafe { env.get_memory_and_wasi_state_and_inodes(&ctx, 0) };
 
@@ -0,0 +1,38 @@
+use super::*;
+use crate::{runtime::kv, syscalls::*};
+
+/// ### `kv_delete()`
+/// Removes a key from the runtime's key-value store.
+///
+/// Returns ENOENT if the key isn't set, ENOTCAPABLE if the process isn't
+/// allowed to use the store, and ENOTSUP if the runtime doesn't have one.
+///
+/// ## Parameters
+///
+/// * `key` - The key to remove
+#[instrument(level = "trace", skip_all, ret)]
+pub fn kv_delete<M: MemorySize>(
+    mut ctx: FunctionEnvMut<'_, WasiEnv>,
+    key: WasmPtr<u8, M>,
+    key_len: M::Offset,
+) -> Result<Errno, WasiError> {
+    ctx.data().process.count_syscall(Syscall::KvDelete);
+    WasiEnv::do_pending_operations(&mut ctx)?;
+
+    let env = ctx.data();
+    let (backend, namespace) = wasi_try_ok!(kv::for_env(env));
+    let memory = unsafe { env.memory_view(&ctx) };
+    let key = wasi_try_mem_ok!(key
+        .slice(&memory, key_len)
+        .and_then(|key| key.read_to_vec()));
+
+    let deleted = wasi_try_ok!(__asyncify_light(env, None, async {
+        backend.delete(&namespace, &key).await.map_err(Errno::from)
+    })?);
+
+    Ok(if deleted {
+        Errno::Success
+    } else {
+        Errno::Noent
+    })
+}
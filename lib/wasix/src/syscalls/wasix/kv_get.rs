@@ -0,0 +1,59 @@
+use super::*;
+use crate::{runtime::kv, syscalls::*};
+
+/// ### `kv_get()`
+/// Reads the value of a key from the runtime's key-value store.
+///
+/// The length of the value is always written to `value_len`. If it exceeds
+/// the size of the buffer then this function will return ERANGE, and if the
+/// key isn't set it will return ENOENT.
+///
+/// Returns ENOTCAPABLE if the process isn't allowed to use the store, and
+/// ENOTSUP if the runtime doesn't have one.
+///
+/// ## Parameters
+///
+/// * `key` - The key to look up
+/// * `value` - Buffer the value is written to
+/// * `value_len` - The size of the buffer, replaced by the length of the value
+#[instrument(level = "trace", skip_all, fields(max_value_len = field::Empty), ret)]
+pub fn kv_get<M: MemorySize>(
+    mut ctx: FunctionEnvMut<'_, WasiEnv>,
+    key: WasmPtr<u8, M>,
+    key_len: M::Offset,
+    value: WasmPtr<u8, M>,
+    value_len: WasmPtr<M::Offset, M>,
+) -> Result<Errno, WasiError> {
+    ctx.data().process.count_syscall(Syscall::KvGet);
+    WasiEnv::do_pending_operations(&mut ctx)?;
+
+    let env = ctx.data();
+    let (backend, namespace) = wasi_try_ok!(kv::for_env(env));
+    let memory = unsafe { env.memory_view(&ctx) };
+    let key = wasi_try_mem_ok!(key
+        .slice(&memory, key_len)
+        .and_then(|key| key.read_to_vec()));
+
+    let found = wasi_try_ok!(__asyncify_light(env, None, async {
+        backend.get(&namespace, &key).await.map_err(Errno::from)
+    })?);
+    let Some(found) = found else {
+        return Ok(Errno::Noent);
+    };
+
+    let memory = unsafe { env.memory_view(&ctx) };
+    let max_value_len = wasi_try_mem_ok!(value_len.read(&memory));
+    let max_value_len64: u64 = max_value_len.into();
+    Span::current().record("max_value_len", max_value_len64);
+
+    let len = wasi_try_ok!(to_offset::<M>(found.len()));
+    wasi_try_mem_ok!(value_len.write(&memory, len));
+    if found.len() as u64 > max_value_len64 {
+        return Ok(Errno::Range);
+    }
+
+    let slice = wasi_try_mem_ok!(value.slice(&memory, len));
+    wasi_try_mem_ok!(slice.write_slice(&found));
+
+    Ok(Errno::Success)
+}
@@ -0,0 +1,45 @@
+use super::*;
+use crate::{runtime::kv, syscalls::*};
+
+/// ### `kv_put()`
+/// Sets the value of a key in the runtime's key-value store, replacing any
+/// previous value.
+///
+/// Returns ENOSPC if storing the value would exceed the store's quota,
+/// ENOTCAPABLE if the process isn't allowed to use the store, and ENOTSUP if
+/// the runtime doesn't have one.
+///
+/// ## Parameters
+///
+/// * `key` - The key to set
+/// * `value` - The new value
+#[instrument(level = "trace", skip_all, ret)]
+pub fn kv_put<M: MemorySize>(
+    mut ctx: FunctionEnvMut<'_, WasiEnv>,
+    key: WasmPtr<u8, M>,
+    key_len: M::Offset,
+    value: WasmPtr<u8, M>,
+    value_len: M::Offset,
+) -> Result<Errno, WasiError> {
+    ctx.data().process.count_syscall(Syscall::KvPut);
+    WasiEnv::do_pending_operations(&mut ctx)?;
+
+    let env = ctx.data();
+    let (backend, namespace) = wasi_try_ok!(kv::for_env(env));
+    let memory = unsafe { env.memory_view(&ctx) };
+    let key = wasi_try_mem_ok!(key
+        .slice(&memory, key_len)
+        .and_then(|key| key.read_to_vec()));
+    let value = wasi_try_mem_ok!(value
+        .slice(&memory, value_len)
+        .and_then(|value| value.read_to_vec()));
+
+    wasi_try_ok!(__asyncify_light(env, None, async {
+        backend
+            .put(&namespace, &key, &value)
+            .await
+            .map_err(Errno::from)
+    })?);
+
+    Ok(Errno::Success)
+}
@@ -0,0 +1,82 @@
+use super::*;
+use crate::{runtime::kv, syscalls::*};
+
+/// ### `kv_scan()`
+/// Lists the keys in the runtime's key-value store that start with a prefix,
+/// in lexicographic order.
+///
+/// Keys are written to `buf` one after the other, each prefixed with its
+/// length as a little-endian `u32`, for as many as fit. The number of bytes
+/// used is written to `buf_len` and the cookie to continue from to
+/// `ret_cookie`, so the keys can be listed in several calls starting with a
+/// cookie of `0`. All the keys have been listed once no bytes are used.
+///
+/// If the next key doesn't fit in the buffer at all, the size it needs is
+/// written to `buf_len` and this function will return ERANGE.
+///
+/// Returns ENOTCAPABLE if the process isn't allowed to use the store, and
+/// ENOTSUP if the runtime doesn't have one.
+///
+/// ## Parameters
+///
+/// * `prefix` - The prefix of the keys to list
+/// * `cookie` - Where to continue listing from
+/// * `buf` - Buffer the keys are written to
+/// * `buf_len` - The size of the buffer, replaced by the number of bytes used
+/// * `ret_cookie` - Where to continue listing from in the next call
+#[instrument(level = "trace", skip_all, fields(%cookie, keys = field::Empty), ret)]
+pub fn kv_scan<M: MemorySize>(
+    mut ctx: FunctionEnvMut<'_, WasiEnv>,
+    prefix: WasmPtr<u8, M>,
+    prefix_len: M::Offset,
+    cookie: u64,
+    buf: WasmPtr<u8, M>,
+    buf_len: WasmPtr<M::Offset, M>,
+    ret_cookie: WasmPtr<u64, M>,
+) -> Result<Errno, WasiError> {
+    ctx.data().process.count_syscall(Syscall::KvScan);
+    WasiEnv::do_pending_operations(&mut ctx)?;
+
+    let env = ctx.data();
+    let (backend, namespace) = wasi_try_ok!(kv::for_env(env));
+    let memory = unsafe { env.memory_view(&ctx) };
+    let prefix = wasi_try_mem_ok!(prefix
+        .slice(&memory, prefix_len)
+        .and_then(|prefix| prefix.read_to_vec()));
+
+    let keys = wasi_try_ok!(__asyncify_light(env, None, async {
+        backend.list(&namespace, &prefix).await.map_err(Errno::from)
+    })?);
+
+    let memory = unsafe { env.memory_view(&ctx) };
+    let max_buf_len: u64 = wasi_try_mem_ok!(buf_len.read(&memory)).into();
+
+    let mut entries = Vec::new();
+    let mut count: u64 = 0;
+    for key in keys.iter().skip(cookie.try_into().unwrap_or(usize::MAX)) {
+        let Ok(len) = u32::try_from(key.len()) else {
+            return Ok(Errno::Overflow);
+        };
+        let entry_len = 4 + key.len();
+        if (entries.len() + entry_len) as u64 > max_buf_len {
+            if count == 0 {
+                let needed = wasi_try_ok!(to_offset::<M>(entry_len));
+                wasi_try_mem_ok!(buf_len.write(&memory, needed));
+                return Ok(Errno::Range);
+            }
+            break;
+        }
+        entries.extend_from_slice(&len.to_le_bytes());
+        entries.extend_from_slice(key);
+        count += 1;
+    }
+    Span::current().record("keys", count);
+
+    let used = wasi_try_ok!(to_offset::<M>(entries.len()));
+    let slice = wasi_try_mem_ok!(buf.slice(&memory, used));
+    wasi_try_mem_ok!(slice.write_slice(&entries));
+    wasi_try_mem_ok!(buf_len.write(&memory, used));
+    wasi_try_mem_ok!(ret_cookie.write(&memory, cookie + count));
+
+    Ok(Errno::Success)
+}
@@ -1,9 +1,12 @@
+mod archive_extract;
+mod archive_extract_poll;
 mod call_dynamic;
 mod callback_signal;
 mod chdir;
 mod closure_allocate;
 mod closure_free;
 mod closure_prepare;
+mod cpu_features;
 mod dl_invalid_handle;
 mod dlopen;
 mod dlsym;
@@ -13,12 +16,18 @@ mod epoll_wait;
 mod fd_dup2;
 mod fd_fdflags_get;
 mod fd_fdflags_set;
+mod fd_link;
 mod fd_pipe;
 mod futex_wait;
 mod futex_wake;
 mod futex_wake_all;
 mod getcwd;
+mod kv_delete;
+mod kv_get;
+mod kv_put;
+mod kv_scan;
 mod path_open2;
+mod path_open_tmpfile;
 mod port_addr_add;
 mod port_addr_clear;
 mod port_addr_list;
@@ -32,12 +41,14 @@ mod port_route_clear;
 mod port_route_list;
 mod port_route_remove;
 mod port_unbridge;
+mod proc_daemonize;
 mod proc_exec;
 mod proc_exec2;
 mod proc_exec3;
 mod proc_fork;
 mod proc_id;
 mod proc_join;
+mod proc_package_metadata;
 mod proc_parent;
 mod proc_signal;
 mod proc_signals_get;
@@ -53,6 +64,7 @@ mod sock_addr_local;
 mod sock_addr_peer;
 mod sock_bind;
 mod sock_connect;
+mod sock_get_opt_bytes;
 mod sock_get_opt_flag;
 mod sock_get_opt_size;
 mod sock_get_opt_time;
@@ -68,11 +80,13 @@ mod sock_recv_from;
 mod sock_send;
 mod sock_send_file;
 mod sock_send_to;
+mod sock_set_opt_bytes;
 mod sock_set_opt_flag;
 mod sock_set_opt_size;
 mod sock_set_opt_time;
 mod sock_shutdown;
 mod sock_status;
+mod sock_tls_upgrade;
 mod stack_checkpoint;
 mod stack_restore;
 mod thread_exit;
@@ -85,12 +99,15 @@ mod thread_spawn;
 mod tty_get;
 mod tty_set;
 
+pub use archive_extract::*;
+pub use archive_extract_poll::*;
 pub use call_dynamic::*;
 pub use callback_signal::*;
 pub use chdir::*;
 pub use closure_allocate::*;
 pub use closure_free::*;
 pub use closure_prepare::*;
+pub use cpu_features::*;
 pub use dl_invalid_handle::*;
 pub use dlopen::*;
 pub use dlsym::*;
@@ -100,12 +117,18 @@ pub use epoll_wait::*;
 pub use fd_dup2::*;
 pub use fd_fdflags_get::*;
 pub use fd_fdflags_set::*;
+pub use fd_link::*;
 pub use fd_pipe::*;
 pub use futex_wait::*;
 pub use futex_wake::*;
 pub use futex_wake_all::*;
 pub use getcwd::*;
+pub use kv_delete::*;
+pub use kv_get::*;
+pub use kv_put::*;
+pub use kv_scan::*;
 pub use path_open2::*;
+pub use path_open_tmpfile::*;
 pub use port_addr_add::*;
 pub use port_addr_clear::*;
 pub use port_addr_list::*;
@@ -119,12 +142,14 @@ pub use port_route_clear::*;
 pub use port_route_list::*;
 pub use port_route_remove::*;
 pub use port_unbridge::*;
+pub use proc_daemonize::*;
 pub use proc_exec::*;
 pub use proc_exec2::*;
 pub use proc_exec3::*;
 pub use proc_fork::*;
 pub use proc_id::*;
 pub use proc_join::*;
+pub use proc_package_metadata::*;
 pub use proc_parent::*;
 pub use proc_signal::*;
 pub use proc_signals_get::*;
@@ -140,6 +165,7 @@ pub use sock_addr_local::*;
 pub use sock_addr_peer::*;
 pub use sock_bind::*;
 pub use sock_connect::*;
+pub use sock_get_opt_bytes::*;
 pub use sock_get_opt_flag::*;
 pub use sock_get_opt_size::*;
 pub use sock_get_opt_time::*;
@@ -155,11 +181,13 @@ pub use sock_recv_from::*;
 pub use sock_send::*;
 pub use sock_send_file::*;
 pub use sock_send_to::*;
+pub use sock_set_opt_bytes::*;
 pub use sock_set_opt_flag::*;
 pub use sock_set_opt_size::*;
 pub use sock_set_opt_time::*;
 pub use sock_shutdown::*;
 pub use sock_status::*;
+pub use sock_tls_upgrade::*;
 pub use stack_checkpoint::*;
 pub use stack_restore::*;
 pub use thread_exit::*;
@@ -39,6 +39,7 @@ pub fn path_open2<M: MemorySize>(
     fd_flags: Fdflagsext,
     fd: WasmPtr<WasiFd, M>,
 ) -> Result<Errno, WasiError> {
+    ctx.data().process.count_syscall(Syscall::PathOpen2);
     WasiEnv::do_pending_operations(&mut ctx)?;
 
     if dirflags & __WASI_LOOKUP_SYMLINK_FOLLOW != 0 {
@@ -128,12 +129,21 @@ pub(crate) fn path_open_internal(
     let inodes = &state.inodes;
 
     let path_arg = std::path::PathBuf::from(&path);
+    let access = FsAccess {
+        read: fs_rights_base.intersects(Rights::FD_READ | Rights::FD_READDIR),
+        write: fs_rights_base.contains(Rights::FD_WRITE) || o_flags.contains(Oflags::TRUNC),
+        create: o_flags.contains(Oflags::CREATE),
+    };
     let maybe_inode = state.fs.get_inode_at_path(
         inodes,
         dirfd,
         path,
         dirflags & __WASI_LOOKUP_SYMLINK_FOLLOW != 0,
+        access,
     );
+    if let Err(err @ (Errno::Notcapable | Errno::Rofs)) = maybe_inode {
+        return Ok(Err(err));
+    }
 
     let working_dir = wasi_try_ok_ok!(state.fs.get_fd(dirfd));
     let working_dir_rights_inheriting = working_dir.inner.rights_inheriting;
@@ -251,9 +261,11 @@ pub(crate) fn path_open_internal(
                 }
                 // TODO: I strongly suspect that assigning the handle unconditionally
                 // breaks opening the same file multiple times.
-                *handle = Some(Arc::new(std::sync::RwLock::new(wasi_try_ok_ok!(
-                    open_options.open(&path).map_err(fs_error_into_wasi_err)
-                ))));
+                if !state.fs.is_process_file(&inode) {
+                    *handle = Some(Arc::new(std::sync::RwLock::new(wasi_try_ok_ok!(
+                        open_options.open(&path).map_err(fs_error_into_wasi_err)
+                    ))));
+                }
 
                 if let Some(handle) = handle {
                     let handle = handle.read().unwrap();
@@ -318,7 +330,8 @@ pub(crate) fn path_open_internal(
                     inodes,
                     dirfd,
                     &path_arg,
-                    dirflags & __WASI_LOOKUP_SYMLINK_FOLLOW != 0
+                    dirflags & __WASI_LOOKUP_SYMLINK_FOLLOW != 0,
+                    access
                 ));
             let new_file_host_path = {
                 let guard = parent_inode.read();
@@ -340,11 +353,13 @@ pub(crate) fn path_open_internal(
             // todo: extra check that opening with write access is okay
             let handle = {
                 // We set create_new because the path already didn't resolve to an existing file,
-                // so it must be created.
+                // so it must be created. Appending is left to `fd_write` (which has to handle
+                // `fd_fdstat_set_flags` turning it on later anyway), so that the handle can
+                // still seek for reads. The handle still needs to be writable for that.
                 let open_options = open_options
                     .read(minimum_rights.read)
-                    .append(minimum_rights.append)
-                    .write(minimum_rights.write)
+                    .append(false)
+                    .write(minimum_rights.write || minimum_rights.append)
                     .create_new(true);
 
                 if minimum_rights.read {
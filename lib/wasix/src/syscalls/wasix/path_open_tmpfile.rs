@@ -0,0 +1,121 @@
+use std::sync::atomic::AtomicUsize;
+
+use super::*;
+use crate::syscalls::*;
+
+// Used to make the names of unnamed files unique. This is necessary since we
+// use a hash of the path to calculate inode numbers.
+static TMPFILE_NUMBER: AtomicUsize = AtomicUsize::new(0);
+
+/// ### `path_open_tmpfile()`
+/// Creates an unnamed temporary file in a directory (like `O_TMPFILE`)
+///
+/// The file can be read and written through the returned file descriptor,
+/// and is deleted once the last file descriptor referring to it is closed,
+/// unless it is given a name with `fd_link()` first.
+/// Inputs:
+/// - `Fd dirfd`
+///     The fd corresponding to the directory that `path` is relative to
+/// - `LookupFlags dirflags`
+///     Flags specifying how the path will be resolved
+/// - `char *path`
+///     The path of the directory the file is created in
+/// - `u32 path_len`
+///     The length of the `path` string
+/// - `Rights fs_rights_inheriting`
+///     The rights of file descriptors derived from the created file descriptor
+/// - `Fdflags fs_flags`
+///     The flags of the file descriptor
+/// Output:
+/// - `Fd* fd`
+///     The new file descriptor
+/// Possible Errors:
+/// - `Errno::Access`, `Errno::Badf`, `Errno::Noent`, `Errno::Notdir`, `Errno::Nospc`,
+///   `Errno::Notsup` (the file system doesn't support unnamed files)
+#[instrument(level = "trace", skip_all, fields(%dirfd, path = field::Empty, ret_fd = field::Empty), ret)]
+pub fn path_open_tmpfile<M: MemorySize>(
+    mut ctx: FunctionEnvMut<'_, WasiEnv>,
+    dirfd: WasiFd,
+    dirflags: LookupFlags,
+    path: WasmPtr<u8, M>,
+    path_len: M::Offset,
+    fs_rights_inheriting: Rights,
+    fs_flags: Fdflags,
+    fd: WasmPtr<WasiFd, M>,
+) -> Result<Errno, WasiError> {
+    ctx.data().process.count_syscall(Syscall::PathOpenTmpfile);
+    WasiEnv::do_pending_operations(&mut ctx)?;
+
+    let env = ctx.data();
+    let memory = unsafe { env.memory_view(&ctx) };
+    let path_string = unsafe { get_input_str_ok!(&memory, path, path_len) };
+    Span::current().record("path", path_string.as_str());
+
+    let out_fd = wasi_try_ok!(path_open_tmpfile_internal(
+        env,
+        dirfd,
+        dirflags,
+        &path_string,
+        fs_rights_inheriting,
+        fs_flags,
+    ));
+    Span::current().record("ret_fd", out_fd);
+
+    wasi_try_mem_ok!(fd.write(&memory, out_fd));
+
+    Ok(Errno::Success)
+}
+
+pub(crate) fn path_open_tmpfile_internal(
+    env: &WasiEnv,
+    dirfd: WasiFd,
+    dirflags: LookupFlags,
+    path: &str,
+    fs_rights_inheriting: Rights,
+    fs_flags: Fdflags,
+) -> Result<WasiFd, Errno> {
+    let state = env.state.deref();
+    let inodes = &state.inodes;
+
+    let working_dir = state.fs.get_fd(dirfd)?;
+    if !working_dir.inner.rights.contains(Rights::PATH_OPEN) {
+        return Err(Errno::Access);
+    }
+
+    let dir_inode = state.fs.get_inode_at_path(
+        inodes,
+        dirfd,
+        path,
+        dirflags & __WASI_LOOKUP_SYMLINK_FOLLOW != 0,
+        FsAccess::CREATE,
+    )?;
+    let dir_path = match dir_inode.read().deref() {
+        Kind::Dir { path, .. } => path.clone(),
+        Kind::Root { .. } => std::path::PathBuf::from("/"),
+        _ => return Err(Errno::Notdir),
+    };
+
+    let handle = state
+        .fs
+        .root_fs
+        .create_tmpfile(&dir_path)
+        .map_err(fs_error_into_wasi_err)?;
+
+    // The file has no directory entry, so nothing can look it up by this name
+    let name = format!(".tmpfile-{}", TMPFILE_NUMBER.fetch_add(1, Ordering::SeqCst));
+    let kind = Kind::File {
+        handle: Some(Arc::new(std::sync::RwLock::new(handle))),
+        path: dir_path.join(&name),
+        fd: None,
+    };
+    let inode = state.fs.create_inode(inodes, kind, false, name)?;
+
+    state.fs.create_fd(
+        working_dir.inner.rights_inheriting,
+        fs_rights_inheriting,
+        fs_flags,
+        Fdflagsext::empty(),
+        Fd::READ | Fd::WRITE | Fd::CREATE,
+        inode,
+    )
+}
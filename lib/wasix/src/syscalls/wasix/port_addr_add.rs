@@ -14,6 +14,7 @@ pub fn port_addr_add<M: MemorySize>(
     mut ctx: FunctionEnvMut<'_, WasiEnv>,
     ip: WasmPtr<__wasi_cidr_t, M>,
 ) -> Result<Errno, WasiError> {
+    ctx.data().process.count_syscall(Syscall::PortAddrAdd);
     WasiEnv::do_pending_operations(&mut ctx)?;
 
     let env = ctx.data();
@@ -5,6 +5,7 @@ use crate::syscalls::*;
 /// Clears all the addresses on the local port
 #[instrument(level = "trace", skip_all, ret)]
 pub fn port_addr_clear(mut ctx: FunctionEnvMut<'_, WasiEnv>) -> Result<Errno, WasiError> {
+    ctx.data().process.count_syscall(Syscall::PortAddrClear);
     WasiEnv::do_pending_operations(&mut ctx)?;
 
     wasi_try_ok!(port_addr_clear_internal(&mut ctx)?);
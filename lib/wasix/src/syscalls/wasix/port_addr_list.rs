@@ -20,6 +20,7 @@ pub fn port_addr_list<M: MemorySize>(
     addrs_ptr: WasmPtr<__wasi_cidr_t, M>,
     naddrs_ptr: WasmPtr<M::Offset, M>,
 ) -> Result<Errno, WasiError> {
+    ctx.data().process.count_syscall(Syscall::PortAddrList);
     WasiEnv::do_pending_operations(&mut ctx)?;
 
     let mut env = ctx.data();
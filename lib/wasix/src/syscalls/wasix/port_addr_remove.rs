@@ -12,6 +12,7 @@ pub fn port_addr_remove<M: MemorySize>(
     mut ctx: FunctionEnvMut<'_, WasiEnv>,
     ip: WasmPtr<__wasi_addr_t, M>,
 ) -> Result<Errno, WasiError> {
+    ctx.data().process.count_syscall(Syscall::PortAddrRemove);
     WasiEnv::do_pending_operations(&mut ctx)?;
 
     let env = ctx.data();
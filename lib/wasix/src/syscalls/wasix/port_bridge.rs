@@ -18,6 +18,7 @@ pub fn port_bridge<M: MemorySize>(
     token_len: M::Offset,
     security: Streamsecurity,
 ) -> Result<Errno, WasiError> {
+    ctx.data().process.count_syscall(Syscall::PortBridge);
     WasiEnv::do_pending_operations(&mut ctx)?;
 
     let env = ctx.data();
@@ -8,6 +8,7 @@ pub fn port_mac<M: MemorySize>(
     mut ctx: FunctionEnvMut<'_, WasiEnv>,
     ret_mac: WasmPtr<__wasi_hardwareaddress_t, M>,
 ) -> Result<Errno, WasiError> {
+    ctx.data().process.count_syscall(Syscall::PortMac);
     WasiEnv::do_pending_operations(&mut ctx)?;
 
     let mut env = ctx.data();
@@ -13,6 +13,7 @@ pub fn port_route_add<M: MemorySize>(
     preferred_until: WasmPtr<OptionTimestamp, M>,
     expires_at: WasmPtr<OptionTimestamp, M>,
 ) -> Result<Errno, WasiError> {
+    ctx.data().process.count_syscall(Syscall::PortRouteAdd);
     WasiEnv::do_pending_operations(&mut ctx)?;
 
     let env = ctx.data();
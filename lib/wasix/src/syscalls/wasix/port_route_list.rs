@@ -16,6 +16,7 @@ pub fn port_route_list<M: MemorySize>(
     routes_ptr: WasmPtr<Route, M>,
     nroutes_ptr: WasmPtr<M::Offset, M>,
 ) -> Result<Errno, WasiError> {
+    ctx.data().process.count_syscall(Syscall::PortRouteList);
     WasiEnv::do_pending_operations(&mut ctx)?;
 
     let mut env = ctx.data();
@@ -8,6 +8,7 @@ pub fn port_route_remove<M: MemorySize>(
     mut ctx: FunctionEnvMut<'_, WasiEnv>,
     ip: WasmPtr<__wasi_addr_t, M>,
 ) -> Result<Errno, WasiError> {
+    ctx.data().process.count_syscall(Syscall::PortRouteRemove);
     WasiEnv::do_pending_operations(&mut ctx)?;
 
     let env = ctx.data();
@@ -5,6 +5,7 @@ use crate::syscalls::*;
 /// Disconnects from a remote network
 #[instrument(level = "trace", skip_all, ret)]
 pub fn port_unbridge(mut ctx: FunctionEnvMut<'_, WasiEnv>) -> Result<Errno, WasiError> {
+    ctx.data().process.count_syscall(Syscall::PortUnbridge);
     WasiEnv::do_pending_operations(&mut ctx)?;
 
     wasi_try_ok!(port_unbridge_internal(&mut ctx)?);
@@ -0,0 +1,39 @@
+use super::*;
+use crate::syscalls::*;
+
+/// ### `proc_daemonize()`
+/// Turns the current process into a daemon that keeps running after the
+/// process that started it exits.
+///
+/// The process becomes the leader of a new session and process group (whose
+/// ID is the process ID), is no longer a child of its parent and has its
+/// stdin, stdout and stderr replaced with `/dev/null`. Log files can be
+/// put in their place afterwards with `fd_renumber`. The daemon runs until
+/// it exits or is terminated through the control plane.
+///
+/// Returns EPERM if the process already leads a session.
+///
+/// ## Parameters
+///
+/// * `ret_sid` - The ID of the new session
+#[instrument(level = "trace", skip_all, fields(sid = field::Empty), ret)]
+pub fn proc_daemonize<M: MemorySize>(
+    mut ctx: FunctionEnvMut<'_, WasiEnv>,
+    ret_sid: WasmPtr<Pid, M>,
+) -> Errno {
+    ctx.data().process.count_syscall(Syscall::ProcDaemonize);
+    let handle = wasi_try!(ctx.data().process.detach());
+    let sid = ctx.data().process.sid();
+    Span::current().record("sid", sid.raw());
+
+    // The daemon now keeps its own main thread alive
+    ctx.data_mut().owned_handles.push(handle);
+
+    let env = ctx.data();
+    let (state, inodes) = env.get_wasi_state_and_inodes();
+    state.fs.detach_stdio(inodes);
+
+    let memory = unsafe { env.memory_view(&ctx) };
+    wasi_try_mem!(ret_sid.write(&memory, sid.raw() as Pid));
+    Errno::Success
+}
@@ -25,6 +25,7 @@ pub fn proc_exec<M: MemorySize>(
     args: WasmPtr<u8, M>,
     args_len: M::Offset,
 ) -> Result<(), WasiError> {
+    ctx.data().process.count_syscall(Syscall::ProcExec);
     proc_exec2(
         ctx,
         name,
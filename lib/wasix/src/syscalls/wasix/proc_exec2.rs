@@ -28,6 +28,7 @@ pub fn proc_exec2<M: MemorySize>(
     envs: WasmPtr<u8, M>,
     envs_len: M::Offset,
 ) -> Result<(), WasiError> {
+    ctx.data().process.count_syscall(Syscall::ProcExec2);
     match proc_exec3(
         ctx,
         name,
@@ -32,6 +32,7 @@ pub fn proc_exec3<M: MemorySize>(
     path: WasmPtr<u8, M>,
     path_len: M::Offset,
 ) -> Result<Errno, WasiError> {
+    ctx.data().process.count_syscall(Syscall::ProcExec3);
     WasiEnv::do_pending_operations(&mut ctx)?;
 
     // If we were just restored the stack then we were woken after a deep sleep
@@ -124,7 +125,7 @@ pub fn proc_exec3<M: MemorySize>(
         }
     };
 
-    let new_store = ctx.data().runtime.new_store();
+    let new_store = ctx.data().new_store();
 
     // If we are in a vfork we need to first spawn a subprocess of this type
     // with the forked WasiEnv, then do a longjmp back to the vfork point.
@@ -149,6 +150,12 @@ pub fn proc_exec3<M: MemorySize>(
         wasi_env.owned_handles.push(vfork.handle.clone());
         _prepare_wasi(&mut wasi_env, Some(args), envs, None);
 
+        // The spawn policy gets a say before the program is looked up
+        let name = ctx
+            .data()
+            .bin_factory
+            .check_spawn(name, &wasi_env, child_pid);
+
         // Recrod the stack offsets before we give up ownership of the wasi_env
         let stack_lower = wasi_env.layout.stack_lower;
         let stack_upper = wasi_env.layout.stack_upper;
@@ -156,7 +163,11 @@ pub fn proc_exec3<M: MemorySize>(
         // Spawn a new process with this current execution environment
         let mut err_exit_code: ExitCode = Errno::Success.into();
 
-        let spawn_result = {
+        let spawn_result = 'spawn: {
+            let name = match name {
+                Ok(name) => name,
+                Err(err) => break 'spawn Err(err),
+            };
             let bin_factory = Box::new(ctx.data().bin_factory.clone());
             let tasks = wasi_env.tasks().clone();
 
@@ -238,6 +249,13 @@ pub fn proc_exec3<M: MemorySize>(
         let mut wasi_env = ctx.data().clone();
         _prepare_wasi(&mut wasi_env, Some(args), envs, None);
 
+        // The spawn policy gets a say before the program is looked up
+        let name =
+            wasi_try_ok!(ctx
+                .data()
+                .bin_factory
+                .check_spawn(name, &wasi_env, ctx.data().pid()));
+
         // Get a reference to the runtime
         let bin_factory = ctx.data().bin_factory.clone();
         let tasks = wasi_env.tasks().clone();
@@ -313,7 +331,7 @@ pub(crate) fn find_executable_in_path<'a>(
     let mut encountered_eaccess = false;
     for p in path {
         let full_path = format!("{}/{}", p.trim_end_matches('/'), file_name);
-        match fs.get_inode_at_path(inodes, VIRTUAL_ROOT_FD, &full_path, true) {
+        match fs.get_inode_at_path(inodes, VIRTUAL_ROOT_FD, &full_path, true, FsAccess::READ) {
             Ok(_) => return FindExecutableResult::Found(full_path),
             Err(Errno::Access) => encountered_eaccess = true,
             Err(_) => (),
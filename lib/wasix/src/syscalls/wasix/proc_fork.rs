@@ -25,6 +25,7 @@ pub fn proc_fork<M: MemorySize>(
     mut copy_memory: Bool,
     pid_ptr: WasmPtr<Pid, M>,
 ) -> Result<Errno, WasiError> {
+    ctx.data().process.count_syscall(Syscall::ProcFork);
     WasiEnv::do_pending_operations(&mut ctx)?;
 
     wasi_try_ok!(ctx.data().ensure_static_module().map_err(|_| {
@@ -57,8 +58,7 @@ pub fn proc_fork<M: MemorySize>(
         Ok(p) => p,
         Err(err) => {
             debug!("could not fork process: {err}");
-            // TODO: evaluate the appropriate error code, document it in the spec.
-            return Ok(Errno::Perm);
+            return Ok(err.into());
         }
     };
     let child_pid = child_env.process.pid();
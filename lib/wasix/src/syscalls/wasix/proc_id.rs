@@ -5,6 +5,7 @@ use crate::syscalls::*;
 /// Returns the handle of the current process
 #[instrument(level = "trace", skip_all, fields(pid = field::Empty), ret)]
 pub fn proc_id<M: MemorySize>(ctx: FunctionEnvMut<'_, WasiEnv>, ret_pid: WasmPtr<Pid, M>) -> Errno {
+    ctx.data().process.count_syscall(Syscall::ProcId);
     let env = ctx.data();
     let memory = unsafe { env.memory_view(&ctx) };
 
@@ -27,6 +27,7 @@ pub fn proc_join<M: MemorySize + 'static>(
     flags: JoinFlags,
     status_ptr: WasmPtr<JoinStatus, M>,
 ) -> Result<Errno, WasiError> {
+    ctx.data().process.count_syscall(Syscall::ProcJoin);
     WasiEnv::do_pending_operations(&mut ctx)?;
 
     proc_join_internal(ctx, pid_ptr, flags, status_ptr)
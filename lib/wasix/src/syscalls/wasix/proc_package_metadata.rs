@@ -0,0 +1,42 @@
+use super::*;
+use crate::syscalls::*;
+
+/// ### `proc_package_metadata()`
+/// Returns information about the package the current process was started
+/// from, encoded as JSON (the same contents as `/etc/wasmer/package.json`).
+///
+/// The length of the metadata is always written to `buf_len`. If it exceeds
+/// the size of the buffer then this function will return ERANGE, and if the
+/// process was not started from a package it will return ENOENT.
+#[instrument(level = "trace", skip_all, fields(max_buf_len = field::Empty), ret)]
+pub fn proc_package_metadata<M: MemorySize>(
+    ctx: FunctionEnvMut<'_, WasiEnv>,
+    buf: WasmPtr<u8, M>,
+    buf_len: WasmPtr<M::Offset, M>,
+) -> Errno {
+    ctx.data()
+        .process
+        .count_syscall(Syscall::ProcPackageMetadata);
+    let env = ctx.data();
+    let memory = unsafe { env.memory_view(&ctx) };
+
+    let metadata = match env.state.package.lock().unwrap().as_ref() {
+        Some(metadata) => metadata.to_json(),
+        None => return Errno::Noent,
+    };
+
+    let max_buf_len = wasi_try_mem!(buf_len.read(&memory));
+    let max_buf_len64: u64 = max_buf_len.into();
+    Span::current().record("max_buf_len", max_buf_len64);
+
+    wasi_try_mem!(buf_len.write(&memory, wasi_try!(to_offset::<M>(metadata.len()))));
+    if metadata.len() as u64 > max_buf_len64 {
+        return Errno::Range;
+    }
+
+    let len = wasi_try!(to_offset::<M>(metadata.len()));
+    let slice = wasi_try_mem!(buf.slice(&memory, len));
+    wasi_try_mem!(slice.write_slice(&metadata));
+
+    Errno::Success
+}
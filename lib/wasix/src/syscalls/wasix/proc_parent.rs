@@ -9,6 +9,7 @@ pub fn proc_parent<M: MemorySize>(
     pid: Pid,
     ret_parent: WasmPtr<Pid, M>,
 ) -> Errno {
+    ctx.data().process.count_syscall(Syscall::ProcParent);
     let env = ctx.data();
     let pid: WasiProcessId = pid.into();
     if pid == env.process.pid() {
@@ -2,27 +2,29 @@ use super::*;
 use crate::syscalls::*;
 
 /// ### `proc_signal()`
-/// Sends a signal to a child process
+/// Sends a signal to a process on the same control plane
 ///
 /// ## Parameters
 ///
-/// * `pid` - Handle of the child process to wait on
-/// * `sig` - Signal to send the child process
+/// * `pid` - Handle of the process to send the signal to
+/// * `sig` - Signal to send the process, or `Signone` to only check that
+///   the process exists
+///
+/// Returns `Errno::Srch` if the process does not exist (or has exited),
+/// and `Errno::Perm` if the caller is not allowed to signal it.
 #[instrument(level = "trace", skip_all, fields(%pid, ?sig), ret)]
 pub fn proc_signal(
     mut ctx: FunctionEnvMut<'_, WasiEnv>,
     pid: Pid,
     sig: Signal,
 ) -> Result<Errno, WasiError> {
-    let process = {
-        let pid: WasiProcessId = pid.into();
-        ctx.data().control_plane.get_process(pid)
-    };
-    if let Some(process) = process {
-        process.signal_process(sig);
-    }
+    ctx.data().process.count_syscall(Syscall::ProcSignal);
+    let ret = ctx.data().kill(pid.into(), sig);
 
     WasiEnv::do_pending_operations(&mut ctx)?;
 
-    Ok(Errno::Success)
+    Ok(match ret {
+        Ok(()) => Errno::Success,
+        Err(err) => err,
+    })
 }
@@ -12,6 +12,7 @@ pub fn proc_signals_get<M: MemorySize>(
     mut ctx: FunctionEnvMut<'_, WasiEnv>,
     buf: WasmPtr<SignalDisposition, M>,
 ) -> Result<Errno, WasiError> {
+    ctx.data().process.count_syscall(Syscall::ProcSignalsGet);
     let env = ctx.data();
     let (memory, mut state) = unsafe { env.get_memory_and_wasi_state(&ctx, 0) };
 
@@ -6,6 +6,7 @@ use crate::syscalls::*;
 pub fn proc_snapshot<M: MemorySize>(
     mut ctx: FunctionEnvMut<'_, WasiEnv>,
 ) -> Result<Errno, WasiError> {
+    ctx.data().process.count_syscall(Syscall::ProcSnapshot);
     WasiEnv::do_pending_operations(&mut ctx)?;
 
     // If we have an Explicit trigger, process that...
@@ -40,6 +40,7 @@ pub fn proc_spawn<M: MemorySize>(
     working_dir_len: M::Offset,
     ret_handles: WasmPtr<ProcessHandles, M>,
 ) -> Result<Errno, WasiError> {
+    ctx.data().process.count_syscall(Syscall::ProcSpawn);
     WasiEnv::do_pending_operations(&mut ctx)?;
 
     let env = ctx.data();
@@ -142,6 +143,12 @@ pub fn proc_spawn_internal(
         child_env.state.fs.set_current_dir(working_dir.as_str());
     }
 
+    // The spawn policy gets a say before the program is looked up
+    let name = match env.bin_factory.check_spawn(name, &child_env, env.pid()) {
+        Ok(name) => name,
+        Err(err) => return Ok(Err(err)),
+    };
+
     // Replace the STDIO
     let (stdin, stdout, stderr) = {
         let (child_state, child_inodes) = child_env.get_wasi_state_and_inodes();
@@ -42,6 +42,7 @@ pub fn proc_spawn2<M: MemorySize>(
     path_len: M::Offset,
     ret: WasmPtr<Pid, M>,
 ) -> Result<Errno, WasiError> {
+    ctx.data().process.count_syscall(Syscall::ProcSpawn2);
     WasiEnv::do_pending_operations(&mut ctx)?;
 
     let env = ctx.data();
@@ -132,6 +133,14 @@ pub fn proc_spawn2<M: MemorySize>(
         }
     };
 
+    _prepare_wasi(&mut child_env, Some(args), envs, signals);
+
+    // The spawn policy gets a say before the program is looked up
+    let name = wasi_try_ok!(ctx
+        .data()
+        .bin_factory
+        .check_spawn(name, &child_env, ctx.data().pid()));
+
     {
         let mut inner = ctx.data().process.lock();
         inner.children.push(child_env.process.clone());
@@ -145,8 +154,6 @@ pub fn proc_spawn2<M: MemorySize>(
         .record("pid", pid.raw())
         .record("tid", tid.raw());
 
-    _prepare_wasi(&mut child_env, Some(args), envs, signals);
-
     for fd_op in fd_ops {
         wasi_try_ok!(apply_fd_op(&mut child_env, &memory, &fd_op));
     }
@@ -1,4 +1,4 @@
-use crate::{state::FunctionLookupError, WasiEnv, WasiError};
+use crate::{os::task::counters::Syscall, state::FunctionLookupError, WasiEnv, WasiError};
 use tracing::{instrument, trace};
 use wasmer::{FunctionEnvMut, MemorySize, Type, WasmPtr, WasmSlice};
 use wasmer_wasix_types::wasi::{Bool, Errno, ReflectionResult, WasmValueType};
@@ -49,6 +49,7 @@ pub fn reflect_signature<M: MemorySize>(
     result_types_len: u16,
     result: WasmPtr<ReflectionResult, M>,
 ) -> Result<Errno, WasiError> {
+    ctx.data().process.count_syscall(Syscall::ReflectSignature);
     let (env, mut store) = ctx.data_and_store_mut();
 
     let function_lookup_result = env
@@ -22,23 +22,20 @@ pub fn sock_accept<M: MemorySize>(
     fd_flags: Fdflags,
     ro_fd: WasmPtr<WasiFd, M>,
 ) -> Result<Errno, WasiError> {
+    ctx.data().process.count_syscall(Syscall::SockAccept);
     WasiEnv::do_pending_operations(&mut ctx)?;
 
     ctx = wasi_try_ok!(maybe_snapshot::<M>(ctx)?);
 
     let env = ctx.data();
-    let (memory, state, _) = unsafe { env.get_memory_and_wasi_state_and_inodes(&ctx, 0) };
-
     let nonblocking = fd_flags.contains(Fdflags::NONBLOCK);
 
-    let (fd, _, _) = wasi_try_ok!(sock_accept_internal(
-        env,
-        sock,
-        fd_flags,
-        nonblocking,
-        None
-    )?);
+    let res = sock_accept_internal(env, sock, fd_flags, nonblocking, None)?;
+    handle_interrupt(&mut ctx, &res)?;
+    let (fd, _, _) = wasi_try_ok!(res);
 
+    let env = ctx.data();
+    let (memory, state, _) = unsafe { env.get_memory_and_wasi_state_and_inodes(&ctx, 0) };
     wasi_try_mem_ok!(ro_fd.write(&memory, fd));
 
     Ok(Errno::Success)
@@ -65,20 +62,15 @@ pub fn sock_accept_v2<M: MemorySize>(
     ro_fd: WasmPtr<WasiFd, M>,
     ro_addr: WasmPtr<__wasi_addr_port_t, M>,
 ) -> Result<Errno, WasiError> {
+    ctx.data().process.count_syscall(Syscall::SockAcceptV2);
     WasiEnv::do_pending_operations(&mut ctx)?;
 
     let env = ctx.data();
-    let (memory, state, _) = unsafe { env.get_memory_and_wasi_state_and_inodes(&ctx, 0) };
-
     let nonblocking = fd_flags.contains(Fdflags::NONBLOCK);
 
-    let (fd, local_addr, peer_addr) = wasi_try_ok!(sock_accept_internal(
-        env,
-        sock,
-        fd_flags,
-        nonblocking,
-        None
-    )?);
+    let res = sock_accept_internal(env, sock, fd_flags, nonblocking, None)?;
+    handle_interrupt(&mut ctx, &res)?;
+    let (fd, local_addr, peer_addr) = wasi_try_ok!(res);
 
     #[cfg(feature = "journal")]
     if ctx.data().enable_journal {
@@ -18,6 +18,7 @@ pub fn sock_addr_peer<M: MemorySize>(
     sock: WasiFd,
     ro_addr: WasmPtr<__wasi_addr_port_t, M>,
 ) -> Errno {
+    ctx.data().process.count_syscall(Syscall::SockAddrPeer);
     let addr = wasi_try!(__sock_actor(
         &mut ctx,
         sock,
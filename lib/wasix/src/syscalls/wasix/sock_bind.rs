@@ -15,6 +15,7 @@ pub fn sock_bind<M: MemorySize>(
     sock: WasiFd,
     addr: WasmPtr<__wasi_addr_port_t, M>,
 ) -> Result<Errno, WasiError> {
+    ctx.data().process.count_syscall(Syscall::SockBind);
     WasiEnv::do_pending_operations(&mut ctx)?;
 
     let env = ctx.data();
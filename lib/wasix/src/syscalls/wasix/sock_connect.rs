@@ -19,6 +19,7 @@ pub fn sock_connect<M: MemorySize>(
     sock: WasiFd,
     addr: WasmPtr<__wasi_addr_port_t, M>,
 ) -> Result<Errno, WasiError> {
+    ctx.data().process.count_syscall(Syscall::SockConnect);
     WasiEnv::do_pending_operations(&mut ctx)?;
 
     let env = ctx.data();
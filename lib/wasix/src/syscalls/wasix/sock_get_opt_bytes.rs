@@ -0,0 +1,50 @@
+use super::*;
+use crate::syscalls::*;
+
+/// ### `sock_get_opt_bytes()`
+/// Retrieves an option of this socket whose value is a sequence of bytes
+///
+/// `Sockoption::TlsAlpn` returns the protocol the server selected through
+/// ALPN, which is empty when none was selected
+///
+/// ## Parameters
+///
+/// * `fd` - Socket descriptor
+/// * `opt` - Socket option to be retrieved
+/// * `buf` - Buffer the value is written to
+///
+/// ## Return
+///
+/// The length of the value, `Errno::Overflow` is returned when it doesn't
+/// fit in the buffer
+#[instrument(level = "trace", skip_all, fields(%sock, %opt), ret)]
+pub fn sock_get_opt_bytes<M: MemorySize>(
+    mut ctx: FunctionEnvMut<'_, WasiEnv>,
+    sock: WasiFd,
+    opt: Sockoption,
+    buf: WasmPtr<u8, M>,
+    buf_len: M::Offset,
+    ret_len: WasmPtr<M::Offset, M>,
+) -> Errno {
+    ctx.data().process.count_syscall(Syscall::SockGetOptBytes);
+    let value = wasi_try!(__sock_actor(
+        &mut ctx,
+        sock,
+        Rights::empty(),
+        |socket, _| match opt {
+            Sockoption::TlsAlpn => socket.alpn_protocol().map(Option::unwrap_or_default),
+            _ => Err(Errno::Inval),
+        }
+    ));
+
+    let env = ctx.data();
+    let memory = unsafe { env.memory_view(&ctx) };
+    let len: M::Offset = wasi_try!(to_offset::<M>(value.len()));
+    wasi_try_mem!(ret_len.write(&memory, len));
+    if len > buf_len {
+        return Errno::Overflow;
+    }
+    wasi_try_mem!(wasi_try_mem!(buf.slice(&memory, len)).write_slice(&value));
+
+    Errno::Success
+}
@@ -16,6 +16,7 @@ pub fn sock_get_opt_flag<M: MemorySize>(
     opt: Sockoption,
     ret_flag: WasmPtr<Bool, M>,
 ) -> Errno {
+    ctx.data().process.count_syscall(Syscall::SockGetOptFlag);
     let option: crate::net::socket::WasiSocketOption = opt.into();
     let flag = wasi_try!(__sock_actor(
         &mut ctx,
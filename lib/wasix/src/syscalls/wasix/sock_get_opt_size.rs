@@ -3,7 +3,8 @@ use crate::syscalls::*;
 
 /// ### `sock_get_opt_size()`
 /// Retrieve the size of particular option for this socket
-/// Note: This is similar to `getsockopt` in POSIX for SO_RCVBUF
+/// Note: This is similar to `getsockopt` in POSIX for SO_RCVBUF, SO_PROTOCOL
+/// and SO_ERROR
 ///
 /// ## Parameters
 ///
@@ -16,6 +17,7 @@ pub fn sock_get_opt_size<M: MemorySize>(
     opt: Sockoption,
     ret_size: WasmPtr<Filesize, M>,
 ) -> Errno {
+    ctx.data().process.count_syscall(Syscall::SockGetOptSize);
     let size = wasi_try!(__sock_actor(
         &mut ctx,
         sock,
@@ -27,6 +29,11 @@ pub fn sock_get_opt_size<M: MemorySize>(
             Sockoption::MulticastTtlV4 => {
                 socket.multicast_ttl_v4().map(|a| a as Filesize)
             }
+            Sockoption::Proto => socket.proto().map(|a| a as Filesize),
+            // Reading the error clears it, like `SO_ERROR` does
+            Sockoption::LastError => {
+                Ok(socket.take_last_error().unwrap_or(Errno::Success) as Filesize)
+            }
             _ => Err(Errno::Inval),
         }
     ));
@@ -15,6 +15,7 @@ pub fn sock_get_opt_time<M: MemorySize>(
     opt: Sockoption,
     ret_time: WasmPtr<OptionTimestamp, M>,
 ) -> Errno {
+    ctx.data().process.count_syscall(Syscall::SockGetOptTime);
     let ty = match opt {
         Sockoption::RecvTimeout => TimeType::ReadTimeout,
         Sockoption::SendTimeout => TimeType::WriteTimeout,
@@ -16,6 +16,9 @@ pub fn sock_join_multicast_v4<M: MemorySize>(
     multiaddr: WasmPtr<__wasi_addr_ip4_t, M>,
     iface: WasmPtr<__wasi_addr_ip4_t, M>,
 ) -> Result<Errno, WasiError> {
+    ctx.data()
+        .process
+        .count_syscall(Syscall::SockJoinMulticastV4);
     WasiEnv::do_pending_operations(&mut ctx)?;
 
     let env = ctx.data();
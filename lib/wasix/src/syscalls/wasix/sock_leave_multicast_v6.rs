@@ -16,6 +16,9 @@ pub fn sock_leave_multicast_v6<M: MemorySize>(
     multiaddr: WasmPtr<__wasi_addr_ip6_t, M>,
     iface: u32,
 ) -> Result<Errno, WasiError> {
+    ctx.data()
+        .process
+        .count_syscall(Syscall::SockLeaveMulticastV6);
     WasiEnv::do_pending_operations(&mut ctx)?;
 
     let env = ctx.data();
@@ -1,5 +1,5 @@
 use super::*;
-use crate::{journal::SnapshotTrigger, syscalls::*};
+use crate::{journal::SnapshotTrigger, net::listener::ListenerExposer, syscalls::*};
 
 /// ### `sock_listen()`
 /// Listen for connections on a socket
@@ -19,6 +19,7 @@ pub fn sock_listen<M: MemorySize>(
     sock: WasiFd,
     backlog: M::Offset,
 ) -> Result<Errno, WasiError> {
+    ctx.data().process.count_syscall(Syscall::SockListen);
     WasiEnv::do_pending_operations(&mut ctx)?;
 
     ctx = wasi_try_ok!(maybe_snapshot_once::<M>(ctx, SnapshotTrigger::FirstListen)?);
@@ -47,11 +48,20 @@ pub(crate) fn sock_listen_internal(
     let env = ctx.data();
     let net = env.net().clone();
     let tasks = ctx.data().tasks().clone();
+    let exposer = ListenerExposer {
+        capability: env.capabilities.listen.clone(),
+        control_plane: env.control_plane.clone(),
+        runtime: env.runtime.clone(),
+    };
     wasi_try_ok_ok!(__sock_upgrade(
         ctx,
         sock,
         Rights::SOCK_LISTEN,
-        |socket, _| async move { socket.listen(tasks.deref(), net.deref(), backlog).await }
+        |socket, _| async move {
+            socket
+                .listen(tasks.deref(), net.deref(), backlog, &exposer)
+                .await
+        }
     ));
 
     Ok(Ok(()))
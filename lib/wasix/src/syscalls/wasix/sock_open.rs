@@ -15,7 +15,8 @@ use crate::{net::socket::SocketProperties, syscalls::*};
 ///
 /// * `af` - Address family
 /// * `socktype` - Socket type, either datagram or stream
-/// * `sock_proto` - Socket protocol
+/// * `sock_proto` - Socket protocol, or `0` for the default protocol of
+///   the socket type (TCP for streams and UDP for datagrams)
 ///
 /// ## Return
 ///
@@ -28,6 +29,7 @@ pub fn sock_open<M: MemorySize>(
     pt: SockProto,
     ro_sock: WasmPtr<WasiFd, M>,
 ) -> Result<Errno, WasiError> {
+    ctx.data().process.count_syscall(Syscall::SockOpen);
     WasiEnv::do_pending_operations(&mut ctx)?;
 
     // only certain combinations are supported
@@ -42,7 +44,13 @@ pub fn sock_open<M: MemorySize>(
                 return Ok(Errno::Notsup);
             }
         }
-        _ => {}
+        // Picks the default protocol of the socket type
+        SockProto::Ip => {}
+        _ => {
+            if matches!(ty, Socktype::Stream | Socktype::Dgram) {
+                return Ok(Errno::Protonosupport);
+            }
+        }
     }
 
     let fd = wasi_try_ok!(sock_open_internal(&mut ctx, af, ty, pt, None)?);
@@ -72,6 +80,12 @@ pub(crate) fn sock_open_internal(
     let env = ctx.data();
     let (memory, state, inodes) = unsafe { env.get_memory_and_wasi_state_and_inodes(&ctx, 0) };
 
+    let pt = match (ty, pt) {
+        (Socktype::Stream, SockProto::Ip) => SockProto::Tcp,
+        (Socktype::Dgram, SockProto::Ip) => SockProto::Udp,
+        (_, pt) => pt,
+    };
+
     let kind = match ty {
         Socktype::Stream | Socktype::Dgram => Kind::Socket {
             socket: InodeSocket::new(InodeSocketKind::PreSocket {
@@ -26,6 +26,7 @@ pub fn sock_recv<M: MemorySize>(
     ro_data_len: WasmPtr<M::Offset, M>,
     ro_flags: WasmPtr<RoFlags, M>,
 ) -> Result<Errno, WasiError> {
+    ctx.data().process.count_syscall(Syscall::SockRecv);
     WasiEnv::do_pending_operations(&mut ctx)?;
 
     let env = ctx.data();
@@ -49,6 +50,7 @@ pub fn sock_recv<M: MemorySize>(
             ro_data_len,
             ro_flags,
         )?;
+        handle_interrupt(&mut ctx, &res)?;
 
         sock_recv_internal_handler(ctx, res, ro_data_len, ro_flags)
     }
@@ -126,41 +128,38 @@ pub(super) fn sock_recv_internal<M: MemorySize>(
                 .map_err(mem_error_to_wasi)?;
             let iovs_arr = iovs_arr.access().map_err(mem_error_to_wasi)?;
 
-            let mut total_read = 0;
+            let mut guest_bufs = Vec::with_capacity(iovs_arr.len());
             for iovs in iovs_arr.iter() {
-                let mut buf = WasmPtr::<u8, M>::new(iovs.buf)
+                let buf = WasmPtr::<u8, M>::new(iovs.buf)
                     .slice(&memory, iovs.buf_len)
                     .map_err(mem_error_to_wasi)?
                     .access()
                     .map_err(mem_error_to_wasi)?;
+                guest_bufs.push(buf);
+            }
+            // Received straight into guest memory, filling the iovecs in
+            // order and leaving everything past the bytes received untouched
+            let mut bufs: Vec<_> = guest_bufs
+                .iter_mut()
+                .map(|buf| buf.as_mut_uninit())
+                .collect();
 
-                let nonblocking = nonblocking_flag || fd.inner.flags.contains(Fdflags::NONBLOCK);
-                let timeout = socket
-                    .opt_time(TimeType::ReadTimeout)
-                    .ok()
-                    .flatten()
-                    .unwrap_or(Duration::from_secs(30));
+            let nonblocking = nonblocking_flag || fd.inner.flags.contains(Fdflags::NONBLOCK);
+            let timeout = socket
+                .opt_time(TimeType::ReadTimeout)
+                .ok()
+                .flatten()
+                .unwrap_or(Duration::from_secs(30));
 
-                let local_read = match socket
-                    .recv(
-                        env.tasks().deref(),
-                        buf.as_mut_uninit(),
-                        Some(timeout),
-                        nonblocking,
-                        peek,
-                    )
-                    .await
-                {
-                    Ok(s) => s,
-                    Err(_) if total_read > 0 => break,
-                    Err(err) => return Err(err),
-                };
-                total_read += local_read;
-                if local_read != buf.len() {
-                    break;
-                }
-            }
-            Ok(total_read)
+            socket
+                .recv_vectored(
+                    env.tasks().deref(),
+                    &mut bufs,
+                    Some(timeout),
+                    nonblocking,
+                    peek,
+                )
+                .await
         }
     ));
     Ok(Ok(data))
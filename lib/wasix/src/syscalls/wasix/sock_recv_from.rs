@@ -27,10 +27,11 @@ pub fn sock_recv_from<M: MemorySize>(
     ro_flags: WasmPtr<RoFlags, M>,
     ro_addr: WasmPtr<__wasi_addr_port_t, M>,
 ) -> Result<Errno, WasiError> {
+    ctx.data().process.count_syscall(Syscall::SockRecvFrom);
     WasiEnv::do_pending_operations(&mut ctx)?;
 
-    sock_recv_from_internal(
-        ctx,
+    let ret = sock_recv_from_internal(
+        ctx.as_mut(),
         sock,
         ri_data,
         ri_data_len,
@@ -38,7 +39,11 @@ pub fn sock_recv_from<M: MemorySize>(
         ro_data_len,
         ro_flags,
         ro_addr,
-    )
+    )?;
+    if ret == Errno::Intr {
+        _ = WasiEnv::process_signals_and_exit(&mut ctx)?;
+    }
+    Ok(ret)
 }
 
 pub(super) fn sock_recv_from_internal<M: MemorySize>(
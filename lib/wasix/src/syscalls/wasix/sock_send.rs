@@ -1,4 +1,4 @@
-use std::{mem::MaybeUninit, task::Waker};
+use std::{cell::Cell, mem::MaybeUninit, task::Waker};
 
 use super::*;
 use crate::{net::socket::TimeType, syscalls::*};
@@ -25,6 +25,7 @@ pub fn sock_send<M: MemorySize>(
     si_flags: SiFlags,
     ret_data_len: WasmPtr<M::Offset, M>,
 ) -> Result<Errno, WasiError> {
+    ctx.data().process.count_syscall(Syscall::SockSend);
     WasiEnv::do_pending_operations(&mut ctx)?;
 
     let env = ctx.data();
@@ -56,15 +57,17 @@ pub fn sock_send<M: MemorySize>(
             enable_journal
         )?)
     } else {
-        wasi_try_ok!(sock_send_internal::<M>(
+        let res = sock_send_internal::<M>(
             &ctx,
             fd,
             FdWriteSource::Iovs {
                 iovs: si_data,
-                iovs_len: si_data_len
+                iovs_len: si_data_len,
             },
             si_flags,
-        )?)
+        )?;
+        handle_interrupt(&mut ctx, &res)?;
+        wasi_try_ok!(res)
     };
 
     #[cfg(feature = "journal")]
@@ -99,63 +102,65 @@ pub(crate) fn sock_send_internal<M: MemorySize>(
 
     let nonblocking_flag = (si_flags & __WASI_SOCK_SEND_INPUT_DONT_WAIT) != 0;
 
-    let bytes_written = wasi_try_ok_ok!(__sock_asyncify(
-        env,
-        sock,
-        Rights::SOCK_SEND,
-        |socket, fd| async move {
-            let nonblocking = nonblocking_flag || fd.inner.flags.contains(Fdflags::NONBLOCK);
-            let timeout = socket
-                .opt_time(TimeType::WriteTimeout)
-                .ok()
-                .flatten()
-                .unwrap_or(Duration::from_secs(30));
-
-            match si_data {
-                FdWriteSource::Iovs { iovs, iovs_len } => {
-                    let iovs_arr = iovs.slice(&memory, iovs_len).map_err(mem_error_to_wasi)?;
-                    let iovs_arr = iovs_arr.access().map_err(mem_error_to_wasi)?;
-
-                    let mut sent = 0usize;
-                    for iovs in iovs_arr.iter() {
-                        let buf = WasmPtr::<u8, M>::new(iovs.buf)
-                            .slice(&memory, iovs.buf_len)
-                            .map_err(mem_error_to_wasi)?
-                            .access()
-                            .map_err(mem_error_to_wasi)?;
-                        let local_sent = match socket
-                            .send(
-                                env.tasks().deref(),
-                                buf.as_ref(),
-                                Some(timeout),
-                                nonblocking,
-                            )
-                            .await
-                        {
-                            Ok(s) => s,
-                            Err(_) if sent > 0 => break,
-                            Err(err) => return Err(err),
-                        };
-                        sent += local_sent;
-                        if local_sent != buf.len() {
-                            break;
-                        }
-                    }
-                    Ok(sent)
-                }
-                FdWriteSource::Buffer(data) => {
-                    socket
+    // Bytes that were already sent when a signal interrupts the write
+    let progress = &Cell::new(0);
+    let res = __sock_asyncify(env, sock, Rights::SOCK_SEND, |socket, fd| async move {
+        let nonblocking = nonblocking_flag || fd.inner.flags.contains(Fdflags::NONBLOCK);
+        let timeout = socket
+            .opt_time(TimeType::WriteTimeout)
+            .ok()
+            .flatten()
+            .unwrap_or(Duration::from_secs(30));
+
+        match si_data {
+            FdWriteSource::Iovs { iovs, iovs_len } => {
+                let iovs_arr = iovs.slice(&memory, iovs_len).map_err(mem_error_to_wasi)?;
+                let iovs_arr = iovs_arr.access().map_err(mem_error_to_wasi)?;
+
+                let mut sent = 0usize;
+                for iovs in iovs_arr.iter() {
+                    let buf = WasmPtr::<u8, M>::new(iovs.buf)
+                        .slice(&memory, iovs.buf_len)
+                        .map_err(mem_error_to_wasi)?
+                        .access()
+                        .map_err(mem_error_to_wasi)?;
+                    let local_sent = match socket
                         .send(
                             env.tasks().deref(),
-                            data.as_ref(),
+                            buf.as_ref(),
                             Some(timeout),
                             nonblocking,
                         )
                         .await
+                    {
+                        Ok(s) => s,
+                        Err(_) if sent > 0 => break,
+                        Err(err) => return Err(err),
+                    };
+                    sent += local_sent;
+                    progress.set(sent);
+                    if local_sent != buf.len() {
+                        break;
+                    }
                 }
+                Ok(sent)
+            }
+            FdWriteSource::Buffer(data) => {
+                socket
+                    .send(
+                        env.tasks().deref(),
+                        data.as_ref(),
+                        Some(timeout),
+                        nonblocking,
+                    )
+                    .await
             }
         }
-    ));
+    });
+    let bytes_written = match res {
+        Err(Errno::Intr) if progress.get() > 0 => progress.get(),
+        res => wasi_try_ok_ok!(res),
+    };
     trace!(
         %bytes_written,
     );
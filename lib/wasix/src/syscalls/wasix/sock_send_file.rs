@@ -24,6 +24,7 @@ pub fn sock_send_file<M: MemorySize>(
     count: Filesize,
     ret_sent: WasmPtr<Filesize, M>,
 ) -> Result<Errno, WasiError> {
+    ctx.data().process.count_syscall(Syscall::SockSendFile);
     WasiEnv::do_pending_operations(&mut ctx)?;
 
     let total_written = wasi_try_ok!(sock_send_file_internal(
@@ -86,7 +87,10 @@ pub(crate) fn sock_send_file_internal(
                     let data = wasi_try_ok_ok!(__asyncify(ctx, None, async move {
                         // TODO: optimize with MaybeUninit
                         let mut buf = vec![0u8; sub_count as usize];
-                        let amt = stdin.read(&mut buf[..]).await.map_err(map_io_err)?;
+                        let amt = stdin
+                            .read(&mut buf[..])
+                            .await
+                            .map_err(io_error_into_wasi_err)?;
                         buf.truncate(amt);
                         Ok(buf)
                     })?);
@@ -115,11 +119,11 @@ pub(crate) fn sock_send_file_internal(
                                             handle
                                                 .seek(std::io::SeekFrom::Start(offset as u64))
                                                 .await
-                                                .map_err(map_io_err)?;
+                                                .map_err(io_error_into_wasi_err)?;
                                             let amt = handle
                                                 .read(&mut buf[..])
                                                 .await
-                                                .map_err(map_io_err)?;
+                                                .map_err(io_error_into_wasi_err)?;
                                             buf.truncate(amt);
                                             Ok(buf)
                                         })?);
@@ -171,7 +175,7 @@ pub(crate) fn sock_send_file_internal(
                                     let mut buf = vec![0u8; sub_count as usize];
                                     let amt = virtual_fs::AsyncReadExt::read(rx, &mut buf[..])
                                         .await
-                                        .map_err(map_io_err)?;
+                                        .map_err(io_error_into_wasi_err)?;
                                     buf.truncate(amt);
                                     Ok(buf)
                                 })?);
@@ -184,7 +188,7 @@ pub(crate) fn sock_send_file_internal(
                                     let mut buf = vec![0u8; sub_count as usize];
                                     let amt = virtual_fs::AsyncReadExt::read(pipe, &mut buf[..])
                                         .await
-                                        .map_err(map_io_err)?;
+                                        .map_err(io_error_into_wasi_err)?;
                                     buf.truncate(amt);
                                     Ok(buf)
                                 })?);
@@ -209,7 +213,7 @@ pub(crate) fn sock_send_file_internal(
                                     &mut buf_read,
                                     &mut buf[..]
                                 )
-                                .map_err(map_io_err));
+                                .map_err(io_error_into_wasi_err));
                                 buf.truncate(amt);
                                 buf
                             }
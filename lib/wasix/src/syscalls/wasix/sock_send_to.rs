@@ -27,6 +27,7 @@ pub fn sock_send_to<M: MemorySize>(
     addr: WasmPtr<__wasi_addr_port_t, M>,
     ret_data_len: WasmPtr<M::Offset, M>,
 ) -> Result<Errno, WasiError> {
+    ctx.data().process.count_syscall(Syscall::SockSendTo);
     WasiEnv::do_pending_operations(&mut ctx)?;
 
     let env = ctx.data();
@@ -40,16 +41,18 @@ pub fn sock_send_to<M: MemorySize>(
     let addr = SocketAddr::new(addr_ip, addr_port);
     Span::current().record("addr", format!("{addr:?}"));
 
-    let bytes_written = wasi_try_ok!(sock_send_to_internal(
+    let res = sock_send_to_internal(
         &mut ctx,
         sock,
         FdWriteSource::Iovs {
             iovs: si_data,
-            iovs_len: si_data_len
+            iovs_len: si_data_len,
         },
         si_flags,
         addr,
-    )?);
+    )?;
+    handle_interrupt(&mut ctx, &res)?;
+    let bytes_written = wasi_try_ok!(res);
 
     #[cfg(feature = "journal")]
     if ctx.data().enable_journal {
@@ -0,0 +1,55 @@
+use super::*;
+use crate::syscalls::*;
+
+/// ### `sock_set_opt_bytes()`
+/// Sets an option of this socket whose value is a sequence of bytes
+///
+/// `Sockoption::TlsAlpn` takes the protocols offered through ALPN when
+/// the socket is upgraded to TLS, in the wire format (each protocol name is
+/// prefixed with its length as a single byte)
+///
+/// ## Parameters
+///
+/// * `fd` - Socket descriptor
+/// * `opt` - Socket option to be set
+/// * `buf` - Value of the option
+#[instrument(level = "trace", skip_all, fields(%sock, %opt), ret)]
+pub fn sock_set_opt_bytes<M: MemorySize>(
+    mut ctx: FunctionEnvMut<'_, WasiEnv>,
+    sock: WasiFd,
+    opt: Sockoption,
+    buf: WasmPtr<u8, M>,
+    buf_len: M::Offset,
+) -> Result<Errno, WasiError> {
+    ctx.data().process.count_syscall(Syscall::SockSetOptBytes);
+    WasiEnv::do_pending_operations(&mut ctx)?;
+
+    let env = ctx.data();
+    let memory = unsafe { env.memory_view(&ctx) };
+    let value = wasi_try_mem_ok!(wasi_try_mem_ok!(buf.slice(&memory, buf_len)).read_to_vec());
+
+    let protocols = match opt {
+        Sockoption::TlsAlpn => {
+            let mut protocols = Vec::new();
+            let mut rest = value.as_slice();
+            while let Some((&len, tail)) = rest.split_first() {
+                let len = len as usize;
+                if len == 0 || len > tail.len() {
+                    return Ok(Errno::Inval);
+                }
+                protocols.push(tail[..len].to_vec());
+                rest = &tail[len..];
+            }
+            protocols
+        }
+        _ => return Ok(Errno::Inval),
+    };
+
+    wasi_try_ok!(__sock_actor(
+        &mut ctx,
+        sock,
+        Rights::empty(),
+        |socket, _| socket.set_alpn_protocols(protocols)
+    ));
+    Ok(Errno::Success)
+}
@@ -17,6 +17,7 @@ pub fn sock_set_opt_flag(
     opt: Sockoption,
     flag: Bool,
 ) -> Result<Errno, WasiError> {
+    ctx.data().process.count_syscall(Syscall::SockSetOptFlag);
     WasiEnv::do_pending_operations(&mut ctx)?;
 
     let flag = match flag {
@@ -16,6 +16,7 @@ pub fn sock_set_opt_time<M: MemorySize>(
     opt: Sockoption,
     time: WasmPtr<OptionTimestamp, M>,
 ) -> Result<Errno, WasiError> {
+    ctx.data().process.count_syscall(Syscall::SockSetOptTime);
     WasiEnv::do_pending_operations(&mut ctx)?;
 
     let env = ctx.data();
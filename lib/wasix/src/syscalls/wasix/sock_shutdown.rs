@@ -16,6 +16,7 @@ pub fn sock_shutdown(
     sock: WasiFd,
     how: SdFlags,
 ) -> Result<Errno, WasiError> {
+    ctx.data().process.count_syscall(Syscall::SockShutdown);
     WasiEnv::do_pending_operations(&mut ctx)?;
 
     let both = __WASI_SHUT_RD | __WASI_SHUT_WR;
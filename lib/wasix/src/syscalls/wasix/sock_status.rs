@@ -9,6 +9,7 @@ pub fn sock_status<M: MemorySize>(
     sock: WasiFd,
     ret_status: WasmPtr<Sockstatus, M>,
 ) -> Errno {
+    ctx.data().process.count_syscall(Syscall::SockStatus);
     let status = wasi_try!(__sock_actor(
         &mut ctx,
         sock,
@@ -0,0 +1,68 @@
+use super::*;
+use crate::syscalls::*;
+
+/// ### `sock_tls_upgrade()`
+/// Upgrades a connected TCP socket to TLS, the handshake is performed by the
+/// host and once it completes the same socket descriptor sends and receives
+/// plaintext while the connection itself is encrypted
+///
+/// The server certificate is verified against the root store of the runtime
+/// and the hostname given here, which is also sent as the SNI. Protocols to
+/// offer through ALPN are set beforehand with `sock_set_opt_bytes`.
+///
+/// ## Parameters
+///
+/// * `fd` - Socket descriptor
+/// * `hostname` - Name of the server the socket is connected to
+#[instrument(level = "trace", skip_all, fields(%sock, hostname = field::Empty), ret)]
+pub fn sock_tls_upgrade<M: MemorySize>(
+    mut ctx: FunctionEnvMut<'_, WasiEnv>,
+    sock: WasiFd,
+    hostname: WasmPtr<u8, M>,
+    hostname_len: M::Offset,
+) -> Result<Errno, WasiError> {
+    ctx.data().process.count_syscall(Syscall::SockTlsUpgrade);
+    WasiEnv::do_pending_operations(&mut ctx)?;
+
+    let env = ctx.data();
+    let hostname = {
+        let memory = unsafe { env.memory_view(&ctx) };
+        unsafe { get_input_str_ok!(&memory, hostname, hostname_len) }
+    };
+    Span::current().record("hostname", hostname.as_str());
+
+    wasi_try_ok!(sock_tls_upgrade_internal(&mut ctx, sock, hostname)?);
+    Ok(Errno::Success)
+}
+
+#[cfg(feature = "host-tls")]
+pub(crate) fn sock_tls_upgrade_internal(
+    ctx: &mut FunctionEnvMut<'_, WasiEnv>,
+    sock: WasiFd,
+    hostname: String,
+) -> Result<Result<(), Errno>, WasiError> {
+    let env = ctx.data();
+    let tasks = env.tasks().clone();
+    let roots = env.runtime().tls_root_store();
+    let insecure_skip_verify = env.capabilities.tls.insecure_skip_verify;
+    wasi_try_ok_ok!(__sock_asyncify(
+        env,
+        sock,
+        Rights::empty(),
+        |socket, _| async move {
+            socket
+                .upgrade_tls(tasks.deref(), roots, insecure_skip_verify, &hostname)
+                .await
+        }
+    ));
+    Ok(Ok(()))
+}
+
+#[cfg(not(feature = "host-tls"))]
+pub(crate) fn sock_tls_upgrade_internal(
+    _ctx: &mut FunctionEnvMut<'_, WasiEnv>,
+    _sock: WasiFd,
+    _hostname: String,
+) -> Result<Result<(), Errno>, WasiError> {
+    Ok(Err(Errno::Notsup))
+}
@@ -10,6 +10,7 @@ pub fn stack_checkpoint<M: MemorySize>(
     snapshot_ptr: WasmPtr<StackSnapshot, M>,
     ret_val: WasmPtr<Longsize, M>,
 ) -> Result<Errno, WasiError> {
+    ctx.data().process.count_syscall(Syscall::StackCheckpoint);
     // If we were just restored then we need to return the value instead
     if let Some(val) = unsafe { handle_rewind::<M, Longsize>(&mut ctx) } {
         let env = ctx.data();
@@ -14,6 +14,7 @@ pub fn stack_restore<M: MemorySize>(
     snapshot_ptr: WasmPtr<StackSnapshot, M>,
     mut val: Longsize,
 ) -> Result<(), WasiError> {
+    ctx.data().process.count_syscall(Syscall::StackRestore);
     WasiEnv::do_pending_operations(&mut ctx)?;
 
     // Read the snapshot from the stack
@@ -9,6 +9,7 @@ pub fn thread_id<M: MemorySize>(
     ctx: FunctionEnvMut<'_, WasiEnv>,
     ret_tid: WasmPtr<Tid, M>,
 ) -> Errno {
+    ctx.data().process.count_syscall(Syscall::ThreadId);
     let env = ctx.data();
     let tid: Tid = env.thread.tid().into();
     Span::current().record("tid", tid);
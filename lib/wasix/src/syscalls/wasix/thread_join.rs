@@ -15,6 +15,7 @@ pub fn thread_join<M: MemorySize + 'static>(
     mut ctx: FunctionEnvMut<'_, WasiEnv>,
     join_tid: Tid,
 ) -> Result<Errno, WasiError> {
+    ctx.data().process.count_syscall(Syscall::ThreadJoin);
     WasiEnv::do_pending_operations(&mut ctx)?;
 
     thread_join_internal::<M>(ctx, join_tid)
@@ -1,5 +1,5 @@
 use super::*;
-use crate::syscalls::*;
+use crate::{os::cpu::CpuInfo, syscalls::*};
 
 /// ### `thread_parallelism()`
 /// Returns the available parallelism which is normally the
@@ -9,11 +9,14 @@ pub fn thread_parallelism<M: MemorySize>(
     ctx: FunctionEnvMut<'_, WasiEnv>,
     ret_parallelism: WasmPtr<M::Offset, M>,
 ) -> Errno {
+    ctx.data().process.count_syscall(Syscall::ThreadParallelism);
     let env = ctx.data();
-    let parallelism = wasi_try!(env.tasks().thread_parallelism().map_err(|err| {
-        let err: Errno = err.into();
-        err
-    }));
+    let parallelism = CpuInfo::new(
+        &env.capabilities.cpu,
+        env.tasks().as_ref(),
+        &env.runtime().engine(),
+    )
+    .count;
     Span::current().record("parallelism", parallelism);
     let parallelism: M::Offset = wasi_try!(parallelism.try_into().map_err(|_| Errno::Overflow));
     let memory = unsafe { env.memory_view(&ctx) };
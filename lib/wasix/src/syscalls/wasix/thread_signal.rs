@@ -13,6 +13,7 @@ pub fn thread_signal(
     tid: Tid,
     sig: Signal,
 ) -> Result<Errno, WasiError> {
+    ctx.data().process.count_syscall(Syscall::ThreadSignal);
     {
         let tid: WasiThreadId = tid.into();
         ctx.data().process.signal_thread(&tid, sig);
@@ -14,6 +14,7 @@ pub fn thread_sleep<M: MemorySize + 'static>(
     mut ctx: FunctionEnvMut<'_, WasiEnv>,
     duration: Timestamp,
 ) -> Result<Errno, WasiError> {
+    ctx.data().process.count_syscall(Syscall::ThreadSleep);
     WasiEnv::do_pending_operations(&mut ctx)?;
 
     thread_sleep_internal::<M>(ctx, duration)
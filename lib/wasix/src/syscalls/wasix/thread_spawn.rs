@@ -35,6 +35,7 @@ pub fn thread_spawn_v2<M: MemorySize>(
     start_ptr: WasmPtr<ThreadStart<M>, M>,
     ret_tid: WasmPtr<Tid, M>,
 ) -> Result<Errno, WasiError> {
+    ctx.data().process.count_syscall(Syscall::ThreadSpawnV2);
     WasiEnv::do_pending_operations(&mut ctx)?;
 
     // Create the thread
@@ -96,10 +97,9 @@ pub fn thread_spawn_internal_from_wasi<M: MemorySize>(
         Err(err) => {
             error!(
                 stack_base = layout.stack_lower,
-                "failed to create thread handle",
+                "failed to create thread handle: {err}",
             );
-            // TODO: evaluate the appropriate error code, document it in the spec.
-            return Err(Errno::Access);
+            return Err(err.into());
         }
     };
     let thread_id: Tid = thread_handle.id().into();
@@ -170,8 +170,9 @@ pub fn thread_spawn_internal_using_layout<M: MemorySize>(
 
     // Now spawn a thread
     trace!("threading: spawning background thread");
+    let log_span = crate::log_sink::process_span(&thread_env.process);
     let run = move |props: TaskWasmRunProperties| {
-        execute_module(props.ctx, props.store);
+        log_span.in_scope(|| execute_module(props.ctx, props.store));
     };
 
     let mut task_wasm = TaskWasm::new(Box::new(run), thread_env, thread_module, false, false)
@@ -265,10 +266,13 @@ fn handle_thread_result(
             Ok(Some(ExitCode::from(129)))
         }
         Err(err) => {
-            eprintln!("Thread {tid} of process {pid} failed with runtime error: {err}");
+            let failure =
+                WasiRuntimeError::from(err.clone()).with_process_context(env.data(&store));
+            error!(%failure, "thread failed with a runtime error");
             env.data(&store)
                 .runtime
                 .on_taint(TaintReason::RuntimeError(err));
+            env.data(&store).thread.set_status_finished(Err(failure));
             Ok(Some(ExitCode::from(129)))
         }
     }
@@ -284,6 +288,7 @@ fn call_module<M: MemorySize>(
 ) {
     let env = ctx.data(&store);
     let tasks = env.tasks().clone();
+    thread_handle.resume_cpu_clock();
 
     // If we need to rewind then do so
     if let Some((rewind_state, rewind_result)) = rewind_state {
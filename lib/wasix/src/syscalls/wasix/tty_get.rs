@@ -8,6 +8,7 @@ pub fn tty_get<M: MemorySize>(
     ctx: FunctionEnvMut<'_, WasiEnv>,
     tty_state: WasmPtr<Tty, M>,
 ) -> Errno {
+    ctx.data().process.count_syscall(Syscall::TtyGet);
     let env = ctx.data();
 
     let env = ctx.data();
@@ -8,6 +8,7 @@ pub fn tty_set<M: MemorySize>(
     mut ctx: FunctionEnvMut<'_, WasiEnv>,
     tty_state: WasmPtr<Tty, M>,
 ) -> Result<Errno, WasiError> {
+    ctx.data().process.count_syscall(Syscall::TtySet);
     WasiEnv::do_pending_operations(&mut ctx)?;
 
     let env = ctx.data();
@@ -0,0 +1,117 @@
+//! [`tracing`] spans describing what the runtime does on behalf of a guest.
+//!
+//! When the `telemetry` feature is enabled, key runtime operations are
+//! wrapped in `INFO`-level spans under the [`TARGET`] target. The spans and
+//! their fields are part of the public interface and use [OpenTelemetry
+//! semantic conventions][semconv] where one exists, so an embedder that
+//! installs an OTLP exporter (e.g. via `tracing-opentelemetry`) gets a
+//! sensible trace without any extra mapping.
+//!
+//! The spans nest like this:
+//!
+//! ```text
+//! <embedder span, e.g. the inbound HTTP request>
+//! ├── fetch_webc           pkg.id, url.full, pkg.cached
+//! │   └── http_request     http.request.method, url.full, http.response.status_code
+//! └── spawn_exec           process.pid, process.command
+//!     ├── compile_module   module.hash, module.size, module.cached
+//!     └── process          process.pid, process.exit.code
+//!         └── (event) process_exit
+//! ```
+//!
+//! `fetch_webc` and `compile_module` may also show up under other spans
+//! (e.g. when a guest spawns a sub-process, or while dependencies are being
+//! resolved), and `http_request` is emitted for every request made by the
+//! built-in HTTP client. The `process` span lives for as long as the guest's
+//! main thread is running, so it will usually outlive its parent.
+//!
+//! Spans are created through the normal [`tracing`] machinery, so they cost
+//! next to nothing when no subscriber is interested in them. Without the
+//! `telemetry` feature, no spans are created at all.
+//!
+//! [semconv]: https://opentelemetry.io/docs/specs/semconv/
+
+use tracing::{field::Empty, Span};
+use url::Url;
+use wasmer_types::ModuleHash;
+use wasmer_wasix_types::wasi::ExitCode;
+
+use crate::os::task::process::WasiProcessId;
+
+/// The target used by all telemetry spans and events.
+pub const TARGET: &str = "wasmer_wasix::telemetry";
+
+macro_rules! telemetry_span {
+    ($name:literal, $($fields:tt)*) => {
+        if cfg!(feature = "telemetry") {
+            tracing::info_span!(target: TARGET, $name, $($fields)*)
+        } else {
+            Span::none()
+        }
+    };
+}
+
+/// Downloading (or loading from a cache) the webc for a package.
+pub(crate) fn fetch_webc(package: &dyn std::fmt::Display, url: &Url) -> Span {
+    telemetry_span!(
+        "fetch_webc",
+        pkg.id = %package,
+        url.full = %url,
+        pkg.cached = Empty,
+    )
+}
+
+/// Compiling a module, or loading it from the module cache.
+pub(crate) fn compile_module(hash: &ModuleHash, size: usize) -> Span {
+    telemetry_span!(
+        "compile_module",
+        module.hash = %hash,
+        module.size = size,
+        module.cached = Empty,
+    )
+}
+
+/// Preparing a command and spawning the process that runs it.
+pub(crate) fn spawn_exec(pid: WasiProcessId, command: &str) -> Span {
+    telemetry_span!(
+        "spawn_exec",
+        process.pid = pid.raw(),
+        process.command = command,
+    )
+}
+
+/// The lifetime of a process's main thread.
+pub(crate) fn process(pid: WasiProcessId) -> Span {
+    telemetry_span!(
+        "process",
+        process.pid = pid.raw(),
+        process.exit.code = Empty,
+    )
+}
+
+/// Record that the current process has exited.
+///
+/// This must be called from within the [`process()`] span.
+pub(crate) fn process_exit(pid: WasiProcessId, code: ExitCode) {
+    if cfg!(feature = "telemetry") {
+        Span::current().record("process.exit.code", code.raw());
+        tracing::event!(
+            target: TARGET,
+            tracing::Level::INFO,
+            process.pid = pid.raw(),
+            process.exit.code = code.raw(),
+            "process_exit",
+        );
+    }
+}
+
+/// A request made by the built-in HTTP client.
+pub(crate) fn http_request(method: &http::Method, url: &Url) -> Span {
+    telemetry_span!(
+        "http_request",
+        otel.kind = "client",
+        http.request.method = %method,
+        url.full = %url,
+        http.response.status_code = Empty,
+    )
+}
@@ -0,0 +1,306 @@
+//! Structured test results from a guest.
+//!
+//! A test harness running inside the guest can report what it is doing by
+//! writing newline-delimited JSON events to [`TEST_REPORT_PATH`], using the
+//! same format as libtest's `--format json`:
+//!
+//! ```text
+//! { "type": "suite", "event": "started", "test_count": 2 }
+//! { "type": "test", "event": "started", "name": "tests::adds" }
+//! { "type": "test", "event": "ok", "name": "tests::adds" }
+//! { "type": "test", "event": "started", "name": "tests::subtracts" }
+//! { "type": "test", "event": "failed", "name": "tests::subtracts", "stdout": "..." }
+//! { "type": "suite", "event": "failed", "passed": 1, "failed": 1, "ignored": 0 }
+//! ```
+//!
+//! The file only exists when the embedder asked for the events with
+//! [`WasiEnvBuilder::with_test_reporter()`](crate::WasiEnvBuilder::with_test_reporter),
+//! which gets every event as soon as its line has been written. A test that
+//! hangs or brings the program down can be found in
+//! [`TestSummary::running`].
+
+use std::{
+    io::{self, IoSlice, SeekFrom},
+    path::Path,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncRead, AsyncSeek, AsyncWrite};
+use virtual_fs::{FileSystem, FsError, TmpFileSystem, VirtualFile};
+
+/// Where the guest writes its test events.
+pub const TEST_REPORT_PATH: &str = "/dev/wasmer-test-report";
+
+/// Lines longer than this are dropped rather than buffered forever.
+const MAX_LINE_LEN: usize = 1 << 20;
+
+/// Something that happened while running the guest's tests.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TestEvent {
+    /// The test run as a whole.
+    Suite {
+        event: SuiteEvent,
+        /// How many tests are going to run (only set when starting).
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        test_count: Option<u64>,
+    },
+    /// A single test.
+    Test {
+        event: TestStatus,
+        name: String,
+        /// How long the test took, in seconds.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        exec_time: Option<f64>,
+        /// Whatever the test printed, usually only set for failed tests.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        stdout: Option<String>,
+        /// Why the test failed or was ignored.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        message: Option<String>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SuiteEvent {
+    Started,
+    Ok,
+    Failed,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TestStatus {
+    Started,
+    Ok,
+    Failed,
+    Ignored,
+}
+
+/// The results of a test run, built up from its [`TestEvent`]s.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize)]
+pub struct TestSummary {
+    /// How many tests the harness said it was going to run.
+    pub expected: Option<u64>,
+    pub passed: Vec<String>,
+    pub failed: Vec<String>,
+    pub ignored: Vec<String>,
+    /// Tests that started but never finished.
+    pub running: Vec<String>,
+}
+
+impl TestSummary {
+    pub fn record(&mut self, event: &TestEvent) {
+        match event {
+            TestEvent::Suite {
+                event: SuiteEvent::Started,
+                test_count,
+            } => {
+                self.expected = *test_count;
+            }
+            TestEvent::Suite { .. } => {}
+            TestEvent::Test { event, name, .. } => {
+                self.running.retain(|running| running != name);
+                let list = match event {
+                    TestStatus::Started => &mut self.running,
+                    TestStatus::Ok => &mut self.passed,
+                    TestStatus::Failed => &mut self.failed,
+                    TestStatus::Ignored => &mut self.ignored,
+                };
+                list.push(name.clone());
+            }
+        }
+    }
+
+    /// Whether every test that was started also passed (or was ignored).
+    pub fn is_success(&self) -> bool {
+        self.failed.is_empty() && self.running.is_empty()
+    }
+}
+
+impl std::fmt::Display for TestSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "test result: {}. {} passed; {} failed; {} ignored",
+            if self.is_success() { "ok" } else { "FAILED" },
+            self.passed.len(),
+            self.failed.len(),
+            self.ignored.len(),
+        )?;
+        if !self.running.is_empty() {
+            write!(f, "; {} did not finish", self.running.len())?;
+        }
+        Ok(())
+    }
+}
+
+/// Receives the [`TestEvent`]s a guest reports, see
+/// [`WasiEnvBuilder::with_test_reporter()`](crate::WasiEnvBuilder::with_test_reporter).
+#[derive(Clone)]
+pub struct TestReporter(Arc<dyn Fn(&TestEvent) + Send + Sync>);
+
+impl TestReporter {
+    pub fn new(callback: impl Fn(&TestEvent) + Send + Sync + 'static) -> Self {
+        TestReporter(Arc::new(callback))
+    }
+
+    pub fn report(&self, event: &TestEvent) {
+        (self.0)(event)
+    }
+}
+
+impl<F> From<F> for TestReporter
+where
+    F: Fn(&TestEvent) + Send + Sync + 'static,
+{
+    fn from(callback: F) -> Self {
+        TestReporter::new(callback)
+    }
+}
+
+impl std::fmt::Debug for TestReporter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("TestReporter").finish_non_exhaustive()
+    }
+}
+
+/// Puts a [`TestReportFile`] at [`TEST_REPORT_PATH`] in `fs`.
+pub(crate) fn install(fs: &TmpFileSystem, reporter: TestReporter) {
+    let path = Path::new(TEST_REPORT_PATH);
+    if let Some(dev) = path.parent() {
+        match fs.create_dir(dev) {
+            Ok(()) | Err(FsError::AlreadyExists) => {}
+            Err(err) => tracing::debug!("failed to create [{}] - {}", dev.display(), err),
+        }
+    }
+
+    let file = TestReportFile {
+        line: Vec::new(),
+        reporter,
+    };
+    if let Err(err) = fs
+        .new_open_options_ext()
+        .insert_device_file(path.to_path_buf(), Box::new(file))
+    {
+        tracing::warn!("failed to create [{}] - {}", TEST_REPORT_PATH, err);
+    }
+}
+
+/// The device file at [`TEST_REPORT_PATH`], which passes every line written
+/// to it on to a [`TestReporter`].
+#[derive(Debug)]
+struct TestReportFile {
+    /// The current, unfinished line.
+    line: Vec<u8>,
+    reporter: TestReporter,
+}
+
+impl TestReportFile {
+    fn write(&mut self, mut buf: &[u8]) {
+        while let Some(end) = buf.iter().position(|&b| b == b'\n') {
+            self.line.extend_from_slice(&buf[..end]);
+            buf = &buf[end + 1..];
+
+            let line = std::mem::take(&mut self.line);
+            let line = String::from_utf8_lossy(&line);
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            match serde_json::from_str::<TestEvent>(line) {
+                Ok(event) => self.reporter.report(&event),
+                Err(err) => tracing::warn!(%line, %err, "Ignoring an invalid test event"),
+            }
+        }
+
+        if self.line.len() + buf.len() > MAX_LINE_LEN {
+            tracing::warn!("Ignoring a test event longer than {MAX_LINE_LEN} bytes");
+            self.line.clear();
+        } else {
+            self.line.extend_from_slice(buf);
+        }
+    }
+}
+
+impl AsyncSeek for TestReportFile {
+    fn start_seek(self: Pin<&mut Self>, _position: SeekFrom) -> io::Result<()> {
+        Ok(())
+    }
+    fn poll_complete(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<u64>> {
+        Poll::Ready(Ok(0))
+    }
+}
+
+impl AsyncWrite for TestReportFile {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        self.write(buf);
+        Poll::Ready(Ok(buf.len()))
+    }
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+    fn poll_shutdown(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+    fn poll_write_vectored(
+        mut self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        bufs: &[IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        let mut written = 0;
+        for buf in bufs {
+            self.write(buf);
+            written += buf.len();
+        }
+        Poll::Ready(Ok(written))
+    }
+    fn is_write_vectored(&self) -> bool {
+        true
+    }
+}
+
+impl AsyncRead for TestReportFile {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        _buf: &mut tokio::io::ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl VirtualFile for TestReportFile {
+    fn last_accessed(&self) -> u64 {
+        0
+    }
+    fn last_modified(&self) -> u64 {
+        0
+    }
+    fn created_time(&self) -> u64 {
+        0
+    }
+    fn size(&self) -> u64 {
+        0
+    }
+    fn set_len(&mut self, _new_size: u64) -> virtual_fs::Result<()> {
+        Ok(())
+    }
+    fn unlink(&mut self) -> virtual_fs::Result<()> {
+        Ok(())
+    }
+    fn poll_read_ready(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<usize>> {
+        Poll::Ready(Ok(8192))
+    }
+    fn poll_write_ready(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<usize>> {
+        Poll::Ready(Ok(8192))
+    }
+}
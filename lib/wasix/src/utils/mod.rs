@@ -15,7 +15,6 @@ pub(crate) use owned_mutex_guard::{
 use std::collections::BTreeSet;
 
 use wasmer::Module;
-use wasmer_wasix_types::wasi::Errno;
 
 /// Check if a provided module is compiled for some version of WASI.
 /// Use [`get_wasi_version`] to find out which version of WASI the module is.
@@ -34,14 +33,10 @@ pub fn is_wasix_module(module: &Module) -> bool {
     }
 }
 
-pub fn map_io_err(err: std::io::Error) -> Errno {
-    From::<std::io::Error>::from(err)
-}
-
 #[cfg(feature = "journal")]
-pub fn map_snapshot_err(err: anyhow::Error) -> Errno {
+pub fn map_snapshot_err(err: anyhow::Error) -> wasmer_wasix_types::wasi::Errno {
     tracing::warn!("unknown snapshot error: {}", err);
-    Errno::Unknown
+    wasmer_wasix_types::wasi::Errno::Unknown
 }
 
 /// The version of WASI. This is determined by the imports namespace
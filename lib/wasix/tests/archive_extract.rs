@@ -0,0 +1,280 @@
+#![cfg(all(feature = "host-archive", not(target_family = "wasm")))]
+
+use std::{path::Path, sync::Arc};
+
+use virtual_fs::{limiter::FixedMemoryLimiter, mem_fs, AsyncReadExt, AsyncWriteExt, FileSystem};
+use virtual_mio::InlineWaker;
+use wasmer::Module;
+use wasmer_types::ModuleHash;
+use wasmer_wasix::{
+    capabilities::CapabilityArchiveV1,
+    runners::wasi::{RuntimeOrEngine, WasiRunner},
+    runtime::task_manager::tokio::TokioTaskManager,
+    wasmer_wasix_types::wasi::Errno,
+    Pipe, PluggableRuntime,
+};
+
+/// Extracts `/data/pkg.tar.gz` into `/data/out` in the background, polls it
+/// until it's done and then looks at what was extracted. It prints, as bytes:
+/// the errno of `archive_extract`, the outcome of the extraction, the errnos
+/// of reading the `pkg/current` symlink, of the stat of the `pkg/hello.txt`
+/// hard link and of opening a file through the symlink, followed by the
+/// number of entries and bytes extracted, and the target of the symlink.
+const PROGRAM: &str = r#"
+(module
+    (import "wasi_snapshot_preview1" "path_open" (func $path_open (param i32 i32 i32 i32 i32 i64 i64 i32 i32) (result i32)))
+    (import "wasi_snapshot_preview1" "path_readlink" (func $path_readlink (param i32 i32 i32 i32 i32 i32) (result i32)))
+    (import "wasi_snapshot_preview1" "path_filestat_get" (func $path_filestat_get (param i32 i32 i32 i32 i32) (result i32)))
+    (import "wasi_snapshot_preview1" "fd_write" (func $fd_write (param i32 i32 i32 i32) (result i32)))
+    (import "wasix_32v1" "archive_extract" (func $archive_extract (param i32 i32 i32 i32) (result i32)))
+    (import "wasix_32v1" "archive_extract_poll" (func $archive_extract_poll (param i32 i32 i32) (result i32)))
+    (memory (export "memory") 1)
+    (data (i32.const 300) "data/pkg.tar.gz")
+    (data (i32.const 320) "data/out")
+    (data (i32.const 340) "data/out/pkg/current")
+    (data (i32.const 380) "data/out/pkg/hello.txt")
+    (data (i32.const 420) "data/out/pkg/current/data.txt")
+
+    (func (export "_start")
+        (local $errno i32)
+        (if (call $path_open (i32.const 3) (i32.const 0) (i32.const 300) (i32.const 15)
+                (i32.const 0) (i64.const 2) (i64.const 0) (i32.const 0) (i32.const 0))
+            (then unreachable))
+        (if (call $path_open (i32.const 3) (i32.const 0) (i32.const 320) (i32.const 8)
+                (i32.const 2) (i64.const 0) (i64.const 0) (i32.const 0) (i32.const 4))
+            (then unreachable))
+
+        (i32.store8 (i32.const 200)
+            (call $archive_extract (i32.load (i32.const 0)) (i32.load (i32.const 4)) (i32.const 1) (i32.const 8)))
+        (loop $poll
+            (local.set $errno
+                (call $archive_extract_poll (i32.load (i32.const 8)) (i32.const 208) (i32.const 216)))
+            ;; EAGAIN
+            (br_if $poll (i32.eq (local.get $errno) (i32.const 6)))
+        )
+        (i32.store8 (i32.const 201) (local.get $errno))
+
+        (i32.store8 (i32.const 202)
+            (call $path_readlink (i32.const 3) (i32.const 340) (i32.const 20) (i32.const 232) (i32.const 64) (i32.const 224)))
+        (i32.store8 (i32.const 203)
+            (call $path_filestat_get (i32.const 3) (i32.const 0) (i32.const 380) (i32.const 22) (i32.const 400)))
+        (i32.store8 (i32.const 204)
+            (call $path_open (i32.const 3) (i32.const 1) (i32.const 420) (i32.const 29)
+                (i32.const 0) (i64.const 2) (i64.const 0) (i32.const 0) (i32.const 12)))
+
+        (i32.store (i32.const 16) (i32.const 200))
+        (i32.store (i32.const 20) (i32.const 96))
+        (if (call $fd_write (i32.const 1) (i32.const 16) (i32.const 1) (i32.const 24))
+            (then unreachable))
+    )
+)
+"#;
+
+const MTIME: u64 = 1_700_000_000;
+const BIG_FILE_LEN: usize = 4 * 1024 * 1024;
+
+struct Outcome {
+    extract: Errno,
+    result: Errno,
+    readlink: Errno,
+    hard_link: Errno,
+    through_symlink: Errno,
+    entries: u64,
+    bytes: u64,
+    symlink_target: String,
+}
+
+fn big_file() -> Vec<u8> {
+    (0..BIG_FILE_LEN).map(|i| (i % 251) as u8).collect()
+}
+
+/// A gzipped tarball with nested directories, a file, a symlink and a hard
+/// link to it, and a large file.
+fn fixture(symlink_target: &str) -> Vec<u8> {
+    let encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::fast());
+    let mut builder = tar::Builder::new(encoder);
+
+    let header = |kind: tar::EntryType, size: usize| {
+        let mut header = tar::Header::new_gnu();
+        header.set_entry_type(kind);
+        header.set_size(size as u64);
+        header.set_mode(0o755);
+        header.set_mtime(MTIME);
+        header
+    };
+    for dir in ["pkg/", "pkg/lib/", "pkg/lib/nested/"] {
+        builder
+            .append_data(
+                &mut header(tar::EntryType::Directory, 0),
+                dir,
+                std::io::empty(),
+            )
+            .unwrap();
+    }
+    builder
+        .append_data(
+            &mut header(tar::EntryType::Regular, 5),
+            "pkg/lib/nested/data.txt",
+            &b"hello"[..],
+        )
+        .unwrap();
+    builder
+        .append_link(
+            &mut header(tar::EntryType::Symlink, 0),
+            "pkg/current",
+            symlink_target,
+        )
+        .unwrap();
+    builder
+        .append_link(
+            &mut header(tar::EntryType::Link, 0),
+            "pkg/hello.txt",
+            "pkg/lib/nested/data.txt",
+        )
+        .unwrap();
+    builder
+        .append_data(
+            &mut header(tar::EntryType::Regular, BIG_FILE_LEN),
+            "pkg/big.bin",
+            &big_file()[..],
+        )
+        .unwrap();
+
+    builder.into_inner().unwrap().finish().unwrap()
+}
+
+fn run(data: &mem_fs::FileSystem, archive: &[u8], capability: CapabilityArchiveV1) -> Outcome {
+    let tokio_rt = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+    let _guard = tokio_rt.enter();
+
+    data.create_dir(Path::new("/out")).unwrap();
+    let mut file = data
+        .new_open_options()
+        .create(true)
+        .write(true)
+        .open(Path::new("/pkg.tar.gz"))
+        .unwrap();
+    InlineWaker::block_on(file.write_all(archive)).unwrap();
+
+    let engine = wasmer::Engine::default();
+    let module = Module::new(&engine, PROGRAM).unwrap();
+    let mut rt = PluggableRuntime::new(Arc::new(TokioTaskManager::new(tokio_rt.handle().clone())));
+    rt.set_engine(engine);
+
+    let (stdout_tx, mut stdout_rx) = Pipe::channel();
+    let mut runner = WasiRunner::new();
+    runner
+        .with_stdout(Box::new(stdout_tx))
+        .with_mount("/data".to_string(), Arc::new(data.clone()));
+    runner.capabilities_mut().archive = capability;
+    runner
+        .run_wasm(
+            RuntimeOrEngine::Runtime(Arc::new(rt)),
+            "archive-extract",
+            module,
+            ModuleHash::random(),
+        )
+        .unwrap();
+
+    let mut stdout = Vec::new();
+    InlineWaker::block_on(stdout_rx.read_to_end(&mut stdout)).unwrap();
+    let errno = |offset: usize| Errno::try_from(stdout[offset] as u16).unwrap();
+    let u64_at = |offset: usize| u64::from_le_bytes(stdout[offset..offset + 8].try_into().unwrap());
+    let target_len = u32::from_le_bytes(stdout[24..28].try_into().unwrap()) as usize;
+    Outcome {
+        extract: errno(0),
+        result: errno(1),
+        readlink: errno(2),
+        hard_link: errno(3),
+        through_symlink: errno(4),
+        entries: u64_at(8),
+        bytes: u64_at(16),
+        symlink_target: String::from_utf8_lossy(&stdout[32..32 + target_len.min(64)]).into_owned(),
+    }
+}
+
+fn read(fs: &mem_fs::FileSystem, path: &str) -> Vec<u8> {
+    let mut file = fs
+        .new_open_options()
+        .read(true)
+        .open(Path::new(path))
+        .unwrap();
+    let mut contents = Vec::new();
+    InlineWaker::block_on(file.read_to_end(&mut contents)).unwrap();
+    contents
+}
+
+fn enabled() -> CapabilityArchiveV1 {
+    CapabilityArchiveV1 { enabled: true }
+}
+
+#[test]
+fn extracts_into_the_filesystem() {
+    let data = mem_fs::FileSystem::default();
+    data.set_memory_limiter(Arc::new(FixedMemoryLimiter::new(64 * 1024 * 1024)));
+
+    let outcome = run(&data, &fixture("lib/nested"), enabled());
+
+    assert_eq!(outcome.extract, Errno::Success);
+    assert_eq!(outcome.result, Errno::Success);
+    assert_eq!(outcome.entries, 7);
+    assert_eq!(outcome.bytes, 5 + BIG_FILE_LEN as u64);
+    assert_eq!(outcome.readlink, Errno::Success);
+    assert_eq!(outcome.symlink_target, "lib/nested");
+    assert_eq!(outcome.hard_link, Errno::Success);
+    assert_eq!(outcome.through_symlink, Errno::Success);
+
+    assert!(data
+        .metadata(Path::new("/out/pkg/lib/nested"))
+        .unwrap()
+        .is_dir());
+    assert_eq!(read(&data, "/out/pkg/lib/nested/data.txt"), b"hello");
+    assert_eq!(read(&data, "/out/pkg/big.bin"), big_file());
+    let metadata = data
+        .metadata(Path::new("/out/pkg/lib/nested/data.txt"))
+        .unwrap();
+    assert_eq!(metadata.modified, MTIME * 1_000_000_000);
+}
+
+#[test]
+fn the_memory_limit_is_enforced() {
+    let data = mem_fs::FileSystem::default();
+    data.set_memory_limiter(Arc::new(FixedMemoryLimiter::new(1024 * 1024)));
+
+    let outcome = run(&data, &fixture("lib/nested"), enabled());
+
+    assert_eq!(outcome.extract, Errno::Success);
+    assert_eq!(outcome.result, Errno::Nospc);
+    // Everything before the large file made it
+    assert_eq!(outcome.entries, 6);
+    assert!(outcome.bytes < BIG_FILE_LEN as u64);
+    assert_eq!(read(&data, "/out/pkg/lib/nested/data.txt"), b"hello");
+}
+
+#[test]
+fn symlinks_cannot_escape_the_destination() {
+    let data = mem_fs::FileSystem::default();
+
+    let outcome = run(&data, &fixture("../../etc"), enabled());
+
+    assert_eq!(outcome.result, Errno::Notcapable);
+    assert_eq!(outcome.readlink, Errno::Noent);
+    assert_eq!(outcome.entries, 4);
+}
+
+#[test]
+fn extracting_requires_the_capability() {
+    let data = mem_fs::FileSystem::default();
+
+    let outcome = run(
+        &data,
+        &fixture("lib/nested"),
+        CapabilityArchiveV1::default(),
+    );
+
+    assert_eq!(outcome.extract, Errno::Notcapable);
+    assert!(data.read_dir(Path::new("/out")).unwrap().next().is_none());
+}
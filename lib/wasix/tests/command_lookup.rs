@@ -0,0 +1,131 @@
+#![cfg(not(target_family = "wasm"))]
+
+use std::{path::Path, sync::Arc};
+
+use virtual_fs::{mem_fs, AsyncReadExt, AsyncWriteExt, FileSystem};
+use wasmer_wasix::{
+    runtime::task_manager::tokio::TokioTaskManager, Pipe, PluggableRuntime, Runtime, WasiEnvBuilder,
+};
+
+/// A program that prints `message` followed by a newline.
+fn program(message: char) -> Vec<u8> {
+    let wat = format!(
+        r#"
+        (module
+            (import "wasi_snapshot_preview1" "fd_write" (func $fd_write (param i32 i32 i32 i32) (result i32)))
+            (memory (export "memory") 1)
+            (data (i32.const 100) "{message}\n")
+
+            (func (export "_start")
+                (i32.store (i32.const 16) (i32.const 100))
+                (i32.store (i32.const 20) (i32.const 2))
+                (if (call $fd_write (i32.const 1) (i32.const 16) (i32.const 1) (i32.const 8))
+                    (then unreachable))
+            )
+        )
+        "#
+    );
+    wasmer::wat2wasm(wat.as_bytes()).unwrap().to_vec()
+}
+
+async fn write_file(fs: &mem_fs::FileSystem, path: &str, contents: &[u8]) {
+    let path = Path::new(path);
+    fs.create_dir(path.parent().unwrap()).ok();
+    let mut f = fs
+        .new_open_options()
+        .create(true)
+        .write(true)
+        .open(path)
+        .unwrap();
+    f.write_all(contents).await.unwrap();
+}
+
+/// Two programs called `hello` in `/a` and `/b`, and scripts running
+/// `hello` in `/usr/local/bin`.
+async fn file_system() -> mem_fs::FileSystem {
+    let fs = mem_fs::FileSystem::default();
+    fs.create_dir(Path::new("/usr")).unwrap();
+    write_file(&fs, "/a/hello", &program('a')).await;
+    write_file(&fs, "/b/hello", &program('b')).await;
+    write_file(&fs, "/usr/local/bin/greet", b"#!/usr/bin/env hello\n").await;
+    write_file(&fs, "/usr/local/bin/greet-a", b"#!/a/hello -x\n").await;
+    fs
+}
+
+/// Spawns `command` with the given `PATH` and returns what it printed.
+async fn run(
+    rt: Arc<dyn Runtime + Send + Sync>,
+    fs: &mem_fs::FileSystem,
+    path: &str,
+    command: &str,
+) -> String {
+    let (stdout_tx, mut stdout_rx) = Pipe::channel();
+    let env = WasiEnvBuilder::new(command)
+        .runtime(rt)
+        .fs(Box::new(fs.clone()))
+        .env("PATH", path)
+        .stdout(Box::new(stdout_tx))
+        .build()
+        .unwrap();
+    let bin_factory = env.bin_factory.clone();
+
+    let mut task = bin_factory.spawn(command.to_string(), env).await.unwrap();
+    task.wait_finished().await.unwrap();
+
+    let mut output = [0; 2];
+    stdout_rx.read_exact(&mut output).await.unwrap();
+    String::from_utf8(output.to_vec()).unwrap()
+}
+
+#[test]
+fn path_decides_which_command_runs() {
+    let tokio_rt = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+    let handle = tokio_rt.handle().clone();
+    let _guard = handle.enter();
+    let rt: Arc<dyn Runtime + Send + Sync> = Arc::new(PluggableRuntime::new(Arc::new(
+        TokioTaskManager::new(tokio_rt),
+    )));
+
+    handle.block_on(async {
+        let fs = file_system().await;
+
+        assert_eq!(run(rt.clone(), &fs, "/a:/b", "hello").await, "a\n");
+        assert_eq!(run(rt.clone(), &fs, "/b:/a", "hello").await, "b\n");
+        assert_eq!(run(rt.clone(), &fs, "/missing:/b", "hello").await, "b\n");
+
+        // Lookups notice new files in directories that were searched
+        write_file(&fs, "/missing/hello", &program('m')).await;
+        assert_eq!(run(rt.clone(), &fs, "/missing:/b", "hello").await, "m\n");
+    });
+}
+
+#[test]
+fn scripts_run_with_their_interpreter() {
+    let tokio_rt = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+    let handle = tokio_rt.handle().clone();
+    let _guard = handle.enter();
+    let rt: Arc<dyn Runtime + Send + Sync> = Arc::new(PluggableRuntime::new(Arc::new(
+        TokioTaskManager::new(tokio_rt),
+    )));
+
+    handle.block_on(async {
+        let fs = file_system().await;
+
+        // `#!/usr/bin/env hello` looks the interpreter up in PATH
+        let path = "/usr/local/bin:/b:/a";
+        assert_eq!(run(rt.clone(), &fs, path, "greet").await, "b\n");
+        let path = "/usr/local/bin:/a:/b";
+        assert_eq!(run(rt.clone(), &fs, path, "greet").await, "a\n");
+
+        assert_eq!(
+            run(rt.clone(), &fs, "/b:/usr/local/bin", "greet-a").await,
+            "a\n"
+        );
+    });
+}
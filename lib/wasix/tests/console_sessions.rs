@@ -0,0 +1,270 @@
+#![cfg(not(target_family = "wasm"))]
+
+use std::{path::Path, sync::Arc};
+
+use virtual_fs::{AsyncReadExt, FileSystem, Pipe};
+use wasmer_wasix::{
+    os::Console,
+    runtime::{
+        package_loader::BuiltinPackageLoader, resolver::FileSystemSource,
+        task_manager::tokio::TokioTaskManager,
+    },
+    PluggableRuntime,
+};
+
+const WASMER_TOML: &str = r#"
+[package]
+name = "test/session"
+version = "0.1.0"
+
+[[module]]
+name = "main"
+source = "main.wasm"
+abi = "wasi"
+
+[[command]]
+name = "main"
+module = "main"
+"#;
+
+/// Prints "seen" if `/tmp/shared` already exists, otherwise creates it and
+/// prints "new". It then waits for stdin to be closed.
+const MAIN_WAT: &str = r#"
+(module
+    (import "wasi_snapshot_preview1" "path_open" (func $path_open (param i32 i32 i32 i32 i32 i64 i64 i32 i32) (result i32)))
+    (import "wasi_snapshot_preview1" "fd_read" (func $fd_read (param i32 i32 i32 i32) (result i32)))
+    (import "wasi_snapshot_preview1" "fd_write" (func $fd_write (param i32 i32 i32 i32) (result i32)))
+    (memory (export "memory") 1)
+    (data (i32.const 100) "tmp/shared")
+    (data (i32.const 120) "seen\n")
+    (data (i32.const 130) "new\n")
+
+    (func $print (param $ptr i32) (param $len i32)
+        (i32.store (i32.const 16) (local.get $ptr))
+        (i32.store (i32.const 20) (local.get $len))
+        (drop (call $fd_write (i32.const 1) (i32.const 16) (i32.const 1) (i32.const 24)))
+    )
+
+    (func (export "_start")
+        (if (call $path_open (i32.const 3) (i32.const 0) (i32.const 100) (i32.const 10)
+                (i32.const 0) (i64.const 2) (i64.const 0) (i32.const 0) (i32.const 32))
+            (then
+                ;; O_CREAT
+                (if (call $path_open (i32.const 3) (i32.const 0) (i32.const 100) (i32.const 10)
+                        (i32.const 1) (i64.const 0x40) (i64.const 0) (i32.const 0) (i32.const 32))
+                    (then unreachable))
+                (call $print (i32.const 130) (i32.const 4)))
+            (else
+                (call $print (i32.const 120) (i32.const 5))))
+
+        ;; Wait for stdin to be closed
+        (i32.store (i32.const 0) (i32.const 1024))
+        (i32.store (i32.const 4) (i32.const 1024))
+        (loop $read
+            (if (call $fd_read (i32.const 0) (i32.const 0) (i32.const 1) (i32.const 8))
+                (then return))
+            (br_if $read (i32.load (i32.const 8))))
+    )
+)
+"#;
+
+/// Prints "tty" to `/dev/tty`, then waits for stdin to be closed.
+const TTY_WAT: &str = r#"
+(module
+    (import "wasi_snapshot_preview1" "path_open" (func $path_open (param i32 i32 i32 i32 i32 i64 i64 i32 i32) (result i32)))
+    (import "wasi_snapshot_preview1" "fd_read" (func $fd_read (param i32 i32 i32 i32) (result i32)))
+    (import "wasi_snapshot_preview1" "fd_write" (func $fd_write (param i32 i32 i32 i32) (result i32)))
+    (memory (export "memory") 1)
+    (data (i32.const 100) "dev/tty")
+    (data (i32.const 120) "tty\n")
+
+    (func (export "_start")
+        ;; FD_WRITE
+        (if (call $path_open (i32.const 3) (i32.const 0) (i32.const 100) (i32.const 7)
+                (i32.const 0) (i64.const 0x40) (i64.const 0) (i32.const 0) (i32.const 32))
+            (then unreachable))
+        (i32.store (i32.const 16) (i32.const 120))
+        (i32.store (i32.const 20) (i32.const 4))
+        (drop (call $fd_write (i32.load (i32.const 32)) (i32.const 16) (i32.const 1) (i32.const 24)))
+
+        ;; Wait for stdin to be closed
+        (i32.store (i32.const 0) (i32.const 1024))
+        (i32.store (i32.const 4) (i32.const 1024))
+        (loop $read
+            (if (call $fd_read (i32.const 0) (i32.const 0) (i32.const 1) (i32.const 8))
+                (then return))
+            (br_if $read (i32.load (i32.const 8))))
+    )
+)
+"#;
+
+/// A console whose boot command runs `wat`.
+fn console(temp: &Path, wat: &str, handle: &tokio::runtime::Handle) -> Console {
+    let pkg_dir = temp.join("pkg");
+    std::fs::create_dir(&pkg_dir).unwrap();
+    std::fs::write(pkg_dir.join("wasmer.toml"), WASMER_TOML).unwrap();
+    std::fs::write(
+        pkg_dir.join("main.wasm"),
+        wasmer::wat2wasm(wat.as_bytes()).unwrap(),
+    )
+    .unwrap();
+    let webc = wasmer_package::package::Package::from_manifest(pkg_dir.join("wasmer.toml"))
+        .unwrap()
+        .serialize()
+        .unwrap();
+    let webc_path = temp.join("session.webc");
+    std::fs::write(&webc_path, webc).unwrap();
+
+    let mut rt = PluggableRuntime::new(Arc::new(TokioTaskManager::new(handle.clone())));
+    rt.set_source(FileSystemSource::default())
+        .set_package_loader(BuiltinPackageLoader::new());
+
+    Console::new(webc_path.to_str().unwrap(), Arc::new(rt)).with_no_welcome(true)
+}
+
+fn tokio_runtime() -> tokio::runtime::Runtime {
+    tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .unwrap()
+}
+
+#[test]
+fn sessions_share_processes_and_files() {
+    let temp = tempfile::tempdir().unwrap();
+    let tokio_rt = tokio_runtime();
+    let handle = tokio_rt.handle().clone();
+    let _guard = handle.enter();
+    let mut console = console(temp.path(), MAIN_WAT, &handle);
+
+    // Session A keeps running in the background until we close its stdin
+    let (stdin_a_tx, stdin_a_rx) = Pipe::channel();
+    let (stdout_a_tx, mut stdout_a_rx) = Pipe::channel();
+    let mut session_a = console
+        .new_session(
+            Box::new(stdin_a_rx),
+            Box::new(stdout_a_tx),
+            Box::new(Pipe::channel().0),
+        )
+        .unwrap();
+    let mut line = [0u8; 4];
+    handle.block_on(stdout_a_rx.read_exact(&mut line)).unwrap();
+    assert_eq!(&line, b"new\n");
+
+    // Session B sees the file A created
+    let (stdout_b_tx, mut stdout_b_rx) = Pipe::channel();
+    let mut session_b = console
+        .new_session(
+            Box::new(Pipe::channel().1),
+            Box::new(stdout_b_tx),
+            Box::new(Pipe::channel().0),
+        )
+        .unwrap();
+    let code = handle.block_on(session_b.handle().wait_finished()).unwrap();
+    assert!(code.is_success());
+    let mut output = String::new();
+    handle
+        .block_on(stdout_b_rx.read_to_string(&mut output))
+        .unwrap();
+    assert_eq!(output, "seen\n");
+
+    // ... and A's process, along with both sessions
+    let host = console.host();
+    let sessions: Vec<_> = host.sessions().iter().map(|s| s.pid).collect();
+    assert_eq!(
+        sessions,
+        [session_a.process().pid(), session_b.process().pid()]
+    );
+    let processes = host.processes();
+    let a = processes
+        .iter()
+        .find(|p| p.pid() == session_a.process().pid())
+        .expect("session A's process isn't listed");
+    assert!(a.try_join().is_none());
+
+    // Closing B doesn't affect A or the shared file system
+    drop(session_b);
+    assert_eq!(host.sessions().len(), 1);
+    assert!(session_a.process().try_join().is_none());
+    assert!(host
+        .root_fs()
+        .metadata(std::path::Path::new("/tmp/shared"))
+        .is_ok());
+
+    drop(stdin_a_tx);
+    let code = handle.block_on(session_a.handle().wait_finished()).unwrap();
+    assert!(code.is_success());
+}
+
+#[test]
+fn each_session_has_its_own_tty() {
+    let temp = tempfile::tempdir().unwrap();
+    let tokio_rt = tokio_runtime();
+    let handle = tokio_rt.handle().clone();
+    let _guard = handle.enter();
+    let mut console = console(temp.path(), TTY_WAT, &handle);
+
+    let mut sessions = Vec::new();
+    for _ in 0..2 {
+        let (stdin_tx, stdin_rx) = Pipe::channel();
+        let (stdout_tx, mut stdout_rx) = Pipe::channel();
+        let session = console
+            .new_session(
+                Box::new(stdin_rx),
+                Box::new(stdout_tx),
+                Box::new(Pipe::channel().0),
+            )
+            .unwrap();
+        let mut line = [0u8; 4];
+        handle.block_on(stdout_rx.read_exact(&mut line)).unwrap();
+        assert_eq!(&line, b"tty\n");
+        sessions.push((session, stdin_tx, stdout_rx));
+    }
+
+    // Nothing leaked into the other session's terminal
+    for (mut session, stdin_tx, mut stdout_rx) in sessions {
+        drop(stdin_tx);
+        let code = handle.block_on(session.handle().wait_finished()).unwrap();
+        assert!(code.is_success());
+        let mut rest = String::new();
+        handle
+            .block_on(stdout_rx.read_to_string(&mut rest))
+            .unwrap();
+        assert_eq!(rest, "");
+    }
+}
+
+#[test]
+fn run_keeps_the_session_until_the_command_exits() {
+    let temp = tempfile::tempdir().unwrap();
+    let tokio_rt = tokio_runtime();
+    let handle = tokio_rt.handle().clone();
+    let _guard = handle.enter();
+    let (stdin_tx, stdin_rx) = Pipe::channel();
+    let (stdout_tx, mut stdout_rx) = Pipe::channel();
+    let mut console = console(temp.path(), TTY_WAT, &handle)
+        .with_stdin(Box::new(stdin_rx))
+        .with_stdout(Box::new(stdout_tx));
+
+    let (mut join, process) = console.run().unwrap();
+    let mut line = [0u8; 4];
+    handle.block_on(stdout_rx.read_exact(&mut line)).unwrap();
+    assert_eq!(&line, b"tty\n");
+    let host = console.host();
+    let pids: Vec<_> = host.sessions().iter().map(|s| s.pid).collect();
+    assert_eq!(pids, [process.pid()]);
+
+    drop(stdin_tx);
+    let code = handle.block_on(join.wait_finished()).unwrap();
+    assert!(code.is_success());
+    let detached = handle.block_on(async {
+        for _ in 0..100 {
+            if host.sessions().is_empty() {
+                return true;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        false
+    });
+    assert!(detached, "the session outlived its command");
+}
@@ -0,0 +1,140 @@
+#![cfg(not(target_family = "wasm"))]
+
+use wasmer::{Instance, Module, Store, Value};
+use wasmer_wasix::{
+    os::task::control_plane::{ControlPlaneConfig, WasiControlPlane},
+    wasmer_wasix_types::wasi::Errno,
+    WasiEnv, WasiFunctionEnv,
+};
+
+/// Starts with a memory of one page, and exports:
+///
+/// - `grow(delta)`, which returns what `memory.grow` does;
+/// - `crash()`, which traps.
+const GUEST: &str = r#"
+(module
+    (memory (export "memory") 1)
+    (func (export "grow") (param $delta i32) (result i32)
+        (memory.grow (local.get $delta)))
+    (func (export "crash")
+        unreachable)
+)
+"#;
+
+struct Guest {
+    store: Store,
+    instance: Instance,
+    env: WasiFunctionEnv,
+}
+
+impl Guest {
+    fn new(control_plane: &WasiControlPlane) -> Self {
+        let mut store = Store::default();
+        let module = Module::new(&store, GUEST).unwrap();
+        let (instance, env) = WasiEnv::builder("limits")
+            .engine(store.engine().clone())
+            .control_plane(control_plane.clone())
+            .instantiate(module, &mut store)
+            .unwrap();
+
+        Guest {
+            store,
+            instance,
+            env,
+        }
+    }
+
+    fn grow(&mut self, delta: i32) -> i32 {
+        let func = self.instance.exports.get_function("grow").unwrap();
+        func.call(&mut self.store, &[Value::I32(delta)]).unwrap()[0].unwrap_i32()
+    }
+
+    fn pid(&self) -> u32 {
+        self.env.data(&self.store).pid().raw()
+    }
+
+    fn exit(&self) {
+        self.env
+            .data(&self.store)
+            .process
+            .terminate(Errno::Success.into());
+    }
+}
+
+fn runtime() -> tokio::runtime::Runtime {
+    tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .unwrap()
+}
+
+fn control_plane(max_memory_pages: u64) -> WasiControlPlane {
+    WasiControlPlane::new(ControlPlaneConfig {
+        max_memory_pages: Some(max_memory_pages),
+        ..ControlPlaneConfig::new()
+    })
+}
+
+/// The pages charged to each process that is still running.
+fn memory_by_pid(control_plane: &WasiControlPlane) -> Vec<(u32, u64)> {
+    control_plane
+        .resource_usage()
+        .processes
+        .iter()
+        .map(|process| (process.pid.raw(), process.memory_pages))
+        .collect()
+}
+
+#[test]
+fn memory_is_released_when_a_process_exits() {
+    let rt = runtime();
+    let _guard = rt.enter();
+    let plane = control_plane(10);
+    let mut a = Guest::new(&plane);
+    let mut b = Guest::new(&plane);
+    assert_eq!(plane.resource_usage().memory_pages, 2);
+
+    // The second process is blocked by the first one
+    assert_eq!(a.grow(5), 1);
+    assert_eq!(b.grow(4), -1);
+    assert_eq!(b.grow(3), 1);
+
+    let usage = plane.resource_usage();
+    assert_eq!(usage.process_count, 2);
+    assert_eq!(usage.memory_pages, 10);
+    assert_eq!(memory_by_pid(&plane), vec![(a.pid(), 6), (b.pid(), 4)]);
+
+    // Once the first one exits, the second one can use its memory
+    a.exit();
+    let usage = plane.resource_usage();
+    assert_eq!(usage.process_count, 1);
+    assert_eq!(usage.memory_pages, 4);
+    assert_eq!(memory_by_pid(&plane), vec![(b.pid(), 4)]);
+
+    assert_eq!(b.grow(6), 4);
+    assert_eq!(plane.resource_usage().memory_pages, 10);
+
+    // An exited process doesn't get anything back
+    assert_eq!(a.grow(1), -1);
+}
+
+#[test]
+fn crashed_processes_are_released() {
+    let rt = runtime();
+    let _guard = rt.enter();
+    let plane = control_plane(5);
+    let mut a = Guest::new(&plane);
+    assert_eq!(a.grow(3), 1);
+
+    let crash = a.instance.exports.get_function("crash").unwrap();
+    assert!(crash.call(&mut a.store, &[]).is_err());
+    let mut b = Guest::new(&plane);
+    assert_eq!(b.grow(1), -1);
+
+    // Nothing cleans up after the crashed process, it is just dropped
+    drop(a);
+    let usage = plane.resource_usage();
+    assert_eq!(usage.process_count, 1);
+    assert_eq!(usage.memory_pages, 1);
+    assert_eq!(b.grow(3), 1);
+}
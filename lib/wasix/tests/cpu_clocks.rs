@@ -0,0 +1,140 @@
+#![cfg(not(target_family = "wasm"))]
+
+use std::{path::Path, sync::Arc, time::Duration};
+
+use virtual_fs::{AsyncReadExt, Pipe};
+use wasmer_wasix::{
+    os::Console,
+    runtime::{
+        package_loader::BuiltinPackageLoader, resolver::FileSystemSource,
+        task_manager::tokio::TokioTaskManager,
+    },
+    PluggableRuntime,
+};
+
+/// Spawns a thread that spins until its thread CPU clock reaches 100ms (and
+/// traps if the clock ever goes backwards) while the main thread sleeps and
+/// then joins it. It prints the CPU time of the main thread, of the process
+/// and of the spinning thread (as little-endian `u64`s).
+const PROGRAM: &str = r#"
+(module
+    (import "wasi_snapshot_preview1" "clock_time_get" (func $clock_time_get (param i32 i64 i32) (result i32)))
+    (import "wasi_snapshot_preview1" "fd_write" (func $fd_write (param i32 i32 i32 i32) (result i32)))
+    (import "wasix_32v1" "thread_spawn_v2" (func $thread_spawn (param i32 i32) (result i32)))
+    (import "wasix_32v1" "thread_sleep" (func $thread_sleep (param i64) (result i32)))
+    (import "wasix_32v1" "thread_join" (func $thread_join (param i32) (result i32)))
+    (import "env" "memory" (memory 2 2 shared))
+    (export "memory" (memory 0))
+
+    ;; CLOCK_PROCESS_CPUTIME_ID = 2, CLOCK_THREAD_CPUTIME_ID = 3
+    (func (export "wasi_thread_start") (param $tid i32) (param $start i32)
+        (local $previous i64)
+        (loop $spin
+            (drop (call $clock_time_get (i32.const 3) (i64.const 1) (i32.const 2016)))
+            (if (i64.lt_u (i64.load (i32.const 2016)) (local.get $previous))
+                (then unreachable))
+            (local.set $previous (i64.load (i32.const 2016)))
+            (br_if $spin (i64.lt_u (local.get $previous) (i64.const 100000000))))
+    )
+
+    (func (export "_start")
+        ;; ThreadStart { stack_upper, .., stack_size, guard_size }
+        (i32.store (i32.const 1024) (i32.const 131072))
+        (i32.store (i32.const 1080) (i32.const 8192))
+        (if (call $thread_spawn (i32.const 1024) (i32.const 1100))
+            (then unreachable))
+
+        (drop (call $thread_sleep (i64.const 50000000)))
+        (drop (call $thread_join (i32.load (i32.const 1100))))
+
+        (drop (call $clock_time_get (i32.const 3) (i64.const 1) (i32.const 2000)))
+        (drop (call $clock_time_get (i32.const 2) (i64.const 1) (i32.const 2008)))
+
+        (i32.store (i32.const 16) (i32.const 2000))
+        (i32.store (i32.const 20) (i32.const 24))
+        (drop (call $fd_write (i32.const 1) (i32.const 16) (i32.const 1) (i32.const 24)))
+    )
+)
+"#;
+
+fn build_package(dir: &Path) -> String {
+    let wasmer_toml = r#"
+[package]
+name = "test/cpu-clocks"
+version = "0.1.0"
+
+[[module]]
+name = "main"
+source = "main.wasm"
+abi = "wasi"
+
+[[command]]
+name = "main"
+module = "main"
+"#;
+    let pkg_dir = dir.join("pkg");
+    std::fs::create_dir(&pkg_dir).unwrap();
+    std::fs::write(pkg_dir.join("wasmer.toml"), wasmer_toml).unwrap();
+    std::fs::write(
+        pkg_dir.join("main.wasm"),
+        wasmer::wat2wasm(PROGRAM.as_bytes()).unwrap(),
+    )
+    .unwrap();
+    let webc = wasmer_package::package::Package::from_manifest(pkg_dir.join("wasmer.toml"))
+        .unwrap()
+        .serialize()
+        .unwrap();
+
+    let webc_path = dir.join("cpu-clocks.webc");
+    std::fs::write(&webc_path, webc).unwrap();
+    webc_path.to_str().unwrap().to_string()
+}
+
+#[test]
+fn cpu_clocks_are_monotonic_and_ordered() {
+    let temp = tempfile::tempdir().unwrap();
+    let webc = build_package(temp.path());
+
+    let tokio_rt = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+    let handle = tokio_rt.handle().clone();
+    let _guard = handle.enter();
+
+    let mut rt = PluggableRuntime::new(Arc::new(TokioTaskManager::new(tokio_rt)));
+    rt.set_source(FileSystemSource::default())
+        .set_package_loader(BuiltinPackageLoader::new());
+
+    let (stdout_tx, mut stdout_rx) = Pipe::channel();
+    let mut session = Console::new(&webc, Arc::new(rt))
+        .with_no_welcome(true)
+        .new_session(
+            Box::new(Pipe::channel().1),
+            Box::new(stdout_tx),
+            Box::new(Pipe::channel().0),
+        )
+        .unwrap();
+    let code = handle.block_on(session.handle().wait_finished()).unwrap();
+    assert!(code.is_success());
+
+    let mut output = Vec::new();
+    handle.block_on(stdout_rx.read_to_end(&mut output)).unwrap();
+    let [main, process, spinner]: [Duration; 3] = output
+        .chunks_exact(8)
+        .map(|chunk| Duration::from_nanos(u64::from_le_bytes(chunk.try_into().unwrap())))
+        .collect::<Vec<_>>()
+        .try_into()
+        .unwrap();
+
+    // How much more the process clock shows than the threads' depends on the
+    // scheduler, so only the ordering is checked
+    assert!(spinner >= Duration::from_millis(100), "{spinner:?}");
+    assert!(process >= main, "{process:?} vs {main:?}");
+    assert!(process >= spinner, "{process:?} vs {spinner:?}");
+
+    // The clocks are frozen once the process has finished
+    let process_cpu = session.process().cpu_time();
+    assert!(process_cpu >= process, "{process_cpu:?}");
+    assert_eq!(process_cpu, session.process().cpu_time());
+}
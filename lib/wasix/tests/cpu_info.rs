@@ -0,0 +1,92 @@
+#![cfg(not(target_family = "wasm"))]
+
+use std::sync::Arc;
+
+use virtual_fs::AsyncReadExt;
+use virtual_mio::InlineWaker;
+use wasmer::Module;
+use wasmer_types::ModuleHash;
+use wasmer_wasix::{
+    runners::wasi::{RuntimeOrEngine, WasiRunner},
+    runtime::task_manager::tokio::TokioTaskManager,
+    Pipe, PluggableRuntime,
+};
+
+/// Prints the CPU count reported by `thread_parallelism()` (as a
+/// little-endian `u32`), followed by the contents of `/proc/cpuinfo`.
+const PROGRAM: &str = r#"
+(module
+    (import "wasix_32v1" "thread_parallelism" (func $thread_parallelism (param i32) (result i32)))
+    (import "wasi_snapshot_preview1" "path_open" (func $path_open (param i32 i32 i32 i32 i32 i64 i64 i32 i32) (result i32)))
+    (import "wasi_snapshot_preview1" "fd_write" (func $fd_write (param i32 i32 i32 i32) (result i32)))
+    (import "wasi_snapshot_preview1" "fd_read" (func $fd_read (param i32 i32 i32 i32) (result i32)))
+    (memory (export "memory") 1)
+    (data (i32.const 100) "proc/cpuinfo")
+
+    (func (export "_start")
+        (if (call $thread_parallelism (i32.const 1024))
+            (then unreachable))
+        (i32.store (i32.const 16) (i32.const 1024))
+        (i32.store (i32.const 20) (i32.const 4))
+        (if (call $fd_write (i32.const 1) (i32.const 16) (i32.const 1) (i32.const 8))
+            (then unreachable))
+
+        (if (call $path_open (i32.const 3) (i32.const 0) (i32.const 100) (i32.const 12)
+                (i32.const 0) (i64.const -1) (i64.const -1) (i32.const 0) (i32.const 0))
+            (then unreachable))
+        (i32.store (i32.const 16) (i32.const 2048))
+        (i32.store (i32.const 20) (i32.const 4096))
+        (if (call $fd_read (i32.load (i32.const 0)) (i32.const 16) (i32.const 1) (i32.const 8))
+            (then unreachable))
+        (i32.store (i32.const 20) (i32.load (i32.const 8)))
+        (if (call $fd_write (i32.const 1) (i32.const 16) (i32.const 1) (i32.const 8))
+            (then unreachable))
+    )
+)
+"#;
+
+#[test]
+fn cpu_count_can_be_overridden() {
+    let tokio_rt = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+    let _guard = tokio_rt.enter();
+
+    let engine = wasmer::Engine::default();
+    let module = Module::new(&engine, PROGRAM).unwrap();
+    let mut rt = PluggableRuntime::new(Arc::new(TokioTaskManager::new(tokio_rt.handle().clone())));
+    rt.set_engine(engine);
+
+    let (stdout_tx, mut stdout_rx) = Pipe::channel();
+    let mut runner = WasiRunner::new();
+    runner.capabilities_mut().cpu.count = Some(4);
+    runner
+        .with_stdout(Box::new(stdout_tx))
+        .run_wasm(
+            RuntimeOrEngine::Runtime(Arc::new(rt)),
+            "cpu-info",
+            module,
+            ModuleHash::random(),
+        )
+        .unwrap();
+
+    let mut stdout = Vec::new();
+    InlineWaker::block_on(stdout_rx.read_to_end(&mut stdout)).unwrap();
+    let (count, cpuinfo) = stdout.split_at(4);
+    assert_eq!(u32::from_le_bytes(count.try_into().unwrap()), 4);
+    let cpuinfo = String::from_utf8_lossy(cpuinfo);
+    let processors: Vec<_> = cpuinfo
+        .lines()
+        .filter(|line| line.starts_with("processor"))
+        .collect();
+    assert_eq!(
+        processors,
+        [
+            "processor\t: 0",
+            "processor\t: 1",
+            "processor\t: 2",
+            "processor\t: 3"
+        ]
+    );
+}
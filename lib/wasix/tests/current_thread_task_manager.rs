@@ -0,0 +1,94 @@
+#![cfg(target_os = "linux")]
+
+use std::{collections::HashSet, sync::Arc};
+
+use virtual_fs::AsyncReadExt;
+use virtual_mio::InlineWaker;
+use wasmer::Module;
+use wasmer_types::ModuleHash;
+use wasmer_wasix::{
+    runners::wasi::{RuntimeOrEngine, WasiRunner},
+    runtime::task_manager::current_thread::CurrentThreadTaskManager,
+    Pipe, PluggableRuntime,
+};
+
+/// Prints "hello", sleeps for 10ms and then tries to spawn a thread, printing
+/// "no threads" if that fails with `Errno::Notsup`.
+const PROGRAM: &str = r#"
+(module
+    (import "wasi_snapshot_preview1" "fd_write" (func $fd_write (param i32 i32 i32 i32) (result i32)))
+    (import "wasix_32v1" "thread_sleep" (func $thread_sleep (param i64) (result i32)))
+    (import "wasix_32v1" "thread_spawn_v2" (func $thread_spawn (param i32 i32) (result i32)))
+    (import "env" "memory" (memory 1 1 shared))
+    (export "memory" (memory 0))
+    (data (i32.const 100) "hello\n")
+    (data (i32.const 110) "no threads\n")
+
+    (func $print (param $buf i32) (param $len i32)
+        (i32.store (i32.const 0) (local.get $buf))
+        (i32.store (i32.const 4) (local.get $len))
+        (if (call $fd_write (i32.const 1) (i32.const 0) (i32.const 1) (i32.const 8))
+            (then unreachable))
+    )
+
+    (func (export "wasi_thread_start") (param i32 i32))
+
+    (func (export "_start")
+        (call $print (i32.const 100) (i32.const 6))
+        (if (call $thread_sleep (i64.const 10000000))
+            (then unreachable))
+        ;; 58 is `Errno::Notsup`
+        (if (i32.eq (call $thread_spawn (i32.const 1024) (i32.const 1536)) (i32.const 58))
+            (then (call $print (i32.const 110) (i32.const 11))))
+    )
+)
+"#;
+
+fn thread_ids() -> HashSet<String> {
+    std::fs::read_dir("/proc/self/task")
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .collect()
+}
+
+#[test]
+fn runs_without_spawning_threads() {
+    // Compiling may start a pool of compiler threads, so do it up front
+    let engine = wasmer::Engine::default();
+    let module = Module::new(&engine, PROGRAM).unwrap();
+
+    // Entering a current-thread runtime stops the runner from creating a
+    // multi-threaded one
+    let tokio_rt = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+    let _guard = tokio_rt.enter();
+
+    let mut rt = PluggableRuntime::new(Arc::new(CurrentThreadTaskManager::new()));
+    rt.set_engine(engine);
+
+    let threads_before = thread_ids();
+
+    let (stdout_tx, mut stdout_rx) = Pipe::channel();
+    WasiRunner::new()
+        .with_stdout(Box::new(stdout_tx))
+        .run_wasm(
+            RuntimeOrEngine::Runtime(Arc::new(rt)),
+            "current-thread",
+            module,
+            ModuleHash::random(),
+        )
+        .unwrap();
+
+    // Threads that existed before may have exited, but no new ones started
+    let new_threads = thread_ids()
+        .difference(&threads_before)
+        .cloned()
+        .collect::<Vec<_>>();
+    assert!(new_threads.is_empty(), "{new_threads:?}");
+
+    let mut stdout = Vec::new();
+    InlineWaker::block_on(stdout_rx.read_to_end(&mut stdout)).unwrap();
+    assert_eq!(String::from_utf8_lossy(&stdout), "hello\nno threads\n");
+}
@@ -0,0 +1,138 @@
+#![cfg(not(target_family = "wasm"))]
+
+use std::{path::Path, sync::Arc, time::Duration};
+
+use virtual_fs::{mem_fs, AsyncReadExt, AsyncWriteExt, FileSystem};
+use wasmer_wasix::{
+    runtime::task_manager::tokio::TokioTaskManager, wasmer_wasix_types::wasi::Signal, Pipe,
+    PluggableRuntime, WasiEnvBuilder, WasiProcessId,
+};
+
+/// Spawns `/looper` with its stdout piped back, waits for it to say it
+/// has daemonized, prints its pid (as a little-endian `u32`) and exits.
+const SHELL: &str = r#"
+(module
+    (import "wasi_snapshot_preview1" "fd_read" (func $fd_read (param i32 i32 i32 i32) (result i32)))
+    (import "wasi_snapshot_preview1" "fd_write" (func $fd_write (param i32 i32 i32 i32) (result i32)))
+    (import "wasix_32v1" "proc_spawn" (func $proc_spawn (param i32 i32 i32 i32 i32 i32 i32 i32 i32 i32 i32 i32 i32) (result i32)))
+    (memory (export "memory") 1)
+    (data (i32.const 100) "/looper")
+    (data (i32.const 120) ".")
+
+    (func (export "_start")
+        ;; stdin and stderr are null, stdout is piped
+        (if (call $proc_spawn (i32.const 100) (i32.const 7) (i32.const 0)
+                (i32.const 100) (i32.const 7) (i32.const 0) (i32.const 0)
+                (i32.const 2) (i32.const 0) (i32.const 2)
+                (i32.const 120) (i32.const 1) (i32.const 200))
+            (then unreachable))
+
+        ;; Wait for "rdy\n" on the pipe (its fd is at 216)
+        (i32.store (i32.const 32) (i32.const 300))
+        (i32.store (i32.const 36) (i32.const 4))
+        (if (call $fd_read (i32.load (i32.const 216)) (i32.const 32) (i32.const 1) (i32.const 40))
+            (then unreachable))
+        (if (i32.ne (i32.load (i32.const 300)) (i32.const 0x0a796472))
+            (then unreachable))
+
+        (i32.store (i32.const 32) (i32.const 200))
+        (i32.store (i32.const 36) (i32.const 4))
+        (drop (call $fd_write (i32.const 1) (i32.const 32) (i32.const 1) (i32.const 40)))
+    )
+)
+"#;
+
+/// Keeps a copy of its stdout, daemonizes, says so on the copy and then
+/// loops forever.
+const LOOPER: &str = r#"
+(module
+    (import "wasi_snapshot_preview1" "fd_write" (func $fd_write (param i32 i32 i32 i32) (result i32)))
+    (import "wasi_snapshot_preview1" "fd_close" (func $fd_close (param i32) (result i32)))
+    (import "wasi_snapshot_preview1" "sched_yield" (func $sched_yield (result i32)))
+    (import "wasix_32v1" "fd_dup" (func $fd_dup (param i32 i32) (result i32)))
+    (import "wasix_32v1" "proc_daemonize" (func $proc_daemonize (param i32) (result i32)))
+    (memory (export "memory") 1)
+    (data (i32.const 100) "rdy\n")
+
+    (func (export "_start")
+        (if (call $fd_dup (i32.const 1) (i32.const 0))
+            (then unreachable))
+        (if (call $proc_daemonize (i32.const 4))
+            (then unreachable))
+
+        (i32.store (i32.const 16) (i32.const 100))
+        (i32.store (i32.const 20) (i32.const 4))
+        (drop (call $fd_write (i32.load (i32.const 0)) (i32.const 16) (i32.const 1) (i32.const 24)))
+        (drop (call $fd_close (i32.load (i32.const 0))))
+
+        (loop $forever
+            (drop (call $sched_yield))
+            (br $forever))
+    )
+)
+"#;
+
+async fn write_file(fs: &mem_fs::FileSystem, path: &str, contents: &[u8]) {
+    let mut f = fs
+        .new_open_options()
+        .create(true)
+        .write(true)
+        .open(Path::new(path))
+        .unwrap();
+    f.write_all(contents).await.unwrap();
+}
+
+#[test]
+fn daemons_outlive_the_process_that_started_them() {
+    let tokio_rt = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+    let handle = tokio_rt.handle().clone();
+    let _guard = handle.enter();
+    let rt = Arc::new(PluggableRuntime::new(Arc::new(TokioTaskManager::new(
+        tokio_rt,
+    ))));
+
+    handle.block_on(async {
+        let fs = mem_fs::FileSystem::default();
+        write_file(&fs, "/shell", &wasmer::wat2wasm(SHELL.as_bytes()).unwrap()).await;
+        write_file(
+            &fs,
+            "/looper",
+            &wasmer::wat2wasm(LOOPER.as_bytes()).unwrap(),
+        )
+        .await;
+
+        let (stdout_tx, mut stdout_rx) = Pipe::channel();
+        let env = WasiEnvBuilder::new("/shell")
+            .runtime(rt)
+            .fs(Box::new(fs))
+            .stdout(Box::new(stdout_tx))
+            .build()
+            .unwrap();
+        let control_plane = env.control_plane.clone();
+        let shell = env.process.clone();
+        let bin_factory = env.bin_factory.clone();
+
+        // The shell exits without waiting for the daemon
+        let mut task = bin_factory.spawn("/shell".to_string(), env).await.unwrap();
+        task.wait_finished().await.unwrap();
+
+        let mut pid = [0; 4];
+        stdout_rx.read_exact(&mut pid).await.unwrap();
+        let pid = WasiProcessId::from(u32::from_le_bytes(pid));
+
+        std::thread::sleep(Duration::from_millis(100));
+        let daemon = control_plane.get_process(pid).unwrap();
+        assert!(daemon.try_join().is_none());
+        assert_eq!(daemon.sid(), pid);
+        assert_eq!(daemon.pgid(), pid);
+        assert_ne!(daemon.sid(), shell.sid());
+        assert!(shell.lock().children.is_empty());
+
+        control_plane.signal_process(pid, Signal::Sigkill).unwrap();
+        daemon.join().await.ok();
+        assert!(daemon.try_join().is_some());
+    });
+}
@@ -0,0 +1,162 @@
+#![cfg(all(not(target_family = "wasm"), feature = "differential"))]
+
+use wasmer::{
+    sys::{Cranelift, EngineBuilder, Singlepass},
+    Engine, Value,
+};
+use wasmer_wasix::differential::{DifferentialRunner, Divergence, Event, Outcome};
+
+/// Reads a file, prints it, and leaves the random bytes, the time and some
+/// arithmetic on them in memory before exiting.
+const DETERMINISTIC: &str = r#"
+(module
+    (import "wasi_snapshot_preview1" "random_get" (func $random_get (param i32 i32) (result i32)))
+    (import "wasi_snapshot_preview1" "clock_time_get" (func $clock_time_get (param i32 i64 i32) (result i32)))
+    (import "wasi_snapshot_preview1" "path_open" (func $path_open (param i32 i32 i32 i32 i32 i64 i64 i32 i32) (result i32)))
+    (import "wasi_snapshot_preview1" "fd_read" (func $fd_read (param i32 i32 i32 i32) (result i32)))
+    (import "wasi_snapshot_preview1" "fd_write" (func $fd_write (param i32 i32 i32 i32) (result i32)))
+    (import "wasi_snapshot_preview1" "proc_exit" (func $proc_exit (param i32)))
+    (memory (export "memory") 1)
+    (data (i32.const 100) "input.txt")
+
+    (func (export "_start")
+        (drop (call $random_get (i32.const 32) (i32.const 16)))
+        (drop (call $clock_time_get (i32.const 0) (i64.const 1) (i32.const 48)))
+        ;; Relative to the preopened `/`, which is fd 4
+        (drop (call $path_open (i32.const 4) (i32.const 0) (i32.const 100) (i32.const 9)
+            (i32.const 0) (i64.const 2) (i64.const 2) (i32.const 0) (i32.const 20)))
+
+        (i32.store (i32.const 0) (i32.const 1024))
+        (i32.store (i32.const 4) (i32.const 64))
+        (drop (call $fd_read (i32.load (i32.const 20)) (i32.const 0) (i32.const 1) (i32.const 16)))
+        (i32.store (i32.const 4) (i32.load (i32.const 16)))
+        (drop (call $fd_write (i32.const 1) (i32.const 0) (i32.const 1) (i32.const 16)))
+
+        (f64.store (i32.const 56)
+            (f64.div
+                (f64.convert_i64_u (i64.load (i32.const 48)))
+                (f64.convert_i64_u (i64.add (i64.load (i32.const 32)) (i64.const 1)))))
+        (call $proc_exit (i32.const 3)))
+)
+"#;
+
+/// Adds 1 to a NaN with a payload, which keeps the payload unless the
+/// compiler canonicalizes NaNs.
+const NAN_BITS: &str = r#"
+(module
+    (memory (export "memory") 1)
+    (func (export "nan_bits") (result i32)
+        (i32.reinterpret_f32
+            (f32.add (f32.reinterpret_i32 (i32.const 0x7fc12345)) (f32.const 1))))
+)
+"#;
+
+fn cranelift(canonicalize_nans: bool) -> Engine {
+    let mut compiler = Cranelift::default();
+    compiler.canonicalize_nans(canonicalize_nans);
+    EngineBuilder::new(compiler).engine().into()
+}
+
+fn singlepass(canonicalize_nans: bool) -> Engine {
+    let mut compiler = Singlepass::default();
+    compiler.canonicalize_nans(canonicalize_nans);
+    EngineBuilder::new(compiler).engine().into()
+}
+
+fn tokio_runtime() -> tokio::runtime::Runtime {
+    tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .unwrap()
+}
+
+fn syscalls(events: &[Event]) -> Vec<&str> {
+    events
+        .iter()
+        .filter_map(|event| match event {
+            Event::Syscall { name, .. } => Some(name.as_str()),
+            Event::Returned { .. } => None,
+        })
+        .collect()
+}
+
+#[test]
+fn a_deterministic_module_runs_the_same_under_both_compilers() {
+    let tokio_rt = tokio_runtime();
+    let _guard = tokio_rt.enter();
+    let wasm = wasmer::wat2wasm(DETERMINISTIC.as_bytes()).unwrap();
+
+    let report = DifferentialRunner::new(cranelift(false), singlepass(false))
+        .with_seed(42)
+        .with_file("/input.txt", "the same on both sides\n")
+        .run(&wasm)
+        .unwrap();
+
+    if let Some(divergence) = &report.divergence {
+        panic!("{divergence}");
+    }
+    for run in [&report.left, &report.right] {
+        assert_eq!(
+            syscalls(&run.events),
+            [
+                "random_get",
+                "clock_time_get",
+                "path_open",
+                "fd_read",
+                "fd_write",
+                "proc_exit"
+            ]
+        );
+        assert!(matches!(
+            run.events.last(),
+            Some(Event::Returned {
+                outcome: Outcome::Exited(code),
+                ..
+            }) if code.raw() == 3
+        ));
+        assert_eq!(run.stdout, b"the same on both sides\n");
+        assert!(run.memory_hash.is_some());
+    }
+}
+
+#[test]
+fn nan_payloads_diverge_when_only_one_side_canonicalizes() {
+    let tokio_rt = tokio_runtime();
+    let _guard = tokio_rt.enter();
+    let wasm = wasmer::wat2wasm(NAN_BITS.as_bytes()).unwrap();
+
+    let report = DifferentialRunner::new(cranelift(true), singlepass(false))
+        .with_invocation("nan_bits", [])
+        .run(&wasm)
+        .unwrap();
+
+    let divergence = report.divergence.expect("the NaN payloads differ");
+    assert!(divergence.to_string().contains("nan_bits"), "{divergence}");
+    match &divergence {
+        Divergence::Event {
+            index: 0,
+            left:
+                Some(Event::Returned {
+                    outcome: Outcome::Returned(left),
+                    ..
+                }),
+            right:
+                Some(Event::Returned {
+                    outcome: Outcome::Returned(right),
+                    ..
+                }),
+            ..
+        } => {
+            assert_eq!(left, &[Value::I32(0x7fc00000)]);
+            assert_eq!(right, &[Value::I32(0x7fc12345)]);
+        }
+        _ => panic!("unexpected divergence: {divergence}"),
+    }
+
+    // Canonicalizing on both sides hides the payload from both
+    let report = DifferentialRunner::new(cranelift(true), singlepass(true))
+        .with_invocation("nan_bits", [])
+        .run(&wasm)
+        .unwrap();
+    assert!(report.is_identical(), "{:?}", report.divergence);
+}
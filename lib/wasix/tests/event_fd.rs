@@ -0,0 +1,191 @@
+#![cfg(not(target_family = "wasm"))]
+
+use std::{thread, time::Duration};
+
+use wasmer::{Instance, Module, Store, Value};
+use wasmer_wasix::{
+    wasmer_wasix_types::{types::EVENT_FD_FLAGS_SEMAPHORE, wasi::Errno},
+    WasiEnv, WasiFunctionEnv,
+};
+
+/// A guest that uses event fds through its exports:
+///
+/// - `create(flags)` creates one with `fd_event` and returns its fd;
+/// - `wait(fd)` polls it until it is readable, then reads it;
+/// - `read(fd)` reads it, returning the value or the negated errno;
+/// - `write(fd, val)` adds `val` to it;
+/// - `set_nonblocking(fd)` sets `O_NONBLOCK` on it.
+const GUEST: &str = r#"
+(module
+    (import "wasi_snapshot_preview1" "fd_read" (func $fd_read (param i32 i32 i32 i32) (result i32)))
+    (import "wasi_snapshot_preview1" "fd_write" (func $fd_write (param i32 i32 i32 i32) (result i32)))
+    (import "wasi_snapshot_preview1" "fd_fdstat_set_flags" (func $fd_fdstat_set_flags (param i32 i32) (result i32)))
+    (import "wasi_snapshot_preview1" "poll_oneoff" (func $poll_oneoff (param i32 i32 i32 i32) (result i32)))
+    (import "wasix_32v1" "fd_event" (func $fd_event (param i64 i32 i32) (result i32)))
+    (memory (export "memory") 1)
+
+    (func (export "create") (param $flags i32) (result i32)
+        (if (call $fd_event (i64.const 0) (local.get $flags) (i32.const 0))
+            (then unreachable))
+        (i32.load (i32.const 0))
+    )
+
+    (func $read (export "read") (param $fd i32) (result i64)
+        (local $errno i32)
+        (i32.store (i32.const 240) (i32.const 256))
+        (i32.store (i32.const 244) (i32.const 8))
+        (local.set $errno
+            (call $fd_read (local.get $fd) (i32.const 240) (i32.const 1) (i32.const 248)))
+        (if (result i64) (local.get $errno)
+            (then (i64.sub (i64.const 0) (i64.extend_i32_u (local.get $errno))))
+            (else (i64.load (i32.const 256))))
+    )
+
+    (func (export "wait") (param $fd i32) (result i64)
+        ;; Wait until the fd is readable
+        (i32.store8 (i32.const 72) (i32.const 1))
+        (i32.store (i32.const 80) (local.get $fd))
+        (loop $wait
+            (if (call $poll_oneoff (i32.const 64) (i32.const 128) (i32.const 1) (i32.const 192))
+                (then unreachable))
+            (br_if $wait (i32.eqz (i32.load (i32.const 192)))))
+        (if (i32.load16_u (i32.const 136))
+            (then unreachable))
+        (call $read (local.get $fd))
+    )
+
+    (func (export "write") (param $fd i32) (param $val i64)
+        (i64.store (i32.const 256) (local.get $val))
+        (i32.store (i32.const 240) (i32.const 256))
+        (i32.store (i32.const 244) (i32.const 8))
+        (if (call $fd_write (local.get $fd) (i32.const 240) (i32.const 1) (i32.const 248))
+            (then unreachable))
+    )
+
+    (func (export "set_nonblocking") (param $fd i32)
+        (if (call $fd_fdstat_set_flags (local.get $fd) (i32.const 4))
+            (then unreachable))
+    )
+)
+"#;
+
+struct Guest {
+    store: Store,
+    instance: Instance,
+    env: WasiFunctionEnv,
+}
+
+impl Guest {
+    fn new() -> Self {
+        let mut store = Store::default();
+        let module = Module::new(&store, GUEST).unwrap();
+        let (instance, env) = WasiEnv::builder("event-fd")
+            .engine(store.engine().clone())
+            .instantiate(module, &mut store)
+            .unwrap();
+
+        Guest {
+            store,
+            instance,
+            env,
+        }
+    }
+
+    fn call(&mut self, name: &str, params: &[Value]) -> Option<Value> {
+        let func = self.instance.exports.get_function(name).unwrap();
+        func.call(&mut self.store, params).unwrap().first().cloned()
+    }
+
+    fn call_i64(&mut self, name: &str, fd: u32) -> i64 {
+        self.call(name, &[Value::I32(fd as i32)])
+            .and_then(|value| value.i64())
+            .unwrap()
+    }
+}
+
+fn runtime() -> tokio::runtime::Runtime {
+    tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .unwrap()
+}
+
+#[test]
+fn host_writes_wake_up_the_guest() {
+    let rt = runtime();
+    let _guard = rt.enter();
+    let mut guest = Guest::new();
+
+    let (fd, handle) = guest.env.data(&guest.store).create_event_fd(0, 0).unwrap();
+    assert!(!handle.is_semaphore());
+
+    let writer = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(100));
+        handle.write(3);
+        handle.write(4);
+        handle
+    });
+    assert_eq!(guest.call_i64("wait", fd), 7);
+    let handle = writer.join().unwrap();
+
+    // Reading took the whole counter
+    assert_eq!(handle.try_read(), None);
+    guest.call("set_nonblocking", &[Value::I32(fd as i32)]);
+    assert_eq!(guest.call_i64("read", fd), -(Errno::Again as i64));
+}
+
+#[test]
+fn guest_writes_are_seen_by_the_host() {
+    let rt = runtime();
+    let _guard = rt.enter();
+    let mut guest = Guest::new();
+
+    let fd = match guest.call("create", &[Value::I32(0)]) {
+        Some(Value::I32(fd)) => fd as u32,
+        other => panic!("unexpected result: {other:?}"),
+    };
+    let handle = guest.env.data(&guest.store).event_fd_handle(fd).unwrap();
+    assert_eq!(handle.try_read(), None);
+
+    guest.call("write", &[Value::I32(fd as i32), Value::I64(5)]);
+    assert_eq!(handle.try_read(), Some(5));
+    assert_eq!(handle.try_read(), None);
+
+    guest.call("write", &[Value::I32(fd as i32), Value::I64(2)]);
+    assert_eq!(rt.block_on(handle.read()), 2);
+
+    // Only event fds have handles
+    let env = guest.env.data(&guest.store);
+    assert_eq!(env.event_fd_handle(1).unwrap_err(), Errno::Inval);
+    assert_eq!(env.event_fd_handle(1000).unwrap_err(), Errno::Badf);
+}
+
+#[test]
+fn semaphores_are_read_one_at_a_time() {
+    let rt = runtime();
+    let _guard = rt.enter();
+    let mut guest = Guest::new();
+
+    // Created by the host, read by the guest
+    let (fd, handle) = guest
+        .env
+        .data(&guest.store)
+        .create_event_fd(2, EVENT_FD_FLAGS_SEMAPHORE)
+        .unwrap();
+    assert!(handle.is_semaphore());
+    guest.call("set_nonblocking", &[Value::I32(fd as i32)]);
+    assert_eq!(guest.call_i64("read", fd), 1);
+    assert_eq!(guest.call_i64("read", fd), 1);
+    assert_eq!(guest.call_i64("read", fd), -(Errno::Again as i64));
+
+    // Created by the guest, read by the host
+    let fd = match guest.call("create", &[Value::I32(EVENT_FD_FLAGS_SEMAPHORE as i32)]) {
+        Some(Value::I32(fd)) => fd as u32,
+        other => panic!("unexpected result: {other:?}"),
+    };
+    let handle = guest.env.data(&guest.store).event_fd_handle(fd).unwrap();
+    guest.call("write", &[Value::I32(fd as i32), Value::I64(2)]);
+    assert_eq!(handle.try_read(), Some(1));
+    assert_eq!(handle.try_read(), Some(1));
+    assert_eq!(handle.try_read(), None);
+}
@@ -0,0 +1,241 @@
+#![cfg(not(target_family = "wasm"))]
+
+use std::path::Path;
+
+use tokio::io::AsyncReadExt;
+use virtual_fs::{mem_fs, FileSystem, Pipe};
+use wasmer::{Instance, Module, Store, Value};
+use wasmer_wasix::{
+    os::command::Commands, wasmer_wasix_types::wasi::Errno, WasiEnv, WasiFunctionEnv,
+};
+
+/// Exports wrappers around the syscalls a shell uses for redirections, which
+/// return the errno (or, for `open`, the new fd):
+///
+/// - `open(path, len)` creates (or truncates) a file relative to the
+///   preopened `/` (fd 4);
+/// - `renumber(from, to)`, `write(fd, buf, len)` and `close(fd)`.
+const PROGRAM: &str = r#"
+(module
+    (import "wasi_snapshot_preview1" "path_open" (func $path_open (param i32 i32 i32 i32 i32 i64 i64 i32 i32) (result i32)))
+    (import "wasi_snapshot_preview1" "fd_renumber" (func $fd_renumber (param i32 i32) (result i32)))
+    (import "wasi_snapshot_preview1" "fd_write" (func $fd_write (param i32 i32 i32 i32) (result i32)))
+    (import "wasi_snapshot_preview1" "fd_close" (func $fd_close (param i32) (result i32)))
+    (memory (export "memory") 1)
+
+    (func (export "open") (param $path i32) (param $len i32) (result i32)
+        (local $errno i32)
+        (local.set $errno
+            (call $path_open (i32.const 4) (i32.const 0) (local.get $path) (local.get $len)
+                (i32.const 9) (i64.const 0x42) (i64.const 0x42) (i32.const 0) (i32.const 0)))
+        (if (result i32) (local.get $errno)
+            (then (i32.sub (i32.const 0) (local.get $errno)))
+            (else (i32.load (i32.const 0))))
+    )
+
+    (func (export "renumber") (param $from i32) (param $to i32) (result i32)
+        (call $fd_renumber (local.get $from) (local.get $to)))
+
+    (func (export "write") (param $fd i32) (param $buf i32) (param $len i32) (result i32)
+        (i32.store (i32.const 8) (local.get $buf))
+        (i32.store (i32.const 12) (local.get $len))
+        (call $fd_write (local.get $fd) (i32.const 8) (i32.const 1) (i32.const 16)))
+
+    (func (export "close") (param $fd i32) (result i32)
+        (call $fd_close (local.get $fd)))
+)
+"#;
+
+struct Guest {
+    store: Store,
+    instance: Instance,
+    env: WasiFunctionEnv,
+    fs: mem_fs::FileSystem,
+    stdout: Pipe,
+    stderr: Pipe,
+}
+
+impl Guest {
+    fn new() -> Self {
+        let fs = mem_fs::FileSystem::default();
+        let (stdout_tx, stdout) = Pipe::channel();
+        let (stderr_tx, stderr) = Pipe::channel();
+
+        let mut store = Store::default();
+        let module = Module::new(&store, PROGRAM).unwrap();
+        let (instance, env) = WasiEnv::builder("redirections")
+            .engine(store.engine().clone())
+            .fs(Box::new(fs.clone()))
+            .preopen_dir("/")
+            .unwrap()
+            .stdout(Box::new(stdout_tx))
+            .stderr(Box::new(stderr_tx))
+            .instantiate(module, &mut store)
+            .unwrap();
+
+        Guest {
+            store,
+            instance,
+            env,
+            fs,
+            stdout,
+            stderr,
+        }
+    }
+
+    fn call(&mut self, name: &str, params: &[Value]) -> i32 {
+        let func = self.instance.exports.get_function(name).unwrap();
+        func.call(&mut self.store, params).unwrap()[0].unwrap_i32()
+    }
+
+    /// Copies `string` into memory and passes its address and length to
+    /// `name` after `params`.
+    fn call_with_str(&mut self, name: &str, params: &[Value], string: &str) -> i32 {
+        let memory = self.instance.exports.get_memory("memory").unwrap();
+        memory
+            .view(&self.store)
+            .write(1024, string.as_bytes())
+            .unwrap();
+        let mut params = params.to_vec();
+        params.extend([Value::I32(1024), Value::I32(string.len() as i32)]);
+        self.call(name, &params)
+    }
+
+    fn open(&mut self, path: &str) -> u32 {
+        let fd = self.call_with_str("open", &[], path);
+        assert!(fd >= 0, "opening {path} failed with {fd}");
+        fd as u32
+    }
+
+    fn renumber(&mut self, from: u32, to: u32) -> i32 {
+        self.call(
+            "renumber",
+            &[Value::I32(from as i32), Value::I32(to as i32)],
+        )
+    }
+
+    fn write(&mut self, fd: u32, data: &str) -> i32 {
+        self.call_with_str("write", &[Value::I32(fd as i32)], data)
+    }
+
+    fn close(&mut self, fd: u32) -> i32 {
+        self.call("close", &[Value::I32(fd as i32)])
+    }
+
+    /// Writes to the guest's stderr the way the runtime does for its
+    /// own messages, by running a command that doesn't exist.
+    fn host_message(&mut self) {
+        let runtime = self.env.data(&self.store).runtime.clone();
+        let ctx = self.env.env.clone().into_mut(&mut self.store);
+        Commands::new_with_builtins(runtime)
+            .exec(&ctx, "/bin/missing", &mut None)
+            .unwrap();
+    }
+
+    fn file(&self, rt: &tokio::runtime::Runtime, path: &str) -> String {
+        let mut file = self
+            .fs
+            .new_open_options()
+            .read(true)
+            .open(Path::new(path))
+            .unwrap();
+        let mut contents = String::new();
+        rt.block_on(file.read_to_string(&mut contents)).unwrap();
+        contents
+    }
+}
+
+/// Everything written to `pipe` so far.
+fn output(pipe: &mut Pipe) -> String {
+    let mut output = Vec::new();
+    let mut buf = [0; 1024];
+    while let Some(read @ 1..) = pipe.try_read(&mut buf) {
+        output.extend_from_slice(&buf[..read]);
+    }
+    String::from_utf8(output).unwrap()
+}
+
+fn runtime() -> tokio::runtime::Runtime {
+    tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .unwrap()
+}
+
+#[test]
+fn stderr_to_stdout_before_redirecting_stdout() {
+    let rt = runtime();
+    let _guard = rt.enter();
+    let mut guest = Guest::new();
+
+    // cmd 2>&1 >/out
+    assert_eq!(guest.renumber(1, 2), Errno::Success as i32);
+    let fd = guest.open("out");
+    assert_eq!(guest.renumber(fd, 1), Errno::Success as i32);
+    assert_eq!(guest.close(fd), Errno::Success as i32);
+
+    assert_eq!(guest.write(1, "out\n"), Errno::Success as i32);
+    assert_eq!(guest.write(2, "err\n"), Errno::Success as i32);
+    guest.host_message();
+
+    assert_eq!(guest.file(&rt, "/out"), "out\n");
+    let stdout = output(&mut guest.stdout);
+    assert!(
+        stdout.starts_with("err\nwasm command unknown"),
+        "{stdout:?}"
+    );
+    assert_eq!(output(&mut guest.stderr), "");
+}
+
+#[test]
+fn stderr_to_stdout_after_redirecting_stdout() {
+    let rt = runtime();
+    let _guard = rt.enter();
+    let mut guest = Guest::new();
+
+    // cmd >/out 2>&1
+    let fd = guest.open("out");
+    assert_eq!(guest.renumber(fd, 1), Errno::Success as i32);
+    assert_eq!(guest.close(fd), Errno::Success as i32);
+    assert_eq!(guest.renumber(1, 2), Errno::Success as i32);
+
+    assert_eq!(guest.write(1, "out\n"), Errno::Success as i32);
+    assert_eq!(guest.write(2, "err\n"), Errno::Success as i32);
+    guest.host_message();
+
+    let contents = guest.file(&rt, "/out");
+    assert!(
+        contents.starts_with("out\nerr\nwasm command unknown"),
+        "{contents:?}"
+    );
+    assert_eq!(output(&mut guest.stdout), "");
+    assert_eq!(output(&mut guest.stderr), "");
+}
+
+#[test]
+fn renumbering_onto_a_closed_fd() {
+    let rt = runtime();
+    let _guard = rt.enter();
+    let mut guest = Guest::new();
+
+    assert_eq!(guest.close(1), Errno::Success as i32);
+    assert_eq!(guest.write(1, "lost\n"), Errno::Badf as i32);
+
+    let fd = guest.open("out");
+    assert_eq!(guest.renumber(fd, 1), Errno::Success as i32);
+    assert_eq!(guest.write(1, "out\n"), Errno::Success as i32);
+    assert_eq!(guest.file(&rt, "/out"), "out\n");
+    assert_eq!(output(&mut guest.stdout), "");
+}
+
+#[test]
+fn renumbering_an_invalid_fd_leaves_the_target_open() {
+    let rt = runtime();
+    let _guard = rt.enter();
+    let mut guest = Guest::new();
+
+    assert_eq!(guest.renumber(100, 2), Errno::Badf as i32);
+    assert_eq!(guest.renumber(100, 100), Errno::Badf as i32);
+    assert_eq!(guest.write(2, "err\n"), Errno::Success as i32);
+    assert_eq!(output(&mut guest.stderr), "err\n");
+}
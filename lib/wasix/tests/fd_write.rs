@@ -0,0 +1,291 @@
+#![cfg(not(target_family = "wasm"))]
+
+use std::{path::Path, sync::Arc};
+
+use virtual_fs::{host_fs, mem_fs, AsyncReadExt, FileSystem};
+use virtual_mio::InlineWaker;
+use wasmer::Module;
+use wasmer_types::ModuleHash;
+use wasmer_wasix::{
+    runners::wasi::{RuntimeOrEngine, WasiRunner},
+    runtime::task_manager::tokio::TokioTaskManager,
+    Pipe, PluggableRuntime,
+};
+
+/// Writes to `/data/file` at various offsets (with `fd_write` from several
+/// iovecs, `fd_pwrite`, after an `fd_seek` and through a second descriptor
+/// opened for appending) while reading it back in between. Prints what
+/// `fd_pread` read in the middle of the writes followed by the whole file.
+const PROGRAM: &str = r#"
+(module
+    (import "wasi_snapshot_preview1" "path_open" (func $path_open (param i32 i32 i32 i32 i32 i64 i64 i32 i32) (result i32)))
+    (import "wasi_snapshot_preview1" "fd_write" (func $fd_write (param i32 i32 i32 i32) (result i32)))
+    (import "wasi_snapshot_preview1" "fd_pwrite" (func $fd_pwrite (param i32 i32 i32 i64 i32) (result i32)))
+    (import "wasi_snapshot_preview1" "fd_read" (func $fd_read (param i32 i32 i32 i32) (result i32)))
+    (import "wasi_snapshot_preview1" "fd_pread" (func $fd_pread (param i32 i32 i32 i64 i32) (result i32)))
+    (import "wasi_snapshot_preview1" "fd_seek" (func $fd_seek (param i32 i64 i32 i32) (result i32)))
+    (memory (export "memory") 1)
+    (data (i32.const 100) "hello worldHELLOthere!!??")
+    (data (i32.const 130) "data/file")
+
+    ;; Writes `iovs_len` iovecs and checks that all `len` bytes were written
+    (func $write (param $fd i32) (param $iovs i32) (param $iovs_len i32) (param $len i32)
+        (if (call $fd_write (local.get $fd) (local.get $iovs) (local.get $iovs_len) (i32.const 8))
+            (then unreachable))
+        (if (i32.ne (i32.load (i32.const 8)) (local.get $len))
+            (then unreachable))
+    )
+
+    (func $iov (param $at i32) (param $buf i32) (param $len i32)
+        (i32.store (local.get $at) (local.get $buf))
+        (i32.store (i32.add (local.get $at) (i32.const 4)) (local.get $len))
+    )
+
+    (func $open (param $fdflags i32) (result i32)
+        (if (call $path_open (i32.const 3) (i32.const 0) (i32.const 130) (i32.const 9)
+                (i32.const 1) (i64.const -1) (i64.const -1) (local.get $fdflags) (i32.const 0))
+            (then unreachable))
+        (i32.load (i32.const 0))
+    )
+
+    (func (export "_start")
+        (local $fd i32)
+        (local $append i32)
+        (call $iov (i32.const 200) (i32.const 100) (i32.const 6))
+        (call $iov (i32.const 208) (i32.const 106) (i32.const 5))
+        (call $iov (i32.const 216) (i32.const 111) (i32.const 5))
+        (call $iov (i32.const 224) (i32.const 116) (i32.const 5))
+        (call $iov (i32.const 232) (i32.const 121) (i32.const 2))
+        (call $iov (i32.const 240) (i32.const 123) (i32.const 2))
+        (call $iov (i32.const 256) (i32.const 400) (i32.const 5))
+        (call $iov (i32.const 264) (i32.const 408) (i32.const 32))
+
+        (local.set $fd (call $open (i32.const 0)))
+
+        ;; "hello world"
+        (call $write (local.get $fd) (i32.const 200) (i32.const 2) (i32.const 11))
+        ;; "HELLO world", without moving the cursor
+        (if (call $fd_pwrite (local.get $fd) (i32.const 216) (i32.const 1) (i64.const 0) (i32.const 8))
+            (then unreachable))
+        ;; "HELLO there!!"
+        (if (call $fd_seek (local.get $fd) (i64.const 6) (i32.const 0) (i32.const 16))
+            (then unreachable))
+        (call $write (local.get $fd) (i32.const 224) (i32.const 2) (i32.const 7))
+        (if (call $fd_pread (local.get $fd) (i32.const 256) (i32.const 1) (i64.const 6) (i32.const 8))
+            (then unreachable))
+
+        ;; "HELLO there!!??", whatever the cursor of the other descriptor is
+        (local.set $append (call $open (i32.const 1)))
+        (if (call $fd_seek (local.get $fd) (i64.const 0) (i32.const 0) (i32.const 16))
+            (then unreachable))
+        (call $write (local.get $append) (i32.const 240) (i32.const 1) (i32.const 2))
+
+        (if (call $fd_read (local.get $fd) (i32.const 264) (i32.const 1) (i32.const 8))
+            (then unreachable))
+
+        (call $iov (i32.const 280) (i32.const 400) (i32.const 5))
+        (call $iov (i32.const 288) (i32.const 408) (i32.load (i32.const 8)))
+        (drop (call $fd_write (i32.const 1) (i32.const 280) (i32.const 2) (i32.const 8)))
+    )
+)
+"#;
+
+/// Opens `/data/log` twice, once with `fdflags::append` and once turning it
+/// on later with `fd_fdstat_set_flags`, and spawns 4 threads that each append
+/// 1000 lines (`"<thread> <line>\n"`, written from two iovecs) through one of
+/// them. It then seeks back to the start, appends `"end\n"` and prints the
+/// first line of the file.
+const APPENDERS: &str = r#"
+(module
+    (import "wasi_snapshot_preview1" "path_open" (func $path_open (param i32 i32 i32 i32 i32 i64 i64 i32 i32) (result i32)))
+    (import "wasi_snapshot_preview1" "fd_fdstat_set_flags" (func $fd_fdstat_set_flags (param i32 i32) (result i32)))
+    (import "wasi_snapshot_preview1" "fd_write" (func $fd_write (param i32 i32 i32 i32) (result i32)))
+    (import "wasi_snapshot_preview1" "fd_read" (func $fd_read (param i32 i32 i32 i32) (result i32)))
+    (import "wasi_snapshot_preview1" "fd_seek" (func $fd_seek (param i32 i64 i32 i32) (result i32)))
+    (import "wasix_32v1" "thread_spawn_v2" (func $thread_spawn (param i32 i32) (result i32)))
+    (import "wasix_32v1" "thread_join" (func $thread_join (param i32) (result i32)))
+    (import "env" "memory" (memory 4 4 shared))
+    (export "memory" (memory 0))
+    (data (i32.const 100) "data/log")
+    (data (i32.const 112) "end\n")
+
+    (func $open (param $fdflags i32) (result i32)
+        (if (call $path_open (i32.const 3) (i32.const 0) (i32.const 100) (i32.const 8)
+                (i32.const 1) (i64.const -1) (i64.const -1) (local.get $fdflags) (i32.const 0))
+            (then unreachable))
+        (i32.load (i32.const 0))
+    )
+
+    ;; Formats `n` as four digits and a newline
+    (func $digits (param $at i32) (param $n i32)
+        (i32.store8 (local.get $at)
+            (i32.add (i32.const 48) (i32.div_u (local.get $n) (i32.const 1000))))
+        (i32.store8 offset=1 (local.get $at)
+            (i32.add (i32.const 48) (i32.rem_u (i32.div_u (local.get $n) (i32.const 100)) (i32.const 10))))
+        (i32.store8 offset=2 (local.get $at)
+            (i32.add (i32.const 48) (i32.rem_u (i32.div_u (local.get $n) (i32.const 10)) (i32.const 10))))
+        (i32.store8 offset=3 (local.get $at)
+            (i32.add (i32.const 48) (i32.rem_u (local.get $n) (i32.const 10))))
+        (i32.store8 offset=4 (local.get $at) (i32.const 10))
+    )
+
+    ;; Thread `t` starts from the `ThreadStart` at 1024 + t * 64, writes its
+    ;; lines at 4096 + t * 64 and uses the first fd when `t` is even
+    (func (export "wasi_thread_start") (param $tid i32) (param $start i32)
+        (local $t i32)
+        (local $at i32)
+        (local $fd i32)
+        (local $n i32)
+        (local.set $t (i32.shr_u (i32.sub (local.get $start) (i32.const 1024)) (i32.const 6)))
+        (local.set $at (i32.add (i32.const 4096) (i32.shl (local.get $t) (i32.const 6))))
+        (local.set $fd (i32.load (i32.add (i32.const 200)
+            (i32.shl (i32.and (local.get $t) (i32.const 1)) (i32.const 2)))))
+
+        (i32.store8 (local.get $at) (i32.add (i32.const 48) (local.get $t)))
+        (i32.store8 offset=1 (local.get $at) (i32.const 32))
+        (i32.store offset=16 (local.get $at) (local.get $at))
+        (i32.store offset=20 (local.get $at) (i32.const 2))
+        (i32.store offset=24 (local.get $at) (i32.add (local.get $at) (i32.const 8)))
+        (i32.store offset=28 (local.get $at) (i32.const 5))
+        (loop $again
+            (call $digits (i32.add (local.get $at) (i32.const 8)) (local.get $n))
+            (if (call $fd_write (local.get $fd) (i32.add (local.get $at) (i32.const 16)) (i32.const 2)
+                    (i32.add (local.get $at) (i32.const 32)))
+                (then unreachable))
+            (local.set $n (i32.add (local.get $n) (i32.const 1)))
+            (br_if $again (i32.lt_u (local.get $n) (i32.const 1000))))
+    )
+
+    (func (export "_start")
+        (local $t i32)
+        (local $fd i32)
+        (i32.store (i32.const 200) (call $open (i32.const 1)))
+        (local.set $fd (call $open (i32.const 0)))
+        (if (call $fd_fdstat_set_flags (local.get $fd) (i32.const 1))
+            (then unreachable))
+        (i32.store (i32.const 204) (local.get $fd))
+
+        (loop $spawn
+            ;; ThreadStart { stack_upper, .., stack_size, guard_size }
+            (i32.store (i32.add (i32.const 1024) (i32.shl (local.get $t) (i32.const 6)))
+                (i32.add (i32.const 131072) (i32.shl (local.get $t) (i32.const 14))))
+            (i32.store (i32.add (i32.const 1080) (i32.shl (local.get $t) (i32.const 6)))
+                (i32.const 8192))
+            (if (call $thread_spawn (i32.add (i32.const 1024) (i32.shl (local.get $t) (i32.const 6)))
+                    (i32.add (i32.const 1536) (i32.shl (local.get $t) (i32.const 2))))
+                (then unreachable))
+            (local.set $t (i32.add (local.get $t) (i32.const 1)))
+            (br_if $spawn (i32.lt_u (local.get $t) (i32.const 4))))
+
+        (local.set $t (i32.const 0))
+        (loop $join
+            (drop (call $thread_join (i32.load (i32.add (i32.const 1536) (i32.shl (local.get $t) (i32.const 2))))))
+            (local.set $t (i32.add (local.get $t) (i32.const 1)))
+            (br_if $join (i32.lt_u (local.get $t) (i32.const 4))))
+
+        ;; Seeking only moves where reads start from
+        (local.set $fd (i32.load (i32.const 200)))
+        (if (call $fd_seek (local.get $fd) (i64.const 0) (i32.const 0) (i32.const 8))
+            (then unreachable))
+        (i32.store (i32.const 16) (i32.const 112))
+        (i32.store (i32.const 20) (i32.const 4))
+        (if (call $fd_write (local.get $fd) (i32.const 16) (i32.const 1) (i32.const 8))
+            (then unreachable))
+        (if (call $fd_seek (local.get $fd) (i64.const 0) (i32.const 0) (i32.const 8))
+            (then unreachable))
+        (i32.store (i32.const 16) (i32.const 2048))
+        (i32.store (i32.const 20) (i32.const 7))
+        (if (call $fd_read (local.get $fd) (i32.const 16) (i32.const 1) (i32.const 8))
+            (then unreachable))
+
+        (drop (call $fd_write (i32.const 1) (i32.const 16) (i32.const 1) (i32.const 8)))
+    )
+)
+"#;
+
+fn tokio_runtime() -> tokio::runtime::Runtime {
+    tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .unwrap()
+}
+
+fn run(
+    tokio_rt: &tokio::runtime::Runtime,
+    program: &str,
+    fs: Arc<dyn FileSystem + Send + Sync>,
+) -> Vec<u8> {
+    let _guard = tokio_rt.enter();
+
+    let engine = wasmer::Engine::default();
+    let module = Module::new(&engine, program).unwrap();
+    let mut rt = PluggableRuntime::new(Arc::new(TokioTaskManager::new(tokio_rt.handle().clone())));
+    rt.set_engine(engine);
+
+    let (stdout_tx, mut stdout_rx) = Pipe::channel();
+    WasiRunner::new()
+        .with_stdout(Box::new(stdout_tx))
+        .with_mount("/data".to_string(), fs)
+        .run_wasm(
+            RuntimeOrEngine::Runtime(Arc::new(rt)),
+            "fd-write",
+            module,
+            ModuleHash::random(),
+        )
+        .unwrap();
+
+    let mut stdout = Vec::new();
+    InlineWaker::block_on(stdout_rx.read_to_end(&mut stdout)).unwrap();
+    stdout
+}
+
+fn read_file(fs: &dyn FileSystem, path: &str) -> Vec<u8> {
+    let mut contents = Vec::new();
+    let mut file = fs
+        .new_open_options()
+        .read(true)
+        .open(Path::new(path))
+        .unwrap();
+    InlineWaker::block_on(file.read_to_end(&mut contents)).unwrap();
+    contents
+}
+
+/// Checks that every line the appenders wrote is in the log exactly once,
+/// followed by `"end"`
+fn assert_appended(stdout: &[u8], log: &[u8]) {
+    let log = String::from_utf8(log.to_vec()).unwrap();
+    let mut lines = log.lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), 4001);
+    assert_eq!(lines.pop(), Some("end"));
+    assert_eq!(String::from_utf8_lossy(stdout), format!("{}\n", lines[0]));
+
+    lines.sort_unstable();
+    let expected = (0..4)
+        .flat_map(|thread| (0..1000).map(move |line| format!("{thread} {line:04}")))
+        .collect::<Vec<_>>();
+    assert_eq!(lines, expected);
+}
+
+#[test]
+fn concurrent_appends_to_memfs_never_interleave() {
+    let fs = mem_fs::FileSystem::default();
+    let stdout = run(&tokio_runtime(), APPENDERS, Arc::new(fs.clone()));
+    assert_appended(&stdout, &read_file(&fs, "/log"));
+}
+
+#[test]
+fn concurrent_appends_to_host_files_never_interleave() {
+    let temp = tempfile::tempdir().unwrap();
+    let tokio_rt = tokio_runtime();
+    let fs = host_fs::FileSystem::new(tokio_rt.handle().clone(), temp.path()).unwrap();
+    let stdout = run(&tokio_rt, APPENDERS, Arc::new(fs));
+    assert_appended(&stdout, &std::fs::read(temp.path().join("log")).unwrap());
+}
+
+#[test]
+fn interleaved_writes_and_reads_at_offsets() {
+    let fs = mem_fs::FileSystem::default();
+    let stdout = run(&tokio_runtime(), PROGRAM, Arc::new(fs.clone()));
+    assert_eq!(String::from_utf8_lossy(&stdout), "thereHELLO there!!??");
+    assert_eq!(read_file(&fs, "/file"), b"HELLO there!!??");
+}
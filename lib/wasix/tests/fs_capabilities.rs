@@ -0,0 +1,153 @@
+#![cfg(not(target_family = "wasm"))]
+
+use std::{path::Path, sync::Arc};
+
+use virtual_fs::{mem_fs, AsyncReadExt, FileSystem};
+use virtual_mio::InlineWaker;
+use wasmer::Module;
+use wasmer_types::ModuleHash;
+use wasmer_wasix::{
+    capabilities::{CapabilityFsV1, PathRule},
+    runners::wasi::{RuntimeOrEngine, WasiRunner},
+    runtime::task_manager::tokio::TokioTaskManager,
+    wasmer_wasix_types::wasi::Errno,
+    Pipe, PluggableRuntime,
+};
+
+/// Opens a handful of paths and prints the errno of each attempt as a byte:
+/// reading `/dev/urandom`, reading `/data/ro/file`, creating `/data/ro/new`,
+/// reading `/data/wo/file` and creating `/data/wo/new`.
+const PROGRAM: &str = r#"
+(module
+    (import "wasi_snapshot_preview1" "path_open" (func $path_open (param i32 i32 i32 i32 i32 i64 i64 i32 i32) (result i32)))
+    (import "wasi_snapshot_preview1" "fd_write" (func $fd_write (param i32 i32 i32 i32) (result i32)))
+    (memory (export "memory") 1)
+    (data (i32.const 300) "dev/urandom")
+    (data (i32.const 320) "data/ro/file")
+    (data (i32.const 340) "data/ro/new")
+    (data (i32.const 360) "data/wo/file")
+    (data (i32.const 380) "data/wo/new")
+
+    (func $open (param $index i32) (param $path i32) (param $len i32) (param $oflags i32) (param $rights i64)
+        (i32.store8 (i32.add (i32.const 200) (local.get $index))
+            (call $path_open (i32.const 3) (i32.const 0) (local.get $path) (local.get $len)
+                (local.get $oflags) (local.get $rights) (i64.const 0) (i32.const 0) (i32.const 0)))
+    )
+
+    (func (export "_start")
+        (call $open (i32.const 0) (i32.const 300) (i32.const 11) (i32.const 0) (i64.const 2))
+        (call $open (i32.const 1) (i32.const 320) (i32.const 12) (i32.const 0) (i64.const 2))
+        (call $open (i32.const 2) (i32.const 340) (i32.const 11) (i32.const 1) (i64.const 64))
+        (call $open (i32.const 3) (i32.const 360) (i32.const 12) (i32.const 0) (i64.const 2))
+        (call $open (i32.const 4) (i32.const 380) (i32.const 11) (i32.const 1) (i64.const 64))
+
+        (i32.store (i32.const 16) (i32.const 200))
+        (i32.store (i32.const 20) (i32.const 5))
+        (if (call $fd_write (i32.const 1) (i32.const 16) (i32.const 1) (i32.const 8))
+            (then unreachable))
+    )
+)
+"#;
+
+fn run(fs: CapabilityFsV1, grants: Vec<PathRule>) -> Vec<Errno> {
+    let tokio_rt = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+    let _guard = tokio_rt.enter();
+
+    let data = mem_fs::FileSystem::default();
+    for dir in ["/ro", "/wo"] {
+        data.create_dir(Path::new(dir)).unwrap();
+        data.new_open_options()
+            .create(true)
+            .write(true)
+            .open(Path::new(dir).join("file"))
+            .unwrap();
+    }
+
+    let engine = wasmer::Engine::default();
+    let module = Module::new(&engine, PROGRAM).unwrap();
+    let mut rt = PluggableRuntime::new(Arc::new(TokioTaskManager::new(tokio_rt.handle().clone())));
+    rt.set_engine(engine);
+
+    let (stdout_tx, mut stdout_rx) = Pipe::channel();
+    let mut runner = WasiRunner::new();
+    runner
+        .with_stdout(Box::new(stdout_tx))
+        .with_mount("/data".to_string(), Arc::new(data));
+    runner.capabilities_mut().fs = fs;
+    for rule in grants {
+        runner.with_fs_grant(rule);
+    }
+    runner
+        .run_wasm(
+            RuntimeOrEngine::Runtime(Arc::new(rt)),
+            "fs-capabilities",
+            module,
+            ModuleHash::random(),
+        )
+        .unwrap();
+
+    let mut stdout = Vec::new();
+    InlineWaker::block_on(stdout_rx.read_to_end(&mut stdout)).unwrap();
+    stdout
+        .into_iter()
+        .map(|errno| Errno::try_from(errno as u16).unwrap())
+        .collect()
+}
+
+#[test]
+fn unrestricted_allows_everything() {
+    assert_eq!(
+        run(CapabilityFsV1::Unrestricted, Vec::new()),
+        vec![Errno::Success; 5]
+    );
+}
+
+#[test]
+fn preopens_only_blocks_undeclared_paths() {
+    assert_eq!(
+        run(CapabilityFsV1::PreopensOnly, Vec::new()),
+        vec![
+            Errno::Notcapable,
+            Errno::Success,
+            Errno::Success,
+            Errno::Success,
+            Errno::Success,
+        ]
+    );
+    assert_eq!(
+        run(
+            CapabilityFsV1::PreopensOnly,
+            vec![PathRule::read_only("/dev/urandom")]
+        ),
+        vec![Errno::Success; 5]
+    );
+}
+
+#[test]
+fn custom_rules_restrict_access_per_prefix() {
+    let rules = vec![
+        PathRule::read_only("/data/ro"),
+        PathRule::write_only("/data/wo"),
+    ];
+    assert_eq!(
+        run(CapabilityFsV1::Custom(rules), Vec::new()),
+        vec![
+            Errno::Notcapable,
+            Errno::Success,
+            Errno::Notcapable,
+            Errno::Notcapable,
+            Errno::Success,
+        ]
+    );
+}
+
+#[test]
+fn deny_all_still_allows_stdio() {
+    assert_eq!(
+        run(CapabilityFsV1::DenyAll, Vec::new()),
+        vec![Errno::Notcapable; 5]
+    );
+}
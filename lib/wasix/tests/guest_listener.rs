@@ -0,0 +1,101 @@
+#![cfg(not(target_family = "wasm"))]
+
+use std::{net::SocketAddr, sync::Arc};
+
+use virtual_fs::{AsyncReadExt, AsyncWriteExt};
+use virtual_mio::InlineWaker;
+use virtual_net::LoopbackNetworking;
+use wasmer::Module;
+use wasmer_types::ModuleHash;
+use wasmer_wasix::{
+    runners::wasi::{RuntimeOrEngine, WasiRunner},
+    runtime::task_manager::tokio::TokioTaskManager,
+    PluggableRuntime,
+};
+
+/// Listens on `127.0.0.1:8080`, accepts a single connection and sends back
+/// the first message it receives.
+const PROGRAM: &str = r#"
+(module
+    (import "wasix_32v1" "sock_open" (func $sock_open (param i32 i32 i32 i32) (result i32)))
+    (import "wasix_32v1" "sock_bind" (func $sock_bind (param i32 i32) (result i32)))
+    (import "wasix_32v1" "sock_listen" (func $sock_listen (param i32 i32) (result i32)))
+    (import "wasix_32v1" "sock_accept_v2" (func $sock_accept (param i32 i32 i32 i32) (result i32)))
+    (import "wasix_32v1" "sock_recv" (func $sock_recv (param i32 i32 i32 i32 i32 i32) (result i32)))
+    (import "wasix_32v1" "sock_send" (func $sock_send (param i32 i32 i32 i32 i32) (result i32)))
+    (memory (export "memory") 1)
+
+    (func (export "_start")
+        ;; An IPv4 stream socket with the default protocol
+        (if (call $sock_open (i32.const 1) (i32.const 1) (i32.const 0) (i32.const 0))
+            (then unreachable))
+
+        ;; Listen on 127.0.0.1:8080
+        (i32.store8 (i32.const 16) (i32.const 1))
+        (i32.store16 (i32.const 18) (i32.const 8080))
+        (i32.store (i32.const 20) (i32.const 0x0100007f))
+        (if (call $sock_bind (i32.load (i32.const 0)) (i32.const 16))
+            (then unreachable))
+        (if (call $sock_listen (i32.load (i32.const 0)) (i32.const 1))
+            (then unreachable))
+
+        (if (call $sock_accept (i32.load (i32.const 0)) (i32.const 0) (i32.const 4) (i32.const 64))
+            (then unreachable))
+
+        (i32.store (i32.const 128) (i32.const 1024))
+        (i32.store (i32.const 132) (i32.const 1024))
+        (if (call $sock_recv (i32.load (i32.const 4)) (i32.const 128) (i32.const 1) (i32.const 0)
+                (i32.const 136) (i32.const 140))
+            (then unreachable))
+        (i32.store (i32.const 132) (i32.load (i32.const 136)))
+        (if (call $sock_send (i32.load (i32.const 4)) (i32.const 128) (i32.const 1) (i32.const 0)
+                (i32.const 144))
+            (then unreachable))
+    )
+)
+"#;
+
+#[test]
+fn host_can_connect_to_guest_listeners() {
+    let tokio_rt = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+    let handle = tokio_rt.handle().clone();
+    let _guard = handle.enter();
+
+    let engine = wasmer::Engine::default();
+    let module = Module::new(&engine, PROGRAM).unwrap();
+    let mut rt = PluggableRuntime::new(Arc::new(TokioTaskManager::new(tokio_rt)));
+    let (listeners_tx, listeners_rx) = std::sync::mpsc::channel();
+    rt.set_engine(engine)
+        .set_networking_implementation(LoopbackNetworking::new())
+        .set_guest_listener_callback(move |addr, listener| {
+            listeners_tx.send((addr, listener)).unwrap();
+        });
+
+    let guest = std::thread::spawn(move || {
+        let mut runner = WasiRunner::new();
+        runner.capabilities_mut().listen.exposed_ports.push(8080);
+        runner.run_wasm(
+            RuntimeOrEngine::Runtime(Arc::new(rt)),
+            "echo",
+            module,
+            ModuleHash::random(),
+        )
+    });
+
+    let (addr, listener) = listeners_rx.recv().unwrap();
+    assert_eq!(addr, "127.0.0.1:8080".parse::<SocketAddr>().unwrap());
+
+    let peer: SocketAddr = "10.0.0.1:50000".parse().unwrap();
+    let (mut tx, mut rx) = listener.connect(peer).unwrap().split();
+    let mut echo = [0; 5];
+    InlineWaker::block_on(async {
+        tx.write_all(b"hello").await.unwrap();
+        rx.read_exact(&mut echo).await.unwrap();
+    });
+    assert_eq!(&echo, b"hello");
+
+    guest.join().unwrap().unwrap();
+}
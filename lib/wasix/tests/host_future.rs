@@ -0,0 +1,213 @@
+#![cfg(not(target_family = "wasm"))]
+
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use bytes::Bytes;
+use wasmer::{Instance, Module, Store, Value};
+use wasmer_wasix::{wasmer_wasix_types::wasi::Errno, WasiEnv, WasiFunctionEnv};
+
+/// A guest that uses the fds of host futures through its exports:
+///
+/// - `first_ready(a, b)` polls both fds and returns the first readable one;
+/// - `read(fd)` reads it into memory at 1024, returning the number of bytes
+///   read or the negated errno;
+/// - `close(fd)` closes it.
+const GUEST: &str = r#"
+(module
+    (import "wasi_snapshot_preview1" "fd_read" (func $fd_read (param i32 i32 i32 i32) (result i32)))
+    (import "wasi_snapshot_preview1" "fd_close" (func $fd_close (param i32) (result i32)))
+    (import "wasi_snapshot_preview1" "poll_oneoff" (func $poll_oneoff (param i32 i32 i32 i32) (result i32)))
+    (memory (export "memory") 1)
+
+    (func (export "first_ready") (param $a i32) (param $b i32) (result i32)
+        (i64.store (i32.const 64) (i64.extend_i32_u (local.get $a)))
+        (i32.store8 (i32.const 72) (i32.const 1))
+        (i32.store (i32.const 80) (local.get $a))
+        (i64.store (i32.const 112) (i64.extend_i32_u (local.get $b)))
+        (i32.store8 (i32.const 120) (i32.const 1))
+        (i32.store (i32.const 128) (local.get $b))
+        (loop $wait
+            (if (call $poll_oneoff (i32.const 64) (i32.const 160) (i32.const 2) (i32.const 232))
+                (then unreachable))
+            (br_if $wait (i32.eqz (i32.load (i32.const 232)))))
+        (if (i32.load16_u (i32.const 168))
+            (then unreachable))
+        (i32.load (i32.const 160))
+    )
+
+    (func (export "read") (param $fd i32) (result i32)
+        (local $errno i32)
+        (i32.store (i32.const 240) (i32.const 1024))
+        (i32.store (i32.const 244) (i32.const 256))
+        (local.set $errno
+            (call $fd_read (local.get $fd) (i32.const 240) (i32.const 1) (i32.const 248)))
+        (if (result i32) (local.get $errno)
+            (then (i32.sub (i32.const 0) (local.get $errno)))
+            (else (i32.load (i32.const 248))))
+    )
+
+    (func (export "close") (param $fd i32)
+        (if (call $fd_close (local.get $fd))
+            (then unreachable))
+    )
+)
+"#;
+
+struct Guest {
+    store: Store,
+    instance: Instance,
+    env: WasiFunctionEnv,
+}
+
+impl Guest {
+    fn new() -> Self {
+        let mut store = Store::default();
+        let module = Module::new(&store, GUEST).unwrap();
+        let (instance, env) = WasiEnv::builder("host-future")
+            .engine(store.engine().clone())
+            .instantiate(module, &mut store)
+            .unwrap();
+
+        Guest {
+            store,
+            instance,
+            env,
+        }
+    }
+
+    fn env(&self) -> &WasiEnv {
+        self.env.data(&self.store)
+    }
+
+    fn call(&mut self, name: &str, params: &[Value]) -> Option<i32> {
+        let func = self.instance.exports.get_function(name).unwrap();
+        func.call(&mut self.store, params)
+            .unwrap()
+            .first()
+            .and_then(|value| value.i32())
+    }
+
+    fn first_ready(&mut self, a: u32, b: u32) -> u32 {
+        self.call("first_ready", &[Value::I32(a as i32), Value::I32(b as i32)])
+            .unwrap() as u32
+    }
+
+    /// Reads `fd` and returns what was read, or the errno.
+    fn read(&mut self, fd: u32) -> Result<Vec<u8>, Errno> {
+        let read = self.call("read", &[Value::I32(fd as i32)]).unwrap();
+        if read < 0 {
+            return Err(Errno::try_from(-read as u16).unwrap());
+        }
+        let memory = self.instance.exports.get_memory("memory").unwrap();
+        let mut buf = vec![0; read as usize];
+        memory.view(&self.store).read(1024, &mut buf).unwrap();
+        Ok(buf)
+    }
+}
+
+fn runtime() -> tokio::runtime::Runtime {
+    tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .unwrap()
+}
+
+async fn after(delay: Duration, result: &'static str) -> Bytes {
+    tokio::time::sleep(delay).await;
+    Bytes::from_static(result.as_bytes())
+}
+
+fn fail() -> Bytes {
+    panic!("the host future failed")
+}
+
+#[test]
+fn results_are_read_in_completion_order() {
+    let rt = runtime();
+    let _guard = rt.enter();
+    let mut guest = Guest::new();
+
+    let start = Instant::now();
+    let slow = guest
+        .env()
+        .spawn_host_future(after(Duration::from_millis(400), "slow"))
+        .unwrap();
+    let fast = guest
+        .env()
+        .spawn_host_future(after(Duration::from_millis(50), "fast"))
+        .unwrap();
+
+    let mut results = Vec::new();
+    let first = guest.first_ready(slow, fast);
+    assert_eq!(first, fast);
+    results.push(guest.read(first).unwrap());
+    // Without waiting for the slow one
+    assert!(start.elapsed() < Duration::from_millis(400));
+
+    let second = guest.first_ready(slow, slow);
+    assert_eq!(second, slow);
+    results.push(guest.read(second).unwrap());
+    assert!(start.elapsed() >= Duration::from_millis(400));
+
+    assert_eq!(results, [b"fast", b"slow"]);
+    // Everything was read
+    assert_eq!(guest.read(fast).unwrap(), b"");
+}
+
+#[test]
+fn closing_the_fd_drops_the_future() {
+    struct SetOnDrop(Arc<AtomicBool>);
+
+    impl Drop for SetOnDrop {
+        fn drop(&mut self) {
+            self.0.store(true, Ordering::SeqCst);
+        }
+    }
+
+    let rt = runtime();
+    let _guard = rt.enter();
+    let mut guest = Guest::new();
+
+    let dropped = Arc::new(AtomicBool::new(false));
+    let on_drop = SetOnDrop(dropped.clone());
+    let fd = guest
+        .env()
+        .spawn_host_future(async move {
+            let _on_drop = on_drop;
+            tokio::time::sleep(Duration::from_secs(600)).await;
+            Bytes::new()
+        })
+        .unwrap();
+
+    guest.call("close", &[Value::I32(fd as i32)]);
+
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while !dropped.load(Ordering::SeqCst) {
+        assert!(Instant::now() < deadline, "the future was never dropped");
+        thread::sleep(Duration::from_millis(10));
+    }
+}
+
+#[test]
+fn reads_fail_if_the_future_panics() {
+    let rt = runtime();
+    let _guard = rt.enter();
+    let mut guest = Guest::new();
+
+    let fd = guest
+        .env()
+        .spawn_host_future(async {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            fail()
+        })
+        .unwrap();
+
+    assert_eq!(guest.read(fd), Err(Errno::Io));
+}
@@ -0,0 +1,150 @@
+#![cfg(all(feature = "host-reqwest", not(target_family = "wasm")))]
+
+use std::{
+    io::{BufRead, BufReader, Read, Write},
+    net::TcpListener,
+    sync::mpsc,
+    thread::JoinHandle,
+};
+
+use wasmer_wasix::http::{reqwest::ReqwestHttpClient, ContentEncoding, HttpClient, HttpRequest};
+
+const BODY: &str = "The quick brown fox jumps over the lazy dog. ";
+
+/// A request as the server received it.
+struct ReceivedRequest {
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+impl ReceivedRequest {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// Serves `requests` HTTP/1.1 requests, answering `/gzip`, `/br` and
+/// `/identity` with [`BODY`] repeated and encoded accordingly.
+fn serve(requests: usize) -> (String, mpsc::Receiver<ReceivedRequest>, JoinHandle<()>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let (tx, rx) = mpsc::channel();
+
+    let handle = std::thread::spawn(move || {
+        for _ in 0..requests {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream);
+
+            let mut request_line = String::new();
+            reader.read_line(&mut request_line).unwrap();
+            let path = request_line.split(' ').nth(1).unwrap().to_string();
+
+            let mut headers = Vec::new();
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                let line = line.trim_end();
+                if line.is_empty() {
+                    break;
+                }
+                let (name, value) = line.split_once(':').unwrap();
+                headers.push((name.to_string(), value.trim().to_string()));
+            }
+            let len = headers
+                .iter()
+                .find(|(name, _)| name.eq_ignore_ascii_case("content-length"))
+                .map(|(_, value)| value.parse().unwrap())
+                .unwrap_or(0);
+            let mut body = vec![0; len];
+            reader.read_exact(&mut body).unwrap();
+            tx.send(ReceivedRequest { headers, body }).unwrap();
+
+            let plain = BODY.repeat(100).into_bytes();
+            let (encoding, body) = match path.as_str() {
+                "/gzip" => ("gzip", ContentEncoding::Gzip.encode(&plain).unwrap()),
+                "/br" => ("br", ContentEncoding::Brotli.encode(&plain).unwrap()),
+                _ => ("identity", plain),
+            };
+            let mut stream = reader.into_inner();
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Encoding: {encoding}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                body.len()
+            )
+            .unwrap();
+            stream.write_all(&body).unwrap();
+        }
+    });
+
+    (url, rx, handle)
+}
+
+#[tokio::test]
+async fn responses_are_decoded() {
+    let (url, requests, server) = serve(3);
+    let client = ReqwestHttpClient::default();
+    let plain = BODY.repeat(100).into_bytes();
+
+    for (path, encoding) in [
+        ("gzip", Some(ContentEncoding::Gzip)),
+        ("br", Some(ContentEncoding::Brotli)),
+        ("identity", None),
+    ] {
+        let request = HttpRequest::get(format!("{url}/{path}").parse().unwrap())
+            .build()
+            .unwrap();
+        let response = client.request(request).await.unwrap();
+
+        assert_eq!(response.body.as_deref(), Some(plain.as_slice()), "{path}");
+        assert_eq!(response.content_encoding, encoding, "{path}");
+        assert_eq!(response.decoded_body_size, plain.len(), "{path}");
+        assert_eq!(response.headers["content-encoding"].to_str().unwrap(), path);
+        if encoding.is_some() {
+            assert!(response.encoded_body_size < plain.len(), "{path}");
+        } else {
+            assert_eq!(response.encoded_body_size, plain.len());
+        }
+
+        let received = requests.recv().unwrap();
+        assert_eq!(received.header("accept-encoding"), Some("gzip, br"));
+    }
+
+    server.join().unwrap();
+}
+
+#[tokio::test]
+async fn large_request_bodies_are_compressed() {
+    let (url, requests, server) = serve(2);
+    let client = ReqwestHttpClient::default();
+    let url: url::Url = format!("{url}/identity").parse().unwrap();
+
+    let small = HttpRequest::post(url.clone())
+        .body("tiny")
+        .body_encoding(ContentEncoding::Gzip)
+        .build()
+        .unwrap();
+    client.request(small).await.unwrap();
+    let received = requests.recv().unwrap();
+    assert_eq!(received.header("content-encoding"), None);
+    assert_eq!(received.body, b"tiny");
+
+    let plain = BODY.repeat(100).into_bytes();
+    let large = HttpRequest::post(url)
+        .body(plain.clone())
+        .body_encoding(ContentEncoding::Brotli)
+        .build()
+        .unwrap();
+    client.request(large).await.unwrap();
+    let received = requests.recv().unwrap();
+    assert_eq!(received.header("content-encoding"), Some("br"));
+    assert!(received.body.len() < plain.len());
+    assert_eq!(
+        ContentEncoding::Brotli.decode(&received.body).unwrap(),
+        plain
+    );
+
+    server.join().unwrap();
+}
@@ -0,0 +1,268 @@
+#![cfg(not(target_family = "wasm"))]
+
+use std::sync::Arc;
+
+use wasmer::{Instance, Module, Store, Value};
+use wasmer_wasix::{
+    capabilities::{Capabilities, CapabilityKvV1},
+    os::task::control_plane::{ControlPlaneConfig, WasiControlPlane},
+    runtime::{kv::InMemoryKvBackend, task_manager::tokio::TokioTaskManager},
+    wasmer_wasix_types::wasi::Errno,
+    PluggableRuntime, WasiEnv,
+};
+
+/// Exports the key-value syscalls as they are, so they can be called with
+/// buffers in its memory.
+const PROGRAM: &str = r#"
+(module
+    (import "wasix_32v1" "kv_get" (func $kv_get (param i32 i32 i32 i32) (result i32)))
+    (import "wasix_32v1" "kv_put" (func $kv_put (param i32 i32 i32 i32) (result i32)))
+    (import "wasix_32v1" "kv_delete" (func $kv_delete (param i32 i32) (result i32)))
+    (import "wasix_32v1" "kv_scan" (func $kv_scan (param i32 i32 i64 i32 i32 i32) (result i32)))
+    (memory (export "memory") 1)
+    (export "get" (func $kv_get))
+    (export "put" (func $kv_put))
+    (export "delete" (func $kv_delete))
+    (export "scan" (func $kv_scan))
+)
+"#;
+
+const KEY: u64 = 1024;
+const VALUE: u64 = 2048;
+const BUF: u64 = 4096;
+const BUF_LEN: u64 = 16;
+const COOKIE: u64 = 24;
+
+struct Guest {
+    store: Store,
+    instance: Instance,
+}
+
+impl Guest {
+    fn new(rt: &Arc<PluggableRuntime>, plane: &WasiControlPlane, kv: CapabilityKvV1) -> Self {
+        let mut store = Store::new(rt.engine.clone());
+        let module = Module::new(&store, PROGRAM).unwrap();
+        let (instance, _env) = WasiEnv::builder("kv")
+            .runtime(rt.clone())
+            .control_plane(plane.clone())
+            .capabilities(Capabilities {
+                kv,
+                ..Capabilities::new()
+            })
+            .instantiate(module, &mut store)
+            .unwrap();
+
+        Guest { store, instance }
+    }
+
+    fn write(&self, offset: u64, data: &[u8]) {
+        let memory = self.instance.exports.get_memory("memory").unwrap();
+        memory.view(&self.store).write(offset, data).unwrap();
+    }
+
+    fn read(&self, offset: u64, len: usize) -> Vec<u8> {
+        let memory = self.instance.exports.get_memory("memory").unwrap();
+        let mut data = vec![0; len];
+        memory.view(&self.store).read(offset, &mut data).unwrap();
+        data
+    }
+
+    fn read_u32(&self, offset: u64) -> u32 {
+        u32::from_le_bytes(self.read(offset, 4).try_into().unwrap())
+    }
+
+    fn call(&mut self, name: &str, params: &[Value]) -> Errno {
+        let func = self.instance.exports.get_function(name).unwrap();
+        let errno = func.call(&mut self.store, params).unwrap()[0].unwrap_i32();
+        Errno::try_from(errno as u16).unwrap()
+    }
+
+    fn put(&mut self, key: &str, value: &[u8]) -> Errno {
+        self.write(KEY, key.as_bytes());
+        self.write(VALUE, value);
+        self.call(
+            "put",
+            &[
+                Value::I32(KEY as i32),
+                Value::I32(key.len() as i32),
+                Value::I32(VALUE as i32),
+                Value::I32(value.len() as i32),
+            ],
+        )
+    }
+
+    /// Reads a value into a buffer of `buf_len` bytes, returning the errno,
+    /// the value and the length the syscall reported.
+    fn get(&mut self, key: &str, buf_len: u32) -> (Errno, Vec<u8>, u32) {
+        self.write(KEY, key.as_bytes());
+        self.write(BUF_LEN, &buf_len.to_le_bytes());
+        let errno = self.call(
+            "get",
+            &[
+                Value::I32(KEY as i32),
+                Value::I32(key.len() as i32),
+                Value::I32(VALUE as i32),
+                Value::I32(BUF_LEN as i32),
+            ],
+        );
+        let len = self.read_u32(BUF_LEN);
+        let value = match errno {
+            Errno::Success => self.read(VALUE, len as usize),
+            _ => Vec::new(),
+        };
+        (errno, value, len)
+    }
+
+    fn delete(&mut self, key: &str) -> Errno {
+        self.write(KEY, key.as_bytes());
+        self.call(
+            "delete",
+            &[Value::I32(KEY as i32), Value::I32(key.len() as i32)],
+        )
+    }
+
+    /// Lists the keys starting with `prefix` in as many calls as it takes
+    /// with a buffer of `buf_len` bytes.
+    fn scan(&mut self, prefix: &str, buf_len: u32) -> Vec<String> {
+        let mut keys = Vec::new();
+        let mut cookie = 0;
+        loop {
+            self.write(KEY, prefix.as_bytes());
+            self.write(BUF_LEN, &buf_len.to_le_bytes());
+            let errno = self.call(
+                "scan",
+                &[
+                    Value::I32(KEY as i32),
+                    Value::I32(prefix.len() as i32),
+                    Value::I64(cookie),
+                    Value::I32(BUF as i32),
+                    Value::I32(BUF_LEN as i32),
+                    Value::I32(COOKIE as i32),
+                ],
+            );
+            assert_eq!(errno, Errno::Success);
+
+            let used = self.read_u32(BUF_LEN) as usize;
+            if used == 0 {
+                return keys;
+            }
+            let entries = self.read(BUF, used);
+            let mut entries = &entries[..];
+            while !entries.is_empty() {
+                let (len, rest) = entries.split_at(4);
+                let len = u32::from_le_bytes(len.try_into().unwrap()) as usize;
+                keys.push(String::from_utf8(rest[..len].to_vec()).unwrap());
+                entries = &rest[len..];
+            }
+            cookie = u64::from_le_bytes(self.read(COOKIE, 8).try_into().unwrap()) as i64;
+        }
+    }
+}
+
+fn runtime(
+    tokio_rt: &tokio::runtime::Runtime,
+    backend: InMemoryKvBackend,
+) -> Arc<PluggableRuntime> {
+    let mut rt = PluggableRuntime::new(Arc::new(TokioTaskManager::new(tokio_rt.handle().clone())));
+    rt.set_kv_backend(backend);
+    Arc::new(rt)
+}
+
+fn enabled() -> CapabilityKvV1 {
+    CapabilityKvV1 {
+        enabled: true,
+        ..Default::default()
+    }
+}
+
+fn tokio_runtime() -> tokio::runtime::Runtime {
+    tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .unwrap()
+}
+
+#[test]
+fn processes_share_the_store() {
+    let tokio_rt = tokio_runtime();
+    let _guard = tokio_rt.enter();
+    let rt = runtime(&tokio_rt, InMemoryKvBackend::new());
+    let plane = WasiControlPlane::new(ControlPlaneConfig::new());
+
+    let mut writer = Guest::new(&rt, &plane, enabled());
+    assert_eq!(writer.put("user/1", b"alice"), Errno::Success);
+    assert_eq!(writer.put("user/2", b"bob"), Errno::Success);
+    assert_eq!(writer.put("post/1", b"hello"), Errno::Success);
+
+    let mut reader = Guest::new(&rt, &plane, enabled());
+    assert_eq!(
+        reader.get("user/1", 64),
+        (Errno::Success, b"alice".to_vec(), 5)
+    );
+    assert_eq!(reader.get("user/3", 64).0, Errno::Noent);
+    // The length of the value is reported when the buffer is too small
+    assert_eq!(reader.get("user/1", 2), (Errno::Range, Vec::new(), 5));
+
+    // Buffers that only fit one key at a time take several calls
+    assert_eq!(reader.scan("user/", 64), ["user/1", "user/2"]);
+    assert_eq!(reader.scan("user/", 10), ["user/1", "user/2"]);
+    assert_eq!(reader.scan("", 64), ["post/1", "user/1", "user/2"]);
+
+    assert_eq!(reader.delete("user/1"), Errno::Success);
+    assert_eq!(reader.delete("user/1"), Errno::Noent);
+    assert_eq!(writer.get("user/1", 64).0, Errno::Noent);
+}
+
+#[test]
+fn namespaces_are_kept_apart() {
+    let tokio_rt = tokio_runtime();
+    let _guard = tokio_rt.enter();
+    let rt = runtime(&tokio_rt, InMemoryKvBackend::new());
+    let plane = WasiControlPlane::new(ControlPlaneConfig::new());
+
+    let mut first = Guest::new(
+        &rt,
+        &plane,
+        CapabilityKvV1 {
+            namespace: Some("first".to_string()),
+            ..enabled()
+        },
+    );
+    let mut second = Guest::new(
+        &rt,
+        &plane,
+        CapabilityKvV1 {
+            namespace: Some("second".to_string()),
+            ..enabled()
+        },
+    );
+
+    assert_eq!(first.put("key", b"value"), Errno::Success);
+    assert_eq!(second.get("key", 64).0, Errno::Noent);
+    assert!(second.scan("", 64).is_empty());
+}
+
+#[test]
+fn quota_violations_are_enospc() {
+    let tokio_rt = tokio_runtime();
+    let _guard = tokio_rt.enter();
+    let rt = runtime(&tokio_rt, InMemoryKvBackend::new().with_quota(16));
+    let plane = WasiControlPlane::new(ControlPlaneConfig::new());
+
+    let mut guest = Guest::new(&rt, &plane, enabled());
+    assert_eq!(guest.put("key", b"0123456789"), Errno::Success);
+    assert_eq!(guest.put("other", b"0123456789"), Errno::Nospc);
+    assert_eq!(guest.get("other", 64).0, Errno::Noent);
+}
+
+#[test]
+fn the_store_requires_the_capability() {
+    let tokio_rt = tokio_runtime();
+    let _guard = tokio_rt.enter();
+    let rt = runtime(&tokio_rt, InMemoryKvBackend::new());
+    let plane = WasiControlPlane::new(ControlPlaneConfig::new());
+
+    let mut guest = Guest::new(&rt, &plane, CapabilityKvV1::default());
+    assert_eq!(guest.put("key", b"value"), Errno::Notcapable);
+    assert_eq!(guest.get("key", 64).0, Errno::Notcapable);
+}
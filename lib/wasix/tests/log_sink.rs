@@ -0,0 +1,113 @@
+#![cfg(all(feature = "log-sink", not(target_family = "wasm")))]
+
+use std::sync::{Arc, Mutex};
+
+use tracing::{
+    field::{Field, Visit},
+    Subscriber,
+};
+use tracing_subscriber::{layer::Context, prelude::*, registry::LookupSpan, Layer};
+use wasmer::Module;
+use wasmer_wasix::{
+    bin_factory::spawn_exec_module,
+    log_sink::{LogRecord, LogSinkLayer},
+    runtime::task_manager::tokio::TokioTaskManager,
+    PluggableRuntime, Runtime, WasiEnvBuilder,
+};
+
+const PROGRAM: &str = r#"
+(module
+    (import "wasi_snapshot_preview1" "proc_exit" (func $proc_exit (param i32)))
+    (memory (export "memory") 1)
+    (func (export "_start")
+        (call $proc_exit (i32.const 0))
+    )
+)
+"#;
+
+/// The messages of the events the rest of the subscriber sees.
+#[derive(Debug, Clone, Default)]
+struct Uncaptured(Arc<Mutex<Vec<String>>>);
+
+impl Visit for Uncaptured {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "message" {
+            self.0.lock().unwrap().push(format!("{value:?}"));
+        }
+    }
+}
+
+impl<S> Layer<S> for Uncaptured
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_event(&self, event: &tracing::Event<'_>, _ctx: Context<'_, S>) {
+        event.record(&mut self.clone());
+    }
+}
+
+#[test]
+fn only_the_events_of_one_process_are_captured() {
+    let uncaptured = Uncaptured::default();
+    tracing_subscriber::registry()
+        .with(LogSinkLayer::new())
+        .with(
+            uncaptured
+                .clone()
+                .with_filter(LogSinkLayer::exclude_captured()),
+        )
+        .try_init()
+        .unwrap();
+
+    let tokio_rt = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+    let handle = tokio_rt.handle().clone();
+    let _guard = handle.enter();
+    let rt: Arc<dyn Runtime + Send + Sync> = Arc::new(PluggableRuntime::new(Arc::new(
+        TokioTaskManager::new(tokio_rt),
+    )));
+
+    let module = Module::new(&rt.engine(), PROGRAM).unwrap();
+
+    let captured = Arc::new(Mutex::new(Vec::<LogRecord>::new()));
+    let sink = captured.clone();
+    let env_a = WasiEnvBuilder::new("a")
+        .runtime(rt.clone())
+        .with_log_sink(move |record: &LogRecord| sink.lock().unwrap().push(record.clone()))
+        .build()
+        .unwrap();
+    let env_b = WasiEnvBuilder::new("b")
+        .runtime(rt.clone())
+        .build()
+        .unwrap();
+    let pid_a = env_a.pid().raw();
+
+    // Both processes run at the same time
+    let mut a = spawn_exec_module(module.clone(), env_a, &rt).unwrap();
+    let mut b = spawn_exec_module(module, env_b, &rt).unwrap();
+    handle.block_on(async {
+        a.wait_finished().await.unwrap();
+        b.wait_finished().await.unwrap();
+    });
+
+    let captured = captured.lock().unwrap();
+    assert!(!captured.is_empty());
+    assert!(captured.iter().all(|record| record.pid == pid_a));
+    let called_main = |message: &str| message.ends_with("::called main()");
+    assert_eq!(
+        captured.iter().filter(|r| called_main(&r.message)).count(),
+        1,
+        "{captured:#?}"
+    );
+
+    // The other process's events still go to the rest of the subscriber,
+    // but the captured ones don't
+    let uncaptured = uncaptured.0.lock().unwrap();
+    assert_eq!(
+        uncaptured.iter().filter(|m| called_main(m)).count(),
+        1,
+        "{uncaptured:#?}"
+    );
+}
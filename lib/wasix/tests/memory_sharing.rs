@@ -0,0 +1,118 @@
+#![cfg(not(target_family = "wasm"))]
+
+use std::sync::Arc;
+
+use virtual_fs::{AsyncReadExt, Pipe};
+use wasmer::{imports, Instance, InstantiationError, Module, Store};
+use wasmer_wasix::{
+    runtime::task_manager::tokio::TokioTaskManager, PluggableRuntime, WasiEnvBuilder,
+};
+
+/// Writes "hello" at offset 1024 on start, and exports functions to print
+/// what is there and to grow its memory.
+const GUEST: &str = r#"
+(module
+    (import "wasi_snapshot_preview1" "fd_write" (func $fd_write (param i32 i32 i32 i32) (result i32)))
+    (memory (export "memory") 1)
+
+    (func (export "_start")
+        (i32.store8 (i32.const 1024) (i32.const 104))
+        (i32.store8 (i32.const 1025) (i32.const 101))
+        (i32.store8 (i32.const 1026) (i32.const 108))
+        (i32.store8 (i32.const 1027) (i32.const 108))
+        (i32.store8 (i32.const 1028) (i32.const 111))
+    )
+
+    (func (export "print")
+        (i32.store (i32.const 16) (i32.const 1024))
+        (i32.store (i32.const 20) (i32.const 5))
+        (drop (call $fd_write (i32.const 1) (i32.const 16) (i32.const 1) (i32.const 24)))
+    )
+
+    (func (export "grow")
+        (drop (memory.grow (i32.const 1))))
+)
+"#;
+
+/// Upper-cases the five bytes at offset 1024 of the memory it imports.
+const PLUGIN: &str = r#"
+(module
+    (import "env" "memory" (memory 1))
+
+    (func (export "shout")
+        (local $i i32)
+        (loop $next
+            (i32.store8 (i32.add (i32.const 1024) (local.get $i))
+                (i32.sub (i32.load8_u (i32.add (i32.const 1024) (local.get $i))) (i32.const 32)))
+            (local.set $i (i32.add (local.get $i) (i32.const 1)))
+            (br_if $next (i32.lt_u (local.get $i) (i32.const 5))))
+    )
+
+    (func (export "pages") (result i32)
+        (memory.size))
+)
+"#;
+
+#[test]
+fn plugin_instances_can_import_the_guest_memory() {
+    let tokio_rt = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+    let handle = tokio_rt.handle().clone();
+    let _guard = handle.enter();
+
+    let engine = wasmer::Engine::default();
+    let mut rt = PluggableRuntime::new(Arc::new(TokioTaskManager::new(tokio_rt)));
+    rt.set_engine(engine.clone());
+
+    let (stdout_tx, mut stdout_rx) = Pipe::channel();
+    let mut store = Store::new(engine.clone());
+    let (guest, env) = WasiEnvBuilder::new("guest")
+        .runtime(Arc::new(rt))
+        .stdout(Box::new(stdout_tx))
+        .instantiate(Module::new(&engine, GUEST).unwrap(), &mut store)
+        .unwrap();
+    let start = guest.exports.get_function("_start").unwrap();
+    start.call(&mut store, &[]).unwrap();
+
+    let memory = env.data(&store).memory_clone().unwrap();
+    let plugin_module = Module::new(&engine, PLUGIN).unwrap();
+    let plugin = Instance::new(
+        &mut store,
+        &plugin_module,
+        &imports! { "env" => { "memory" => memory.clone() } },
+    )
+    .unwrap();
+
+    let mut greeting = [0; 5];
+    memory.view(&store).read(1024, &mut greeting).unwrap();
+    assert_eq!(&greeting, b"hello");
+
+    let shout = plugin.exports.get_function("shout").unwrap();
+    shout.call(&mut store, &[]).unwrap();
+    let print = guest.exports.get_function("print").unwrap();
+    print.call(&mut store, &[]).unwrap();
+    let mut stdout = [0; 5];
+    handle.block_on(stdout_rx.read_exact(&mut stdout)).unwrap();
+    assert_eq!(&stdout, b"HELLO");
+
+    // Growing the memory from the guest is visible to the plugin
+    let grow = guest.exports.get_function("grow").unwrap();
+    grow.call(&mut store, &[]).unwrap();
+    let pages = plugin.exports.get_function("pages").unwrap();
+    assert_eq!(pages.call(&mut store, &[]).unwrap()[0].unwrap_i32(), 2);
+
+    // The memory can't be used from another store
+    let mut other_store = Store::new(engine);
+    let err = Instance::new(
+        &mut other_store,
+        &plugin_module,
+        &imports! { "env" => { "memory" => memory } },
+    )
+    .unwrap_err();
+    assert!(
+        matches!(err, InstantiationError::DifferentStores),
+        "{err:?}"
+    );
+}
@@ -0,0 +1,179 @@
+#![cfg(not(target_family = "wasm"))]
+
+use std::{path::Path, sync::Arc};
+
+use serde_json::Value;
+use sha2::Digest;
+use virtual_fs::{AsyncReadExt, AsyncSeekExt};
+use wasmer_wasix::{
+    bin_factory::{BinaryPackage, PackageMetadata},
+    runners::wasi::{RuntimeOrEngine, WasiRunner},
+    runtime::{
+        package_loader::BuiltinPackageLoader, resolver::FileSystemSource,
+        task_manager::tokio::TokioTaskManager,
+    },
+    PluggableRuntime,
+};
+
+/// A program that prints the contents of `/etc/wasmer/package.json`, then
+/// the output of `proc_package_metadata()`, and finally `exec()`s `exec`
+/// (if there is one).
+fn program(exec: Option<&str>) -> String {
+    let exec_name = exec.unwrap_or_default();
+    let exec_call = match exec {
+        Some(name) => format!(
+            "(call $proc_exec (i32.const 200) (i32.const {len}) (i32.const 200) (i32.const {len}))",
+            len = name.len(),
+        ),
+        None => String::new(),
+    };
+
+    format!(
+        r#"
+(module
+    (import "wasi_snapshot_preview1" "path_open" (func $path_open (param i32 i32 i32 i32 i32 i64 i64 i32 i32) (result i32)))
+    (import "wasi_snapshot_preview1" "fd_read" (func $fd_read (param i32 i32 i32 i32) (result i32)))
+    (import "wasi_snapshot_preview1" "fd_write" (func $fd_write (param i32 i32 i32 i32) (result i32)))
+    (import "wasix_32v1" "proc_package_metadata" (func $proc_package_metadata (param i32 i32) (result i32)))
+    (import "wasix_32v1" "proc_exec" (func $proc_exec (param i32 i32 i32 i32)))
+    (memory (export "memory") 1)
+    (data (i32.const 100) "etc/wasmer/package.json")
+    (data (i32.const 200) "{exec_name}")
+    (data (i32.const 300) "\n")
+
+    (func $print (param $ptr i32) (param $len i32)
+        (i32.store (i32.const 16) (local.get $ptr))
+        (i32.store (i32.const 20) (local.get $len))
+        (drop (call $fd_write (i32.const 1) (i32.const 16) (i32.const 1) (i32.const 24)))
+        (i32.store (i32.const 16) (i32.const 300))
+        (i32.store (i32.const 20) (i32.const 1))
+        (drop (call $fd_write (i32.const 1) (i32.const 16) (i32.const 1) (i32.const 24)))
+    )
+
+    (func (export "_start")
+        ;; Read the file, using the "/" pre-open
+        (if (call $path_open (i32.const 3) (i32.const 0) (i32.const 100) (i32.const 23)
+                (i32.const 0) (i64.const 2) (i64.const 0) (i32.const 0) (i32.const 32))
+            (then unreachable))
+        (i32.store (i32.const 0) (i32.const 1024))
+        (i32.store (i32.const 4) (i32.const 4096))
+        (if (call $fd_read (i32.load (i32.const 32)) (i32.const 0) (i32.const 1) (i32.const 8))
+            (then unreachable))
+        (call $print (i32.const 1024) (i32.load (i32.const 8)))
+
+        ;; Ask for the same thing using the syscall
+        (i32.store (i32.const 40) (i32.const 4096))
+        (if (call $proc_package_metadata (i32.const 8192) (i32.const 40))
+            (then unreachable))
+        (call $print (i32.const 8192) (i32.load (i32.const 40)))
+
+        {exec_call}
+    )
+)
+"#
+    )
+}
+
+fn build_package(dir: &Path, name: &str, exec: Option<&str>) -> Vec<u8> {
+    let pkg_dir = dir.join(name);
+    std::fs::create_dir(&pkg_dir).unwrap();
+    let wasmer_toml = format!(
+        r#"
+[package]
+name = "test/{name}"
+version = "0.1.0"
+
+[[module]]
+name = "{name}"
+source = "{name}.wasm"
+abi = "wasi"
+
+[[command]]
+name = "{name}"
+module = "{name}"
+"#
+    );
+    std::fs::write(pkg_dir.join("wasmer.toml"), wasmer_toml).unwrap();
+    let wat = program(exec);
+    let wasm = wasmer::wat2wasm(wat.as_bytes()).unwrap();
+    std::fs::write(pkg_dir.join(format!("{name}.wasm")), wasm).unwrap();
+
+    wasmer_package::package::Package::from_manifest(pkg_dir.join("wasmer.toml"))
+        .unwrap()
+        .serialize()
+        .unwrap()
+        .to_vec()
+}
+
+#[test]
+fn package_metadata_is_replaced_on_exec() {
+    let temp = tempfile::tempdir().unwrap();
+    let first = build_package(temp.path(), "first", Some("/bin/second"));
+    let second = build_package(temp.path(), "second", None);
+
+    let tokio_rt = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+    let handle = tokio_rt.handle().clone();
+    let _guard = handle.enter();
+
+    let mut rt = PluggableRuntime::new(Arc::new(TokioTaskManager::new(tokio_rt)));
+    rt.set_source(FileSystemSource::default())
+        .set_package_loader(BuiltinPackageLoader::new());
+    let rt = Arc::new(rt);
+
+    let load = |webc: &[u8]| {
+        let container = wasmer_package::utils::from_bytes(webc.to_vec()).unwrap();
+        handle
+            .block_on(BinaryPackage::from_webc(&container, &*rt))
+            .unwrap()
+    };
+    let pkg = load(&first);
+    let other_pkg = load(&second);
+    let mut stdout = virtual_fs::ArcFile::new(Box::<virtual_fs::BufferFile>::default());
+
+    let stdout_2 = stdout.clone();
+    std::thread::spawn(move || {
+        let _guard = handle.enter();
+        WasiRunner::new()
+            .with_injected_package(other_pkg)
+            .with_stdout(Box::new(stdout_2) as Box<_>)
+            .run_command("first", &pkg, RuntimeOrEngine::Runtime(rt))
+    })
+    .join()
+    .unwrap()
+    .unwrap();
+
+    let mut output = String::new();
+    futures::executor::block_on(async {
+        stdout.rewind().await.unwrap();
+        stdout.read_to_string(&mut output).await.unwrap();
+    });
+    let lines: Vec<Value> = output
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    let [first_file, first_syscall, second_file, second_syscall] = lines.as_slice() else {
+        panic!("unexpected output: {output}");
+    };
+
+    let sha256 = |webc: &[u8]| hex::encode(sha2::Sha256::digest(webc));
+
+    assert_eq!(first_file, first_syscall);
+    assert_eq!(first_file["command"], "first");
+    assert_eq!(first_file["webc_sha256"], sha256(&first));
+    // Packages serialized locally don't keep their name, so they are
+    // identified by their hash instead.
+    assert_eq!(first_file["id"], format!("sha256:{}", sha256(&first)));
+    assert_eq!(first_file["name"], Value::Null);
+    assert_eq!(first_file["version"], Value::Null);
+
+    // After exec, both should describe the other package
+    assert_eq!(second_file, second_syscall);
+    assert_eq!(second_file["command"], "second");
+    assert_eq!(second_file["webc_sha256"], sha256(&second));
+
+    let metadata: PackageMetadata = serde_json::from_value(second_file.clone()).unwrap();
+    assert_eq!(metadata.id, format!("sha256:{}", sha256(&second)));
+}
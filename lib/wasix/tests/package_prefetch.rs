@@ -0,0 +1,207 @@
+#![cfg(not(target_family = "wasm"))]
+
+use std::{
+    collections::HashMap,
+    path::Path,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use tempfile::TempDir;
+use wasmer_package::{package::Package, utils::from_disk};
+use wasmer_wasix::{
+    bin_factory::BinaryPackage,
+    runtime::{
+        package_loader::{load_package_tree, PackageLoader},
+        resolver::{InMemorySource, PackageSummary, Resolution},
+        task_manager::tokio::TokioTaskManager,
+    },
+    PluggableRuntime, Runtime, WasiEnvBuilder,
+};
+use webc::Container;
+
+/// A registry where every package takes a while to download.
+#[derive(Debug, Default)]
+struct SlowRegistry {
+    /// How long each package takes, by name.
+    latency: HashMap<String, Duration>,
+    /// The packages that can't be downloaded, after the same latency.
+    broken: Vec<String>,
+    /// Every download, in the order they started.
+    started: Mutex<Vec<String>>,
+    /// Every successful download, in the order they finished.
+    finished: Mutex<Vec<String>>,
+}
+
+#[async_trait::async_trait]
+impl PackageLoader for SlowRegistry {
+    async fn load(&self, summary: &PackageSummary) -> Result<Container, anyhow::Error> {
+        let path = summary.dist.webc.to_file_path().unwrap();
+        let name = path.file_stem().unwrap().to_str().unwrap().to_string();
+        self.started.lock().unwrap().push(name.clone());
+
+        tokio::time::sleep(self.latency[&name]).await;
+        if self.broken.contains(&name) {
+            anyhow::bail!("the registry is having a bad day");
+        }
+
+        let container = from_disk(&path)?;
+        self.finished.lock().unwrap().push(name);
+        Ok(container)
+    }
+
+    async fn load_package_tree(
+        &self,
+        root: &Container,
+        resolution: &Resolution,
+        root_is_local_dir: bool,
+    ) -> Result<BinaryPackage, anyhow::Error> {
+        load_package_tree(root, self, resolution, root_is_local_dir).await
+    }
+}
+
+/// Publishes an empty `test/<name>` package for each of `latency` to an
+/// in-memory source.
+fn registry(
+    dir: &Path,
+    latency: &[(&str, u64)],
+    broken: &[&str],
+) -> (InMemorySource, Arc<SlowRegistry>) {
+    let mut source = InMemorySource::new();
+    for (name, _) in latency {
+        let manifest = dir.join(format!("{name}.toml"));
+        std::fs::write(
+            &manifest,
+            format!("[package]\nname = \"test/{name}\"\nversion = \"0.1.0\"\ndescription = \"{name}\"\n"),
+        )
+        .unwrap();
+        let webc = dir.join(format!("{name}.webc"));
+        std::fs::write(
+            &webc,
+            Package::from_manifest(&manifest)
+                .unwrap()
+                .serialize()
+                .unwrap(),
+        )
+        .unwrap();
+        source.add_webc(&webc).unwrap();
+    }
+
+    let loader = SlowRegistry {
+        latency: latency
+            .iter()
+            .map(|(name, ms)| (name.to_string(), Duration::from_millis(*ms)))
+            .collect(),
+        broken: broken.iter().map(|name| name.to_string()).collect(),
+        ..Default::default()
+    };
+    (source, Arc::new(loader))
+}
+
+fn runtime(
+    tokio_rt: tokio::runtime::Runtime,
+    source: InMemorySource,
+    loader: &Arc<SlowRegistry>,
+) -> PluggableRuntime {
+    let mut rt = PluggableRuntime::new(Arc::new(TokioTaskManager::new(tokio_rt)));
+    rt.set_source(source).set_package_loader(loader.clone());
+    rt
+}
+
+fn tokio_runtime() -> tokio::runtime::Runtime {
+    tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .unwrap()
+}
+
+fn uses(rt: Arc<dyn Runtime + Send + Sync>, packages: &[&str]) -> Result<(), String> {
+    let env = WasiEnvBuilder::new("main").runtime(rt).build().unwrap();
+    env.uses(packages.iter().map(|name| format!("test/{name}")))
+        .map_err(|e| e.to_string())
+}
+
+#[test]
+fn dependencies_download_in_parallel() {
+    let tokio_rt = tokio_runtime();
+    let handle = tokio_rt.handle().clone();
+    let _guard = handle.enter();
+    let temp = TempDir::new().unwrap();
+    let (source, loader) = registry(temp.path(), &[("a", 200), ("b", 200), ("c", 400)], &[]);
+    let rt = runtime(tokio_rt, source, &loader);
+
+    let start = Instant::now();
+    uses(Arc::new(rt), &["a", "b", "c"]).unwrap();
+    let elapsed = start.elapsed();
+
+    // The slowest download, rather than all of them one after the other
+    assert!(elapsed >= Duration::from_millis(400), "{elapsed:?}");
+    assert!(elapsed < Duration::from_millis(700), "{elapsed:?}");
+    assert_eq!(loader.finished.lock().unwrap().len(), 3);
+}
+
+#[test]
+fn downloads_respect_the_concurrency_limit() {
+    let tokio_rt = tokio_runtime();
+    let handle = tokio_rt.handle().clone();
+    let _guard = handle.enter();
+    let temp = TempDir::new().unwrap();
+    let (source, loader) = registry(temp.path(), &[("a", 200), ("b", 200), ("c", 200)], &[]);
+    let mut rt = runtime(tokio_rt, source, &loader);
+    rt.set_package_download_concurrency(1);
+
+    let start = Instant::now();
+    uses(Arc::new(rt), &["a", "b", "c"]).unwrap();
+
+    assert!(start.elapsed() >= Duration::from_millis(600));
+    assert_eq!(*loader.finished.lock().unwrap(), ["a", "b", "c"]);
+}
+
+#[test]
+fn failures_name_the_dependency() {
+    let tokio_rt = tokio_runtime();
+    let handle = tokio_rt.handle().clone();
+    let _guard = handle.enter();
+    let temp = TempDir::new().unwrap();
+    let (source, loader) = registry(
+        temp.path(),
+        &[("a", 300), ("broken", 100), ("c", 300)],
+        &["broken"],
+    );
+    let rt = runtime(tokio_rt, source, &loader);
+
+    let error = uses(Arc::new(rt), &["a", "broken", "c"]).unwrap_err();
+
+    assert!(error.contains("package_name=test/broken"), "{error}");
+    assert!(!error.contains("package_name=test/a"), "{error}");
+    // The failure didn't cut the slower downloads short
+    let mut finished = loader.finished.lock().unwrap().clone();
+    finished.sort();
+    assert_eq!(finished, ["a", "c"]);
+}
+
+#[test]
+fn concurrent_processes_share_downloads() {
+    let tokio_rt = tokio_runtime();
+    let handle = tokio_rt.handle().clone();
+    let _guard = handle.enter();
+    let temp = TempDir::new().unwrap();
+    let (source, loader) = registry(temp.path(), &[("a", 300)], &[]);
+    let rt: Arc<dyn Runtime + Send + Sync> = Arc::new(runtime(tokio_rt, source, &loader));
+
+    let processes: Vec<_> = (0..3)
+        .map(|_| {
+            let rt = rt.clone();
+            let handle = handle.clone();
+            std::thread::spawn(move || {
+                let _guard = handle.enter();
+                uses(rt, &["a"])
+            })
+        })
+        .collect();
+    for process in processes {
+        process.join().unwrap().unwrap();
+    }
+
+    assert_eq!(*loader.started.lock().unwrap(), ["a"]);
+}
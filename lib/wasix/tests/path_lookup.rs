@@ -0,0 +1,221 @@
+#![cfg(not(target_family = "wasm"))]
+
+use std::path::Path;
+
+use virtual_fs::{mem_fs, FileSystem};
+use wasmer::{Instance, Module, Store, Value};
+use wasmer_wasix::{
+    fs::PathLookupStats, wasmer_wasix_types::wasi::Errno, WasiEnv, WasiFunctionEnv,
+};
+
+const FILE: &str = "a/b/c/d/e/file";
+
+/// Exports wrappers around `path_open` (which closes the file again),
+/// `path_unlink_file`, `path_rename` and `path_symlink` that work on paths
+/// relative to the preopened `/` (fd 4) and return the errno.
+const PROGRAM: &str = r#"
+(module
+    (import "wasi_snapshot_preview1" "path_open" (func $path_open (param i32 i32 i32 i32 i32 i64 i64 i32 i32) (result i32)))
+    (import "wasi_snapshot_preview1" "fd_close" (func $fd_close (param i32) (result i32)))
+    (import "wasi_snapshot_preview1" "path_unlink_file" (func $path_unlink_file (param i32 i32 i32) (result i32)))
+    (import "wasi_snapshot_preview1" "path_rename" (func $path_rename (param i32 i32 i32 i32 i32 i32) (result i32)))
+    (import "wasi_snapshot_preview1" "path_symlink" (func $path_symlink (param i32 i32 i32 i32 i32) (result i32)))
+    (memory (export "memory") 1)
+
+    (func (export "open") (param $path i32) (param $len i32) (result i32)
+        (local $errno i32)
+        (local.set $errno
+            (call $path_open (i32.const 4) (i32.const 1) (local.get $path) (local.get $len)
+                (i32.const 0) (i64.const 2) (i64.const 0) (i32.const 0) (i32.const 0)))
+        (if (i32.eqz (local.get $errno))
+            (then (drop (call $fd_close (i32.load (i32.const 0))))))
+        (local.get $errno)
+    )
+
+    (func (export "unlink") (param $path i32) (param $len i32) (result i32)
+        (call $path_unlink_file (i32.const 4) (local.get $path) (local.get $len)))
+
+    (func (export "rename") (param $from i32) (param $from_len i32) (param $to i32) (param $to_len i32) (result i32)
+        (call $path_rename (i32.const 4) (local.get $from) (local.get $from_len)
+            (i32.const 4) (local.get $to) (local.get $to_len)))
+
+    (func (export "symlink") (param $target i32) (param $target_len i32) (param $link i32) (param $link_len i32) (result i32)
+        (call $path_symlink (local.get $target) (local.get $target_len)
+            (i32.const 4) (local.get $link) (local.get $link_len)))
+)
+"#;
+
+struct Guest {
+    store: Store,
+    instance: Instance,
+    env: WasiFunctionEnv,
+}
+
+impl Guest {
+    /// Starts the guest with `a/b/c/d/e/file` in its file system.
+    fn new() -> Self {
+        let fs = mem_fs::FileSystem::default();
+        for dir in ["/a", "/a/b", "/a/b/c", "/a/b/c/d", "/a/b/c/d/e"] {
+            fs.create_dir(Path::new(dir)).unwrap();
+        }
+        fs.new_open_options()
+            .create(true)
+            .write(true)
+            .open(Path::new("/").join(FILE))
+            .unwrap();
+
+        let mut store = Store::default();
+        let module = Module::new(&store, PROGRAM).unwrap();
+        let (instance, env) = WasiEnv::builder("paths")
+            .engine(store.engine().clone())
+            .fs(Box::new(fs))
+            .preopen_dir("/")
+            .unwrap()
+            .instantiate(module, &mut store)
+            .unwrap();
+
+        Guest {
+            store,
+            instance,
+            env,
+        }
+    }
+
+    /// Copies `strings` into memory at 1024, 2048, ... and calls `name` with
+    /// the address and length of each, returning the errno.
+    fn call(&mut self, name: &str, strings: &[&str]) -> i32 {
+        let memory = self.instance.exports.get_memory("memory").unwrap();
+        let mut params = Vec::new();
+        for (i, string) in strings.iter().enumerate() {
+            let ptr = 1024 * (i as u64 + 1);
+            memory
+                .view(&self.store)
+                .write(ptr, string.as_bytes())
+                .unwrap();
+            params.push(Value::I32(ptr as i32));
+            params.push(Value::I32(string.len() as i32));
+        }
+        let func = self.instance.exports.get_function(name).unwrap();
+        let ret = func.call(&mut self.store, &params).unwrap();
+        ret[0].unwrap_i32()
+    }
+
+    fn open(&mut self, path: &str) -> i32 {
+        self.call("open", &[path])
+    }
+
+    fn stats(&self) -> PathLookupStats {
+        self.env.data(&self.store).path_lookup_stats()
+    }
+}
+
+fn runtime() -> tokio::runtime::Runtime {
+    tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .unwrap()
+}
+
+#[test]
+fn repeated_opens_are_served_from_the_cache() {
+    let rt = runtime();
+    let _guard = rt.enter();
+    let mut guest = Guest::new();
+
+    // The first open loads the entries, the second caches them
+    assert_eq!(guest.open(FILE), Errno::Success as i32);
+    assert_eq!(guest.open(FILE), Errno::Success as i32);
+    let before = guest.stats();
+    assert!(before.fallbacks > 0, "{before:?}");
+    assert!(before.misses > 0, "{before:?}");
+
+    assert_eq!(guest.open(FILE), Errno::Success as i32);
+    let after = guest.stats();
+    assert_eq!(after.misses, before.misses, "{after:?}");
+    assert_eq!(after.fallbacks, before.fallbacks, "{after:?}");
+    assert!(after.hits >= before.hits + 6, "{after:?}");
+}
+
+#[test]
+fn unlink_invalidates_cached_lookups() {
+    let rt = runtime();
+    let _guard = rt.enter();
+    let mut guest = Guest::new();
+    assert_eq!(guest.open(FILE), Errno::Success as i32);
+    assert_eq!(guest.open(FILE), Errno::Success as i32);
+    let invalidations = guest.stats().invalidations;
+
+    assert_eq!(guest.call("unlink", &[FILE]), Errno::Success as i32);
+    assert!(guest.stats().invalidations > invalidations);
+    assert_eq!(guest.open(FILE), Errno::Noent as i32);
+}
+
+#[test]
+fn rename_invalidates_cached_lookups() {
+    let rt = runtime();
+    let _guard = rt.enter();
+    let mut guest = Guest::new();
+    assert_eq!(guest.open(FILE), Errno::Success as i32);
+    assert_eq!(guest.open(FILE), Errno::Success as i32);
+
+    assert_eq!(
+        guest.call("rename", &["a/b/c", "a/b/x"]),
+        Errno::Success as i32
+    );
+    assert_eq!(guest.open(FILE), Errno::Noent as i32);
+    assert_eq!(guest.open("a/b/x/d/e/file"), Errno::Success as i32);
+}
+
+#[test]
+fn symlinks_take_the_slow_path() {
+    let rt = runtime();
+    let _guard = rt.enter();
+    let mut guest = Guest::new();
+    assert_eq!(
+        guest.call("symlink", &["a/b", "a/link"]),
+        Errno::Success as i32
+    );
+
+    let path = "a/link/c/d/e/file";
+    assert_eq!(guest.open(path), Errno::Success as i32);
+    let before = guest.stats();
+    assert_eq!(guest.open(path), Errno::Success as i32);
+    let after = guest.stats();
+    assert!(after.fallbacks > before.fallbacks, "{after:?}");
+}
+
+#[test]
+fn symlink_loops_are_reported() {
+    let rt = runtime();
+    let _guard = rt.enter();
+    let mut guest = Guest::new();
+    assert_eq!(
+        guest.call("symlink", &["ping", "a/pong"]),
+        Errno::Success as i32
+    );
+    assert_eq!(
+        guest.call("symlink", &["pong", "a/ping"]),
+        Errno::Success as i32
+    );
+
+    assert_eq!(guest.open("a/ping"), Errno::Loop as i32);
+}
+
+#[test]
+fn rename_invalidates_both_directories() {
+    let rt = runtime();
+    let _guard = rt.enter();
+    let mut guest = Guest::new();
+    assert_eq!(guest.open(FILE), Errno::Success as i32);
+    assert_eq!(guest.open(FILE), Errno::Success as i32);
+    let invalidations = guest.stats().invalidations;
+
+    // Both `a/b/c/d/e` and `a/b` have cached entries
+    assert_eq!(
+        guest.call("rename", &[FILE, "a/b/file"]),
+        Errno::Success as i32
+    );
+    assert_eq!(guest.stats().invalidations, invalidations + 2);
+    assert_eq!(guest.open(FILE), Errno::Noent as i32);
+    assert_eq!(guest.open("a/b/file"), Errno::Success as i32);
+}
@@ -0,0 +1,175 @@
+#![cfg(not(target_family = "wasm"))]
+
+use std::{path::Path, sync::Arc};
+
+use virtual_fs::{AsyncReadExt, Pipe};
+use wasmer_wasix::{
+    os::{Console, ConsoleHost},
+    runtime::{
+        package_loader::BuiltinPackageLoader, resolver::FileSystemSource,
+        task_manager::tokio::TokioTaskManager,
+    },
+    wasmer_wasix_types::wasi::{Errno, Signal},
+    PluggableRuntime,
+};
+
+/// Installs a signal handler, says it is ready, and then waits for a signal
+/// before printing its number (as a little-endian `u32`).
+const RECEIVER: &str = r#"
+(module
+    (import "wasi_snapshot_preview1" "fd_write" (func $fd_write (param i32 i32 i32 i32) (result i32)))
+    (import "wasi_snapshot_preview1" "sched_yield" (func $sched_yield (result i32)))
+    (import "wasix_32v1" "callback_signal" (func $callback_signal (param i32 i32)))
+    (memory (export "memory") 1)
+    (data (i32.const 100) "on_signal")
+    (data (i32.const 120) "rdy\n")
+
+    (func $print (param $ptr i32) (param $len i32)
+        (i32.store (i32.const 16) (local.get $ptr))
+        (i32.store (i32.const 20) (local.get $len))
+        (drop (call $fd_write (i32.const 1) (i32.const 16) (i32.const 1) (i32.const 24)))
+    )
+
+    (func (export "on_signal") (param $sig i32)
+        (i32.store (i32.const 200) (local.get $sig)))
+
+    (func (export "_start")
+        (call $callback_signal (i32.const 100) (i32.const 9))
+        (call $print (i32.const 120) (i32.const 4))
+        (loop $wait
+            (drop (call $sched_yield))
+            (br_if $wait (i32.eqz (i32.load (i32.const 200)))))
+        (call $print (i32.const 200) (i32.const 4))
+    )
+)
+"#;
+
+/// Checks that `pid` exists, sends it SIGTERM, and then tries to signal a
+/// process that doesn't exist, printing each errno as a little-endian `u32`.
+fn sender(pid: u32) -> String {
+    format!(
+        r#"
+(module
+    (import "wasi_snapshot_preview1" "fd_write" (func $fd_write (param i32 i32 i32 i32) (result i32)))
+    (import "wasix_32v1" "proc_signal" (func $proc_signal (param i32 i32) (result i32)))
+    (memory (export "memory") 1)
+
+    (func (export "_start")
+        (i32.store (i32.const 200) (call $proc_signal (i32.const {pid}) (i32.const 0)))
+        (i32.store (i32.const 204) (call $proc_signal (i32.const {pid}) (i32.const 15)))
+        (i32.store (i32.const 208) (call $proc_signal (i32.const 9999) (i32.const 15)))
+
+        (i32.store (i32.const 16) (i32.const 200))
+        (i32.store (i32.const 20) (i32.const 12))
+        (drop (call $fd_write (i32.const 1) (i32.const 16) (i32.const 1) (i32.const 24)))
+    )
+)
+"#
+    )
+}
+
+fn build_package(dir: &Path, name: &str, wat: &str) -> String {
+    let pkg_dir = dir.join(name);
+    std::fs::create_dir(&pkg_dir).unwrap();
+    let wasmer_toml = format!(
+        r#"
+[package]
+name = "test/{name}"
+version = "0.1.0"
+
+[[module]]
+name = "{name}"
+source = "{name}.wasm"
+abi = "wasi"
+
+[[command]]
+name = "{name}"
+module = "{name}"
+"#
+    );
+    std::fs::write(pkg_dir.join("wasmer.toml"), wasmer_toml).unwrap();
+    std::fs::write(
+        pkg_dir.join(format!("{name}.wasm")),
+        wasmer::wat2wasm(wat.as_bytes()).unwrap(),
+    )
+    .unwrap();
+    let webc = wasmer_package::package::Package::from_manifest(pkg_dir.join("wasmer.toml"))
+        .unwrap()
+        .serialize()
+        .unwrap();
+
+    let webc_path = dir.join(format!("{name}.webc"));
+    std::fs::write(&webc_path, webc).unwrap();
+    webc_path.to_str().unwrap().to_string()
+}
+
+#[test]
+fn guest_sends_sigterm_to_another_guest() {
+    let temp = tempfile::tempdir().unwrap();
+
+    let tokio_rt = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+    let handle = tokio_rt.handle().clone();
+    let _guard = handle.enter();
+
+    let mut rt = PluggableRuntime::new(Arc::new(TokioTaskManager::new(tokio_rt)));
+    rt.set_source(FileSystemSource::default())
+        .set_package_loader(BuiltinPackageLoader::new());
+    let rt = Arc::new(rt);
+    let host = Arc::new(ConsoleHost::new(rt.clone(), &Default::default()));
+
+    let run = |webc: &str| {
+        let (stdout_tx, stdout_rx) = Pipe::channel();
+        let session = Console::new(webc, rt.clone())
+            .with_host(host.clone())
+            .with_no_welcome(true)
+            .new_session(
+                Box::new(Pipe::channel().1),
+                Box::new(stdout_tx),
+                Box::new(Pipe::channel().0),
+            )
+            .unwrap();
+        (session, stdout_rx)
+    };
+
+    let (mut receiver, mut receiver_stdout) =
+        run(&build_package(temp.path(), "receiver", RECEIVER));
+    let mut ready = [0u8; 4];
+    handle
+        .block_on(receiver_stdout.read_exact(&mut ready))
+        .unwrap();
+    assert_eq!(&ready, b"rdy\n");
+
+    let pid = receiver.process().pid().raw();
+    let (mut sender, mut sender_stdout) = run(&build_package(temp.path(), "sender", &sender(pid)));
+    let code = handle.block_on(sender.handle().wait_finished()).unwrap();
+    assert!(code.is_success());
+
+    let mut results = Vec::new();
+    handle
+        .block_on(sender_stdout.read_to_end(&mut results))
+        .unwrap();
+    let results: Vec<u32> = results
+        .chunks_exact(4)
+        .map(|chunk| u32::from_le_bytes(chunk.try_into().unwrap()))
+        .collect();
+    assert_eq!(
+        results,
+        [
+            Errno::Success as u32,
+            Errno::Success as u32,
+            Errno::Srch as u32
+        ]
+    );
+
+    // The receiver's handler ran with SIGTERM
+    let code = handle.block_on(receiver.handle().wait_finished()).unwrap();
+    assert!(code.is_success());
+    let mut received = Vec::new();
+    handle
+        .block_on(receiver_stdout.read_to_end(&mut received))
+        .unwrap();
+    assert_eq!(received, (Signal::Sigterm as u32).to_le_bytes());
+}
@@ -0,0 +1,118 @@
+#![cfg(not(target_family = "wasm"))]
+
+use std::sync::Arc;
+
+use wasmer_types::TrapCode;
+use wasmer_wasix::{
+    bin_factory::BinaryPackage,
+    runtime::{
+        package_loader::BuiltinPackageLoader, resolver::FileSystemSource,
+        task_manager::tokio::TokioTaskManager,
+    },
+    ExitIntent, PluggableRuntime, WasiEnvBuilder,
+};
+
+const PROGRAM: &str = r#"
+(module
+    (import "wasi_snapshot_preview1" "proc_exit" (func $proc_exit (param i32)))
+    (memory (export "memory") 1)
+
+    (func $inner
+        unreachable)
+
+    (func (export "_start")
+        (call $inner))
+)
+"#;
+
+fn build_package(dir: &std::path::Path) -> Vec<u8> {
+    let wasmer_toml = r#"
+[package]
+name = "test/trap"
+version = "0.1.0"
+
+[[module]]
+name = "trap"
+source = "trap.wasm"
+abi = "wasi"
+
+[[command]]
+name = "trap"
+module = "trap"
+"#;
+    std::fs::write(dir.join("wasmer.toml"), wasmer_toml).unwrap();
+    let wasm = wasmer::wat2wasm(PROGRAM.as_bytes()).unwrap();
+    std::fs::write(dir.join("trap.wasm"), wasm).unwrap();
+
+    wasmer_package::package::Package::from_manifest(dir.join("wasmer.toml"))
+        .unwrap()
+        .serialize()
+        .unwrap()
+        .to_vec()
+}
+
+#[test]
+fn trapping_command_reports_process_context() {
+    let temp = tempfile::tempdir().unwrap();
+    let webc = build_package(temp.path());
+
+    let tokio_rt = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+    let handle = tokio_rt.handle().clone();
+    let _guard = handle.enter();
+
+    let mut rt = PluggableRuntime::new(Arc::new(TokioTaskManager::new(tokio_rt)));
+    rt.set_source(FileSystemSource::default())
+        .set_package_loader(BuiltinPackageLoader::new());
+    let rt = Arc::new(rt);
+
+    let container = wasmer_package::utils::from_bytes(webc).unwrap();
+    let pkg = handle
+        .block_on(BinaryPackage::from_webc(&container, &*rt))
+        .unwrap();
+
+    let env = WasiEnvBuilder::new("trap")
+        .runtime(rt.clone())
+        .build()
+        .unwrap();
+    let (pid, tid) = (env.pid(), env.tid());
+    let bin_factory = env.bin_factory.clone();
+    bin_factory.set_binary("trap", pkg);
+
+    let err = handle
+        .block_on(async move {
+            let mut task = bin_factory.spawn("trap".to_string(), env).await.unwrap();
+            task.wait_finished().await
+        })
+        .unwrap_err();
+
+    let failure = err.process_failure().expect("the error has no context");
+    assert_eq!(failure.pid(), pid);
+    assert_eq!(failure.tid(), tid);
+    assert_eq!(failure.command(), Some("trap"));
+    assert_eq!(failure.package(), Some("test/trap"));
+    let hash = failure.package_hash().unwrap();
+    assert_eq!(hash.len(), 64, "{hash}");
+    assert!(hash.chars().all(|c| c.is_ascii_hexdigit()), "{hash}");
+    assert_eq!(
+        failure.intent(),
+        &ExitIntent::Trap(TrapCode::UnreachableCodeReached)
+    );
+    assert_eq!(failure.frames()[0].function_name(), Some("inner"));
+    assert_eq!(err.as_exit_code(), None);
+
+    let line = err.to_string();
+    assert!(!line.contains('\n'), "{line}");
+    assert!(
+        line.contains(&format!("process {pid} (thread {tid}")),
+        "{line}"
+    );
+    assert!(line.contains(r#"command "trap""#), "{line}");
+    assert!(line.contains("trapped"), "{line}");
+
+    let report = format!("{failure:#}");
+    assert!(report.contains("  command: trap"), "{report}");
+    assert!(report.contains("  backtrace:"), "{report}");
+}
@@ -0,0 +1,204 @@
+#![cfg(not(target_family = "wasm"))]
+
+use std::{
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+use tracing::{
+    field::{Field, Visit},
+    Subscriber,
+};
+use tracing_subscriber::{layer::Context, prelude::*, registry::LookupSpan, Layer};
+use virtual_fs::{mem_fs, AsyncReadExt, AsyncWriteExt, FileSystem};
+use wasmer::{Module, Store};
+use wasmer_wasix::{Pipe, WasiEnv, WasiEnvBuilder};
+
+/// Prints its environment variables separated by spaces, followed by a
+/// newline. Unless it has an argument, it then spawns itself with one
+/// (sharing its stdout) and waits for it.
+const PRINTENV: &str = r#"
+(module
+    (import "wasi_snapshot_preview1" "args_sizes_get" (func $args_sizes_get (param i32 i32) (result i32)))
+    (import "wasi_snapshot_preview1" "environ_sizes_get" (func $environ_sizes_get (param i32 i32) (result i32)))
+    (import "wasi_snapshot_preview1" "environ_get" (func $environ_get (param i32 i32) (result i32)))
+    (import "wasi_snapshot_preview1" "fd_write" (func $fd_write (param i32 i32 i32 i32) (result i32)))
+    (import "wasix_32v1" "proc_spawn" (func $proc_spawn (param i32 i32 i32 i32 i32 i32 i32 i32 i32 i32 i32 i32 i32) (result i32)))
+    (import "wasix_32v1" "proc_join" (func $proc_join (param i32 i32 i32) (result i32)))
+    (memory (export "memory") 1)
+    (data (i32.const 100) "/printenv\nchild")
+    (data (i32.const 160) ".")
+
+    (func $print_environ
+        (local $i i32)
+        (local $len i32)
+
+        (if (call $environ_sizes_get (i32.const 0) (i32.const 4))
+            (then unreachable))
+        (if (call $environ_get (i32.const 300) (i32.const 1000))
+            (then unreachable))
+
+        ;; Every variable ends with a NUL, which becomes a space or, for the
+        ;; last one, a newline
+        (local.set $len (i32.load (i32.const 4)))
+        (loop $chars
+            (if (i32.eqz (i32.load8_u (i32.add (i32.const 1000) (local.get $i))))
+                (then (i32.store8 (i32.add (i32.const 1000) (local.get $i)) (i32.const 32))))
+            (local.set $i (i32.add (local.get $i) (i32.const 1)))
+            (br_if $chars (i32.lt_u (local.get $i) (local.get $len))))
+        (i32.store8 (i32.add (i32.const 999) (local.get $len)) (i32.const 10))
+
+        (i32.store (i32.const 16) (i32.const 1000))
+        (i32.store (i32.const 20) (local.get $len))
+        (if (call $fd_write (i32.const 1) (i32.const 16) (i32.const 1) (i32.const 24))
+            (then unreachable))
+    )
+
+    (func (export "_start")
+        (call $print_environ)
+
+        (if (call $args_sizes_get (i32.const 8) (i32.const 12))
+            (then unreachable))
+        (if (i32.eq (i32.load (i32.const 8)) (i32.const 1))
+            (then
+                ;; stdin and stderr are null, stdout is inherited
+                (if (call $proc_spawn (i32.const 100) (i32.const 9) (i32.const 0)
+                        (i32.const 100) (i32.const 15) (i32.const 0) (i32.const 0)
+                        (i32.const 2) (i32.const 1) (i32.const 2)
+                        (i32.const 160) (i32.const 1) (i32.const 200))
+                    (then unreachable))
+
+                ;; Some(pid) at 240
+                (i32.store (i32.const 240) (i32.const 1))
+                (i32.store (i32.const 244) (i32.load (i32.const 200)))
+                (if (call $proc_join (i32.const 240) (i32.const 0) (i32.const 256))
+                    (then unreachable))))
+    )
+)
+"#;
+
+/// Exports `count()`, which returns how many environment variables it has.
+const COUNT: &str = r#"
+(module
+    (import "wasi_snapshot_preview1" "environ_sizes_get" (func $environ_sizes_get (param i32 i32) (result i32)))
+    (memory (export "memory") 1)
+    (func (export "count") (result i32)
+        (if (call $environ_sizes_get (i32.const 0) (i32.const 4))
+            (then unreachable))
+        (i32.load (i32.const 0)))
+)
+"#;
+
+/// The fields of every event, formatted with `Debug`.
+#[derive(Debug, Clone, Default)]
+struct Events(Arc<Mutex<Vec<String>>>);
+
+impl Visit for Events {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        self.0
+            .lock()
+            .unwrap()
+            .push(format!("{}={value:?}", field.name()));
+    }
+}
+
+impl<S> Layer<S> for Events
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_event(&self, event: &tracing::Event<'_>, _ctx: Context<'_, S>) {
+        event.record(&mut self.clone());
+    }
+}
+
+#[test]
+fn secrets_are_only_seen_by_the_initial_process() {
+    let events = Events::default();
+    tracing_subscriber::registry()
+        .with(events.clone())
+        .try_init()
+        .unwrap();
+
+    let tokio_rt = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+    let handle = tokio_rt.handle().clone();
+    let _guard = handle.enter();
+
+    handle.block_on(async {
+        let fs = mem_fs::FileSystem::default();
+        let mut f = fs
+            .new_open_options()
+            .create(true)
+            .write(true)
+            .open(Path::new("/printenv"))
+            .unwrap();
+        f.write_all(&wasmer::wat2wasm(PRINTENV.as_bytes()).unwrap())
+            .await
+            .unwrap();
+
+        let (stdout_tx, mut stdout_rx) = Pipe::channel();
+        let env = WasiEnvBuilder::new("/printenv")
+            .fs(Box::new(fs))
+            .env("PUBLIC", "1")
+            .secret_env("TOKEN", "hunter2")
+            .stdout(Box::new(stdout_tx))
+            .build()
+            .unwrap();
+        let bin_factory = env.bin_factory.clone();
+
+        let mut task = bin_factory
+            .spawn("/printenv".to_string(), env)
+            .await
+            .unwrap();
+        let exit_code = task.wait_finished().await.unwrap();
+        assert!(exit_code.is_success());
+
+        // The parent prints first, then waits for the child
+        let expected = "PUBLIC=1 TOKEN=hunter2\nPUBLIC=1\n";
+        let mut output = vec![0; expected.len()];
+        stdout_rx.read_exact(&mut output).await.unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), expected);
+    });
+
+    let events = events.0.lock().unwrap();
+    assert!(
+        events.iter().any(|e| e.contains("TOKEN=<redacted>")),
+        "{events:#?}"
+    );
+    assert!(!events.iter().any(|e| e.contains("hunter2")), "{events:#?}");
+}
+
+#[test]
+fn revoked_secrets_are_gone_from_the_environment() {
+    let tokio_rt = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+    let _guard = tokio_rt.enter();
+
+    let mut store = Store::default();
+    let module = Module::new(&store, COUNT).unwrap();
+    let (instance, env) = WasiEnv::builder("count")
+        .engine(store.engine().clone())
+        .env("PUBLIC", "1")
+        .secret_env("TOKEN", "hunter2")
+        .instantiate(module, &mut store)
+        .unwrap();
+    let func = instance.exports.get_function("count").unwrap();
+    let count = |store: &mut Store| func.call(store, &[]).unwrap()[0].unwrap_i32();
+
+    assert_eq!(count(&mut store), 2);
+
+    let secrets = env.data(&store).secret_env().clone();
+    assert!(secrets.revoke("TOKEN"));
+    assert_eq!(count(&mut store), 1);
+
+    // A secret can be granted to a process later on
+    let pid = env.data(&store).pid();
+    secrets.insert("LATE", "value");
+    assert_eq!(count(&mut store), 1);
+    assert!(secrets.grant("LATE", pid));
+    assert_eq!(count(&mut store), 2);
+}
@@ -0,0 +1,78 @@
+#![cfg(not(target_family = "wasm"))]
+
+use std::sync::Arc;
+
+use virtual_fs::AsyncReadExt;
+use virtual_mio::InlineWaker;
+use wasmer::Module;
+use wasmer_types::ModuleHash;
+use wasmer_wasix::{
+    runners::wasi::{RuntimeOrEngine, WasiRunner},
+    runtime::task_manager::tokio::TokioTaskManager,
+    Pipe, PluggableRuntime,
+};
+
+/// Creates `/dev/shm/buffer` and writes "hello" to it, then opens the object
+/// a second time and prints what reading it back returns.
+const PROGRAM: &str = r#"
+(module
+    (import "wasi_snapshot_preview1" "path_open" (func $path_open (param i32 i32 i32 i32 i32 i64 i64 i32 i32) (result i32)))
+    (import "wasi_snapshot_preview1" "fd_write" (func $fd_write (param i32 i32 i32 i32) (result i32)))
+    (import "wasi_snapshot_preview1" "fd_read" (func $fd_read (param i32 i32 i32 i32) (result i32)))
+    (memory (export "memory") 1)
+    (data (i32.const 100) "dev/shm/buffer")
+    (data (i32.const 130) "hello")
+
+    (func $open (param $oflags i32) (result i32)
+        (if (call $path_open (i32.const 3) (i32.const 0) (i32.const 100) (i32.const 14)
+                (local.get $oflags) (i64.const -1) (i64.const -1) (i32.const 0) (i32.const 0))
+            (then unreachable))
+        (i32.load (i32.const 0))
+    )
+
+    (func (export "_start")
+        ;; O_CREAT
+        (i32.store (i32.const 16) (i32.const 130))
+        (i32.store (i32.const 20) (i32.const 5))
+        (if (call $fd_write (call $open (i32.const 1)) (i32.const 16) (i32.const 1) (i32.const 8))
+            (then unreachable))
+
+        (i32.store (i32.const 16) (i32.const 1024))
+        (i32.store (i32.const 20) (i32.const 64))
+        (if (call $fd_read (call $open (i32.const 0)) (i32.const 16) (i32.const 1) (i32.const 8))
+            (then unreachable))
+        (i32.store (i32.const 20) (i32.load (i32.const 8)))
+        (if (call $fd_write (i32.const 1) (i32.const 16) (i32.const 1) (i32.const 8))
+            (then unreachable))
+    )
+)
+"#;
+
+#[test]
+fn dev_shm_objects_are_shared_between_handles() {
+    let tokio_rt = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+    let _guard = tokio_rt.enter();
+
+    let engine = wasmer::Engine::default();
+    let module = Module::new(&engine, PROGRAM).unwrap();
+    let mut rt = PluggableRuntime::new(Arc::new(TokioTaskManager::new(tokio_rt.handle().clone())));
+    rt.set_engine(engine);
+
+    let (stdout_tx, mut stdout_rx) = Pipe::channel();
+    WasiRunner::new()
+        .with_stdout(Box::new(stdout_tx))
+        .run_wasm(
+            RuntimeOrEngine::Runtime(Arc::new(rt)),
+            "shm",
+            module,
+            ModuleHash::random(),
+        )
+        .unwrap();
+
+    let mut stdout = Vec::new();
+    InlineWaker::block_on(stdout_rx.read_to_end(&mut stdout)).unwrap();
+    assert_eq!(String::from_utf8_lossy(&stdout), "hello");
+}
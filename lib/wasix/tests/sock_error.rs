@@ -0,0 +1,116 @@
+#![cfg(all(feature = "host-vnet", not(target_family = "wasm")))]
+
+use std::{net::TcpListener, sync::Arc};
+
+use virtual_fs::AsyncReadExt;
+use virtual_mio::InlineWaker;
+use wasmer::Module;
+use wasmer_types::ModuleHash;
+use wasmer_wasix::{
+    runners::wasi::{RuntimeOrEngine, WasiRunner},
+    runtime::task_manager::tokio::TokioTaskManager,
+    wasmer_wasix_types::wasi::{Errno, SockProto},
+    Pipe, PluggableRuntime,
+};
+
+/// A program that does a nonblocking connect to `127.0.0.1:{port}`, polls
+/// the socket until it is writable, and then prints `SO_ERROR` twice,
+/// followed by `SO_PROTOCOL` (each as a little-endian `u64`).
+fn program(port: u16) -> String {
+    format!(
+        r#"
+(module
+    (import "wasi_snapshot_preview1" "fd_write" (func $fd_write (param i32 i32 i32 i32) (result i32)))
+    (import "wasi_snapshot_preview1" "fd_fdstat_set_flags" (func $fd_fdstat_set_flags (param i32 i32) (result i32)))
+    (import "wasi_snapshot_preview1" "poll_oneoff" (func $poll_oneoff (param i32 i32 i32 i32) (result i32)))
+    (import "wasix_32v1" "sock_open" (func $sock_open (param i32 i32 i32 i32) (result i32)))
+    (import "wasix_32v1" "sock_connect" (func $sock_connect (param i32 i32) (result i32)))
+    (import "wasix_32v1" "sock_get_opt_size" (func $sock_get_opt_size (param i32 i32 i32) (result i32)))
+    (memory (export "memory") 1)
+
+    (func (export "_start")
+        ;; An IPv4 stream socket with the default protocol
+        (if (call $sock_open (i32.const 1) (i32.const 1) (i32.const 0) (i32.const 0))
+            (then unreachable))
+        ;; O_NONBLOCK
+        (if (call $fd_fdstat_set_flags (i32.load (i32.const 0)) (i32.const 4))
+            (then unreachable))
+
+        ;; Connect to 127.0.0.1:{port}
+        (i32.store8 (i32.const 16) (i32.const 1))
+        (i32.store16 (i32.const 18) (i32.const {port}))
+        (i32.store (i32.const 20) (i32.const 0x0100007f))
+        (if (call $sock_connect (i32.load (i32.const 0)) (i32.const 16))
+            (then unreachable))
+
+        ;; Wait until the socket is writable
+        (i32.store8 (i32.const 72) (i32.const 2))
+        (i32.store (i32.const 80) (i32.load (i32.const 0)))
+        (loop $wait
+            (if (call $poll_oneoff (i32.const 64) (i32.const 128) (i32.const 1) (i32.const 192))
+                (then unreachable))
+            (br_if $wait (i32.eqz (i32.load (i32.const 192)))))
+        (if (i32.load16_u (i32.const 136))
+            (then unreachable))
+
+        ;; SO_ERROR, twice, and SO_PROTOCOL
+        (if (call $sock_get_opt_size (i32.load (i32.const 0)) (i32.const 11) (i32.const 200))
+            (then unreachable))
+        (if (call $sock_get_opt_size (i32.load (i32.const 0)) (i32.const 11) (i32.const 208))
+            (then unreachable))
+        (if (call $sock_get_opt_size (i32.load (i32.const 0)) (i32.const 26) (i32.const 216))
+            (then unreachable))
+
+        (i32.store (i32.const 240) (i32.const 200))
+        (i32.store (i32.const 244) (i32.const 24))
+        (drop (call $fd_write (i32.const 1) (i32.const 240) (i32.const 1) (i32.const 256)))
+    )
+)
+"#
+    )
+}
+
+#[test]
+fn nonblocking_connect_reports_so_error() {
+    // Find a port that nobody listens on
+    let port = {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        listener.local_addr().unwrap().port()
+    };
+
+    let tokio_rt = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+    let handle = tokio_rt.handle().clone();
+    let _guard = handle.enter();
+
+    let engine = wasmer::Engine::default();
+    let module = Module::new(&engine, program(port)).unwrap();
+    let mut rt = PluggableRuntime::new(Arc::new(TokioTaskManager::new(tokio_rt)));
+    rt.set_engine(engine);
+
+    let (stdout_tx, mut stdout_rx) = Pipe::channel();
+    WasiRunner::new()
+        .with_stdout(Box::new(stdout_tx))
+        .run_wasm(
+            RuntimeOrEngine::Runtime(Arc::new(rt)),
+            "sock-error",
+            module,
+            ModuleHash::random(),
+        )
+        .unwrap();
+
+    let mut stdout = Vec::new();
+    InlineWaker::block_on(stdout_rx.read_to_end(&mut stdout)).unwrap();
+    let values: Vec<u64> = stdout
+        .chunks_exact(8)
+        .map(|chunk| u64::from_le_bytes(chunk.try_into().unwrap()))
+        .collect();
+
+    assert_eq!(
+        values,
+        [Errno::Connrefused as u64, 0, SockProto::Tcp as u64],
+        "SO_ERROR should be reported once, and cleared on read"
+    );
+}
@@ -0,0 +1,108 @@
+#![cfg(not(target_family = "wasm"))]
+
+use std::{sync::Arc, time::Duration};
+
+use virtual_fs::{AsyncReadExt, Pipe};
+use virtual_net::LoopbackNetworking;
+use wasmer::{Module, Store};
+use wasmer_wasix::{
+    runtime::task_manager::tokio::TokioTaskManager,
+    wasmer_wasix_types::wasi::{Errno, Signal},
+    PluggableRuntime, WasiEnvBuilder,
+};
+
+/// Installs a signal handler, says it is ready and then blocks accepting a
+/// connection that never comes. Afterwards it prints the errno `sock_accept`
+/// returned and the signal the handler saw (both as little-endian `u32`s).
+const PROGRAM: &str = r#"
+(module
+    (import "wasi_snapshot_preview1" "fd_write" (func $fd_write (param i32 i32 i32 i32) (result i32)))
+    (import "wasix_32v1" "callback_signal" (func $callback_signal (param i32 i32)))
+    (import "wasix_32v1" "sock_open" (func $sock_open (param i32 i32 i32 i32) (result i32)))
+    (import "wasix_32v1" "sock_bind" (func $sock_bind (param i32 i32) (result i32)))
+    (import "wasix_32v1" "sock_listen" (func $sock_listen (param i32 i32) (result i32)))
+    (import "wasix_32v1" "sock_accept_v2" (func $sock_accept (param i32 i32 i32 i32) (result i32)))
+    (memory (export "memory") 1)
+    (data (i32.const 100) "on_signal")
+    (data (i32.const 120) "rdy\n")
+
+    (func $print (param $ptr i32) (param $len i32)
+        (i32.store (i32.const 32) (local.get $ptr))
+        (i32.store (i32.const 36) (local.get $len))
+        (drop (call $fd_write (i32.const 1) (i32.const 32) (i32.const 1) (i32.const 40)))
+    )
+
+    (func (export "on_signal") (param $sig i32)
+        (i32.store (i32.const 204) (local.get $sig)))
+
+    (func (export "_start")
+        (call $callback_signal (i32.const 100) (i32.const 9))
+
+        ;; Listen on 127.0.0.1:8080
+        (if (call $sock_open (i32.const 1) (i32.const 1) (i32.const 0) (i32.const 0))
+            (then unreachable))
+        (i32.store8 (i32.const 16) (i32.const 1))
+        (i32.store16 (i32.const 18) (i32.const 8080))
+        (i32.store (i32.const 20) (i32.const 0x0100007f))
+        (if (call $sock_bind (i32.load (i32.const 0)) (i32.const 16))
+            (then unreachable))
+        (if (call $sock_listen (i32.load (i32.const 0)) (i32.const 1))
+            (then unreachable))
+
+        (call $print (i32.const 120) (i32.const 4))
+        (i32.store (i32.const 200)
+            (call $sock_accept (i32.load (i32.const 0)) (i32.const 0) (i32.const 4) (i32.const 64)))
+        (call $print (i32.const 200) (i32.const 8))
+    )
+)
+"#;
+
+#[test]
+fn signals_interrupt_blocked_socket_syscalls() {
+    let tokio_rt = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+    let handle = tokio_rt.handle().clone();
+    let _guard = handle.enter();
+
+    let engine = wasmer::Engine::default();
+    let module = Module::new(&engine, PROGRAM).unwrap();
+    let mut rt = PluggableRuntime::new(Arc::new(TokioTaskManager::new(tokio_rt)));
+    rt.set_engine(engine.clone())
+        .set_networking_implementation(LoopbackNetworking::new());
+
+    let (stdout_tx, mut stdout_rx) = Pipe::channel();
+    let mut store = Store::new(engine);
+    let (instance, env) = WasiEnvBuilder::new("sock-interrupt")
+        .runtime(Arc::new(rt))
+        .stdout(Box::new(stdout_tx))
+        .instantiate(module, &mut store)
+        .unwrap();
+    let process = env.data(&store).process.clone();
+
+    let guest = std::thread::spawn(move || {
+        let start = instance.exports.get_function("_start").unwrap();
+        start.call(&mut store, &[]).unwrap();
+    });
+
+    let mut ready = [0u8; 4];
+    handle.block_on(stdout_rx.read_exact(&mut ready)).unwrap();
+    assert_eq!(&ready, b"rdy\n");
+
+    // A signal that arrives before the guest blocks is handled without
+    // interrupting anything, so keep sending them until one lands
+    while !guest.is_finished() {
+        process.signal_process(Signal::Sigint);
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    guest.join().unwrap();
+
+    let mut results = [0u8; 8];
+    handle.block_on(stdout_rx.read_exact(&mut results)).unwrap();
+    let results: Vec<u32> = results
+        .chunks_exact(4)
+        .map(|chunk| u32::from_le_bytes(chunk.try_into().unwrap()))
+        .collect();
+    assert_eq!(results, [Errno::Intr as u32, Signal::Sigint as u32]);
+}
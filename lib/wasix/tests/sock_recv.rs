@@ -0,0 +1,153 @@
+#![cfg(not(target_family = "wasm"))]
+
+use std::{net::SocketAddr, sync::Arc};
+
+use virtual_fs::{AsyncReadExt, AsyncWriteExt, Pipe};
+use virtual_mio::InlineWaker;
+use virtual_net::LoopbackNetworking;
+use wasmer::Module;
+use wasmer_types::ModuleHash;
+use wasmer_wasix::{
+    runners::wasi::{RuntimeOrEngine, WasiRunner},
+    runtime::task_manager::tokio::TokioTaskManager,
+    PluggableRuntime,
+};
+
+/// How many times the guest calls `sock_recv`.
+const ROUNDS: usize = 4;
+
+/// Listens on `127.0.0.1:8080` and accepts a single connection, then
+/// receives from it `ROUNDS` times into three 4-byte iovecs at 1024, 1032 and
+/// 1040. Every time, the 24 bytes from 1024 are filled with 0xaa beforehand
+/// and printed afterwards, after the number of bytes received (as a
+/// little-endian `u32`).
+const PROGRAM: &str = r#"
+(module
+    (import "wasi_snapshot_preview1" "fd_write" (func $fd_write (param i32 i32 i32 i32) (result i32)))
+    (import "wasix_32v1" "sock_open" (func $sock_open (param i32 i32 i32 i32) (result i32)))
+    (import "wasix_32v1" "sock_bind" (func $sock_bind (param i32 i32) (result i32)))
+    (import "wasix_32v1" "sock_listen" (func $sock_listen (param i32 i32) (result i32)))
+    (import "wasix_32v1" "sock_accept_v2" (func $sock_accept (param i32 i32 i32 i32) (result i32)))
+    (import "wasix_32v1" "sock_recv" (func $sock_recv (param i32 i32 i32 i32 i32 i32) (result i32)))
+    (memory (export "memory") 1)
+
+    (func (export "_start")
+        (local $round i32)
+
+        ;; Listen on 127.0.0.1:8080
+        (if (call $sock_open (i32.const 1) (i32.const 1) (i32.const 0) (i32.const 0))
+            (then unreachable))
+        (i32.store8 (i32.const 16) (i32.const 1))
+        (i32.store16 (i32.const 18) (i32.const 8080))
+        (i32.store (i32.const 20) (i32.const 0x0100007f))
+        (if (call $sock_bind (i32.load (i32.const 0)) (i32.const 16))
+            (then unreachable))
+        (if (call $sock_listen (i32.load (i32.const 0)) (i32.const 1))
+            (then unreachable))
+        (if (call $sock_accept (i32.load (i32.const 0)) (i32.const 0) (i32.const 4) (i32.const 64))
+            (then unreachable))
+
+        (i32.store (i32.const 128) (i32.const 1024))
+        (i32.store (i32.const 132) (i32.const 4))
+        (i32.store (i32.const 136) (i32.const 1032))
+        (i32.store (i32.const 140) (i32.const 4))
+        (i32.store (i32.const 144) (i32.const 1040))
+        (i32.store (i32.const 148) (i32.const 4))
+
+        ;; The output is a single iovec from 1020 to 1048
+        (i32.store (i32.const 160) (i32.const 1020))
+        (i32.store (i32.const 164) (i32.const 28))
+
+        (loop $rounds
+            (memory.fill (i32.const 1024) (i32.const 0xaa) (i32.const 24))
+            (if (call $sock_recv (i32.load (i32.const 4)) (i32.const 128) (i32.const 3) (i32.const 0)
+                    (i32.const 1020) (i32.const 168))
+                (then unreachable))
+            (if (call $fd_write (i32.const 1) (i32.const 160) (i32.const 1) (i32.const 172))
+                (then unreachable))
+
+            (local.set $round (i32.add (local.get $round) (i32.const 1)))
+            (br_if $rounds (i32.lt_u (local.get $round) (i32.const 4))))
+    )
+)
+"#;
+
+#[test]
+fn sock_recv_scatters_over_iovecs() {
+    let tokio_rt = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+    let handle = tokio_rt.handle().clone();
+    let _guard = handle.enter();
+
+    let engine = wasmer::Engine::default();
+    let module = Module::new(&engine, PROGRAM).unwrap();
+    let mut rt = PluggableRuntime::new(Arc::new(TokioTaskManager::new(tokio_rt)));
+    let (listeners_tx, listeners_rx) = std::sync::mpsc::channel();
+    rt.set_engine(engine)
+        .set_networking_implementation(LoopbackNetworking::new())
+        .set_guest_listener_callback(move |addr, listener| {
+            listeners_tx.send((addr, listener)).unwrap();
+        });
+
+    let (stdout_tx, mut stdout_rx) = Pipe::channel();
+    let guest = std::thread::spawn(move || {
+        let mut runner = WasiRunner::new();
+        runner.capabilities_mut().listen.exposed_ports.push(8080);
+        runner.with_stdout(Box::new(stdout_tx));
+        runner.run_wasm(
+            RuntimeOrEngine::Runtime(Arc::new(rt)),
+            "recv",
+            module,
+            ModuleHash::random(),
+        )
+    });
+
+    let (addr, listener) = listeners_rx.recv().unwrap();
+    assert_eq!(addr, "127.0.0.1:8080".parse::<SocketAddr>().unwrap());
+    let peer: SocketAddr = "10.0.0.1:50000".parse().unwrap();
+    let (mut tx, _rx) = listener.connect(peer).unwrap().split();
+
+    // Each message is only sent once the guest received the previous one,
+    // so every round sees exactly one of them
+    let rounds: [(&[u8], usize, [u8; 24]); ROUNDS] = [
+        // Fits in the first iovec
+        (
+            b"abc",
+            3,
+            *b"abc\xaa\xaa\xaa\xaa\xaa\xaa\xaa\xaa\xaa\xaa\xaa\xaa\xaa\xaa\xaa\xaa\xaa\xaa\xaa\xaa\xaa",
+        ),
+        // Spans the first two
+        (
+            b"defghij",
+            7,
+            *b"defg\xaa\xaa\xaa\xaahij\xaa\xaa\xaa\xaa\xaa\xaa\xaa\xaa\xaa\xaa\xaa\xaa\xaa",
+        ),
+        // Doesn't fit, so the rest is left for the next call
+        (
+            b"0123456789ABCDEF",
+            12,
+            *b"0123\xaa\xaa\xaa\xaa4567\xaa\xaa\xaa\xaa89AB\xaa\xaa\xaa\xaa",
+        ),
+        (
+            b"",
+            4,
+            *b"CDEF\xaa\xaa\xaa\xaa\xaa\xaa\xaa\xaa\xaa\xaa\xaa\xaa\xaa\xaa\xaa\xaa\xaa\xaa\xaa\xaa",
+        ),
+    ];
+
+    for (message, expected_len, expected) in rounds {
+        let mut output = [0u8; 28];
+        InlineWaker::block_on(async {
+            tx.write_all(message).await.unwrap();
+            stdout_rx.read_exact(&mut output).await.unwrap();
+        });
+
+        let nread = u32::from_le_bytes(output[..4].try_into().unwrap());
+        assert_eq!(nread as usize, expected_len);
+        assert_eq!(output[4..], expected, "after receiving {nread} bytes");
+    }
+
+    guest.join().unwrap().unwrap();
+}
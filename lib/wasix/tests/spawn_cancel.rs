@@ -0,0 +1,239 @@
+#![cfg(not(target_family = "wasm"))]
+
+use std::{
+    path::Path,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use futures::future::BoxFuture;
+use tempfile::TempDir;
+use wasmer::{Engine, Module};
+use wasmer_types::ModuleHash;
+use wasmer_wasix::{
+    bin_factory::{spawn_exec_cancellable, BinaryPackage, SpawnCancellation},
+    http::{HttpClient, HttpRequest, HttpResponse},
+    os::task::control_plane::{ControlPlaneConfig, WasiControlPlane},
+    runtime::{
+        module_cache::{CacheError, ModuleCache},
+        package_loader::BuiltinPackageLoader,
+        resolver::{InMemorySource, PackageSummary},
+        task_manager::tokio::TokioTaskManager,
+    },
+    wasmer_wasix_types::wasi::{Errno, ExitCode},
+    PluggableRuntime, Runtime, SpawnError, WasiEnvBuilder,
+};
+
+/// A registry that takes forever to respond.
+#[derive(Debug, Default)]
+struct SlowClient {
+    /// Set when the download is dropped.
+    aborted: Arc<AtomicBool>,
+}
+
+impl HttpClient for SlowClient {
+    fn request(&self, _request: HttpRequest) -> BoxFuture<'_, Result<HttpResponse, anyhow::Error>> {
+        let on_drop = SetOnDrop(self.aborted.clone());
+        Box::pin(async move {
+            let _on_drop = on_drop;
+            tokio::time::sleep(Duration::from_secs(600)).await;
+            anyhow::bail!("the download finished")
+        })
+    }
+}
+
+/// A module cache that takes a while to miss, as if compiling took that
+/// long.
+#[derive(Debug)]
+struct SlowCompiler {
+    latency: Duration,
+}
+
+#[async_trait::async_trait]
+impl ModuleCache for SlowCompiler {
+    async fn load(&self, _key: ModuleHash, _engine: &Engine) -> Result<Module, CacheError> {
+        // Block the thread, like the compiler does
+        std::thread::sleep(self.latency);
+        Err(CacheError::NotFound)
+    }
+
+    async fn contains(&self, _key: ModuleHash, _engine: &Engine) -> Result<bool, CacheError> {
+        Ok(false)
+    }
+
+    async fn save(
+        &self,
+        _key: ModuleHash,
+        _engine: &Engine,
+        _module: &Module,
+    ) -> Result<(), CacheError> {
+        Ok(())
+    }
+}
+
+struct SetOnDrop(Arc<AtomicBool>);
+
+impl Drop for SetOnDrop {
+    fn drop(&mut self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+}
+
+/// Builds a `test/slow` package with a command that returns straight away.
+fn build_package(dir: &Path) -> std::path::PathBuf {
+    let pkg_dir = dir.join("slow");
+    std::fs::create_dir(&pkg_dir).unwrap();
+    std::fs::write(
+        pkg_dir.join("wasmer.toml"),
+        r#"
+[package]
+name = "test/slow"
+version = "0.1.0"
+
+[[module]]
+name = "slow"
+source = "slow.wasm"
+abi = "wasi"
+
+[[command]]
+name = "slow"
+module = "slow"
+"#,
+    )
+    .unwrap();
+    let wasm = wasmer::wat2wasm(br#"(module (func (export "_start")))"#).unwrap();
+    std::fs::write(pkg_dir.join("slow.wasm"), wasm).unwrap();
+
+    let webc = dir.join("slow.webc");
+    let bytes = wasmer_package::package::Package::from_manifest(pkg_dir.join("wasmer.toml"))
+        .unwrap()
+        .serialize()
+        .unwrap();
+    std::fs::write(&webc, bytes).unwrap();
+    webc
+}
+
+fn tokio_runtime() -> tokio::runtime::Runtime {
+    tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .unwrap()
+}
+
+/// Cancels `cancel` after `delay`, from another thread.
+fn cancel_after(cancel: &SpawnCancellation, delay: Duration) {
+    let cancel = cancel.clone();
+    std::thread::spawn(move || {
+        std::thread::sleep(delay);
+        cancel.cancel();
+    });
+}
+
+/// Every file below `dir`.
+fn files(dir: &Path) -> Vec<std::path::PathBuf> {
+    let mut found = Vec::new();
+    for entry in std::fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.is_dir() {
+            found.extend(files(&path));
+        } else {
+            found.push(path);
+        }
+    }
+    found
+}
+
+#[test]
+fn cancelling_aborts_the_download() {
+    let tokio_rt = tokio_runtime();
+    let handle = tokio_rt.handle().clone();
+    let _guard = handle.enter();
+    let temp = TempDir::new().unwrap();
+    let cache_dir = temp.path().join("cache");
+    std::fs::create_dir(&cache_dir).unwrap();
+
+    // Published to a registry rather than as a local file, so the loader
+    // has to download it
+    let mut summary = PackageSummary::from_webc_file(build_package(temp.path())).unwrap();
+    summary.dist.webc = "https://registry.example/test/slow.webc".parse().unwrap();
+    let mut source = InMemorySource::new();
+    source.add(summary);
+    let client = SlowClient::default();
+    let aborted = client.aborted.clone();
+    let mut rt = PluggableRuntime::new(Arc::new(TokioTaskManager::new(tokio_rt)));
+    rt.set_source(source).set_package_loader(
+        BuiltinPackageLoader::new()
+            .with_cache_dir(&cache_dir)
+            .with_http_client(client),
+    );
+
+    let cancel = SpawnCancellation::new();
+    cancel_after(&cancel, Duration::from_millis(100));
+    let start = Instant::now();
+    let error = handle
+        .block_on(BinaryPackage::from_registry_cancellable(
+            &"test/slow".parse().unwrap(),
+            &rt,
+            &cancel,
+        ))
+        .unwrap_err();
+
+    assert!(
+        matches!(
+            error.downcast_ref::<SpawnError>(),
+            Some(SpawnError::Cancelled)
+        ),
+        "{error:?}"
+    );
+    assert!(start.elapsed() < Duration::from_secs(5));
+    assert!(aborted.load(Ordering::SeqCst));
+    assert_eq!(files(&cache_dir), Vec::<std::path::PathBuf>::new());
+}
+
+#[test]
+fn cancelling_abandons_the_compilation() {
+    let tokio_rt = tokio_runtime();
+    let handle = tokio_rt.handle().clone();
+    let _guard = handle.enter();
+    let temp = TempDir::new().unwrap();
+    let webc = build_package(temp.path());
+
+    let mut rt = PluggableRuntime::new(Arc::new(TokioTaskManager::new(tokio_rt)));
+    rt.set_module_cache(SlowCompiler {
+        latency: Duration::from_secs(3),
+    });
+    let rt: Arc<dyn Runtime + Send + Sync> = Arc::new(rt);
+    let container = wasmer_package::utils::from_disk(&webc).unwrap();
+    let pkg = handle
+        .block_on(BinaryPackage::from_webc(&container, &*rt))
+        .unwrap();
+
+    let plane = WasiControlPlane::new(ControlPlaneConfig::new());
+    let env = WasiEnvBuilder::new("slow")
+        .runtime(rt.clone())
+        .control_plane(plane.clone())
+        .build()
+        .unwrap();
+    let process = env.process.clone();
+
+    let cancel = SpawnCancellation::new();
+    cancel_after(&cancel, Duration::from_millis(100));
+    let start = Instant::now();
+    let error = handle
+        .block_on(spawn_exec_cancellable(pkg, "slow", env, &rt, &cancel))
+        .unwrap_err();
+
+    assert!(matches!(error, SpawnError::Cancelled), "{error:?}");
+    // Long before the compiler would have finished
+    assert!(start.elapsed() < Duration::from_secs(2));
+    assert_eq!(
+        process.try_join().unwrap().unwrap(),
+        ExitCode::from(Errno::Canceled)
+    );
+    for process in plane.processes() {
+        assert!(process.try_join().is_some(), "{} leaked", process.pid());
+    }
+}
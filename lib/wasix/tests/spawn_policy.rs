@@ -0,0 +1,171 @@
+#![cfg(not(target_family = "wasm"))]
+
+use std::{
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+use virtual_fs::{mem_fs, AsyncReadExt, AsyncWriteExt, FileSystem};
+use wasmer_wasix::{
+    bin_factory::{SpawnDecision, SpawnRequest},
+    runtime::task_manager::tokio::TokioTaskManager,
+    wasmer_wasix_types::wasi::Errno,
+    Pipe, PluggableRuntime, WasiEnvBuilder,
+};
+
+/// Spawns `/forbidden` (which has to fail with `EACCES`), then `/greet` and
+/// `/echo` with a single argument each, waiting for them in turn. The
+/// children share its stdout.
+const SHELL: &str = r#"
+(module
+    (import "wasix_32v1" "proc_spawn" (func $proc_spawn (param i32 i32 i32 i32 i32 i32 i32 i32 i32 i32 i32 i32 i32) (result i32)))
+    (import "wasix_32v1" "proc_join" (func $proc_join (param i32 i32 i32) (result i32)))
+    (memory (export "memory") 1)
+    (data (i32.const 100) "/forbidden")
+    (data (i32.const 120) "/greet\nplease")
+    (data (i32.const 140) "/echo\nallowed")
+    (data (i32.const 160) ".")
+
+    (func $spawn (param $args i32) (param $args_len i32) (param $name_len i32) (result i32)
+        ;; stdin and stderr are null, stdout is inherited
+        (call $proc_spawn (local.get $args) (local.get $name_len) (i32.const 0)
+            (local.get $args) (local.get $args_len) (i32.const 0) (i32.const 0)
+            (i32.const 2) (i32.const 1) (i32.const 2)
+            (i32.const 160) (i32.const 1) (i32.const 200))
+    )
+
+    (func $join
+        ;; Some(pid) at 240
+        (i32.store (i32.const 240) (i32.const 1))
+        (i32.store (i32.const 244) (i32.load (i32.const 200)))
+        (if (call $proc_join (i32.const 240) (i32.const 0) (i32.const 256))
+            (then unreachable))
+    )
+
+    (func (export "_start")
+        (if (i32.ne (call $spawn (i32.const 100) (i32.const 10) (i32.const 10)) (i32.const 2))
+            (then unreachable))
+
+        (if (call $spawn (i32.const 120) (i32.const 13) (i32.const 6))
+            (then unreachable))
+        (call $join)
+
+        (if (call $spawn (i32.const 140) (i32.const 13) (i32.const 5))
+            (then unreachable))
+        (call $join)
+    )
+)
+"#;
+
+/// Prints its arguments separated by spaces, followed by a newline.
+const ECHO: &str = r#"
+(module
+    (import "wasi_snapshot_preview1" "args_sizes_get" (func $args_sizes_get (param i32 i32) (result i32)))
+    (import "wasi_snapshot_preview1" "args_get" (func $args_get (param i32 i32) (result i32)))
+    (import "wasi_snapshot_preview1" "fd_write" (func $fd_write (param i32 i32 i32 i32) (result i32)))
+    (memory (export "memory") 1)
+
+    (func (export "_start")
+        (local $i i32)
+        (local $len i32)
+
+        (if (call $args_sizes_get (i32.const 0) (i32.const 4))
+            (then unreachable))
+        (if (call $args_get (i32.const 100) (i32.const 1000))
+            (then unreachable))
+
+        ;; Every argument ends with a NUL, which becomes a space or, for the
+        ;; last one, a newline
+        (local.set $len (i32.load (i32.const 4)))
+        (loop $chars
+            (if (i32.eqz (i32.load8_u (i32.add (i32.const 1000) (local.get $i))))
+                (then (i32.store8 (i32.add (i32.const 1000) (local.get $i)) (i32.const 32))))
+            (local.set $i (i32.add (local.get $i) (i32.const 1)))
+            (br_if $chars (i32.lt_u (local.get $i) (local.get $len))))
+        (i32.store8 (i32.add (i32.const 999) (local.get $len)) (i32.const 10))
+
+        (i32.store (i32.const 16) (i32.const 1000))
+        (i32.store (i32.const 20) (local.get $len))
+        (if (call $fd_write (i32.const 1) (i32.const 16) (i32.const 1) (i32.const 24))
+            (then unreachable))
+    )
+)
+"#;
+
+async fn write_file(fs: &mem_fs::FileSystem, path: &str, contents: &[u8]) {
+    let mut f = fs
+        .new_open_options()
+        .create(true)
+        .write(true)
+        .open(Path::new(path))
+        .unwrap();
+    f.write_all(contents).await.unwrap();
+}
+
+#[test]
+fn spawn_policy_can_deny_and_rewrite() {
+    let tokio_rt = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+    let handle = tokio_rt.handle().clone();
+    let _guard = handle.enter();
+
+    let requests = Arc::new(Mutex::new(Vec::new()));
+    let mut rt = PluggableRuntime::new(Arc::new(TokioTaskManager::new(tokio_rt)));
+    rt.set_spawn_policy({
+        let requests = requests.clone();
+        move |req: &SpawnRequest| {
+            requests.lock().unwrap().push(req.clone());
+            match req.program.as_str() {
+                "/forbidden" => SpawnDecision::Deny(Errno::Access),
+                "/greet" => SpawnDecision::Rewrite(SpawnRequest {
+                    program: "/echo".to_string(),
+                    args: vec!["greet".to_string(), "rewritten".to_string()],
+                    ..req.clone()
+                }),
+                _ => SpawnDecision::Allow,
+            }
+        }
+    });
+    let rt = Arc::new(rt);
+
+    handle.block_on(async {
+        let fs = mem_fs::FileSystem::default();
+        write_file(&fs, "/shell", &wasmer::wat2wasm(SHELL.as_bytes()).unwrap()).await;
+        write_file(&fs, "/echo", &wasmer::wat2wasm(ECHO.as_bytes()).unwrap()).await;
+        // Never run, but it has to exist for the denial to be the policy's
+        write_file(
+            &fs,
+            "/forbidden",
+            &wasmer::wat2wasm(ECHO.as_bytes()).unwrap(),
+        )
+        .await;
+
+        let (stdout_tx, mut stdout_rx) = Pipe::channel();
+        let env = WasiEnvBuilder::new("/shell")
+            .runtime(rt)
+            .fs(Box::new(fs))
+            .stdout(Box::new(stdout_tx))
+            .build()
+            .unwrap();
+        let shell = env.process.pid();
+        let bin_factory = env.bin_factory.clone();
+
+        // The policy isn't asked about the program the embedder starts
+        let mut task = bin_factory.spawn("/shell".to_string(), env).await.unwrap();
+        let exit_code = task.wait_finished().await.unwrap();
+        assert!(exit_code.is_success());
+
+        let expected = "greet rewritten\n/echo allowed\n";
+        let mut output = vec![0; expected.len()];
+        stdout_rx.read_exact(&mut output).await.unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), expected);
+
+        let requests = requests.lock().unwrap();
+        let programs: Vec<_> = requests.iter().map(|req| req.program.as_str()).collect();
+        assert_eq!(programs, ["/forbidden", "/greet", "/echo"]);
+        assert_eq!(requests[1].args, ["/greet", "please"]);
+        assert!(requests.iter().all(|req| req.pid == shell));
+    });
+}
@@ -0,0 +1,161 @@
+#![cfg(not(target_family = "wasm"))]
+
+use std::path::Path;
+
+use virtual_fs::{mem_fs, AsyncWriteExt, FileSystem};
+use wasmer::{Instance, Module, Store, Value};
+use wasmer_wasix::{
+    wasmer_wasix_types::wasi::Errno, Pipe, StdioBuffering, StdioPrefix, WasiEnv, WasiEnvBuilder,
+    WasiFunctionEnv,
+};
+
+/// Exports `write(offset, len)`, which writes part of "hello\nworld\n" to
+/// stdout.
+const WRITER: &str = r#"
+(module
+    (import "wasi_snapshot_preview1" "fd_write" (func $fd_write (param i32 i32 i32 i32) (result i32)))
+    (memory (export "memory") 1)
+    (data (i32.const 100) "hello\nworld\n")
+    (func (export "write") (param $offset i32) (param $len i32)
+        (i32.store (i32.const 0) (i32.add (i32.const 100) (local.get $offset)))
+        (i32.store (i32.const 4) (local.get $len))
+        (if (call $fd_write (i32.const 1) (i32.const 0) (i32.const 1) (i32.const 8))
+            (then unreachable)))
+)
+"#;
+
+/// Writes a partial line to stdout, then traps.
+const CRASH: &str = r#"
+(module
+    (import "wasi_snapshot_preview1" "fd_write" (func $fd_write (param i32 i32 i32 i32) (result i32)))
+    (memory (export "memory") 1)
+    (data (i32.const 100) "partial")
+    (func (export "_start")
+        (i32.store (i32.const 0) (i32.const 100))
+        (i32.store (i32.const 4) (i32.const 7))
+        (if (call $fd_write (i32.const 1) (i32.const 0) (i32.const 1) (i32.const 8))
+            (then unreachable))
+        unreachable)
+)
+"#;
+
+struct Writer {
+    store: Store,
+    instance: Instance,
+    env: WasiFunctionEnv,
+}
+
+impl Writer {
+    fn new(stdout: &Pipe) -> Self {
+        let mut store = Store::default();
+        let module = Module::new(&store, WRITER).unwrap();
+        let (instance, env) = WasiEnv::builder("writer")
+            .engine(store.engine().clone())
+            .stdout(Box::new(stdout.clone()))
+            .stdio_buffering(StdioBuffering::line_buffered().with_prefix(StdioPrefix::Pid))
+            .instantiate(module, &mut store)
+            .unwrap();
+
+        Writer {
+            store,
+            instance,
+            env,
+        }
+    }
+
+    fn write(&mut self, offset: i32, len: i32) {
+        let func = self.instance.exports.get_function("write").unwrap();
+        func.call(&mut self.store, &[Value::I32(offset), Value::I32(len)])
+            .unwrap();
+    }
+
+    fn tag(&self) -> String {
+        format!("[pid {}] ", self.env.data(&self.store).pid())
+    }
+
+    fn exit(&mut self) {
+        self.env
+            .on_exit(&mut self.store, Some(Errno::Success.into()));
+    }
+}
+
+/// Everything that can be read from `rx` without blocking.
+fn available(rx: &mut Pipe) -> String {
+    let mut output = Vec::new();
+    let mut buf = [0; 256];
+    while let Some(read @ 1..) = rx.try_read(&mut buf) {
+        output.extend_from_slice(&buf[..read]);
+    }
+    String::from_utf8(output).unwrap()
+}
+
+#[test]
+fn interleaved_partial_lines_come_out_whole() {
+    let rt = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+    let _guard = rt.enter();
+
+    let (stdout_tx, mut stdout_rx) = Pipe::channel();
+    let mut a = Writer::new(&stdout_tx);
+    let mut b = Writer::new(&stdout_tx);
+    let (tag_a, tag_b) = (a.tag(), b.tag());
+
+    a.write(0, 3);
+    b.write(0, 3);
+    assert_eq!(available(&mut stdout_rx), "");
+    a.write(3, 5);
+    b.write(3, 9);
+    a.write(8, 4);
+    assert_eq!(
+        available(&mut stdout_rx),
+        format!("{tag_a}hello\n{tag_b}hello\n{tag_b}world\n{tag_a}world\n")
+    );
+
+    // Partial lines are written when the process exits
+    a.write(0, 3);
+    b.write(6, 2);
+    a.exit();
+    assert_eq!(available(&mut stdout_rx), format!("{tag_a}hel"));
+    b.exit();
+    assert_eq!(available(&mut stdout_rx), format!("{tag_b}wo"));
+}
+
+#[test]
+fn buffered_output_is_flushed_when_a_process_traps() {
+    let rt = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+    let handle = rt.handle().clone();
+    let _guard = handle.enter();
+
+    handle.block_on(async {
+        let fs = mem_fs::FileSystem::default();
+        let mut f = fs
+            .new_open_options()
+            .create(true)
+            .write(true)
+            .open(Path::new("/crash"))
+            .unwrap();
+        f.write_all(&wasmer::wat2wasm(CRASH.as_bytes()).unwrap())
+            .await
+            .unwrap();
+
+        let (stdout_tx, mut stdout_rx) = Pipe::channel();
+        let env = WasiEnvBuilder::new("crash")
+            .fs(Box::new(fs))
+            .stdout(Box::new(stdout_tx))
+            .stdio_buffering(StdioBuffering::line_buffered().with_prefix(StdioPrefix::Command))
+            .build()
+            .unwrap();
+        let bin_factory = env.bin_factory.clone();
+
+        let mut task = bin_factory.spawn("/crash".to_string(), env).await.unwrap();
+        let exit_code = task.wait_finished().await;
+        assert!(!matches!(exit_code, Ok(code) if code.is_success()));
+
+        assert_eq!(available(&mut stdout_rx), "[crash] partial");
+    });
+}
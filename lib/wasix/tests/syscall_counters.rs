@@ -0,0 +1,76 @@
+#![cfg(all(feature = "syscall-counters", not(target_family = "wasm")))]
+
+use std::sync::Arc;
+
+use wasmer::Module;
+use wasmer_wasix::{
+    bin_factory::spawn_exec_module, os::task::counters::Syscall,
+    runtime::task_manager::tokio::TokioTaskManager, PluggableRuntime, Runtime, WasiEnvBuilder,
+};
+
+/// Reads from stdin 3 times and writes to stdout 5 times.
+const PROGRAM: &str = r#"
+(module
+    (import "wasi_snapshot_preview1" "fd_read" (func $fd_read (param i32 i32 i32 i32) (result i32)))
+    (import "wasi_snapshot_preview1" "fd_write" (func $fd_write (param i32 i32 i32 i32) (result i32)))
+    (memory (export "memory") 1)
+    (data (i32.const 64) "hello\n")
+
+    (func (export "_start")
+        (local $i i32)
+        (i32.store (i32.const 0) (i32.const 128))
+        (i32.store (i32.const 4) (i32.const 16))
+        (i32.store (i32.const 8) (i32.const 64))
+        (i32.store (i32.const 12) (i32.const 6))
+
+        (local.set $i (i32.const 3))
+        (loop $read
+            (drop (call $fd_read (i32.const 0) (i32.const 0) (i32.const 1) (i32.const 32)))
+            (local.set $i (i32.sub (local.get $i) (i32.const 1)))
+            (br_if $read (local.get $i)))
+
+        (local.set $i (i32.const 5))
+        (loop $write
+            (drop (call $fd_write (i32.const 1) (i32.const 8) (i32.const 1) (i32.const 32)))
+            (local.set $i (i32.sub (local.get $i) (i32.const 1)))
+            (br_if $write (local.get $i)))
+    )
+)
+"#;
+
+#[test]
+fn counts_each_syscall_the_guest_makes() {
+    let tokio_rt = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+    let handle = tokio_rt.handle().clone();
+    let _guard = handle.enter();
+    let rt: Arc<dyn Runtime + Send + Sync> = Arc::new(PluggableRuntime::new(Arc::new(
+        TokioTaskManager::new(tokio_rt),
+    )));
+
+    let module = Module::new(&rt.engine(), PROGRAM).unwrap();
+    let env = WasiEnvBuilder::new("counters")
+        .runtime(rt.clone())
+        .stdin(Box::<virtual_fs::NullFile>::default())
+        .stdout(Box::<virtual_fs::NullFile>::default())
+        .build()
+        .unwrap();
+    let process = env.process.clone();
+    let before = process.syscall_counters();
+    assert_eq!(before.total(), 0);
+
+    let mut task = spawn_exec_module(module, env, &rt).unwrap();
+    handle.block_on(task.wait_finished()).unwrap();
+
+    let after = process.syscall_counters();
+    assert_eq!(after.fd_read(), 3);
+    assert_eq!(after.fd_write(), 5);
+    assert_eq!(after.get(Syscall::FdWrite), 5);
+    assert_eq!(
+        after.iter().collect::<Vec<_>>(),
+        [(Syscall::FdRead, 3), (Syscall::FdWrite, 5)]
+    );
+    assert_eq!(after.delta(&before), after);
+}
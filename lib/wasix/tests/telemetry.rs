@@ -0,0 +1,217 @@
+#![cfg(all(feature = "telemetry", not(target_family = "wasm")))]
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use tracing::{
+    field::{Field, Visit},
+    span::{Attributes, Id, Record},
+    Subscriber,
+};
+use tracing_subscriber::{layer::Context, prelude::*, registry::LookupSpan, Layer};
+use wasmer_wasix::{
+    os::Console,
+    runtime::{
+        package_loader::BuiltinPackageLoader, resolver::FileSystemSource,
+        task_manager::tokio::TokioTaskManager,
+    },
+    telemetry::TARGET,
+    PluggableRuntime,
+};
+
+const WASMER_TOML: &str = r#"
+[package]
+name = "test/telemetry"
+version = "0.1.0"
+
+[[module]]
+name = "main"
+source = "main.wasm"
+abi = "wasi"
+
+[[command]]
+name = "main"
+module = "main"
+"#;
+
+const MAIN_WAT: &str = r#"
+(module
+    (import "wasi_snapshot_preview1" "proc_exit" (func $proc_exit (param i32)))
+    (memory (export "memory") 1)
+    (func (export "_start")
+        (call $proc_exit (i32.const 0))
+    )
+)
+"#;
+
+/// A telemetry span, along with the closest telemetry span it is nested in.
+#[derive(Debug, Clone)]
+struct CapturedSpan {
+    name: &'static str,
+    parent: Option<&'static str>,
+    fields: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct Capture {
+    spans: Arc<Mutex<HashMap<u64, CapturedSpan>>>,
+    events: Arc<Mutex<Vec<CapturedSpan>>>,
+}
+
+impl Capture {
+    fn span(&self, name: &str) -> CapturedSpan {
+        let spans = self.spans.lock().unwrap();
+        let matching: Vec<_> = spans.values().filter(|s| s.name == name).collect();
+        assert_eq!(matching.len(), 1, "expected exactly one {name} span");
+        matching[0].clone()
+    }
+
+    /// Find the closest ancestor of `id` (including itself) that is a
+    /// telemetry span.
+    fn telemetry_ancestor<S>(id: Option<&Id>, ctx: &Context<'_, S>) -> Option<&'static str>
+    where
+        S: Subscriber + for<'a> LookupSpan<'a>,
+    {
+        let span = ctx.span(id?)?;
+        span.scope()
+            .find(|s| s.metadata().target() == TARGET)
+            .map(|s| s.metadata().name())
+    }
+}
+
+struct Fields<'a>(&'a mut HashMap<String, String>);
+
+impl Visit for Fields<'_> {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        self.0
+            .insert(field.name().to_string(), format!("{value:?}"));
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name().to_string(), value.to_string());
+    }
+}
+
+impl<S> Layer<S> for Capture
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        if attrs.metadata().target() != TARGET {
+            return;
+        }
+
+        let parent = if attrs.is_contextual() {
+            Self::telemetry_ancestor(ctx.current_span().id(), &ctx)
+        } else {
+            Self::telemetry_ancestor(attrs.parent(), &ctx)
+        };
+        let mut fields = HashMap::new();
+        attrs.record(&mut Fields(&mut fields));
+
+        let span = CapturedSpan {
+            name: attrs.metadata().name(),
+            parent,
+            fields,
+        };
+        self.spans.lock().unwrap().insert(id.into_u64(), span);
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, _ctx: Context<'_, S>) {
+        if let Some(span) = self.spans.lock().unwrap().get_mut(&id.into_u64()) {
+            values.record(&mut Fields(&mut span.fields));
+        }
+    }
+
+    fn on_event(&self, event: &tracing::Event<'_>, ctx: Context<'_, S>) {
+        if event.metadata().target() != TARGET {
+            return;
+        }
+
+        let parent = Self::telemetry_ancestor(ctx.event_span(event).map(|s| s.id()).as_ref(), &ctx);
+        let mut fields = HashMap::new();
+        event.record(&mut Fields(&mut fields));
+
+        self.events.lock().unwrap().push(CapturedSpan {
+            name: event.metadata().name(),
+            parent,
+            fields,
+        });
+    }
+}
+
+#[test]
+fn console_run_emits_span_tree() {
+    let capture = Capture::default();
+    tracing_subscriber::registry()
+        .with(capture.clone())
+        .try_init()
+        .unwrap();
+
+    let temp = tempfile::tempdir().unwrap();
+    let pkg_dir = temp.path().join("pkg");
+    std::fs::create_dir(&pkg_dir).unwrap();
+    std::fs::write(pkg_dir.join("wasmer.toml"), WASMER_TOML).unwrap();
+    std::fs::write(
+        pkg_dir.join("main.wasm"),
+        wasmer::wat2wasm(MAIN_WAT.as_bytes()).unwrap(),
+    )
+    .unwrap();
+    let webc = wasmer_package::package::Package::from_manifest(pkg_dir.join("wasmer.toml"))
+        .unwrap()
+        .serialize()
+        .unwrap();
+    let webc_path = temp.path().join("telemetry.webc");
+    std::fs::write(&webc_path, webc).unwrap();
+
+    let tokio_rt = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+    let handle = tokio_rt.handle().clone();
+    let _guard = handle.enter();
+
+    let mut rt = PluggableRuntime::new(Arc::new(TokioTaskManager::new(tokio_rt)));
+    rt.set_source(FileSystemSource::default())
+        .set_package_loader(BuiltinPackageLoader::new());
+
+    let (mut process, _) = Console::new(webc_path.to_str().unwrap(), Arc::new(rt))
+        .with_no_welcome(true)
+        .run()
+        .unwrap();
+    let code = handle.block_on(process.wait_finished()).unwrap();
+    assert!(code.is_success());
+
+    let fetch_webc = capture.span("fetch_webc");
+    assert_eq!(fetch_webc.parent, None);
+    assert!(!fetch_webc.fields["pkg.id"].is_empty());
+    assert_eq!(fetch_webc.fields["pkg.cached"], "false");
+    assert!(fetch_webc.fields["url.full"].starts_with("file://"));
+
+    let spawn_exec = capture.span("spawn_exec");
+    assert_eq!(spawn_exec.parent, None);
+    assert!(spawn_exec.fields.contains_key("process.pid"));
+    assert!(spawn_exec.fields.contains_key("process.command"));
+
+    let compile_module = capture.span("compile_module");
+    assert_eq!(compile_module.parent, Some("spawn_exec"));
+    assert_eq!(compile_module.fields["module.cached"], "false");
+
+    let process_span = capture.span("process");
+    assert_eq!(process_span.parent, Some("spawn_exec"));
+    assert_eq!(
+        process_span.fields["process.pid"],
+        spawn_exec.fields["process.pid"]
+    );
+    assert_eq!(process_span.fields["process.exit.code"], "0");
+
+    let events = capture.events.lock().unwrap();
+    let exit = events
+        .iter()
+        .find(|e| e.fields.get("message").map(String::as_str) == Some("process_exit"))
+        .expect("no process_exit event");
+    assert_eq!(exit.parent, Some("process"));
+    assert_eq!(exit.fields["process.exit.code"], "0");
+}
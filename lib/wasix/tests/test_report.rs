@@ -0,0 +1,177 @@
+#![cfg(not(target_family = "wasm"))]
+
+use std::sync::{Arc, Mutex};
+
+use wasmer::{Instance, Module, Store, Value};
+use wasmer_wasix::{
+    test_report::{TestEvent, TestStatus, TestSummary},
+    WasiEnv, WasiFunctionEnv,
+};
+
+/// A test harness that runs four tests: one passes, one fails, one is
+/// ignored and the last one never finishes.
+///
+/// `report` writes a buffer to `/dev/wasmer-test-report`, opened relative to
+/// the preopened `/` (fd 4) on first use.
+const HARNESS: &str = r#"
+(module
+    (import "wasi_snapshot_preview1" "path_open" (func $path_open (param i32 i32 i32 i32 i32 i64 i64 i32 i32) (result i32)))
+    (import "wasi_snapshot_preview1" "fd_write" (func $fd_write (param i32 i32 i32 i32) (result i32)))
+    (memory (export "memory") 1)
+    (global $fd (mut i32) (i32.const -1))
+    (data (i32.const 64) "dev/wasmer-test-report")
+
+    (data (i32.const 1024) "{\"type\":\"suite\",\"event\":\"started\",\"test_count\":4}\n")
+    (data (i32.const 1152) "{\"type\":\"test\",\"event\":\"started\",\"name\":\"adds\"}\n")
+    (data (i32.const 1280) "{\"type\":\"test\",\"event\":\"ok\",\"name\":\"adds\",\"exec_time\":0.001}\n")
+    (data (i32.const 1408) "{\"type\":\"test\",\"event\":\"started\",\"name\":\"subtracts\"}\n")
+    (data (i32.const 1536) "{\"type\":\"test\",\"event\":\"failed\",\"name\":\"subtracts\",\"stdout\":\"assertion failed\"}\n")
+    (data (i32.const 1664) "{\"type\":\"test\",\"event\":\"ignored\",\"name\":\"slow\",\"message\":\"too slow\"}\n")
+    (data (i32.const 1792) "{\"type\":\"test\",\"event\":\"started\",\"name\":\"hangs\"}\n")
+
+    (func $report (export "report") (param $ptr i32) (param $len i32)
+        (if (i32.lt_s (global.get $fd) (i32.const 0))
+            (then
+                ;; Open it for writing (FD_WRITE)
+                (if (call $path_open (i32.const 4) (i32.const 0) (i32.const 64) (i32.const 22)
+                        (i32.const 0) (i64.const 64) (i64.const 0) (i32.const 0) (i32.const 0))
+                    (then unreachable))
+                (global.set $fd (i32.load (i32.const 0)))))
+
+        (i32.store (i32.const 8) (local.get $ptr))
+        (i32.store (i32.const 12) (local.get $len))
+        (if (call $fd_write (global.get $fd) (i32.const 8) (i32.const 1) (i32.const 16))
+            (then unreachable))
+    )
+
+    (func (export "_start")
+        (call $report (i32.const 1024) (i32.const 50))
+        (call $report (i32.const 1152) (i32.const 48))
+        (call $report (i32.const 1280) (i32.const 61))
+        (call $report (i32.const 1408) (i32.const 53))
+        (call $report (i32.const 1536) (i32.const 80))
+        (call $report (i32.const 1664) (i32.const 69))
+        (call $report (i32.const 1792) (i32.const 49))
+    )
+)
+"#;
+
+struct Guest {
+    store: Store,
+    instance: Instance,
+    _env: WasiFunctionEnv,
+    events: Arc<Mutex<Vec<TestEvent>>>,
+}
+
+impl Guest {
+    fn new() -> Self {
+        let events = Arc::new(Mutex::new(Vec::new()));
+
+        let mut store = Store::default();
+        let module = Module::new(&store, HARNESS).unwrap();
+        let reported = events.clone();
+        let (instance, env) = WasiEnv::builder("harness")
+            .engine(store.engine().clone())
+            .with_test_reporter(move |event: &TestEvent| {
+                reported.lock().unwrap().push(event.clone())
+            })
+            .preopen_dir("/")
+            .unwrap()
+            .instantiate(module, &mut store)
+            .unwrap();
+
+        Guest {
+            store,
+            instance,
+            _env: env,
+            events,
+        }
+    }
+
+    fn call(&mut self, name: &str, params: &[Value]) {
+        let func = self.instance.exports.get_function(name).unwrap();
+        func.call(&mut self.store, params).unwrap();
+    }
+
+    /// Has the guest write `text` to the report.
+    fn report(&mut self, text: &str) {
+        let memory = self.instance.exports.get_memory("memory").unwrap();
+        memory
+            .view(&self.store)
+            .write(4096, text.as_bytes())
+            .unwrap();
+        self.call("report", &[Value::I32(4096), Value::I32(text.len() as i32)]);
+    }
+
+    fn events(&self) -> Vec<TestEvent> {
+        self.events.lock().unwrap().clone()
+    }
+}
+
+fn runtime() -> tokio::runtime::Runtime {
+    tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .unwrap()
+}
+
+#[test]
+fn harness_results_are_summarized() {
+    let rt = runtime();
+    let _guard = rt.enter();
+    let mut guest = Guest::new();
+
+    guest.call("_start", &[]);
+
+    let mut summary = TestSummary::default();
+    for event in guest.events() {
+        summary.record(&event);
+    }
+    assert_eq!(
+        summary,
+        TestSummary {
+            expected: Some(4),
+            passed: vec!["adds".to_string()],
+            failed: vec!["subtracts".to_string()],
+            ignored: vec!["slow".to_string()],
+            running: vec!["hangs".to_string()],
+        }
+    );
+    assert!(!summary.is_success());
+    assert_eq!(
+        summary.to_string(),
+        "test result: FAILED. 1 passed; 1 failed; 1 ignored; 1 did not finish"
+    );
+}
+
+#[test]
+fn events_are_reported_as_soon_as_their_line_is_written() {
+    let rt = runtime();
+    let _guard = rt.enter();
+    let mut guest = Guest::new();
+
+    guest.report(r#"{"type":"test","event":"started","#);
+    assert!(guest.events().is_empty());
+
+    guest.report("\"name\":\"stuck\"}\n{\"type\":");
+    assert_eq!(
+        guest.events(),
+        [TestEvent::Test {
+            event: TestStatus::Started,
+            name: "stuck".to_string(),
+            exec_time: None,
+            stdout: None,
+            message: None,
+        }]
+    );
+
+    // Garbage doesn't stop later events from being reported
+    guest.report("\"nonsense\"}\nnot even json\n");
+    guest.report("{\"type\":\"test\",\"event\":\"ok\",\"name\":\"stuck\"}\n");
+    let events = guest.events();
+    assert_eq!(events.len(), 2);
+    assert!(matches!(
+        &events[1],
+        TestEvent::Test { event: TestStatus::Ok, name, .. } if name == "stuck"
+    ));
+}
@@ -0,0 +1,108 @@
+#![cfg(not(target_family = "wasm"))]
+
+use std::{path::Path, sync::Arc};
+
+use virtual_fs::{limiter::FixedMemoryLimiter, mem_fs, AsyncReadExt, FileSystem};
+use virtual_mio::InlineWaker;
+use wasmer::Module;
+use wasmer_types::ModuleHash;
+use wasmer_wasix::{
+    runners::wasi::{RuntimeOrEngine, WasiRunner},
+    runtime::task_manager::tokio::TokioTaskManager,
+    Pipe, PluggableRuntime,
+};
+
+/// Writes 64 KiB to an unnamed file in `/data` and closes it, then writes
+/// "hello" to an unnamed file in `/tmp`, links it to `/tmp/out` and prints
+/// what reading `/tmp/out` back returns.
+const PROGRAM: &str = r#"
+(module
+    (import "wasi_snapshot_preview1" "path_open" (func $path_open (param i32 i32 i32 i32 i32 i64 i64 i32 i32) (result i32)))
+    (import "wasi_snapshot_preview1" "fd_write" (func $fd_write (param i32 i32 i32 i32) (result i32)))
+    (import "wasi_snapshot_preview1" "fd_read" (func $fd_read (param i32 i32 i32 i32) (result i32)))
+    (import "wasi_snapshot_preview1" "fd_close" (func $fd_close (param i32) (result i32)))
+    (import "wasix_32v1" "path_open_tmpfile" (func $path_open_tmpfile (param i32 i32 i32 i32 i64 i32 i32) (result i32)))
+    (import "wasix_32v1" "fd_link" (func $fd_link (param i32 i32 i32 i32) (result i32)))
+    (memory (export "memory") 2)
+    (data (i32.const 100) "data")
+    (data (i32.const 110) "tmp")
+    (data (i32.const 120) "tmp/out")
+    (data (i32.const 130) "hello")
+
+    (func $tmpfile (param $dir i32) (param $len i32) (result i32)
+        (if (call $path_open_tmpfile (i32.const 3) (i32.const 0) (local.get $dir) (local.get $len)
+                (i64.const -1) (i32.const 0) (i32.const 0))
+            (then unreachable))
+        (i32.load (i32.const 0))
+    )
+
+    (func $write (param $fd i32) (param $buf i32) (param $len i32)
+        (i32.store (i32.const 16) (local.get $buf))
+        (i32.store (i32.const 20) (local.get $len))
+        (if (call $fd_write (local.get $fd) (i32.const 16) (i32.const 1) (i32.const 8))
+            (then unreachable))
+    )
+
+    (func (export "_start")
+        (local $fd i32)
+        (local.set $fd (call $tmpfile (i32.const 100) (i32.const 4)))
+        (call $write (local.get $fd) (i32.const 65536) (i32.const 65536))
+        (if (call $fd_close (local.get $fd))
+            (then unreachable))
+
+        (local.set $fd (call $tmpfile (i32.const 110) (i32.const 3)))
+        (call $write (local.get $fd) (i32.const 130) (i32.const 5))
+        (if (call $fd_link (local.get $fd) (i32.const 3) (i32.const 120) (i32.const 7))
+            (then unreachable))
+        (if (call $fd_close (local.get $fd))
+            (then unreachable))
+
+        (if (call $path_open (i32.const 3) (i32.const 0) (i32.const 120) (i32.const 7)
+                (i32.const 0) (i64.const -1) (i64.const -1) (i32.const 0) (i32.const 0))
+            (then unreachable))
+        (i32.store (i32.const 16) (i32.const 1024))
+        (i32.store (i32.const 20) (i32.const 64))
+        (if (call $fd_read (i32.load (i32.const 0)) (i32.const 16) (i32.const 1) (i32.const 8))
+            (then unreachable))
+        (call $write (i32.const 1) (i32.const 1024) (i32.load (i32.const 8)))
+    )
+)
+"#;
+
+#[test]
+fn unnamed_files_are_freed_or_linked() {
+    let tokio_rt = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+    let _guard = tokio_rt.enter();
+
+    let data = mem_fs::FileSystem::default();
+    let limiter = Arc::new(FixedMemoryLimiter::new(1024 * 1024));
+    data.set_memory_limiter(limiter.clone());
+
+    let engine = wasmer::Engine::default();
+    let module = Module::new(&engine, PROGRAM).unwrap();
+    let mut rt = PluggableRuntime::new(Arc::new(TokioTaskManager::new(tokio_rt.handle().clone())));
+    rt.set_engine(engine);
+
+    let (stdout_tx, mut stdout_rx) = Pipe::channel();
+    WasiRunner::new()
+        .with_stdout(Box::new(stdout_tx))
+        .with_mount("/data".to_string(), Arc::new(data.clone()))
+        .run_wasm(
+            RuntimeOrEngine::Runtime(Arc::new(rt)),
+            "tmpfile",
+            module,
+            ModuleHash::random(),
+        )
+        .unwrap();
+
+    let mut stdout = Vec::new();
+    InlineWaker::block_on(stdout_rx.read_to_end(&mut stdout)).unwrap();
+    assert_eq!(String::from_utf8_lossy(&stdout), "hello");
+
+    // The unnamed file never showed up in `/data` and its memory is gone
+    assert_eq!(data.read_dir(Path::new("/")).unwrap().count(), 0);
+    assert_eq!(limiter.used(), 0);
+}
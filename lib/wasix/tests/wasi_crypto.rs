@@ -0,0 +1,296 @@
+#![cfg(all(not(target_family = "wasm"), feature = "wasi-crypto"))]
+
+use std::sync::Arc;
+
+use wasmer::{Instance, Module, Store, Value};
+use wasmer_wasix::{
+    capabilities::{Capabilities, CapabilityCryptoV1},
+    os::task::control_plane::{ControlPlaneConfig, WasiControlPlane},
+    runtime::task_manager::tokio::TokioTaskManager,
+    PluggableRuntime, WasiEnv,
+};
+
+/// Exports the wasi-crypto imports as they are, so they can be called with
+/// buffers in its memory.
+const PROGRAM: &str = r#"
+(module
+    (import "wasi_ephemeral_crypto_common" "array_output_len" (func $array_output_len (param i32 i32) (result i32)))
+    (import "wasi_ephemeral_crypto_common" "array_output_pull" (func $array_output_pull (param i32 i32 i32 i32) (result i32)))
+    (import "wasi_ephemeral_crypto_asymmetric_common" "keypair_import" (func $keypair_import (param i32 i32 i32 i32 i32 i32 i32) (result i32)))
+    (import "wasi_ephemeral_crypto_asymmetric_common" "keypair_publickey" (func $keypair_publickey (param i32 i32) (result i32)))
+    (import "wasi_ephemeral_crypto_asymmetric_common" "keypair_export" (func $keypair_export (param i32 i32 i32) (result i32)))
+    (import "wasi_ephemeral_crypto_asymmetric_common" "publickey_import" (func $publickey_import (param i32 i32 i32 i32 i32 i32 i32) (result i32)))
+    (import "wasi_ephemeral_crypto_asymmetric_common" "publickey_export" (func $publickey_export (param i32 i32 i32) (result i32)))
+    (import "wasi_ephemeral_crypto_signatures" "signature_import" (func $signature_import (param i32 i32 i32 i32 i32 i32) (result i32)))
+    (import "wasi_ephemeral_crypto_signatures" "signature_export" (func $signature_export (param i32 i32 i32) (result i32)))
+    (import "wasi_ephemeral_crypto_signatures" "signature_state_open" (func $signature_state_open (param i32 i32) (result i32)))
+    (import "wasi_ephemeral_crypto_signatures" "signature_state_update" (func $signature_state_update (param i32 i32 i32) (result i32)))
+    (import "wasi_ephemeral_crypto_signatures" "signature_state_sign" (func $signature_state_sign (param i32 i32) (result i32)))
+    (import "wasi_ephemeral_crypto_signatures" "signature_verification_state_open" (func $signature_verification_state_open (param i32 i32) (result i32)))
+    (import "wasi_ephemeral_crypto_signatures" "signature_verification_state_update" (func $signature_verification_state_update (param i32 i32 i32) (result i32)))
+    (import "wasi_ephemeral_crypto_signatures" "signature_verification_state_verify" (func $signature_verification_state_verify (param i32 i32) (result i32)))
+    (memory (export "memory") 1)
+    (export "array_output_len" (func $array_output_len))
+    (export "array_output_pull" (func $array_output_pull))
+    (export "keypair_import" (func $keypair_import))
+    (export "keypair_publickey" (func $keypair_publickey))
+    (export "keypair_export" (func $keypair_export))
+    (export "publickey_import" (func $publickey_import))
+    (export "publickey_export" (func $publickey_export))
+    (export "signature_import" (func $signature_import))
+    (export "signature_export" (func $signature_export))
+    (export "signature_state_open" (func $signature_state_open))
+    (export "signature_state_update" (func $signature_state_update))
+    (export "signature_state_sign" (func $signature_state_sign))
+    (export "signature_verification_state_open" (func $signature_verification_state_open))
+    (export "signature_verification_state_update" (func $signature_verification_state_update))
+    (export "signature_verification_state_verify" (func $signature_verification_state_verify))
+)
+"#;
+
+const RESULT: u64 = 16;
+const ALGORITHM: u64 = 1024;
+const DATA: u64 = 2048;
+const BUF: u64 = 4096;
+
+const ALGORITHM_TYPE_SIGNATURES: i32 = 0;
+const ENCODING_RAW: i32 = 0;
+
+// The proposal's error codes
+const SUCCESS: u16 = 0;
+const PROHIBITED_OPERATION: u16 = 4;
+const VERIFICATION_FAILED: u16 = 10;
+
+/// Test 1 of RFC 8032, which signs an empty message.
+const SECRET_KEY: &str = "9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60";
+const PUBLIC_KEY: &str = "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a";
+const SIGNATURE: &str = "e5564300c360ac729086e2cc806e828a84877f1eb8e5d974d873e065224901555fb8821590a33bacc61e39701cf9b46bd25bf5f0595bbe24655141438e7a100b";
+
+struct Guest {
+    store: Store,
+    instance: Instance,
+}
+
+impl Guest {
+    fn new(
+        rt: &Arc<PluggableRuntime>,
+        plane: &WasiControlPlane,
+        crypto: CapabilityCryptoV1,
+    ) -> Self {
+        let mut store = Store::new(rt.engine.clone());
+        let module = Module::new(&store, PROGRAM).unwrap();
+        let (instance, _env) = WasiEnv::builder("crypto")
+            .runtime(rt.clone())
+            .control_plane(plane.clone())
+            .capabilities(Capabilities {
+                crypto,
+                ..Capabilities::new()
+            })
+            .instantiate(module, &mut store)
+            .unwrap();
+
+        Guest { store, instance }
+    }
+
+    fn write(&self, offset: u64, data: &[u8]) {
+        let memory = self.instance.exports.get_memory("memory").unwrap();
+        memory.view(&self.store).write(offset, data).unwrap();
+    }
+
+    fn read(&self, offset: u64, len: usize) -> Vec<u8> {
+        let memory = self.instance.exports.get_memory("memory").unwrap();
+        let mut data = vec![0; len];
+        memory.view(&self.store).read(offset, &mut data).unwrap();
+        data
+    }
+
+    fn read_u32(&self, offset: u64) -> u32 {
+        u32::from_le_bytes(self.read(offset, 4).try_into().unwrap())
+    }
+
+    fn call(&mut self, name: &str, params: &[i32]) -> u16 {
+        let func = self.instance.exports.get_function(name).unwrap();
+        let params: Vec<_> = params.iter().copied().map(Value::I32).collect();
+        func.call(&mut self.store, &params).unwrap()[0].unwrap_i32() as u16
+    }
+
+    /// Calls a function that creates an object, returning its handle.
+    fn open(&mut self, name: &str, params: &[i32]) -> Result<u32, u16> {
+        let mut params = params.to_vec();
+        params.push(RESULT as i32);
+        match self.call(name, &params) {
+            SUCCESS => Ok(self.read_u32(RESULT)),
+            errno => Err(errno),
+        }
+    }
+
+    /// Calls one of the `*_import` functions with the Ed25519 algorithm and
+    /// `encoded` in its raw encoding.
+    fn import(&mut self, name: &str, encoded: &[u8]) -> Result<u32, u16> {
+        self.write(ALGORITHM, b"Ed25519");
+        self.write(DATA, encoded);
+        let mut params = vec![
+            ALGORITHM as i32,
+            "Ed25519".len() as i32,
+            DATA as i32,
+            encoded.len() as i32,
+            ENCODING_RAW,
+        ];
+        if name != "signature_import" {
+            params.insert(0, ALGORITHM_TYPE_SIGNATURES);
+        }
+        self.open(name, &params)
+    }
+
+    /// Calls one of the `*_export` functions with the raw encoding and pulls
+    /// the resulting array output.
+    fn export(&mut self, name: &str, handle: u32) -> Result<Vec<u8>, u16> {
+        let output = self.open(name, &[handle as i32, ENCODING_RAW])?;
+        assert_eq!(
+            self.call("array_output_len", &[output as i32, RESULT as i32]),
+            SUCCESS
+        );
+        let len = self.read_u32(RESULT);
+        let errno = self.call(
+            "array_output_pull",
+            &[output as i32, BUF as i32, len as i32, RESULT as i32],
+        );
+        assert_eq!(errno, SUCCESS);
+        assert_eq!(self.read_u32(RESULT), len);
+        Ok(self.read(BUF, len as usize))
+    }
+
+    fn sign(&mut self, keypair: u32, message: &[u8]) -> u32 {
+        let state = self
+            .open("signature_state_open", &[keypair as i32])
+            .unwrap();
+        self.write(DATA, message);
+        let errno = self.call(
+            "signature_state_update",
+            &[state as i32, DATA as i32, message.len() as i32],
+        );
+        assert_eq!(errno, SUCCESS);
+        self.open("signature_state_sign", &[state as i32]).unwrap()
+    }
+
+    fn verify(&mut self, publickey: u32, message: &[u8], signature: u32) -> u16 {
+        let state = self
+            .open("signature_verification_state_open", &[publickey as i32])
+            .unwrap();
+        self.write(DATA, message);
+        let errno = self.call(
+            "signature_verification_state_update",
+            &[state as i32, DATA as i32, message.len() as i32],
+        );
+        assert_eq!(errno, SUCCESS);
+        self.call(
+            "signature_verification_state_verify",
+            &[state as i32, signature as i32],
+        )
+    }
+}
+
+fn runtime(tokio_rt: &tokio::runtime::Runtime) -> Arc<PluggableRuntime> {
+    Arc::new(PluggableRuntime::new(Arc::new(TokioTaskManager::new(
+        tokio_rt.handle().clone(),
+    ))))
+}
+
+fn tokio_runtime() -> tokio::runtime::Runtime {
+    tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .unwrap()
+}
+
+fn enabled() -> CapabilityCryptoV1 {
+    CapabilityCryptoV1 {
+        enabled: true,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn ed25519_signs_and_verifies_the_rfc_8032_vector() {
+    let tokio_rt = tokio_runtime();
+    let _guard = tokio_rt.enter();
+    let rt = runtime(&tokio_rt);
+    let plane = WasiControlPlane::new(ControlPlaneConfig::new());
+    let mut guest = Guest::new(&rt, &plane, enabled());
+
+    let keypair = guest
+        .import("keypair_import", &hex::decode(SECRET_KEY).unwrap())
+        .unwrap();
+    let publickey = guest.open("keypair_publickey", &[keypair as i32]).unwrap();
+    assert_eq!(
+        guest.export("publickey_export", publickey).unwrap(),
+        hex::decode(PUBLIC_KEY).unwrap()
+    );
+
+    let signature = guest.sign(keypair, b"");
+    assert_eq!(
+        guest.export("signature_export", signature).unwrap(),
+        hex::decode(SIGNATURE).unwrap()
+    );
+
+    // The vector verifies with a public key imported on its own
+    let publickey = guest
+        .import("publickey_import", &hex::decode(PUBLIC_KEY).unwrap())
+        .unwrap();
+    let signature = guest
+        .import("signature_import", &hex::decode(SIGNATURE).unwrap())
+        .unwrap();
+    assert_eq!(guest.verify(publickey, b"", signature), SUCCESS);
+    assert_eq!(
+        guest.verify(publickey, b"tampered", signature),
+        VERIFICATION_FAILED
+    );
+}
+
+#[test]
+fn exporting_secret_keys_is_refused_unless_allowed() {
+    let tokio_rt = tokio_runtime();
+    let _guard = tokio_rt.enter();
+    let rt = runtime(&tokio_rt);
+    let plane = WasiControlPlane::new(ControlPlaneConfig::new());
+    let secret_key = hex::decode(SECRET_KEY).unwrap();
+
+    let mut guest = Guest::new(&rt, &plane, enabled());
+    let keypair = guest.import("keypair_import", &secret_key).unwrap();
+    assert_eq!(
+        guest.export("keypair_export", keypair),
+        Err(PROHIBITED_OPERATION)
+    );
+    // The public half can still be exported
+    let publickey = guest.open("keypair_publickey", &[keypair as i32]).unwrap();
+    assert!(guest.export("publickey_export", publickey).is_ok());
+
+    let mut guest = Guest::new(
+        &rt,
+        &plane,
+        CapabilityCryptoV1 {
+            enabled: true,
+            allow_secret_export: true,
+        },
+    );
+    let keypair = guest.import("keypair_import", &secret_key).unwrap();
+    assert_eq!(guest.export("keypair_export", keypair), Ok(secret_key));
+}
+
+#[test]
+fn nothing_is_allowed_without_the_capability() {
+    let tokio_rt = tokio_runtime();
+    let _guard = tokio_rt.enter();
+    let rt = runtime(&tokio_rt);
+    let plane = WasiControlPlane::new(ControlPlaneConfig::new());
+    let mut guest = Guest::new(&rt, &plane, CapabilityCryptoV1::default());
+
+    assert_eq!(
+        guest.import("keypair_import", &hex::decode(SECRET_KEY).unwrap()),
+        Err(PROHIBITED_OPERATION)
+    );
+    assert_eq!(
+        guest.import("publickey_import", &hex::decode(PUBLIC_KEY).unwrap()),
+        Err(PROHIBITED_OPERATION)
+    );
+}
@@ -1,5 +1,5 @@
 use anyhow::Result;
-use wasmer::{wat2wasm, Module, Store};
+use wasmer::{imports, wat2wasm, Instance, Module, Store, TypedFunction};
 
 fn compile_and_compare(wasm: &[u8]) -> Result<()> {
     let store = Store::default();
@@ -42,3 +42,40 @@ fn deterministic_table() -> Result<()> {
 
     compile_and_compare(&wasm_bytes)
 }
+
+#[compiler_test(deterministic)]
+fn canonical_nans(mut config: crate::Config) -> Result<()> {
+    config.set_nan_canonicalization(true);
+    let mut store = config.store();
+    let wat = r#"(module
+        (func (export "div32") (param f32 f32) (result i32)
+            (i32.reinterpret_f32 (f32.div (local.get 0) (local.get 1))))
+        (func (export "div64") (param f64 f64) (result i64)
+            (i64.reinterpret_f64 (f64.div (local.get 0) (local.get 1))))
+        (func (export "add64") (param f64 f64) (result i64)
+            (i64.reinterpret_f64 (f64.add (local.get 0) (local.get 1))))
+)"#;
+    let module = Module::new(&store, wat)?;
+    let instance = Instance::new(&mut store, &module, &imports! {})?;
+
+    let div32: TypedFunction<(f32, f32), i32> =
+        instance.exports.get_typed_function(&mut store, "div32")?;
+    let div64: TypedFunction<(f64, f64), i64> =
+        instance.exports.get_typed_function(&mut store, "div64")?;
+    let add64: TypedFunction<(f64, f64), i64> =
+        instance.exports.get_typed_function(&mut store, "add64")?;
+
+    // 0/0 is a NaN whose sign depends on the hardware
+    assert_eq!(div32.call(&mut store, 0.0, 0.0)? as u32, 0x7fc0_0000);
+    assert_eq!(
+        div64.call(&mut store, 0.0, 0.0)? as u64,
+        0x7ff8_0000_0000_0000
+    );
+    // A signaling NaN's payload would otherwise be propagated
+    let signaling = f64::from_bits(0x7ff0_0000_0000_0001);
+    assert_eq!(
+        add64.call(&mut store, signaling, 1.0)? as u64,
+        0x7ff8_0000_0000_0000
+    );
+    Ok(())
+}
@@ -9,6 +9,7 @@ mod config;
 mod deterministic;
 mod imports;
 mod issues;
+mod memory_styles;
 mod metering;
 mod middlewares;
 // mod multi_value_imports;
@@ -0,0 +1,130 @@
+use anyhow::Result;
+use wasmer::{
+    sys::{BaseTunables, NativeEngineExt, Tunables},
+    *,
+};
+use wasmer_types::TrapCode;
+
+const WAT: &str = r#"(module
+    (memory (export "memory") 1 16)
+    (func (export "load") (param i32) (result i32)
+        (i32.load (local.get 0)))
+    (func (export "load_offset") (param i32) (result i32)
+        (i32.load offset=0x10000 (local.get 0)))
+    (func (export "store") (param i32 i32)
+        (i32.store (local.get 0) (local.get 1))))"#;
+
+fn store_with_tunables(config: &crate::Config, tunables: BaseTunables) -> Store {
+    let mut engine = config.engine(config.compiler_config(false));
+    engine.set_tunables(tunables);
+    Store::new(engine)
+}
+
+/// Check that accesses inside the memory work and every kind of
+/// out-of-bounds access traps, no matter how the memory is laid out.
+fn check_bounds(config: &crate::Config, tunables: BaseTunables) -> Result<MemoryStyle> {
+    let mut store = store_with_tunables(config, tunables);
+    let module = Module::new(&store, WAT)?;
+    let instance = Instance::new(&mut store, &module, &imports! {})?;
+    let memory = instance.exports.get_memory("memory")?.clone();
+    let load: TypedFunction<u32, i32> = instance.exports.get_typed_function(&store, "load")?;
+    let load_offset: TypedFunction<u32, i32> =
+        instance.exports.get_typed_function(&store, "load_offset")?;
+    let store_i32: TypedFunction<(u32, i32), ()> =
+        instance.exports.get_typed_function(&store, "store")?;
+
+    let is_oob = |result: Result<i32, RuntimeError>| {
+        matches!(
+            result.map_err(RuntimeError::to_trap),
+            Err(Some(TrapCode::HeapAccessOutOfBounds))
+        )
+    };
+    let page = WASM_PAGE_SIZE as u32;
+
+    store_i32.call(&mut store, page - 4, 42)?;
+    assert_eq!(load.call(&mut store, page - 4)?, 42);
+    assert!(is_oob(load.call(&mut store, page - 3)));
+    assert!(is_oob(load.call(&mut store, page)));
+    assert!(is_oob(load.call(&mut store, 16 * page)));
+    assert!(is_oob(load.call(&mut store, u32::MAX - 3)));
+    assert!(is_oob(load_offset.call(&mut store, 0)));
+    assert!(is_oob(load_offset.call(&mut store, u32::MAX - 3)));
+
+    // Growing makes the new pages accessible, but nothing past them
+    memory.grow(&mut store, 15)?;
+    assert_eq!(load.call(&mut store, page)?, 0);
+    assert_eq!(load_offset.call(&mut store, page - 4)?, 0);
+    assert_eq!(load.call(&mut store, 16 * page - 4)?, 0);
+    assert!(is_oob(load.call(&mut store, 16 * page - 3)));
+    assert!(is_oob(load.call(&mut store, 16 * page)));
+    assert!(is_oob(load_offset.call(&mut store, 15 * page)));
+
+    Ok(memory.style(&store).unwrap())
+}
+
+#[compiler_test(memory_styles)]
+fn default_static_memory(config: crate::Config) -> Result<()> {
+    let tunables = BaseTunables::for_target(&Default::default());
+    let style = check_bounds(&config, tunables.clone())?;
+
+    assert_eq!(
+        tunables.memory_style(&MemoryType::new(1, Some(16), false)),
+        style
+    );
+    assert!(matches!(style, MemoryStyle::Static { .. }));
+    #[cfg(target_pointer_width = "64")]
+    assert!(!style.needs_bounds_checks());
+    Ok(())
+}
+
+#[compiler_test(memory_styles)]
+fn small_static_memory(config: crate::Config) -> Result<()> {
+    let tunables = BaseTunables::for_target(&Default::default())
+        .with_static_memory_bound(Pages(16))
+        .with_guard_size(0x1_0000);
+    let style = check_bounds(&config, tunables)?;
+
+    assert_eq!(
+        style,
+        MemoryStyle::Static {
+            bound: Pages(16),
+            offset_guard_size: 0x1_0000,
+        }
+    );
+    assert!(style.needs_bounds_checks());
+    Ok(())
+}
+
+#[compiler_test(memory_styles)]
+fn static_memory_without_guard(config: crate::Config) -> Result<()> {
+    let tunables = BaseTunables::for_target(&Default::default())
+        .with_static_memory_bound(Pages(16))
+        .with_guard_size(0);
+    let style = check_bounds(&config, tunables)?;
+
+    assert_eq!(
+        style,
+        MemoryStyle::Static {
+            bound: Pages(16),
+            offset_guard_size: 0,
+        }
+    );
+    Ok(())
+}
+
+#[compiler_test(memory_styles)]
+fn dynamic_bounds_checks(config: crate::Config) -> Result<()> {
+    let tunables = BaseTunables::for_target(&Default::default())
+        .with_dynamic_bounds_checks(true)
+        .with_guard_size(0x1_0000);
+    let style = check_bounds(&config, tunables)?;
+
+    assert_eq!(
+        style,
+        MemoryStyle::Dynamic {
+            offset_guard_size: 0x1_0000,
+        }
+    );
+    assert!(style.needs_bounds_checks());
+    Ok(())
+}
@@ -1,6 +1,6 @@
 use anyhow::Result;
 
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use wasmer::wasmparser::Operator;
 use wasmer::FunctionEnv;
 use wasmer::{sys::*, *};
@@ -88,6 +88,45 @@ impl FunctionMiddleware for Fusion {
     }
 }
 
+/// Records the signature and locals of each function, and stores its
+/// first parameter to global 0 on entry.
+#[derive(Debug, Default)]
+struct PrologueGen {
+    seen: Mutex<Vec<(FunctionType, Vec<(u32, Type)>)>>,
+}
+
+#[derive(Debug)]
+struct Prologue;
+
+impl ModuleMiddleware for PrologueGen {
+    fn generate_function_middleware(&self, _: LocalFunctionIndex) -> Box<dyn FunctionMiddleware> {
+        Box::new(Prologue)
+    }
+
+    fn generate_function_middleware_with_locals(
+        &self,
+        _: LocalFunctionIndex,
+        signature: &FunctionType,
+        locals: &[(u32, Type)],
+    ) -> Box<dyn FunctionMiddleware> {
+        self.seen
+            .lock()
+            .unwrap()
+            .push((signature.clone(), locals.to_vec()));
+        Box::new(Prologue)
+    }
+}
+
+impl FunctionMiddleware for Prologue {
+    fn prologue(&mut self, state: &mut MiddlewareReaderState<'_>) -> Result<(), MiddlewareError> {
+        state.extend([
+            Operator::LocalGet { local_index: 0 },
+            Operator::GlobalSet { global_index: 0 },
+        ]);
+        Ok(())
+    }
+}
+
 #[compiler_test(middlewares)]
 fn middleware_basic(mut config: crate::Config) -> Result<()> {
     config.set_middlewares(vec![
@@ -210,3 +249,31 @@ fn middleware_chain_order_2(mut config: crate::Config) -> Result<()> {
     assert_eq!(result, 48);
     Ok(())
 }
+
+#[compiler_test(middlewares)]
+fn middleware_prologue_runs_before_the_function(mut config: crate::Config) -> Result<()> {
+    let prologue = Arc::new(PrologueGen::default());
+    config.set_middlewares(vec![prologue.clone() as Arc<dyn ModuleMiddleware>]);
+    let mut store = config.store();
+    let wat = r#"(module
+        (global $entered (mut i32) (i32.const -1))
+        (func (export "entered") (param i32) (result i32)
+           (local i64 i64) (local f32)
+           (global.get $entered))
+)"#;
+    let module = Module::new(&store, wat).unwrap();
+    let import_object = imports! {};
+
+    let instance = Instance::new(&mut store, &module, &import_object)?;
+
+    let f: TypedFunction<i32, i32> = instance.exports.get_typed_function(&mut store, "entered")?;
+    assert_eq!(f.call(&mut store, 42)?, 42);
+    assert_eq!(
+        *prologue.seen.lock().unwrap(),
+        [(
+            FunctionType::new([Type::I32], [Type::I32]),
+            vec![(2, Type::I64), (1, Type::F32)]
+        )]
+    );
+    Ok(())
+}
@@ -190,6 +190,75 @@ RuntimeError: unreachable
     Ok(())
 }
 
+#[compiler_test(traps)]
+fn trap_display_source_map(config: crate::Config) -> Result<()> {
+    let mut store = config.store();
+    // The source map maps every offset to `assembly/index.ts:3:3`, and is
+    // embedded as a (length-prefixed) base64 `data:` URL
+    let wat = r#"
+        (module $m
+            (func $die unreachable)
+            (func (export "bar") call $die)
+            (@custom "sourceMappingURL" "\81\01data:application/json;base64,eyJ2ZXJzaW9uIjozLCJzb3VyY2VzIjpbImFzc2VtYmx5L2luZGV4LnRzIl0sIm5hbWVzIjpbXSwibWFwcGluZ3MiOiJBQUVFIn0=")
+        )
+    "#;
+
+    let module = Module::new(&store, wat)?;
+    let instance = Instance::new(&mut store, &module, &imports! {})?;
+    let run_func = instance
+        .exports
+        .get_function("bar")
+        .expect("expected function export");
+
+    let e = run_func
+        .call(&mut store, &[])
+        .expect_err("error calling function");
+    let location = e.trace()[0].source_location().unwrap();
+    assert_eq!(location.file(), "assembly/index.ts");
+    assert_eq!((location.line(), location.column()), (3, 3));
+    assert!(e.to_string().contains("    at die (m[0]:0x"), "{e}");
+    assert!(e.to_string().contains(", assembly/index.ts:3:3)"), "{e}");
+    Ok(())
+}
+
+#[compiler_test(traps)]
+fn trap_names_survive_serialization(config: crate::Config) -> Result<()> {
+    let mut store = config.store();
+    let wat = r#"
+        (module $m
+            (func $_ZN4core9panicking5panic17h0123456789abcdefE (param $code i32) (local $scratch i64)
+                unreachable)
+            (func (export "run") (call $_ZN4core9panicking5panic17h0123456789abcdefE (i32.const 1)))
+        )
+    "#;
+
+    let module = Module::new(&store, wat)?;
+    let serialized = module.serialize()?;
+    let module = unsafe { Module::deserialize(&store, serialized)? };
+    let instance = Instance::new(&mut store, &module, &imports! {})?;
+    let run_func = instance
+        .exports
+        .get_function("run")
+        .expect("expected function export");
+
+    let e = run_func
+        .call(&mut store, &[])
+        .expect_err("error calling function");
+    let frame = &e.trace()[0];
+    assert_eq!(
+        frame.function_name(),
+        Some("_ZN4core9panicking5panic17h0123456789abcdefE")
+    );
+    assert_eq!(frame.local_name(0), Some("code"));
+    assert_eq!(frame.local_name(1), Some("scratch"));
+    assert_eq!(frame.local_name(2), None);
+    assert!(
+        e.to_string().contains("    at core::panicking::panic"),
+        "{e}"
+    );
+    Ok(())
+}
+
 #[cfg_attr(target_env = "musl", ignore)]
 #[compiler_test(traps)]
 fn trap_display_multi_module(config: crate::Config) -> Result<()> {
@@ -274,7 +343,9 @@ fn trap_start_function_import(config: crate::Config) -> Result<()> {
         InstantiationError::Link(_)
         | InstantiationError::DifferentStores
         | InstantiationError::DifferentArchOS
-        | InstantiationError::CpuFeature(_) => {
+        | InstantiationError::CpuFeature(_)
+        | InstantiationError::LimitExceeded { .. }
+        | InstantiationError::StartTimeout { .. } => {
             panic!("It should be a start error")
         }
         InstantiationError::Start(err) => {
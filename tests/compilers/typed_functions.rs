@@ -81,6 +81,57 @@ fn typed_function_works_for_wasm(config: crate::Config) -> anyhow::Result<()> {
     Ok(())
 }
 
+#[compiler_test(typed_functions)]
+fn typed_function_returns_multiple_values_from_wasm(config: crate::Config) -> anyhow::Result<()> {
+    // Singlepass doesn't support multi-value
+    if config.compiler == crate::Compiler::Singlepass {
+        return Ok(());
+    }
+
+    let mut store = config.store();
+    let wat = r#"(module
+        (func (export "split") (param i32 i32) (result i64 f32)
+           (i64.extend_i32_s (local.get 0))
+           (f32.convert_i32_s (local.get 1)))
+        (func (export "mixed") (param i32 i64 f32 f64) (result f64 f32 i64 i32)
+           (f64.mul (local.get 3) (f64.const 2))
+           (f32.mul (local.get 2) (f32.const 2))
+           (i64.mul (local.get 1) (i64.const 2))
+           (i32.mul (local.get 0) (i32.const 2)))
+        (func (export "eight") (result i32 i64 f32 f64 i32 i64 f32 f64)
+           (i32.const 1) (i64.const 2) (f32.const 3) (f64.const 4)
+           (i32.const -5) (i64.const -6) (f32.const -7) (f64.const -8))
+)"#;
+    let module = Module::new(&store, wat)?;
+    let instance = Instance::new(&mut store, &module, &imports! {})?;
+
+    let split: TypedFunction<(i32, i32), (i64, f32)> =
+        instance.exports.get_typed_function(&store, "split")?;
+    assert_eq!(split.call(&mut store, -3, 7)?, (-3, 7.0));
+
+    let mixed: TypedFunction<(i32, i64, f32, f64), (f64, f32, i64, i32)> =
+        instance.exports.get_typed_function(&store, "mixed")?;
+    assert_eq!(
+        mixed.call(&mut store, 1, i64::MAX / 4, 1.5, -0.25)?,
+        (-0.5, 3.0, i64::MAX / 4 * 2, 2)
+    );
+
+    let eight: TypedFunction<(), (i32, i64, f32, f64, i32, i64, f32, f64)> =
+        instance.exports.get_typed_function(&store, "eight")?;
+    assert_eq!(
+        eight.call(&mut store)?,
+        (1, 2, 3.0, 4.0, -5, -6, -7.0, -8.0)
+    );
+
+    // The result types have to match
+    assert!(instance
+        .exports
+        .get_typed_function::<(i32, i32), (i32, f32)>(&store, "split")
+        .is_err());
+
+    Ok(())
+}
+
 #[compiler_test(typed_functions)]
 fn typed_host_function_closure_panics(config: crate::Config) {
     let mut store = config.store();
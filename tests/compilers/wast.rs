@@ -1,6 +1,6 @@
-use ::wasmer::sys::Features;
+use ::wasmer::{imports, sys::Features, Function, Global, Value};
 use std::path::Path;
-use wasmer_wast::Wast;
+use wasmer_wast::{DirectiveErrors, Wast};
 
 // The generated tests (from build.rs) look like:
 // #[cfg(test)]
@@ -78,3 +78,75 @@ pub fn run_wast(mut config: crate::Config, wast_path: &str) -> anyhow::Result<()
     let path = Path::new(wast_path);
     wast.run_file(path)
 }
+
+#[compiler_test(wast)]
+fn failures_are_reported_per_directive(config: crate::Config) -> anyhow::Result<()> {
+    let script = r#"
+(module
+  (func (export "add") (param i32 i32) (result i32)
+    (i32.add (local.get 0) (local.get 1))))
+(register "math")
+(assert_return (invoke "add" (i32.const 1) (i32.const 2)) (i32.const 3))
+(assert_return (invoke "add" (i32.const 2) (i32.const 2)) (i32.const 5))
+(assert_trap (invoke "add" (i32.const 0) (i32.const 0)) "unreachable")
+(assert_invalid (module (func (result i32))) "type mismatch")
+"#;
+    let mut wast = Wast::new_with_spectest(config.store());
+    wast.fail_fast = false;
+    let err = wast
+        .run_buffer(Path::new("report.wast"), script.as_bytes())
+        .unwrap_err()
+        .downcast::<DirectiveErrors>()?;
+
+    assert_eq!(err.filename, "report.wast");
+    let failures: Vec<_> = err
+        .errors
+        .iter()
+        .map(|e| {
+            (
+                e.directive,
+                e.line,
+                e.expected.as_deref(),
+                e.actual.as_deref(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        failures,
+        vec![
+            ("assert_return", 7, Some("Core(I32(5))"), Some("I32(4)")),
+            (
+                "assert_trap",
+                8,
+                Some("trap 'unreachable'"),
+                Some("[I32(0)]")
+            ),
+        ]
+    );
+    Ok(())
+}
+
+#[compiler_test(wast)]
+fn spectest_imports_can_be_replaced(config: crate::Config) -> anyhow::Result<()> {
+    let script = r#"
+(module
+  (import "spectest" "answer" (global i32))
+  (import "host" "double" (func $double (param i32) (result i32)))
+  (func (export "run") (result i32) (call $double (global.get 0))))
+(assert_return (invoke "run") (i32.const 84))
+(assert_unlinkable
+  (module (import "spectest" "print_i32" (func (param i32))))
+  "unknown import")
+"#;
+    let mut wast = Wast::new_with_imports(config.store(), |store| {
+        imports! {
+            "spectest" => {
+                "answer" => Global::new(store, Value::I32(42)),
+            },
+            "host" => {
+                "double" => Function::new_typed(store, |x: i32| x * 2),
+            },
+        }
+    });
+    wast.run_buffer(Path::new("imports.wast"), script.as_bytes())
+}
@@ -0,0 +1,64 @@
+use assert_cmd::Command;
+use serde_json::Value;
+use wasmer_integration_tests_cli::{fixtures, get_wasmer_path};
+
+fn assert_timings(timings: &Value, samples: u64) {
+    assert_eq!(timings["samples"], samples, "{timings}");
+
+    let field = |name: &str| {
+        timings[name]
+            .as_u64()
+            .unwrap_or_else(|| panic!("missing \"{name}\" in {timings}"))
+    };
+    let (min, mean, p50, p95, max) = (
+        field("min_ns"),
+        field("mean_ns"),
+        field("p50_ns"),
+        field("p95_ns"),
+        field("max_ns"),
+    );
+
+    assert!(min > 0, "{timings}");
+    assert!(min <= p50 && p50 <= p95 && p95 <= max, "{timings}");
+    assert!(min <= mean && mean <= max, "{timings}");
+}
+
+#[test]
+fn bench_fib_json() {
+    let assert = Command::new(get_wasmer_path())
+        .arg("bench")
+        .arg(fixtures::fib())
+        .arg("--iterations=3")
+        .arg("--warmup=2")
+        .arg("--format=json")
+        .assert()
+        .success();
+
+    let stdout = String::from_utf8(assert.get_output().stdout.clone()).unwrap();
+    let results: Value = serde_json::from_str(&stdout).unwrap();
+    let results = results.as_array().unwrap();
+    assert_eq!(results.len(), 1, "{stdout}");
+
+    let result = &results[0];
+    assert!(result["compiler"].is_string(), "{stdout}");
+    assert_eq!(result["function"], "_start");
+    assert!(result["compile_ns"].as_u64().unwrap() > 0, "{stdout}");
+    assert!(result["artifact_size"].as_u64().unwrap() > 0, "{stdout}");
+    // Warmup iterations are not part of the samples
+    assert_timings(&result["instantiate"], 3);
+    assert_timings(&result["call"], 3);
+}
+
+#[test]
+fn bench_rejects_mismatched_arguments() {
+    Command::new(get_wasmer_path())
+        .arg("bench")
+        .arg(fixtures::fib())
+        .arg("--iterations=1")
+        .arg("42")
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains(
+            "Function expected 0 arguments, but received 1",
+        ));
+}
@@ -599,6 +599,52 @@ fn create_obj_default() -> anyhow::Result<()> {
     create_obj(vec![])
 }
 
+/// Objects for targets we can only link with a toolchain that isn't around
+/// in CI, so we only check that the right kind of object is emitted.
+#[test]
+fn create_obj_for_static_cross_targets() -> anyhow::Result<()> {
+    use object::{Architecture, BinaryFormat, Object};
+
+    let targets = [
+        (
+            "x86_64-unknown-linux-musl",
+            BinaryFormat::Elf,
+            Architecture::X86_64,
+        ),
+        (
+            "aarch64-unknown-linux-musl",
+            BinaryFormat::Elf,
+            Architecture::Aarch64,
+        ),
+        (
+            "aarch64-pc-windows-msvc",
+            BinaryFormat::Coff,
+            Architecture::Aarch64,
+        ),
+    ];
+
+    for (target, format, architecture) in targets {
+        let temp_dir = tempfile::tempdir()?;
+        let object_path = temp_dir.path().join("wasm.o");
+        WasmerCreateObj {
+            current_dir: temp_dir.path().to_path_buf(),
+            output_object_path: object_path.clone(),
+            extra_cli_flags: vec!["--target".to_string(), target.to_string()],
+            ..Default::default()
+        }
+        .run()
+        .with_context(|| format!("Failed to create-obj for {target}"))?;
+
+        let bytes = fs::read(&object_path)?;
+        let obj = object::File::parse(&*bytes)
+            .with_context(|| format!("Invalid object file for {target}"))?;
+        assert_eq!(obj.format(), format, "{target}");
+        assert_eq!(obj.architecture(), architecture, "{target}");
+    }
+
+    Ok(())
+}
+
 fn create_exe_with_object_input(args: Vec<String>) -> anyhow::Result<()> {
     let temp_dir = tempfile::tempdir()?;
     let operating_dir: PathBuf = temp_dir.path().to_owned();
@@ -844,6 +890,8 @@ fn assert_tarball_is_present_local(target: &str) -> Result<PathBuf, anyhow::Erro
         "x86_64-linux-gnu" => "wasmer-linux-amd64.tar.gz",
         "aarch64-linux-gnu" => "wasmer-linux-aarch64.tar.gz",
         "x86_64-windows-gnu" => "wasmer-windows-gnu64.tar.gz",
+        "x86_64-linux-musl" => "wasmer-linux-musl-static-amd64.tar.gz",
+        "aarch64-windows-msvc" => "wasmer-windows-arm64.tar.gz",
         _ => return Err(anyhow::anyhow!("unknown target {target}")),
     };
     let libwasmer_cache_path = Path::new(&wasmer_dir).join("cache").join(directory);
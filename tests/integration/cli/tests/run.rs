@@ -594,6 +594,324 @@ fn run_invoke_works_with_nomain_wasi() {
     std::fs::remove_file(&module_file).unwrap();
 }
 
+const INVOKE_WAT: &str = r#"
+(module
+    (memory (export "memory") 1)
+    (global $heap (mut i32) (i32.const 1024))
+    (func (export "malloc") (param $size i32) (result i32)
+        (local $ptr i32)
+        (local.set $ptr (global.get $heap))
+        (global.set $heap (i32.add (global.get $heap) (local.get $size)))
+        (local.get $ptr))
+    (func (export "add64") (param i64 i64) (result i64)
+        (i64.add (local.get 0) (local.get 1)))
+    (func (export "mul32") (param f32 f32) (result f32)
+        (f32.mul (local.get 0) (local.get 1)))
+    (func (export "half") (param f64) (result f64)
+        (f64.div (local.get 0) (f64.const 2)))
+    (func (export "swap") (param i32 i64) (result i64 i32)
+        (local.get 1)
+        (local.get 0))
+    (func (export "first_byte") (param $ptr i32) (param $len i32) (result i32 i32)
+        (i32.load8_u (local.get $ptr))
+        (local.get $len)))
+"#;
+
+fn run_invoke(module: &Path, args: &[&str]) -> Assert {
+    Command::new(get_wasmer_path())
+        .arg("run")
+        .arg(module)
+        .args(args)
+        .assert()
+}
+
+#[test]
+fn run_invoke_coerces_arguments_and_prints_all_results() {
+    let temp = TempDir::new().unwrap();
+    let module = temp.path().join("invoke.wat");
+    std::fs::write(&module, INVOKE_WAT).unwrap();
+
+    run_invoke(&module, &["--invoke", "add64", "0x10", "-3"])
+        .success()
+        .stdout("13\n");
+    run_invoke(&module, &["--invoke", "mul32", "1.5", "2"])
+        .success()
+        .stdout("3\n");
+    run_invoke(&module, &["--invoke", "half", "5"])
+        .success()
+        .stdout("2.5\n");
+    run_invoke(&module, &["--invoke", "swap", "1", "2"])
+        .success()
+        .stdout("2 1\n");
+
+    run_invoke(&module, &["--invoke", "add64", "1", "two"])
+        .failure()
+        .stderr(contains("Invalid value \"two\" for parameter 1 (i64)"));
+}
+
+#[test]
+fn run_invoke_with_string_arguments() {
+    let temp = TempDir::new().unwrap();
+    let module = temp.path().join("invoke.wat");
+    std::fs::write(&module, INVOKE_WAT).unwrap();
+
+    run_invoke(
+        &module,
+        &["--invoke", "first_byte", "--invoke-string", "hello"],
+    )
+    .success()
+    .stdout("104 5\n");
+}
+
+#[test]
+fn run_invoke_typed_prints_each_result_with_its_type() {
+    let temp = TempDir::new().unwrap();
+    let module = temp.path().join("invoke.wat");
+    std::fs::write(&module, INVOKE_WAT).unwrap();
+
+    run_invoke(&module, &["--invoke", "swap", "--invoke-typed", "1", "2"])
+        .success()
+        .stdout("2 (i64)\n1 (i32)\n");
+    run_invoke(&module, &["--invoke", "half", "--invoke-typed", "5"])
+        .success()
+        .stdout("2.5 (f64)\n");
+}
+
+/// Writes the bytes its data segment includes from `blob.bin`, between `<`
+/// and `>`, to stdout.
+const INCLUDE_WAT: &str = r#"
+(module
+    (import "wasi_snapshot_preview1" "fd_write" (func $fd_write (param i32 i32 i32 i32) (result i32)))
+    (memory (export "memory") 1)
+    (data (i32.const 100) "<" (@data.file "blob.bin") ">")
+    (func (export "_start")
+        (i32.store (i32.const 0) (i32.const 100))
+        (i32.store (i32.const 4) (i32.const 6))
+        (if (call $fd_write (i32.const 1) (i32.const 0) (i32.const 1) (i32.const 8))
+            (then unreachable)))
+)
+"#;
+
+#[test]
+fn run_wat_with_included_data_file() {
+    let temp = TempDir::new().unwrap();
+    let module = temp.path().join("include.wat");
+    std::fs::write(&module, INCLUDE_WAT).unwrap();
+    std::fs::write(temp.path().join("blob.bin"), [0x00, 0xff, b'"', b'\\']).unwrap();
+
+    Command::new(get_wasmer_path())
+        .arg("run")
+        .arg("--wat-include-dir")
+        .arg(temp.path())
+        .arg(&module)
+        .assert()
+        .success()
+        .stdout(&b"<\x00\xff\"\\>"[..]);
+
+    std::fs::remove_file(temp.path().join("blob.bin")).unwrap();
+    Command::new(get_wasmer_path())
+        .arg("run")
+        .arg("--wat-include-dir")
+        .arg(temp.path())
+        .arg(&module)
+        .assert()
+        .failure()
+        .stderr(contains("line 5: unable to read"));
+}
+
+const LOOP_FOREVER_WAT: &str = r#"
+(module
+    (import "wasi_snapshot_preview1" "proc_exit" (func $proc_exit (param i32)))
+    (memory (export "memory") 1)
+    (func (export "_start")
+        (loop $forever (br $forever)))
+)
+"#;
+
+/// Copies stdin to stdout until it reaches EOF.
+const CAT_WAT: &str = r#"
+(module
+    (import "wasi_snapshot_preview1" "fd_read" (func $fd_read (param i32 i32 i32 i32) (result i32)))
+    (import "wasi_snapshot_preview1" "fd_write" (func $fd_write (param i32 i32 i32 i32) (result i32)))
+    (memory (export "memory") 1)
+    (func (export "_start")
+        (i32.store (i32.const 0) (i32.const 1024))
+        (i32.store (i32.const 4) (i32.const 1024))
+        (loop $copy
+            (if (call $fd_read (i32.const 0) (i32.const 0) (i32.const 1) (i32.const 8))
+                (then unreachable))
+            (if (i32.load (i32.const 8))
+                (then
+                    (i32.store (i32.const 16) (i32.const 1024))
+                    (i32.store (i32.const 20) (i32.load (i32.const 8)))
+                    (if (call $fd_write (i32.const 1) (i32.const 16) (i32.const 1) (i32.const 8))
+                        (then unreachable))
+                    (br $copy))))
+    )
+)
+"#;
+
+#[test]
+fn run_with_timeout_stops_infinite_loop() {
+    let temp = TempDir::new().unwrap();
+    let module = temp.path().join("loop.wat");
+    std::fs::write(&module, LOOP_FOREVER_WAT).unwrap();
+
+    let start = Instant::now();
+    Command::new(get_wasmer_path())
+        .arg("run")
+        .arg("--timeout=1s")
+        .arg("--kill-after=1s")
+        .arg(&module)
+        .stdin(Stdio::null())
+        .assert()
+        .code(124)
+        .stderr(contains("The program didn't exit within 1s"));
+
+    // Leave plenty of room for compiling the module
+    assert!(start.elapsed() < Duration::from_secs(30));
+}
+
+#[test]
+fn run_with_stdin_close_reads_eof() {
+    let temp = TempDir::new().unwrap();
+    let module = temp.path().join("cat.wat");
+    std::fs::write(&module, CAT_WAT).unwrap();
+
+    let mut child = Command::new(get_wasmer_path())
+        .arg("run")
+        .arg("--stdin-close")
+        .arg(&module)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    // Keep our end of the pipe open, so only --stdin-close can give the
+    // program an EOF
+    let _stdin = child.stdin.take().unwrap();
+
+    let deadline = Instant::now() + Duration::from_secs(30);
+    let status = loop {
+        if let Some(status) = child.try_wait().unwrap() {
+            break status;
+        }
+        if Instant::now() > deadline {
+            child.kill().unwrap();
+            panic!("The program is still waiting for stdin");
+        }
+        std::thread::sleep(Duration::from_millis(50));
+    };
+
+    assert!(status.success());
+    let mut stdout = String::new();
+    child
+        .stdout
+        .take()
+        .unwrap()
+        .read_to_string(&mut stdout)
+        .unwrap();
+    assert_eq!(stdout, "");
+}
+
+#[test]
+fn run_python_from_precompiled_artifacts() {
+    let temp = TempDir::new().unwrap();
+
+    for (output, flags) in [("artifacts", &[][..]), ("python.bundle", &["--bundle"][..])] {
+        let artifacts = temp.path().join(output);
+
+        Command::new(get_wasmer_path())
+            .arg("compile")
+            .arg(fixtures::python())
+            .arg("-o")
+            .arg(&artifacts)
+            .args(flags)
+            .assert()
+            .success();
+
+        // With --require-precompiled, running fails instead of invoking the
+        // compiler for anything that isn't in the artifacts
+        Command::new(get_wasmer_path())
+            .arg("run")
+            .arg("--precompiled")
+            .arg(&artifacts)
+            .arg("--require-precompiled")
+            .arg(fixtures::python())
+            .arg("--")
+            .arg("-c")
+            .arg("print('Hello, World!')")
+            .env("RUST_LOG", &*RUST_LOG)
+            .assert()
+            .success()
+            .stdout(contains("Hello, World!"));
+    }
+
+    let module = temp.path().join("loop.wat");
+    std::fs::write(&module, LOOP_FOREVER_WAT).unwrap();
+    Command::new(get_wasmer_path())
+        .arg("run")
+        .arg("--precompiled")
+        .arg(temp.path().join("artifacts"))
+        .arg("--require-precompiled")
+        .arg(&module)
+        .assert()
+        .failure()
+        .stderr(contains(
+            "isn't precompiled, and compiling it isn't allowed",
+        ));
+}
+
+#[test]
+fn compile_with_json_events() {
+    let temp = TempDir::new().unwrap();
+
+    for (input, output) in [
+        (fixtures::qjs(), temp.path().join("qjs.wasmu")),
+        (fixtures::python(), temp.path().join("python")),
+    ] {
+        let assert = Command::new(get_wasmer_path())
+            .arg("compile")
+            .arg(&input)
+            .arg("-o")
+            .arg(&output)
+            .arg("--message-format=json-events")
+            .assert()
+            .success();
+
+        let stderr = std::str::from_utf8(&assert.get_output().stderr).unwrap();
+        let events: Vec<serde_json::Value> = stderr
+            .lines()
+            .map(|line| {
+                serde_json::from_str(line).unwrap_or_else(|e| panic!("{e}: {line:?} isn't JSON"))
+            })
+            .collect();
+
+        // Phases end in the reverse order they were started in, and progress
+        // is reported for the innermost one
+        let mut phases = Vec::new();
+        for event in &events[..events.len() - 1] {
+            match event["event"].as_str().unwrap() {
+                "phase-start" => phases.push(event["phase"].clone()),
+                "phase-end" => {
+                    assert_eq!(phases.pop().as_ref(), Some(&event["phase"]), "{stderr}");
+                    assert_eq!(event["success"], true);
+                }
+                "progress" => assert_eq!(phases.last(), Some(&event["phase"]), "{stderr}"),
+                "warning" => {}
+                other => panic!("Unexpected \"{other}\" event in {stderr}"),
+            }
+        }
+        assert!(phases.is_empty(), "{stderr}");
+        assert!(events.iter().any(|e| e["phase"] == "compile"), "{stderr}");
+
+        let result = events.last().unwrap();
+        assert_eq!(result["event"], "result");
+        assert_eq!(result["success"], true);
+        assert_eq!(result["details"]["output"], output.to_str().unwrap());
+        assert!(output.exists());
+    }
+}
+
 #[test]
 fn run_no_start_wasm_report_error() {
     let assert = Command::new(get_wasmer_path())
@@ -764,6 +1082,32 @@ fn webc_files_on_disk_with_multiple_commands_require_an_entrypoint_flag() {
     assert.failure().stderr(contains(msg));
 }
 
+#[test]
+fn webc_files_on_disk_with_command_suffix() {
+    let assert = Command::new(get_wasmer_path())
+        .arg("run")
+        .arg(format!("{}:wat2wasm", fixtures::wabt().display()))
+        .arg("--")
+        .arg("--help")
+        .env("RUST_LOG", &*RUST_LOG)
+        .assert();
+
+    assert.success().stdout(contains("usage: wat2wasm"));
+}
+
+#[test]
+fn webc_files_on_disk_with_unknown_command_suffix() {
+    let assert = Command::new(get_wasmer_path())
+        .arg("run")
+        .arg(format!("{}:nope", fixtures::wabt().display()))
+        .env("RUST_LOG", &*RUST_LOG)
+        .assert();
+
+    assert
+        .failure()
+        .stderr(contains(r#"The package doesn't have a "nope" command"#));
+}
+
 #[test]
 #[cfg_attr(
     all(target_env = "musl", target_os = "linux"),
@@ -784,6 +1128,42 @@ fn wasi_runner_on_disk_with_env_vars() {
     assert.success().stdout(contains("Hello, World!"));
 }
 
+#[test]
+#[cfg_attr(
+    all(target_env = "musl", target_os = "linux"),
+    ignore = "wasmer run-unstable segfaults on musl"
+)]
+fn wasi_runner_passes_matching_host_env_vars() {
+    let script = "import os; \
+        names = ('DYLD_LIBRARY_PATH', 'DYLD_FRAMEWORK_PATH'); \
+        print(sorted((k, v) for k, v in os.environ.items() if k.startswith('ENV_PASS_') or k in names))";
+    let assert = Command::new(get_wasmer_path())
+        .arg("run")
+        .arg(fixtures::python())
+        .arg("--env-pass=ENV_PASS_AWS_*")
+        // Denied variables are only passed by name
+        .arg("--env-pass=DYLD_*")
+        .arg("--env-pass=DYLD_LIBRARY_PATH")
+        .arg("--env=ENV_PASS_AWS_EXPLICIT=from-flag")
+        .arg("--")
+        .arg("-B")
+        .arg("-c")
+        .arg(script)
+        .env("RUST_LOG", &*RUST_LOG)
+        .env("ENV_PASS_AWS_REGION", "eu-west-1")
+        .env("ENV_PASS_AWS_EXPLICIT", "from-host")
+        .env("ENV_PASS_OTHER", "nope")
+        .env("DYLD_LIBRARY_PATH", "/nonexistent/lib")
+        .env("DYLD_FRAMEWORK_PATH", "/nonexistent/frameworks")
+        .assert();
+
+    assert.success().stdout(contains(
+        "[('DYLD_LIBRARY_PATH', '/nonexistent/lib'), \
+         ('ENV_PASS_AWS_EXPLICIT', 'from-flag'), \
+         ('ENV_PASS_AWS_REGION', 'eu-west-1')]",
+    ));
+}
+
 #[test]
 #[cfg_attr(
     all(target_env = "musl", target_os = "linux"),
@@ -1094,6 +1474,39 @@ fn run_bash_using_coreutils() {
         .stdout(is_match(some_expected_binaries).unwrap());
 }
 
+#[test]
+#[cfg_attr(
+    all(target_env = "musl", target_os = "linux"),
+    ignore = "wasmer run-unstable segfaults on musl"
+)]
+#[cfg_attr(
+    windows,
+    ignore = "TODO(Michael-F-Bryan): Figure out why WasiFs::get_inode_at_path_inner() returns Errno::notcapable on Windows"
+)]
+#[cfg_attr(
+    feature = "wamr",
+    ignore = "FIXME(xdoardo): Bash is currently not working in wamr"
+)]
+#[cfg_attr(feature = "wasmi", ignore = "wasmi currently does not support threads")]
+fn run_bash_with_a_mapped_package_command() {
+    let assert = Command::new(get_wasmer_path())
+        .arg("run")
+        .arg("sharrattj/bash")
+        .arg("--entrypoint=bash")
+        .arg("--mapcommand=snake=python/python:python")
+        .arg("--registry=wasmer.io")
+        .arg("--")
+        .arg("-c")
+        .arg("ls /bin && snake -c 'print(\"Hello, \" + \"World!\")'")
+        .env("RUST_LOG", &*RUST_LOG)
+        .assert();
+
+    assert
+        .success()
+        .stdout(contains("snake"))
+        .stdout(contains("Hello, World!"));
+}
+
 #[test]
 fn run_a_package_that_uses_an_atom_from_a_dependency() {
     let js_script_dir = project_root()
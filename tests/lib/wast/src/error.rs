@@ -8,10 +8,35 @@ pub struct DirectiveError {
     pub line: usize,
     /// The column where the directive is defined
     pub col: usize,
+    /// The kind of directive, as written in the script (`assert_return`,
+    /// `register`, ...)
+    pub directive: &'static str,
+    /// What the directive asserted, if the failure was a mismatch
+    pub expected: Option<String>,
+    /// What running the directive produced instead, if the failure was a
+    /// mismatch
+    pub actual: Option<String>,
     /// The failing message received when running the directive
     pub message: String,
 }
 
+/// An assertion whose outcome didn't match what the script expected.
+#[derive(Error, Debug)]
+#[error("expected {expected}, got {actual}")]
+pub(crate) struct AssertionMismatch {
+    pub(crate) expected: String,
+    pub(crate) actual: String,
+}
+
+impl AssertionMismatch {
+    pub(crate) fn new(expected: impl ToString, actual: impl ToString) -> Self {
+        Self {
+            expected: expected.to_string(),
+            actual: actual.to_string(),
+        }
+    }
+}
+
 /// A structure holding the list of all executed directives
 #[derive(Error, Debug)]
 pub struct DirectiveErrors {
@@ -30,7 +55,11 @@ impl fmt::Display for DirectiveErrors {
         // is very similar to `println!`.
         writeln!(f, "Failed directives on {}:", self.filename)?;
         for error in self.errors.iter() {
-            writeln!(f, "  • {} ({}:{})", error.message, error.line, error.col)?;
+            writeln!(
+                f,
+                "  • {}: {} ({}:{})",
+                error.directive, error.message, error.line, error.col
+            )?;
         }
         Ok(())
     }
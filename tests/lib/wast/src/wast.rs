@@ -1,4 +1,4 @@
-use crate::error::{DirectiveError, DirectiveErrors};
+use crate::error::{AssertionMismatch, DirectiveError, DirectiveErrors};
 use crate::spectest::spectest_importobject;
 use anyhow::{anyhow, bail, Result};
 use std::collections::{HashMap, HashSet};
@@ -82,8 +82,20 @@ impl Wast {
     }
 
     /// Construct a new instance of `Wast` with the spectests imports.
-    pub fn new_with_spectest(mut store: Store) -> Self {
-        let import_object = spectest_importobject(&mut store);
+    pub fn new_with_spectest(store: Store) -> Self {
+        Self::new_with_imports(store, spectest_importobject)
+    }
+
+    /// Construct a new instance of `Wast`, letting `provider` create the
+    /// imports available to every module in the store.
+    ///
+    /// This is how the `spectest` module can be swapped for another
+    /// implementation, or have more namespaces added next to it.
+    pub fn new_with_imports(
+        mut store: Store,
+        provider: impl FnOnce(&mut Store) -> Imports,
+    ) -> Self {
+        let import_object = provider(&mut store);
         Self::new(store, import_object)
     }
 
@@ -148,33 +160,36 @@ impl Wast {
 
             if let Value::V128(bits) = v {
                 if let wast::WastRet::Core(WastRetCore::V128(pattern)) = e {
-                    bail!(
-                        "expected {:?}, got {:?} (v128 bits: {})",
-                        e,
-                        v128_format(*bits, pattern),
-                        bits
-                    );
+                    let actual = format!("{:?} (v128 bits: {})", v128_format(*bits, pattern), bits);
+                    return Err(AssertionMismatch::new(format!("{e:?}"), actual).into());
                 }
             }
-            if let Some(f) = v.f64() {
+            let mismatch = if let Some(f) = v.f64() {
                 if let wast::WastRet::Core(WastRetCore::F64(wast::core::NanPattern::Value(f1))) = e
                 {
-                    let f = f64::from_bits(f1.bits);
-                    bail!("expected {f:?} ({:?}), got {v:?} ({})", e, f.to_bits())
+                    let expected = f64::from_bits(f1.bits);
+                    AssertionMismatch::new(
+                        format!("{expected:?} ({e:?})"),
+                        format!("{v:?} ({})", f.to_bits()),
+                    )
                 } else {
-                    bail!("expected {:?}, got {:?} ({})", e, v, f.to_bits())
+                    AssertionMismatch::new(format!("{e:?}"), format!("{v:?} ({})", f.to_bits()))
                 }
             } else if let Some(f) = v.f32() {
                 if let wast::WastRet::Core(WastRetCore::F32(wast::core::NanPattern::Value(f1))) = e
                 {
-                    let f = f32::from_bits(f1.bits);
-                    bail!("expected {f:?} ({:?}), got {v:?} ({})", e, f.to_bits())
+                    let expected = f32::from_bits(f1.bits);
+                    AssertionMismatch::new(
+                        format!("{expected:?} ({e:?})"),
+                        format!("{v:?} ({})", f.to_bits()),
+                    )
                 } else {
-                    bail!("expected {:?}, got {:?} ({})", e, v, f.to_bits())
+                    AssertionMismatch::new(format!("{e:?}"), format!("{v:?} ({})", f.to_bits()))
                 }
             } else {
-                bail!("expected {:?}, got {:?}", e, v)
-            }
+                AssertionMismatch::new(format!("{e:?}"), format!("{v:?}"))
+            };
+            return Err(mismatch.into());
         }
         Ok(())
     }
@@ -197,13 +212,19 @@ impl Wast {
 
     fn assert_trap(&self, result: Result<Vec<Value>>, expected: &str) -> Result<()> {
         let actual = match result {
-            Ok(values) => bail!("expected trap, got {:?}", values),
+            Ok(values) => {
+                return Err(AssertionMismatch::new(
+                    format!("trap '{expected}'"),
+                    format!("{values:?}"),
+                )
+                .into())
+            }
             Err(t) => format!("{t}"),
         };
         if self.matches_message_assert_trap(expected, &actual) {
             return Ok(());
         }
-        bail!("expected '{}', got '{}'", expected, actual)
+        Err(AssertionMismatch::new(format!("'{expected}'"), format!("'{actual}'")).into())
     }
 
     fn run_directive(&mut self, _test: &Path, directive: wast::WastDirective) -> Result<()> {
@@ -256,16 +277,22 @@ impl Wast {
                 message,
             } => {
                 let err = match self.wat(module) {
-                    Ok(()) => bail!("expected module to fail to build"),
+                    Ok(()) => {
+                        return Err(AssertionMismatch::new(
+                            format!("\"{message}\""),
+                            "a valid module",
+                        )
+                        .into())
+                    }
                     Err(e) => e,
                 };
                 let error_message = format!("{err:?}");
                 if !Self::matches_message_assert_invalid(message, &error_message) {
-                    bail!(
-                        "assert_invalid: expected \"{}\", got \"{}\"",
-                        message,
-                        error_message
+                    return Err(AssertionMismatch::new(
+                        format!("\"{message}\""),
+                        format!("\"{error_message}\""),
                     )
+                    .into());
                 }
             }
             AssertException { span: _, exec } => {
@@ -300,16 +327,14 @@ impl Wast {
             } => {
                 let bytes = module.encode()?;
                 let err = match self.module(None, &bytes) {
-                    Ok(()) => bail!("expected module to fail to link"),
+                    Ok(()) => {
+                        return Err(AssertionMismatch::new(message, "a module that links").into())
+                    }
                     Err(e) => e,
                 };
                 let error_message = format!("{err:?}");
                 if !Self::matches_message_assert_unlinkable(message, &error_message) {
-                    bail!(
-                        "assert_unlinkable: expected {}, got {}",
-                        message,
-                        error_message
-                    )
+                    return Err(AssertionMismatch::new(message, error_message).into());
                 }
             }
             Thread(_) => anyhow::bail!("`thread` directives not implemented yet!"),
@@ -343,6 +368,7 @@ impl Wast {
         let mut errors = Vec::with_capacity(ast.directives.len());
         for directive in ast.directives {
             let sp = directive.span();
+            let kind = directive_name(&directive);
             if let Err(e) = self.run_directive(test, directive) {
                 let message = format!("{e}");
                 // If depends on an instance that doesn't exist
@@ -355,9 +381,13 @@ impl Wast {
                     continue;
                 }
                 let (line, col) = sp.linecol_in(wast);
+                let mismatch = e.downcast_ref::<AssertionMismatch>();
                 errors.push(DirectiveError {
                     line: line + 1,
                     col,
+                    directive: kind,
+                    expected: mismatch.map(|m| m.expected.clone()),
+                    actual: mismatch.map(|m| m.actual.clone()),
                     message,
                 });
                 if self.fail_fast {
@@ -598,6 +628,29 @@ impl Wast {
     }
 }
 
+/// The name of a directive, as written in a wast script.
+fn directive_name(directive: &wast::WastDirective<'_>) -> &'static str {
+    use wast::WastDirective::*;
+
+    match directive {
+        ModuleDefinition(_) => "module definition",
+        Module(_) => "module",
+        Register { .. } => "register",
+        Invoke(_) => "invoke",
+        AssertReturn { .. } => "assert_return",
+        AssertTrap { .. } => "assert_trap",
+        AssertExhaustion { .. } => "assert_exhaustion",
+        AssertInvalid { .. } => "assert_invalid",
+        AssertException { .. } => "assert_exception",
+        AssertMalformed { .. } => "assert_malformed",
+        AssertUnlinkable { .. } => "assert_unlinkable",
+        Thread(_) => "thread",
+        Wait { .. } => "wait",
+        ModuleInstance { .. } => "module instance",
+        AssertSuspension { .. } => "assert_suspension",
+    }
+}
+
 fn extract_lane_as_i8(bytes: u128, lane: usize) -> i8 {
     (bytes >> (lane * 8)) as i8
 }
@@ -0,0 +1,83 @@
+#include <errno.h>
+#include <fcntl.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/mman.h>
+#include <sys/types.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+#define SIZE 4096
+
+int main()
+{
+    pid_t pid;
+    int fd;
+    char *buf;
+    char check[16] = {0};
+    int status = 1;
+
+    fd = shm_open("/buffer", O_CREAT | O_EXCL | O_RDWR, 0600);
+    if (fd == -1 || ftruncate(fd, SIZE) == -1)
+    {
+        goto end;
+    }
+
+    buf = mmap(NULL, SIZE, PROT_READ | PROT_WRITE, MAP_SHARED, fd, 0);
+    if (buf == MAP_FAILED)
+    {
+        goto end;
+    }
+    strcpy(buf, "from parent");
+    if (msync(buf, SIZE, MS_SYNC) == -1)
+    {
+        goto end;
+    }
+
+    pid = fork();
+
+    if (pid == -1)
+    {
+        goto end;
+    }
+    else if (pid == 0)
+    {
+        int child_fd = shm_open("/buffer", O_RDWR, 0);
+        if (child_fd == -1)
+        {
+            exit(EXIT_FAILURE);
+        }
+
+        char *child_buf = mmap(NULL, SIZE, PROT_READ | PROT_WRITE, MAP_SHARED, child_fd, 0);
+        if (child_buf == MAP_FAILED || strcmp(child_buf, "from parent") != 0)
+        {
+            exit(EXIT_FAILURE);
+        }
+
+        strcpy(child_buf, "from child");
+        return msync(child_buf, SIZE, MS_SYNC) != 0;
+    }
+    else
+    {
+        waitpid(pid, &status, 0);
+
+        if (pread(fd, check, sizeof(check) - 1, 0) == -1 || strcmp(check, "from child") != 0)
+        {
+            status = 1;
+            goto end;
+        }
+
+        // The object stays readable through open descriptors once unlinked
+        if (shm_unlink("/buffer") == -1 || pread(fd, check, sizeof(check) - 1, 0) == -1)
+        {
+            status = 1;
+            goto end;
+        }
+
+        status = status | (shm_open("/buffer", O_RDWR, 0) != -1 || errno != ENOENT);
+    }
+
+end:
+    printf("%d", status);
+}
@@ -0,0 +1,154 @@
+// One thread floods stdout, which is read by a slow consumer, while the
+// main thread keeps echoing data over a loopback TCP connection. Waiting
+// for the consumer to make room in stdout must only block the flooding
+// thread, not the syscalls of the rest of the process.
+
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <time.h>
+#include <unistd.h>
+#include <pthread.h>
+#include <sys/socket.h>
+#include <netinet/in.h>
+#include <arpa/inet.h>
+
+#define ROUNDS 100
+#define MAX_LATENCY_MS 250
+
+static volatile long flooded = 0;
+
+void *flood(void *arg)
+{
+    char buf[4096];
+    memset(buf, 'x', sizeof(buf));
+
+    for (;;)
+    {
+        ssize_t n = write(STDOUT_FILENO, buf, sizeof(buf));
+        if (n <= 0)
+        {
+            break;
+        }
+        flooded += n;
+    }
+    return NULL;
+}
+
+long now_ms()
+{
+    struct timespec ts;
+    clock_gettime(CLOCK_MONOTONIC, &ts);
+    return ts.tv_sec * 1000 + ts.tv_nsec / 1000000;
+}
+
+int connect_pair(int *client, int *server)
+{
+    struct sockaddr_in addr;
+    socklen_t addr_len = sizeof(addr);
+
+    int listener = socket(AF_INET, SOCK_STREAM, 0);
+    if (listener < 0)
+    {
+        perror("socket");
+        return -1;
+    }
+
+    memset(&addr, 0, sizeof(addr));
+    addr.sin_family = AF_INET;
+    addr.sin_port = htons(0);
+    addr.sin_addr.s_addr = htonl(INADDR_LOOPBACK);
+    if (bind(listener, (struct sockaddr *)&addr, sizeof(addr)) < 0 ||
+        listen(listener, 1) < 0 ||
+        getsockname(listener, (struct sockaddr *)&addr, &addr_len) < 0)
+    {
+        perror("listen");
+        return -1;
+    }
+
+    *client = socket(AF_INET, SOCK_STREAM, 0);
+    if (*client < 0 || connect(*client, (struct sockaddr *)&addr, sizeof(addr)) < 0)
+    {
+        perror("connect");
+        return -1;
+    }
+
+    *server = accept(listener, NULL, NULL);
+    if (*server < 0)
+    {
+        perror("accept");
+        return -1;
+    }
+
+    close(listener);
+    return 0;
+}
+
+int echo(int client, int server)
+{
+    char buf[4];
+
+    if (send(client, "ping", 4, 0) != 4 ||
+        recv(server, buf, sizeof(buf), MSG_WAITALL) != 4 ||
+        send(server, buf, 4, 0) != 4 ||
+        recv(client, buf, sizeof(buf), MSG_WAITALL) != 4)
+    {
+        perror("echo");
+        return -1;
+    }
+    if (memcmp(buf, "ping", 4) != 0)
+    {
+        fprintf(stderr, "Echoed the wrong data\n");
+        return -1;
+    }
+    return 0;
+}
+
+int main()
+{
+    int client, server;
+    pthread_t thread;
+
+    if (connect_pair(&client, &server) < 0)
+    {
+        _exit(EXIT_FAILURE);
+    }
+
+    if (pthread_create(&thread, NULL, flood, NULL) != 0)
+    {
+        perror("pthread_create");
+        _exit(EXIT_FAILURE);
+    }
+
+    // Wait until the flooding thread is stuck on the slow consumer
+    long last = -1;
+    while (flooded != last)
+    {
+        last = flooded;
+        usleep(200 * 1000);
+    }
+
+    long worst = 0;
+    for (int i = 0; i < ROUNDS; i++)
+    {
+        long start = now_ms();
+        if (echo(client, server) < 0)
+        {
+            _exit(EXIT_FAILURE);
+        }
+        long elapsed = now_ms() - start;
+        if (elapsed > worst)
+        {
+            worst = elapsed;
+        }
+    }
+
+    if (worst > MAX_LATENCY_MS)
+    {
+        fprintf(stderr, "An echo took %ldms while stdout was full\n", worst);
+        _exit(EXIT_FAILURE);
+    }
+
+    // Exiting normally would flush stdout, which is still full
+    _exit(EXIT_SUCCESS);
+}
@@ -0,0 +1,4 @@
+set -eo pipefail
+
+# stdout is only read once the guest is done with the echo test
+$WASMER -q run main.wasm --net | (sleep 5; cat > /dev/null)